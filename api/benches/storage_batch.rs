@@ -0,0 +1,93 @@
+//! Saving 10k note arrivals one file write each, against the same saves in
+//! a storage batch. Run with `cargo bench -p api --bench storage_batch`.
+
+use api::wallet::storage::{self, StorageManager};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+const NOTES: u64 = 10_000;
+
+#[derive(Serialize)]
+struct NoteRecord {
+    id: u64,
+    address: String,
+    amount: u64,
+}
+
+#[derive(Serialize)]
+struct BalanceRecord {
+    notes: u64,
+    total: u64,
+}
+
+/// Each arrival saves the note and the updated balance, as the wallet does
+async fn receive_notes(storage: &StorageManager) {
+    let mut total = 0;
+    for id in 0..NOTES {
+        let note = NoteRecord {
+            id,
+            address: format!("address-{}", id % 20),
+            amount: 1_000 + id,
+        };
+        total += note.amount;
+        storage.save("note.json", &note).await.unwrap();
+        let balance = BalanceRecord {
+            notes: id + 1,
+            total,
+        };
+        storage.save("balance.json", &balance).await.unwrap();
+    }
+}
+
+fn temp_dir(label: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "nockchain-bench-{}-{}",
+        label,
+        uuid::Uuid::new_v4()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn report(label: &str, elapsed: Duration, writes: u64) {
+    println!(
+        "{:<10} {:>6} saves  {:>6} writes  {:>9.1} ms  {:>7.1} us/note",
+        label,
+        NOTES * 2,
+        writes,
+        elapsed.as_secs_f64() * 1e3,
+        elapsed.as_secs_f64() * 1e6 / NOTES as f64,
+    );
+}
+
+fn main() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        let dir = temp_dir("unbatched");
+        let storage = StorageManager::new(dir.clone()).unwrap();
+        let before = storage::write_stats().performed;
+        let start = Instant::now();
+        receive_notes(&storage).await;
+        report(
+            "unbatched",
+            start.elapsed(),
+            storage::write_stats().performed - before,
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let dir = temp_dir("batched");
+        let storage = StorageManager::new(dir.clone()).unwrap();
+        let before = storage::write_stats().performed;
+        let start = Instant::now();
+        let batch = storage.batch();
+        receive_notes(&storage).await;
+        batch.commit().unwrap();
+        report(
+            "batched",
+            start.elapsed(),
+            storage::write_stats().performed - before,
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    });
+}
@@ -1,5 +1,8 @@
 //! This crate contains all shared fullstack server functions.
 
+#[cfg(any(test, feature = "test-utils"))]
+pub mod testing;
+pub mod version;
 pub mod wallet;
 
 /// Simple echo function (not a server function for now)
@@ -7,12 +10,134 @@ pub fn echo_string(input: String) -> String {
     input
 }
 
+/// Async echo taking the same path a server function call would; swap the body
+/// for the server function once the fullstack server is wired up
+pub async fn echo_server(input: String) -> Result<String, String> {
+    if input.trim().is_empty() {
+        return Err("Nothing to echo".to_string());
+    }
+    Ok(echo_string(input))
+}
+
 // Re-export wallet types for easier access
 pub use wallet::{
-    Address, Balance, Note, Transaction, TransactionStatus, WalletConfig, WalletError, WalletResult,
+    Address, AddressBalance, AddressError, AddressNetwork, Balance, BalanceDiscrepancy, Blockchain,
+    Contact, ContactBook, MiningPayout, MiningReward, Note, ReconciliationReport, SpendableSummary,
+    Transaction, TransactionDirection, TransactionOrigin, TransactionStatus, Wallet, WalletConfig,
+    WalletError, WalletResult, NOCKCHAIN_ADDRESS_PREFIX,
 };
 
-pub use wallet::keys::{KeyManager, KeyPair, TransactionInput, TransactionOutput};
+pub use wallet::accelerate::DUST_LIMIT;
+pub use wallet::amount::{
+    format_amount, format_display, parse_amount, AmountUnit, DigitGrouping, DisplaySettings,
+    RoundingMode,
+};
+pub use wallet::audit::{
+    AuditCategory, AuditChainBreak, AuditEntry, AuditEvent, AuditFilter, AuditOutcome,
+};
+pub use wallet::chain::{CompactionReport, PrunedRange, DEFAULT_KEEP_FULL_BLOCKS};
+pub use wallet::checkpoints::{Checkpoint, ValidationPhase, MAINNET_CHECKPOINTS};
+pub use wallet::clock::{Clock, ClockJump, ClockMonitor, SystemClock};
+pub use wallet::cold_signing::{
+    InputSignature, PackageInput, PackageSignatures, UnsignedTransactionPackage,
+};
+pub use wallet::config_builder::{ConfigError, Multiaddr, NockchainNodeConfigBuilder};
+pub use wallet::config_file::{
+    ConfigFileError, ConfigLayers, ConfigSource, ConfigSources, NODE_CONFIG_FILE,
+    RUNTIME_CONFIG_FILE,
+};
+pub use wallet::console_history::{
+    load_console_history, save_console_history, CONSOLE_HISTORY_FILE, CONSOLE_HISTORY_LINES,
+};
+pub use wallet::decode::{decode_transaction, DecodeError, DecodedTransaction};
+pub use wallet::integrity::{FileCheck, FileStatus, IntegrityReport, Severity};
+pub use wallet::journal::{JournalEntry, Operation, RecoveryAction, TransactionLookup};
+pub use wallet::key_import::{KeyImportEntry, KeyImportFormat, KeyImportOutcome, KeyImportReport};
+pub use wallet::keys::{
+    AddressKind, KeyManager, KeyPair, OutPoint, TransactionInput, TransactionOutput,
+};
 
 // Re-export node management types
-pub use wallet::network::{LogEntry, LogLevel, LogSource, NodeConfig, NodeManager, NodeStatus};
+pub use wallet::data_dir::{DataDirUsage, PurgeScope, StorageBreakdown};
+pub use wallet::expiry::ExpiredTransaction;
+pub use wallet::explorer::{
+    BlockSummary, BlockTransaction, BlockView, BlocksPage, ChainTransactionView,
+};
+pub use wallet::export::{
+    export_ledger_to_writer, export_logs_to_writer, export_to_file, ExportFilter, ExportFormat,
+    ExportSummary, EXPORT_INCOMPLETE_MARKER,
+};
+pub use wallet::external::ExternalSpend;
+pub use wallet::fees::{package_rate, BlockFees, FeeEstimator, FeeRates};
+pub use wallet::filters::{BlockFilter, FilterSource, FilterSyncStats};
+pub use wallet::geoip::{country_flag, PeerLocation};
+pub use wallet::handshake::service_labels;
+pub use wallet::health::{HealthReport, HealthStatus};
+pub use wallet::incoming::IncomingEvent;
+pub use wallet::launch::{LaunchError, LaunchOptions, LAUNCH_USAGE};
+pub use wallet::ledger::{LedgerAccount, LedgerCheck, LedgerEntry, LedgerEvent};
+pub use wallet::log_buffer::{
+    sanitize_log_message, LogBufferConfig, LogLevelCounts, LogLevels, LogRateLimit,
+};
+pub use wallet::mnemonic::{
+    check_words, detect_language, generate_mnemonic, validate_mnemonic, MnemonicLanguage,
+    MnemonicWord,
+};
+pub use wallet::netsim::NetworkConditions;
+pub use wallet::network::{
+    LogEntry, LogLevel, LogRepeat, LogSource, NodeConfig, NodeHandle, NodeManager, NodeStats,
+    NodeStatus,
+};
+pub use wallet::payment_request::{
+    PaymentMatch, PaymentRequest, PaymentRequestHandle, PaymentRequestStatus, PaymentRequests,
+};
+pub use wallet::peers::{PeerInfo, PeerSource};
+pub use wallet::profiles::{WalletProfile, WalletProfileManager};
+pub use wallet::provenance::{NoteOrigin, NoteProvenance};
+pub use wallet::proxy::Socks5Proxy;
+pub use wallet::rebroadcast::{RebroadcastResult, MIN_REBROADCAST_INTERVAL};
+pub use wallet::receipt::{verify_receipt, ChainTipInfo, MerkleProof, Receipt, ReceiptValidity};
+pub use wallet::reject::{RejectCode, Rejection, RelayReport, TxReject};
+pub use wallet::retention::{
+    CategoryPruned, MaintenanceReport, RetentionCategory, RetentionPolicy, RetentionRule,
+};
+pub use wallet::search::{MatchRank, SearchResult, SearchTarget};
+pub use wallet::secret_key::{
+    encode_secret_key, preview_secret_key, SecretKeyError, SecretKeyFormat, SecretKeyPreview,
+    SECRET_KEY_VERSION,
+};
+pub use wallet::selftest::{CheckResult, CheckStatus};
+pub use wallet::send::{SendFailure, SendProgress, SendRequest, SendStage};
+pub use wallet::send_draft::{SendDraft, TemplateDraft, SEND_DRAFT_FILE};
+pub use wallet::session::{Session, DEFAULT_AUTO_LOCK_MINUTES, DEFAULT_REAUTH_MINUTES};
+pub use wallet::spending::{DailyAllowance, SpendingLimits};
+pub use wallet::storage::{StorageWriteStats, STORAGE_SCHEMA_VERSION};
+pub use wallet::sync_progress::SyncProgress;
+pub use wallet::templates::{FeePriority, PaymentTemplate, PaymentTemplates};
+pub use wallet::transaction::{MerkleProofStatus, OutputDetail, TransactionDetail};
+pub use wallet::ui_prefs::{load_ui_prefs, save_ui_prefs, UiPrefs, UI_PREFS_FILE};
+pub use wallet::uptime::{
+    availability_report, AvailabilityReport, DailyUptime, StatusTransition, TransitionCause,
+};
+pub use wallet::webhooks::{
+    deliver_due_webhooks, DeliveryStatus, WalletEvent, WebhookConfig, WebhookDelivery,
+    WebhookEventType,
+};
+
+pub use version::VersionInfo;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn the_server_echo_refuses_blank_input() {
+        assert_eq!(echo_server("hi".to_string()).await, Ok("hi".to_string()));
+        for blank in ["", "   ", "\n\t"] {
+            assert_eq!(
+                echo_server(blank.to_string()).await,
+                Err("Nothing to echo".to_string())
+            );
+        }
+    }
+}
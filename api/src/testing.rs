@@ -0,0 +1,1122 @@
+//! Deterministic fixtures for tests, behind the `test-utils` feature and
+//! always available to this crate's own unit tests.
+//!
+//! Builders start from plausible defaults, so a test only spells out what it
+//! is about:
+//!
+//! ```ignore
+//! let note = fake_note().amount(5_000).confirmed_at(10).build();
+//! let block = fake_block().height(3).with_txs(2).build();
+//! ```
+//!
+//! Ids, addresses and hashes come from a seeded RNG kept per thread. Each
+//! test runs on its own thread, so a test gets the same values on every run;
+//! `reseed` restarts the sequence. Timestamps count from `FIXTURE_EPOCH`
+//! rather than the clock.
+//!
+//! Other crates enable the feature for their tests only, so it never reaches
+//! a release build:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! api = { workspace = true, features = ["test-utils"] }
+//! ```
+
+use chrono::{DateTime, TimeZone, Utc};
+use futures::future::BoxFuture;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::wallet::chain::Blockchain;
+use crate::wallet::clock::Clock;
+use crate::wallet::data_dir::DiskCapacity;
+use crate::wallet::encoding;
+use crate::wallet::geoip::PeerLocation;
+use crate::wallet::keys::{NockchainTransaction, OutPoint, TransactionInput, TransactionOutput};
+use crate::wallet::mempool::Mempool;
+use crate::wallet::netsim::NetworkConditions;
+use crate::wallet::network::NockchainNodeConfig;
+use crate::wallet::provenance::NoteOrigin;
+use crate::wallet::seeds::SeedResolver;
+use crate::wallet::transaction::SignedTransaction;
+use crate::wallet::{
+    Address, Block, BlockchainConfig, Note, Transaction, TransactionDirection, TransactionOrigin,
+    TransactionStatus, WalletResult,
+};
+
+/// Seed every thread's fixture RNG starts from
+pub const FIXTURE_SEED: u64 = 0x6e6f_636b;
+
+/// Unix time fixtures count from, 2024-01-01 00:00 UTC
+pub const FIXTURE_EPOCH: i64 = 1_704_067_200;
+
+/// Seconds between fixture blocks, as on mainnet
+const BLOCK_INTERVAL: i64 = 600;
+
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::seed_from_u64(FIXTURE_SEED));
+}
+
+/// Restart this thread's fixture values from `seed`
+pub fn reseed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+/// Next 32 bytes from this thread's fixture RNG
+pub fn fake_bytes32() -> [u8; 32] {
+    RNG.with(|rng| rng.borrow_mut().gen())
+}
+
+pub fn fake_address() -> Address {
+    Address::from_public_key(fake_bytes32())
+}
+
+/// Hex id in the shape of a transaction id
+pub fn fake_txid() -> String {
+    hex::encode(fake_bytes32())
+}
+
+fn fake_uuid() -> uuid::Uuid {
+    let bytes = fake_bytes32();
+    uuid::Builder::from_random_bytes(bytes[..16].try_into().expect("16 of 32 bytes")).into_uuid()
+}
+
+/// `FIXTURE_EPOCH` plus `seconds`
+pub fn fixture_time(seconds: i64) -> DateTime<Utc> {
+    Utc.timestamp_opt(FIXTURE_EPOCH + seconds, 0)
+        .single()
+        .expect("fixture times are in range")
+}
+
+/// Clock that starts at `FIXTURE_EPOCH` and only moves when told to. Share
+/// it through an `Arc` with what is under test to simulate clock jumps.
+#[derive(Debug)]
+pub struct FakeClock {
+    now: Mutex<(DateTime<Utc>, Instant)>,
+}
+
+impl Default for FakeClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FakeClock {
+    pub fn new() -> Self {
+        Self {
+            now: Mutex::new((fixture_time(0), Instant::now())),
+        }
+    }
+
+    /// Let `duration` pass on both clocks
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.now.lock().expect("fake clock lock");
+        now.0 += chrono::Duration::from_std(duration).expect("duration in range");
+        now.1 += duration;
+    }
+
+    /// Move only the wall clock, by `seconds`: forward as waking from sleep
+    /// does, back as setting the clock can
+    pub fn jump(&self, seconds: i64) {
+        let mut now = self.now.lock().expect("fake clock lock");
+        now.0 += chrono::Duration::seconds(seconds);
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.now.lock().expect("fake clock lock").0
+    }
+
+    fn instant(&self) -> Instant {
+        self.now.lock().expect("fake clock lock").1
+    }
+}
+
+/// Node config for a local fakenet: no bootstrap peers, no mempool on disk
+/// and broadcasts accepted without peers. `data_dir` is left at the default;
+/// tests touching disk should point it at their own directory.
+pub fn fakenet_config() -> NockchainNodeConfig {
+    NockchainNodeConfig {
+        fakenet: true,
+        genesis_leader: true,
+        peers: Vec::new(),
+        persist_mempool: false,
+        min_broadcast_peers: 0,
+        ..NockchainNodeConfig::default()
+    }
+}
+
+/// Chain config matching `fakenet_config`
+pub fn fakenet_chain_config() -> BlockchainConfig {
+    BlockchainConfig::fakenet()
+}
+
+/// Disk with fixed capacity figures, for data dir health checks
+#[derive(Debug, Clone, Copy)]
+pub struct FakeDisk {
+    /// `None` makes every query fail
+    capacity: Option<(u64, u64)>,
+}
+
+impl FakeDisk {
+    pub fn new(available: u64, total: u64) -> Self {
+        Self {
+            capacity: Some((available, total)),
+        }
+    }
+
+    pub fn failing() -> Self {
+        Self { capacity: None }
+    }
+
+    fn capacity(&self) -> std::io::Result<(u64, u64)> {
+        self.capacity
+            .ok_or_else(|| std::io::Error::other("fake disk unavailable"))
+    }
+}
+
+impl DiskCapacity for FakeDisk {
+    fn available_space(&self, _path: &Path) -> std::io::Result<u64> {
+        Ok(self.capacity()?.0)
+    }
+
+    fn total_space(&self, _path: &Path) -> std::io::Result<u64> {
+        Ok(self.capacity()?.1)
+    }
+}
+
+/// DNS seed resolver answering from a fixed table and counting lookups.
+/// Seeds missing from the table fail to resolve.
+#[derive(Debug, Default)]
+pub struct FakeResolver {
+    answers: HashMap<String, Vec<String>>,
+    lookups: Mutex<Vec<String>>,
+}
+
+impl FakeResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer `seed` with `addrs`
+    pub fn answering(mut self, seed: &str, addrs: &[&str]) -> Self {
+        self.answers.insert(
+            seed.to_string(),
+            addrs.iter().map(|addr| addr.to_string()).collect(),
+        );
+        self
+    }
+
+    /// Seeds looked up so far, in order
+    pub fn lookups(&self) -> Vec<String> {
+        self.lookups.lock().unwrap().clone()
+    }
+}
+
+impl SeedResolver for FakeResolver {
+    fn resolve<'a>(
+        &'a self,
+        seed: &'a str,
+        _port: u16,
+    ) -> BoxFuture<'a, std::io::Result<Vec<String>>> {
+        self.lookups.lock().unwrap().push(seed.to_string());
+        let answer = self
+            .answers
+            .get(seed)
+            .cloned()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no such seed"));
+        Box::pin(async move { answer })
+    }
+}
+
+/// SOCKS5 proxy on a local port, answering every CONNECT with one reply code
+/// (0 for success) and recording the targets asked for. Connections it
+/// accepts echo back what they are sent.
+pub struct MockSocks5 {
+    addr: SocketAddr,
+    targets: Arc<Mutex<Vec<String>>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl MockSocks5 {
+    pub async fn start(reply: u8) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let targets = Arc::new(Mutex::new(Vec::new()));
+        let seen = targets.clone();
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve_socks5(stream, reply, seen.clone()));
+            }
+        });
+        Self {
+            addr,
+            targets,
+            task,
+        }
+    }
+
+    /// `host:port` to configure as the proxy
+    pub fn addr(&self) -> String {
+        self.addr.to_string()
+    }
+
+    /// Target of each CONNECT so far as `host:port`, hostnames as sent
+    pub fn targets(&self) -> Vec<String> {
+        self.targets.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockSocks5 {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn serve_socks5(
+    mut stream: TcpStream,
+    reply: u8,
+    targets: Arc<Mutex<Vec<String>>>,
+) -> std::io::Result<()> {
+    // Greeting: version 5 offering only "no authentication"
+    let mut greeting = [0u8; 3];
+    stream.read_exact(&mut greeting).await?;
+    assert_eq!(greeting, [5, 1, 0]);
+    stream.write_all(&[5, 0]).await?;
+
+    // CONNECT request: version, command, reserved, address type
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header).await?;
+    assert_eq!(header[..3], [5, 1, 0]);
+    let host = match header[3] {
+        1 => {
+            let mut ip = [0u8; 4];
+            stream.read_exact(&mut ip).await?;
+            IpAddr::from(ip).to_string()
+        }
+        4 => {
+            let mut ip = [0u8; 16];
+            stream.read_exact(&mut ip).await?;
+            format!("[{}]", IpAddr::from(ip))
+        }
+        3 => {
+            let mut len = [0u8; 1];
+            stream.read_exact(&mut len).await?;
+            let mut name = vec![0u8; len[0] as usize];
+            stream.read_exact(&mut name).await?;
+            String::from_utf8(name).unwrap()
+        }
+        other => panic!("unknown SOCKS5 address type {}", other),
+    };
+    let mut port = [0u8; 2];
+    stream.read_exact(&mut port).await?;
+    targets
+        .lock()
+        .unwrap()
+        .push(format!("{}:{}", host, u16::from_be_bytes(port)));
+
+    // Reply with a bound address of 0.0.0.0:0
+    stream
+        .write_all(&[5, reply, 0, 1, 0, 0, 0, 0, 0, 0])
+        .await?;
+    if reply == 0 {
+        let mut buf = [0u8; 64];
+        loop {
+            let read = stream.read(&mut buf).await?;
+            if read == 0 {
+                return Ok(());
+            }
+            stream.write_all(&buf[..read]).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Fakenet nodes in one process, each with its own mempool, chain and
+/// `NetworkConditions`. Transactions and chains only travel between nodes
+/// through the sender's conditions, so a test sees what latency, loss and
+/// partitions do to relay and to chain selection.
+///
+/// ```ignore
+/// let mut net = SimNetwork::new(4);
+/// net.set_conditions(0, NetworkConditions { drop_rate: 0.2, ..Default::default() })?;
+/// net.submit(0, fake_signed_transaction().build())?;
+/// assert!(net.relay_until_converged(50).await.is_some());
+/// ```
+pub struct SimNetwork {
+    nodes: Vec<SimNode>,
+}
+
+pub struct SimNode {
+    pub id: String,
+    pub conditions: NetworkConditions,
+    pub mempool: Mempool,
+    pub chain: Blockchain,
+}
+
+impl SimNetwork {
+    /// `count` nodes with clean links, empty mempools and empty chains
+    pub fn new(count: usize) -> Self {
+        let nodes = (0..count)
+            .map(|index| SimNode {
+                id: SimNetwork::peer_id(index),
+                conditions: NetworkConditions::default(),
+                mempool: Mempool::new(),
+                chain: Blockchain::new(fakenet_chain_config()),
+            })
+            .collect();
+        Self { nodes }
+    }
+
+    /// Peer id of the node at `index`, as used in `NetworkConditions`
+    pub fn peer_id(index: usize) -> String {
+        format!("sim-{}", index)
+    }
+
+    pub fn node(&self, index: usize) -> &SimNode {
+        &self.nodes[index]
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Conditions on everything node `index` sends
+    pub fn set_conditions(
+        &mut self,
+        index: usize,
+        conditions: NetworkConditions,
+    ) -> WalletResult<()> {
+        conditions.validate()?;
+        self.nodes[index].conditions = conditions;
+        Ok(())
+    }
+
+    /// The same drop rate on every node's messages
+    pub fn set_drop_rate(&mut self, drop_rate: f64) -> WalletResult<()> {
+        for index in 0..self.nodes.len() {
+            let conditions = NetworkConditions {
+                drop_rate,
+                ..self.nodes[index].conditions.clone()
+            };
+            self.set_conditions(index, conditions)?;
+        }
+        Ok(())
+    }
+
+    /// Cut the nodes in `side` off from the rest, both ways
+    pub fn partition(&mut self, side: &[usize]) {
+        for index in 0..self.nodes.len() {
+            let far: Vec<usize> = if side.contains(&index) {
+                (0..self.nodes.len())
+                    .filter(|i| !side.contains(i))
+                    .collect()
+            } else {
+                side.to_vec()
+            };
+            self.nodes[index].conditions.partitioned = far.into_iter().map(Self::peer_id).collect();
+        }
+    }
+
+    /// Join every partition again
+    pub fn heal(&mut self) {
+        for node in &mut self.nodes {
+            node.conditions.partitioned.clear();
+        }
+    }
+
+    /// Hand `tx` to node `index`, as a wallet broadcasting through it
+    pub fn submit(&mut self, index: usize, tx: SignedTransaction) -> WalletResult<bool> {
+        self.nodes[index].mempool.insert(tx)
+    }
+
+    /// One gossip round: every node offers each transaction in its mempool
+    /// to every peer missing it. Returns how many arrived.
+    pub async fn relay_round(&mut self) -> usize {
+        let mut arrived = Vec::new();
+        for (from, sender) in self.nodes.iter().enumerate() {
+            for (to, receiver) in self.nodes.iter().enumerate() {
+                if from == to {
+                    continue;
+                }
+                for tx in sender.mempool.transactions() {
+                    if receiver.mempool.contains(&tx.id)
+                        || !sender.conditions.deliver(&receiver.id).await
+                    {
+                        continue;
+                    }
+                    arrived.push((to, tx.clone()));
+                }
+            }
+        }
+        let mut count = 0;
+        for (to, tx) in arrived {
+            if self.nodes[to].mempool.insert(tx).unwrap_or(false) {
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Gossip until every mempool holds the same transactions. Returns the
+    /// rounds it took, or `None` if `max_rounds` weren't enough.
+    pub async fn relay_until_converged(&mut self, max_rounds: usize) -> Option<usize> {
+        for round in 0..=max_rounds {
+            if self.mempools_agree() {
+                return Some(round);
+            }
+            self.relay_round().await;
+        }
+        None
+    }
+
+    pub fn mempools_agree(&self) -> bool {
+        let ids = |node: &SimNode| {
+            let mut ids: Vec<String> = node
+                .mempool
+                .transactions()
+                .map(|tx| tx.id.clone())
+                .collect();
+            ids.sort();
+            ids
+        };
+        self.nodes
+            .windows(2)
+            .all(|pair| ids(&pair[0]) == ids(&pair[1]))
+    }
+
+    /// Mine a block on node `index`'s tip paying a coinbase to a fresh address
+    pub fn mine(&mut self, index: usize) -> WalletResult<()> {
+        let chain = &mut self.nodes[index].chain;
+        let coinbase = fake_coinbase(&fake_address(), 5_000);
+        let mut block = chain.new_block(vec![coinbase], chain.config().initial_difficulty);
+        block.mine()?;
+        chain.add_block(block)
+    }
+
+    /// One sync round: every node offers its chain to every peer, and a peer
+    /// whose own chain is shorter switches to it once the blocks validate.
+    /// Returns how many nodes switched.
+    pub async fn sync_round(&mut self) -> usize {
+        let mut offers: Vec<(usize, Vec<Block>)> = Vec::new();
+        for (from, sender) in self.nodes.iter().enumerate() {
+            for (to, receiver) in self.nodes.iter().enumerate() {
+                if from == to || sender.chain.blocks().len() <= receiver.chain.blocks().len() {
+                    continue;
+                }
+                if sender.conditions.deliver(&receiver.id).await {
+                    offers.push((to, sender.chain.blocks().to_vec()));
+                }
+            }
+        }
+        let mut switched = 0;
+        for (to, blocks) in offers {
+            if blocks.len() <= self.nodes[to].chain.blocks().len() {
+                continue;
+            }
+            let mut chain = Blockchain::new(fakenet_chain_config());
+            if blocks
+                .into_iter()
+                .all(|block| chain.add_block(block).is_ok())
+            {
+                self.nodes[to].chain = chain;
+                switched += 1;
+            }
+        }
+        switched
+    }
+
+    /// Tip hash of each node, in node order
+    pub fn tips(&self) -> Vec<Option<[u8; 32]>> {
+        self.nodes
+            .iter()
+            .map(|node| node.chain.tip().map(Block::hash))
+            .collect()
+    }
+}
+
+/// Fresh directory under the system temp dir, removed with everything in it
+/// when dropped
+#[derive(Debug)]
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new(label: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("nockchain-{}-{}", label, uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&path).expect("create temp dir");
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn join(&self, name: impl AsRef<Path>) -> PathBuf {
+        self.path.join(name)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Unspent, unconfirmed note of 1,000 to a fresh address
+pub fn fake_note() -> FakeNote {
+    FakeNote {
+        note: Note {
+            id: fake_uuid(),
+            address: fake_address(),
+            amount: 1_000,
+            block_height: None,
+            transaction_id: fake_txid(),
+            output_index: 0,
+            spent: false,
+            locked: false,
+            created_at: fixture_time(0),
+            coinbase: false,
+            origin: Some(NoteOrigin::External),
+        },
+    }
+}
+
+pub struct FakeNote {
+    note: Note,
+}
+
+impl FakeNote {
+    pub fn amount(mut self, amount: u64) -> Self {
+        self.note.amount = amount;
+        self
+    }
+
+    /// Confirmed in the block at `height`
+    pub fn confirmed_at(mut self, height: u64) -> Self {
+        self.note.block_height = Some(height);
+        self.note.created_at = fixture_time(height as i64 * BLOCK_INTERVAL);
+        self
+    }
+
+    pub fn address(mut self, address: Address) -> Self {
+        self.note.address = address;
+        self
+    }
+
+    /// Output `index` of transaction `txid`
+    pub fn outpoint(mut self, txid: impl Into<String>, index: u32) -> Self {
+        self.note.transaction_id = txid.into();
+        self.note.output_index = index;
+        self
+    }
+
+    pub fn spent(mut self) -> Self {
+        self.note.spent = true;
+        self
+    }
+
+    pub fn locked(mut self) -> Self {
+        self.note.locked = true;
+        self
+    }
+
+    pub fn coinbase(mut self) -> Self {
+        self.note.coinbase = true;
+        self.note.origin = Some(NoteOrigin::Coinbase);
+        self
+    }
+
+    /// Change of a transaction the wallet funded
+    pub fn change(mut self) -> Self {
+        self.note.origin = Some(NoteOrigin::Change);
+        self
+    }
+
+    pub fn build(self) -> Note {
+        self.note
+    }
+}
+
+/// Pending incoming transaction of 1,000 with a fee of 10
+pub fn fake_transaction() -> FakeTransaction {
+    FakeTransaction {
+        tx: Transaction {
+            id: fake_txid(),
+            status: TransactionStatus::Pending,
+            amount: 1_000,
+            fee: 10,
+            from_address: Some(fake_address()),
+            to_address: Some(fake_address()),
+            created_at: fixture_time(0),
+            broadcast_at: None,
+            confirmed_at: None,
+            is_outgoing: false,
+            direction: TransactionDirection::Incoming,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            rejections: Vec::new(),
+            origin: TransactionOrigin::Wallet,
+            accelerates: None,
+        },
+    }
+}
+
+pub struct FakeTransaction {
+    tx: Transaction,
+}
+
+impl FakeTransaction {
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.tx.id = id.into();
+        self
+    }
+
+    pub fn amount(mut self, amount: u64) -> Self {
+        self.tx.amount = amount;
+        self
+    }
+
+    pub fn fee(mut self, fee: u64) -> Self {
+        self.tx.fee = fee;
+        self
+    }
+
+    pub fn from(mut self, address: Address) -> Self {
+        self.tx.from_address = Some(address);
+        self
+    }
+
+    pub fn to(mut self, address: Address) -> Self {
+        self.tx.to_address = Some(address);
+        self
+    }
+
+    /// Sent by the wallet, broadcast when created
+    pub fn outgoing(mut self) -> Self {
+        self.tx.is_outgoing = true;
+        self.tx.direction = TransactionDirection::Outgoing;
+        self.tx.broadcast_at = Some(self.tx.created_at);
+        self
+    }
+
+    /// Funded by the wallet and paid to its own addresses
+    pub fn internal(mut self) -> Self {
+        self.tx.is_outgoing = true;
+        self.tx.direction = TransactionDirection::Internal;
+        self.tx.broadcast_at = Some(self.tx.created_at);
+        self
+    }
+
+    /// Confirmed in the block at `height`
+    pub fn confirmed_at(mut self, height: u64) -> Self {
+        self.tx.status = TransactionStatus::Confirmed {
+            block_height: height,
+        };
+        self.tx.confirmed_at = Some(fixture_time(height as i64 * BLOCK_INTERVAL));
+        self
+    }
+
+    pub fn failed(mut self, reason: impl Into<String>) -> Self {
+        self.tx.status = TransactionStatus::Failed {
+            reason: reason.into(),
+        };
+        self
+    }
+
+    pub fn build(self) -> Transaction {
+        self.tx
+    }
+}
+
+/// Fakenet transaction with a fee of 10 and an all-zero signature. Without
+/// `paying` it pays 1,000 to a fresh address; without `spending` it spends a
+/// fresh outpoint covering its outputs and fee.
+pub fn fake_signed_transaction() -> FakeSignedTransaction {
+    FakeSignedTransaction {
+        tx: SignedTransaction {
+            id: String::new(),
+            chain_id: BlockchainConfig::fakenet().chain_id(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            fee: 10,
+            expiry_height: None,
+            signature: vec![0; 64],
+            hash: Vec::new(),
+        },
+    }
+}
+
+pub struct FakeSignedTransaction {
+    tx: SignedTransaction,
+}
+
+impl FakeSignedTransaction {
+    /// Spend output `index` of `txid`, holding `amount`
+    pub fn spending(mut self, txid: impl Into<String>, index: u32, amount: u64) -> Self {
+        self.tx.inputs.push(TransactionInput {
+            previous_output: OutPoint::new(txid, index),
+            amount,
+        });
+        self
+    }
+
+    pub fn paying(mut self, address: &Address, amount: u64) -> Self {
+        self.tx.outputs.push(TransactionOutput {
+            amount,
+            recipient_address: address.to_string(),
+        });
+        self
+    }
+
+    pub fn fee(mut self, fee: u64) -> Self {
+        self.tx.fee = fee;
+        self
+    }
+
+    pub fn chain_id(mut self, chain_id: [u8; 32]) -> Self {
+        self.tx.chain_id = chain_id;
+        self
+    }
+
+    pub fn expiring_at(mut self, height: u64) -> Self {
+        self.tx.expiry_height = Some(height);
+        self
+    }
+
+    pub fn build(self) -> SignedTransaction {
+        let mut tx = self.tx;
+        if tx.outputs.is_empty() {
+            tx.outputs.push(TransactionOutput {
+                amount: 1_000,
+                recipient_address: fake_address().to_string(),
+            });
+        }
+        if tx.inputs.is_empty() {
+            let amount = tx.outputs.iter().map(|output| output.amount).sum::<u64>() + tx.fee;
+            tx.inputs.push(TransactionInput {
+                previous_output: OutPoint::new(fake_txid(), 0),
+                amount,
+            });
+        }
+        tx.hash = encoding::signing_hash(
+            &tx.chain_id,
+            &tx.inputs,
+            &tx.outputs,
+            tx.fee,
+            tx.expiry_height,
+        );
+        tx.id = encoding::transaction_id(&tx);
+        tx
+    }
+}
+
+/// Empty fakenet block at height 0 on a zero previous hash, unmined
+pub fn fake_block() -> FakeBlock {
+    FakeBlock {
+        height: 0,
+        previous_hash: [0u8; 32],
+        bits: BlockchainConfig::fakenet().initial_difficulty,
+        transactions: Vec::new(),
+    }
+}
+
+pub struct FakeBlock {
+    height: u64,
+    previous_hash: [u8; 32],
+    bits: u32,
+    transactions: Vec<NockchainTransaction>,
+}
+
+impl FakeBlock {
+    pub fn height(mut self, height: u64) -> Self {
+        self.height = height;
+        self
+    }
+
+    /// Extend `parent`: its hash, at the height after it
+    pub fn on(mut self, parent: &Block) -> Self {
+        self.previous_hash = parent.hash();
+        self.height = parent.header.height + 1;
+        self
+    }
+
+    pub fn bits(mut self, bits: u32) -> Self {
+        self.bits = bits;
+        self
+    }
+
+    /// Add `count` transactions, each spending a fresh outpoint to pay 1,000
+    /// to a fresh address
+    pub fn with_txs(mut self, count: usize) -> Self {
+        for _ in 0..count {
+            self.transactions
+                .push(fake_block_transaction(fake_address(), 1_000));
+        }
+        self
+    }
+
+    /// Add a transaction paying `amount` to `address`
+    pub fn paying(mut self, address: &Address, amount: u64) -> Self {
+        self.transactions
+            .push(fake_block_transaction(address.clone(), amount));
+        self
+    }
+
+    /// Add a transaction spending `note` to pay `amount` to `to`, the rest
+    /// of the note left as fee
+    pub fn spending(mut self, note: &Note, to: &Address, amount: u64) -> Self {
+        let mut tx = NockchainTransaction::new(fake_txid());
+        tx.inputs.push(TransactionInput {
+            previous_output: OutPoint::new(note.transaction_id.clone(), note.output_index),
+            amount: note.amount,
+        });
+        tx.outputs.push(TransactionOutput {
+            amount,
+            recipient_address: to.to_string(),
+        });
+        tx.hash = fake_bytes32().to_vec();
+        self.transactions.push(tx);
+        self
+    }
+
+    pub fn build(self) -> Block {
+        let mut block = Block::new(
+            self.previous_hash,
+            self.transactions,
+            self.height,
+            self.bits,
+        );
+        block.header.timestamp = (FIXTURE_EPOCH + self.height as i64 * BLOCK_INTERVAL) as u64;
+        block
+    }
+}
+
+/// Coinbase paying `amount` to `address`: it spends nothing, and every block
+/// after genesis must start with one
+pub fn fake_coinbase(address: &Address, amount: u64) -> NockchainTransaction {
+    let mut tx = NockchainTransaction::new(fake_txid());
+    tx.outputs.push(TransactionOutput {
+        amount,
+        recipient_address: address.to_string(),
+    });
+    tx.hash = fake_bytes32().to_vec();
+    tx
+}
+
+fn fake_block_transaction(to: Address, amount: u64) -> NockchainTransaction {
+    let mut tx = NockchainTransaction::new(fake_txid());
+    tx.inputs.push(TransactionInput {
+        previous_output: OutPoint::new(fake_txid(), 0),
+        amount,
+    });
+    tx.outputs.push(TransactionOutput {
+        amount,
+        recipient_address: to.to_string(),
+    });
+    tx.hash = fake_bytes32().to_vec();
+    tx
+}
+
+/// A MaxMind DB file placing each `(network, prefix length, location)`, in
+/// the layout of GeoLite2 Country and ASN combined, for `GeoIpDatabase::from_bytes`
+/// or writing to disk. IPv4 networks go in the IPv4 part of an IPv6 tree, as
+/// in the real files. Networks must not overlap.
+pub fn geoip_fixture(entries: &[(IpAddr, u8, PeerLocation)]) -> Vec<u8> {
+    #[derive(Clone, Copy)]
+    enum Record {
+        Empty,
+        Node(u32),
+        Data(u32),
+    }
+
+    let mut data = Vec::new();
+    let mut nodes = vec![[Record::Empty; 2]];
+    for (ip, prefix, location) in entries {
+        let (bits, prefix) = match ip {
+            IpAddr::V4(ip) => {
+                let mut bits = [0u8; 16];
+                bits[12..].copy_from_slice(&ip.octets());
+                (bits, 96 + (*prefix).min(32) as usize)
+            }
+            IpAddr::V6(ip) => (ip.octets(), (*prefix).min(128) as usize),
+        };
+
+        let offset = data.len() as u32;
+        let mut fields = Vec::new();
+        if let Some(code) = &location.country_code {
+            let mut country = Vec::new();
+            mmdb_map(&mut country, vec![("iso_code", mmdb_string(code))]);
+            fields.push(("country", country));
+        }
+        if let Some(asn) = location.asn {
+            fields.push(("autonomous_system_number", mmdb_uint(6, asn as u64)));
+        }
+        if let Some(org) = &location.as_org {
+            fields.push(("autonomous_system_organization", mmdb_string(org)));
+        }
+        mmdb_map(&mut data, fields);
+
+        let mut node = 0;
+        for bit in 0..prefix.max(1) {
+            let right = (bits[bit / 8] & (0x80 >> (bit % 8)) != 0) as usize;
+            if bit + 1 == prefix.max(1) {
+                nodes[node][right] = Record::Data(offset);
+                break;
+            }
+            node = match nodes[node][right] {
+                Record::Node(next) => next as usize,
+                _ => {
+                    nodes.push([Record::Empty; 2]);
+                    nodes[node][right] = Record::Node(nodes.len() as u32 - 1);
+                    nodes.len() - 1
+                }
+            };
+        }
+    }
+
+    let node_count = nodes.len() as u32;
+    let mut bytes = Vec::new();
+    for records in &nodes {
+        for record in records {
+            let value = match *record {
+                Record::Empty => node_count,
+                Record::Node(node) => node,
+                Record::Data(offset) => node_count + 16 + offset,
+            };
+            bytes.extend_from_slice(&value.to_be_bytes()[1..]);
+        }
+    }
+    bytes.extend_from_slice(&[0; 16]);
+    bytes.extend_from_slice(&data);
+    bytes.extend_from_slice(b"\xAB\xCD\xEFMaxMind.com");
+    mmdb_map(
+        &mut bytes,
+        vec![
+            ("node_count", mmdb_uint(6, node_count as u64)),
+            ("record_size", mmdb_uint(5, 24)),
+            ("ip_version", mmdb_uint(5, 6)),
+            ("database_type", mmdb_string("Fixture-Country-ASN")),
+        ],
+    );
+    bytes
+}
+
+/// Control byte(s) of a data section field of `kind` and `size`
+fn mmdb_control(out: &mut Vec<u8>, kind: u8, size: usize) {
+    let (kind_bits, extended) = if kind > 7 {
+        (0, Some(kind - 7))
+    } else {
+        (kind, None)
+    };
+    match size {
+        0..=28 => out.push(kind_bits << 5 | size as u8),
+        29..=284 => out.push(kind_bits << 5 | 29),
+        _ => out.push(kind_bits << 5 | 30),
+    }
+    out.extend(extended);
+    match size {
+        0..=28 => {}
+        29..=284 => out.push((size - 29) as u8),
+        _ => out.extend_from_slice(&((size - 285) as u16).to_be_bytes()),
+    }
+}
+
+fn mmdb_string(value: &str) -> Vec<u8> {
+    let mut out = Vec::new();
+    mmdb_control(&mut out, 2, value.len());
+    out.extend_from_slice(value.as_bytes());
+    out
+}
+
+/// An unsigned int of `kind` 5 (16 bits) or 6 (32 bits)
+fn mmdb_uint(kind: u8, value: u64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let skip = bytes.iter().take_while(|b| **b == 0).count();
+    let mut out = Vec::new();
+    mmdb_control(&mut out, kind, bytes.len() - skip);
+    out.extend_from_slice(&bytes[skip..]);
+    out
+}
+
+fn mmdb_map(out: &mut Vec<u8>, fields: Vec<(&str, Vec<u8>)>) {
+    mmdb_control(out, 7, fields.len());
+    for (key, value) in fields {
+        out.extend(mmdb_string(key));
+        out.extend(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reseeding_repeats_the_same_values() {
+        reseed(7);
+        let first = (fake_txid(), fake_address(), fake_note().build().id);
+        reseed(7);
+        let second = (fake_txid(), fake_address(), fake_note().build().id);
+        assert_eq!(first, second);
+        assert_ne!(fake_txid(), first.0);
+    }
+
+    #[test]
+    fn confirmed_notes_and_transactions_are_timed_by_height() {
+        let note = fake_note().confirmed_at(3).build();
+        assert_eq!(note.block_height, Some(3));
+        assert_eq!(note.created_at, fixture_time(3 * BLOCK_INTERVAL));
+
+        let tx = fake_transaction().outgoing().confirmed_at(3).build();
+        assert_eq!(tx.status, TransactionStatus::Confirmed { block_height: 3 });
+        assert_eq!(tx.confirmed_at, Some(fixture_time(3 * BLOCK_INTERVAL)));
+        assert_eq!(tx.broadcast_at, Some(tx.created_at));
+    }
+
+    #[test]
+    fn chained_blocks_link_and_commit_to_their_transactions() {
+        let parent = fake_block().with_txs(2).build();
+        let child = fake_block().on(&parent).paying(&fake_address(), 5).build();
+        assert_eq!(child.header.height, 1);
+        assert_eq!(child.header.previous_hash, parent.hash());
+        assert_eq!(parent.transactions.len(), 2);
+        assert!(parent.commits_to(&parent.transactions[1].id));
+        assert!(child.header.timestamp > parent.header.timestamp);
+    }
+
+    #[test]
+    fn signed_transaction_fixture_balances_its_input() {
+        let to = fake_address();
+        let tx = fake_signed_transaction().paying(&to, 300).fee(7).build();
+        assert_eq!(tx.inputs.len(), 1);
+        assert_eq!(tx.inputs[0].amount, 307);
+        assert_eq!(tx.outputs[0].recipient_address, to.to_string());
+        assert_eq!(tx.id, encoding::transaction_id(&tx));
+    }
+
+    #[test]
+    fn fake_clock_jumps_only_the_wall_clock() {
+        let clock = FakeClock::new();
+        let (wall, instant) = (clock.now(), clock.instant());
+        clock.jump(-60);
+        assert_eq!(clock.now(), wall - chrono::Duration::seconds(60));
+        assert_eq!(clock.instant(), instant);
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(clock.instant(), instant + Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,93 @@
+//! Versions of everything an installation runs, for support requests.
+//!
+//! `VersionInfo` is what the About section of Settings shows, what the
+//! `getversion` RPC returns and what the debug bundle carries as
+//! `version.json`.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::wallet::network::NockchainNodeConfig;
+use crate::wallet::storage::STORAGE_SCHEMA_VERSION;
+
+/// Crate, library, schema, chain and build versions of this installation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionInfo {
+    /// Version of the api crate
+    pub api_version: String,
+    /// Version of the nockchain libraries the node runs
+    pub nockchain_version: String,
+    /// Newest storage schema this build reads and writes
+    pub storage_schema: u32,
+    /// Schema the open wallet's data dir was last written with, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wallet_schema: Option<u32>,
+    /// Whether the node is configured for fakenet rather than mainnet
+    pub fakenet: bool,
+    /// Hex id of the chain transactions are signed for
+    pub chain_id: String,
+    /// Hex hash of the genesis block
+    pub genesis_hash: String,
+    /// `debug` or `release`
+    pub build_profile: String,
+    /// Architecture and OS the build targets, e.g. `x86_64-linux`
+    pub target: String,
+}
+
+impl VersionInfo {
+    /// Versions of this build and of the chain `config` is for
+    pub fn collect(nockchain_version: String, config: &NockchainNodeConfig) -> Self {
+        Self {
+            api_version: env!("CARGO_PKG_VERSION").to_string(),
+            nockchain_version,
+            storage_schema: STORAGE_SCHEMA_VERSION,
+            wallet_schema: None,
+            fakenet: config.fakenet,
+            chain_id: hex::encode(config.chain_id()),
+            genesis_hash: hex::encode(config.genesis_hash),
+            build_profile: if cfg!(debug_assertions) {
+                "debug"
+            } else {
+                "release"
+            }
+            .to_string(),
+            target: format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS),
+        }
+    }
+
+    /// Add the schema the open wallet was written with
+    pub fn with_wallet_schema(mut self, wallet_schema: Option<u32>) -> Self {
+        self.wallet_schema = wallet_schema;
+        self
+    }
+
+    /// Label and value of each field, in display order
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        let wallet_schema = self
+            .wallet_schema
+            .map_or_else(|| "not recorded".to_string(), |schema| schema.to_string());
+        vec![
+            ("API", self.api_version.clone()),
+            ("Nockchain", self.nockchain_version.clone()),
+            ("Storage schema", self.storage_schema.to_string()),
+            ("Wallet data schema", wallet_schema),
+            (
+                "Network",
+                if self.fakenet { "fakenet" } else { "mainnet" }.to_string(),
+            ),
+            ("Chain ID", self.chain_id.clone()),
+            ("Genesis", self.genesis_hash.clone()),
+            ("Build", format!("{} ({})", self.build_profile, self.target)),
+        ]
+    }
+}
+
+/// One `label: value` line per field, for pasting into a support request
+impl fmt::Display for VersionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (label, value) in self.fields() {
+            writeln!(f, "{}: {}", label, value)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,297 @@
+//! Speeding up a stuck incoming payment with child-pays-for-parent.
+//!
+//! `Wallet::accelerate_incoming` spends the wallet's unconfirmed output of a
+//! pending incoming transaction back to one of its change addresses, paying a
+//! fee large enough that parent and child together meet the rate of the
+//! chosen priority. A miner can only take the child along with its parent, so
+//! the pair is worth mining at that rate. The mempool keeps a child for as
+//! long as its parent is pending. The child is recorded as an internal move
+//! with `Transaction::accelerates` naming the parent.
+
+use tracing::info;
+
+use crate::wallet::encoding::Encode;
+use crate::wallet::keys::{OutPoint, TransactionInput, TransactionOutput};
+use crate::wallet::send::{Broadcaster, SendError, SendProgress, SendStage};
+use crate::wallet::templates::FeePriority;
+use crate::wallet::transaction::{SignedTransaction, TransactionBuilder};
+use crate::wallet::{Note, TransactionDirection, Wallet, WalletError, WalletResult};
+
+/// Smallest output worth creating; an acceleration leaving less is refused
+pub const DUST_LIMIT: u64 = 1_000;
+
+impl Wallet {
+    /// Spend the largest unconfirmed note pending incoming transaction `tx_id`
+    /// pays the wallet back to a change address, with a fee bringing the pair
+    /// up to `priority`'s rate, and broadcast it. `fallback_fee` is the normal
+    /// fee used while there is too little fee history. Refused with
+    /// `WalletError::AccelerationTooSmall` if the note can't cover the fee
+    /// and still leave `DUST_LIMIT`.
+    pub async fn accelerate_incoming<B: Broadcaster>(
+        &mut self,
+        tx_id: &str,
+        priority: FeePriority,
+        fallback_fee: u64,
+        broadcaster: &B,
+    ) -> Result<SignedTransaction, SendError> {
+        let fail = SendError::before_broadcast;
+
+        let (note, key_name, parent) = self
+            .ensure_writable()
+            .and_then(|_| self.acceleration_input(tx_id))
+            .map_err(|e| fail(SendStage::SelectFunds, e, false, false))?;
+
+        let ancestors = [(parent.fee, parent.to_bytes().len())];
+        let child = self
+            .build_acceleration(&note, &key_name, 0)
+            .and_then(|builder| builder.build_and_sign(self.keys(), &key_name))
+            .map_err(|e| fail(SendStage::Sign, e, false, false))?;
+        let fee =
+            self.fees
+                .package_child_fee(priority, &ancestors, child.to_bytes().len(), fallback_fee);
+        let needed = fee.saturating_add(DUST_LIMIT);
+        if note.amount < needed {
+            return Err(fail(
+                SendStage::SelectFunds,
+                WalletError::AccelerationTooSmall {
+                    amount: note.amount,
+                    needed,
+                },
+                false,
+                false,
+            ));
+        }
+
+        self.balances_mut()
+            .lock_notes(&[note.id])
+            .map_err(|e| fail(SendStage::SelectFunds, e, false, false))?;
+        let child = match self
+            .build_acceleration(&note, &key_name, fee)
+            .and_then(|builder| builder.build_and_sign(self.keys(), &key_name))
+        {
+            Ok(child) => child,
+            Err(e) => {
+                self.balances_mut().unlock_notes(&[note.id]);
+                return Err(fail(SendStage::Sign, e, true, true));
+            }
+        };
+
+        let amount = note.amount - fee;
+        let to = child
+            .outputs
+            .first()
+            .map(|output| output.recipient_address.clone())
+            .unwrap_or_default();
+        let child = self
+            .broadcast_and_record(
+                child,
+                amount,
+                to,
+                &[note.id],
+                (broadcaster, false),
+                |_: SendProgress| {},
+            )
+            .await?;
+        self.transactions_mut().set_accelerates(&child.id, tx_id);
+        info!(
+            "Accelerating transaction {} with child {} paying {}",
+            tx_id, child.id, fee
+        );
+        Ok(child)
+    }
+
+    /// The note to spend for accelerating `tx_id`, the key that signs for it
+    /// and the signed parent
+    fn acceleration_input(&self, tx_id: &str) -> WalletResult<(Note, String, SignedTransaction)> {
+        let incoming = self
+            .transactions()
+            .get_pending_transactions()
+            .iter()
+            .any(|tx| tx.id == tx_id && tx.direction == TransactionDirection::Incoming);
+        if !incoming {
+            return Err(WalletError::Transaction(format!(
+                "Transaction {} is not a pending incoming payment",
+                tx_id
+            )));
+        }
+        let parent = self.transactions().signed(tx_id).cloned().ok_or_else(|| {
+            WalletError::Transaction(format!("Signed form of transaction {} is unknown", tx_id))
+        })?;
+
+        let note = (0..parent.outputs.len() as u32)
+            .filter_map(|index| self.balances().note_at(&OutPoint::new(tx_id, index)))
+            .filter(|note| note.block_height.is_none() && !note.spent && !note.locked)
+            .filter(|note| self.keys().owner_of(&note.address).is_some())
+            .max_by_key(|note| note.amount)
+            .cloned()
+            .ok_or_else(|| {
+                WalletError::Transaction(format!(
+                    "Transaction {} pays no unconfirmed output this wallet can spend",
+                    tx_id
+                ))
+            })?;
+        let key_name = self
+            .keys()
+            .owner_of(&note.address)
+            .map(str::to_string)
+            .ok_or_else(|| WalletError::KeyNotFound(note.address.to_string()))?;
+        Ok((note, key_name, parent))
+    }
+
+    /// Builder moving `note` less `fee` to the key's next change address
+    fn build_acceleration(
+        &mut self,
+        note: &Note,
+        key_name: &str,
+        fee: u64,
+    ) -> WalletResult<TransactionBuilder> {
+        let change_address = self.keys.change_address(key_name)?;
+        let mut builder = TransactionBuilder::new(self.chain_id());
+        builder.add_owned_input(
+            TransactionInput {
+                previous_output: OutPoint::new(note.transaction_id.clone(), note.output_index),
+                amount: note.amount,
+            },
+            note.address.clone(),
+        );
+        builder.add_output(TransactionOutput {
+            amount: note.amount.saturating_sub(fee),
+            recipient_address: change_address.to_string(),
+        });
+        builder.set_fee(fee);
+        Ok(builder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_note, fake_signed_transaction, fake_txid};
+    use crate::wallet::mempool::Mempool;
+    use crate::wallet::Address;
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+
+    /// Broadcasts into a local mempool
+    struct IntoMempool(Mutex<Mempool>);
+
+    impl Broadcaster for IntoMempool {
+        async fn broadcast(&self, tx: &SignedTransaction, _force: bool) -> WalletResult<()> {
+            self.0.lock().unwrap().insert(tx.clone()).map(|_| ())
+        }
+    }
+
+    /// A wallet with a pending incoming payment of `amount` and a fee of 10,
+    /// which is already in `mempool`
+    fn paid_pending(amount: u64, mempool: &IntoMempool) -> (Wallet, SignedTransaction, Address) {
+        let mut wallet = Wallet::new();
+        let address = wallet.generate_key("main").unwrap();
+        let parent = fake_signed_transaction()
+            .spending(fake_txid(), 0, amount + 10)
+            .paying(&address, amount)
+            .fee(10)
+            .build();
+        wallet.record_transaction(parent.clone(), false);
+        wallet
+            .observe_note(
+                fake_note()
+                    .address(address.clone())
+                    .amount(amount)
+                    .outpoint(parent.id.clone(), 0)
+                    .build(),
+            )
+            .unwrap();
+        mempool.0.lock().unwrap().insert(parent.clone()).unwrap();
+        (wallet, parent, address)
+    }
+
+    #[tokio::test]
+    async fn the_child_spends_the_pending_output_and_pays_for_both() {
+        let mempool = IntoMempool(Mutex::new(Mempool::new()));
+        let (mut wallet, parent, address) = paid_pending(5_000, &mempool);
+
+        let child = wallet
+            .accelerate_incoming(&parent.id, FeePriority::Normal, 100, &mempool)
+            .await
+            .unwrap();
+        // No fee history, so parent and child are each held to the normal fee
+        assert_eq!(child.fee, 2 * 100 - parent.fee);
+        assert_eq!(child.inputs.len(), 1);
+        assert_eq!(
+            child.inputs[0].outpoint(),
+            &OutPoint::new(parent.id.clone(), 0)
+        );
+        assert_eq!(child.outputs.len(), 1);
+        assert_eq!(child.outputs[0].amount, 5_000 - child.fee);
+        let change = Address::from_string(&child.outputs[0].recipient_address).unwrap();
+        assert_ne!(change, address);
+        assert!(wallet.keys().is_mine(&change));
+
+        // The mempool takes the child and keeps it while the parent is valid
+        let mut mempool = mempool.0.into_inner().unwrap();
+        let ancestors: Vec<&str> = mempool
+            .ancestors(&child.id)
+            .into_iter()
+            .map(|tx| tx.id.as_str())
+            .collect();
+        assert_eq!(ancestors, vec![parent.id.as_str()]);
+        let utxos: HashSet<OutPoint> = [parent.inputs[0].outpoint().clone()].into();
+        assert!(mempool.revalidate(&utxos).is_empty());
+        assert_eq!(mempool.len(), 2);
+
+        let recorded = wallet.transactions().accelerated_by(&parent.id).unwrap();
+        assert_eq!(recorded.id, child.id);
+        assert_eq!(recorded.accelerates.as_deref(), Some(parent.id.as_str()));
+        let note = wallet
+            .balances()
+            .note_at(&OutPoint::new(parent.id.clone(), 0))
+            .unwrap();
+        assert!(note.spent || note.locked);
+    }
+
+    #[tokio::test]
+    async fn an_output_too_small_for_the_fee_and_dust_is_refused() {
+        let mempool = IntoMempool(Mutex::new(Mempool::new()));
+        let (mut wallet, parent, _) = paid_pending(1_100, &mempool);
+
+        let error = wallet
+            .accelerate_incoming(&parent.id, FeePriority::Normal, 100, &mempool)
+            .await
+            .unwrap_err();
+        assert_eq!(error.stage, SendStage::SelectFunds);
+        assert!(!error.funds_locked);
+        match error.source {
+            WalletError::AccelerationTooSmall { amount, needed } => {
+                assert_eq!(amount, 1_100);
+                assert_eq!(needed, 2 * 100 - parent.fee + DUST_LIMIT);
+            }
+            other => panic!("expected AccelerationTooSmall, got {:?}", other),
+        }
+        let note = wallet
+            .balances()
+            .note_at(&OutPoint::new(parent.id.clone(), 0))
+            .unwrap();
+        assert!(!note.locked && !note.spent);
+        assert_eq!(mempool.0.lock().unwrap().len(), 1);
+        assert!(wallet.transactions().accelerated_by(&parent.id).is_none());
+    }
+
+    #[tokio::test]
+    async fn only_pending_incoming_payments_are_accelerated() {
+        let mempool = IntoMempool(Mutex::new(Mempool::new()));
+        let (mut wallet, _, _) = paid_pending(5_000, &mempool);
+        let outgoing = fake_signed_transaction().build();
+        wallet.record_transaction(outgoing.clone(), true);
+
+        for tx_id in [outgoing.id.as_str(), "unknown"] {
+            let error = wallet
+                .accelerate_incoming(tx_id, FeePriority::High, 100, &mempool)
+                .await
+                .unwrap_err();
+            assert!(error
+                .source
+                .to_string()
+                .contains("is not a pending incoming payment"));
+        }
+    }
+}
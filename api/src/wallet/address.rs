@@ -0,0 +1,322 @@
+//! Checksummed address strings.
+//!
+//! The checked form is base58 of a network version byte, the 32-byte public
+//! key and the first four bytes of sha256(sha256(version || key)). Plain
+//! base58 keys, as produced by `Address::to_string`, are still accepted by
+//! `Address::from_string_checked` but carry no checksum or network.
+//!
+//! Addresses are shown to the user in the nockchain form, the plain key
+//! behind a `nock_` prefix. Every parser here takes either form, with the
+//! prefix in any case, so a pasted address works whichever one it is.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::wallet::encoding::sha256;
+use crate::wallet::{Address, WalletError};
+
+const KEY_LEN: usize = 32;
+const CHECKSUM_LEN: usize = 4;
+const CHECKED_LEN: usize = 1 + KEY_LEN + CHECKSUM_LEN;
+
+/// Prefix of the nockchain form of an address
+pub const NOCKCHAIN_ADDRESS_PREFIX: &str = "nock_";
+
+/// Network an address is meant for, encoded as its version byte
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AddressNetwork {
+    #[default]
+    Mainnet,
+    Fakenet,
+}
+
+impl AddressNetwork {
+    pub fn version_byte(&self) -> u8 {
+        match self {
+            AddressNetwork::Mainnet => 0x4e,
+            AddressNetwork::Fakenet => 0x66,
+        }
+    }
+
+    pub fn from_version_byte(byte: u8) -> Option<Self> {
+        [AddressNetwork::Mainnet, AddressNetwork::Fakenet]
+            .into_iter()
+            .find(|network| network.version_byte() == byte)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AddressNetwork::Mainnet => "mainnet",
+            AddressNetwork::Fakenet => "fakenet",
+        }
+    }
+}
+
+/// Why a string is not a usable address
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum AddressError {
+    #[error("Address is empty")]
+    Empty,
+
+    #[error("'{character}' at position {index} is not a base58 character")]
+    InvalidCharacter { character: char, index: usize },
+
+    #[error("Address decodes to {0} bytes; expected {KEY_LEN} or {CHECKED_LEN}")]
+    WrongLength(usize),
+
+    #[error("Checksum does not match; the address may have a typo")]
+    ChecksumMismatch,
+
+    #[error("Address is for {found}, not {expected}")]
+    WrongNetwork { expected: String, found: String },
+
+    #[error("Address doesn't start with {NOCKCHAIN_ADDRESS_PREFIX}")]
+    MissingPrefix,
+}
+
+impl From<AddressError> for WalletError {
+    fn from(e: AddressError) -> Self {
+        WalletError::InvalidAddress(e.to_string())
+    }
+}
+
+impl Address {
+    /// Parse a checked address for `network`, or a plain base58 key
+    pub fn from_string_checked(s: &str, network: AddressNetwork) -> Result<Self, AddressError> {
+        let s = strip_nockchain_prefix(s.trim()).unwrap_or(s.trim());
+        if s.is_empty() {
+            return Err(AddressError::Empty);
+        }
+
+        let decoded = bs58::decode(s).into_vec().map_err(|e| match e {
+            bs58::decode::Error::InvalidCharacter { character, index } => {
+                AddressError::InvalidCharacter { character, index }
+            }
+            _ => AddressError::WrongLength(0),
+        })?;
+
+        match decoded.len() {
+            KEY_LEN => Ok(Address::from_bytes(&decoded)),
+            CHECKED_LEN => {
+                let (payload, checksum) = decoded.split_at(1 + KEY_LEN);
+                if checksum != address_checksum(payload) {
+                    return Err(AddressError::ChecksumMismatch);
+                }
+                if payload[0] != network.version_byte() {
+                    let found = AddressNetwork::from_version_byte(payload[0])
+                        .map(|found| found.label().to_string())
+                        .unwrap_or_else(|| format!("unknown network 0x{:02x}", payload[0]));
+                    return Err(AddressError::WrongNetwork {
+                        expected: network.label().to_string(),
+                        found,
+                    });
+                }
+                Ok(Address::from_bytes(&payload[1..]))
+            }
+            len => Err(AddressError::WrongLength(len)),
+        }
+    }
+
+    /// The form addresses are shown in: the plain key after `nock_`
+    pub fn to_nockchain_string(&self) -> String {
+        format!("{}{}", NOCKCHAIN_ADDRESS_PREFIX, self.to_string())
+    }
+
+    /// Parse the nockchain form; the prefix is required
+    pub fn from_nockchain_string(s: &str) -> Result<Self, AddressError> {
+        let key = strip_nockchain_prefix(s.trim()).ok_or(AddressError::MissingPrefix)?;
+        if key.is_empty() {
+            return Err(AddressError::Empty);
+        }
+        let decoded = bs58::decode(key).into_vec().map_err(|e| match e {
+            bs58::decode::Error::InvalidCharacter { character, index } => {
+                AddressError::InvalidCharacter {
+                    character,
+                    index: NOCKCHAIN_ADDRESS_PREFIX.len() + index,
+                }
+            }
+            _ => AddressError::WrongLength(0),
+        })?;
+        if decoded.len() != KEY_LEN {
+            return Err(AddressError::WrongLength(decoded.len()));
+        }
+        Ok(Address::from_bytes(&decoded))
+    }
+
+    /// Checksummed form of this address for `network`
+    pub fn to_checked_string(&self, network: AddressNetwork) -> String {
+        let mut bytes = Vec::with_capacity(CHECKED_LEN);
+        bytes.push(network.version_byte());
+        bytes.extend_from_slice(&self.public_key);
+        let checksum = address_checksum(&bytes);
+        bytes.extend_from_slice(&checksum);
+        bs58::encode(bytes).into_string()
+    }
+}
+
+/// `s` without the `nock_` prefix, matched in any case; `None` if it has none
+pub(crate) fn strip_nockchain_prefix(s: &str) -> Option<&str> {
+    let prefix = s.get(..NOCKCHAIN_ADDRESS_PREFIX.len())?;
+    prefix
+        .eq_ignore_ascii_case(NOCKCHAIN_ADDRESS_PREFIX)
+        .then(|| &s[NOCKCHAIN_ADDRESS_PREFIX.len()..])
+}
+
+/// First four bytes of sha256(sha256(payload))
+pub(crate) fn address_checksum(payload: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let hash = sha256(&sha256(payload));
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fake_address;
+
+    fn checked(address: &Address) -> String {
+        address.to_checked_string(AddressNetwork::Mainnet)
+    }
+
+    /// `s` with the character at `index` replaced by another base58 character
+    fn with_typo(s: &str, index: usize) -> String {
+        let mut chars: Vec<char> = s.chars().collect();
+        chars[index] = if chars[index] == '2' { '3' } else { '2' };
+        chars.into_iter().collect()
+    }
+
+    #[test]
+    fn checked_plain_and_prefixed_forms_all_parse() {
+        let address = fake_address();
+        for text in [
+            checked(&address),
+            format!(" {} ", checked(&address)),
+            address.to_string(),
+            address.to_nockchain_string(),
+            format!("NOCK_{}", address),
+        ] {
+            assert_eq!(
+                Address::from_string_checked(&text, AddressNetwork::Mainnet),
+                Ok(address.clone()),
+                "{}",
+                text
+            );
+        }
+        // A plain key carries no network, so it passes for any
+        assert_eq!(
+            Address::from_string_checked(&address.to_string(), AddressNetwork::Fakenet),
+            Ok(address)
+        );
+    }
+
+    #[test]
+    fn each_kind_of_bad_address_gets_its_own_error() {
+        let address = fake_address();
+        let text = checked(&address);
+        let check = |s: &str| Address::from_string_checked(s, AddressNetwork::Mainnet);
+
+        assert_eq!(check("   "), Err(AddressError::Empty));
+        assert_eq!(check("nock_"), Err(AddressError::Empty));
+        assert_eq!(
+            check(&format!("{}0", &text[..5])),
+            Err(AddressError::InvalidCharacter {
+                character: '0',
+                index: 5
+            })
+        );
+        assert_eq!(
+            check(&text[..text.len() - 3]),
+            Err(AddressError::WrongLength(
+                bs58::decode(&text[..text.len() - 3])
+                    .into_vec()
+                    .unwrap()
+                    .len()
+            ))
+        );
+        assert_eq!(
+            check(&with_typo(&text, text.len() / 2)),
+            Err(AddressError::ChecksumMismatch)
+        );
+        assert_eq!(
+            check(&address.to_checked_string(AddressNetwork::Fakenet)),
+            Err(AddressError::WrongNetwork {
+                expected: "mainnet".to_string(),
+                found: "fakenet".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn an_unknown_version_byte_is_named_in_the_error() {
+        let mut bytes = vec![0x01];
+        bytes.extend_from_slice(&fake_address().public_key);
+        let checksum = address_checksum(&bytes);
+        bytes.extend_from_slice(&checksum);
+        assert_eq!(
+            Address::from_string_checked(
+                &bs58::encode(bytes).into_string(),
+                AddressNetwork::Mainnet
+            ),
+            Err(AddressError::WrongNetwork {
+                expected: "mainnet".to_string(),
+                found: "unknown network 0x01".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn the_nockchain_form_requires_its_prefix() {
+        let address = fake_address();
+        assert_eq!(
+            Address::from_nockchain_string(&address.to_nockchain_string()),
+            Ok(address.clone())
+        );
+        assert_eq!(
+            Address::from_nockchain_string(&address.to_string()),
+            Err(AddressError::MissingPrefix)
+        );
+        assert_eq!(
+            Address::from_nockchain_string("nock_0"),
+            Err(AddressError::InvalidCharacter {
+                character: '0',
+                index: 5
+            })
+        );
+    }
+
+    #[test]
+    fn both_forms_round_trip_and_compare_equal() {
+        for seed in 0..16u8 {
+            let address = Address::from_bytes(&[seed.wrapping_mul(37); KEY_LEN]);
+            let prefixed = address.to_nockchain_string();
+            assert!(prefixed.starts_with(NOCKCHAIN_ADDRESS_PREFIX));
+            assert_eq!(
+                &prefixed[NOCKCHAIN_ADDRESS_PREFIX.len()..],
+                address.to_string()
+            );
+
+            let parsed = [
+                Address::from_nockchain_string(&prefixed).unwrap(),
+                Address::from_string(&prefixed).unwrap(),
+                Address::from_string(&address.to_string()).unwrap(),
+                Address::from_string_checked(&prefixed, AddressNetwork::Mainnet).unwrap(),
+                Address::from_string_checked(&checked(&address), AddressNetwork::Mainnet).unwrap(),
+            ];
+            assert!(parsed.iter().all(|parsed| *parsed == address));
+            assert_eq!(parsed[0].to_nockchain_string(), prefixed);
+        }
+    }
+
+    #[test]
+    fn a_prefixed_key_of_the_wrong_length_is_refused() {
+        let short = format!("nock_{}", bs58::encode([7u8; 20]).into_string());
+        assert_eq!(
+            Address::from_nockchain_string(&short),
+            Err(AddressError::WrongLength(20))
+        );
+        assert_eq!(
+            Address::from_nockchain_string(" nock_ "),
+            Err(AddressError::Empty)
+        );
+        assert!(Address::from_string(&short).is_err());
+    }
+}
@@ -0,0 +1,480 @@
+//! Parsing and formatting of NOCK amounts. Amounts are always held in base
+//! units (nano-NOCK) and converted with integer arithmetic only.
+//!
+//! `format_amount` gives the exact value, for inputs and anything that is
+//! parsed again. `format_display` follows the user's `DisplaySettings` and
+//! may round, so it is for showing amounts only; exports keep base units.
+
+use serde::{Deserialize, Serialize};
+
+use crate::wallet::storage::StorageManager;
+use crate::wallet::{Wallet, WalletError, WalletResult};
+
+/// Display settings, saved through `StorageManager` in the wallet data dir
+pub const DISPLAY_SETTINGS_FILE: &str = "display_settings.json";
+
+/// Decimal places of one NOCK
+pub const NOCK_DECIMALS: u32 = 6;
+/// Base units (nano-NOCK) per NOCK
+pub const NANO_PER_NOCK: u64 = 10u64.pow(NOCK_DECIMALS);
+
+/// Unit an amount is entered or displayed in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AmountUnit {
+    #[default]
+    Nock,
+    Nano,
+}
+
+impl AmountUnit {
+    pub const ALL: [AmountUnit; 2] = [AmountUnit::Nock, AmountUnit::Nano];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AmountUnit::Nock => "NOCK",
+            AmountUnit::Nano => "nano",
+        }
+    }
+
+    /// Decimal places accepted when entering an amount in this unit
+    pub fn decimals(&self) -> u32 {
+        match self {
+            AmountUnit::Nock => NOCK_DECIMALS,
+            AmountUnit::Nano => 0,
+        }
+    }
+}
+
+/// Parse `text` entered in `unit` into base units
+pub fn parse_amount(text: &str, unit: AmountUnit) -> WalletResult<u64> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(WalletError::InvalidAmount("Enter an amount".to_string()));
+    }
+
+    let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+    let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty()) || !is_digits(whole) || !is_digits(fraction) {
+        return Err(WalletError::InvalidAmount(format!(
+            "'{}' is not a number",
+            text
+        )));
+    }
+
+    let decimals = unit.decimals();
+    if fraction.len() > decimals as usize {
+        return Err(WalletError::InvalidAmount(match decimals {
+            0 => format!("{} amounts are whole numbers", unit.label()),
+            _ => format!(
+                "{} amounts have at most {} decimal places",
+                unit.label(),
+                decimals
+            ),
+        }));
+    }
+
+    let too_large = || WalletError::InvalidAmount("Amount is too large".to_string());
+    let scale = 10u64.pow(decimals);
+    let whole: u64 = match whole {
+        "" => 0,
+        digits => digits.parse().map_err(|_| too_large())?,
+    };
+    let fraction: u64 = match fraction {
+        "" => 0,
+        digits => {
+            digits.parse::<u64>().map_err(|_| too_large())?
+                * 10u64.pow(decimals - digits.len() as u32)
+        }
+    };
+
+    whole
+        .checked_mul(scale)
+        .and_then(|base| base.checked_add(fraction))
+        .ok_or_else(too_large)
+}
+
+/// Format base units in `unit`, without trailing zeros after the decimal point
+pub fn format_amount(amount: u64, unit: AmountUnit) -> String {
+    match unit {
+        AmountUnit::Nano => amount.to_string(),
+        AmountUnit::Nock => {
+            let whole = amount / NANO_PER_NOCK;
+            let fraction = amount % NANO_PER_NOCK;
+            if fraction == 0 {
+                whole.to_string()
+            } else {
+                let fraction = format!("{:0width$}", fraction, width = NOCK_DECIMALS as usize);
+                format!("{}.{}", whole, fraction.trim_end_matches('0'))
+            }
+        }
+    }
+}
+
+/// How amounts are cut to the decimals shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RoundingMode {
+    /// To the nearest, ties to the even digit
+    #[default]
+    HalfEven,
+    /// Drop the digits not shown
+    Truncate,
+}
+
+impl RoundingMode {
+    pub const ALL: [RoundingMode; 2] = [RoundingMode::HalfEven, RoundingMode::Truncate];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RoundingMode::HalfEven => "Round half to even",
+            RoundingMode::Truncate => "Truncate",
+        }
+    }
+}
+
+/// Thousands separator, and with it the decimal mark
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DigitGrouping {
+    /// 1234567.89
+    #[default]
+    None,
+    /// 1,234,567.89
+    Comma,
+    /// 1.234.567,89
+    Period,
+    /// 1 234 567.89
+    Space,
+    /// 1'234'567.89
+    Apostrophe,
+}
+
+impl DigitGrouping {
+    pub const ALL: [DigitGrouping; 5] = [
+        DigitGrouping::None,
+        DigitGrouping::Comma,
+        DigitGrouping::Period,
+        DigitGrouping::Space,
+        DigitGrouping::Apostrophe,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DigitGrouping::None => "1234567.89",
+            DigitGrouping::Comma => "1,234,567.89",
+            DigitGrouping::Period => "1.234.567,89",
+            DigitGrouping::Space => "1 234 567.89",
+            DigitGrouping::Apostrophe => "1'234'567.89",
+        }
+    }
+
+    pub fn separator(&self) -> Option<char> {
+        match self {
+            DigitGrouping::None => None,
+            DigitGrouping::Comma => Some(','),
+            DigitGrouping::Period => Some('.'),
+            DigitGrouping::Space => Some(' '),
+            DigitGrouping::Apostrophe => Some('\''),
+        }
+    }
+
+    pub fn decimal_mark(&self) -> char {
+        match self {
+            DigitGrouping::Period => ',',
+            _ => '.',
+        }
+    }
+}
+
+/// How NOCK amounts are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisplaySettings {
+    /// Decimal places shown, at most `NOCK_DECIMALS`
+    pub decimals: u32,
+    #[serde(default)]
+    pub rounding: RoundingMode,
+    /// Pad the fraction to `decimals` places rather than trimming its zeros
+    pub trailing_zeros: bool,
+    #[serde(default)]
+    pub grouping: DigitGrouping,
+}
+
+impl Default for DisplaySettings {
+    /// Full precision with every decimal place shown
+    fn default() -> Self {
+        Self {
+            decimals: NOCK_DECIMALS,
+            rounding: RoundingMode::default(),
+            trailing_zeros: true,
+            grouping: DigitGrouping::default(),
+        }
+    }
+}
+
+/// Format base units as NOCK for display, following `settings`
+pub fn format_display(amount: u64, settings: &DisplaySettings) -> String {
+    let decimals = settings.decimals.min(NOCK_DECIMALS);
+    let divisor = 10u64.pow(NOCK_DECIMALS - decimals);
+    let mut scaled = amount / divisor;
+    let remainder = amount % divisor;
+    if settings.rounding == RoundingMode::HalfEven && remainder != 0 {
+        let half = divisor / 2;
+        if remainder > half || (remainder == half && scaled % 2 == 1) {
+            scaled += 1;
+        }
+    }
+
+    let scale = 10u64.pow(decimals);
+    let whole = (scaled / scale).to_string();
+    let mut fraction = format!("{:0width$}", scaled % scale, width = decimals as usize);
+    if !settings.trailing_zeros {
+        fraction.truncate(fraction.trim_end_matches('0').len());
+    }
+
+    let mut text = match settings.grouping.separator() {
+        Some(separator) => {
+            let mut grouped = String::with_capacity(whole.len() * 4 / 3);
+            for (i, digit) in whole.chars().enumerate() {
+                if i > 0 && (whole.len() - i).is_multiple_of(3) {
+                    grouped.push(separator);
+                }
+                grouped.push(digit);
+            }
+            grouped
+        }
+        None => whole,
+    };
+    if decimals > 0 && !fraction.is_empty() {
+        text.push(settings.grouping.decimal_mark());
+        text.push_str(&fraction);
+    }
+    text
+}
+
+impl Wallet {
+    pub fn display_settings(&self) -> DisplaySettings {
+        self.display
+    }
+
+    /// Change how amounts are shown; fails in read-only mode
+    pub fn set_display_settings(&mut self, settings: DisplaySettings) -> WalletResult<()> {
+        self.ensure_writable()?;
+        self.display = DisplaySettings {
+            decimals: settings.decimals.min(NOCK_DECIMALS),
+            ..settings
+        };
+        Ok(())
+    }
+
+    /// Load the display settings saved in the wallet data dir
+    pub async fn load_display_settings(&mut self) -> WalletResult<()> {
+        let Some(data_dir) = self.data_dir.clone() else {
+            return Ok(());
+        };
+        let storage = StorageManager::new(data_dir)?;
+        if storage.exists(DISPLAY_SETTINGS_FILE) {
+            self.display = storage.load(DISPLAY_SETTINGS_FILE).await?;
+        }
+        Ok(())
+    }
+
+    /// Save the display settings; does nothing in read-only mode
+    pub async fn save_display_settings(&self) -> WalletResult<()> {
+        let Some(data_dir) = self.data_dir.clone().filter(|_| !self.read_only) else {
+            return Ok(());
+        };
+        let storage = StorageManager::new(data_dir)?;
+        storage.save(DISPLAY_SETTINGS_FILE, &self.display).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(text: &str, unit: AmountUnit) -> String {
+        match parse_amount(text, unit) {
+            Err(WalletError::InvalidAmount(message)) => message,
+            other => panic!("expected an invalid amount for '{}', got {:?}", text, other),
+        }
+    }
+
+    #[test]
+    fn nock_amounts_parse_to_exact_base_units() {
+        let cases = [
+            ("1", 1_000_000),
+            ("1.5", 1_500_000),
+            (".25", 250_000),
+            ("7.", 7_000_000),
+            (" 0.000001 ", 1),
+            ("18446744073709.551615", u64::MAX),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(
+                parse_amount(text, AmountUnit::Nock).unwrap(),
+                expected,
+                "{}",
+                text
+            );
+        }
+        assert_eq!(parse_amount("42", AmountUnit::Nano).unwrap(), 42);
+    }
+
+    #[test]
+    fn malformed_or_oversized_amounts_are_explained() {
+        assert_eq!(parse_error("", AmountUnit::Nock), "Enter an amount");
+        assert_eq!(parse_error(".", AmountUnit::Nock), "'.' is not a number");
+        assert_eq!(parse_error("-1", AmountUnit::Nock), "'-1' is not a number");
+        assert_eq!(
+            parse_error("1e3", AmountUnit::Nock),
+            "'1e3' is not a number"
+        );
+        assert_eq!(
+            parse_error("0.0000001", AmountUnit::Nock),
+            "NOCK amounts have at most 6 decimal places"
+        );
+        assert_eq!(
+            parse_error("1.0", AmountUnit::Nano),
+            "nano amounts are whole numbers"
+        );
+        assert_eq!(
+            parse_error("18446744073709.551616", AmountUnit::Nock),
+            "Amount is too large"
+        );
+        assert_eq!(
+            parse_error("18446744073709551616", AmountUnit::Nano),
+            "Amount is too large"
+        );
+    }
+
+    #[test]
+    fn formatting_trims_trailing_zeros_and_parses_back() {
+        assert_eq!(format_amount(1_500_000, AmountUnit::Nock), "1.5");
+        assert_eq!(format_amount(2_000_000, AmountUnit::Nock), "2");
+        assert_eq!(format_amount(1, AmountUnit::Nock), "0.000001");
+        for amount in [0, 1, 999_999, 1_000_000, 123_456_789, u64::MAX] {
+            for unit in AmountUnit::ALL {
+                assert_eq!(
+                    parse_amount(&format_amount(amount, unit), unit).unwrap(),
+                    amount
+                );
+            }
+        }
+    }
+
+    fn at(decimals: u32, rounding: RoundingMode) -> DisplaySettings {
+        DisplaySettings {
+            decimals,
+            rounding,
+            ..DisplaySettings::default()
+        }
+    }
+
+    #[test]
+    fn rounding_at_the_half_unit_boundaries() {
+        // (base units, half-even, truncate) at 2 decimals
+        let cases = [
+            (5_000, "0.00", "0.00"),
+            (5_001, "0.01", "0.00"),
+            (15_000, "0.02", "0.01"),
+            (25_000, "0.02", "0.02"),
+            (25_001, "0.03", "0.02"),
+            (34_999, "0.03", "0.03"),
+            (999_995_000, "1000.00", "999.99"),
+        ];
+        for (amount, half_even, truncate) in cases {
+            assert_eq!(
+                format_display(amount, &at(2, RoundingMode::HalfEven)),
+                half_even,
+                "{}",
+                amount
+            );
+            assert_eq!(
+                format_display(amount, &at(2, RoundingMode::Truncate)),
+                truncate,
+                "{}",
+                amount
+            );
+        }
+        assert_eq!(
+            format_display(2_500_000, &at(0, RoundingMode::HalfEven)),
+            "2"
+        );
+        assert_eq!(
+            format_display(3_500_000, &at(0, RoundingMode::HalfEven)),
+            "4"
+        );
+        assert_eq!(
+            format_display(3_500_000, &at(0, RoundingMode::Truncate)),
+            "3"
+        );
+    }
+
+    #[test]
+    fn full_precision_is_exact_and_never_rounds() {
+        for rounding in RoundingMode::ALL {
+            let settings = at(NOCK_DECIMALS, rounding);
+            assert_eq!(format_display(1, &settings), "0.000001");
+            assert_eq!(format_display(u64::MAX, &settings), "18446744073709.551615");
+        }
+        // More decimals than NOCK has are shown as full precision
+        assert_eq!(
+            format_display(1, &at(9, RoundingMode::HalfEven)),
+            "0.000001"
+        );
+    }
+
+    #[test]
+    fn trailing_zeros_and_grouping_follow_the_settings() {
+        let trimmed = DisplaySettings {
+            trailing_zeros: false,
+            ..at(4, RoundingMode::HalfEven)
+        };
+        assert_eq!(format_display(1_500_000, &trimmed), "1.5");
+        assert_eq!(format_display(2_000_000, &trimmed), "2");
+        assert_eq!(
+            format_display(2_000_000, &at(4, RoundingMode::HalfEven)),
+            "2.0000"
+        );
+
+        let cases = [
+            (DigitGrouping::None, "1234567.50"),
+            (DigitGrouping::Comma, "1,234,567.50"),
+            (DigitGrouping::Period, "1.234.567,50"),
+            (DigitGrouping::Space, "1 234 567.50"),
+            (DigitGrouping::Apostrophe, "1'234'567.50"),
+        ];
+        for (grouping, expected) in cases {
+            let settings = DisplaySettings {
+                grouping,
+                ..at(2, RoundingMode::HalfEven)
+            };
+            assert_eq!(format_display(1_234_567_500_000, &settings), expected);
+        }
+        let comma = DisplaySettings {
+            grouping: DigitGrouping::Comma,
+            ..at(2, RoundingMode::HalfEven)
+        };
+        assert_eq!(format_display(123_000_000, &comma), "123.00");
+        assert_eq!(format_display(u64::MAX, &comma), "18,446,744,073,709.55");
+    }
+
+    #[tokio::test]
+    async fn display_settings_are_clamped_and_saved_per_wallet() {
+        let dir = crate::testing::TempDir::new("display");
+        let mut wallet = Wallet::open(dir.path()).unwrap();
+        wallet
+            .set_display_settings(DisplaySettings {
+                grouping: DigitGrouping::Space,
+                ..at(12, RoundingMode::Truncate)
+            })
+            .unwrap();
+        assert_eq!(wallet.display_settings().decimals, NOCK_DECIMALS);
+        wallet.save_display_settings().await.unwrap();
+        drop(wallet);
+
+        let mut reopened = Wallet::open(dir.path()).unwrap();
+        assert_eq!(reopened.display_settings(), DisplaySettings::default());
+        reopened.load_display_settings().await.unwrap();
+        assert_eq!(reopened.display_settings().grouping, DigitGrouping::Space);
+        assert_eq!(reopened.display_settings().rounding, RoundingMode::Truncate);
+    }
+}
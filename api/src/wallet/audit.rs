@@ -0,0 +1,578 @@
+//! Append-only record of sensitive wallet operations.
+//!
+//! Each entry stores the hash of the one before it and a hash over its own
+//! contents, so editing, removing or reordering a saved entry breaks the chain.
+//! Breaks are reported when the log is loaded; entries are never rewritten.
+//! Retention may drop the oldest entries, after which the log verifies from
+//! its first remaining entry.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use tracing::warn;
+
+use crate::wallet::storage::StorageManager;
+use crate::wallet::WalletResult;
+
+/// Audit log file, saved through `StorageManager` in the wallet data dir
+pub const AUDIT_LOG_FILE: &str = "audit_log.json";
+
+/// `prev_hash` of the first entry
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// A sensitive operation worth recording
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuditEvent {
+    WalletUnlocked,
+    WalletLocked,
+    PinAttemptFailed,
+    KeyGenerated {
+        key_name: String,
+    },
+    KeyImported {
+        key_name: String,
+    },
+    KeyExported {
+        key_name: String,
+    },
+    KeyDeleted {
+        key_name: String,
+    },
+    DefaultKeyChanged {
+        key_name: String,
+    },
+    /// Seed replaced with the one from a recovery phrase
+    SeedRestored {
+        language: String,
+    },
+    BackupExported {
+        path: String,
+    },
+    SendBroadcast {
+        tx_id: String,
+        amount: u64,
+        to: String,
+    },
+    SettingsChanged {
+        setting: String,
+    },
+}
+
+/// Groups of events the log can be filtered by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuditCategory {
+    Session,
+    Keys,
+    Backup,
+    Send,
+    Settings,
+}
+
+impl AuditCategory {
+    pub const ALL: [AuditCategory; 5] = [
+        AuditCategory::Session,
+        AuditCategory::Keys,
+        AuditCategory::Backup,
+        AuditCategory::Send,
+        AuditCategory::Settings,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AuditCategory::Session => "Lock & PIN",
+            AuditCategory::Keys => "Keys",
+            AuditCategory::Backup => "Backups",
+            AuditCategory::Send => "Sends",
+            AuditCategory::Settings => "Settings",
+        }
+    }
+}
+
+impl AuditEvent {
+    pub fn category(&self) -> AuditCategory {
+        match self {
+            AuditEvent::WalletUnlocked
+            | AuditEvent::WalletLocked
+            | AuditEvent::PinAttemptFailed => AuditCategory::Session,
+            AuditEvent::KeyGenerated { .. }
+            | AuditEvent::KeyImported { .. }
+            | AuditEvent::KeyExported { .. }
+            | AuditEvent::KeyDeleted { .. }
+            | AuditEvent::DefaultKeyChanged { .. }
+            | AuditEvent::SeedRestored { .. } => AuditCategory::Keys,
+            AuditEvent::BackupExported { .. } => AuditCategory::Backup,
+            AuditEvent::SendBroadcast { .. } => AuditCategory::Send,
+            AuditEvent::SettingsChanged { .. } => AuditCategory::Settings,
+        }
+    }
+}
+
+impl fmt::Display for AuditEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditEvent::WalletUnlocked => write!(f, "Wallet unlocked"),
+            AuditEvent::WalletLocked => write!(f, "Wallet locked"),
+            AuditEvent::PinAttemptFailed => write!(f, "Incorrect PIN entered"),
+            AuditEvent::KeyGenerated { key_name } => write!(f, "Key '{}' generated", key_name),
+            AuditEvent::KeyImported { key_name } => write!(f, "Key '{}' imported", key_name),
+            AuditEvent::KeyExported { key_name } => write!(f, "Key '{}' exported", key_name),
+            AuditEvent::KeyDeleted { key_name } => write!(f, "Key '{}' deleted", key_name),
+            AuditEvent::DefaultKeyChanged { key_name } => {
+                write!(f, "Default key changed to '{}'", key_name)
+            }
+            AuditEvent::SeedRestored { language } => {
+                write!(f, "Seed restored from a recovery phrase ({})", language)
+            }
+            AuditEvent::BackupExported { path } => write!(f, "Backup exported to {}", path),
+            AuditEvent::SendBroadcast { tx_id, amount, to } => {
+                write!(f, "Sent {} nano to {} ({})", amount, to, tx_id)
+            }
+            AuditEvent::SettingsChanged { setting } => write!(f, "Setting '{}' changed", setting),
+        }
+    }
+}
+
+/// Whether the recorded operation went through
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AuditOutcome {
+    Success,
+    Failure(String),
+}
+
+impl AuditOutcome {
+    /// Outcome of an operation that returned `result`
+    pub fn of<T, E: fmt::Display>(result: &Result<T, E>) -> Self {
+        match result {
+            Ok(_) => AuditOutcome::Success,
+            Err(e) => AuditOutcome::Failure(e.to_string()),
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        matches!(self, AuditOutcome::Success)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Position in the log, starting at 0; counts entries pruned since
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    pub event: AuditEvent,
+    pub outcome: AuditOutcome,
+    /// Hex SHA-256 `hash` of the previous entry
+    pub prev_hash: String,
+    /// Hex SHA-256 over `prev_hash` and this entry's other fields
+    pub hash: String,
+}
+
+impl AuditEntry {
+    fn compute_hash(&self) -> String {
+        let body = serde_json::json!({
+            "seq": self.seq,
+            "timestamp": self.timestamp,
+            "event": self.event,
+            "outcome": self.outcome,
+        });
+        let mut hasher = Sha256::new();
+        hasher.update(self.prev_hash.as_bytes());
+        hasher.update(body.to_string().as_bytes());
+        hex::encode(hasher.finalize())
+    }
+}
+
+/// First entry at which a loaded log stops verifying
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditChainBreak {
+    pub seq: u64,
+    pub reason: String,
+}
+
+impl fmt::Display for AuditChainBreak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Audit log has been altered at entry {}: {}",
+            self.seq, self.reason
+        )
+    }
+}
+
+/// Which entries `Wallet::audit_log` returns
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AuditFilter {
+    pub category: Option<AuditCategory>,
+    pub since: Option<DateTime<Utc>>,
+    pub failures_only: bool,
+    /// Most recent entries to return; all when `None`
+    pub limit: Option<usize>,
+}
+
+impl AuditFilter {
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        self.category
+            .is_none_or(|category| entry.event.category() == category)
+            && self.since.is_none_or(|since| entry.timestamp >= since)
+            && (!self.failures_only || !entry.outcome.is_success())
+    }
+}
+
+/// The hash-chained log. Entries are appended in memory and written by `save`;
+/// entries already on disk are only rewritten to drop pruned ones.
+#[derive(Debug, Clone, Default)]
+pub struct AuditLog {
+    entries: Vec<AuditEntry>,
+    /// Number of leading entries known to be on disk
+    saved: usize,
+    chain_break: Option<AuditChainBreak>,
+    /// Entries were pruned, so the next save replaces the file
+    pruned: bool,
+}
+
+impl AuditLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the saved log, or an empty one if none was saved, and verify its
+    /// chain. A break is logged and kept for `chain_break`, not returned as an error.
+    pub async fn load(storage: &StorageManager) -> WalletResult<Self> {
+        if !storage.exists(AUDIT_LOG_FILE) {
+            return Ok(Self::new());
+        }
+        let entries: Vec<AuditEntry> = storage.load(AUDIT_LOG_FILE).await?;
+        let chain_break = verify_chain(&entries);
+        if let Some(chain_break) = &chain_break {
+            warn!("{}", chain_break);
+        }
+        Ok(Self {
+            saved: entries.len(),
+            entries,
+            chain_break,
+            pruned: false,
+        })
+    }
+
+    /// Pick up the saved log, re-chaining entries not yet saved onto its end
+    pub async fn reload(&mut self, storage: &StorageManager) -> WalletResult<()> {
+        let mut merged = Self::load(storage).await?;
+        for entry in self.entries.drain(self.saved..) {
+            merged.push(entry.timestamp, entry.event, entry.outcome);
+        }
+        *self = merged;
+        Ok(())
+    }
+
+    /// Write unsaved entries. The file is reloaded first, so entries another
+    /// process saved in the meantime are kept rather than overwritten.
+    pub async fn save(&mut self, storage: &StorageManager) -> WalletResult<()> {
+        if self.saved == self.entries.len() && !self.pruned {
+            return Ok(());
+        }
+        if !self.pruned {
+            self.reload(storage).await?;
+        }
+        storage.save(AUDIT_LOG_FILE, &self.entries).await?;
+        self.saved = self.entries.len();
+        self.pruned = false;
+        Ok(())
+    }
+
+    /// Drop the oldest entries: those before `before`, then more until the
+    /// rest serialize to at most `max_bytes`. The newest entry is always
+    /// kept, so the chain goes on from it. Returns the entries dropped and
+    /// their serialized size.
+    pub fn prune(&mut self, before: Option<DateTime<Utc>>, max_bytes: Option<u64>) -> (usize, u64) {
+        let sizes: Vec<u64> = self
+            .entries
+            .iter()
+            .map(|entry| serde_json::to_vec(entry).map_or(0, |json| json.len() as u64))
+            .collect();
+        let mut total: u64 = sizes.iter().sum();
+        let mut drop = 0;
+        while drop + 1 < self.entries.len() {
+            let old = before.is_some_and(|before| self.entries[drop].timestamp < before);
+            let over = max_bytes.is_some_and(|max| total > max);
+            if !old && !over {
+                break;
+            }
+            total -= sizes[drop];
+            drop += 1;
+        }
+        if drop == 0 {
+            return (0, 0);
+        }
+        self.entries.drain(..drop);
+        self.saved = self.saved.saturating_sub(drop);
+        self.pruned = true;
+        (drop, sizes[..drop].iter().sum())
+    }
+
+    /// Record an operation now
+    pub fn append(&mut self, event: AuditEvent, outcome: AuditOutcome) -> &AuditEntry {
+        self.push(Utc::now(), event, outcome)
+    }
+
+    fn push(
+        &mut self,
+        timestamp: DateTime<Utc>,
+        event: AuditEvent,
+        outcome: AuditOutcome,
+    ) -> &AuditEntry {
+        let mut entry = AuditEntry {
+            seq: self.entries.last().map_or(0, |last| last.seq + 1),
+            timestamp,
+            event,
+            outcome,
+            prev_hash: self
+                .entries
+                .last()
+                .map_or_else(|| GENESIS_HASH.to_string(), |last| last.hash.clone()),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+        self.entries.push(entry);
+        self.entries.last().unwrap()
+    }
+
+    /// Entries matching `filter`, newest first
+    pub fn entries(&self, filter: &AuditFilter) -> Vec<AuditEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .filter(|entry| filter.matches(entry))
+            .take(filter.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Where verification failed when the log was loaded, if it did
+    pub fn chain_break(&self) -> Option<&AuditChainBreak> {
+        self.chain_break.as_ref()
+    }
+}
+
+/// First entry whose position, link or contents don't match its hashes. A
+/// pruned log starts wherever its first entry says it does.
+fn verify_chain(entries: &[AuditEntry]) -> Option<AuditChainBreak> {
+    let first_seq = entries.first().map_or(0, |first| first.seq);
+    let mut prev_hash = entries
+        .first()
+        .filter(|first| first.seq > 0)
+        .map_or(GENESIS_HASH, |first| first.prev_hash.as_str());
+    for (index, entry) in entries.iter().enumerate() {
+        let expected = first_seq + index as u64;
+        let reason = if entry.seq != expected {
+            Some(format!("expected entry {}, found {}", expected, entry.seq))
+        } else if entry.prev_hash != prev_hash {
+            Some("does not follow the previous entry".to_string())
+        } else if entry.hash != entry.compute_hash() {
+            Some("contents do not match its hash".to_string())
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            return Some(AuditChainBreak {
+                seq: expected,
+                reason,
+            });
+        }
+        prev_hash = &entry.hash;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fixture_time, TempDir};
+
+    fn sample_log() -> AuditLog {
+        let mut log = AuditLog::new();
+        log.append(AuditEvent::WalletUnlocked, AuditOutcome::Success);
+        log.append(
+            AuditEvent::SendBroadcast {
+                tx_id: "tx".to_string(),
+                amount: 5_000,
+                to: "nock1dest".to_string(),
+            },
+            AuditOutcome::Success,
+        );
+        log.append(
+            AuditEvent::KeyExported {
+                key_name: "main".to_string(),
+            },
+            AuditOutcome::Failure("locked".to_string()),
+        );
+        log
+    }
+
+    fn all(log: &AuditLog) -> Vec<AuditEntry> {
+        let mut entries = log.entries(&AuditFilter::default());
+        entries.reverse();
+        entries
+    }
+
+    #[test]
+    fn appended_entries_chain_onto_each_other() {
+        let entries = all(&sample_log());
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].prev_hash, GENESIS_HASH);
+        for (index, pair) in entries.windows(2).enumerate() {
+            assert_eq!(pair[1].seq, index as u64 + 1);
+            assert_eq!(pair[1].prev_hash, pair[0].hash);
+        }
+        assert_eq!(verify_chain(&entries), None);
+    }
+
+    #[tokio::test]
+    async fn a_saved_log_loads_and_verifies() {
+        let dir = TempDir::new("audit");
+        let storage = StorageManager::new(dir.path().to_path_buf()).unwrap();
+        let mut log = sample_log();
+        log.save(&storage).await.unwrap();
+
+        let loaded = AuditLog::load(&storage).await.unwrap();
+        assert_eq!(all(&loaded), all(&log));
+        assert_eq!(loaded.chain_break(), None);
+    }
+
+    #[tokio::test]
+    async fn a_tampered_middle_entry_is_reported_on_load() {
+        let dir = TempDir::new("audit");
+        let storage = StorageManager::new(dir.path().to_path_buf()).unwrap();
+        let mut entries = all(&sample_log());
+        if let AuditEvent::SendBroadcast { amount, .. } = &mut entries[1].event {
+            *amount = 1;
+        }
+        storage.save(AUDIT_LOG_FILE, &entries).await.unwrap();
+
+        let loaded = AuditLog::load(&storage).await.unwrap();
+        assert_eq!(
+            loaded.chain_break(),
+            Some(&AuditChainBreak {
+                seq: 1,
+                reason: "contents do not match its hash".to_string(),
+            })
+        );
+        // The entries are still shown, not discarded
+        assert_eq!(loaded.len(), 3);
+    }
+
+    #[test]
+    fn removed_and_reordered_entries_break_the_chain() {
+        let entries = all(&sample_log());
+
+        let removed = vec![entries[0].clone(), entries[2].clone()];
+        assert_eq!(
+            verify_chain(&removed).unwrap(),
+            AuditChainBreak {
+                seq: 1,
+                reason: "expected entry 1, found 2".to_string(),
+            }
+        );
+
+        let mut relinked = entries.clone();
+        relinked[1].prev_hash = GENESIS_HASH.to_string();
+        relinked[1].hash = relinked[1].compute_hash();
+        assert_eq!(
+            verify_chain(&relinked).unwrap().reason,
+            "does not follow the previous entry"
+        );
+    }
+
+    #[tokio::test]
+    async fn saving_keeps_entries_another_instance_saved() {
+        let dir = TempDir::new("audit");
+        let storage = StorageManager::new(dir.path().to_path_buf()).unwrap();
+        let mut first = AuditLog::new();
+        let mut second = AuditLog::new();
+        first.append(AuditEvent::WalletUnlocked, AuditOutcome::Success);
+        second.append(AuditEvent::WalletLocked, AuditOutcome::Success);
+        first.save(&storage).await.unwrap();
+        second.save(&storage).await.unwrap();
+
+        let loaded = AuditLog::load(&storage).await.unwrap();
+        let events: Vec<AuditEvent> = all(&loaded).into_iter().map(|e| e.event).collect();
+        assert_eq!(
+            events,
+            vec![AuditEvent::WalletUnlocked, AuditEvent::WalletLocked]
+        );
+        assert_eq!(loaded.chain_break(), None);
+    }
+
+    #[tokio::test]
+    async fn a_pruned_log_verifies_from_its_first_remaining_entry() {
+        let dir = TempDir::new("audit");
+        let storage = StorageManager::new(dir.path().to_path_buf()).unwrap();
+        let mut log = AuditLog::new();
+        for days in 0..4 {
+            log.push(
+                fixture_time(days * 86_400),
+                AuditEvent::WalletUnlocked,
+                AuditOutcome::Success,
+            );
+        }
+        let cutoff = fixture_time(2 * 86_400);
+        assert_eq!(log.prune(Some(cutoff), None).0, 2);
+        log.save(&storage).await.unwrap();
+
+        let loaded = AuditLog::load(&storage).await.unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(all(&loaded)[0].seq, 2);
+        assert_eq!(loaded.chain_break(), None);
+    }
+
+    #[test]
+    fn pruning_always_keeps_the_newest_entry() {
+        let mut log = sample_log();
+        assert_eq!(log.prune(None, Some(0)).0, 2);
+        assert_eq!(log.len(), 1);
+    }
+
+    #[test]
+    fn filters_select_by_category_failure_and_limit() {
+        let log = sample_log();
+        let sends = log.entries(&AuditFilter {
+            category: Some(
+                AuditEvent::SendBroadcast {
+                    tx_id: String::new(),
+                    amount: 0,
+                    to: String::new(),
+                }
+                .category(),
+            ),
+            ..Default::default()
+        });
+        assert_eq!(sends.len(), 1);
+
+        let failures = log.entries(&AuditFilter {
+            failures_only: true,
+            ..Default::default()
+        });
+        assert_eq!(failures.len(), 1);
+        assert!(matches!(failures[0].event, AuditEvent::KeyExported { .. }));
+
+        let newest = log.entries(&AuditFilter {
+            limit: Some(1),
+            ..Default::default()
+        });
+        assert_eq!(newest[0].seq, 2);
+
+        let since = log.entries(&AuditFilter {
+            since: Some(Utc::now() + chrono::Duration::hours(1)),
+            ..Default::default()
+        });
+        assert!(since.is_empty());
+    }
+}
@@ -1,12 +1,82 @@
+use crate::wallet::keys::{AddressKind, OutPoint};
+use crate::wallet::ledger::{Ledger, LedgerEvent};
 use crate::wallet::{Address, Balance, Note, WalletError, WalletResult};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
 
-/// Balance manager for tracking UTXOs and balances
+/// Blocks a coinbase note must be buried under before it can be spent
+pub const COINBASE_MATURITY: u64 = 100;
+
+/// Confirmations a note needs before coin selection uses it
+pub const DEFAULT_MIN_CONFIRMATIONS: u64 = 3;
+
+/// Where the wallet's unspent funds stand, for explaining why not all of it can be sent
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpendableSummary {
+    /// Unlocked notes with enough confirmations (and matured, for coinbase)
+    pub spendable: u64,
+    /// Unconfirmed notes and notes below the confirmation threshold
+    pub awaiting_confirmations: u64,
+    /// Coinbase notes that are confirmed but not yet mature
+    pub immature: u64,
+    /// Notes reserved by a send in progress
+    pub locked: u64,
+    /// Funds at watch-only addresses, which the wallet can't sign for
+    pub watch_only: u64,
+    /// Threshold the totals were computed with
+    pub min_confirmations: u64,
+}
+
+/// Funds at one of the wallet's addresses, for the per-address breakdown
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AddressBalance {
+    pub address: Address,
+    pub kind: AddressKind,
+    /// Key the address belongs to; `None` for watch-only addresses
+    pub key_name: Option<String>,
+    pub balance: Balance,
+}
+
+/// An address whose cached balance doesn't match its notes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BalanceDiscrepancy {
+    pub address: Address,
+    /// Balance the cache held
+    pub cached: Balance,
+    /// Balance worked out from the notes
+    pub expected: Balance,
+}
+
+/// Result of checking the cached balances against the notes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReconciliationReport {
+    /// Notes the balances were worked out from
+    pub notes: usize,
+    /// Addresses compared
+    pub addresses: usize,
+    pub discrepancies: Vec<BalanceDiscrepancy>,
+    /// The cache was rebuilt from the notes
+    pub repaired: bool,
+}
+
+impl ReconciliationReport {
+    pub fn is_consistent(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+}
+
+/// Balance manager for tracking UTXOs and balances. `address_balances` is
+/// updated alongside the notes on every change; `reconcile` checks the two
+/// still agree. Every change to the unspent total is posted to `ledger`.
 #[derive(Debug)]
 pub struct BalanceManager {
     notes: HashMap<Uuid, Note>,
     address_balances: HashMap<Address, Balance>,
+    /// Height of the chain tip, used to decide which coinbase notes have matured
+    tip_height: Option<u64>,
+    min_confirmations: u64,
+    ledger: Ledger,
 }
 
 impl BalanceManager {
@@ -14,14 +84,100 @@ impl BalanceManager {
         Self {
             notes: HashMap::new(),
             address_balances: HashMap::new(),
+            tip_height: None,
+            min_confirmations: DEFAULT_MIN_CONFIRMATIONS,
+            ledger: Ledger::new(),
         }
     }
 
-    /// Add a new note (UTXO) to the wallet
+    pub fn ledger(&self) -> &Ledger {
+        &self.ledger
+    }
+
+    pub fn min_confirmations(&self) -> u64 {
+        self.min_confirmations
+    }
+
+    /// Confirmations required before notes are selected; at least 1
+    pub fn set_min_confirmations(&mut self, confirmations: u64) {
+        self.min_confirmations = confirmations.max(1);
+    }
+
+    /// Blocks containing and burying the note: 0 while unconfirmed. A confirmed
+    /// note counts as 1 while the chain tip is unknown.
+    pub fn confirmations(&self, note: &Note) -> u64 {
+        match (note.block_height, self.tip_height) {
+            (None, _) => 0,
+            (Some(_), None) => 1,
+            (Some(height), Some(tip)) => tip.saturating_sub(height) + 1,
+        }
+    }
+
+    pub fn tip_height(&self) -> Option<u64> {
+        self.tip_height
+    }
+
+    /// Move the chain tip, releasing coinbase notes that have now matured
+    pub fn set_tip_height(&mut self, height: u64) {
+        self.tip_height = Some(self.tip_height.map_or(height, |tip| tip.max(height)));
+        self.refresh_immature();
+        self.check_reconciled();
+    }
+
+    fn is_mature(&self, note: &Note) -> bool {
+        if !note.coinbase {
+            return true;
+        }
+        match (note.block_height, self.tip_height) {
+            (Some(height), Some(tip)) => tip >= height + COINBASE_MATURITY,
+            _ => false,
+        }
+    }
+
+    fn refresh_immature(&mut self) {
+        for balance in self.address_balances.values_mut() {
+            balance.immature = 0;
+        }
+        let immature: Vec<(Address, u64)> = self
+            .notes
+            .values()
+            .filter(|note| !note.spent && note.block_height.is_some() && !self.is_mature(note))
+            .map(|note| (note.address.clone(), note.amount))
+            .collect();
+        for (address, amount) in immature {
+            self.address_balances
+                .entry(address)
+                .or_insert_with(Balance::new)
+                .immature += amount;
+        }
+
+        let moved: Vec<(Uuid, bool)> = self
+            .notes
+            .values()
+            .filter(|note| note.coinbase && !note.spent)
+            .map(|note| (note.id, !self.is_mature(note)))
+            .filter(|(id, immature)| self.ledger.is_immature(id) != *immature)
+            .collect();
+        for (id, immature) in moved {
+            if let Some(note) = self.notes.get(&id) {
+                self.ledger.set_immature(note, immature);
+            }
+        }
+    }
+
+    /// Add a new note (UTXO) to the wallet. A note that arrives already
+    /// spent or locked is counted as such.
     pub fn add_note(&mut self, note: Note) -> WalletResult<()> {
         let address = note.address.clone();
         let amount = note.amount;
         let block_height = note.block_height;
+        let coinbase = note.coinbase;
+        let (spent, locked) = (note.spent, note.locked);
+
+        self.ledger.receive(&note, !self.is_mature(&note));
+        if spent {
+            self.ledger.spend(&note);
+        }
 
         // Add note to collection
         self.notes.insert(note.id, note);
@@ -32,11 +188,18 @@ impl BalanceManager {
             .entry(address)
             .or_insert_with(Balance::new);
 
-        if block_height.is_some() {
-            balance.confirmed += amount;
-        } else {
-            balance.unconfirmed += amount;
+        match (spent, block_height) {
+            (true, _) => {}
+            (false, Some(_)) => balance.confirmed += amount,
+            (false, None) => balance.unconfirmed += amount,
         }
+        if locked {
+            balance.locked += amount;
+        }
+        if coinbase {
+            self.refresh_immature();
+        }
+        self.check_reconciled();
 
         Ok(())
     }
@@ -49,6 +212,7 @@ impl BalanceManager {
             }
 
             note.spent = true;
+            let was_locked = std::mem::take(&mut note.locked);
 
             // Update balance
             let balance = self
@@ -61,6 +225,11 @@ impl BalanceManager {
             } else {
                 balance.unconfirmed = balance.unconfirmed.saturating_sub(note.amount);
             }
+            if was_locked {
+                balance.locked = balance.locked.saturating_sub(note.amount);
+            }
+            self.ledger.spend(note);
+            self.check_reconciled();
 
             Ok(())
         } else {
@@ -71,6 +240,246 @@ impl BalanceManager {
         }
     }
 
+    /// Make a note spent by a transaction that will never be mined spendable again
+    pub fn unspend_note(&mut self, note_id: Uuid) -> WalletResult<()> {
+        let note = self
+            .notes
+            .get_mut(&note_id)
+            .ok_or_else(|| WalletError::KeyNotFound(format!("Note {} not found", note_id)))?;
+        if !note.spent {
+            return Ok(());
+        }
+        note.spent = false;
+
+        let balance = self
+            .address_balances
+            .entry(note.address.clone())
+            .or_insert_with(Balance::new);
+        if note.block_height.is_some() {
+            balance.confirmed += note.amount;
+        } else {
+            balance.unconfirmed += note.amount;
+        }
+        self.ledger.unspend(note);
+        self.check_reconciled();
+        Ok(())
+    }
+
+    /// Give the unconfirmed notes created by `tx_id` the height of the block
+    /// that confirmed it. Returns how many notes moved to the confirmed balance.
+    pub fn confirm_notes(&mut self, tx_id: &str, block_height: u64) -> usize {
+        let mut confirmed = 0;
+        for note in self.notes.values_mut().filter(|note| {
+            note.transaction_id == tx_id && note.block_height.is_none() && !note.spent
+        }) {
+            note.block_height = Some(block_height);
+            let balance = self
+                .address_balances
+                .entry(note.address.clone())
+                .or_insert_with(Balance::new);
+            balance.unconfirmed = balance.unconfirmed.saturating_sub(note.amount);
+            balance.confirmed += note.amount;
+            confirmed += 1;
+        }
+        self.check_reconciled();
+        confirmed
+    }
+
+    /// Forget the unconfirmed notes created by `tx_id`, once the transaction
+    /// can no longer be mined. Returns the amount they held.
+    pub fn remove_unconfirmed_notes(&mut self, tx_id: &str) -> u64 {
+        let ids: Vec<Uuid> = self
+            .notes
+            .values()
+            .filter(|note| note.transaction_id == tx_id && note.block_height.is_none())
+            .map(|note| note.id)
+            .collect();
+
+        let mut removed = 0;
+        for id in ids {
+            let Some(note) = self.notes.remove(&id) else {
+                continue;
+            };
+            if let Some(balance) = self.address_balances.get_mut(&note.address) {
+                if !note.spent {
+                    balance.unconfirmed = balance.unconfirmed.saturating_sub(note.amount);
+                }
+                if note.locked {
+                    balance.locked = balance.locked.saturating_sub(note.amount);
+                }
+            }
+            self.ledger
+                .reverse_receipt(&note, LedgerEvent::ReceiptReversed);
+            removed += note.amount;
+        }
+        self.check_reconciled();
+        removed
+    }
+
+    /// Undo the blocks above `height` after a reorg. Coinbase notes they
+    /// minted are removed, other notes they confirmed go back to
+    /// unconfirmed until the new chain includes them, and the tip moves
+    /// down to `height`. Returns the amount of the unspent notes removed.
+    pub fn revert_blocks_above(&mut self, height: u64) -> u64 {
+        let reverted: Vec<Uuid> = self
+            .notes
+            .values()
+            .filter(|note| note.block_height.is_some_and(|at| at > height))
+            .map(|note| note.id)
+            .collect();
+
+        let mut removed = 0;
+        for id in reverted {
+            let Some(note) = self.notes.get_mut(&id) else {
+                continue;
+            };
+            let balance = self
+                .address_balances
+                .entry(note.address.clone())
+                .or_insert_with(Balance::new);
+            if !note.spent {
+                balance.confirmed = balance.confirmed.saturating_sub(note.amount);
+            }
+            if note.coinbase {
+                if note.locked {
+                    balance.locked = balance.locked.saturating_sub(note.amount);
+                }
+                if !note.spent {
+                    removed += note.amount;
+                }
+                if let Some(note) = self.notes.remove(&id) {
+                    self.ledger
+                        .reverse_receipt(&note, LedgerEvent::ReorgReversal);
+                }
+            } else {
+                note.block_height = None;
+                if !note.spent {
+                    balance.unconfirmed += note.amount;
+                }
+            }
+        }
+
+        if self.tip_height.is_some_and(|tip| tip > height) {
+            self.tip_height = Some(height);
+        }
+        self.refresh_immature();
+        self.check_reconciled();
+        removed
+    }
+
+    /// Split `fee` out of what the spends of `tx_id` paid out, in the ledger
+    pub fn record_fee(&mut self, tx_id: &str, fee: u64) {
+        self.ledger.record_fee(tx_id, fee);
+    }
+
+    /// Reverse the fee of `tx_id` in the ledger, once it will never be mined
+    pub fn reverse_fee(&mut self, tx_id: &str) {
+        self.ledger.reverse_fee(tx_id);
+    }
+
+    /// Pick unlocked notes from `addresses` with at least `min_confirmations`
+    /// (the wallet default if `None`, never below 1) covering `amount`, largest first
+    pub fn select_notes(
+        &self,
+        addresses: &[Address],
+        amount: u64,
+        min_confirmations: Option<u64>,
+    ) -> WalletResult<Vec<Uuid>> {
+        let min_confirmations = min_confirmations.unwrap_or(self.min_confirmations).max(1);
+        let mut candidates: Vec<&Note> = addresses
+            .iter()
+            .flat_map(|address| self.get_spendable_notes(address, amount))
+            .filter(|note| self.confirmations(note) >= min_confirmations)
+            .collect();
+        candidates.sort_by(|a, b| b.amount.cmp(&a.amount).then(a.id.cmp(&b.id)));
+
+        let mut selected = Vec::new();
+        let mut total = 0u64;
+        for note in candidates {
+            if total >= amount {
+                break;
+            }
+            total = total.saturating_add(note.amount);
+            selected.push(note.id);
+        }
+
+        if total < amount {
+            return Err(WalletError::InsufficientFunds {
+                required: amount,
+                available: total,
+            });
+        }
+        Ok(selected)
+    }
+
+    pub fn get_note(&self, note_id: &Uuid) -> Option<&Note> {
+        self.notes.get(note_id)
+    }
+
+    /// Note created by `outpoint`, spent or not
+    pub fn note_at(&self, outpoint: &OutPoint) -> Option<&Note> {
+        self.notes.values().find(|note| {
+            note.transaction_id == outpoint.txid && note.output_index == outpoint.index
+        })
+    }
+
+    /// Outputs the wallet holds unspent, for matching spends of them
+    pub fn unspent_outpoints(&self) -> Vec<OutPoint> {
+        self.notes
+            .values()
+            .filter(|note| !note.spent)
+            .map(|note| OutPoint::new(note.transaction_id.clone(), note.output_index))
+            .collect()
+    }
+
+    /// Reserve notes for a transaction in progress so they can't be selected twice
+    pub fn lock_notes(&mut self, note_ids: &[Uuid]) -> WalletResult<()> {
+        if let Some(id) = note_ids.iter().find(|id| {
+            !self
+                .notes
+                .get(*id)
+                .is_some_and(|note| !note.locked && !note.spent)
+        }) {
+            return Err(WalletError::Transaction(format!(
+                "Note {} is not available to lock",
+                id
+            )));
+        }
+
+        for id in note_ids {
+            self.set_locked(id, true);
+        }
+        Ok(())
+    }
+
+    /// Release notes locked by `lock_notes`; unknown or unlocked notes are ignored
+    pub fn unlock_notes(&mut self, note_ids: &[Uuid]) {
+        for id in note_ids {
+            self.set_locked(id, false);
+        }
+    }
+
+    fn set_locked(&mut self, note_id: &Uuid, locked: bool) {
+        let Some(note) = self.notes.get_mut(note_id) else {
+            return;
+        };
+        if note.locked == locked {
+            return;
+        }
+        note.locked = locked;
+
+        let balance = self
+            .address_balances
+            .entry(note.address.clone())
+            .or_insert_with(Balance::new);
+        if locked {
+            balance.locked += note.amount;
+        } else {
+            balance.locked = balance.locked.saturating_sub(note.amount);
+        }
+        self.check_reconciled();
+    }
+
     /// Get balance for a specific address
     pub fn get_balance(&self, address: &Address) -> Balance {
         self.address_balances
@@ -87,6 +496,7 @@ impl BalanceManager {
             total.confirmed += balance.confirmed;
             total.unconfirmed += balance.unconfirmed;
             total.locked += balance.locked;
+            total.immature += balance.immature;
         }
 
         total
@@ -101,10 +511,51 @@ impl BalanceManager {
                     && !note.spent
                     && !note.locked
                     && note.block_height.is_some() // Only confirmed notes
+                    && self.is_mature(note)
             })
             .collect()
     }
 
+    /// Totals of every unspent note by whether it can be sent right now.
+    /// `is_watch_only` tells which addresses the wallet has no key for.
+    pub fn spendable_summary(&self, is_watch_only: impl Fn(&Address) -> bool) -> SpendableSummary {
+        let mut summary = SpendableSummary {
+            min_confirmations: self.min_confirmations,
+            ..SpendableSummary::default()
+        };
+        for note in self.notes.values().filter(|note| !note.spent) {
+            let bucket = if is_watch_only(&note.address) {
+                &mut summary.watch_only
+            } else if note.locked {
+                &mut summary.locked
+            } else if self.confirmations(note) < self.min_confirmations {
+                &mut summary.awaiting_confirmations
+            } else if !self.is_mature(note) {
+                &mut summary.immature
+            } else {
+                &mut summary.spendable
+            };
+            *bucket += note.amount;
+        }
+        summary
+    }
+
+    /// Sum of the unspent notes, confirmed or not
+    pub fn unspent_total(&self) -> u64 {
+        self.notes
+            .values()
+            .filter(|note| !note.spent)
+            .map(|note| note.amount)
+            .sum()
+    }
+
+    /// Every note, spent or not, newest first
+    pub fn notes(&self) -> Vec<&Note> {
+        let mut notes: Vec<&Note> = self.notes.values().collect();
+        notes.sort_by_key(|note| std::cmp::Reverse(note.created_at));
+        notes
+    }
+
     /// Get all notes for an address
     pub fn get_notes_for_address(&self, address: &Address) -> Vec<&Note> {
         self.notes
@@ -112,4 +563,386 @@ impl BalanceManager {
             .filter(|note| note.address == *address)
             .collect()
     }
+
+    /// Balances worked out from the notes alone, as the cache should hold them
+    fn expected_balances(&self) -> HashMap<Address, Balance> {
+        let mut expected: HashMap<Address, Balance> = HashMap::new();
+        for note in self.notes.values() {
+            let balance = expected
+                .entry(note.address.clone())
+                .or_insert_with(Balance::new);
+            if note.locked {
+                balance.locked += note.amount;
+            }
+            if note.spent {
+                continue;
+            }
+            if note.block_height.is_some() {
+                balance.confirmed += note.amount;
+                if !self.is_mature(note) {
+                    balance.immature += note.amount;
+                }
+            } else {
+                balance.unconfirmed += note.amount;
+            }
+        }
+        expected
+    }
+
+    /// Compare the cached balance of every address with one worked out from
+    /// its notes. An address missing on one side counts as a zero balance.
+    pub fn reconcile(&self) -> ReconciliationReport {
+        let mut expected = self.expected_balances();
+        let mut addresses: Vec<Address> = self.address_balances.keys().cloned().collect();
+        addresses.extend(
+            expected
+                .keys()
+                .filter(|address| !self.address_balances.contains_key(*address))
+                .cloned(),
+        );
+
+        let mut discrepancies = Vec::new();
+        for address in &addresses {
+            let cached = self.get_balance(address);
+            let expected = expected.remove(address).unwrap_or_else(Balance::new);
+            if cached != expected {
+                discrepancies.push(BalanceDiscrepancy {
+                    address: address.clone(),
+                    cached,
+                    expected,
+                });
+            }
+        }
+        discrepancies.sort_by_key(|discrepancy| discrepancy.address.to_string());
+
+        ReconciliationReport {
+            notes: self.notes.len(),
+            addresses: addresses.len(),
+            discrepancies,
+            repaired: false,
+        }
+    }
+
+    /// `reconcile`, then rebuild the cache from the notes if it was off
+    pub fn repair_balances(&mut self) -> ReconciliationReport {
+        let mut report = self.reconcile();
+        if !report.is_consistent() {
+            self.address_balances = self.expected_balances();
+            report.repaired = true;
+        }
+        report
+    }
+
+    /// Overwrite the cached balance of `address`, leaving the notes alone, to
+    /// exercise `reconcile`
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn set_cached_balance(&mut self, address: Address, balance: Balance) {
+        self.address_balances.insert(address, balance);
+    }
+
+    /// With the `balance-assertions` feature, panic as soon as a change leaves
+    /// the cache out of step with the notes; does nothing otherwise
+    fn check_reconciled(&self) {
+        #[cfg(feature = "balance-assertions")]
+        {
+            let report = self.reconcile();
+            assert!(
+                report.is_consistent(),
+                "Cached balances out of step with notes: {:?}",
+                report.discrepancies
+            );
+            let ledger = self.ledger.check(self.unspent_total());
+            assert!(ledger.is_balanced(), "{}", ledger);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_address, fake_note};
+
+    fn manager_with(notes: Vec<Note>) -> BalanceManager {
+        let mut balances = BalanceManager::new();
+        for note in notes {
+            balances.add_note(note).unwrap();
+        }
+        balances
+    }
+
+    #[test]
+    fn notes_count_towards_confirmed_or_unconfirmed() {
+        let address = fake_address();
+        let balances = manager_with(vec![
+            fake_note()
+                .address(address.clone())
+                .amount(500)
+                .confirmed_at(1)
+                .build(),
+            fake_note().address(address.clone()).amount(200).build(),
+            fake_note()
+                .address(address.clone())
+                .amount(50)
+                .spent()
+                .build(),
+        ]);
+
+        let balance = balances.get_balance(&address);
+        assert_eq!(balance.confirmed, 500);
+        assert_eq!(balance.unconfirmed, 200);
+        assert_eq!(balances.unspent_total(), 700);
+        assert!(balances.reconcile().is_consistent());
+    }
+
+    #[test]
+    fn spending_moves_a_note_out_of_the_balance_once() {
+        let note = fake_note().amount(500).confirmed_at(1).build();
+        let (id, address) = (note.id, note.address.clone());
+        let mut balances = manager_with(vec![note]);
+
+        balances.spend_note(id).unwrap();
+        assert_eq!(balances.get_balance(&address).confirmed, 0);
+        assert!(balances.spend_note(id).is_err());
+
+        balances.unspend_note(id).unwrap();
+        assert_eq!(balances.get_balance(&address).confirmed, 500);
+    }
+
+    #[test]
+    fn selection_takes_largest_confirmed_notes_first() {
+        let address = fake_address();
+        let small = fake_note()
+            .address(address.clone())
+            .amount(100)
+            .confirmed_at(1)
+            .build();
+        let large = fake_note()
+            .address(address.clone())
+            .amount(900)
+            .confirmed_at(1)
+            .build();
+        let pending = fake_note().address(address.clone()).amount(5_000).build();
+        let large_id = large.id;
+        let mut balances = manager_with(vec![small, large, pending]);
+        balances.set_tip_height(10);
+
+        assert_eq!(
+            balances
+                .select_notes(std::slice::from_ref(&address), 800, None)
+                .unwrap(),
+            vec![large_id]
+        );
+        assert!(matches!(
+            balances.select_notes(&[address], 1_500, None),
+            Err(WalletError::InsufficientFunds {
+                required: 1_500,
+                available: 1_000
+            })
+        ));
+    }
+
+    #[test]
+    fn selection_respects_the_confirmation_threshold() {
+        let note = fake_note().amount(100).confirmed_at(9).build();
+        let address = note.address.clone();
+        let mut balances = manager_with(vec![note]);
+        balances.set_tip_height(10);
+
+        assert!(balances
+            .select_notes(std::slice::from_ref(&address), 100, Some(3))
+            .is_err());
+        assert_eq!(
+            balances
+                .select_notes(&[address], 100, Some(2))
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn locked_notes_are_reserved_until_unlocked() {
+        let note = fake_note().amount(300).confirmed_at(1).build();
+        let (id, address) = (note.id, note.address.clone());
+        let mut balances = manager_with(vec![note]);
+        balances.set_tip_height(10);
+
+        balances.lock_notes(&[id]).unwrap();
+        assert_eq!(balances.get_balance(&address).locked, 300);
+        assert!(balances.lock_notes(&[id]).is_err());
+        assert!(balances
+            .select_notes(std::slice::from_ref(&address), 1, None)
+            .is_err());
+
+        balances.unlock_notes(&[id]);
+        assert_eq!(balances.get_balance(&address).locked, 0);
+        assert!(balances.select_notes(&[address], 1, None).is_ok());
+    }
+
+    #[test]
+    fn coinbase_notes_mature_after_the_maturity_window() {
+        let note = fake_note().amount(50).confirmed_at(10).coinbase().build();
+        let address = note.address.clone();
+        let mut balances = manager_with(vec![note]);
+
+        balances.set_tip_height(10 + COINBASE_MATURITY - 1);
+        assert_eq!(balances.get_balance(&address).immature, 50);
+        assert!(balances.get_spendable_notes(&address, 50).is_empty());
+
+        balances.set_tip_height(10 + COINBASE_MATURITY);
+        assert_eq!(balances.get_balance(&address).immature, 0);
+        assert_eq!(balances.get_spendable_notes(&address, 50).len(), 1);
+    }
+
+    #[test]
+    fn reverting_blocks_drops_coinbase_and_unconfirms_the_rest() {
+        let address = fake_address();
+        let mined = fake_note()
+            .address(address.clone())
+            .amount(50)
+            .confirmed_at(5)
+            .coinbase()
+            .build();
+        let paid = fake_note()
+            .address(address.clone())
+            .amount(70)
+            .confirmed_at(6)
+            .build();
+        let kept = fake_note()
+            .address(address.clone())
+            .amount(10)
+            .confirmed_at(2)
+            .build();
+        let paid_id = paid.id;
+        let mut balances = manager_with(vec![mined, paid, kept]);
+        balances.set_tip_height(6);
+
+        assert_eq!(balances.revert_blocks_above(4), 50);
+        let balance = balances.get_balance(&address);
+        assert_eq!(balance.confirmed, 10);
+        assert_eq!(balance.unconfirmed, 70);
+        assert_eq!(balances.get_note(&paid_id).unwrap().block_height, None);
+        assert_eq!(balances.tip_height(), Some(4));
+        assert!(balances.reconcile().is_consistent());
+    }
+
+    #[test]
+    fn confirming_and_removing_notes_by_transaction() {
+        let note = fake_note().amount(40).outpoint("aa", 0).build();
+        let other = fake_note().amount(60).outpoint("bb", 1).build();
+        let (address, other_address) = (note.address.clone(), other.address.clone());
+        let mut balances = manager_with(vec![note, other]);
+
+        assert_eq!(balances.confirm_notes("aa", 3), 1);
+        assert_eq!(balances.get_balance(&address).confirmed, 40);
+        assert_eq!(balances.remove_unconfirmed_notes("bb"), 60);
+        assert_eq!(balances.get_balance(&other_address).unconfirmed, 0);
+        assert!(balances.note_at(&OutPoint::new("bb", 1)).is_none());
+    }
+
+    #[test]
+    fn reconcile_finds_and_repairs_a_drifted_cache() {
+        let note = fake_note().amount(80).confirmed_at(1).build();
+        let address = note.address.clone();
+        let mut balances = manager_with(vec![note]);
+        let mut wrong = balances.get_balance(&address);
+        wrong.confirmed += 1;
+        balances.set_cached_balance(address.clone(), wrong);
+
+        let report = balances.reconcile();
+        assert_eq!(report.discrepancies.len(), 1);
+        assert_eq!(report.discrepancies[0].expected.confirmed, 80);
+
+        let repaired = balances.repair_balances();
+        assert!(repaired.repaired);
+        assert!(balances.reconcile().is_consistent());
+    }
+
+    #[test]
+    fn a_cached_address_without_notes_should_hold_nothing() {
+        let note = fake_note().amount(30).confirmed_at(1).build();
+        let (kept, stray) = (note.address.clone(), fake_address());
+        let mut balances = manager_with(vec![note]);
+        let mut leftover = Balance::new();
+        leftover.unconfirmed = 12;
+        balances.set_cached_balance(stray.clone(), leftover.clone());
+
+        let report = balances.reconcile();
+        assert_eq!(report.notes, 1);
+        assert_eq!(report.addresses, 2);
+        assert_eq!(
+            report.discrepancies,
+            vec![BalanceDiscrepancy {
+                address: stray.clone(),
+                cached: leftover,
+                expected: Balance::new(),
+            }]
+        );
+
+        assert!(balances.repair_balances().repaired);
+        assert_eq!(balances.get_balance(&stray), Balance::new());
+        assert_eq!(balances.get_balance(&kept).confirmed, 30);
+    }
+
+    #[test]
+    fn a_consistent_cache_is_left_alone() {
+        let mut balances = manager_with(vec![
+            fake_note().amount(5).confirmed_at(1).locked().build(),
+            fake_note().amount(7).spent().build(),
+        ]);
+        let report = balances.repair_balances();
+        assert!(report.is_consistent());
+        assert!(!report.repaired);
+        assert_eq!((report.notes, report.addresses), (2, 2));
+    }
+
+    #[test]
+    fn discrepancies_are_listed_by_address() {
+        let notes: Vec<Note> = (0..3)
+            .map(|_| fake_note().amount(10).confirmed_at(1).build())
+            .collect();
+        let addresses: Vec<Address> = notes.iter().map(|note| note.address.clone()).collect();
+        let mut balances = manager_with(notes);
+        for address in &addresses {
+            balances.set_cached_balance(address.clone(), Balance::new());
+        }
+
+        let listed: Vec<String> = balances
+            .reconcile()
+            .discrepancies
+            .iter()
+            .map(|discrepancy| discrepancy.address.to_string())
+            .collect();
+        let mut sorted = listed.clone();
+        sorted.sort();
+        assert_eq!(listed.len(), 3);
+        assert_eq!(listed, sorted);
+    }
+
+    #[test]
+    fn spendable_summary_buckets_every_unspent_note() {
+        let watched = fake_address();
+        let balances = {
+            let mut balances = manager_with(vec![
+                fake_note().amount(1).confirmed_at(1).build(),
+                fake_note().amount(2).confirmed_at(9).build(),
+                fake_note().amount(4).confirmed_at(1).locked().build(),
+                fake_note().amount(8).confirmed_at(1).coinbase().build(),
+                fake_note()
+                    .amount(16)
+                    .address(watched.clone())
+                    .confirmed_at(1)
+                    .build(),
+            ]);
+            balances.set_tip_height(10);
+            balances
+        };
+
+        let summary = balances.spendable_summary(|address| *address == watched);
+        assert_eq!(summary.spendable, 1);
+        assert_eq!(summary.awaiting_confirmations, 2);
+        assert_eq!(summary.locked, 4);
+        assert_eq!(summary.immature, 8);
+        assert_eq!(summary.watch_only, 16);
+    }
 }
@@ -0,0 +1,898 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tracing::{debug, info};
+
+use crate::wallet::checkpoints::{self, Checkpoint, ValidationPhase};
+use crate::wallet::clock::{Clock, SystemClock};
+use crate::wallet::encoding::Encode;
+use crate::wallet::filters::BlockFilter;
+use crate::wallet::keys::NockchainTransaction;
+use crate::wallet::{
+    Address, Block, BlockHeader, BlockchainConfig, Wallet, WalletError, WalletResult,
+};
+
+/// Number of ancestors whose median timestamp a new block must exceed
+pub const MEDIAN_TIME_SPAN: usize = 11;
+
+/// Newest blocks whose bodies "Compact now" keeps
+pub const DEFAULT_KEEP_FULL_BLOCKS: u64 = 1000;
+
+/// Heights `start..=end` whose block bodies were pruned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrunedRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl PrunedRange {
+    pub fn contains(&self, height: u64) -> bool {
+        (self.start..=self.end).contains(&height)
+    }
+}
+
+/// What `Blockchain::compact` pruned
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompactionReport {
+    pub pruned_blocks: u64,
+    /// Encoded size of the bodies dropped
+    pub freed_bytes: u64,
+    /// Old enough to prune but kept for the wallet's transactions
+    pub kept_blocks: u64,
+}
+
+/// Validated chain of blocks, genesis first
+#[derive(Debug, Clone)]
+pub struct Blockchain {
+    config: BlockchainConfig,
+    blocks: Vec<Block>,
+    /// Height of each block by hash
+    by_hash: HashMap<[u8; 32], u64>,
+    /// Height of the containing block and position in it, by transaction id
+    by_tx: HashMap<String, (u64, usize)>,
+    /// Compact filter of each block, by block hash, served to wallet sync
+    filters: HashMap<[u8; 32], BlockFilter>,
+    /// Heights whose bodies were pruned, in order and not touching
+    pruned: Vec<PrunedRange>,
+    /// Heights whose bodies compaction keeps however old
+    kept: HashSet<u64>,
+    /// Read afresh for each block's timestamp check
+    clock: Arc<dyn Clock>,
+}
+
+impl Blockchain {
+    pub fn new(config: BlockchainConfig) -> Self {
+        Self {
+            config,
+            blocks: Vec::new(),
+            by_hash: HashMap::new(),
+            by_tx: HashMap::new(),
+            filters: HashMap::new(),
+            pruned: Vec::new(),
+            kept: HashSet::new(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Check block timestamps against `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn config(&self) -> &BlockchainConfig {
+        &self.config
+    }
+
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
+    pub fn tip(&self) -> Option<&Block> {
+        self.blocks.last()
+    }
+
+    /// Block at `height`, if the chain is that long
+    pub fn block_at(&self, height: u64) -> Option<&Block> {
+        self.blocks
+            .get(usize::try_from(height).ok()?)
+            .filter(|block| block.header.height == height)
+    }
+
+    /// Height of the tip, or `None` before genesis
+    pub fn height(&self) -> Option<u64> {
+        self.tip().map(|block| block.header.height)
+    }
+
+    /// Up to `limit` blocks, newest first, skipping the `offset` newest
+    pub fn blocks_page(&self, offset: usize, limit: usize) -> Vec<&Block> {
+        self.blocks.iter().rev().skip(offset).take(limit).collect()
+    }
+
+    pub fn block_by_height(&self, height: u64) -> Option<&Block> {
+        self.block_at(height)
+    }
+
+    pub fn block_by_hash(&self, hash: &[u8; 32]) -> Option<&Block> {
+        self.block_at(*self.by_hash.get(hash)?)
+    }
+
+    /// Compact filter of the block `hash`, built when it was added
+    pub fn filter_for_block(&self, hash: &[u8; 32]) -> Option<&BlockFilter> {
+        self.filters.get(hash)
+    }
+
+    /// Block `hash` with its transactions, for serving to peers and wallet
+    /// sync. `Ok(None)` if the chain doesn't have it; `BlockPruned` if only
+    /// its header is left.
+    pub fn full_block_by_hash(&self, hash: &[u8; 32]) -> WalletResult<Option<&Block>> {
+        match self.block_by_hash(hash) {
+            Some(block) if self.is_pruned(block.header.height) => {
+                Err(WalletError::BlockPruned(block.header.height))
+            }
+            block => Ok(block),
+        }
+    }
+
+    /// Whether the body of the block at `height` was pruned
+    pub fn is_pruned(&self, height: u64) -> bool {
+        self.pruned.iter().any(|range| range.contains(height))
+    }
+
+    pub fn pruned_ranges(&self) -> &[PrunedRange] {
+        &self.pruned
+    }
+
+    /// Keep the body of the block at `height` through every compaction
+    pub fn keep_full_block(&mut self, height: u64) {
+        self.kept.insert(height);
+    }
+
+    /// Drop the transactions of blocks below `keep_full_blocks_after`,
+    /// keeping their headers and filters and the blocks marked with
+    /// `keep_full_block`. Pruned transactions are no longer found by id.
+    pub fn compact(&mut self, keep_full_blocks_after: u64) -> CompactionReport {
+        let mut report = CompactionReport::default();
+        let mut pruned = Vec::new();
+        for block in self.blocks.iter_mut() {
+            let height = block.header.height;
+            if height >= keep_full_blocks_after {
+                break;
+            }
+            if self.pruned.iter().any(|range| range.contains(height)) {
+                continue;
+            }
+            if self.kept.contains(&height) {
+                report.kept_blocks += 1;
+                continue;
+            }
+            let size = block.to_bytes().len();
+            for tx in block.transactions.drain(..) {
+                self.by_tx.remove(&tx.id);
+            }
+            report.freed_bytes += size.saturating_sub(block.to_bytes().len()) as u64;
+            report.pruned_blocks += 1;
+            pruned.push(height);
+        }
+        for height in pruned {
+            self.mark_pruned(height);
+        }
+
+        if report.pruned_blocks > 0 {
+            info!(
+                "Pruned {} block bodies below height {}, {} bytes",
+                report.pruned_blocks, keep_full_blocks_after, report.freed_bytes
+            );
+        }
+        report
+    }
+
+    /// Add `height` to the pruned ranges, joining it to its neighbours
+    fn mark_pruned(&mut self, height: u64) {
+        let index = self.pruned.partition_point(|range| range.end < height);
+        let joins_previous = index > 0 && self.pruned[index - 1].end + 1 == height;
+        let joins_next = self
+            .pruned
+            .get(index)
+            .is_some_and(|range| range.start == height + 1);
+        match (joins_previous, joins_next) {
+            (true, true) => {
+                self.pruned[index - 1].end = self.pruned[index].end;
+                self.pruned.remove(index);
+            }
+            (true, false) => self.pruned[index - 1].end = height,
+            (false, true) => self.pruned[index].start = height,
+            (false, false) => self.pruned.insert(
+                index,
+                PrunedRange {
+                    start: height,
+                    end: height,
+                },
+            ),
+        }
+    }
+
+    /// Transaction `tx_id` and the block containing it
+    pub fn transaction_by_id(&self, tx_id: &str) -> Option<(&Block, &NockchainTransaction)> {
+        let (height, index) = *self.by_tx.get(tx_id)?;
+        let block = self.block_at(height)?;
+        Some((block, block.transactions.get(index)?))
+    }
+
+    /// Checkpoint with the greatest height, if the config has any
+    pub fn latest_checkpoint(&self) -> Option<&Checkpoint> {
+        checkpoints::latest(&self.config.checkpoints)
+    }
+
+    /// Whether the next block skips the proof-of-work check because the
+    /// latest checkpoint is still ahead of it
+    pub fn validation_phase(&self) -> ValidationPhase {
+        let next_height = self.height().map_or(0, |height| height + 1);
+        match self.latest_checkpoint() {
+            Some(checkpoint)
+                if self.config.skip_pow_below_checkpoint && next_height < checkpoint.height =>
+            {
+                ValidationPhase::Fast {
+                    checkpoint: checkpoint.height,
+                }
+            }
+            _ => ValidationPhase::Full,
+        }
+    }
+
+    /// Median timestamp of the last `MEDIAN_TIME_SPAN` blocks, or `None` before genesis
+    pub fn median_time_past(&self) -> Option<u64> {
+        let start = self.blocks.len().saturating_sub(MEDIAN_TIME_SPAN);
+        let mut timestamps: Vec<u64> = self.blocks[start..]
+            .iter()
+            .map(|block| block.header.timestamp)
+            .collect();
+        if timestamps.is_empty() {
+            return None;
+        }
+        timestamps.sort_unstable();
+        Some(timestamps[timestamps.len() / 2])
+    }
+
+    /// Earliest timestamp the next block may carry
+    pub fn min_next_timestamp(&self) -> u64 {
+        self.median_time_past().map_or(0, |median| median + 1)
+    }
+
+    /// Unmined block on top of the tip, timestamped so it satisfies the median rule
+    pub fn new_block(&self, transactions: Vec<NockchainTransaction>, bits: u32) -> Block {
+        let (previous_hash, height) = match self.tip() {
+            Some(tip) => (tip.hash(), tip.header.height + 1),
+            None => (self.config.genesis_hash, 0),
+        };
+
+        let mut block = Block::new(previous_hash, transactions, height, bits);
+        block.header.timestamp = block.header.timestamp.max(self.min_next_timestamp());
+        block
+    }
+
+    /// Validate `block` against the current tip and the checkpoints and
+    /// append it
+    pub fn add_block(&mut self, block: Block) -> WalletResult<()> {
+        let header = &block.header;
+        let hash = block.hash();
+
+        if header.version > self.config.max_header_version {
+            return Err(WalletError::BlockValidation(format!(
+                "unsupported header version {} (max {})",
+                header.version, self.config.max_header_version
+            )));
+        }
+
+        match self.tip() {
+            Some(tip) => {
+                if header.previous_hash != tip.hash() {
+                    return Err(WalletError::BlockValidation(
+                        "Block does not extend the current tip".to_string(),
+                    ));
+                }
+                if header.height != tip.header.height + 1 {
+                    return Err(WalletError::BlockValidation(format!(
+                        "Expected height {}, got {}",
+                        tip.header.height + 1,
+                        header.height
+                    )));
+                }
+            }
+            None => {
+                if header.height != 0 || header.previous_hash != self.config.genesis_hash {
+                    return Err(WalletError::BlockValidation(
+                        "First block must be genesis".to_string(),
+                    ));
+                }
+            }
+        }
+
+        checkpoints::check(&self.config.checkpoints, header.height, &hash)?;
+        // Sampled per block, so a clock corrected while the chain is open
+        // counts from then on
+        self.check_timestamp(header, self.clock.now().timestamp().max(0) as u64)?;
+        match self.validation_phase() {
+            ValidationPhase::Fast { .. } => block.validate_contents()?,
+            ValidationPhase::Full => block.validate()?,
+        }
+
+        debug!(
+            "Added block {} at height {}",
+            hex::encode(hash),
+            header.height
+        );
+        self.by_hash.insert(hash, header.height);
+        self.filters.insert(hash, BlockFilter::build(&block));
+        for (index, tx) in block.transactions.iter().enumerate() {
+            self.by_tx.insert(tx.id.clone(), (header.height, index));
+        }
+        self.blocks.push(block);
+        Ok(())
+    }
+
+    fn check_timestamp(&self, header: &BlockHeader, now: u64) -> WalletResult<()> {
+        if let Some(median) = self.median_time_past() {
+            if header.timestamp <= median {
+                return Err(WalletError::BlockValidation(format!(
+                    "Timestamp {} is not after the median of the last {} blocks ({})",
+                    header.timestamp, MEDIAN_TIME_SPAN, median
+                )));
+            }
+        }
+
+        let latest = now.saturating_add(self.config.max_future_block_time);
+        if header.timestamp > latest {
+            return Err(WalletError::BlockValidation(format!(
+                "Timestamp {} is more than {}s ahead of local time",
+                header.timestamp, self.config.max_future_block_time
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl Wallet {
+    /// Compact `chain` below `keep_full_blocks_after`, first marking every
+    /// block that pays or spends from the wallet or holds a transaction it
+    /// recorded so those keep their bodies
+    pub fn compact_chain(
+        &self,
+        chain: &mut Blockchain,
+        keep_full_blocks_after: u64,
+    ) -> CompactionReport {
+        let relevant: Vec<u64> = chain
+            .blocks()
+            .iter()
+            .filter(|block| block.header.height < keep_full_blocks_after)
+            .filter(|block| block.transactions.iter().any(|tx| self.is_relevant(tx)))
+            .map(|block| block.header.height)
+            .collect();
+        for height in relevant {
+            chain.keep_full_block(height);
+        }
+        chain.compact(keep_full_blocks_after)
+    }
+
+    fn is_relevant(&self, tx: &NockchainTransaction) -> bool {
+        self.transactions.contains(&tx.id)
+            || tx.outputs.iter().any(|output| {
+                Address::from_string(&output.recipient_address)
+                    .is_ok_and(|address| self.keys.is_mine(&address))
+            })
+            || tx
+                .inputs
+                .iter()
+                .any(|input| self.balances.note_at(input.outpoint()).is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{
+        fake_address, fake_block, fake_coinbase, fake_note, fake_signed_transaction,
+        fakenet_chain_config, FakeClock, FIXTURE_EPOCH,
+    };
+    use crate::wallet::encoding;
+    use chrono::Utc;
+
+    /// Difficulty a test can mine in a few hundred hashes
+    const EASY_BITS: u32 = 0x1f7f_ffff;
+
+    fn chain_with(config: BlockchainConfig) -> Blockchain {
+        Blockchain::new(BlockchainConfig {
+            initial_difficulty: EASY_BITS,
+            ..config
+        })
+    }
+
+    fn mined(chain: &Blockchain, transactions: Vec<NockchainTransaction>) -> Block {
+        let mut block = chain.new_block(transactions, EASY_BITS);
+        block.mine().unwrap();
+        block
+    }
+
+    #[test]
+    fn headers_newer_than_the_configured_version_are_rejected() {
+        let mut chain = chain_with(fakenet_chain_config());
+        let mut block = chain.new_block(Vec::new(), EASY_BITS);
+        block.header.version = encoding::MAX_HEADER_VERSION + 1;
+        let error = chain.add_block(block).unwrap_err().to_string();
+        assert!(error.contains(&format!(
+            "unsupported header version {} (max {})",
+            encoding::MAX_HEADER_VERSION + 1,
+            encoding::MAX_HEADER_VERSION
+        )));
+        assert!(chain.tip().is_none());
+
+        let genesis = mined(&chain, Vec::new());
+        assert_eq!(genesis.header.version, 1);
+        chain.add_block(genesis).unwrap();
+        assert_eq!(chain.height(), Some(0));
+    }
+
+    /// Mine the next block of `chain` with `timestamp`
+    fn mined_at(chain: &Blockchain, timestamp: u64) -> Block {
+        let coinbase = match chain.tip() {
+            Some(_) => vec![fake_coinbase(&fake_address(), 50)],
+            None => Vec::new(),
+        };
+        let mut block = chain.new_block(coinbase, EASY_BITS);
+        block.header.timestamp = timestamp;
+        // Not `mine`, which moves the timestamp up to this machine's clock
+        while !block.header.meets_difficulty() {
+            block.header.nonce += 1;
+        }
+        block
+    }
+
+    /// Chain on a clock stopped at `FIXTURE_EPOCH`
+    fn chain_at_epoch() -> Blockchain {
+        chain_with(fakenet_chain_config()).with_clock(Arc::new(FakeClock::new()))
+    }
+
+    const EPOCH: u64 = FIXTURE_EPOCH as u64;
+
+    #[test]
+    fn blocks_more_than_two_hours_ahead_are_rejected() {
+        let mut chain = chain_at_epoch();
+        let error = chain
+            .add_block(mined_at(&chain, EPOCH + 7_201))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Block validation error: Timestamp {} is more than 7200s ahead of local time",
+                EPOCH + 7_201
+            )
+        );
+        chain.add_block(mined_at(&chain, EPOCH + 7_200)).unwrap();
+    }
+
+    #[test]
+    fn blocks_must_be_after_the_median_of_the_last_eleven() {
+        let mut chain = chain_at_epoch();
+        // Timestamps out of order, as miners' clocks disagree
+        for offset in [0, 60, 120, 90, 180, 150, 240, 210, 300, 270, 330] {
+            chain.add_block(mined_at(&chain, EPOCH + offset)).unwrap();
+        }
+        let median = EPOCH + 180;
+        assert_eq!(chain.median_time_past(), Some(median));
+
+        let error = chain.add_block(mined_at(&chain, median)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Block validation error: Timestamp {} is not after the median of the last 11 blocks ({})",
+                median, median
+            )
+        );
+        assert!(chain.add_block(mined_at(&chain, EPOCH)).is_err());
+
+        // Earlier than its parent is fine as long as it's after the median
+        chain.add_block(mined_at(&chain, median + 1)).unwrap();
+        assert_eq!(chain.height(), Some(11));
+    }
+
+    #[test]
+    fn new_blocks_are_timestamped_past_the_median() {
+        let mut chain = chain_with(BlockchainConfig {
+            max_future_block_time: u64::MAX / 2,
+            ..fakenet_chain_config()
+        });
+        // A chain whose timestamps run far ahead of this machine's clock
+        let ahead = Utc::now().timestamp() as u64 + 86_400;
+        chain.add_block(mined_at(&chain, ahead)).unwrap();
+
+        let mut block = chain.new_block(vec![fake_coinbase(&fake_address(), 50)], EASY_BITS);
+        assert_eq!(block.header.timestamp, chain.min_next_timestamp());
+        block.mine().unwrap();
+        assert!(block.header.timestamp > ahead);
+        chain.add_block(block).unwrap();
+    }
+
+    #[test]
+    fn the_version_cap_can_be_lowered() {
+        let mut chain = chain_with(BlockchainConfig {
+            max_header_version: 0,
+            ..fakenet_chain_config()
+        });
+        let genesis = mined(&chain, Vec::new());
+        assert!(matches!(
+            chain.add_block(genesis),
+            Err(WalletError::BlockValidation(_))
+        ));
+    }
+
+    /// Far harder than `EASY_BITS`; an unmined block won't meet it
+    const HARD_BITS: u32 = 0x1d00_ffff;
+
+    /// Chain on the epoch clock holding `checkpoints`
+    fn checkpointed(checkpoints: Vec<Checkpoint>, skip_pow: bool) -> Blockchain {
+        chain_with(BlockchainConfig {
+            checkpoints,
+            skip_pow_below_checkpoint: skip_pow,
+            ..fakenet_chain_config()
+        })
+        .with_clock(Arc::new(FakeClock::new()))
+    }
+
+    /// Six blocks a minute apart, with a checkpoint to be taken from
+    fn honest_blocks() -> Vec<Block> {
+        let mut chain = checkpointed(Vec::new(), false);
+        for minute in 0..6 {
+            chain
+                .add_block(mined_at(&chain, EPOCH + minute * 60))
+                .unwrap();
+        }
+        chain.blocks().to_vec()
+    }
+
+    #[test]
+    fn a_fork_below_a_checkpoint_is_rejected_at_it() {
+        let honest = honest_blocks();
+        let checkpoint = Checkpoint::new(4, honest[4].hash());
+        let mut chain = checkpointed(vec![checkpoint], true);
+        chain.add_block(honest[0].clone()).unwrap();
+        chain.add_block(honest[1].clone()).unwrap();
+
+        // A rival history from height 2, valid in every other way
+        for minute in [10, 11] {
+            chain
+                .add_block(mined_at(&chain, EPOCH + minute * 60))
+                .unwrap();
+        }
+        assert_ne!(chain.tip().unwrap().hash(), honest[3].hash());
+        let rival = mined_at(&chain, EPOCH + 12 * 60);
+        match chain.add_block(rival.clone()) {
+            Err(WalletError::CheckpointMismatch {
+                height,
+                expected,
+                found,
+            }) => {
+                assert_eq!(height, 4);
+                assert_eq!(expected, hex::encode(honest[4].hash()));
+                assert_eq!(found, hex::encode(rival.hash()));
+            }
+            other => panic!("expected a checkpoint mismatch, got {:?}", other),
+        }
+        assert_eq!(chain.height(), Some(3));
+
+        // Header sync turns the fork away before fetching its blocks
+        let mut headers: Vec<BlockHeader> = chain
+            .blocks()
+            .iter()
+            .map(|block| block.header.clone())
+            .collect();
+        headers.push(rival.header.clone());
+        assert!(checkpoints::check_headers(&[checkpoint], &headers).is_err());
+        let honest_headers: Vec<BlockHeader> =
+            honest.iter().map(|block| block.header.clone()).collect();
+        assert!(checkpoints::check_headers(&[checkpoint], &honest_headers).is_ok());
+    }
+
+    #[test]
+    fn the_honest_chain_passes_its_checkpoints() {
+        let honest = honest_blocks();
+        let mut chain = checkpointed(
+            vec![
+                Checkpoint::new(2, honest[2].hash()),
+                Checkpoint::new(5, honest[5].hash()),
+            ],
+            true,
+        );
+        for block in &honest {
+            chain.add_block(block.clone()).unwrap();
+        }
+        assert_eq!(chain.latest_checkpoint().map(|c| c.height), Some(5));
+        assert_eq!(chain.validation_phase(), ValidationPhase::Full);
+    }
+
+    /// Next block of `chain` at `timestamp`, left unmined at `HARD_BITS`
+    fn unmined_at(chain: &Blockchain, timestamp: u64) -> Block {
+        let coinbase = match chain.tip() {
+            Some(_) => vec![fake_coinbase(&fake_address(), 50)],
+            None => Vec::new(),
+        };
+        let mut block = chain.new_block(coinbase, HARD_BITS);
+        block.header.timestamp = timestamp;
+        block
+    }
+
+    #[test]
+    fn proof_of_work_is_skipped_below_the_checkpoint_only_when_enabled() {
+        let mut staged = checkpointed(vec![Checkpoint::new(u64::MAX, [0; 32])], true);
+        for minute in 0..4 {
+            staged
+                .add_block(unmined_at(&staged, EPOCH + minute * 60))
+                .unwrap();
+        }
+        let blocks = staged.blocks().to_vec();
+        let checkpoint = Checkpoint::new(3, blocks[3].hash());
+
+        let mut fast = checkpointed(vec![checkpoint], true);
+        assert_eq!(
+            fast.validation_phase(),
+            ValidationPhase::Fast { checkpoint: 3 }
+        );
+        assert_eq!(
+            fast.validation_phase().to_string(),
+            "Validating below checkpoint 3 (fast)"
+        );
+        for block in &blocks[..3] {
+            fast.add_block(block.clone()).unwrap();
+        }
+        // The checkpoint block itself gets every check
+        assert_eq!(fast.validation_phase(), ValidationPhase::Full);
+        assert_eq!(fast.validation_phase().to_string(), "Full validation");
+        let error = fast.add_block(blocks[3].clone()).unwrap_err();
+        assert!(
+            error.to_string().contains("Invalid proof of work"),
+            "{}",
+            error
+        );
+
+        let mut full = checkpointed(vec![checkpoint], false);
+        assert_eq!(full.validation_phase(), ValidationPhase::Full);
+        assert!(full.add_block(blocks[0].clone()).is_err());
+        assert!(full.tip().is_none());
+
+        // Skipping is opt-in
+        let default = Blockchain::new(BlockchainConfig {
+            checkpoints: vec![checkpoint],
+            ..BlockchainConfig::default()
+        });
+        assert_eq!(default.validation_phase(), ValidationPhase::Full);
+    }
+
+    /// Chain of `count` blocks whose proof of work isn't checked, since a
+    /// checkpoint lies far ahead; each block after genesis holds a coinbase,
+    /// and the last few a payment too
+    fn long_chain(count: u64) -> Blockchain {
+        chain_of((0..count).map(|height| {
+            let mut transactions = Vec::new();
+            if height > 0 {
+                transactions.push(fake_coinbase(&fake_address(), 50));
+            }
+            if height > 0 && height + 10 >= count {
+                transactions.push(payment());
+            }
+            transactions
+        }))
+    }
+
+    /// Chain of one block per entry of `blocks`, holding those transactions,
+    /// with proof of work unchecked as in `long_chain`
+    fn chain_of(blocks: impl IntoIterator<Item = Vec<NockchainTransaction>>) -> Blockchain {
+        let mut chain = Blockchain::new(BlockchainConfig {
+            checkpoints: vec![Checkpoint::new(u64::MAX, [0; 32])],
+            skip_pow_below_checkpoint: true,
+            max_future_block_time: u64::MAX / 2,
+            ..fakenet_chain_config()
+        });
+        for (height, transactions) in blocks.into_iter().enumerate() {
+            let mut block = chain.new_block(transactions, EASY_BITS);
+            block.header.timestamp = EPOCH + height as u64 * 600;
+            chain.add_block(block).unwrap();
+        }
+        chain
+    }
+
+    /// A payment between two strangers
+    fn payment() -> NockchainTransaction {
+        fake_block().with_txs(1).build().transactions.remove(0)
+    }
+
+    fn encoded_size(chain: &Blockchain) -> usize {
+        chain
+            .blocks()
+            .iter()
+            .map(|block| block.to_bytes().len())
+            .sum()
+    }
+
+    fn heights(blocks: &[&Block]) -> Vec<u64> {
+        blocks.iter().map(|block| block.header.height).collect()
+    }
+
+    #[test]
+    fn pages_run_newest_first_and_end_past_genesis() {
+        let chain = long_chain(25);
+        assert_eq!(heights(&chain.blocks_page(0, 5)), vec![24, 23, 22, 21, 20]);
+        assert_eq!(heights(&chain.blocks_page(20, 10)), vec![4, 3, 2, 1, 0]);
+        assert!(chain.blocks_page(25, 10).is_empty());
+        assert!(chain.blocks_page(usize::MAX, 10).is_empty());
+        assert!(chain.blocks_page(0, 0).is_empty());
+
+        // Consecutive pages cover the chain once
+        let mut seen = Vec::new();
+        let mut offset = 0;
+        loop {
+            let page = chain.blocks_page(offset, 7);
+            if page.is_empty() {
+                break;
+            }
+            offset += page.len();
+            seen.extend(heights(&page));
+        }
+        assert_eq!(seen, (0..25).rev().collect::<Vec<_>>());
+        assert!(Blockchain::new(fakenet_chain_config())
+            .blocks_page(0, 10)
+            .is_empty());
+    }
+
+    #[test]
+    fn blocks_are_found_by_height_and_hash() {
+        let chain = long_chain(10);
+        let hash_of = |block: Option<&Block>| block.map(Block::hash);
+        for block in chain.blocks() {
+            let hash = Some(block.hash());
+            assert_eq!(hash_of(chain.block_by_height(block.header.height)), hash);
+            assert_eq!(hash_of(chain.block_by_hash(&block.hash())), hash);
+        }
+        assert!(chain.block_by_height(10).is_none());
+        assert!(chain.block_by_height(u64::MAX).is_none());
+        assert!(chain.block_by_hash(&[7; 32]).is_none());
+    }
+
+    #[test]
+    fn transactions_are_found_with_their_block() {
+        let chain = long_chain(6);
+        let block = chain.block_at(4).unwrap();
+        let payment = &block.transactions[1];
+        let (found_block, found) = chain.transaction_by_id(&payment.id).unwrap();
+        assert_eq!(found_block.header.height, 4);
+        assert_eq!(found.id, payment.id);
+        assert!(chain.transaction_by_id("not-a-tx").is_none());
+    }
+
+    #[test]
+    fn paging_a_chain_of_100k_blocks_stays_cheap() {
+        let chain = long_chain(100_000);
+        let start = std::time::Instant::now();
+        let newest = chain.blocks_page(0, 50);
+        let oldest = chain.blocks_page(99_950, 50);
+        let tip = chain.tip().unwrap();
+        let by_hash = chain.block_by_hash(&tip.hash()).map(Block::hash);
+        let elapsed = start.elapsed();
+
+        assert_eq!(newest.len(), 50);
+        assert_eq!(newest[0].header.height, 99_999);
+        assert_eq!(oldest.last().unwrap().header.height, 0);
+        assert_eq!(by_hash, Some(tip.hash()));
+        assert!(
+            elapsed < std::time::Duration::from_millis(50),
+            "{:?}",
+            elapsed
+        );
+    }
+
+    #[test]
+    fn compaction_prunes_old_bodies_and_keeps_their_headers() {
+        let mut chain = long_chain(10);
+        let hashes: Vec<[u8; 32]> = chain.blocks().iter().map(Block::hash).collect();
+        let coinbase_at_2 = chain.block_at(2).unwrap().transactions[0].id.clone();
+        let before = encoded_size(&chain);
+
+        let report = chain.compact(4);
+        assert_eq!(report.pruned_blocks, 4);
+        assert_eq!(report.kept_blocks, 0);
+        assert_eq!(report.freed_bytes as usize, before - encoded_size(&chain));
+        assert!(report.freed_bytes > 0);
+        assert_eq!(chain.pruned_ranges(), &[PrunedRange { start: 0, end: 3 }]);
+
+        // Headers still answer by height and hash
+        assert_eq!(chain.height(), Some(9));
+        assert_eq!(chain.block_at(2).unwrap().hash(), hashes[2]);
+        assert!(chain.block_at(2).unwrap().transactions.is_empty());
+        assert!(chain.transaction_by_id(&coinbase_at_2).is_none());
+        assert!(chain.is_pruned(3) && !chain.is_pruned(4));
+
+        // Serving refuses the pruned bodies and hands out the rest whole
+        assert!(matches!(
+            chain.full_block_by_hash(&hashes[2]),
+            Err(WalletError::BlockPruned(2))
+        ));
+        let whole = chain.full_block_by_hash(&hashes[4]).unwrap().unwrap();
+        assert_eq!(whole.transactions.len(), 2);
+        assert!(chain.full_block_by_hash(&[7; 32]).unwrap().is_none());
+
+        // Later compactions skip what is gone and join the ranges up
+        chain.keep_full_block(5);
+        let report = chain.compact(8);
+        assert_eq!((report.pruned_blocks, report.kept_blocks), (3, 1));
+        assert_eq!(
+            chain.pruned_ranges(),
+            &[
+                PrunedRange { start: 0, end: 4 },
+                PrunedRange { start: 6, end: 7 },
+            ]
+        );
+        assert_eq!(chain.block_at(5).unwrap().transactions.len(), 2);
+        assert_eq!(chain.compact(8).pruned_blocks, 0);
+    }
+
+    #[test]
+    fn blocks_the_wallet_needs_survive_compaction() {
+        let mut wallet = Wallet::new();
+        let address = wallet.generate_key("main").unwrap();
+        let note = fake_note().address(address.clone()).amount(800).build();
+        wallet.observe_note(note.clone()).unwrap();
+        let recorded = fake_signed_transaction().build();
+        wallet
+            .transactions_mut()
+            .add_pending_transaction(recorded.clone(), true);
+        let mut recorded_in_block = payment();
+        recorded_in_block.id = recorded.id.clone();
+
+        let spend = fake_block()
+            .spending(&note, &fake_address(), 700)
+            .build()
+            .transactions
+            .remove(0);
+        // Past the cutoff a stranger's early coinbase is spent to the wallet
+        let early = fake_coinbase(&fake_address(), 50);
+        let early_note = fake_note().outpoint(early.id.clone(), 0).amount(50).build();
+        let late_payment = fake_block()
+            .spending(&early_note, &address, 40)
+            .build()
+            .transactions
+            .remove(0);
+        let mut chain = chain_of((0..10).map(|height| {
+            let mut transactions = match height {
+                0 => return Vec::new(),
+                1 => vec![early.clone()],
+                2 => vec![fake_coinbase(&address, 50)],
+                _ => vec![fake_coinbase(&fake_address(), 50)],
+            };
+            match height {
+                4 => transactions.push(spend.clone()),
+                6 => transactions.push(recorded_in_block.clone()),
+                8 => transactions.push(late_payment.clone()),
+                _ => transactions.push(payment()),
+            }
+            transactions
+        }));
+
+        let report = wallet.compact_chain(&mut chain, 8);
+        assert_eq!(report.pruned_blocks, 5);
+        assert_eq!(report.kept_blocks, 3);
+        for height in [2, 4, 6] {
+            let hash = chain.block_at(height).unwrap().hash();
+            let block = chain.full_block_by_hash(&hash).unwrap().unwrap();
+            assert_eq!(block.transactions.len(), 2, "block {}", height);
+        }
+        assert!(chain.transaction_by_id(&spend.id).is_some());
+        assert_eq!(
+            chain.pruned_ranges(),
+            &[
+                PrunedRange { start: 0, end: 1 },
+                PrunedRange { start: 3, end: 3 },
+                PrunedRange { start: 5, end: 5 },
+                PrunedRange { start: 7, end: 7 },
+            ]
+        );
+    }
+}
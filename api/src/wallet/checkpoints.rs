@@ -0,0 +1,116 @@
+//! Chain checkpoints: block hashes at fixed heights that every accepted chain
+//! must contain.
+//!
+//! A chain that puts a different block at a checkpoint height is rejected
+//! outright, however much work it claims, so history before the latest
+//! checkpoint can't be rewritten by a long-range attacker. Because the
+//! checkpoint hash vouches for every block beneath it, blocks below the latest
+//! checkpoint can be set to skip the proof-of-work check while syncing
+//! (`BlockchainConfig::skip_pow_below_checkpoint`, off by default). Mainnet
+//! checkpoints are compiled in; fakenet starts with none and takes them from
+//! its config.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::wallet::{BlockHeader, WalletError, WalletResult};
+
+/// Hash the block at `height` must have
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub height: u64,
+    pub hash: [u8; 32],
+}
+
+impl Checkpoint {
+    pub const fn new(height: u64, hash: [u8; 32]) -> Self {
+        Self { height, hash }
+    }
+}
+
+/// Compiled-in mainnet checkpoints, oldest first. Extended at each release
+/// with blocks buried deep enough that no honest reorg reaches them.
+pub const MAINNET_CHECKPOINTS: &[Checkpoint] = &[];
+
+/// How blocks are being checked as the chain grows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ValidationPhase {
+    /// Below the checkpoint at this height; proof of work isn't re-checked
+    Fast { checkpoint: u64 },
+    /// Every check, proof of work included
+    Full,
+}
+
+impl fmt::Display for ValidationPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fast { checkpoint } => {
+                write!(f, "Validating below checkpoint {checkpoint} (fast)")
+            }
+            Self::Full => f.write_str("Full validation"),
+        }
+    }
+}
+
+/// Checkpoint with the greatest height
+pub fn latest(checkpoints: &[Checkpoint]) -> Option<&Checkpoint> {
+    checkpoints
+        .iter()
+        .max_by_key(|checkpoint| checkpoint.height)
+}
+
+/// Fails if a checkpoint at `height` names a block other than `hash`
+pub fn check(checkpoints: &[Checkpoint], height: u64, hash: &[u8; 32]) -> WalletResult<()> {
+    match checkpoints
+        .iter()
+        .find(|checkpoint| checkpoint.height == height)
+    {
+        Some(checkpoint) if checkpoint.hash != *hash => Err(WalletError::CheckpointMismatch {
+            height,
+            expected: hex::encode(checkpoint.hash),
+            found: hex::encode(hash),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Check a run of headers received during header sync, before any of their
+/// blocks are fetched
+pub fn check_headers(checkpoints: &[Checkpoint], headers: &[BlockHeader]) -> WalletResult<()> {
+    headers
+        .iter()
+        .try_for_each(|header| check(checkpoints, header.height, &header.hash()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_height_of_a_checkpoint_is_held_to_its_hash() {
+        let checkpoints = [Checkpoint::new(10, [1; 32]), Checkpoint::new(20, [2; 32])];
+        assert!(check(&checkpoints, 10, &[1; 32]).is_ok());
+        assert!(check(&checkpoints, 11, &[7; 32]).is_ok());
+        assert_eq!(
+            check(&checkpoints, 20, &[7; 32]).unwrap_err().to_string(),
+            WalletError::CheckpointMismatch {
+                height: 20,
+                expected: hex::encode([2; 32]),
+                found: hex::encode([7; 32]),
+            }
+            .to_string()
+        );
+        assert!(check(&[], 20, &[7; 32]).is_ok());
+    }
+
+    #[test]
+    fn the_latest_checkpoint_is_the_highest_in_any_order() {
+        let checkpoints = [
+            Checkpoint::new(20, [2; 32]),
+            Checkpoint::new(30, [3; 32]),
+            Checkpoint::new(10, [1; 32]),
+        ];
+        assert_eq!(latest(&checkpoints).map(|c| c.height), Some(30));
+        assert_eq!(latest(&[]), None);
+    }
+}
@@ -0,0 +1,224 @@
+//! Wall-clock jumps.
+//!
+//! The wall clock moves when a laptop sleeps and resumes, when the user sets
+//! it, or when time sync corrects it; the monotonic clock does neither, and
+//! on most systems doesn't count time spent asleep. Durations such as the
+//! auto-lock idle time, backoff and uptime are therefore measured on the
+//! monotonic clock, and `ClockMonitor` compares the two to notice when the
+//! wall clock jumped. The node logs a jump and forgives the peer timeouts it
+//! causes; the wallet counts a forward jump, usually a sleep, as idle time;
+//! the UI redraws its relative times.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Difference between how far the wall and monotonic clocks moved that
+/// counts as a jump; smaller differences are ordinary drift and correction
+pub const CLOCK_JUMP_THRESHOLD: Duration = Duration::from_secs(30);
+
+/// Source of wall-clock and monotonic time, replaceable for tests
+pub trait Clock: Send + Sync + fmt::Debug {
+    fn now(&self) -> DateTime<Utc>;
+    fn instant(&self) -> Instant;
+}
+
+/// Time from the operating system
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// The wall clock moving by `skew_secs` more than the monotonic clock
+/// between two checks: positive for forward, as after a sleep, negative for
+/// back
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockJump {
+    /// Wall-clock time the jump was noticed, after it
+    pub at: DateTime<Utc>,
+    pub skew_secs: i64,
+}
+
+impl ClockJump {
+    pub fn is_forward(&self) -> bool {
+        self.skew_secs > 0
+    }
+
+    /// Size of the jump, whichever way it went
+    pub fn magnitude(&self) -> Duration {
+        Duration::from_secs(self.skew_secs.unsigned_abs())
+    }
+}
+
+impl fmt::Display for ClockJump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.skew_secs.unsigned_abs();
+        let direction = if self.is_forward() { "forward" } else { "back" };
+        match secs {
+            0..120 => write!(f, "{} {}s", direction, secs),
+            120..3600 => write!(f, "{} {}m", direction, secs / 60),
+            _ => write!(f, "{} {}h {}m", direction, secs / 3600, secs % 3600 / 60),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct MonitorState {
+    wall: DateTime<Utc>,
+    instant: Instant,
+    last_jump: Option<ClockJump>,
+    jumps: u64,
+}
+
+/// Notices wall-clock jumps by comparing each clock's progress between
+/// calls to `check`
+#[derive(Debug)]
+pub struct ClockMonitor {
+    clock: Arc<dyn Clock>,
+    threshold: Duration,
+    state: Mutex<MonitorState>,
+}
+
+impl Default for ClockMonitor {
+    fn default() -> Self {
+        Self::new(Arc::new(SystemClock))
+    }
+}
+
+impl ClockMonitor {
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        let state = MonitorState {
+            wall: clock.now(),
+            instant: clock.instant(),
+            last_jump: None,
+            jumps: 0,
+        };
+        Self {
+            clock,
+            threshold: CLOCK_JUMP_THRESHOLD,
+            state: Mutex::new(state),
+        }
+    }
+
+    pub fn with_threshold(mut self, threshold: Duration) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// Sample both clocks, returning a jump if the wall clock moved more
+    /// than the threshold further than the monotonic clock since the last
+    /// check. Call it every few seconds; a jump is reported once.
+    pub fn check(&self) -> Option<ClockJump> {
+        let wall = self.clock.now();
+        let instant = self.clock.instant();
+        let mut state = self.state.lock().ok()?;
+
+        let wall_elapsed = (wall - state.wall).num_milliseconds();
+        let elapsed =
+            i64::try_from(instant.duration_since(state.instant).as_millis()).unwrap_or(i64::MAX);
+        state.wall = wall;
+        state.instant = instant;
+
+        let skew_ms = wall_elapsed.saturating_sub(elapsed);
+        if skew_ms.unsigned_abs() < self.threshold.as_millis() as u64 {
+            return None;
+        }
+        let jump = ClockJump {
+            at: wall,
+            skew_secs: skew_ms / 1000,
+        };
+        state.last_jump = Some(jump);
+        state.jumps += 1;
+        Some(jump)
+    }
+
+    /// Most recent jump noticed
+    pub fn last_jump(&self) -> Option<ClockJump> {
+        self.state.lock().ok()?.last_jump
+    }
+
+    /// Jumps noticed so far, for callers that redraw when it changes
+    pub fn jump_count(&self) -> u64 {
+        self.state.lock().map(|state| state.jumps).unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::FakeClock;
+
+    fn monitor() -> (Arc<FakeClock>, ClockMonitor) {
+        let clock = Arc::new(FakeClock::new());
+        (clock.clone(), ClockMonitor::new(clock))
+    }
+
+    #[test]
+    fn time_passing_on_both_clocks_is_no_jump() {
+        let (clock, monitor) = monitor();
+        clock.advance(Duration::from_secs(3600));
+        assert_eq!(monitor.check(), None);
+        // Drift under the threshold is ordinary correction
+        clock.jump(29);
+        assert_eq!(monitor.check(), None);
+        clock.jump(-29);
+        assert_eq!(monitor.check(), None);
+        assert_eq!(monitor.jump_count(), 0);
+    }
+
+    #[test]
+    fn a_jump_either_way_is_reported_once() {
+        let (clock, monitor) = monitor();
+        clock.advance(Duration::from_secs(5));
+        clock.jump(2 * 3600);
+        let forward = monitor.check().unwrap();
+        assert!(forward.is_forward());
+        assert_eq!(forward.magnitude(), Duration::from_secs(2 * 3600));
+        assert_eq!(forward.at, clock.now());
+        assert_eq!(monitor.check(), None);
+
+        clock.jump(-90);
+        let back = monitor.check().unwrap();
+        assert!(!back.is_forward());
+        assert_eq!(back.skew_secs, -90);
+        assert_eq!(monitor.last_jump(), Some(back));
+        assert_eq!(monitor.jump_count(), 2);
+    }
+
+    #[test]
+    fn the_threshold_can_be_lowered() {
+        let (clock, monitor) = monitor();
+        let monitor = monitor.with_threshold(Duration::from_secs(5));
+        clock.jump(10);
+        assert_eq!(monitor.check().map(|jump| jump.skew_secs), Some(10));
+    }
+
+    #[test]
+    fn jumps_describe_their_size_and_direction() {
+        let jump = |skew_secs| ClockJump {
+            at: Utc::now(),
+            skew_secs,
+        };
+        assert_eq!(jump(45).to_string(), "forward 45s");
+        assert_eq!(jump(-119).to_string(), "back 119s");
+        assert_eq!(jump(600).to_string(), "forward 10m");
+        assert_eq!(jump(3_599).to_string(), "forward 59m");
+        assert_eq!(jump(3_600).to_string(), "forward 1h 0m");
+        assert_eq!(jump(-7_200).to_string(), "back 2h 0m");
+        assert_eq!(jump(9 * 3600 + 125).to_string(), "forward 9h 2m");
+    }
+}
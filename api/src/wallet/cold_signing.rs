@@ -0,0 +1,439 @@
+//! Split signing for air-gapped keys.
+//!
+//! The online wallet selects funds and exports an `UnsignedTransactionPackage`:
+//! the inputs with their amounts and owning addresses, the outputs, the fee,
+//! the expiry height if there is one, and one sighash per input. The offline wallet signs the sighashes with
+//! `NockchainKeyManager::sign_package`, needing no chain state. The online
+//! wallet then checks every signature against the package before assembling
+//! and broadcasting the transaction.
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+
+use crate::wallet::encoding;
+use crate::wallet::keys::{NockchainKeyManager, TransactionInput, TransactionOutput};
+use crate::wallet::transaction::SignedTransaction;
+use crate::wallet::{Address, WalletError, WalletResult};
+
+/// An input together with the address whose key must sign it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageInput {
+    pub input: TransactionInput,
+    pub owner: Address,
+}
+
+/// A transaction ready to be signed elsewhere
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsignedTransactionPackage {
+    /// Chain the signatures bind the transaction to
+    pub chain_id: [u8; 32],
+    pub inputs: Vec<PackageInput>,
+    pub outputs: Vec<TransactionOutput>,
+    pub fee: u64,
+    /// Last block height the transaction may be mined at
+    pub expiry_height: Option<u64>,
+    /// What each input's signature covers, by input position
+    pub sighashes: Vec<[u8; 32]>,
+}
+
+impl UnsignedTransactionPackage {
+    pub fn new(
+        chain_id: [u8; 32],
+        inputs: Vec<PackageInput>,
+        outputs: Vec<TransactionOutput>,
+        fee: u64,
+        expiry_height: Option<u64>,
+    ) -> Self {
+        let mut package = Self {
+            chain_id,
+            inputs,
+            outputs,
+            fee,
+            expiry_height,
+            sighashes: Vec::new(),
+        };
+        package.sighashes = package.compute_sighashes();
+        package
+    }
+
+    fn transaction_inputs(&self) -> Vec<TransactionInput> {
+        self.inputs
+            .iter()
+            .map(|input| input.input.clone())
+            .collect()
+    }
+
+    /// Hash of the transaction's signing bytes, as `build_and_sign` computes it
+    pub fn signing_hash(&self) -> Vec<u8> {
+        encoding::signing_hash(
+            &self.chain_id,
+            &self.transaction_inputs(),
+            &self.outputs,
+            self.fee,
+            self.expiry_height,
+        )
+    }
+
+    /// Sighash of each input: the signing hash bound to the input's position
+    pub(crate) fn compute_sighashes(&self) -> Vec<[u8; 32]> {
+        let signing_hash = self.signing_hash();
+        (0..self.inputs.len() as u32)
+            .map(|index| {
+                let mut bytes = signing_hash.clone();
+                bytes.extend_from_slice(&index.to_le_bytes());
+                encoding::sha256(&bytes)
+            })
+            .collect()
+    }
+
+    /// Fail if the stored sighashes don't match the package contents
+    pub fn verify_sighashes(&self) -> WalletResult<()> {
+        if self.sighashes != self.compute_sighashes() {
+            return Err(WalletError::Crypto(
+                "Package sighashes do not match its contents".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn total_input(&self) -> u64 {
+        self.inputs.iter().map(|input| input.input.amount).sum()
+    }
+
+    pub fn total_output(&self) -> u64 {
+        self.outputs.iter().map(|output| output.amount).sum()
+    }
+}
+
+/// Signature over one input's sighash
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputSignature {
+    pub input_index: u32,
+    pub signature: [u8; 64],
+}
+
+/// Signatures for the inputs a key manager could sign, written back to the online wallet
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageSignatures {
+    pub signatures: Vec<InputSignature>,
+}
+
+impl SignedTransaction {
+    /// Assemble a transaction from a package and signatures for every input,
+    /// rejecting any signature that doesn't match its input's owner and sighash
+    pub fn from_package_and_signatures(
+        package: &UnsignedTransactionPackage,
+        signatures: &PackageSignatures,
+    ) -> WalletResult<Self> {
+        package.verify_sighashes()?;
+
+        let mut signature = Vec::with_capacity(package.inputs.len() * 64);
+        for (index, input) in package.inputs.iter().enumerate() {
+            let mut matching = signatures
+                .signatures
+                .iter()
+                .filter(|sig| sig.input_index as usize == index);
+            let (Some(input_sig), None) = (matching.next(), matching.next()) else {
+                return Err(WalletError::Crypto(format!(
+                    "Input {} needs exactly one signature",
+                    index
+                )));
+            };
+            let key = VerifyingKey::from_bytes(&input.owner.public_key).map_err(|_| {
+                WalletError::Crypto(format!("Owner of input {} is not a valid key", index))
+            })?;
+            key.verify(
+                &package.sighashes[index],
+                &Signature::from_bytes(&input_sig.signature),
+            )
+            .map_err(|_| {
+                WalletError::Crypto(format!(
+                    "Signature for input {} does not match the package",
+                    index
+                ))
+            })?;
+            signature.extend_from_slice(&input_sig.signature);
+        }
+        if let Some(stray) = signatures
+            .signatures
+            .iter()
+            .find(|sig| sig.input_index as usize >= package.inputs.len())
+        {
+            return Err(WalletError::Crypto(format!(
+                "Signature for input {} which the package does not have",
+                stray.input_index
+            )));
+        }
+
+        let mut tx = SignedTransaction {
+            id: String::new(),
+            chain_id: package.chain_id,
+            inputs: package.transaction_inputs(),
+            outputs: package.outputs.clone(),
+            fee: package.fee,
+            expiry_height: package.expiry_height,
+            signature,
+            hash: package.signing_hash(),
+        };
+        tx.id = encoding::transaction_id(&tx);
+        Ok(tx)
+    }
+}
+
+impl NockchainKeyManager {
+    /// Sign every input of `package` owned by `key_name`, offline. Fails if
+    /// the package was altered or the key owns none of its inputs.
+    pub fn sign_package(
+        &self,
+        package: &UnsignedTransactionPackage,
+        key_name: &str,
+    ) -> WalletResult<PackageSignatures> {
+        package.verify_sighashes()?;
+        if self.get_key(key_name).is_none() {
+            return Err(WalletError::KeyNotFound(key_name.to_string()));
+        }
+
+        let mut signatures = Vec::new();
+        for (index, input) in package.inputs.iter().enumerate() {
+            if let Some(secret) = self.secret_for_key_address(key_name, &input.owner) {
+                let signature = SigningKey::from_bytes(&secret).sign(&package.sighashes[index]);
+                signatures.push(InputSignature {
+                    input_index: index as u32,
+                    signature: signature.to_bytes(),
+                });
+            }
+        }
+        if signatures.is_empty() {
+            return Err(WalletError::Crypto(format!(
+                "Key '{}' does not own any input of the package",
+                key_name
+            )));
+        }
+        Ok(PackageSignatures { signatures })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_address, fake_note};
+    use crate::wallet::encoding::{Decode, Encode};
+    use crate::wallet::send::{Broadcaster, SendRequest};
+    use crate::wallet::Wallet;
+    use std::cell::RefCell;
+
+    const SEED: [u8; 32] = [3; 32];
+
+    #[derive(Default)]
+    struct RecordingBroadcaster {
+        sent: RefCell<Vec<String>>,
+    }
+
+    impl Broadcaster for RecordingBroadcaster {
+        async fn broadcast(&self, tx: &SignedTransaction, _force: bool) -> WalletResult<()> {
+            self.sent.borrow_mut().push(tx.id.clone());
+            Ok(())
+        }
+    }
+
+    /// Online wallet restored from `SEED` holding two confirmed notes of 1,000
+    fn online_wallet() -> Wallet {
+        let mut wallet = Wallet::new();
+        wallet.keys = NockchainKeyManager::from_seed(SEED);
+        let address = wallet.generate_key("main").unwrap();
+        for txid in ["aa", "bb"] {
+            wallet
+                .balances_mut()
+                .add_note(
+                    fake_note()
+                        .address(address.clone())
+                        .outpoint(txid, 0)
+                        .amount(1_000)
+                        .confirmed_at(1)
+                        .build(),
+                )
+                .unwrap();
+        }
+        wallet.balances_mut().set_tip_height(10);
+        wallet
+    }
+
+    /// Air-gapped key manager holding the same key
+    fn offline_keys(seed: [u8; 32]) -> NockchainKeyManager {
+        let mut keys = NockchainKeyManager::from_seed(seed);
+        keys.generate_key("main".to_string()).unwrap();
+        keys
+    }
+
+    fn request(amount: u64) -> SendRequest {
+        SendRequest {
+            key_name: "main".to_string(),
+            to: fake_address(),
+            amount,
+            fee: 10,
+            min_confirmations: None,
+            valid_for_blocks: None,
+            confirmed_amount: None,
+            force_broadcast: false,
+        }
+    }
+
+    fn locked(wallet: &Wallet) -> usize {
+        wallet
+            .balances()
+            .notes()
+            .iter()
+            .filter(|note| note.locked)
+            .count()
+    }
+
+    #[tokio::test]
+    async fn a_package_signed_offline_is_broadcast_and_recorded() {
+        let mut wallet = online_wallet();
+        let package = wallet.export_unsigned_send(&request(1_500)).unwrap();
+        assert_eq!(package.inputs.len(), 2);
+        assert_eq!(package.sighashes.len(), 2);
+        assert_eq!(locked(&wallet), 2);
+
+        // Carried to the offline machine and back as bytes
+        let carried = UnsignedTransactionPackage::from_bytes(&package.to_bytes()).unwrap();
+        assert_eq!(carried, package);
+        let signatures = offline_keys(SEED).sign_package(&carried, "main").unwrap();
+        assert_eq!(signatures.signatures.len(), 2);
+        let returned = PackageSignatures::from_bytes(&signatures.to_bytes()).unwrap();
+
+        let broadcaster = RecordingBroadcaster::default();
+        let tx = wallet
+            .broadcast_signed_package(&package, &returned, &broadcaster, false, |_| {})
+            .await
+            .unwrap();
+        assert_eq!(*broadcaster.sent.borrow(), vec![tx.id.clone()]);
+        assert_eq!(tx.hash, package.signing_hash());
+        assert_eq!(tx.signature.len(), 128);
+        assert!(wallet.transactions().signed(&tx.id).is_some());
+
+        // The package can't be spent twice
+        assert!(wallet
+            .broadcast_signed_package(&package, &returned, &broadcaster, false, |_| {})
+            .await
+            .is_err());
+        assert_eq!(broadcaster.sent.borrow().len(), 1);
+    }
+
+    #[test]
+    fn a_package_signed_offline_assembles_like_one_signed_online() {
+        let mut wallet = online_wallet();
+        let package = wallet.export_unsigned_send(&request(500)).unwrap();
+        let signatures = offline_keys(SEED).sign_package(&package, "main").unwrap();
+        let tx = SignedTransaction::from_package_and_signatures(&package, &signatures).unwrap();
+        assert_eq!(tx.chain_id, wallet.chain_id());
+        assert_eq!(tx.fee, 10);
+        assert_eq!(tx.inputs.len(), package.inputs.len());
+        assert_eq!(tx.id, encoding::transaction_id(&tx));
+    }
+
+    #[tokio::test]
+    async fn a_package_signed_for_another_chain_is_not_broadcast() {
+        let mut wallet = online_wallet();
+        wallet.set_chain_id(crate::wallet::BlockchainConfig::fakenet().chain_id());
+        let package = wallet.export_unsigned_send(&request(500)).unwrap();
+        let signatures = offline_keys(SEED).sign_package(&package, "main").unwrap();
+
+        // Each input's sighash commits to the chain
+        let elsewhere = UnsignedTransactionPackage::new(
+            crate::wallet::BlockchainConfig::default().chain_id(),
+            package.inputs.clone(),
+            package.outputs.clone(),
+            package.fee,
+            package.expiry_height,
+        );
+        assert_ne!(elsewhere.signing_hash(), package.signing_hash());
+        assert!(elsewhere
+            .sighashes
+            .iter()
+            .zip(&package.sighashes)
+            .all(|(a, b)| a != b));
+
+        wallet.set_chain_id(crate::wallet::BlockchainConfig::default().chain_id());
+        let broadcaster = RecordingBroadcaster::default();
+        let error = wallet
+            .broadcast_signed_package(&package, &signatures, &broadcaster, false, |_| {})
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(error.source, WalletError::ChainIdMismatch { .. }),
+            "{}",
+            error
+        );
+        assert!(error.broadcast_tx_id.is_none());
+        assert!(broadcaster.sent.borrow().is_empty());
+    }
+
+    #[test]
+    fn an_altered_package_is_refused_by_the_signer_and_the_importer() {
+        let mut wallet = online_wallet();
+        let package = wallet.export_unsigned_send(&request(500)).unwrap();
+        let signatures = offline_keys(SEED).sign_package(&package, "main").unwrap();
+
+        let mut altered = package.clone();
+        altered.outputs[0].amount += 100;
+        assert!(altered.verify_sighashes().is_err());
+        assert!(offline_keys(SEED).sign_package(&altered, "main").is_err());
+        assert!(SignedTransaction::from_package_and_signatures(&altered, &signatures).is_err());
+        assert!(UnsignedTransactionPackage::from_bytes(&altered.to_bytes()).is_err());
+    }
+
+    #[test]
+    fn signatures_that_do_not_match_the_package_are_rejected() {
+        let mut wallet = online_wallet();
+        let package = wallet.export_unsigned_send(&request(500)).unwrap();
+        let good = offline_keys(SEED).sign_package(&package, "main").unwrap();
+        let rejected = |signatures: &PackageSignatures| {
+            SignedTransaction::from_package_and_signatures(&package, signatures)
+                .unwrap_err()
+                .to_string()
+        };
+
+        let mut corrupted = good.clone();
+        corrupted.signatures[0].signature[0] ^= 1;
+        assert!(rejected(&corrupted).contains("does not match the package"));
+
+        let missing = PackageSignatures::default();
+        assert!(rejected(&missing).contains("needs exactly one signature"));
+
+        let mut doubled = good.clone();
+        doubled.signatures.push(good.signatures[0].clone());
+        assert!(rejected(&doubled).contains("needs exactly one signature"));
+
+        let mut stray = good.clone();
+        stray.signatures.push(InputSignature {
+            input_index: 9,
+            signature: good.signatures[0].signature,
+        });
+        assert!(rejected(&stray).contains("does not have"));
+    }
+
+    #[test]
+    fn a_key_that_owns_no_input_cannot_sign() {
+        let mut wallet = online_wallet();
+        let package = wallet.export_unsigned_send(&request(500)).unwrap();
+        let stranger = offline_keys([4; 32]);
+        assert!(stranger
+            .sign_package(&package, "main")
+            .unwrap_err()
+            .to_string()
+            .contains("does not own any input"));
+        assert!(matches!(
+            offline_keys(SEED).sign_package(&package, "other"),
+            Err(WalletError::KeyNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn releasing_a_package_unlocks_its_notes() {
+        let mut wallet = online_wallet();
+        let package = wallet.export_unsigned_send(&request(500)).unwrap();
+        assert_eq!(locked(&wallet), package.inputs.len());
+        wallet.release_unsigned(&package);
+        assert_eq!(locked(&wallet), 0);
+    }
+}
@@ -0,0 +1,724 @@
+//! Typed, validating construction of `NockchainNodeConfig`.
+//!
+//! Setters take typed values where the config stores strings or raw numbers,
+//! so most mistakes can't be expressed at all. The rest (peer strings that
+//! fail to parse, options that exclude each other) are collected and returned
+//! together from `build`.
+
+use semver::VersionReq;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::num::NonZeroU16;
+use std::path::PathBuf;
+use std::str::FromStr;
+use thiserror::Error;
+
+use crate::wallet::data_dir::DataDirLimits;
+use crate::wallet::log_buffer::{LogBufferConfig, LogLevels};
+use crate::wallet::network::NockchainNodeConfig;
+use crate::wallet::peers::PeerScoringConfig;
+
+/// A problem with a node configuration
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ConfigError {
+    #[error("Data directory must not be empty")]
+    EmptyDataDir,
+
+    #[error("{0} port must not be 0")]
+    ZeroPort(&'static str),
+
+    #[error("P2P and RPC ports must differ (both {0})")]
+    PortConflict(u16),
+
+    #[error("Invalid bind address: {0}")]
+    InvalidBindAddress(String),
+
+    #[error("Invalid multiaddr '{addr}': {reason}")]
+    InvalidMultiaddr { addr: String, reason: String },
+
+    #[error("Genesis leader mode is only allowed on fakenet")]
+    GenesisLeaderRequiresFakenet,
+
+    #[error("Mining is enabled but no mining pubkey or key is set")]
+    MiningRequiresPubkey,
+
+    #[error("Invalid SOCKS5 proxy '{0}': expected host:port")]
+    InvalidProxy(String),
+}
+
+/// A peer address such as `/ip4/1.2.3.4/tcp/4001/p2p/<peer id>`, checked when
+/// it is parsed
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Multiaddr(String);
+
+impl Multiaddr {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for Multiaddr {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: String| ConfigError::InvalidMultiaddr {
+            addr: s.to_string(),
+            reason,
+        };
+
+        let mut parts = s
+            .strip_prefix('/')
+            .ok_or_else(|| invalid("must start with '/'".to_string()))?
+            .split('/');
+        let mut has_host = false;
+        let mut has_transport = false;
+        while let Some(protocol) = parts.next() {
+            // Every protocol we accept except the QUIC markers takes a value
+            if matches!(protocol, "quic" | "quic-v1") {
+                continue;
+            }
+            let value = parts
+                .next()
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| invalid(format!("'{}' has no value", protocol)))?;
+            match protocol {
+                "ip4" => {
+                    value
+                        .parse::<Ipv4Addr>()
+                        .map_err(|_| invalid(format!("'{}' is not an IPv4 address", value)))?;
+                    has_host = true;
+                }
+                "ip6" => {
+                    value
+                        .parse::<Ipv6Addr>()
+                        .map_err(|_| invalid(format!("'{}' is not an IPv6 address", value)))?;
+                    has_host = true;
+                }
+                "dns" | "dns4" | "dns6" => has_host = true,
+                "tcp" | "udp" => {
+                    value
+                        .parse::<u16>()
+                        .map_err(|_| invalid(format!("'{}' is not a port", value)))?;
+                    has_transport = true;
+                }
+                "p2p" => {
+                    if !value.chars().all(|c| c.is_ascii_alphanumeric()) {
+                        return Err(invalid(format!("'{}' is not a peer id", value)));
+                    }
+                }
+                other => return Err(invalid(format!("unsupported protocol '{}'", other))),
+            }
+        }
+
+        if !has_host {
+            return Err(invalid("no ip4, ip6 or dns host".to_string()));
+        }
+        if !has_transport {
+            return Err(invalid("no tcp or udp port".to_string()));
+        }
+        Ok(Self(s.to_string()))
+    }
+}
+
+impl TryFrom<String> for Multiaddr {
+    type Error = ConfigError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Multiaddr> for String {
+    fn from(addr: Multiaddr) -> Self {
+        addr.0
+    }
+}
+
+impl fmt::Display for Multiaddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Builder for `NockchainNodeConfig`; see `NockchainNodeConfig::builder`
+#[derive(Debug, Clone)]
+pub struct NockchainNodeConfigBuilder {
+    config: NockchainNodeConfig,
+    /// Problems found by setters, reported together by `build`
+    errors: Vec<ConfigError>,
+}
+
+impl NockchainNodeConfigBuilder {
+    /// Start from an existing config, e.g. to change a running node's settings.
+    /// Its peers are re-parsed, so malformed ones are reported by `build`.
+    pub fn from_config(config: NockchainNodeConfig) -> Self {
+        let errors = config
+            .peers
+            .iter()
+            .filter_map(|peer| peer.parse::<Multiaddr>().err())
+            .collect();
+        Self { config, errors }
+    }
+
+    pub fn data_dir(mut self, data_dir: impl Into<PathBuf>) -> Self {
+        self.config.data_dir = data_dir.into();
+        self
+    }
+
+    /// Interface and port libp2p listens on
+    pub fn bind(mut self, addr: SocketAddr) -> Self {
+        self.config.bind_address = addr.ip().to_string();
+        match NonZeroU16::new(addr.port()) {
+            Some(port) => self.config.p2p_port = port.get(),
+            None => self.errors.push(ConfigError::ZeroPort("P2P")),
+        }
+        self
+    }
+
+    pub fn bind_ip(mut self, ip: IpAddr) -> Self {
+        self.config.bind_address = ip.to_string();
+        self
+    }
+
+    pub fn p2p_port(mut self, port: NonZeroU16) -> Self {
+        self.config.p2p_port = port.get();
+        self
+    }
+
+    pub fn rpc_port(mut self, port: NonZeroU16) -> Self {
+        self.config.rpc_port = port.get();
+        self
+    }
+
+    pub fn peer(mut self, peer: Multiaddr) -> Self {
+        self.config.peers.push(peer.into());
+        self
+    }
+
+    /// Replace the peer list
+    pub fn peers(mut self, peers: impl IntoIterator<Item = Multiaddr>) -> Self {
+        self.config.peers = peers.into_iter().map(String::from).collect();
+        self
+    }
+
+    /// Add a peer given as text, e.g. from a form; a malformed one is reported by `build`
+    pub fn peer_str(self, peer: &str) -> Self {
+        match peer.trim().parse() {
+            Ok(peer) => self.peer(peer),
+            Err(e) => self.error(e),
+        }
+    }
+
+    pub fn dns_seed(mut self, seed: impl Into<String>) -> Self {
+        self.config.dns_seeds.push(seed.into());
+        self
+    }
+
+    pub fn dns_seed_ttl_secs(mut self, secs: u64) -> Self {
+        self.config.dns_seed_ttl_secs = secs;
+        self
+    }
+
+    pub fn fakenet(mut self, fakenet: bool) -> Self {
+        self.config.fakenet = fakenet;
+        self
+    }
+
+    pub fn genesis_watcher(mut self, watcher: bool) -> Self {
+        self.config.genesis_watcher = watcher;
+        self
+    }
+
+    /// Only allowed together with `fakenet(true)`
+    pub fn genesis_leader(mut self, leader: bool) -> Self {
+        self.config.genesis_leader = leader;
+        self
+    }
+
+    pub fn genesis_hash(mut self, hash: [u8; 32]) -> Self {
+        self.config.genesis_hash = hash;
+        self
+    }
+
+    /// Requires `mining_pubkey` or `mining_key`
+    pub fn mining_enabled(mut self, enabled: bool) -> Self {
+        self.config.mining_enabled = enabled;
+        self
+    }
+
+    pub fn mining_pubkey(mut self, pubkey: impl Into<String>) -> Self {
+        self.config.mining_pubkey = Some(pubkey.into());
+        self
+    }
+
+    /// Wallet key that receives mining payouts
+    pub fn mining_key(mut self, key_name: impl Into<String>) -> Self {
+        self.config.mining_key = Some(key_name.into());
+        self
+    }
+
+    pub fn rotate_mining_address(mut self, rotate: bool) -> Self {
+        self.config.rotate_mining_address = rotate;
+        self
+    }
+
+    pub fn btc_node(
+        mut self,
+        url: impl Into<String>,
+        credentials: Option<(String, String)>,
+    ) -> Self {
+        self.config.btc_node_url = url.into();
+        let (username, password) = credentials.unzip();
+        self.config.btc_username = username;
+        self.config.btc_password = password;
+        self
+    }
+
+    pub fn max_established(mut self, incoming: Option<u32>, outgoing: Option<u32>) -> Self {
+        self.config.max_established_incoming = incoming;
+        self.config.max_established_outgoing = outgoing;
+        self
+    }
+
+    pub fn peer_scoring(mut self, scoring: PeerScoringConfig) -> Self {
+        self.config.peer_scoring = scoring;
+        self
+    }
+
+    pub fn data_dir_limits(mut self, limits: DataDirLimits) -> Self {
+        self.config.data_dir_limits = limits;
+        self
+    }
+
+    pub fn log_buffer(mut self, log_buffer: LogBufferConfig) -> Self {
+        self.config.log_buffer = log_buffer;
+        self
+    }
+
+    pub fn log_levels(mut self, levels: LogLevels) -> Self {
+        self.config.log_levels = levels;
+        self
+    }
+
+    pub fn debug_logging(mut self, enabled: bool) -> Self {
+        self.config.debug_logging = enabled;
+        self
+    }
+
+    pub fn persist_mempool(mut self, persist: bool) -> Self {
+        self.config.persist_mempool = persist;
+        self
+    }
+
+    pub fn start_timeout_secs(mut self, secs: u64) -> Self {
+        self.config.start_timeout_secs = secs;
+        self
+    }
+
+    /// Disconnect peers whose software version doesn't satisfy `required`
+    pub fn min_peer_version(mut self, required: VersionReq) -> Self {
+        self.config.min_peer_version = Some(required);
+        self
+    }
+
+    /// Also disconnect peers that advertise no readable version
+    pub fn strict_peer_version(mut self, strict: bool) -> Self {
+        self.config.strict_peer_version = strict;
+        self
+    }
+
+    /// Refuse wallet broadcasts while fewer than `peers` peers are connected
+    pub fn min_broadcast_peers(mut self, peers: u32) -> Self {
+        self.config.min_broadcast_peers = peers;
+        self
+    }
+
+    /// Look peer IPs up in the MaxMind DB file at `path`, after any added before
+    pub fn geoip_database(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.geoip_databases.push(path.into());
+        self
+    }
+
+    /// Send outbound peer and RPC connections through the SOCKS5 proxy at
+    /// `addr` (`host:port`), resolving hostnames through it if `proxy_dns`
+    pub fn socks5_proxy(mut self, addr: impl Into<String>, proxy_dns: bool) -> Self {
+        self.config.socks5_proxy = Some(addr.into());
+        self.config.proxy_dns = proxy_dns;
+        self
+    }
+
+    fn error(mut self, error: ConfigError) -> Self {
+        self.errors.push(error);
+        self
+    }
+
+    /// The config, or every problem found with it
+    pub fn build(self) -> Result<NockchainNodeConfig, Vec<ConfigError>> {
+        let mut errors = self.errors;
+        errors.extend(self.config.problems());
+        if errors.is_empty() {
+            Ok(self.config)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl NockchainNodeConfig {
+    /// Builder starting from the defaults
+    pub fn builder() -> NockchainNodeConfigBuilder {
+        NockchainNodeConfigBuilder::from_config(Self::default())
+    }
+
+    /// Builder starting from this config
+    pub fn to_builder(&self) -> NockchainNodeConfigBuilder {
+        NockchainNodeConfigBuilder::from_config(self.clone())
+    }
+
+    /// Every rule this config breaks, in the order `validate` checks them
+    pub fn problems(&self) -> Vec<ConfigError> {
+        let mut problems = Vec::new();
+        if self.data_dir.as_os_str().is_empty() {
+            problems.push(ConfigError::EmptyDataDir);
+        }
+        if self.p2p_port == 0 {
+            problems.push(ConfigError::ZeroPort("P2P"));
+        }
+        if self.rpc_port == 0 {
+            problems.push(ConfigError::ZeroPort("RPC"));
+        }
+        if self.p2p_port != 0 && self.p2p_port == self.rpc_port {
+            problems.push(ConfigError::PortConflict(self.p2p_port));
+        }
+        if self.bind_address.parse::<IpAddr>().is_err() {
+            problems.push(ConfigError::InvalidBindAddress(self.bind_address.clone()));
+        }
+        if self.genesis_leader && !self.fakenet {
+            problems.push(ConfigError::GenesisLeaderRequiresFakenet);
+        }
+        if self.mining_enabled && self.mining_pubkey.is_none() && self.mining_key.is_none() {
+            problems.push(ConfigError::MiningRequiresPubkey);
+        }
+        if let Some(proxy) = self
+            .socks5_proxy
+            .as_ref()
+            .filter(|proxy| !is_host_port(proxy))
+        {
+            problems.push(ConfigError::InvalidProxy(proxy.clone()));
+        }
+        problems
+    }
+}
+
+/// Whether `addr` looks like `host:port`, with IPv6 hosts in brackets
+fn is_host_port(addr: &str) -> bool {
+    addr.trim().rsplit_once(':').is_some_and(|(host, port)| {
+        !host.is_empty()
+            && (!host.contains(':') || host.starts_with('['))
+            && port.parse::<NonZeroU16>().is_ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::WalletError;
+
+    const PEER: &str = "/ip4/10.0.0.1/tcp/4001/p2p/12D3KooWPeer";
+
+    fn port(port: u16) -> NonZeroU16 {
+        NonZeroU16::new(port).unwrap()
+    }
+
+    fn multiaddr_reason(addr: &str) -> String {
+        match addr.parse::<Multiaddr>() {
+            Err(ConfigError::InvalidMultiaddr { addr: bad, reason }) => {
+                assert_eq!(bad, addr);
+                reason
+            }
+            other => panic!("expected an invalid multiaddr, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn the_defaults_build() {
+        let config = NockchainNodeConfig::builder().build().unwrap();
+        assert_eq!(config.p2p_port, 4001);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn well_formed_multiaddrs_parse() {
+        for addr in [
+            PEER,
+            "/ip4/127.0.0.1/tcp/4001",
+            "/ip6/::1/tcp/4001",
+            "/dns/seed.example.com/tcp/4001",
+            "/dns4/seed.example.com/udp/4001/quic-v1",
+            "/dns6/seed.example.com/udp/4001/quic/p2p/12D3KooWPeer",
+        ] {
+            let parsed: Multiaddr = addr.parse().unwrap();
+            assert_eq!(parsed.as_str(), addr);
+            assert_eq!(parsed.to_string(), addr);
+        }
+    }
+
+    #[test]
+    fn each_malformed_multiaddr_names_its_problem() {
+        let cases = [
+            ("ip4/1.2.3.4/tcp/4001", "must start with '/'"),
+            ("/ip4", "'ip4' has no value"),
+            ("/ip4//tcp/4001", "'ip4' has no value"),
+            (
+                "/ip4/1.2.3.999/tcp/4001",
+                "'1.2.3.999' is not an IPv4 address",
+            ),
+            ("/ip6/1.2.3.4/tcp/4001", "'1.2.3.4' is not an IPv6 address"),
+            ("/ip4/1.2.3.4/tcp/70000", "'70000' is not a port"),
+            (
+                "/ip4/1.2.3.4/tcp/4001/p2p/not-a-peer",
+                "'not-a-peer' is not a peer id",
+            ),
+            ("/ip4/1.2.3.4/sctp/4001", "unsupported protocol 'sctp'"),
+            ("/tcp/4001", "no ip4, ip6 or dns host"),
+            ("/ip4/1.2.3.4", "no tcp or udp port"),
+        ];
+        for (addr, reason) in cases {
+            assert_eq!(multiaddr_reason(addr), reason, "{}", addr);
+        }
+    }
+
+    #[test]
+    fn multiaddrs_are_checked_when_deserialized() {
+        let parsed: Multiaddr = serde_json::from_str(&format!("\"{}\"", PEER)).unwrap();
+        assert_eq!(
+            serde_json::to_string(&parsed).unwrap(),
+            format!("\"{}\"", PEER)
+        );
+        assert!(serde_json::from_str::<Multiaddr>("\"/ip4/1.2.3.4\"").is_err());
+    }
+
+    #[test]
+    fn an_empty_data_dir_is_rejected() {
+        let errors = NockchainNodeConfig::builder()
+            .data_dir("")
+            .build()
+            .unwrap_err();
+        assert_eq!(errors, vec![ConfigError::EmptyDataDir]);
+    }
+
+    #[test]
+    fn binding_port_zero_is_rejected() {
+        let errors = NockchainNodeConfig::builder()
+            .bind("127.0.0.1:0".parse().unwrap())
+            .build()
+            .unwrap_err();
+        assert_eq!(errors, vec![ConfigError::ZeroPort("P2P")]);
+    }
+
+    #[test]
+    fn zero_ports_in_an_existing_config_are_rejected() {
+        let config = NockchainNodeConfig {
+            p2p_port: 0,
+            rpc_port: 0,
+            ..Default::default()
+        };
+        let errors = config.to_builder().build().unwrap_err();
+        assert_eq!(
+            errors,
+            vec![ConfigError::ZeroPort("P2P"), ConfigError::ZeroPort("RPC")]
+        );
+    }
+
+    #[test]
+    fn p2p_and_rpc_ports_must_differ() {
+        let errors = NockchainNodeConfig::builder()
+            .p2p_port(port(9000))
+            .rpc_port(port(9000))
+            .build()
+            .unwrap_err();
+        assert_eq!(errors, vec![ConfigError::PortConflict(9000)]);
+    }
+
+    #[test]
+    fn bind_sets_the_interface_and_port() {
+        let config = NockchainNodeConfig::builder()
+            .bind("[::1]:4100".parse().unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(config.bind_address, "::1");
+        assert_eq!(config.p2p_port, 4100);
+    }
+
+    #[test]
+    fn an_unparseable_bind_address_is_rejected() {
+        let config = NockchainNodeConfig {
+            bind_address: "localhost".to_string(),
+            ..Default::default()
+        };
+        let errors = config.to_builder().build().unwrap_err();
+        assert_eq!(
+            errors,
+            vec![ConfigError::InvalidBindAddress("localhost".to_string())]
+        );
+    }
+
+    #[test]
+    fn a_malformed_peer_string_is_reported_by_build() {
+        let builder = NockchainNodeConfig::builder()
+            .peers([])
+            .peer_str(&format!("  {}  ", PEER))
+            .peer_str("/ip4/1.2.3.4");
+        let errors = builder.build().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            &errors[0],
+            ConfigError::InvalidMultiaddr { reason, .. } if reason == "no tcp or udp port"
+        ));
+
+        let config = NockchainNodeConfig::builder()
+            .peers([])
+            .peer_str(PEER)
+            .build()
+            .unwrap();
+        assert_eq!(config.peers, vec![PEER.to_string()]);
+    }
+
+    #[test]
+    fn malformed_peers_in_an_existing_config_are_reported() {
+        let config = NockchainNodeConfig {
+            peers: vec![PEER.to_string(), "garbage".to_string()],
+            ..Default::default()
+        };
+        let errors = config.to_builder().build().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            &errors[0],
+            ConfigError::InvalidMultiaddr { addr, .. } if addr == "garbage"
+        ));
+    }
+
+    #[test]
+    fn genesis_leader_requires_fakenet() {
+        let errors = NockchainNodeConfig::builder()
+            .genesis_leader(true)
+            .build()
+            .unwrap_err();
+        assert_eq!(errors, vec![ConfigError::GenesisLeaderRequiresFakenet]);
+
+        assert!(NockchainNodeConfig::builder()
+            .genesis_leader(true)
+            .fakenet(true)
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn mining_requires_a_pubkey_or_key() {
+        let errors = NockchainNodeConfig::builder()
+            .mining_enabled(true)
+            .build()
+            .unwrap_err();
+        assert_eq!(errors, vec![ConfigError::MiningRequiresPubkey]);
+
+        for builder in [
+            NockchainNodeConfig::builder().mining_pubkey("pubkey"),
+            NockchainNodeConfig::builder().mining_key("miner"),
+        ] {
+            assert!(builder.mining_enabled(true).build().is_ok());
+        }
+    }
+
+    #[test]
+    fn the_proxy_must_be_host_and_port() {
+        for proxy in ["127.0.0.1:9050", "tor.local:9050", "[::1]:9050"] {
+            assert!(
+                NockchainNodeConfig::builder()
+                    .socks5_proxy(proxy, true)
+                    .build()
+                    .is_ok(),
+                "{}",
+                proxy
+            );
+        }
+        for proxy in ["127.0.0.1", ":9050", "127.0.0.1:0", "::1:9050", "host:port"] {
+            let errors = NockchainNodeConfig::builder()
+                .socks5_proxy(proxy, false)
+                .build()
+                .unwrap_err();
+            assert_eq!(errors, vec![ConfigError::InvalidProxy(proxy.to_string())]);
+        }
+    }
+
+    #[test]
+    fn build_reports_every_problem_at_once() {
+        let errors = NockchainNodeConfig::builder()
+            .data_dir("")
+            .peer_str("nope")
+            .p2p_port(port(9000))
+            .rpc_port(port(9000))
+            .genesis_leader(true)
+            .mining_enabled(true)
+            .socks5_proxy("proxy", false)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                ConfigError::InvalidMultiaddr {
+                    addr: "nope".to_string(),
+                    reason: "must start with '/'".to_string(),
+                },
+                ConfigError::EmptyDataDir,
+                ConfigError::PortConflict(9000),
+                ConfigError::GenesisLeaderRequiresFakenet,
+                ConfigError::MiningRequiresPubkey,
+                ConfigError::InvalidProxy("proxy".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_proxy_must_be_host_and_port() {
+        for addr in ["127.0.0.1:9050", "[::1]:9050", "tor.local:9050"] {
+            let config = NockchainNodeConfig::builder()
+                .socks5_proxy(addr, true)
+                .build()
+                .unwrap();
+            assert_eq!(config.socks5_proxy.as_deref(), Some(addr));
+            assert!(config.proxy_dns);
+        }
+        for addr in [
+            "127.0.0.1",
+            "::1:9050",
+            "tor.local:0",
+            ":9050",
+            "tor.local:tor",
+        ] {
+            assert_eq!(
+                NockchainNodeConfig::builder()
+                    .socks5_proxy(addr, false)
+                    .build()
+                    .unwrap_err(),
+                vec![ConfigError::InvalidProxy(addr.to_string())]
+            );
+        }
+    }
+
+    #[test]
+    fn validate_reports_the_first_problem() {
+        let config = NockchainNodeConfig {
+            data_dir: PathBuf::new(),
+            genesis_leader: true,
+            ..Default::default()
+        };
+        match config.validate() {
+            Err(WalletError::Network(message)) => {
+                assert_eq!(message, ConfigError::EmptyDataDir.to_string())
+            }
+            other => panic!("expected a network error, got {:?}", other),
+        }
+    }
+}
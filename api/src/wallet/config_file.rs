@@ -0,0 +1,740 @@
+//! Layered loading of `NockchainNodeConfig`.
+//!
+//! Each top-level field is taken from the last layer that sets it:
+//!
+//! 1. the built-in defaults
+//! 2. `nockchain.toml` in the config dir
+//! 3. `NOCKCHAIN_*` environment variables
+//! 4. edits made while the app runs, kept in `node_config.runtime.json`
+//! 5. command-line overrides, which last for the session and are never saved
+//!
+//! The config dir is the `data_dir` override if given, else
+//! `NOCKCHAIN_DATA_DIR` if set, else the default data dir.
+//! Every field has an environment variable named after it:
+//!
+//! | Variable | Field | Format |
+//! |---|---|---|
+//! | `NOCKCHAIN_DATA_DIR` | `data_dir` | path |
+//! | `NOCKCHAIN_MINING_ENABLED` | `mining_enabled` | bool |
+//! | `NOCKCHAIN_MINING_PUBKEY` | `mining_pubkey` | text, empty or `none` to unset |
+//! | `NOCKCHAIN_MINING_KEY` | `mining_key` | text, empty or `none` to unset |
+//! | `NOCKCHAIN_ROTATE_MINING_ADDRESS` | `rotate_mining_address` | bool |
+//! | `NOCKCHAIN_P2P_PORT` | `p2p_port` | number |
+//! | `NOCKCHAIN_RPC_PORT` | `rpc_port` | number |
+//! | `NOCKCHAIN_PEERS` | `peers` | comma-separated multiaddrs |
+//! | `NOCKCHAIN_DNS_SEEDS` | `dns_seeds` | comma-separated hostnames |
+//! | `NOCKCHAIN_DNS_SEED_TTL_SECS` | `dns_seed_ttl_secs` | number |
+//! | `NOCKCHAIN_BIND_ADDRESS` | `bind_address` | IP address |
+//! | `NOCKCHAIN_GENESIS_WATCHER` | `genesis_watcher` | bool |
+//! | `NOCKCHAIN_GENESIS_LEADER` | `genesis_leader` | bool |
+//! | `NOCKCHAIN_FAKENET` | `fakenet` | bool |
+//! | `NOCKCHAIN_GENESIS_HASH` | `genesis_hash` | 64 hex characters |
+//! | `NOCKCHAIN_BTC_NODE_URL` | `btc_node_url` | text |
+//! | `NOCKCHAIN_BTC_USERNAME` | `btc_username` | text, empty or `none` to unset |
+//! | `NOCKCHAIN_BTC_PASSWORD` | `btc_password` | text, empty or `none` to unset |
+//! | `NOCKCHAIN_MAX_ESTABLISHED_INCOMING` | `max_established_incoming` | number, empty or `none` for no limit |
+//! | `NOCKCHAIN_MAX_ESTABLISHED_OUTGOING` | `max_established_outgoing` | number, empty or `none` for no limit |
+//! | `NOCKCHAIN_PEER_SCORING` | `peer_scoring` | JSON object |
+//! | `NOCKCHAIN_DATA_DIR_LIMITS` | `data_dir_limits` | JSON object |
+//! | `NOCKCHAIN_LOG_BUFFER` | `log_buffer` | JSON object |
+//! | `NOCKCHAIN_LOG_LEVELS` | `log_levels` | JSON object |
+//! | `NOCKCHAIN_DEBUG_LOGGING` | `debug_logging` | bool |
+//! | `NOCKCHAIN_PERSIST_MEMPOOL` | `persist_mempool` | bool |
+//! | `NOCKCHAIN_START_TIMEOUT_SECS` | `start_timeout_secs` | number |
+//! | `NOCKCHAIN_MIN_PEER_VERSION` | `min_peer_version` | version requirement such as `>=0.2.0`, empty or `none` to unset |
+//! | `NOCKCHAIN_STRICT_PEER_VERSION` | `strict_peer_version` | bool |
+//! | `NOCKCHAIN_MIN_BROADCAST_PEERS` | `min_broadcast_peers` | number |
+//! | `NOCKCHAIN_GEOIP_DATABASES` | `geoip_databases` | comma-separated paths |
+//! | `NOCKCHAIN_SOCKS5_PROXY` | `socks5_proxy` | `host:port`, empty or `none` to unset |
+//! | `NOCKCHAIN_PROXY_DNS` | `proxy_dns` | bool |
+//!
+//! Bools accept `1`/`0`, `true`/`false`, `yes`/`no` and `on`/`off`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::{debug, warn};
+
+use crate::wallet::network::NockchainNodeConfig;
+
+/// Node config file read from the config dir
+pub const NODE_CONFIG_FILE: &str = "nockchain.toml";
+
+/// Edits made at runtime, written next to `NODE_CONFIG_FILE`
+pub const RUNTIME_CONFIG_FILE: &str = "node_config.runtime.json";
+
+/// Prefix of the environment variables that override config fields
+pub const ENV_PREFIX: &str = "NOCKCHAIN_";
+
+/// A config file or override that could not be used
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ConfigFileError {
+    #[error("Failed to read {path}: {message}")]
+    Read { path: PathBuf, message: String },
+
+    /// The message includes the line, column and field as reported by the parser
+    #[error("Invalid {path}: {message}")]
+    Parse { path: PathBuf, message: String },
+
+    #[error("Unknown field '{field}' in {path} at line {line}")]
+    UnknownField {
+        path: PathBuf,
+        field: String,
+        line: usize,
+    },
+
+    #[error("Invalid {var}: {message}")]
+    Env { var: String, message: String },
+
+    #[error("Invalid command-line value for {field}: {message}")]
+    Override { field: String, message: String },
+
+    #[error("Failed to write {path}: {message}")]
+    Write { path: PathBuf, message: String },
+}
+
+/// Layer a config field's value came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigSource {
+    Default,
+    File,
+    Env,
+    Runtime,
+    CommandLine,
+}
+
+impl ConfigSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConfigSource::Default => "default",
+            ConfigSource::File => NODE_CONFIG_FILE,
+            ConfigSource::Env => "environment",
+            ConfigSource::Runtime => "runtime edit",
+            ConfigSource::CommandLine => "command line",
+        }
+    }
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// Source of each top-level config field, keyed by field name
+pub type ConfigSources = BTreeMap<String, ConfigSource>;
+
+/// How an environment variable's text becomes a field value
+#[derive(Debug, Clone, Copy)]
+enum EnvFormat {
+    Bool,
+    Number,
+    Text,
+    OptionalText,
+    OptionalNumber,
+    List,
+    Hex32,
+    Json,
+}
+
+/// Every config field and how its environment variable is parsed; keep in
+/// step with `NockchainNodeConfig` and the table in the module docs
+const ENV_FIELDS: &[(&str, EnvFormat)] = &[
+    ("data_dir", EnvFormat::Text),
+    ("mining_enabled", EnvFormat::Bool),
+    ("mining_pubkey", EnvFormat::OptionalText),
+    ("mining_key", EnvFormat::OptionalText),
+    ("rotate_mining_address", EnvFormat::Bool),
+    ("p2p_port", EnvFormat::Number),
+    ("rpc_port", EnvFormat::Number),
+    ("peers", EnvFormat::List),
+    ("dns_seeds", EnvFormat::List),
+    ("dns_seed_ttl_secs", EnvFormat::Number),
+    ("bind_address", EnvFormat::Text),
+    ("genesis_watcher", EnvFormat::Bool),
+    ("genesis_leader", EnvFormat::Bool),
+    ("fakenet", EnvFormat::Bool),
+    ("genesis_hash", EnvFormat::Hex32),
+    ("btc_node_url", EnvFormat::Text),
+    ("btc_username", EnvFormat::OptionalText),
+    ("btc_password", EnvFormat::OptionalText),
+    ("max_established_incoming", EnvFormat::OptionalNumber),
+    ("max_established_outgoing", EnvFormat::OptionalNumber),
+    ("peer_scoring", EnvFormat::Json),
+    ("data_dir_limits", EnvFormat::Json),
+    ("log_buffer", EnvFormat::Json),
+    ("log_levels", EnvFormat::Json),
+    ("debug_logging", EnvFormat::Bool),
+    ("persist_mempool", EnvFormat::Bool),
+    ("start_timeout_secs", EnvFormat::Number),
+    ("min_peer_version", EnvFormat::OptionalText),
+    ("strict_peer_version", EnvFormat::Bool),
+    ("min_broadcast_peers", EnvFormat::Number),
+    ("geoip_databases", EnvFormat::List),
+    ("socks5_proxy", EnvFormat::OptionalText),
+    ("proxy_dns", EnvFormat::Bool),
+];
+
+/// Name of the environment variable that overrides `field`
+pub fn env_var_name(field: &str) -> String {
+    format!("{}{}", ENV_PREFIX, field.to_uppercase())
+}
+
+fn parse_env_value(format: EnvFormat, raw: &str) -> Result<Value, String> {
+    let raw = raw.trim();
+    let unset = raw.is_empty() || raw.eq_ignore_ascii_case("none");
+    let number = |raw: &str| {
+        raw.parse::<u64>()
+            .map(Value::from)
+            .map_err(|_| format!("'{}' is not a number", raw))
+    };
+    match format {
+        EnvFormat::Bool => match raw.to_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Ok(Value::Bool(true)),
+            "0" | "false" | "no" | "off" => Ok(Value::Bool(false)),
+            _ => Err(format!("'{}' is not a bool", raw)),
+        },
+        EnvFormat::Number => number(raw),
+        EnvFormat::Text => Ok(Value::from(raw)),
+        EnvFormat::OptionalText if unset => Ok(Value::Null),
+        EnvFormat::OptionalText => Ok(Value::from(raw)),
+        EnvFormat::OptionalNumber if unset => Ok(Value::Null),
+        EnvFormat::OptionalNumber => number(raw),
+        EnvFormat::List => Ok(Value::from(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .collect::<Vec<_>>(),
+        )),
+        EnvFormat::Hex32 => {
+            let bytes = hex::decode(raw).map_err(|e| format!("not hex: {}", e))?;
+            if bytes.len() != 32 {
+                return Err(format!("expected 32 bytes, got {}", bytes.len()));
+            }
+            Ok(Value::from(bytes))
+        }
+        EnvFormat::Json => serde_json::from_str(raw).map_err(|e| format!("not JSON: {}", e)),
+    }
+}
+
+/// Top-level fields of `config` as a JSON object
+fn config_fields(config: &NockchainNodeConfig) -> Map<String, Value> {
+    match serde_json::to_value(config) {
+        Ok(Value::Object(fields)) => fields,
+        _ => Map::new(),
+    }
+}
+
+/// 1-based line on which `field` is assigned or opens a table in `text`
+fn field_line(text: &str, field: &str) -> usize {
+    text.lines()
+        .position(|line| {
+            let line = line.trim_start();
+            line.strip_prefix(field)
+                .is_some_and(|rest| rest.trim_start().starts_with('='))
+                || line
+                    .strip_prefix('[')
+                    .and_then(|rest| rest.trim_start_matches('[').strip_prefix(field))
+                    .is_some_and(|rest| rest.starts_with(['.', ']']))
+        })
+        .map_or(0, |index| index + 1)
+}
+
+/// Parse a TOML config file, returning the config and the fields it sets
+fn read_toml(path: &Path) -> Result<(NockchainNodeConfig, Vec<String>), ConfigFileError> {
+    let text = std::fs::read_to_string(path).map_err(|e| ConfigFileError::Read {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    })?;
+    let parse_error = |e: toml::de::Error| ConfigFileError::Parse {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    };
+
+    let table: toml::Table = toml::from_str(&text).map_err(parse_error)?;
+    let known = config_fields(&NockchainNodeConfig::default());
+    if let Some(field) = table.keys().find(|key| !known.contains_key(*key)) {
+        return Err(ConfigFileError::UnknownField {
+            path: path.to_path_buf(),
+            line: field_line(&text, field),
+            field: field.clone(),
+        });
+    }
+
+    let config = toml::from_str(&text).map_err(parse_error)?;
+    Ok((config, table.keys().cloned().collect()))
+}
+
+impl NockchainNodeConfig {
+    /// Read a config from a TOML file. Fields it leaves out keep their
+    /// defaults; unknown fields and bad values are errors naming the line.
+    pub fn from_toml(path: impl AsRef<Path>) -> Result<Self, ConfigFileError> {
+        read_toml(path.as_ref()).map(|(config, _)| config)
+    }
+}
+
+/// Where a layered config was loaded from, and the runtime edits layered on top.
+/// Hold on to it to persist later edits with `record_runtime_edit`.
+#[derive(Debug, Clone)]
+pub struct ConfigLayers {
+    config_dir: PathBuf,
+    runtime: Map<String, Value>,
+    sources: ConfigSources,
+}
+
+impl ConfigLayers {
+    /// Load the config for the process environment; see the module docs
+    pub fn load() -> Result<(NockchainNodeConfig, Self), ConfigFileError> {
+        Self::load_from(std::env::vars())
+    }
+
+    /// Load the config using `env` in place of the process environment
+    pub fn load_from(
+        env: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<(NockchainNodeConfig, Self), ConfigFileError> {
+        Self::load_with_overrides(env, Map::new())
+    }
+
+    /// Load the config using `env` in place of the process environment, with
+    /// `overrides` from the command line layered over everything else. They
+    /// are checked like environment variables, and never written to
+    /// `RUNTIME_CONFIG_FILE`.
+    pub fn load_with_overrides(
+        env: impl IntoIterator<Item = (String, String)>,
+        overrides: Map<String, Value>,
+    ) -> Result<(NockchainNodeConfig, Self), ConfigFileError> {
+        let env: HashMap<String, String> = env
+            .into_iter()
+            .filter(|(var, _)| var.starts_with(ENV_PREFIX))
+            .collect();
+        let config_dir = overrides
+            .get("data_dir")
+            .and_then(Value::as_str)
+            .or_else(|| env.get(&env_var_name("data_dir")).map(String::as_str))
+            .filter(|dir| !dir.trim().is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| NockchainNodeConfig::default().data_dir);
+
+        let mut fields = config_fields(&NockchainNodeConfig::default());
+        let mut sources: ConfigSources = fields
+            .keys()
+            .map(|field| (field.clone(), ConfigSource::Default))
+            .collect();
+
+        let file_path = config_dir.join(NODE_CONFIG_FILE);
+        if file_path.exists() {
+            let (file_config, set) = read_toml(&file_path)?;
+            let mut file_fields = config_fields(&file_config);
+            for field in set {
+                if let Some(value) = file_fields.remove(&field) {
+                    fields.insert(field.clone(), value);
+                    sources.insert(field, ConfigSource::File);
+                }
+            }
+            debug!("Loaded node config from {}", file_path.display());
+        }
+
+        for (field, format) in ENV_FIELDS {
+            let var = env_var_name(field);
+            let Some(raw) = env.get(&var) else {
+                continue;
+            };
+            let env_error = |message: String| ConfigFileError::Env {
+                var: var.clone(),
+                message,
+            };
+            let value = parse_env_value(*format, raw).map_err(env_error)?;
+            // Check each override on its own so a bad one is reported by name
+            let mut candidate = fields.clone();
+            candidate.insert(field.to_string(), value.clone());
+            serde_json::from_value::<NockchainNodeConfig>(Value::Object(candidate))
+                .map_err(|e| env_error(e.to_string()))?;
+            fields.insert(field.to_string(), value);
+            sources.insert(field.to_string(), ConfigSource::Env);
+        }
+
+        let runtime_path = config_dir.join(RUNTIME_CONFIG_FILE);
+        let mut runtime = Map::new();
+        if runtime_path.exists() {
+            let read_error = |message: String| ConfigFileError::Read {
+                path: runtime_path.clone(),
+                message,
+            };
+            let text =
+                std::fs::read_to_string(&runtime_path).map_err(|e| read_error(e.to_string()))?;
+            runtime = serde_json::from_str(&text).map_err(|e| read_error(e.to_string()))?;
+            runtime.retain(|field, value| {
+                if !fields.contains_key(field) {
+                    warn!("Ignoring unknown runtime config field '{}'", field);
+                    return false;
+                }
+                let mut candidate = fields.clone();
+                candidate.insert(field.clone(), value.clone());
+                let valid =
+                    serde_json::from_value::<NockchainNodeConfig>(Value::Object(candidate)).is_ok();
+                if !valid {
+                    warn!("Ignoring invalid runtime config field '{}'", field);
+                }
+                valid
+            });
+            for (field, value) in &runtime {
+                fields.insert(field.clone(), value.clone());
+                sources.insert(field.clone(), ConfigSource::Runtime);
+            }
+        }
+
+        for (field, value) in overrides {
+            let override_error = |message: String| ConfigFileError::Override {
+                field: field.clone(),
+                message,
+            };
+            if !fields.contains_key(&field) {
+                return Err(override_error("not a config field".to_string()));
+            }
+            let mut candidate = fields.clone();
+            candidate.insert(field.clone(), value.clone());
+            serde_json::from_value::<NockchainNodeConfig>(Value::Object(candidate))
+                .map_err(|e| override_error(e.to_string()))?;
+            fields.insert(field.clone(), value);
+            sources.insert(field, ConfigSource::CommandLine);
+        }
+
+        let config =
+            serde_json::from_value(Value::Object(fields)).map_err(|e| ConfigFileError::Parse {
+                path: config_dir.clone(),
+                message: e.to_string(),
+            })?;
+        Ok((
+            config,
+            Self {
+                config_dir,
+                runtime,
+                sources,
+            },
+        ))
+    }
+
+    pub fn config_dir(&self) -> &Path {
+        &self.config_dir
+    }
+
+    /// Which layer each field's current value came from
+    pub fn sources(&self) -> &ConfigSources {
+        &self.sources
+    }
+
+    /// Persist the fields that differ between `before` and `after` as runtime
+    /// edits, so they survive a restart and win over the file and environment.
+    /// Command-line overrides are only saved once edited to another value.
+    pub fn record_runtime_edit(
+        &mut self,
+        before: &NockchainNodeConfig,
+        after: &NockchainNodeConfig,
+    ) -> Result<(), ConfigFileError> {
+        let before = config_fields(before);
+        let changed: Vec<(String, Value)> = config_fields(after)
+            .into_iter()
+            .filter(|(field, value)| before.get(field) != Some(value))
+            .collect();
+        if changed.is_empty() {
+            return Ok(());
+        }
+
+        for (field, value) in changed {
+            self.sources.insert(field.clone(), ConfigSource::Runtime);
+            self.runtime.insert(field, value);
+        }
+
+        let path = self.config_dir.join(RUNTIME_CONFIG_FILE);
+        let write_error = |message: String| ConfigFileError::Write {
+            path: path.clone(),
+            message,
+        };
+        let contents =
+            serde_json::to_vec_pretty(&self.runtime).map_err(|e| write_error(e.to_string()))?;
+        let tmp_path = path.with_extension("json.tmp");
+        std::fs::create_dir_all(&self.config_dir)
+            .and_then(|_| std::fs::write(&tmp_path, contents))
+            .and_then(|_| std::fs::rename(&tmp_path, &path))
+            .map_err(|e| write_error(e.to_string()))?;
+        debug!("Saved {} runtime config fields", self.runtime.len());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use serde_json::json;
+
+    /// Environment pointing the config dir at `dir`, plus `vars`
+    fn env(dir: &TempDir, vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        let mut env = vec![(env_var_name("data_dir"), dir.path().display().to_string())];
+        env.extend(
+            vars.iter()
+                .map(|(var, value)| (var.to_string(), value.to_string())),
+        );
+        env
+    }
+
+    fn write_toml(dir: &TempDir, text: &str) {
+        std::fs::write(dir.join(NODE_CONFIG_FILE), text).unwrap();
+    }
+
+    #[test]
+    fn a_toml_file_sets_only_the_fields_it_names() {
+        let dir = TempDir::new("config-file");
+        write_toml(&dir, "p2p_port = 5001\npeers = []\n");
+        let config = NockchainNodeConfig::from_toml(dir.join(NODE_CONFIG_FILE)).unwrap();
+        assert_eq!(config.p2p_port, 5001);
+        assert!(config.peers.is_empty());
+        assert_eq!(config.rpc_port, NockchainNodeConfig::default().rpc_port);
+    }
+
+    #[test]
+    fn a_bad_value_reports_its_line_and_field() {
+        let dir = TempDir::new("config-file");
+        write_toml(&dir, "fakenet = true\np2p_port = \"high\"\n");
+        match NockchainNodeConfig::from_toml(dir.join(NODE_CONFIG_FILE)) {
+            Err(ConfigFileError::Parse { message, .. }) => {
+                assert!(message.contains("line 2"), "{}", message);
+                assert!(message.contains("p2p_port"), "{}", message);
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn an_unknown_field_reports_its_line() {
+        let dir = TempDir::new("config-file");
+        write_toml(&dir, "fakenet = true\n\n[mystery]\nvalue = 1\n");
+        assert_eq!(
+            NockchainNodeConfig::from_toml(dir.join(NODE_CONFIG_FILE)).unwrap_err(),
+            ConfigFileError::UnknownField {
+                path: dir.join(NODE_CONFIG_FILE),
+                field: "mystery".to_string(),
+                line: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn a_missing_file_is_a_read_error() {
+        let dir = TempDir::new("config-file");
+        assert!(matches!(
+            NockchainNodeConfig::from_toml(dir.join(NODE_CONFIG_FILE)),
+            Err(ConfigFileError::Read { .. })
+        ));
+    }
+
+    #[test]
+    fn each_layer_overrides_the_one_before() {
+        let dir = TempDir::new("config-file");
+        write_toml(
+            &dir,
+            "p2p_port = 5001\nrpc_port = 5002\nbind_address = \"127.0.0.1\"\n",
+        );
+        let runtime = json!({ "bind_address": "10.0.0.1", "fakenet": true });
+        std::fs::write(dir.join(RUNTIME_CONFIG_FILE), runtime.to_string()).unwrap();
+        let env = env(
+            &dir,
+            &[
+                ("NOCKCHAIN_RPC_PORT", "6002"),
+                ("NOCKCHAIN_BIND_ADDRESS", "0.0.0.0"),
+                ("NOCKCHAIN_FAKENET", "false"),
+            ],
+        );
+        let mut overrides = Map::new();
+        overrides.insert("fakenet".to_string(), Value::Bool(false));
+
+        let (config, layers) = ConfigLayers::load_with_overrides(env, overrides).unwrap();
+        assert_eq!(config.p2p_port, 5001);
+        assert_eq!(config.rpc_port, 6002);
+        assert_eq!(config.bind_address, "10.0.0.1");
+        assert!(!config.fakenet);
+        assert_eq!(config.data_dir, dir.path());
+
+        let sources = layers.sources();
+        assert_eq!(sources["genesis_watcher"], ConfigSource::Default);
+        assert_eq!(sources["p2p_port"], ConfigSource::File);
+        assert_eq!(sources["rpc_port"], ConfigSource::Env);
+        assert_eq!(sources["data_dir"], ConfigSource::Env);
+        assert_eq!(sources["bind_address"], ConfigSource::Runtime);
+        assert_eq!(sources["fakenet"], ConfigSource::CommandLine);
+    }
+
+    #[test]
+    fn environment_bools_accept_every_spelling() {
+        for (raw, expected) in [
+            ("1", true),
+            ("true", true),
+            ("YES", true),
+            ("on", true),
+            ("0", false),
+            ("False", false),
+            ("no", false),
+            ("off", false),
+        ] {
+            assert_eq!(
+                parse_env_value(EnvFormat::Bool, raw),
+                Ok(Value::Bool(expected))
+            );
+        }
+        assert_eq!(
+            parse_env_value(EnvFormat::Bool, "maybe"),
+            Err("'maybe' is not a bool".to_string())
+        );
+    }
+
+    #[test]
+    fn environment_lists_are_comma_separated() {
+        let dir = TempDir::new("config-file");
+        let env = env(
+            &dir,
+            &[(
+                "NOCKCHAIN_PEERS",
+                " /ip4/10.0.0.1/tcp/4001 ,, /dns/seed.example.com/tcp/4001 ",
+            )],
+        );
+        let (config, _) = ConfigLayers::load_from(env).unwrap();
+        assert_eq!(
+            config.peers,
+            vec!["/ip4/10.0.0.1/tcp/4001", "/dns/seed.example.com/tcp/4001"]
+        );
+        assert_eq!(parse_env_value(EnvFormat::List, ""), Ok(json!([])));
+    }
+
+    #[test]
+    fn the_peer_version_policy_loads_from_the_file_and_environment() {
+        let dir = TempDir::new("config-file");
+        write_toml(&dir, "min_peer_version = \">=0.2.0\"\n");
+        let (config, _) = ConfigLayers::load_from(env(&dir, &[])).unwrap();
+        assert_eq!(
+            config.min_peer_version,
+            Some(semver::VersionReq::parse(">=0.2.0").unwrap())
+        );
+        assert!(!config.strict_peer_version);
+
+        let env = env(
+            &dir,
+            &[
+                ("NOCKCHAIN_MIN_PEER_VERSION", "none"),
+                ("NOCKCHAIN_STRICT_PEER_VERSION", "on"),
+            ],
+        );
+        let (config, _) = ConfigLayers::load_from(env).unwrap();
+        assert_eq!(config.min_peer_version, None);
+        assert!(config.strict_peer_version);
+    }
+
+    #[test]
+    fn environment_optional_values_can_be_unset() {
+        let dir = TempDir::new("config-file");
+        write_toml(
+            &dir,
+            "mining_pubkey = \"pubkey\"\nmax_established_incoming = 8\n",
+        );
+        let env = env(
+            &dir,
+            &[
+                ("NOCKCHAIN_MINING_PUBKEY", "none"),
+                ("NOCKCHAIN_MAX_ESTABLISHED_INCOMING", ""),
+                ("NOCKCHAIN_MAX_ESTABLISHED_OUTGOING", "12"),
+                ("NOCKCHAIN_BTC_USERNAME", "satoshi"),
+            ],
+        );
+        let (config, _) = ConfigLayers::load_from(env).unwrap();
+        assert_eq!(config.mining_pubkey, None);
+        assert_eq!(config.max_established_incoming, None);
+        assert_eq!(config.max_established_outgoing, Some(12));
+        assert_eq!(config.btc_username.as_deref(), Some("satoshi"));
+    }
+
+    #[test]
+    fn a_bad_environment_value_is_reported_by_variable() {
+        let dir = TempDir::new("config-file");
+        for (var, value) in [
+            ("NOCKCHAIN_P2P_PORT", "lots"),
+            ("NOCKCHAIN_P2P_PORT", "70000"),
+            ("NOCKCHAIN_GENESIS_HASH", "abcd"),
+            ("NOCKCHAIN_LOG_LEVELS", "{"),
+        ] {
+            match ConfigLayers::load_from(env(&dir, &[(var, value)])) {
+                Err(ConfigFileError::Env { var: reported, .. }) => assert_eq!(reported, var),
+                other => panic!("expected {} to be rejected, got {:?}", var, other),
+            }
+        }
+    }
+
+    #[test]
+    fn unrelated_environment_variables_are_ignored() {
+        let dir = TempDir::new("config-file");
+        let env = env(&dir, &[("P2P_PORT", "1"), ("NOCKCHAIN_UNKNOWN", "1")]);
+        let (config, _) = ConfigLayers::load_from(env).unwrap();
+        assert_eq!(config.p2p_port, NockchainNodeConfig::default().p2p_port);
+    }
+
+    #[test]
+    fn runtime_edits_are_saved_and_win_on_the_next_load() {
+        let dir = TempDir::new("config-file");
+        write_toml(&dir, "p2p_port = 5001\n");
+        let (before, mut layers) = ConfigLayers::load_from(env(&dir, &[])).unwrap();
+        let after = NockchainNodeConfig {
+            p2p_port: 7001,
+            ..before.clone()
+        };
+        layers.record_runtime_edit(&before, &after).unwrap();
+        assert_eq!(layers.sources()["p2p_port"], ConfigSource::Runtime);
+
+        let (reloaded, layers) = ConfigLayers::load_from(env(&dir, &[])).unwrap();
+        assert_eq!(reloaded.p2p_port, 7001);
+        assert_eq!(layers.sources()["p2p_port"], ConfigSource::Runtime);
+    }
+
+    #[test]
+    fn invalid_runtime_edits_are_dropped() {
+        let dir = TempDir::new("config-file");
+        let runtime = json!({ "p2p_port": "high", "gone": 1, "rpc_port": 9100 });
+        std::fs::write(dir.join(RUNTIME_CONFIG_FILE), runtime.to_string()).unwrap();
+        let (config, layers) = ConfigLayers::load_from(env(&dir, &[])).unwrap();
+        assert_eq!(config.p2p_port, NockchainNodeConfig::default().p2p_port);
+        assert_eq!(config.rpc_port, 9100);
+        assert!(!layers.sources().contains_key("gone"));
+    }
+
+    #[test]
+    fn an_override_for_an_unknown_field_is_rejected() {
+        let dir = TempDir::new("config-file");
+        let mut overrides = Map::new();
+        overrides.insert("turbo".to_string(), Value::Bool(true));
+        assert_eq!(
+            ConfigLayers::load_with_overrides(env(&dir, &[]), overrides).unwrap_err(),
+            ConfigFileError::Override {
+                field: "turbo".to_string(),
+                message: "not a config field".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn every_config_field_has_an_environment_variable() {
+        let fields = config_fields(&NockchainNodeConfig::default());
+        let mapped: Vec<&str> = ENV_FIELDS.iter().map(|(field, _)| *field).collect();
+        assert_eq!(
+            fields.keys().map(String::as_str).collect::<Vec<_>>().len(),
+            mapped.len()
+        );
+        for field in fields.keys() {
+            assert!(
+                mapped.contains(&field.as_str()),
+                "{} has no variable",
+                field
+            );
+        }
+    }
+}
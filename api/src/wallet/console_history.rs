@@ -0,0 +1,107 @@
+//! The last lines of the node console, kept so the next launch can show
+//! what the previous session was doing.
+//!
+//! Saved synchronously with plain file writes rather than through
+//! `StorageManager`: the history belongs to the app, not to a wallet, and is
+//! written while the window closes, when no async runtime can be relied on.
+
+use std::borrow::Cow;
+use std::path::Path;
+
+use crate::wallet::log_buffer::sanitize_log_message;
+use crate::wallet::network::LogEntry;
+use crate::wallet::{WalletError, WalletResult};
+
+/// Console history, saved next to the profiles file
+pub const CONSOLE_HISTORY_FILE: &str = "console_history.json";
+
+/// Lines of the console kept from one session to the next
+pub const CONSOLE_HISTORY_LINES: usize = 200;
+
+/// Lines saved by the previous session in `dir`, oldest first; empty if none
+pub fn load_console_history(dir: &Path) -> WalletResult<Vec<LogEntry>> {
+    let path = dir.join(CONSOLE_HISTORY_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| WalletError::Storage(format!("Failed to read console history: {}", e)))?;
+    let mut logs: Vec<LogEntry> = serde_json::from_str(&text)
+        .map_err(|e| WalletError::Serialization(format!("Invalid console history: {}", e)))?;
+    // Lines saved before messages were sanitized on the way in
+    for log in &mut logs {
+        if let Cow::Owned(message) = sanitize_log_message(&log.message) {
+            log.message = message;
+        }
+    }
+    Ok(logs)
+}
+
+/// Save the last `CONSOLE_HISTORY_LINES` of `logs` to `dir`
+pub fn save_console_history(dir: &Path, logs: &[LogEntry]) -> WalletResult<()> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| WalletError::Storage(format!("Failed to create config dir: {}", e)))?;
+    let kept = &logs[logs.len().saturating_sub(CONSOLE_HISTORY_LINES)..];
+    let path = dir.join(CONSOLE_HISTORY_FILE);
+    let tmp_path = path.with_extension("json.tmp");
+    let contents =
+        serde_json::to_string(kept).map_err(|e| WalletError::Serialization(e.to_string()))?;
+    std::fs::write(&tmp_path, contents)
+        .and_then(|_| std::fs::rename(&tmp_path, &path))
+        .map_err(|e| WalletError::Storage(format!("Failed to save console history: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fixture_time, TempDir};
+    use crate::wallet::network::{LogLevel, LogSource};
+
+    fn line(message: &str) -> LogEntry {
+        LogEntry {
+            timestamp: fixture_time(0),
+            level: LogLevel::Info,
+            source: LogSource::Node,
+            message: message.to_string(),
+            operation_id: None,
+            repeat: None,
+        }
+    }
+
+    #[test]
+    fn no_saved_history_loads_as_empty() {
+        let dir = TempDir::new("console-history");
+        assert!(load_console_history(dir.path()).unwrap().is_empty());
+
+        std::fs::write(dir.join(CONSOLE_HISTORY_FILE), "[{").unwrap();
+        assert!(matches!(
+            load_console_history(dir.path()),
+            Err(WalletError::Serialization(_))
+        ));
+    }
+
+    #[test]
+    fn only_the_last_lines_are_kept_for_the_next_launch() {
+        let dir = TempDir::new("console-history");
+        let logs: Vec<LogEntry> = (0..250).map(|i| line(&format!("line {}", i))).collect();
+        save_console_history(dir.path(), &logs).unwrap();
+
+        let loaded = load_console_history(dir.path()).unwrap();
+        assert_eq!(loaded.len(), CONSOLE_HISTORY_LINES);
+        assert_eq!(loaded[0].message, "line 50");
+        assert_eq!(loaded.last().unwrap().message, "line 249");
+
+        save_console_history(dir.path(), &logs[..3]).unwrap();
+        assert_eq!(load_console_history(dir.path()).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn lines_saved_unsanitized_are_cleaned_on_load() {
+        let dir = TempDir::new("console-history");
+        let raw = "\u{1b}[31mred\u{1b}[0m";
+        save_console_history(dir.path(), &[line(raw)]).unwrap();
+        let loaded = load_console_history(dir.path()).unwrap();
+        assert_eq!(loaded[0].message, sanitize_log_message(raw));
+        assert_ne!(loaded[0].message, raw);
+    }
+}
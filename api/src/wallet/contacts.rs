@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+
+use crate::wallet::storage::StorageManager;
+use crate::wallet::{Address, WalletError, WalletResult};
+
+/// Saved contacts, stored through `StorageManager`
+pub const CONTACTS_FILE: &str = "contacts.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Contact {
+    pub name: String,
+    pub address: Address,
+}
+
+/// Named addresses the user sends to, kept in insertion order
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContactBook {
+    contacts: Vec<Contact>,
+}
+
+impl ContactBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the saved contacts, or an empty book if none were saved
+    pub async fn load(storage: &StorageManager) -> WalletResult<Self> {
+        if !storage.exists(CONTACTS_FILE) {
+            return Ok(Self::new());
+        }
+        storage.load(CONTACTS_FILE).await
+    }
+
+    pub async fn save(&self, storage: &StorageManager) -> WalletResult<()> {
+        storage.save(CONTACTS_FILE, self).await
+    }
+
+    pub fn contacts(&self) -> &[Contact] {
+        &self.contacts
+    }
+
+    /// Add a contact; names are unique, ignoring case
+    pub fn add(&mut self, name: &str, address: Address) -> WalletResult<()> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(WalletError::Contact(
+                "Contact name must not be empty".to_string(),
+            ));
+        }
+        if self.get(name).is_some() {
+            return Err(WalletError::Contact(format!(
+                "A contact named {} already exists",
+                name
+            )));
+        }
+        self.contacts.push(Contact {
+            name: name.to_string(),
+            address,
+        });
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Contact> {
+        let index = self
+            .contacts
+            .iter()
+            .position(|contact| contact.name.eq_ignore_ascii_case(name))?;
+        Some(self.contacts.remove(index))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Contact> {
+        self.contacts
+            .iter()
+            .find(|contact| contact.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Contacts whose name contains `query`, ignoring case; names starting
+    /// with it come first
+    pub fn matching(&self, query: &str) -> Vec<&Contact> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let mut matches: Vec<&Contact> = self
+            .contacts
+            .iter()
+            .filter(|contact| contact.name.to_lowercase().contains(&query))
+            .collect();
+        matches.sort_by_key(|contact| !contact.name.to_lowercase().starts_with(&query));
+        matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_address, TempDir};
+
+    fn book(names: &[&str]) -> ContactBook {
+        let mut book = ContactBook::new();
+        for name in names {
+            book.add(name, fake_address()).unwrap();
+        }
+        book
+    }
+
+    fn names(contacts: Vec<&Contact>) -> Vec<&str> {
+        contacts
+            .into_iter()
+            .map(|contact| contact.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn names_starting_with_the_query_are_suggested_first() {
+        let book = book(&["Bob", "Sally", "Alice", "Al"]);
+        assert_eq!(names(book.matching("al")), vec!["Alice", "Al", "Sally"]);
+        assert_eq!(names(book.matching(" BO ")), vec!["Bob"]);
+        assert!(book.matching("").is_empty());
+        assert!(book.matching("zed").is_empty());
+    }
+
+    #[test]
+    fn names_are_unique_ignoring_case_and_never_empty() {
+        let mut book = book(&["Alice"]);
+        assert!(book.add("alice", fake_address()).is_err());
+        assert!(book.add("  ", fake_address()).is_err());
+        assert_eq!(book.remove("ALICE").unwrap().name, "Alice");
+        assert!(book.contacts().is_empty());
+    }
+
+    #[tokio::test]
+    async fn the_book_survives_a_save_and_load() {
+        let dir = TempDir::new("contacts");
+        let storage = StorageManager::new(dir.path().to_path_buf()).unwrap();
+        assert_eq!(
+            ContactBook::load(&storage).await.unwrap(),
+            ContactBook::new()
+        );
+
+        let saved = book(&["Alice", "Bob"]);
+        saved.save(&storage).await.unwrap();
+        assert_eq!(ContactBook::load(&storage).await.unwrap(), saved);
+    }
+}
@@ -0,0 +1,680 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::wallet::chain::Blockchain;
+use crate::wallet::encoding::Encode;
+use crate::wallet::uptime::UPTIME_FILE;
+use crate::wallet::{WalletError, WalletResult};
+
+/// Fraction of the soft cap at which a usage warning is logged
+pub const SOFT_CAP_WARN_FRACTION: f64 = 0.8;
+
+/// How long a measured usage is reused before the data dir is walked again
+const USAGE_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Name of the throwaway file used to verify the data dir is writable
+const WRITE_PROBE_FILE: &str = ".write_probe";
+
+/// Marker file telling the wallet to rebuild balances on its next sync
+pub const RESCAN_FLAG_FILE: &str = "wallet.rescan";
+
+/// Chain state: kernel checkpoints, persistent memory arena, blocks, headers and UTXO set
+const CHAIN_ENTRIES: &[&str] = &[
+    "pma",
+    "nockchain_a.jam",
+    "nockchain_b.jam",
+    "blocks",
+    "headers",
+    "utxo",
+    "mempool.dat",
+];
+
+/// Peer book and libp2p identity state
+const PEER_ENTRIES: &[&str] = &["peers", "peers.json", "known_peers.json", "p2p"];
+
+/// Entries never removed by a purge, even if they end up inside the data dir
+const PRESERVED_ENTRIES: &[&str] = &["keys", "wallet", RESCAN_FLAG_FILE, UPTIME_FILE];
+
+/// Subdirectories that make up an empty data dir
+const SKELETON_DIRS: &[&str] = &["pma"];
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+/// Source of filesystem capacity figures, replaceable for tests
+pub trait DiskCapacity: Send + Sync {
+    /// Bytes available to this process on the filesystem holding `path`
+    fn available_space(&self, path: &Path) -> io::Result<u64>;
+    /// Total size of the filesystem holding `path`
+    fn total_space(&self, path: &Path) -> io::Result<u64>;
+}
+
+/// Capacity figures from the operating system
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemDisk;
+
+impl DiskCapacity for SystemDisk {
+    fn available_space(&self, path: &Path) -> io::Result<u64> {
+        fs2::available_space(path)
+    }
+
+    fn total_space(&self, path: &Path) -> io::Result<u64> {
+        fs2::total_space(path)
+    }
+}
+
+/// Disk space limits enforced on the node data directory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataDirLimits {
+    /// Refuse to start when less than this many bytes are free
+    pub min_free_bytes: u64,
+    /// Expected upper bound on data dir size; a warning is logged at 80%
+    pub soft_cap_bytes: Option<u64>,
+}
+
+impl Default for DataDirLimits {
+    fn default() -> Self {
+        Self {
+            min_free_bytes: 4 * GIB,
+            soft_cap_bytes: Some(200 * GIB),
+        }
+    }
+}
+
+/// Snapshot of data directory size and filesystem capacity
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataDirUsage {
+    pub used_bytes: u64,
+    pub available_bytes: u64,
+    pub total_bytes: u64,
+    pub soft_cap_bytes: Option<u64>,
+}
+
+impl DataDirUsage {
+    /// Fraction of the soft cap currently used, if a cap is configured
+    pub fn soft_cap_fraction(&self) -> Option<f64> {
+        self.soft_cap_bytes
+            .filter(|cap| *cap > 0)
+            .map(|cap| self.used_bytes as f64 / cap as f64)
+    }
+
+    pub fn is_near_soft_cap(&self) -> bool {
+        self.soft_cap_fraction()
+            .is_some_and(|fraction| fraction >= SOFT_CAP_WARN_FRACTION)
+    }
+}
+
+/// Bytes used by each kind of data, for seeing what fills the disk
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageBreakdown {
+    /// Block bodies, on disk and in the local chain
+    pub blocks: u64,
+    /// Block headers, on disk and in the local chain
+    pub headers: u64,
+    pub utxo_set: u64,
+    /// Wallet files and keys
+    pub wallet: u64,
+    pub logs: u64,
+}
+
+impl StorageBreakdown {
+    /// Measure the node data dir `dir`, the wallet files in `wallet_dir`
+    /// (else those in `dir`) and the blocks `chain` holds
+    pub fn measure(dir: &Path, wallet_dir: Option<&Path>, chain: &Blockchain) -> Self {
+        let (mut blocks, mut headers) = (0, 0);
+        for block in chain.blocks() {
+            let header = block.header.to_bytes().len() as u64;
+            headers += header;
+            blocks += (block.to_bytes().len() as u64).saturating_sub(header);
+        }
+        let wallet = match wallet_dir {
+            Some(wallet_dir) => dir_size(wallet_dir),
+            None => entry_size(&dir.join("wallet")) + entry_size(&dir.join("keys")),
+        };
+        Self {
+            blocks: blocks + entry_size(&dir.join("blocks")),
+            headers: headers + entry_size(&dir.join("headers")),
+            utxo_set: entry_size(&dir.join("utxo")),
+            wallet,
+            logs: entry_size(&dir.join("logs")),
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.blocks + self.headers + self.utxo_set + self.wallet + self.logs
+    }
+
+    /// Label and size of each kind, in display order
+    pub fn parts(&self) -> [(&'static str, u64); 5] {
+        [
+            ("Blocks", self.blocks),
+            ("Headers", self.headers),
+            ("UTXO set", self.utxo_set),
+            ("Wallet", self.wallet),
+            ("Logs", self.logs),
+        ]
+    }
+}
+
+/// Which part of the node data directory to delete
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PurgeScope {
+    /// Blocks, headers, UTXO set and kernel state
+    ChainOnly,
+    /// Known peers and network identity
+    PeersOnly,
+    /// Everything in the data dir except wallet keys
+    Everything,
+}
+
+impl PurgeScope {
+    /// Whether wallet balances must be rebuilt after this purge
+    pub fn requires_rescan(&self) -> bool {
+        matches!(self, PurgeScope::ChainOnly | PurgeScope::Everything)
+    }
+}
+
+/// Delete the entries covered by `scope`, recreate the skeleton, and return bytes freed
+pub fn purge_data_dir(dir: &Path, scope: PurgeScope) -> WalletResult<u64> {
+    let targets: Vec<PathBuf> = match scope {
+        PurgeScope::ChainOnly => CHAIN_ENTRIES.iter().map(|name| dir.join(name)).collect(),
+        PurgeScope::PeersOnly => PEER_ENTRIES.iter().map(|name| dir.join(name)).collect(),
+        PurgeScope::Everything => match std::fs::read_dir(dir) {
+            Ok(read_dir) => read_dir
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| {
+                    !PRESERVED_ENTRIES
+                        .iter()
+                        .any(|name| entry.file_name() == *name)
+                })
+                .map(|entry| entry.path())
+                .collect(),
+            Err(_) => Vec::new(),
+        },
+    };
+
+    let mut freed = 0;
+    for target in targets {
+        let Ok(meta) = std::fs::symlink_metadata(&target) else {
+            continue;
+        };
+        let (size, result) = if meta.is_dir() {
+            (dir_size(&target), std::fs::remove_dir_all(&target))
+        } else {
+            (meta.len(), std::fs::remove_file(&target))
+        };
+        result.map_err(|e| {
+            WalletError::Storage(format!("Failed to remove {}: {}", target.display(), e))
+        })?;
+        freed += size;
+    }
+
+    for name in SKELETON_DIRS {
+        std::fs::create_dir_all(dir.join(name)).map_err(|e| {
+            WalletError::Storage(format!("Failed to recreate data directory: {}", e))
+        })?;
+    }
+
+    if scope.requires_rescan() {
+        std::fs::write(dir.join(RESCAN_FLAG_FILE), Utc::now().to_rfc3339()).map_err(|e| {
+            WalletError::Storage(format!("Failed to set wallet rescan flag: {}", e))
+        })?;
+    }
+
+    Ok(freed)
+}
+
+/// Whether a purge has left the wallet needing a rescan
+pub fn rescan_required(dir: &Path) -> bool {
+    dir.join(RESCAN_FLAG_FILE).exists()
+}
+
+/// Clear the rescan marker once the wallet has rebuilt its balances
+pub fn clear_rescan_flag(dir: &Path) -> WalletResult<()> {
+    match std::fs::remove_file(dir.join(RESCAN_FLAG_FILE)) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(WalletError::Storage(format!(
+            "Failed to clear wallet rescan flag: {}",
+            e
+        ))),
+    }
+}
+
+/// Total size in bytes of all files below `path`
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(read_dir) = std::fs::read_dir(path) else {
+        return 0;
+    };
+
+    read_dir
+        .filter_map(|entry| entry.ok())
+        .map(|entry| match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => dir_size(&entry.path()),
+            Ok(file_type) if file_type.is_file() => {
+                entry.metadata().map(|meta| meta.len()).unwrap_or(0)
+            }
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Size of the file at `path`, or of everything below it if it's a directory
+fn entry_size(path: &Path) -> u64 {
+    match std::fs::metadata(path) {
+        Ok(meta) if meta.is_file() => meta.len(),
+        Ok(_) => dir_size(path),
+        Err(_) => 0,
+    }
+}
+
+/// Verify the directory accepts writes by creating and removing a probe file
+pub fn probe_writable(dir: &Path) -> WalletResult<()> {
+    let probe = dir.join(WRITE_PROBE_FILE);
+    std::fs::write(&probe, b"probe").map_err(|e| {
+        WalletError::Storage(format!(
+            "Data directory {} is not writable: {}",
+            dir.display(),
+            e
+        ))
+    })?;
+    let _ = std::fs::remove_file(&probe);
+    Ok(())
+}
+
+/// Measure the data directory without enforcing any limits
+pub fn measure_data_dir(
+    dir: &Path,
+    limits: &DataDirLimits,
+    disk: &dyn DiskCapacity,
+) -> WalletResult<DataDirUsage> {
+    let capacity_err =
+        |e: io::Error| WalletError::Storage(format!("Failed to query disk capacity: {}", e));
+
+    Ok(DataDirUsage {
+        used_bytes: dir_size(dir),
+        available_bytes: disk.available_space(dir).map_err(capacity_err)?,
+        total_bytes: disk.total_space(dir).map_err(capacity_err)?,
+        soft_cap_bytes: limits.soft_cap_bytes,
+    })
+}
+
+/// Pre-start health check: writable, measurable, and enough free space
+pub fn check_data_dir(
+    dir: &Path,
+    limits: &DataDirLimits,
+    disk: &dyn DiskCapacity,
+) -> WalletResult<DataDirUsage> {
+    probe_writable(dir)?;
+    let usage = measure_data_dir(dir, limits, disk)?;
+
+    if usage.available_bytes < limits.min_free_bytes {
+        return Err(WalletError::Storage(format!(
+            "Insufficient disk space in {}: {} MiB free, at least {} MiB required",
+            dir.display(),
+            usage.available_bytes / (1024 * 1024),
+            limits.min_free_bytes / (1024 * 1024)
+        )));
+    }
+
+    Ok(usage)
+}
+
+/// Periodic data dir measurement with caching and soft-cap crossing detection
+pub struct DataDirMonitor {
+    disk: Arc<dyn DiskCapacity>,
+    last: Mutex<Option<(Instant, DataDirUsage)>>,
+    near_cap: AtomicBool,
+}
+
+impl DataDirMonitor {
+    pub fn new(disk: Arc<dyn DiskCapacity>) -> Self {
+        Self {
+            disk,
+            last: Mutex::new(None),
+            near_cap: AtomicBool::new(false),
+        }
+    }
+
+    pub fn disk(&self) -> &dyn DiskCapacity {
+        self.disk.as_ref()
+    }
+
+    /// Record a fresh measurement, e.g. from the pre-start check
+    pub fn store(&self, usage: DataDirUsage) {
+        if let Ok(mut last) = self.last.lock() {
+            *last = Some((Instant::now(), usage));
+        }
+    }
+
+    /// Most recent usage, re-measuring once the cached value is stale
+    pub fn usage(&self, dir: &Path, limits: &DataDirLimits) -> Option<DataDirUsage> {
+        let mut last = self.last.lock().ok()?;
+        if let Some((measured_at, usage)) = last.as_ref() {
+            if measured_at.elapsed() < USAGE_REFRESH_INTERVAL {
+                return Some(usage.clone());
+            }
+        }
+
+        let usage = measure_data_dir(dir, limits, self.disk.as_ref()).ok()?;
+        *last = Some((Instant::now(), usage.clone()));
+        Some(usage)
+    }
+
+    /// True only on the transition into the near-soft-cap state
+    pub fn crossed_soft_cap(&self, usage: &DataDirUsage) -> bool {
+        let near = usage.is_near_soft_cap();
+        let was_near = self.near_cap.swap(near, Ordering::Relaxed);
+        near && !was_near
+    }
+
+    pub fn reset(&self) {
+        if let Ok(mut last) = self.last.lock() {
+            *last = None;
+        }
+        self.near_cap.store(false, Ordering::Relaxed);
+    }
+}
+
+impl Default for DataDirMonitor {
+    fn default() -> Self {
+        Self::new(Arc::new(SystemDisk))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_address, fake_coinbase, fakenet_chain_config, FakeDisk, TempDir};
+    use crate::wallet::checkpoints::Checkpoint;
+    use crate::wallet::BlockchainConfig;
+
+    const MIB: u64 = 1024 * 1024;
+
+    fn disk(available: u64) -> FakeDisk {
+        FakeDisk::new(available, 100 * GIB)
+    }
+
+    fn limits(min_free_bytes: u64, soft_cap_bytes: Option<u64>) -> DataDirLimits {
+        DataDirLimits {
+            min_free_bytes,
+            soft_cap_bytes,
+        }
+    }
+
+    #[test]
+    fn healthy_dir_is_measured_and_left_clean() {
+        let dir = TempDir::new("data-dir");
+        std::fs::create_dir_all(dir.join("blocks")).unwrap();
+        std::fs::write(dir.join("blocks/0.dat"), vec![0; 3_000]).unwrap();
+        std::fs::write(dir.join("mempool.dat"), vec![0; 500]).unwrap();
+
+        let usage = check_data_dir(dir.path(), &limits(GIB, Some(GIB)), &disk(5 * GIB)).unwrap();
+        assert_eq!(
+            usage,
+            DataDirUsage {
+                used_bytes: 3_500,
+                available_bytes: 5 * GIB,
+                total_bytes: 100 * GIB,
+                soft_cap_bytes: Some(GIB),
+            }
+        );
+        assert!(!dir.join(WRITE_PROBE_FILE).exists());
+    }
+
+    #[test]
+    fn too_little_free_space_refuses_to_start() {
+        let dir = TempDir::new("data-dir");
+        let err = check_data_dir(dir.path(), &limits(4 * GIB, None), &disk(4 * GIB - 1))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Insufficient disk space"), "{}", err);
+        assert!(
+            err.contains("4095 MiB free, at least 4096 MiB required"),
+            "{}",
+            err
+        );
+        // Exactly the minimum is enough
+        check_data_dir(dir.path(), &limits(4 * GIB, None), &disk(4 * GIB)).unwrap();
+    }
+
+    #[test]
+    fn unwritable_dir_and_capacity_errors_are_reported() {
+        let dir = TempDir::new("data-dir");
+        let file = dir.join("not-a-dir");
+        std::fs::write(&file, "x").unwrap();
+        let err = check_data_dir(&file, &limits(0, None), &disk(GIB)).unwrap_err();
+        assert!(err.to_string().contains("is not writable"), "{}", err);
+
+        let err = check_data_dir(dir.path(), &limits(0, None), &FakeDisk::failing()).unwrap_err();
+        assert!(
+            err.to_string().contains("Failed to query disk capacity"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn soft_cap_fraction_needs_a_positive_cap() {
+        let usage = |used_bytes, soft_cap_bytes| DataDirUsage {
+            used_bytes,
+            available_bytes: 0,
+            total_bytes: 0,
+            soft_cap_bytes,
+        };
+        assert_eq!(usage(80 * MIB, None).soft_cap_fraction(), None);
+        assert_eq!(usage(80 * MIB, Some(0)).soft_cap_fraction(), None);
+        assert_eq!(
+            usage(50 * MIB, Some(100 * MIB)).soft_cap_fraction(),
+            Some(0.5)
+        );
+        assert!(!usage(79 * MIB, Some(100 * MIB)).is_near_soft_cap());
+        assert!(usage(80 * MIB, Some(100 * MIB)).is_near_soft_cap());
+    }
+
+    #[test]
+    fn monitor_warns_once_per_crossing_and_caches_measurements() {
+        let dir = TempDir::new("data-dir");
+        std::fs::write(dir.join("chain"), vec![0; 900]).unwrap();
+        let monitor = DataDirMonitor::new(Arc::new(disk(GIB)));
+        let limits = limits(0, Some(1_000));
+
+        let usage = monitor.usage(dir.path(), &limits).unwrap();
+        assert!(monitor.crossed_soft_cap(&usage));
+        assert!(!monitor.crossed_soft_cap(&usage));
+
+        // Growth within the refresh interval isn't seen yet
+        std::fs::write(dir.join("more"), vec![0; 900]).unwrap();
+        assert_eq!(monitor.usage(dir.path(), &limits).unwrap().used_bytes, 900);
+
+        let below = DataDirUsage {
+            used_bytes: 100,
+            ..usage.clone()
+        };
+        assert!(!monitor.crossed_soft_cap(&below));
+        assert!(monitor.crossed_soft_cap(&usage));
+
+        monitor.reset();
+        assert_eq!(
+            monitor.usage(dir.path(), &limits).unwrap().used_bytes,
+            1_800
+        );
+        assert!(monitor.crossed_soft_cap(&usage));
+    }
+
+    /// Lay out a populated data dir; every file holds 100 bytes
+    fn populated_data_dir() -> TempDir {
+        let dir = TempDir::new("purge");
+        for file in [
+            "pma/0.snap",
+            "nockchain_a.jam",
+            "blocks/0.dat",
+            "headers/0.dat",
+            "utxo/set.db",
+            "mempool.dat",
+            "peers.json",
+            "p2p/identity",
+            "logs/node.log",
+            "keys/wallet.key",
+            "wallet/notes.json",
+        ] {
+            let path = dir.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, vec![0; 100]).unwrap();
+        }
+        dir
+    }
+
+    fn exists(dir: &TempDir, files: &[&str]) -> Vec<bool> {
+        files.iter().map(|file| dir.join(file).exists()).collect()
+    }
+
+    #[test]
+    fn chain_purge_removes_chain_state_and_flags_a_rescan() {
+        let dir = populated_data_dir();
+        assert_eq!(
+            purge_data_dir(dir.path(), PurgeScope::ChainOnly).unwrap(),
+            600
+        );
+
+        let chain = [
+            "pma/0.snap",
+            "nockchain_a.jam",
+            "blocks",
+            "headers",
+            "utxo",
+            "mempool.dat",
+        ];
+        assert_eq!(exists(&dir, &chain), vec![false; 6]);
+        let kept = [
+            "peers.json",
+            "p2p/identity",
+            "logs/node.log",
+            "keys/wallet.key",
+            "wallet/notes.json",
+        ];
+        assert_eq!(exists(&dir, &kept), vec![true; 5]);
+        assert!(dir.join("pma").is_dir());
+        assert!(rescan_required(dir.path()));
+
+        clear_rescan_flag(dir.path()).unwrap();
+        assert!(!rescan_required(dir.path()));
+        clear_rescan_flag(dir.path()).unwrap();
+    }
+
+    #[test]
+    fn peer_purge_keeps_the_chain_and_needs_no_rescan() {
+        let dir = populated_data_dir();
+        assert_eq!(
+            purge_data_dir(dir.path(), PurgeScope::PeersOnly).unwrap(),
+            200
+        );
+
+        assert_eq!(exists(&dir, &["peers.json", "p2p"]), vec![false, false]);
+        assert_eq!(
+            exists(&dir, &["blocks/0.dat", "utxo/set.db"]),
+            vec![true, true]
+        );
+        assert!(!rescan_required(dir.path()));
+    }
+
+    #[test]
+    fn full_purge_spares_only_wallet_keys() {
+        let dir = populated_data_dir();
+        assert_eq!(
+            purge_data_dir(dir.path(), PurgeScope::Everything).unwrap(),
+            900
+        );
+
+        let mut left: Vec<String> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        assert_eq!(left, vec!["keys", "pma", "wallet", RESCAN_FLAG_FILE]);
+        assert_eq!(
+            std::fs::read(dir.join("keys/wallet.key")).unwrap(),
+            vec![0; 100]
+        );
+        assert_eq!(dir_size(&dir.join("pma")), 0);
+
+        // Purging an already empty dir frees nothing
+        assert_eq!(
+            purge_data_dir(dir.path(), PurgeScope::Everything).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn the_breakdown_sorts_files_by_kind_and_counts_the_chain() {
+        let dir = TempDir::new("breakdown");
+        for (path, size) in [
+            ("blocks/0.dat", 3_000),
+            ("headers", 200),
+            ("utxo/set.dat", 700),
+            ("wallet/notes.json", 400),
+            ("keys", 50),
+            ("logs/node.log", 100),
+            ("mempool.dat", 9_000),
+        ] {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, vec![0; size]).unwrap();
+        }
+
+        let mut chain = Blockchain::new(BlockchainConfig {
+            checkpoints: vec![Checkpoint::new(u64::MAX, [0; 32])],
+            skip_pow_below_checkpoint: true,
+            max_future_block_time: u64::MAX / 2,
+            ..fakenet_chain_config()
+        });
+        let breakdown = StorageBreakdown::measure(dir.path(), None, &chain);
+        assert_eq!(
+            breakdown,
+            StorageBreakdown {
+                blocks: 3_000,
+                headers: 200,
+                utxo_set: 700,
+                wallet: 450,
+                logs: 100,
+            }
+        );
+        assert_eq!(breakdown.total(), 4_450);
+        assert_eq!(breakdown.parts()[2], ("UTXO set", 700));
+
+        let wallet_dir = TempDir::new("breakdown-wallet");
+        std::fs::write(wallet_dir.join("wallet.json"), vec![0; 1_234]).unwrap();
+        assert_eq!(
+            StorageBreakdown::measure(dir.path(), Some(wallet_dir.path()), &chain).wallet,
+            1_234
+        );
+
+        for height in 0..4 {
+            let transactions = match height {
+                0 => Vec::new(),
+                _ => vec![fake_coinbase(&fake_address(), 50)],
+            };
+            chain
+                .add_block(chain.new_block(transactions, 0x1f7f_ffff))
+                .unwrap();
+        }
+        let header_bytes: u64 = chain
+            .blocks()
+            .iter()
+            .map(|block| block.header.to_bytes().len() as u64)
+            .sum();
+        let grown = StorageBreakdown::measure(dir.path(), None, &chain);
+        assert_eq!(grown.headers, 200 + header_bytes);
+        assert!(grown.blocks > 3_000);
+
+        // Compaction frees body bytes only
+        let freed = chain.compact(3).freed_bytes;
+        let compacted = StorageBreakdown::measure(dir.path(), None, &chain);
+        assert_eq!(compacted.blocks, grown.blocks - freed);
+        assert_eq!(compacted.headers, grown.headers);
+    }
+}
@@ -0,0 +1,476 @@
+//! Field-by-field breakdown of raw transactions, for debugging.
+//!
+//! Unlike `SignedTransaction::from_bytes`, decoding never fails outright:
+//! whatever could be read before a problem is kept, and the problem is
+//! reported with the offset it was found at. Unknown format versions are read
+//! with the current layout and flagged, so a transaction from a newer build
+//! still shows what it can.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::wallet::encoding::{
+    self, Reader, EXPIRING_TRANSACTION_FORMAT_VERSION, TRANSACTION_FORMAT_VERSION,
+};
+use crate::wallet::{Address, WalletError, WalletResult};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodedInput {
+    /// Transaction and output index of the note being spent
+    pub txid: String,
+    pub index: u32,
+    pub amount: u64,
+    /// Byte offset the input starts at
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodedOutput {
+    pub amount: u64,
+    pub address: String,
+    /// Whether `address` parses as a Nockchain address
+    pub address_valid: bool,
+    /// Byte offset the output starts at
+    pub offset: usize,
+}
+
+/// Where and why decoding stopped
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodeError {
+    /// Byte offset of the field that failed. For hex input that isn't valid
+    /// hex, the character offset in the text instead.
+    pub offset: usize,
+    /// Field being read, e.g. "output 1 address"
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at offset {}: {}",
+            self.field, self.offset, self.message
+        )
+    }
+}
+
+/// Everything that could be read from a raw transaction
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodedTransaction {
+    /// Size in bytes, after hex decoding
+    pub size: usize,
+    pub version: Option<u8>,
+    /// Hex id of the chain the signature binds to; version 1 has none
+    pub chain_id: Option<String>,
+    /// Counts as declared by the encoding, which may exceed what was decoded
+    pub input_count: Option<u32>,
+    pub inputs: Vec<DecodedInput>,
+    pub output_count: Option<u32>,
+    pub outputs: Vec<DecodedOutput>,
+    pub fee: Option<u64>,
+    /// Last block height it may be mined at; only version 3 has one
+    pub expiry_height: Option<u64>,
+    /// Hex signature bytes
+    pub signature: Option<String>,
+    /// Transaction id, set only when the whole input decoded
+    pub txid: Option<String>,
+    /// Oddities that didn't stop decoding
+    pub warnings: Vec<String>,
+    pub error: Option<DecodeError>,
+}
+
+impl DecodedTransaction {
+    pub fn is_complete(&self) -> bool {
+        self.error.is_none() && self.txid.is_some()
+    }
+
+    /// Sum of the input amounts, once every declared input was decoded
+    pub fn input_total(&self) -> Option<u64> {
+        let complete = self.input_count == Some(self.inputs.len() as u32);
+        complete.then(|| self.inputs.iter().map(|input| input.amount).sum())
+    }
+
+    /// Sum of the output amounts, once every declared output was decoded
+    pub fn output_total(&self) -> Option<u64> {
+        let complete = self.output_count == Some(self.outputs.len() as u32);
+        complete.then(|| self.outputs.iter().map(|output| output.amount).sum())
+    }
+}
+
+/// Decode a raw transaction given as hex text (optionally `0x`-prefixed) or
+/// as the raw bytes themselves
+pub fn decode_transaction(input: impl AsRef<[u8]>) -> DecodedTransaction {
+    let mut decoded = DecodedTransaction::default();
+    let bytes = match raw_bytes(input.as_ref()) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            decoded.error = Some(e);
+            return decoded;
+        }
+    };
+    decoded.size = bytes.len();
+
+    let mut reader = Reader::new(&bytes);
+    match read_transaction(&mut reader, &mut decoded) {
+        Ok(()) => {
+            decoded.txid = Some(hex::encode(encoding::sha256(&bytes)));
+            check_amounts(&mut decoded);
+        }
+        Err(e) => decoded.error = Some(e),
+    }
+    decoded
+}
+
+/// Hex text is anything printable; real transaction bytes start with a
+/// version byte, which never is
+fn raw_bytes(input: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let text = std::str::from_utf8(input)
+        .ok()
+        .filter(|text| !text.is_empty())
+        .filter(|text| {
+            text.chars()
+                .all(|c| c.is_ascii_graphic() || c.is_whitespace())
+        });
+    let Some(text) = text else {
+        if input.is_empty() {
+            return Err(DecodeError {
+                offset: 0,
+                field: "input".to_string(),
+                message: "empty input".to_string(),
+            });
+        }
+        return Ok(input.to_vec());
+    };
+
+    let trimmed = text.trim();
+    let (start, hex_text) = match trimmed
+        .strip_prefix("0x")
+        .or_else(|| trimmed.strip_prefix("0X"))
+    {
+        Some(rest) => (text.len() - text.trim_start().len() + 2, rest),
+        None => (text.len() - text.trim_start().len(), trimmed),
+    };
+    let error = |offset: usize, message: String| DecodeError {
+        offset: start + offset,
+        field: "hex".to_string(),
+        message,
+    };
+    if hex_text.is_empty() {
+        return Err(error(0, "empty input".to_string()));
+    }
+    hex::decode(hex_text).map_err(|e| match e {
+        hex::FromHexError::InvalidHexCharacter { c, index } => {
+            error(index, format!("invalid hex character {:?}", c))
+        }
+        hex::FromHexError::OddLength => {
+            error(hex_text.len(), "odd number of hex digits".to_string())
+        }
+        e => error(0, e.to_string()),
+    })
+}
+
+/// Read one field, tagging a failure with the field name and its start offset
+fn field<'a, T>(
+    reader: &mut Reader<'a>,
+    name: impl FnOnce() -> String,
+    read: impl FnOnce(&mut Reader<'a>) -> WalletResult<T>,
+) -> Result<T, DecodeError> {
+    let offset = reader.position();
+    read(reader).map_err(|e| DecodeError {
+        offset,
+        field: name(),
+        message: match e {
+            WalletError::Serialization(message) => message,
+            e => e.to_string(),
+        },
+    })
+}
+
+/// A list count, rejected when it can't fit in what is left of the input
+fn count(reader: &mut Reader<'_>, what: &str) -> Result<u32, DecodeError> {
+    let offset = reader.position();
+    let count = field(reader, || format!("{} count", what), Reader::u32)?;
+    if count as usize > reader.remaining() {
+        return Err(DecodeError {
+            offset,
+            field: format!("{} count", what),
+            message: format!(
+                "{} {}s declared but only {} bytes left",
+                count,
+                what,
+                reader.remaining()
+            ),
+        });
+    }
+    Ok(count)
+}
+
+fn read_transaction(
+    reader: &mut Reader<'_>,
+    decoded: &mut DecodedTransaction,
+) -> Result<(), DecodeError> {
+    let version = field(reader, || "version".to_string(), Reader::u8)?;
+    decoded.version = Some(version);
+    if version == 1 {
+        decoded.warnings.push(
+            "Version 1 predates chain ids; no node accepts it any more, so sign it again"
+                .to_string(),
+        );
+    } else {
+        if version != TRANSACTION_FORMAT_VERSION && version != EXPIRING_TRANSACTION_FORMAT_VERSION {
+            decoded.warnings.push(format!(
+                "Unknown format version {}; read as version {}",
+                version, TRANSACTION_FORMAT_VERSION
+            ));
+        }
+        let chain_id = field(reader, || "chain id".to_string(), Reader::array32)?;
+        decoded.chain_id = Some(hex::encode(chain_id));
+    }
+
+    let inputs = count(reader, "input")?;
+    decoded.input_count = Some(inputs);
+    for i in 0..inputs {
+        let offset = reader.position();
+        let txid = field(reader, || format!("input {} txid", i), Reader::string)?;
+        let index = field(reader, || format!("input {} index", i), Reader::u32)?;
+        let amount = field(reader, || format!("input {} amount", i), Reader::u64)?;
+        decoded.inputs.push(DecodedInput {
+            txid,
+            index,
+            amount,
+            offset,
+        });
+    }
+
+    let outputs = count(reader, "output")?;
+    decoded.output_count = Some(outputs);
+    for i in 0..outputs {
+        let offset = reader.position();
+        let amount = field(reader, || format!("output {} amount", i), Reader::u64)?;
+        let address = field(reader, || format!("output {} address", i), Reader::string)?;
+        decoded.outputs.push(DecodedOutput {
+            amount,
+            address_valid: Address::from_string(&address).is_ok(),
+            address,
+            offset,
+        });
+    }
+
+    decoded.fee = Some(field(reader, || "fee".to_string(), Reader::u64)?);
+    if version == EXPIRING_TRANSACTION_FORMAT_VERSION {
+        decoded.expiry_height = Some(field(reader, || "expiry height".to_string(), Reader::u64)?);
+    }
+    let signature = field(reader, || "signature".to_string(), Reader::bytes)?;
+    decoded.signature = Some(hex::encode(signature));
+
+    let offset = reader.position();
+    reader.finish().map_err(|_| DecodeError {
+        offset,
+        field: "end".to_string(),
+        message: format!("{} trailing bytes after the signature", reader.remaining()),
+    })
+}
+
+/// Flag a declared fee that doesn't match what the inputs and outputs imply
+fn check_amounts(decoded: &mut DecodedTransaction) {
+    let (Some(inputs), Some(outputs), Some(fee)) =
+        (decoded.input_total(), decoded.output_total(), decoded.fee)
+    else {
+        return;
+    };
+    match inputs.checked_sub(outputs) {
+        Some(implied) if implied != fee => decoded.warnings.push(format!(
+            "Declared fee {} but inputs minus outputs is {}",
+            fee, implied
+        )),
+        Some(_) => {}
+        None => decoded
+            .warnings
+            .push(format!("Outputs ({}) exceed inputs ({})", outputs, inputs)),
+    }
+    for output in decoded
+        .outputs
+        .iter()
+        .filter(|output| !output.address_valid)
+    {
+        decoded.warnings.push(format!(
+            "Output at offset {} pays an invalid address",
+            output.offset
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_address, fake_signed_transaction};
+    use crate::wallet::encoding::Encode;
+    use crate::wallet::transaction::SignedTransaction;
+
+    /// Offset of the first input: version byte, chain id, input count
+    const FIRST_INPUT: usize = 1 + 32 + 4;
+    /// Offset of the output count after one input spending txid "aa"
+    const OUTPUT_COUNT: usize = FIRST_INPUT + 4 + 2 + 4 + 8;
+
+    fn sample() -> SignedTransaction {
+        fake_signed_transaction()
+            .spending("aa", 1, 1_000)
+            .paying(&fake_address(), 990)
+            .build()
+    }
+
+    #[test]
+    fn a_valid_transaction_decodes_completely() {
+        let tx = sample();
+        let bytes = tx.to_bytes();
+        let decoded = decode_transaction(hex::encode(&bytes));
+
+        assert!(decoded.is_complete(), "{:?}", decoded.error);
+        assert_eq!(decoded.size, bytes.len());
+        assert_eq!(decoded.version, Some(TRANSACTION_FORMAT_VERSION));
+        assert_eq!(decoded.chain_id, Some(hex::encode(tx.chain_id)));
+        assert_eq!(
+            decoded.inputs,
+            [DecodedInput {
+                txid: "aa".to_string(),
+                index: 1,
+                amount: 1_000,
+                offset: FIRST_INPUT,
+            }]
+        );
+        assert_eq!(decoded.outputs.len(), 1);
+        assert_eq!(decoded.outputs[0].amount, 990);
+        assert_eq!(decoded.outputs[0].offset, OUTPUT_COUNT + 4);
+        assert!(decoded.outputs[0].address_valid);
+        assert_eq!(decoded.fee, Some(10));
+        assert_eq!(decoded.signature, Some(hex::encode(&tx.signature)));
+        assert_eq!(decoded.txid, Some(tx.id.clone()));
+        assert!(decoded.warnings.is_empty(), "{:?}", decoded.warnings);
+    }
+
+    #[test]
+    fn raw_bytes_and_prefixed_hex_decode_the_same() {
+        let bytes = sample().to_bytes();
+        let from_hex = decode_transaction(hex::encode(&bytes));
+        assert_eq!(decode_transaction(&bytes), from_hex);
+        assert_eq!(
+            decode_transaction(format!("  0x{}\n", hex::encode(&bytes))),
+            from_hex
+        );
+    }
+
+    #[test]
+    fn an_expiring_transaction_shows_its_expiry() {
+        let tx = fake_signed_transaction().expiring_at(500).build();
+        let decoded = decode_transaction(tx.to_bytes());
+        assert!(decoded.is_complete());
+        assert_eq!(decoded.version, Some(EXPIRING_TRANSACTION_FORMAT_VERSION));
+        assert_eq!(decoded.expiry_height, Some(500));
+    }
+
+    #[test]
+    fn a_truncated_transaction_keeps_what_was_read() {
+        let bytes = sample().to_bytes();
+        // Cut in the middle of the output's address
+        let decoded = decode_transaction(&bytes[..OUTPUT_COUNT + 4 + 8 + 4 + 3]);
+
+        assert!(!decoded.is_complete());
+        assert_eq!(decoded.txid, None);
+        assert_eq!(decoded.inputs.len(), 1);
+        assert_eq!(decoded.output_count, Some(1));
+        assert!(decoded.outputs.is_empty());
+        let error = decoded.error.unwrap();
+        assert_eq!(error.field, "output 0 address");
+        assert_eq!(error.offset, OUTPUT_COUNT + 4 + 8);
+        assert!(error.message.contains("Unexpected end of input"));
+    }
+
+    #[test]
+    fn each_cut_point_reports_the_field_it_falls_in() {
+        let bytes = sample().to_bytes();
+        let cases = [
+            (1, "chain id", 1),
+            (FIRST_INPUT - 1, "input count", 33),
+            (FIRST_INPUT + 7, "input 0 index", FIRST_INPUT + 6),
+            (OUTPUT_COUNT + 2, "output count", OUTPUT_COUNT),
+            (bytes.len() - 1, "signature", bytes.len() - 68),
+        ];
+        for (len, field, offset) in cases {
+            let error = decode_transaction(&bytes[..len]).error.unwrap();
+            assert_eq!(
+                (error.field.as_str(), error.offset),
+                (field, offset),
+                "{}",
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn trailing_bytes_are_reported_after_the_signature() {
+        let mut bytes = sample().to_bytes();
+        let end = bytes.len();
+        bytes.extend_from_slice(&[1, 2, 3]);
+        let decoded = decode_transaction(&bytes);
+        let error = decoded.error.unwrap();
+        assert_eq!(error.field, "end");
+        assert_eq!(error.offset, end);
+        assert_eq!(error.message, "3 trailing bytes after the signature");
+        assert!(decoded.signature.is_some());
+    }
+
+    #[test]
+    fn bad_hex_reports_the_character_offset() {
+        let error = decode_transaction("02abzz").error.unwrap();
+        assert_eq!((error.field.as_str(), error.offset), ("hex", 4));
+
+        let error = decode_transaction(" 0x02abc").error.unwrap();
+        assert_eq!(error.offset, 8);
+        assert_eq!(error.message, "odd number of hex digits");
+
+        for empty in ["", "0x", "   "] {
+            assert!(decode_transaction(empty).error.is_some(), "{:?}", empty);
+        }
+    }
+
+    #[test]
+    fn garbage_fails_without_panicking() {
+        let huge_count = [
+            vec![TRANSACTION_FORMAT_VERSION],
+            vec![0; 32],
+            u32::MAX.to_le_bytes().to_vec(),
+        ]
+        .concat();
+        let error = decode_transaction(&huge_count).error.unwrap();
+        assert_eq!(error.field, "input count");
+        assert!(error.message.contains("declared but only 0 bytes left"));
+
+        let mut seed = 7u32;
+        for len in 0..200 {
+            let noise: Vec<u8> = (0..len)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                    (seed >> 16) as u8 | 0x80
+                })
+                .collect();
+            assert_eq!(decode_transaction(&noise).size, len);
+        }
+    }
+
+    #[test]
+    fn odd_amounts_and_versions_are_flagged() {
+        let overpaid = fake_signed_transaction()
+            .spending("aa", 0, 100)
+            .paying(&fake_address(), 500)
+            .build();
+        let decoded = decode_transaction(overpaid.to_bytes());
+        assert_eq!(decoded.warnings, ["Outputs (500) exceed inputs (100)"]);
+
+        let mut bytes = sample().to_bytes();
+        bytes[0] = 9;
+        let decoded = decode_transaction(&bytes);
+        assert!(decoded.error.is_none());
+        assert!(decoded.warnings[0].starts_with("Unknown format version 9"));
+    }
+}
@@ -0,0 +1,361 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use crate::wallet::network::NockchainNodeConfig;
+use crate::wallet::{WalletError, WalletResult};
+
+/// Placeholder written in place of secret values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Host and build details included in every bundle
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemInfo {
+    pub os: String,
+    pub arch: String,
+    pub crate_version: String,
+    pub nockchain_version: String,
+    pub generated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl SystemInfo {
+    pub fn collect(nockchain_version: String) -> Self {
+        Self {
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            nockchain_version,
+            generated_at: chrono::Utc::now(),
+        }
+    }
+}
+
+/// Copy of the node config with credentials replaced by `REDACTED`
+pub fn redact_config(config: &NockchainNodeConfig) -> NockchainNodeConfig {
+    let mut redacted = config.clone();
+    if redacted.btc_username.is_some() {
+        redacted.btc_username = Some(REDACTED.to_string());
+    }
+    if redacted.btc_password.is_some() {
+        redacted.btc_password = Some(REDACTED.to_string());
+    }
+    redacted
+}
+
+/// Secret strings from the config that must never appear in a bundle
+pub fn config_secrets(config: &NockchainNodeConfig) -> Vec<String> {
+    [&config.btc_username, &config.btc_password]
+        .into_iter()
+        .flatten()
+        .filter(|secret| !secret.is_empty())
+        .cloned()
+        .collect()
+}
+
+/// Contents of one bundle entry
+#[derive(Debug)]
+enum BundleData {
+    Bytes(Vec<u8>),
+    /// The first `len` bytes of a file, read only while the archive is
+    /// written, so large log files aren't held in memory
+    File {
+        path: PathBuf,
+        len: u64,
+    },
+}
+
+/// Set of files destined for a `.tar.gz` diagnostic bundle. Small entries are
+/// held in memory; files added with `add_file` are streamed from disk.
+#[derive(Debug, Default)]
+pub struct DebugBundle {
+    entries: Vec<(String, BundleData)>,
+    secrets: Vec<String>,
+}
+
+impl DebugBundle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register strings that are scrubbed from every entry before writing
+    pub fn with_secrets(mut self, secrets: Vec<String>) -> Self {
+        self.secrets = secrets;
+        self
+    }
+
+    pub fn add_json<T: Serialize>(&mut self, name: &str, value: &T) -> WalletResult<()> {
+        let data = serde_json::to_vec_pretty(value).map_err(|e| {
+            WalletError::Serialization(format!("Failed to serialize {}: {}", name, e))
+        })?;
+        self.add_bytes(name, data);
+        Ok(())
+    }
+
+    pub fn add_bytes(&mut self, name: &str, data: Vec<u8>) {
+        let data = self.scrub(&data).into_owned();
+        self.entries
+            .push((name.to_string(), BundleData::Bytes(data)));
+    }
+
+    /// Add the file at `path` as it is now. Bytes appended to it later, as to
+    /// a log still being written, are left out.
+    pub fn add_file(&mut self, name: &str, path: &Path) -> WalletResult<()> {
+        let len = std::fs::metadata(path)
+            .map_err(|e| WalletError::Storage(format!("Failed to read {}: {}", path.display(), e)))?
+            .len();
+        self.entries.push((
+            name.to_string(),
+            BundleData::File {
+                path: path.to_path_buf(),
+                len,
+            },
+        ));
+        Ok(())
+    }
+
+    pub fn entry_names(&self) -> Vec<&str> {
+        self.entries.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    fn scrub<'a>(&self, data: &'a [u8]) -> Cow<'a, [u8]> {
+        if self.secrets.is_empty() {
+            return Cow::Borrowed(data);
+        }
+        let mut text = String::from_utf8_lossy(data).into_owned();
+        for secret in &self.secrets {
+            text = text.replace(secret.as_str(), REDACTED);
+        }
+        Cow::Owned(text.into_bytes())
+    }
+
+    /// Scrubbed contents of a file entry, a line at a time
+    fn scrubbed_file(&self, path: &Path, len: u64) -> io::Result<ScrubbedLines<'_>> {
+        Ok(ScrubbedLines {
+            bundle: self,
+            lines: BufReader::new(File::open(path)?.take(len)),
+            line: Vec::new(),
+            pos: 0,
+        })
+    }
+
+    /// Write the bundle as gzip-compressed tar, atomically via a temporary file
+    pub fn write_atomic(&self, path: &Path) -> WalletResult<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                WalletError::Storage(format!("Failed to create bundle directory: {}", e))
+            })?;
+        }
+
+        let tmp_path = path.with_extension("partial");
+        let result = self.write_archive(&tmp_path).and_then(|()| {
+            std::fs::rename(&tmp_path, path)
+                .map_err(|e| WalletError::Storage(format!("Failed to finalize bundle: {}", e)))
+        });
+
+        if result.is_err() {
+            let _ = std::fs::remove_file(&tmp_path);
+        }
+        result
+    }
+
+    fn write_archive(&self, path: &Path) -> WalletResult<()> {
+        let io_err = |e: std::io::Error| {
+            WalletError::Storage(format!("Failed to write debug bundle: {}", e))
+        };
+
+        let file = File::create(path).map_err(io_err)?;
+        let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        let mtime = chrono::Utc::now().timestamp().max(0) as u64;
+
+        for (name, data) in &self.entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_mode(0o644);
+            header.set_mtime(mtime);
+            match data {
+                BundleData::Bytes(data) => {
+                    header.set_size(data.len() as u64);
+                    builder
+                        .append_data(&mut header, name, data.as_slice())
+                        .map_err(io_err)?;
+                }
+                BundleData::File { path, len } => {
+                    // Scrubbing can change the length, which the header
+                    // needs up front, so the file is read twice
+                    let size = io::copy(
+                        &mut self.scrubbed_file(path, *len).map_err(io_err)?,
+                        &mut io::sink(),
+                    )
+                    .map_err(io_err)?;
+                    header.set_size(size);
+                    builder
+                        .append_data(
+                            &mut header,
+                            name,
+                            self.scrubbed_file(path, *len).map_err(io_err)?,
+                        )
+                        .map_err(io_err)?;
+                }
+            }
+        }
+
+        let encoder = builder.into_inner().map_err(io_err)?;
+        let file = encoder.finish().map_err(io_err)?;
+        file.sync_all().map_err(io_err)?;
+        Ok(())
+    }
+}
+
+/// Reader over a file with the bundle's secrets scrubbed from each line
+struct ScrubbedLines<'a> {
+    bundle: &'a DebugBundle,
+    lines: BufReader<io::Take<File>>,
+    line: Vec<u8>,
+    pos: usize,
+}
+
+impl Read for ScrubbedLines<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.line.len() {
+            self.line.clear();
+            self.pos = 0;
+            if self.lines.read_until(b'\n', &mut self.line)? == 0 {
+                return Ok(0);
+            }
+            if let Cow::Owned(scrubbed) = self.bundle.scrub(&self.line) {
+                self.line = scrubbed;
+            }
+        }
+        let n = buf.len().min(self.line.len() - self.pos);
+        buf[..n].copy_from_slice(&self.line[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Names and paths of up to `limit` of the most recently modified `*.log`
+/// files in `dir`
+pub fn recent_log_files(dir: &Path, limit: usize) -> Vec<(String, PathBuf)> {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<_> = read_dir
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "log"))
+        .filter_map(|entry| {
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            Some((modified, entry.path()))
+        })
+        .collect();
+    files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+
+    files
+        .into_iter()
+        .take(limit)
+        .filter_map(|(_, path)| {
+            let name = path.file_name()?.to_string_lossy().into_owned();
+            Some((name, path))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    fn unpack(path: &Path) -> Vec<(String, Vec<u8>)> {
+        let file = File::open(path).unwrap();
+        tar::Archive::new(flate2::read::GzDecoder::new(file))
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let name = entry.path().unwrap().display().to_string();
+                let mut data = Vec::new();
+                entry.read_to_end(&mut data).unwrap();
+                (name, data)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn redaction_covers_only_set_credentials() {
+        let config = NockchainNodeConfig {
+            btc_username: Some("alice".to_string()),
+            btc_password: Some(String::new()),
+            ..NockchainNodeConfig::default()
+        };
+        let redacted = redact_config(&config);
+        assert_eq!(redacted.btc_username.as_deref(), Some(REDACTED));
+        assert_eq!(redacted.btc_password.as_deref(), Some(REDACTED));
+        assert_eq!(config_secrets(&config), vec!["alice".to_string()]);
+        assert_eq!(
+            redact_config(&NockchainNodeConfig::default()).btc_password,
+            None
+        );
+    }
+
+    #[test]
+    fn files_are_scrubbed_line_by_line_and_cut_at_their_size_when_added() {
+        let dir = TempDir::new("bundle-file");
+        let log = dir.join("node.log");
+        let lines: String = (0..2_000)
+            .map(|i| format!("line {} password=s3cret tail\n", i))
+            .collect();
+        std::fs::write(&log, &lines).unwrap();
+
+        let mut bundle = DebugBundle::new().with_secrets(vec!["s3cret".to_string()]);
+        bundle.add_file("logs/node.log", &log).unwrap();
+        bundle.add_bytes("note.txt", b"s3cret s3cret".to_vec());
+        // Written after the file was added, so left out
+        std::fs::write(&log, format!("{}late s3cret\n", lines)).unwrap();
+        bundle.write_atomic(&dir.join("bundle.tar.gz")).unwrap();
+
+        let entries = unpack(&dir.join("bundle.tar.gz"));
+        assert_eq!(bundle.entry_names(), vec!["logs/node.log", "note.txt"]);
+        assert_eq!(
+            String::from_utf8(entries[0].1.clone()).unwrap(),
+            lines.replace("s3cret", REDACTED)
+        );
+        assert_eq!(entries[1].1, b"[REDACTED] [REDACTED]");
+    }
+
+    #[test]
+    fn a_failed_write_leaves_no_partial_file() {
+        let dir = TempDir::new("bundle-fail");
+        let mut bundle = DebugBundle::new();
+        bundle.add_bytes("a.txt", b"a".to_vec());
+        bundle
+            .add_file("gone.log", &dir.join("missing.log"))
+            .unwrap_err();
+        std::fs::write(dir.join("b.log"), "b").unwrap();
+        bundle.add_file("b.log", &dir.join("b.log")).unwrap();
+        std::fs::remove_file(dir.join("b.log")).unwrap();
+
+        assert!(bundle.write_atomic(&dir.join("bundle.tar.gz")).is_err());
+        assert!(!dir.join("bundle.tar.gz").exists());
+        assert!(!dir.join("bundle.partial").exists());
+    }
+
+    #[test]
+    fn recent_log_files_are_newest_first_and_limited() {
+        let dir = TempDir::new("bundle-logs");
+        for name in ["old.log", "mid.log", "new.log"] {
+            std::fs::write(dir.join(name), name).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        std::fs::write(dir.join("notes.txt"), "not a log").unwrap();
+
+        let names: Vec<String> = recent_log_files(dir.path(), 2)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, vec!["new.log", "mid.log"]);
+        assert!(recent_log_files(&dir.join("missing"), 5).is_empty());
+    }
+}
@@ -0,0 +1,905 @@
+//! Canonical binary encoding used for hashing, peer exchange, RPC and disk.
+//!
+//! All integers are little-endian and fixed width. Variable-length data
+//! (byte strings, strings, lists) is prefixed with its length as a u32.
+//! Top-level wire types start with a format version byte. The same value
+//! always encodes to the same bytes, so hashes of the encoding are stable.
+//!
+//! Transactions with an expiry height, and the packages and blocks that hold
+//! them, are written under the next format version, which adds the expiry.
+//! Everything without one keeps its old version and bytes, so the ids and
+//! signatures of transactions made before expiries existed don't change.
+
+use sha2::{Digest, Sha256};
+
+use crate::wallet::address::AddressNetwork;
+use crate::wallet::cold_signing::{
+    InputSignature, PackageInput, PackageSignatures, UnsignedTransactionPackage,
+};
+use crate::wallet::filters::BlockFilter;
+use crate::wallet::handshake::Handshake;
+use crate::wallet::keys::{NockchainTransaction, OutPoint, TransactionInput, TransactionOutput};
+use crate::wallet::reject::{RejectCode, TxReject};
+use crate::wallet::transaction::SignedTransaction;
+use crate::wallet::{Address, Block, BlockHeader, WalletError, WalletResult};
+
+/// Version byte prefixed to encoded transactions
+pub const TRANSACTION_FORMAT_VERSION: u8 = 2;
+
+/// Version byte prefixed to encoded transactions with an expiry height
+pub const EXPIRING_TRANSACTION_FORMAT_VERSION: u8 = 3;
+
+/// Version byte prefixed to encoded blocks
+pub const BLOCK_FORMAT_VERSION: u8 = 1;
+
+/// Version byte prefixed to encoded blocks holding a transaction with an
+/// expiry height; every transaction in them carries an optional expiry
+pub const EXPIRING_BLOCK_FORMAT_VERSION: u8 = 2;
+
+/// Version byte prefixed to compact block filters
+pub const FILTER_FORMAT_VERSION: u8 = 1;
+
+/// Version byte prefixed to unsigned transaction packages and their signatures
+pub const PACKAGE_FORMAT_VERSION: u8 = 2;
+
+/// Version byte prefixed to unsigned transaction packages with an expiry height
+pub const EXPIRING_PACKAGE_FORMAT_VERSION: u8 = 3;
+
+/// Version byte prefixed to transaction rejections sent to peers
+pub const REJECT_FORMAT_VERSION: u8 = 1;
+
+/// Newest block header version this build can decode
+pub const MAX_HEADER_VERSION: u32 = 1;
+
+pub trait Encode {
+    fn encode(&self, out: &mut Vec<u8>);
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode(&mut out);
+        out
+    }
+}
+
+pub trait Decode: Sized {
+    fn decode(reader: &mut Reader<'_>) -> WalletResult<Self>;
+
+    /// Decode a complete value, rejecting trailing bytes
+    fn from_bytes(bytes: &[u8]) -> WalletResult<Self> {
+        let mut reader = Reader::new(bytes);
+        let value = Self::decode(&mut reader)?;
+        reader.finish()?;
+        Ok(value)
+    }
+}
+
+/// Cursor over encoded bytes
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    /// Offset of the next byte to be read
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+
+    pub fn take(&mut self, len: usize) -> WalletResult<&'a [u8]> {
+        if len > self.remaining() {
+            return Err(WalletError::Serialization(format!(
+                "Unexpected end of input: needed {} bytes at offset {}, {} left",
+                len,
+                self.pos,
+                self.remaining()
+            )));
+        }
+        let slice = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    pub fn u8(&mut self) -> WalletResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn u32(&mut self) -> WalletResult<u32> {
+        let mut buf = [0u8; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    pub fn u64(&mut self) -> WalletResult<u64> {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    pub fn array32(&mut self) -> WalletResult<[u8; 32]> {
+        let mut buf = [0u8; 32];
+        buf.copy_from_slice(self.take(32)?);
+        Ok(buf)
+    }
+
+    pub fn bytes(&mut self) -> WalletResult<Vec<u8>> {
+        let len = self.u32()? as usize;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    pub fn string(&mut self) -> WalletResult<String> {
+        String::from_utf8(self.bytes()?)
+            .map_err(|e| WalletError::Serialization(format!("Invalid UTF-8 string: {}", e)))
+    }
+
+    pub fn list<T: Decode>(&mut self) -> WalletResult<Vec<T>> {
+        let count = self.u32()? as usize;
+        // Every item takes at least one byte, so this bounds the allocation
+        if count > self.remaining() {
+            return Err(WalletError::Serialization(format!(
+                "List length {} exceeds remaining input",
+                count
+            )));
+        }
+        (0..count).map(|_| T::decode(self)).collect()
+    }
+
+    pub fn version(&mut self, expected: u8, what: &str) -> WalletResult<()> {
+        self.version_of(&[expected], what).map(|_| ())
+    }
+
+    /// Read a version byte that must be one of `supported`, returning it
+    pub fn version_of(&mut self, supported: &[u8], what: &str) -> WalletResult<u8> {
+        let version = self.u8()?;
+        if !supported.contains(&version) {
+            return Err(WalletError::Serialization(format!(
+                "Unsupported {} format version {}",
+                what, version
+            )));
+        }
+        Ok(version)
+    }
+
+    /// A `u64` behind a presence byte of 0 or 1
+    pub fn option_u64(&mut self) -> WalletResult<Option<u64>> {
+        match self.u8()? {
+            0 => Ok(None),
+            1 => Ok(Some(self.u64()?)),
+            tag => Err(WalletError::Serialization(format!(
+                "Invalid option tag {}",
+                tag
+            ))),
+        }
+    }
+
+    /// Fail if any input is left unread
+    pub fn finish(&self) -> WalletResult<()> {
+        if self.remaining() != 0 {
+            return Err(WalletError::Serialization(format!(
+                "{} trailing bytes after value",
+                self.remaining()
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u64(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_option_u64(out: &mut Vec<u8>, value: Option<u64>) {
+    match value {
+        Some(value) => {
+            out.push(1);
+            put_u64(out, value);
+        }
+        None => out.push(0),
+    }
+}
+
+fn put_len(out: &mut Vec<u8>, len: usize) {
+    let len = u32::try_from(len).expect("encoded length exceeds u32::MAX");
+    put_u32(out, len);
+}
+
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    put_len(out, bytes.len());
+    out.extend_from_slice(bytes);
+}
+
+fn put_list<T: Encode>(out: &mut Vec<u8>, items: &[T]) {
+    put_len(out, items.len());
+    for item in items {
+        item.encode(out);
+    }
+}
+
+pub fn sha256(bytes: &[u8]) -> [u8; 32] {
+    Sha256::digest(bytes).into()
+}
+
+impl Encode for OutPoint {
+    fn encode(&self, out: &mut Vec<u8>) {
+        put_bytes(out, self.txid.as_bytes());
+        put_u32(out, self.index);
+    }
+}
+
+impl Decode for OutPoint {
+    fn decode(reader: &mut Reader<'_>) -> WalletResult<Self> {
+        Ok(Self {
+            txid: reader.string()?,
+            index: reader.u32()?,
+        })
+    }
+}
+
+impl Encode for TransactionInput {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.previous_output.encode(out);
+        put_u64(out, self.amount);
+    }
+}
+
+impl Decode for TransactionInput {
+    fn decode(reader: &mut Reader<'_>) -> WalletResult<Self> {
+        Ok(Self {
+            previous_output: OutPoint::decode(reader)?,
+            amount: reader.u64()?,
+        })
+    }
+}
+
+impl Encode for TransactionOutput {
+    fn encode(&self, out: &mut Vec<u8>) {
+        put_u64(out, self.amount);
+        put_bytes(out, self.recipient_address.as_bytes());
+    }
+}
+
+impl Decode for TransactionOutput {
+    fn decode(reader: &mut Reader<'_>) -> WalletResult<Self> {
+        Ok(Self {
+            amount: reader.u64()?,
+            recipient_address: reader.string()?,
+        })
+    }
+}
+
+/// Id of the chain a transaction is signed for: sha256 of a tag, the network's
+/// version byte and the genesis hash. A signature covers it, so a transaction
+/// signed for one chain is invalid on every other.
+pub fn chain_id(network: AddressNetwork, genesis_hash: &[u8; 32]) -> [u8; 32] {
+    let mut bytes = b"nockchain-chain-id".to_vec();
+    bytes.push(network.version_byte());
+    bytes.extend_from_slice(genesis_hash);
+    sha256(&bytes)
+}
+
+/// Bytes covered by the transaction signature: everything except the
+/// signature itself. An expiry height follows the fee.
+pub fn signing_bytes(
+    chain_id: &[u8; 32],
+    inputs: &[TransactionInput],
+    outputs: &[TransactionOutput],
+    fee: u64,
+    expiry_height: Option<u64>,
+) -> Vec<u8> {
+    let version = match expiry_height {
+        Some(_) => EXPIRING_TRANSACTION_FORMAT_VERSION,
+        None => TRANSACTION_FORMAT_VERSION,
+    };
+    let mut out = vec![version];
+    out.extend_from_slice(chain_id);
+    put_list(&mut out, inputs);
+    put_list(&mut out, outputs);
+    put_u64(&mut out, fee);
+    if let Some(height) = expiry_height {
+        put_u64(&mut out, height);
+    }
+    out
+}
+
+/// Hash that gets signed when building a transaction
+pub fn signing_hash(
+    chain_id: &[u8; 32],
+    inputs: &[TransactionInput],
+    outputs: &[TransactionOutput],
+    fee: u64,
+    expiry_height: Option<u64>,
+) -> Vec<u8> {
+    sha256(&signing_bytes(
+        chain_id,
+        inputs,
+        outputs,
+        fee,
+        expiry_height,
+    ))
+    .to_vec()
+}
+
+impl Encode for PackageInput {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.input.encode(out);
+        out.extend_from_slice(&self.owner.public_key);
+    }
+}
+
+impl Decode for PackageInput {
+    fn decode(reader: &mut Reader<'_>) -> WalletResult<Self> {
+        Ok(Self {
+            input: TransactionInput::decode(reader)?,
+            owner: Address::from_public_key(reader.array32()?),
+        })
+    }
+}
+
+impl Encode for UnsignedTransactionPackage {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(match self.expiry_height {
+            Some(_) => EXPIRING_PACKAGE_FORMAT_VERSION,
+            None => PACKAGE_FORMAT_VERSION,
+        });
+        out.extend_from_slice(&self.chain_id);
+        put_list(out, &self.inputs);
+        put_list(out, &self.outputs);
+        put_u64(out, self.fee);
+        if let Some(height) = self.expiry_height {
+            put_u64(out, height);
+        }
+        put_len(out, self.sighashes.len());
+        for sighash in &self.sighashes {
+            out.extend_from_slice(sighash);
+        }
+    }
+}
+
+impl Decode for UnsignedTransactionPackage {
+    /// Sighashes are checked against the decoded contents, so an altered
+    /// package is rejected rather than signed
+    fn decode(reader: &mut Reader<'_>) -> WalletResult<Self> {
+        let version = reader.version_of(
+            &[PACKAGE_FORMAT_VERSION, EXPIRING_PACKAGE_FORMAT_VERSION],
+            "transaction package",
+        )?;
+        let chain_id = reader.array32()?;
+        let inputs: Vec<PackageInput> = reader.list()?;
+        let outputs: Vec<TransactionOutput> = reader.list()?;
+        let fee = reader.u64()?;
+        let expiry_height = match version {
+            EXPIRING_PACKAGE_FORMAT_VERSION => Some(reader.u64()?),
+            _ => None,
+        };
+        let count = reader.u32()? as usize;
+        let sighashes = (0..count)
+            .map(|_| reader.array32())
+            .collect::<WalletResult<Vec<_>>>()?;
+
+        let package = Self {
+            chain_id,
+            inputs,
+            outputs,
+            fee,
+            expiry_height,
+            sighashes,
+        };
+        package.verify_sighashes().map_err(|_| {
+            WalletError::Serialization("Package sighashes do not match its contents".to_string())
+        })?;
+        Ok(package)
+    }
+}
+
+impl Encode for InputSignature {
+    fn encode(&self, out: &mut Vec<u8>) {
+        put_u32(out, self.input_index);
+        out.extend_from_slice(&self.signature);
+    }
+}
+
+impl Decode for InputSignature {
+    fn decode(reader: &mut Reader<'_>) -> WalletResult<Self> {
+        let input_index = reader.u32()?;
+        let signature = reader
+            .take(64)?
+            .try_into()
+            .expect("take returns exactly 64 bytes");
+        Ok(Self {
+            input_index,
+            signature,
+        })
+    }
+}
+
+impl Encode for PackageSignatures {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(PACKAGE_FORMAT_VERSION);
+        put_list(out, &self.signatures);
+    }
+}
+
+impl Decode for PackageSignatures {
+    fn decode(reader: &mut Reader<'_>) -> WalletResult<Self> {
+        reader.version(PACKAGE_FORMAT_VERSION, "package signatures")?;
+        Ok(Self {
+            signatures: reader.list()?,
+        })
+    }
+}
+
+/// Transaction id: hex of the hash of the full canonical encoding
+pub fn transaction_id(tx: &SignedTransaction) -> String {
+    hex::encode(sha256(&tx.to_bytes()))
+}
+
+impl Encode for SignedTransaction {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&signing_bytes(
+            &self.chain_id,
+            &self.inputs,
+            &self.outputs,
+            self.fee,
+            self.expiry_height,
+        ));
+        put_bytes(out, &self.signature);
+    }
+}
+
+impl Decode for SignedTransaction {
+    /// `id` and `hash` are derived from the decoded content, never read from the wire
+    fn decode(reader: &mut Reader<'_>) -> WalletResult<Self> {
+        let version = reader.version_of(
+            &[
+                TRANSACTION_FORMAT_VERSION,
+                EXPIRING_TRANSACTION_FORMAT_VERSION,
+            ],
+            "transaction",
+        )?;
+        let chain_id = reader.array32()?;
+        let inputs: Vec<TransactionInput> = reader.list()?;
+        let outputs: Vec<TransactionOutput> = reader.list()?;
+        let fee = reader.u64()?;
+        let expiry_height = match version {
+            EXPIRING_TRANSACTION_FORMAT_VERSION => Some(reader.u64()?),
+            _ => None,
+        };
+        let signature = reader.bytes()?;
+
+        let mut tx = SignedTransaction {
+            id: String::new(),
+            hash: signing_hash(&chain_id, &inputs, &outputs, fee, expiry_height),
+            chain_id,
+            inputs,
+            outputs,
+            fee,
+            expiry_height,
+            signature,
+        };
+        tx.id = transaction_id(&tx);
+        Ok(tx)
+    }
+}
+
+impl Encode for NockchainTransaction {
+    fn encode(&self, out: &mut Vec<u8>) {
+        put_bytes(out, self.id.as_bytes());
+        put_list(out, &self.inputs);
+        put_list(out, &self.outputs);
+        put_bytes(out, &self.hash);
+    }
+}
+
+/// The expiry height isn't part of a block transaction's own encoding: blocks
+/// of `EXPIRING_BLOCK_FORMAT_VERSION` write it after each transaction
+impl Decode for NockchainTransaction {
+    fn decode(reader: &mut Reader<'_>) -> WalletResult<Self> {
+        Ok(Self {
+            id: reader.string()?,
+            inputs: reader.list()?,
+            outputs: reader.list()?,
+            hash: reader.bytes()?,
+            expiry_height: None,
+        })
+    }
+}
+
+/// Header layout, version 1 (96 bytes):
+///
+/// | offset | size | field         |
+/// |--------|------|---------------|
+/// | 0      | 4    | version (u32) |
+/// | 4      | 32   | previous_hash |
+/// | 36     | 32   | merkle_root   |
+/// | 68     | 8    | timestamp     |
+/// | 76     | 4    | bits          |
+/// | 80     | 8    | nonce         |
+/// | 88     | 8    | height        |
+///
+/// The version always comes first so decoders can pick the layout before
+/// reading anything else. Future versions may change everything after it.
+impl Encode for BlockHeader {
+    fn encode(&self, out: &mut Vec<u8>) {
+        put_u32(out, self.version);
+        out.extend_from_slice(&self.previous_hash);
+        out.extend_from_slice(&self.merkle_root);
+        put_u64(out, self.timestamp);
+        put_u32(out, self.bits);
+        put_u64(out, self.nonce);
+        put_u64(out, self.height);
+    }
+}
+
+impl Decode for BlockHeader {
+    fn decode(reader: &mut Reader<'_>) -> WalletResult<Self> {
+        match reader.u32()? {
+            1 => decode_header_v1(reader),
+            version => Err(WalletError::BlockValidation(format!(
+                "unsupported header version {}",
+                version
+            ))),
+        }
+    }
+}
+
+fn decode_header_v1(reader: &mut Reader<'_>) -> WalletResult<BlockHeader> {
+    Ok(BlockHeader {
+        version: 1,
+        previous_hash: reader.array32()?,
+        merkle_root: reader.array32()?,
+        timestamp: reader.u64()?,
+        bits: reader.u32()?,
+        nonce: reader.u64()?,
+        height: reader.u64()?,
+    })
+}
+
+impl Encode for BlockFilter {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(FILTER_FORMAT_VERSION);
+        out.extend_from_slice(&self.block_hash);
+        put_u64(out, self.block_size);
+        put_u32(out, self.count);
+        put_bytes(out, &self.data);
+    }
+}
+
+impl Decode for BlockFilter {
+    fn decode(reader: &mut Reader<'_>) -> WalletResult<Self> {
+        reader.version(FILTER_FORMAT_VERSION, "block filter")?;
+        Ok(Self {
+            block_hash: reader.array32()?,
+            block_size: reader.u64()?,
+            count: reader.u32()?,
+            data: reader.bytes()?,
+        })
+    }
+}
+
+impl Encode for Handshake {
+    fn encode(&self, out: &mut Vec<u8>) {
+        put_u32(out, self.protocol_version);
+        put_bytes(out, self.user_agent.as_bytes());
+        out.extend_from_slice(&self.genesis_hash);
+        put_u64(out, self.best_height);
+        put_u64(out, self.services);
+    }
+}
+
+impl Decode for Handshake {
+    fn decode(reader: &mut Reader<'_>) -> WalletResult<Self> {
+        Ok(Self {
+            protocol_version: reader.u32()?,
+            user_agent: reader.string()?,
+            genesis_hash: reader.array32()?,
+            best_height: reader.u64()?,
+            services: reader.u64()?,
+        })
+    }
+}
+
+impl Encode for TxReject {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(REJECT_FORMAT_VERSION);
+        put_bytes(out, self.tx_id.as_bytes());
+        out.push(self.code.code());
+        put_bytes(out, self.detail.as_bytes());
+    }
+}
+
+impl Decode for TxReject {
+    fn decode(reader: &mut Reader<'_>) -> WalletResult<Self> {
+        reader.version(REJECT_FORMAT_VERSION, "transaction rejection")?;
+        let tx_id = reader.string()?;
+        let code = reader.u8()?;
+        let code = RejectCode::from_code(code)
+            .ok_or_else(|| WalletError::Serialization(format!("Unknown reject code {}", code)))?;
+        Ok(Self {
+            tx_id,
+            code,
+            detail: reader.string()?,
+        })
+    }
+}
+
+impl Encode for Block {
+    fn encode(&self, out: &mut Vec<u8>) {
+        let expiring = self
+            .transactions
+            .iter()
+            .any(|tx| tx.expiry_height.is_some());
+        if !expiring {
+            out.push(BLOCK_FORMAT_VERSION);
+            self.header.encode(out);
+            put_list(out, &self.transactions);
+            return;
+        }
+
+        out.push(EXPIRING_BLOCK_FORMAT_VERSION);
+        self.header.encode(out);
+        put_len(out, self.transactions.len());
+        for tx in &self.transactions {
+            tx.encode(out);
+            put_option_u64(out, tx.expiry_height);
+        }
+    }
+}
+
+impl Decode for Block {
+    fn decode(reader: &mut Reader<'_>) -> WalletResult<Self> {
+        let version = reader.version_of(
+            &[BLOCK_FORMAT_VERSION, EXPIRING_BLOCK_FORMAT_VERSION],
+            "block",
+        )?;
+        let header = BlockHeader::decode(reader)?;
+        if version == BLOCK_FORMAT_VERSION {
+            return Ok(Self {
+                header,
+                transactions: reader.list()?,
+            });
+        }
+
+        let count = reader.u32()? as usize;
+        if count > reader.remaining() {
+            return Err(WalletError::Serialization(format!(
+                "List length {} exceeds remaining input",
+                count
+            )));
+        }
+        let mut transactions = Vec::with_capacity(count);
+        for _ in 0..count {
+            let mut tx = NockchainTransaction::decode(reader)?;
+            tx.expiry_height = reader.option_u64()?;
+            transactions.push(tx);
+        }
+        // Without any expiry the block would have been written as version 1
+        if transactions.iter().all(|tx| tx.expiry_height.is_none()) {
+            return Err(WalletError::Serialization(
+                "Block format version 2 without an expiring transaction".to_string(),
+            ));
+        }
+        Ok(Self {
+            header,
+            transactions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input() -> TransactionInput {
+        TransactionInput {
+            previous_output: OutPoint::new("ab", 1),
+            amount: 500,
+        }
+    }
+
+    fn output() -> TransactionOutput {
+        TransactionOutput {
+            amount: 490,
+            recipient_address: "nock1x".to_string(),
+        }
+    }
+
+    fn signed(expiry_height: Option<u64>) -> SignedTransaction {
+        let chain_id = [7; 32];
+        let (inputs, outputs) = (vec![input()], vec![output()]);
+        let mut tx = SignedTransaction {
+            id: String::new(),
+            hash: signing_hash(&chain_id, &inputs, &outputs, 10, expiry_height),
+            chain_id,
+            inputs,
+            outputs,
+            fee: 10,
+            expiry_height,
+            signature: vec![0xaa; 4],
+        };
+        tx.id = transaction_id(&tx);
+        tx
+    }
+
+    fn header() -> BlockHeader {
+        BlockHeader {
+            version: 1,
+            previous_hash: [1; 32],
+            merkle_root: [2; 32],
+            timestamp: 1_704_067_200,
+            bits: 0x1f7f_ffff,
+            nonce: 42,
+            height: 3,
+        }
+    }
+
+    fn block_tx(expiry_height: Option<u64>) -> NockchainTransaction {
+        NockchainTransaction {
+            id: "c0".to_string(),
+            inputs: vec![input()],
+            outputs: vec![output()],
+            hash: vec![9, 9],
+            expiry_height,
+        }
+    }
+
+    fn block(expiry_height: Option<u64>) -> Block {
+        Block {
+            header: header(),
+            transactions: vec![block_tx(expiry_height)],
+        }
+    }
+
+    /// `value` encodes to `golden`, and decoding that gives the same bytes back
+    fn assert_golden<T: Encode + Decode>(value: &T, golden: &str) {
+        let bytes = value.to_bytes();
+        assert_eq!(hex::encode(&bytes), golden, "encoding drifted");
+        assert_eq!(T::from_bytes(&bytes).unwrap().to_bytes(), bytes);
+    }
+
+    const HEADER: &str = concat!(
+        "01000000",
+        "0101010101010101010101010101010101010101010101010101010101010101",
+        "0202020202020202020202020202020202020202020202020202020202020202",
+        "8000926500000000",
+        "ffff7f1f",
+        "2a00000000000000",
+        "0300000000000000",
+    );
+
+    /// Block transaction `block_tx` as written inside a block
+    const BLOCK_TX: &str = concat!(
+        "020000006330",
+        "0100000002000000616201000000f401000000000000",
+        "01000000ea01000000000000060000006e6f636b3178",
+        "020000000909",
+    );
+
+    #[test]
+    fn inputs_and_outputs_match_their_golden_bytes() {
+        // txid "ab" (length-prefixed), index 1, amount 500
+        assert_golden(&input(), "02000000616201000000f401000000000000");
+        // amount 490, address "nock1x" (length-prefixed)
+        assert_golden(&output(), "ea01000000000000060000006e6f636b3178");
+    }
+
+    #[test]
+    fn signed_transactions_match_their_golden_bytes_and_ids() {
+        let body = concat!(
+            "0707070707070707070707070707070707070707070707070707070707070707",
+            "0100000002000000616201000000f401000000000000",
+            "01000000ea01000000000000060000006e6f636b3178",
+            "0a00000000000000",
+        );
+        let tx = signed(None);
+        assert_golden(&tx, &format!("02{}04000000aaaaaaaa", body));
+        assert_eq!(
+            tx.id,
+            "405f012120db0d29be0bdca3e8fe051ac49fe06ebfa1a8f111b42e0125043926"
+        );
+
+        let expiring = signed(Some(100));
+        assert_golden(
+            &expiring,
+            &format!("03{}640000000000000004000000aaaaaaaa", body),
+        );
+        assert_eq!(
+            expiring.id,
+            "5846775f173d44450aed331570495a074a58673fc00a6eb5d2f9d56c30f37dea"
+        );
+
+        // Decoding derives the id and signing hash rather than trusting them
+        let decoded = SignedTransaction::from_bytes(&tx.to_bytes()).unwrap();
+        assert_eq!((decoded.id, decoded.hash), (tx.id, tx.hash));
+    }
+
+    #[test]
+    fn headers_and_blocks_match_their_golden_bytes() {
+        assert_golden(&header(), HEADER);
+        assert_eq!(header().to_bytes().len(), 96);
+        assert_golden(&block(None), &format!("01{}01000000{}", HEADER, BLOCK_TX));
+        assert_golden(
+            &block(Some(100)),
+            &format!("02{}01000000{}016400000000000000", HEADER, BLOCK_TX),
+        );
+    }
+
+    #[test]
+    fn version_1_header_hashes_are_pinned() {
+        // sha256 over exactly the 96 bytes of `HEADER`
+        assert_eq!(
+            hex::encode(header().hash()),
+            "f4b36f905b0efa99bdc497a9c1badfa13a37cc68c189c1a4e36ae2fd4e3e980d"
+        );
+        assert_eq!(header().hash(), sha256(&hex::decode(HEADER).unwrap()));
+        let bumped = BlockHeader {
+            nonce: 43,
+            ..header()
+        };
+        assert_ne!(bumped.hash(), header().hash());
+    }
+
+    #[test]
+    fn chain_ids_are_pinned() {
+        assert_eq!(
+            hex::encode(chain_id(AddressNetwork::Mainnet, &[0; 32])),
+            "2e349c1fed98dad15101d5bac8421e0a605bbed5b794d9ec2cc74fe3aaa6d840"
+        );
+        assert_ne!(
+            chain_id(AddressNetwork::Fakenet, &[0; 32]),
+            chain_id(AddressNetwork::Mainnet, &[0; 32])
+        );
+    }
+
+    #[test]
+    fn malformed_input_is_rejected() {
+        let bytes = signed(None).to_bytes();
+        let error = |bytes: &[u8]| {
+            SignedTransaction::from_bytes(bytes)
+                .unwrap_err()
+                .to_string()
+        };
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(error(&trailing).contains("1 trailing bytes"));
+        assert!(error(&bytes[..bytes.len() - 1]).contains("Unexpected end of input"));
+        let mut version = bytes.clone();
+        version[0] = 9;
+        assert!(error(&version).contains("Unsupported transaction format version 9"));
+
+        // An input count larger than the rest of the input fails before allocating
+        let mut huge = bytes[..33].to_vec();
+        huge.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert!(error(&huge).contains("exceeds remaining input"));
+
+        let mut header = header().to_bytes();
+        header[0] = 2;
+        assert!(BlockHeader::from_bytes(&header)
+            .unwrap_err()
+            .to_string()
+            .contains("unsupported header version 2"));
+    }
+
+    #[test]
+    fn expiring_block_without_an_expiry_is_rejected() {
+        let mut bytes = block(Some(100)).to_bytes();
+        let len = bytes.len();
+        bytes.truncate(len - 9);
+        bytes.push(0);
+        assert!(Block::from_bytes(&bytes)
+            .unwrap_err()
+            .to_string()
+            .contains("version 2 without an expiring transaction"));
+    }
+}
@@ -0,0 +1,228 @@
+//! Transactions that stop being valid at a block height.
+//!
+//! A transaction with an expiry height may be mined in blocks up to and
+//! including that height, never after. The expiry is signed with the rest of
+//! the transaction, so nobody can extend it. The mempool refuses one the next
+//! block could no longer include and evicts it once the tip reaches its
+//! expiry; `Block::validate` rejects a block holding one past its expiry. The
+//! wallet then fails its own expired sends and frees the notes they spent.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tracing::info;
+
+use crate::wallet::chain::Blockchain;
+use crate::wallet::{Wallet, WalletResult};
+
+/// A send of the wallet that expired before being mined
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpiredTransaction {
+    pub tx_id: String,
+    pub expiry_height: u64,
+}
+
+impl fmt::Display for ExpiredTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Transaction {} expired at height {}",
+            self.tx_id, self.expiry_height
+        )
+    }
+}
+
+impl Wallet {
+    /// Fail every pending send the next block on `chain` could no longer
+    /// include and that isn't on `chain` already, with the reason "expired at
+    /// height H", and make the notes it spent spendable again
+    pub fn expire_transactions(
+        &mut self,
+        chain: &Blockchain,
+    ) -> WalletResult<Vec<ExpiredTransaction>> {
+        self.ensure_writable()?;
+        let Some(tip) = chain.height() else {
+            return Ok(Vec::new());
+        };
+
+        let candidates: Vec<ExpiredTransaction> = self
+            .transactions
+            .get_pending_transactions()
+            .iter()
+            .filter_map(|tx| self.transactions.pending_signed(&tx.id))
+            .filter(|signed| signed.is_expired_at(tip.saturating_add(1)))
+            .filter(|signed| chain.transaction_by_id(&signed.id).is_none())
+            .filter_map(|signed| {
+                Some(ExpiredTransaction {
+                    tx_id: signed.id.clone(),
+                    expiry_height: signed.expiry_height?,
+                })
+            })
+            .collect();
+
+        let mut expired = Vec::new();
+        for candidate in candidates {
+            let reason = format!("expired at height {}", candidate.expiry_height);
+            if self.fail_sent_transaction(&candidate.tx_id, &reason)? {
+                info!("{}", candidate);
+                expired.push(candidate);
+            }
+        }
+        Ok(expired)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_address, fake_note, fakenet_chain_config, TempDir};
+    use crate::wallet::mining::MiningPayout;
+    use crate::wallet::send::{Broadcaster, SendRequest, SendStage};
+    use crate::wallet::storage::StorageManager;
+    use crate::wallet::transaction::SignedTransaction;
+    use crate::wallet::TransactionStatus;
+
+    struct AcceptAll;
+
+    impl Broadcaster for AcceptAll {
+        async fn broadcast(&self, _tx: &SignedTransaction, _force: bool) -> WalletResult<()> {
+            Ok(())
+        }
+    }
+
+    /// Wallet at tip 10 holding one confirmed note of 1,000 under "main"
+    fn funded_wallet() -> Wallet {
+        let mut wallet = Wallet::new();
+        let address = wallet.generate_key("main").unwrap();
+        wallet
+            .balances_mut()
+            .add_note(
+                fake_note()
+                    .address(address)
+                    .amount(1_000)
+                    .confirmed_at(1)
+                    .build(),
+            )
+            .unwrap();
+        wallet.balances_mut().set_tip_height(10);
+        wallet
+    }
+
+    fn request(valid_for_blocks: Option<u64>) -> SendRequest {
+        SendRequest {
+            key_name: "main".to_string(),
+            to: fake_address(),
+            amount: 400,
+            fee: 10,
+            min_confirmations: None,
+            valid_for_blocks,
+            confirmed_amount: None,
+            force_broadcast: false,
+        }
+    }
+
+    /// Another wallet mines empty blocks onto `chain` until its tip is `height`
+    async fn mine_to(chain: &mut Blockchain, height: u64, storage: &StorageManager) {
+        let mut miner = Wallet::new();
+        miner.generate_key("miner").unwrap();
+        let payout = MiningPayout {
+            key_name: "miner".to_string(),
+            rotate: false,
+        };
+        while chain.height().is_none_or(|tip| tip < height) {
+            miner
+                .mine_block(chain, &payout, 5_000, storage)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn a_send_fails_once_the_next_block_is_past_its_expiry() {
+        let dir = TempDir::new("expiry");
+        let storage = StorageManager::new(dir.path().to_path_buf()).unwrap();
+        let mut wallet = funded_wallet();
+        let tx = wallet
+            .send(request(Some(2)), &AcceptAll, |_| {})
+            .await
+            .unwrap();
+        assert_eq!(tx.expiry_height, Some(12));
+        assert_eq!(wallet.spendable_summary().spendable, 0);
+
+        let mut chain = Blockchain::new(fakenet_chain_config());
+        assert!(wallet.expire_transactions(&chain).unwrap().is_empty());
+        // At tip 11 the next block is 12, the last one allowed to include it
+        mine_to(&mut chain, 11, &storage).await;
+        assert!(wallet.expire_transactions(&chain).unwrap().is_empty());
+
+        mine_to(&mut chain, 12, &storage).await;
+        let expired = wallet.expire_transactions(&chain).unwrap();
+        assert_eq!(
+            expired,
+            vec![ExpiredTransaction {
+                tx_id: tx.id.clone(),
+                expiry_height: 12,
+            }]
+        );
+        assert_eq!(
+            expired[0].to_string(),
+            format!("Transaction {} expired at height 12", tx.id)
+        );
+
+        let failed = wallet
+            .transactions()
+            .get_all_transactions()
+            .into_iter()
+            .find(|recorded| recorded.id == tx.id)
+            .unwrap();
+        assert_eq!(
+            failed.status,
+            TransactionStatus::Failed {
+                reason: "expired at height 12".to_string()
+            }
+        );
+        assert_eq!(wallet.spendable_summary().spendable, 1_000);
+        assert!(wallet.expire_transactions(&chain).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn sends_without_an_expiry_never_expire() {
+        let dir = TempDir::new("expiry");
+        let storage = StorageManager::new(dir.path().to_path_buf()).unwrap();
+        let mut wallet = funded_wallet();
+        let tx = wallet
+            .send(request(None), &AcceptAll, |_| {})
+            .await
+            .unwrap();
+        assert_eq!(tx.expiry_height, None);
+
+        let mut chain = Blockchain::new(fakenet_chain_config());
+        mine_to(&mut chain, 20, &storage).await;
+        assert!(wallet.expire_transactions(&chain).unwrap().is_empty());
+        assert_eq!(wallet.transactions().get_pending_transactions().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn an_expiry_needs_a_known_tip() {
+        // Without a tip the note has a single confirmation and couldn't be
+        // selected either, but the missing tip is reported before selection
+        let mut unsynced = Wallet::new();
+        let address = unsynced.generate_key("main").unwrap();
+        unsynced
+            .balances_mut()
+            .add_note(
+                fake_note()
+                    .address(address)
+                    .amount(1_000)
+                    .confirmed_at(1)
+                    .build(),
+            )
+            .unwrap();
+        let error = unsynced
+            .send(request(Some(5)), &AcceptAll, |_| {})
+            .await
+            .unwrap_err();
+        assert_eq!(error.stage, SendStage::SelectFunds);
+        assert!(error.to_string().contains("sync before"), "{}", error);
+        assert!(!error.funds_locked);
+    }
+}
@@ -0,0 +1,306 @@
+//! Views of the local chain for the block explorer pages.
+//!
+//! `Blockchain` hands out blocks by page, hash or height and transactions by
+//! id; these types flatten what the pages show so the UI never holds whole
+//! blocks. A page of the block list is built from one `blocks_page` call, so
+//! a chain of any length costs the same to browse. Blocks whose bodies were
+//! pruned by `Blockchain::compact` show their header with no transactions.
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::wallet::chain::Blockchain;
+use crate::wallet::encoding::Encode;
+use crate::wallet::keys::{NockchainTransaction, OutPoint};
+use crate::wallet::{difficulty, Block};
+
+/// One row of the latest-blocks list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockSummary {
+    pub height: u64,
+    pub hash: String,
+    pub timestamp: DateTime<Utc>,
+    pub tx_count: usize,
+    /// Encoded size in bytes
+    pub size: usize,
+    /// Only the header is left; the transactions were pruned
+    #[serde(default)]
+    pub body_pruned: bool,
+}
+
+impl BlockSummary {
+    pub fn of(chain: &Blockchain, block: &Block) -> Self {
+        Self {
+            height: block.header.height,
+            hash: hex::encode(block.hash()),
+            timestamp: block_time(block),
+            tx_count: block.transactions.len(),
+            size: block.to_bytes().len(),
+            body_pruned: chain.is_pruned(block.header.height),
+        }
+    }
+}
+
+/// A page of the latest-blocks list, newest first
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BlocksPage {
+    pub blocks: Vec<BlockSummary>,
+    /// Blocks in the whole chain
+    pub total: usize,
+    pub offset: usize,
+}
+
+impl BlocksPage {
+    pub fn of(chain: &Blockchain, offset: usize, limit: usize) -> Self {
+        Self {
+            blocks: chain
+                .blocks_page(offset, limit)
+                .into_iter()
+                .map(|block| BlockSummary::of(chain, block))
+                .collect(),
+            total: chain.blocks().len(),
+            offset,
+        }
+    }
+}
+
+/// A transaction as listed in a block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockTransaction {
+    pub tx_id: String,
+    pub coinbase: bool,
+    pub input_count: usize,
+    pub output_count: usize,
+    /// Total paid to the outputs
+    pub output_total: u64,
+}
+
+impl BlockTransaction {
+    fn of(tx: &NockchainTransaction) -> Self {
+        Self {
+            tx_id: tx.id.clone(),
+            coinbase: tx.inputs.is_empty(),
+            input_count: tx.inputs.len(),
+            output_count: tx.outputs.len(),
+            output_total: tx.outputs.iter().map(|output| output.amount).sum(),
+        }
+    }
+}
+
+/// Everything the block detail page shows
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockView {
+    pub summary: BlockSummary,
+    pub version: u32,
+    pub previous_hash: String,
+    pub merkle_root: String,
+    pub bits: u32,
+    pub difficulty: f64,
+    pub nonce: u64,
+    /// Address the coinbase's first output pays
+    pub miner_address: Option<String>,
+    /// Height of the next block, if the chain has one
+    pub next_height: Option<u64>,
+    pub transactions: Vec<BlockTransaction>,
+}
+
+impl BlockView {
+    pub fn of(chain: &Blockchain, block: &Block) -> Self {
+        let header = &block.header;
+        let next_height = header.height + 1;
+        Self {
+            summary: BlockSummary::of(chain, block),
+            version: header.version,
+            previous_hash: hex::encode(header.previous_hash),
+            merkle_root: hex::encode(header.merkle_root),
+            bits: header.bits,
+            difficulty: difficulty(header.bits),
+            nonce: header.nonce,
+            miner_address: block
+                .transactions
+                .first()
+                .filter(|tx| tx.inputs.is_empty())
+                .and_then(|coinbase| coinbase.outputs.first())
+                .map(|output| output.recipient_address.clone()),
+            next_height: chain.block_at(next_height).map(|_| next_height),
+            transactions: block
+                .transactions
+                .iter()
+                .map(BlockTransaction::of)
+                .collect(),
+        }
+    }
+
+    /// Block `id`, a height or a hex hash, on `chain`
+    pub fn find(chain: &Blockchain, id: &str) -> Option<Self> {
+        let block = match id.parse::<u64>() {
+            Ok(height) => chain.block_by_height(height),
+            Err(_) => {
+                let hash: [u8; 32] = hex::decode(id).ok()?.try_into().ok()?;
+                chain.block_by_hash(&hash)
+            }
+        }?;
+        Some(Self::of(chain, block))
+    }
+}
+
+/// A transaction on the chain, with the block containing it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChainTransactionView {
+    pub tx_id: String,
+    pub block_height: u64,
+    pub block_hash: String,
+    pub block_time: DateTime<Utc>,
+    /// Blocks containing and burying it
+    pub confirmations: u64,
+    pub coinbase: bool,
+    pub inputs: Vec<(OutPoint, u64)>,
+    pub outputs: Vec<(String, u64)>,
+    /// Inputs less outputs; 0 for the coinbase
+    pub fee: u64,
+}
+
+impl ChainTransactionView {
+    pub fn find(chain: &Blockchain, tx_id: &str) -> Option<Self> {
+        let (block, tx) = chain.transaction_by_id(tx_id)?;
+        let height = block.header.height;
+        let input_total: u64 = tx.inputs.iter().map(|input| input.amount).sum();
+        let output_total: u64 = tx.outputs.iter().map(|output| output.amount).sum();
+        Some(Self {
+            tx_id: tx.id.clone(),
+            block_height: height,
+            block_hash: hex::encode(block.hash()),
+            block_time: block_time(block),
+            confirmations: chain
+                .height()
+                .map_or(1, |tip| tip.saturating_sub(height) + 1),
+            coinbase: tx.inputs.is_empty(),
+            inputs: tx
+                .inputs
+                .iter()
+                .map(|input| (input.previous_output.clone(), input.amount))
+                .collect(),
+            outputs: tx
+                .outputs
+                .iter()
+                .map(|output| (output.recipient_address.clone(), output.amount))
+                .collect(),
+            fee: input_total.saturating_sub(output_total),
+        })
+    }
+}
+
+fn block_time(block: &Block) -> DateTime<Utc> {
+    Utc.timestamp_opt(block.header.timestamp as i64, 0)
+        .single()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_address, fake_block, fake_coinbase, fakenet_chain_config};
+
+    /// Genesis, then two blocks each paying a coinbase to `miner`; the first
+    /// of them also carries a payment spending 1,000 for 990
+    fn explored_chain(miner: &crate::wallet::Address) -> Blockchain {
+        let mut chain = Blockchain::new(fakenet_chain_config());
+        for height in 0..3 {
+            let mut transactions = Vec::new();
+            if height > 0 {
+                transactions.push(fake_coinbase(miner, 5_000));
+            }
+            if height == 1 {
+                let mut payment = fake_block().with_txs(1).build().transactions.remove(0);
+                payment.outputs[0].amount = 990;
+                transactions.push(payment);
+            }
+            let mut block = chain.new_block(transactions, chain.config().initial_difficulty);
+            block.mine().unwrap();
+            chain.add_block(block).unwrap();
+        }
+        chain
+    }
+
+    #[test]
+    fn a_page_summarises_its_blocks_newest_first() {
+        let chain = explored_chain(&fake_address());
+        let page = BlocksPage::of(&chain, 1, 10);
+        assert_eq!(page.total, 3);
+        assert_eq!(page.offset, 1);
+        let heights: Vec<u64> = page.blocks.iter().map(|block| block.height).collect();
+        assert_eq!(heights, vec![1, 0]);
+
+        let block = chain.block_at(1).unwrap();
+        let summary = &page.blocks[0];
+        assert_eq!(summary.hash, hex::encode(block.hash()));
+        assert_eq!(summary.tx_count, 2);
+        assert_eq!(summary.size, block.to_bytes().len());
+        assert_eq!(summary.timestamp.timestamp() as u64, block.header.timestamp);
+        assert!(!summary.body_pruned);
+
+        assert!(BlocksPage::of(&chain, 3, 10).blocks.is_empty());
+    }
+
+    #[test]
+    fn a_block_is_found_by_height_or_hash_and_links_its_neighbours() {
+        let miner = fake_address();
+        let chain = explored_chain(&miner);
+        let by_height = BlockView::find(&chain, "1").unwrap();
+        let by_hash = BlockView::find(&chain, &by_height.summary.hash).unwrap();
+        assert_eq!(by_height, by_hash);
+
+        let genesis = chain.block_at(0).unwrap();
+        assert_eq!(by_height.previous_hash, hex::encode(genesis.hash()));
+        assert_eq!(by_height.next_height, Some(2));
+        assert_eq!(by_height.miner_address, Some(miner.to_string()));
+        assert_eq!(by_height.transactions.len(), 2);
+        assert!(by_height.transactions[0].coinbase);
+        assert_eq!(by_height.transactions[0].output_total, 5_000);
+        assert!(!by_height.transactions[1].coinbase);
+        assert_eq!(by_height.transactions[1].input_count, 1);
+
+        let tip = BlockView::find(&chain, "2").unwrap();
+        assert_eq!(tip.next_height, None);
+        assert_eq!(BlockView::find(&chain, "0").unwrap().miner_address, None);
+
+        assert!(BlockView::find(&chain, "3").is_none());
+        assert!(BlockView::find(&chain, "not hex").is_none());
+        assert!(BlockView::find(&chain, &"ab".repeat(32)).is_none());
+    }
+
+    #[test]
+    fn a_transaction_shows_its_block_confirmations_and_fee() {
+        let chain = explored_chain(&fake_address());
+        let block = chain.block_at(1).unwrap();
+        let payment = &block.transactions[1];
+        let view = ChainTransactionView::find(&chain, &payment.id).unwrap();
+        assert_eq!(view.block_height, 1);
+        assert_eq!(view.block_hash, hex::encode(block.hash()));
+        assert_eq!(view.confirmations, 2);
+        assert!(!view.coinbase);
+        assert_eq!(view.fee, 10);
+        assert_eq!(
+            view.inputs,
+            vec![(payment.inputs[0].previous_output.clone(), 1_000)]
+        );
+        assert_eq!(view.outputs[0].1, 990);
+
+        let coinbase = ChainTransactionView::find(&chain, &block.transactions[0].id).unwrap();
+        assert!(coinbase.coinbase);
+        assert_eq!(coinbase.fee, 0);
+        assert!(ChainTransactionView::find(&chain, "missing").is_none());
+    }
+
+    #[test]
+    fn pruned_blocks_show_their_header_alone() {
+        let mut chain = explored_chain(&fake_address());
+        chain.compact(2);
+        let page = BlocksPage::of(&chain, 0, 10);
+        let pruned: Vec<bool> = page.blocks.iter().map(|block| block.body_pruned).collect();
+        assert_eq!(pruned, vec![false, true, true]);
+        let view = BlockView::find(&chain, "1").unwrap();
+        assert!(view.summary.body_pruned);
+        assert!(view.transactions.is_empty());
+    }
+}
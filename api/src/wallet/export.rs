@@ -0,0 +1,563 @@
+//! Transaction history, the ledger and logs written out as CSV or JSON.
+//!
+//! Exports stream: each row is serialized on its own into a buffered writer,
+//! so memory use doesn't grow with the number of rows. After every row the
+//! caller's `progress` callback gets the count written so far and returns
+//! `false` to cancel. A cancelled export ends with `EXPORT_INCOMPLETE_MARKER`
+//! on a line of its own, which leaves a JSON export unparseable too, and
+//! `export_to_file` keeps it under a `.partial` name, so a cut-short export
+//! can't pass for a whole one.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::wallet::ledger::LedgerEntry;
+use crate::wallet::network::LogEntry;
+use crate::wallet::{
+    Transaction, TransactionDirection, TransactionStatus, WalletError, WalletResult,
+};
+
+/// Last line of an export that was cancelled before its last row
+pub const EXPORT_INCOMPLETE_MARKER: &str = "# INCOMPLETE EXPORT: cancelled before the last row";
+
+/// Bytes buffered before they are handed to the destination
+const EXPORT_BUFFER: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    /// Format named by the extension of `path`, if it names one
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?;
+        if extension.eq_ignore_ascii_case("csv") {
+            Some(ExportFormat::Csv)
+        } else if extension.eq_ignore_ascii_case("json") {
+            Some(ExportFormat::Json)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportFormat::Csv => write!(f, "CSV"),
+            ExportFormat::Json => write!(f, "JSON"),
+        }
+    }
+}
+
+/// Which transactions `TransactionManager::export_to_writer` writes
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExportFilter {
+    pub direction: Option<TransactionDirection>,
+    /// Created at or after
+    pub since: Option<DateTime<Utc>>,
+    /// Created before
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl ExportFilter {
+    pub fn matches(&self, transaction: &Transaction) -> bool {
+        self.direction
+            .is_none_or(|direction| transaction.direction == direction)
+            && self
+                .since
+                .is_none_or(|since| transaction.created_at >= since)
+            && self
+                .until
+                .is_none_or(|until| transaction.created_at < until)
+    }
+}
+
+/// What an export wrote
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportSummary {
+    pub rows: u64,
+    /// False when it was cancelled before the last row
+    pub complete: bool,
+}
+
+/// A row of an export, in both formats
+pub(crate) trait ExportRecord: Serialize {
+    const COLUMNS: &'static [&'static str];
+
+    /// Values in the order of `COLUMNS`, for CSV
+    fn fields(&self) -> Vec<String>;
+}
+
+/// One transaction as exported
+#[derive(Debug, Serialize)]
+pub(crate) struct TransactionRow<'a> {
+    id: &'a str,
+    status: &'static str,
+    block_height: Option<u64>,
+    direction: &'static str,
+    amount: u64,
+    /// `None` for a spend signed elsewhere whose fee isn't known
+    fee: Option<u64>,
+    from_address: Option<String>,
+    to_address: Option<String>,
+    created_at: DateTime<Utc>,
+    confirmed_at: Option<DateTime<Utc>>,
+    external: bool,
+    label: Option<&'a str>,
+}
+
+impl<'a> TransactionRow<'a> {
+    pub(crate) fn new(transaction: &'a Transaction, label: Option<&'a str>) -> Self {
+        let (status, block_height) = match &transaction.status {
+            TransactionStatus::Pending => ("pending", None),
+            TransactionStatus::Confirmed { block_height } => ("confirmed", Some(*block_height)),
+            TransactionStatus::Failed { .. } => ("failed", None),
+        };
+        let direction = match transaction.direction {
+            TransactionDirection::Incoming => "incoming",
+            TransactionDirection::Outgoing => "outgoing",
+            TransactionDirection::Internal => "internal",
+        };
+        Self {
+            id: &transaction.id,
+            status,
+            block_height,
+            direction,
+            amount: transaction.amount,
+            fee: transaction.fee_known().then_some(transaction.fee),
+            from_address: transaction.from_address.as_ref().map(|a| a.to_string()),
+            to_address: transaction.to_address.as_ref().map(|a| a.to_string()),
+            created_at: transaction.created_at,
+            confirmed_at: transaction.confirmed_at,
+            external: transaction.is_external(),
+            label,
+        }
+    }
+}
+
+impl ExportRecord for TransactionRow<'_> {
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "status",
+        "block_height",
+        "direction",
+        "amount",
+        "fee",
+        "from_address",
+        "to_address",
+        "created_at",
+        "confirmed_at",
+        "external",
+        "label",
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.status.to_string(),
+            optional(self.block_height),
+            self.direction.to_string(),
+            self.amount.to_string(),
+            optional(self.fee),
+            self.from_address.clone().unwrap_or_default(),
+            self.to_address.clone().unwrap_or_default(),
+            timestamp(&self.created_at),
+            optional(self.confirmed_at.as_ref().map(timestamp)),
+            self.external.to_string(),
+            self.label.unwrap_or_default().to_string(),
+        ]
+    }
+}
+
+impl ExportRecord for &LogEntry {
+    const COLUMNS: &'static [&'static str] =
+        &["timestamp", "level", "source", "message", "repeat_count"];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            timestamp(&self.timestamp),
+            format!("{:?}", self.level),
+            format!("{:?}", self.source),
+            self.message.clone(),
+            optional(self.repeat.as_ref().map(|repeat| repeat.count)),
+        ]
+    }
+}
+
+impl ExportRecord for &LedgerEntry {
+    const COLUMNS: &'static [&'static str] = &[
+        "sequence",
+        "recorded_at",
+        "event",
+        "account",
+        "counter_account",
+        "amount",
+        "tx_id",
+        "note_id",
+        "block_height",
+        "reverses",
+        "balance_after",
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.sequence.to_string(),
+            timestamp(&self.recorded_at),
+            self.event.to_string(),
+            self.account.to_string(),
+            self.counter_account.to_string(),
+            self.amount.to_string(),
+            self.tx_id.clone().unwrap_or_default(),
+            optional(self.note_id),
+            optional(self.block_height),
+            optional(self.reverses),
+            self.balance_after.to_string(),
+        ]
+    }
+}
+
+/// A time as JSON exports write it
+fn timestamp(at: &DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+fn optional(value: Option<impl ToString>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// Write log `entries` in order as `format`, streaming each row
+pub fn export_logs_to_writer<'a>(
+    writer: impl Write,
+    entries: impl IntoIterator<Item = &'a LogEntry>,
+    format: ExportFormat,
+    progress: impl FnMut(u64) -> bool,
+) -> WalletResult<ExportSummary> {
+    write_records(writer, format, entries, progress)
+}
+
+/// Write ledger `entries` in order as `format`, streaming each row
+pub fn export_ledger_to_writer<'a>(
+    writer: impl Write,
+    entries: impl IntoIterator<Item = &'a LedgerEntry>,
+    format: ExportFormat,
+    progress: impl FnMut(u64) -> bool,
+) -> WalletResult<ExportSummary> {
+    write_records(writer, format, entries, progress)
+}
+
+/// Stream `records` to `writer` as `format`, stopping early when `progress`
+/// returns false
+pub(crate) fn write_records<R: ExportRecord>(
+    writer: impl Write,
+    format: ExportFormat,
+    records: impl IntoIterator<Item = R>,
+    mut progress: impl FnMut(u64) -> bool,
+) -> WalletResult<ExportSummary> {
+    let io_err = |e: std::io::Error| WalletError::Storage(format!("Failed to write export: {}", e));
+    let json_err =
+        |e: serde_json::Error| WalletError::Serialization(format!("Failed to export row: {}", e));
+
+    let mut out = BufWriter::with_capacity(EXPORT_BUFFER, writer);
+    match format {
+        ExportFormat::Csv => write_csv_line(&mut out, R::COLUMNS.iter().copied()),
+        ExportFormat::Json => out.write_all(b"["),
+    }
+    .map_err(io_err)?;
+
+    let mut records = records.into_iter().peekable();
+    let mut rows = 0;
+    while let Some(record) = records.next() {
+        match format {
+            ExportFormat::Csv => {
+                let fields = record.fields();
+                write_csv_line(&mut out, fields.iter().map(String::as_str)).map_err(io_err)?;
+            }
+            ExportFormat::Json => {
+                out.write_all(if rows == 0 { b"\n" } else { b",\n" })
+                    .map_err(io_err)?;
+                serde_json::to_writer(&mut out, &record).map_err(json_err)?;
+            }
+        }
+        rows += 1;
+
+        if !progress(rows) && records.peek().is_some() {
+            writeln!(out, "\n{}", EXPORT_INCOMPLETE_MARKER).map_err(io_err)?;
+            out.flush().map_err(io_err)?;
+            return Ok(ExportSummary {
+                rows,
+                complete: false,
+            });
+        }
+    }
+
+    if format == ExportFormat::Json {
+        out.write_all(b"\n]\n").map_err(io_err)?;
+    }
+    out.flush().map_err(io_err)?;
+    Ok(ExportSummary {
+        rows,
+        complete: true,
+    })
+}
+
+/// One CSV record, quoting fields that hold a comma, quote or line break
+fn write_csv_line<'a>(
+    out: &mut impl Write,
+    fields: impl Iterator<Item = &'a str>,
+) -> std::io::Result<()> {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        if field.contains([',', '"', '\n', '\r']) {
+            write!(out, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            out.write_all(field.as_bytes())?;
+        }
+    }
+    out.write_all(b"\n")
+}
+
+/// Run `write` against a `.partial` file next to `path` and move it to `path`
+/// once the export is complete. A cancelled export stays under the `.partial`
+/// name; a failed one is removed. Returns where the export ended up.
+pub fn export_to_file(
+    path: &Path,
+    write: impl FnOnce(&mut File) -> WalletResult<ExportSummary>,
+) -> WalletResult<(PathBuf, ExportSummary)> {
+    let io_err = |e: std::io::Error| WalletError::Storage(format!("Failed to write export: {}", e));
+    let mut partial_name = path.file_name().unwrap_or_default().to_os_string();
+    partial_name.push(".partial");
+    let partial = path.with_file_name(partial_name);
+
+    let result = File::create(&partial).map_err(io_err).and_then(|mut file| {
+        let summary = write(&mut file)?;
+        file.sync_all().map_err(io_err)?;
+        Ok(summary)
+    });
+    match result {
+        Ok(summary) if summary.complete => {
+            std::fs::rename(&partial, path).map_err(io_err)?;
+            Ok((path.to_path_buf(), summary))
+        }
+        Ok(summary) => Ok((partial, summary)),
+        Err(e) => {
+            let _ = std::fs::remove_file(&partial);
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fixture_time, TempDir};
+    use crate::wallet::network::{LogLevel, LogSource};
+
+    /// Synthetic row, made on demand so the only copy of the export in
+    /// memory is whatever the writer holds
+    #[derive(Serialize)]
+    struct Row {
+        n: u64,
+        memo: String,
+    }
+
+    impl ExportRecord for Row {
+        const COLUMNS: &'static [&'static str] = &["n", "memo"];
+
+        fn fields(&self) -> Vec<String> {
+            vec![self.n.to_string(), self.memo.clone()]
+        }
+    }
+
+    fn rows(count: u64) -> impl Iterator<Item = Row> {
+        (0..count).map(|n| Row {
+            n,
+            memo: format!("payment {}", n),
+        })
+    }
+
+    /// Destination that keeps only counts, failing any write larger than
+    /// the export buffer
+    #[derive(Default)]
+    struct Ceiling {
+        bytes: usize,
+        lines: usize,
+        largest_write: usize,
+    }
+
+    impl Write for Ceiling {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            assert!(
+                buf.len() <= EXPORT_BUFFER,
+                "write of {} bytes is over the buffer",
+                buf.len()
+            );
+            self.bytes += buf.len();
+            self.lines += buf.iter().filter(|&&b| b == b'\n').count();
+            self.largest_write = self.largest_write.max(buf.len());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn log(message: &str) -> LogEntry {
+        LogEntry {
+            timestamp: fixture_time(0),
+            level: LogLevel::Info,
+            message: message.to_string(),
+            source: LogSource::Node,
+            operation_id: None,
+            repeat: None,
+        }
+    }
+
+    #[test]
+    fn a_hundred_thousand_rows_stream_through_a_bounded_buffer() {
+        for format in [ExportFormat::Csv, ExportFormat::Json] {
+            let mut out = Ceiling::default();
+            let mut reported = 0;
+            let summary = write_records(&mut out, format, rows(100_000), |written| {
+                assert_eq!(written, reported + 1);
+                reported = written;
+                true
+            })
+            .unwrap();
+
+            assert_eq!(
+                summary,
+                ExportSummary {
+                    rows: 100_000,
+                    complete: true
+                }
+            );
+            // A line per row, plus the header or the closing bracket
+            let expected = match format {
+                ExportFormat::Csv => 100_001,
+                ExportFormat::Json => 100_002,
+            };
+            assert_eq!(out.lines, expected, "{}", format);
+            assert!(out.bytes > 2 * EXPORT_BUFFER);
+            assert!(out.largest_write <= EXPORT_BUFFER);
+        }
+    }
+
+    #[test]
+    fn a_cancelled_export_ends_with_the_marker() {
+        let mut csv = Vec::new();
+        let summary = write_records(&mut csv, ExportFormat::Csv, rows(10), |n| n < 3).unwrap();
+        assert_eq!(
+            summary,
+            ExportSummary {
+                rows: 3,
+                complete: false
+            }
+        );
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "n,memo");
+        assert_eq!(lines[3], "2,payment 2");
+        assert_eq!(lines.last(), Some(&EXPORT_INCOMPLETE_MARKER));
+
+        let mut json = Vec::new();
+        write_records(&mut json, ExportFormat::Json, rows(10), |n| n < 3).unwrap();
+        assert!(serde_json::from_slice::<serde_json::Value>(&json).is_err());
+
+        let mut json = Vec::new();
+        write_records(&mut json, ExportFormat::Json, rows(5), |_| true).unwrap();
+        let parsed: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(parsed.as_array().map(Vec::len), Some(5));
+    }
+
+    #[test]
+    fn cancelling_on_the_last_row_still_completes() {
+        let mut csv = Vec::new();
+        let summary = write_records(&mut csv, ExportFormat::Csv, rows(3), |_| false).unwrap();
+        assert_eq!(summary.rows, 1);
+        assert!(!summary.complete);
+
+        let mut csv = Vec::new();
+        let summary = write_records(&mut csv, ExportFormat::Csv, rows(3), |n| n < 3).unwrap();
+        assert!(summary.complete);
+        assert!(!String::from_utf8(csv)
+            .unwrap()
+            .contains(EXPORT_INCOMPLETE_MARKER));
+    }
+
+    #[test]
+    fn csv_fields_with_commas_quotes_and_line_breaks_are_quoted() {
+        let entries = [log("plain"), log("a, b"), log("say \"hi\"\nbye")];
+        let mut csv = Vec::new();
+        export_logs_to_writer(&mut csv, &entries, ExportFormat::Csv, |_| true).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.starts_with("timestamp,level,source,message,repeat_count\n"));
+        assert!(csv.contains(",Info,Node,plain,\n"));
+        assert!(csv.contains(",\"a, b\","));
+        assert!(csv.contains(",\"say \"\"hi\"\"\nbye\","));
+    }
+
+    #[test]
+    fn export_to_file_moves_into_place_only_when_complete() {
+        let dir = TempDir::new("export");
+        let path = dir.join("history.csv");
+        let partial = dir.join("history.csv.partial");
+
+        let (written, summary) = export_to_file(&path, |file| {
+            write_records(file, ExportFormat::Csv, rows(10), |n| n < 4)
+        })
+        .unwrap();
+        assert_eq!(written, partial);
+        assert!(!summary.complete);
+        assert!(!path.exists());
+        let kept = std::fs::read_to_string(&partial).unwrap();
+        assert!(kept.trim_end().ends_with(EXPORT_INCOMPLETE_MARKER));
+
+        let (written, summary) = export_to_file(&path, |file| {
+            write_records(file, ExportFormat::Csv, rows(10), |_| true)
+        })
+        .unwrap();
+        assert_eq!(written, path);
+        assert_eq!(summary.rows, 10);
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 11);
+        assert!(!partial.exists());
+
+        let failed = export_to_file(&dir.join("broken.json"), |_| {
+            Err(WalletError::Storage("disk gone".to_string()))
+        });
+        assert!(failed.is_err());
+        assert!(!dir.join("broken.json.partial").exists());
+        assert!(!dir.join("broken.json").exists());
+    }
+
+    #[test]
+    fn formats_follow_the_file_extension() {
+        assert_eq!(
+            ExportFormat::from_path(Path::new("out.CSV")),
+            Some(ExportFormat::Csv)
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("out.json")),
+            Some(ExportFormat::Json)
+        );
+        assert_eq!(ExportFormat::from_path(Path::new("out.txt")), None);
+        assert_eq!(ExportFormat::from_path(Path::new("out")), None);
+    }
+}
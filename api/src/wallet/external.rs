@@ -0,0 +1,216 @@
+//! Spends of the wallet's notes signed somewhere else.
+//!
+//! The wallet records the transactions it sends and the payments it receives,
+//! but the notes of a watch-only address are spent by whichever wallet holds
+//! its key, and an imported key may be used elsewhere too. When sync finds a
+//! confirmed transaction spending a note the wallet holds that it has no
+//! record of, the transaction is recorded as one the wallet funded, the notes
+//! are marked spent and an `ExternalSpend` is reported. Its fee is the inputs
+//! less the outputs when every input was the wallet's, and unknown otherwise.
+
+use chrono::{TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::wallet::amount::{format_amount, AmountUnit};
+use crate::wallet::transaction::SignedTransaction;
+use crate::wallet::{Address, Block, Wallet, WalletResult};
+
+/// A transaction the wallet didn't create, found spending its notes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExternalSpend {
+    pub tx_id: String,
+    /// Paid to addresses outside the wallet
+    pub amount: u64,
+    /// `None` when not every input was the wallet's
+    pub fee: Option<u64>,
+    pub block_height: u64,
+    /// Notes of the wallet it spent
+    pub notes: usize,
+}
+
+impl fmt::Display for ExternalSpend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Spend of {} NOCK signed outside this wallet confirmed in block {}",
+            format_amount(self.amount, AmountUnit::Nock),
+            self.block_height
+        )
+    }
+}
+
+impl Wallet {
+    /// Record the transactions in `block` that spend the wallet's notes and
+    /// aren't recorded yet, marking those notes spent. Outputs of `block`
+    /// paying the wallet should be recorded first, so change coming back to it
+    /// is counted as its own.
+    pub fn record_external_spends(&mut self, block: &Block) -> WalletResult<Vec<ExternalSpend>> {
+        self.ensure_writable()?;
+        let confirmed_at = Utc
+            .timestamp_opt(block.header.timestamp as i64, 0)
+            .single()
+            .unwrap_or_else(Utc::now);
+
+        let mut spends = Vec::new();
+        for tx in &block.transactions {
+            if self.transactions.contains(&tx.id) {
+                continue;
+            }
+            let spent: Vec<(Uuid, Address, u64, bool)> = tx
+                .inputs
+                .iter()
+                .filter_map(|input| self.balances.note_at(input.outpoint()))
+                .map(|note| (note.id, note.address.clone(), note.amount, note.spent))
+                .collect();
+            if spent.is_empty() {
+                continue;
+            }
+
+            let fee = (spent.len() == tx.inputs.len()).then(|| {
+                let outputs: u64 = tx.outputs.iter().map(|output| output.amount).sum();
+                spent
+                    .iter()
+                    .map(|(_, _, amount, _)| amount)
+                    .sum::<u64>()
+                    .saturating_sub(outputs)
+            });
+            for (note_id, _, _, already_spent) in &spent {
+                if !already_spent {
+                    self.balances.spend_note(*note_id)?;
+                }
+            }
+            if let Some(fee) = fee {
+                self.balances.record_fee(&tx.id, fee);
+            }
+
+            let signed = SignedTransaction {
+                id: tx.id.clone(),
+                chain_id: self.chain_id,
+                inputs: tx.inputs.clone(),
+                outputs: tx.outputs.clone(),
+                fee: fee.unwrap_or(0),
+                expiry_height: tx.expiry_height,
+                signature: Vec::new(),
+                hash: tx.hash.clone(),
+            };
+            let keys = &self.keys;
+            let recorded = self.transactions.add_external_spend(
+                signed,
+                fee.is_some(),
+                spent.first().map(|(_, address, _, _)| address.clone()),
+                block.header.height,
+                confirmed_at,
+                |address| keys.is_mine(address),
+            );
+
+            let spend = ExternalSpend {
+                tx_id: tx.id.clone(),
+                amount: recorded.amount,
+                fee,
+                block_height: block.header.height,
+                notes: spent.len(),
+            };
+            info!("{} ({})", spend, spend.tx_id);
+            spends.push(spend);
+        }
+        Ok(spends)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_address, fake_block, fake_note, fake_txid};
+    use crate::wallet::keys::{OutPoint, TransactionInput};
+    use crate::wallet::{Note, TransactionDirection, TransactionOrigin, TransactionStatus};
+
+    /// Wallet watching one address that holds a confirmed note of 5,000
+    fn watching() -> (Wallet, Address, Note) {
+        let mut wallet = Wallet::new();
+        let watched = fake_address();
+        wallet.keys_mut().unwrap().add_watch_only(watched.clone());
+        let note = fake_note()
+            .address(watched.clone())
+            .amount(5_000)
+            .confirmed_at(1)
+            .build();
+        wallet.balances_mut().add_note(note.clone()).unwrap();
+        wallet.balances_mut().set_tip_height(10);
+        (wallet, watched, note)
+    }
+
+    #[test]
+    fn a_spend_of_a_watched_note_is_recorded_as_external() {
+        let (mut wallet, watched, note) = watching();
+        let payee = fake_address();
+        let block = fake_block()
+            .height(12)
+            .spending(&note, &payee, 4_900)
+            .build();
+
+        let spends = wallet.record_external_spends(&block).unwrap();
+        let tx_id = block.transactions[0].id.clone();
+        assert_eq!(
+            spends,
+            vec![ExternalSpend {
+                tx_id: tx_id.clone(),
+                amount: 4_900,
+                fee: Some(100),
+                block_height: 12,
+                notes: 1,
+            }]
+        );
+        assert!(spends[0].to_string().ends_with("confirmed in block 12"));
+
+        assert_eq!(wallet.balances().get_balance(&watched).total(), 0);
+        assert_eq!(wallet.spendable_summary().watch_only, 0);
+        let recorded = &wallet.transactions().get_confirmed_transactions()[0];
+        assert_eq!(recorded.id, tx_id);
+        assert_eq!(
+            recorded.status,
+            TransactionStatus::Confirmed { block_height: 12 }
+        );
+        assert_eq!(recorded.direction, TransactionDirection::Outgoing);
+        assert_eq!(recorded.amount, 4_900);
+        assert_eq!(recorded.fee, 100);
+        assert_eq!(recorded.from_address, Some(watched));
+        assert_eq!(recorded.to_address, Some(payee));
+        assert!(recorded.is_external() && recorded.fee_known());
+
+        // Seeing the block again records nothing more
+        assert!(wallet.record_external_spends(&block).unwrap().is_empty());
+        assert_eq!(wallet.transactions().count(), 1);
+    }
+
+    #[test]
+    fn the_fee_is_unknown_when_another_input_was_not_the_wallets() {
+        let (mut wallet, _, note) = watching();
+        let mut block = fake_block().spending(&note, &fake_address(), 6_000).build();
+        block.transactions[0].inputs.push(TransactionInput {
+            previous_output: OutPoint::new(fake_txid(), 0),
+            amount: 2_000,
+        });
+
+        let spends = wallet.record_external_spends(&block).unwrap();
+        assert_eq!(spends[0].fee, None);
+        let recorded = &wallet.transactions().get_confirmed_transactions()[0];
+        assert_eq!(
+            recorded.origin,
+            TransactionOrigin::External { fee_known: false }
+        );
+        assert!(!recorded.fee_known());
+        assert_eq!(recorded.fee, 0);
+    }
+
+    #[test]
+    fn transactions_not_touching_the_wallets_notes_are_ignored() {
+        let (mut wallet, _, _) = watching();
+        let block = fake_block().with_txs(3).build();
+        assert!(wallet.record_external_spends(&block).unwrap().is_empty());
+        assert_eq!(wallet.transactions().count(), 0);
+        assert_eq!(wallet.spendable_summary().watch_only, 5_000);
+    }
+}
@@ -0,0 +1,641 @@
+//! Fee rates paid in confirmed blocks, and fee suggestions drawn from them.
+//!
+//! Each recorded block keeps the 25th, 50th and 90th percentile fee rate of
+//! its transactions. A suggestion takes, over the last `FEE_ESTIMATE_BLOCKS`
+//! blocks that had any, the median of each block's percentile for the
+//! priority, so one block of giveaway or absurd fees doesn't move it. Until
+//! `MIN_FEE_HISTORY_BLOCKS` such blocks are known the static fee is used.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::ops::RangeBounds;
+
+use crate::wallet::chain::Blockchain;
+use crate::wallet::encoding::Encode;
+use crate::wallet::storage::StorageManager;
+use crate::wallet::templates::FeePriority;
+use crate::wallet::{Block, Wallet, WalletResult};
+
+/// Fee history, saved through `StorageManager` in the wallet data dir
+pub const FEE_HISTORY_FILE: &str = "fee_history.json";
+
+/// Blocks kept in the fee history; older ones are forgotten
+pub const FEE_HISTORY_BLOCKS: usize = 1000;
+
+/// Most recent blocks with transactions that suggestions are drawn from
+pub const FEE_ESTIMATE_BLOCKS: usize = 50;
+
+/// Blocks with transactions needed before suggestions replace the static fee
+pub const MIN_FEE_HISTORY_BLOCKS: usize = 6;
+
+/// Fee rates of one block's transactions, in base units per encoded byte
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FeeRates {
+    pub p25: f64,
+    pub median: f64,
+    pub p90: f64,
+    /// Median encoded size of the block's transactions, in bytes
+    pub median_size: u64,
+}
+
+impl FeeRates {
+    /// Rates of transactions given as (fee, encoded size); `None` if there
+    /// are none with a size
+    pub fn from_fees(fees: impl IntoIterator<Item = (u64, usize)>) -> Option<Self> {
+        let fees: Vec<(u64, usize)> = fees.into_iter().filter(|(_, size)| *size > 0).collect();
+        let mut rates: Vec<f64> = fees
+            .iter()
+            .map(|(fee, size)| *fee as f64 / *size as f64)
+            .collect();
+        let mut sizes: Vec<u64> = fees.iter().map(|(_, size)| *size as u64).collect();
+        rates.sort_by(f64::total_cmp);
+        sizes.sort_unstable();
+        Some(Self {
+            p25: percentile(&rates, 25)?,
+            median: percentile(&rates, 50)?,
+            p90: percentile(&rates, 90)?,
+            median_size: percentile(&sizes, 50)?,
+        })
+    }
+
+    /// Rate paid at `priority`: the 25th, 50th or 90th percentile
+    pub fn rate(&self, priority: FeePriority) -> f64 {
+        match priority {
+            FeePriority::Low => self.p25,
+            FeePriority::Normal => self.median,
+            FeePriority::High => self.p90,
+        }
+    }
+}
+
+/// Fees seen in one confirmed block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockFees {
+    pub height: u64,
+    /// Transactions other than the coinbase
+    pub tx_count: usize,
+    /// `None` for a block with nothing but the coinbase
+    pub rates: Option<FeeRates>,
+}
+
+impl BlockFees {
+    /// Fees of `block`'s transactions, each the inputs less the outputs.
+    /// The coinbase, having no inputs, pays none and is left out.
+    pub fn from_block(block: &Block) -> Self {
+        let fees: Vec<(u64, usize)> = block
+            .transactions
+            .iter()
+            .filter(|tx| !tx.inputs.is_empty())
+            .map(|tx| {
+                let inputs: u64 = tx.inputs.iter().map(|input| input.amount).sum();
+                let outputs: u64 = tx.outputs.iter().map(|output| output.amount).sum();
+                (inputs.saturating_sub(outputs), tx.to_bytes().len())
+            })
+            .collect();
+        Self {
+            height: block.header.height,
+            tx_count: fees.len(),
+            rates: FeeRates::from_fees(fees),
+        }
+    }
+}
+
+/// Rolling history of block fees, oldest first
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeeEstimator {
+    blocks: VecDeque<BlockFees>,
+}
+
+impl FeeEstimator {
+    /// Load the saved history, or an empty one if none was saved
+    pub async fn load(storage: &StorageManager) -> WalletResult<Self> {
+        if !storage.exists(FEE_HISTORY_FILE) {
+            return Ok(Self::default());
+        }
+        storage.load(FEE_HISTORY_FILE).await
+    }
+
+    pub async fn save(&self, storage: &StorageManager) -> WalletResult<()> {
+        storage.save(FEE_HISTORY_FILE, self).await
+    }
+
+    /// Add a block's fees, replacing any recorded at the same height (a
+    /// reorg), and forget blocks beyond `FEE_HISTORY_BLOCKS`
+    pub fn record(&mut self, fees: BlockFees) {
+        let index = self
+            .blocks
+            .partition_point(|block| block.height < fees.height);
+        match self.blocks.get_mut(index) {
+            Some(block) if block.height == fees.height => *block = fees,
+            _ => self.blocks.insert(index, fees),
+        }
+        while self.blocks.len() > FEE_HISTORY_BLOCKS {
+            self.blocks.pop_front();
+        }
+    }
+
+    pub fn record_block(&mut self, block: &Block) {
+        self.record(BlockFees::from_block(block));
+    }
+
+    /// Forget the oldest blocks: those below `min_height`, then more until
+    /// the rest serialize to at most `max_bytes`. Returns the blocks
+    /// forgotten and their serialized size.
+    pub fn prune(&mut self, min_height: Option<u64>, max_bytes: Option<u64>) -> (usize, u64) {
+        let size =
+            |block: &BlockFees| serde_json::to_vec(block).map_or(0, |json| json.len() as u64);
+        let mut total: u64 = self.blocks.iter().map(size).sum();
+        let mut removed = (0, 0);
+        while let Some(oldest) = self.blocks.front() {
+            let old = min_height.is_some_and(|min| oldest.height < min);
+            let over = max_bytes.is_some_and(|max| total > max);
+            if !old && !over {
+                break;
+            }
+            let bytes = size(oldest);
+            total -= bytes;
+            removed.0 += 1;
+            removed.1 += bytes;
+            self.blocks.pop_front();
+        }
+        removed
+    }
+
+    /// Height of the newest recorded block
+    pub fn latest_height(&self) -> Option<u64> {
+        self.blocks.back().map(|block| block.height)
+    }
+
+    /// Recorded blocks whose height falls in `heights`, oldest first
+    pub fn history(&self, heights: impl RangeBounds<u64>) -> Vec<&BlockFees> {
+        self.blocks
+            .iter()
+            .filter(|block| heights.contains(&block.height))
+            .collect()
+    }
+
+    /// The last `count` recorded blocks, empty ones included, oldest first
+    pub fn recent(&self, count: usize) -> Vec<&BlockFees> {
+        let start = self.blocks.len().saturating_sub(count);
+        self.blocks.range(start..).collect()
+    }
+
+    /// Rates of the most recent blocks that had transactions, newest first
+    fn estimate_window(&self) -> Vec<&FeeRates> {
+        self.blocks
+            .iter()
+            .rev()
+            .filter_map(|block| block.rates.as_ref())
+            .take(FEE_ESTIMATE_BLOCKS)
+            .collect()
+    }
+
+    /// Whether enough blocks are known for `estimate` to use them
+    pub fn has_history(&self) -> bool {
+        self.estimate_window().len() >= MIN_FEE_HISTORY_BLOCKS
+    }
+
+    /// Suggested fee per byte at `priority`, if there is enough history
+    pub fn rate(&self, priority: FeePriority) -> Option<f64> {
+        let window = self.estimate_window();
+        if window.len() < MIN_FEE_HISTORY_BLOCKS {
+            return None;
+        }
+        let mut rates: Vec<f64> = window.iter().map(|rates| rates.rate(priority)).collect();
+        rates.sort_by(f64::total_cmp);
+        percentile(&rates, 50)
+    }
+
+    /// Suggested fee for a typical transaction at `priority`: the rate times
+    /// the recent median transaction size, never below 1. Without enough
+    /// history, `priority.fee(fallback)`.
+    pub fn estimate(&self, priority: FeePriority, fallback: u64) -> u64 {
+        let Some(rate) = self.rate(priority) else {
+            return priority.fee(fallback);
+        };
+        let mut sizes: Vec<u64> = self
+            .estimate_window()
+            .iter()
+            .map(|rates| rates.median_size)
+            .collect();
+        sizes.sort_unstable();
+        let size = percentile(&sizes, 50).unwrap_or_default();
+        ((rate * size as f64).ceil() as u64).max(1)
+    }
+
+    /// Fee a child of `child_size` bytes must pay for it and its unconfirmed
+    /// `ancestors`, given as (fee, encoded size), to pay `priority`'s rate
+    /// together. Without enough history every transaction in the package is
+    /// held to `priority.fee(fallback)` instead. Never below 1.
+    pub fn package_child_fee(
+        &self,
+        priority: FeePriority,
+        ancestors: &[(u64, usize)],
+        child_size: usize,
+        fallback: u64,
+    ) -> u64 {
+        let paid: u64 = ancestors.iter().map(|(fee, _)| fee).sum();
+        let needed = match self.rate(priority) {
+            Some(rate) => {
+                let size = ancestors.iter().map(|(_, size)| size).sum::<usize>() + child_size;
+                (rate * size as f64).ceil() as u64
+            }
+            None => priority
+                .fee(fallback)
+                .saturating_mul(ancestors.len() as u64 + 1),
+        };
+        needed.saturating_sub(paid).max(1)
+    }
+}
+
+/// Fee rate of transactions mined together, given as (fee, encoded size): all
+/// their fees over all their bytes. `None` if they have no size.
+pub fn package_rate(transactions: &[(u64, usize)]) -> Option<f64> {
+    let fee: u64 = transactions.iter().map(|(fee, _)| fee).sum();
+    let size: usize = transactions.iter().map(|(_, size)| size).sum();
+    (size > 0).then(|| fee as f64 / size as f64)
+}
+
+/// Nearest-rank percentile of sorted `values`
+fn percentile<T: Copy>(values: &[T], percent: usize) -> Option<T> {
+    if values.is_empty() {
+        return None;
+    }
+    let rank = (percent * values.len()).div_ceil(100).max(1);
+    values.get(rank - 1).copied()
+}
+
+impl Wallet {
+    pub fn fee_estimator(&self) -> &FeeEstimator {
+        &self.fees
+    }
+
+    /// Record the fees of a confirmed block; kept in memory until
+    /// `save_fee_history`
+    pub fn record_block_fees(&mut self, block: &Block) {
+        self.fees.record_block(block);
+    }
+
+    /// Record the fees of `chain`'s blocks above the newest one recorded.
+    /// Returns how many were added.
+    pub fn record_chain_fees(&mut self, chain: &Blockchain) -> usize {
+        let after = self.fees.latest_height();
+        let new: Vec<&Block> = chain
+            .blocks()
+            .iter()
+            .filter(|block| after.is_none_or(|after| block.header.height > after))
+            .collect();
+        for block in &new {
+            self.fees.record_block(block);
+        }
+        new.len()
+    }
+
+    /// Load the fee history saved in the wallet data dir
+    pub async fn load_fee_history(&mut self) -> WalletResult<()> {
+        let Some(data_dir) = self.data_dir.clone() else {
+            return Ok(());
+        };
+        let storage = StorageManager::new(data_dir)?;
+        self.fees = FeeEstimator::load(&storage).await?;
+        Ok(())
+    }
+
+    /// Save the fee history. Does nothing in read-only mode.
+    pub async fn save_fee_history(&self) -> WalletResult<()> {
+        let Some(data_dir) = self.data_dir.clone().filter(|_| !self.read_only) else {
+            return Ok(());
+        };
+        let storage = StorageManager::new(data_dir)?;
+        self.fees.save(&storage).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{
+        fake_address, fake_block, fake_coinbase, fake_note, fakenet_chain_config, TempDir,
+    };
+    use crate::wallet::keys::NockchainKeyManager;
+    use crate::wallet::mining::MiningPayout;
+
+    /// Size given to every synthetic transaction, so a fee of `rate * SIZE`
+    /// pays exactly `rate` per byte
+    const SIZE: usize = 100;
+
+    /// Block at `height` whose transactions pay `rates` per byte
+    fn block(height: u64, rates: &[u64]) -> BlockFees {
+        BlockFees {
+            height,
+            tx_count: rates.len(),
+            rates: FeeRates::from_fees(rates.iter().map(|rate| (rate * SIZE as u64, SIZE))),
+        }
+    }
+
+    /// `count` blocks from height 0, each paying 1..=10 per byte
+    fn steady(count: u64) -> FeeEstimator {
+        let mut estimator = FeeEstimator::default();
+        for height in 0..count {
+            estimator.record(block(height, &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]));
+        }
+        estimator
+    }
+
+    fn heights(blocks: &[&BlockFees]) -> Vec<u64> {
+        blocks.iter().map(|block| block.height).collect()
+    }
+
+    #[test]
+    fn percentile_is_nearest_rank() {
+        let values: Vec<u64> = (1..=10).collect();
+        assert_eq!(percentile(&values, 25), Some(3));
+        assert_eq!(percentile(&values, 50), Some(5));
+        assert_eq!(percentile(&values, 90), Some(9));
+        assert_eq!(percentile(&values, 100), Some(10));
+        assert_eq!(percentile(&values, 0), Some(1));
+        assert_eq!(percentile(&[7u64], 90), Some(7));
+        assert_eq!(percentile::<u64>(&[], 50), None);
+    }
+
+    #[test]
+    fn block_rates_are_the_25th_50th_and_90th_percentiles() {
+        // Unsorted, with sizes that vary
+        let rates =
+            FeeRates::from_fees([(900, 100), (200, 200), (1_500, 300), (400, 100)]).unwrap();
+        assert_eq!(rates.p25, 1.0);
+        assert_eq!(rates.median, 4.0);
+        assert_eq!(rates.p90, 9.0);
+        assert_eq!(rates.median_size, 100);
+        assert_eq!(rates.rate(FeePriority::Low), 1.0);
+        assert_eq!(rates.rate(FeePriority::Normal), 4.0);
+        assert_eq!(rates.rate(FeePriority::High), 9.0);
+    }
+
+    #[test]
+    fn transactions_without_a_size_are_left_out() {
+        let rates = FeeRates::from_fees([(500, 0), (300, 100)]).unwrap();
+        assert_eq!(rates.median, 3.0);
+        assert_eq!(FeeRates::from_fees([(500, 0)]), None);
+        assert_eq!(FeeRates::from_fees([]), None);
+    }
+
+    #[test]
+    fn a_block_fee_is_inputs_less_outputs_and_the_coinbase_pays_none() {
+        let note = fake_note().amount(5_000).build();
+        let block = fake_block()
+            .height(7)
+            .spending(&note, &fake_address(), 4_000)
+            .build();
+        let mut with_coinbase = block.clone();
+        with_coinbase
+            .transactions
+            .insert(0, fake_coinbase(&fake_address(), 50_000));
+
+        let fees = BlockFees::from_block(&with_coinbase);
+        assert_eq!(fees.height, 7);
+        assert_eq!(fees.tx_count, 1);
+        let size = block.transactions[0].to_bytes().len();
+        let rates = fees.rates.unwrap();
+        assert_eq!(rates.median, 1_000.0 / size as f64);
+        assert_eq!(rates.median_size, size as u64);
+    }
+
+    #[test]
+    fn a_block_with_only_a_coinbase_has_no_rates() {
+        let mut block = fake_block().height(3).build();
+        block
+            .transactions
+            .push(fake_coinbase(&fake_address(), 50_000));
+        let fees = BlockFees::from_block(&block);
+        assert_eq!(fees.tx_count, 0);
+        assert_eq!(fees.rates, None);
+    }
+
+    #[test]
+    fn blocks_are_kept_in_height_order_and_a_reorg_replaces_one() {
+        let mut estimator = FeeEstimator::default();
+        for height in [5, 2, 9, 4] {
+            estimator.record(block(height, &[1]));
+        }
+        assert_eq!(heights(&estimator.recent(10)), vec![2, 4, 5, 9]);
+
+        estimator.record(block(5, &[40]));
+        assert_eq!(estimator.recent(10).len(), 4);
+        assert_eq!(estimator.history(5..=5)[0].rates.unwrap().median, 40.0);
+        assert_eq!(estimator.latest_height(), Some(9));
+    }
+
+    #[test]
+    fn the_history_is_bounded() {
+        let estimator = steady(FEE_HISTORY_BLOCKS as u64 + 25);
+        let kept = estimator.recent(usize::MAX);
+        assert_eq!(kept.len(), FEE_HISTORY_BLOCKS);
+        assert_eq!(kept[0].height, 25);
+    }
+
+    #[test]
+    fn history_and_recent_select_by_height_and_count() {
+        let estimator = steady(20);
+        assert_eq!(heights(&estimator.history(5..8)), vec![5, 6, 7]);
+        assert_eq!(heights(&estimator.history(18..)), vec![18, 19]);
+        assert!(estimator.history(100..).is_empty());
+        assert_eq!(heights(&estimator.recent(3)), vec![17, 18, 19]);
+    }
+
+    #[test]
+    fn a_child_pays_what_its_package_is_short_of_the_rate() {
+        // A 200-byte parent paying 1 per byte; the Normal rate is 5
+        let estimator = steady(MIN_FEE_HISTORY_BLOCKS as u64);
+        let parent = [(200, 200)];
+        let fee = estimator.package_child_fee(FeePriority::Normal, &parent, 100, 1_000);
+        assert_eq!(fee, 5 * 300 - 200);
+        assert_eq!(package_rate(&[(200, 200), (fee, 100)]), Some(5.0));
+        // Every ancestor's bytes and fees count
+        assert_eq!(
+            estimator.package_child_fee(FeePriority::High, &[(200, 200), (100, 100)], 100, 1_000),
+            9 * 400 - 300
+        );
+        // A parent already past the rate still needs a child fee
+        assert_eq!(
+            estimator.package_child_fee(FeePriority::Normal, &[(10_000, 200)], 100, 1_000),
+            1
+        );
+
+        // Without history each transaction is held to the static fee
+        let fresh = FeeEstimator::default();
+        assert_eq!(
+            fresh.package_child_fee(FeePriority::Normal, &parent, 100, 1_000),
+            2 * 1_000 - 200
+        );
+        assert_eq!(
+            fresh.package_child_fee(FeePriority::High, &[(200, 200), (300, 100)], 100, 1_000),
+            3 * 2_000 - 500
+        );
+        assert_eq!(package_rate(&[]), None);
+        assert_eq!(package_rate(&[(500, 0)]), None);
+    }
+
+    #[test]
+    fn the_static_fee_is_used_until_enough_blocks_are_known() {
+        let estimator = steady(MIN_FEE_HISTORY_BLOCKS as u64 - 1);
+        assert!(!estimator.has_history());
+        assert_eq!(estimator.rate(FeePriority::Normal), None);
+        for priority in [FeePriority::Low, FeePriority::Normal, FeePriority::High] {
+            assert_eq!(estimator.estimate(priority, 1_000), priority.fee(1_000));
+        }
+
+        let estimator = steady(MIN_FEE_HISTORY_BLOCKS as u64);
+        assert!(estimator.has_history());
+        assert_eq!(estimator.estimate(FeePriority::Normal, 1_000), 5 * 100);
+    }
+
+    #[test]
+    fn estimates_follow_the_priority_percentiles() {
+        let estimator = steady(30);
+        assert_eq!(estimator.rate(FeePriority::Low), Some(3.0));
+        assert_eq!(estimator.rate(FeePriority::Normal), Some(5.0));
+        assert_eq!(estimator.rate(FeePriority::High), Some(9.0));
+        assert_eq!(estimator.estimate(FeePriority::Low, 1), 300);
+        assert_eq!(estimator.estimate(FeePriority::Normal, 1), 500);
+        assert_eq!(estimator.estimate(FeePriority::High, 1), 900);
+    }
+
+    #[test]
+    fn empty_blocks_are_skipped_not_counted_as_free() {
+        let mut estimator = FeeEstimator::default();
+        for height in 0..40 {
+            if height % 4 == 0 {
+                estimator.record(block(height, &[5]));
+            } else {
+                estimator.record(block(height, &[]));
+            }
+        }
+        assert_eq!(estimator.recent(40).len(), 40);
+        assert!(estimator.has_history());
+        assert_eq!(estimator.rate(FeePriority::Normal), Some(5.0));
+    }
+
+    #[test]
+    fn an_outlier_block_does_not_move_the_estimate() {
+        let mut estimator = steady(20);
+        let before = estimator.estimate(FeePriority::High, 1);
+        estimator.record(block(20, &[100_000; 10]));
+        estimator.record(block(21, &[0; 10]));
+        assert_eq!(estimator.estimate(FeePriority::High, 1), before);
+        assert_eq!(estimator.estimate(FeePriority::Normal, 1), 500);
+    }
+
+    #[test]
+    fn only_the_most_recent_blocks_are_estimated_from() {
+        let mut estimator = FeeEstimator::default();
+        for height in 0..100 {
+            let rate = if height < 100 - FEE_ESTIMATE_BLOCKS as u64 {
+                1
+            } else {
+                8
+            };
+            estimator.record(block(height, &[rate]));
+        }
+        assert_eq!(estimator.rate(FeePriority::Low), Some(8.0));
+    }
+
+    #[test]
+    fn a_free_history_still_suggests_a_fee_of_one() {
+        let mut estimator = FeeEstimator::default();
+        for height in 0..10 {
+            estimator.record(block(height, &[0, 0, 0]));
+        }
+        assert_eq!(estimator.estimate(FeePriority::High, 1_000), 1);
+    }
+
+    #[test]
+    fn a_child_pays_for_its_ancestors_to_reach_the_rate() {
+        let estimator = steady(10);
+        // 5 per byte over 300 bytes, less the 200 the parent already paid
+        assert_eq!(
+            estimator.package_child_fee(FeePriority::Normal, &[(200, 200)], 100, 1),
+            1_300
+        );
+        // A parent that overpaid leaves the child the minimum
+        assert_eq!(
+            estimator.package_child_fee(FeePriority::Normal, &[(10_000, 200)], 100, 1),
+            1
+        );
+        // Without history each of the two is held to the static fee
+        assert_eq!(
+            FeeEstimator::default().package_child_fee(FeePriority::High, &[(100, 200)], 100, 500),
+            1_900
+        );
+    }
+
+    #[test]
+    fn package_rate_is_total_fee_over_total_size() {
+        assert_eq!(package_rate(&[(100, 200), (500, 100)]), Some(2.0));
+        assert_eq!(package_rate(&[(100, 0)]), None);
+        assert_eq!(package_rate(&[]), None);
+    }
+
+    #[test]
+    fn pruning_drops_old_blocks_then_enough_to_fit() {
+        let mut estimator = steady(10);
+        let (removed, _) = estimator.prune(Some(4), None);
+        assert_eq!(removed, 4);
+        assert_eq!(estimator.recent(10)[0].height, 4);
+
+        let one = serde_json::to_vec(estimator.recent(1)[0]).unwrap().len() as u64;
+        let (removed, bytes) = estimator.prune(None, Some(one * 2));
+        assert_eq!(removed, 4);
+        assert_eq!(bytes, one * 4);
+        assert_eq!(heights(&estimator.recent(10)), vec![8, 9]);
+    }
+
+    #[tokio::test]
+    async fn the_history_survives_reopening_the_wallet() {
+        let dir = TempDir::new("fee-history");
+        let mut wallet = Wallet::new();
+        wallet.data_dir = Some(dir.path().to_path_buf());
+        wallet.fees = steady(12);
+        wallet.save_fee_history().await.unwrap();
+
+        let mut reopened = Wallet::new();
+        reopened.data_dir = Some(dir.path().to_path_buf());
+        reopened.load_fee_history().await.unwrap();
+        assert_eq!(reopened.fee_estimator(), &steady(12));
+
+        // Nothing saved yet is an empty history, not an error
+        let mut fresh = Wallet::new();
+        fresh.data_dir = Some(TempDir::new("fee-history-none").path().to_path_buf());
+        fresh.load_fee_history().await.unwrap();
+        assert!(!fresh.fee_estimator().has_history());
+    }
+
+    #[tokio::test]
+    async fn chain_fees_are_recorded_once() {
+        let dir = TempDir::new("fee-chain");
+        let storage = StorageManager::new(dir.path().to_path_buf()).unwrap();
+        let mut miner = Wallet::new();
+        miner.keys = NockchainKeyManager::from_seed([4; 32]);
+        miner.generate_key("miner").unwrap();
+        let payout = MiningPayout {
+            key_name: "miner".to_string(),
+            rotate: false,
+        };
+        let mut chain = Blockchain::new(fakenet_chain_config());
+        for _ in 0..3 {
+            miner
+                .mine_block(&mut chain, &payout, 5_000, &storage)
+                .await
+                .unwrap();
+        }
+        // Mining records each block it mines
+        assert_eq!(miner.fee_estimator().latest_height(), Some(2));
+
+        let mut wallet = Wallet::new();
+        assert_eq!(wallet.record_chain_fees(&chain), 3);
+        assert_eq!(wallet.record_chain_fees(&chain), 0);
+        let recorded = wallet.fee_estimator().recent(10);
+        assert_eq!(heights(&recorded), vec![0, 1, 2]);
+        assert!(recorded.iter().all(|block| block.rates.is_none()));
+    }
+}
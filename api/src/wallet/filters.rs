@@ -0,0 +1,547 @@
+//! Compact block filters, so wallet sync downloads only the blocks that pay it
+//! or spend from it.
+//!
+//! Each block gets a Golomb-coded set of the addresses its outputs pay and the
+//! outputs its inputs spend, written `txid:index`, built
+//! when the block is added to the chain and kept next to it. Every address is
+//! hashed with the block's hash as key into `[0, count * FILTER_M)`, and the
+//! sorted values are stored as Golomb-Rice coded gaps with `FILTER_P` low
+//! bits, in the manner of BIP 158. A filter costs a few bytes per address.
+//!
+//! Sync fetches the filter of every block and matches the wallet's addresses
+//! and unspent notes against it locally. Only on a match does it fetch the full block. A match
+//! can be a false positive, about once in `FILTER_M` lookups of an address
+//! the block doesn't pay. The fetched block then simply turns out to hold
+//! nothing for the wallet. A filter never misses an address the block pays.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+
+use crate::wallet::chain::Blockchain;
+use crate::wallet::encoding::Encode;
+use crate::wallet::external::ExternalSpend;
+use crate::wallet::keys::OutPoint;
+use crate::wallet::provenance::NoteOrigin;
+use crate::wallet::{Address, Block, Note, Wallet, WalletError, WalletResult};
+
+/// Low bits stored verbatim for each gap
+pub const FILTER_P: u8 = 19;
+
+/// Inverse false-positive rate: values fall in `[0, count * FILTER_M)`
+pub const FILTER_M: u64 = 784_931;
+
+/// Golomb-coded set of the addresses a block's outputs pay and the outputs
+/// its inputs spend
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockFilter {
+    pub block_hash: [u8; 32],
+    /// Encoded size of the block, so sync can count what skipping it saved
+    pub block_size: u64,
+    /// Distinct items in the set
+    pub count: u32,
+    pub data: Vec<u8>,
+}
+
+impl BlockFilter {
+    pub fn build(block: &Block) -> Self {
+        let block_hash = block.hash();
+        let spent: Vec<String> = block
+            .transactions
+            .iter()
+            .flat_map(|tx| &tx.inputs)
+            .map(|input| input.outpoint().to_string())
+            .collect();
+        let items: BTreeSet<&[u8]> = block
+            .transactions
+            .iter()
+            .flat_map(|tx| &tx.outputs)
+            .map(|output| output.recipient_address.as_bytes())
+            .chain(spent.iter().map(String::as_bytes))
+            .collect();
+        let count = items.len() as u32;
+
+        let mut values: Vec<u64> = items
+            .into_iter()
+            .map(|item| hash_to_range(&block_hash, item, count))
+            .collect();
+        values.sort_unstable();
+
+        let mut writer = BitWriter::default();
+        let mut previous = 0;
+        for value in values {
+            writer.golomb_rice(value - previous);
+            previous = value;
+        }
+
+        Self {
+            block_hash,
+            block_size: block.to_bytes().len() as u64,
+            count,
+            data: writer.finish(),
+        }
+    }
+
+    /// Whether the block may pay any of `items`. A corrupt filter matches, so
+    /// the block is fetched rather than skipped.
+    pub fn matches_any<T: AsRef<[u8]>>(&self, items: &[T]) -> bool {
+        if self.count == 0 || items.is_empty() {
+            return false;
+        }
+        let mut queries: Vec<u64> = items
+            .iter()
+            .map(|item| hash_to_range(&self.block_hash, item.as_ref(), self.count))
+            .collect();
+        queries.sort_unstable();
+
+        let mut reader = BitReader::new(&self.data);
+        let mut queries = queries.into_iter().peekable();
+        let mut value = 0u64;
+        for _ in 0..self.count {
+            let Some(gap) = reader.golomb_rice() else {
+                return true;
+            };
+            value += gap;
+            while queries.next_if(|query| *query < value).is_some() {}
+            match queries.peek() {
+                None => return false,
+                Some(query) if *query == value => return true,
+                Some(_) => {}
+            }
+        }
+        false
+    }
+
+    pub fn matches(&self, item: &[u8]) -> bool {
+        self.matches_any(&[item])
+    }
+}
+
+/// Hash `item` keyed by the block into `[0, count * FILTER_M)`
+fn hash_to_range(block_hash: &[u8; 32], item: &[u8], count: u32) -> u64 {
+    let digest = Sha256::new()
+        .chain_update(&block_hash[..16])
+        .chain_update(item)
+        .finalize();
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&digest[..8]);
+    let range = u64::from(count) * FILTER_M;
+    ((u128::from(u64::from_be_bytes(prefix)) * u128::from(range)) >> 64) as u64
+}
+
+/// Bits packed most significant first
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    /// Bits used in the last byte, 0 when it is full
+    used: u8,
+}
+
+impl BitWriter {
+    fn bit(&mut self, bit: bool) {
+        if self.used == 0 {
+            self.bytes.push(0);
+        }
+        if bit {
+            *self.bytes.last_mut().expect("pushed above") |= 0x80 >> self.used;
+        }
+        self.used = (self.used + 1) % 8;
+    }
+
+    /// Quotient in unary, then the `FILTER_P` low bits
+    fn golomb_rice(&mut self, value: u64) {
+        for _ in 0..value >> FILTER_P {
+            self.bit(true);
+        }
+        self.bit(false);
+        for shift in (0..FILTER_P).rev() {
+            self.bit(value >> shift & 1 == 1);
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn bit(&mut self) -> Option<bool> {
+        let byte = self.bytes.get(self.position / 8)?;
+        let bit = byte & (0x80 >> (self.position % 8)) != 0;
+        self.position += 1;
+        Some(bit)
+    }
+
+    fn golomb_rice(&mut self) -> Option<u64> {
+        let mut quotient = 0u64;
+        while self.bit()? {
+            quotient += 1;
+        }
+        let mut remainder = 0u64;
+        for _ in 0..FILTER_P {
+            remainder = remainder << 1 | u64::from(self.bit()?);
+        }
+        Some(quotient << FILTER_P | remainder)
+    }
+}
+
+/// Where filtered sync gets block hashes, filters and the blocks that match
+pub trait FilterSource {
+    fn block_hash_at(&self, height: u64) -> Option<[u8; 32]>;
+
+    fn filter(&self, block_hash: &[u8; 32]) -> Option<BlockFilter>;
+
+    fn block(&self, block_hash: &[u8; 32]) -> Option<Block>;
+}
+
+impl FilterSource for Blockchain {
+    fn block_hash_at(&self, height: u64) -> Option<[u8; 32]> {
+        self.block_at(height).map(Block::hash)
+    }
+
+    fn filter(&self, block_hash: &[u8; 32]) -> Option<BlockFilter> {
+        self.filter_for_block(block_hash).cloned()
+    }
+
+    /// `None` for a block whose body was pruned
+    fn block(&self, block_hash: &[u8; 32]) -> Option<Block> {
+        self.full_block_by_hash(block_hash).ok().flatten().cloned()
+    }
+}
+
+/// What a filtered sync downloaded, and what it saved over fetching every block
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilterSyncStats {
+    pub blocks_scanned: u64,
+    /// Blocks whose filter matched, so the full block was fetched
+    pub blocks_fetched: u64,
+    /// Fetched blocks that turned out to pay none of the wallet's addresses
+    /// and hold no spend of its notes made elsewhere
+    pub false_positives: u64,
+    /// Spends of the wallet's notes signed elsewhere, found in fetched blocks
+    #[serde(default)]
+    pub external_spends: Vec<ExternalSpend>,
+    pub filter_bytes: u64,
+    pub block_bytes: u64,
+    /// What fetching every scanned block would have cost
+    pub full_block_bytes: u64,
+}
+
+impl FilterSyncStats {
+    pub fn downloaded_bytes(&self) -> u64 {
+        self.filter_bytes + self.block_bytes
+    }
+
+    pub fn bytes_saved(&self) -> u64 {
+        self.full_block_bytes
+            .saturating_sub(self.downloaded_bytes())
+    }
+}
+
+impl Wallet {
+    /// Scan blocks from `from_height` to the tip of `source` by their
+    /// filters, fetching only blocks that may pay or spend from the wallet,
+    /// recording the notes they do pay and the spends of its notes it didn't
+    /// make
+    pub fn sync_filtered(
+        &mut self,
+        source: &impl FilterSource,
+        from_height: u64,
+    ) -> WalletResult<FilterSyncStats> {
+        self.ensure_writable()?;
+        let mut items = self.filter_items();
+
+        let mut stats = FilterSyncStats::default();
+        let mut height = from_height;
+        while let Some(block_hash) = source.block_hash_at(height) {
+            let filter = source.filter(&block_hash).ok_or_else(|| {
+                WalletError::Network(format!("No filter for block at height {}", height))
+            })?;
+            stats.blocks_scanned += 1;
+            stats.filter_bytes += filter.to_bytes().len() as u64;
+            stats.full_block_bytes += filter.block_size;
+
+            if filter.matches_any(&items) {
+                let block = source.block(&block_hash).ok_or_else(|| {
+                    WalletError::Network(format!("Block at height {} is unavailable", height))
+                })?;
+                stats.blocks_fetched += 1;
+                stats.block_bytes += block.to_bytes().len() as u64;
+                let paid = self.record_block_notes(&block)?;
+                let spends = self.record_external_spends(&block)?;
+                if paid == 0 && spends.is_empty() {
+                    stats.false_positives += 1;
+                } else {
+                    // Notes found here may be spent in a later block
+                    items = self.filter_items();
+                }
+                stats.external_spends.extend(spends);
+            }
+            self.balances.set_tip_height(height);
+            height += 1;
+        }
+
+        self.filter_sync = Some(stats.clone());
+        self.mark_synced();
+        Ok(stats)
+    }
+
+    /// Stats of the last filtered sync
+    pub fn last_filter_sync(&self) -> Option<&FilterSyncStats> {
+        self.filter_sync.as_ref()
+    }
+
+    /// What to match filters against: the wallet's addresses and its unspent
+    /// notes
+    fn filter_items(&self) -> Vec<String> {
+        let mut items: Vec<String> = self
+            .keys
+            .watched_addresses()
+            .iter()
+            .map(Address::to_string)
+            .collect();
+        items.extend(
+            self.balances
+                .unspent_outpoints()
+                .iter()
+                .map(OutPoint::to_string),
+        );
+        items
+    }
+
+    /// Record the outputs of `block` paying the wallet that aren't known yet.
+    /// Returns how many outputs paid it, known or not.
+    fn record_block_notes(&mut self, block: &Block) -> WalletResult<usize> {
+        let mut paid = 0;
+        for tx in &block.transactions {
+            let funded_by_wallet = tx
+                .inputs
+                .iter()
+                .any(|input| self.balances.note_at(input.outpoint()).is_some());
+            let origin = NoteOrigin::of(tx.inputs.is_empty(), funded_by_wallet);
+            for (index, output) in tx.outputs.iter().enumerate() {
+                let Ok(address) = Address::from_string(&output.recipient_address) else {
+                    continue;
+                };
+                if !self.keys.is_mine(&address) {
+                    continue;
+                }
+                paid += 1;
+                let outpoint = OutPoint::new(tx.id.clone(), index as u32);
+                if self.balances.note_at(&outpoint).is_some() {
+                    continue;
+                }
+                self.observe_note(Note {
+                    id: uuid::Uuid::new_v4(),
+                    address,
+                    amount: output.amount,
+                    block_height: Some(block.header.height),
+                    transaction_id: tx.id.clone(),
+                    output_index: index as u32,
+                    spent: false,
+                    locked: false,
+                    created_at: chrono::Utc::now(),
+                    coinbase: tx.inputs.is_empty(),
+                    origin: Some(origin),
+                })?;
+            }
+        }
+        Ok(paid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_address, fake_block, fake_coinbase, fakenet_chain_config};
+    use crate::wallet::checkpoints::Checkpoint;
+    use crate::wallet::keys::NockchainTransaction;
+    use crate::wallet::BlockchainConfig;
+
+    fn payment(to: &Address, amount: u64) -> NockchainTransaction {
+        fake_block()
+            .paying(to, amount)
+            .build()
+            .transactions
+            .remove(0)
+    }
+
+    #[test]
+    fn a_filter_matches_every_address_paid_and_outpoint_spent() {
+        let paid: Vec<Address> = (0..5).map(|_| fake_address()).collect();
+        let block = fake_block()
+            .paying(&paid[0], 10)
+            .paying(&paid[1], 20)
+            .with_txs(3)
+            .build();
+        let filter = BlockFilter::build(&block);
+        assert_eq!(filter.block_hash, block.hash());
+        assert_eq!(filter.block_size, block.to_bytes().len() as u64);
+
+        for tx in &block.transactions {
+            for output in &tx.outputs {
+                assert!(filter.matches(output.recipient_address.as_bytes()));
+            }
+            for input in &tx.inputs {
+                assert!(filter.matches(input.outpoint().to_string().as_bytes()));
+            }
+        }
+        assert!(filter.matches_any(&[paid[4].to_string(), paid[1].to_string()]));
+        assert!(!filter.matches_any(&[paid[2].to_string(), paid[3].to_string()]));
+        assert!(!filter.matches_any::<String>(&[]));
+    }
+
+    #[test]
+    fn an_empty_block_matches_nothing_and_a_corrupt_filter_everything() {
+        let empty = BlockFilter::build(&fake_block().build());
+        assert_eq!(empty.count, 0);
+        assert!(!empty.matches(fake_address().to_string().as_bytes()));
+
+        let mut corrupt = BlockFilter::build(&fake_block().with_txs(4).build());
+        corrupt.data.truncate(1);
+        assert!(corrupt.matches(fake_address().to_string().as_bytes()));
+    }
+
+    #[test]
+    fn false_positives_stay_rare() {
+        let mut block = fake_block().build();
+        block.transactions = (0..200).map(|_| payment(&fake_address(), 1)).collect();
+        let filter = BlockFilter::build(&block);
+        assert_eq!(filter.count, 400);
+        // A few bytes per item
+        assert!(filter.data.len() < 400 * 4, "{}", filter.data.len());
+
+        let hits = (0..20_000)
+            .filter(|_| filter.matches(fake_address().to_string().as_bytes()))
+            .count();
+        assert!(hits <= 2, "{} false positives", hits);
+    }
+
+    /// Chain of `count` blocks whose proof of work isn't checked; the blocks
+    /// at `paying` heights also pay `to` 1,000 each
+    fn chain_paying(to: &Address, count: u64, paying: &[u64]) -> Blockchain {
+        let mut chain = Blockchain::new(BlockchainConfig {
+            checkpoints: vec![Checkpoint::new(u64::MAX, [0; 32])],
+            skip_pow_below_checkpoint: true,
+            max_future_block_time: u64::MAX / 2,
+            ..fakenet_chain_config()
+        });
+        for height in 0..count {
+            let mut transactions = Vec::new();
+            if height > 0 {
+                transactions.push(fake_coinbase(&fake_address(), 50));
+            }
+            if paying.contains(&height) {
+                transactions.push(payment(to, 1_000));
+            }
+            let block = chain.new_block(transactions, 0x1f7f_ffff);
+            chain.add_block(block).unwrap();
+        }
+        chain
+    }
+
+    #[test]
+    fn syncing_1000_blocks_fetches_only_the_three_that_pay_the_wallet() {
+        let mut wallet = Wallet::new();
+        let address = wallet.generate_key("Main").unwrap();
+        let chain = chain_paying(&address, 1_000, &[17, 512, 998]);
+
+        let stats = wallet.sync_filtered(&chain, 0).unwrap();
+        assert_eq!(stats.blocks_scanned, 1_000);
+        assert_eq!(stats.blocks_fetched - stats.false_positives, 3);
+        assert!(stats.blocks_fetched <= 4, "{:?}", stats);
+        assert!(stats.external_spends.is_empty());
+
+        let mut heights: Vec<Option<u64>> = wallet
+            .balances()
+            .notes()
+            .iter()
+            .map(|note| note.block_height)
+            .collect();
+        heights.sort();
+        assert_eq!(heights, vec![Some(17), Some(512), Some(998)]);
+        assert_eq!(wallet.balances().tip_height(), Some(999));
+
+        assert!(stats.bytes_saved() > 0);
+        assert_eq!(
+            stats.bytes_saved(),
+            stats.full_block_bytes - stats.filter_bytes - stats.block_bytes
+        );
+        assert_eq!(wallet.last_filter_sync(), Some(&stats));
+
+        // Syncing again from the same height records nothing twice
+        wallet.sync_filtered(&chain, 0).unwrap();
+        assert_eq!(wallet.balances().notes().len(), 3);
+    }
+
+    /// Serves the filters of `chain` but, for every block, `decoy` instead
+    struct Decoying {
+        chain: Blockchain,
+        decoy: Block,
+    }
+
+    impl FilterSource for Decoying {
+        fn block_hash_at(&self, height: u64) -> Option<[u8; 32]> {
+            self.chain.block_hash_at(height)
+        }
+
+        fn filter(&self, block_hash: &[u8; 32]) -> Option<BlockFilter> {
+            self.chain.filter(block_hash)
+        }
+
+        fn block(&self, _block_hash: &[u8; 32]) -> Option<Block> {
+            Some(self.decoy.clone())
+        }
+    }
+
+    #[test]
+    fn a_fetched_block_paying_nothing_counts_as_a_false_positive() {
+        let mut wallet = Wallet::new();
+        let address = wallet.generate_key("Main").unwrap();
+        let source = Decoying {
+            chain: chain_paying(&address, 30, &[3, 9]),
+            decoy: fake_block().with_txs(2).build(),
+        };
+
+        let stats = wallet.sync_filtered(&source, 0).unwrap();
+        assert_eq!(stats.blocks_scanned, 30);
+        assert_eq!(stats.blocks_fetched, 2);
+        assert_eq!(stats.false_positives, 2);
+        assert!(wallet.balances().notes().is_empty());
+    }
+
+    #[test]
+    fn a_missing_filter_stops_the_sync() {
+        struct NoFilters(Blockchain);
+        impl FilterSource for NoFilters {
+            fn block_hash_at(&self, height: u64) -> Option<[u8; 32]> {
+                self.0.block_hash_at(height)
+            }
+            fn filter(&self, _block_hash: &[u8; 32]) -> Option<BlockFilter> {
+                None
+            }
+            fn block(&self, block_hash: &[u8; 32]) -> Option<Block> {
+                FilterSource::block(&self.0, block_hash)
+            }
+        }
+
+        let mut wallet = Wallet::new();
+        let address = wallet.generate_key("Main").unwrap();
+        let error = wallet
+            .sync_filtered(&NoFilters(chain_paying(&address, 2, &[])), 0)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            WalletError::Network("No filter for block at height 0".to_string()).to_string()
+        );
+    }
+}
@@ -0,0 +1,685 @@
+//! Country and network (ASN) of peers, from databases on disk.
+//!
+//! Peer IPs are looked up in MaxMind DB (`.mmdb`) files listed in
+//! `NockchainNodeConfig::geoip_databases`, such as GeoLite2 Country and ASN
+//! or a combined country/ASN file. Nothing leaves the machine: each file is
+//! read into memory once when the node starts, and a lookup is a walk down
+//! its search tree, cached per IP. When a field is in more than one file,
+//! the first file listed wins.
+//!
+//! Both common record layouts are understood: MaxMind's
+//! (`country.iso_code`, `autonomous_system_number`,
+//! `autonomous_system_organization`) and the flat one of combined files
+//! (`country`, `asn` as `AS13335`, `as_name`).
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::debug;
+
+use crate::wallet::{WalletError, WalletResult};
+
+/// Marks the start of the metadata section at the end of a database
+const METADATA_MARKER: &[u8] = b"\xAB\xCD\xEFMaxMind.com";
+
+/// The metadata section is within this many bytes of the end
+const METADATA_MAX_SIZE: usize = 128 * 1024;
+
+/// Zero bytes between the search tree and the data section
+const DATA_SEPARATOR: usize = 16;
+
+/// Deepest nesting of maps and arrays decoded
+const MAX_DEPTH: usize = 32;
+
+/// IPs whose lookup is remembered; the cache starts over once full
+const CACHE_CAPACITY: usize = 4096;
+
+/// Where a peer's IP is, as far as the databases know
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerLocation {
+    /// ISO 3166-1 alpha-2 code, upper case
+    pub country_code: Option<String>,
+    pub asn: Option<u32>,
+    /// Name of the network operator
+    pub as_org: Option<String>,
+}
+
+impl PeerLocation {
+    pub fn is_empty(&self) -> bool {
+        self.country_code.is_none() && self.asn.is_none() && self.as_org.is_none()
+    }
+
+    /// Fill the fields this one lacks from `other`
+    fn merge(&mut self, other: PeerLocation) {
+        self.country_code = self.country_code.take().or(other.country_code);
+        self.asn = self.asn.or(other.asn);
+        self.as_org = self.as_org.take().or(other.as_org);
+    }
+}
+
+/// A decoded value of the data section
+#[derive(Debug, Clone, PartialEq)]
+enum MmdbValue {
+    String(String),
+    Double(f64),
+    Bytes(Vec<u8>),
+    Uint(u128),
+    Int(i32),
+    Map(Vec<(String, MmdbValue)>),
+    Array(Vec<MmdbValue>),
+    Bool(bool),
+}
+
+impl MmdbValue {
+    fn get(&self, key: &str) -> Option<&MmdbValue> {
+        match self {
+            MmdbValue::Map(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            MmdbValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn as_uint(&self) -> Option<u128> {
+        match self {
+            MmdbValue::Uint(n) => Some(*n),
+            MmdbValue::Int(n) => u128::try_from(*n).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// One MaxMind DB file, held in memory
+#[derive(Debug)]
+pub struct GeoIpDatabase {
+    bytes: Vec<u8>,
+    node_count: u32,
+    record_size: u16,
+    ip_version: u16,
+    database_type: String,
+    /// Offset of the data section
+    data_start: usize,
+    /// Node the IPv4 part of an IPv6 tree starts at
+    ipv4_start: u32,
+}
+
+impl GeoIpDatabase {
+    pub fn open(path: &Path) -> WalletResult<Self> {
+        let bytes = std::fs::read(path).map_err(|e| {
+            WalletError::Storage(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+        Self::from_bytes(bytes)
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> WalletResult<Self> {
+        let format_err = |message: &str| {
+            WalletError::Serialization(format!("Not a MaxMind DB file: {}", message))
+        };
+        let search_from = bytes.len().saturating_sub(METADATA_MAX_SIZE);
+        let marker = bytes[search_from..]
+            .windows(METADATA_MARKER.len())
+            .rposition(|window| window == METADATA_MARKER)
+            .ok_or_else(|| format_err("metadata marker missing"))?;
+        let metadata_start = search_from + marker + METADATA_MARKER.len();
+        let metadata = Decoder {
+            bytes: &bytes[metadata_start..],
+        }
+        .decode(0, 0)?
+        .0;
+
+        let field = |name: &str| metadata.get(name).and_then(MmdbValue::as_uint);
+        let node_count = field("node_count")
+            .and_then(|n| u32::try_from(n).ok())
+            .ok_or_else(|| format_err("node_count missing"))?;
+        let record_size = field("record_size")
+            .and_then(|n| u16::try_from(n).ok())
+            .filter(|size| matches!(size, 24 | 28 | 32))
+            .ok_or_else(|| format_err("record_size missing or unsupported"))?;
+        let ip_version = field("ip_version")
+            .and_then(|n| u16::try_from(n).ok())
+            .filter(|version| matches!(version, 4 | 6))
+            .ok_or_else(|| format_err("ip_version missing or unsupported"))?;
+        let database_type = metadata
+            .get("database_type")
+            .and_then(MmdbValue::as_str)
+            .unwrap_or_default()
+            .to_string();
+
+        let tree_size = node_count as usize * record_size as usize / 4;
+        let data_start = tree_size + DATA_SEPARATOR;
+        if data_start > search_from + marker {
+            return Err(format_err("search tree runs past the data section"));
+        }
+
+        let mut database = Self {
+            bytes,
+            node_count,
+            record_size,
+            ip_version,
+            database_type,
+            data_start,
+            ipv4_start: 0,
+        };
+        if ip_version == 6 {
+            let mut node = 0;
+            for _ in 0..96 {
+                if node >= node_count {
+                    break;
+                }
+                node = database.record(node, false);
+            }
+            database.ipv4_start = node;
+        }
+        Ok(database)
+    }
+
+    /// E.g. `GeoLite2-Country`
+    pub fn database_type(&self) -> &str {
+        &self.database_type
+    }
+
+    pub fn node_count(&self) -> u32 {
+        self.node_count
+    }
+
+    /// What the database knows about `ip`, `None` if it has no record for it
+    pub fn lookup(&self, ip: IpAddr) -> WalletResult<Option<PeerLocation>> {
+        let Some(record) = self.find(ip)? else {
+            return Ok(None);
+        };
+        Ok(Some(location_of(&record)).filter(|location| !location.is_empty()))
+    }
+
+    fn find(&self, ip: IpAddr) -> WalletResult<Option<MmdbValue>> {
+        let (bits, start): (Vec<u8>, u32) = match (ip, self.ip_version) {
+            (IpAddr::V4(ip), 4) => (ip.octets().to_vec(), 0),
+            (IpAddr::V4(ip), _) => (ip.octets().to_vec(), self.ipv4_start),
+            (IpAddr::V6(ip), 6) => (ip.octets().to_vec(), 0),
+            (IpAddr::V6(ip), _) => match ip.to_ipv4_mapped() {
+                Some(ip) => (ip.octets().to_vec(), 0),
+                None => return Ok(None),
+            },
+        };
+
+        let mut node = start;
+        for bit in 0..bits.len() * 8 {
+            if node >= self.node_count {
+                break;
+            }
+            let right = bits[bit / 8] & (0x80 >> (bit % 8)) != 0;
+            node = self.record(node, right);
+        }
+        if node <= self.node_count {
+            return Ok(None);
+        }
+
+        let offset = ((node - self.node_count) as usize)
+            .checked_sub(DATA_SEPARATOR)
+            .ok_or_else(|| {
+                WalletError::Serialization("MaxMind DB record points into the tree".to_string())
+            })?;
+        let data = self.bytes.get(self.data_start..).unwrap_or_default();
+        Decoder { bytes: data }
+            .decode(offset, 0)
+            .map(|(value, _)| Some(value))
+    }
+
+    /// Left or right record of `node`
+    fn record(&self, node: u32, right: bool) -> u32 {
+        let size = self.record_size as usize / 4;
+        let at = node as usize * size;
+        let Some(b) = self.bytes.get(at..at + size) else {
+            return self.node_count;
+        };
+        let be = |bytes: &[u8]| bytes.iter().fold(0u32, |n, b| (n << 8) | *b as u32);
+        match (self.record_size, right) {
+            (24, false) => be(&b[0..3]),
+            (24, true) => be(&b[3..6]),
+            (28, false) => ((b[3] as u32 & 0xf0) << 20) | be(&b[0..3]),
+            (28, true) => ((b[3] as u32 & 0x0f) << 24) | be(&b[4..7]),
+            (_, false) => be(&b[0..4]),
+            (_, true) => be(&b[4..8]),
+        }
+    }
+}
+
+fn location_of(record: &MmdbValue) -> PeerLocation {
+    let country_code = record
+        .get("country")
+        .and_then(|country| country.get("iso_code").or(Some(country)))
+        .and_then(MmdbValue::as_str)
+        .or_else(|| record.get("country_code").and_then(MmdbValue::as_str))
+        .filter(|code| code.len() == 2 && code.chars().all(|c| c.is_ascii_alphabetic()))
+        .map(str::to_ascii_uppercase);
+    let asn = record
+        .get("autonomous_system_number")
+        .and_then(MmdbValue::as_uint)
+        .or_else(|| match record.get("asn")? {
+            MmdbValue::String(asn) => {
+                let digits = asn.strip_prefix("AS").unwrap_or(asn);
+                digits.parse().ok()
+            }
+            value => value.as_uint(),
+        })
+        .and_then(|asn| u32::try_from(asn).ok());
+    let as_org = record
+        .get("autonomous_system_organization")
+        .or_else(|| record.get("as_name"))
+        .and_then(MmdbValue::as_str)
+        .map(str::to_string);
+    PeerLocation {
+        country_code,
+        asn,
+        as_org,
+    }
+}
+
+/// Reader of the data section format, offsets relative to `bytes`
+struct Decoder<'a> {
+    bytes: &'a [u8],
+}
+
+impl Decoder<'_> {
+    fn take(&self, offset: usize, len: usize) -> WalletResult<&[u8]> {
+        offset
+            .checked_add(len)
+            .and_then(|end| self.bytes.get(offset..end))
+            .ok_or_else(|| {
+                WalletError::Serialization(format!("MaxMind DB data ends before offset {}", offset))
+            })
+    }
+
+    /// Value at `offset` and the offset after it
+    fn decode(&self, offset: usize, depth: usize) -> WalletResult<(MmdbValue, usize)> {
+        if depth > MAX_DEPTH {
+            return Err(WalletError::Serialization(
+                "MaxMind DB data nests too deep".to_string(),
+            ));
+        }
+        let control = self.take(offset, 1)?[0];
+        let mut next = offset + 1;
+        let mut kind = control >> 5;
+
+        if kind == 1 {
+            // Pointer: the value lives elsewhere, and decoding carries on
+            // after the pointer itself
+            let size = ((control >> 3) & 0x3) as usize;
+            let high = (control & 0x7) as usize;
+            let b = self.take(next, size + 1)?;
+            next += size + 1;
+            let low = b.iter().fold(0usize, |n, b| (n << 8) | *b as usize);
+            let target = match size {
+                0 => (high << 8) | low,
+                1 => ((high << 16) | low) + 2048,
+                2 => ((high << 24) | low) + 526_336,
+                _ => low,
+            };
+            let (value, _) = self.decode(target, depth + 1)?;
+            return Ok((value, next));
+        }
+        if kind == 0 {
+            kind = self.take(next, 1)?[0] + 7;
+            next += 1;
+        }
+
+        let mut size = (control & 0x1f) as usize;
+        if size >= 29 {
+            let extra = size - 28;
+            let b = self.take(next, extra)?;
+            next += extra;
+            let n = b.iter().fold(0usize, |n, b| (n << 8) | *b as usize);
+            size = match extra {
+                1 => 29 + n,
+                2 => 285 + n,
+                _ => 65_821 + n,
+            };
+        }
+
+        let uint = |bytes: &[u8]| bytes.iter().fold(0u128, |n, b| (n << 8) | *b as u128);
+        let value = match kind {
+            2 => {
+                let text = String::from_utf8_lossy(self.take(next, size)?).into_owned();
+                next += size;
+                MmdbValue::String(text)
+            }
+            3 | 15 => {
+                let len = if kind == 3 { 8 } else { 4 };
+                let b = self.take(next, len)?;
+                next += len;
+                MmdbValue::Double(if kind == 3 {
+                    f64::from_be_bytes(b.try_into().unwrap_or_default())
+                } else {
+                    f32::from_be_bytes(b.try_into().unwrap_or_default()) as f64
+                })
+            }
+            4 => {
+                let b = self.take(next, size)?.to_vec();
+                next += size;
+                MmdbValue::Bytes(b)
+            }
+            5 | 6 | 9 | 10 => {
+                let b = self.take(next, size.min(16))?;
+                next += size.min(16);
+                MmdbValue::Uint(uint(b))
+            }
+            8 => {
+                let b = self.take(next, size.min(4))?;
+                next += size.min(4);
+                MmdbValue::Int(uint(b) as u32 as i32)
+            }
+            7 => {
+                let mut entries = Vec::with_capacity(size.min(64));
+                for _ in 0..size {
+                    let (key, after_key) = self.decode(next, depth + 1)?;
+                    let (value, after_value) = self.decode(after_key, depth + 1)?;
+                    next = after_value;
+                    let MmdbValue::String(key) = key else {
+                        return Err(WalletError::Serialization(
+                            "MaxMind DB map key is not a string".to_string(),
+                        ));
+                    };
+                    entries.push((key, value));
+                }
+                MmdbValue::Map(entries)
+            }
+            11 => {
+                let mut items = Vec::with_capacity(size.min(64));
+                for _ in 0..size {
+                    let (item, after) = self.decode(next, depth + 1)?;
+                    next = after;
+                    items.push(item);
+                }
+                MmdbValue::Array(items)
+            }
+            14 => MmdbValue::Bool(size != 0),
+            kind => {
+                return Err(WalletError::Serialization(format!(
+                    "Unsupported MaxMind DB data type {}",
+                    kind
+                )))
+            }
+        };
+        Ok((value, next))
+    }
+}
+
+/// The configured databases, with lookups cached per IP
+#[derive(Debug, Default)]
+pub struct GeoIpResolver {
+    databases: Vec<(PathBuf, GeoIpDatabase)>,
+    cache: Mutex<HashMap<IpAddr, Option<PeerLocation>>>,
+}
+
+impl GeoIpResolver {
+    /// Read every file in `paths`, failing on the first that can't be used
+    pub fn open(paths: &[PathBuf]) -> WalletResult<Self> {
+        let databases = paths
+            .iter()
+            .map(|path| Ok((path.clone(), GeoIpDatabase::open(path)?)))
+            .collect::<WalletResult<Vec<_>>>()?;
+        Ok(Self::from_databases(databases))
+    }
+
+    pub fn from_databases(databases: Vec<(PathBuf, GeoIpDatabase)>) -> Self {
+        Self {
+            databases,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Files the resolver reads, in priority order
+    pub fn paths(&self) -> Vec<&Path> {
+        self.databases
+            .iter()
+            .map(|(path, _)| path.as_path())
+            .collect()
+    }
+
+    /// What the databases know about `ip`. Addresses that can't be public,
+    /// like loopback and private ranges, are never looked up. IPv4-mapped
+    /// IPv6 addresses are looked up as the IPv4 address.
+    pub fn resolve(&self, ip: IpAddr) -> Option<PeerLocation> {
+        let ip = ip.to_canonical();
+        if !is_public(ip) {
+            return None;
+        }
+        if let Some(cached) = self.cache.lock().ok().and_then(|c| c.get(&ip).cloned()) {
+            return cached;
+        }
+
+        let mut location = PeerLocation::default();
+        for (path, database) in &self.databases {
+            match database.lookup(ip) {
+                Ok(Some(found)) => location.merge(found),
+                Ok(None) => {}
+                Err(e) => debug!("Lookup of {} in {} failed: {}", ip, path.display(), e),
+            }
+        }
+        let location = Some(location).filter(|location| !location.is_empty());
+
+        if let Ok(mut cache) = self.cache.lock() {
+            if cache.len() >= CACHE_CAPACITY {
+                cache.clear();
+            }
+            cache.insert(ip, location.clone());
+        }
+        location
+    }
+
+    /// Look up the IP of a multiaddr such as `/ip4/1.2.3.4/tcp/4001/p2p/...`;
+    /// `None` for DNS names, which are never resolved here
+    pub fn resolve_addr(&self, address: &str) -> Option<PeerLocation> {
+        self.resolve(ip_from_multiaddr(address)?)
+    }
+}
+
+/// IP of a multiaddr starting `/ip4/` or `/ip6/`
+pub fn ip_from_multiaddr(address: &str) -> Option<IpAddr> {
+    let mut parts = address.trim_start_matches('/').split('/');
+    match (parts.next()?, parts.next()?) {
+        ("ip4" | "ip6", ip) => ip.parse().ok(),
+        _ => None,
+    }
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation())
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || (ip.segments()[0] & 0xfe00) == 0xfc00
+                    || (ip.segments()[0] & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+/// Flag emoji of an ISO 3166-1 alpha-2 code, e.g. 🇩🇪 for `DE`
+pub fn country_flag(code: &str) -> Option<String> {
+    if code.len() != 2 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    code.to_ascii_uppercase()
+        .chars()
+        .map(|c| char::from_u32(0x1F1E6 + (c as u32 - 'A' as u32)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{geoip_fixture, TempDir};
+
+    fn location(country: Option<&str>, asn: Option<u32>, org: Option<&str>) -> PeerLocation {
+        PeerLocation {
+            country_code: country.map(str::to_string),
+            asn,
+            as_org: org.map(str::to_string),
+        }
+    }
+
+    fn cloudflare() -> PeerLocation {
+        location(Some("AU"), Some(13335), Some("Cloudflare"))
+    }
+
+    fn google() -> PeerLocation {
+        location(Some("US"), Some(15169), Some("Google"))
+    }
+
+    fn fixture() -> GeoIpDatabase {
+        GeoIpDatabase::from_bytes(geoip_fixture(&[
+            ("1.1.1.0".parse().unwrap(), 24, cloudflare()),
+            ("2001:4860::".parse().unwrap(), 32, google()),
+        ]))
+        .unwrap()
+    }
+
+    fn resolver(databases: Vec<GeoIpDatabase>) -> GeoIpResolver {
+        GeoIpResolver::from_databases(
+            databases
+                .into_iter()
+                .enumerate()
+                .map(|(i, database)| (PathBuf::from(format!("db{}.mmdb", i)), database))
+                .collect(),
+        )
+    }
+
+    fn ip(text: &str) -> IpAddr {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn addresses_inside_a_network_resolve_to_its_record() {
+        let database = fixture();
+        assert_eq!(database.database_type(), "Fixture-Country-ASN");
+        assert_eq!(database.lookup(ip("1.1.1.1")).unwrap(), Some(cloudflare()));
+        assert_eq!(
+            database.lookup(ip("1.1.1.254")).unwrap(),
+            Some(cloudflare())
+        );
+        assert_eq!(
+            database.lookup(ip("2001:4860:4860::8888")).unwrap(),
+            Some(google())
+        );
+        assert_eq!(database.lookup(ip("1.1.2.1")).unwrap(), None);
+        assert_eq!(database.lookup(ip("2001:4861::1")).unwrap(), None);
+    }
+
+    #[test]
+    fn mapped_addresses_resolve_as_ipv4_and_private_ones_not_at_all() {
+        let resolver = resolver(vec![fixture()]);
+        assert_eq!(resolver.resolve(ip("::ffff:1.1.1.1")), Some(cloudflare()));
+        for private in ["10.0.0.1", "127.0.0.1", "192.168.1.1", "fe80::1", "fd00::1"] {
+            assert_eq!(resolver.resolve(ip(private)), None, "{}", private);
+        }
+        // Private addresses never reach the cache; the mapped one is cached
+        // under its IPv4 form
+        let cache = resolver.cache.lock().unwrap();
+        assert_eq!(cache.len(), 1);
+        assert!(cache.contains_key(&ip("1.1.1.1")));
+    }
+
+    #[test]
+    fn lookups_are_cached_misses_included() {
+        let resolver = resolver(vec![fixture()]);
+        assert_eq!(resolver.resolve(ip("1.1.1.1")), Some(cloudflare()));
+        assert_eq!(resolver.resolve(ip("9.9.9.9")), None);
+        assert_eq!(resolver.cache.lock().unwrap().len(), 2);
+        assert_eq!(resolver.resolve(ip("1.1.1.1")), Some(cloudflare()));
+        assert_eq!(resolver.cache.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn the_first_database_listed_wins_and_later_ones_fill_gaps() {
+        let country = GeoIpDatabase::from_bytes(geoip_fixture(&[(
+            ip("1.1.1.0"),
+            24,
+            location(Some("nz"), None, None),
+        )]))
+        .unwrap();
+        let resolver = resolver(vec![country, fixture()]);
+        assert_eq!(
+            resolver.resolve(ip("1.1.1.1")),
+            Some(location(Some("NZ"), Some(13335), Some("Cloudflare")))
+        );
+        assert_eq!(resolver.resolve(ip("2001:4860::1")), Some(google()));
+    }
+
+    #[test]
+    fn databases_are_read_from_disk_once() {
+        let dir = TempDir::new("geoip");
+        let path = dir.join("peers.mmdb");
+        std::fs::write(&path, geoip_fixture(&[(ip("1.1.1.0"), 24, cloudflare())])).unwrap();
+
+        let resolver = GeoIpResolver::open(std::slice::from_ref(&path)).unwrap();
+        assert_eq!(resolver.paths(), vec![path.as_path()]);
+        // The file isn't needed once loaded
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            resolver.resolve_addr("/ip4/1.1.1.1/tcp/4001/p2p/12D3KooWPeer"),
+            Some(cloudflare())
+        );
+
+        assert!(GeoIpResolver::open(&[dir.join("missing.mmdb")]).is_err());
+        assert!(GeoIpDatabase::from_bytes(b"not a database".to_vec()).is_err());
+    }
+
+    #[test]
+    fn flat_records_are_understood_too() {
+        let record = MmdbValue::Map(vec![
+            ("country".to_string(), MmdbValue::String("de".to_string())),
+            ("asn".to_string(), MmdbValue::String("AS3320".to_string())),
+            (
+                "as_name".to_string(),
+                MmdbValue::String("Deutsche Telekom".to_string()),
+            ),
+        ]);
+        assert_eq!(
+            location_of(&record),
+            location(Some("DE"), Some(3320), Some("Deutsche Telekom"))
+        );
+        let odd = MmdbValue::Map(vec![
+            (
+                "country".to_string(),
+                MmdbValue::String("Germany".to_string()),
+            ),
+            ("asn".to_string(), MmdbValue::Uint(3320)),
+        ]);
+        assert_eq!(location_of(&odd), location(None, Some(3320), None));
+    }
+
+    #[test]
+    fn only_ip_multiaddrs_are_resolved() {
+        assert_eq!(
+            ip_from_multiaddr("/ip6/2001:4860::1/tcp/4001"),
+            Some(ip("2001:4860::1"))
+        );
+        assert_eq!(ip_from_multiaddr("/dns/seed.example.com/tcp/4001"), None);
+        assert_eq!(ip_from_multiaddr("/ip4/not-an-ip/tcp/4001"), None);
+    }
+
+    #[test]
+    fn flags_come_from_two_letter_codes() {
+        assert_eq!(country_flag("DE").as_deref(), Some("🇩🇪"));
+        assert_eq!(country_flag("us").as_deref(), Some("🇺🇸"));
+        assert_eq!(country_flag("DEU"), None);
+        assert_eq!(country_flag("1A"), None);
+    }
+}
@@ -0,0 +1,306 @@
+//! Handshake exchanged as the first message on every peer connection.
+//!
+//! Each side sends its `Handshake` as a u32 length followed by the canonical
+//! encoding, then reads the other's. Peers on a different genesis or outside
+//! the supported protocol range are rejected before anything else is exchanged.
+//! The software version in a peer's user agent can also be held to a minimum
+//! with `Handshake::check_version`.
+
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::wallet::encoding::{Decode, Encode};
+use crate::wallet::{WalletError, WalletResult};
+
+/// Protocol version this build speaks
+pub const PROTOCOL_VERSION: u32 = 1;
+/// Oldest protocol version this build still talks to
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// Connections that don't finish the handshake within this are dropped
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Services bitfield: serves blocks and relays transactions
+pub const SERVICE_FULL_NODE: u64 = 1 << 0;
+/// Services bitfield: mines blocks
+pub const SERVICE_MINING: u64 = 1 << 1;
+
+/// Upper bound on an encoded handshake, so a peer can't make us allocate freely
+const MAX_HANDSHAKE_BYTES: u32 = 4096;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Handshake {
+    pub protocol_version: u32,
+    /// Node software version, shown as the peer's user agent
+    pub user_agent: String,
+    pub genesis_hash: [u8; 32],
+    pub best_height: u64,
+    pub services: u64,
+}
+
+impl Handshake {
+    /// Fail if a peer advertising `self` can't talk to a node advertising `local`
+    pub fn check_compatible(&self, local: &Handshake) -> WalletResult<()> {
+        if self.genesis_hash != local.genesis_hash {
+            return Err(WalletError::HandshakeRejected(format!(
+                "genesis mismatch (peer {}, ours {})",
+                hex::encode(self.genesis_hash),
+                hex::encode(local.genesis_hash)
+            )));
+        }
+        if !(MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&self.protocol_version) {
+            return Err(WalletError::HandshakeRejected(format!(
+                "protocol version {} not in supported range {}..={}",
+                self.protocol_version, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn has_service(&self, service: u64) -> bool {
+        self.services & service == service
+    }
+
+    /// Software version in the user agent, if it carries one
+    pub fn software_version(&self) -> Option<Version> {
+        parse_user_agent_version(&self.user_agent)
+    }
+
+    /// Fail if the peer's software version doesn't satisfy `required`. A user
+    /// agent without a readable version passes unless `strict` is set.
+    pub fn check_version(&self, required: Option<&VersionReq>, strict: bool) -> WalletResult<()> {
+        match (self.software_version(), required) {
+            (Some(version), Some(required)) if !required.matches(&version) => {
+                Err(WalletError::PeerVersionRejected(format!(
+                    "version {} does not satisfy {}",
+                    version, required
+                )))
+            }
+            (None, _) if strict => Err(WalletError::PeerVersionRejected(format!(
+                "no version in user agent '{}'",
+                self.user_agent
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Names of the services set in a handshake's `services` bitfield
+pub fn service_labels(services: u64) -> Vec<&'static str> {
+    [(SERVICE_FULL_NODE, "full node"), (SERVICE_MINING, "mining")]
+        .into_iter()
+        .filter(|(service, _)| services & service == *service)
+        .map(|(_, label)| label)
+        .collect()
+}
+
+/// The first `MAJOR.MINOR.PATCH` in a user agent such as `nockchain/1.2.3` or
+/// `nockchain-node-0.4.1-rc1`, at its start or after a `/`, `-`, `_`, space
+/// or `v`. Anything after the patch number is ignored.
+pub fn parse_user_agent_version(user_agent: &str) -> Option<Version> {
+    let bytes = user_agent.as_bytes();
+    (0..bytes.len())
+        .filter(|&i| {
+            bytes[i].is_ascii_digit()
+                && (i == 0 || matches!(bytes[i - 1], b'/' | b'-' | b'_' | b' ' | b'v'))
+        })
+        .find_map(|start| {
+            let mut parts = user_agent[start..].splitn(3, '.');
+            let major = parse_digits(parts.next()?)?;
+            let minor = parse_digits(parts.next()?)?;
+            let rest = parts.next()?;
+            let patch_len = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let patch = parse_digits(&rest[..patch_len])?;
+            Some(Version::new(major, minor, patch))
+        })
+}
+
+fn parse_digits(s: &str) -> Option<u64> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+/// Send `local`, read the peer's handshake and check it is compatible.
+/// Gives up after `HANDSHAKE_TIMEOUT`.
+pub async fn exchange<S>(stream: &mut S, local: &Handshake) -> WalletResult<Handshake>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    exchange_within(stream, local, HANDSHAKE_TIMEOUT).await
+}
+
+async fn exchange_within<S>(
+    stream: &mut S,
+    local: &Handshake,
+    timeout: Duration,
+) -> WalletResult<Handshake>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let remote = tokio::time::timeout(timeout, async {
+        let encoded = local.to_bytes();
+        stream.write_u32_le(encoded.len() as u32).await?;
+        stream.write_all(&encoded).await?;
+        stream.flush().await?;
+
+        let len = stream.read_u32_le().await?;
+        if len > MAX_HANDSHAKE_BYTES {
+            return Err(std::io::Error::other(format!(
+                "handshake of {} bytes exceeds limit",
+                len
+            )));
+        }
+        let mut buf = vec![0u8; len as usize];
+        stream.read_exact(&mut buf).await?;
+        Ok(buf)
+    })
+    .await
+    .map_err(|_| WalletError::Network("Handshake timed out".to_string()))?
+    .map_err(|e| WalletError::Network(format!("Handshake failed: {}", e)))?;
+
+    let remote = Handshake::from_bytes(&remote)?;
+    remote.check_compatible(local)?;
+    Ok(remote)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local() -> Handshake {
+        Handshake {
+            protocol_version: PROTOCOL_VERSION,
+            user_agent: "nockchain/1.2.3".to_string(),
+            genesis_hash: [7; 32],
+            best_height: 10,
+            services: SERVICE_FULL_NODE,
+        }
+    }
+
+    /// Run both ends of a handshake over an in-memory pipe
+    async fn exchange_with(
+        remote: Handshake,
+    ) -> (WalletResult<Handshake>, WalletResult<Handshake>) {
+        let (mut ours, mut theirs) = tokio::io::duplex(1024);
+        let local = local();
+        tokio::join!(exchange(&mut ours, &local), exchange(&mut theirs, &remote))
+    }
+
+    #[tokio::test]
+    async fn a_compatible_peer_hands_back_its_height_and_user_agent() {
+        let remote = Handshake {
+            user_agent: "nockchain/1.3.0".to_string(),
+            best_height: 4321,
+            services: SERVICE_FULL_NODE | SERVICE_MINING,
+            ..local()
+        };
+        let (ours, theirs) = exchange_with(remote.clone()).await;
+        assert_eq!(ours.unwrap(), remote);
+        assert_eq!(theirs.unwrap(), local());
+    }
+
+    #[tokio::test]
+    async fn a_peer_on_another_genesis_is_rejected() {
+        let remote = Handshake {
+            genesis_hash: [8; 32],
+            ..local()
+        };
+        let (ours, _) = exchange_with(remote).await;
+        match ours {
+            Err(WalletError::HandshakeRejected(reason)) => {
+                assert!(reason.starts_with("genesis mismatch"), "{}", reason)
+            }
+            other => panic!("expected a genesis rejection, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn a_peer_outside_the_protocol_range_is_rejected() {
+        for protocol_version in [MIN_PROTOCOL_VERSION - 1, PROTOCOL_VERSION + 1] {
+            let remote = Handshake {
+                protocol_version,
+                ..local()
+            };
+            let (ours, _) = exchange_with(remote).await;
+            match ours {
+                Err(WalletError::HandshakeRejected(reason)) => assert_eq!(
+                    reason,
+                    format!(
+                        "protocol version {} not in supported range {}..={}",
+                        protocol_version, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+                    )
+                ),
+                other => panic!("expected a protocol rejection, got {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn a_silent_peer_times_out() {
+        let (mut ours, _theirs) = tokio::io::duplex(1024);
+        let result = exchange_within(&mut ours, &local(), Duration::from_millis(50)).await;
+        match result {
+            Err(WalletError::Network(message)) => assert_eq!(message, "Handshake timed out"),
+            other => panic!("expected a timeout, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn an_oversized_handshake_is_refused_before_allocating() {
+        let (mut ours, mut theirs) = tokio::io::duplex(1024);
+        let local = local();
+        let (result, _) = tokio::join!(exchange(&mut ours, &local), async {
+            theirs.write_u32_le(MAX_HANDSHAKE_BYTES + 1).await.unwrap();
+            theirs.flush().await.unwrap();
+        });
+        match result {
+            Err(WalletError::Network(message)) => {
+                assert!(message.contains("exceeds limit"), "{}", message)
+            }
+            other => panic!("expected an oversized handshake error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn user_agent_versions_are_parsed_and_checked() {
+        let cases = [
+            ("nockchain/1.2.3", Some(Version::new(1, 2, 3))),
+            ("nockchain-node-0.4.1-rc1", Some(Version::new(0, 4, 1))),
+            ("client v2.0.10", Some(Version::new(2, 0, 10))),
+            ("nockchain/1.2", None),
+            ("build42.1.1", None),
+        ];
+        for (user_agent, expected) in cases {
+            assert_eq!(
+                parse_user_agent_version(user_agent),
+                expected,
+                "{}",
+                user_agent
+            );
+        }
+
+        let required = VersionReq::parse(">=1.2.0").unwrap();
+        let old = Handshake {
+            user_agent: "nockchain/1.1.9".to_string(),
+            ..local()
+        };
+        let unversioned = Handshake {
+            user_agent: "mystery".to_string(),
+            ..local()
+        };
+        assert!(local().check_version(Some(&required), true).is_ok());
+        assert!(old.check_version(Some(&required), false).is_err());
+        assert!(unversioned.check_version(Some(&required), false).is_ok());
+        assert!(unversioned.check_version(Some(&required), true).is_err());
+        assert_eq!(
+            service_labels(SERVICE_FULL_NODE | SERVICE_MINING),
+            vec!["full node", "mining"]
+        );
+    }
+}
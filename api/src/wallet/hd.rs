@@ -0,0 +1,214 @@
+use ed25519_dalek::SigningKey;
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::wallet::Address;
+
+/// Consecutive unused addresses after which a scan stops deriving
+pub const DEFAULT_GAP_LIMIT: u32 = 20;
+
+/// Derivation branch of a chain: addresses handed out to receive payments,
+/// or internal addresses the wallet's own sends return their change to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Branch {
+    #[default]
+    Receive,
+    Change,
+}
+
+impl fmt::Display for Branch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Branch::Receive => "receive",
+            Branch::Change => "change",
+        })
+    }
+}
+
+/// Deterministically derive the ed25519 secret behind address `index` of
+/// `account` on `branch`
+pub fn derive_secret(seed: &[u8; 32], branch: Branch, account: u32, index: u32) -> [u8; 32] {
+    let hkdf = Hkdf::<Sha256>::new(Some(b"nockchain-wallet-hd"), seed);
+    let info = format!("{}/{}/{}", branch, account, index);
+    let mut secret = [0u8; 32];
+    hkdf.expand(info.as_bytes(), &mut secret)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    secret
+}
+
+/// Deterministically derive the address at `index` of `account` on `branch`
+pub fn derive_address(seed: &[u8; 32], branch: Branch, account: u32, index: u32) -> Address {
+    let signing_key = SigningKey::from_bytes(&derive_secret(seed, branch, account, index));
+    Address::from_public_key(signing_key.verifying_key().to_bytes())
+}
+
+/// Derived addresses of one account's branch and which of them have been used
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AddressChain {
+    pub account: u32,
+    #[serde(default)]
+    pub branch: Branch,
+    /// Derived addresses; the position is the derivation index
+    addresses: Vec<Address>,
+    used: BTreeSet<u32>,
+    /// Lowest index not yet handed out by `next_receive_address`
+    next_index: u32,
+}
+
+impl AddressChain {
+    /// Receive chain of `account`
+    pub fn new(account: u32) -> Self {
+        Self {
+            account,
+            ..Self::default()
+        }
+    }
+
+    /// Change chain of `account`
+    pub fn change(account: u32) -> Self {
+        Self {
+            account,
+            branch: Branch::Change,
+            ..Self::default()
+        }
+    }
+
+    /// Address at `index`, deriving it and any before it if needed
+    pub fn address_at(&mut self, seed: &[u8; 32], index: u32) -> &Address {
+        while self.addresses.len() <= index as usize {
+            let next = self.addresses.len() as u32;
+            self.addresses
+                .push(derive_address(seed, self.branch, self.account, next));
+        }
+        &self.addresses[index as usize]
+    }
+
+    /// Addresses derived so far, in index order
+    pub fn addresses(&self) -> &[Address] {
+        &self.addresses
+    }
+
+    pub fn index_of(&self, address: &Address) -> Option<u32> {
+        self.addresses
+            .iter()
+            .position(|candidate| candidate == address)
+            .map(|index| index as u32)
+    }
+
+    pub fn is_used(&self, index: u32) -> bool {
+        self.used.contains(&index)
+    }
+
+    /// Mark a derived address as used. Returns false if it is not in this chain.
+    pub fn mark_used(&mut self, address: &Address) -> bool {
+        match self.index_of(address) {
+            Some(index) => {
+                self.used.insert(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn mark_index_used(&mut self, index: u32) {
+        self.used.insert(index);
+    }
+
+    pub fn used_indices(&self) -> impl Iterator<Item = u32> + '_ {
+        self.used.iter().copied()
+    }
+
+    pub fn highest_used(&self) -> Option<u32> {
+        self.used.iter().next_back().copied()
+    }
+
+    pub fn next_index(&self) -> u32 {
+        self.next_index
+    }
+
+    /// Index `next_receive_address` would hand out: the first unused one at or after
+    /// the cursor
+    pub fn peek_receive_index(&self) -> u32 {
+        let mut index = self.next_index;
+        while self.used.contains(&index) {
+            index += 1;
+        }
+        index
+    }
+
+    /// Move the cursor past `index`
+    pub fn advance_past(&mut self, index: u32) {
+        self.next_index = self.next_index.max(index.saturating_add(1));
+    }
+
+    pub fn derived_count(&self) -> u32 {
+        self.addresses.len() as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: [u8; 32] = [7; 32];
+
+    fn receive(account: u32, index: u32) -> Address {
+        derive_address(&SEED, Branch::Receive, account, index)
+    }
+
+    #[test]
+    fn derivation_is_deterministic_and_distinct() {
+        assert_eq!(receive(0, 3), receive(0, 3));
+        assert_ne!(receive(0, 3), receive(0, 4));
+        assert_ne!(receive(0, 3), receive(1, 3));
+        assert_ne!(receive(0, 3), derive_address(&SEED, Branch::Change, 0, 3));
+        assert_ne!(
+            receive(0, 3),
+            derive_address(&[8; 32], Branch::Receive, 0, 3)
+        );
+    }
+
+    #[test]
+    fn chains_derive_lazily_and_find_their_own_addresses() {
+        let mut chain = AddressChain::new(2);
+        assert_eq!(chain.derived_count(), 0);
+        let fifth = chain.address_at(&SEED, 4).clone();
+        assert_eq!(fifth, receive(2, 4));
+        assert_eq!(chain.derived_count(), 5);
+        assert_eq!(chain.index_of(&fifth), Some(4));
+
+        assert!(chain.mark_used(&fifth));
+        assert!(!chain.mark_used(&receive(2, 9)));
+        assert!(chain.is_used(4));
+        assert_eq!(chain.highest_used(), Some(4));
+    }
+
+    #[test]
+    fn the_receive_cursor_skips_used_addresses_and_never_moves_back() {
+        let mut chain = AddressChain::new(0);
+        chain.mark_index_used(0);
+        chain.mark_index_used(1);
+        chain.mark_index_used(3);
+        assert_eq!(chain.peek_receive_index(), 2);
+
+        chain.advance_past(2);
+        assert_eq!(chain.next_index(), 3);
+        assert_eq!(chain.peek_receive_index(), 4);
+
+        chain.advance_past(0);
+        assert_eq!(chain.next_index(), 3);
+    }
+
+    #[test]
+    fn chains_round_trip_through_json() {
+        let mut chain = AddressChain::new(1);
+        chain.address_at(&SEED, 2);
+        chain.mark_index_used(1);
+        chain.advance_past(1);
+        let json = serde_json::to_string(&chain).unwrap();
+        assert_eq!(serde_json::from_str::<AddressChain>(&json).unwrap(), chain);
+    }
+}
@@ -0,0 +1,586 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::wallet::data_dir::{probe_writable, DataDirLimits, DiskCapacity, SystemDisk};
+use crate::wallet::network::{NodeHandle, NodeStats, NodeStatus};
+use crate::wallet::sync_progress::SyncProgress;
+
+/// Upper bound on how long any single component probe may take
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Blocks behind the network tip before the node is reported as degraded
+pub const MAX_SYNC_LAG_BLOCKS: u64 = 6;
+
+/// How long since the last wallet sync before the wallet is reported as degraded
+pub const MAX_SYNC_AGE: chrono::Duration = chrono::Duration::hours(1);
+
+/// Health of a single component, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum HealthStatus {
+    Ok,
+    Degraded,
+    Unhealthy,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeHealth {
+    pub status: HealthStatus,
+    pub node_status: Option<NodeStatus>,
+    pub connected_peers: u32,
+    /// Blocks behind the best known tip, if the tip is known
+    pub sync_lag: Option<u64>,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StorageHealth {
+    pub status: HealthStatus,
+    pub writable: bool,
+    pub available_bytes: Option<u64>,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WalletHealth {
+    pub status: HealthStatus,
+    pub unlocked: bool,
+    pub last_sync: Option<DateTime<Utc>>,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcHealth {
+    pub status: HealthStatus,
+    pub listening: bool,
+    pub detail: Option<String>,
+}
+
+/// Combined health of node, storage, wallet and RPC with an overall rollup
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub overall: HealthStatus,
+    pub node: NodeHealth,
+    pub storage: StorageHealth,
+    pub wallet: WalletHealth,
+    pub rpc: RpcHealth,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl HealthReport {
+    pub fn new(
+        node: NodeHealth,
+        storage: StorageHealth,
+        wallet: WalletHealth,
+        rpc: RpcHealth,
+    ) -> Self {
+        let overall = [node.status, storage.status, wallet.status, rpc.status]
+            .into_iter()
+            .max()
+            .unwrap_or(HealthStatus::Ok);
+
+        Self {
+            overall,
+            node,
+            storage,
+            wallet,
+            rpc,
+            checked_at: Utc::now(),
+        }
+    }
+
+    /// One line per component that is not Ok, for tooltips and logs
+    pub fn problems(&self) -> Vec<String> {
+        [
+            ("Node", self.node.status, &self.node.detail),
+            ("Storage", self.storage.status, &self.storage.detail),
+            ("Wallet", self.wallet.status, &self.wallet.detail),
+            ("RPC", self.rpc.status, &self.rpc.detail),
+        ]
+        .into_iter()
+        .filter(|(_, status, _)| *status != HealthStatus::Ok)
+        .map(|(name, status, detail)| match detail {
+            Some(detail) => format!("{}: {:?} ({})", name, status, detail),
+            None => format!("{}: {:?}", name, status),
+        })
+        .collect()
+    }
+}
+
+/// Probe every component, each bounded by `PROBE_TIMEOUT`, and roll up the result
+pub async fn check_health(
+    node: &NodeHandle,
+    wallet_unlocked: bool,
+    wallet_last_sync: Option<DateTime<Utc>>,
+) -> HealthReport {
+    let (node_health, storage_target) = probe_node(node).await;
+    let storage = match storage_target {
+        Some((data_dir, limits)) => probe_storage(data_dir, limits).await,
+        None => StorageHealth {
+            status: HealthStatus::Degraded,
+            writable: false,
+            available_bytes: None,
+            detail: Some("Data directory unknown while the node is unresponsive".to_string()),
+        },
+    };
+
+    HealthReport::new(
+        node_health,
+        storage,
+        wallet_health(wallet_unlocked, wallet_last_sync),
+        rpc_health(node.rpc_listening()),
+    )
+}
+
+/// Snapshot the node through the handle without waiting longer than `PROBE_TIMEOUT`.
+/// Also returns the data dir and limits so storage can be probed separately.
+async fn probe_node(node: &NodeHandle) -> (NodeHealth, Option<(PathBuf, DataDirLimits)>) {
+    let snapshot = node
+        .with_timeout(PROBE_TIMEOUT, |manager| {
+            let config = manager.get_config();
+            (
+                manager.get_status(),
+                manager.get_node_stats(),
+                (config.data_dir.clone(), config.data_dir_limits.clone()),
+            )
+        })
+        .await;
+
+    let Some((node_status, stats, storage_target)) = snapshot else {
+        let health = NodeHealth {
+            status: HealthStatus::Degraded,
+            node_status: None,
+            connected_peers: 0,
+            sync_lag: None,
+            detail: Some("Node did not respond in time".to_string()),
+        };
+        return (health, None);
+    };
+
+    (
+        node_health(node_status, stats.as_ref()),
+        Some(storage_target),
+    )
+}
+
+/// Rate a node snapshot: errors are unhealthy; stopped, transitional,
+/// peerless or lagging nodes are degraded
+pub fn node_health(node_status: NodeStatus, stats: Option<&NodeStats>) -> NodeHealth {
+    let connected_peers = stats.map(|s| s.connected_peers).unwrap_or(0);
+    let sync_lag = stats.and_then(|s| s.sync_lag());
+    let (status, detail) = match &node_status {
+        NodeStatus::Error(e) => (HealthStatus::Unhealthy, Some(e.clone())),
+        NodeStatus::Stopped => (HealthStatus::Degraded, Some("Node is stopped".to_string())),
+        NodeStatus::Starting | NodeStatus::Stopping => (
+            HealthStatus::Degraded,
+            Some(format!("Node is {:?}", node_status).to_lowercase()),
+        ),
+        NodeStatus::Running if connected_peers == 0 => (
+            HealthStatus::Degraded,
+            Some("No connected peers".to_string()),
+        ),
+        NodeStatus::Running if sync_lag.is_some_and(|lag| lag > MAX_SYNC_LAG_BLOCKS) => {
+            let lag = format!("{} blocks behind", sync_lag.unwrap_or(0));
+            let detail = match stats.and_then(|s| s.sync.as_ref()) {
+                Some(sync) if sync.stalled => format!("{}, sync stalled", lag),
+                Some(SyncProgress { eta: Some(eta), .. }) => {
+                    format!("{}, about {} min left", lag, eta.as_secs().div_ceil(60))
+                }
+                _ => lag,
+            };
+            (HealthStatus::Degraded, Some(detail))
+        }
+        NodeStatus::Running => (HealthStatus::Ok, None),
+    };
+
+    NodeHealth {
+        status,
+        node_status: Some(node_status),
+        connected_peers,
+        sync_lag,
+        detail,
+    }
+}
+
+/// Check the data dir is writable and has room, off the async executor
+pub async fn probe_storage(data_dir: PathBuf, limits: DataDirLimits) -> StorageHealth {
+    probe_storage_with(data_dir, limits, SystemDisk).await
+}
+
+pub async fn probe_storage_with<D>(
+    data_dir: PathBuf,
+    limits: DataDirLimits,
+    disk: D,
+) -> StorageHealth
+where
+    D: DiskCapacity + 'static,
+{
+    let probe = tokio::task::spawn_blocking(move || {
+        let writable = probe_writable(&data_dir);
+        let available = disk.available_space(&data_dir).ok();
+        (writable, available)
+    });
+
+    let (writable, available_bytes) = match tokio::time::timeout(PROBE_TIMEOUT, probe).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(e)) => {
+            return StorageHealth {
+                status: HealthStatus::Degraded,
+                writable: false,
+                available_bytes: None,
+                detail: Some(format!("Storage probe failed: {}", e)),
+            }
+        }
+        Err(_) => {
+            return StorageHealth {
+                status: HealthStatus::Degraded,
+                writable: false,
+                available_bytes: None,
+                detail: Some("Storage did not respond in time".to_string()),
+            }
+        }
+    };
+
+    let (status, detail) = match (&writable, available_bytes) {
+        (Err(e), _) => (HealthStatus::Unhealthy, Some(e.to_string())),
+        (Ok(()), Some(free)) if free < limits.min_free_bytes => (
+            HealthStatus::Unhealthy,
+            Some(format!("Only {} MiB free", free / (1024 * 1024))),
+        ),
+        (Ok(()), Some(free)) if free < limits.min_free_bytes.saturating_mul(2) => (
+            HealthStatus::Degraded,
+            Some(format!("Low disk space: {} MiB free", free / (1024 * 1024))),
+        ),
+        (Ok(()), None) => (
+            HealthStatus::Degraded,
+            Some("Free space unknown".to_string()),
+        ),
+        (Ok(()), Some(_)) => (HealthStatus::Ok, None),
+    };
+
+    StorageHealth {
+        status,
+        writable: writable.is_ok(),
+        available_bytes,
+        detail,
+    }
+}
+
+/// Rate wallet state: a locked wallet is fine, a stale or missing sync is not
+pub fn wallet_health(unlocked: bool, last_sync: Option<DateTime<Utc>>) -> WalletHealth {
+    let (status, detail) = match last_sync {
+        None => (
+            HealthStatus::Degraded,
+            Some("Wallet has never synced".to_string()),
+        ),
+        Some(at) if Utc::now() - at > MAX_SYNC_AGE => (
+            HealthStatus::Degraded,
+            Some(format!("Last synced {}", at.format("%Y-%m-%d %H:%M UTC"))),
+        ),
+        Some(_) => (HealthStatus::Ok, None),
+    };
+
+    WalletHealth {
+        status,
+        unlocked,
+        last_sync,
+        detail,
+    }
+}
+
+pub fn rpc_health(listening: bool) -> RpcHealth {
+    RpcHealth {
+        status: if listening {
+            HealthStatus::Ok
+        } else {
+            HealthStatus::Degraded
+        },
+        listening,
+        detail: (!listening).then(|| "RPC server is not listening".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fakenet_config, FakeDisk, TempDir};
+    use crate::wallet::network::{NockchainNodeConfig, NockchainNodeManager};
+    use std::sync::Arc;
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+    const STATUSES: [HealthStatus; 3] = [
+        HealthStatus::Ok,
+        HealthStatus::Degraded,
+        HealthStatus::Unhealthy,
+    ];
+
+    fn stats(connected_peers: u32, block_height: u64, tip: Option<u64>) -> NodeStats {
+        NodeStats {
+            uptime_seconds: 60,
+            connected_peers,
+            min_broadcast_peers: 0,
+            block_height,
+            best_known_height: tip,
+            mempool_size: 0,
+            network_in_bytes: 0,
+            network_out_bytes: 0,
+            network_in_rate: 0.0,
+            network_out_rate: 0.0,
+            data_dir_usage: None,
+            logs_dropped_count: 0,
+            peer_versions: Default::default(),
+            sync: None,
+        }
+    }
+
+    fn limits(min_free_bytes: u64) -> DataDirLimits {
+        DataDirLimits {
+            min_free_bytes,
+            soft_cap_bytes: None,
+        }
+    }
+
+    #[test]
+    fn overall_is_the_worst_component_for_every_combination() {
+        for node in STATUSES {
+            for storage in STATUSES {
+                for wallet in STATUSES {
+                    for rpc in STATUSES {
+                        let mut report = HealthReport::new(
+                            NodeHealth {
+                                status: node,
+                                ..node_health(NodeStatus::Stopped, None)
+                            },
+                            StorageHealth {
+                                status: storage,
+                                writable: true,
+                                available_bytes: None,
+                                detail: None,
+                            },
+                            WalletHealth {
+                                status: wallet,
+                                ..wallet_health(true, Some(Utc::now()))
+                            },
+                            RpcHealth {
+                                status: rpc,
+                                ..rpc_health(true)
+                            },
+                        );
+                        let worst = *[node, storage, wallet, rpc].iter().max().unwrap();
+                        assert_eq!(report.overall, worst);
+
+                        report.node.detail = None;
+                        let failing = [node, storage, wallet, rpc]
+                            .iter()
+                            .filter(|status| **status != HealthStatus::Ok)
+                            .count();
+                        assert_eq!(report.problems().len(), failing);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn problems_name_the_component_and_its_detail() {
+        let report = HealthReport::new(
+            node_health(NodeStatus::Error("crashed".to_string()), None),
+            StorageHealth {
+                status: HealthStatus::Ok,
+                writable: true,
+                available_bytes: Some(GIB),
+                detail: None,
+            },
+            wallet_health(false, Some(Utc::now())),
+            rpc_health(false),
+        );
+        assert_eq!(report.overall, HealthStatus::Unhealthy);
+        assert_eq!(
+            report.problems(),
+            vec![
+                "Node: Unhealthy (crashed)".to_string(),
+                "RPC: Degraded (RPC server is not listening)".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn node_rating_by_status_peers_and_lag() {
+        let rate = |status: NodeStatus, stats: Option<NodeStats>| {
+            let health = node_health(status, stats.as_ref());
+            (health.status, health.detail)
+        };
+        let degraded = |detail: &str| (HealthStatus::Degraded, Some(detail.to_string()));
+
+        assert_eq!(
+            rate(NodeStatus::Running, Some(stats(3, 100, Some(106)))),
+            (HealthStatus::Ok, None)
+        );
+        assert_eq!(
+            rate(NodeStatus::Running, Some(stats(3, 100, None))),
+            (HealthStatus::Ok, None)
+        );
+        assert_eq!(
+            rate(NodeStatus::Running, Some(stats(3, 100, Some(107)))),
+            degraded("7 blocks behind")
+        );
+        assert_eq!(
+            rate(NodeStatus::Running, Some(stats(0, 100, Some(100)))),
+            degraded("No connected peers")
+        );
+        assert_eq!(rate(NodeStatus::Stopped, None), degraded("Node is stopped"));
+        assert_eq!(
+            rate(NodeStatus::Starting, None),
+            degraded("node is starting")
+        );
+        assert_eq!(
+            rate(NodeStatus::Stopping, None),
+            degraded("node is stopping")
+        );
+        assert_eq!(
+            rate(NodeStatus::Error("disk".to_string()), None),
+            (HealthStatus::Unhealthy, Some("disk".to_string()))
+        );
+
+        let mut lagging = stats(3, 0, Some(50));
+        lagging.sync = Some(SyncProgress {
+            current: 0,
+            target: 50,
+            blocks_per_sec: 0.1,
+            bytes_per_sec: 0.0,
+            eta: Some(Duration::from_secs(500)),
+            stalled: false,
+        });
+        assert_eq!(
+            rate(NodeStatus::Running, Some(lagging.clone())),
+            degraded("50 blocks behind, about 9 min left")
+        );
+        lagging.sync.as_mut().unwrap().stalled = true;
+        assert_eq!(
+            rate(NodeStatus::Running, Some(lagging)),
+            degraded("50 blocks behind, sync stalled")
+        );
+    }
+
+    #[test]
+    fn wallet_needs_a_recent_sync_but_not_an_unlock() {
+        assert_eq!(
+            wallet_health(false, Some(Utc::now())).status,
+            HealthStatus::Ok
+        );
+        assert_eq!(wallet_health(true, None).status, HealthStatus::Degraded);
+        let stale = wallet_health(
+            true,
+            Some(Utc::now() - MAX_SYNC_AGE - chrono::Duration::minutes(1)),
+        );
+        assert_eq!(stale.status, HealthStatus::Degraded);
+        assert!(stale.detail.unwrap().starts_with("Last synced"));
+    }
+
+    #[tokio::test]
+    async fn storage_rating_by_space_and_writability() {
+        let dir = TempDir::new("health-storage");
+        let probe = |free: u64| {
+            probe_storage_with(
+                dir.path().to_path_buf(),
+                limits(GIB),
+                FakeDisk::new(free, 10 * GIB),
+            )
+        };
+
+        assert_eq!(probe(3 * GIB).await.status, HealthStatus::Ok);
+        let low = probe(GIB + 1).await;
+        assert_eq!((low.status, low.writable), (HealthStatus::Degraded, true));
+        assert_eq!(probe(GIB - 1).await.status, HealthStatus::Unhealthy);
+
+        let unknown =
+            probe_storage_with(dir.path().to_path_buf(), limits(GIB), FakeDisk::failing()).await;
+        assert_eq!(unknown.status, HealthStatus::Degraded);
+        assert_eq!(unknown.detail.as_deref(), Some("Free space unknown"));
+
+        let unwritable = probe_storage_with(
+            dir.join("missing"),
+            limits(0),
+            FakeDisk::new(3 * GIB, 10 * GIB),
+        )
+        .await;
+        assert_eq!(
+            (unwritable.status, unwritable.writable),
+            (HealthStatus::Unhealthy, false)
+        );
+    }
+
+    /// Disk whose every query hangs past `PROBE_TIMEOUT`
+    struct HungDisk;
+
+    impl DiskCapacity for HungDisk {
+        fn available_space(&self, _path: &std::path::Path) -> std::io::Result<u64> {
+            std::thread::sleep(PROBE_TIMEOUT + Duration::from_millis(200));
+            Ok(0)
+        }
+
+        fn total_space(&self, path: &std::path::Path) -> std::io::Result<u64> {
+            self.available_space(path)
+        }
+    }
+
+    #[tokio::test]
+    async fn hung_subsystems_degrade_instead_of_blocking() {
+        let dir = TempDir::new("health-hung");
+        let started = std::time::Instant::now();
+        let storage = probe_storage_with(dir.path().to_path_buf(), limits(0), HungDisk).await;
+        assert_eq!(storage.status, HealthStatus::Degraded);
+        assert_eq!(
+            storage.detail.as_deref(),
+            Some("Storage did not respond in time")
+        );
+
+        let node = NodeHandle::new(NockchainNodeManager::new(NockchainNodeConfig {
+            data_dir: dir.path().to_path_buf(),
+            ..fakenet_config()
+        }));
+        // Another thread sits on the manager lock, as a stuck start would
+        let (locked_tx, locked_rx) = std::sync::mpsc::channel();
+        let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+        let shared = node.shared();
+        let holder = std::thread::spawn(move || {
+            let _held = shared.lock().unwrap();
+            locked_tx.send(()).unwrap();
+            let _ = release_rx.recv();
+        });
+        locked_rx.recv().unwrap();
+        let report = check_health(&node, true, Some(Utc::now())).await;
+        release_tx.send(()).unwrap();
+        holder.join().unwrap();
+
+        assert_eq!(report.overall, HealthStatus::Degraded);
+        assert_eq!(
+            report.node.detail.as_deref(),
+            Some("Node did not respond in time")
+        );
+        assert_eq!(report.storage.status, HealthStatus::Degraded);
+        assert!(started.elapsed() < PROBE_TIMEOUT * 3);
+    }
+
+    #[tokio::test]
+    async fn running_fakenet_node_without_peers_is_degraded() {
+        let dir = TempDir::new("health-node");
+        let mut manager = NockchainNodeManager::new(NockchainNodeConfig {
+            data_dir: dir.path().to_path_buf(),
+            data_dir_limits: limits(0),
+            ..fakenet_config()
+        })
+        .with_disk_capacity(Arc::new(FakeDisk::new(GIB, GIB)));
+        manager.start_node().await.unwrap();
+        let node = NodeHandle::new(manager);
+        node.set_rpc_listening(true);
+
+        let report = check_health(&node, true, Some(Utc::now())).await;
+        assert_eq!(report.node.node_status, Some(NodeStatus::Running));
+        assert_eq!(report.node.detail.as_deref(), Some("No connected peers"));
+        assert_eq!(report.storage.status, HealthStatus::Ok);
+        assert_eq!(report.rpc.status, HealthStatus::Ok);
+        assert_eq!(report.overall, HealthStatus::Degraded);
+    }
+}
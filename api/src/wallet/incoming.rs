@@ -0,0 +1,472 @@
+//! Incoming payments seen before they are mined.
+//!
+//! A mempool transaction paying one of the wallet's addresses, and not funded
+//! by it, is recorded straight away as a pending incoming transaction with
+//! unconfirmed notes, so it shows in the pending balance with 0
+//! confirmations. Each later scan settles it: a block containing it confirms
+//! it, a conflicting transaction that took its place fails it, and leaving the
+//! mempool otherwise removes it again.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::wallet::amount::{format_amount, AmountUnit};
+use crate::wallet::chain::Blockchain;
+use crate::wallet::keys::OutPoint;
+use crate::wallet::provenance::NoteOrigin;
+use crate::wallet::transaction::SignedTransaction;
+use crate::wallet::{Address, Note, TransactionDirection, Wallet};
+
+/// Change to an unconfirmed incoming payment found by `Wallet::track_incoming`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum IncomingEvent {
+    /// A payment to the wallet entered the mempool
+    Arrived { tx_id: String, amount: u64 },
+    /// A block containing the payment reached the chain
+    Confirmed {
+        tx_id: String,
+        amount: u64,
+        block_height: u64,
+    },
+    /// The payment left the mempool without being mined and was removed
+    Evicted { tx_id: String, amount: u64 },
+    /// A transaction spending the same inputs took the payment's place, so it
+    /// is kept as failed
+    Replaced {
+        tx_id: String,
+        amount: u64,
+        by: String,
+    },
+}
+
+impl IncomingEvent {
+    pub fn tx_id(&self) -> &str {
+        match self {
+            IncomingEvent::Arrived { tx_id, .. }
+            | IncomingEvent::Confirmed { tx_id, .. }
+            | IncomingEvent::Evicted { tx_id, .. }
+            | IncomingEvent::Replaced { tx_id, .. } => tx_id,
+        }
+    }
+}
+
+impl fmt::Display for IncomingEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IncomingEvent::Arrived { amount, .. } => write!(
+                f,
+                "Incoming payment of {} NOCK (unconfirmed)",
+                format_amount(*amount, AmountUnit::Nock)
+            ),
+            IncomingEvent::Confirmed {
+                amount,
+                block_height,
+                ..
+            } => write!(
+                f,
+                "Incoming payment of {} NOCK confirmed in block {}",
+                format_amount(*amount, AmountUnit::Nock),
+                block_height
+            ),
+            IncomingEvent::Evicted { amount, .. } => write!(
+                f,
+                "Incoming payment of {} NOCK was dropped from the mempool",
+                format_amount(*amount, AmountUnit::Nock)
+            ),
+            IncomingEvent::Replaced { amount, .. } => write!(
+                f,
+                "Incoming payment of {} NOCK was replaced by a double spend",
+                format_amount(*amount, AmountUnit::Nock)
+            ),
+        }
+    }
+}
+
+impl Wallet {
+    /// Record mempool transactions paying the wallet and settle the ones
+    /// recorded by earlier scans. `pending` is the whole mempool. Without a
+    /// `chain` a payment that left the mempool stays pending unless a
+    /// conflicting transaction is pending in its place, since being mined and
+    /// being evicted can't be told apart.
+    pub fn track_incoming(
+        &mut self,
+        pending: &[SignedTransaction],
+        chain: Option<&Blockchain>,
+    ) -> Vec<IncomingEvent> {
+        let mut events = Vec::new();
+        if self.ensure_writable().is_err() {
+            return events;
+        }
+
+        for tx in pending {
+            if let Some(event) = self.record_incoming(tx) {
+                events.push(event);
+            }
+        }
+
+        let unsettled: Vec<(String, u64)> = self
+            .transactions
+            .get_pending_transactions()
+            .iter()
+            .filter(|tx| tx.direction == TransactionDirection::Incoming)
+            .map(|tx| (tx.id.clone(), tx.amount))
+            .collect();
+        for (tx_id, amount) in unsettled {
+            if let Some(event) = self.settle_incoming(&tx_id, amount, pending, chain) {
+                events.push(event);
+            }
+        }
+
+        for event in &events {
+            info!("{} ({})", event, event.tx_id());
+        }
+        events
+    }
+
+    /// Record `tx` as a pending incoming payment if it pays the wallet, isn't
+    /// funded by it and isn't recorded yet
+    fn record_incoming(&mut self, tx: &SignedTransaction) -> Option<IncomingEvent> {
+        if self.transactions.contains(&tx.id) {
+            return None;
+        }
+        let funded_by_wallet = tx
+            .inputs
+            .iter()
+            .any(|input| self.balances.note_at(input.outpoint()).is_some());
+        if funded_by_wallet {
+            return None;
+        }
+
+        let ours: Vec<(u32, Address, u64)> = tx
+            .outputs
+            .iter()
+            .enumerate()
+            .filter_map(|(index, output)| {
+                let address = Address::from_string(&output.recipient_address).ok()?;
+                self.keys
+                    .is_mine(&address)
+                    .then_some((index as u32, address, output.amount))
+            })
+            .collect();
+        if ours.is_empty() {
+            return None;
+        }
+
+        let now = chrono::Utc::now();
+        for (index, address, amount) in &ours {
+            self.observe_note(Note {
+                id: Uuid::new_v4(),
+                address: address.clone(),
+                amount: *amount,
+                block_height: None,
+                transaction_id: tx.id.clone(),
+                output_index: *index,
+                spent: false,
+                locked: false,
+                created_at: now,
+                coinbase: false,
+                origin: Some(NoteOrigin::External),
+            })
+            .ok()?;
+        }
+        self.record_transaction(tx.clone(), false);
+
+        Some(IncomingEvent::Arrived {
+            tx_id: tx.id.clone(),
+            amount: ours.iter().map(|(_, _, amount)| amount).sum(),
+        })
+    }
+
+    /// Confirm, fail or remove the pending incoming payment `tx_id` if its
+    /// fate is known
+    fn settle_incoming(
+        &mut self,
+        tx_id: &str,
+        amount: u64,
+        pending: &[SignedTransaction],
+        chain: Option<&Blockchain>,
+    ) -> Option<IncomingEvent> {
+        if let Some(block_height) = chain.and_then(|chain| confirming_height(chain, tx_id)) {
+            self.balances.confirm_notes(tx_id, block_height);
+            self.transactions
+                .confirm_transaction(tx_id, block_height)
+                .ok()?;
+            return Some(IncomingEvent::Confirmed {
+                tx_id: tx_id.to_string(),
+                amount,
+                block_height,
+            });
+        }
+        if pending.iter().any(|tx| tx.id == tx_id) {
+            return None;
+        }
+
+        let signed = self.transactions.signed(tx_id)?.clone();
+        let spends: Vec<&OutPoint> = signed.input_outpoints().into_iter().collect();
+        let conflicting = pending
+            .iter()
+            .find(|other| signed.conflicts_with(other))
+            .map(|other| other.id.clone())
+            .or_else(|| chain.and_then(|chain| conflicting_in_chain(chain, tx_id, &spends)));
+
+        match conflicting {
+            Some(by) => {
+                self.balances.remove_unconfirmed_notes(tx_id);
+                let reason = format!(
+                    "Replaced by transaction {}, which spends the same inputs (double spend)",
+                    by
+                );
+                self.transactions.fail_transaction(tx_id, &reason).ok()?;
+                Some(IncomingEvent::Replaced {
+                    tx_id: tx_id.to_string(),
+                    amount,
+                    by,
+                })
+            }
+            None if chain.is_some() => {
+                self.balances.remove_unconfirmed_notes(tx_id);
+                self.transactions.remove_pending(tx_id)?;
+                Some(IncomingEvent::Evicted {
+                    tx_id: tx_id.to_string(),
+                    amount,
+                })
+            }
+            None => None,
+        }
+    }
+}
+
+/// Height of the block on `chain` containing `tx_id`
+fn confirming_height(chain: &Blockchain, tx_id: &str) -> Option<u64> {
+    chain
+        .blocks()
+        .iter()
+        .find(|block| block.transactions.iter().any(|tx| tx.id == tx_id))
+        .map(|block| block.header.height)
+}
+
+/// Id of a transaction on `chain`, other than `tx_id`, spending any of `spends`
+fn conflicting_in_chain(chain: &Blockchain, tx_id: &str, spends: &[&OutPoint]) -> Option<String> {
+    chain
+        .blocks()
+        .iter()
+        .flat_map(|block| &block.transactions)
+        .find(|tx| {
+            tx.id != tx_id
+                && tx
+                    .inputs
+                    .iter()
+                    .any(|input| spends.contains(&input.outpoint()))
+        })
+        .map(|tx| tx.id.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_address, fake_note, fake_signed_transaction, fakenet_chain_config};
+    use crate::wallet::keys::NockchainTransaction;
+    use crate::wallet::TransactionStatus;
+
+    fn wallet_with_key() -> (Wallet, Address) {
+        let mut wallet = Wallet::new();
+        let address = wallet.generate_key("Main").unwrap();
+        (wallet, address)
+    }
+
+    /// Chain whose genesis block holds `txs`
+    fn chain_with(txs: &[&SignedTransaction]) -> Blockchain {
+        let mut chain = Blockchain::new(fakenet_chain_config());
+        let included = txs
+            .iter()
+            .map(|tx| NockchainTransaction {
+                id: tx.id.clone(),
+                inputs: tx.inputs.clone(),
+                outputs: tx.outputs.clone(),
+                hash: tx.hash.clone(),
+                expiry_height: tx.expiry_height,
+            })
+            .collect();
+        let mut block = chain.new_block(included, chain.config().initial_difficulty);
+        block.mine().unwrap();
+        chain.add_block(block).unwrap();
+        chain
+    }
+
+    fn status(wallet: &Wallet, tx_id: &str) -> Option<TransactionStatus> {
+        wallet
+            .transactions()
+            .get_all_transactions()
+            .into_iter()
+            .find(|tx| tx.id == tx_id)
+            .map(|tx| tx.status)
+    }
+
+    #[test]
+    fn a_payment_in_the_mempool_shows_as_pending_straight_away() {
+        let (mut wallet, address) = wallet_with_key();
+        let payment = fake_signed_transaction()
+            .paying(&address, 4_000)
+            .paying(&fake_address(), 900)
+            .paying(&address, 1_000)
+            .build();
+
+        let events = wallet.track_incoming(std::slice::from_ref(&payment), None);
+        assert_eq!(
+            events,
+            [IncomingEvent::Arrived {
+                tx_id: payment.id.clone(),
+                amount: 5_000,
+            }]
+        );
+        let balance = wallet.balances().get_total_balance();
+        assert_eq!((balance.unconfirmed, balance.confirmed), (5_000, 0));
+        let pending = wallet.transactions().get_pending_transactions();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].direction, TransactionDirection::Incoming);
+
+        // Seen again on the next scan: nothing new
+        assert!(wallet.track_incoming(&[payment], None).is_empty());
+        assert_eq!(wallet.balances().get_total_balance().unconfirmed, 5_000);
+    }
+
+    #[test]
+    fn payments_to_others_and_the_wallets_own_spends_are_ignored() {
+        let (mut wallet, address) = wallet_with_key();
+        let elsewhere = fake_signed_transaction()
+            .paying(&fake_address(), 700)
+            .build();
+
+        let note = fake_note().address(address.clone()).amount(2_000).build();
+        wallet.observe_note(note.clone()).unwrap();
+        let change = fake_signed_transaction()
+            .spending(note.transaction_id.clone(), note.output_index, 2_000)
+            .paying(&fake_address(), 1_500)
+            .paying(&address, 490)
+            .build();
+
+        assert!(wallet.track_incoming(&[elsewhere, change], None).is_empty());
+        assert!(wallet.transactions().get_pending_transactions().is_empty());
+    }
+
+    #[test]
+    fn a_mined_payment_is_confirmed() {
+        let (mut wallet, address) = wallet_with_key();
+        let payment = fake_signed_transaction().paying(&address, 3_000).build();
+        wallet.track_incoming(std::slice::from_ref(&payment), None);
+
+        let chain = chain_with(&[&payment]);
+        let events = wallet.track_incoming(&[], Some(&chain));
+        assert_eq!(
+            events,
+            [IncomingEvent::Confirmed {
+                tx_id: payment.id.clone(),
+                amount: 3_000,
+                block_height: 0,
+            }]
+        );
+        let balance = wallet.balances().get_total_balance();
+        assert_eq!((balance.unconfirmed, balance.confirmed), (0, 3_000));
+        assert!(matches!(
+            status(&wallet, &payment.id),
+            Some(TransactionStatus::Confirmed { .. })
+        ));
+        assert!(wallet.track_incoming(&[], Some(&chain)).is_empty());
+    }
+
+    #[test]
+    fn a_payment_that_leaves_the_mempool_unmined_is_removed() {
+        let (mut wallet, address) = wallet_with_key();
+        let payment = fake_signed_transaction().paying(&address, 3_000).build();
+        wallet.track_incoming(std::slice::from_ref(&payment), None);
+
+        // Without a chain, mined and evicted look the same: keep waiting
+        assert!(wallet.track_incoming(&[], None).is_empty());
+        assert_eq!(wallet.balances().get_total_balance().unconfirmed, 3_000);
+
+        let chain = chain_with(&[]);
+        let events = wallet.track_incoming(&[], Some(&chain));
+        assert_eq!(
+            events,
+            [IncomingEvent::Evicted {
+                tx_id: payment.id.clone(),
+                amount: 3_000,
+            }]
+        );
+        assert_eq!(
+            wallet.balances().get_total_balance(),
+            crate::wallet::Balance::new()
+        );
+        assert_eq!(status(&wallet, &payment.id), None);
+    }
+
+    #[test]
+    fn a_double_spend_in_the_mempool_fails_the_payment() {
+        let (mut wallet, address) = wallet_with_key();
+        let payment = fake_signed_transaction()
+            .spending("aa".repeat(32), 0, 3_010)
+            .paying(&address, 3_000)
+            .build();
+        wallet.track_incoming(std::slice::from_ref(&payment), None);
+
+        let replacement = fake_signed_transaction()
+            .spending("aa".repeat(32), 0, 3_010)
+            .paying(&fake_address(), 3_000)
+            .build();
+        let events = wallet.track_incoming(std::slice::from_ref(&replacement), None);
+        assert_eq!(
+            events,
+            [IncomingEvent::Replaced {
+                tx_id: payment.id.clone(),
+                amount: 3_000,
+                by: replacement.id.clone(),
+            }]
+        );
+        assert_eq!(wallet.balances().get_total_balance().unconfirmed, 0);
+        match status(&wallet, &payment.id) {
+            Some(TransactionStatus::Failed { reason }) => {
+                assert!(reason.contains(&replacement.id));
+                assert!(reason.contains("double spend"));
+            }
+            other => panic!("expected a failed payment, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn a_double_spend_mined_instead_fails_the_payment() {
+        let (mut wallet, address) = wallet_with_key();
+        let payment = fake_signed_transaction()
+            .spending("bb".repeat(32), 1, 3_010)
+            .paying(&address, 3_000)
+            .build();
+        wallet.track_incoming(std::slice::from_ref(&payment), None);
+
+        let replacement = fake_signed_transaction()
+            .spending("bb".repeat(32), 1, 3_010)
+            .paying(&fake_address(), 3_000)
+            .build();
+        let chain = chain_with(&[&replacement]);
+        let events = wallet.track_incoming(&[], Some(&chain));
+        assert!(matches!(
+            &events[..],
+            [IncomingEvent::Replaced { by, .. }] if *by == replacement.id
+        ));
+        assert!(matches!(
+            status(&wallet, &payment.id),
+            Some(TransactionStatus::Failed { .. })
+        ));
+    }
+
+    #[test]
+    fn events_read_as_toasts() {
+        let event = IncomingEvent::Confirmed {
+            tx_id: "t".to_string(),
+            amount: 150_000_000,
+            block_height: 42,
+        };
+        assert_eq!(event.tx_id(), "t");
+        assert!(event.to_string().ends_with("NOCK confirmed in block 42"));
+    }
+}
@@ -0,0 +1,462 @@
+//! Checksums of wallet storage files.
+//!
+//! `StorageManager` records the SHA-256 of every file it writes in a manifest
+//! in the data dir. `Wallet::verify_integrity` compares the files against it,
+//! so corruption shows up when the wallet is opened rather than as a
+//! deserialization error the next time a file happens to be read. While a
+//! critical file fails the check the wallet stays read-only. The same check
+//! confirms the ledger still sums to the wallet's unspent notes.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::warn;
+
+use crate::wallet::audit::AUDIT_LOG_FILE;
+use crate::wallet::balance::ReconciliationReport;
+use crate::wallet::encoding;
+use crate::wallet::ledger::LedgerCheck;
+use crate::wallet::spending::SPENDING_FILE;
+use crate::wallet::{Wallet, WalletError, WalletResult, ADDRESS_STATE_FILE};
+
+/// Content hashes of the files saved through `StorageManager`
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Files the wallet can't safely write without: losing them risks address
+/// reuse, bypassed spending limits or a broken audit trail
+const CRITICAL_FILES: &[&str] = &[ADDRESS_STATE_FILE, SPENDING_FILE, AUDIT_LOG_FILE];
+
+/// Serializes manifest updates from concurrent saves in this process
+static MANIFEST_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Manifest {
+    /// Hex SHA-256 by file name
+    files: BTreeMap<String, String>,
+}
+
+impl Manifest {
+    /// The manifest in `data_dir`; empty if there is none or it can't be read
+    fn load(data_dir: &Path) -> Self {
+        std::fs::read(data_dir.join(MANIFEST_FILE))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, data_dir: &Path) -> WalletResult<()> {
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|e| WalletError::Storage(format!("Serialization failed: {}", e)))?;
+        let path = data_dir.join(MANIFEST_FILE);
+        let tmp_path = data_dir.join(format!("{}.tmp", MANIFEST_FILE));
+        std::fs::write(&tmp_path, contents)
+            .and_then(|_| std::fs::rename(&tmp_path, &path))
+            .map_err(|e| WalletError::Storage(format!("Failed to save manifest: {}", e)))
+    }
+}
+
+fn content_hash(contents: &[u8]) -> String {
+    hex::encode(encoding::sha256(contents))
+}
+
+/// Record the hash of each file as just written, or forget it with `None`,
+/// rewriting the manifest once for all of them
+pub(crate) fn record_files<'a>(
+    data_dir: &Path,
+    files: impl IntoIterator<Item = (&'a str, Option<&'a [u8]>)>,
+) -> WalletResult<()> {
+    let _guard = MANIFEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut manifest = Manifest::load(data_dir);
+    for (filename, contents) in files {
+        match contents {
+            Some(contents) => {
+                manifest
+                    .files
+                    .insert(filename.to_string(), content_hash(contents));
+            }
+            None => {
+                manifest.files.remove(filename);
+            }
+        }
+    }
+    manifest.save(data_dir)
+}
+
+/// How bad a problem with one file is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Severity {
+    Info,
+    Warning,
+    /// The wallet opens read-only until this is resolved
+    Critical,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "Info",
+            Severity::Warning => "Warning",
+            Severity::Critical => "Critical",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileStatus {
+    Ok,
+    /// Listed in the manifest but gone from the data dir
+    Missing,
+    /// Contents differ from what the app last wrote
+    Modified,
+    /// Not valid JSON
+    Unparseable(String),
+    /// Not in the manifest, e.g. last written before checksums were kept
+    Untracked,
+}
+
+impl fmt::Display for FileStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileStatus::Ok => f.write_str("OK"),
+            FileStatus::Missing => f.write_str("Missing"),
+            FileStatus::Modified => f.write_str("Modified outside the app"),
+            FileStatus::Unparseable(e) => write!(f, "Unreadable: {}", e),
+            FileStatus::Untracked => f.write_str("No checksum recorded"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileCheck {
+    pub file: String,
+    pub status: FileStatus,
+    pub severity: Severity,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    pub checked_at: DateTime<Utc>,
+    /// Every file checked, by name
+    pub files: Vec<FileCheck>,
+    /// Ledger against the notes; `None` from `verify_data_dir`, which has
+    /// no wallet to check
+    #[serde(default)]
+    pub ledger: Option<LedgerCheck>,
+}
+
+impl IntegrityReport {
+    /// Files that are not OK
+    pub fn problems(&self) -> impl Iterator<Item = &FileCheck> {
+        self.files
+            .iter()
+            .filter(|check| check.status != FileStatus::Ok)
+    }
+
+    /// Worst severity among the problems, if there are any
+    pub fn severity(&self) -> Option<Severity> {
+        self.problems().map(|check| check.severity).max()
+    }
+
+    pub fn is_critical(&self) -> bool {
+        self.severity() == Some(Severity::Critical)
+    }
+}
+
+/// Check every file in the manifest, and any JSON file missing from it,
+/// against `data_dir`
+pub fn verify_data_dir(data_dir: &Path) -> IntegrityReport {
+    let manifest = {
+        let _guard = MANIFEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        Manifest::load(data_dir)
+    };
+
+    let mut names: Vec<String> = manifest.files.keys().cloned().collect();
+    if let Ok(entries) = std::fs::read_dir(data_dir) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.ends_with(".json") && name != MANIFEST_FILE && !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    names.sort();
+
+    let files = names
+        .into_iter()
+        .map(|file| {
+            let status = match std::fs::read(data_dir.join(&file)) {
+                Err(_) => FileStatus::Missing,
+                Ok(contents) => check_contents(&file, &contents, manifest.files.get(&file)),
+            };
+            let severity = match status {
+                FileStatus::Ok | FileStatus::Untracked => Severity::Info,
+                _ if CRITICAL_FILES.contains(&file.as_str()) => Severity::Critical,
+                _ => Severity::Warning,
+            };
+            FileCheck {
+                file,
+                status,
+                severity,
+            }
+        })
+        .collect();
+
+    IntegrityReport {
+        checked_at: Utc::now(),
+        files,
+        ledger: None,
+    }
+}
+
+fn check_contents(file: &str, contents: &[u8], expected: Option<&String>) -> FileStatus {
+    if file.ends_with(".json") {
+        if let Err(e) = serde_json::from_slice::<serde_json::Value>(contents) {
+            return FileStatus::Unparseable(e.to_string());
+        }
+    }
+    match expected {
+        None => FileStatus::Untracked,
+        Some(hash) if *hash == content_hash(contents) => FileStatus::Ok,
+        Some(_) => FileStatus::Modified,
+    }
+}
+
+/// Record the current contents of every file that parses, dropping missing
+/// and unreadable files from the manifest
+fn accept_data_dir(data_dir: &Path) -> WalletResult<()> {
+    let report = verify_data_dir(data_dir);
+    let _guard = MANIFEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut manifest = Manifest::default();
+    for check in &report.files {
+        if matches!(
+            check.status,
+            FileStatus::Missing | FileStatus::Unparseable(_)
+        ) {
+            continue;
+        }
+        if let Ok(contents) = std::fs::read(data_dir.join(&check.file)) {
+            manifest
+                .files
+                .insert(check.file.clone(), content_hash(&contents));
+        }
+    }
+    manifest.save(data_dir)
+}
+
+impl Wallet {
+    /// Check the wallet's files against their recorded checksums, and the
+    /// ledger against the notes. While a critical file fails, the wallet
+    /// releases its lock and stays read-only.
+    pub fn verify_integrity(&mut self) -> IntegrityReport {
+        let mut report = match self.data_dir.as_deref() {
+            Some(data_dir) => verify_data_dir(data_dir),
+            None => IntegrityReport {
+                checked_at: Utc::now(),
+                files: Vec::new(),
+                ledger: None,
+            },
+        };
+        let ledger = self.check_ledger();
+        if !ledger.is_balanced() {
+            warn!("{}", ledger);
+        }
+        report.ledger = Some(ledger);
+        if report.is_critical() {
+            self.lockfile = None;
+            self.read_only = true;
+        }
+        self.integrity = Some(report.clone());
+        report
+    }
+
+    /// Check the cached balances against the wallet's notes, rebuilding them
+    /// from the notes if they drifted. Part of verifying the wallet, and run
+    /// after every rescan.
+    pub fn reconcile_balances(&mut self) -> ReconciliationReport {
+        let report = self.balances.repair_balances();
+        if report.repaired {
+            warn!(
+                "Rebuilt cached balances of {} address(es) from {} notes",
+                report.discrepancies.len(),
+                report.notes
+            );
+        }
+        report
+    }
+
+    /// Result of the last `verify_integrity`, if it has run
+    pub fn integrity_report(&self) -> Option<&IntegrityReport> {
+        self.integrity.as_ref()
+    }
+
+    /// Whether the last check found a critical problem, keeping the wallet read-only
+    pub fn integrity_failed(&self) -> bool {
+        self.integrity
+            .as_ref()
+            .is_some_and(IntegrityReport::is_critical)
+    }
+
+    /// Trust the files as they are now: record the checksums of every file
+    /// that parses, then check again and take the wallet lock if that passes.
+    /// Files that don't parse stay critical. Fails if another instance holds
+    /// the lock or a newer version wrote the data dir.
+    pub fn accept_wallet_files(&mut self) -> WalletResult<IntegrityReport> {
+        let Some(data_dir) = self.data_dir.clone() else {
+            return Ok(self.verify_integrity());
+        };
+        if let Some(e) = self.unsupported_schema() {
+            return Err(e);
+        }
+        if !self.take_lockfile()? {
+            return Err(WalletError::ReadOnly(
+                "another instance has this wallet open".to_string(),
+            ));
+        }
+        accept_data_dir(&data_dir)?;
+        Ok(self.verify_integrity())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_note, TempDir};
+    use crate::wallet::storage::StorageManager;
+    use crate::wallet::Balance;
+    use serde_json::json;
+
+    const TEMPLATES: &str = "templates.json";
+
+    async fn saved_wallet_dir() -> TempDir {
+        let dir = TempDir::new("integrity");
+        let storage = StorageManager::new(dir.path().to_path_buf()).unwrap();
+        storage
+            .save(SPENDING_FILE, &json!({"sends": []}))
+            .await
+            .unwrap();
+        storage
+            .save(TEMPLATES, &json!({"rent": 100}))
+            .await
+            .unwrap();
+        dir
+    }
+
+    fn status_of<'a>(report: &'a IntegrityReport, file: &str) -> &'a FileCheck {
+        report
+            .files
+            .iter()
+            .find(|check| check.file == file)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn files_saved_through_storage_verify_clean() {
+        let dir = saved_wallet_dir().await;
+        let report = verify_data_dir(dir.path());
+        assert_eq!(report.files.len(), 2);
+        assert_eq!(report.severity(), None);
+
+        StorageManager::new(dir.path().to_path_buf())
+            .unwrap()
+            .delete(TEMPLATES)
+            .await
+            .unwrap();
+        assert_eq!(verify_data_dir(dir.path()).files.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn each_kind_of_damage_is_reported_with_its_severity() {
+        let dir = saved_wallet_dir().await;
+        std::fs::write(dir.join(TEMPLATES), r#"{"rent": 999}"#).unwrap();
+        std::fs::write(dir.join("stray.json"), "{}").unwrap();
+        let report = verify_data_dir(dir.path());
+        assert_eq!(status_of(&report, TEMPLATES).status, FileStatus::Modified);
+        assert_eq!(status_of(&report, TEMPLATES).severity, Severity::Warning);
+        assert_eq!(
+            status_of(&report, "stray.json").status,
+            FileStatus::Untracked
+        );
+        assert_eq!(status_of(&report, "stray.json").severity, Severity::Info);
+        assert_eq!(report.severity(), Some(Severity::Warning));
+
+        std::fs::remove_file(dir.join(SPENDING_FILE)).unwrap();
+        let report = verify_data_dir(dir.path());
+        assert_eq!(
+            status_of(&report, SPENDING_FILE).status,
+            FileStatus::Missing
+        );
+        assert!(report.is_critical());
+
+        std::fs::write(dir.join(SPENDING_FILE), "{\"sends\": [").unwrap();
+        let report = verify_data_dir(dir.path());
+        assert!(matches!(
+            status_of(&report, SPENDING_FILE).status,
+            FileStatus::Unparseable(_)
+        ));
+        assert_eq!(
+            status_of(&report, SPENDING_FILE).severity,
+            Severity::Critical
+        );
+    }
+
+    #[tokio::test]
+    async fn a_corrupt_critical_file_opens_the_wallet_read_only() {
+        let dir = saved_wallet_dir().await;
+        std::fs::write(dir.join(SPENDING_FILE), "{\"sends\": [").unwrap();
+
+        let mut wallet = Wallet::open(dir.path()).unwrap();
+        assert!(wallet.is_read_only());
+        assert!(wallet.integrity_failed());
+        assert!(matches!(
+            wallet.ensure_writable(),
+            Err(WalletError::ReadOnly(_))
+        ));
+        assert!(!wallet.try_upgrade_to_writable().unwrap());
+
+        // Trusting the files can't make an unreadable file acceptable
+        let report = wallet.accept_wallet_files().unwrap();
+        assert!(report.is_critical());
+        assert!(wallet.is_read_only());
+
+        std::fs::remove_file(dir.join(SPENDING_FILE)).unwrap();
+        let report = wallet.accept_wallet_files().unwrap();
+        assert_eq!(report.severity(), None);
+        assert!(!wallet.is_read_only());
+        assert!(wallet.ensure_writable().is_ok());
+    }
+
+    #[tokio::test]
+    async fn a_modified_non_critical_file_only_warns() {
+        let dir = saved_wallet_dir().await;
+        std::fs::write(dir.join(TEMPLATES), r#"{"rent": 999}"#).unwrap();
+
+        let wallet = Wallet::open(dir.path()).unwrap();
+        assert!(!wallet.is_read_only());
+        assert_eq!(
+            wallet.integrity_report().unwrap().severity(),
+            Some(Severity::Warning)
+        );
+        assert!(wallet.integrity_report().unwrap().ledger.is_some());
+    }
+
+    #[test]
+    fn reconciling_rebuilds_a_drifted_cache_from_the_notes() {
+        let mut wallet = Wallet::new();
+        let note = fake_note().amount(40).confirmed_at(1).build();
+        let address = note.address.clone();
+        wallet.balances_mut().add_note(note).unwrap();
+        assert!(!wallet.reconcile_balances().repaired);
+
+        wallet
+            .balances_mut()
+            .set_cached_balance(address.clone(), Balance::new());
+        let report = wallet.reconcile_balances();
+        assert!(report.repaired);
+        assert_eq!(report.discrepancies[0].expected.confirmed, 40);
+        assert_eq!(wallet.balances().get_balance(&address).confirmed, 40);
+        assert!(wallet.balances().reconcile().is_consistent());
+    }
+}
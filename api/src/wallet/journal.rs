@@ -0,0 +1,588 @@
+//! Journal of long-running operations.
+//!
+//! An operation writes an entry when it starts, a checkpoint as each stage
+//! completes, and removes the entry when it finishes, whether it succeeded or
+//! failed. Anything still in the journal when the wallet is next opened was
+//! interrupted, e.g. by a crash; `Wallet::recover_operations` resumes it or
+//! reconciles it against the network.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+use crate::wallet::amount::{format_amount, AmountUnit};
+use crate::wallet::chain::Blockchain;
+use crate::wallet::mempool::Mempool;
+use crate::wallet::network::{NodeHandle, NodeStatus};
+use crate::wallet::send::SendStage;
+use crate::wallet::storage::StorageManager;
+use crate::wallet::transaction::SignedTransaction;
+use crate::wallet::{Wallet, WalletResult, ADDRESS_STATE_FILE};
+
+/// Operation journal, saved through `StorageManager` in the wallet data dir
+pub const JOURNAL_FILE: &str = "operations.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Operation {
+    /// Broadcast and recording of a signed send, including packages signed
+    /// offline
+    Send {
+        amount: u64,
+        to: String,
+        tx: SignedTransaction,
+        /// Last stage completed
+        stage: SendStage,
+    },
+    /// Gap-limit scan of every key's receive chain
+    Rescan {
+        gap_limit: u32,
+        /// Keys already scanned
+        done_keys: Vec<String>,
+    },
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operation::Send { amount, to, .. } => write!(
+                f,
+                "Send of {} NOCK to {}",
+                format_amount(*amount, AmountUnit::Nock),
+                to
+            ),
+            Operation::Rescan { .. } => f.write_str("Wallet rescan"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub id: Uuid,
+    pub operation: Operation,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OperationJournal {
+    entries: Vec<JournalEntry>,
+}
+
+impl OperationJournal {
+    /// Load the saved journal, or an empty one if none was saved
+    pub async fn load(storage: &StorageManager) -> WalletResult<Self> {
+        if !storage.exists(JOURNAL_FILE) {
+            return Ok(Self::default());
+        }
+        storage.load(JOURNAL_FILE).await
+    }
+
+    pub async fn save(&self, storage: &StorageManager) -> WalletResult<()> {
+        storage.save(JOURNAL_FILE, self).await
+    }
+
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    fn begin(&mut self, operation: Operation) -> Uuid {
+        let now = Utc::now();
+        let id = Uuid::new_v4();
+        self.entries.push(JournalEntry {
+            id,
+            operation,
+            started_at: now,
+            updated_at: now,
+        });
+        id
+    }
+
+    fn checkpoint(&mut self, id: Uuid, update: impl FnOnce(&mut Operation)) {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.id == id) {
+            update(&mut entry.operation);
+            entry.updated_at = Utc::now();
+        }
+    }
+
+    fn finish(&mut self, id: Uuid) {
+        self.entries.retain(|entry| entry.id != id);
+    }
+}
+
+/// Whether a transaction reached the network
+pub trait TransactionLookup {
+    /// `Some(true)` if the transaction is pending or confirmed, `Some(false)`
+    /// if it is neither, `None` if that can't be told right now
+    fn knows_transaction(&self, tx_id: &str) -> Option<bool>;
+}
+
+impl TransactionLookup for Mempool {
+    fn knows_transaction(&self, tx_id: &str) -> Option<bool> {
+        Some(self.contains(tx_id))
+    }
+}
+
+impl TransactionLookup for Blockchain {
+    fn knows_transaction(&self, tx_id: &str) -> Option<bool> {
+        Some(
+            self.blocks()
+                .iter()
+                .any(|block| block.transactions.iter().any(|tx| tx.id == tx_id)),
+        )
+    }
+}
+
+/// Asks the running node's mempool; can't tell while the node is stopped,
+/// since its mempool is only reloaded on start
+impl TransactionLookup for NodeHandle {
+    fn knows_transaction(&self, tx_id: &str) -> Option<bool> {
+        if self.status() != NodeStatus::Running {
+            return None;
+        }
+        self.try_with(|node| node.with_mempool(|mempool| mempool.contains(tx_id)))
+            .flatten()
+    }
+}
+
+/// What recovery did with one interrupted operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RecoveryAction {
+    /// The transaction reached the network and is now recorded
+    RecordedSend {
+        tx_id: String,
+        amount: u64,
+    },
+    /// The transaction never reached the network; its notes are spendable again
+    ReleasedSend {
+        amount: u64,
+        notes: usize,
+    },
+    /// Whether the transaction reached the network can't be told yet; the
+    /// entry stays in the journal for the next attempt
+    SendUnresolved {
+        tx_id: String,
+    },
+    ResumedRescan {
+        used_addresses: usize,
+    },
+    Failed {
+        operation: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for RecoveryAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecoveryAction::RecordedSend { tx_id, amount } => write!(
+                f,
+                "Send of {} NOCK had reached the network and was recorded as {}",
+                format_amount(*amount, AmountUnit::Nock),
+                tx_id
+            ),
+            RecoveryAction::ReleasedSend { amount, notes } => write!(
+                f,
+                "Send of {} NOCK never reached the network; {} note(s) are spendable again",
+                format_amount(*amount, AmountUnit::Nock),
+                notes
+            ),
+            RecoveryAction::SendUnresolved { tx_id } => write!(
+                f,
+                "Can't tell yet whether {} reached the network; start the node to check",
+                tx_id
+            ),
+            RecoveryAction::ResumedRescan { used_addresses } => write!(
+                f,
+                "Finished the interrupted rescan; {} used address(es) found",
+                used_addresses
+            ),
+            RecoveryAction::Failed { operation, message } => {
+                write!(f, "Could not recover {}: {}", operation, message)
+            }
+        }
+    }
+}
+
+impl Wallet {
+    /// Operations interrupted before they finished, as loaded from the journal
+    pub fn pending_operations(&self) -> &[JournalEntry] {
+        self.journal.entries()
+    }
+
+    /// Load the journal saved in the wallet data dir
+    pub async fn load_operation_journal(&mut self) -> WalletResult<()> {
+        let Some(data_dir) = self.data_dir.clone() else {
+            return Ok(());
+        };
+        let storage = StorageManager::new(data_dir)?;
+        self.journal = OperationJournal::load(&storage).await?;
+        Ok(())
+    }
+
+    /// Save the journal; does nothing in read-only mode
+    pub async fn save_operation_journal(&self) -> WalletResult<()> {
+        let Some(data_dir) = self.data_dir.clone().filter(|_| !self.read_only) else {
+            return Ok(());
+        };
+        let storage = StorageManager::new(data_dir)?;
+        self.journal.save(&storage).await
+    }
+
+    /// Start journaling an operation. Fails if the journal can't be saved, so
+    /// nothing runs that couldn't be recovered.
+    pub(crate) async fn journal_begin(&mut self, operation: Operation) -> WalletResult<Uuid> {
+        let id = self.journal.begin(operation);
+        if let Err(e) = self.save_operation_journal().await {
+            self.journal.finish(id);
+            return Err(e);
+        }
+        Ok(id)
+    }
+
+    /// Record progress of a journaled operation. A failed save is only
+    /// logged: the operation is already under way.
+    pub(crate) async fn journal_checkpoint(
+        &mut self,
+        id: Uuid,
+        update: impl FnOnce(&mut Operation),
+    ) {
+        self.journal.checkpoint(id, update);
+        if let Err(e) = self.save_operation_journal().await {
+            tracing::warn!("Failed to save operation journal: {}", e);
+        }
+    }
+
+    pub(crate) async fn journal_finish(&mut self, id: Uuid) {
+        self.journal.finish(id);
+        if let Err(e) = self.save_operation_journal().await {
+            tracing::warn!("Failed to save operation journal: {}", e);
+        }
+    }
+
+    /// Resume or reconcile every interrupted operation. A send whose
+    /// transaction `lookup` knows is recorded, once; one it doesn't know has
+    /// its notes released; one it can't tell about stays pending.
+    pub async fn recover_operations(
+        &mut self,
+        lookup: &impl TransactionLookup,
+    ) -> Vec<RecoveryAction> {
+        let mut actions = Vec::new();
+        for entry in self.journal.entries().to_vec() {
+            let operation = entry.operation.to_string();
+            let action = match entry.operation {
+                Operation::Send { amount, tx, .. } => match lookup.knows_transaction(&tx.id) {
+                    Some(true) => {
+                        let tx_id = tx.id.clone();
+                        self.reconcile_broadcast(tx, amount);
+                        RecoveryAction::RecordedSend { tx_id, amount }
+                    }
+                    Some(false) => self.release_send(&tx, amount),
+                    None => {
+                        actions.push(RecoveryAction::SendUnresolved { tx_id: tx.id });
+                        continue;
+                    }
+                },
+                Operation::Rescan {
+                    gap_limit,
+                    done_keys,
+                } => match self.run_rescan(entry.id, gap_limit, done_keys).await {
+                    Ok(used_addresses) => RecoveryAction::ResumedRescan { used_addresses },
+                    Err(e) => RecoveryAction::Failed {
+                        operation,
+                        message: e.to_string(),
+                    },
+                },
+            };
+            self.journal_finish(entry.id).await;
+            actions.push(action);
+        }
+        actions
+    }
+
+    /// Record a send that reached the network, skipping whatever was already
+    /// recorded before the interruption
+    fn reconcile_broadcast(&mut self, tx: SignedTransaction, amount: u64) {
+        let now = Utc::now();
+        if !self.spending.has_send(&tx.id) {
+            self.spending.record(&tx.id, amount + tx.fee, now);
+        }
+        for outpoint in tx.input_outpoints() {
+            let unspent = self
+                .balances()
+                .note_at(outpoint)
+                .filter(|note| !note.spent)
+                .map(|note| note.id);
+            if let Some(id) = unspent {
+                if let Err(e) = self.balances_mut().spend_note(id) {
+                    tracing::warn!("Failed to mark note {} spent: {}", outpoint, e);
+                }
+            }
+        }
+        self.balances_mut().record_fee(&tx.id, tx.fee);
+        if self.transactions().signed(&tx.id).is_none() {
+            let tx_id = tx.id.clone();
+            self.record_transaction(tx, true);
+            self.transactions_mut().mark_broadcast(&tx_id, now);
+        }
+    }
+
+    fn release_send(&mut self, tx: &SignedTransaction, amount: u64) -> RecoveryAction {
+        let note_ids: Vec<Uuid> = tx
+            .input_outpoints()
+            .into_iter()
+            .filter_map(|outpoint| self.balances().note_at(outpoint))
+            .filter(|note| !note.spent)
+            .map(|note| note.id)
+            .collect();
+        self.balances_mut().unlock_notes(&note_ids);
+        RecoveryAction::ReleasedSend {
+            amount,
+            notes: note_ids.len(),
+        }
+    }
+
+    /// Gap-limit scan of every key's receive chain, journaled key by key so an
+    /// interrupted rescan resumes where it stopped. Returns the number of
+    /// used addresses found. Checkpoints are batched, so a crash repeats at
+    /// most `storage::batch_delay` worth of keys.
+    pub async fn rescan(&mut self, gap_limit: u32) -> WalletResult<usize> {
+        self.ensure_writable()?;
+        let id = self
+            .journal_begin(Operation::Rescan {
+                gap_limit,
+                done_keys: Vec::new(),
+            })
+            .await?;
+        let result = self.run_rescan(id, gap_limit, Vec::new()).await;
+        self.journal_finish(id).await;
+        result
+    }
+
+    async fn run_rescan(
+        &mut self,
+        id: Uuid,
+        gap_limit: u32,
+        done_keys: Vec<String>,
+    ) -> WalletResult<usize> {
+        self.ensure_writable()?;
+        let storage = self.data_dir.clone().map(StorageManager::new).transpose()?;
+        let batch = storage.as_ref().map(StorageManager::batch);
+        let result = self.scan_chains(id, gap_limit, done_keys).await;
+        self.reconcile_balances();
+        if let Some(batch) = batch {
+            batch.commit()?;
+        }
+        let used = result?;
+
+        if let Some(storage) = &storage {
+            storage
+                .save(ADDRESS_STATE_FILE, self.keys.address_chains())
+                .await?;
+        }
+        Ok(used)
+    }
+
+    async fn scan_chains(
+        &mut self,
+        id: Uuid,
+        gap_limit: u32,
+        mut done_keys: Vec<String>,
+    ) -> WalletResult<usize> {
+        let mut used = 0;
+        for key_name in self.keys.list_keys() {
+            if done_keys.contains(&key_name) {
+                continue;
+            }
+            let balances = &self.balances;
+            used += self.keys.scan_chain(&key_name, gap_limit, |address| {
+                !balances.get_notes_for_address(address).is_empty()
+            })?;
+            done_keys.push(key_name);
+            let done = done_keys.clone();
+            self.journal_checkpoint(id, |operation| {
+                if let Operation::Rescan { done_keys, .. } = operation {
+                    *done_keys = done;
+                }
+            })
+            .await;
+        }
+        Ok(used)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{
+        fake_address, fake_note, fake_signed_transaction, fakenet_config, TempDir,
+    };
+    use crate::wallet::network::NockchainNodeManager;
+
+    /// Wallet holding one note of 1,000 at `aa:0`, and a send of 500 spending it
+    fn wallet_and_send() -> (Wallet, SignedTransaction) {
+        let mut wallet = Wallet::new();
+        let address = wallet.generate_key("main").unwrap();
+        wallet
+            .balances_mut()
+            .add_note(
+                fake_note()
+                    .address(address)
+                    .outpoint("aa", 0)
+                    .amount(1_000)
+                    .build(),
+            )
+            .unwrap();
+        let tx = fake_signed_transaction()
+            .spending("aa", 0, 1_000)
+            .paying(&fake_address(), 500)
+            .build();
+        (wallet, tx)
+    }
+
+    fn send_at(tx: &SignedTransaction, stage: SendStage) -> Operation {
+        Operation::Send {
+            amount: 500,
+            to: fake_address().to_string(),
+            tx: tx.clone(),
+            stage,
+        }
+    }
+
+    fn mempool_with(tx: &SignedTransaction) -> Mempool {
+        let mut mempool = Mempool::new();
+        assert!(mempool.insert(tx.clone()).unwrap());
+        mempool
+    }
+
+    fn note_spent(wallet: &Wallet) -> bool {
+        wallet.balances().notes().iter().all(|note| note.spent)
+    }
+
+    #[tokio::test]
+    async fn a_send_broadcast_before_a_crash_is_recorded_exactly_once() {
+        let dir = TempDir::new("journal");
+        let (mut wallet, tx) = wallet_and_send();
+        wallet.data_dir = Some(dir.path().to_path_buf());
+        wallet
+            .journal_begin(send_at(&tx, SendStage::Broadcast))
+            .await
+            .unwrap();
+        // Crash: the process dies before the Record stage
+        drop(wallet);
+
+        let (mut wallet, _) = wallet_and_send();
+        wallet.data_dir = Some(dir.path().to_path_buf());
+        wallet.load_operation_journal().await.unwrap();
+        assert_eq!(wallet.pending_operations().len(), 1);
+
+        let mempool = mempool_with(&tx);
+        let actions = wallet.recover_operations(&mempool).await;
+        assert_eq!(
+            actions,
+            [RecoveryAction::RecordedSend {
+                tx_id: tx.id.clone(),
+                amount: 500
+            }]
+        );
+        assert_eq!(wallet.transactions().count(), 1);
+        assert!(wallet.transactions().signed(&tx.id).is_some());
+        assert_eq!(wallet.spending.spent_in_window(Utc::now()), 500 + tx.fee);
+        assert!(note_spent(&wallet));
+        assert!(wallet.pending_operations().is_empty());
+
+        // The journal on disk is cleared too, so a second open does nothing
+        assert!(wallet.recover_operations(&mempool).await.is_empty());
+        wallet.load_operation_journal().await.unwrap();
+        assert!(wallet.pending_operations().is_empty());
+        assert_eq!(wallet.transactions().count(), 1);
+        assert_eq!(wallet.spending.spent_in_window(Utc::now()), 500 + tx.fee);
+    }
+
+    #[tokio::test]
+    async fn recovery_skips_whatever_was_recorded_before_the_crash() {
+        let (mut wallet, tx) = wallet_and_send();
+        wallet
+            .journal_begin(send_at(&tx, SendStage::Broadcast))
+            .await
+            .unwrap();
+        wallet.spending.record(&tx.id, 500 + tx.fee, Utc::now());
+        wallet.record_transaction(tx.clone(), true);
+
+        wallet.recover_operations(&mempool_with(&tx)).await;
+        assert_eq!(wallet.transactions().count(), 1);
+        assert_eq!(wallet.spending.spent_in_window(Utc::now()), 500 + tx.fee);
+        assert!(note_spent(&wallet));
+    }
+
+    #[tokio::test]
+    async fn a_send_the_network_never_saw_releases_its_notes() {
+        let (mut wallet, tx) = wallet_and_send();
+        let note_ids: Vec<Uuid> = wallet.balances().notes().iter().map(|n| n.id).collect();
+        wallet.balances_mut().lock_notes(&note_ids).unwrap();
+        wallet
+            .journal_begin(send_at(&tx, SendStage::Sign))
+            .await
+            .unwrap();
+
+        let actions = wallet.recover_operations(&Mempool::new()).await;
+        assert_eq!(
+            actions,
+            [RecoveryAction::ReleasedSend {
+                amount: 500,
+                notes: 1
+            }]
+        );
+        assert!(wallet.balances().notes().iter().all(|note| !note.locked));
+        assert_eq!(wallet.transactions().count(), 0);
+        assert_eq!(wallet.spending.spent_in_window(Utc::now()), 0);
+    }
+
+    #[tokio::test]
+    async fn a_stopped_node_leaves_the_send_pending() {
+        let (mut wallet, tx) = wallet_and_send();
+        wallet
+            .journal_begin(send_at(&tx, SendStage::Broadcast))
+            .await
+            .unwrap();
+        let node = NodeHandle::new(NockchainNodeManager::new(fakenet_config()));
+
+        let actions = wallet.recover_operations(&node).await;
+        assert_eq!(
+            actions,
+            [RecoveryAction::SendUnresolved {
+                tx_id: tx.id.clone()
+            }]
+        );
+        assert_eq!(wallet.pending_operations().len(), 1);
+        assert!(!note_spent(&wallet));
+
+        // Once the chain answers, the same entry settles
+        let actions = wallet.recover_operations(&mempool_with(&tx)).await;
+        assert!(matches!(actions[..], [RecoveryAction::RecordedSend { .. }]));
+        assert!(wallet.pending_operations().is_empty());
+    }
+
+    #[tokio::test]
+    async fn an_interrupted_rescan_resumes_and_leaves_the_journal() {
+        let mut wallet = Wallet::new();
+        wallet.generate_key("first").unwrap();
+        wallet.generate_key("second").unwrap();
+        wallet
+            .journal_begin(Operation::Rescan {
+                gap_limit: 5,
+                done_keys: vec!["first".to_string()],
+            })
+            .await
+            .unwrap();
+
+        let actions = wallet.recover_operations(&Mempool::new()).await;
+        assert_eq!(
+            actions,
+            [RecoveryAction::ResumedRescan { used_addresses: 0 }]
+        );
+        assert!(wallet.pending_operations().is_empty());
+        assert_eq!(wallet.rescan(5).await.unwrap(), 0);
+        assert!(wallet.pending_operations().is_empty());
+    }
+}
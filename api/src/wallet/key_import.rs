@@ -0,0 +1,474 @@
+//! Importing many keys at once from a file exported by another tool.
+//!
+//! Each entry is imported on its own: a bad or duplicate entry is reported and
+//! the rest of the file still goes in. Only problems with the file as a whole
+//! (unreadable, wrong shape) fail the import.
+//!
+//! A jammed export is a null-terminated list of `[name=@t secret=@ux]` cells,
+//! the secret being the 32-byte key read as a big-endian number.
+
+use bytes::Bytes;
+use ed25519_dalek::SigningKey;
+use nockapp::noun::slab::NounSlab;
+use nockvm::noun::{Atom, Noun};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::wallet::keys::NockchainKeyManager;
+use crate::wallet::{Address, WalletError, WalletResult};
+
+/// Layout of a key import file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyImportFormat {
+    /// JSON array of `{ "name": ..., "secret_hex": ... }`
+    #[default]
+    Json,
+    /// Jammed noun as written by nockchain-wallet's key export
+    Jam,
+}
+
+impl KeyImportFormat {
+    pub const ALL: [KeyImportFormat; 2] = [KeyImportFormat::Json, KeyImportFormat::Jam];
+
+    /// Format implied by the file extension, if it names one
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "json" => Some(KeyImportFormat::Json),
+            "jam" => Some(KeyImportFormat::Jam),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            KeyImportFormat::Json => "JSON",
+            KeyImportFormat::Jam => "Jammed noun",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonKeyEntry {
+    name: String,
+    secret_hex: String,
+}
+
+/// One entry of an import file, whichever its format, with its secret or
+/// why it couldn't be read
+struct KeyFileEntry {
+    name: String,
+    secret: Result<[u8; 32], String>,
+}
+
+impl From<JsonKeyEntry> for KeyFileEntry {
+    fn from(entry: JsonKeyEntry) -> Self {
+        KeyFileEntry {
+            secret: parse_secret(&entry.secret_hex),
+            name: entry.name,
+        }
+    }
+}
+
+/// What happened to one entry of an import file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum KeyImportOutcome {
+    Imported,
+    /// Already in the wallet, under this or another name
+    SkippedDuplicate(String),
+    Failed(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyImportEntry {
+    pub name: String,
+    /// Address of the key, if its secret could be read
+    pub address: Option<Address>,
+    pub outcome: KeyImportOutcome,
+}
+
+/// Per-entry results of an import, in file order
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeyImportReport {
+    pub entries: Vec<KeyImportEntry>,
+}
+
+impl KeyImportReport {
+    pub fn imported(&self) -> usize {
+        self.count(|outcome| matches!(outcome, KeyImportOutcome::Imported))
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.count(|outcome| matches!(outcome, KeyImportOutcome::SkippedDuplicate(_)))
+    }
+
+    pub fn failed(&self) -> usize {
+        self.count(|outcome| matches!(outcome, KeyImportOutcome::Failed(_)))
+    }
+
+    fn count(&self, pred: impl Fn(&KeyImportOutcome) -> bool) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| pred(&entry.outcome))
+            .count()
+    }
+}
+
+impl NockchainKeyManager {
+    /// Import every key in the file at `path`, reporting each entry's outcome
+    pub fn import_keys_from_file(
+        &mut self,
+        path: &Path,
+        format: KeyImportFormat,
+    ) -> WalletResult<KeyImportReport> {
+        let bytes = std::fs::read(path).map_err(|e| {
+            WalletError::Storage(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+
+        let entries: Vec<KeyFileEntry> = match format {
+            KeyImportFormat::Json => serde_json::from_slice::<Vec<JsonKeyEntry>>(&bytes)
+                .map_err(|e| {
+                    WalletError::Serialization(format!(
+                        "{} is not a JSON array of {{ name, secret_hex }}: {}",
+                        path.display(),
+                        e
+                    ))
+                })?
+                .into_iter()
+                .map(KeyFileEntry::from)
+                .collect(),
+            KeyImportFormat::Jam => cue_entries(bytes).map_err(|reason| {
+                WalletError::Serialization(format!("{} is {}", path.display(), reason))
+            })?,
+        };
+
+        let mut report = KeyImportReport::default();
+        for entry in entries {
+            report.entries.push(self.import_entry(entry));
+        }
+        Ok(report)
+    }
+
+    fn import_entry(&mut self, entry: KeyFileEntry) -> KeyImportEntry {
+        let name = entry.name.trim().to_string();
+        let secret = match entry.secret {
+            Ok(secret) => secret,
+            Err(reason) => {
+                return KeyImportEntry {
+                    name,
+                    address: None,
+                    outcome: KeyImportOutcome::Failed(reason),
+                }
+            }
+        };
+        let address = address_for_secret(&secret);
+
+        let outcome = if name.is_empty() {
+            KeyImportOutcome::Failed("Key name is empty".to_string())
+        } else if let Some(existing) = self.key_name_for_address(&address) {
+            KeyImportOutcome::SkippedDuplicate(if existing == name {
+                "Key is already in the wallet".to_string()
+            } else {
+                format!("Same key as '{}'", existing)
+            })
+        } else if self.get_key(&name).is_some() {
+            KeyImportOutcome::SkippedDuplicate(format!(
+                "A different key named '{}' already exists",
+                name
+            ))
+        } else {
+            match self.import_key(name.clone(), secret) {
+                Ok(_) => KeyImportOutcome::Imported,
+                Err(e) => KeyImportOutcome::Failed(e.to_string()),
+            }
+        };
+
+        KeyImportEntry {
+            name,
+            address: Some(address),
+            outcome,
+        }
+    }
+}
+
+fn parse_secret(secret_hex: &str) -> Result<[u8; 32], String> {
+    let bytes = hex::decode(secret_hex.trim().trim_start_matches("0x"))
+        .map_err(|e| format!("Secret is not valid hex: {}", e))?;
+    bytes
+        .try_into()
+        .map_err(|bytes: Vec<u8>| format!("Secret is {} bytes; expected 32", bytes.len()))
+}
+
+/// Entries of a jammed key export in list order. An entry of the wrong shape
+/// is kept as a failure; only a file that isn't a jammed list fails whole.
+fn cue_entries(bytes: Vec<u8>) -> Result<Vec<KeyFileEntry>, String> {
+    let mut slab: NounSlab = NounSlab::new();
+    let mut list = slab
+        .cue_into(Bytes::from(bytes))
+        .map_err(|e| format!("not a jammed noun: {:?}", e))?;
+    let mut entries = Vec::new();
+    while let Ok(cell) = list.as_cell() {
+        entries.push(jammed_entry(cell.head()));
+        list = cell.tail();
+    }
+    if !list.as_atom().is_ok_and(|atom| atom_bytes(atom).is_empty()) {
+        return Err("not a null-terminated list of keys".to_string());
+    }
+    Ok(entries)
+}
+
+fn jammed_entry(noun: Noun) -> KeyFileEntry {
+    let failed = |reason: &str| KeyFileEntry {
+        name: String::new(),
+        secret: Err(reason.to_string()),
+    };
+    let Ok(cell) = noun.as_cell() else {
+        return failed("Entry is not a [name secret] cell");
+    };
+    let Some(name) = cell
+        .head()
+        .as_atom()
+        .ok()
+        .and_then(|atom| String::from_utf8(atom_bytes(atom)).ok())
+    else {
+        return failed("Key name is not text");
+    };
+    let secret = match cell.tail().as_atom() {
+        Ok(atom) => secret_from_atom(atom_bytes(atom)),
+        Err(_) => Err("Secret is not an atom".to_string()),
+    };
+    KeyFileEntry { name, secret }
+}
+
+/// Little-endian bytes of `atom` without trailing zeros. Native order is
+/// little-endian on every target the wallet builds for.
+fn atom_bytes(atom: Atom) -> Vec<u8> {
+    let mut bytes = atom.as_ne_bytes().to_vec();
+    while bytes.last() == Some(&0) {
+        bytes.pop();
+    }
+    bytes
+}
+
+/// The 32-byte secret whose big-endian value is the atom with little-endian
+/// bytes `bytes`; leading zero bytes of the secret aren't stored in the atom
+fn secret_from_atom(bytes: Vec<u8>) -> Result<[u8; 32], String> {
+    if bytes.len() > 32 {
+        return Err(format!("Secret is {} bytes; expected 32", bytes.len()));
+    }
+    let mut secret = [0u8; 32];
+    for (index, byte) in bytes.iter().enumerate() {
+        secret[31 - index] = *byte;
+    }
+    Ok(secret)
+}
+
+/// Address of the ed25519 key with secret `secret`
+pub(crate) fn address_for_secret(secret: &[u8; 32]) -> Address {
+    Address::from_public_key(SigningKey::from_bytes(secret).verifying_key().to_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    const ALICE: &str = "0101010101010101010101010101010101010101010101010101010101010101";
+    const BOB: &str = "0202020202020202020202020202020202020202020202020202020202020202";
+    const CAROL: &str = "0303030303030303030303030303030303030303030303030303030303030303";
+    const DAVE: &str = "0004040404040404040404040404040404040404040404040404040404040404";
+
+    fn secret(hex: &str) -> [u8; 32] {
+        parse_secret(hex).unwrap()
+    }
+
+    /// Import `json` into a wallet that already holds "alice"
+    fn import(json: &str) -> (NockchainKeyManager, WalletResult<KeyImportReport>) {
+        import_as(json.as_bytes(), "keys.json", KeyImportFormat::Json)
+    }
+
+    /// Import `bytes`, saved as `file_name`, into a wallet that already
+    /// holds "alice"
+    fn import_as(
+        bytes: &[u8],
+        file_name: &str,
+        format: KeyImportFormat,
+    ) -> (NockchainKeyManager, WalletResult<KeyImportReport>) {
+        let dir = TempDir::new("key-import");
+        let path = dir.join(file_name);
+        std::fs::write(&path, bytes).unwrap();
+        let mut keys = NockchainKeyManager::new();
+        keys.import_key("alice".to_string(), secret(ALICE)).unwrap();
+        let report = keys.import_keys_from_file(&path, format);
+        (keys, report)
+    }
+
+    #[test]
+    fn a_mixed_file_imports_what_it_can_and_reports_the_rest() {
+        let json = format!(
+            r#"[
+                {{ "name": "bob", "secret_hex": "{BOB}" }},
+                {{ "name": "alice-again", "secret_hex": "{ALICE}" }},
+                {{ "name": "alice", "secret_hex": "{ALICE}" }},
+                {{ "name": "bob", "secret_hex": "{CAROL}" }},
+                {{ "name": "broken", "secret_hex": "zz" }},
+                {{ "name": "short", "secret_hex": "0x0102" }},
+                {{ "name": "  ", "secret_hex": "{CAROL}" }},
+                {{ "name": " carol ", "secret_hex": "0x{CAROL}" }}
+            ]"#
+        );
+        let (keys, report) = import(&json);
+        let report = report.unwrap();
+
+        let outcomes: Vec<(&str, &KeyImportOutcome)> = report
+            .entries
+            .iter()
+            .map(|entry| (entry.name.as_str(), &entry.outcome))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                ("bob", &KeyImportOutcome::Imported),
+                (
+                    "alice-again",
+                    &KeyImportOutcome::SkippedDuplicate("Same key as 'alice'".to_string())
+                ),
+                (
+                    "alice",
+                    &KeyImportOutcome::SkippedDuplicate("Key is already in the wallet".to_string())
+                ),
+                (
+                    "bob",
+                    &KeyImportOutcome::SkippedDuplicate(
+                        "A different key named 'bob' already exists".to_string()
+                    )
+                ),
+                (
+                    "broken",
+                    &KeyImportOutcome::Failed(
+                        "Secret is not valid hex: Invalid character 'z' at position 0".to_string()
+                    )
+                ),
+                (
+                    "short",
+                    &KeyImportOutcome::Failed("Secret is 2 bytes; expected 32".to_string())
+                ),
+                (
+                    "",
+                    &KeyImportOutcome::Failed("Key name is empty".to_string())
+                ),
+                ("carol", &KeyImportOutcome::Imported),
+            ]
+        );
+        assert_eq!(
+            (report.imported(), report.skipped(), report.failed()),
+            (2, 3, 3)
+        );
+
+        // Flagged duplicates name the address they collide on
+        assert_eq!(
+            report.entries[1].address,
+            Some(address_for_secret(&secret(ALICE)))
+        );
+        assert_eq!(report.entries[4].address, None);
+        assert_eq!(
+            keys.get_key("carol").unwrap().address(),
+            &address_for_secret(&secret(CAROL))
+        );
+        assert_eq!(
+            keys.key_name_for_address(&address_for_secret(&secret(BOB))),
+            Some("bob")
+        );
+    }
+
+    #[test]
+    fn a_file_of_the_wrong_shape_fails_as_a_whole() {
+        let (keys, report) = import(r#"{ "name": "bob", "secret_hex": "00" }"#);
+        assert!(matches!(report, Err(WalletError::Serialization(_))));
+        assert!(keys.get_key("bob").is_none());
+    }
+
+    #[test]
+    fn a_jammed_export_imports_each_entry_it_can() {
+        // The jam of `~[[%bob 0x202..] [%alice-again 0x101..] [%long 0x505..]
+        // %stray [%dave 0x4..404]]`: the long secret is 33 bytes, the stray
+        // entry isn't a cell and dave's secret starts with a zero byte
+        let (keys, report) = import_as(
+            include_bytes!("testdata/keys.jam"),
+            "keys.jam",
+            KeyImportFormat::Jam,
+        );
+        let report = report.unwrap();
+
+        let outcomes: Vec<(&str, &KeyImportOutcome)> = report
+            .entries
+            .iter()
+            .map(|entry| (entry.name.as_str(), &entry.outcome))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                ("bob", &KeyImportOutcome::Imported),
+                (
+                    "alice-again",
+                    &KeyImportOutcome::SkippedDuplicate("Same key as 'alice'".to_string())
+                ),
+                (
+                    "long",
+                    &KeyImportOutcome::Failed("Secret is 33 bytes; expected 32".to_string())
+                ),
+                (
+                    "",
+                    &KeyImportOutcome::Failed("Entry is not a [name secret] cell".to_string())
+                ),
+                ("dave", &KeyImportOutcome::Imported),
+            ]
+        );
+        assert_eq!(
+            keys.key_name_for_address(&address_for_secret(&secret(BOB))),
+            Some("bob")
+        );
+        assert_eq!(
+            keys.get_key("dave").unwrap().address(),
+            &address_for_secret(&secret(DAVE))
+        );
+    }
+
+    #[test]
+    fn a_jammed_noun_that_is_not_a_list_fails_as_a_whole() {
+        // The jam of the atom 5
+        let (keys, report) = import_as(&[0xb8], "keys.jam", KeyImportFormat::Jam);
+        match report {
+            Err(WalletError::Serialization(message)) => {
+                assert!(
+                    message.ends_with("is not a null-terminated list of keys"),
+                    "{}",
+                    message
+                )
+            }
+            other => panic!("expected a serialization error, got {:?}", other),
+        }
+        assert_eq!(keys.list_keys(), vec!["alice".to_string()]);
+    }
+
+    #[test]
+    fn the_format_follows_the_file_extension() {
+        assert_eq!(
+            KeyImportFormat::from_path(Path::new("keys.JSON")),
+            Some(KeyImportFormat::Json)
+        );
+        assert_eq!(
+            KeyImportFormat::from_path(Path::new("/exports/keys.jam")),
+            Some(KeyImportFormat::Jam)
+        );
+        assert_eq!(KeyImportFormat::from_path(Path::new("keys.txt")), None);
+        assert_eq!(KeyImportFormat::from_path(Path::new("keys")), None);
+    }
+
+    #[test]
+    fn a_missing_file_is_a_storage_error() {
+        let mut keys = NockchainKeyManager::new();
+        let report =
+            keys.import_keys_from_file(Path::new("/nonexistent/keys.json"), KeyImportFormat::Json);
+        assert!(matches!(report, Err(WalletError::Storage(_))));
+    }
+}
@@ -1,12 +1,19 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
 
-use crate::wallet::{Address, WalletError, WalletResult};
+use crate::wallet::hd::{self, AddressChain, DEFAULT_GAP_LIMIT};
+use crate::wallet::key_import::address_for_secret;
+use crate::wallet::mnemonic::MnemonicLanguage;
+use crate::wallet::{encoding, Address, WalletError, WalletResult};
 
 /// Simplified key pair for debugging
 #[derive(Debug, Clone)]
 pub struct NockchainKeyPair {
     name: String,
     address: Address,
+    /// HD account whose receive chain belongs to this key
+    account: u32,
 }
 
 impl NockchainKeyPair {
@@ -16,9 +23,23 @@ impl NockchainKeyPair {
         Self {
             name,
             address: Address::from_public_key(dummy_pubkey),
+            account: 0,
         }
     }
 
+    /// Key backed by an HD account, identified by the account's first address
+    pub fn for_account(name: String, account: u32, address: Address) -> Self {
+        Self {
+            name,
+            address,
+            account,
+        }
+    }
+
+    pub fn account(&self) -> u32 {
+        self.account
+    }
+
     pub fn address(&self) -> &Address {
         &self.address
     }
@@ -28,11 +49,62 @@ impl NockchainKeyPair {
     }
 }
 
+/// What one of the wallet's addresses is for
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+pub enum AddressKind {
+    /// A key's own address or one handed out to receive payments
+    Receive,
+    /// Where the wallet's own sends return their change; never handed out
+    Change,
+    /// Tracked without its key
+    WatchOnly,
+}
+
+impl AddressKind {
+    /// Whether the wallet uses the address for itself rather than showing it
+    /// to payers
+    pub fn is_internal(&self) -> bool {
+        *self == AddressKind::Change
+    }
+}
+
 /// Simplified key manager for debugging
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct NockchainKeyManager {
     keys: HashMap<String, NockchainKeyPair>,
     default_key: Option<String>,
+    /// HD seed every key's receive chain is derived from
+    seed: [u8; 32],
+    /// Addresses tracked without a key
+    watch_only: HashSet<Address>,
+    /// Receive chain of each key, by key name
+    chains: HashMap<String, AddressChain>,
+    /// Change chain of each key, by key name
+    change_chains: HashMap<String, AddressChain>,
+    /// Secrets of keys imported rather than derived from `seed`, by key name
+    imported: HashMap<String, [u8; 32]>,
+    /// Names the user gave their own addresses, e.g. "donations"
+    labels: HashMap<Address, String>,
+    /// Wordlist of the recovery phrase `seed` was restored from, so the
+    /// phrase is shown in its original words
+    mnemonic_language: Option<MnemonicLanguage>,
+}
+
+impl fmt::Debug for NockchainKeyManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NockchainKeyManager")
+            .field("keys", &self.keys)
+            .field("default_key", &self.default_key)
+            .field("chains", &self.chains)
+            .field("change_chains", &self.change_chains)
+            .field("watch_only", &self.watch_only)
+            .field("imported", &self.imported.keys().collect::<Vec<_>>())
+            .field("labels", &self.labels)
+            .field("mnemonic_language", &self.mnemonic_language)
+            .finish_non_exhaustive()
+    }
 }
 
 impl Default for NockchainKeyManager {
@@ -43,18 +115,52 @@ impl Default for NockchainKeyManager {
 
 impl NockchainKeyManager {
     pub fn new() -> Self {
+        Self::from_seed(rand::random())
+    }
+
+    /// Key manager whose addresses are derived from `seed`, e.g. when restoring
+    pub fn from_seed(seed: [u8; 32]) -> Self {
         Self {
             keys: HashMap::new(),
             default_key: None,
+            seed,
+            watch_only: HashSet::new(),
+            chains: HashMap::new(),
+            change_chains: HashMap::new(),
+            imported: HashMap::new(),
+            labels: HashMap::new(),
+            mnemonic_language: None,
         }
     }
 
+    /// Language of the recovery phrase the seed came from, if it came from one
+    pub fn mnemonic_language(&self) -> Option<MnemonicLanguage> {
+        self.mnemonic_language
+    }
+
+    pub(crate) fn set_mnemonic_language(&mut self, language: MnemonicLanguage) {
+        self.mnemonic_language = Some(language);
+    }
+
     pub fn generate_key(&mut self, name: String) -> WalletResult<&NockchainKeyPair> {
         if self.keys.contains_key(&name) {
             return Err(WalletError::KeyExists(name));
         }
 
-        let keypair = NockchainKeyPair::new(name.clone());
+        // Keys created in the same order from the same seed get the same accounts
+        let account = (self.keys.len() - self.imported.len()) as u32;
+        let mut chain = self
+            .chains
+            .remove(&name)
+            .unwrap_or_else(|| AddressChain::new(account));
+        let address = chain.address_at(&self.seed, 0).clone();
+        let keypair = NockchainKeyPair::for_account(name.clone(), chain.account, address);
+        let change = self
+            .change_chains
+            .remove(&name)
+            .unwrap_or_else(|| AddressChain::change(account));
+        self.chains.insert(name.clone(), chain);
+        self.change_chains.insert(name.clone(), change);
         self.keys.insert(name.clone(), keypair);
 
         if self.default_key.is_none() {
@@ -64,6 +170,49 @@ impl NockchainKeyManager {
         Ok(self.keys.get(&name).unwrap())
     }
 
+    /// Add a key from its ed25519 secret. Imported keys have no receive or
+    /// change chain; they receive, and take their change, at their own
+    /// address only.
+    pub fn import_key(
+        &mut self,
+        name: String,
+        secret: [u8; 32],
+    ) -> WalletResult<&NockchainKeyPair> {
+        if self.keys.contains_key(&name) {
+            return Err(WalletError::KeyExists(name));
+        }
+
+        let keypair = NockchainKeyPair::for_account(name.clone(), 0, address_for_secret(&secret));
+        self.imported.insert(name.clone(), secret);
+        self.keys.insert(name.clone(), keypair);
+
+        if self.default_key.is_none() {
+            self.default_key = Some(name.clone());
+        }
+
+        Ok(self.keys.get(&name).unwrap())
+    }
+
+    /// Whether `name` was imported from a secret rather than derived from the seed
+    pub fn is_imported(&self, name: &str) -> bool {
+        self.imported.contains_key(name)
+    }
+
+    /// Name of the key whose own address is `address`
+    pub fn key_name_for_address(&self, address: &Address) -> Option<&str> {
+        self.keys
+            .values()
+            .find(|key| key.address() == address)
+            .map(|key| key.name())
+    }
+
+    /// Key whose own address is `address`, given in the nockchain or the
+    /// plain form
+    pub fn key_for_address_str(&self, address: &str) -> Option<&NockchainKeyPair> {
+        let address = Address::from_string(address.trim()).ok()?;
+        self.keys.values().find(|key| key.address() == &address)
+    }
+
     pub fn get_key(&self, name: &str) -> Option<&NockchainKeyPair> {
         self.keys.get(name)
     }
@@ -85,14 +234,262 @@ impl NockchainKeyManager {
             .collect()
     }
 
-    /// Dummy implementation for compatibility
+    /// Every address the wallet watches for funds: each key's own address,
+    /// its derived receive and change addresses and the watch-only addresses
+    pub fn watched_addresses(&self) -> Vec<Address> {
+        let mut addresses: Vec<Address> = self
+            .keys
+            .values()
+            .map(|key| key.address().clone())
+            .chain(
+                self.all_chains()
+                    .flat_map(|chain| chain.addresses().iter().cloned()),
+            )
+            .chain(self.watch_only.iter().cloned())
+            .collect();
+        addresses.sort_by_key(Address::to_string);
+        addresses.dedup();
+        addresses
+    }
+
+    pub fn address_chain(&self, key_name: &str) -> Option<&AddressChain> {
+        self.chains.get(key_name)
+    }
+
+    /// All receive chains, by key name, for persistence
+    pub fn address_chains(&self) -> &HashMap<String, AddressChain> {
+        &self.chains
+    }
+
+    /// Load persisted receive chains; keys generated later pick theirs up by name
+    pub fn restore_address_chains(&mut self, chains: HashMap<String, AddressChain>) {
+        self.chains.extend(chains);
+    }
+
+    pub(crate) fn replace_address_chain(&mut self, key_name: &str, chain: AddressChain) {
+        self.chains.insert(key_name.to_string(), chain);
+    }
+
+    /// Change chain of a key; its addresses are internal, never handed out
+    pub fn change_chain(&self, key_name: &str) -> Option<&AddressChain> {
+        self.change_chains.get(key_name)
+    }
+
+    fn all_chains(&self) -> impl Iterator<Item = &AddressChain> {
+        self.chains.values().chain(self.change_chains.values())
+    }
+
+    /// Address the change of a send from `key_name` goes to: the first unused
+    /// address of its change chain, which stays the same until a send to it
+    /// is recorded. Imported keys have no change chain, so their change
+    /// returns to their own address.
+    pub fn change_address(&mut self, key_name: &str) -> WalletResult<Address> {
+        let Some(chain) = self.change_chains.get_mut(key_name) else {
+            return self
+                .keys
+                .get(key_name)
+                .map(|key| key.address().clone())
+                .ok_or_else(|| WalletError::KeyNotFound(key_name.to_string()));
+        };
+        let index = chain.peek_receive_index();
+        Ok(chain.address_at(&self.seed, index).clone())
+    }
+
+    /// What `address` is for, or `None` if it isn't the wallet's
+    pub fn address_kind(&self, address: &Address) -> Option<AddressKind> {
+        if self
+            .change_chains
+            .values()
+            .any(|chain| chain.index_of(address).is_some())
+        {
+            Some(AddressKind::Change)
+        } else if self.watch_only.contains(address) {
+            Some(AddressKind::WatchOnly)
+        } else {
+            self.is_mine(address).then_some(AddressKind::Receive)
+        }
+    }
+
+    /// Name of the key `address` was derived for or is the own address of
+    pub fn owner_of(&self, address: &Address) -> Option<&str> {
+        self.key_name_for_address(address).or_else(|| {
+            self.chains
+                .iter()
+                .chain(&self.change_chains)
+                .find(|(_, chain)| chain.index_of(address).is_some())
+                .map(|(name, _)| name.as_str())
+        })
+    }
+
+    /// Hand out the next receive address for a key, skipping used ones
+    pub fn next_receive_address(&mut self, key_name: &str) -> WalletResult<Address> {
+        let chain = self
+            .chains
+            .get_mut(key_name)
+            .ok_or_else(|| WalletError::KeyNotFound(key_name.to_string()))?;
+        let index = chain.peek_receive_index();
+        let address = chain.address_at(&self.seed, index).clone();
+        chain.advance_past(index);
+        Ok(address)
+    }
+
+    /// Address `next_receive_address` would hand out, without advancing the chain
+    pub fn peek_receive_address(&mut self, key_name: &str) -> WalletResult<Address> {
+        let chain = self
+            .chains
+            .get_mut(key_name)
+            .ok_or_else(|| WalletError::KeyNotFound(key_name.to_string()))?;
+        let index = chain.peek_receive_index();
+        Ok(chain.address_at(&self.seed, index).clone())
+    }
+
+    /// Secret behind an address the wallet holds the key for: an imported key's
+    /// own address or any derived receive or change address
+    pub(crate) fn secret_for_address(&self, address: &Address) -> Option<[u8; 32]> {
+        let imported = self.imported.iter().find_map(|(name, secret)| {
+            (self.keys.get(name)?.address() == address).then_some(*secret)
+        });
+        imported.or_else(|| {
+            self.all_chains().find_map(|chain| {
+                let index = chain.index_of(address)?;
+                Some(hd::derive_secret(
+                    &self.seed,
+                    chain.branch,
+                    chain.account,
+                    index,
+                ))
+            })
+        })
+    }
+
+    /// Secret of `address` if it belongs to `key_name`. Receive and change
+    /// addresses not derived yet are looked for up to the gap limit past the
+    /// derived ones, so a freshly restored wallet can sign without a chain scan.
+    pub(crate) fn secret_for_key_address(
+        &self,
+        key_name: &str,
+        address: &Address,
+    ) -> Option<[u8; 32]> {
+        if let Some(secret) = self.imported.get(key_name) {
+            return (self.keys.get(key_name)?.address() == address).then_some(*secret);
+        }
+        [self.chains.get(key_name), self.change_chains.get(key_name)]
+            .into_iter()
+            .flatten()
+            .find_map(|chain| {
+                let derived = chain.addresses().len() as u32;
+                let derive =
+                    |index| hd::derive_address(&self.seed, chain.branch, chain.account, index);
+                chain
+                    .index_of(address)
+                    .or_else(|| {
+                        (derived..derived + DEFAULT_GAP_LIMIT)
+                            .find(|index| derive(*index) == *address)
+                    })
+                    .map(|index| hd::derive_secret(&self.seed, chain.branch, chain.account, index))
+            })
+    }
+
+    /// Track an address for incoming funds without holding its key
+    pub fn add_watch_only(&mut self, address: Address) {
+        self.watch_only.insert(address);
+    }
+
+    /// Whether the address is tracked without its key, so its funds can't be spent
+    pub fn is_watch_only(&self, address: &Address) -> bool {
+        self.watch_only.contains(address)
+    }
+
+    /// Whether the address belongs to the wallet: a key's own address, any
+    /// derived receive or change address, or a watch-only address
+    pub fn is_mine(&self, address: &Address) -> bool {
+        self.keys.values().any(|key| key.address() == address)
+            || self
+                .all_chains()
+                .any(|chain| chain.index_of(address).is_some())
+            || self.watch_only.contains(address)
+    }
+
+    /// Label one of the wallet's own addresses; a blank label removes it
+    pub fn set_address_label(&mut self, address: &Address, label: &str) -> WalletResult<()> {
+        if !self.is_mine(address) {
+            return Err(WalletError::InvalidAddress(format!(
+                "{} does not belong to this wallet",
+                address
+            )));
+        }
+        let label = label.trim();
+        if label.is_empty() {
+            self.labels.remove(address);
+        } else {
+            self.labels.insert(address.clone(), label.to_string());
+        }
+        Ok(())
+    }
+
+    pub fn address_label(&self, address: &Address) -> Option<&str> {
+        self.labels.get(address).map(String::as_str)
+    }
+
+    /// All address labels, for persistence and display
+    pub fn address_labels(&self) -> &HashMap<Address, String> {
+        &self.labels
+    }
+
+    /// Load persisted labels. They are kept by address, so they apply again
+    /// once a rescan rederives the addresses.
+    pub fn restore_address_labels(&mut self, labels: HashMap<Address, String>) {
+        self.labels.extend(labels);
+    }
+
+    /// Mark a derived address as used in whichever chain owns it
+    pub fn mark_address_used(&mut self, address: &Address) -> bool {
+        self.chains
+            .values_mut()
+            .chain(self.change_chains.values_mut())
+            .any(|chain| chain.mark_used(address))
+    }
+
+    /// Whether a derived address is marked used in the chain that owns it
+    pub fn is_address_used(&self, address: &Address) -> bool {
+        self.all_chains().any(|chain| {
+            chain
+                .index_of(address)
+                .is_some_and(|index| chain.is_used(index))
+        })
+    }
+
+    /// Gap-limit scan of one key's receive and change chains: keep deriving
+    /// until `gap_limit` consecutive addresses are neither already used nor
+    /// reported by `has_funds`. Returns the number of used addresses in both
+    /// chains afterwards.
+    pub fn scan_chain(
+        &mut self,
+        key_name: &str,
+        gap_limit: u32,
+        has_funds: impl Fn(&Address) -> bool,
+    ) -> WalletResult<usize> {
+        let chain = self
+            .chains
+            .get_mut(key_name)
+            .ok_or_else(|| WalletError::KeyNotFound(key_name.to_string()))?;
+        let mut used = scan(chain, &self.seed, gap_limit, &has_funds);
+        if let Some(change) = self.change_chains.get_mut(key_name) {
+            used += scan(change, &self.seed, gap_limit, &has_funds);
+        }
+        Ok(used)
+    }
+
+    /// Hash of the canonical signing bytes for a transaction
     pub fn create_transaction_hash(
         &self,
-        _inputs: &[TransactionInput],
-        _outputs: &[TransactionOutput],
-        _fee: u64,
+        chain_id: &[u8; 32],
+        inputs: &[TransactionInput],
+        outputs: &[TransactionOutput],
+        fee: u64,
+        expiry_height: Option<u64>,
     ) -> Vec<u8> {
-        vec![0u8; 32]
+        encoding::signing_hash(chain_id, inputs, outputs, fee, expiry_height)
     }
 
     /// Dummy implementation for compatibility  
@@ -101,6 +498,33 @@ impl NockchainKeyManager {
     }
 }
 
+/// Derive `chain` until `gap_limit` consecutive addresses are unused, marking
+/// those `has_funds` reports as used. Returns the used count afterwards.
+fn scan(
+    chain: &mut AddressChain,
+    seed: &[u8; 32],
+    gap_limit: u32,
+    has_funds: impl Fn(&Address) -> bool,
+) -> usize {
+    let mut index = 0;
+    let mut gap = 0;
+    while gap < gap_limit {
+        let used = chain.is_used(index) || has_funds(chain.address_at(seed, index));
+        if used {
+            chain.mark_index_used(index);
+            gap = 0;
+        } else {
+            gap += 1;
+        }
+        index += 1;
+    }
+
+    if let Some(highest) = chain.highest_used() {
+        chain.advance_past(highest);
+    }
+    chain.used_indices().count()
+}
+
 /// Dummy transaction for compatibility
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct NockchainTransaction {
@@ -108,6 +532,9 @@ pub struct NockchainTransaction {
     pub inputs: Vec<TransactionInput>,
     pub outputs: Vec<TransactionOutput>,
     pub hash: Vec<u8>,
+    /// Last block height the transaction may be mined at
+    #[serde(default)]
+    pub expiry_height: Option<u64>,
 }
 
 impl NockchainTransaction {
@@ -117,23 +544,269 @@ impl NockchainTransaction {
             inputs: Vec::new(),
             outputs: Vec::new(),
             hash: Vec::new(),
+            expiry_height: None,
         }
     }
+
+    /// Whether a block at `height` may no longer include it
+    pub fn is_expired_at(&self, height: u64) -> bool {
+        self.expiry_height.is_some_and(|expiry| height > expiry)
+    }
+}
+
+/// Reference to a specific output of a previous transaction, written `txid:index`
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+pub struct OutPoint {
+    /// Hex-encoded id of the transaction that created the output
+    pub txid: String,
+    pub index: u32,
+}
+
+impl OutPoint {
+    pub fn new(txid: impl Into<String>, index: u32) -> Self {
+        Self {
+            txid: txid.into(),
+            index,
+        }
+    }
+}
+
+impl fmt::Display for OutPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.txid, self.index)
+    }
+}
+
+impl FromStr for OutPoint {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| {
+            WalletError::Transaction(format!("Invalid outpoint '{}': {}", s, reason))
+        };
+
+        let (txid, index) = s
+            .rsplit_once(':')
+            .ok_or_else(|| invalid("expected txid:index"))?;
+        if txid.is_empty() {
+            return Err(invalid("empty txid"));
+        }
+        if txid.len() % 2 != 0 || !txid.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(invalid("txid must be hex"));
+        }
+        let index = index
+            .parse::<u32>()
+            .map_err(|_| invalid("index must be a u32"))?;
+
+        Ok(Self::new(txid.to_ascii_lowercase(), index))
+    }
 }
 
 /// Dummy transaction input
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct TransactionInput {
+    /// Output being spent
+    pub previous_output: OutPoint,
     pub amount: u64,
 }
 
+impl TransactionInput {
+    pub fn outpoint(&self) -> &OutPoint {
+        &self.previous_output
+    }
+}
+
+impl fmt::Display for TransactionInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.previous_output, self.amount)
+    }
+}
+
 /// Dummy transaction output  
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct TransactionOutput {
     pub amount: u64,
     pub recipient_address: String,
 }
 
+impl fmt::Display for TransactionOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.recipient_address, self.amount)
+    }
+}
+
 // Type aliases for compatibility
 pub type KeyManager = NockchainKeyManager;
 pub type KeyPair = NockchainKeyPair;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::hd::Branch;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn outpoints_round_trip_through_text() {
+        for text in ["ab:0", "00ff:4294967295", "deadbeef:7"] {
+            let outpoint: OutPoint = text.parse().unwrap();
+            assert_eq!(outpoint.to_string(), text);
+        }
+        let upper: OutPoint = "ABCD:3".parse().unwrap();
+        assert_eq!(upper, OutPoint::new("abcd", 3));
+    }
+
+    #[test]
+    fn malformed_outpoints_say_what_is_wrong() {
+        let reason = |text: &str| text.parse::<OutPoint>().unwrap_err().to_string();
+        assert!(reason("abcd").contains("expected txid:index"));
+        assert!(reason(":1").contains("empty txid"));
+        assert!(reason("abc:1").contains("txid must be hex"));
+        assert!(reason("zz:1").contains("txid must be hex"));
+        assert!(reason("ab:-1").contains("index must be a u32"));
+        assert!(reason("ab:4294967296").contains("index must be a u32"));
+        assert!(reason("ab:1:2").contains("txid must be hex"));
+    }
+
+    #[test]
+    fn outpoints_order_by_txid_then_index_and_dedup() {
+        let set: BTreeSet<OutPoint> = ["bb:0", "aa:2", "aa:10", "aa:2"]
+            .iter()
+            .map(|text| text.parse().unwrap())
+            .collect();
+        let ordered: Vec<String> = set.iter().map(ToString::to_string).collect();
+        assert_eq!(ordered, vec!["aa:2", "aa:10", "bb:0"]);
+    }
+
+    #[test]
+    fn inputs_show_their_outpoint_and_amount() {
+        let input = TransactionInput {
+            previous_output: OutPoint::new("ab", 1),
+            amount: 500,
+        };
+        assert_eq!(input.outpoint(), &OutPoint::new("ab", 1));
+        assert_eq!(input.to_string(), "ab:1 (500)");
+    }
+
+    const SEED: [u8; 32] = [42; 32];
+
+    fn receive(index: u32) -> Address {
+        hd::derive_address(&SEED, Branch::Receive, 0, index)
+    }
+
+    /// A key manager restored from `SEED` with one key, "main"
+    fn restored() -> NockchainKeyManager {
+        let mut keys = NockchainKeyManager::from_seed(SEED);
+        keys.generate_key("main".to_string()).unwrap();
+        keys
+    }
+
+    #[test]
+    fn the_gap_limit_scan_finds_funds_at_7_and_19() {
+        let funded = [receive(7), receive(19)];
+        let mut keys = restored();
+
+        let used = keys
+            .scan_chain("main", DEFAULT_GAP_LIMIT, |address| {
+                funded.contains(address)
+            })
+            .unwrap();
+
+        assert_eq!(used, 2);
+        let chain = keys.address_chain("main").unwrap();
+        assert_eq!(chain.used_indices().collect::<Vec<_>>(), vec![7, 19]);
+        // Twenty unused addresses past the last funded one were checked
+        assert_eq!(chain.derived_count(), 40);
+        assert_eq!(keys.next_receive_address("main").unwrap(), receive(20));
+    }
+
+    #[test]
+    fn a_gap_limit_shorter_than_the_gap_misses_later_funds() {
+        let funded = [receive(7), receive(19)];
+        let mut keys = restored();
+        let used = keys
+            .scan_chain("main", 10, |address| funded.contains(address))
+            .unwrap();
+        assert_eq!(used, 1);
+        assert_eq!(keys.address_chain("main").unwrap().derived_count(), 18);
+    }
+
+    #[test]
+    fn receive_addresses_skip_ones_seen_used() {
+        let mut keys = restored();
+        let first = keys.next_receive_address("main").unwrap();
+        assert_eq!(first, receive(0));
+
+        // Funds arrive at the next two addresses before they were handed out
+        keys.scan_chain("main", 3, |_| false).unwrap();
+        assert!(keys.mark_address_used(&receive(1)));
+        assert!(keys.mark_address_used(&receive(2)));
+        assert!(!keys.mark_address_used(&hd::derive_address(&SEED, Branch::Receive, 1, 0)));
+
+        assert_eq!(keys.peek_receive_address("main").unwrap(), receive(3));
+        assert_eq!(keys.next_receive_address("main").unwrap(), receive(3));
+        assert!(matches!(
+            keys.next_receive_address("other"),
+            Err(WalletError::KeyNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn keys_generated_in_order_from_one_seed_match() {
+        let mut a = NockchainKeyManager::from_seed(SEED);
+        let mut b = NockchainKeyManager::from_seed(SEED);
+        for name in ["main", "savings"] {
+            assert_eq!(
+                a.generate_key(name.to_string()).unwrap().address(),
+                b.generate_key(name.to_string()).unwrap().address()
+            );
+        }
+        assert_eq!(a.address_chain("savings").unwrap().account, 1);
+    }
+
+    #[test]
+    fn is_mine_covers_derived_imported_and_watch_only_addresses() {
+        let mut keys = restored();
+        let receive_address = keys.next_receive_address("main").unwrap();
+        let change = keys.change_address("main").unwrap();
+        let imported = keys
+            .import_key("cold".to_string(), [3; 32])
+            .unwrap()
+            .address()
+            .clone();
+        let watched = hd::derive_address(&[1; 32], Branch::Receive, 0, 0);
+        keys.add_watch_only(watched.clone());
+
+        for address in [&receive_address, &change, &imported, &watched] {
+            assert!(keys.is_mine(address), "{}", address);
+        }
+        // Not yet derived, and someone else's
+        assert!(!keys.is_mine(&receive(5)));
+        assert!(!keys.is_mine(&hd::derive_address(&[1; 32], Branch::Receive, 0, 1)));
+    }
+
+    #[test]
+    fn a_key_is_found_by_either_address_form() {
+        let mut keys = NockchainKeyManager::from_seed([9; 32]);
+        let address = keys
+            .generate_key("main".to_string())
+            .unwrap()
+            .address()
+            .clone();
+        keys.generate_key("other".to_string()).unwrap();
+
+        for text in [
+            address.to_string(),
+            address.to_nockchain_string(),
+            format!("  NOCK_{}\n", address),
+        ] {
+            let key = keys.key_for_address_str(&text).unwrap();
+            assert_eq!(key.name(), "main", "{}", text);
+        }
+        assert!(keys
+            .key_for_address_str(&crate::testing::fake_address().to_nockchain_string())
+            .is_none());
+        assert!(keys.key_for_address_str("nock_not-an-address").is_none());
+    }
+}
@@ -0,0 +1,349 @@
+//! Command-line flags of the desktop app.
+//!
+//! Flags change how one session starts and are never saved. `--data-dir`,
+//! `--fakenet` and `--log-level` become the top layer of the node config (see
+//! `ConfigLayers::load_with_overrides`); the rest are for the app to act on
+//! once it's up. Each flag may be given once, and values can follow as the
+//! next argument or after `=`.
+
+use serde_json::{Map, Value};
+use std::path::PathBuf;
+use thiserror::Error;
+
+use crate::wallet::log_buffer::LogLevels;
+use crate::wallet::network::LogLevel;
+
+/// Options listed by `--help`
+pub const LAUNCH_USAGE: &str = "\
+Options:
+  --data-dir <PATH>     Read config, profiles and node data from PATH
+  --profile <NAME>      Open the wallet profile NAME at startup
+  --fakenet             Run the node on a fake network
+  --auto-start-node     Start the node once a profile is open
+  --locked              Open the profile locked, whatever its settings
+  --log-level <LEVEL>   Lowest level logged: trace, debug, info, warn or error
+  -h, --help            Print this help
+
+Options apply to this session only and are never saved.";
+
+/// Flags that couldn't be used
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum LaunchError {
+    #[error("Unknown option '{0}'")]
+    UnknownFlag(String),
+
+    #[error("{0} needs a value")]
+    MissingValue(&'static str),
+
+    #[error("{0} takes no value")]
+    UnexpectedValue(&'static str),
+
+    #[error("Invalid value '{value}' for {flag}: {message}")]
+    InvalidValue {
+        flag: &'static str,
+        value: String,
+        message: String,
+    },
+
+    #[error("{0} was given more than once")]
+    Repeated(&'static str),
+
+    #[error("{first} can't be combined with {second}: {reason}")]
+    Conflict {
+        first: &'static str,
+        second: &'static str,
+        reason: &'static str,
+    },
+}
+
+/// Flags the desktop app was started with
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LaunchOptions {
+    pub data_dir: Option<PathBuf>,
+    pub profile: Option<String>,
+    pub fakenet: bool,
+    pub auto_start_node: bool,
+    pub locked: bool,
+    pub log_level: Option<LogLevel>,
+    /// `--help` was given; print `LAUNCH_USAGE` and exit
+    pub help: bool,
+}
+
+impl LaunchOptions {
+    /// Parse `args`, without the program name
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, LaunchError> {
+        let mut options = Self::default();
+        let mut args = args.into_iter().peekable();
+        while let Some(arg) = args.next() {
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => {
+                    (name.to_string(), Some(value.to_string()))
+                }
+                _ => (arg.clone(), None),
+            };
+            let flag = match name.as_str() {
+                "--data-dir" => "--data-dir",
+                "--profile" => "--profile",
+                "--fakenet" => "--fakenet",
+                "--auto-start-node" => "--auto-start-node",
+                "--locked" => "--locked",
+                "--log-level" => "--log-level",
+                "-h" | "--help" => "--help",
+                _ => return Err(LaunchError::UnknownFlag(arg)),
+            };
+
+            let takes_value = matches!(flag, "--data-dir" | "--profile" | "--log-level");
+            let value = if !takes_value {
+                if inline.is_some() {
+                    return Err(LaunchError::UnexpectedValue(flag));
+                }
+                None
+            } else {
+                let value = match inline {
+                    Some(value) => Some(value),
+                    None => args.next_if(|next| !next.starts_with("--")),
+                };
+                let value = value.filter(|value| !value.trim().is_empty());
+                Some(value.ok_or(LaunchError::MissingValue(flag))?)
+            };
+
+            let repeated = match flag {
+                "--data-dir" => options.data_dir.is_some(),
+                "--profile" => options.profile.is_some(),
+                "--fakenet" => options.fakenet,
+                "--auto-start-node" => options.auto_start_node,
+                "--locked" => options.locked,
+                "--log-level" => options.log_level.is_some(),
+                _ => options.help,
+            };
+            if repeated {
+                return Err(LaunchError::Repeated(flag));
+            }
+
+            match (flag, value) {
+                ("--data-dir", Some(value)) => options.data_dir = Some(PathBuf::from(value)),
+                ("--profile", Some(value)) => options.profile = Some(value),
+                ("--log-level", Some(value)) => {
+                    options.log_level =
+                        Some(
+                            parse_log_level(&value).ok_or_else(|| LaunchError::InvalidValue {
+                                flag,
+                                value,
+                                message: "expected trace, debug, info, warn or error".to_string(),
+                            })?,
+                        )
+                }
+                ("--fakenet", _) => options.fakenet = true,
+                ("--auto-start-node", _) => options.auto_start_node = true,
+                ("--locked", _) => options.locked = true,
+                _ => options.help = true,
+            }
+        }
+
+        if options.profile.is_some() && options.data_dir.is_some() {
+            return Err(LaunchError::Conflict {
+                first: "--profile",
+                second: "--data-dir",
+                reason: "a profile has its own data dir",
+            });
+        }
+        Ok(options)
+    }
+
+    /// Parse the arguments the process was started with
+    pub fn from_env() -> Result<Self, LaunchError> {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    /// Whether any flag changes the session
+    pub fn is_empty(&self) -> bool {
+        self.active_flags().is_empty()
+    }
+
+    /// Node config fields the flags set, for `ConfigLayers::load_with_overrides`
+    pub fn config_overrides(&self) -> Map<String, Value> {
+        let mut overrides = Map::new();
+        if let Some(dir) = &self.data_dir {
+            overrides.insert(
+                "data_dir".to_string(),
+                Value::from(dir.to_string_lossy().into_owned()),
+            );
+        }
+        if self.fakenet {
+            overrides.insert("fakenet".to_string(), Value::Bool(true));
+        }
+        if let Some(level) = self.log_level {
+            let levels = LogLevels {
+                default: level,
+                ..LogLevels::default()
+            };
+            if let Ok(levels) = serde_json::to_value(levels) {
+                overrides.insert("log_levels".to_string(), levels);
+            }
+        }
+        overrides
+    }
+
+    /// Each flag in effect as it would be typed, e.g. `--profile savings`
+    pub fn active_flags(&self) -> Vec<String> {
+        let mut flags = Vec::new();
+        if let Some(dir) = &self.data_dir {
+            flags.push(format!("--data-dir {}", dir.display()));
+        }
+        if let Some(profile) = &self.profile {
+            flags.push(format!("--profile {}", profile));
+        }
+        if self.fakenet {
+            flags.push("--fakenet".to_string());
+        }
+        if self.auto_start_node {
+            flags.push("--auto-start-node".to_string());
+        }
+        if self.locked {
+            flags.push("--locked".to_string());
+        }
+        if let Some(level) = self.log_level {
+            let level = format!("{:?}", level).to_lowercase();
+            flags.push(format!("--log-level {}", level));
+        }
+        flags
+    }
+}
+
+fn parse_log_level(value: &str) -> Option<LogLevel> {
+    LogLevel::ALL
+        .into_iter()
+        .find(|level| format!("{:?}", level).eq_ignore_ascii_case(value.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use crate::wallet::config_file::{ConfigLayers, ConfigSource, RUNTIME_CONFIG_FILE};
+    use crate::wallet::NockchainNodeConfig;
+
+    fn parse(args: &[&str]) -> Result<LaunchOptions, LaunchError> {
+        LaunchOptions::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn flags_take_values_after_a_space_or_an_equals_sign() {
+        let options = parse(&[
+            "--data-dir",
+            "/tmp/nock",
+            "--fakenet",
+            "--log-level=WARN",
+            "--locked",
+            "--auto-start-node",
+        ])
+        .unwrap();
+        assert_eq!(
+            options,
+            LaunchOptions {
+                data_dir: Some(PathBuf::from("/tmp/nock")),
+                fakenet: true,
+                auto_start_node: true,
+                locked: true,
+                log_level: Some(LogLevel::Warn),
+                ..LaunchOptions::default()
+            }
+        );
+        assert_eq!(
+            options.active_flags(),
+            vec![
+                "--data-dir /tmp/nock",
+                "--fakenet",
+                "--auto-start-node",
+                "--locked",
+                "--log-level warn",
+            ]
+        );
+        assert!(parse(&[]).unwrap().is_empty());
+        assert!(parse(&["-h"]).unwrap().help);
+    }
+
+    #[test]
+    fn bad_command_lines_say_what_is_wrong() {
+        assert_eq!(
+            parse(&["--turbo"]),
+            Err(LaunchError::UnknownFlag("--turbo".to_string()))
+        );
+        assert_eq!(
+            parse(&["--profile", "--fakenet"]),
+            Err(LaunchError::MissingValue("--profile"))
+        );
+        assert_eq!(
+            parse(&["--data-dir="]),
+            Err(LaunchError::MissingValue("--data-dir"))
+        );
+        assert_eq!(
+            parse(&["--fakenet=yes"]),
+            Err(LaunchError::UnexpectedValue("--fakenet"))
+        );
+        assert_eq!(
+            parse(&["--locked", "--locked"]),
+            Err(LaunchError::Repeated("--locked"))
+        );
+        assert!(matches!(
+            parse(&["--log-level", "loud"]),
+            Err(LaunchError::InvalidValue {
+                flag: "--log-level",
+                ..
+            })
+        ));
+
+        let conflict = parse(&["--profile", "savings", "--data-dir", "/tmp/nock"]).unwrap_err();
+        assert!(matches!(conflict, LaunchError::Conflict { .. }));
+        assert_eq!(
+            conflict.to_string(),
+            "--profile can't be combined with --data-dir: a profile has its own data dir"
+        );
+    }
+
+    #[test]
+    fn only_node_settings_become_config_overrides() {
+        let options = parse(&["--profile", "savings", "--locked"]).unwrap();
+        assert!(options.config_overrides().is_empty());
+
+        let overrides = parse(&["--fakenet", "--log-level", "debug"])
+            .unwrap()
+            .config_overrides();
+        assert_eq!(overrides["fakenet"], Value::Bool(true));
+        assert_eq!(overrides["log_levels"]["default"], "Debug");
+    }
+
+    #[test]
+    fn overrides_win_over_every_saved_layer_and_are_never_saved() {
+        let dir = TempDir::new("launch");
+        std::fs::write(
+            dir.join(RUNTIME_CONFIG_FILE),
+            r#"{ "fakenet": false, "p2p_port": 5001 }"#,
+        )
+        .unwrap();
+        let data_dir = dir.path().display().to_string();
+        let options = parse(&["--data-dir", &data_dir, "--fakenet"]).unwrap();
+        // The environment names another dir; the flag decides
+        let env = vec![("NOCKCHAIN_DATA_DIR".to_string(), "/nowhere".to_string())];
+
+        let (before, mut layers) =
+            ConfigLayers::load_with_overrides(env, options.config_overrides()).unwrap();
+        assert_eq!(layers.config_dir(), dir.path());
+        assert_eq!(before.data_dir, dir.path());
+        assert!(before.fakenet);
+        assert_eq!(before.p2p_port, 5001);
+        assert_eq!(layers.sources()["fakenet"], ConfigSource::CommandLine);
+        assert_eq!(layers.sources()["data_dir"], ConfigSource::CommandLine);
+
+        let after = NockchainNodeConfig {
+            p2p_port: 7001,
+            ..before.clone()
+        };
+        layers.record_runtime_edit(&before, &after).unwrap();
+        let saved: Map<String, Value> =
+            serde_json::from_slice(&std::fs::read(dir.join(RUNTIME_CONFIG_FILE)).unwrap()).unwrap();
+        assert_eq!(saved["p2p_port"], 7001);
+        assert_eq!(saved["fakenet"], false);
+        assert!(!saved.contains_key("data_dir"));
+    }
+}
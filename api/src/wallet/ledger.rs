@@ -0,0 +1,708 @@
+//! Double-entry record of every change to the wallet's balance.
+//!
+//! Balances are worked out from note state, which changes in place; the
+//! ledger keeps the history of how they got there. Each change posts an
+//! entry moving an amount from one account to another: between an account
+//! holding the wallet's funds (`Available`, `Immature`) and one outside it
+//! (`External`, `Fees`, `BlockRewards`), or between two on the same side, as
+//! when a coinbase matures or a fee is split out of a spend. Every entry sums
+//! to zero across the two, so the wallet's accounts together always hold
+//! exactly the unspent notes; `Wallet::verify_integrity` checks that.
+//!
+//! Entries are never changed or removed. A spend that is undone, a payment
+//! that is dropped before confirming or a block that a reorg takes away
+//! posts a contra entry pointing back at the entry it reverses.
+//! `BalanceManager` posts the entries as it changes the notes, so nothing
+//! that moves a balance can skip the ledger; like the notes, it lives in
+//! memory.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
+use uuid::Uuid;
+
+use crate::wallet::{Note, Wallet, WalletError, WalletResult};
+
+/// Where an amount is booked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerAccount {
+    /// The wallet's funds other than immature coinbase
+    Available,
+    /// Coinbase the wallet mined that can't be spent yet
+    Immature,
+    /// Everyone the wallet pays or is paid by
+    External,
+    /// Fees the wallet paid to miners
+    Fees,
+    /// Block rewards, where mined coinbase comes from
+    BlockRewards,
+}
+
+impl LedgerAccount {
+    pub const ALL: [LedgerAccount; 5] = [
+        LedgerAccount::Available,
+        LedgerAccount::Immature,
+        LedgerAccount::External,
+        LedgerAccount::Fees,
+        LedgerAccount::BlockRewards,
+    ];
+
+    /// Whether the account holds the wallet's own funds
+    pub fn is_wallet(self) -> bool {
+        matches!(self, LedgerAccount::Available | LedgerAccount::Immature)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LedgerAccount::Available => "available",
+            LedgerAccount::Immature => "immature",
+            LedgerAccount::External => "external",
+            LedgerAccount::Fees => "fees",
+            LedgerAccount::BlockRewards => "block_rewards",
+        }
+    }
+}
+
+impl fmt::Display for LedgerAccount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// What caused an entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerEvent {
+    NoteReceived,
+    CoinbaseReceived,
+    NoteSpent,
+    /// Part of a spend that went to the miner rather than a recipient
+    FeePaid,
+    CoinbaseMatured,
+    /// A spend that will never be mined, made spendable again
+    SpendReversed,
+    /// A fee of a spend that will never be mined
+    FeeReversed,
+    /// A payment dropped or replaced before it confirmed
+    ReceiptReversed,
+    /// Funds from blocks a reorg took away
+    ReorgReversal,
+}
+
+impl LedgerEvent {
+    pub fn label(self) -> &'static str {
+        match self {
+            LedgerEvent::NoteReceived => "note_received",
+            LedgerEvent::CoinbaseReceived => "coinbase_received",
+            LedgerEvent::NoteSpent => "note_spent",
+            LedgerEvent::FeePaid => "fee_paid",
+            LedgerEvent::CoinbaseMatured => "coinbase_matured",
+            LedgerEvent::SpendReversed => "spend_reversed",
+            LedgerEvent::FeeReversed => "fee_reversed",
+            LedgerEvent::ReceiptReversed => "receipt_reversed",
+            LedgerEvent::ReorgReversal => "reorg_reversal",
+        }
+    }
+
+    /// Whether the entry undoes an earlier one
+    pub fn is_reversal(self) -> bool {
+        matches!(
+            self,
+            LedgerEvent::SpendReversed
+                | LedgerEvent::FeeReversed
+                | LedgerEvent::ReceiptReversed
+                | LedgerEvent::ReorgReversal
+        )
+    }
+}
+
+impl fmt::Display for LedgerEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// One posting: `amount` is added to `account` and taken from
+/// `counter_account`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    /// Position in the ledger, from 0
+    pub sequence: u64,
+    pub recorded_at: DateTime<Utc>,
+    pub event: LedgerEvent,
+    pub account: LedgerAccount,
+    pub counter_account: LedgerAccount,
+    /// Negative when `account` gives to `counter_account`
+    pub amount: i128,
+    pub tx_id: Option<String>,
+    pub note_id: Option<Uuid>,
+    pub block_height: Option<u64>,
+    /// Sequence of the entry this one undoes
+    pub reverses: Option<u64>,
+    /// The wallet's funds once this entry is posted
+    pub balance_after: i128,
+}
+
+impl LedgerEntry {
+    /// How much the entry changes the wallet's funds by
+    pub fn wallet_change(&self) -> i128 {
+        let mut change = 0;
+        if self.account.is_wallet() {
+            change += self.amount;
+        }
+        if self.counter_account.is_wallet() {
+            change -= self.amount;
+        }
+        change
+    }
+}
+
+/// Result of checking the ledger against the notes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerCheck {
+    pub entries: usize,
+    /// Sum of the wallet's accounts
+    pub ledger_balance: i128,
+    /// Sum of the unspent notes
+    pub note_balance: u64,
+}
+
+impl LedgerCheck {
+    pub fn is_balanced(&self) -> bool {
+        self.ledger_balance == self.note_balance as i128
+    }
+}
+
+impl fmt::Display for LedgerCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_balanced() {
+            write!(
+                f,
+                "Ledger of {} entries matches the notes ({})",
+                self.entries, self.note_balance
+            )
+        } else {
+            write!(
+                f,
+                "Ledger of {} entries sums to {}, but the notes hold {}",
+                self.entries, self.ledger_balance, self.note_balance
+            )
+        }
+    }
+}
+
+/// What an entry refers to
+enum Source<'a> {
+    Note(&'a Note),
+    Transaction(&'a str),
+}
+
+/// Where an unspent note's amount is booked
+#[derive(Debug, Clone, Copy)]
+struct Holding {
+    account: LedgerAccount,
+    /// Where the amount came from
+    counter_account: LedgerAccount,
+    /// Entry that received it
+    received: u64,
+    /// Entry that matured it, for coinbase
+    matured: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+pub struct Ledger {
+    entries: Vec<LedgerEntry>,
+    balance: i128,
+    /// Unspent notes by id
+    held: HashMap<Uuid, Holding>,
+    /// Spent notes by id: the spend entry and where the note was held
+    spends: HashMap<Uuid, (u64, Holding)>,
+    /// Fees by transaction, with the entry that paid them, until reversed
+    fees: HashMap<String, (u64, u64)>,
+}
+
+impl Ledger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn entries(&self) -> &[LedgerEntry] {
+        &self.entries
+    }
+
+    /// Entries with sequence numbers in `sequences`
+    pub fn range(&self, sequences: impl RangeBounds<u64>) -> &[LedgerEntry] {
+        let len = self.entries.len() as u64;
+        let start = match sequences.start_bound() {
+            Bound::Included(start) => *start,
+            Bound::Excluded(start) => start.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match sequences.end_bound() {
+            Bound::Included(end) => end.saturating_add(1),
+            Bound::Excluded(end) => *end,
+            Bound::Unbounded => len,
+        };
+        let end = end.min(len);
+        &self.entries[start.min(end) as usize..end as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The wallet's funds by the ledger
+    pub fn balance(&self) -> i128 {
+        self.balance
+    }
+
+    /// Net amount posted to `account`. The accounts always sum to zero.
+    pub fn account_balance(&self, account: LedgerAccount) -> i128 {
+        self.entries
+            .iter()
+            .map(|entry| {
+                let mut amount = 0;
+                if entry.account == account {
+                    amount += entry.amount;
+                }
+                if entry.counter_account == account {
+                    amount -= entry.amount;
+                }
+                amount
+            })
+            .sum()
+    }
+
+    /// Compare the ledger with `note_balance`, the unspent notes' total
+    pub fn check(&self, note_balance: u64) -> LedgerCheck {
+        LedgerCheck {
+            entries: self.entries.len(),
+            ledger_balance: self.balance,
+            note_balance,
+        }
+    }
+
+    /// Append an entry adding `amount` to `accounts.0`, taken from
+    /// `accounts.1`, and return its sequence number
+    fn post(
+        &mut self,
+        event: LedgerEvent,
+        (account, counter_account): (LedgerAccount, LedgerAccount),
+        amount: i128,
+        source: Source<'_>,
+        reverses: Option<u64>,
+    ) -> u64 {
+        let sequence = self.entries.len() as u64;
+        let (tx_id, note) = match source {
+            Source::Note(note) => (note.transaction_id.as_str(), Some(note)),
+            Source::Transaction(tx_id) => (tx_id, None),
+        };
+        let mut entry = LedgerEntry {
+            sequence,
+            recorded_at: Utc::now(),
+            event,
+            account,
+            counter_account,
+            amount,
+            tx_id: Some(tx_id.to_string()),
+            note_id: note.map(|note| note.id),
+            block_height: note.and_then(|note| note.block_height),
+            reverses,
+            balance_after: 0,
+        };
+        self.balance += entry.wallet_change();
+        entry.balance_after = self.balance;
+        self.entries.push(entry);
+        sequence
+    }
+
+    /// Book a note the wallet received, as immature if it can't be spent yet
+    pub(crate) fn receive(&mut self, note: &Note, immature: bool) {
+        if self.held.contains_key(&note.id) {
+            return;
+        }
+        let (event, counter_account) = if note.coinbase {
+            (LedgerEvent::CoinbaseReceived, LedgerAccount::BlockRewards)
+        } else {
+            (LedgerEvent::NoteReceived, LedgerAccount::External)
+        };
+        let account = if immature {
+            LedgerAccount::Immature
+        } else {
+            LedgerAccount::Available
+        };
+        let received = self.post(
+            event,
+            (account, counter_account),
+            note.amount as i128,
+            Source::Note(note),
+            None,
+        );
+        self.held.insert(
+            note.id,
+            Holding {
+                account,
+                counter_account,
+                received,
+                matured: None,
+            },
+        );
+    }
+
+    /// Book the spend of a held note
+    pub(crate) fn spend(&mut self, note: &Note) {
+        let Some(held) = self.held.remove(&note.id) else {
+            return;
+        };
+        let spent = self.post(
+            LedgerEvent::NoteSpent,
+            (held.account, LedgerAccount::External),
+            -(note.amount as i128),
+            Source::Note(note),
+            None,
+        );
+        self.spends.insert(note.id, (spent, held));
+    }
+
+    /// Reverse the spend of a note that is spendable again
+    pub(crate) fn unspend(&mut self, note: &Note) {
+        let Some((spent, held)) = self.spends.remove(&note.id) else {
+            return;
+        };
+        self.post(
+            LedgerEvent::SpendReversed,
+            (held.account, LedgerAccount::External),
+            note.amount as i128,
+            Source::Note(note),
+            Some(spent),
+        );
+        self.held.insert(note.id, held);
+    }
+
+    /// Reverse the receipt of a held note that is gone, with `event` saying why
+    pub(crate) fn reverse_receipt(&mut self, note: &Note, event: LedgerEvent) {
+        let Some(held) = self.held.remove(&note.id) else {
+            return;
+        };
+        self.post(
+            event,
+            (held.account, held.counter_account),
+            -(note.amount as i128),
+            Source::Note(note),
+            Some(held.received),
+        );
+    }
+
+    /// Whether `note` is booked as immature
+    pub(crate) fn is_immature(&self, note_id: &Uuid) -> bool {
+        self.held
+            .get(note_id)
+            .is_some_and(|held| held.account == LedgerAccount::Immature)
+    }
+
+    /// Move a held coinbase note between immature and available. Maturing
+    /// is a `CoinbaseMatured` entry; going back, after a reorg lowered the
+    /// tip, is a `ReorgReversal` of it.
+    pub(crate) fn set_immature(&mut self, note: &Note, immature: bool) {
+        let Some(mut held) = self.held.get(&note.id).copied() else {
+            return;
+        };
+        if (held.account == LedgerAccount::Immature) == immature {
+            return;
+        }
+        if immature {
+            self.post(
+                LedgerEvent::ReorgReversal,
+                (LedgerAccount::Immature, LedgerAccount::Available),
+                note.amount as i128,
+                Source::Note(note),
+                held.matured.take(),
+            );
+            held.account = LedgerAccount::Immature;
+        } else {
+            held.matured = Some(self.post(
+                LedgerEvent::CoinbaseMatured,
+                (LedgerAccount::Available, LedgerAccount::Immature),
+                note.amount as i128,
+                Source::Note(note),
+                None,
+            ));
+            held.account = LedgerAccount::Available;
+        }
+        self.held.insert(note.id, held);
+    }
+
+    /// Split `fee` out of what the spends of `tx_id` paid out. Posted once
+    /// per transaction.
+    pub(crate) fn record_fee(&mut self, tx_id: &str, fee: u64) {
+        if fee == 0 || self.fees.contains_key(tx_id) {
+            return;
+        }
+        let paid = self.post(
+            LedgerEvent::FeePaid,
+            (LedgerAccount::Fees, LedgerAccount::External),
+            fee as i128,
+            Source::Transaction(tx_id),
+            None,
+        );
+        self.fees.insert(tx_id.to_string(), (fee, paid));
+    }
+
+    /// Reverse the fee of `tx_id`, which will never be mined
+    pub(crate) fn reverse_fee(&mut self, tx_id: &str) {
+        let Some((fee, paid)) = self.fees.remove(tx_id) else {
+            return;
+        };
+        self.post(
+            LedgerEvent::FeeReversed,
+            (LedgerAccount::Fees, LedgerAccount::External),
+            -(fee as i128),
+            Source::Transaction(tx_id),
+            Some(paid),
+        );
+    }
+}
+
+impl Wallet {
+    /// Ledger entries with sequence numbers in `sequences`, e.g. `..` for all
+    pub fn ledger(&self, sequences: impl RangeBounds<u64>) -> &[LedgerEntry] {
+        self.balances.ledger().range(sequences)
+    }
+
+    /// Check that the ledger sums to the wallet's unspent notes. Part of
+    /// `verify_integrity`.
+    pub fn check_ledger(&self) -> LedgerCheck {
+        self.balances.ledger().check(self.balances.unspent_total())
+    }
+
+    /// Undo the blocks above `height` after a reorg, posting contra entries
+    /// for what they paid the wallet. Returns the amount removed.
+    pub fn revert_blocks_above(&mut self, height: u64) -> WalletResult<u64> {
+        self.ensure_writable()?;
+        if let Some(tip) = self.balances.tip_height().filter(|tip| *tip < height) {
+            return Err(WalletError::BlockValidation(format!(
+                "Can't revert to height {} above the tip {}",
+                height, tip
+            )));
+        }
+        Ok(self.balances.revert_blocks_above(height))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fake_note;
+    use crate::wallet::balance::{BalanceManager, COINBASE_MATURITY};
+    use crate::wallet::export::{export_ledger_to_writer, ExportFormat};
+
+    /// After each step the ledger holds `balance`, matches the notes, sums
+    /// to zero across its accounts and ends with an entry for `event`
+    fn step(balances: &BalanceManager, event: LedgerEvent, balance: i128) {
+        let ledger = balances.ledger();
+        let last = ledger.entries().last().unwrap();
+        assert_eq!(last.event, event);
+        assert_eq!(last.balance_after, balance, "after {}", event);
+        assert_eq!(ledger.balance(), balance, "after {}", event);
+        assert!(
+            ledger.check(balances.unspent_total()).is_balanced(),
+            "after {}",
+            event
+        );
+        let total: i128 = LedgerAccount::ALL
+            .iter()
+            .map(|account| ledger.account_balance(*account))
+            .sum();
+        assert_eq!(total, 0, "after {}", event);
+        let held = ledger.account_balance(LedgerAccount::Available)
+            + ledger.account_balance(LedgerAccount::Immature);
+        assert_eq!(held, balance, "after {}", event);
+    }
+
+    fn last(balances: &BalanceManager) -> &LedgerEntry {
+        balances.ledger().entries().last().unwrap()
+    }
+
+    #[test]
+    fn a_scripted_history_keeps_a_running_sum_at_every_step() {
+        let mut balances = BalanceManager::new();
+        balances.set_tip_height(2);
+
+        let received = fake_note().amount(1_000).confirmed_at(1).build();
+        let received_id = received.id;
+        balances.add_note(received.clone()).unwrap();
+        step(&balances, LedgerEvent::NoteReceived, 1_000);
+        assert_eq!(
+            (last(&balances).account, last(&balances).counter_account),
+            (LedgerAccount::Available, LedgerAccount::External)
+        );
+
+        let mined = fake_note().amount(5_000).confirmed_at(2).coinbase().build();
+        balances.add_note(mined).unwrap();
+        step(&balances, LedgerEvent::CoinbaseReceived, 6_000);
+        let coinbase_received = last(&balances).sequence;
+        assert_eq!(last(&balances).account, LedgerAccount::Immature);
+        assert_eq!(last(&balances).block_height, Some(2));
+
+        // Pay 300 out of the 1,000 note with a fee of 10 and 690 change
+        balances.spend_note(received_id).unwrap();
+        step(&balances, LedgerEvent::NoteSpent, 5_000);
+        let spent = last(&balances).sequence;
+        let change = fake_note().amount(690).outpoint("send", 1).build();
+        balances.add_note(change).unwrap();
+        step(&balances, LedgerEvent::NoteReceived, 5_690);
+        balances.record_fee("send", 10);
+        step(&balances, LedgerEvent::FeePaid, 5_690);
+        let fee_paid = last(&balances).sequence;
+        assert_eq!(balances.ledger().account_balance(LedgerAccount::Fees), 10);
+        balances.record_fee("send", 10);
+        assert_eq!(last(&balances).sequence, fee_paid);
+
+        // The send never confirms and is undone entry by entry
+        assert_eq!(balances.remove_unconfirmed_notes("send"), 690);
+        step(&balances, LedgerEvent::ReceiptReversed, 5_000);
+        balances.unspend_note(received_id).unwrap();
+        step(&balances, LedgerEvent::SpendReversed, 6_000);
+        assert_eq!(last(&balances).reverses, Some(spent));
+        balances.reverse_fee("send");
+        step(&balances, LedgerEvent::FeeReversed, 6_000);
+        assert_eq!(last(&balances).reverses, Some(fee_paid));
+        assert_eq!(balances.ledger().account_balance(LedgerAccount::Fees), 0);
+
+        balances.set_tip_height(2 + COINBASE_MATURITY);
+        step(&balances, LedgerEvent::CoinbaseMatured, 6_000);
+        let matured = last(&balances).sequence;
+        assert_eq!(
+            balances.ledger().account_balance(LedgerAccount::Immature),
+            0
+        );
+
+        // A shallow reorg makes the coinbase immature again
+        balances.revert_blocks_above(2 + COINBASE_MATURITY - 1);
+        step(&balances, LedgerEvent::ReorgReversal, 6_000);
+        assert_eq!(last(&balances).reverses, Some(matured));
+        assert_eq!(
+            balances.ledger().account_balance(LedgerAccount::Immature),
+            5_000
+        );
+
+        // A deep one takes the block that mined it
+        assert_eq!(balances.revert_blocks_above(1), 5_000);
+        step(&balances, LedgerEvent::ReorgReversal, 1_000);
+        assert_eq!(last(&balances).reverses, Some(coinbase_received));
+        assert_eq!(
+            balances
+                .ledger()
+                .account_balance(LedgerAccount::BlockRewards),
+            0
+        );
+
+        // Nothing was ever removed, and every reversal says what it undoes
+        let entries = balances.ledger().entries();
+        assert_eq!(entries.len(), 11);
+        assert!(entries
+            .iter()
+            .enumerate()
+            .all(|(i, entry)| entry.sequence == i as u64));
+        assert!(entries
+            .iter()
+            .filter(|entry| entry.event.is_reversal())
+            .all(|entry| entry.reverses.is_some_and(|of| of < entry.sequence)));
+    }
+
+    #[test]
+    fn a_note_that_arrives_spent_is_received_then_spent() {
+        let mut balances = BalanceManager::new();
+        balances
+            .add_note(fake_note().amount(70).spent().build())
+            .unwrap();
+        let events: Vec<LedgerEvent> = balances
+            .ledger()
+            .entries()
+            .iter()
+            .map(|entry| entry.event)
+            .collect();
+        assert_eq!(
+            events,
+            vec![LedgerEvent::NoteReceived, LedgerEvent::NoteSpent]
+        );
+        step(&balances, LedgerEvent::NoteSpent, 0);
+    }
+
+    #[test]
+    fn ranges_are_clamped_to_the_entries() {
+        let mut balances = BalanceManager::new();
+        for _ in 0..5 {
+            balances.add_note(fake_note().build()).unwrap();
+        }
+        let ledger = balances.ledger();
+        let sequences =
+            |entries: &[LedgerEntry]| entries.iter().map(|e| e.sequence).collect::<Vec<_>>();
+        assert_eq!(sequences(ledger.range(..)), vec![0, 1, 2, 3, 4]);
+        assert_eq!(sequences(ledger.range(1..3)), vec![1, 2]);
+        assert_eq!(sequences(ledger.range(..=1)), vec![0, 1]);
+        assert_eq!(sequences(ledger.range(3..99)), vec![3, 4]);
+        assert!(ledger.range(7..).is_empty());
+        // A reversed range, as a caller computing its bounds might pass
+        let (start, end) = (4, 2);
+        assert!(ledger.range(start..end).is_empty());
+    }
+
+    #[test]
+    fn a_mismatch_with_the_notes_is_reported() {
+        let mut balances = BalanceManager::new();
+        balances.add_note(fake_note().amount(40).build()).unwrap();
+        let check = balances.ledger().check(45);
+        assert!(!check.is_balanced());
+        assert_eq!(
+            check.to_string(),
+            "Ledger of 1 entries sums to 40, but the notes hold 45"
+        );
+        assert_eq!(
+            balances.ledger().check(40).to_string(),
+            "Ledger of 1 entries matches the notes (40)"
+        );
+    }
+
+    #[test]
+    fn the_wallet_refuses_to_revert_above_its_tip() {
+        let mut wallet = Wallet::new();
+        wallet.balances_mut().set_tip_height(5);
+        assert!(wallet.revert_blocks_above(6).is_err());
+        assert_eq!(wallet.revert_blocks_above(5).unwrap(), 0);
+        assert!(wallet.check_ledger().is_balanced());
+        assert!(wallet.ledger(..).is_empty());
+    }
+
+    #[test]
+    fn the_ledger_exports_as_csv() {
+        let mut balances = BalanceManager::new();
+        let note = fake_note().amount(25).outpoint("paid", 0).build();
+        let note_id = note.id;
+        balances.add_note(note).unwrap();
+        balances.spend_note(note_id).unwrap();
+
+        let mut csv = Vec::new();
+        let summary = export_ledger_to_writer(
+            &mut csv,
+            balances.ledger().entries(),
+            ExportFormat::Csv,
+            |_| true,
+        )
+        .unwrap();
+        assert_eq!(summary.rows, 2);
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[0].starts_with("sequence,recorded_at,event,account,counter_account,amount"));
+        assert!(lines[1].contains(",note_received,available,external,25,paid,"));
+        assert!(lines[2].contains(",note_spent,available,external,-25,paid,"));
+        assert!(lines[2].ends_with(",0"));
+    }
+}
@@ -0,0 +1,196 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// Why a lockfile could not be taken
+#[derive(Debug)]
+pub(crate) enum LockfileError {
+    /// Another live process holds the lock
+    Held {
+        pid: u32,
+        path: PathBuf,
+    },
+    Io(String),
+}
+
+/// PID lockfile keeping a second process off the same data dir. The file holds
+/// the owner's PID, when it was taken and the executable; a lockfile whose
+/// process is gone is treated as stale and replaced.
+#[derive(Debug)]
+pub(crate) struct Lockfile {
+    lockfile_path: PathBuf,
+    /// What the lock guards, for log messages
+    kind: &'static str,
+    _lock_file: Option<File>,
+}
+
+impl Lockfile {
+    pub(crate) fn new(data_dir: &Path, file_name: &str, kind: &'static str) -> Self {
+        Self {
+            lockfile_path: data_dir.join(file_name),
+            kind,
+            _lock_file: None,
+        }
+    }
+
+    pub(crate) fn is_held(&self) -> bool {
+        self._lock_file.is_some()
+    }
+
+    pub(crate) fn acquire(&mut self) -> Result<(), LockfileError> {
+        if self.is_held() {
+            return Ok(());
+        }
+
+        // Check if lockfile already exists
+        if self.lockfile_path.exists() {
+            // Try to read the existing lockfile to see what process owns it
+            match std::fs::read_to_string(&self.lockfile_path) {
+                Ok(content) => {
+                    let lines: Vec<&str> = content.lines().collect();
+                    if let Some(pid_line) = lines.first() {
+                        if let Ok(existing_pid) = pid_line.parse::<u32>() {
+                            // Check if the process is still running (Unix-style)
+                            #[cfg(unix)]
+                            {
+                                use std::process::Command;
+                                let is_running = Command::new("kill")
+                                    .args(["-0", &existing_pid.to_string()])
+                                    .output()
+                                    .map(|output| output.status.success())
+                                    .unwrap_or(false);
+
+                                if is_running {
+                                    return Err(LockfileError::Held {
+                                        pid: existing_pid,
+                                        path: self.lockfile_path.clone(),
+                                    });
+                                } else {
+                                    // Stale lockfile, remove it
+                                    let _ = std::fs::remove_file(&self.lockfile_path);
+                                    debug!("🧹 Removed stale lockfile from PID {}", existing_pid);
+                                }
+                            }
+
+                            // On non-Unix systems we can't tell, so treat it as held
+                            #[cfg(not(unix))]
+                            {
+                                return Err(LockfileError::Held {
+                                    pid: existing_pid,
+                                    path: self.lockfile_path.clone(),
+                                });
+                            }
+                        }
+                    }
+                }
+                Err(_) => {
+                    // If we can't read the lockfile, assume it's corrupted and remove it
+                    let _ = std::fs::remove_file(&self.lockfile_path);
+                    debug!("🧹 Removed corrupted lockfile");
+                }
+            }
+        }
+
+        // Create the lockfile with current process info
+        let current_pid = std::process::id();
+        let lockfile_content = format!(
+            "{}\n{}\n{}\n",
+            current_pid,
+            chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
+            std::env::current_exe()
+                .map(|p| p.display().to_string())
+                .unwrap_or_else(|_| "unknown".to_string())
+        );
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.lockfile_path)
+            .map_err(|e| LockfileError::Io(format!("Failed to create lockfile: {}", e)))?;
+
+        file.write_all(lockfile_content.as_bytes())
+            .map_err(|e| LockfileError::Io(format!("Failed to write lockfile: {}", e)))?;
+
+        file.sync_all()
+            .map_err(|e| LockfileError::Io(format!("Failed to sync lockfile: {}", e)))?;
+
+        self._lock_file = Some(file);
+        debug!(
+            "🔒 Acquired {} lockfile at: {}",
+            self.kind,
+            self.lockfile_path.display()
+        );
+
+        Ok(())
+    }
+
+    pub(crate) fn release(&mut self) {
+        // Only remove the file if we wrote it; otherwise it belongs to another process
+        if self._lock_file.take().is_none() {
+            return;
+        }
+        if self.lockfile_path.exists() {
+            if let Err(e) = std::fs::remove_file(&self.lockfile_path) {
+                warn!("Failed to remove lockfile: {}", e);
+            } else {
+                debug!("Released {} lockfile", self.kind);
+            }
+        }
+    }
+}
+
+impl Drop for Lockfile {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn a_live_owner_keeps_the_lock_until_it_releases() {
+        let dir = TempDir::new("lockfile-held");
+        let mut first = Lockfile::new(dir.path(), "test.lock", "test");
+        first.acquire().unwrap();
+        let content = std::fs::read_to_string(dir.join("test.lock")).unwrap();
+        assert_eq!(
+            content.lines().next(),
+            Some(std::process::id().to_string().as_str())
+        );
+
+        let mut second = Lockfile::new(dir.path(), "test.lock", "test");
+        match second.acquire() {
+            Err(LockfileError::Held { pid, .. }) => assert_eq!(pid, std::process::id()),
+            other => panic!("expected the lock to be held, got {:?}", other),
+        }
+        // Releasing a lock it never took leaves the owner's file alone
+        second.release();
+        assert!(dir.join("test.lock").exists());
+
+        first.release();
+        assert!(!dir.join("test.lock").exists());
+        second.acquire().unwrap();
+        assert!(second.is_held());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_stale_or_unreadable_lockfile_is_replaced() {
+        let dir = TempDir::new("lockfile-stale");
+        // PIDs never get this high on Linux, so nothing is running as it
+        std::fs::write(dir.join("test.lock"), "4294967294\n").unwrap();
+        let mut lock = Lockfile::new(dir.path(), "test.lock", "test");
+        lock.acquire().unwrap();
+        lock.release();
+
+        std::fs::write(dir.join("test.lock"), [0xff, 0xfe, 0x00]).unwrap();
+        lock.acquire().unwrap();
+        let content = std::fs::read_to_string(dir.join("test.lock")).unwrap();
+        assert!(content.starts_with(&std::process::id().to_string()));
+    }
+}
@@ -0,0 +1,865 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+
+use crate::wallet::network::{LogEntry, LogLevel, LogRepeat, LogSource};
+
+/// Fixed per-entry cost added to the message length when estimating memory use
+const ENTRY_OVERHEAD_BYTES: usize = std::mem::size_of::<LogEntry>();
+
+/// Limits for the in-memory log ring buffer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogBufferConfig {
+    /// Maximum number of entries kept in memory
+    pub max_entries: usize,
+    /// Approximate upper bound on memory used by kept entries
+    pub max_bytes: usize,
+    #[serde(default)]
+    pub rate_limit: LogRateLimit,
+}
+
+impl Default for LogBufferConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 1000,
+            max_bytes: 2 * 1024 * 1024,
+            rate_limit: LogRateLimit::default(),
+        }
+    }
+}
+
+/// Limits that keep a storm of log entries from flooding the buffer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogRateLimit {
+    /// Consecutive entries with the same level, source and message, numbers
+    /// aside, are collapsed into one while each comes within this long of the
+    /// previous; 0 turns collapsing off
+    pub collapse_window_ms: u64,
+    /// Entries a source may log at once before it is limited; 0 turns the
+    /// limit off. Warnings and errors are never limited.
+    pub burst: u32,
+    /// Entries per second a source may log once its burst is spent
+    pub per_second: u32,
+}
+
+impl Default for LogRateLimit {
+    fn default() -> Self {
+        Self {
+            collapse_window_ms: 2000,
+            burst: 100,
+            per_second: 20,
+        }
+    }
+}
+
+/// Token bucket of one log source
+#[derive(Debug, Clone)]
+struct SourceBucket {
+    tokens: f64,
+    updated: DateTime<Utc>,
+    /// Entries dropped since the source last had a full bucket
+    suppressed: u64,
+}
+
+impl SourceBucket {
+    fn refill(&mut self, now: DateTime<Utc>, limit: &LogRateLimit) {
+        let elapsed = (now - self.updated).num_milliseconds().max(0) as f64 / 1000.0;
+        self.tokens =
+            (self.tokens + elapsed * limit.per_second.max(1) as f64).min(limit.burst as f64);
+        self.updated = self.updated.max(now);
+    }
+}
+
+/// Minimum level a log entry needs to be kept, per source
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogLevels {
+    /// Level for sources without an override
+    pub default: LogLevel,
+    #[serde(default)]
+    pub sources: HashMap<LogSource, LogLevel>,
+}
+
+impl Default for LogLevels {
+    fn default() -> Self {
+        Self {
+            default: LogLevel::Trace,
+            sources: HashMap::new(),
+        }
+    }
+}
+
+impl LogLevels {
+    /// Effective minimum level for `source`
+    pub fn threshold(&self, source: &LogSource) -> LogLevel {
+        self.sources.get(source).copied().unwrap_or(self.default)
+    }
+
+    pub fn allows(&self, entry: &LogEntry) -> bool {
+        entry.level >= self.threshold(&entry.source)
+    }
+}
+
+/// Warn and Error entries logged since the counts were last reset, repeats
+/// included. Evicting an entry doesn't take it off the count, and the rate
+/// limit never drops these levels, so none go uncounted; only entries below
+/// a source's log level are left out, as they're never shown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogLevelCounts {
+    pub warn: u64,
+    pub error: u64,
+}
+
+impl LogLevelCounts {
+    fn record(&mut self, level: &LogLevel) {
+        match level {
+            LogLevel::Warn => self.warn += 1,
+            LogLevel::Error => self.error += 1,
+            _ => {}
+        }
+    }
+}
+
+/// Ring buffer of log entries bounded by both count and approximate size.
+/// The oldest entries are evicted first.
+#[derive(Debug, Clone, Default)]
+pub struct LogBuffer {
+    entries: VecDeque<LogEntry>,
+    config: LogBufferConfig,
+    levels: LogLevels,
+    total_bytes: usize,
+    dropped: u64,
+    buckets: HashMap<LogSource, SourceBucket>,
+    rate_limited: u64,
+    level_counts: LogLevelCounts,
+}
+
+impl LogBuffer {
+    pub fn new(config: LogBufferConfig) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            config,
+            levels: LogLevels::default(),
+            total_bytes: 0,
+            dropped: 0,
+            buckets: HashMap::new(),
+            rate_limited: 0,
+            level_counts: LogLevelCounts::default(),
+        }
+    }
+
+    /// Keep `entry` unless it is below the level configured for its source.
+    /// A repeat of the last entry is counted on that entry instead, and an
+    /// entry over its source's rate limit is dropped. The message is run
+    /// through `sanitize_log_message` first, so everything that reads the
+    /// buffer gets clean text.
+    pub fn push(&mut self, mut entry: LogEntry) {
+        if !self.levels.allows(&entry) {
+            return;
+        }
+        if let Cow::Owned(message) = sanitize_log_message(&entry.message) {
+            entry.message = message;
+        }
+        self.flush_suppressed(entry.timestamp);
+        if self.collapse(&entry) || !self.take_token(&entry) {
+            return;
+        }
+        self.keep(entry);
+    }
+
+    /// For each source whose storm has passed, its bucket full again, keep a
+    /// line saying how many of its entries the rate limit dropped
+    pub fn flush_suppressed(&mut self, now: DateTime<Utc>) {
+        let limit = self.config.rate_limit.clone();
+        let mut summaries = Vec::new();
+        for (source, bucket) in self.buckets.iter_mut() {
+            if bucket.suppressed == 0 {
+                continue;
+            }
+            bucket.refill(now, &limit);
+            if bucket.tokens >= limit.burst as f64 {
+                summaries.push(LogEntry {
+                    timestamp: now,
+                    level: LogLevel::Warn,
+                    source: *source,
+                    message: format!(
+                        "Rate limit dropped {} log entries (more than {} per second)",
+                        bucket.suppressed,
+                        limit.per_second.max(1)
+                    ),
+                    operation_id: None,
+                    repeat: None,
+                });
+                bucket.suppressed = 0;
+            }
+        }
+        for summary in summaries {
+            self.keep(summary);
+        }
+    }
+
+    /// Count `entry` on the last entry if it repeats it within the window
+    fn collapse(&mut self, entry: &LogEntry) -> bool {
+        let window_ms = self.config.rate_limit.collapse_window_ms;
+        let Some(last) = self.entries.back_mut() else {
+            return false;
+        };
+        let last_at = last.repeat.as_ref().map_or(last.timestamp, |r| r.last_at);
+        let repeats = window_ms > 0
+            && entry.timestamp - last_at <= Duration::milliseconds(window_ms as i64)
+            && last.level == entry.level
+            && last.source == entry.source
+            && last.operation_id == entry.operation_id
+            && message_template(&last.message) == message_template(&entry.message);
+        if !repeats {
+            return false;
+        }
+        let repeat = last.repeat.get_or_insert(LogRepeat {
+            count: 1,
+            last_at: last.timestamp,
+        });
+        repeat.count += 1;
+        repeat.last_at = entry.timestamp;
+        self.level_counts.record(&entry.level);
+        true
+    }
+
+    /// Spend a token of `entry`'s source, or count the entry as dropped.
+    /// Warnings and errors are never dropped: a storm is when they matter
+    /// most, and collapsing still keeps a loop of the same one to a line.
+    fn take_token(&mut self, entry: &LogEntry) -> bool {
+        let limit = &self.config.rate_limit;
+        if limit.burst == 0 || entry.level >= LogLevel::Warn {
+            return true;
+        }
+        let bucket = self
+            .buckets
+            .entry(entry.source)
+            .or_insert_with(|| SourceBucket {
+                tokens: limit.burst as f64,
+                updated: entry.timestamp,
+                suppressed: 0,
+            });
+        bucket.refill(entry.timestamp, limit);
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            bucket.suppressed += 1;
+            self.rate_limited += 1;
+            false
+        }
+    }
+
+    fn keep(&mut self, entry: LogEntry) {
+        self.level_counts.record(&entry.level);
+        self.total_bytes += entry_size(&entry);
+        self.entries.push_back(entry);
+        self.enforce_limits();
+    }
+
+    /// Apply new limits immediately, shrinking the buffer if necessary
+    pub fn set_config(&mut self, config: LogBufferConfig) {
+        self.config = config;
+        self.enforce_limits();
+    }
+
+    pub fn config(&self) -> &LogBufferConfig {
+        &self.config
+    }
+
+    /// Apply new log levels to entries pushed from now on
+    pub fn set_levels(&mut self, levels: LogLevels) {
+        self.levels = levels;
+    }
+
+    pub fn levels(&self) -> &LogLevels {
+        &self.levels
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &LogEntry> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Approximate memory held by the kept entries
+    pub fn total_bytes(&self) -> usize {
+        self.total_bytes
+    }
+
+    /// Number of entries evicted since the buffer was created
+    pub fn dropped_count(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Number of entries the rate limit dropped since the buffer was created
+    pub fn rate_limited_count(&self) -> u64 {
+        self.rate_limited
+    }
+
+    /// Warn and Error entries logged since the last `reset_level_counts`
+    pub fn level_counts(&self) -> LogLevelCounts {
+        self.level_counts
+    }
+
+    /// Start counting afresh, e.g. when the node is started again
+    pub fn reset_level_counts(&mut self) {
+        self.level_counts = LogLevelCounts::default();
+    }
+
+    fn enforce_limits(&mut self) {
+        while self.entries.len() > self.config.max_entries
+            || (self.total_bytes > self.config.max_bytes && !self.entries.is_empty())
+        {
+            let Some(evicted) = self.entries.pop_front() else {
+                break;
+            };
+            self.total_bytes = self.total_bytes.saturating_sub(entry_size(&evicted));
+            self.dropped += 1;
+        }
+    }
+}
+
+/// `message` with every run of digits replaced by `#`, so lines that differ
+/// only in counters, ids or timings compare equal
+fn message_template(message: &str) -> String {
+    let mut template = String::with_capacity(message.len());
+    let mut in_number = false;
+    for c in message.chars() {
+        if c.is_ascii_digit() {
+            if !in_number {
+                template.push('#');
+            }
+            in_number = true;
+        } else {
+            template.push(c);
+            in_number = false;
+        }
+    }
+    template
+}
+
+fn entry_size(entry: &LogEntry) -> usize {
+    ENTRY_OVERHEAD_BYTES + entry.message.len()
+}
+
+/// `message` made safe to show and export: ANSI escape sequences and
+/// bidirectional overrides are removed, `\r\n` becomes `\n`, tabs become
+/// spaces and any other control character is replaced with its visible
+/// Control Pictures symbol, e.g. `␀` for NUL. Newlines are kept for the
+/// console to wrap on. Borrowed when there was nothing to change.
+pub fn sanitize_log_message(message: &str) -> Cow<'_, str> {
+    if !message.chars().any(needs_sanitizing) {
+        return Cow::Borrowed(message);
+    }
+
+    let mut clean = String::with_capacity(message.len());
+    let mut chars = message.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => clean.push('\n'),
+            '\t' => clean.push(' '),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            // ESC [ and the single-character CSI introduce a control sequence:
+            // parameter and intermediate bytes, then one final byte
+            '\u{1b}' if chars.peek() == Some(&'[') => {
+                chars.next();
+                skip_csi(&mut chars);
+            }
+            '\u{9b}' => skip_csi(&mut chars),
+            // ESC ] starts an operating system command, such as a hyperlink
+            // or window title, ended by BEL or ESC \
+            '\u{1b}' if chars.peek() == Some(&']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\u{7}' || (c == '\u{1b}' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            // Any other escape takes the character after it with it
+            '\u{1b}' => {
+                chars.next_if(|next| ('\u{40}'..='\u{5f}').contains(next));
+            }
+            c if is_bidi_control(c) => {}
+            c if (c as u32) < 0x20 => {
+                clean.push(char::from_u32(0x2400 + c as u32).unwrap_or('\u{fffd}'))
+            }
+            '\u{7f}' => clean.push('\u{2421}'),
+            c if c.is_control() => clean.push('\u{fffd}'),
+            c => clean.push(c),
+        }
+    }
+    Cow::Owned(clean)
+}
+
+fn needs_sanitizing(c: char) -> bool {
+    (c.is_control() && c != '\n') || is_bidi_control(c)
+}
+
+/// Characters that reorder the text around them
+fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+}
+
+/// Skip the rest of a control sequence after its introducer
+fn skip_csi(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+    while chars
+        .next_if(|c| ('\u{20}'..='\u{3f}').contains(c))
+        .is_some()
+    {}
+    chars.next_if(|c| ('\u{40}'..='\u{7e}').contains(c));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fixture_time;
+
+    /// Entry logged `ms` milliseconds after the fixture epoch
+    fn entry(level: LogLevel, source: LogSource, message: &str, ms: i64) -> LogEntry {
+        LogEntry {
+            timestamp: fixture_time(0) + Duration::milliseconds(ms),
+            level,
+            source,
+            message: message.to_string(),
+            operation_id: None,
+            repeat: None,
+        }
+    }
+
+    fn info(message: &str, ms: i64) -> LogEntry {
+        entry(LogLevel::Info, LogSource::Node, message, ms)
+    }
+
+    /// Buffer with collapsing and rate limiting off, so only the size limits apply
+    fn unlimited(max_entries: usize, max_bytes: usize) -> LogBuffer {
+        LogBuffer::new(LogBufferConfig {
+            max_entries,
+            max_bytes,
+            rate_limit: LogRateLimit {
+                collapse_window_ms: 0,
+                burst: 0,
+                per_second: 0,
+            },
+        })
+    }
+
+    fn messages(buffer: &LogBuffer) -> Vec<&str> {
+        buffer.iter().map(|entry| entry.message.as_str()).collect()
+    }
+
+    #[test]
+    fn entry_limit_evicts_the_oldest_first() {
+        let mut buffer = unlimited(3, usize::MAX);
+        for i in 0..5 {
+            buffer.push(info(&format!("entry {}", i), i));
+        }
+        assert_eq!(messages(&buffer), vec!["entry 2", "entry 3", "entry 4"]);
+        assert_eq!(buffer.dropped_count(), 2);
+        assert_eq!(
+            buffer.total_bytes(),
+            3 * (ENTRY_OVERHEAD_BYTES + "entry 0".len())
+        );
+    }
+
+    #[test]
+    fn byte_limit_evicts_until_the_entries_fit() {
+        let big = "x".repeat(4_000);
+        let mut buffer = unlimited(1_000, 2 * ENTRY_OVERHEAD_BYTES + 8_004);
+        buffer.push(info("small", 0));
+        buffer.push(info(&big, 1));
+        assert_eq!(buffer.len(), 2);
+
+        buffer.push(info(&big, 2));
+        assert_eq!(buffer.len(), 2);
+        assert_eq!(buffer.dropped_count(), 1);
+        assert!(buffer.total_bytes() <= buffer.config().max_bytes);
+
+        // An entry larger than the whole budget is evicted straight away
+        buffer.push(info(&"y".repeat(20_000), 3));
+        assert!(buffer.is_empty());
+        assert_eq!((buffer.total_bytes(), buffer.dropped_count()), (0, 4));
+    }
+
+    fn p2p_debug_info_elsewhere() -> LogLevels {
+        LogLevels {
+            default: LogLevel::Info,
+            sources: HashMap::from([(LogSource::P2P, LogLevel::Debug)]),
+        }
+    }
+
+    #[test]
+    fn a_source_override_takes_precedence_over_the_default() {
+        let levels = p2p_debug_info_elsewhere();
+        assert_eq!(levels.threshold(&LogSource::P2P), LogLevel::Debug);
+        assert_eq!(levels.threshold(&LogSource::Node), LogLevel::Info);
+
+        // An override can be stricter than the default as well as looser
+        let quiet_mining = LogLevels {
+            default: LogLevel::Debug,
+            sources: HashMap::from([(LogSource::Mining, LogLevel::Error)]),
+        };
+        assert!(!quiet_mining.allows(&entry(LogLevel::Warn, LogSource::Mining, "w", 0)));
+        assert!(quiet_mining.allows(&entry(LogLevel::Debug, LogSource::Node, "d", 0)));
+    }
+
+    #[test]
+    fn entries_below_their_sources_level_are_never_stored() {
+        let mut buffer = unlimited(100, usize::MAX);
+        buffer.set_levels(p2p_debug_info_elsewhere());
+        buffer.push(entry(LogLevel::Debug, LogSource::P2P, "p2p debug", 0));
+        buffer.push(entry(LogLevel::Trace, LogSource::P2P, "p2p trace", 1));
+        buffer.push(entry(LogLevel::Debug, LogSource::Node, "node debug", 2));
+        buffer.push(entry(LogLevel::Info, LogSource::Node, "node info", 3));
+        assert_eq!(messages(&buffer), vec!["p2p debug", "node info"]);
+        assert_eq!(buffer.dropped_count(), 0);
+    }
+
+    #[test]
+    fn new_limits_shrink_the_buffer_immediately() {
+        let mut buffer = unlimited(10, usize::MAX);
+        for i in 0..10 {
+            buffer.push(info(&format!("entry {}", i), i));
+        }
+        buffer.set_config(LogBufferConfig {
+            max_entries: 4,
+            ..buffer.config().clone()
+        });
+        assert_eq!(buffer.len(), 4);
+        assert_eq!(buffer.iter().next().unwrap().message, "entry 6");
+        assert_eq!(buffer.dropped_count(), 6);
+
+        buffer.set_config(LogBufferConfig {
+            max_bytes: ENTRY_OVERHEAD_BYTES + 7,
+            ..buffer.config().clone()
+        });
+        assert_eq!(messages(&buffer), vec!["entry 9"]);
+    }
+
+    /// `n` spelled in letters, so distinct numbers give distinct templates
+    fn letters(mut n: usize) -> String {
+        let mut word = String::new();
+        loop {
+            word.push((b'a' + (n % 26) as u8) as char);
+            n /= 26;
+            if n == 0 {
+                return word;
+            }
+        }
+    }
+
+    /// Default limits: a two second collapse window, a burst of 100, then
+    /// 20 entries a second
+    fn limited() -> LogBuffer {
+        LogBuffer::new(LogBufferConfig::default())
+    }
+
+    #[test]
+    fn a_flood_of_identical_lines_collapses_into_one() {
+        let mut buffer = limited();
+        for i in 0..10_000 {
+            buffer.push(info(
+                &format!("Retrying peer 10.0.0.{} in {}ms", i % 7, i),
+                i,
+            ));
+        }
+        assert_eq!(buffer.len(), 1);
+        let repeat = buffer.iter().next().unwrap().repeat.clone().unwrap();
+        assert_eq!(repeat.count, 10_000);
+        assert_eq!(
+            repeat.last_at,
+            fixture_time(0) + Duration::milliseconds(9_999)
+        );
+        assert_eq!(buffer.rate_limited_count(), 0);
+    }
+
+    #[test]
+    fn a_repeat_after_the_window_starts_a_new_entry() {
+        let mut buffer = limited();
+        buffer.push(info("Peer timed out", 0));
+        buffer.push(info("Peer timed out", 1_500));
+        // Measured from the last repeat, not the first line
+        buffer.push(info("Peer timed out", 3_400));
+        buffer.push(info("Peer timed out", 5_401));
+        assert_eq!(messages(&buffer), ["Peer timed out", "Peer timed out"]);
+        assert_eq!(
+            buffer.iter().next().unwrap().repeat.as_ref().unwrap().count,
+            3
+        );
+        assert_eq!(buffer.iter().nth(1).unwrap().repeat, None);
+    }
+
+    #[test]
+    fn only_an_identical_neighbour_collapses() {
+        let mut buffer = limited();
+        buffer.push(info("Peer 1 connected", 0));
+        buffer.push(entry(
+            LogLevel::Warn,
+            LogSource::Node,
+            "Peer 2 connected",
+            1,
+        ));
+        buffer.push(entry(LogLevel::Warn, LogSource::P2P, "Peer 3 connected", 2));
+        buffer.push(entry(
+            LogLevel::Warn,
+            LogSource::P2P,
+            "Peer 4 disconnected",
+            3,
+        ));
+        let mut other_run = entry(LogLevel::Warn, LogSource::P2P, "Peer 5 disconnected", 4);
+        other_run.operation_id = Some(uuid::Uuid::new_v4());
+        buffer.push(other_run);
+        assert_eq!(buffer.len(), 5);
+        assert!(buffer.iter().all(|entry| entry.repeat.is_none()));
+    }
+
+    #[test]
+    fn a_flood_of_distinct_lines_stays_within_the_burst() {
+        let mut buffer = limited();
+        for i in 0..10_000 {
+            buffer.push(entry(
+                LogLevel::Info,
+                LogSource::P2P,
+                &format!("gossip {}", letters(i)),
+                0,
+            ));
+        }
+        assert_eq!(buffer.len(), 100);
+        assert_eq!(buffer.rate_limited_count(), 9_900);
+
+        // Another source has a bucket of its own
+        buffer.push(entry(LogLevel::Info, LogSource::Mining, "found block", 0));
+        assert_eq!(buffer.len(), 101);
+    }
+
+    #[test]
+    fn the_bucket_refills_at_the_configured_rate() {
+        let mut buffer = limited();
+        for i in 0..150 {
+            buffer.push(info(&letters(i), 0));
+        }
+        assert_eq!(buffer.len(), 100);
+        // Half a second later ten more tokens have come in
+        for i in 0..20 {
+            buffer.push(info(&format!("b{}", letters(i)), 500));
+        }
+        assert_eq!(buffer.len(), 110);
+        assert_eq!(buffer.rate_limited_count(), 60);
+    }
+
+    #[test]
+    fn a_summary_line_reports_the_drops_once_the_storm_passes() {
+        let mut buffer = limited();
+        for i in 0..300 {
+            buffer.push(info(&letters(i), 0));
+        }
+        // Not yet: the bucket is still far from full
+        buffer.flush_suppressed(fixture_time(1));
+        assert_eq!(buffer.len(), 100);
+
+        // A burst's worth of tokens takes five seconds at 20 a second
+        buffer.flush_suppressed(fixture_time(5));
+        assert_eq!(buffer.len(), 101);
+        let summary = buffer.iter().last().unwrap();
+        assert_eq!(summary.level, LogLevel::Warn);
+        assert_eq!(summary.source, LogSource::Node);
+        assert_eq!(
+            summary.message,
+            "Rate limit dropped 200 log entries (more than 20 per second)"
+        );
+
+        // Reported once
+        buffer.flush_suppressed(fixture_time(60));
+        assert_eq!(buffer.len(), 101);
+    }
+
+    #[test]
+    fn warnings_and_errors_are_never_rate_limited() {
+        let mut buffer = limited();
+        for i in 0..500 {
+            buffer.push(info(&letters(i), 0));
+        }
+        for i in 0..50 {
+            buffer.push(entry(
+                LogLevel::Warn,
+                LogSource::Node,
+                &format!("w{}", letters(i)),
+                0,
+            ));
+            buffer.push(entry(
+                LogLevel::Error,
+                LogSource::Node,
+                &format!("e{}", letters(i)),
+                0,
+            ));
+        }
+        assert_eq!(buffer.len(), 200);
+        assert_eq!(buffer.rate_limited_count(), 400);
+        let errors = buffer.iter().filter(|e| e.level == LogLevel::Error).count();
+        assert_eq!(errors, 50);
+    }
+
+    #[test]
+    fn zero_turns_each_defence_off() {
+        let mut buffer = unlimited(usize::MAX, usize::MAX);
+        for i in 0..1_000 {
+            buffer.push(info("same line", i));
+        }
+        assert_eq!(buffer.len(), 1_000);
+        assert_eq!(buffer.rate_limited_count(), 0);
+    }
+
+    #[test]
+    fn message_templates_mask_numbers() {
+        assert_eq!(message_template("block 12 in 340ms"), "block # in #ms");
+        assert_eq!(message_template("no digits"), "no digits");
+        assert_eq!(message_template("v1.2.3"), "v#.#.#");
+    }
+
+    #[test]
+    fn collapse_metadata_survives_an_export() {
+        let mut buffer = limited();
+        buffer.push(info("Sync stalled", 0));
+        buffer.push(info("Sync stalled", 1_000));
+        let json = serde_json::to_string(&buffer.iter().collect::<Vec<_>>()).unwrap();
+        let back: Vec<LogEntry> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            back[0].repeat,
+            Some(LogRepeat {
+                count: 2,
+                last_at: fixture_time(1),
+            })
+        );
+
+        // Entries written before collapsing existed have no repeat
+        let old =
+            r#"{"timestamp":"2024-01-01T00:00:00Z","level":"Info","source":"Node","message":"up"}"#;
+        assert_eq!(serde_json::from_str::<LogEntry>(old).unwrap().repeat, None);
+    }
+
+    fn counts(warn: u64, error: u64) -> LogLevelCounts {
+        LogLevelCounts { warn, error }
+    }
+
+    #[test]
+    fn level_counts_include_collapsed_repeats() {
+        let mut buffer = LogBuffer::new(LogBufferConfig::default());
+        for ms in 0..5 {
+            buffer.push(entry(LogLevel::Warn, LogSource::P2P, "peer 7 is slow", ms));
+        }
+        buffer.push(entry(LogLevel::Error, LogSource::Node, "bad block", 10));
+        buffer.push(info("synced", 20));
+        assert_eq!(buffer.len(), 3);
+        assert_eq!(buffer.level_counts(), counts(5, 1));
+    }
+
+    #[test]
+    fn level_counts_survive_eviction() {
+        let mut buffer = unlimited(2, usize::MAX);
+        buffer.push(entry(LogLevel::Error, LogSource::Node, "first", 0));
+        buffer.push(entry(LogLevel::Warn, LogSource::Node, "second", 1));
+        for i in 0..5 {
+            buffer.push(info(&format!("filler {}", i), 2 + i));
+        }
+        assert!(buffer.iter().all(|entry| entry.level == LogLevel::Info));
+        assert_eq!(buffer.level_counts(), counts(1, 1));
+    }
+
+    #[test]
+    fn level_counts_include_warnings_and_errors_in_a_storm() {
+        let mut buffer = LogBuffer::new(LogBufferConfig {
+            max_entries: 100,
+            max_bytes: usize::MAX,
+            rate_limit: LogRateLimit {
+                collapse_window_ms: 0,
+                burst: 2,
+                per_second: 1,
+            },
+        });
+        for i in 0..50 {
+            let level = if i % 2 == 0 {
+                LogLevel::Warn
+            } else {
+                LogLevel::Error
+            };
+            buffer.push(entry(level, LogSource::P2P, &letters(i), 0));
+            buffer.push(entry(LogLevel::Info, LogSource::P2P, &letters(100 + i), 0));
+        }
+        assert_eq!(buffer.rate_limited_count(), 48);
+        assert_eq!(buffer.level_counts(), counts(25, 25));
+    }
+
+    #[test]
+    fn level_counts_leave_out_entries_below_the_log_level() {
+        let mut buffer = LogBuffer::new(LogBufferConfig::default());
+        let mut levels = LogLevels::default();
+        levels.sources.insert(LogSource::P2P, LogLevel::Error);
+        buffer.set_levels(levels);
+        buffer.push(entry(LogLevel::Warn, LogSource::P2P, "hidden", 0));
+        buffer.push(entry(LogLevel::Warn, LogSource::Node, "shown", 1));
+        buffer.push(entry(LogLevel::Error, LogSource::P2P, "shown too", 2));
+        assert_eq!(buffer.level_counts(), counts(1, 1));
+    }
+
+    #[test]
+    fn resetting_level_counts_keeps_the_entries() {
+        let mut buffer = unlimited(10, usize::MAX);
+        buffer.push(entry(LogLevel::Error, LogSource::Node, "bad block", 0));
+        buffer.push(entry(LogLevel::Warn, LogSource::Node, "slow", 1));
+        buffer.reset_level_counts();
+        assert_eq!(buffer.level_counts(), LogLevelCounts::default());
+        assert_eq!(buffer.len(), 2);
+        buffer.push(entry(LogLevel::Warn, LogSource::Node, "slow again", 2));
+        assert_eq!(buffer.level_counts(), counts(1, 0));
+    }
+
+    #[test]
+    fn escape_sequences_are_removed_and_control_characters_shown() {
+        assert_eq!(
+            sanitize_log_message("\u{1b}[1;31mERROR\u{1b}[0m peer \u{9b}2Kgone"),
+            "ERROR peer gone"
+        );
+        assert_eq!(
+            sanitize_log_message("see \u{1b}]8;;https://x.io\u{7}docs\u{1b}]8;;\u{1b}\\ now"),
+            "see docs now"
+        );
+        assert_eq!(sanitize_log_message("a\u{0}b\u{7f}c\u{7}"), "a␀b␡c␇");
+        assert_eq!(
+            sanitize_log_message("one\r\ntwo\rthree\tfour"),
+            "one\ntwo␍three four"
+        );
+        assert_eq!(sanitize_log_message("\u{1b}Mup \u{1b}"), "up ");
+        assert_eq!(sanitize_log_message("id \u{202e}cod.exe"), "id cod.exe");
+        assert_eq!(sanitize_log_message("next\u{85}line"), "next\u{fffd}line");
+    }
+
+    #[test]
+    fn clean_messages_are_borrowed() {
+        for message in ["synced to 1,204", "line one\nline two", "ünïcødé ✓"] {
+            assert!(matches!(
+                sanitize_log_message(message),
+                Cow::Borrowed(borrowed) if borrowed == message
+            ));
+        }
+    }
+
+    #[test]
+    fn stored_messages_are_sanitized_but_never_cut() {
+        let mut buffer = unlimited(10, usize::MAX);
+        buffer.push(info("\u{1b}[32mok\u{1b}[0m\u{0}", 0));
+        let token = "x".repeat(1 << 20);
+        buffer.push(info(&format!("\u{1b}[2m{}\u{1b}[0m", token), 1));
+        let stored: Vec<&LogEntry> = buffer.iter().collect();
+        assert_eq!(stored[0].message, "ok␀");
+        assert_eq!(stored[1].message.len(), 1 << 20);
+        assert!(stored[1].message == token);
+    }
+}
@@ -0,0 +1,212 @@
+use chrono::Utc;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Once, Weak};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::filter::filter_fn;
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+
+use crate::wallet::log_buffer::LogBuffer;
+use crate::wallet::network::{LogEntry, LogLevel, LogSource};
+
+/// Environment variable that turns on debug logging regardless of config
+pub const DEBUG_LOGGING_ENV: &str = "NOCKCHAIN_DEBUG_LOGGING";
+
+/// Crate targets whose events are forwarded into node log buffers
+const BRIDGED_TARGETS: &[&str] = &["api", "ui", "desktop", "web", "mobile"];
+
+static DEBUG_LOGGING: AtomicBool = AtomicBool::new(false);
+static SUBSCRIBER_INIT: Once = Once::new();
+static SINKS: Mutex<Vec<Weak<Mutex<LogBuffer>>>> = Mutex::new(Vec::new());
+
+/// Whether `NOCKCHAIN_DEBUG_LOGGING` is set to a truthy value
+pub fn debug_logging_from_env() -> bool {
+    std::env::var(DEBUG_LOGGING_ENV)
+        .map(|value| matches!(value.to_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+        .unwrap_or(false)
+}
+
+/// Install the global tracing subscriber. Safe to call more than once; only the
+/// first call installs anything. Nothing is ever written to stdout: the console
+/// layer writes warnings (or debug output when enabled) to stderr, and the bridge
+/// layer forwards events into every registered node log buffer.
+pub fn init_tracing(debug_logging: bool) {
+    set_debug_logging(debug_logging || debug_logging_from_env());
+
+    SUBSCRIBER_INIT.call_once(|| {
+        let console = tracing_subscriber::fmt::layer()
+            .with_writer(std::io::stderr)
+            .with_filter(filter_fn(|meta| is_enabled(meta, Level::WARN)));
+        let bridge = LogBridge.with_filter(filter_fn(|meta| {
+            is_bridged_target(meta.target()) && is_enabled(meta, Level::INFO)
+        }));
+
+        let _ = tracing_subscriber::registry()
+            .with(console)
+            .with(bridge)
+            .try_init();
+    });
+}
+
+/// Raise or lower the subscriber filter at runtime
+pub fn set_debug_logging(enabled: bool) {
+    if DEBUG_LOGGING.swap(enabled, Ordering::Relaxed) != enabled {
+        tracing::callsite::rebuild_interest_cache();
+    }
+}
+
+pub fn debug_logging_enabled() -> bool {
+    DEBUG_LOGGING.load(Ordering::Relaxed)
+}
+
+/// Register a log buffer to receive bridged tracing events
+pub(crate) fn register_sink(buffer: &Arc<Mutex<LogBuffer>>) {
+    if let Ok(mut sinks) = SINKS.lock() {
+        sinks.retain(|sink| sink.strong_count() > 0);
+        sinks.push(Arc::downgrade(buffer));
+    }
+}
+
+fn is_enabled(meta: &Metadata<'_>, default_level: Level) -> bool {
+    let max_level = if debug_logging_enabled() {
+        Level::DEBUG
+    } else {
+        default_level
+    };
+    *meta.level() <= max_level
+}
+
+fn is_bridged_target(target: &str) -> bool {
+    BRIDGED_TARGETS
+        .iter()
+        .any(|prefix| target == *prefix || target.starts_with(&format!("{}::", prefix)))
+}
+
+fn log_level(level: &Level) -> LogLevel {
+    match *level {
+        Level::TRACE => LogLevel::Trace,
+        Level::DEBUG => LogLevel::Debug,
+        Level::INFO => LogLevel::Info,
+        Level::WARN => LogLevel::Warn,
+        Level::ERROR => LogLevel::Error,
+    }
+}
+
+fn log_source(target: &str) -> LogSource {
+    if target.contains("peers") {
+        LogSource::P2P
+    } else if target.contains("network") || target.contains("data_dir") {
+        LogSource::Node
+    } else if target.contains("keys") || target.contains("transaction") {
+        LogSource::Wallet
+    } else {
+        LogSource::Debug
+    }
+}
+
+/// Tracing layer that turns events into `LogEntry` values for the node console
+struct LogBridge;
+
+impl<S: Subscriber> Layer<S> for LogBridge {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let meta = event.metadata();
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        let entry = LogEntry {
+            timestamp: Utc::now(),
+            level: log_level(meta.level()),
+            source: log_source(meta.target()),
+            message: visitor.message,
+            operation_id: None,
+            repeat: None,
+        };
+
+        let Ok(mut sinks) = SINKS.lock() else {
+            return;
+        };
+        sinks.retain(|sink| {
+            let Some(buffer) = sink.upgrade() else {
+                return false;
+            };
+            // Skip rather than block if the event was emitted while the buffer is held
+            if let Ok(mut buffer) = buffer.try_lock() {
+                buffer.push(entry.clone());
+            }
+            true
+        });
+    }
+}
+
+/// Collects the `message` field followed by any other fields as `key=value`
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let fields = std::mem::take(&mut self.message);
+            let _ = write!(self.message, "{:?}{}", value, fields);
+        } else {
+            let _ = write!(self.message, " {}={:?}", field.name(), value);
+        }
+    }
+
+    /// Byte fields are written as text, with invalid UTF-8 replaced
+    fn record_bytes(&mut self, field: &Field, value: &[u8]) {
+        let _ = write!(
+            self.message,
+            " {}={}",
+            field.name(),
+            String::from_utf8_lossy(value)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_our_crates_are_bridged() {
+        assert!(is_bridged_target("api"));
+        assert!(is_bridged_target("api::wallet::network"));
+        assert!(is_bridged_target("desktop::node"));
+        assert!(!is_bridged_target("apis::other"));
+        assert!(!is_bridged_target("hyper::client"));
+    }
+
+    #[test]
+    fn sources_follow_the_module_path() {
+        assert_eq!(log_source("api::wallet::peers"), LogSource::P2P);
+        assert_eq!(log_source("api::wallet::network"), LogSource::Node);
+        assert_eq!(log_source("api::wallet::keys"), LogSource::Wallet);
+        assert_eq!(log_source("desktop"), LogSource::Debug);
+    }
+
+    #[test]
+    fn levels_map_one_to_one() {
+        let levels = [
+            Level::TRACE,
+            Level::DEBUG,
+            Level::INFO,
+            Level::WARN,
+            Level::ERROR,
+        ];
+        let mapped: Vec<LogLevel> = levels.iter().map(log_level).collect();
+        assert_eq!(
+            mapped,
+            vec![
+                LogLevel::Trace,
+                LogLevel::Debug,
+                LogLevel::Info,
+                LogLevel::Warn,
+                LogLevel::Error
+            ]
+        );
+    }
+}
@@ -0,0 +1,468 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+use crate::wallet::encoding::{Encode, Reader};
+use crate::wallet::keys::OutPoint;
+use crate::wallet::reject::{RejectCode, LOCAL_MEMPOOL};
+use crate::wallet::transaction::SignedTransaction;
+use crate::wallet::{WalletError, WalletResult};
+
+/// Mempool snapshot inside the node data dir
+pub const MEMPOOL_FILE: &str = "mempool.dat";
+
+/// Version byte prefixed to the mempool file
+const MEMPOOL_FORMAT_VERSION: u8 = 1;
+
+/// Changes are written at most this often; `flush` forces a write
+const MEMPOOL_WRITE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Read-only view of unspent outputs, used to re-check reloaded transactions
+pub trait UtxoSet: Send + Sync {
+    fn is_unspent(&self, outpoint: &OutPoint) -> bool;
+}
+
+impl UtxoSet for HashSet<OutPoint> {
+    fn is_unspent(&self, outpoint: &OutPoint) -> bool {
+        self.contains(outpoint)
+    }
+}
+
+/// Transactions waiting to be mined, optionally mirrored to `mempool.dat`
+#[derive(Debug, Default)]
+pub struct Mempool {
+    transactions: BTreeMap<String, SignedTransaction>,
+    /// Height of the chain tip, once known; transactions that can't be mined
+    /// above it are refused and evicted
+    tip_height: Option<u64>,
+    /// Snapshot location, or `None` when persistence is off
+    path: Option<PathBuf>,
+    dirty: bool,
+    last_write: Option<Instant>,
+}
+
+impl Mempool {
+    /// In-memory mempool that is never written to disk
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the snapshot in `data_dir`, or start empty if there is none.
+    /// With `persist` off nothing is read or written.
+    pub fn open(data_dir: &Path, persist: bool) -> WalletResult<Self> {
+        if !persist {
+            return Ok(Self::new());
+        }
+
+        let path = data_dir.join(MEMPOOL_FILE);
+        let transactions = if path.exists() {
+            let bytes = std::fs::read(&path).map_err(|e| {
+                WalletError::Storage(format!("Failed to read {}: {}", path.display(), e))
+            })?;
+            decode_snapshot(&bytes)?
+                .into_iter()
+                .map(|tx| (tx.id.clone(), tx))
+                .collect()
+        } else {
+            BTreeMap::new()
+        };
+
+        Ok(Self {
+            transactions,
+            tip_height: None,
+            path: Some(path),
+            dirty: false,
+            last_write: None,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    pub fn contains(&self, tx_id: &str) -> bool {
+        self.transactions.contains_key(tx_id)
+    }
+
+    pub fn transactions(&self) -> impl Iterator<Item = &SignedTransaction> {
+        self.transactions.values()
+    }
+
+    pub fn tip_height(&self) -> Option<u64> {
+        self.tip_height
+    }
+
+    /// Add a transaction. Returns false if it was already present; fails if it
+    /// spends an output another pending transaction already spends, or if
+    /// the next block could no longer include it.
+    pub fn insert(&mut self, tx: SignedTransaction) -> WalletResult<bool> {
+        if self.contains(&tx.id) {
+            return Ok(false);
+        }
+        if let (Some(tip), Some(expiry)) = (self.tip_height, tx.expiry_height) {
+            if tx.is_expired_at(tip.saturating_add(1)) {
+                return Err(WalletError::TxRejected {
+                    code: RejectCode::Expired,
+                    peer: LOCAL_MEMPOOL.to_string(),
+                    detail: format!(
+                        "Transaction {} expired at height {}; the tip is at {}",
+                        tx.id, expiry, tip
+                    ),
+                });
+            }
+        }
+        if let Some(existing) = self
+            .transactions
+            .values()
+            .find(|other| other.conflicts_with(&tx))
+        {
+            return Err(WalletError::TxRejected {
+                code: RejectCode::Conflict,
+                peer: LOCAL_MEMPOOL.to_string(),
+                detail: format!(
+                    "Transaction {} conflicts with pending transaction {}",
+                    tx.id, existing.id
+                ),
+            });
+        }
+
+        self.transactions.insert(tx.id.clone(), tx);
+        self.mark_dirty();
+        Ok(true)
+    }
+
+    pub fn remove(&mut self, tx_id: &str) -> Option<SignedTransaction> {
+        let removed = self.transactions.remove(tx_id);
+        if removed.is_some() {
+            self.mark_dirty();
+        }
+        removed
+    }
+
+    /// Whether a pending transaction creates `outpoint`, so a child may spend
+    /// it before it is mined
+    pub fn creates(&self, outpoint: &OutPoint) -> bool {
+        self.transactions
+            .get(&outpoint.txid)
+            .is_some_and(|tx| (outpoint.index as usize) < tx.outputs.len())
+    }
+
+    /// Whether a pending transaction may spend `outpoint`: it is unspent in
+    /// `utxos`, or an output of another pending transaction
+    pub fn is_spendable(&self, utxos: &dyn UtxoSet, outpoint: &OutPoint) -> bool {
+        utxos.is_unspent(outpoint) || self.creates(outpoint)
+    }
+
+    /// Pending transactions `tx_id` depends on: those creating the outputs it
+    /// spends, their own pending parents, and so on. Parents come first.
+    pub fn ancestors(&self, tx_id: &str) -> Vec<&SignedTransaction> {
+        let mut ancestors: Vec<&SignedTransaction> = Vec::new();
+        let mut queue: Vec<&str> = vec![tx_id];
+        while let Some(id) = queue.pop() {
+            let Some(tx) = self.transactions.get(id) else {
+                continue;
+            };
+            for input in &tx.inputs {
+                let Some(parent) = self.transactions.get(&input.outpoint().txid) else {
+                    continue;
+                };
+                if ancestors.iter().all(|known| known.id != parent.id) {
+                    ancestors.push(parent);
+                    queue.push(&parent.id);
+                }
+            }
+        }
+        ancestors
+    }
+
+    /// Drop every transaction spending an output that is neither unspent nor
+    /// created by another pending transaction, then every child of one dropped
+    pub fn revalidate(&mut self, utxos: &dyn UtxoSet) -> Vec<SignedTransaction> {
+        let mut dropped = Vec::new();
+        loop {
+            let invalid: Vec<String> = self
+                .transactions
+                .values()
+                .filter(|tx| {
+                    !tx.inputs
+                        .iter()
+                        .all(|input| self.is_spendable(utxos, input.outpoint()))
+                })
+                .map(|tx| tx.id.clone())
+                .collect();
+            if invalid.is_empty() {
+                return dropped;
+            }
+            dropped.extend(invalid.iter().filter_map(|tx_id| self.remove(tx_id)));
+        }
+    }
+
+    /// Move the chain tip to `height` and evict every transaction the next
+    /// block could no longer include, returning them
+    pub fn set_tip_height(&mut self, height: u64) -> Vec<SignedTransaction> {
+        self.tip_height = Some(height);
+        let expired: Vec<String> = self
+            .transactions
+            .values()
+            .filter(|tx| tx.is_expired_at(height.saturating_add(1)))
+            .map(|tx| tx.id.clone())
+            .collect();
+
+        expired
+            .iter()
+            .filter_map(|tx_id| self.remove(tx_id))
+            .collect()
+    }
+
+    /// Write pending changes if the last write was long enough ago
+    pub fn flush_if_due(&mut self) -> WalletResult<()> {
+        let due = self
+            .last_write
+            .is_none_or(|written| written.elapsed() >= MEMPOOL_WRITE_INTERVAL);
+        if self.dirty && due {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Write pending changes now
+    pub fn flush(&mut self) -> WalletResult<()> {
+        let Some(path) = self.path.as_ref().filter(|_| self.dirty) else {
+            return Ok(());
+        };
+
+        let tmp_path = path.with_extension("dat.tmp");
+        std::fs::write(&tmp_path, self.encode_snapshot())
+            .and_then(|_| std::fs::rename(&tmp_path, path))
+            .map_err(|e| {
+                WalletError::Storage(format!("Failed to write {}: {}", path.display(), e))
+            })?;
+        debug!("Wrote {} mempool transactions", self.transactions.len());
+
+        self.dirty = false;
+        self.last_write = Some(Instant::now());
+        Ok(())
+    }
+
+    fn mark_dirty(&mut self) {
+        self.dirty = true;
+        if let Err(e) = self.flush_if_due() {
+            warn!("Failed to persist mempool: {}", e);
+        }
+    }
+
+    fn encode_snapshot(&self) -> Vec<u8> {
+        let mut out = vec![MEMPOOL_FORMAT_VERSION];
+        out.extend_from_slice(&(self.transactions.len() as u32).to_le_bytes());
+        for tx in self.transactions.values() {
+            tx.encode(&mut out);
+        }
+        out
+    }
+}
+
+fn decode_snapshot(bytes: &[u8]) -> WalletResult<Vec<SignedTransaction>> {
+    let mut reader = Reader::new(bytes);
+    reader.version(MEMPOOL_FORMAT_VERSION, "mempool")?;
+    let transactions = reader.list()?;
+    reader.finish()?;
+    Ok(transactions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_address, fake_signed_transaction, fake_txid, TempDir};
+
+    #[test]
+    fn transactions_spending_a_pending_input_are_refused() {
+        let funding = fake_txid();
+        let first = fake_signed_transaction()
+            .spending(&funding, 0, 1_000)
+            .spending(&funding, 1, 1_000)
+            .build();
+        let double_spend = fake_signed_transaction()
+            .spending(&funding, 1, 1_000)
+            .paying(&fake_address(), 900)
+            .build();
+        let independent = fake_signed_transaction()
+            .spending(&funding, 2, 1_000)
+            .build();
+
+        let mut mempool = Mempool::new();
+        assert!(mempool.insert(first.clone()).unwrap());
+        assert!(!mempool.insert(first.clone()).unwrap());
+        match mempool.insert(double_spend.clone()) {
+            Err(WalletError::TxRejected { code, detail, .. }) => {
+                assert_eq!(code, RejectCode::Conflict);
+                assert!(detail.contains(&first.id));
+            }
+            other => panic!("double spend accepted: {:?}", other),
+        }
+        assert!(mempool.insert(independent).unwrap());
+
+        // Once the first is gone its inputs are free again
+        mempool.remove(&first.id);
+        assert!(mempool.insert(double_spend).unwrap());
+    }
+
+    #[test]
+    fn expiring_transactions_are_refused_and_evicted_at_the_boundary() {
+        let lasting = fake_signed_transaction().build();
+        let until_10 = fake_signed_transaction().expiring_at(10).build();
+        let until_9 = fake_signed_transaction().expiring_at(9).build();
+
+        // Before the tip is known nothing can be judged expired
+        let mut mempool = Mempool::new();
+        assert!(mempool.insert(until_9.clone()).unwrap());
+        mempool.remove(&until_9.id);
+
+        assert!(mempool.set_tip_height(9).is_empty());
+        assert_eq!(mempool.tip_height(), Some(9));
+        assert!(mempool.insert(lasting.clone()).unwrap());
+        // The next block is 10, the last one allowed to include it
+        assert!(mempool.insert(until_10.clone()).unwrap());
+        match mempool.insert(until_9.clone()) {
+            Err(WalletError::TxRejected { code, detail, .. }) => {
+                assert_eq!(code, RejectCode::Expired);
+                assert!(detail.contains("expired at height 9"), "{}", detail);
+            }
+            other => panic!("expired transaction accepted: {:?}", other),
+        }
+
+        let evicted: Vec<String> = mempool
+            .set_tip_height(10)
+            .into_iter()
+            .map(|tx| tx.id)
+            .collect();
+        assert_eq!(evicted, vec![until_10.id]);
+        assert_eq!(ids(&mempool), vec![lasting.id]);
+    }
+
+    fn ids(mempool: &Mempool) -> Vec<String> {
+        mempool.transactions().map(|tx| tx.id.clone()).collect()
+    }
+
+    #[test]
+    fn the_snapshot_survives_a_reopen() {
+        let dir = TempDir::new("mempool");
+        let (a, b) = (
+            fake_signed_transaction().build(),
+            fake_signed_transaction().build(),
+        );
+        let mut mempool = Mempool::open(dir.path(), true).unwrap();
+        mempool.insert(a.clone()).unwrap();
+        mempool.insert(b.clone()).unwrap();
+        mempool.flush().unwrap();
+
+        let reopened = Mempool::open(dir.path(), true).unwrap();
+        assert_eq!(ids(&reopened), ids(&mempool));
+        let reloaded = reopened.transactions().find(|tx| tx.id == a.id).unwrap();
+        assert_eq!(reloaded.to_bytes(), a.to_bytes());
+
+        // With persistence off the snapshot is neither read nor written
+        let mut off = Mempool::open(dir.path(), false).unwrap();
+        assert!(off.is_empty());
+        off.insert(fake_signed_transaction().build()).unwrap();
+        off.flush().unwrap();
+        assert_eq!(Mempool::open(dir.path(), true).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn writes_are_batched_until_flushed() {
+        let dir = TempDir::new("mempool-batch");
+        let mut mempool = Mempool::open(dir.path(), true).unwrap();
+        // The first change is written straight away, the next waits its turn
+        mempool.insert(fake_signed_transaction().build()).unwrap();
+        assert_eq!(Mempool::open(dir.path(), true).unwrap().len(), 1);
+        mempool.insert(fake_signed_transaction().build()).unwrap();
+        mempool.flush_if_due().unwrap();
+        assert_eq!(Mempool::open(dir.path(), true).unwrap().len(), 1);
+
+        mempool.flush().unwrap();
+        assert_eq!(Mempool::open(dir.path(), true).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn an_unreadable_snapshot_is_an_error() {
+        let dir = TempDir::new("mempool-corrupt");
+        std::fs::write(dir.join(MEMPOOL_FILE), b"not a mempool").unwrap();
+        assert!(Mempool::open(dir.path(), true).is_err());
+    }
+
+    #[test]
+    fn revalidating_drops_spent_inputs_and_their_children() {
+        let (spent, unspent) = (fake_txid(), fake_txid());
+        let parent = fake_signed_transaction()
+            .spending(&spent, 0, 1_000)
+            .paying(&fake_address(), 990)
+            .build();
+        let child = fake_signed_transaction()
+            .spending(&parent.id, 0, 990)
+            .build();
+        let valid = fake_signed_transaction()
+            .spending(&unspent, 0, 1_000)
+            .build();
+        let mut mempool = Mempool::new();
+        for tx in [&parent, &child, &valid] {
+            mempool.insert(tx.clone()).unwrap();
+        }
+
+        let utxos: HashSet<OutPoint> = [OutPoint::new(unspent, 0)].into();
+        let mut dropped: Vec<String> = mempool
+            .revalidate(&utxos)
+            .into_iter()
+            .map(|tx| tx.id)
+            .collect();
+        dropped.sort();
+        let mut expected = vec![parent.id, child.id];
+        expected.sort();
+        assert_eq!(dropped, expected);
+        assert_eq!(ids(&mempool), vec![valid.id]);
+    }
+
+    #[test]
+    fn a_child_may_spend_a_pending_parent_and_lives_as_long_as_it() {
+        let funding = fake_txid();
+        let parent = fake_signed_transaction()
+            .spending(&funding, 0, 1_000)
+            .paying(&fake_address(), 990)
+            .fee(10)
+            .build();
+        let child = fake_signed_transaction()
+            .spending(&parent.id, 0, 990)
+            .paying(&fake_address(), 500)
+            .fee(490)
+            .build();
+        let grandchild = fake_signed_transaction()
+            .spending(&child.id, 0, 500)
+            .build();
+        let mut mempool = Mempool::new();
+        for tx in [&parent, &child, &grandchild] {
+            assert!(mempool.insert(tx.clone()).unwrap());
+        }
+
+        let utxos: HashSet<OutPoint> = [OutPoint::new(funding, 0)].into();
+        assert!(mempool.is_spendable(&utxos, &OutPoint::new(parent.id.clone(), 0)));
+        // The parent has no second output to spend
+        assert!(!mempool.creates(&OutPoint::new(parent.id.clone(), 1)));
+        let ancestors: Vec<&str> = mempool
+            .ancestors(&grandchild.id)
+            .into_iter()
+            .map(|tx| tx.id.as_str())
+            .collect();
+        assert_eq!(ancestors, vec![child.id.as_str(), parent.id.as_str()]);
+        assert!(mempool.ancestors(&parent.id).is_empty());
+
+        // While the parent's own input is unspent the whole package stays
+        assert!(mempool.revalidate(&utxos).is_empty());
+        assert_eq!(mempool.len(), 3);
+        assert_eq!(mempool.revalidate(&HashSet::<OutPoint>::new()).len(), 3);
+        assert!(mempool.is_empty());
+    }
+}
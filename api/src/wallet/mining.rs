@@ -0,0 +1,297 @@
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::ops::RangeBounds;
+use uuid::Uuid;
+
+use crate::wallet::balance::COINBASE_MATURITY;
+use crate::wallet::chain::Blockchain;
+use crate::wallet::keys::{NockchainTransaction, TransactionOutput};
+use crate::wallet::provenance::NoteOrigin;
+use crate::wallet::storage::StorageManager;
+use crate::wallet::{encoding, Address, Block, Note, Wallet, WalletError, WalletResult};
+
+/// Mined block payouts, saved through `StorageManager`
+pub const MINING_REWARDS_FILE: &str = "mining_rewards.json";
+
+/// Where mining payouts go
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MiningPayout {
+    /// Wallet key that receives the coinbase
+    pub key_name: String,
+    /// Pay each block to a fresh receive address of the key instead of its main address
+    #[serde(default)]
+    pub rotate: bool,
+}
+
+/// Coinbase payout of one block mined by this wallet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MiningReward {
+    /// Hex-encoded block hash
+    pub block_hash: String,
+    pub height: u64,
+    pub amount: u64,
+    pub timestamp: DateTime<Utc>,
+    pub address: Address,
+}
+
+impl MiningReward {
+    /// Whether the payout can be spent once the chain is at `tip_height`
+    pub fn is_mature(&self, tip_height: Option<u64>) -> bool {
+        tip_height.is_some_and(|tip| tip >= self.height + COINBASE_MATURITY)
+    }
+}
+
+impl Wallet {
+    /// Address the next mined block should pay. With rotation on this hands out
+    /// (and persists) a fresh receive address each call.
+    pub async fn mining_payout_address(
+        &mut self,
+        payout: &MiningPayout,
+        storage: &StorageManager,
+    ) -> WalletResult<Address> {
+        if payout.rotate {
+            return self.next_receive_address(&payout.key_name, storage).await;
+        }
+        self.keys()
+            .get_key(&payout.key_name)
+            .map(|key| key.address().clone())
+            .ok_or_else(|| WalletError::KeyNotFound(payout.key_name.clone()))
+    }
+
+    /// Mine one block on `chain` whose coinbase pays `reward` to `payout`, and
+    /// record the payout
+    pub async fn mine_block(
+        &mut self,
+        chain: &mut Blockchain,
+        payout: &MiningPayout,
+        reward: u64,
+        storage: &StorageManager,
+    ) -> WalletResult<MiningReward> {
+        self.ensure_writable()?;
+        let address = self.mining_payout_address(payout, storage).await?;
+        let height = chain.height().map_or(0, |tip| tip + 1);
+        let coinbase = coinbase_transaction(height, &address, reward);
+
+        let mut block = chain.new_block(vec![coinbase], chain.config().initial_difficulty);
+        block.mine()?;
+        chain.add_block(block.clone())?;
+
+        self.balances_mut().set_tip_height(height);
+        self.expire_transactions(chain)?;
+        self.fees.record_block(&block);
+        let reward = self.record_mining_reward(&block, storage).await?;
+        self.fees.save(storage).await?;
+        Ok(reward)
+    }
+
+    /// Record the coinbase outputs of `block` that pay this wallet as immature
+    /// notes and save the payout to the reward history
+    pub async fn record_mining_reward(
+        &mut self,
+        block: &Block,
+        storage: &StorageManager,
+    ) -> WalletResult<MiningReward> {
+        self.ensure_writable()?;
+        let coinbase = block
+            .transactions
+            .first()
+            .filter(|tx| tx.inputs.is_empty())
+            .ok_or_else(|| WalletError::BlockValidation("Block has no coinbase".to_string()))?;
+
+        let ours: Vec<(u32, &TransactionOutput, Address)> = coinbase
+            .outputs
+            .iter()
+            .enumerate()
+            .filter_map(|(index, output)| {
+                let address = Address::from_string(&output.recipient_address).ok()?;
+                self.keys()
+                    .is_mine(&address)
+                    .then_some((index as u32, output, address))
+            })
+            .collect();
+        let Some((_, _, address)) = ours.first() else {
+            return Err(WalletError::Transaction(
+                "Coinbase does not pay this wallet".to_string(),
+            ));
+        };
+
+        let timestamp = Utc
+            .timestamp_opt(block.header.timestamp as i64, 0)
+            .single()
+            .unwrap_or_else(Utc::now);
+        let reward = MiningReward {
+            block_hash: hex::encode(block.hash()),
+            height: block.header.height,
+            amount: ours.iter().map(|(_, output, _)| output.amount).sum(),
+            timestamp,
+            address: address.clone(),
+        };
+
+        for (index, output, address) in &ours {
+            self.observe_note(Note {
+                id: Uuid::new_v4(),
+                address: address.clone(),
+                amount: output.amount,
+                block_height: Some(block.header.height),
+                transaction_id: coinbase.id.clone(),
+                output_index: *index,
+                spent: false,
+                locked: false,
+                created_at: timestamp,
+                coinbase: true,
+                origin: Some(NoteOrigin::Coinbase),
+            })?;
+        }
+
+        self.mining_rewards.push(reward.clone());
+        storage
+            .save(MINING_REWARDS_FILE, &self.mining_rewards)
+            .await?;
+        Ok(reward)
+    }
+
+    /// Rewards for blocks whose height falls in `heights`, oldest first
+    pub fn mining_rewards(&self, heights: impl RangeBounds<u64>) -> Vec<&MiningReward> {
+        self.mining_rewards
+            .iter()
+            .filter(|reward| heights.contains(&reward.height))
+            .collect()
+    }
+
+    /// Replace the reward history with one loaded from storage
+    pub fn restore_mining_rewards(&mut self, rewards: Vec<MiningReward>) {
+        self.mining_rewards = rewards;
+    }
+}
+
+/// Coinbase paying `reward` to `address`; the height makes every coinbase unique
+fn coinbase_transaction(height: u64, address: &Address, reward: u64) -> NockchainTransaction {
+    let hash = encoding::sha256(format!("coinbase/{}/{}/{}", height, address, reward).as_bytes());
+    NockchainTransaction {
+        id: hex::encode(hash),
+        inputs: Vec::new(),
+        outputs: vec![TransactionOutput {
+            amount: reward,
+            recipient_address: address.to_string(),
+        }],
+        hash: hash.to_vec(),
+        expiry_height: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_address, fake_block, fake_coinbase, fakenet_chain_config, TempDir};
+    use crate::wallet::keys::NockchainKeyManager;
+    use std::collections::HashSet;
+
+    const REWARD: u64 = 5_000;
+
+    fn mining_wallet() -> Wallet {
+        let mut wallet = Wallet::new();
+        wallet.keys = NockchainKeyManager::from_seed([4; 32]);
+        wallet.generate_key("miner").unwrap();
+        wallet
+    }
+
+    fn payout(rotate: bool) -> MiningPayout {
+        MiningPayout {
+            key_name: "miner".to_string(),
+            rotate,
+        }
+    }
+
+    #[tokio::test]
+    async fn rotation_pays_each_block_to_a_fresh_address() {
+        let dir = TempDir::new("mining-rotate");
+        let storage = StorageManager::new(dir.path().to_path_buf()).unwrap();
+        let mut wallet = mining_wallet();
+        let mut chain = Blockchain::new(fakenet_chain_config());
+
+        for _ in 0..3 {
+            wallet
+                .mine_block(&mut chain, &payout(true), REWARD, &storage)
+                .await
+                .unwrap();
+        }
+
+        let rewards = wallet.mining_rewards(..);
+        assert_eq!(
+            rewards.iter().map(|r| r.height).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        let addresses: HashSet<_> = rewards.iter().map(|r| r.address.clone()).collect();
+        assert_eq!(addresses.len(), 3);
+        assert!(addresses.iter().all(|address| wallet.keys.is_mine(address)));
+        assert_eq!(wallet.mining_rewards(1..).len(), 2);
+
+        let saved: Vec<MiningReward> = storage.load(MINING_REWARDS_FILE).await.unwrap();
+        assert_eq!(saved.iter().collect::<Vec<_>>(), rewards);
+    }
+
+    #[tokio::test]
+    async fn without_rotation_every_block_pays_the_keys_address() {
+        let dir = TempDir::new("mining-fixed");
+        let storage = StorageManager::new(dir.path().to_path_buf()).unwrap();
+        let mut wallet = mining_wallet();
+        let mut chain = Blockchain::new(fakenet_chain_config());
+        let main = wallet.keys.get_key("miner").unwrap().address().clone();
+
+        for _ in 0..2 {
+            let reward = wallet
+                .mine_block(&mut chain, &payout(false), REWARD, &storage)
+                .await
+                .unwrap();
+            assert_eq!(reward.address, main);
+        }
+    }
+
+    #[tokio::test]
+    async fn mined_rewards_stay_immature_until_the_maturity_window_passes() {
+        let dir = TempDir::new("mining-maturity");
+        let storage = StorageManager::new(dir.path().to_path_buf()).unwrap();
+        let mut wallet = mining_wallet();
+        let mut chain = Blockchain::new(fakenet_chain_config());
+        let reward = wallet
+            .mine_block(&mut chain, &payout(false), REWARD, &storage)
+            .await
+            .unwrap();
+
+        let spendable = |wallet: &Wallet| {
+            wallet
+                .balances()
+                .get_spendable_notes(&reward.address, REWARD)
+                .len()
+        };
+        let balance = wallet.balances().get_total_balance();
+        assert_eq!(balance.confirmed, REWARD);
+        assert_eq!(balance.immature, REWARD);
+        assert_eq!(spendable(&wallet), 0);
+        assert!(!reward.is_mature(Some(COINBASE_MATURITY - 1)));
+        assert!(reward.is_mature(Some(COINBASE_MATURITY)));
+
+        wallet.balances_mut().set_tip_height(COINBASE_MATURITY);
+        let balance = wallet.balances().get_total_balance();
+        assert_eq!(balance.immature, 0);
+        assert_eq!(spendable(&wallet), 1);
+    }
+
+    #[tokio::test]
+    async fn a_block_that_pays_someone_else_is_not_recorded() {
+        let dir = TempDir::new("mining-foreign");
+        let storage = StorageManager::new(dir.path().to_path_buf()).unwrap();
+        let mut wallet = mining_wallet();
+        let mut foreign = fake_block().height(1).build();
+        foreign
+            .transactions
+            .push(fake_coinbase(&fake_address(), REWARD));
+
+        assert!(wallet
+            .record_mining_reward(&foreign, &storage)
+            .await
+            .is_err());
+        assert!(wallet.mining_rewards(..).is_empty());
+        assert!(wallet.balances().notes().is_empty());
+    }
+}
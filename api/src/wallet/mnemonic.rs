@@ -0,0 +1,414 @@
+//! BIP39 recovery phrases in every wordlist the spec defines.
+//!
+//! Phrases are NFKD-normalized before they are split into words or turned
+//! into a seed, so a phrase typed with composed characters, or Japanese
+//! written with ideographic spaces, restores the same wallet.
+
+use bip39::{Language, Mnemonic};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fmt;
+
+use crate::wallet::audit::{AuditEvent, AuditOutcome};
+use crate::wallet::keys::NockchainKeyManager;
+use crate::wallet::{Wallet, WalletError, WalletResult};
+
+/// Word counts a recovery phrase may have
+pub const MNEMONIC_WORD_COUNTS: [usize; 5] = [12, 15, 18, 21, 24];
+
+/// BIP39 wordlist a phrase is written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum MnemonicLanguage {
+    #[default]
+    English,
+    SimplifiedChinese,
+    TraditionalChinese,
+    Czech,
+    French,
+    Italian,
+    Japanese,
+    Korean,
+    Portuguese,
+    Spanish,
+}
+
+impl MnemonicLanguage {
+    /// In the order detection prefers them when two lists match equally well
+    pub const ALL: [MnemonicLanguage; 10] = [
+        MnemonicLanguage::English,
+        MnemonicLanguage::SimplifiedChinese,
+        MnemonicLanguage::TraditionalChinese,
+        MnemonicLanguage::Czech,
+        MnemonicLanguage::French,
+        MnemonicLanguage::Italian,
+        MnemonicLanguage::Japanese,
+        MnemonicLanguage::Korean,
+        MnemonicLanguage::Portuguese,
+        MnemonicLanguage::Spanish,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MnemonicLanguage::English => "English",
+            MnemonicLanguage::SimplifiedChinese => "Chinese (Simplified)",
+            MnemonicLanguage::TraditionalChinese => "Chinese (Traditional)",
+            MnemonicLanguage::Czech => "Czech",
+            MnemonicLanguage::French => "French",
+            MnemonicLanguage::Italian => "Italian",
+            MnemonicLanguage::Japanese => "Japanese",
+            MnemonicLanguage::Korean => "Korean",
+            MnemonicLanguage::Portuguese => "Portuguese",
+            MnemonicLanguage::Spanish => "Spanish",
+        }
+    }
+
+    fn bip39(self) -> Language {
+        match self {
+            MnemonicLanguage::English => Language::English,
+            MnemonicLanguage::SimplifiedChinese => Language::SimplifiedChinese,
+            MnemonicLanguage::TraditionalChinese => Language::TraditionalChinese,
+            MnemonicLanguage::Czech => Language::Czech,
+            MnemonicLanguage::French => Language::French,
+            MnemonicLanguage::Italian => Language::Italian,
+            MnemonicLanguage::Japanese => Language::Japanese,
+            MnemonicLanguage::Korean => Language::Korean,
+            MnemonicLanguage::Portuguese => Language::Portuguese,
+            MnemonicLanguage::Spanish => Language::Spanish,
+        }
+    }
+
+    /// Whether `word`, already normalized, is in this wordlist
+    fn contains(self, word: &str) -> bool {
+        self.bip39().find_word(word).is_some()
+    }
+}
+
+impl fmt::Display for MnemonicLanguage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// One word of a phrase checked against a wordlist
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MnemonicWord {
+    /// The word as normalized
+    pub word: String,
+    pub known: bool,
+}
+
+fn normalize(phrase: &str) -> Cow<'_, str> {
+    let mut phrase = Cow::Borrowed(phrase);
+    Mnemonic::normalize_utf8_cow(&mut phrase);
+    phrase
+}
+
+/// Check each word of `phrase` against the wordlist of `language`
+pub fn check_words(phrase: &str, language: MnemonicLanguage) -> Vec<MnemonicWord> {
+    normalize(phrase)
+        .split_whitespace()
+        .map(|word| MnemonicWord {
+            known: language.contains(word),
+            word: word.to_string(),
+        })
+        .collect()
+}
+
+/// The wordlist with the most of the phrase's words; `None` if no list has
+/// any of them
+pub fn detect_language(phrase: &str) -> Option<MnemonicLanguage> {
+    let phrase = normalize(phrase);
+    let words: Vec<&str> = phrase.split_whitespace().collect();
+    let mut best = None;
+    let mut best_count = 0;
+    for language in MnemonicLanguage::ALL {
+        let count = words.iter().filter(|word| language.contains(word)).count();
+        if count > best_count {
+            best = Some(language);
+            best_count = count;
+        }
+    }
+    best
+}
+
+fn parse(phrase: &str, language: MnemonicLanguage) -> WalletResult<Mnemonic> {
+    Mnemonic::parse_in(language.bip39(), phrase).map_err(|e| {
+        let message = match e {
+            bip39::Error::BadWordCount(count) => format!(
+                "{} words; a recovery phrase has 12, 15, 18, 21 or 24",
+                count
+            ),
+            bip39::Error::UnknownWord(index) => {
+                format!("word {} is not in the {} wordlist", index + 1, language)
+            }
+            bip39::Error::InvalidChecksum => {
+                "checksum doesn't match; a word may be wrong or out of order".to_string()
+            }
+            e => e.to_string(),
+        };
+        WalletError::Mnemonic(message)
+    })
+}
+
+/// Check a whole phrase, words and checksum, in `language`, or in the
+/// wordlist it matches best if `None`. Returns the language it is valid in.
+pub fn validate_mnemonic(
+    phrase: &str,
+    language: Option<MnemonicLanguage>,
+) -> WalletResult<MnemonicLanguage> {
+    let language = language
+        .or_else(|| detect_language(phrase))
+        .ok_or_else(|| WalletError::Mnemonic("no word is in any BIP39 wordlist".to_string()))?;
+    parse(phrase, language)?;
+    Ok(language)
+}
+
+/// A new random phrase of `word_count` words in `language`
+pub fn generate_mnemonic(language: MnemonicLanguage, word_count: usize) -> WalletResult<String> {
+    if !MNEMONIC_WORD_COUNTS.contains(&word_count) {
+        return Err(WalletError::Mnemonic(format!(
+            "can't generate a {}-word phrase",
+            word_count
+        )));
+    }
+    let entropy: [u8; 32] = rand::random();
+    Mnemonic::from_entropy_in(language.bip39(), &entropy[..word_count / 3 * 4])
+        .map(|mnemonic| mnemonic.to_string())
+        .map_err(|e| WalletError::Mnemonic(e.to_string()))
+}
+
+/// HD seed for a phrase: the first half of its BIP39 seed. Phrase and
+/// passphrase are NFKD-normalized as the spec requires, whatever the language.
+pub fn mnemonic_seed(
+    phrase: &str,
+    passphrase: &str,
+    language: MnemonicLanguage,
+) -> WalletResult<[u8; 32]> {
+    let seed = parse(phrase, language)?.to_seed(passphrase);
+    let mut hd_seed = [0u8; 32];
+    hd_seed.copy_from_slice(&seed[..32]);
+    Ok(hd_seed)
+}
+
+impl NockchainKeyManager {
+    /// Key manager restored from a recovery phrase, remembering its language
+    pub fn from_mnemonic(
+        phrase: &str,
+        passphrase: &str,
+        language: Option<MnemonicLanguage>,
+    ) -> WalletResult<Self> {
+        let language = validate_mnemonic(phrase, language)?;
+        let mut keys = Self::from_seed(mnemonic_seed(phrase, passphrase, language)?);
+        keys.set_mnemonic_language(language);
+        Ok(keys)
+    }
+}
+
+impl Wallet {
+    /// Replace the wallet's seed with the one from a recovery phrase, in
+    /// `language` or the one detected. Only a wallet without keys can be
+    /// restored into; generate keys afterwards to derive the restored accounts.
+    pub fn restore_from_mnemonic(
+        &mut self,
+        phrase: &str,
+        passphrase: &str,
+        language: Option<MnemonicLanguage>,
+    ) -> WalletResult<MnemonicLanguage> {
+        let result = self.ensure_writable().and_then(|_| {
+            if !self.keys().list_keys().is_empty() {
+                return Err(WalletError::Mnemonic(
+                    "restore into a new profile; this wallet already has keys".to_string(),
+                ));
+            }
+            NockchainKeyManager::from_mnemonic(phrase, passphrase, language)
+        });
+        let result = result.map(|keys| {
+            self.keys = keys;
+            self.keys.mnemonic_language().unwrap_or_default()
+        });
+        let recorded = result.as_ref().ok().copied().or(language);
+        self.record_audit(
+            AuditEvent::SeedRestored {
+                language: recorded
+                    .map_or("undetected".to_string(), |language| language.to_string()),
+            },
+            AuditOutcome::of(&result),
+        );
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::audit::AuditFilter;
+
+    /// First half of a 64-byte hex BIP39 seed, as the wallet uses it
+    fn hd_seed(hex_seed: &str) -> [u8; 32] {
+        hex::decode(&hex_seed[..64]).unwrap().try_into().unwrap()
+    }
+
+    const JAPANESE_PASSPHRASE: &str = "㍍ガバヴァぱばぐゞちぢ十人十色";
+
+    /// The Japanese vectors from the BIP39 reference list, with their
+    /// ideographic spaces
+    const JAPANESE: [(&str, &str); 2] = [
+        (
+            "あいこくしん　あいこくしん　あいこくしん　あいこくしん　あいこくしん　あいこくしん　あいこくしん　あいこくしん　あいこくしん　あいこくしん　あいこくしん　あおぞら",
+            "a262d6fb6122ecf45be09c50492b31f92e9beb7d9a845987a02cefda57a15f9c467a17872029a9e92299b5cbdf306e3a0ee620245cbd508959b6cb7ca637bd55",
+        ),
+        (
+            "そつう　れきだい　ほんやく　わかす　りくつ　ばいか　ろせん　やちん　そつう　れきだい　ほんやく　わかめ",
+            "aee025cbe6ca256862f889e48110a6a382365142f7d16f2b9545285b3af64e542143a577e9c144e101a6bdca18f8d97ec3366ebf5b088b1c1af9bc31346e60d9",
+        ),
+    ];
+
+    /// Entropy 00… and 7f… in the Spanish wordlist with passphrase "TREZOR".
+    /// BIP39 lists no Spanish seeds; these were derived with the spec's
+    /// PBKDF2-HMAC-SHA512 over the NFKD phrase, outside this crate.
+    const SPANISH: [(&str, &str); 2] = [
+        (
+            "ábaco ábaco ábaco ábaco ábaco ábaco ábaco ábaco ábaco ábaco ábaco abierto",
+            "29a2ee16de47d07025de37e7d9c596869439f9bcd26a702d2bae64db2bf0f68383841c5444b5b3bd39dd720d2ebe59969e110e5955c8e6d32c6c3294fd87439b",
+        ),
+        (
+            "ligero vista talar yogur venta queso yacer trozo ligero vista talar zafiro",
+            "1580aa5d5d67057b3a0a12253c283b93921851555529d0bbe9634349d641029216f791ddce3527819d44d833a0df3500b15fd8ba4cae7ca24e1464b9167de633",
+        ),
+    ];
+
+    #[test]
+    fn japanese_vectors_give_their_seeds() {
+        for (phrase, seed) in JAPANESE {
+            assert_eq!(
+                validate_mnemonic(phrase, None).unwrap(),
+                MnemonicLanguage::Japanese
+            );
+            let derived =
+                mnemonic_seed(phrase, JAPANESE_PASSPHRASE, MnemonicLanguage::Japanese).unwrap();
+            assert_eq!(derived, hd_seed(seed));
+
+            // Typed with ASCII spaces, the phrase is the same wallet
+            let ascii_spaced = phrase.replace('\u{3000}', " ");
+            assert_eq!(
+                mnemonic_seed(
+                    &ascii_spaced,
+                    JAPANESE_PASSPHRASE,
+                    MnemonicLanguage::Japanese
+                )
+                .unwrap(),
+                derived
+            );
+        }
+    }
+
+    #[test]
+    fn spanish_vectors_give_their_seeds_composed_or_not() {
+        for (phrase, seed) in SPANISH {
+            assert_eq!(
+                validate_mnemonic(phrase, None).unwrap(),
+                MnemonicLanguage::Spanish
+            );
+            assert_eq!(
+                mnemonic_seed(phrase, "TREZOR", MnemonicLanguage::Spanish).unwrap(),
+                hd_seed(seed)
+            );
+        }
+        // "á" written as "a" plus a combining acute accent
+        let decomposed = SPANISH[0].0.replace('á', "a\u{301}");
+        assert_ne!(decomposed, SPANISH[0].0);
+        assert_eq!(
+            mnemonic_seed(&decomposed, "TREZOR", MnemonicLanguage::Spanish).unwrap(),
+            hd_seed(SPANISH[0].1)
+        );
+    }
+
+    #[test]
+    fn the_english_trezor_vector_still_matches() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        assert_eq!(
+            mnemonic_seed(phrase, "TREZOR", MnemonicLanguage::English).unwrap(),
+            hd_seed("c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04")
+        );
+    }
+
+    #[test]
+    fn bad_phrases_say_what_is_wrong() {
+        let message = |phrase: &str, language| match validate_mnemonic(phrase, language) {
+            Err(WalletError::Mnemonic(message)) => message,
+            other => panic!("expected a mnemonic error, got {:?}", other),
+        };
+        let swapped = "ábaco ábaco ábaco ábaco ábaco ábaco ábaco ábaco ábaco ábaco ábaco ábaco";
+        assert!(message(swapped, None).starts_with("checksum doesn't match"));
+        assert_eq!(
+            message(SPANISH[0].0, Some(MnemonicLanguage::English)),
+            "word 1 is not in the English wordlist"
+        );
+        assert!(message("ábaco ábaco ábaco", None).starts_with("3 words"));
+        assert_eq!(
+            message("qwxz zzqv", None),
+            "no word is in any BIP39 wordlist"
+        );
+    }
+
+    #[test]
+    fn words_are_checked_one_by_one_against_the_chosen_list() {
+        let words = check_words("ligero vista colour", MnemonicLanguage::Spanish);
+        let known: Vec<bool> = words.iter().map(|word| word.known).collect();
+        assert_eq!(known, [true, true, false]);
+        assert_eq!(words[2].word, "colour");
+        assert_eq!(
+            detect_language("ligero vista colour"),
+            Some(MnemonicLanguage::Spanish)
+        );
+        assert_eq!(detect_language(""), None);
+    }
+
+    #[test]
+    fn generated_phrases_validate_back_to_their_language() {
+        for language in MnemonicLanguage::ALL {
+            for word_count in [12, 24] {
+                let phrase = generate_mnemonic(language, word_count).unwrap();
+                assert_eq!(check_words(&phrase, language).len(), word_count);
+                assert_eq!(
+                    validate_mnemonic(&phrase, Some(language)).unwrap(),
+                    language
+                );
+            }
+        }
+        assert!(generate_mnemonic(MnemonicLanguage::English, 13).is_err());
+    }
+
+    #[test]
+    fn restoring_remembers_the_language_and_derives_the_same_keys() {
+        let (phrase, seed) = JAPANESE[1];
+        let mut wallet = Wallet::new();
+        assert_eq!(
+            wallet
+                .restore_from_mnemonic(phrase, JAPANESE_PASSPHRASE, None)
+                .unwrap(),
+            MnemonicLanguage::Japanese
+        );
+        assert_eq!(
+            wallet.keys().mnemonic_language(),
+            Some(MnemonicLanguage::Japanese)
+        );
+        let restored = wallet.generate_key("default").unwrap();
+        let mut expected = NockchainKeyManager::from_seed(hd_seed(seed));
+        let key = expected.generate_key("default".to_string()).unwrap();
+        assert_eq!(key.address(), &restored);
+
+        // A wallet with keys can't be restored into, and the refusal is audited
+        assert!(wallet
+            .restore_from_mnemonic(phrase, JAPANESE_PASSPHRASE, None)
+            .is_err());
+        let outcomes: Vec<AuditOutcome> = wallet
+            .audit_log(&AuditFilter::default())
+            .into_iter()
+            .filter(|entry| matches!(entry.event, AuditEvent::SeedRestored { .. }))
+            .map(|entry| entry.outcome)
+            .collect();
+        assert_eq!(outcomes.len(), 2);
+        assert!(outcomes.contains(&AuditOutcome::Success));
+    }
+}
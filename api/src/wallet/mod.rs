@@ -1,12 +1,79 @@
+pub mod accelerate;
+pub mod address;
+pub mod amount;
+pub mod audit;
 pub mod balance;
+pub mod chain;
+pub mod checkpoints;
+pub mod clock;
+pub mod cold_signing;
+pub mod config_builder;
+pub mod config_file;
+pub mod console_history;
+pub mod contacts;
+pub mod data_dir;
+pub mod decode;
+pub mod diagnostics;
+pub mod encoding;
+pub mod expiry;
+pub mod explorer;
+pub mod export;
+pub mod external;
+pub mod fees;
+pub mod filters;
+pub mod geoip;
+pub mod handshake;
+pub mod hd;
+pub mod health;
+pub mod incoming;
+pub mod integrity;
+pub mod journal;
+pub mod key_import;
 pub mod keys;
+pub mod launch;
+pub mod ledger;
+mod lockfile;
+pub mod log_buffer;
+pub mod logging;
+pub mod mempool;
+pub mod mining;
+pub mod mnemonic;
+pub mod netsim;
 pub mod network;
+pub mod payment_request;
+pub mod peers;
+pub mod profiles;
+pub mod provenance;
+pub mod proxy;
+pub mod qr;
+pub mod rebroadcast;
+pub mod receipt;
+pub mod reject;
+pub mod retention;
+pub mod rpc;
+pub mod rpc_client;
+pub mod search;
+pub mod secret_key;
+pub mod seeds;
+pub mod selftest;
+pub mod send;
+pub mod send_draft;
+pub mod session;
+pub mod spending;
 pub mod storage;
+pub mod sync_progress;
+pub mod templates;
 pub mod transaction;
+pub mod ui_prefs;
+pub mod uptime;
+pub mod watchdog;
+pub mod webhooks;
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 use std::fmt;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -37,6 +104,9 @@ pub enum WalletError {
     #[error("Key not found: {0}")]
     KeyNotFound(String),
 
+    #[error("Note not found: {0}")]
+    NoteNotFound(Uuid),
+
     #[error("Key already exists: {0}")]
     KeyExists(String),
 
@@ -49,8 +119,131 @@ pub enum WalletError {
     #[error("Block validation error: {0}")]
     BlockValidation(String),
 
+    #[error("Body of block {0} has been pruned")]
+    BlockPruned(u64),
+
     #[error("Consensus error: {0}")]
     Consensus(String),
+
+    #[error("Block {found} at height {height} contradicts checkpoint {expected}")]
+    CheckpointMismatch {
+        height: u64,
+        expected: String,
+        found: String,
+    },
+
+    #[error("Transaction is signed for chain {found}, not this chain ({expected})")]
+    ChainIdMismatch { expected: String, found: String },
+
+    #[error("Only {have} of the {need} peers needed to broadcast are connected")]
+    InsufficientPeers { have: u32, need: u32 },
+
+    #[error("Transaction rejected by {peer} ({code}): {detail}")]
+    TxRejected {
+        code: reject::RejectCode,
+        peer: String,
+        detail: String,
+    },
+
+    #[error("Invalid node state transition from {from} to {to}")]
+    InvalidStateTransition { from: String, to: String },
+
+    #[error("Node start cancelled")]
+    StartCancelled,
+
+    #[error("Peer handshake rejected: {0}")]
+    HandshakeRejected(String),
+
+    #[error("Peer version rejected: {0}")]
+    PeerVersionRejected(String),
+
+    #[error("SOCKS5 proxy {proxy} is unreachable: {reason}")]
+    ProxyUnreachable { proxy: String, reason: String },
+
+    #[error("SOCKS5 proxy {proxy} could not connect to {target}: {reason}")]
+    ProxyConnectFailed {
+        proxy: String,
+        target: String,
+        reason: String,
+    },
+
+    #[error("Invalid amount: {0}")]
+    InvalidAmount(String),
+
+    #[error("Contact error: {0}")]
+    Contact(String),
+
+    #[error("Wallet is read-only: {0}")]
+    ReadOnly(String),
+
+    #[error("Wallet is locked")]
+    Locked,
+
+    #[error("Enter the wallet password again to continue")]
+    ReauthRequired,
+
+    #[error("Node start timed out at {0}")]
+    StartTimedOut(watchdog::StartPhase),
+
+    #[error(
+        "Sends over {} NOCK need the amount typed again to confirm",
+        amount::format_amount(*threshold, amount::AmountUnit::Nock)
+    )]
+    ConfirmationRequired { threshold: u64 },
+
+    #[error(
+        "Send exceeds the daily spending limit: {} NOCK left{}",
+        amount::format_amount(*remaining, amount::AmountUnit::Nock),
+        describe_reset(resets_at)
+    )]
+    DailyLimitExceeded {
+        remaining: u64,
+        resets_at: Option<DateTime<Utc>>,
+    },
+
+    #[error("Invalid receipt: {0}")]
+    InvalidReceipt(String),
+
+    #[error("Template error: {0}")]
+    Template(String),
+
+    #[error("Payment request error: {0}")]
+    PaymentRequest(String),
+
+    #[error("Profile error: {0}")]
+    Profile(String),
+
+    #[error("Invalid recovery phrase: {0}")]
+    Mnemonic(String),
+
+    #[error("Invalid secret key: {0}")]
+    InvalidSecretKey(String),
+
+    #[error("Transaction {tx_id} was just rebroadcast; try again in {retry_after_secs}s")]
+    RebroadcastTooSoon {
+        tx_id: String,
+        retry_after_secs: u64,
+    },
+
+    #[error("Output of {amount} is too small to accelerate with: the fee and a dust-free output need {needed}")]
+    AccelerationTooSmall { amount: u64, needed: u64 },
+}
+
+impl WalletError {
+    /// Whether the same operation may succeed if simply tried again later
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            WalletError::InsufficientPeers { .. } | WalletError::RebroadcastTooSoon { .. }
+        )
+    }
+}
+
+/// When more of the daily allowance frees up, for `DailyLimitExceeded`
+fn describe_reset(resets_at: &Option<DateTime<Utc>>) -> String {
+    resets_at.map_or_else(String::new, |at| {
+        format!(", more from {}", at.format("%Y-%m-%d %H:%M UTC"))
+    })
 }
 
 pub type WalletResult<T> = Result<T, WalletError>;
@@ -77,7 +270,9 @@ impl Address {
         bs58::encode(&self.public_key).into_string()
     }
 
+    /// Parse a plain base58 key, with or without the `nock_` prefix
     pub fn from_string(s: &str) -> WalletResult<Self> {
+        let s = address::strip_nockchain_prefix(s).unwrap_or(s);
         let decoded = bs58::decode(s)
             .into_vec()
             .map_err(|e| WalletError::InvalidAddress(format!("Base58 decode error: {}", e)))?;
@@ -107,6 +302,9 @@ pub struct Balance {
     pub confirmed: u64,
     pub unconfirmed: u64,
     pub locked: u64,
+    /// Confirmed coinbase amounts that can't be spent until they mature
+    #[serde(default)]
+    pub immature: u64,
 }
 
 impl Balance {
@@ -115,6 +313,7 @@ impl Balance {
             confirmed: 0,
             unconfirmed: 0,
             locked: 0,
+            immature: 0,
         }
     }
 
@@ -123,12 +322,14 @@ impl Balance {
     }
 
     pub fn available(&self) -> u64 {
-        self.confirmed.saturating_sub(self.locked)
+        self.confirmed
+            .saturating_sub(self.locked)
+            .saturating_sub(self.immature)
     }
 }
 
 /// UTXO note for nockchain wallet
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Note {
     pub id: Uuid,
     pub address: Address,
@@ -139,6 +340,13 @@ pub struct Note {
     pub spent: bool,
     pub locked: bool,
     pub created_at: DateTime<Utc>,
+    /// Minted by a block this wallet mined; spendable only after `COINBASE_MATURITY` blocks
+    #[serde(default)]
+    pub coinbase: bool,
+    /// How the note reached the wallet, tagged when it was recorded; `None`
+    /// for notes recorded before origins were kept
+    #[serde(default)]
+    pub origin: Option<provenance::NoteOrigin>,
 }
 
 /// Transaction status in the blockchain
@@ -149,6 +357,28 @@ pub enum TransactionStatus {
     Failed { reason: String },
 }
 
+/// Which way value moves relative to the wallet
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TransactionDirection {
+    #[default]
+    Incoming,
+    Outgoing,
+    /// Funded by the wallet and paid only to its own addresses
+    Internal,
+}
+
+/// Where a recorded transaction came from
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TransactionOrigin {
+    /// Sent by this wallet, or paying it
+    #[default]
+    Wallet,
+    /// Signed elsewhere and found by sync spending the wallet's notes, e.g.
+    /// by the cold wallet behind a watch-only address. Without `fee_known`
+    /// some inputs weren't the wallet's, so the fee couldn't be worked out.
+    External { fee_known: bool },
+}
+
 /// Transaction record
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Transaction {
@@ -159,8 +389,51 @@ pub struct Transaction {
     pub from_address: Option<Address>,
     pub to_address: Option<Address>,
     pub created_at: DateTime<Utc>,
+    /// When this wallet handed the transaction to the node, for ones it sent
+    #[serde(default)]
+    pub broadcast_at: Option<DateTime<Utc>>,
     pub confirmed_at: Option<DateTime<Utc>>,
     pub is_outgoing: bool,
+    #[serde(default)]
+    pub direction: TransactionDirection,
+    #[serde(default)]
+    pub inputs: Vec<keys::TransactionInput>,
+    #[serde(default)]
+    pub outputs: Vec<keys::TransactionOutput>,
+    /// What peers that refused to relay it answered, oldest first
+    #[serde(default)]
+    pub rejections: Vec<reject::Rejection>,
+    #[serde(default)]
+    pub origin: TransactionOrigin,
+    /// Pending incoming transaction this child spends from to speed it up;
+    /// see `Wallet::accelerate_incoming`
+    #[serde(default)]
+    pub accelerates: Option<String>,
+}
+
+impl Transaction {
+    /// Change in wallet balance caused by this transaction. For internal moves
+    /// only the fee leaves the wallet.
+    pub fn net_amount(&self) -> i128 {
+        match self.direction {
+            TransactionDirection::Incoming => self.amount as i128,
+            TransactionDirection::Outgoing => -(self.amount as i128 + self.fee as i128),
+            TransactionDirection::Internal => -(self.fee as i128),
+        }
+    }
+
+    /// Spent the wallet's notes without the wallet creating it
+    pub fn is_external(&self) -> bool {
+        matches!(self.origin, TransactionOrigin::External { .. })
+    }
+
+    /// Whether `fee` is what was paid, rather than 0 for not known
+    pub fn fee_known(&self) -> bool {
+        !matches!(
+            self.origin,
+            TransactionOrigin::External { fee_known: false }
+        )
+    }
 }
 
 /// Nockchain block header
@@ -176,20 +449,11 @@ pub struct BlockHeader {
 }
 
 impl BlockHeader {
-    /// Calculate the hash of this block header
+    /// Calculate the hash of this block header's canonical encoding
     pub fn hash(&self) -> [u8; 32] {
-        use sha2::{Digest, Sha256};
+        use encoding::Encode;
 
-        let mut hasher = Sha256::new();
-        hasher.update(&self.version.to_le_bytes());
-        hasher.update(&self.previous_hash);
-        hasher.update(&self.merkle_root);
-        hasher.update(&self.timestamp.to_le_bytes());
-        hasher.update(&self.bits.to_le_bytes());
-        hasher.update(&self.nonce.to_le_bytes());
-        hasher.update(&self.height.to_le_bytes());
-
-        hasher.finalize().into()
+        encoding::sha256(&self.to_bytes())
     }
 
     /// Check if this block meets the proof-of-work difficulty requirement
@@ -217,13 +481,16 @@ pub struct Block {
 }
 
 impl Block {
-    /// Create a new block
+    /// Create a new block, putting the transactions after the coinbase in
+    /// hash order as `validate` requires
     pub fn new(
         previous_hash: [u8; 32],
-        transactions: Vec<keys::NockchainTransaction>,
+        mut transactions: Vec<keys::NockchainTransaction>,
         height: u64,
         bits: u32,
     ) -> Self {
+        let first_sorted = usize::from(transactions.first().is_some_and(is_coinbase));
+        transactions[first_sorted..].sort_by(|a, b| a.hash.cmp(&b.hash));
         let merkle_root = calculate_merkle_root(&transactions);
         let timestamp = Utc::now().timestamp() as u64;
 
@@ -254,9 +521,11 @@ impl Block {
                 return Ok(());
             }
 
-            // Update timestamp occasionally during mining
+            // Update timestamp occasionally during mining, never moving it
+            // below what the block was created with
             if nonce % 100000 == 0 {
-                self.header.timestamp = Utc::now().timestamp() as u64;
+                let now = Utc::now().timestamp() as u64;
+                self.header.timestamp = self.header.timestamp.max(now);
             }
         }
 
@@ -274,6 +543,51 @@ impl Block {
             ));
         }
 
+        self.validate_contents()
+    }
+
+    /// Every check of `validate` except proof of work, for blocks a
+    /// checkpoint already vouches for
+    pub fn validate_contents(&self) -> WalletResult<()> {
+        // Every block after genesis starts with its coinbase
+        if self.header.height > 0 && !self.transactions.first().is_some_and(is_coinbase) {
+            return Err(WalletError::BlockValidation(
+                "Block is missing its coinbase transaction".to_string(),
+            ));
+        }
+
+        // No transaction may appear twice, and no output may be spent twice
+        let mut seen_hashes = HashSet::new();
+        let mut spent = HashSet::new();
+        for tx in &self.transactions {
+            if !seen_hashes.insert(tx.hash.as_slice()) {
+                return Err(WalletError::BlockValidation(format!(
+                    "Duplicate transaction {}",
+                    tx.id
+                )));
+            }
+            for input in &tx.inputs {
+                if !spent.insert(&input.previous_output) {
+                    return Err(WalletError::BlockValidation(format!(
+                        "Output {} is spent twice",
+                        input.previous_output
+                    )));
+                }
+            }
+        }
+
+        // After the coinbase, transactions go in hash order, so the merkle
+        // root (which commits to their order) has one valid form
+        let first_sorted = usize::from(self.transactions.first().is_some_and(is_coinbase));
+        if self.transactions[first_sorted..]
+            .windows(2)
+            .any(|pair| pair[0].hash > pair[1].hash)
+        {
+            return Err(WalletError::BlockValidation(
+                "Transactions after the coinbase are not sorted by hash".to_string(),
+            ));
+        }
+
         // Check merkle root
         let calculated_merkle = calculate_merkle_root(&self.transactions);
         if calculated_merkle != self.header.merkle_root {
@@ -283,9 +597,9 @@ impl Block {
         }
 
         // Validate all transactions
-        for tx in &self.transactions {
+        for (index, tx) in self.transactions.iter().enumerate() {
             // Basic transaction validation would go here
-            if tx.inputs.is_empty() {
+            if tx.inputs.is_empty() && index > 0 {
                 return Err(WalletError::BlockValidation(
                     "Transaction has no inputs".to_string(),
                 ));
@@ -295,6 +609,15 @@ impl Block {
                     "Transaction has no outputs".to_string(),
                 ));
             }
+            if let Some(expiry) = tx
+                .expiry_height
+                .filter(|_| tx.is_expired_at(self.header.height))
+            {
+                return Err(WalletError::BlockValidation(format!(
+                    "Transaction {} expired at height {}",
+                    tx.id, expiry
+                )));
+            }
         }
 
         Ok(())
@@ -304,49 +627,105 @@ impl Block {
     pub fn hash(&self) -> [u8; 32] {
         self.header.hash()
     }
+
+    /// Whether the block contains `tx_id` and its merkle root matches its transactions
+    pub fn commits_to(&self, tx_id: &str) -> bool {
+        self.transactions.iter().any(|tx| tx.id == tx_id)
+            && calculate_merkle_root(&self.transactions) == self.header.merkle_root
+    }
+
+    /// Path from `tx_id` up to this block's merkle root, if the block contains
+    /// it and commits to its transactions
+    pub fn merkle_proof(&self, tx_id: &str) -> Option<receipt::MerkleProof> {
+        if !self.commits_to(tx_id) {
+            return None;
+        }
+        let mut index = self.transactions.iter().position(|tx| tx.id == tx_id)?;
+        let mut hashes: Vec<[u8; 32]> = self.transactions.iter().map(merkle_leaf).collect();
+
+        let position = index as u32;
+        let mut siblings = Vec::new();
+        while hashes.len() > 1 {
+            // The last hash of an odd level is paired with itself
+            let sibling = hashes.get(index ^ 1).unwrap_or(&hashes[index]);
+            siblings.push(hex::encode(sibling));
+            hashes = hashes
+                .chunks(2)
+                .map(|chunk| merkle_parent(&chunk[0], chunk.get(1).unwrap_or(&chunk[0])))
+                .collect();
+            index /= 2;
+        }
+
+        Some(receipt::MerkleProof {
+            block_height: self.header.height,
+            index: position,
+            siblings,
+        })
+    }
 }
 
-/// Calculate merkle root of transactions
-fn calculate_merkle_root(transactions: &[keys::NockchainTransaction]) -> [u8; 32] {
+/// A coinbase mints new coins, so it spends nothing
+fn is_coinbase(tx: &keys::NockchainTransaction) -> bool {
+    tx.inputs.is_empty() && !tx.outputs.is_empty()
+}
+
+/// Leaf hash of a transaction: its hash, truncated or zero-padded to 32 bytes
+pub(crate) fn merkle_leaf_hash(tx_hash: &[u8]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    let len = std::cmp::min(32, tx_hash.len());
+    hash[..len].copy_from_slice(&tx_hash[..len]);
+    hash
+}
+
+fn merkle_leaf(tx: &keys::NockchainTransaction) -> [u8; 32] {
+    merkle_leaf_hash(&tx.hash)
+}
+
+/// Hash of two sibling nodes, left first
+pub(crate) fn merkle_parent(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
     use sha2::{Digest, Sha256};
 
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Merkle root of transactions in the order given, so reordering or
+/// repeating a transaction changes it
+fn calculate_merkle_root(transactions: &[keys::NockchainTransaction]) -> [u8; 32] {
     if transactions.is_empty() {
         return [0u8; 32];
     }
 
-    let mut hashes: Vec<[u8; 32]> = transactions
-        .iter()
-        .map(|tx| {
-            let mut hash = [0u8; 32];
-            let len = std::cmp::min(32, tx.hash.len());
-            hash[..len].copy_from_slice(&tx.hash[..len]);
-            hash
-        })
-        .collect();
+    let mut hashes: Vec<[u8; 32]> = transactions.iter().map(merkle_leaf).collect();
 
     while hashes.len() > 1 {
-        let mut next_level = Vec::new();
-
-        for chunk in hashes.chunks(2) {
-            let mut hasher = Sha256::new();
-            hasher.update(&chunk[0]);
-
-            if chunk.len() == 2 {
-                hasher.update(&chunk[1]);
-            } else {
-                // Odd number of hashes, duplicate the last one
-                hasher.update(&chunk[0]);
-            }
-
-            next_level.push(hasher.finalize().into());
-        }
-
-        hashes = next_level;
+        hashes = hashes
+            .chunks(2)
+            // Odd number of hashes, duplicate the last one
+            .map(|chunk| merkle_parent(&chunk[0], chunk.get(1).unwrap_or(&chunk[0])))
+            .collect();
     }
 
     hashes[0]
 }
 
+/// Difficulty of `bits` relative to 0x1d00ffff, the Bitcoin-style minimum:
+/// how many times more work a block needs than at that target
+pub fn difficulty(bits: u32) -> f64 {
+    let value = |target: [u8; 32]| {
+        target
+            .iter()
+            .fold(0.0_f64, |value, byte| value * 256.0 + *byte as f64)
+    };
+    let target = value(difficulty_to_target(bits));
+    if target == 0.0 {
+        return 0.0;
+    }
+    value(difficulty_to_target(0x1d00ffff)) / target
+}
+
 /// Convert difficulty bits to target hash
 fn difficulty_to_target(bits: u32) -> [u8; 32] {
     let exponent = ((bits >> 24) & 0xff) as usize;
@@ -359,7 +738,7 @@ fn difficulty_to_target(bits: u32) -> [u8; 32] {
         target[29] = (target_value >> 16) as u8;
         target[30] = (target_value >> 8) as u8;
         target[31] = target_value as u8;
-    } else if exponent < 32 {
+    } else if exponent <= 32 {
         let start_byte = 32 - exponent;
         target[start_byte] = (mantissa >> 16) as u8;
         target[start_byte + 1] = (mantissa >> 8) as u8;
@@ -377,6 +756,57 @@ pub struct BlockchainConfig {
     pub difficulty_adjustment_interval: u64, // blocks
     pub max_block_size: usize,
     pub genesis_hash: [u8; 32],
+    /// Network the chain belongs to; with the genesis hash it makes the chain id
+    #[serde(default)]
+    pub network: AddressNetwork,
+    /// Blocks with a newer header version are rejected
+    #[serde(default = "default_max_header_version")]
+    pub max_header_version: u32,
+    /// How far ahead of local time a block timestamp may be, in seconds
+    #[serde(default = "default_max_future_block_time")]
+    pub max_future_block_time: u64,
+    /// Block hashes every accepted chain must contain
+    #[serde(default = "default_checkpoints")]
+    pub checkpoints: Vec<checkpoints::Checkpoint>,
+    /// Skip proof-of-work checks on blocks below the latest checkpoint. Off
+    /// by default: a block is only tied to the checkpoint once the chain
+    /// reaches it, so until then blocks without work would be accepted.
+    #[serde(default = "default_skip_pow_below_checkpoint")]
+    pub skip_pow_below_checkpoint: bool,
+}
+
+fn default_max_header_version() -> u32 {
+    encoding::MAX_HEADER_VERSION
+}
+
+fn default_max_future_block_time() -> u64 {
+    2 * 60 * 60
+}
+
+fn default_checkpoints() -> Vec<checkpoints::Checkpoint> {
+    checkpoints::MAINNET_CHECKPOINTS.to_vec()
+}
+
+fn default_skip_pow_below_checkpoint() -> bool {
+    false
+}
+
+impl BlockchainConfig {
+    /// Fakenet settings: trivial proof of work so blocks can be mined locally
+    pub fn fakenet() -> Self {
+        Self {
+            initial_difficulty: 0x207fffff,
+            network: AddressNetwork::Fakenet,
+            // Mainnet checkpoints mean nothing on a local chain
+            checkpoints: Vec::new(),
+            ..Self::default()
+        }
+    }
+
+    /// Id every transaction on this chain is signed for
+    pub fn chain_id(&self) -> [u8; 32] {
+        encoding::chain_id(self.network, &self.genesis_hash)
+    }
 }
 
 impl Default for BlockchainConfig {
@@ -387,6 +817,11 @@ impl Default for BlockchainConfig {
             difficulty_adjustment_interval: 2016, // ~2 weeks
             max_block_size: 1_000_000,            // 1MB
             genesis_hash: [0u8; 32],
+            network: AddressNetwork::Mainnet,
+            max_header_version: default_max_header_version(),
+            max_future_block_time: default_max_future_block_time(),
+            checkpoints: default_checkpoints(),
+            skip_pow_below_checkpoint: default_skip_pow_below_checkpoint(),
         }
     }
 }
@@ -397,6 +832,12 @@ pub struct WalletConfig {
     pub network: NetworkConfig,
     pub security: SecurityConfig,
     pub blockchain: BlockchainConfig,
+    /// Endpoints told about wallet events
+    #[serde(default)]
+    pub webhooks: Vec<webhooks::WebhookConfig>,
+    /// How long logs, fee history, audit entries and settled transactions are kept
+    #[serde(default)]
+    pub retention: retention::RetentionPolicy,
 }
 
 /// Network configuration
@@ -416,12 +857,1075 @@ pub struct SecurityConfig {
     pub pin_timeout_minutes: u64,
     pub enable_biometrics: bool,
     pub auto_lock_minutes: u64,
+    /// Sends above this many base units need a typed confirmation
+    #[serde(default)]
+    pub per_transaction_limit: Option<u64>,
+    /// Most base units that may be sent, fees included, in any 24 hours
+    #[serde(default)]
+    pub daily_limit: Option<u64>,
+}
+
+/// File the per-key receive chains are persisted to
+pub const ADDRESS_STATE_FILE: &str = "addresses.json";
+
+/// File the labels of the wallet's own addresses are persisted to
+pub const ADDRESS_LABELS_FILE: &str = "address_labels.json";
+
+/// Lockfile inside the wallet data dir held by the process that may write to it
+pub const WALLET_LOCK_FILE: &str = "wallet.lock";
+
+/// Wallet state shared by the UI and RPC server
+#[derive(Debug)]
+pub struct Wallet {
+    keys: keys::NockchainKeyManager,
+    balances: balance::BalanceManager,
+    transactions: transaction::TransactionManager,
+    session: session::Session,
+    last_sync: Option<DateTime<Utc>>,
+    mining_rewards: Vec<mining::MiningReward>,
+    /// Data dir this wallet was opened on, if any
+    data_dir: Option<PathBuf>,
+    /// Held while this handle may write; `None` in read-only mode or when the
+    /// wallet was not opened on a data dir
+    lockfile: Option<lockfile::Lockfile>,
+    read_only: bool,
+    /// Storage schema the data dir was last written with, if recorded
+    schema: Option<u32>,
+    audit: audit::AuditLog,
+    spending: spending::SpendingLedger,
+    templates: templates::PaymentTemplates,
+    /// Receive addresses watched for one expected payment each
+    payment_requests: payment_request::PaymentRequests,
+    /// Last integrity check; a critical result keeps the wallet read-only
+    integrity: Option<integrity::IntegrityReport>,
+    display: amount::DisplaySettings,
+    /// Operations under way, so one interrupted by a crash can be recovered
+    journal: journal::OperationJournal,
+    /// Fee rates of recent blocks, for fee suggestions
+    fees: fees::FeeEstimator,
+    webhooks: webhooks::WebhookQueue,
+    /// Downloads of the last filtered sync
+    filter_sync: Option<filters::FilterSyncStats>,
+    /// Chain new transactions are signed for
+    chain_id: [u8; 32],
+    retention: retention::RetentionPolicy,
+    /// Last maintenance run in this session
+    last_maintenance: Option<retention::MaintenanceReport>,
+}
+
+impl Default for Wallet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Wallet {
+    pub fn new() -> Self {
+        Self {
+            keys: keys::NockchainKeyManager::new(),
+            balances: balance::BalanceManager::new(),
+            transactions: transaction::TransactionManager::new(),
+            session: session::Session::default(),
+            last_sync: None,
+            mining_rewards: Vec::new(),
+            data_dir: None,
+            lockfile: None,
+            read_only: false,
+            schema: None,
+            audit: audit::AuditLog::new(),
+            spending: spending::SpendingLedger::default(),
+            templates: templates::PaymentTemplates::new(),
+            payment_requests: payment_request::PaymentRequests::new(),
+            integrity: None,
+            display: amount::DisplaySettings::default(),
+            journal: journal::OperationJournal::default(),
+            fees: fees::FeeEstimator::default(),
+            webhooks: webhooks::WebhookQueue::default(),
+            filter_sync: None,
+            chain_id: BlockchainConfig::default().chain_id(),
+            retention: retention::RetentionPolicy::default(),
+            last_maintenance: None,
+        }
+    }
+
+    /// Open the wallet stored in `data_dir`, taking its lockfile. If another
+    /// process holds the lock, a critical file fails `verify_integrity`, or a
+    /// newer version wrote the data dir, the wallet opens read-only: balances,
+    /// history and receive addresses work, but anything that writes returns
+    /// `ReadOnly`.
+    pub fn open(data_dir: &Path) -> WalletResult<Self> {
+        std::fs::create_dir_all(data_dir)
+            .map_err(|e| WalletError::Storage(format!("Failed to create data directory: {}", e)))?;
+
+        let mut wallet = Self {
+            data_dir: Some(data_dir.to_path_buf()),
+            schema: storage::read_schema_version(data_dir)?,
+            ..Self::new()
+        };
+        if let Some(e) = wallet.unsupported_schema() {
+            tracing::warn!("{}", e);
+        }
+        wallet.verify_integrity();
+        if !wallet.try_upgrade_to_writable()? {
+            wallet.read_only = true;
+        }
+        Ok(wallet)
+    }
+
+    /// Whether another process holds the wallet lock, so this handle can't write
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Directory the wallet's files are saved in, if it was opened from one
+    pub fn data_dir(&self) -> Option<&Path> {
+        self.data_dir.as_deref()
+    }
+
+    /// Storage schema the data dir was last written with, if recorded
+    pub fn storage_schema(&self) -> Option<u32> {
+        self.schema
+    }
+
+    /// Whether a newer version wrote the data dir. The wallet then stays
+    /// read-only, so this version can't overwrite files it doesn't understand.
+    pub fn schema_too_new(&self) -> bool {
+        self.unsupported_schema().is_some()
+    }
+
+    /// `ReadOnly` naming the schema, if a newer version wrote the data dir
+    fn unsupported_schema(&self) -> Option<WalletError> {
+        let version = self
+            .schema
+            .filter(|version| *version > storage::STORAGE_SCHEMA_VERSION)?;
+        Some(WalletError::ReadOnly(format!(
+            "wallet data was written with storage schema {}; this version supports up to {}",
+            version,
+            storage::STORAGE_SCHEMA_VERSION
+        )))
+    }
+
+    /// Try to take the wallet lock again, e.g. after the other instance exited.
+    /// Returns whether the wallet is now writable; never while the last
+    /// integrity check failed.
+    pub fn try_upgrade_to_writable(&mut self) -> WalletResult<bool> {
+        if self.integrity_failed() {
+            return Ok(false);
+        }
+        self.take_lockfile()
+    }
+
+    /// Take the wallet lock, ignoring the integrity check, and record the
+    /// storage schema. Never succeeds while a newer version wrote the data dir.
+    fn take_lockfile(&mut self) -> WalletResult<bool> {
+        if self.schema_too_new() {
+            return Ok(false);
+        }
+        let Some(data_dir) = self.data_dir.as_deref() else {
+            return Ok(true);
+        };
+        if self.lockfile.is_some() {
+            return Ok(true);
+        }
+
+        let mut lockfile = lockfile::Lockfile::new(data_dir, WALLET_LOCK_FILE, "wallet");
+        match lockfile.acquire() {
+            Ok(()) => {
+                if self.schema != Some(storage::STORAGE_SCHEMA_VERSION) {
+                    storage::write_schema_version(data_dir)?;
+                    self.schema = Some(storage::STORAGE_SCHEMA_VERSION);
+                }
+                self.lockfile = Some(lockfile);
+                self.read_only = false;
+                Ok(true)
+            }
+            Err(lockfile::LockfileError::Held { .. }) => Ok(false),
+            Err(lockfile::LockfileError::Io(message)) => Err(WalletError::Storage(message)),
+        }
+    }
+
+    /// Fail with `ReadOnly` unless this handle may write to the wallet
+    pub fn ensure_writable(&self) -> WalletResult<()> {
+        if let Some(e) = self.unsupported_schema() {
+            return Err(e);
+        }
+        if self.integrity_failed() {
+            return Err(WalletError::ReadOnly(
+                "wallet files failed the integrity check".to_string(),
+            ));
+        }
+        if self.read_only {
+            return Err(WalletError::ReadOnly(
+                "another instance has this wallet open".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn keys(&self) -> &keys::NockchainKeyManager {
+        &self.keys
+    }
+
+    /// Key management; fails in read-only mode
+    pub fn keys_mut(&mut self) -> WalletResult<&mut keys::NockchainKeyManager> {
+        self.ensure_writable()?;
+        Ok(&mut self.keys)
+    }
+
+    /// Generate a key from the wallet seed, recording it in the audit log
+    pub fn generate_key(&mut self, name: &str) -> WalletResult<Address> {
+        let result = self.keys_mut().and_then(|keys| {
+            keys.generate_key(name.to_string())
+                .map(|key| key.address().clone())
+        });
+        self.record_audit(
+            audit::AuditEvent::KeyGenerated {
+                key_name: name.to_string(),
+            },
+            audit::AuditOutcome::of(&result),
+        );
+        result
+    }
+
+    /// Import every key in a file, recording each imported key in the audit log
+    pub fn import_keys_from_file(
+        &mut self,
+        path: &Path,
+        format: key_import::KeyImportFormat,
+    ) -> WalletResult<key_import::KeyImportReport> {
+        let result = self
+            .keys_mut()
+            .and_then(|keys| keys.import_keys_from_file(path, format));
+        match &result {
+            Ok(report) => {
+                for entry in &report.entries {
+                    let outcome = match &entry.outcome {
+                        key_import::KeyImportOutcome::Imported => audit::AuditOutcome::Success,
+                        key_import::KeyImportOutcome::SkippedDuplicate(_) => continue,
+                        key_import::KeyImportOutcome::Failed(reason) => {
+                            audit::AuditOutcome::Failure(reason.clone())
+                        }
+                    };
+                    self.record_audit(
+                        audit::AuditEvent::KeyImported {
+                            key_name: entry.name.clone(),
+                        },
+                        outcome,
+                    );
+                }
+            }
+            Err(e) => self.record_audit(
+                audit::AuditEvent::KeyImported {
+                    key_name: path.display().to_string(),
+                },
+                audit::AuditOutcome::Failure(e.to_string()),
+            ),
+        }
+        result
+    }
+
+    pub fn balances(&self) -> &balance::BalanceManager {
+        &self.balances
+    }
+
+    pub fn balances_mut(&mut self) -> &mut balance::BalanceManager {
+        &mut self.balances
+    }
+
+    /// Unspent funds split into spendable, awaiting confirmations, immature,
+    /// locked and watch-only totals
+    pub fn spendable_summary(&self) -> balance::SpendableSummary {
+        self.balances
+            .spendable_summary(|address| self.keys.is_watch_only(address))
+    }
+
+    /// Every address holding unspent funds or already used with its balance,
+    /// grouped by kind so change addresses come after the receive addresses.
+    /// Change is only credited once a sync sees it, so a used change address
+    /// is listed even while its balance is zero.
+    pub fn address_breakdown(&self) -> Vec<balance::AddressBalance> {
+        let mut breakdown: Vec<balance::AddressBalance> = self
+            .keys
+            .watched_addresses()
+            .into_iter()
+            .filter_map(|address| {
+                let balance = self.balances.get_balance(&address);
+                if balance.confirmed + balance.unconfirmed == 0
+                    && !self.keys.is_address_used(&address)
+                {
+                    return None;
+                }
+                Some(balance::AddressBalance {
+                    kind: self.keys.address_kind(&address)?,
+                    key_name: self.keys.owner_of(&address).map(str::to_string),
+                    address,
+                    balance,
+                })
+            })
+            .collect();
+        breakdown.sort_by_key(|row| row.kind);
+        breakdown
+    }
+
+    pub fn transactions(&self) -> &transaction::TransactionManager {
+        &self.transactions
+    }
+
+    pub fn transactions_mut(&mut self) -> &mut transaction::TransactionManager {
+        &mut self.transactions
+    }
+
+    pub fn last_sync(&self) -> Option<DateTime<Utc>> {
+        self.last_sync
+    }
+
+    /// Chain new transactions are signed for; mainnet until set
+    pub fn chain_id(&self) -> [u8; 32] {
+        self.chain_id
+    }
+
+    /// Sign new transactions for the chain with id `chain_id`, as given by
+    /// `BlockchainConfig::chain_id` or `NockchainNodeConfig::chain_id`
+    pub fn set_chain_id(&mut self, chain_id: [u8; 32]) {
+        self.chain_id = chain_id;
+    }
+
+    /// Record a transaction, classifying it against the wallet's own addresses.
+    /// `funded_by_wallet` is true when the wallet supplied the inputs.
+    pub fn record_transaction(
+        &mut self,
+        signed_tx: transaction::SignedTransaction,
+        funded_by_wallet: bool,
+    ) {
+        let keys = &self.keys;
+        self.transactions
+            .add_classified_transaction(signed_tx, funded_by_wallet, |address| {
+                keys.is_mine(address)
+            });
+    }
+
+    /// Record a note seen during sync and mark its address as used
+    pub fn observe_note(&mut self, note: Note) -> WalletResult<()> {
+        self.keys.mark_address_used(&note.address);
+        self.balances.add_note(note)
+    }
+
+    /// Next unused receive address for a key. The advanced index is persisted
+    /// before the address is returned; if saving fails the index is not consumed.
+    /// In read-only mode the chain isn't advanced, so the same address repeats.
+    pub async fn next_receive_address(
+        &mut self,
+        key_name: &str,
+        storage: &storage::StorageManager,
+    ) -> WalletResult<Address> {
+        if self.read_only {
+            return self.keys.peek_receive_address(key_name);
+        }
+
+        let previous = self
+            .keys
+            .address_chain(key_name)
+            .cloned()
+            .ok_or_else(|| WalletError::KeyNotFound(key_name.to_string()))?;
+        let address = self.keys.next_receive_address(key_name)?;
+
+        if let Err(e) = storage
+            .save(ADDRESS_STATE_FILE, self.keys.address_chains())
+            .await
+        {
+            self.keys.replace_address_chain(key_name, previous);
+            return Err(e);
+        }
+        Ok(address)
+    }
+
+    /// Next receive address for a key, labelled with `label` unless it is blank.
+    /// Without a data dir the chain is only advanced in memory.
+    pub async fn new_receive_address(
+        &mut self,
+        key_name: &str,
+        label: &str,
+    ) -> WalletResult<Address> {
+        let address = match self.data_dir.clone() {
+            Some(data_dir) => {
+                let storage = storage::StorageManager::new(data_dir)?;
+                self.next_receive_address(key_name, &storage).await?
+            }
+            None => self.keys_mut()?.next_receive_address(key_name)?,
+        };
+        if !label.trim().is_empty() {
+            self.set_address_label(&address, label)?;
+        }
+        Ok(address)
+    }
+
+    /// Label one of the wallet's own addresses; kept in memory until
+    /// `save_address_labels`
+    pub fn set_address_label(&mut self, address: &Address, label: &str) -> WalletResult<()> {
+        self.keys_mut()?.set_address_label(address, label)
+    }
+
+    /// Load the address labels saved in the wallet data dir
+    pub async fn load_address_labels(&mut self) -> WalletResult<()> {
+        let Some(data_dir) = self.data_dir.clone() else {
+            return Ok(());
+        };
+        let storage = storage::StorageManager::new(data_dir)?;
+        if !storage.exists(ADDRESS_LABELS_FILE) {
+            return Ok(());
+        }
+        let saved: BTreeMap<String, String> = storage.load(ADDRESS_LABELS_FILE).await?;
+        let labels = saved
+            .into_iter()
+            .map(|(address, label)| Ok((Address::from_string(&address)?, label)))
+            .collect::<WalletResult<_>>()?;
+        self.keys.restore_address_labels(labels);
+        Ok(())
+    }
+
+    /// Save the address labels, keyed by address string; does nothing in
+    /// read-only mode
+    pub async fn save_address_labels(&self) -> WalletResult<()> {
+        let Some(data_dir) = self.data_dir.clone().filter(|_| !self.read_only) else {
+            return Ok(());
+        };
+        let storage = storage::StorageManager::new(data_dir)?;
+        let labels: BTreeMap<String, &String> = self
+            .keys
+            .address_labels()
+            .iter()
+            .map(|(address, label)| (address.to_string(), label))
+            .collect();
+        storage.save(ADDRESS_LABELS_FILE, &labels).await
+    }
+
+    /// Gap-limit scan over every key's receive chain against the notes observed so
+    /// far, for restore and rescan. Returns the number of used addresses found.
+    pub fn scan_for_addresses(&mut self, gap_limit: u32) -> WalletResult<usize> {
+        let balances = &self.balances;
+        let mut used = 0;
+        for key_name in self.keys.list_keys() {
+            used += self.keys.scan_chain(&key_name, gap_limit, |address| {
+                !balances.get_notes_for_address(address).is_empty()
+            })?;
+        }
+        Ok(used)
+    }
+
+    /// Everything known about one transaction, joined with the mempool and chain
+    /// when they are available
+    pub fn transaction_detail(
+        &self,
+        tx_id: &str,
+        mempool: Option<&mempool::Mempool>,
+        chain: Option<&chain::Blockchain>,
+    ) -> Option<transaction::TransactionDetail> {
+        let mut detail = self.transactions.get_transaction_detail(
+            tx_id,
+            |address| self.keys.is_mine(address),
+            self.balances.tip_height(),
+            mempool,
+            chain,
+        )?;
+        for output in &mut detail.outputs {
+            output.address_label = Address::from_string(&output.address)
+                .ok()
+                .and_then(|address| self.keys.address_label(&address))
+                .map(str::to_string);
+        }
+        Some(detail)
+    }
+
+    /// Append to the audit log. Entries are kept in memory until `save_audit_log`.
+    pub fn record_audit(&mut self, event: audit::AuditEvent, outcome: audit::AuditOutcome) {
+        self.audit.append(event, outcome);
+    }
+
+    /// Audit entries matching `filter`, newest first
+    pub fn audit_log(&self, filter: &audit::AuditFilter) -> Vec<audit::AuditEntry> {
+        self.audit.entries(filter)
+    }
+
+    /// Where the saved audit log failed verification, if it did
+    pub fn audit_chain_break(&self) -> Option<&audit::AuditChainBreak> {
+        self.audit.chain_break()
+    }
+
+    /// Load and verify the audit log saved in the wallet data dir. Entries
+    /// recorded before the load are kept and chained after the saved ones.
+    pub async fn load_audit_log(&mut self) -> WalletResult<()> {
+        let Some(data_dir) = self.data_dir.clone() else {
+            return Ok(());
+        };
+        let storage = storage::StorageManager::new(data_dir)?;
+        self.audit.reload(&storage).await
+    }
+
+    /// Write audit entries recorded since the last save. Does nothing in
+    /// read-only mode; the entries are written once this handle can write.
+    pub async fn save_audit_log(&mut self) -> WalletResult<()> {
+        let Some(data_dir) = self.data_dir.clone().filter(|_| !self.read_only) else {
+            return Ok(());
+        };
+        let storage = storage::StorageManager::new(data_dir)?;
+        self.audit.save(&storage).await
+    }
+
+    /// Record that balances were brought up to date with the node
+    pub fn mark_synced(&mut self) {
+        self.last_sync = Some(Utc::now());
+    }
+
+    /// Combined node, storage, wallet and RPC health. Every probe is time-bounded,
+    /// so a hung node or disk yields a degraded report rather than a stalled caller.
+    pub async fn health_check(&self, node: &network::NodeHandle) -> health::HealthReport {
+        health::check_health(node, self.session.is_unlocked(), self.last_sync).await
+    }
 }
 
 // Re-export important nockchain types for external use
-pub use keys::{NockchainKeyManager, NockchainKeyPair, NockchainTransaction};
+pub use accelerate::DUST_LIMIT;
+pub use address::{AddressError, AddressNetwork, NOCKCHAIN_ADDRESS_PREFIX};
+pub use amount::{
+    AmountUnit, DigitGrouping, DisplaySettings, RoundingMode, DISPLAY_SETTINGS_FILE, NANO_PER_NOCK,
+    NOCK_DECIMALS,
+};
+pub use audit::{
+    AuditCategory, AuditChainBreak, AuditEntry, AuditEvent, AuditFilter, AuditLog, AuditOutcome,
+    AUDIT_LOG_FILE,
+};
+pub use balance::{
+    AddressBalance, BalanceDiscrepancy, ReconciliationReport, SpendableSummary,
+    DEFAULT_MIN_CONFIRMATIONS,
+};
+pub use chain::{Blockchain, CompactionReport, PrunedRange, DEFAULT_KEEP_FULL_BLOCKS};
+pub use checkpoints::{Checkpoint, ValidationPhase, MAINNET_CHECKPOINTS};
+pub use clock::{Clock, ClockJump, ClockMonitor, SystemClock};
+pub use cold_signing::{
+    InputSignature, PackageInput, PackageSignatures, UnsignedTransactionPackage,
+};
+pub use config_builder::{ConfigError, Multiaddr, NockchainNodeConfigBuilder};
+pub use config_file::{
+    ConfigFileError, ConfigLayers, ConfigSource, ConfigSources, NODE_CONFIG_FILE,
+    RUNTIME_CONFIG_FILE,
+};
+pub use console_history::{
+    load_console_history, save_console_history, CONSOLE_HISTORY_FILE, CONSOLE_HISTORY_LINES,
+};
+pub use contacts::{Contact, ContactBook, CONTACTS_FILE};
+pub use data_dir::{DataDirLimits, DataDirUsage, PurgeScope, StorageBreakdown};
+pub use decode::{
+    decode_transaction, DecodeError, DecodedInput, DecodedOutput, DecodedTransaction,
+};
+pub use encoding::{Decode, Encode};
+pub use expiry::ExpiredTransaction;
+pub use explorer::{BlockSummary, BlockTransaction, BlockView, BlocksPage, ChainTransactionView};
+pub use export::{
+    export_ledger_to_writer, export_logs_to_writer, export_to_file, ExportFilter, ExportFormat,
+    ExportSummary, EXPORT_INCOMPLETE_MARKER,
+};
+pub use external::ExternalSpend;
+pub use fees::{package_rate, BlockFees, FeeEstimator, FeeRates, FEE_HISTORY_FILE};
+pub use filters::{BlockFilter, FilterSource, FilterSyncStats};
+pub use geoip::{country_flag, GeoIpDatabase, GeoIpResolver, PeerLocation};
+pub use handshake::{parse_user_agent_version, service_labels, Handshake};
+pub use hd::{AddressChain, Branch, DEFAULT_GAP_LIMIT};
+pub use health::{HealthReport, HealthStatus};
+pub use incoming::IncomingEvent;
+pub use integrity::{FileCheck, FileStatus, IntegrityReport, Severity, MANIFEST_FILE};
+pub use journal::{
+    JournalEntry, Operation, OperationJournal, RecoveryAction, TransactionLookup, JOURNAL_FILE,
+};
+pub use key_import::{KeyImportEntry, KeyImportFormat, KeyImportOutcome, KeyImportReport};
+pub use keys::{
+    AddressKind, NockchainKeyManager, NockchainKeyPair, NockchainTransaction, OutPoint,
+};
+pub use launch::{LaunchError, LaunchOptions, LAUNCH_USAGE};
+pub use ledger::{Ledger, LedgerAccount, LedgerCheck, LedgerEntry, LedgerEvent};
+pub use log_buffer::{
+    sanitize_log_message, LogBufferConfig, LogLevelCounts, LogLevels, LogRateLimit,
+};
+pub use mempool::{Mempool, UtxoSet};
+pub use mining::{MiningPayout, MiningReward, MINING_REWARDS_FILE};
+pub use mnemonic::{
+    check_words, detect_language, generate_mnemonic, validate_mnemonic, MnemonicLanguage,
+    MnemonicWord, MNEMONIC_WORD_COUNTS,
+};
+pub use netsim::NetworkConditions;
 pub use network::{
-    LogEntry, LogLevel, LogSource, NockchainNodeConfig, NockchainNodeManager, NockchainNodeRunner,
-    NodeStatus,
+    LogEntry, LogLevel, LogRepeat, LogSource, NockchainNodeConfig, NockchainNodeManager,
+    NockchainNodeRunner, NodeHandle, NodeStats, NodeStatus,
+};
+pub use payment_request::{
+    PaymentMatch, PaymentRequest, PaymentRequestHandle, PaymentRequestStatus, PaymentRequests,
+    PAYMENT_REQUESTS_FILE,
 };
-pub use transaction::TransactionManager;
+pub use peers::{PeerEvent, PeerInfo, PeerScoringConfig, PeerSource};
+pub use profiles::{WalletProfile, WalletProfileManager, PROFILES_FILE};
+pub use provenance::{NoteOrigin, NoteProvenance};
+pub use proxy::Socks5Proxy;
+pub use rebroadcast::{RebroadcastResult, MIN_REBROADCAST_INTERVAL};
+pub use receipt::{
+    verify_receipt, ChainTipInfo, MerkleProof, Receipt, ReceiptValidity, RECEIPT_QR_PREFIX,
+};
+pub use reject::{
+    aggregate_rejections, RejectCode, Rejection, RelayReport, TxReject, LOCAL_MEMPOOL,
+};
+pub use retention::{
+    CategoryPruned, MaintenanceReport, RetentionCategory, RetentionPolicy, RetentionRule,
+    RETENTION_FILE,
+};
+pub use search::{MatchRank, SearchResult, SearchTarget};
+pub use secret_key::{
+    encode_secret_key, preview_secret_key, SecretKeyError, SecretKeyFormat, SecretKeyPreview,
+    SECRET_KEY_VERSION,
+};
+pub use selftest::{CheckResult, CheckStatus};
+pub use send::{SendError, SendFailure, SendProgress, SendRequest, SendStage};
+pub use send_draft::{SendDraft, TemplateDraft, SEND_DRAFT_FILE};
+pub use session::{Session, SessionKey, DEFAULT_AUTO_LOCK_MINUTES, DEFAULT_REAUTH_MINUTES};
+pub use spending::{DailyAllowance, SpendRecord, SpendingLedger, SpendingLimits, SPENDING_FILE};
+pub use storage::{StorageWriteStats, MAX_BATCH_DELAY, STORAGE_SCHEMA_VERSION};
+pub use sync_progress::{SyncProgress, SyncTracker};
+pub use templates::{FeePriority, PaymentTemplate, PaymentTemplates, TEMPLATES_FILE};
+pub use transaction::{MerkleProofStatus, OutputDetail, TransactionDetail, TransactionManager};
+pub use ui_prefs::{load_ui_prefs, save_ui_prefs, UiPrefs, UI_PREFS_FILE};
+pub use uptime::{AvailabilityReport, DailyUptime, StatusTransition, TransitionCause, UPTIME_FILE};
+pub use watchdog::{StartPhase, StartWatchdog, DEFAULT_START_TIMEOUT_SECS};
+pub use webhooks::{
+    DeliveryStatus, WalletEvent, WebhookConfig, WebhookDelivery, WebhookEventType, WebhookQueue,
+    WEBHOOKS_FILE,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{
+        fake_address, fake_block, fake_coinbase, fake_note, fake_signed_transaction, TempDir,
+    };
+    use crate::wallet::hd::{derive_address, Branch};
+
+    const SEED: [u8; 32] = [9; 32];
+
+    fn receive(index: u32) -> Address {
+        derive_address(&SEED, Branch::Receive, 0, index)
+    }
+
+    /// A wallet restored from `SEED` with one key, "main"
+    fn restored_wallet() -> Wallet {
+        let mut wallet = Wallet::new();
+        wallet.keys = keys::NockchainKeyManager::from_seed(SEED);
+        wallet.generate_key("main").unwrap();
+        wallet
+    }
+
+    #[tokio::test]
+    async fn restoring_finds_funds_at_7_and_19_and_receives_past_them() {
+        let mut wallet = restored_wallet();
+        for (index, amount) in [(7, 500), (19, 700)] {
+            wallet
+                .observe_note(fake_note().address(receive(index)).amount(amount).build())
+                .unwrap();
+        }
+
+        assert_eq!(wallet.scan_for_addresses(DEFAULT_GAP_LIMIT).unwrap(), 2);
+        let balance = wallet.balances().get_total_balance();
+        assert_eq!(balance.confirmed + balance.unconfirmed, 1_200);
+
+        let dir = TempDir::new("gap-limit");
+        let storage = storage::StorageManager::new(dir.path().to_path_buf()).unwrap();
+        assert_eq!(
+            wallet.next_receive_address("main", &storage).await.unwrap(),
+            receive(20)
+        );
+
+        // The advanced cursor was saved before the address was handed out
+        let saved: std::collections::HashMap<String, AddressChain> =
+            storage.load(ADDRESS_STATE_FILE).await.unwrap();
+        assert_eq!(saved["main"].next_index(), 21);
+        assert_eq!(
+            saved["main"].used_indices().collect::<Vec<_>>(),
+            vec![7, 19]
+        );
+    }
+
+    #[tokio::test]
+    async fn an_address_label_survives_a_reload_and_shows_on_incoming_payments() {
+        let dir = TempDir::new("wallet-labels");
+        let address = {
+            let mut wallet = Wallet::open(dir.path()).unwrap();
+            wallet.keys = keys::NockchainKeyManager::from_seed(SEED);
+            wallet.generate_key("main").unwrap();
+            let address = wallet
+                .new_receive_address("main", " Donations ")
+                .await
+                .unwrap();
+            wallet.save_address_labels().await.unwrap();
+            address
+        };
+
+        let mut reopened = Wallet::open(dir.path()).unwrap();
+        reopened.keys = keys::NockchainKeyManager::from_seed(SEED);
+        reopened.generate_key("main").unwrap();
+        reopened.load_address_labels().await.unwrap();
+        assert_eq!(reopened.keys().address_label(&address), Some("Donations"));
+
+        let incoming = fake_signed_transaction().paying(&address, 500).build();
+        reopened.record_transaction(incoming.clone(), false);
+        let detail = reopened
+            .transaction_detail(&incoming.id, None, None)
+            .unwrap();
+        assert_eq!(
+            detail.outputs[0].address_label.as_deref(),
+            Some("Donations")
+        );
+
+        // A rescan rederives the address and the label still applies
+        reopened
+            .observe_note(fake_note().address(address.clone()).amount(500).build())
+            .unwrap();
+        reopened.scan_for_addresses(DEFAULT_GAP_LIMIT).unwrap();
+        assert_eq!(reopened.keys().address_label(&address), Some("Donations"));
+    }
+
+    #[test]
+    fn only_own_addresses_take_labels_and_a_blank_one_clears_it() {
+        let mut wallet = restored_wallet();
+        assert!(wallet.set_address_label(&fake_address(), "theirs").is_err());
+
+        wallet.set_address_label(&receive(0), "Savings").unwrap();
+        assert_eq!(wallet.keys().address_label(&receive(0)), Some("Savings"));
+        wallet.set_address_label(&receive(0), "  ").unwrap();
+        assert_eq!(wallet.keys().address_label(&receive(0)), None);
+    }
+
+    #[tokio::test]
+    async fn a_second_handle_reads_but_every_write_fails_cleanly() {
+        let dir = TempDir::new("wallet-lock");
+        let mut first = Wallet::open(dir.path()).unwrap();
+        first.keys = keys::NockchainKeyManager::from_seed(SEED);
+        first.generate_key("main").unwrap();
+        first
+            .observe_note(fake_note().address(receive(0)).amount(700).build())
+            .unwrap();
+        assert!(!first.is_read_only());
+        assert!(dir.join(WALLET_LOCK_FILE).exists());
+
+        let mut second = Wallet::open(dir.path()).unwrap();
+        assert!(second.is_read_only());
+        // Stand in for loading the first handle's saved state
+        second.keys = first.keys.clone();
+        second
+            .observe_note(fake_note().address(receive(0)).amount(700).build())
+            .unwrap();
+
+        let balance = second.balances().get_total_balance();
+        assert_eq!(balance.confirmed + balance.unconfirmed, 700);
+        let storage = storage::StorageManager::new(dir.path().to_path_buf()).unwrap();
+        // Receive addresses are shown without advancing the chain
+        for _ in 0..2 {
+            assert_eq!(
+                second.next_receive_address("main", &storage).await.unwrap(),
+                receive(1)
+            );
+        }
+        assert!(!storage.exists(ADDRESS_STATE_FILE));
+
+        let read_only = |result: WalletResult<()>| match result {
+            Err(WalletError::ReadOnly(reason)) => {
+                assert_eq!(reason, "another instance has this wallet open")
+            }
+            other => panic!("expected ReadOnly, got {:?}", other),
+        };
+        read_only(second.generate_key("other").map(|_| ()));
+        read_only(second.keys_mut().map(|_| ()));
+        read_only(second.set_display_settings(amount::DisplaySettings::default()));
+        assert!(second.keys.get_key("other").is_none());
+
+        // Until the first handle lets go, retrying changes nothing
+        assert!(!second.try_upgrade_to_writable().unwrap());
+        drop(first);
+        assert!(second.try_upgrade_to_writable().unwrap());
+        assert!(!second.is_read_only());
+        second.generate_key("other").unwrap();
+    }
+
+    #[tokio::test]
+    async fn data_from_a_newer_schema_opens_read_only_and_is_left_alone() {
+        let dir = TempDir::new("newer-schema");
+        let first = Wallet::open(dir.path()).unwrap();
+        assert_eq!(
+            first.storage_schema(),
+            Some(storage::STORAGE_SCHEMA_VERSION)
+        );
+        assert_eq!(
+            storage::read_schema_version(dir.path()).unwrap(),
+            Some(storage::STORAGE_SCHEMA_VERSION)
+        );
+        drop(first);
+
+        let newer = storage::STORAGE_SCHEMA_VERSION + 1;
+        let marker = format!("{{\"version\": {}}}", newer);
+        std::fs::write(dir.join(storage::SCHEMA_FILE), &marker).unwrap();
+        let mut wallet = Wallet::open(dir.path()).unwrap();
+        assert!(wallet.is_read_only());
+        assert!(wallet.schema_too_new());
+        assert_eq!(wallet.storage_schema(), Some(newer));
+        assert!(!dir.join(WALLET_LOCK_FILE).exists());
+
+        let expected = format!(
+            "wallet data was written with storage schema {}; this version supports up to {}",
+            newer,
+            storage::STORAGE_SCHEMA_VERSION
+        );
+        let refused = |result: WalletResult<()>| match result {
+            Err(WalletError::ReadOnly(reason)) => assert_eq!(reason, expected),
+            other => panic!("expected ReadOnly, got {:?}", other),
+        };
+        refused(wallet.ensure_writable());
+        refused(wallet.generate_key("main").map(|_| ()));
+        refused(wallet.accept_wallet_files().map(|_| ()));
+        assert!(!wallet.try_upgrade_to_writable().unwrap());
+        assert!(wallet.is_read_only());
+        assert_eq!(
+            std::fs::read_to_string(dir.join(storage::SCHEMA_FILE)).unwrap(),
+            marker
+        );
+    }
+
+    #[test]
+    fn the_fakenet_target_fills_the_top_bytes() {
+        let target = difficulty_to_target(BlockchainConfig::fakenet().initial_difficulty);
+        assert_eq!(target[..3], [0x7f, 0xff, 0xff]);
+        assert!(target[3..].iter().all(|byte| *byte == 0));
+        assert!(difficulty(BlockchainConfig::fakenet().initial_difficulty) > 0.0);
+    }
+
+    #[test]
+    fn a_self_send_is_internal_and_costs_only_the_fee() {
+        let mut wallet = restored_wallet();
+        let own = wallet.keys.next_receive_address("main").unwrap();
+        let tx = fake_signed_transaction().paying(&own, 800).fee(15).build();
+        wallet.record_transaction(tx, true);
+
+        let recorded = &wallet.transactions.get_pending_transactions()[0];
+        assert_eq!(recorded.direction, TransactionDirection::Internal);
+        assert_eq!(recorded.to_address, Some(own));
+        assert_eq!(recorded.net_amount(), -15);
+    }
+
+    #[test]
+    fn change_to_a_derived_address_is_not_counted_as_sent() {
+        let mut wallet = restored_wallet();
+        let change = wallet.keys.change_address("main").unwrap();
+        let theirs = fake_address();
+        let tx = fake_signed_transaction()
+            .paying(&theirs, 300)
+            .paying(&change, 650)
+            .fee(10)
+            .build();
+        wallet.record_transaction(tx, true);
+
+        let recorded = &wallet.transactions.get_pending_transactions()[0];
+        assert_eq!(recorded.direction, TransactionDirection::Outgoing);
+        assert_eq!(recorded.amount, 300);
+        assert_eq!(recorded.to_address, Some(theirs));
+        assert_eq!(recorded.net_amount(), -310);
+    }
+
+    #[test]
+    fn payments_from_others_count_only_what_reaches_the_wallet() {
+        let mut wallet = restored_wallet();
+        let own = wallet.keys.next_receive_address("main").unwrap();
+        let tx = fake_signed_transaction()
+            .paying(&fake_address(), 4_000)
+            .paying(&own, 250)
+            .build();
+        wallet.record_transaction(tx, false);
+
+        let recorded = &wallet.transactions.get_pending_transactions()[0];
+        assert_eq!(recorded.direction, TransactionDirection::Incoming);
+        assert_eq!(recorded.net_amount(), 250);
+    }
+
+    /// Block at height 1 with a coinbase and `count` other transactions
+    fn block_with(count: usize) -> Block {
+        let mut block = fake_block().height(1).with_txs(count).build();
+        block
+            .transactions
+            .insert(0, fake_coinbase(&fake_address(), 50));
+        block.header.merkle_root = calculate_merkle_root(&block.transactions);
+        block
+    }
+
+    /// `block` with its transactions edited and the merkle root recomputed,
+    /// so only the rule under test can fail
+    fn edited(mut block: Block, edit: impl FnOnce(&mut Vec<keys::NockchainTransaction>)) -> Block {
+        edit(&mut block.transactions);
+        block.header.merkle_root = calculate_merkle_root(&block.transactions);
+        block
+    }
+
+    fn rejection(block: &Block) -> String {
+        block.validate_contents().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn built_blocks_are_sorted_and_valid() {
+        let block = fake_block().height(1).with_txs(5).build();
+        // `Block::new` leaves a leading coinbase in place and sorts the rest
+        let mut txs = block.transactions.clone();
+        txs.insert(0, fake_coinbase(&fake_address(), 50));
+        let block = Block::new([0; 32], txs, 1, block.header.bits);
+        assert!(is_coinbase(&block.transactions[0]));
+        assert!(block.transactions[1..]
+            .windows(2)
+            .all(|pair| pair[0].hash < pair[1].hash));
+        block.validate_contents().unwrap();
+
+        // Genesis may be empty, later blocks may hold only their coinbase
+        fake_block().build().validate_contents().unwrap();
+        block_with(0).validate_contents().unwrap();
+    }
+
+    #[test]
+    fn a_block_past_a_transactions_expiry_is_rejected() {
+        // `block_with` builds height 1
+        let in_time = edited(block_with(1), |txs| txs[1].expiry_height = Some(1));
+        in_time.validate_contents().unwrap();
+
+        let late = edited(block_with(1), |txs| txs[1].expiry_height = Some(0));
+        let id = late.transactions[1].id.clone();
+        assert_eq!(
+            rejection(&late),
+            format!(
+                "Block validation error: Transaction {} expired at height 0",
+                id
+            )
+        );
+    }
+
+    #[test]
+    fn blocks_after_genesis_need_a_coinbase_first() {
+        let block = fake_block().height(1).with_txs(2).build();
+        assert!(rejection(&block).contains("missing its coinbase"));
+        let empty = fake_block().height(1).build();
+        assert!(rejection(&empty).contains("missing its coinbase"));
+        let coinbase_last = edited(block_with(2), |txs| txs.rotate_left(1));
+        assert!(rejection(&coinbase_last).contains("missing its coinbase"));
+    }
+
+    #[test]
+    fn repeated_transactions_are_rejected() {
+        let block = edited(block_with(2), |txs| txs.push(txs[2].clone()));
+        assert!(rejection(&block).contains("Duplicate transaction"));
+    }
+
+    #[test]
+    fn outputs_spent_twice_are_rejected() {
+        let note = fake_note().build();
+        let mut block = fake_block()
+            .height(1)
+            .spending(&note, &fake_address(), 400)
+            .spending(&note, &fake_address(), 300)
+            .build();
+        block = edited(block, |txs| {
+            txs.insert(0, fake_coinbase(&fake_address(), 50))
+        });
+        assert!(rejection(&block).contains("is spent twice"));
+    }
+
+    #[test]
+    fn transactions_out_of_hash_order_are_rejected() {
+        let block = edited(block_with(3), |txs| txs.swap(1, 3));
+        assert!(rejection(&block).contains("not sorted by hash"));
+    }
+
+    #[test]
+    fn the_merkle_root_commits_to_order_and_repeats() {
+        let block = block_with(3);
+        let txs = &block.transactions;
+        let mut swapped = txs.clone();
+        swapped.swap(1, 2);
+        assert_ne!(calculate_merkle_root(&swapped), block.header.merkle_root);
+
+        // Repeating the last of an odd number of leaves gives the same root,
+        // so the duplicate rule is what stops that block
+        let mut odd = txs[..3].to_vec();
+        let root = calculate_merkle_root(&odd);
+        odd.push(odd[2].clone());
+        assert_eq!(calculate_merkle_root(&odd), root);
+        let padded = edited(block_with(2), |txs| txs.push(txs[2].clone()));
+        assert!(rejection(&padded).contains("Duplicate transaction"));
+
+        // Reordered without fixing the root, the block no longer matches it
+        let mut tampered = block.clone();
+        tampered.transactions.swap(1, 2);
+        assert!(tampered.validate_contents().is_err());
+        assert!(!tampered.commits_to(&txs[1].id));
+    }
+
+    #[test]
+    fn merkle_proofs_use_the_positions_in_the_block() {
+        let block = block_with(4);
+        for (position, tx) in block.transactions.iter().enumerate() {
+            let proof = block.merkle_proof(&tx.id).unwrap();
+            assert_eq!(proof.index as usize, position);
+            assert_eq!(proof.root(&tx.hash), Some(block.header.merkle_root));
+        }
+        assert!(block.merkle_proof("not-in-block").is_none());
+    }
+
+    #[test]
+    fn a_block_breaking_several_rules_reports_one_and_fixing_it_validates() {
+        let note = fake_note().build();
+        let spends = fake_block()
+            .height(1)
+            .spending(&note, &fake_address(), 400)
+            .spending(&note, &fake_address(), 300)
+            .build()
+            .transactions;
+        // No coinbase, a double spend, a repeat, and descending hashes
+        let mut broken = edited(block_with(2), |txs| {
+            txs.remove(0);
+            txs.extend(spends.clone());
+            txs.push(txs[0].clone());
+            txs.sort_by(|a, b| b.hash.cmp(&a.hash));
+        });
+        assert!(rejection(&broken).contains("missing its coinbase"));
+
+        // Whichever of the repeat and the double spend comes first is reported
+        broken = edited(broken, |txs| {
+            txs.insert(0, fake_coinbase(&fake_address(), 50))
+        });
+        let reason = rejection(&broken);
+        assert!(reason.contains("is spent twice") || reason.contains("Duplicate transaction"));
+        broken = edited(broken, |txs| txs.retain(|tx| tx.id != spends[1].id));
+        let reason = rejection(&broken);
+        assert!(reason.contains("Duplicate transaction"), "{}", reason);
+        broken = edited(broken, |txs| txs.dedup_by(|a, b| a.hash == b.hash));
+        assert!(rejection(&broken).contains("not sorted by hash"));
+
+        let fixed = Block::new([0; 32], broken.transactions, 1, broken.header.bits);
+        fixed.validate_contents().unwrap();
+        assert_eq!(fixed.transactions.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn a_failed_save_does_not_consume_the_address() {
+        let mut wallet = restored_wallet();
+        let dir = TempDir::new("gap-limit-save");
+        let storage = storage::StorageManager::new(dir.path().to_path_buf()).unwrap();
+        // A directory where the state file should go makes every save fail
+        std::fs::create_dir_all(dir.join(ADDRESS_STATE_FILE)).unwrap();
+
+        assert!(wallet.next_receive_address("main", &storage).await.is_err());
+        assert_eq!(wallet.keys.address_chain("main").unwrap().next_index(), 0);
+
+        std::fs::remove_dir(dir.join(ADDRESS_STATE_FILE)).unwrap();
+        assert_eq!(
+            wallet.next_receive_address("main", &storage).await.unwrap(),
+            receive(0)
+        );
+    }
+}
@@ -0,0 +1,261 @@
+//! Simulated network faults for fakenet.
+//!
+//! Reorg and relay behaviour needs testing under bad network conditions. On
+//! fakenet, `NodeHandle::set_network_conditions` injects latency, message
+//! loss and partitions into the peer transport; every dial and message to a
+//! peer passes through `NetworkConditions::deliver` first. Outside fakenet
+//! only the default, which changes nothing, is accepted.
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::time::Duration;
+
+use crate::wallet::{WalletError, WalletResult};
+
+/// Faults applied to traffic with peers. The default is a clean network.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkConditions {
+    /// Delay added to every message, in milliseconds
+    pub latency_ms: u64,
+    /// Delay for particular links by peer id, replacing `latency_ms`
+    pub link_latency_ms: BTreeMap<String, u64>,
+    /// Chance from 0 to 1 that a message is lost
+    pub drop_rate: f64,
+    /// Peer ids on the far side of a partition; nothing reaches them
+    pub partitioned: BTreeSet<String>,
+}
+
+impl NetworkConditions {
+    /// Whether any fault is set
+    pub fn is_active(&self) -> bool {
+        *self != Self::default()
+    }
+
+    /// Check the values, e.g. before accepting them from the UI or RPC
+    pub fn validate(&self) -> WalletResult<()> {
+        if !(0.0..=1.0).contains(&self.drop_rate) {
+            return Err(WalletError::Network(format!(
+                "Drop rate must be between 0 and 1, got {}",
+                self.drop_rate
+            )));
+        }
+        Ok(())
+    }
+
+    /// Delay of a message to or from `peer_id`
+    pub fn latency(&self, peer_id: &str) -> Duration {
+        let ms = self
+            .link_latency_ms
+            .get(peer_id)
+            .copied()
+            .unwrap_or(self.latency_ms);
+        Duration::from_millis(ms)
+    }
+
+    pub fn is_partitioned(&self, peer_id: &str) -> bool {
+        self.partitioned.contains(peer_id)
+    }
+
+    /// Wait out the link's latency, then decide whether a message to
+    /// `peer_id` gets through
+    pub async fn deliver(&self, peer_id: &str) -> bool {
+        let latency = self.latency(peer_id);
+        if !latency.is_zero() {
+            tokio::time::sleep(latency).await;
+        }
+        !self.is_partitioned(peer_id) && !self.drops(&mut rand::thread_rng())
+    }
+
+    /// Roll for message loss
+    pub fn drops(&self, rng: &mut impl Rng) -> bool {
+        self.drop_rate > 0.0 && rng.gen_bool(self.drop_rate.clamp(0.0, 1.0))
+    }
+}
+
+impl fmt::Display for NetworkConditions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if self.latency_ms > 0 {
+            parts.push(format!("{}ms latency", self.latency_ms));
+        }
+        if !self.link_latency_ms.is_empty() {
+            let links = self.link_latency_ms.len();
+            parts.push(format!(
+                "custom latency on {} link{}",
+                links,
+                if links == 1 { "" } else { "s" }
+            ));
+        }
+        if self.drop_rate > 0.0 {
+            parts.push(format!("{:.0}% drop", self.drop_rate * 100.0));
+        }
+        if !self.partitioned.is_empty() {
+            let peers = self.partitioned.len();
+            parts.push(format!(
+                "{} peer{} partitioned",
+                peers,
+                if peers == 1 { "" } else { "s" }
+            ));
+        }
+        if parts.is_empty() {
+            f.write_str("none")
+        } else {
+            f.write_str(&parts.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_signed_transaction, SimNetwork};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::time::Instant;
+
+    fn dropping(drop_rate: f64) -> NetworkConditions {
+        NetworkConditions {
+            drop_rate,
+            ..NetworkConditions::default()
+        }
+    }
+
+    #[test]
+    fn any_fault_makes_the_conditions_active() {
+        assert!(!NetworkConditions::default().is_active());
+        assert!(NetworkConditions {
+            latency_ms: 1,
+            ..NetworkConditions::default()
+        }
+        .is_active());
+        assert!(dropping(0.1).is_active());
+        assert!(NetworkConditions {
+            partitioned: BTreeSet::from(["peer".to_string()]),
+            ..NetworkConditions::default()
+        }
+        .is_active());
+    }
+
+    #[test]
+    fn drop_rates_outside_zero_to_one_are_refused() {
+        assert!(dropping(0.0).validate().is_ok());
+        assert!(dropping(1.0).validate().is_ok());
+        for rate in [-0.1, 1.5, f64::NAN] {
+            let err = dropping(rate).validate().unwrap_err();
+            assert!(err.to_string().contains("between 0 and 1"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn a_link_latency_replaces_the_global_one() {
+        let conditions = NetworkConditions {
+            latency_ms: 100,
+            link_latency_ms: BTreeMap::from([("slow".to_string(), 900)]),
+            ..NetworkConditions::default()
+        };
+        assert_eq!(conditions.latency("slow"), Duration::from_millis(900));
+        assert_eq!(conditions.latency("other"), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn the_drop_rate_is_the_share_of_messages_lost() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let lost = |conditions: &NetworkConditions, rng: &mut StdRng| {
+            (0..10_000).filter(|_| conditions.drops(rng)).count()
+        };
+        assert_eq!(lost(&dropping(0.0), &mut rng), 0);
+        assert_eq!(lost(&dropping(1.0), &mut rng), 10_000);
+        let share = lost(&dropping(0.3), &mut rng);
+        assert!((2_800..3_200).contains(&share), "{}", share);
+    }
+
+    #[tokio::test]
+    async fn delivery_waits_out_the_link_and_never_crosses_a_partition() {
+        let conditions = NetworkConditions {
+            link_latency_ms: BTreeMap::from([("far".to_string(), 40)]),
+            partitioned: BTreeSet::from(["cut".to_string()]),
+            ..NetworkConditions::default()
+        };
+        let start = Instant::now();
+        assert!(conditions.deliver("far").await);
+        assert!(start.elapsed() >= Duration::from_millis(40));
+        assert!(conditions.deliver("near").await);
+        assert!(!conditions.deliver("cut").await);
+    }
+
+    #[test]
+    fn conditions_describe_themselves() {
+        assert_eq!(NetworkConditions::default().to_string(), "none");
+        let conditions = NetworkConditions {
+            latency_ms: 250,
+            link_latency_ms: BTreeMap::from([("a".to_string(), 1), ("b".to_string(), 2)]),
+            drop_rate: 0.2,
+            partitioned: BTreeSet::from(["c".to_string()]),
+        };
+        assert_eq!(
+            conditions.to_string(),
+            "250ms latency, custom latency on 2 links, 20% drop, 1 peer partitioned"
+        );
+    }
+
+    #[tokio::test]
+    async fn transaction_relay_converges_under_20_percent_drop() {
+        let mut net = SimNetwork::new(5);
+        net.set_drop_rate(0.2).unwrap();
+        for index in 0..net.len() {
+            for _ in 0..3 {
+                let tx = fake_signed_transaction().build();
+                assert!(net.submit(index, tx).unwrap());
+            }
+        }
+        assert!(!net.mempools_agree());
+
+        let rounds = net.relay_until_converged(50).await;
+        assert!(rounds.is_some(), "relay did not converge");
+        for index in 0..net.len() {
+            assert_eq!(net.node(index).mempool.len(), 15);
+        }
+    }
+
+    #[tokio::test]
+    async fn nothing_is_relayed_when_every_message_drops() {
+        let mut net = SimNetwork::new(3);
+        net.set_drop_rate(1.0).unwrap();
+        net.submit(0, fake_signed_transaction().build()).unwrap();
+        assert_eq!(net.relay_until_converged(10).await, None);
+        assert!(net.node(1).mempool.is_empty());
+        assert!(net.node(2).mempool.is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_healed_partition_resolves_to_the_longer_chain() {
+        let mut net = SimNetwork::new(4);
+        net.mine(0).unwrap();
+        while net.sync_round().await > 0 {}
+        let genesis = net.tips()[0];
+        assert!(net.tips().iter().all(|tip| *tip == genesis));
+
+        // Two sides mine on their own: three blocks against one
+        net.partition(&[0, 1]);
+        for _ in 0..3 {
+            net.mine(0).unwrap();
+        }
+        net.mine(2).unwrap();
+        while net.sync_round().await > 0 {}
+        let tips = net.tips();
+        assert_eq!(tips[0], tips[1]);
+        assert_eq!(tips[2], tips[3]);
+        assert_ne!(tips[0], tips[2]);
+        let longer = tips[0];
+
+        net.heal();
+        while net.sync_round().await > 0 {}
+        assert!(net.tips().iter().all(|tip| *tip == longer));
+        for index in 0..net.len() {
+            assert_eq!(net.node(index).chain.height(), Some(3));
+        }
+    }
+}
@@ -0,0 +1,422 @@
+//! Receive addresses handed out for one expected payment each.
+//!
+//! `Wallet::create_payment_request` derives a fresh receive address and
+//! remembers the amount expected there and, optionally, until when.
+//! `Wallet::check_payment_requests` settles the waiting requests: the notes
+//! that reached a request's address before it expired pay it, compared with
+//! the amount asked, and a request past its expiry with nothing received
+//! expires. Either way a `WalletEvent` says so. Requests are saved in the
+//! wallet data dir, so they are still watched after a restart.
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::wallet::storage::StorageManager;
+use crate::wallet::webhooks::WalletEvent;
+use crate::wallet::{Address, Wallet, WalletError, WalletResult};
+
+/// Payment requests, saved through `StorageManager` in the wallet data dir
+pub const PAYMENT_REQUESTS_FILE: &str = "payment_requests.json";
+
+/// How the amount received compares with the amount requested
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentMatch {
+    Exact,
+    Under,
+    Over,
+}
+
+impl PaymentMatch {
+    pub fn of(requested: u64, received: u64) -> Self {
+        match received.cmp(&requested) {
+            std::cmp::Ordering::Equal => PaymentMatch::Exact,
+            std::cmp::Ordering::Less => PaymentMatch::Under,
+            std::cmp::Ordering::Greater => PaymentMatch::Over,
+        }
+    }
+}
+
+impl fmt::Display for PaymentMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PaymentMatch::Exact => "paid in full",
+            PaymentMatch::Under => "underpaid",
+            PaymentMatch::Over => "overpaid",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PaymentRequestStatus {
+    Waiting,
+    /// Notes reached the address before the request expired
+    Paid {
+        /// Transaction of the first note received
+        tx_id: String,
+        received: u64,
+        matched: PaymentMatch,
+        at: DateTime<Utc>,
+    },
+    /// Nothing reached the address before the request expired
+    Expired {
+        at: DateTime<Utc>,
+    },
+}
+
+/// A payment expected at one receive address
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaymentRequest {
+    pub id: String,
+    pub address: Address,
+    /// Amount asked for, in base units
+    pub amount: u64,
+    #[serde(default)]
+    pub label: String,
+    pub created_at: DateTime<Utc>,
+    /// Payments after this are not matched; `None` waits indefinitely
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    pub status: PaymentRequestStatus,
+}
+
+impl PaymentRequest {
+    pub fn is_expired_at(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| now > expires_at)
+    }
+
+    pub fn handle(&self) -> PaymentRequestHandle {
+        PaymentRequestHandle {
+            id: self.id.clone(),
+            address: self.address.clone(),
+            amount: self.amount,
+            expires_at: self.expires_at,
+        }
+    }
+}
+
+/// What to show the payer for a new request; look it up again by `id` with
+/// `PaymentRequests::get` to follow its status
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentRequestHandle {
+    pub id: String,
+    pub address: Address,
+    pub amount: u64,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Payment requests, oldest first
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PaymentRequests {
+    requests: Vec<PaymentRequest>,
+}
+
+impl PaymentRequests {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the saved requests, or none if none were saved
+    pub async fn load(storage: &StorageManager) -> WalletResult<Self> {
+        if !storage.exists(PAYMENT_REQUESTS_FILE) {
+            return Ok(Self::new());
+        }
+        storage.load(PAYMENT_REQUESTS_FILE).await
+    }
+
+    pub async fn save(&self, storage: &StorageManager) -> WalletResult<()> {
+        storage.save(PAYMENT_REQUESTS_FILE, self).await
+    }
+
+    pub fn requests(&self) -> &[PaymentRequest] {
+        &self.requests
+    }
+
+    pub fn get(&self, id: &str) -> Option<&PaymentRequest> {
+        self.requests.iter().find(|request| request.id == id)
+    }
+
+    /// The request for `address`, if one was made for it
+    pub fn for_address(&self, address: &Address) -> Option<&PaymentRequest> {
+        self.requests
+            .iter()
+            .find(|request| request.address == *address)
+    }
+}
+
+impl Wallet {
+    pub fn payment_requests(&self) -> &PaymentRequests {
+        &self.payment_requests
+    }
+
+    /// Derive a fresh receive address from the default key, labelled with
+    /// `label`, and watch it for a payment of `amount`. With `expires_in`,
+    /// payments arriving later than that from now don't count.
+    pub async fn create_payment_request(
+        &mut self,
+        amount: u64,
+        label: &str,
+        expires_in: Option<TimeDelta>,
+    ) -> WalletResult<PaymentRequestHandle> {
+        self.ensure_writable()?;
+        if amount == 0 {
+            return Err(WalletError::InvalidAmount(
+                "A payment request must ask for more than zero".to_string(),
+            ));
+        }
+        if expires_in.is_some_and(|expires_in| expires_in <= TimeDelta::zero()) {
+            return Err(WalletError::PaymentRequest(
+                "Expiry must be in the future".to_string(),
+            ));
+        }
+        let key_name = self
+            .keys
+            .get_default_key()
+            .map(|key| key.name().to_string())
+            .ok_or(WalletError::NoDefaultKey)?;
+        let address = self.new_receive_address(&key_name, label).await?;
+
+        let created_at = Utc::now();
+        let request = PaymentRequest {
+            id: Uuid::new_v4().to_string(),
+            address,
+            amount,
+            label: label.trim().to_string(),
+            created_at,
+            expires_at: expires_in.map(|expires_in| created_at + expires_in),
+            status: PaymentRequestStatus::Waiting,
+        };
+        let handle = request.handle();
+        self.payment_requests.requests.push(request);
+        Ok(handle)
+    }
+
+    /// Settle the waiting requests as of `now`: paid if notes reached the
+    /// address before expiry, expired if it has passed with none. Returns an
+    /// event for each request settled. Does nothing in read-only mode.
+    pub fn check_payment_requests(&mut self, now: DateTime<Utc>) -> Vec<WalletEvent> {
+        let mut events = Vec::new();
+        if self.ensure_writable().is_err() {
+            return events;
+        }
+
+        for request in self.payment_requests.requests.iter_mut() {
+            if request.status != PaymentRequestStatus::Waiting {
+                continue;
+            }
+            let mut notes: Vec<_> = self
+                .balances
+                .get_notes_for_address(&request.address)
+                .into_iter()
+                .filter(|note| note.created_at >= request.created_at)
+                .filter(|note| !request.is_expired_at(note.created_at))
+                .collect();
+            notes.sort_by_key(|note| note.created_at);
+
+            if let Some(first) = notes.first() {
+                let received = notes.iter().map(|note| note.amount).sum();
+                let matched = PaymentMatch::of(request.amount, received);
+                request.status = PaymentRequestStatus::Paid {
+                    tx_id: first.transaction_id.clone(),
+                    received,
+                    matched,
+                    at: first.created_at,
+                };
+                events.push(WalletEvent::PaymentReceived {
+                    request_id: request.id.clone(),
+                    tx_id: first.transaction_id.clone(),
+                    matched_amount: received,
+                    outcome: matched,
+                });
+            } else if request.is_expired_at(now) {
+                request.status = PaymentRequestStatus::Expired { at: now };
+                events.push(WalletEvent::PaymentExpired {
+                    request_id: request.id.clone(),
+                });
+            }
+        }
+
+        for event in &events {
+            info!("{}", event);
+        }
+        events
+    }
+
+    /// Load the payment requests saved in the wallet data dir
+    pub async fn load_payment_requests(&mut self) -> WalletResult<()> {
+        let Some(data_dir) = self.data_dir.clone() else {
+            return Ok(());
+        };
+        let storage = StorageManager::new(data_dir)?;
+        self.payment_requests = PaymentRequests::load(&storage).await?;
+        Ok(())
+    }
+
+    /// Save the payment requests; does nothing in read-only mode
+    pub async fn save_payment_requests(&self) -> WalletResult<()> {
+        let Some(data_dir) = self.data_dir.clone().filter(|_| !self.read_only) else {
+            return Ok(());
+        };
+        let storage = StorageManager::new(data_dir)?;
+        self.payment_requests.save(&storage).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_note, fake_txid, TempDir};
+
+    fn wallet_with_key() -> Wallet {
+        let mut wallet = Wallet::new();
+        wallet.generate_key("main").unwrap();
+        wallet
+    }
+
+    /// Pay `amount` to the request `id`, `after` seconds after it was made;
+    /// returns the paying transaction
+    fn pay(wallet: &mut Wallet, id: &str, after: i64, amount: u64) -> String {
+        let request = wallet.payment_requests().get(id).unwrap().clone();
+        let tx_id = fake_txid();
+        let mut note = fake_note()
+            .address(request.address)
+            .amount(amount)
+            .outpoint(tx_id.clone(), 0)
+            .build();
+        note.created_at = request.created_at + TimeDelta::seconds(after);
+        wallet.observe_note(note).unwrap();
+        tx_id
+    }
+
+    #[tokio::test]
+    async fn payments_are_matched_as_exact_under_or_over_the_amount() {
+        let mut wallet = wallet_with_key();
+        let mut handles = Vec::new();
+        for label in ["exact", "under", "over"] {
+            handles.push(
+                wallet
+                    .create_payment_request(1_000, label, None)
+                    .await
+                    .unwrap(),
+            );
+        }
+        assert_ne!(handles[0].address, handles[1].address);
+        let exact = pay(&mut wallet, &handles[0].id, 5, 1_000);
+        let under = pay(&mut wallet, &handles[1].id, 5, 600);
+        // Over in two payments; the first one names the request's transaction
+        let over = pay(&mut wallet, &handles[2].id, 5, 1_000);
+        pay(&mut wallet, &handles[2].id, 9, 500);
+
+        let events = wallet.check_payment_requests(Utc::now() + TimeDelta::minutes(1));
+        let expected = [
+            (exact, 1_000, PaymentMatch::Exact),
+            (under, 600, PaymentMatch::Under),
+            (over, 1_500, PaymentMatch::Over),
+        ];
+        assert_eq!(events.len(), 3);
+        for ((event, handle), (tx_id, received, matched)) in
+            events.iter().zip(&handles).zip(expected)
+        {
+            assert_eq!(
+                *event,
+                WalletEvent::PaymentReceived {
+                    request_id: handle.id.clone(),
+                    tx_id: tx_id.clone(),
+                    matched_amount: received,
+                    outcome: matched,
+                }
+            );
+            assert!(matches!(
+                &wallet.payment_requests().get(&handle.id).unwrap().status,
+                PaymentRequestStatus::Paid { tx_id: paid_by, received: got, matched: m, .. }
+                    if *paid_by == tx_id && *got == received && *m == matched
+            ));
+        }
+        assert_eq!(
+            wallet
+                .payment_requests()
+                .for_address(&handles[1].address)
+                .unwrap()
+                .label,
+            "under"
+        );
+
+        // Settled requests are not reported again
+        assert!(wallet.check_payment_requests(Utc::now()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn an_unpaid_request_expires_and_a_late_payment_does_not_count() {
+        let mut wallet = wallet_with_key();
+        let handle = wallet
+            .create_payment_request(1_000, "", Some(TimeDelta::minutes(10)))
+            .await
+            .unwrap();
+        let expires_at = handle.expires_at.unwrap();
+        let created_at = wallet
+            .payment_requests()
+            .get(&handle.id)
+            .unwrap()
+            .created_at;
+        assert_eq!(expires_at - created_at, TimeDelta::minutes(10));
+
+        assert!(wallet
+            .check_payment_requests(created_at + TimeDelta::minutes(5))
+            .is_empty());
+        pay(&mut wallet, &handle.id, 11 * 60, 1_000);
+
+        let now = expires_at + TimeDelta::minutes(2);
+        assert_eq!(
+            wallet.check_payment_requests(now),
+            vec![WalletEvent::PaymentExpired {
+                request_id: handle.id.clone()
+            }]
+        );
+        assert_eq!(
+            wallet.payment_requests().get(&handle.id).unwrap().status,
+            PaymentRequestStatus::Expired { at: now }
+        );
+        assert!(wallet.check_payment_requests(now).is_empty());
+    }
+
+    #[tokio::test]
+    async fn requests_need_an_amount_and_a_future_expiry() {
+        let mut wallet = wallet_with_key();
+        assert!(matches!(
+            wallet.create_payment_request(0, "", None).await,
+            Err(WalletError::InvalidAmount(_))
+        ));
+        assert!(matches!(
+            wallet
+                .create_payment_request(1_000, "", Some(TimeDelta::zero()))
+                .await,
+            Err(WalletError::PaymentRequest(_))
+        ));
+        assert!(wallet.payment_requests().requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn requests_are_still_watched_after_a_restart() {
+        let dir = TempDir::new("payment-requests");
+        let handle = {
+            let mut wallet = Wallet::open(dir.path()).unwrap();
+            wallet.generate_key("main").unwrap();
+            let handle = wallet
+                .create_payment_request(2_500, "invoice 7", Some(TimeDelta::hours(1)))
+                .await
+                .unwrap();
+            wallet.save_payment_requests().await.unwrap();
+            handle
+        };
+
+        let mut reopened = Wallet::open(dir.path()).unwrap();
+        reopened.load_payment_requests().await.unwrap();
+        let request = reopened.payment_requests().get(&handle.id).unwrap();
+        assert_eq!(request.handle(), handle);
+        assert_eq!(request.label, "invoice 7");
+        assert_eq!(request.status, PaymentRequestStatus::Waiting);
+    }
+}
@@ -0,0 +1,1036 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::sync::watch;
+
+use crate::wallet::geoip::{GeoIpResolver, PeerLocation};
+use crate::wallet::handshake::Handshake;
+
+/// Window over which transfer rates are averaged
+pub const RATE_WINDOW: Duration = Duration::from_secs(10);
+
+/// How long after a clock jump peer timeouts are forgiven
+pub const PEER_TIMEOUT_GRACE: Duration = Duration::from_secs(120);
+
+/// Where the node learned about a peer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PeerSource {
+    /// Listed in the node config
+    #[default]
+    Static,
+    /// Returned by a DNS seed
+    Dns,
+    /// Learned from another peer
+    Discovered,
+}
+
+impl PeerSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PeerSource::Static => "static",
+            PeerSource::Dns => "dns",
+            PeerSource::Discovered => "discovered",
+        }
+    }
+}
+
+/// Snapshot of a known peer for display in the UI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeerInfo {
+    pub peer_id: String,
+    pub address: String,
+    #[serde(default)]
+    pub source: PeerSource,
+    pub connected: bool,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    /// Inbound bytes/sec over the last `RATE_WINDOW`
+    pub rate_in: f64,
+    /// Outbound bytes/sec over the last `RATE_WINDOW`
+    pub rate_out: f64,
+    pub score: i32,
+    pub banned: bool,
+    /// Chain height the peer advertised in its handshake
+    #[serde(default)]
+    pub best_height: Option<u64>,
+    /// Software version the peer advertised in its handshake
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// Services bitfield the peer advertised in its handshake
+    #[serde(default)]
+    pub services: Option<u64>,
+    /// Why the peer was last disconnected without a penalty, such as a
+    /// version below the configured minimum
+    #[serde(default)]
+    pub rejection: Option<String>,
+    /// Country of the peer's IP, from the configured location databases
+    #[serde(default)]
+    pub country_code: Option<String>,
+    /// Network the peer's IP belongs to, from the same databases
+    #[serde(default)]
+    pub asn: Option<u32>,
+    #[serde(default)]
+    pub as_org: Option<String>,
+}
+
+/// Scoring parameters for peer reputation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeerScoringConfig {
+    pub initial_score: i32,
+    pub max_score: i32,
+    /// Peers whose score drops below this are disconnected and banned
+    pub ban_threshold: i32,
+    pub ban_duration_secs: u64,
+    #[serde(default = "default_valid_header_reward")]
+    pub valid_header_reward: i32,
+    #[serde(default = "default_valid_block_reward")]
+    pub valid_block_reward: i32,
+    #[serde(alias = "fast_response_reward")]
+    pub useful_response_reward: i32,
+    pub protocol_violation_penalty: i32,
+    pub invalid_data_penalty: i32,
+    pub timeout_penalty: i32,
+}
+
+impl Default for PeerScoringConfig {
+    fn default() -> Self {
+        Self {
+            initial_score: 100,
+            max_score: 200,
+            ban_threshold: 0,
+            ban_duration_secs: 3600,
+            valid_header_reward: default_valid_header_reward(),
+            valid_block_reward: default_valid_block_reward(),
+            useful_response_reward: 1,
+            protocol_violation_penalty: 50,
+            invalid_data_penalty: 40,
+            timeout_penalty: 10,
+        }
+    }
+}
+
+fn default_valid_header_reward() -> i32 {
+    1
+}
+
+fn default_valid_block_reward() -> i32 {
+    5
+}
+
+/// Observed peer behaviour fed into the scorer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PeerEvent {
+    /// Sent headers that passed the checkpoints
+    ValidHeader,
+    /// Sent a block that was added to the chain
+    ValidBlock,
+    /// Accepted a transaction we relayed to it
+    #[serde(alias = "FastResponse")]
+    UsefulResponse,
+    ProtocolViolation,
+    /// Sent a message that doesn't decode
+    InvalidData,
+    Timeout,
+    /// Software version ruled out by the config; noted, not penalised
+    VersionRejected,
+}
+
+impl PeerEvent {
+    /// Score change for this event under `config`
+    pub fn delta(&self, config: &PeerScoringConfig) -> i32 {
+        match self {
+            PeerEvent::ValidHeader => config.valid_header_reward,
+            PeerEvent::ValidBlock => config.valid_block_reward,
+            PeerEvent::UsefulResponse => config.useful_response_reward,
+            PeerEvent::ProtocolViolation => -config.protocol_violation_penalty,
+            PeerEvent::InvalidData => -config.invalid_data_penalty,
+            PeerEvent::Timeout => -config.timeout_penalty,
+            PeerEvent::VersionRejected => 0,
+        }
+    }
+}
+
+/// A peer that was disconnected and banned for misbehaving
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerEviction {
+    pub peer_id: String,
+    pub score: i32,
+    pub last_event: PeerEvent,
+    pub ban_duration: Duration,
+}
+
+/// Rolling window of byte samples used to compute a transfer rate
+#[derive(Debug)]
+pub struct RateWindow {
+    window: Duration,
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl RateWindow {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Record `bytes` transferred at `now`
+    pub fn record_at(&mut self, now: Instant, bytes: u64) {
+        self.samples.push_back((now, bytes));
+        self.prune(now);
+    }
+
+    /// Average bytes/sec over the window ending at `now`
+    pub fn rate_at(&mut self, now: Instant) -> f64 {
+        self.prune(now);
+        let total: u64 = self.samples.iter().map(|(_, bytes)| bytes).sum();
+        total as f64 / self.window.as_secs_f64()
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while let Some((at, _)) = self.samples.front() {
+            if now.saturating_duration_since(*at) >= self.window {
+                self.samples.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+}
+
+/// Cumulative and windowed byte counters for one direction pair
+#[derive(Debug)]
+pub struct BandwidthCounter {
+    total_in: AtomicU64,
+    total_out: AtomicU64,
+    window_in: Mutex<RateWindow>,
+    window_out: Mutex<RateWindow>,
+}
+
+impl Default for BandwidthCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BandwidthCounter {
+    pub fn new() -> Self {
+        Self {
+            total_in: AtomicU64::new(0),
+            total_out: AtomicU64::new(0),
+            window_in: Mutex::new(RateWindow::new(RATE_WINDOW)),
+            window_out: Mutex::new(RateWindow::new(RATE_WINDOW)),
+        }
+    }
+
+    pub fn record_in(&self, bytes: u64) {
+        self.record_in_at(Instant::now(), bytes);
+    }
+
+    pub fn record_out(&self, bytes: u64) {
+        self.record_out_at(Instant::now(), bytes);
+    }
+
+    pub fn record_in_at(&self, now: Instant, bytes: u64) {
+        self.total_in.fetch_add(bytes, Ordering::Relaxed);
+        if let Ok(mut window) = self.window_in.lock() {
+            window.record_at(now, bytes);
+        }
+    }
+
+    pub fn record_out_at(&self, now: Instant, bytes: u64) {
+        self.total_out.fetch_add(bytes, Ordering::Relaxed);
+        if let Ok(mut window) = self.window_out.lock() {
+            window.record_at(now, bytes);
+        }
+    }
+
+    pub fn total_in(&self) -> u64 {
+        self.total_in.load(Ordering::Relaxed)
+    }
+
+    pub fn total_out(&self) -> u64 {
+        self.total_out.load(Ordering::Relaxed)
+    }
+
+    /// Inbound and outbound bytes/sec as of `now`
+    pub fn rates_at(&self, now: Instant) -> (f64, f64) {
+        let rate_in = self
+            .window_in
+            .lock()
+            .map(|mut w| w.rate_at(now))
+            .unwrap_or(0.0);
+        let rate_out = self
+            .window_out
+            .lock()
+            .map(|mut w| w.rate_at(now))
+            .unwrap_or(0.0);
+        (rate_in, rate_out)
+    }
+
+    pub fn rates(&self) -> (f64, f64) {
+        self.rates_at(Instant::now())
+    }
+
+    pub fn reset(&self) {
+        self.total_in.store(0, Ordering::Relaxed);
+        self.total_out.store(0, Ordering::Relaxed);
+        if let Ok(mut window) = self.window_in.lock() {
+            window.clear();
+        }
+        if let Ok(mut window) = self.window_out.lock() {
+            window.clear();
+        }
+    }
+}
+
+/// Records traffic against both a peer counter and the global counter
+#[derive(Debug, Clone)]
+pub struct BandwidthMeter {
+    peer: Arc<BandwidthCounter>,
+    global: Arc<BandwidthCounter>,
+}
+
+impl BandwidthMeter {
+    pub fn new(peer: Arc<BandwidthCounter>, global: Arc<BandwidthCounter>) -> Self {
+        Self { peer, global }
+    }
+
+    pub fn record_in(&self, bytes: u64) {
+        self.peer.record_in(bytes);
+        self.global.record_in(bytes);
+    }
+
+    pub fn record_out(&self, bytes: u64) {
+        self.peer.record_out(bytes);
+        self.global.record_out(bytes);
+    }
+}
+
+/// Connection wrapper counting every byte read from and written to the inner stream
+pub struct MeteredConnection<C> {
+    inner: C,
+    meter: BandwidthMeter,
+}
+
+impl<C> MeteredConnection<C> {
+    pub fn new(inner: C, meter: BandwidthMeter) -> Self {
+        Self { inner, meter }
+    }
+
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+}
+
+impl<C: AsyncRead + Unpin> AsyncRead for MeteredConnection<C> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        let result = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = result {
+            let read = buf.filled().len() - before;
+            if read > 0 {
+                self.meter.record_in(read as u64);
+            }
+        }
+        result
+    }
+}
+
+impl<C: AsyncWrite + Unpin> AsyncWrite for MeteredConnection<C> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let result = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = result {
+            if written > 0 {
+                self.meter.record_out(written as u64);
+            }
+        }
+        result
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[derive(Debug)]
+struct PeerEntry {
+    peer_id: String,
+    address: String,
+    source: PeerSource,
+    connected: bool,
+    bandwidth: Arc<BandwidthCounter>,
+    score: i32,
+    handshake: Option<Handshake>,
+    rejection: Option<String>,
+    location: Option<PeerLocation>,
+}
+
+/// Registry of known peers, their traffic counters, and reputation scores
+#[derive(Debug)]
+pub struct PeerRegistry {
+    peers: Mutex<Vec<PeerEntry>>,
+    global: Arc<BandwidthCounter>,
+    scoring: Mutex<PeerScoringConfig>,
+    // Bans outlive node restarts, so they are kept apart from the peer list
+    bans: Mutex<HashMap<String, Instant>>,
+    /// Timeouts before this are forgiven; set after a clock jump
+    timeout_grace_until: Mutex<Option<Instant>>,
+    /// Connected peer count, republished whenever a peer connects or drops
+    connected: watch::Sender<u32>,
+    /// Locates peers as they register; `None` when no database is configured
+    geoip: Mutex<Option<Arc<GeoIpResolver>>>,
+}
+
+impl Default for PeerRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PeerRegistry {
+    pub fn new() -> Self {
+        Self::with_scoring(PeerScoringConfig::default())
+    }
+
+    pub fn with_scoring(scoring: PeerScoringConfig) -> Self {
+        Self {
+            peers: Mutex::new(Vec::new()),
+            global: Arc::new(BandwidthCounter::new()),
+            scoring: Mutex::new(scoring),
+            bans: Mutex::new(HashMap::new()),
+            timeout_grace_until: Mutex::new(None),
+            connected: watch::channel(0).0,
+            geoip: Mutex::new(None),
+        }
+    }
+
+    pub fn set_scoring(&self, scoring: PeerScoringConfig) {
+        if let Ok(mut current) = self.scoring.lock() {
+            *current = scoring;
+        }
+    }
+
+    /// Apply a behaviour event to a peer's score, evicting it if it falls below the threshold
+    pub fn record_event(&self, peer_id: &str, event: PeerEvent) -> Option<PeerEviction> {
+        if event == PeerEvent::Timeout && self.in_timeout_grace() {
+            return None;
+        }
+        let scoring = self.scoring.lock().ok()?.clone();
+        let mut peers = self.peers.lock().ok()?;
+        let entry = peers.iter_mut().find(|p| p.peer_id == peer_id)?;
+
+        entry.score = (entry.score + event.delta(&scoring)).min(scoring.max_score);
+        if entry.score >= scoring.ban_threshold {
+            return None;
+        }
+
+        entry.connected = false;
+        let score = entry.score;
+        self.publish_connected(&peers);
+        let ban_duration = Duration::from_secs(scoring.ban_duration_secs);
+        if let Ok(mut bans) = self.bans.lock() {
+            bans.insert(peer_id.to_string(), Instant::now() + ban_duration);
+        }
+
+        Some(PeerEviction {
+            peer_id: peer_id.to_string(),
+            score,
+            last_event: event,
+            ban_duration,
+        })
+    }
+
+    /// Don't count timeouts against peers for the next `grace`. After the
+    /// machine wakes or the clock jumps, every peer times out at once
+    /// through no fault of its own.
+    pub fn forgive_timeouts(&self, grace: Duration) {
+        if let Ok(mut until) = self.timeout_grace_until.lock() {
+            *until = Some(Instant::now() + grace);
+        }
+    }
+
+    fn in_timeout_grace(&self) -> bool {
+        self.timeout_grace_until
+            .lock()
+            .ok()
+            .and_then(|until| *until)
+            .is_some_and(|until| Instant::now() < until)
+    }
+
+    /// Whether a peer is currently serving a ban (expired bans are cleared)
+    pub fn is_banned(&self, peer_id: &str) -> bool {
+        let Ok(mut bans) = self.bans.lock() else {
+            return false;
+        };
+        match bans.get(peer_id) {
+            Some(until) if *until > Instant::now() => true,
+            Some(_) => {
+                bans.remove(peer_id);
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Locate peers with `resolver` from now on, and the known ones right
+    /// away; `None` stops locating them and forgets their locations
+    pub fn set_geoip(&self, resolver: Option<Arc<GeoIpResolver>>) {
+        if let Ok(mut peers) = self.peers.lock() {
+            for entry in peers.iter_mut() {
+                entry.location = resolver
+                    .as_ref()
+                    .and_then(|resolver| resolver.resolve_addr(&entry.address));
+            }
+        }
+        if let Ok(mut geoip) = self.geoip.lock() {
+            *geoip = resolver;
+        }
+    }
+
+    pub fn geoip(&self) -> Option<Arc<GeoIpResolver>> {
+        self.geoip.lock().ok().and_then(|geoip| geoip.clone())
+    }
+
+    /// Register a peer by multiaddr (idempotent) and return a meter for its
+    /// connection. A new peer is located from memory, before the peer list
+    /// is locked, so registering never waits on disk or network.
+    pub fn register(&self, address: &str, source: PeerSource) -> BandwidthMeter {
+        let peer_id = peer_id_from_addr(address).to_string();
+        let location = self
+            .geoip()
+            .and_then(|resolver| resolver.resolve_addr(address));
+        let mut peers = match self.peers.lock() {
+            Ok(peers) => peers,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        let bandwidth = match peers.iter().find(|p| p.peer_id == peer_id) {
+            Some(entry) => entry.bandwidth.clone(),
+            None => {
+                let bandwidth = Arc::new(BandwidthCounter::new());
+                let score = self
+                    .scoring
+                    .lock()
+                    .map(|scoring| scoring.initial_score)
+                    .unwrap_or_default();
+                peers.push(PeerEntry {
+                    peer_id,
+                    address: address.to_string(),
+                    source,
+                    connected: false,
+                    bandwidth: bandwidth.clone(),
+                    score,
+                    handshake: None,
+                    rejection: None,
+                    location,
+                });
+                bandwidth
+            }
+        };
+
+        BandwidthMeter::new(bandwidth, self.global.clone())
+    }
+
+    pub fn set_connected(&self, peer_id: &str, connected: bool) {
+        if let Ok(mut peers) = self.peers.lock() {
+            if let Some(entry) = peers.iter_mut().find(|p| p.peer_id == peer_id) {
+                entry.connected = connected;
+            }
+            self.publish_connected(&peers);
+        }
+    }
+
+    /// Remember what a peer advertised in its handshake
+    pub fn set_handshake(&self, peer_id: &str, handshake: Handshake) {
+        if let Ok(mut peers) = self.peers.lock() {
+            if let Some(entry) = peers.iter_mut().find(|p| p.peer_id == peer_id) {
+                entry.handshake = Some(handshake);
+                entry.rejection = None;
+            }
+        }
+    }
+
+    /// Record why a peer was turned away; cleared by its next handshake
+    pub fn note_rejection(&self, peer_id: &str, reason: String) {
+        if let Ok(mut peers) = self.peers.lock() {
+            if let Some(entry) = peers.iter_mut().find(|p| p.peer_id == peer_id) {
+                entry.rejection = Some(reason);
+            }
+        }
+    }
+
+    /// Peers that completed a handshake, counted by advertised software version
+    pub fn version_histogram(&self) -> BTreeMap<String, u32> {
+        let mut histogram = BTreeMap::new();
+        if let Ok(peers) = self.peers.lock() {
+            for handshake in peers.iter().filter_map(|p| p.handshake.as_ref()) {
+                let version = handshake
+                    .software_version()
+                    .map_or_else(|| "unknown".to_string(), |v| v.to_string());
+                *histogram.entry(version).or_insert(0) += 1;
+            }
+        }
+        histogram
+    }
+
+    /// Highest chain height advertised by a connected peer
+    pub fn best_advertised_height(&self) -> Option<u64> {
+        self.peers.lock().ok().and_then(|peers| {
+            peers
+                .iter()
+                .filter(|p| p.connected)
+                .filter_map(|p| p.handshake.as_ref().map(|h| h.best_height))
+                .max()
+        })
+    }
+
+    /// Snapshot all known peers
+    pub fn list(&self) -> Vec<PeerInfo> {
+        let now = Instant::now();
+        match self.peers.lock() {
+            Ok(peers) => peers
+                .iter()
+                .map(|entry| {
+                    let (rate_in, rate_out) = entry.bandwidth.rates_at(now);
+                    PeerInfo {
+                        peer_id: entry.peer_id.clone(),
+                        address: entry.address.clone(),
+                        source: entry.source,
+                        connected: entry.connected,
+                        bytes_in: entry.bandwidth.total_in(),
+                        bytes_out: entry.bandwidth.total_out(),
+                        rate_in,
+                        rate_out,
+                        score: entry.score,
+                        banned: self.is_banned(&entry.peer_id),
+                        best_height: entry.handshake.as_ref().map(|h| h.best_height),
+                        user_agent: entry.handshake.as_ref().map(|h| h.user_agent.clone()),
+                        services: entry.handshake.as_ref().map(|h| h.services),
+                        rejection: entry.rejection.clone(),
+                        country_code: entry
+                            .location
+                            .as_ref()
+                            .and_then(|location| location.country_code.clone()),
+                        asn: entry.location.as_ref().and_then(|location| location.asn),
+                        as_org: entry
+                            .location
+                            .as_ref()
+                            .and_then(|location| location.as_org.clone()),
+                    }
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    pub fn connected_count(&self) -> u32 {
+        self.peers
+            .lock()
+            .map(|peers| peers.iter().filter(|p| p.connected).count() as u32)
+            .unwrap_or(0)
+    }
+
+    /// Connected peer count, notified on every change
+    pub fn subscribe_connected(&self) -> watch::Receiver<u32> {
+        self.connected.subscribe()
+    }
+
+    fn publish_connected(&self, peers: &[PeerEntry]) {
+        self.connected
+            .send_replace(peers.iter().filter(|p| p.connected).count() as u32);
+    }
+
+    pub fn global(&self) -> &BandwidthCounter {
+        &self.global
+    }
+
+    /// Forget every ban, e.g. after purging peer data
+    pub fn clear_bans(&self) {
+        if let Ok(mut bans) = self.bans.lock() {
+            bans.clear();
+        }
+    }
+
+    /// Drop all peers and zero the global counters (called on node stop); bans are kept
+    pub fn reset(&self) {
+        if let Ok(mut peers) = self.peers.lock() {
+            peers.clear();
+        }
+        self.connected.send_replace(0);
+        self.global.reset();
+    }
+}
+
+/// Extract the peer id component of a multiaddr. Addresses without one (e.g.
+/// fresh from a DNS seed) are identified by the whole address.
+pub fn peer_id_from_addr(addr: &str) -> &str {
+    match addr.rsplit_once("/p2p/") {
+        Some((_, peer_id)) => peer_id,
+        None => addr,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::geoip_fixture;
+    use crate::wallet::geoip::GeoIpDatabase;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    const PEER: &str = "/ip4/10.0.0.1/tcp/4001/p2p/12D3KooWPeer";
+
+    #[tokio::test]
+    async fn metered_connection_counts_bytes_against_peer_and_global() {
+        let registry = PeerRegistry::new();
+        let meter = registry.register(PEER, PeerSource::Static);
+        let (local, mut remote) = tokio::io::duplex(1024);
+        let mut connection = MeteredConnection::new(local, meter);
+
+        connection.write_all(&[7; 300]).await.unwrap();
+        let mut received = [0; 300];
+        remote.read_exact(&mut received).await.unwrap();
+        remote.write_all(&[1; 120]).await.unwrap();
+        let mut buf = [0; 120];
+        connection.read_exact(&mut buf).await.unwrap();
+
+        let peer = &registry.list()[0];
+        assert_eq!((peer.bytes_in, peer.bytes_out), (120, 300));
+        assert_eq!(peer.rate_in, 12.0);
+        assert_eq!(peer.rate_out, 30.0);
+        assert_eq!(registry.global().total_in(), 120);
+        assert_eq!(registry.global().total_out(), 300);
+    }
+
+    #[test]
+    fn peers_are_located_when_a_database_is_configured() {
+        let cloudflare = PeerLocation {
+            country_code: Some("AU".to_string()),
+            asn: Some(13335),
+            as_org: Some("Cloudflare".to_string()),
+        };
+        let database = GeoIpDatabase::from_bytes(geoip_fixture(&[(
+            "1.1.1.0".parse().unwrap(),
+            24,
+            cloudflare,
+        )]))
+        .unwrap();
+        let resolver = Arc::new(GeoIpResolver::from_databases(vec![(
+            "peers.mmdb".into(),
+            database,
+        )]));
+        let public = "/ip4/1.1.1.1/tcp/4001/p2p/12D3KooWPublic";
+        let location = |registry: &PeerRegistry, address: &str| {
+            let peer = registry
+                .list()
+                .into_iter()
+                .find(|peer| peer.address == address)
+                .unwrap();
+            (peer.country_code, peer.asn, peer.as_org)
+        };
+
+        // Without a database the fields stay empty
+        let registry = PeerRegistry::new();
+        registry.register(public, PeerSource::Static);
+        assert_eq!(location(&registry, public), (None, None, None));
+
+        // Setting one locates the peers already known and later ones
+        registry.set_geoip(Some(resolver.clone()));
+        registry.register(PEER, PeerSource::Static);
+        let located = (
+            Some("AU".to_string()),
+            Some(13335),
+            Some("Cloudflare".to_string()),
+        );
+        assert_eq!(location(&registry, public), located);
+        assert_eq!(location(&registry, PEER), (None, None, None));
+
+        let registry = PeerRegistry::new();
+        registry.set_geoip(Some(resolver));
+        registry.register(public, PeerSource::Static);
+        assert_eq!(location(&registry, public), located);
+        registry.set_geoip(None);
+        assert_eq!(location(&registry, public), (None, None, None));
+        assert!(registry.geoip().is_none());
+    }
+
+    #[test]
+    fn every_peer_adds_to_the_global_counter() {
+        let registry = PeerRegistry::new();
+        registry.register(PEER, PeerSource::Static).record_in(100);
+        registry
+            .register("/ip4/10.0.0.2/tcp/4001/p2p/other", PeerSource::Dns)
+            .record_in(50);
+        // Registering again shares the existing counter
+        registry.register(PEER, PeerSource::Static).record_out(10);
+
+        let peers = registry.list();
+        assert_eq!(peers.len(), 2);
+        assert_eq!((peers[0].bytes_in, peers[0].bytes_out), (100, 10));
+        assert_eq!(registry.global().total_in(), 150);
+        assert_eq!(registry.global().total_out(), 10);
+    }
+
+    #[test]
+    fn rate_averages_over_the_window_and_forgets_old_samples() {
+        let start = Instant::now();
+        let mut window = RateWindow::new(Duration::from_secs(10));
+        window.record_at(start, 1_000);
+        window.record_at(start + Duration::from_secs(4), 500);
+
+        assert_eq!(window.rate_at(start + Duration::from_secs(5)), 150.0);
+        // The first sample falls out exactly one window after it was taken
+        assert_eq!(window.rate_at(start + Duration::from_secs(10)), 50.0);
+        assert_eq!(window.rate_at(start + Duration::from_secs(14)), 0.0);
+    }
+
+    #[test]
+    fn counter_rates_and_totals_reset_together() {
+        let counter = BandwidthCounter::new();
+        let now = Instant::now();
+        counter.record_in_at(now, 2_000);
+        counter.record_out_at(now, 400);
+        assert_eq!(counter.rates_at(now), (200.0, 40.0));
+
+        counter.reset();
+        assert_eq!((counter.total_in(), counter.total_out()), (0, 0));
+        assert_eq!(counter.rates_at(now), (0.0, 0.0));
+    }
+
+    #[test]
+    fn stopping_the_node_clears_peers_and_global_totals() {
+        let registry = PeerRegistry::new();
+        registry.register(PEER, PeerSource::Static).record_out(64);
+        registry.set_connected(peer_id_from_addr(PEER), true);
+        assert_eq!(registry.connected_count(), 1);
+
+        registry.reset();
+        assert!(registry.list().is_empty());
+        assert_eq!(registry.global().total_out(), 0);
+        assert_eq!(*registry.subscribe_connected().borrow(), 0);
+    }
+
+    #[test]
+    fn penalties_accumulate_until_the_peer_is_evicted_and_banned() {
+        let registry = PeerRegistry::new();
+        registry.register(PEER, PeerSource::Static);
+        let id = peer_id_from_addr(PEER);
+        registry.set_connected(id, true);
+
+        // 100 -> 50 -> 10, still above the threshold of 0
+        assert_eq!(
+            registry.record_event(id, PeerEvent::ProtocolViolation),
+            None
+        );
+        assert_eq!(registry.record_event(id, PeerEvent::InvalidData), None);
+        assert_eq!(registry.list()[0].score, 10);
+        assert_eq!(registry.connected_count(), 1);
+
+        let eviction = registry
+            .record_event(id, PeerEvent::InvalidData)
+            .expect("peer is evicted below the threshold");
+        assert_eq!(eviction.peer_id, id);
+        assert_eq!(eviction.score, -30);
+        assert_eq!(eviction.last_event, PeerEvent::InvalidData);
+        assert_eq!(eviction.ban_duration, Duration::from_secs(3600));
+
+        let peer = &registry.list()[0];
+        assert!(!peer.connected && peer.banned);
+        assert_eq!(registry.connected_count(), 0);
+    }
+
+    #[test]
+    fn rewards_are_capped_and_offset_penalties() {
+        let registry = PeerRegistry::with_scoring(PeerScoringConfig {
+            max_score: 105,
+            ..PeerScoringConfig::default()
+        });
+        registry.register(PEER, PeerSource::Static);
+        let id = peer_id_from_addr(PEER);
+
+        for _ in 0..10 {
+            registry.record_event(id, PeerEvent::UsefulResponse);
+        }
+        assert_eq!(registry.list()[0].score, 105);
+        registry.record_event(id, PeerEvent::Timeout);
+        registry.record_event(id, PeerEvent::VersionRejected);
+        assert_eq!(registry.list()[0].score, 95);
+    }
+
+    #[test]
+    fn timeouts_are_forgiven_during_the_grace_period() {
+        let registry = PeerRegistry::new();
+        registry.register(PEER, PeerSource::Static);
+        let id = peer_id_from_addr(PEER);
+
+        registry.forgive_timeouts(Duration::from_secs(60));
+        for _ in 0..20 {
+            assert_eq!(registry.record_event(id, PeerEvent::Timeout), None);
+        }
+        assert_eq!(registry.list()[0].score, 100);
+        // Other penalties still count
+        registry.record_event(id, PeerEvent::ProtocolViolation);
+        assert_eq!(registry.list()[0].score, 50);
+    }
+
+    #[test]
+    fn bans_outlive_a_reset_until_cleared_or_expired() {
+        let registry = PeerRegistry::with_scoring(PeerScoringConfig {
+            ban_threshold: 95,
+            ban_duration_secs: 0,
+            ..PeerScoringConfig::default()
+        });
+        registry.register(PEER, PeerSource::Static);
+        let id = peer_id_from_addr(PEER);
+        assert!(registry.record_event(id, PeerEvent::Timeout).is_some());
+        // A zero-length ban has already run out
+        assert!(!registry.is_banned(id));
+
+        registry.set_scoring(PeerScoringConfig::default());
+        registry.register(PEER, PeerSource::Static);
+        for _ in 0..3 {
+            registry.record_event(id, PeerEvent::ProtocolViolation);
+        }
+        assert!(registry.is_banned(id));
+        registry.reset();
+        assert!(registry.is_banned(id));
+        registry.clear_bans();
+        assert!(!registry.is_banned(id));
+    }
+
+    fn handshake(user_agent: &str) -> Handshake {
+        Handshake {
+            protocol_version: crate::wallet::handshake::PROTOCOL_VERSION,
+            user_agent: user_agent.to_string(),
+            genesis_hash: [0; 32],
+            best_height: 0,
+            services: crate::wallet::handshake::SERVICE_FULL_NODE,
+        }
+    }
+
+    #[test]
+    fn handshaken_peers_are_counted_by_version() {
+        let registry = PeerRegistry::new();
+        for (i, user_agent) in [
+            "nockchain/1.2.3",
+            "nockchain/1.2.3",
+            "nockchain/1.3.0",
+            "odd",
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let address = format!("/ip4/10.0.0.{}/tcp/4001/p2p/peer{}", i + 1, i);
+            registry.register(&address, PeerSource::Static);
+            registry.set_handshake(peer_id_from_addr(&address), handshake(user_agent));
+        }
+        // Without a handshake a peer isn't counted at all
+        registry.register(PEER, PeerSource::Static);
+
+        let histogram = registry.version_histogram();
+        assert_eq!(
+            histogram.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("1.2.3".to_string(), 2),
+                ("1.3.0".to_string(), 1),
+                ("unknown".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_rejection_is_listed_until_the_next_handshake() {
+        let registry = PeerRegistry::new();
+        registry.register(PEER, PeerSource::Static);
+        let id = peer_id_from_addr(PEER);
+        registry.set_handshake(id, handshake("nockchain/0.1.0"));
+        registry.record_event(id, PeerEvent::VersionRejected);
+        registry.note_rejection(id, "version 0.1.0 does not satisfy >=0.2.0".to_string());
+
+        let peer = &registry.list()[0];
+        assert_eq!(
+            peer.rejection.as_deref(),
+            Some("version 0.1.0 does not satisfy >=0.2.0")
+        );
+        assert_eq!(peer.user_agent.as_deref(), Some("nockchain/0.1.0"));
+        assert_eq!(
+            peer.services,
+            Some(crate::wallet::handshake::SERVICE_FULL_NODE)
+        );
+        assert_eq!(peer.score, 100);
+        assert!(!peer.banned);
+
+        registry.set_handshake(id, handshake("nockchain/0.2.0"));
+        assert_eq!(registry.list()[0].rejection, None);
+    }
+
+    #[test]
+    fn events_for_unknown_peers_are_ignored() {
+        let registry = PeerRegistry::new();
+        assert_eq!(
+            registry.record_event("nobody", PeerEvent::ProtocolViolation),
+            None
+        );
+        assert!(!registry.is_banned("nobody"));
+    }
+
+    #[test]
+    fn old_scoring_configs_still_load() {
+        let config: PeerScoringConfig = serde_json::from_str(
+            r#"{"initial_score":100,"max_score":200,"ban_threshold":0,
+                "ban_duration_secs":3600,"valid_header_reward":1,
+                "valid_block_reward":5,"fast_response_reward":3,
+                "protocol_violation_penalty":50,"invalid_data_penalty":40,
+                "timeout_penalty":10}"#,
+        )
+        .unwrap();
+        assert_eq!(config.useful_response_reward, 3);
+        assert_eq!(config.valid_block_reward, 5);
+        let event: PeerEvent = serde_json::from_str(r#""FastResponse""#).unwrap();
+        assert_eq!(event, PeerEvent::UsefulResponse);
+
+        // Written while the header and block rewards were missing
+        let config: PeerScoringConfig = serde_json::from_str(
+            r#"{"initial_score":100,"max_score":200,"ban_threshold":0,
+                "ban_duration_secs":3600,"useful_response_reward":2,
+                "protocol_violation_penalty":50,"invalid_data_penalty":40,
+                "timeout_penalty":10}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            PeerScoringConfig {
+                useful_response_reward: 2,
+                ..PeerScoringConfig::default()
+            }
+        );
+    }
+
+    #[test]
+    fn peer_id_is_the_p2p_component() {
+        assert_eq!(peer_id_from_addr(PEER), "12D3KooWPeer");
+        assert_eq!(peer_id_from_addr("/dns4/seed/tcp/1"), "/dns4/seed/tcp/1");
+    }
+}
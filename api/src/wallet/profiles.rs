@@ -0,0 +1,478 @@
+//! Separate wallets in one installation.
+//!
+//! Profiles are listed in `profiles.json` in a top-level directory, normally
+//! the config dir. Each new profile gets its own directory under `profiles/`,
+//! holding its wallet data dir and, when node data isn't shared, its node
+//! data. A profile may have its own password, stored only as a salted
+//! PBKDF2-HMAC-SHA256 hash. One profile is open at a time.
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+
+use crate::wallet::{Wallet, WalletError, WalletResult};
+
+/// Profile list, in the profiles root
+pub const PROFILES_FILE: &str = "profiles.json";
+
+/// Directory under the profiles root holding one directory per profile
+pub const PROFILES_DIR: &str = "profiles";
+
+/// PBKDF2 rounds for new password hashes
+pub(crate) const PASSWORD_ITERATIONS: u32 = 100_000;
+
+/// Salted hash of a profile password
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct PasswordHash {
+    /// Hex salt
+    salt: String,
+    iterations: u32,
+    /// Hex PBKDF2-HMAC-SHA256 output
+    hash: String,
+}
+
+impl PasswordHash {
+    fn new(password: &str) -> Self {
+        let salt: [u8; 16] = rand::random();
+        Self {
+            salt: hex::encode(salt),
+            iterations: PASSWORD_ITERATIONS,
+            hash: hex::encode(pbkdf2_sha256(
+                password.as_bytes(),
+                &salt,
+                PASSWORD_ITERATIONS,
+            )),
+        }
+    }
+
+    fn verify(&self, password: &str) -> bool {
+        let Ok(salt) = hex::decode(&self.salt) else {
+            return false;
+        };
+        let hash = pbkdf2_sha256(password.as_bytes(), &salt, self.iterations);
+        // Compare every byte so the time taken doesn't depend on where they differ
+        hex::decode(&self.hash).is_ok_and(|expected| {
+            expected.len() == hash.len()
+                && expected
+                    .iter()
+                    .zip(hash)
+                    .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+                    == 0
+        })
+    }
+}
+
+/// PBKDF2 with HMAC-SHA256, one 32-byte block
+pub(crate) fn pbkdf2_sha256(password: &[u8], salt: &[u8], iterations: u32) -> [u8; 32] {
+    let prf = |data: &[&[u8]]| -> [u8; 32] {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(password).expect("HMAC accepts keys of any length");
+        for part in data {
+            mac.update(part);
+        }
+        mac.finalize().into_bytes().into()
+    };
+    let mut block = prf(&[salt, &1u32.to_be_bytes()]);
+    let mut output = block;
+    for _ in 1..iterations {
+        block = prf(&[&block]);
+        for (out, byte) in output.iter_mut().zip(block) {
+            *out ^= byte;
+        }
+    }
+    output
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WalletProfile {
+    pub name: String,
+    /// The profile's own directory; the wallet lives in `wallet/` inside it
+    pub data_dir: PathBuf,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub last_opened: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    password: Option<PasswordHash>,
+}
+
+impl WalletProfile {
+    /// Wallet data dir of the profile
+    pub fn wallet_dir(&self) -> PathBuf {
+        self.data_dir.join("wallet")
+    }
+
+    /// Node data dir of the profile, used when node data isn't shared
+    pub fn node_dir(&self) -> PathBuf {
+        self.data_dir.join("node")
+    }
+
+    pub fn has_password(&self) -> bool {
+        self.password.is_some()
+    }
+
+    /// Whether `password` opens the profile; any password does if none is set
+    pub fn check_password(&self, password: Option<&str>) -> bool {
+        match &self.password {
+            Some(hash) => password.is_some_and(|password| hash.verify(password)),
+            None => true,
+        }
+    }
+}
+
+fn default_shared_node_data() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ProfilesFile {
+    profiles: Vec<WalletProfile>,
+    /// Whether every profile uses the same node data dir
+    #[serde(default = "default_shared_node_data")]
+    shared_node_data: bool,
+}
+
+impl Default for ProfilesFile {
+    fn default() -> Self {
+        Self {
+            profiles: Vec::new(),
+            shared_node_data: default_shared_node_data(),
+        }
+    }
+}
+
+/// The profiles of one installation
+#[derive(Debug, Clone)]
+pub struct WalletProfileManager {
+    root: PathBuf,
+    file: ProfilesFile,
+}
+
+impl WalletProfileManager {
+    /// Read `profiles.json` from `root`; no profiles if it doesn't exist yet
+    pub fn load(root: &Path) -> WalletResult<Self> {
+        let path = root.join(PROFILES_FILE);
+        let file = if path.exists() {
+            let text = std::fs::read_to_string(&path)
+                .map_err(|e| WalletError::Storage(format!("Failed to read profiles: {}", e)))?;
+            serde_json::from_str(&text)
+                .map_err(|e| WalletError::Serialization(format!("Invalid profiles file: {}", e)))?
+        } else {
+            ProfilesFile::default()
+        };
+        Ok(Self {
+            root: root.to_path_buf(),
+            file,
+        })
+    }
+
+    fn save(&self) -> WalletResult<()> {
+        std::fs::create_dir_all(&self.root)
+            .map_err(|e| WalletError::Storage(format!("Failed to create profiles dir: {}", e)))?;
+        let path = self.root.join(PROFILES_FILE);
+        let tmp_path = path.with_extension("json.tmp");
+        let contents = serde_json::to_string_pretty(&self.file)
+            .map_err(|e| WalletError::Serialization(e.to_string()))?;
+        std::fs::write(&tmp_path, contents)
+            .and_then(|_| std::fs::rename(&tmp_path, &path))
+            .map_err(|e| WalletError::Storage(format!("Failed to save profiles: {}", e)))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Profiles, most recently opened first
+    pub fn profiles(&self) -> Vec<&WalletProfile> {
+        let mut profiles: Vec<&WalletProfile> = self.file.profiles.iter().collect();
+        profiles.sort_by_key(|profile| Reverse(profile.last_opened));
+        profiles
+    }
+
+    pub fn get(&self, name: &str) -> Option<&WalletProfile> {
+        self.file
+            .profiles
+            .iter()
+            .find(|profile| profile.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    fn get_mut(&mut self, name: &str) -> WalletResult<&mut WalletProfile> {
+        self.file
+            .profiles
+            .iter_mut()
+            .find(|profile| profile.name.eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| WalletError::Profile(format!("No profile named {}", name.trim())))
+    }
+
+    fn check_new_name(&self, name: &str) -> WalletResult<()> {
+        if name.is_empty() {
+            return Err(WalletError::Profile(
+                "Profile name must not be empty".to_string(),
+            ));
+        }
+        if self.get(name).is_some() {
+            return Err(WalletError::Profile(format!(
+                "A profile named {} already exists",
+                name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Create a profile with a fresh directory of its own, optionally
+    /// protected by `password`
+    pub fn create(&mut self, name: &str, password: Option<&str>) -> WalletResult<&WalletProfile> {
+        let name = name.trim();
+        self.check_new_name(name)?;
+
+        let slug: String = name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        let profiles_dir = self.root.join(PROFILES_DIR);
+        let mut data_dir = profiles_dir.join(&slug);
+        let mut suffix = 2;
+        while data_dir.exists() {
+            data_dir = profiles_dir.join(format!("{}-{}", slug, suffix));
+            suffix += 1;
+        }
+        std::fs::create_dir_all(&data_dir).map_err(|e| {
+            WalletError::Storage(format!("Failed to create profile directory: {}", e))
+        })?;
+
+        self.push(name, data_dir, password)
+    }
+
+    /// List an existing directory as a profile, e.g. the wallet from before
+    /// profiles existed. Its wallet is expected in `wallet/` inside `data_dir`.
+    pub fn adopt(
+        &mut self,
+        name: &str,
+        data_dir: &Path,
+        password: Option<&str>,
+    ) -> WalletResult<&WalletProfile> {
+        let name = name.trim();
+        self.check_new_name(name)?;
+        self.push(name, data_dir.to_path_buf(), password)
+    }
+
+    fn push(
+        &mut self,
+        name: &str,
+        data_dir: PathBuf,
+        password: Option<&str>,
+    ) -> WalletResult<&WalletProfile> {
+        self.file.profiles.push(WalletProfile {
+            name: name.to_string(),
+            data_dir,
+            created_at: Utc::now(),
+            last_opened: None,
+            password: password
+                .filter(|password| !password.is_empty())
+                .map(PasswordHash::new),
+        });
+        self.save()?;
+        Ok(self.file.profiles.last().expect("profile was just added"))
+    }
+
+    /// Open a profile's wallet, checking its password, and unlock it. The
+    /// wallet takes its lockfile as usual, so close the previous profile's
+    /// wallet first.
+    pub fn open(&mut self, name: &str, password: Option<&str>) -> WalletResult<Wallet> {
+        let profile = self.get_mut(name)?;
+        if !profile.check_password(password) {
+            return Err(WalletError::Profile(format!(
+                "Wrong password for profile {}",
+                profile.name
+            )));
+        }
+        let mut wallet = Wallet::open(&profile.wallet_dir())?;
+        wallet.unlock(password.filter(|_| profile.has_password()))?;
+        profile.last_opened = Some(Utc::now());
+        self.save()?;
+        Ok(wallet)
+    }
+
+    /// Change or, with `new_password` of `None`, remove a profile's password
+    pub fn set_password(
+        &mut self,
+        name: &str,
+        current: Option<&str>,
+        new_password: Option<&str>,
+    ) -> WalletResult<()> {
+        let profile = self.get_mut(name)?;
+        if !profile.check_password(current) {
+            return Err(WalletError::Profile(format!(
+                "Wrong password for profile {}",
+                profile.name
+            )));
+        }
+        profile.password = new_password
+            .filter(|password| !password.is_empty())
+            .map(PasswordHash::new);
+        self.save()
+    }
+
+    /// Whether all profiles use one node data dir rather than each their own
+    pub fn shared_node_data(&self) -> bool {
+        self.file.shared_node_data
+    }
+
+    pub fn set_shared_node_data(&mut self, shared: bool) -> WalletResult<()> {
+        self.file.shared_node_data = shared;
+        self.save()
+    }
+
+    /// Node data dir for a profile: `shared_dir` when node data is shared,
+    /// otherwise the profile's own
+    pub fn node_data_dir(&self, profile: &WalletProfile, shared_dir: &Path) -> PathBuf {
+        if self.file.shared_node_data {
+            shared_dir.to_path_buf()
+        } else {
+            profile.node_dir()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_address, fake_note, TempDir};
+    use crate::wallet::templates::PaymentTemplate;
+
+    #[tokio::test]
+    async fn two_profiles_keep_their_keys_balances_and_files_apart() {
+        let root = TempDir::new("profiles");
+        let mut manager = WalletProfileManager::load(root.path()).unwrap();
+        manager.create("Personal", None).unwrap();
+        manager.create("Business", Some("hunter2")).unwrap();
+
+        let mut personal = manager.open("personal", None).unwrap();
+        let address = personal.generate_key("main").unwrap();
+        personal
+            .observe_note(fake_note().address(address).amount(700).build())
+            .unwrap();
+        personal
+            .payment_templates_mut()
+            .unwrap()
+            .add(PaymentTemplate::new("rent", fake_address(), 100))
+            .unwrap();
+        personal.save_payment_templates().await.unwrap();
+
+        // Both can be open and writable at once: each has its own lockfile
+        let mut business = manager.open("Business", Some("hunter2")).unwrap();
+        assert!(!personal.is_read_only());
+        assert!(!business.is_read_only());
+        assert_ne!(personal.data_dir(), business.data_dir());
+
+        business.load_payment_templates().await.unwrap();
+        assert!(business.keys().list_keys().is_empty());
+        let balance = business.balances().get_total_balance();
+        assert_eq!(balance.confirmed + balance.unconfirmed, 0);
+        assert!(business.payment_templates().templates().is_empty());
+
+        drop(personal);
+        let mut reopened = manager.open("Personal", None).unwrap();
+        reopened.load_payment_templates().await.unwrap();
+        assert!(reopened.payment_templates().get("rent").is_some());
+    }
+
+    #[test]
+    fn a_profile_opens_only_with_its_password() {
+        let root = TempDir::new("profiles");
+        let mut manager = WalletProfileManager::load(root.path()).unwrap();
+        manager.create("Business", Some("hunter2")).unwrap();
+
+        for wrong in [None, Some(""), Some("hunter3")] {
+            assert!(matches!(
+                manager.open("Business", wrong),
+                Err(WalletError::Profile(_))
+            ));
+        }
+        let wallet = manager.open("Business", Some("hunter2")).unwrap();
+        assert!(wallet.is_unlocked());
+        drop(wallet);
+
+        assert!(manager
+            .set_password("Business", Some("wrong"), None)
+            .is_err());
+        manager
+            .set_password("Business", Some("hunter2"), None)
+            .unwrap();
+        assert!(!manager.get("business").unwrap().has_password());
+        assert!(manager.open("Business", None).is_ok());
+    }
+
+    #[test]
+    fn profiles_are_saved_and_listed_most_recently_opened_first() {
+        let root = TempDir::new("profiles");
+        let mut manager = WalletProfileManager::load(root.path()).unwrap();
+        manager.create("First", None).unwrap();
+        manager.create("Second", Some("pw")).unwrap();
+        drop(manager.open("First", None).unwrap());
+
+        let reloaded = WalletProfileManager::load(root.path()).unwrap();
+        let names: Vec<&str> = reloaded
+            .profiles()
+            .iter()
+            .map(|profile| profile.name.as_str())
+            .collect();
+        assert_eq!(names, ["First", "Second"]);
+        assert!(reloaded.get("second").unwrap().check_password(Some("pw")));
+        assert!(reloaded.get("first").unwrap().last_opened.is_some());
+
+        // Only the hash is written, never the password
+        let saved = std::fs::read_to_string(root.join(PROFILES_FILE)).unwrap();
+        assert!(!saved.contains("\"pw\""));
+    }
+
+    #[test]
+    fn names_must_be_unique_and_directories_never_collide() {
+        let root = TempDir::new("profiles");
+        let mut manager = WalletProfileManager::load(root.path()).unwrap();
+        let first = manager.create("My Wallet", None).unwrap().data_dir.clone();
+        assert!(manager.create(" my wallet ", None).is_err());
+        assert!(manager.create("  ", None).is_err());
+
+        let second = manager.create("My-Wallet", None).unwrap().data_dir.clone();
+        assert_eq!(first, root.join(PROFILES_DIR).join("my-wallet"));
+        assert_eq!(second, root.join(PROFILES_DIR).join("my-wallet-2"));
+        assert!(manager.open("Nobody", None).is_err());
+    }
+
+    #[test]
+    fn node_data_is_shared_unless_turned_off() {
+        let root = TempDir::new("profiles");
+        let mut manager = WalletProfileManager::load(root.path()).unwrap();
+        let profile = manager.create("Personal", None).unwrap().clone();
+        let shared = root.join("node");
+
+        assert!(manager.shared_node_data());
+        assert_eq!(manager.node_data_dir(&profile, &shared), shared);
+        manager.set_shared_node_data(false).unwrap();
+        assert_eq!(
+            manager.node_data_dir(&profile, &shared),
+            profile.data_dir.join("node")
+        );
+        assert!(!WalletProfileManager::load(root.path())
+            .unwrap()
+            .shared_node_data());
+    }
+
+    #[test]
+    fn pbkdf2_matches_the_rfc_7914_vector() {
+        // PBKDF2-HMAC-SHA256, P = "passwd", S = "salt", c = 1, first 32 bytes
+        assert_eq!(
+            hex::encode(pbkdf2_sha256(b"passwd", b"salt", 1)),
+            "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc"
+        );
+    }
+}
@@ -0,0 +1,268 @@
+//! Where each note came from.
+//!
+//! Every note is tagged with a `NoteOrigin` when it is first recorded: by
+//! mining for coinbase outputs, by sync and incoming-payment tracking for the
+//! rest, depending on whether the wallet funded the transaction. The tag is
+//! kept on the note. `Wallet::note_provenance` joins a note with the
+//! transaction that created it, as the wallet recorded it and as the chain
+//! has it, and with the block containing it. Notes recorded before tagging
+//! are classified from the recorded transaction instead.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+use crate::wallet::chain::Blockchain;
+use crate::wallet::explorer::{BlockSummary, ChainTransactionView};
+use crate::wallet::mempool::Mempool;
+use crate::wallet::transaction::TransactionDetail;
+use crate::wallet::{Note, TransactionDirection, Wallet, WalletError, WalletResult};
+
+/// How a note reached the wallet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoteOrigin {
+    /// Minted by a block's coinbase
+    Coinbase,
+    /// Paid by a transaction the wallet funded: the change of a send, or a
+    /// move between its own addresses
+    Change,
+    /// Paid by someone else
+    External,
+}
+
+impl NoteOrigin {
+    /// Origin of an output of a transaction with no inputs (`coinbase`) or
+    /// spending at least one of the wallet's notes (`funded_by_wallet`)
+    pub fn of(coinbase: bool, funded_by_wallet: bool) -> Self {
+        if coinbase {
+            NoteOrigin::Coinbase
+        } else if funded_by_wallet {
+            NoteOrigin::Change
+        } else {
+            NoteOrigin::External
+        }
+    }
+}
+
+impl fmt::Display for NoteOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            NoteOrigin::Coinbase => "coinbase",
+            NoteOrigin::Change => "change",
+            NoteOrigin::External => "external payment",
+        })
+    }
+}
+
+/// A note with the transaction and block that created it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoteProvenance {
+    pub note: Note,
+    pub origin: NoteOrigin,
+    /// The wallet's record of the creating transaction; coinbase
+    /// transactions are not recorded
+    pub transaction: Option<TransactionDetail>,
+    /// The creating transaction as the chain has it, once mined and while its
+    /// block body is kept
+    pub chain_transaction: Option<ChainTransactionView>,
+    /// Block containing the creating transaction; `None` while pending
+    pub block: Option<BlockSummary>,
+    /// Blocks on top of and including the containing one; 0 while pending
+    pub confirmations: u64,
+}
+
+impl NoteProvenance {
+    pub fn is_pending(&self) -> bool {
+        self.note.block_height.is_none()
+    }
+}
+
+impl Wallet {
+    /// Join note `note_id` with the transaction that created it and the block
+    /// containing that, looking in `mempool` and `chain` when given
+    pub fn note_provenance(
+        &self,
+        note_id: &Uuid,
+        mempool: Option<&Mempool>,
+        chain: Option<&Blockchain>,
+    ) -> WalletResult<NoteProvenance> {
+        let note = self
+            .balances
+            .get_note(note_id)
+            .cloned()
+            .ok_or(WalletError::NoteNotFound(*note_id))?;
+        let transaction = self.transaction_detail(&note.transaction_id, mempool, chain);
+        let origin = note.origin.unwrap_or_else(|| {
+            let funded_by_wallet = transaction.as_ref().is_some_and(|detail| {
+                detail.transaction.direction != TransactionDirection::Incoming
+            });
+            NoteOrigin::of(note.coinbase, funded_by_wallet)
+        });
+        let block = chain.zip(note.block_height).and_then(|(chain, height)| {
+            chain
+                .block_at(height)
+                .map(|block| BlockSummary::of(chain, block))
+        });
+
+        Ok(NoteProvenance {
+            origin,
+            transaction,
+            chain_transaction: chain
+                .and_then(|chain| ChainTransactionView::find(chain, &note.transaction_id)),
+            block,
+            confirmations: self.balances.confirmations(&note),
+            note,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{
+        fake_address, fake_block, fake_bytes32, fake_coinbase, fake_note, fake_signed_transaction,
+        fake_txid, fakenet_chain_config,
+    };
+    use crate::wallet::checkpoints::Checkpoint;
+    use crate::wallet::keys::{
+        NockchainTransaction, OutPoint, TransactionInput, TransactionOutput,
+    };
+    use crate::wallet::BlockchainConfig;
+
+    /// Chain of one block per entry of `blocks`, holding those transactions,
+    /// whose proof of work isn't checked
+    fn chain_of(blocks: Vec<Vec<NockchainTransaction>>) -> Blockchain {
+        let mut chain = Blockchain::new(BlockchainConfig {
+            checkpoints: vec![Checkpoint::new(u64::MAX, [0; 32])],
+            skip_pow_below_checkpoint: true,
+            max_future_block_time: u64::MAX / 2,
+            ..fakenet_chain_config()
+        });
+        for transactions in blocks {
+            let block = chain.new_block(transactions, 0x1f7f_ffff);
+            chain.add_block(block).unwrap();
+        }
+        chain
+    }
+
+    #[test]
+    fn synced_notes_carry_their_origin_block_and_depth() {
+        let mut wallet = Wallet::new();
+        let address = wallet.generate_key("main").unwrap();
+        let mined = fake_coinbase(&address, 5_000);
+        let external = fake_block()
+            .paying(&address, 700)
+            .build()
+            .transactions
+            .remove(0);
+        let mut send = NockchainTransaction::new(fake_txid());
+        send.inputs.push(TransactionInput {
+            previous_output: OutPoint::new(mined.id.clone(), 0),
+            amount: 5_000,
+        });
+        for (amount, to) in [(3_000, fake_address()), (1_900, address.clone())] {
+            send.outputs.push(TransactionOutput {
+                amount,
+                recipient_address: to.to_string(),
+            });
+        }
+        send.hash = fake_bytes32().to_vec();
+        let chain = chain_of(vec![
+            Vec::new(),
+            vec![mined.clone()],
+            vec![fake_coinbase(&fake_address(), 50), external.clone()],
+            vec![fake_coinbase(&fake_address(), 50), send.clone()],
+        ]);
+        wallet.sync_filtered(&chain, 0).unwrap();
+
+        let expected = [
+            (&mined.id, NoteOrigin::Coinbase, 1, 3),
+            (&external.id, NoteOrigin::External, 2, 2),
+            (&send.id, NoteOrigin::Change, 3, 1),
+        ];
+        for (tx_id, origin, height, confirmations) in expected {
+            let note = wallet
+                .balances()
+                .notes()
+                .into_iter()
+                .find(|note| note.transaction_id == *tx_id)
+                .unwrap()
+                .clone();
+            assert_eq!(note.origin, Some(origin));
+            let provenance = wallet
+                .note_provenance(&note.id, None, Some(&chain))
+                .unwrap();
+            assert_eq!(provenance.origin, origin, "{}", tx_id);
+            assert_eq!(
+                provenance.block.as_ref().map(|block| block.height),
+                Some(height)
+            );
+            assert_eq!(provenance.confirmations, confirmations);
+            let on_chain = provenance.chain_transaction.as_ref().unwrap();
+            assert_eq!(on_chain.tx_id, *tx_id);
+            assert_eq!(on_chain.block_height, height);
+            assert_eq!(on_chain.coinbase, origin == NoteOrigin::Coinbase);
+            assert!(!provenance.is_pending());
+        }
+    }
+
+    #[test]
+    fn a_pending_note_has_no_block_and_untagged_notes_are_classified() {
+        let mut wallet = Wallet::new();
+        let address = wallet.generate_key("main").unwrap();
+        let incoming = fake_signed_transaction().paying(&address, 800).build();
+        let sent = fake_signed_transaction()
+            .paying(&fake_address(), 300)
+            .paying(&address, 600)
+            .build();
+        wallet.record_transaction(incoming.clone(), false);
+        wallet.record_transaction(sent.clone(), true);
+        let mut mempool = Mempool::new();
+        mempool.insert(incoming.clone()).unwrap();
+
+        let untagged = |tx_id: &str, index: u32, amount: u64| {
+            let mut note = fake_note()
+                .address(address.clone())
+                .amount(amount)
+                .outpoint(tx_id, index)
+                .build();
+            note.origin = None;
+            note
+        };
+        let pending = untagged(&incoming.id, 0, 800);
+        let change = untagged(&sent.id, 1, 600);
+        wallet.observe_note(pending.clone()).unwrap();
+        wallet.observe_note(change.clone()).unwrap();
+
+        let provenance = wallet
+            .note_provenance(&pending.id, Some(&mempool), None)
+            .unwrap();
+        assert!(provenance.is_pending());
+        assert_eq!(provenance.origin, NoteOrigin::External);
+        assert_eq!(provenance.block, None);
+        assert_eq!(provenance.chain_transaction, None);
+        assert_eq!(provenance.confirmations, 0);
+        assert_eq!(
+            provenance.transaction.map(|detail| detail.transaction.id),
+            Some(incoming.id)
+        );
+
+        let provenance = wallet.note_provenance(&change.id, None, None).unwrap();
+        assert_eq!(provenance.origin, NoteOrigin::Change);
+
+        let unknown = Uuid::new_v4();
+        assert!(matches!(
+            wallet.note_provenance(&unknown, None, None),
+            Err(WalletError::NoteNotFound(id)) if id == unknown
+        ));
+    }
+
+    #[test]
+    fn the_origin_follows_the_funding_of_the_creating_transaction() {
+        assert_eq!(NoteOrigin::of(true, false), NoteOrigin::Coinbase);
+        assert_eq!(NoteOrigin::of(false, true), NoteOrigin::Change);
+        assert_eq!(NoteOrigin::of(false, false), NoteOrigin::External);
+        assert_eq!(NoteOrigin::External.to_string(), "external payment");
+    }
+}
@@ -0,0 +1,408 @@
+//! Outbound connections through a SOCKS5 proxy, such as Tor.
+//!
+//! With `NockchainNodeConfig::socks5_proxy` set, peer dials and `NodeClient`
+//! connections are opened with a SOCKS5 CONNECT (RFC 1928, no
+//! authentication) instead of directly. There is no fallback: if the proxy
+//! can't be reached the connection fails with `WalletError::ProxyUnreachable`,
+//! and if the proxy can't reach the target it fails with
+//! `WalletError::ProxyConnectFailed`. Neither counts against a peer's score.
+//! Once through, the peer handshake runs over the proxied stream.
+//!
+//! With `proxy_dns`, hostnames are handed to the proxy to resolve, so lookups
+//! never reach the local resolver; DNS seeds, which need the local resolver,
+//! are then only taken from the known peers cache. Without it, hostnames are
+//! resolved locally and the proxy is given the address. The proxy's own
+//! address is always resolved locally. Inbound listening doesn't use the
+//! proxy.
+
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::wallet::network::NockchainNodeConfig;
+use crate::wallet::seeds::SeedResolver;
+use crate::wallet::{WalletError, WalletResult};
+
+const SOCKS_VERSION: u8 = 5;
+const NO_AUTHENTICATION: u8 = 0;
+const NO_ACCEPTABLE_METHODS: u8 = 0xff;
+const CMD_CONNECT: u8 = 1;
+const ATYP_IPV4: u8 = 1;
+const ATYP_DOMAIN: u8 = 3;
+const ATYP_IPV6: u8 = 4;
+
+/// A SOCKS5 proxy outbound connections go through
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Socks5Proxy {
+    /// `host:port` of the proxy, e.g. `127.0.0.1:9050` for Tor
+    pub addr: String,
+    /// Have the proxy resolve hostnames instead of the local resolver
+    #[serde(default)]
+    pub remote_dns: bool,
+}
+
+impl Socks5Proxy {
+    pub fn new(addr: impl Into<String>, remote_dns: bool) -> Self {
+        Self {
+            addr: addr.into(),
+            remote_dns,
+        }
+    }
+
+    /// The proxy `config` sends outbound connections through, if any
+    pub fn from_config(config: &NockchainNodeConfig) -> Option<Self> {
+        config
+            .socks5_proxy
+            .as_ref()
+            .map(|addr| Self::new(addr.trim(), config.proxy_dns))
+    }
+
+    /// Open a connection to `host:port` through the proxy. `host` may be an
+    /// IP address or a hostname; see the module docs for how hostnames are
+    /// resolved.
+    pub async fn connect(&self, host: &str, port: u16) -> WalletResult<TcpStream> {
+        let target = match host.parse::<IpAddr>() {
+            Ok(ip) => Target::Ip(SocketAddr::new(ip, port)),
+            Err(_) if self.remote_dns => {
+                if host.is_empty() || host.len() > u8::MAX as usize {
+                    return Err(self.connect_failed(host, port, "hostname is not 1-255 bytes"));
+                }
+                Target::Domain(host, port)
+            }
+            Err(_) => {
+                let addr = tokio::net::lookup_host((host, port))
+                    .await
+                    .ok()
+                    .and_then(|mut addrs| addrs.next())
+                    .ok_or_else(|| self.connect_failed(host, port, "hostname did not resolve"))?;
+                Target::Ip(addr)
+            }
+        };
+
+        let mut stream = TcpStream::connect(self.addr.as_str())
+            .await
+            .map_err(|e| self.unreachable(e.to_string()))?;
+        self.negotiate(&mut stream).await?;
+        self.request(&mut stream, &target, host, port).await?;
+        Ok(stream)
+    }
+
+    /// Connect to `addr` through the proxy
+    pub async fn connect_addr(&self, addr: SocketAddr) -> WalletResult<TcpStream> {
+        self.connect(&addr.ip().to_string(), addr.port()).await
+    }
+
+    /// Greet the proxy and agree on no authentication
+    async fn negotiate(&self, stream: &mut TcpStream) -> WalletResult<()> {
+        stream
+            .write_all(&[SOCKS_VERSION, 1, NO_AUTHENTICATION])
+            .await
+            .map_err(|e| self.unreachable(e.to_string()))?;
+        let mut reply = [0u8; 2];
+        stream
+            .read_exact(&mut reply)
+            .await
+            .map_err(|e| self.unreachable(e.to_string()))?;
+        match reply {
+            [SOCKS_VERSION, NO_AUTHENTICATION] => Ok(()),
+            [SOCKS_VERSION, NO_ACCEPTABLE_METHODS] => {
+                Err(self.unreachable("proxy requires authentication".to_string()))
+            }
+            _ => Err(self.unreachable("not a SOCKS5 proxy".to_string())),
+        }
+    }
+
+    /// Ask the proxy to connect to `target` and read its reply
+    async fn request(
+        &self,
+        stream: &mut TcpStream,
+        target: &Target<'_>,
+        host: &str,
+        port: u16,
+    ) -> WalletResult<()> {
+        let mut request = vec![SOCKS_VERSION, CMD_CONNECT, 0];
+        match target {
+            Target::Ip(SocketAddr::V4(addr)) => {
+                request.push(ATYP_IPV4);
+                request.extend_from_slice(&addr.ip().octets());
+            }
+            Target::Ip(SocketAddr::V6(addr)) => {
+                request.push(ATYP_IPV6);
+                request.extend_from_slice(&addr.ip().octets());
+            }
+            Target::Domain(domain, _) => {
+                request.push(ATYP_DOMAIN);
+                request.push(domain.len() as u8);
+                request.extend_from_slice(domain.as_bytes());
+            }
+        }
+        request.extend_from_slice(&target.port().to_be_bytes());
+        stream
+            .write_all(&request)
+            .await
+            .map_err(|e| self.unreachable(e.to_string()))?;
+
+        let mut reply = [0u8; 4];
+        stream
+            .read_exact(&mut reply)
+            .await
+            .map_err(|e| self.unreachable(e.to_string()))?;
+        if reply[0] != SOCKS_VERSION {
+            return Err(self.unreachable("not a SOCKS5 proxy".to_string()));
+        }
+        if reply[1] != 0 {
+            return Err(self.connect_failed(host, port, reply_reason(reply[1])));
+        }
+
+        // Skip the address the proxy bound for us
+        let bound_len = match reply[3] {
+            ATYP_IPV4 => 4,
+            ATYP_IPV6 => 16,
+            ATYP_DOMAIN => {
+                let mut len = [0u8; 1];
+                stream
+                    .read_exact(&mut len)
+                    .await
+                    .map_err(|e| self.unreachable(e.to_string()))?;
+                len[0] as usize
+            }
+            _ => return Err(self.unreachable("malformed SOCKS5 reply".to_string())),
+        };
+        let mut bound = vec![0u8; bound_len + 2];
+        stream
+            .read_exact(&mut bound)
+            .await
+            .map_err(|e| self.unreachable(e.to_string()))?;
+        Ok(())
+    }
+
+    fn unreachable(&self, reason: String) -> WalletError {
+        WalletError::ProxyUnreachable {
+            proxy: self.addr.clone(),
+            reason,
+        }
+    }
+
+    fn connect_failed(&self, host: &str, port: u16, reason: &str) -> WalletError {
+        WalletError::ProxyConnectFailed {
+            proxy: self.addr.clone(),
+            target: format!("{}:{}", host, port),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Socks5Proxy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SOCKS5 {}", self.addr)
+    }
+}
+
+/// Where a CONNECT request goes
+enum Target<'a> {
+    Ip(SocketAddr),
+    Domain(&'a str, u16),
+}
+
+impl Target<'_> {
+    fn port(&self) -> u16 {
+        match self {
+            Target::Ip(addr) => addr.port(),
+            Target::Domain(_, port) => *port,
+        }
+    }
+}
+
+/// Meaning of a SOCKS5 reply code, from RFC 1928 section 6
+fn reply_reason(code: u8) -> &'static str {
+    match code {
+        1 => "general failure",
+        2 => "connection not allowed by ruleset",
+        3 => "network unreachable",
+        4 => "host unreachable",
+        5 => "connection refused",
+        6 => "TTL expired",
+        7 => "command not supported",
+        8 => "address type not supported",
+        _ => "unknown error",
+    }
+}
+
+/// Host and TCP port of a multiaddr such as `/ip4/1.2.3.4/tcp/4001/p2p/...`
+/// or `/dns4/seed.example/tcp/4001`
+pub fn tcp_target(address: &str) -> Option<(String, u16)> {
+    let mut parts = address.trim_start_matches('/').split('/');
+    let mut host = None;
+    let mut port = None;
+    while let (Some(protocol), Some(value)) = (parts.next(), parts.next()) {
+        match protocol {
+            "ip4" | "ip6" | "dns" | "dns4" | "dns6" => host = Some(value.to_string()),
+            "tcp" => port = value.parse().ok(),
+            _ => {}
+        }
+    }
+    host.zip(port)
+}
+
+/// Stands in for the DNS seed resolver while `proxy_dns` is set, so seeds
+/// are never looked up locally
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoLocalLookup;
+
+impl SeedResolver for NoLocalLookup {
+    fn resolve<'a>(&'a self, seed: &'a str, _port: u16) -> BoxFuture<'a, io::Result<Vec<String>>> {
+        Box::pin(async move {
+            Err(io::Error::other(format!(
+                "{} is not looked up while proxy_dns is set; add its peers to `peers` instead",
+                seed
+            )))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockSocks5;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+
+    /// An address nothing listens on
+    async fn closed_port() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap().to_string()
+    }
+
+    async fn echo(mut stream: TcpStream) -> Vec<u8> {
+        stream.write_all(b"ping").await.unwrap();
+        let mut reply = [0u8; 4];
+        stream.read_exact(&mut reply).await.unwrap();
+        reply.to_vec()
+    }
+
+    #[tokio::test]
+    async fn connections_traverse_the_proxy() {
+        let mock = MockSocks5::start(0).await;
+        let proxy = Socks5Proxy::new(mock.addr(), false);
+
+        let stream = proxy
+            .connect_addr("10.1.2.3:4001".parse().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(echo(stream).await, b"ping");
+        proxy.connect("2001:db8::7", 4001).await.unwrap();
+        assert_eq!(mock.targets(), vec!["10.1.2.3:4001", "[2001:db8::7]:4001"]);
+    }
+
+    #[tokio::test]
+    async fn hostnames_go_to_the_proxy_only_with_proxy_dns() {
+        let mock = MockSocks5::start(0).await;
+        let remote = Socks5Proxy::new(mock.addr(), true);
+        remote.connect("seed.nockchain.net", 4001).await.unwrap();
+        assert_eq!(mock.targets(), vec!["seed.nockchain.net:4001"]);
+
+        // Without it the proxy is handed the locally resolved address
+        let local = Socks5Proxy::new(mock.addr(), false);
+        local.connect("localhost", 4001).await.unwrap();
+        let targets = mock.targets();
+        let (host, port) = targets[1].rsplit_once(':').unwrap();
+        let ip: IpAddr = host.trim_matches(['[', ']']).parse().unwrap();
+        assert!(ip.is_loopback());
+        assert_eq!(port, "4001");
+
+        let too_long = "a".repeat(256);
+        assert!(matches!(
+            remote.connect(&too_long, 4001).await,
+            Err(WalletError::ProxyConnectFailed { .. })
+        ));
+        assert_eq!(mock.targets().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn a_target_the_proxy_cannot_reach_fails_with_its_reason() {
+        let mock = MockSocks5::start(5).await;
+        let proxy = Socks5Proxy::new(mock.addr(), false);
+        match proxy.connect("10.1.2.3", 4001).await {
+            Err(WalletError::ProxyConnectFailed {
+                proxy,
+                target,
+                reason,
+            }) => {
+                assert_eq!(proxy, mock.addr());
+                assert_eq!(target, "10.1.2.3:4001");
+                assert_eq!(reason, "connection refused");
+            }
+            other => panic!("expected ProxyConnectFailed, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn an_unreachable_proxy_is_never_bypassed() {
+        let target = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = Socks5Proxy::new(closed_port().await, false);
+        assert!(matches!(
+            proxy.connect_addr(target.local_addr().unwrap()).await,
+            Err(WalletError::ProxyUnreachable { .. })
+        ));
+        assert!(
+            tokio::time::timeout(Duration::from_millis(200), target.accept())
+                .await
+                .is_err(),
+            "the target was dialed directly"
+        );
+    }
+
+    #[tokio::test]
+    async fn a_proxy_asking_for_authentication_is_unreachable() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut greeting = [0u8; 3];
+            stream.read_exact(&mut greeting).await.unwrap();
+            stream
+                .write_all(&[SOCKS_VERSION, NO_ACCEPTABLE_METHODS])
+                .await
+                .unwrap();
+        });
+
+        match Socks5Proxy::new(addr, false)
+            .connect("10.1.2.3", 4001)
+            .await
+        {
+            Err(WalletError::ProxyUnreachable { reason, .. }) => {
+                assert_eq!(reason, "proxy requires authentication")
+            }
+            other => panic!("expected ProxyUnreachable, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn tcp_targets_are_read_from_multiaddrs() {
+        assert_eq!(
+            tcp_target("/ip4/10.0.0.1/tcp/4001/p2p/12D3KooWPeer"),
+            Some(("10.0.0.1".to_string(), 4001))
+        );
+        assert_eq!(
+            tcp_target("/dns4/seed.nockchain.net/tcp/4001"),
+            Some(("seed.nockchain.net".to_string(), 4001))
+        );
+        assert_eq!(tcp_target("/ip4/10.0.0.1/udp/4001/quic"), None);
+        assert_eq!(tcp_target("/p2p/12D3KooWPeer"), None);
+    }
+
+    #[test]
+    fn the_proxy_comes_from_the_node_config() {
+        let mut config = NockchainNodeConfig::default();
+        assert_eq!(Socks5Proxy::from_config(&config), None);
+        config.socks5_proxy = Some(" 127.0.0.1:9050 ".to_string());
+        config.proxy_dns = true;
+        let proxy = Socks5Proxy::from_config(&config).unwrap();
+        assert_eq!(proxy, Socks5Proxy::new("127.0.0.1:9050", true));
+        assert_eq!(proxy.to_string(), "SOCKS5 127.0.0.1:9050");
+    }
+}
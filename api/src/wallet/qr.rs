@@ -0,0 +1,491 @@
+//! QR codes of addresses as images to save or print.
+//!
+//! SVG comes from `qrcode`'s own renderer. PNG is written here: a QR code is
+//! black and white, so a 1-bit grayscale image compressed with the zlib
+//! encoder already used for debug bundles is all that is needed, without an
+//! image crate.
+//!
+//! The backup sheet holds only what is safe to print: the key's label, its
+//! address, the address as a QR code and when the key was created. No secret
+//! ever reaches it.
+
+use chrono::{DateTime, Utc};
+use flate2::write::ZlibEncoder;
+use flate2::{Compression, Crc};
+use qrcode::render::svg;
+use qrcode::{Color, QrCode};
+use std::io::Write;
+
+use crate::wallet::audit::{AuditCategory, AuditEvent, AuditFilter};
+use crate::wallet::{Address, Wallet, WalletError, WalletResult};
+
+/// Modules of white border the QR spec asks for around the code
+const QUIET_ZONE: usize = 4;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+fn encode(data: &str) -> WalletResult<QrCode> {
+    QrCode::new(data.as_bytes())
+        .map_err(|e| WalletError::Serialization(format!("Cannot make a QR code: {}", e)))
+}
+
+/// `data` as a QR code in SVG, at least 200 pixels across
+pub fn render_svg(data: &str) -> WalletResult<String> {
+    Ok(encode(data)?
+        .render::<svg::Color>()
+        .min_dimensions(200, 200)
+        .build())
+}
+
+/// `data` as a QR code in a 1-bit grayscale PNG. Modules are scaled to a whole
+/// number of pixels, so the image is at most `size` pixels across, and larger
+/// only if `size` is too small for one pixel per module.
+pub fn render_png(data: &str, size: u32) -> WalletResult<Vec<u8>> {
+    let code = encode(data)?;
+    let width = code.width();
+    let colors = code.to_colors();
+    let modules = width + 2 * QUIET_ZONE;
+    let scale = (size as usize / modules).max(1);
+    let pixels = modules * scale;
+
+    // Each row is a filter byte (0, none) then one bit per pixel, 1 for white
+    let row_len = 1 + pixels.div_ceil(8);
+    let mut raw = vec![0u8; row_len * pixels];
+    for y in 0..pixels {
+        let row = &mut raw[y * row_len..(y + 1) * row_len];
+        for x in 0..pixels {
+            let (mx, my) = (x / scale, y / scale);
+            let dark = (QUIET_ZONE..QUIET_ZONE + width).contains(&mx)
+                && (QUIET_ZONE..QUIET_ZONE + width).contains(&my)
+                && colors[(my - QUIET_ZONE) * width + (mx - QUIET_ZONE)] == Color::Dark;
+            if !dark {
+                row[1 + x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+    }
+
+    let mut zlib = ZlibEncoder::new(Vec::new(), Compression::best());
+    zlib.write_all(&raw)
+        .map_err(|e| WalletError::Serialization(format!("Failed to compress PNG: {}", e)))?;
+    let compressed = zlib
+        .finish()
+        .map_err(|e| WalletError::Serialization(format!("Failed to compress PNG: {}", e)))?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(pixels as u32).to_be_bytes());
+    header.extend_from_slice(&(pixels as u32).to_be_bytes());
+    // Bit depth 1, grayscale, deflate, adaptive filtering, no interlace
+    header.extend_from_slice(&[1, 0, 0, 0, 0]);
+
+    let mut png = PNG_SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &compressed);
+    write_chunk(&mut png, b"IEND", &[]);
+    Ok(png)
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let mut crc = Crc::new();
+    crc.update(kind);
+    crc.update(data);
+    png.extend_from_slice(&crc.sum().to_be_bytes());
+}
+
+/// Printable HTML page for one address: `label`, the address in the
+/// `nock_` form, its QR code and the creation date if known
+pub fn backup_sheet_html(
+    label: &str,
+    address: &Address,
+    created_at: Option<DateTime<Utc>>,
+) -> WalletResult<String> {
+    let address = address.to_nockchain_string();
+    let qr = render_svg(&address)?;
+    let created = created_at
+        .map(|at| at.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    let label = escape_html(label);
+
+    Ok(format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Address backup: {label}</title>
+<style>
+body {{ font-family: sans-serif; margin: 40px; color: #000; }}
+.sheet {{ border: 1px solid #000; padding: 24px; max-width: 480px; }}
+.qr svg {{ width: 240px; height: 240px; }}
+.address {{ font-family: monospace; font-size: 14px; word-break: break-all; }}
+.note {{ font-size: 12px; margin-top: 16px; }}
+</style>
+</head>
+<body>
+<div class="sheet">
+<h1>{label}</h1>
+<div class="qr">{qr}</div>
+<p class="address">{address}</p>
+<p>Created: {created}</p>
+<p class="note">Public receive address. This sheet holds no secret key and cannot spend funds.</p>
+</div>
+</body>
+</html>
+"#
+    ))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl Wallet {
+    /// When key `name` was last generated or imported, from the audit log;
+    /// `None` for keys older than the log
+    pub fn key_created_at(&self, name: &str) -> Option<DateTime<Utc>> {
+        let filter = AuditFilter {
+            category: Some(AuditCategory::Keys),
+            ..Default::default()
+        };
+        self.audit_log(&filter)
+            .into_iter()
+            .filter(|entry| entry.outcome.is_success())
+            .filter(|entry| match &entry.event {
+                AuditEvent::KeyGenerated { key_name } | AuditEvent::KeyImported { key_name } => {
+                    key_name == name
+                }
+                _ => false,
+            })
+            .map(|entry| entry.timestamp)
+            .max()
+    }
+
+    /// Printable backup sheet for key `name`'s address
+    pub fn address_backup_sheet(&self, name: &str) -> WalletResult<String> {
+        let key = self
+            .keys()
+            .get_key(name)
+            .ok_or_else(|| WalletError::KeyNotFound(name.to_string()))?;
+        backup_sheet_html(name, key.address(), self.key_created_at(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_address, fixture_time};
+    use crate::wallet::secret_key::encode_secret_key;
+    use flate2::read::ZlibDecoder;
+    use qrcode::canvas::is_functional;
+    use qrcode::Version;
+    use std::io::Read;
+
+    /// Data codewords per block at level M, as (size, count) pairs, for the
+    /// versions an address can need
+    const BLOCKS_M: [&[(usize, usize)]; 10] = [
+        &[(16, 1)],
+        &[(28, 1)],
+        &[(44, 1)],
+        &[(32, 2)],
+        &[(43, 2)],
+        &[(27, 4)],
+        &[(31, 4)],
+        &[(38, 2), (39, 2)],
+        &[(36, 3), (37, 2)],
+        &[(43, 4), (44, 1)],
+    ];
+
+    /// The PNG's pixels, true for dark, checking the header on the way
+    fn png_pixels(png: &[u8]) -> Vec<Vec<bool>> {
+        assert_eq!(png[..8], PNG_SIGNATURE);
+        let (mut size, mut idat, mut rest) = (0, Vec::new(), &png[8..]);
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let (kind, data) = (&rest[4..8], &rest[8..8 + len]);
+            let mut crc = Crc::new();
+            crc.update(kind);
+            crc.update(data);
+            assert_eq!(rest[8 + len..12 + len], crc.sum().to_be_bytes());
+            match kind {
+                b"IHDR" => {
+                    size = u32::from_be_bytes(data[..4].try_into().unwrap()) as usize;
+                    assert_eq!(data[4..8], data[..4], "the image is square");
+                    assert_eq!(data[8..], [1, 0, 0, 0, 0]);
+                }
+                b"IDAT" => idat.extend_from_slice(data),
+                b"IEND" => assert!(data.is_empty()),
+                other => panic!("unexpected chunk {:?}", other),
+            }
+            rest = &rest[12 + len..];
+        }
+
+        let mut raw = Vec::new();
+        ZlibDecoder::new(&idat[..]).read_to_end(&mut raw).unwrap();
+        let row_len = 1 + size.div_ceil(8);
+        assert_eq!(raw.len(), row_len * size);
+        raw.chunks(row_len)
+            .map(|row| {
+                assert_eq!(row[0], 0, "rows are unfiltered");
+                (0..size)
+                    .map(|x| row[1 + x / 8] & (0x80 >> (x % 8)) == 0)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The modules of the code in `pixels`, sampled at each module's centre
+    fn modules(pixels: &[Vec<bool>]) -> Vec<Vec<bool>> {
+        // The finder pattern's corner sits just inside the quiet zone
+        let corner = pixels
+            .iter()
+            .find_map(|row| row.iter().position(|&dark| dark))
+            .expect("no dark pixel");
+        let scale = corner / QUIET_ZONE;
+        let width = pixels.len() / scale - 2 * QUIET_ZONE;
+        (0..width)
+            .map(|y| {
+                (0..width)
+                    .map(|x| pixels[corner + y * scale + scale / 2][corner + x * scale + scale / 2])
+                    .collect()
+            })
+            .collect()
+    }
+
+    const ALPHANUMERIC: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+    fn mask_applies(mask: u16, x: usize, y: usize) -> bool {
+        match mask {
+            0 => (x + y).is_multiple_of(2),
+            1 => y.is_multiple_of(2),
+            2 => x.is_multiple_of(3),
+            3 => (x + y).is_multiple_of(3),
+            4 => (y / 2 + x / 3).is_multiple_of(2),
+            5 => (x * y) % 2 + (x * y) % 3 == 0,
+            6 => ((x * y) % 2 + (x * y) % 3).is_multiple_of(2),
+            _ => ((x + y) % 2 + (x * y) % 3).is_multiple_of(2),
+        }
+    }
+
+    /// Reads a level M QR code back into its payload
+    fn read_qr(code: &[Vec<bool>]) -> String {
+        let width = code.len();
+        let number = (width - 17) / 4;
+        let version = Version::Normal(number as i16);
+
+        let format_coords = [
+            (0, 8),
+            (1, 8),
+            (2, 8),
+            (3, 8),
+            (4, 8),
+            (5, 8),
+            (7, 8),
+            (8, 8),
+            (8, 7),
+            (8, 5),
+            (8, 4),
+            (8, 3),
+            (8, 2),
+            (8, 1),
+            (8, 0),
+        ];
+        let format = format_coords
+            .iter()
+            .fold(0u16, |acc, &(x, y)| acc << 1 | code[y][x] as u16)
+            ^ 0x5412;
+        assert_eq!(format >> 13, 0b00, "error correction level M");
+        let mask = (format >> 10) & 0b111;
+
+        // Data modules run up and down column pairs from the bottom right,
+        // stepping over the vertical timing pattern
+        let mut bits = Vec::new();
+        let mut right = width - 1;
+        let mut upward = true;
+        loop {
+            for step in 0..width {
+                let y = if upward { width - 1 - step } else { step };
+                for x in [right, right - 1] {
+                    if !is_functional(version, width as i16, x as i16, y as i16) {
+                        bits.push(code[y][x] ^ mask_applies(mask, x, y));
+                    }
+                }
+            }
+            upward = !upward;
+            if right < 3 {
+                break;
+            }
+            right -= 2;
+            if right == 6 {
+                right = 5;
+            }
+        }
+        let codewords: Vec<u8> = bits
+            .chunks_exact(8)
+            .map(|byte| byte.iter().fold(0, |acc, &bit| acc << 1 | bit as u8))
+            .collect();
+
+        // Data codewords are interleaved across blocks, one from each in turn
+        let sizes: Vec<usize> = BLOCKS_M[number - 1]
+            .iter()
+            .flat_map(|&(size, count)| std::iter::repeat_n(size, count))
+            .collect();
+        let mut blocks = vec![Vec::new(); sizes.len()];
+        let mut next = codewords.iter();
+        for i in 0..*sizes.iter().max().unwrap() {
+            for (block, &size) in blocks.iter_mut().zip(&sizes) {
+                if i < size {
+                    block.push(*next.next().unwrap());
+                }
+            }
+        }
+        let data: Vec<bool> = blocks
+            .concat()
+            .iter()
+            .flat_map(|byte| (0..8).rev().map(move |bit| byte >> bit & 1 == 1))
+            .collect();
+
+        // `qrcode` splits the payload into numeric, alphanumeric and byte
+        // segments, whichever is shortest, up to a terminator
+        let mut at = 0;
+        let mut read = |len: usize| {
+            let value = data[at..at + len]
+                .iter()
+                .fold(0usize, |acc, &bit| acc << 1 | bit as usize);
+            at += len;
+            value
+        };
+        let mut payload = Vec::new();
+        loop {
+            match read(4) {
+                0b0000 => break,
+                0b0001 => {
+                    let mut len = read(if number < 10 { 10 } else { 12 });
+                    while len > 0 {
+                        let digits = len.min(3);
+                        let value = read([4, 7, 10][digits - 1]);
+                        payload.extend(format!("{:0width$}", value, width = digits).bytes());
+                        len -= digits;
+                    }
+                }
+                0b0010 => {
+                    let mut len = read(if number < 10 { 9 } else { 11 });
+                    while len > 0 {
+                        let pair = len.min(2);
+                        let value = read([6, 11][pair - 1]);
+                        if pair == 2 {
+                            payload.push(ALPHANUMERIC[value / 45]);
+                        }
+                        payload.push(ALPHANUMERIC[value % 45]);
+                        len -= pair;
+                    }
+                }
+                0b0100 => {
+                    let len = read(if number < 10 { 8 } else { 16 });
+                    payload.extend((0..len).map(|_| read(8) as u8));
+                }
+                mode => panic!("unexpected segment mode {:04b}", mode),
+            }
+        }
+        String::from_utf8(payload).unwrap()
+    }
+
+    #[test]
+    fn png_decodes_back_to_the_address() {
+        let address = fake_address().to_nockchain_string();
+        let png = render_png(&address, 300).unwrap();
+        assert_eq!(read_qr(&modules(&png_pixels(&png))), address);
+    }
+
+    #[test]
+    fn png_decodes_at_every_scale() {
+        let data = "nock_payload-with-MIXED-CASE 12345678 & symbols?";
+        for size in [1, 57, 200, 512] {
+            let pixels = png_pixels(&render_png(data, size).unwrap());
+            let modules_across = encode(data).unwrap().width() + 2 * QUIET_ZONE;
+            assert!(pixels.len() as u32 <= size || pixels.len() == modules_across);
+            assert_eq!(read_qr(&modules(&pixels)), data, "size {}", size);
+        }
+    }
+
+    #[test]
+    fn png_matches_the_code_module_for_module_inside_a_quiet_zone() {
+        let address = fake_address().to_nockchain_string();
+        let code = encode(&address).unwrap();
+        let pixels = png_pixels(&render_png(&address, 400).unwrap());
+        let modules = modules(&pixels);
+
+        let expected: Vec<bool> = code.to_colors().iter().map(|c| *c == Color::Dark).collect();
+        assert_eq!(modules.concat(), expected);
+        let scale = pixels.len() / (code.width() + 2 * QUIET_ZONE);
+        assert!(pixels.len() <= 400);
+        assert!(pixels[..QUIET_ZONE * scale]
+            .iter()
+            .flatten()
+            .all(|&dark| !dark));
+    }
+
+    #[test]
+    fn svg_is_at_least_200_pixels_across() {
+        let svg = render_svg(&fake_address().to_nockchain_string()).unwrap();
+        assert!(svg.starts_with("<?xml"));
+        let width: u32 = svg
+            .split("width=\"")
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .and_then(|w| w.parse().ok())
+            .unwrap();
+        assert!(width >= 200);
+    }
+
+    #[test]
+    fn backup_sheet_shows_the_address_and_escapes_the_label() {
+        let address = fake_address();
+        let sheet =
+            backup_sheet_html("<b>Savings</b> & co", &address, Some(fixture_time(0))).unwrap();
+
+        assert!(sheet.contains(&address.to_nockchain_string()));
+        assert!(sheet.contains("&lt;b&gt;Savings&lt;/b&gt; &amp; co"));
+        assert!(!sheet.contains("<b>Savings"));
+        assert!(sheet.contains("Created: 2024-01-01"));
+        assert!(sheet.contains("<svg"));
+
+        let undated = backup_sheet_html("Savings", &address, None).unwrap();
+        assert!(undated.contains("Created: unknown"));
+    }
+
+    #[test]
+    fn backup_sheet_for_an_imported_key_holds_no_secret() {
+        let secret = [0x5a; 32];
+        let encoded = encode_secret_key(&secret);
+        let mut wallet = Wallet::new();
+        let address = wallet.import_key_flexible("Cold", &encoded).unwrap();
+
+        let sheet = wallet.address_backup_sheet("Cold").unwrap();
+        assert!(sheet.contains(&address.to_nockchain_string()));
+        assert!(!sheet.contains(&encoded));
+        assert!(!sheet.contains(&hex::encode(secret)));
+        assert!(wallet.key_created_at("Cold").is_some());
+
+        assert!(matches!(
+            wallet.address_backup_sheet("Missing"),
+            Err(WalletError::KeyNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn key_created_at_comes_from_successful_key_events() {
+        let mut wallet = Wallet::new();
+        wallet.generate_key("Main").unwrap();
+        let created = wallet.key_created_at("Main").unwrap();
+        assert!(created <= Utc::now());
+
+        // A failed second generation under the same name leaves the date alone
+        assert!(wallet.generate_key("Main").is_err());
+        assert_eq!(wallet.key_created_at("Main"), Some(created));
+        assert_eq!(wallet.key_created_at("Other"), None);
+    }
+}
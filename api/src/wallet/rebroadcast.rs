@@ -0,0 +1,201 @@
+//! Relaying one pending transaction again on request.
+//!
+//! `NodeHandle::rebroadcast` announces a mempool transaction to the
+//! connected peers right away instead of waiting for them to ask. Requests
+//! for the same transaction closer together than `MIN_REBROADCAST_INTERVAL`
+//! are refused, so a button can't flood peers. A transaction whose inputs
+//! have been spent meanwhile is dropped from the mempool and refused with
+//! `RejectCode::MissingInputs`; `Wallet::apply_rebroadcast` then marks it
+//! failed.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::wallet::{Wallet, WalletError, WalletResult};
+
+/// Shortest time between two requested rebroadcasts of one transaction
+pub const MIN_REBROADCAST_INTERVAL: Duration = Duration::from_secs(30);
+
+/// What a requested rebroadcast did
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RebroadcastResult {
+    pub tx_id: String,
+    /// Connected peers the transaction was announced to
+    pub announced_to: u32,
+    /// Peers that have accepted it so far, from its relay report
+    pub accepted: usize,
+}
+
+impl fmt::Display for RebroadcastResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Announced to {} peer(s); {} accepted so far",
+            self.announced_to, self.accepted
+        )
+    }
+}
+
+/// When each transaction was last rebroadcast on request
+#[derive(Debug)]
+pub(crate) struct RebroadcastLimiter {
+    interval: Duration,
+    last: HashMap<String, Instant>,
+}
+
+impl Default for RebroadcastLimiter {
+    fn default() -> Self {
+        Self {
+            interval: MIN_REBROADCAST_INTERVAL,
+            last: HashMap::new(),
+        }
+    }
+}
+
+impl RebroadcastLimiter {
+    /// Refuse `tx_id` if it was rebroadcast less than the interval before `now`
+    pub(crate) fn check(&mut self, tx_id: &str, now: Instant) -> WalletResult<()> {
+        let interval = self.interval;
+        self.last
+            .retain(|_, at| now.saturating_duration_since(*at) < interval);
+        match self.last.get(tx_id) {
+            Some(at) => {
+                let wait = interval.saturating_sub(now.saturating_duration_since(*at));
+                Err(WalletError::RebroadcastTooSoon {
+                    tx_id: tx_id.to_string(),
+                    retry_after_secs: wait.as_secs_f64().ceil() as u64,
+                })
+            }
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn record(&mut self, tx_id: &str, now: Instant) {
+        self.last.insert(tx_id.to_string(), now);
+    }
+}
+
+impl Wallet {
+    /// Act on the outcome of rebroadcasting `tx_id`: a rejection means it can
+    /// never be mined, so it is marked failed with the rejection as reason,
+    /// its unconfirmed outputs dropped and the notes it spent made spendable
+    /// again. Returns whether it was failed.
+    pub fn apply_rebroadcast(
+        &mut self,
+        tx_id: &str,
+        result: &WalletResult<RebroadcastResult>,
+    ) -> WalletResult<bool> {
+        let Err(rejected @ WalletError::TxRejected { .. }) = result else {
+            return Ok(false);
+        };
+        self.ensure_writable()?;
+        self.fail_sent_transaction(tx_id, &rejected.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_address, fake_note};
+    use crate::wallet::reject::{RejectCode, LOCAL_MEMPOOL};
+    use crate::wallet::send::{Broadcaster, SendRequest};
+    use crate::wallet::transaction::SignedTransaction;
+    use crate::wallet::TransactionStatus;
+
+    struct AcceptAll;
+
+    impl Broadcaster for AcceptAll {
+        async fn broadcast(&self, _tx: &SignedTransaction, _force: bool) -> WalletResult<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn a_repeat_within_the_interval_is_refused_until_it_passes() {
+        let mut limiter = RebroadcastLimiter::default();
+        let start = Instant::now();
+        assert!(limiter.check("ab", start).is_ok());
+        limiter.record("ab", start);
+
+        match limiter.check("ab", start + Duration::from_millis(10_500)) {
+            Err(WalletError::RebroadcastTooSoon {
+                tx_id,
+                retry_after_secs,
+            }) => {
+                assert_eq!(tx_id, "ab");
+                assert_eq!(retry_after_secs, 20);
+            }
+            other => panic!("expected RebroadcastTooSoon, got {:?}", other),
+        }
+        // Other transactions are limited on their own
+        assert!(limiter.check("cd", start).is_ok());
+        // A refused request doesn't restart the wait
+        assert!(limiter
+            .check("ab", start + MIN_REBROADCAST_INTERVAL)
+            .is_ok());
+        assert!(limiter.last.is_empty());
+    }
+
+    #[tokio::test]
+    async fn only_a_rejected_rebroadcast_fails_the_send() {
+        let mut wallet = Wallet::new();
+        let address = wallet.generate_key("main").unwrap();
+        wallet
+            .balances_mut()
+            .add_note(
+                fake_note()
+                    .address(address)
+                    .amount(1_000)
+                    .confirmed_at(1)
+                    .build(),
+            )
+            .unwrap();
+        wallet.balances_mut().set_tip_height(10);
+        let request = SendRequest {
+            key_name: "main".to_string(),
+            to: fake_address(),
+            amount: 400,
+            fee: 10,
+            min_confirmations: None,
+            valid_for_blocks: None,
+            confirmed_amount: None,
+            force_broadcast: false,
+        };
+        let tx = wallet.send(request, &AcceptAll, |_| {}).await.unwrap();
+
+        let announced = Ok(RebroadcastResult {
+            tx_id: tx.id.clone(),
+            announced_to: 3,
+            accepted: 1,
+        });
+        let too_soon = Err(WalletError::RebroadcastTooSoon {
+            tx_id: tx.id.clone(),
+            retry_after_secs: 12,
+        });
+        assert!(!wallet.apply_rebroadcast(&tx.id, &announced).unwrap());
+        assert!(!wallet.apply_rebroadcast(&tx.id, &too_soon).unwrap());
+        assert_eq!(wallet.spendable_summary().spendable, 0);
+
+        let rejected = Err(WalletError::TxRejected {
+            code: RejectCode::MissingInputs,
+            peer: LOCAL_MEMPOOL.to_string(),
+            detail: format!("Input x:0 of transaction {} is already spent", tx.id),
+        });
+        assert!(wallet.apply_rebroadcast(&tx.id, &rejected).unwrap());
+        assert_eq!(wallet.spendable_summary().spendable, 1_000);
+        let failed = wallet
+            .transactions()
+            .get_all_transactions()
+            .into_iter()
+            .find(|recorded| recorded.id == tx.id)
+            .unwrap();
+        assert!(matches!(
+            &failed.status,
+            TransactionStatus::Failed { reason } if reason.ends_with("is already spent")
+        ));
+        // Already failed, so a second rejection changes nothing
+        assert!(!wallet.apply_rebroadcast(&tx.id, &rejected).unwrap());
+    }
+}
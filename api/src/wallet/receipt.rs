@@ -0,0 +1,464 @@
+//! Signed payment receipts.
+//!
+//! A receipt states that the wallet sent `amount` to `recipient` in a
+//! transaction, signed by the key that funded it. Once the transaction is
+//! confirmed the receipt also carries a merkle proof tying the transaction
+//! hash to a block, so anyone holding the block header can check it without
+//! access to the wallet. The signature covers every other field; the proof
+//! shows the transaction is in the chain, not what it paid.
+
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+use crate::wallet::chain::Blockchain;
+use crate::wallet::{
+    merkle_leaf_hash, merkle_parent, Address, BlockHeader, TransactionStatus, Wallet, WalletError,
+    WalletResult,
+};
+
+/// Prefix of the string form meant for QR codes
+pub const RECEIPT_QR_PREFIX: &str = "nockreceipt:";
+
+/// Current receipt format
+const RECEIPT_VERSION: u32 = 1;
+
+/// Domain separator so a receipt signature can't be passed off as anything else
+const SIGNING_DOMAIN: &[u8] = b"nockchain-receipt";
+
+/// Path from a transaction hash to the merkle root of the block containing it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    pub block_height: u64,
+    /// Position of the transaction in the block
+    pub index: u32,
+    /// Hex sibling hashes, from the leaves up
+    pub siblings: Vec<String>,
+}
+
+impl MerkleProof {
+    /// Merkle root this proof leads to from `tx_hash`, or `None` if a sibling
+    /// isn't a 32-byte hex hash
+    pub fn root(&self, tx_hash: &[u8]) -> Option<[u8; 32]> {
+        let mut index = self.index;
+        let mut hash = merkle_leaf_hash(tx_hash);
+        for sibling in &self.siblings {
+            let sibling: [u8; 32] = hex::decode(sibling).ok()?.try_into().ok()?;
+            hash = if index.is_multiple_of(2) {
+                merkle_parent(&hash, &sibling)
+            } else {
+                merkle_parent(&sibling, &hash)
+            };
+            index /= 2;
+        }
+        Some(hash)
+    }
+}
+
+/// Proof of a payment, signed by the sending key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Receipt {
+    pub version: u32,
+    pub tx_id: String,
+    /// Amount paid to `recipient`, in base units
+    pub amount: u64,
+    pub recipient: String,
+    /// Address whose key signed the receipt; the first input of the transaction
+    pub sender: String,
+    /// When the transaction was broadcast, or created if it never was
+    pub timestamp: DateTime<Utc>,
+    /// Hex hash of the transaction, the leaf `proof` starts from
+    pub tx_hash: String,
+    /// Set once the transaction is confirmed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proof: Option<MerkleProof>,
+    /// Hex ed25519 signature over every other field
+    pub signature: String,
+}
+
+impl Receipt {
+    /// Bytes the signature covers: the compact JSON of every field but the signature
+    fn signing_bytes(&self) -> Vec<u8> {
+        let body = serde_json::json!({
+            "version": self.version,
+            "tx_id": self.tx_id,
+            "amount": self.amount,
+            "recipient": self.recipient,
+            "sender": self.sender,
+            "timestamp": self.timestamp,
+            "tx_hash": self.tx_hash,
+            "proof": self.proof,
+        });
+        let mut bytes = SIGNING_DOMAIN.to_vec();
+        bytes.extend(body.to_string().into_bytes());
+        bytes
+    }
+
+    /// Compact JSON form
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("receipts always serialize")
+    }
+
+    pub fn from_json(json: &str) -> WalletResult<Self> {
+        serde_json::from_str(json.trim())
+            .map_err(|e| WalletError::InvalidReceipt(format!("not a receipt: {}", e)))
+    }
+
+    /// Single-token form for QR codes: the prefix followed by base58 of the JSON
+    pub fn to_qr_string(&self) -> String {
+        format!(
+            "{}{}",
+            RECEIPT_QR_PREFIX,
+            bs58::encode(self.to_json()).into_string()
+        )
+    }
+
+    pub fn from_qr_string(s: &str) -> WalletResult<Self> {
+        let encoded = s
+            .trim()
+            .strip_prefix(RECEIPT_QR_PREFIX)
+            .ok_or_else(|| WalletError::InvalidReceipt("missing receipt prefix".to_string()))?;
+        let json = bs58::decode(encoded)
+            .into_vec()
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or_else(|| WalletError::InvalidReceipt("not valid base58 text".to_string()))?;
+        Self::from_json(&json)
+    }
+
+    /// Read either the JSON or the QR string form
+    pub fn parse(s: &str) -> WalletResult<Self> {
+        if s.trim().starts_with(RECEIPT_QR_PREFIX) {
+            Self::from_qr_string(s)
+        } else {
+            Self::from_json(s)
+        }
+    }
+}
+
+/// Public chain data a receipt is checked against
+#[derive(Debug, Clone, Default)]
+pub struct ChainTipInfo {
+    pub tip_height: Option<u64>,
+    /// Header of the block the receipt's proof points at, if known
+    pub header: Option<BlockHeader>,
+}
+
+impl ChainTipInfo {
+    /// Tip height and, if the receipt has a proof, the header it points at
+    pub fn for_receipt(chain: &Blockchain, receipt: &Receipt) -> Self {
+        Self {
+            tip_height: chain.height(),
+            header: receipt
+                .proof
+                .as_ref()
+                .and_then(|proof| chain.block_at(proof.block_height))
+                .map(|block| block.header.clone()),
+        }
+    }
+}
+
+/// What a receipt with a valid signature shows about its transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReceiptValidity {
+    /// The proof leads to the merkle root of the given header
+    Confirmed { confirmations: u64 },
+    /// Made before the transaction was confirmed, so there is no proof
+    Unconfirmed,
+    /// Has a proof, but no header was given to check it against
+    ProofUnchecked,
+}
+
+/// Check a receipt's signature and, given the header its proof points at, the
+/// proof. Needs no wallet: only the receipt and public chain data.
+pub fn verify_receipt(receipt: &Receipt, chain: &ChainTipInfo) -> WalletResult<ReceiptValidity> {
+    let invalid = |reason: &str| WalletError::InvalidReceipt(reason.to_string());
+
+    if receipt.version != RECEIPT_VERSION {
+        return Err(invalid("unsupported version"));
+    }
+    let sender =
+        Address::from_string(&receipt.sender).map_err(|_| invalid("bad sender address"))?;
+    let key = VerifyingKey::from_bytes(&sender.public_key)
+        .map_err(|_| invalid("sender is not a valid public key"))?;
+    let signature = hex::decode(&receipt.signature)
+        .ok()
+        .and_then(|bytes| Signature::from_slice(&bytes).ok())
+        .ok_or_else(|| invalid("malformed signature"))?;
+    key.verify(&receipt.signing_bytes(), &signature)
+        .map_err(|_| invalid("signature does not match its contents"))?;
+
+    let Some(proof) = &receipt.proof else {
+        return Ok(ReceiptValidity::Unconfirmed);
+    };
+    let Some(header) = &chain.header else {
+        return Ok(ReceiptValidity::ProofUnchecked);
+    };
+    if header.height != proof.block_height {
+        return Err(invalid("header is for a different block than the proof"));
+    }
+    let tx_hash =
+        hex::decode(&receipt.tx_hash).map_err(|_| invalid("malformed transaction hash"))?;
+    if proof.root(&tx_hash) != Some(header.merkle_root) {
+        return Err(invalid("transaction is not in the block"));
+    }
+
+    let confirmations = chain
+        .tip_height
+        .filter(|tip| *tip >= proof.block_height)
+        .map_or(1, |tip| tip - proof.block_height + 1);
+    Ok(ReceiptValidity::Confirmed { confirmations })
+}
+
+impl Wallet {
+    /// Signed receipt for a transaction this wallet sent. With `chain`, a
+    /// confirmed transaction's receipt includes its merkle proof.
+    pub fn generate_receipt(
+        &self,
+        tx_id: &str,
+        chain: Option<&Blockchain>,
+    ) -> WalletResult<Receipt> {
+        let not_found = || WalletError::Transaction(format!("Transaction {} not found", tx_id));
+        let transactions = self.transactions();
+        let transaction = transactions
+            .get_pending_transactions()
+            .iter()
+            .chain(transactions.get_confirmed_transactions())
+            .find(|tx| tx.id == tx_id)
+            .ok_or_else(not_found)?;
+        if !transaction.is_outgoing {
+            return Err(WalletError::Transaction(format!(
+                "Transaction {} was not sent by this wallet",
+                tx_id
+            )));
+        }
+        let recipient = transaction.to_address.as_ref().ok_or_else(|| {
+            WalletError::Transaction(format!("Transaction {} has no recipient", tx_id))
+        })?;
+
+        let sender = transaction
+            .inputs
+            .first()
+            .and_then(|input| self.balances.note_at(input.outpoint()))
+            .map(|note| note.address.clone())
+            .ok_or_else(|| {
+                WalletError::Transaction(format!("Inputs of transaction {} are unknown", tx_id))
+            })?;
+        let secret = self
+            .keys
+            .secret_for_address(&sender)
+            .ok_or_else(|| WalletError::KeyNotFound(sender.to_string()))?;
+
+        let block = match transaction.status {
+            TransactionStatus::Confirmed { block_height } => {
+                chain.and_then(|chain| chain.block_at(block_height))
+            }
+            _ => None,
+        };
+        let proof = block.and_then(|block| block.merkle_proof(tx_id));
+        let tx_hash = block
+            .and_then(|block| block.transactions.iter().find(|tx| tx.id == tx_id))
+            .map(|tx| tx.hash.clone())
+            .or_else(|| transactions.signed(tx_id).map(|tx| tx.hash.clone()))
+            .ok_or_else(not_found)?;
+
+        let mut receipt = Receipt {
+            version: RECEIPT_VERSION,
+            tx_id: tx_id.to_string(),
+            amount: transaction.amount,
+            recipient: recipient.to_nockchain_string(),
+            sender: sender.to_nockchain_string(),
+            timestamp: transaction.broadcast_at.unwrap_or(transaction.created_at),
+            tx_hash: hex::encode(tx_hash),
+            proof,
+            signature: String::new(),
+        };
+        let signature = SigningKey::from_bytes(&secret).sign(&receipt.signing_bytes());
+        receipt.signature = hex::encode(signature.to_bytes());
+        Ok(receipt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_address, fake_block, fake_note, fakenet_chain_config};
+    use crate::wallet::keys::NockchainTransaction;
+    use crate::wallet::send::{Broadcaster, SendRequest};
+    use crate::wallet::transaction::SignedTransaction;
+
+    struct AcceptAll;
+
+    impl Broadcaster for AcceptAll {
+        async fn broadcast(&self, _tx: &SignedTransaction, _force: bool) -> WalletResult<()> {
+            Ok(())
+        }
+    }
+
+    /// Wallet that has sent 400 from key "main", and the transaction it sent
+    async fn wallet_with_send() -> (Wallet, SignedTransaction) {
+        let mut wallet = Wallet::new();
+        let address = wallet.generate_key("main").unwrap();
+        wallet
+            .balances_mut()
+            .add_note(
+                fake_note()
+                    .address(address)
+                    .amount(1_000)
+                    .confirmed_at(1)
+                    .build(),
+            )
+            .unwrap();
+        wallet.balances_mut().set_tip_height(10);
+        let request = SendRequest {
+            key_name: "main".to_string(),
+            to: fake_address(),
+            amount: 400,
+            fee: 10,
+            min_confirmations: None,
+            valid_for_blocks: None,
+            confirmed_amount: None,
+            force_broadcast: false,
+        };
+        let tx = wallet.send(request, &AcceptAll, |_| {}).await.unwrap();
+        (wallet, tx)
+    }
+
+    /// Fakenet chain whose genesis block holds `tx`, which `wallet` now sees
+    /// as confirmed
+    fn confirm_in_genesis(wallet: &mut Wallet, tx: &SignedTransaction) -> Blockchain {
+        let mut chain = Blockchain::new(fakenet_chain_config());
+        let included = NockchainTransaction {
+            id: tx.id.clone(),
+            inputs: tx.inputs.clone(),
+            outputs: tx.outputs.clone(),
+            hash: tx.hash.clone(),
+            expiry_height: tx.expiry_height,
+        };
+        let others = fake_block().with_txs(2).build().transactions;
+        let mut block = chain.new_block(
+            [vec![included], others].concat(),
+            chain.config().initial_difficulty,
+        );
+        block.mine().unwrap();
+        chain.add_block(block).unwrap();
+        wallet
+            .transactions_mut()
+            .confirm_transaction(&tx.id, 0)
+            .unwrap();
+        chain
+    }
+
+    #[tokio::test]
+    async fn a_receipt_round_trips_through_json_and_qr() {
+        let (wallet, tx) = wallet_with_send().await;
+        let receipt = wallet.generate_receipt(&tx.id, None).unwrap();
+        assert_eq!(receipt.amount, 400);
+        assert_eq!(receipt.tx_hash, hex::encode(&tx.hash));
+
+        let json = receipt.to_json();
+        assert!(!json.contains('\n'));
+        assert_eq!(Receipt::parse(&json).unwrap(), receipt);
+        let qr = receipt.to_qr_string();
+        assert!(qr.starts_with(RECEIPT_QR_PREFIX));
+        assert_eq!(Receipt::parse(&qr).unwrap(), receipt);
+
+        assert_eq!(
+            verify_receipt(&receipt, &ChainTipInfo::default()).unwrap(),
+            ReceiptValidity::Unconfirmed
+        );
+    }
+
+    #[tokio::test]
+    async fn a_receipt_with_a_modified_amount_is_rejected() {
+        let (wallet, tx) = wallet_with_send().await;
+        let mut receipt = wallet.generate_receipt(&tx.id, None).unwrap();
+        receipt.amount = 4_000;
+        let e = verify_receipt(&receipt, &ChainTipInfo::default()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            WalletError::InvalidReceipt("signature does not match its contents".to_string())
+                .to_string()
+        );
+
+        // The same edit made to the QR form is caught too
+        let mut original = wallet.generate_receipt(&tx.id, None).unwrap();
+        let qr = original.to_qr_string();
+        original.recipient = fake_address().to_nockchain_string();
+        assert!(verify_receipt(&original, &ChainTipInfo::default()).is_err());
+        assert!(verify_receipt(&Receipt::parse(&qr).unwrap(), &ChainTipInfo::default()).is_ok());
+    }
+
+    #[tokio::test]
+    async fn a_confirmed_receipt_proves_its_transaction_is_in_the_block() {
+        let (mut wallet, tx) = wallet_with_send().await;
+        let chain = confirm_in_genesis(&mut wallet, &tx);
+        let receipt = wallet.generate_receipt(&tx.id, Some(&chain)).unwrap();
+        let proof = receipt.proof.as_ref().unwrap();
+        assert_eq!(proof.block_height, 0);
+
+        let tip = ChainTipInfo::for_receipt(&chain, &receipt);
+        assert_eq!(
+            verify_receipt(&receipt, &tip).unwrap(),
+            ReceiptValidity::Confirmed { confirmations: 1 }
+        );
+        assert_eq!(
+            verify_receipt(
+                &receipt,
+                &ChainTipInfo {
+                    tip_height: Some(5),
+                    ..tip.clone()
+                }
+            )
+            .unwrap(),
+            ReceiptValidity::Confirmed { confirmations: 6 }
+        );
+        assert_eq!(
+            verify_receipt(&receipt, &ChainTipInfo::default()).unwrap(),
+            ReceiptValidity::ProofUnchecked
+        );
+
+        // A header from another block doesn't match the proof's root
+        let mut other = tip.header.clone().unwrap();
+        other.merkle_root = [7; 32];
+        let e = verify_receipt(
+            &receipt,
+            &ChainTipInfo {
+                tip_height: Some(0),
+                header: Some(other),
+            },
+        )
+        .unwrap_err();
+        assert!(e.to_string().contains("not in the block"));
+    }
+
+    #[test]
+    fn proofs_reach_the_root_for_every_position_and_block_size() {
+        for size in 1..=9 {
+            let block = fake_block().with_txs(size).build();
+            for tx in &block.transactions {
+                let proof = block.merkle_proof(&tx.id).unwrap();
+                assert_eq!(
+                    proof.root(&tx.hash),
+                    Some(block.header.merkle_root),
+                    "tx {} of {}",
+                    proof.index,
+                    size
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn receipts_are_only_made_for_sends_of_this_wallet() {
+        let (wallet, _) = wallet_with_send().await;
+        assert!(wallet.generate_receipt("unknown", None).is_err());
+    }
+
+    #[test]
+    fn malformed_receipt_text_is_rejected() {
+        for text in ["", "{}", "nockreceipt:0OIl", "nockreceipt:"] {
+            assert!(Receipt::parse(text).is_err(), "{:?}", text);
+        }
+    }
+}
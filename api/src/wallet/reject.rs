@@ -0,0 +1,359 @@
+//! Structured rejection of relayed transactions.
+//!
+//! A peer that won't take a transaction answers with a `TxReject`: the
+//! transaction id, a `RejectCode` and free text. The node keeps every answer
+//! for a transaction in a `RelayReport`. Once peers have answered and none
+//! accepted, the report turns into a `WalletError::TxRejected` carrying the
+//! most common code, and the wallet marks the transaction failed while
+//! keeping each rejection for its detail view.
+//!
+//! The local mempool refuses transactions the same way, under the peer name
+//! `LOCAL_MEMPOOL`, so a broadcast it rejects fails straight away.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::wallet::{Wallet, WalletError, WalletResult};
+
+/// Peer name rejections from this node's own mempool are reported under
+pub const LOCAL_MEMPOOL: &str = "local mempool";
+
+/// Longest free text kept from a peer's rejection, in characters
+pub const MAX_REJECT_DETAIL: usize = 256;
+
+/// Why a peer refused a transaction. The wire value is the `u8` discriminant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum RejectCode {
+    InsufficientFee = 1,
+    /// Spends outputs the peer doesn't know or that are already spent
+    MissingInputs = 2,
+    /// Spends the same outputs as a transaction the peer already holds
+    Conflict = 3,
+    TooLarge = 4,
+    InvalidSignature = 5,
+    /// Its expiry height has been reached, so no future block may include it
+    Expired = 6,
+}
+
+impl RejectCode {
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            1 => Some(RejectCode::InsufficientFee),
+            2 => Some(RejectCode::MissingInputs),
+            3 => Some(RejectCode::Conflict),
+            4 => Some(RejectCode::TooLarge),
+            5 => Some(RejectCode::InvalidSignature),
+            6 => Some(RejectCode::Expired),
+            _ => None,
+        }
+    }
+
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RejectCode::InsufficientFee => "insufficient fee",
+            RejectCode::MissingInputs => "missing inputs",
+            RejectCode::Conflict => "conflict",
+            RejectCode::TooLarge => "too large",
+            RejectCode::InvalidSignature => "invalid signature",
+            RejectCode::Expired => "expired",
+        }
+    }
+}
+
+impl fmt::Display for RejectCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// Peer protocol message refusing a relayed transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxReject {
+    pub tx_id: String,
+    pub code: RejectCode,
+    pub detail: String,
+}
+
+/// One peer's refusal of a transaction, as recorded by the node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rejection {
+    pub peer: String,
+    pub code: RejectCode,
+    pub detail: String,
+    pub at: DateTime<Utc>,
+}
+
+impl Rejection {
+    /// Record `reject` from `peer`, cutting its free text to `MAX_REJECT_DETAIL`
+    pub fn from_peer(peer: &str, reject: TxReject) -> Self {
+        Self {
+            peer: peer.to_string(),
+            code: reject.code,
+            detail: reject.detail.chars().take(MAX_REJECT_DETAIL).collect(),
+            at: Utc::now(),
+        }
+    }
+}
+
+/// Answers peers gave to one relayed transaction
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelayReport {
+    /// Peers that accepted it
+    pub accepted: Vec<String>,
+    /// Latest rejection from each peer that refused it, in order received
+    pub rejections: Vec<Rejection>,
+}
+
+impl RelayReport {
+    pub fn record_accept(&mut self, peer: &str) {
+        self.rejections.retain(|rejection| rejection.peer != peer);
+        if !self.accepted.iter().any(|accepted| accepted == peer) {
+            self.accepted.push(peer.to_string());
+        }
+    }
+
+    pub fn record_reject(&mut self, rejection: Rejection) {
+        self.accepted.retain(|accepted| *accepted != rejection.peer);
+        self.rejections
+            .retain(|existing| existing.peer != rejection.peer);
+        self.rejections.push(rejection);
+    }
+
+    /// `TxRejected` for the most common rejection once peers have answered
+    /// and none accepted
+    pub fn outcome(&self) -> WalletResult<()> {
+        if !self.accepted.is_empty() {
+            return Ok(());
+        }
+        aggregate_rejections(&self.rejections).map_or(Ok(()), Err)
+    }
+}
+
+/// Sum up differing rejections as one `TxRejected`: the most common code,
+/// ties going to the one seen first, with the peer and text of its first
+/// rejection. `None` when there are no rejections.
+pub fn aggregate_rejections(rejections: &[Rejection]) -> Option<WalletError> {
+    let count = |code: RejectCode| rejections.iter().filter(|r| r.code == code).count();
+    let mut first: Option<&Rejection> = None;
+    for rejection in rejections {
+        if first.is_none_or(|best| count(rejection.code) > count(best.code)) {
+            first = Some(rejection);
+        }
+    }
+    let first = first?;
+
+    let agreeing = count(first.code);
+    let detail = if agreeing == rejections.len() {
+        first.detail.clone()
+    } else {
+        format!(
+            "{} ({} of {} rejecting peers)",
+            first.detail,
+            agreeing,
+            rejections.len()
+        )
+    };
+    Some(WalletError::TxRejected {
+        code: first.code,
+        peer: first.peer.clone(),
+        detail,
+    })
+}
+
+impl Wallet {
+    /// Keep what peers answered to relaying `tx_id`. A pending transaction no
+    /// peer accepted is marked failed with the aggregated rejection, its
+    /// unconfirmed outputs dropped and the notes it spent made spendable
+    /// again. Returns whether it was failed.
+    pub fn apply_relay_report(&mut self, tx_id: &str, report: &RelayReport) -> WalletResult<bool> {
+        self.ensure_writable()?;
+        self.transactions
+            .set_rejections(tx_id, report.rejections.clone());
+
+        let Err(rejected) = report.outcome() else {
+            return Ok(false);
+        };
+        self.fail_sent_transaction(tx_id, &rejected.to_string())
+    }
+
+    /// Mark a pending transaction the wallet sent failed with `reason`,
+    /// dropping its unconfirmed outputs and making the notes it spent
+    /// spendable again. Returns false if there was no such transaction.
+    pub(crate) fn fail_sent_transaction(
+        &mut self,
+        tx_id: &str,
+        reason: &str,
+    ) -> WalletResult<bool> {
+        let Some(signed) = self.transactions.pending_signed(tx_id).cloned() else {
+            return Ok(false);
+        };
+        self.transactions.fail_transaction(tx_id, reason)?;
+        self.balances.remove_unconfirmed_notes(tx_id);
+        for outpoint in signed.input_outpoints() {
+            if let Some(id) = self.balances.note_at(outpoint).map(|note| note.id) {
+                self.balances.unspend_note(id)?;
+            }
+        }
+        self.balances.reverse_fee(tx_id);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_address, fake_note};
+    use crate::wallet::encoding::{Decode, Encode};
+    use crate::wallet::send::{Broadcaster, SendRequest};
+    use crate::wallet::transaction::SignedTransaction;
+    use crate::wallet::TransactionStatus;
+
+    struct AcceptAll;
+
+    impl Broadcaster for AcceptAll {
+        async fn broadcast(&self, _tx: &SignedTransaction, _force: bool) -> WalletResult<()> {
+            Ok(())
+        }
+    }
+
+    fn reject(peer: &str, code: RejectCode, detail: &str) -> Rejection {
+        Rejection::from_peer(
+            peer,
+            TxReject {
+                tx_id: "ab".to_string(),
+                code,
+                detail: detail.to_string(),
+            },
+        )
+    }
+
+    #[test]
+    fn mixed_rejections_report_the_most_common_code() {
+        let mut report = RelayReport::default();
+        assert!(report.outcome().is_ok());
+        report.record_reject(reject("a", RejectCode::InsufficientFee, "fee too low"));
+        report.record_reject(reject("b", RejectCode::Conflict, "double spend"));
+        report.record_reject(reject("c", RejectCode::Conflict, "spent elsewhere"));
+
+        match report.outcome() {
+            Err(WalletError::TxRejected { code, peer, detail }) => {
+                assert_eq!(code, RejectCode::Conflict);
+                assert_eq!(peer, "b");
+                assert_eq!(detail, "double spend (2 of 3 rejecting peers)");
+            }
+            other => panic!("expected TxRejected, got {:?}", other),
+        }
+
+        // One acceptance is enough for the transaction to propagate
+        report.record_accept("d");
+        assert!(report.outcome().is_ok());
+    }
+
+    #[test]
+    fn a_tie_goes_to_the_first_code_seen() {
+        let rejections = [
+            reject("a", RejectCode::TooLarge, "too big"),
+            reject("b", RejectCode::InvalidSignature, "bad sig"),
+        ];
+        assert!(matches!(
+            aggregate_rejections(&rejections),
+            Some(WalletError::TxRejected {
+                code: RejectCode::TooLarge,
+                ..
+            })
+        ));
+        assert!(aggregate_rejections(&[]).is_none());
+    }
+
+    #[test]
+    fn a_peer_changing_its_answer_keeps_only_the_latest() {
+        let mut report = RelayReport::default();
+        report.record_accept("a");
+        report.record_reject(reject("a", RejectCode::InsufficientFee, "fee too low"));
+        assert!(report.accepted.is_empty());
+        assert!(report.outcome().is_err());
+
+        report.record_accept("a");
+        report.record_accept("a");
+        assert_eq!(report.accepted, vec!["a".to_string()]);
+        assert!(report.rejections.is_empty());
+    }
+
+    #[test]
+    fn rejections_round_trip_and_long_detail_is_cut() {
+        let message = TxReject {
+            tx_id: "ab".to_string(),
+            code: RejectCode::Expired,
+            detail: "x".repeat(MAX_REJECT_DETAIL * 2),
+        };
+        let decoded = TxReject::from_bytes(&message.to_bytes()).unwrap();
+        assert_eq!(decoded, message);
+        let kept = Rejection::from_peer("a", decoded);
+        assert_eq!(kept.detail.chars().count(), MAX_REJECT_DETAIL);
+
+        let mut unknown = message.to_bytes();
+        unknown[1 + 4 + 2] = 99;
+        assert!(TxReject::from_bytes(&unknown)
+            .unwrap_err()
+            .to_string()
+            .contains("Unknown reject code 99"));
+    }
+
+    #[tokio::test]
+    async fn a_send_no_peer_accepted_fails_and_frees_its_funds() {
+        let mut wallet = Wallet::new();
+        let address = wallet.generate_key("main").unwrap();
+        wallet
+            .balances_mut()
+            .add_note(
+                fake_note()
+                    .address(address)
+                    .amount(1_000)
+                    .confirmed_at(1)
+                    .build(),
+            )
+            .unwrap();
+        wallet.balances_mut().set_tip_height(10);
+        let request = SendRequest {
+            key_name: "main".to_string(),
+            to: fake_address(),
+            amount: 400,
+            fee: 10,
+            min_confirmations: None,
+            valid_for_blocks: None,
+            confirmed_amount: None,
+            force_broadcast: false,
+        };
+        let tx = wallet.send(request, &AcceptAll, |_| {}).await.unwrap();
+        assert_eq!(wallet.spendable_summary().spendable, 0);
+
+        let mut report = RelayReport::default();
+        report.record_reject(reject("a", RejectCode::InsufficientFee, "fee too low"));
+        report.record_reject(reject("b", RejectCode::InsufficientFee, "fee too low"));
+        assert!(wallet.apply_relay_report(&tx.id, &report).unwrap());
+
+        let summary = wallet.spendable_summary();
+        assert_eq!(summary.spendable, 1_000);
+        assert_eq!(summary.awaiting_confirmations, 0);
+        let failed = wallet
+            .transactions()
+            .get_all_transactions()
+            .into_iter()
+            .find(|recorded| recorded.id == tx.id)
+            .unwrap();
+        assert!(matches!(
+            &failed.status,
+            TransactionStatus::Failed { reason } if reason.contains("insufficient fee")
+        ));
+        assert_eq!(failed.rejections, report.rejections);
+
+        // Already failed, so a later report changes nothing
+        assert!(!wallet.apply_relay_report(&tx.id, &report).unwrap());
+    }
+}
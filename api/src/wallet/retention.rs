@@ -0,0 +1,465 @@
+//! Retention of the records a long-running install accumulates.
+//!
+//! Each category of record has its own `RetentionRule`: a maximum age and a
+//! maximum size, either of which may be off. `Wallet::run_maintenance` prunes
+//! the oldest records of every category until both hold, logging what it
+//! removed. Keys, notes and pending transactions are never pruned, whatever
+//! their age; a category only ever loses settled history.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+use std::time::SystemTime;
+use tracing::info;
+
+use crate::wallet::audit::{AuditEvent, AuditOutcome};
+use crate::wallet::storage::StorageManager;
+use crate::wallet::{Wallet, WalletError, WalletResult};
+
+/// Retention policy, saved through `StorageManager` in the wallet data dir
+pub const RETENTION_FILE: &str = "retention.json";
+
+/// How often maintenance runs while the app is open
+pub const MAINTENANCE_INTERVAL_HOURS: i64 = 24;
+
+/// Blocks in a day at the 10 minute target, for ageing the fee history
+const BLOCKS_PER_DAY: u64 = 144;
+
+const MIB: u64 = 1024 * 1024;
+
+/// Limits on one category of records; `None` leaves that limit off
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionRule {
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+}
+
+impl RetentionRule {
+    pub fn new(max_age_days: Option<u32>, max_bytes: Option<u64>) -> Self {
+        Self {
+            max_age_days,
+            max_bytes,
+        }
+    }
+
+    /// Records older than this are pruned
+    pub fn cutoff(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.max_age_days
+            .map(|days| now - Duration::days(i64::from(days)))
+    }
+}
+
+/// Retention rule for each category of record
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// Node log files
+    #[serde(default = "default_logs_rule")]
+    pub logs: RetentionRule,
+    /// Fees of past blocks kept for fee suggestions
+    #[serde(default = "default_fee_history_rule")]
+    pub fee_history: RetentionRule,
+    /// Audit log entries
+    #[serde(default = "default_audit_rule")]
+    pub audit: RetentionRule,
+    /// Confirmed and failed transactions
+    #[serde(default)]
+    pub transactions: RetentionRule,
+}
+
+fn default_logs_rule() -> RetentionRule {
+    RetentionRule::new(Some(30), Some(100 * MIB))
+}
+
+fn default_fee_history_rule() -> RetentionRule {
+    RetentionRule::new(Some(30), None)
+}
+
+fn default_audit_rule() -> RetentionRule {
+    RetentionRule::new(Some(365), Some(10 * MIB))
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            logs: default_logs_rule(),
+            fee_history: default_fee_history_rule(),
+            audit: default_audit_rule(),
+            transactions: RetentionRule::default(),
+        }
+    }
+}
+
+impl RetentionPolicy {
+    /// Load the saved policy, or the default if none was saved
+    pub async fn load(storage: &StorageManager) -> WalletResult<Self> {
+        if !storage.exists(RETENTION_FILE) {
+            return Ok(Self::default());
+        }
+        storage.load(RETENTION_FILE).await
+    }
+
+    pub async fn save(&self, storage: &StorageManager) -> WalletResult<()> {
+        storage.save(RETENTION_FILE, self).await
+    }
+}
+
+/// A kind of record pruned by maintenance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RetentionCategory {
+    Logs,
+    FeeHistory,
+    Audit,
+    Transactions,
+}
+
+impl fmt::Display for RetentionCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RetentionCategory::Logs => "log files",
+            RetentionCategory::FeeHistory => "fee history blocks",
+            RetentionCategory::Audit => "audit entries",
+            RetentionCategory::Transactions => "settled transactions",
+        })
+    }
+}
+
+/// What maintenance removed from one category
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategoryPruned {
+    pub category: RetentionCategory,
+    pub removed: usize,
+    /// Size of what was removed: file sizes for logs, serialized size otherwise
+    pub bytes: u64,
+}
+
+/// Result of one maintenance run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaintenanceReport {
+    pub ran_at: DateTime<Utc>,
+    /// Every category, pruned or not
+    pub categories: Vec<CategoryPruned>,
+}
+
+impl MaintenanceReport {
+    pub fn bytes_reclaimed(&self) -> u64 {
+        self.categories.iter().map(|category| category.bytes).sum()
+    }
+
+    pub fn removed(&self) -> usize {
+        self.categories
+            .iter()
+            .map(|category| category.removed)
+            .sum()
+    }
+}
+
+/// Delete `*.log` files in `dir` modified before `before`, then the oldest
+/// until the rest fit in `max_bytes`. The newest file, the one being
+/// written, is always kept.
+pub fn prune_log_files(
+    dir: &Path,
+    before: Option<DateTime<Utc>>,
+    max_bytes: Option<u64>,
+) -> WalletResult<(usize, u64)> {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return Ok((0, 0));
+    };
+    let mut files: Vec<(SystemTime, u64, std::path::PathBuf)> = read_dir
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "log"))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some((metadata.modified().ok()?, metadata.len(), entry.path()))
+        })
+        .collect();
+    files.sort();
+
+    let before = before.map(SystemTime::from);
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    let mut removed = (0, 0);
+    for (modified, len, path) in files.iter().take(files.len().saturating_sub(1)) {
+        let old = before.is_some_and(|before| *modified < before);
+        let over = max_bytes.is_some_and(|max| total > max);
+        if !old && !over {
+            break;
+        }
+        std::fs::remove_file(path).map_err(|e| {
+            WalletError::Storage(format!("Failed to remove {}: {}", path.display(), e))
+        })?;
+        total -= len;
+        removed.0 += 1;
+        removed.1 += len;
+    }
+    Ok(removed)
+}
+
+impl Wallet {
+    pub fn retention_policy(&self) -> &RetentionPolicy {
+        &self.retention
+    }
+
+    /// Replace the retention policy, recording the change in the audit log
+    pub fn set_retention_policy(&mut self, policy: RetentionPolicy) -> WalletResult<()> {
+        let result = self.ensure_writable();
+        if result.is_ok() {
+            self.retention = policy;
+        }
+        self.record_audit(
+            AuditEvent::SettingsChanged {
+                setting: "retention".to_string(),
+            },
+            AuditOutcome::of(&result),
+        );
+        result
+    }
+
+    /// When maintenance last ran in this session
+    pub fn last_maintenance(&self) -> Option<&MaintenanceReport> {
+        self.last_maintenance.as_ref()
+    }
+
+    /// Whether maintenance hasn't run in this session, or not for
+    /// `MAINTENANCE_INTERVAL_HOURS`
+    pub fn maintenance_due(&self) -> bool {
+        self.last_maintenance.as_ref().is_none_or(|report| {
+            Utc::now() - report.ran_at >= Duration::hours(MAINTENANCE_INTERVAL_HOURS)
+        })
+    }
+
+    /// Prune every category to the retention policy: node log files in
+    /// `log_dir` if given, the fee history, the audit log and settled
+    /// transactions. Pruned stores are saved straight away so the space is
+    /// reclaimed on disk.
+    pub async fn run_maintenance(
+        &mut self,
+        log_dir: Option<&Path>,
+    ) -> WalletResult<MaintenanceReport> {
+        self.ensure_writable()?;
+        let now = Utc::now();
+        let policy = self.retention.clone();
+        let prune = |category, (removed, bytes)| CategoryPruned {
+            category,
+            removed,
+            bytes,
+        };
+
+        let logs = match log_dir {
+            Some(dir) => prune_log_files(dir, policy.logs.cutoff(now), policy.logs.max_bytes)?,
+            None => (0, 0),
+        };
+        let min_fee_height = policy.fee_history.max_age_days.and_then(|days| {
+            let latest = self.fees.latest_height()?;
+            Some(latest.saturating_sub(u64::from(days) * BLOCKS_PER_DAY))
+        });
+        let fees = self
+            .fees
+            .prune(min_fee_height, policy.fee_history.max_bytes);
+        let audit = self
+            .audit
+            .prune(policy.audit.cutoff(now), policy.audit.max_bytes);
+        let transactions = self.transactions.prune_history(
+            policy.transactions.cutoff(now),
+            policy.transactions.max_bytes,
+        );
+
+        let report = MaintenanceReport {
+            ran_at: now,
+            categories: vec![
+                prune(RetentionCategory::Logs, logs),
+                prune(RetentionCategory::FeeHistory, fees),
+                prune(RetentionCategory::Audit, audit),
+                prune(RetentionCategory::Transactions, transactions),
+            ],
+        };
+        for pruned in report.categories.iter().filter(|pruned| pruned.removed > 0) {
+            info!(
+                "Maintenance removed {} {} ({} bytes)",
+                pruned.removed, pruned.category, pruned.bytes
+            );
+        }
+        if fees.0 > 0 {
+            self.save_fee_history().await?;
+        }
+        if audit.0 > 0 {
+            self.save_audit_log().await?;
+        }
+        self.last_maintenance = Some(report.clone());
+        Ok(report)
+    }
+
+    /// Load the retention policy saved in the wallet data dir
+    pub async fn load_retention_policy(&mut self) -> WalletResult<()> {
+        let Some(data_dir) = self.data_dir.clone() else {
+            return Ok(());
+        };
+        let storage = StorageManager::new(data_dir)?;
+        self.retention = RetentionPolicy::load(&storage).await?;
+        Ok(())
+    }
+
+    /// Save the retention policy. Does nothing in read-only mode.
+    pub async fn save_retention_policy(&self) -> WalletResult<()> {
+        let Some(data_dir) = self.data_dir.clone().filter(|_| !self.read_only) else {
+            return Ok(());
+        };
+        let storage = StorageManager::new(data_dir)?;
+        self.retention.save(&storage).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_note, fake_signed_transaction, TempDir};
+    use crate::wallet::fees::BlockFees;
+    use std::fs::File;
+    use std::time::Duration as StdDuration;
+
+    /// Wallet with a key, a note, 300 blocks of fee history, a few audit
+    /// entries, and one confirmed and one pending transaction
+    fn busy_wallet() -> Wallet {
+        let mut wallet = Wallet::new();
+        let address = wallet.generate_key("main").unwrap();
+        wallet
+            .balances_mut()
+            .add_note(fake_note().address(address).confirmed_at(1).build())
+            .unwrap();
+        for height in 0..300 {
+            wallet.fees.record(BlockFees {
+                height,
+                tx_count: 0,
+                rates: None,
+            });
+        }
+        for _ in 0..3 {
+            wallet.record_audit(AuditEvent::WalletUnlocked, AuditOutcome::Success);
+        }
+        let (settled, pending) = (
+            fake_signed_transaction().build(),
+            fake_signed_transaction().build(),
+        );
+        let transactions = wallet.transactions_mut();
+        transactions.add_pending_transaction(settled.clone(), true);
+        transactions.add_pending_transaction(pending, true);
+        transactions.confirm_transaction(&settled.id, 1).unwrap();
+        wallet
+    }
+
+    /// Policy with every limit off but the one `set` turns on
+    fn only(set: impl FnOnce(&mut RetentionPolicy)) -> RetentionPolicy {
+        let mut policy = RetentionPolicy {
+            logs: RetentionRule::default(),
+            fee_history: RetentionRule::default(),
+            audit: RetentionRule::default(),
+            transactions: RetentionRule::default(),
+        };
+        set(&mut policy);
+        policy
+    }
+
+    fn removed(report: &MaintenanceReport) -> Vec<(RetentionCategory, usize)> {
+        report
+            .categories
+            .iter()
+            .map(|pruned| (pruned.category, pruned.removed))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn each_category_is_pruned_by_its_own_rule() {
+        use RetentionCategory::*;
+        let cases = [
+            (
+                only(|policy| policy.fee_history = RetentionRule::new(Some(1), None)),
+                // Heights below 155, a day of blocks under the latest at 299
+                [(Logs, 0), (FeeHistory, 155), (Audit, 0), (Transactions, 0)],
+            ),
+            (
+                only(|policy| policy.audit = RetentionRule::new(None, Some(0))),
+                // Everything but the newest entry, which the chain goes on from
+                [(Logs, 0), (FeeHistory, 0), (Audit, 4), (Transactions, 0)],
+            ),
+            (
+                only(|policy| policy.transactions = RetentionRule::new(None, Some(0))),
+                [(Logs, 0), (FeeHistory, 0), (Audit, 0), (Transactions, 1)],
+            ),
+        ];
+        for (policy, expected) in cases {
+            let mut wallet = busy_wallet();
+            wallet.set_retention_policy(policy).unwrap();
+            let report = wallet.run_maintenance(None).await.unwrap();
+            assert_eq!(removed(&report), expected);
+            assert_eq!(report.removed() > 0, report.bytes_reclaimed() > 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn keys_notes_and_pending_transactions_survive_any_policy() {
+        let mut wallet = busy_wallet();
+        let zero = RetentionRule::new(Some(0), Some(0));
+        wallet
+            .set_retention_policy(RetentionPolicy {
+                logs: zero,
+                fee_history: zero,
+                audit: zero,
+                transactions: zero,
+            })
+            .unwrap();
+        assert!(wallet.maintenance_due());
+
+        let report = wallet.run_maintenance(None).await.unwrap();
+        assert!(report.removed() > 0);
+        assert_eq!(wallet.keys().list_keys(), vec!["main".to_string()]);
+        assert_eq!(wallet.balances().notes().len(), 1);
+        assert_eq!(wallet.transactions().get_pending_transactions().len(), 1);
+        assert!(wallet
+            .transactions()
+            .get_confirmed_transactions()
+            .is_empty());
+        assert_eq!(wallet.audit.len(), 1);
+
+        assert_eq!(wallet.last_maintenance(), Some(&report));
+        assert!(!wallet.maintenance_due());
+    }
+
+    #[test]
+    fn old_and_excess_log_files_are_deleted_but_the_newest_kept() {
+        let dir = TempDir::new("retention-logs");
+        let now = SystemTime::now();
+        for (name, age_days) in [("a.log", 40), ("b.log", 2), ("c.log", 0), ("notes.txt", 40)] {
+            let file = File::create(dir.join(name)).unwrap();
+            file.set_len(100).unwrap();
+            file.set_modified(now - StdDuration::from_secs(age_days * 86_400))
+                .unwrap();
+        }
+        let names = || {
+            let mut names: Vec<String> = std::fs::read_dir(dir.path())
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            names
+        };
+
+        let month_ago = RetentionRule::new(Some(30), None).cutoff(Utc::now());
+        assert_eq!(
+            prune_log_files(dir.path(), month_ago, None).unwrap(),
+            (1, 100)
+        );
+        assert_eq!(names(), vec!["b.log", "c.log", "notes.txt"]);
+
+        assert_eq!(
+            prune_log_files(dir.path(), None, Some(0)).unwrap(),
+            (1, 100)
+        );
+        assert_eq!(names(), vec!["c.log", "notes.txt"]);
+
+        assert_eq!(
+            prune_log_files(&dir.join("missing"), None, Some(0)).unwrap(),
+            (0, 0)
+        );
+    }
+}
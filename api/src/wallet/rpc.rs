@@ -0,0 +1,319 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::RwLock;
+use tracing::{debug, warn};
+
+use crate::wallet::decode::decode_transaction;
+use crate::wallet::encoding::Decode;
+use crate::wallet::network::NodeHandle;
+use crate::wallet::selftest;
+use crate::wallet::transaction::SignedTransaction;
+use crate::wallet::{Wallet, WalletError, WalletResult};
+
+/// JSON-RPC error codes
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcRequest {
+    #[serde(default)]
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcResponse {
+    pub id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl RpcResponse {
+    pub fn ok(id: Value, result: Value) -> Self {
+        Self {
+            id,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    pub fn err(id: Value, code: i64, message: impl Into<String>) -> Self {
+        Self {
+            id,
+            result: None,
+            error: Some(RpcError {
+                code,
+                message: message.into(),
+            }),
+        }
+    }
+}
+
+/// State the RPC methods operate on
+#[derive(Clone)]
+pub struct RpcContext {
+    pub wallet: Arc<RwLock<Wallet>>,
+    pub node: NodeHandle,
+}
+
+/// Dispatch a single request to its method handler
+pub async fn handle_request(ctx: &RpcContext, request: RpcRequest) -> RpcResponse {
+    debug!("RPC call: {}", request.method);
+
+    match request.method.as_str() {
+        "gethealth" => {
+            let report = ctx.wallet.read().await.health_check(&ctx.node).await;
+            match serde_json::to_value(report) {
+                Ok(result) => RpcResponse::ok(request.id, result),
+                Err(e) => RpcResponse::err(request.id, INTERNAL_ERROR, e.to_string()),
+            }
+        }
+        "getversion" => {
+            let info = match ctx.node.version_info().await {
+                Ok(info) => info.with_wallet_schema(ctx.wallet.read().await.storage_schema()),
+                Err(e) => return RpcResponse::err(request.id, INTERNAL_ERROR, e.to_string()),
+            };
+            match serde_json::to_value(info) {
+                Ok(result) => RpcResponse::ok(request.id, result),
+                Err(e) => RpcResponse::err(request.id, INTERNAL_ERROR, e.to_string()),
+            }
+        }
+        "selftest" => {
+            // The node holds its ports by now, so their check would only fail
+            let results = selftest::run_from(std::env::vars(), Utc::now(), false);
+            match serde_json::to_value(results) {
+                Ok(result) => RpcResponse::ok(request.id, result),
+                Err(e) => RpcResponse::err(request.id, INTERNAL_ERROR, e.to_string()),
+            }
+        }
+        "sendrawtransaction" => {
+            let tx = match decode_raw_transaction(&request.params) {
+                Ok(tx) => tx,
+                Err(e) => return RpcResponse::err(request.id, INVALID_PARAMS, e.to_string()),
+            };
+            let txid = tx.id.clone();
+            ctx.wallet.write().await.record_transaction(tx, true);
+            RpcResponse::ok(request.id, Value::String(txid))
+        }
+        "decoderawtransaction" => {
+            let Some(raw) = raw_transaction_param(&request.params) else {
+                return RpcResponse::err(
+                    request.id,
+                    INVALID_PARAMS,
+                    "Expected a hex-encoded transaction",
+                );
+            };
+            // A partial decode is still a result: it says where the input went wrong
+            match serde_json::to_value(decode_transaction(raw)) {
+                Ok(result) => RpcResponse::ok(request.id, result),
+                Err(e) => RpcResponse::err(request.id, INTERNAL_ERROR, e.to_string()),
+            }
+        }
+        method => RpcResponse::err(
+            request.id,
+            METHOD_NOT_FOUND,
+            format!("Method not found: {}", method),
+        ),
+    }
+}
+
+/// Answer one line: a request object, or a batch of them as an array. A batch
+/// gets an array of responses in request order; an empty one is an error.
+pub async fn handle_line(ctx: &RpcContext, line: &str) -> Value {
+    let parsed = match serde_json::from_str::<Value>(line) {
+        Ok(parsed) => parsed,
+        Err(e) => return response_value(RpcResponse::err(Value::Null, PARSE_ERROR, e.to_string())),
+    };
+    match parsed {
+        Value::Array(requests) if requests.is_empty() => response_value(RpcResponse::err(
+            Value::Null,
+            INVALID_REQUEST,
+            "Empty batch",
+        )),
+        Value::Array(requests) => {
+            let mut responses = Vec::with_capacity(requests.len());
+            for request in requests {
+                responses.push(response_value(handle_value(ctx, request).await));
+            }
+            Value::Array(responses)
+        }
+        request => response_value(handle_value(ctx, request).await),
+    }
+}
+
+/// Handle one element of a line, which may not be a request at all
+async fn handle_value(ctx: &RpcContext, request: Value) -> RpcResponse {
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    match serde_json::from_value::<RpcRequest>(request) {
+        Ok(request) => handle_request(ctx, request).await,
+        Err(e) => RpcResponse::err(id, INVALID_REQUEST, e.to_string()),
+    }
+}
+
+fn response_value(response: RpcResponse) -> Value {
+    serde_json::to_value(response).expect("responses always serialize")
+}
+
+/// The transaction hex from `"<hex>"` or `["<hex>"]`
+fn raw_transaction_param(params: &Value) -> Option<&str> {
+    let raw = match params {
+        Value::String(raw) => raw.as_str(),
+        Value::Array(items) => items.first().and_then(Value::as_str)?,
+        _ => return None,
+    };
+    (!raw.is_empty()).then_some(raw)
+}
+
+fn decode_raw_transaction(params: &Value) -> WalletResult<SignedTransaction> {
+    let raw = raw_transaction_param(params).ok_or_else(|| {
+        WalletError::Transaction("Expected a hex-encoded transaction".to_string())
+    })?;
+
+    let bytes = hex::decode(raw)
+        .map_err(|e| WalletError::Serialization(format!("Invalid transaction hex: {}", e)))?;
+    SignedTransaction::from_bytes(&bytes)
+}
+
+/// Line-delimited JSON-RPC server: one request object or batch array per
+/// line, one response object or array per line
+pub struct RpcServer {
+    listener: TcpListener,
+    ctx: RpcContext,
+}
+
+impl RpcServer {
+    pub async fn bind(addr: SocketAddr, ctx: RpcContext) -> WalletResult<Self> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .map_err(|e| WalletError::Network(format!("Failed to bind RPC on {}: {}", addr, e)))?;
+        Ok(Self { listener, ctx })
+    }
+
+    pub fn local_addr(&self) -> WalletResult<SocketAddr> {
+        self.listener
+            .local_addr()
+            .map_err(|e| WalletError::Network(e.to_string()))
+    }
+
+    /// Accept connections until the listener fails
+    pub async fn serve(self) -> WalletResult<()> {
+        self.ctx.node.set_rpc_listening(true);
+
+        let result = loop {
+            let (stream, peer) = match self.listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => break Err(WalletError::Network(format!("RPC accept failed: {}", e))),
+            };
+            let ctx = self.ctx.clone();
+            tokio::spawn(async move {
+                if let Err(e) = serve_connection(stream, &ctx).await {
+                    warn!("RPC connection {} closed with error: {}", peer, e);
+                }
+            });
+        };
+
+        self.ctx.node.set_rpc_listening(false);
+        result
+    }
+}
+
+async fn serve_connection(stream: TcpStream, ctx: &RpcContext) -> std::io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = handle_line(ctx, &line).await;
+        let mut encoded = serde_json::to_vec(&response).map_err(std::io::Error::other)?;
+        encoded.push(b'\n');
+        writer.write_all(&encoded).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_signed_transaction, fakenet_config};
+    use crate::wallet::encoding::Encode;
+    use crate::wallet::network::NockchainNodeManager;
+    use serde_json::json;
+
+    fn context() -> RpcContext {
+        RpcContext {
+            wallet: Arc::new(RwLock::new(Wallet::new())),
+            node: NodeHandle::new(NockchainNodeManager::new(fakenet_config())),
+        }
+    }
+
+    async fn call(ctx: &RpcContext, method: &str, params: Value) -> RpcResponse {
+        let request = RpcRequest {
+            id: json!(1),
+            method: method.to_string(),
+            params,
+        };
+        handle_request(ctx, request).await
+    }
+
+    #[tokio::test]
+    async fn decoderawtransaction_accepts_a_string_or_a_one_element_array() {
+        let ctx = context();
+        let tx = fake_signed_transaction().build();
+        let raw = hex::encode(tx.to_bytes());
+
+        for params in [json!(raw), json!([raw])] {
+            let result = call(&ctx, "decoderawtransaction", params)
+                .await
+                .result
+                .unwrap();
+            assert_eq!(result["txid"], json!(tx.id));
+            assert_eq!(result["error"], Value::Null);
+        }
+    }
+
+    #[tokio::test]
+    async fn decoderawtransaction_returns_a_partial_decode_for_bad_input() {
+        let ctx = context();
+        let result = call(&ctx, "decoderawtransaction", json!("02ab"))
+            .await
+            .result
+            .unwrap();
+        assert_eq!(result["version"], json!(2));
+        assert_eq!(result["error"]["field"], json!("chain id"));
+        assert_eq!(result["error"]["offset"], json!(1));
+    }
+
+    #[tokio::test]
+    async fn decoderawtransaction_needs_a_hex_string() {
+        let ctx = context();
+        for params in [Value::Null, json!(""), json!([]), json!([7])] {
+            let error = call(&ctx, "decoderawtransaction", params)
+                .await
+                .error
+                .unwrap();
+            assert_eq!(error.code, INVALID_PARAMS);
+        }
+    }
+}
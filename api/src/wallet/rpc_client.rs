@@ -0,0 +1,609 @@
+//! Client for the line-delimited JSON-RPC server in `rpc`.
+//!
+//! One connection carries many calls at once. Each call or batch is written as
+//! soon as one of `max_in_flight` slots is free, and a reader task hands every
+//! response to the caller waiting on its id, in whatever order the server
+//! answers. Ids are allocated by the client, so a batch's responses are matched
+//! to its calls even if the server reorders or drops some of them. Once the
+//! server closes the connection, calls still waiting fail and later ones fail
+//! at once rather than waiting out the timeout.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tokio::sync::{oneshot, Semaphore};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::warn;
+
+use crate::wallet::proxy::Socks5Proxy;
+use crate::wallet::rpc::{RpcRequest, RpcResponse, INTERNAL_ERROR};
+use crate::wallet::{WalletError, WalletResult};
+
+type Pending = Arc<Mutex<PendingCalls>>;
+
+/// Calls waiting on a response, shared with the reader task
+#[derive(Default)]
+struct PendingCalls {
+    waiting: HashMap<u64, oneshot::Sender<RpcResponse>>,
+    /// Set when the reader stops; nothing more will be answered
+    closed: bool,
+}
+
+/// Limits for a `NodeClient`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeClientConfig {
+    /// Calls and batches written but not yet answered
+    pub max_in_flight: usize,
+    /// Calls per batch in `call_many`
+    pub batch_size: usize,
+    /// How long to wait for a response before giving up on it
+    pub timeout_secs: u64,
+    /// Connect through this proxy instead of directly; see
+    /// `Socks5Proxy::from_config`
+    #[serde(default)]
+    pub proxy: Option<Socks5Proxy>,
+}
+
+impl Default for NodeClientConfig {
+    fn default() -> Self {
+        Self {
+            max_in_flight: 8,
+            batch_size: 50,
+            timeout_secs: 30,
+            proxy: None,
+        }
+    }
+}
+
+/// Pipelined JSON-RPC connection
+pub struct NodeClient {
+    writer: tokio::sync::Mutex<OwnedWriteHalf>,
+    pending: Pending,
+    next_id: AtomicU64,
+    in_flight: Semaphore,
+    config: NodeClientConfig,
+    reader: JoinHandle<()>,
+}
+
+impl NodeClient {
+    /// Connect to the server at `addr`, through `config.proxy` if set
+    pub async fn connect(addr: SocketAddr, config: NodeClientConfig) -> WalletResult<Self> {
+        let stream = match &config.proxy {
+            Some(proxy) => proxy.connect_addr(addr).await?,
+            None => TcpStream::connect(addr).await.map_err(|e| {
+                WalletError::Network(format!("Failed to connect to {}: {}", addr, e))
+            })?,
+        };
+        let (reader, writer) = stream.into_split();
+        let pending = Pending::default();
+        Ok(Self {
+            writer: tokio::sync::Mutex::new(writer),
+            reader: tokio::spawn(read_responses(reader, pending.clone())),
+            pending,
+            next_id: AtomicU64::new(1),
+            in_flight: Semaphore::new(config.max_in_flight.max(1)),
+            config,
+        })
+    }
+
+    pub fn config(&self) -> &NodeClientConfig {
+        &self.config
+    }
+
+    /// Call `method` and decode its result as `T`
+    pub async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> WalletResult<T> {
+        let request = self.request(method, params);
+        let mut responses = self.send(vec![request], false).await?;
+        typed_result(method, responses.remove(0))
+    }
+
+    /// Start a batch; calls added to it go out as one line
+    pub fn batch(&self) -> RpcBatch<'_> {
+        RpcBatch {
+            client: self,
+            requests: Vec::new(),
+        }
+    }
+
+    /// Call `method` once with each of `params`, in batches of `batch_size`
+    /// that are pipelined up to `max_in_flight`. Results are in `params` order;
+    /// one failing call doesn't fail the others.
+    pub async fn call_many<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Vec<Value>,
+    ) -> WalletResult<Vec<WalletResult<T>>> {
+        let requests: Vec<RpcRequest> = params
+            .into_iter()
+            .map(|params| self.request(method, params))
+            .collect();
+        let batches = requests
+            .chunks(self.config.batch_size.max(1))
+            .map(|chunk| self.send(chunk.to_vec(), true));
+        let responses = futures::future::try_join_all(batches).await?;
+        Ok(responses
+            .into_iter()
+            .flatten()
+            .map(|response| typed_result(method, response))
+            .collect())
+    }
+
+    fn request(&self, method: &str, params: Value) -> RpcRequest {
+        RpcRequest {
+            id: Value::from(self.next_id.fetch_add(1, Ordering::Relaxed)),
+            method: method.to_string(),
+            params,
+        }
+    }
+
+    /// Write `requests` as one line, a batch array if `batch`, and wait for
+    /// a response to each. Responses come back in request order.
+    async fn send(&self, requests: Vec<RpcRequest>, batch: bool) -> WalletResult<Vec<RpcResponse>> {
+        let _slot = self
+            .in_flight
+            .acquire()
+            .await
+            .map_err(|_| WalletError::Network("RPC client closed".to_string()))?;
+
+        let ids: Vec<u64> = requests.iter().filter_map(|r| r.id.as_u64()).collect();
+        let mut receivers = Vec::with_capacity(ids.len());
+        {
+            let mut pending = self
+                .pending
+                .lock()
+                .map_err(|_| WalletError::Network("RPC client state poisoned".to_string()))?;
+            if pending.closed {
+                return Err(WalletError::Network(
+                    "RPC connection closed by the server".to_string(),
+                ));
+            }
+            for id in &ids {
+                let (tx, rx) = oneshot::channel();
+                pending.waiting.insert(*id, tx);
+                receivers.push(rx);
+            }
+        }
+
+        let line = if batch {
+            serde_json::to_vec(&requests)
+        } else {
+            serde_json::to_vec(&requests[0])
+        };
+        let mut line = line.map_err(|e| WalletError::Serialization(e.to_string()))?;
+        line.push(b'\n');
+        if let Err(e) = self.writer.lock().await.write_all(&line).await {
+            self.forget(&ids);
+            return Err(WalletError::Network(format!("RPC write failed: {}", e)));
+        }
+
+        let deadline = Instant::now() + Duration::from_secs(self.config.timeout_secs);
+        let mut responses = Vec::with_capacity(ids.len());
+        for (id, rx) in ids.iter().zip(receivers) {
+            let response = match tokio::time::timeout_at(deadline, rx).await {
+                Ok(Ok(response)) => response,
+                Ok(Err(_)) => RpcResponse::err(
+                    Value::from(*id),
+                    INTERNAL_ERROR,
+                    "Connection closed before a response",
+                ),
+                Err(_) => {
+                    self.forget(&[*id]);
+                    RpcResponse::err(Value::from(*id), INTERNAL_ERROR, "No response in time")
+                }
+            };
+            responses.push(response);
+        }
+        Ok(responses)
+    }
+
+    fn forget(&self, ids: &[u64]) {
+        if let Ok(mut pending) = self.pending.lock() {
+            for id in ids {
+                pending.waiting.remove(id);
+            }
+        }
+    }
+
+    /// Whether the server closed the connection; every call now fails
+    pub fn is_closed(&self) -> bool {
+        self.pending.lock().map_or(true, |pending| pending.closed)
+    }
+}
+
+impl Drop for NodeClient {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// Hand each response read from the server to the caller waiting on its id
+async fn read_responses(reader: OwnedReadHalf, pending: Pending) {
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let responses = match serde_json::from_str::<Value>(&line) {
+            Ok(Value::Array(items)) => items,
+            Ok(item) => vec![item],
+            Err(e) => {
+                warn!("Ignoring unreadable RPC response: {}", e);
+                continue;
+            }
+        };
+        for item in responses {
+            let Ok(response) = serde_json::from_value::<RpcResponse>(item) else {
+                warn!("Ignoring malformed RPC response");
+                continue;
+            };
+            let waiting = response.id.as_u64().and_then(|id| {
+                pending
+                    .lock()
+                    .ok()
+                    .and_then(|mut pending| pending.waiting.remove(&id))
+            });
+            match waiting {
+                Some(tx) => {
+                    let _ = tx.send(response);
+                }
+                None => warn!("RPC response for unknown id {}", response.id),
+            }
+        }
+    }
+    // Dropping the senders wakes every caller still waiting
+    if let Ok(mut pending) = pending.lock() {
+        pending.closed = true;
+        pending.waiting.clear();
+    }
+}
+
+fn typed_result<T: DeserializeOwned>(method: &str, response: RpcResponse) -> WalletResult<T> {
+    if let Some(error) = response.error {
+        return Err(WalletError::Network(format!(
+            "{} failed ({}): {}",
+            method, error.code, error.message
+        )));
+    }
+    serde_json::from_value(response.result.unwrap_or(Value::Null))
+        .map_err(|e| WalletError::Serialization(format!("Unexpected {} result: {}", method, e)))
+}
+
+/// Calls collected to go out as one batch
+pub struct RpcBatch<'a> {
+    client: &'a NodeClient,
+    requests: Vec<RpcRequest>,
+}
+
+/// Where a call's result will be in `BatchResults`, and its type
+pub struct BatchSlot<T> {
+    index: usize,
+    method: String,
+    _result: PhantomData<fn() -> T>,
+}
+
+impl<'a> RpcBatch<'a> {
+    /// Add a call whose result decodes as `T`
+    pub fn add<T: DeserializeOwned>(&mut self, method: &str, params: Value) -> BatchSlot<T> {
+        self.requests.push(self.client.request(method, params));
+        BatchSlot {
+            index: self.requests.len() - 1,
+            method: method.to_string(),
+            _result: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    /// Send every call as one line and wait for all the responses. Fails only
+    /// if the batch couldn't be sent; each call's own error is in its slot.
+    pub async fn send(self) -> WalletResult<BatchResults> {
+        if self.requests.is_empty() {
+            return Ok(BatchResults {
+                responses: Vec::new(),
+            });
+        }
+        let responses = self.client.send(self.requests, true).await?;
+        Ok(BatchResults { responses })
+    }
+}
+
+/// Responses to a batch, in the order the calls were added
+pub struct BatchResults {
+    responses: Vec<RpcResponse>,
+}
+
+impl BatchResults {
+    /// Result of the call behind `slot`
+    pub fn get<T: DeserializeOwned>(&self, slot: &BatchSlot<T>) -> WalletResult<T> {
+        let response = self
+            .responses
+            .get(slot.index)
+            .cloned()
+            .ok_or_else(|| WalletError::Network(format!("No response to {}", slot.method)))?;
+        typed_result(&slot.method, response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_signed_transaction, fakenet_config};
+    use crate::wallet::encoding::Encode;
+    use crate::wallet::network::{NockchainNodeManager, NodeHandle};
+    use crate::wallet::rpc::{RpcContext, RpcServer, METHOD_NOT_FOUND};
+    use crate::wallet::Wallet;
+    use serde_json::json;
+    use std::sync::atomic::AtomicUsize;
+    use tokio::net::TcpListener;
+    use tokio::sync::RwLock;
+
+    fn config(timeout_secs: u64) -> NodeClientConfig {
+        NodeClientConfig {
+            timeout_secs,
+            ..NodeClientConfig::default()
+        }
+    }
+
+    /// Echo of each request's params as its result, with the given id
+    fn echo(request: &Value) -> Value {
+        json!({"id": request["id"], "result": request["params"]})
+    }
+
+    /// Server for one connection that reads `lines` lines and then answers
+    /// whatever `answer` makes of them, each answer as its own line
+    async fn scripted_server(
+        lines: usize,
+        answer: impl FnOnce(Vec<Value>) -> Vec<Value> + Send + 'static,
+    ) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = stream.into_split();
+            let mut reader = BufReader::new(reader).lines();
+            let mut received = Vec::new();
+            for _ in 0..lines {
+                let line = reader.next_line().await.unwrap().unwrap();
+                received.push(serde_json::from_str(&line).unwrap());
+            }
+            for reply in answer(received) {
+                let mut line = serde_json::to_vec(&reply).unwrap();
+                line.push(b'\n');
+                writer.write_all(&line).await.unwrap();
+            }
+            // Hold the connection open until the client is done
+            let _ = reader.next_line().await;
+        });
+        addr
+    }
+
+    async fn real_server() -> SocketAddr {
+        let ctx = RpcContext {
+            wallet: Arc::new(RwLock::new(Wallet::new())),
+            node: NodeHandle::new(NockchainNodeManager::new(fakenet_config())),
+        };
+        let server = RpcServer::bind("127.0.0.1:0".parse().unwrap(), ctx)
+            .await
+            .unwrap();
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(server.serve());
+        addr
+    }
+
+    #[tokio::test]
+    async fn pipelined_calls_answered_out_of_order_get_their_own_results() {
+        let addr = scripted_server(2, |mut requests| {
+            requests.reverse();
+            requests.iter().map(echo).collect()
+        })
+        .await;
+        let client = NodeClient::connect(addr, config(5)).await.unwrap();
+
+        let (first, second) = tokio::join!(
+            client.call::<String>("echo", json!("first")),
+            client.call::<String>("echo", json!("second"))
+        );
+        assert_eq!(first.unwrap(), "first");
+        assert_eq!(second.unwrap(), "second");
+    }
+
+    #[tokio::test]
+    async fn a_batch_answered_in_reverse_keeps_each_call_in_its_slot() {
+        let addr = scripted_server(1, |requests| {
+            let Value::Array(mut batch) = requests[0].clone() else {
+                panic!("expected a batch");
+            };
+            batch.reverse();
+            vec![Value::Array(batch.iter().map(echo).collect())]
+        })
+        .await;
+        let client = NodeClient::connect(addr, config(5)).await.unwrap();
+
+        let mut batch = client.batch();
+        let slots: Vec<BatchSlot<u64>> = (0..5).map(|n| batch.add("echo", json!(n * 10))).collect();
+        let results = batch.send().await.unwrap();
+        for (n, slot) in slots.iter().enumerate() {
+            assert_eq!(results.get(slot).unwrap(), n as u64 * 10);
+        }
+    }
+
+    #[tokio::test]
+    async fn a_dropped_reply_fails_only_its_own_call() {
+        let addr = scripted_server(1, |requests| {
+            let Value::Array(batch) = requests[0].clone() else {
+                panic!("expected a batch");
+            };
+            // Answer all but the second, one line each, last first
+            batch
+                .iter()
+                .rev()
+                .filter(|r| r["id"] != batch[1]["id"])
+                .map(echo)
+                .collect()
+        })
+        .await;
+        let client = NodeClient::connect(addr, config(1)).await.unwrap();
+
+        let mut batch = client.batch();
+        let kept = batch.add::<String>("echo", json!("kept"));
+        let dropped = batch.add::<String>("echo", json!("dropped"));
+        let last = batch.add::<String>("echo", json!("last"));
+        let results = batch.send().await.unwrap();
+        assert_eq!(results.get(&kept).unwrap(), "kept");
+        assert_eq!(results.get(&last).unwrap(), "last");
+        let error = results.get(&dropped).unwrap_err().to_string();
+        assert!(error.contains("No response in time"), "{}", error);
+        assert!(!client.is_closed());
+    }
+
+    #[tokio::test]
+    async fn errors_in_a_batch_stay_with_their_calls() {
+        let client = NodeClient::connect(real_server().await, config(5))
+            .await
+            .unwrap();
+        let tx = fake_signed_transaction().build();
+        let raw = hex::encode(tx.to_bytes());
+
+        let mut batch = client.batch();
+        let decoded = batch.add::<Value>("decoderawtransaction", json!(raw));
+        let unknown = batch.add::<Value>("nosuchmethod", json!([]));
+        let mistyped = batch.add::<u64>("decoderawtransaction", json!(raw));
+        assert_eq!(batch.len(), 3);
+        let results = batch.send().await.unwrap();
+
+        assert_eq!(results.get(&decoded).unwrap()["txid"], json!(tx.id));
+        let error = results.get(&unknown).unwrap_err().to_string();
+        assert!(error.contains(&METHOD_NOT_FOUND.to_string()), "{}", error);
+        assert!(matches!(
+            results.get(&mistyped),
+            Err(WalletError::Serialization(_))
+        ));
+        assert!(client.batch().send().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn call_many_splits_into_batches_and_keeps_param_order() {
+        let client = NodeClient::connect(
+            real_server().await,
+            NodeClientConfig {
+                batch_size: 2,
+                ..config(5)
+            },
+        )
+        .await
+        .unwrap();
+        let txs: Vec<_> = (0..5)
+            .map(|n| fake_signed_transaction().fee(n + 1).build())
+            .collect();
+        let params = txs
+            .iter()
+            .map(|tx| json!(hex::encode(tx.to_bytes())))
+            .collect();
+
+        let results = client
+            .call_many::<Value>("decoderawtransaction", params)
+            .await
+            .unwrap();
+        let ids: Vec<Value> = results
+            .into_iter()
+            .map(|result| result.unwrap()["txid"].clone())
+            .collect();
+        let expected: Vec<Value> = txs.iter().map(|tx| json!(tx.id)).collect();
+        assert_eq!(ids, expected);
+    }
+
+    #[tokio::test]
+    async fn no_more_than_max_in_flight_calls_are_outstanding() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let outstanding = Arc::new(AtomicUsize::new(0));
+        let most = Arc::new(AtomicUsize::new(0));
+        let (counter, peak) = (outstanding.clone(), most.clone());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (reader, writer) = stream.into_split();
+            let writer = Arc::new(tokio::sync::Mutex::new(writer));
+            let mut lines = BufReader::new(reader).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let now = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                let (writer, counter) = (writer.clone(), counter.clone());
+                tokio::spawn(async move {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    let request: Value = serde_json::from_str(&line).unwrap();
+                    let replies: Vec<Value> =
+                        request.as_array().unwrap().iter().map(echo).collect();
+                    let mut reply = serde_json::to_vec(&replies).unwrap();
+                    reply.push(b'\n');
+                    counter.fetch_sub(1, Ordering::SeqCst);
+                    writer.lock().await.write_all(&reply).await.unwrap();
+                });
+            }
+        });
+
+        let client = NodeClient::connect(
+            addr,
+            NodeClientConfig {
+                max_in_flight: 2,
+                batch_size: 1,
+                ..config(5)
+            },
+        )
+        .await
+        .unwrap();
+        let results = client
+            .call_many::<u64>("echo", (0..8).map(|n| json!(n)).collect())
+            .await
+            .unwrap();
+        let values: Vec<u64> = results.into_iter().map(Result::unwrap).collect();
+        assert_eq!(values, (0..8).collect::<Vec<u64>>());
+        assert_eq!(most.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn once_the_server_hangs_up_calls_fail_at_once() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut lines = BufReader::new(stream).lines();
+            // Read the first call and hang up without answering
+            let _ = lines.next_line().await;
+        });
+        let client = NodeClient::connect(addr, config(30)).await.unwrap();
+
+        let started = std::time::Instant::now();
+        let error = client
+            .call::<Value>("echo", json!(1))
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.contains("Connection closed before a response"),
+            "{}",
+            error
+        );
+        assert!(client.is_closed());
+
+        let error = client.call::<Value>("echo", json!(2)).await.unwrap_err();
+        assert!(
+            error.to_string().contains("closed by the server"),
+            "{}",
+            error
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
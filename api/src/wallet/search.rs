@@ -0,0 +1,390 @@
+//! One search over transactions, addresses, contacts, labels and blocks.
+//!
+//! Ids, hashes and addresses match exactly or by prefix; names and labels
+//! also match anywhere inside. Results carry a `SearchTarget` saying what
+//! they point at, so a UI can open the right page, and come ranked exact
+//! before prefix before substring.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::wallet::chain::Blockchain;
+use crate::wallet::contacts::ContactBook;
+use crate::wallet::{Address, Wallet};
+
+/// What a search result points at
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SearchTarget {
+    Transaction {
+        tx_id: String,
+    },
+    /// One of the wallet's own addresses
+    Address {
+        address: String,
+    },
+    Contact {
+        name: String,
+    },
+    Block {
+        height: u64,
+        hash: String,
+    },
+}
+
+impl SearchTarget {
+    /// Order of result kinds that rank the same
+    fn kind_order(&self) -> u8 {
+        match self {
+            SearchTarget::Transaction { .. } => 0,
+            SearchTarget::Address { .. } => 1,
+            SearchTarget::Contact { .. } => 2,
+            SearchTarget::Block { .. } => 3,
+        }
+    }
+}
+
+/// How closely a result matched; earlier variants rank higher
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum MatchRank {
+    Exact,
+    Prefix,
+    Substring,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub target: SearchTarget,
+    /// The text that matched
+    pub title: String,
+    /// What the result is, e.g. "Transaction" or the contact's address
+    pub detail: String,
+    pub rank: MatchRank,
+}
+
+/// Exact or prefix match of an id or hash, ignoring case
+fn rank_id(candidate: &str, query: &str) -> Option<MatchRank> {
+    let candidate = candidate.to_ascii_lowercase();
+    let query = query.to_ascii_lowercase();
+    if candidate == query {
+        Some(MatchRank::Exact)
+    } else if candidate.starts_with(&query) {
+        Some(MatchRank::Prefix)
+    } else {
+        None
+    }
+}
+
+/// Exact or prefix match of an address; base58 is case-sensitive
+fn rank_address(candidate: &str, query: &str) -> Option<MatchRank> {
+    if candidate == query {
+        Some(MatchRank::Exact)
+    } else if candidate.starts_with(query) {
+        Some(MatchRank::Prefix)
+    } else {
+        None
+    }
+}
+
+/// Exact, prefix or substring match of a name or label, ignoring case
+fn rank_text(candidate: &str, query: &str) -> Option<MatchRank> {
+    let candidate = candidate.to_lowercase();
+    let query = query.to_lowercase();
+    if candidate == query {
+        Some(MatchRank::Exact)
+    } else if candidate.starts_with(&query) {
+        Some(MatchRank::Prefix)
+    } else if candidate.contains(&query) {
+        Some(MatchRank::Substring)
+    } else {
+        None
+    }
+}
+
+impl Wallet {
+    /// Everything matching `query`, best first. Contacts and blocks are only
+    /// searched when given. A target matched several ways appears once, at
+    /// its best rank.
+    pub fn search(
+        &self,
+        query: &str,
+        contacts: Option<&ContactBook>,
+        chain: Option<&Blockchain>,
+    ) -> Vec<SearchResult> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let mut results = Vec::new();
+        let mut push =
+            |target: SearchTarget, title: &str, detail: String, rank: Option<MatchRank>| {
+                if let Some(rank) = rank {
+                    results.push(SearchResult {
+                        target,
+                        title: title.to_string(),
+                        detail,
+                        rank,
+                    });
+                }
+            };
+
+        for tx in self.transactions.get_all_transactions() {
+            let target = SearchTarget::Transaction {
+                tx_id: tx.id.clone(),
+            };
+            let label = self.transactions.label(&tx.id);
+            push(
+                target.clone(),
+                &tx.id,
+                label.unwrap_or("Transaction").to_string(),
+                rank_id(&tx.id, query),
+            );
+            if let Some(label) = label {
+                push(target, label, tx.id.clone(), rank_text(label, query));
+            }
+        }
+
+        let mut owned: Vec<(Address, String)> = self
+            .keys
+            .get_all_addresses()
+            .into_iter()
+            .map(|(name, address)| (address, format!("Key {}", name)))
+            .collect();
+        for (name, chain) in self.keys.address_chains() {
+            owned.extend(
+                chain
+                    .addresses()
+                    .iter()
+                    .map(|address| (address.clone(), format!("Receive address of {}", name))),
+            );
+            if let Some(change) = self.keys.change_chain(name) {
+                owned.extend(
+                    change
+                        .addresses()
+                        .iter()
+                        .map(|address| (address.clone(), format!("Change address of {}", name))),
+                );
+            }
+        }
+        owned.extend(
+            self.keys
+                .address_labels()
+                .keys()
+                .map(|address| (address.clone(), "Your address".to_string())),
+        );
+        let mut seen = HashSet::new();
+        for (address, kind) in owned {
+            if !seen.insert(address.clone()) {
+                continue;
+            }
+            let address_string = address.to_string();
+            let target = SearchTarget::Address {
+                address: address_string.clone(),
+            };
+            let label = self.keys.address_label(&address);
+            push(
+                target.clone(),
+                &address_string,
+                label.map_or(kind, str::to_string),
+                rank_address(&address_string, query),
+            );
+            if let Some(label) = label {
+                push(
+                    target,
+                    label,
+                    address_string.clone(),
+                    rank_text(label, query),
+                );
+            }
+        }
+
+        for contact in contacts.map(ContactBook::contacts).unwrap_or_default() {
+            let target = SearchTarget::Contact {
+                name: contact.name.clone(),
+            };
+            let address = contact.address.to_string();
+            push(
+                target.clone(),
+                &contact.name,
+                address.clone(),
+                rank_text(&contact.name, query),
+            );
+            push(
+                target,
+                &address,
+                format!("Contact {}", contact.name),
+                rank_address(&address, query),
+            );
+        }
+
+        for block in chain.map(Blockchain::blocks).unwrap_or_default() {
+            let height = block.header.height;
+            let hash = hex::encode(block.hash());
+            let target = SearchTarget::Block {
+                height,
+                hash: hash.clone(),
+            };
+            let detail = format!("Block {}", height);
+            push(target.clone(), &hash, detail.clone(), rank_id(&hash, query));
+            if query.parse::<u64>() == Ok(height) {
+                push(target, &height.to_string(), detail, Some(MatchRank::Exact));
+            }
+        }
+
+        results.sort_by(|a, b| {
+            (a.rank, a.target.kind_order(), &a.title).cmp(&(
+                b.rank,
+                b.target.kind_order(),
+                &b.title,
+            ))
+        });
+        let mut targets = HashSet::new();
+        results.retain(|result| targets.insert(result.target.clone()));
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_address, fake_signed_transaction, fakenet_chain_config};
+    use crate::wallet::keys::NockchainKeyManager;
+
+    /// Wallet with key "main" and one recorded payment labelled `label`
+    fn wallet_with_payment(label: &str) -> (Wallet, String) {
+        let mut wallet = Wallet::new();
+        wallet.keys = NockchainKeyManager::from_seed([5; 32]);
+        let address = wallet.generate_key("main").unwrap();
+        let tx = fake_signed_transaction().paying(&address, 100).build();
+        wallet.record_transaction(tx.clone(), false);
+        wallet.transactions_mut().set_label(&tx.id, label);
+        (wallet, tx.id)
+    }
+
+    fn chain_with_genesis() -> Blockchain {
+        let mut chain = Blockchain::new(fakenet_chain_config());
+        let mut block = chain.new_block(Vec::new(), chain.config().initial_difficulty);
+        block.mine().unwrap();
+        chain.add_block(block).unwrap();
+        chain
+    }
+
+    #[test]
+    fn each_kind_of_result_routes_to_its_target() {
+        let (mut wallet, tx_id) = wallet_with_payment("Rent");
+        let address = wallet.keys.get_key("main").unwrap().address().clone();
+        wallet.set_address_label(&address, "Donations").unwrap();
+        let mut contacts = ContactBook::new();
+        let bob = fake_address();
+        contacts.add("Bob", bob.clone()).unwrap();
+        let chain = chain_with_genesis();
+        let hash = hex::encode(chain.tip().unwrap().hash());
+
+        let first = |query: &str| {
+            wallet
+                .search(query, Some(&contacts), Some(&chain))
+                .into_iter()
+                .next()
+                .unwrap()
+        };
+        let tx = first(&tx_id[..10]);
+        assert_eq!(
+            tx.target,
+            SearchTarget::Transaction {
+                tx_id: tx_id.clone()
+            }
+        );
+        assert_eq!((tx.rank, tx.detail.as_str()), (MatchRank::Prefix, "Rent"));
+
+        let own = first("donations");
+        assert_eq!(
+            own.target,
+            SearchTarget::Address {
+                address: address.to_string(),
+            }
+        );
+        assert_eq!(own.detail, address.to_string());
+
+        let contact = first(&bob.to_string());
+        assert_eq!(
+            contact.target,
+            SearchTarget::Contact {
+                name: "Bob".to_string()
+            }
+        );
+        assert_eq!(contact.rank, MatchRank::Exact);
+
+        let by_hash = first(&hash[..12].to_uppercase());
+        assert_eq!(
+            by_hash.target,
+            SearchTarget::Block {
+                height: 0,
+                hash: hash.clone(),
+            }
+        );
+        assert_eq!(first("0").target, by_hash.target);
+    }
+
+    #[test]
+    fn exact_matches_rank_above_prefixes_above_substrings() {
+        let (wallet, _) = wallet_with_payment("Salary bonus");
+        let mut contacts = ContactBook::new();
+        for name in ["Sal", "Salt", "Basalt"] {
+            contacts.add(name, fake_address()).unwrap();
+        }
+
+        let results = wallet.search("sal", Some(&contacts), None);
+        let ranked: Vec<(&str, MatchRank)> = results
+            .iter()
+            .map(|result| (result.title.as_str(), result.rank))
+            .collect();
+        assert_eq!(
+            ranked,
+            [
+                ("Sal", MatchRank::Exact),
+                // Same rank: transactions come before contacts
+                ("Salary bonus", MatchRank::Prefix),
+                ("Salt", MatchRank::Prefix),
+                ("Basalt", MatchRank::Substring),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_target_matched_several_ways_appears_once_at_its_best_rank() {
+        let (mut wallet, tx_id) = wallet_with_payment("placeholder");
+        wallet.transactions_mut().set_label(&tx_id, &tx_id[..8]);
+
+        let results = wallet.search(&tx_id[..8], None, None);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].rank, MatchRank::Exact);
+        assert_eq!(results[0].title, &tx_id[..8]);
+    }
+
+    #[test]
+    fn addresses_match_case_sensitively_and_ids_do_not() {
+        let (wallet, tx_id) = wallet_with_payment("x");
+        let address = wallet.keys.get_key("main").unwrap().address().to_string();
+
+        assert!(!wallet.search(&tx_id.to_uppercase(), None, None).is_empty());
+        let swapped: String = address
+            .chars()
+            .map(|c| {
+                if c.is_lowercase() {
+                    c.to_ascii_uppercase()
+                } else {
+                    c.to_ascii_lowercase()
+                }
+            })
+            .collect();
+        assert!(wallet
+            .search(&swapped, None, None)
+            .iter()
+            .all(|result| !matches!(result.target, SearchTarget::Address { .. })));
+    }
+
+    #[test]
+    fn a_blank_query_finds_nothing() {
+        let (wallet, _) = wallet_with_payment("anything");
+        assert!(wallet.search("   ", None, None).is_empty());
+    }
+}
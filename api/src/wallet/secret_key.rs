@@ -0,0 +1,410 @@
+//! Secret keys pasted from other tools.
+//!
+//! Three encodings of the 32-byte ed25519 secret are accepted:
+//!
+//! - hex, 64 characters, optionally prefixed with `0x`
+//! - plain base58 of the 32 bytes
+//! - the checked form: base58 of a version byte, the 32 bytes and the first
+//!   four bytes of sha256(sha256(version || secret)), as addresses do
+//!
+//! The forms are told apart by length and alphabet. Anything longer than the
+//! longest base58 form, or made of hex digits and containing a `0` (which
+//! base58 lacks), is hex. A shorter string of hex digits that doesn't decode
+//! as base58 to a key is reported as hex of the wrong length, since that is
+//! the likelier mistake.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::wallet::address::address_checksum;
+use crate::wallet::audit::{AuditEvent, AuditOutcome};
+use crate::wallet::key_import::address_for_secret;
+use crate::wallet::keys::{NockchainKeyManager, NockchainKeyPair};
+use crate::wallet::{Address, Wallet, WalletError, WalletResult};
+
+/// Version byte of the checked secret key form
+pub const SECRET_KEY_VERSION: u8 = 0x9e;
+
+const SECRET_LEN: usize = 32;
+const CHECKSUM_LEN: usize = 4;
+const CHECKED_LEN: usize = 1 + SECRET_LEN + CHECKSUM_LEN;
+const HEX_LEN: usize = SECRET_LEN * 2;
+/// Most characters base58 of `CHECKED_LEN` bytes can take
+const MAX_BASE58_LEN: usize = 51;
+
+/// Encoding a secret key was given in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SecretKeyFormat {
+    Hex,
+    Base58,
+    /// Version byte and checksum around the secret, like a WIF key
+    Checked,
+}
+
+impl SecretKeyFormat {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SecretKeyFormat::Hex => "hex",
+            SecretKeyFormat::Base58 => "base58",
+            SecretKeyFormat::Checked => "checked base58",
+        }
+    }
+}
+
+/// Why a string is not a usable secret key
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SecretKeyError {
+    #[error("Secret key is empty")]
+    Empty,
+
+    #[error("'{character}' at position {index} is not a {encoding} character")]
+    InvalidCharacter {
+        character: char,
+        index: usize,
+        encoding: &'static str,
+    },
+
+    #[error("Hex secret is {0} characters; expected {HEX_LEN}")]
+    WrongHexLength(usize),
+
+    #[error(
+        "Secret decodes to {0} bytes; expected {SECRET_LEN}, or {CHECKED_LEN} in the checked form"
+    )]
+    WrongLength(usize),
+
+    #[error("Checksum does not match; the key may have a typo")]
+    ChecksumMismatch,
+
+    #[error("Version byte 0x{0:02x} is not a secret key's")]
+    UnknownVersion(u8),
+}
+
+impl From<SecretKeyError> for WalletError {
+    fn from(e: SecretKeyError) -> Self {
+        WalletError::InvalidSecretKey(e.to_string())
+    }
+}
+
+/// What a pasted secret would import as, without the secret itself
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecretKeyPreview {
+    pub format: SecretKeyFormat,
+    pub address: Address,
+}
+
+/// Detect the encoding of `input` and decode the secret it holds
+pub(crate) fn parse_secret_key(input: &str) -> Result<(SecretKeyFormat, [u8; 32]), SecretKeyError> {
+    let s = input.trim();
+    if s.is_empty() {
+        return Err(SecretKeyError::Empty);
+    }
+    if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        return parse_hex(hex, 2).map(|secret| (SecretKeyFormat::Hex, secret));
+    }
+
+    let hex_digits = s.chars().all(|c| c.is_ascii_hexdigit());
+    if s.len() > MAX_BASE58_LEN || (hex_digits && s.contains('0')) {
+        return parse_hex(s, 0).map(|secret| (SecretKeyFormat::Hex, secret));
+    }
+
+    let decoded = bs58::decode(s).into_vec().map_err(|e| match e {
+        bs58::decode::Error::InvalidCharacter { character, index } => {
+            SecretKeyError::InvalidCharacter {
+                character,
+                index,
+                encoding: "base58",
+            }
+        }
+        _ => SecretKeyError::WrongLength(0),
+    })?;
+
+    match decoded.len() {
+        SECRET_LEN => Ok((SecretKeyFormat::Base58, to_secret(&decoded))),
+        CHECKED_LEN => {
+            let (payload, checksum) = decoded.split_at(1 + SECRET_LEN);
+            if checksum != address_checksum(payload) {
+                return Err(SecretKeyError::ChecksumMismatch);
+            }
+            if payload[0] != SECRET_KEY_VERSION {
+                return Err(SecretKeyError::UnknownVersion(payload[0]));
+            }
+            Ok((SecretKeyFormat::Checked, to_secret(&payload[1..])))
+        }
+        _ if hex_digits => Err(SecretKeyError::WrongHexLength(s.len())),
+        len => Err(SecretKeyError::WrongLength(len)),
+    }
+}
+
+/// `offset` is where `hex` starts in the input, for error positions
+fn parse_hex(hex: &str, offset: usize) -> Result<[u8; 32], SecretKeyError> {
+    if let Some((index, character)) = hex
+        .chars()
+        .enumerate()
+        .find(|(_, c)| !c.is_ascii_hexdigit())
+    {
+        return Err(SecretKeyError::InvalidCharacter {
+            character,
+            index: offset + index,
+            encoding: "hex",
+        });
+    }
+    if hex.len() != HEX_LEN {
+        return Err(SecretKeyError::WrongHexLength(hex.len()));
+    }
+    let bytes = hex::decode(hex).map_err(|_| SecretKeyError::WrongHexLength(hex.len()))?;
+    Ok(to_secret(&bytes))
+}
+
+fn to_secret(bytes: &[u8]) -> [u8; 32] {
+    let mut secret = [0u8; SECRET_LEN];
+    secret.copy_from_slice(bytes);
+    secret
+}
+
+/// Format and address of the secret in `input`, to show before importing it
+pub fn preview_secret_key(input: &str) -> Result<SecretKeyPreview, SecretKeyError> {
+    let (format, secret) = parse_secret_key(input)?;
+    Ok(SecretKeyPreview {
+        format,
+        address: address_for_secret(&secret),
+    })
+}
+
+/// Checked form of `secret`: base58 of the version byte, secret and checksum
+pub fn encode_secret_key(secret: &[u8; 32]) -> String {
+    let mut bytes = Vec::with_capacity(CHECKED_LEN);
+    bytes.push(SECRET_KEY_VERSION);
+    bytes.extend_from_slice(secret);
+    let checksum = address_checksum(&bytes);
+    bytes.extend_from_slice(&checksum);
+    bs58::encode(bytes).into_string()
+}
+
+impl NockchainKeyManager {
+    /// Import a secret given as hex, base58 or the checked form, whichever
+    /// `input` turns out to be
+    pub fn import_key_flexible(
+        &mut self,
+        name: String,
+        input: &str,
+    ) -> WalletResult<&NockchainKeyPair> {
+        let (_, secret) = parse_secret_key(input)?;
+        if let Some(existing) = self.key_name_for_address(&address_for_secret(&secret)) {
+            return Err(WalletError::KeyExists(format!(
+                "'{}' holds the same key",
+                existing
+            )));
+        }
+        self.import_key(name, secret)
+    }
+
+    /// Secret of key `name` in the checked form, for importing elsewhere
+    pub fn export_key_checked(&self, name: &str) -> WalletResult<String> {
+        let key = self
+            .get_key(name)
+            .ok_or_else(|| WalletError::KeyNotFound(name.to_string()))?;
+        let secret = self
+            .secret_for_address(key.address())
+            .ok_or_else(|| WalletError::KeyNotFound(name.to_string()))?;
+        Ok(encode_secret_key(&secret))
+    }
+}
+
+impl Wallet {
+    /// Import one key from a pasted secret in any supported encoding
+    pub fn import_key_flexible(&mut self, name: &str, input: &str) -> WalletResult<Address> {
+        let name = name.trim().to_string();
+        let result = self.keys_mut().and_then(|keys| {
+            if name.is_empty() {
+                return Err(WalletError::InvalidSecretKey(
+                    "key name is empty".to_string(),
+                ));
+            }
+            keys.import_key_flexible(name.clone(), input)
+                .map(|key| key.address().clone())
+        });
+        self.record_audit(
+            AuditEvent::KeyImported { key_name: name },
+            AuditOutcome::of(&result),
+        );
+        result
+    }
+
+    /// Secret of key `name` in the checked form. Needs a recent password entry.
+    pub fn export_key_checked(&mut self, name: &str) -> WalletResult<String> {
+        let result = self
+            .require_sensitive_auth()
+            .and_then(|_| self.keys().export_key_checked(name));
+        self.record_audit(
+            AuditEvent::KeyExported {
+                key_name: name.to_string(),
+            },
+            AuditOutcome::of(&result),
+        );
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: [u8; 32] = [
+        0x9d, 0x61, 0xb1, 0x9d, 0xef, 0xfd, 0x5a, 0x60, 0xba, 0x84, 0x4a, 0xf4, 0x92, 0xec, 0x2c,
+        0xc4, 0x44, 0x49, 0xc5, 0x69, 0x7b, 0x32, 0x69, 0x19, 0x70, 0x3b, 0xac, 0x03, 0x1c, 0xae,
+        0x7f, 0x60,
+    ];
+
+    fn parsed(input: &str) -> Result<(SecretKeyFormat, [u8; 32]), SecretKeyError> {
+        parse_secret_key(input)
+    }
+
+    #[test]
+    fn each_format_decodes_to_the_same_secret() {
+        let hex = hex::encode(SECRET);
+        let cases = [
+            (hex.clone(), SecretKeyFormat::Hex),
+            (hex.to_uppercase(), SecretKeyFormat::Hex),
+            (format!("0x{}", hex), SecretKeyFormat::Hex),
+            (format!("  {}\n", hex), SecretKeyFormat::Hex),
+            (bs58::encode(SECRET).into_string(), SecretKeyFormat::Base58),
+            (encode_secret_key(&SECRET), SecretKeyFormat::Checked),
+        ];
+        for (input, format) in cases {
+            assert_eq!(parsed(&input), Ok((format, SECRET)), "{}", input);
+        }
+        assert_eq!(
+            preview_secret_key(&encode_secret_key(&SECRET)).unwrap(),
+            SecretKeyPreview {
+                format: SecretKeyFormat::Checked,
+                address: address_for_secret(&SECRET),
+            }
+        );
+    }
+
+    #[test]
+    fn strings_valid_in_both_alphabets_are_told_apart() {
+        // Hex digits without a 0, too long for base58
+        let no_zero = [0x11u8; 32];
+        assert_eq!(
+            parsed(&hex::encode(no_zero)),
+            Ok((SecretKeyFormat::Hex, no_zero))
+        );
+        // A base58 key that happens to use only hex digits stays base58
+        let all_hex_digits = (0u8..=255)
+            .map(|fill| [fill; 32])
+            .map(|secret| (secret, bs58::encode(secret).into_string()))
+            .find(|(_, text)| text.chars().all(|c| c.is_ascii_hexdigit()));
+        if let Some((secret, text)) = all_hex_digits {
+            assert_eq!(parsed(&text), Ok((SecretKeyFormat::Base58, secret)));
+        }
+        // Short hex is reported as hex, whether or not it has a 0
+        assert_eq!(parsed("abcdef12"), Err(SecretKeyError::WrongHexLength(8)));
+        assert_eq!(parsed("abcdef10"), Err(SecretKeyError::WrongHexLength(8)));
+    }
+
+    #[test]
+    fn errors_name_length_characters_or_checksum() {
+        assert_eq!(parsed("   "), Err(SecretKeyError::Empty));
+        assert_eq!(
+            parsed("0xabcg"),
+            Err(SecretKeyError::InvalidCharacter {
+                character: 'g',
+                index: 5,
+                encoding: "hex",
+            })
+        );
+        assert_eq!(
+            parsed(&format!("{}00", hex::encode(SECRET))),
+            Err(SecretKeyError::WrongHexLength(66))
+        );
+        assert_eq!(
+            parsed("3mJr7AoUXx2Wqd0"),
+            Err(SecretKeyError::InvalidCharacter {
+                character: '0',
+                index: 14,
+                encoding: "base58",
+            })
+        );
+        assert_eq!(
+            parsed("3mJr7AoUXx2WqdIl"),
+            Err(SecretKeyError::InvalidCharacter {
+                character: 'I',
+                index: 14,
+                encoding: "base58",
+            })
+        );
+        assert_eq!(
+            parsed(&bs58::encode([1u8; 20]).into_string()),
+            Err(SecretKeyError::WrongLength(20))
+        );
+
+        let mut checked = bs58::decode(encode_secret_key(&SECRET)).into_vec().unwrap();
+        checked[5] ^= 1;
+        assert_eq!(
+            parsed(&bs58::encode(&checked).into_string()),
+            Err(SecretKeyError::ChecksumMismatch)
+        );
+
+        let mut other_version = vec![0x80];
+        other_version.extend_from_slice(&SECRET);
+        let checksum = address_checksum(&other_version);
+        other_version.extend_from_slice(&checksum);
+        assert_eq!(
+            parsed(&bs58::encode(other_version).into_string()),
+            Err(SecretKeyError::UnknownVersion(0x80))
+        );
+    }
+
+    #[test]
+    fn exported_keys_import_back_and_duplicates_are_refused() {
+        let mut keys = NockchainKeyManager::from_seed([3; 32]);
+        let derived = keys
+            .generate_key("main".to_string())
+            .unwrap()
+            .address()
+            .clone();
+        let exported = keys.export_key_checked("main").unwrap();
+
+        let mut other = NockchainKeyManager::from_seed([4; 32]);
+        let imported = other
+            .import_key_flexible("copy".to_string(), &exported)
+            .unwrap();
+        assert_eq!(imported.address(), &derived);
+        assert_eq!(other.export_key_checked("copy").unwrap(), exported);
+
+        let hex = hex::encode(parsed(&exported).unwrap().1);
+        assert!(matches!(
+            other.import_key_flexible("again".to_string(), &hex),
+            Err(WalletError::KeyExists(_))
+        ));
+        assert!(matches!(
+            other.export_key_checked("nobody"),
+            Err(WalletError::KeyNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn the_wallet_import_needs_a_name_and_audits_it() {
+        let mut wallet = Wallet::new();
+        assert!(matches!(
+            wallet.import_key_flexible("  ", &hex::encode(SECRET)),
+            Err(WalletError::InvalidSecretKey(_))
+        ));
+        assert!(matches!(
+            wallet.import_key_flexible("pasted", "not a key"),
+            Err(WalletError::InvalidSecretKey(_))
+        ));
+        let address = wallet
+            .import_key_flexible(" pasted ", &hex::encode(SECRET))
+            .unwrap();
+        assert_eq!(address, address_for_secret(&SECRET));
+        assert!(wallet.keys().get_key("pasted").is_some());
+        let imports = wallet
+            .audit_log(&crate::wallet::audit::AuditFilter::default())
+            .into_iter()
+            .filter(|entry| matches!(entry.event, AuditEvent::KeyImported { .. }))
+            .count();
+        assert_eq!(imports, 3);
+    }
+}
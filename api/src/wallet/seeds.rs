@@ -0,0 +1,272 @@
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::net::SocketAddr;
+use std::path::Path;
+
+use crate::wallet::peers::PeerSource;
+use crate::wallet::{WalletError, WalletResult};
+
+/// Resolved DNS seed addresses cached inside the node data dir
+pub const KNOWN_PEERS_FILE: &str = "known_peers.json";
+
+/// Looks up the peer multiaddrs a DNS seed points at, replaceable for tests
+pub trait SeedResolver: Send + Sync {
+    /// Multiaddrs advertised by `seed`; plain A/AAAA records become
+    /// `/ip4|ip6/<addr>/tcp/<port>`
+    fn resolve<'a>(&'a self, seed: &'a str, port: u16) -> BoxFuture<'a, io::Result<Vec<String>>>;
+}
+
+/// Resolves A/AAAA records through the operating system. TXT multiaddr
+/// records need a resolver that can query them directly.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemResolver;
+
+impl SeedResolver for SystemResolver {
+    fn resolve<'a>(&'a self, seed: &'a str, port: u16) -> BoxFuture<'a, io::Result<Vec<String>>> {
+        Box::pin(async move {
+            let addrs = tokio::net::lookup_host((seed, port)).await?;
+            Ok(addrs.map(|addr| socket_multiaddr(&addr)).collect())
+        })
+    }
+}
+
+fn socket_multiaddr(addr: &SocketAddr) -> String {
+    match addr {
+        SocketAddr::V4(v4) => format!("/ip4/{}/tcp/{}", v4.ip(), v4.port()),
+        SocketAddr::V6(v6) => format!("/ip6/{}/tcp/{}", v6.ip(), v6.port()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedSeed {
+    pub resolved_at: DateTime<Utc>,
+    pub addrs: Vec<String>,
+}
+
+/// Last successful resolution of each DNS seed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KnownPeersCache {
+    seeds: HashMap<String, CachedSeed>,
+}
+
+impl KnownPeersCache {
+    /// Load the cache from `data_dir`; a missing or unreadable file gives an empty cache
+    pub fn load(data_dir: &Path) -> Self {
+        std::fs::read(data_dir.join(KNOWN_PEERS_FILE))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, data_dir: &Path) -> WalletResult<()> {
+        let json = serde_json::to_vec_pretty(self)
+            .map_err(|e| WalletError::Storage(format!("Serialization failed: {}", e)))?;
+        std::fs::write(data_dir.join(KNOWN_PEERS_FILE), json)
+            .map_err(|e| WalletError::Storage(format!("Failed to write known peers: {}", e)))
+    }
+
+    /// Cached addresses for `seed` if they were resolved less than `ttl_secs` ago
+    pub fn fresh(&self, seed: &str, ttl_secs: u64, now: DateTime<Utc>) -> Option<&[String]> {
+        let ttl = ChronoDuration::seconds(ttl_secs.min(i64::MAX as u64) as i64);
+        self.seeds
+            .get(seed)
+            .filter(|cached| now - cached.resolved_at < ttl)
+            .map(|cached| cached.addrs.as_slice())
+    }
+
+    pub fn insert(&mut self, seed: &str, addrs: Vec<String>, now: DateTime<Utc>) {
+        self.seeds.insert(
+            seed.to_string(),
+            CachedSeed {
+                resolved_at: now,
+                addrs,
+            },
+        );
+    }
+}
+
+/// Result of resolving every configured seed
+#[derive(Debug, Default)]
+pub struct SeedResolution {
+    pub addrs: Vec<String>,
+    /// Seeds that could not be resolved, with why
+    pub failures: Vec<(String, io::Error)>,
+    /// Whether any seed was looked up rather than served from the cache
+    pub refreshed: bool,
+}
+
+/// Resolve `seeds`, using cached answers younger than `ttl_secs` and caching new ones
+pub async fn resolve_seeds(
+    seeds: &[String],
+    port: u16,
+    resolver: &dyn SeedResolver,
+    cache: &mut KnownPeersCache,
+    ttl_secs: u64,
+    now: DateTime<Utc>,
+) -> SeedResolution {
+    let mut resolution = SeedResolution::default();
+    for seed in seeds {
+        if let Some(addrs) = cache.fresh(seed, ttl_secs, now) {
+            resolution.addrs.extend_from_slice(addrs);
+            continue;
+        }
+
+        match resolver.resolve(seed, port).await {
+            Ok(addrs) => {
+                resolution.addrs.extend(addrs.iter().cloned());
+                cache.insert(seed, addrs, now);
+                resolution.refreshed = true;
+            }
+            Err(e) => resolution.failures.push((seed.clone(), e)),
+        }
+    }
+    resolution
+}
+
+/// Static peers first, then DNS-resolved ones. Addresses are compared without
+/// their `/p2p/<id>` suffix, so a seed answer never duplicates a static peer.
+pub fn merge_peers(static_peers: &[String], dns_peers: &[String]) -> Vec<(String, PeerSource)> {
+    let mut seen = HashSet::new();
+    static_peers
+        .iter()
+        .map(|addr| (addr, PeerSource::Static))
+        .chain(dns_peers.iter().map(|addr| (addr, PeerSource::Dns)))
+        .filter(|(addr, _)| seen.insert(transport_addr(addr)))
+        .map(|(addr, source)| (addr.clone(), source))
+        .collect()
+}
+
+fn transport_addr(addr: &str) -> &str {
+    addr.split("/p2p/").next().unwrap_or(addr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fixture_time, FakeResolver, TempDir};
+
+    const SEED_A: &str = "seed-a.nockchain.net";
+    const SEED_B: &str = "seed-b.nockchain.net";
+    const TTL: u64 = 3600;
+
+    fn seeds() -> Vec<String> {
+        vec![SEED_A.to_string(), SEED_B.to_string()]
+    }
+
+    fn resolver() -> FakeResolver {
+        FakeResolver::new()
+            .answering(
+                SEED_A,
+                &["/ip4/10.0.0.1/tcp/4001", "/ip4/10.0.0.2/tcp/4001"],
+            )
+            .answering(SEED_B, &["/ip6/::1/tcp/4001"])
+    }
+
+    #[tokio::test]
+    async fn every_seed_is_resolved_and_cached() {
+        let resolver = resolver();
+        let mut cache = KnownPeersCache::default();
+        let now = fixture_time(0);
+        let resolution = resolve_seeds(&seeds(), 4001, &resolver, &mut cache, TTL, now).await;
+
+        assert_eq!(
+            resolution.addrs,
+            vec![
+                "/ip4/10.0.0.1/tcp/4001",
+                "/ip4/10.0.0.2/tcp/4001",
+                "/ip6/::1/tcp/4001"
+            ]
+        );
+        assert!(resolution.failures.is_empty());
+        assert!(resolution.refreshed);
+        assert_eq!(resolver.lookups(), seeds());
+        assert_eq!(
+            cache.fresh(SEED_B, TTL, now),
+            Some(&["/ip6/::1/tcp/4001".to_string()][..])
+        );
+    }
+
+    #[tokio::test]
+    async fn cached_answers_are_reused_until_the_ttl_expires() {
+        let resolver = resolver();
+        let mut cache = KnownPeersCache::default();
+        let first =
+            resolve_seeds(&seeds(), 4001, &resolver, &mut cache, TTL, fixture_time(0)).await;
+
+        let within = fixture_time(TTL as i64 - 1);
+        let cached = resolve_seeds(&seeds(), 4001, &resolver, &mut cache, TTL, within).await;
+        assert_eq!(cached.addrs, first.addrs);
+        assert!(!cached.refreshed);
+        assert_eq!(resolver.lookups().len(), 2);
+
+        let expired = fixture_time(TTL as i64);
+        assert_eq!(cache.fresh(SEED_A, TTL, expired), None);
+        let refreshed = resolve_seeds(&seeds(), 4001, &resolver, &mut cache, TTL, expired).await;
+        assert_eq!(refreshed.addrs, first.addrs);
+        assert!(refreshed.refreshed);
+        assert_eq!(resolver.lookups().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn a_failing_seed_is_reported_and_the_rest_still_resolve() {
+        let resolver = FakeResolver::new().answering(SEED_B, &["/ip4/10.0.0.9/tcp/4001"]);
+        let mut cache = KnownPeersCache::default();
+        let resolution =
+            resolve_seeds(&seeds(), 4001, &resolver, &mut cache, TTL, fixture_time(0)).await;
+
+        assert_eq!(resolution.addrs, vec!["/ip4/10.0.0.9/tcp/4001"]);
+        assert_eq!(resolution.failures.len(), 1);
+        assert_eq!(resolution.failures[0].0, SEED_A);
+        // A failure is not cached, so the next start tries again
+        assert_eq!(cache.fresh(SEED_A, TTL, fixture_time(0)), None);
+    }
+
+    #[test]
+    fn static_peers_win_over_seed_answers_for_the_same_transport_address() {
+        let static_peers = vec![
+            "/ip4/10.0.0.1/tcp/4001/p2p/12D3KooWStatic".to_string(),
+            "/ip4/10.0.0.3/tcp/4001".to_string(),
+        ];
+        let dns_peers = vec![
+            "/ip4/10.0.0.1/tcp/4001".to_string(),
+            "/ip4/10.0.0.2/tcp/4001".to_string(),
+            "/ip4/10.0.0.2/tcp/4001/p2p/12D3KooWDns".to_string(),
+            "/ip4/10.0.0.3/tcp/4001".to_string(),
+        ];
+        assert_eq!(
+            merge_peers(&static_peers, &dns_peers),
+            vec![
+                (static_peers[0].clone(), PeerSource::Static),
+                (static_peers[1].clone(), PeerSource::Static),
+                ("/ip4/10.0.0.2/tcp/4001".to_string(), PeerSource::Dns),
+            ]
+        );
+    }
+
+    #[test]
+    fn the_cache_survives_a_save_and_load() {
+        let dir = TempDir::new("known-peers");
+        assert_eq!(
+            KnownPeersCache::load(dir.path()),
+            KnownPeersCache::default()
+        );
+
+        let mut cache = KnownPeersCache::default();
+        cache.insert(
+            SEED_A,
+            vec!["/ip4/10.0.0.1/tcp/4001".to_string()],
+            fixture_time(5),
+        );
+        cache.save(dir.path()).unwrap();
+        assert_eq!(KnownPeersCache::load(dir.path()), cache);
+
+        std::fs::write(dir.join(KNOWN_PEERS_FILE), b"not json").unwrap();
+        assert_eq!(
+            KnownPeersCache::load(dir.path()),
+            KnownPeersCache::default()
+        );
+    }
+}
@@ -0,0 +1,530 @@
+//! Startup self-test.
+//!
+//! Before the main UI opens, `run` checks the things that otherwise fail
+//! later with a less helpful message: that the data dir is writable, that
+//! the config parses and validates, that the wallet files are intact, that
+//! the clock is plausible and that the node's ports are free. Each check
+//! gives a `CheckResult` with what it found and, unless it passed, what to
+//! do about it.
+//!
+//! The checks take their inputs as arguments, so each can be run against a
+//! made-up directory, environment or time.
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener};
+use std::path::{Path, PathBuf};
+
+use crate::wallet::config_file::{
+    env_var_name, ConfigFileError, ConfigLayers, ENV_PREFIX, NODE_CONFIG_FILE,
+};
+use crate::wallet::data_dir::{probe_writable, DataDirLimits, DiskCapacity, SystemDisk};
+use crate::wallet::integrity::{verify_data_dir, Severity};
+use crate::wallet::network::NockchainNodeConfig;
+use crate::wallet::profiles::WalletProfileManager;
+
+/// Clocks reading earlier than this, 2025-01-01 00:00 UTC, are wrong
+pub const EARLIEST_PLAUSIBLE_TIME: i64 = 1_735_689_600;
+
+/// How far in the future a data file's modification time may be before the
+/// clock is taken to have gone back
+pub const MAX_CLOCK_SKEW: Duration = Duration::hours(1);
+
+/// Outcome of one check, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum CheckStatus {
+    Pass,
+    /// Startup can go on, but something will not work as expected
+    Warn,
+    /// Startup should not go on until this is fixed
+    Fail,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CheckStatus::Pass => "Pass",
+            CheckStatus::Warn => "Warning",
+            CheckStatus::Fail => "Fail",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// What to do about a warning or failure
+    pub hint: Option<String>,
+}
+
+impl CheckResult {
+    fn pass(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Pass,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(name: &str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Warn,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn fail(name: &str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Fail,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// Worst status among `results`; `Pass` when there are none
+pub fn worst_status(results: &[CheckResult]) -> CheckStatus {
+    results
+        .iter()
+        .map(|result| result.status)
+        .max()
+        .unwrap_or(CheckStatus::Pass)
+}
+
+/// Run every check against the process environment and the system clock.
+/// Meant for startup: the port check fails once this process holds the ports.
+pub fn run() -> Vec<CheckResult> {
+    run_from(std::env::vars(), Utc::now(), true)
+}
+
+/// Run the checks with `env` in place of the process environment and `now`
+/// in place of the clock. Pass `with_ports: false` for runs while the node
+/// is up, which holds the ports itself.
+pub fn run_from(
+    env: impl IntoIterator<Item = (String, String)>,
+    now: DateTime<Utc>,
+    with_ports: bool,
+) -> Vec<CheckResult> {
+    let env: Vec<(String, String)> = env
+        .into_iter()
+        .filter(|(var, _)| var.starts_with(ENV_PREFIX))
+        .collect();
+    let loaded = ConfigLayers::load_from(env.clone());
+    let mut results = vec![check_config(&loaded)];
+
+    // Later checks still run on a broken config, against the defaults
+    let (config, config_dir) = match loaded {
+        Ok((config, layers)) => (config, layers.config_dir().to_path_buf()),
+        Err(_) => {
+            let mut config = NockchainNodeConfig::default();
+            let config_dir = env
+                .iter()
+                .find(|(var, _)| *var == env_var_name("data_dir"))
+                .map(|(_, dir)| PathBuf::from(dir))
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .unwrap_or_else(|| config.data_dir.clone());
+            config.data_dir = config_dir.clone();
+            (config, config_dir)
+        }
+    };
+
+    results.push(check_storage(
+        &config.data_dir,
+        &config.data_dir_limits,
+        &SystemDisk,
+    ));
+    let wallet_dirs = wallet_dirs(&config_dir, &config.data_dir);
+    results.push(check_wallet_files(&wallet_dirs));
+    let mut clock_dirs = vec![config_dir, config.data_dir.clone()];
+    clock_dirs.extend(wallet_dirs);
+    results.push(check_clock(now, &clock_dirs));
+    if with_ports {
+        results.push(check_ports(
+            &config.bind_address,
+            config.p2p_port,
+            config.rpc_port,
+        ));
+    }
+    results
+}
+
+/// Wallet dirs of every profile under `config_dir`, and the wallet in the
+/// node data dir that predates profiles
+fn wallet_dirs(config_dir: &Path, data_dir: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = WalletProfileManager::load(config_dir)
+        .map(|manager| {
+            manager
+                .profiles()
+                .iter()
+                .map(|profile| profile.wallet_dir())
+                .collect()
+        })
+        .unwrap_or_default();
+    let legacy = data_dir.join("wallet");
+    if !dirs.contains(&legacy) {
+        dirs.push(legacy);
+    }
+    dirs.retain(|dir| dir.exists());
+    dirs
+}
+
+/// The layered config loaded, and breaks none of the rules `validate` checks
+pub fn check_config(
+    loaded: &Result<(NockchainNodeConfig, ConfigLayers), ConfigFileError>,
+) -> CheckResult {
+    const NAME: &str = "Configuration";
+    let config = match loaded {
+        Ok((config, _)) => config,
+        Err(e) => {
+            return CheckResult::fail(
+                NAME,
+                e.to_string(),
+                format!(
+                    "Correct or remove the setting named above in {} or the environment. \
+                     The node falls back to its defaults until then.",
+                    NODE_CONFIG_FILE
+                ),
+            )
+        }
+    };
+
+    let problems = config.problems();
+    if problems.is_empty() {
+        return CheckResult::pass(NAME, "Loaded and valid");
+    }
+    let detail = problems
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ");
+    CheckResult::fail(
+        NAME,
+        detail,
+        format!(
+            "Change the settings listed in {} or their {}* environment variables",
+            NODE_CONFIG_FILE, ENV_PREFIX
+        ),
+    )
+}
+
+/// The data dir exists or can be created, takes writes, and has the free
+/// space the node needs to start
+pub fn check_storage(dir: &Path, limits: &DataDirLimits, disk: &dyn DiskCapacity) -> CheckResult {
+    const NAME: &str = "Storage";
+    let hint = format!(
+        "Check the permissions of {}, or point {} at a writable directory",
+        dir.display(),
+        env_var_name("data_dir")
+    );
+    if let Err(e) = std::fs::create_dir_all(dir) {
+        return CheckResult::fail(
+            NAME,
+            format!("Cannot create {}: {}", dir.display(), e),
+            hint,
+        );
+    }
+    if let Err(e) = probe_writable(dir) {
+        return CheckResult::fail(NAME, e.to_string(), hint);
+    }
+
+    match disk.available_space(dir) {
+        Ok(available) if available < limits.min_free_bytes => CheckResult::fail(
+            NAME,
+            format!(
+                "{} MiB free in {}, the node needs at least {} MiB",
+                available / (1024 * 1024),
+                dir.display(),
+                limits.min_free_bytes / (1024 * 1024)
+            ),
+            "Free up disk space, or move the data dir to a larger disk",
+        ),
+        Ok(available) => CheckResult::pass(
+            NAME,
+            format!(
+                "{} is writable, {} MiB free",
+                dir.display(),
+                available / (1024 * 1024)
+            ),
+        ),
+        Err(e) => CheckResult::warn(
+            NAME,
+            format!("Cannot tell how much space is free: {}", e),
+            "The node checks again when it starts; make sure the disk has room",
+        ),
+    }
+}
+
+/// Every wallet file in `wallet_dirs` matches what the app last wrote
+pub fn check_wallet_files(wallet_dirs: &[PathBuf]) -> CheckResult {
+    const NAME: &str = "Wallet files";
+    let mut problems = Vec::new();
+    let mut critical = false;
+    for dir in wallet_dirs {
+        let report = verify_data_dir(dir);
+        critical |= report.is_critical();
+        problems.extend(
+            report
+                .problems()
+                .filter(|check| check.severity >= Severity::Warning)
+                .map(|check| format!("{}: {}", dir.join(&check.file).display(), check.status)),
+        );
+    }
+
+    if problems.is_empty() {
+        return CheckResult::pass(
+            NAME,
+            format!("{} wallet(s) checked, no problems", wallet_dirs.len()),
+        );
+    }
+    let hint = if critical {
+        "The affected wallet opens read-only. Restore the files from a backup, or \
+         review and accept them under Settings → Wallet integrity."
+    } else {
+        "Review the files under Settings → Wallet integrity once the wallet is open"
+    };
+    CheckResult::warn(NAME, problems.join("; "), hint)
+}
+
+/// The clock is past `EARLIEST_PLAUSIBLE_TIME`, and no file directly in
+/// `dirs` was modified more than `MAX_CLOCK_SKEW` after `now`
+pub fn check_clock(now: DateTime<Utc>, dirs: &[PathBuf]) -> CheckResult {
+    const NAME: &str = "Clock";
+    let earliest = Utc
+        .timestamp_opt(EARLIEST_PLAUSIBLE_TIME, 0)
+        .single()
+        .expect("constant is in range");
+    if now < earliest {
+        return CheckResult::fail(
+            NAME,
+            format!("System clock reads {}", now.format("%Y-%m-%d %H:%M UTC")),
+            "Set the system date and time, or turn on automatic time sync. \
+             Transactions, receipts and peer handshakes carry timestamps.",
+        );
+    }
+
+    let newest = dirs
+        .iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok()?.metadata().ok()?.modified().ok())
+        .map(DateTime::<Utc>::from)
+        .max();
+    match newest {
+        Some(newest) if newest - now > MAX_CLOCK_SKEW => CheckResult::warn(
+            NAME,
+            format!(
+                "Data files were written at {}, after the current time {}",
+                newest.format("%Y-%m-%d %H:%M UTC"),
+                now.format("%Y-%m-%d %H:%M UTC")
+            ),
+            "The clock may have been set back. Check the system date and time.",
+        ),
+        _ => CheckResult::pass(NAME, now.format("%Y-%m-%d %H:%M UTC").to_string()),
+    }
+}
+
+/// The P2P port is free on `bind_address` and the RPC port on localhost
+pub fn check_ports(bind_address: &str, p2p_port: u16, rpc_port: u16) -> CheckResult {
+    const NAME: &str = "Ports";
+    // An unparseable address is reported by the config check
+    let bind_ip = bind_address
+        .parse::<IpAddr>()
+        .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+    let busy: Vec<String> = [
+        ("P2P", SocketAddr::new(bind_ip, p2p_port)),
+        ("RPC", SocketAddr::from((Ipv4Addr::LOCALHOST, rpc_port))),
+    ]
+    .into_iter()
+    .filter(|(_, addr)| addr.port() != 0)
+    .filter_map(|(label, addr)| {
+        TcpListener::bind(addr)
+            .err()
+            .map(|e| format!("{} port {} on {}: {}", label, addr.port(), addr.ip(), e))
+    })
+    .collect();
+
+    if busy.is_empty() {
+        return CheckResult::pass(
+            NAME,
+            format!("P2P {} and RPC {} are free", p2p_port, rpc_port),
+        );
+    }
+    CheckResult::warn(
+        NAME,
+        busy.join("; "),
+        format!(
+            "Close the other program using the port, often another copy of this app, \
+             or change p2p_port or rpc_port in {}",
+            NODE_CONFIG_FILE
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fixture_time, FakeDisk, TempDir};
+    use crate::wallet::spending::SPENDING_FILE;
+    use crate::wallet::storage::StorageManager;
+    use serde_json::json;
+    use std::fs::File;
+    use std::time::{Duration as StdDuration, SystemTime};
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    /// Environment pointing the config dir at `dir`, plus `vars`
+    fn env(dir: &TempDir, vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        let mut env = vec![(env_var_name("data_dir"), dir.path().display().to_string())];
+        env.extend(
+            vars.iter()
+                .map(|(var, value)| (var.to_string(), value.to_string())),
+        );
+        env
+    }
+
+    fn hint(result: &CheckResult) -> &str {
+        result.hint.as_deref().unwrap_or_default()
+    }
+
+    #[test]
+    fn a_bad_setting_fails_the_config_check() {
+        let dir = TempDir::new("selftest-config");
+        let unparseable = check_config(&ConfigLayers::load_from(env(
+            &dir,
+            &[("NOCKCHAIN_P2P_PORT", "high")],
+        )));
+        assert_eq!(unparseable.status, CheckStatus::Fail);
+        assert!(unparseable.detail.contains("P2P_PORT"), "{:?}", unparseable);
+        assert!(hint(&unparseable).starts_with("Correct or remove the setting"));
+
+        let conflicting = check_config(&ConfigLayers::load_from(env(
+            &dir,
+            &[
+                ("NOCKCHAIN_P2P_PORT", "4001"),
+                ("NOCKCHAIN_RPC_PORT", "4001"),
+            ],
+        )));
+        assert_eq!(conflicting.status, CheckStatus::Fail);
+        assert!(conflicting.detail.contains("4001"), "{:?}", conflicting);
+        assert!(hint(&conflicting).contains(ENV_PREFIX));
+
+        let valid = check_config(&ConfigLayers::load_from(env(&dir, &[])));
+        assert_eq!(valid.status, CheckStatus::Pass);
+        assert_eq!(valid.hint, None);
+    }
+
+    #[test]
+    fn storage_fails_when_unwritable_or_full_and_warns_when_unmeasurable() {
+        let dir = TempDir::new("selftest-storage");
+        let limits = DataDirLimits::default();
+        let roomy = FakeDisk::new(100 * GIB, 200 * GIB);
+        assert_eq!(
+            check_storage(&dir.join("data"), &limits, &roomy).status,
+            CheckStatus::Pass
+        );
+
+        // A file where the data dir should be
+        std::fs::write(dir.join("taken"), "").unwrap();
+        let blocked = check_storage(&dir.join("taken"), &limits, &roomy);
+        assert_eq!(blocked.status, CheckStatus::Fail);
+        assert!(hint(&blocked).contains(&env_var_name("data_dir")));
+
+        let full = check_storage(dir.path(), &limits, &FakeDisk::new(GIB, 200 * GIB));
+        assert_eq!(full.status, CheckStatus::Fail);
+        assert!(full.detail.contains("1024 MiB free"), "{:?}", full);
+        assert!(hint(&full).starts_with("Free up disk space"));
+
+        let unknown = check_storage(dir.path(), &limits, &FakeDisk::failing());
+        assert_eq!(unknown.status, CheckStatus::Warn);
+        assert!(unknown.detail.contains("fake disk unavailable"));
+    }
+
+    #[tokio::test]
+    async fn damaged_wallet_files_warn_with_how_to_recover() {
+        let dir = TempDir::new("selftest-wallet");
+        let storage = StorageManager::new(dir.path().to_path_buf()).unwrap();
+        storage
+            .save(SPENDING_FILE, &json!({"sends": []}))
+            .await
+            .unwrap();
+        storage
+            .save("templates.json", &json!({"rent": 100}))
+            .await
+            .unwrap();
+        let dirs = [dir.path().to_path_buf()];
+        assert_eq!(check_wallet_files(&dirs).status, CheckStatus::Pass);
+
+        std::fs::write(dir.join("templates.json"), r#"{"rent": 999}"#).unwrap();
+        let modified = check_wallet_files(&dirs);
+        assert_eq!(modified.status, CheckStatus::Warn);
+        assert!(modified.detail.contains("templates.json"));
+        assert!(hint(&modified).starts_with("Review the files"));
+
+        std::fs::write(dir.join(SPENDING_FILE), "{\"sends\": [").unwrap();
+        let critical = check_wallet_files(&dirs);
+        assert_eq!(critical.status, CheckStatus::Warn);
+        assert!(hint(&critical).contains("opens read-only"));
+    }
+
+    #[test]
+    fn an_early_or_backwards_clock_is_caught() {
+        let dir = TempDir::new("selftest-clock");
+        let dirs = [dir.path().to_path_buf()];
+        let early = check_clock(fixture_time(0), &dirs);
+        assert_eq!(early.status, CheckStatus::Fail);
+        assert!(early.detail.contains("2024-01-01"));
+        assert!(hint(&early).starts_with("Set the system date and time"));
+
+        let now = Utc::now();
+        assert_eq!(check_clock(now, &dirs).status, CheckStatus::Pass);
+        let file = File::create(dir.join("wallet.json")).unwrap();
+        file.set_modified(SystemTime::now() + StdDuration::from_secs(3 * 3600))
+            .unwrap();
+        let set_back = check_clock(now, &dirs);
+        assert_eq!(set_back.status, CheckStatus::Warn);
+        assert!(hint(&set_back).contains("set back"));
+    }
+
+    #[test]
+    fn a_port_in_use_is_named_in_the_warning() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let busy = check_ports("127.0.0.1", 0, port);
+        assert_eq!(busy.status, CheckStatus::Warn);
+        assert!(
+            busy.detail.starts_with(&format!("RPC port {}", port)),
+            "{:?}",
+            busy
+        );
+        assert!(hint(&busy).contains("p2p_port or rpc_port"));
+
+        drop(listener);
+        assert_eq!(check_ports("127.0.0.1", 0, port).status, CheckStatus::Pass);
+    }
+
+    #[test]
+    fn later_checks_still_run_on_a_broken_config() {
+        let dir = TempDir::new("selftest-run");
+        let results = run_from(
+            env(&dir, &[("NOCKCHAIN_P2P_PORT", "high")]),
+            fixture_time(0),
+            false,
+        );
+        let names: Vec<&str> = results.iter().map(|result| result.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["Configuration", "Storage", "Wallet files", "Clock"]
+        );
+        assert_eq!(results[0].status, CheckStatus::Fail);
+        assert_eq!(results[3].status, CheckStatus::Fail);
+        assert_eq!(worst_status(&results), CheckStatus::Fail);
+        assert_eq!(worst_status(&[]), CheckStatus::Pass);
+    }
+}
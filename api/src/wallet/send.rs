@@ -0,0 +1,1031 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::wallet::audit::{AuditEvent, AuditOutcome};
+use crate::wallet::cold_signing::{PackageSignatures, UnsignedTransactionPackage};
+use crate::wallet::journal::Operation;
+use crate::wallet::keys::{OutPoint, TransactionInput, TransactionOutput};
+use crate::wallet::network::{NodeHandle, NodeStatus};
+use crate::wallet::transaction::{SignedTransaction, TransactionBuilder};
+use crate::wallet::{Address, Wallet, WalletError, WalletResult};
+
+/// Stages of the send pipeline, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum SendStage {
+    SelectFunds,
+    Sign,
+    Broadcast,
+    Record,
+}
+
+impl SendStage {
+    pub const ALL: [SendStage; 4] = [
+        SendStage::SelectFunds,
+        SendStage::Sign,
+        SendStage::Broadcast,
+        SendStage::Record,
+    ];
+}
+
+impl fmt::Display for SendStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            SendStage::SelectFunds => "selecting funds",
+            SendStage::Sign => "signing",
+            SendStage::Broadcast => "broadcasting",
+            SendStage::Record => "recording",
+        };
+        f.write_str(label)
+    }
+}
+
+/// Emitted as each stage of a send completes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SendProgress {
+    FundsSelected { notes: usize, total: u64 },
+    Signed,
+    Broadcast { tx_id: String },
+    Recorded,
+}
+
+impl SendProgress {
+    /// The stage this event completes
+    pub fn stage(&self) -> SendStage {
+        match self {
+            SendProgress::FundsSelected { .. } => SendStage::SelectFunds,
+            SendProgress::Signed => SendStage::Sign,
+            SendProgress::Broadcast { .. } => SendStage::Broadcast,
+            SendProgress::Recorded => SendStage::Record,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SendRequest {
+    /// Key whose addresses fund the payment and receive the change
+    pub key_name: String,
+    pub to: Address,
+    pub amount: u64,
+    pub fee: u64,
+    /// Spend notes with at least this many confirmations for this send only,
+    /// instead of the wallet's threshold; never below 1
+    #[serde(default)]
+    pub min_confirmations: Option<u64>,
+    /// Let only the next this many blocks include the transaction; needs the
+    /// chain tip to be known
+    #[serde(default)]
+    pub valid_for_blocks: Option<u64>,
+    /// The amount typed again by the user; required above the per-transaction limit
+    #[serde(default)]
+    pub confirmed_amount: Option<u64>,
+    /// Broadcast even if fewer than the node's `min_broadcast_peers` are connected
+    #[serde(default)]
+    pub force_broadcast: bool,
+}
+
+/// A send that failed part-way, with what happened to the funds
+#[derive(Debug, Error)]
+#[error("Send failed while {stage}: {source}")]
+pub struct SendError {
+    pub stage: SendStage,
+    #[source]
+    pub source: WalletError,
+    /// Notes were locked for this send before it failed
+    pub funds_locked: bool,
+    /// Locked notes were released again and are spendable
+    pub funds_released: bool,
+    /// Id of the transaction if it reached the network
+    pub broadcast_tx_id: Option<String>,
+}
+
+impl SendError {
+    /// A failure before anything reached the network
+    pub(crate) fn before_broadcast(
+        stage: SendStage,
+        source: WalletError,
+        funds_locked: bool,
+        funds_released: bool,
+    ) -> Self {
+        Self {
+            stage,
+            source,
+            funds_locked,
+            funds_released,
+            broadcast_tx_id: None,
+        }
+    }
+
+    pub fn was_broadcast(&self) -> bool {
+        self.broadcast_tx_id.is_some()
+    }
+
+    /// Clonable summary for display
+    pub fn report(&self) -> SendFailure {
+        SendFailure {
+            stage: self.stage,
+            message: self.source.to_string(),
+            funds_released: self.funds_released,
+            broadcast_tx_id: self.broadcast_tx_id.clone(),
+            retryable: self.source.is_retryable(),
+        }
+    }
+}
+
+/// Display-friendly form of a `SendError`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SendFailure {
+    pub stage: SendStage,
+    pub message: String,
+    pub funds_released: bool,
+    pub broadcast_tx_id: Option<String>,
+    /// Failed for want of peers; the same send can be tried again once they connect
+    #[serde(default)]
+    pub retryable: bool,
+}
+
+impl SendFailure {
+    /// One sentence on whether anything left the wallet
+    pub fn outcome(&self) -> String {
+        match &self.broadcast_tx_id {
+            Some(tx_id) => format!(
+                "Transaction {} was broadcast but could not be recorded locally; do not resend it.",
+                tx_id
+            ),
+            None if self.funds_released => {
+                "Nothing was broadcast. Your funds are available again.".to_string()
+            }
+            None => "Nothing was broadcast.".to_string(),
+        }
+    }
+}
+
+/// Hands a signed transaction to the network. Unless `force` is set, a
+/// broadcaster may refuse while too few peers are connected.
+pub trait Broadcaster {
+    fn broadcast(
+        &self,
+        tx: &SignedTransaction,
+        force: bool,
+    ) -> impl Future<Output = WalletResult<()>>;
+}
+
+/// How long a broadcast waits for the node manager lock
+const BROADCAST_LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Broadcasting requires the local node to be running; the transaction goes
+/// into its mempool
+impl Broadcaster for NodeHandle {
+    async fn broadcast(&self, tx: &SignedTransaction, force: bool) -> WalletResult<()> {
+        let status = self.status();
+        if status != NodeStatus::Running {
+            return Err(WalletError::Network(format!(
+                "Node must be running to broadcast (currently {:?})",
+                status
+            )));
+        }
+
+        tracing::debug!("Broadcasting transaction {}", tx.id);
+        self.with_timeout(BROADCAST_LOCK_TIMEOUT, |node| {
+            node.broadcast_transaction(tx.clone(), force)
+        })
+        .await
+        .ok_or_else(|| WalletError::Network("Timed out waiting for the node".to_string()))?
+        .map(|_| ())
+    }
+}
+
+impl Wallet {
+    /// Rebroadcast the transactions in `dropped` that this wallet sent and that
+    /// are still pending. Returns how many were accepted again.
+    pub async fn rebroadcast_dropped<B: Broadcaster>(
+        &self,
+        dropped: &[SignedTransaction],
+        broadcaster: &B,
+    ) -> usize {
+        let mut rebroadcast = 0;
+        for tx in dropped
+            .iter()
+            .filter_map(|tx| self.transactions().pending_signed(&tx.id))
+        {
+            match broadcaster.broadcast(tx, false).await {
+                Ok(()) => rebroadcast += 1,
+                Err(e) => tracing::warn!("Failed to rebroadcast transaction {}: {}", tx.id, e),
+            }
+        }
+        rebroadcast
+    }
+
+    /// Run select → sign → broadcast → record, reporting each completed stage.
+    /// Notes are locked once selected and always released if a stage before
+    /// broadcast fails; after a broadcast they stay locked until recorded.
+    pub async fn send<B: Broadcaster>(
+        &mut self,
+        request: SendRequest,
+        broadcaster: &B,
+        mut on_progress: impl FnMut(SendProgress),
+    ) -> Result<SignedTransaction, SendError> {
+        let fail = SendError::before_broadcast;
+
+        self.ensure_writable()
+            .and_then(|_| self.check_send_auth(&request))
+            .and_then(|_| self.check_spending_limits(&request))
+            .and_then(|_| self.expiry_height(&request).map(|_| ()))
+            .map_err(|e| fail(SendStage::SelectFunds, e, false, false))?;
+
+        // Select
+        let (note_ids, total) = self
+            .select_and_lock(&request)
+            .map_err(|e| fail(SendStage::SelectFunds, e, false, false))?;
+        on_progress(SendProgress::FundsSelected {
+            notes: note_ids.len(),
+            total,
+        });
+
+        // Sign
+        let tx = match self
+            .build_send(&request, &note_ids, total)
+            .and_then(|builder| builder.build_and_sign(self.keys(), &request.key_name))
+        {
+            Ok(tx) => tx,
+            Err(e) => {
+                self.balances_mut().unlock_notes(&note_ids);
+                return Err(fail(SendStage::Sign, e, true, true));
+            }
+        };
+        on_progress(SendProgress::Signed);
+
+        self.broadcast_and_record(
+            tx,
+            request.amount,
+            request.to.to_string(),
+            &note_ids,
+            (broadcaster, request.force_broadcast),
+            on_progress,
+        )
+        .await
+    }
+
+    /// Select funds for `request` and package the transaction to be signed
+    /// offline. The selected notes stay locked until the signed package is
+    /// broadcast or released with `release_unsigned`.
+    pub fn export_unsigned_send(
+        &mut self,
+        request: &SendRequest,
+    ) -> WalletResult<UnsignedTransactionPackage> {
+        self.ensure_writable()?;
+        self.check_send_auth(request)?;
+        self.check_spending_limits(request)?;
+        self.expiry_height(request)?;
+        let (note_ids, total) = self.select_and_lock(request)?;
+        let package = self
+            .build_send(request, &note_ids, total)
+            .and_then(|builder| builder.export_unsigned());
+        if package.is_err() {
+            self.balances_mut().unlock_notes(&note_ids);
+        }
+        package
+    }
+
+    /// Give up on an exported package, making its notes spendable again
+    pub fn release_unsigned(&mut self, package: &UnsignedTransactionPackage) {
+        let note_ids: Vec<Uuid> = package
+            .inputs
+            .iter()
+            .filter_map(|input| self.balances().note_at(input.input.outpoint()))
+            .map(|note| note.id)
+            .collect();
+        self.balances_mut().unlock_notes(&note_ids);
+    }
+
+    /// Check signatures made offline for a package this wallet exported, then
+    /// broadcast and record the transaction like `send`. Nothing is broadcast
+    /// unless every input carries a valid signature from its owner. `force`
+    /// broadcasts even with too few peers connected.
+    pub async fn broadcast_signed_package<B: Broadcaster>(
+        &mut self,
+        package: &UnsignedTransactionPackage,
+        signatures: &PackageSignatures,
+        broadcaster: &B,
+        force: bool,
+        mut on_progress: impl FnMut(SendProgress),
+    ) -> Result<SignedTransaction, SendError> {
+        let fail = SendError::before_broadcast;
+
+        self.ensure_writable()
+            .map_err(|e| fail(SendStage::SelectFunds, e, false, false))?;
+        let note_ids = package
+            .inputs
+            .iter()
+            .map(|input| {
+                self.balances()
+                    .note_at(input.input.outpoint())
+                    .filter(|note| note.locked && !note.spent)
+                    .map(|note| note.id)
+                    .ok_or_else(|| {
+                        WalletError::Transaction(format!(
+                            "Input {} is not held for an exported package",
+                            input.input.outpoint()
+                        ))
+                    })
+            })
+            .collect::<WalletResult<Vec<_>>>()
+            .map_err(|e| fail(SendStage::SelectFunds, e, false, false))?;
+        on_progress(SendProgress::FundsSelected {
+            notes: note_ids.len(),
+            total: package.total_input(),
+        });
+
+        let tx = SignedTransaction::from_package_and_signatures(package, signatures)
+            .and_then(|tx| tx.check_chain_id(&self.chain_id()).map(|_| tx))
+            .map_err(|e| fail(SendStage::Sign, e, true, false))?;
+        on_progress(SendProgress::Signed);
+
+        // What leaves the wallet; for a move between own addresses, everything sent
+        let paid: Vec<&TransactionOutput> = tx
+            .outputs
+            .iter()
+            .filter(|output| {
+                !Address::from_string(&output.recipient_address)
+                    .is_ok_and(|address| self.keys().is_mine(&address))
+            })
+            .collect();
+        let paid = if paid.is_empty() {
+            tx.outputs.iter().collect()
+        } else {
+            paid
+        };
+        let amount = paid.iter().map(|output| output.amount).sum();
+        let to = paid
+            .first()
+            .map(|output| output.recipient_address.clone())
+            .unwrap_or_default();
+
+        self.broadcast_and_record(tx, amount, to, &note_ids, (broadcaster, force), on_progress)
+            .await
+    }
+
+    /// Broadcast → record stages shared by `send`, `broadcast_signed_package`
+    /// and `accelerate_incoming`.
+    /// The notes are unlocked again if the broadcast fails. Both stages run
+    /// under a journal entry, so a send interrupted between them is reconciled
+    /// by `recover_operations` when the wallet is next opened.
+    pub(crate) async fn broadcast_and_record<B: Broadcaster>(
+        &mut self,
+        tx: SignedTransaction,
+        amount: u64,
+        to: String,
+        note_ids: &[Uuid],
+        (broadcaster, force): (&B, bool),
+        mut on_progress: impl FnMut(SendProgress),
+    ) -> Result<SignedTransaction, SendError> {
+        let fail = SendError::before_broadcast;
+
+        let journal_id = match self
+            .journal_begin(Operation::Send {
+                amount,
+                to: to.clone(),
+                tx: tx.clone(),
+                stage: SendStage::Sign,
+            })
+            .await
+        {
+            Ok(id) => id,
+            Err(e) => {
+                self.balances_mut().unlock_notes(note_ids);
+                return Err(fail(SendStage::Broadcast, e, true, true));
+            }
+        };
+
+        // Broadcast
+        let broadcast = broadcaster.broadcast(&tx, force).await;
+        self.record_audit(
+            AuditEvent::SendBroadcast {
+                tx_id: tx.id.clone(),
+                amount,
+                to,
+            },
+            AuditOutcome::of(&broadcast),
+        );
+        if let Err(e) = broadcast {
+            self.balances_mut().unlock_notes(note_ids);
+            self.journal_finish(journal_id).await;
+            return Err(fail(SendStage::Broadcast, e, true, true));
+        }
+        self.journal_checkpoint(journal_id, |operation| {
+            if let Operation::Send { stage, .. } = operation {
+                *stage = SendStage::Broadcast;
+            }
+        })
+        .await;
+        let broadcast_at = Utc::now();
+        self.spending.record(&tx.id, amount + tx.fee, broadcast_at);
+        on_progress(SendProgress::Broadcast {
+            tx_id: tx.id.clone(),
+        });
+
+        // Record. The change address is used now, so the next send takes a new one.
+        self.record_transaction(tx.clone(), true);
+        for output in &tx.outputs {
+            if let Ok(address) = Address::from_string(&output.recipient_address) {
+                self.keys.mark_address_used(&address);
+            }
+        }
+        self.transactions_mut().mark_broadcast(&tx.id, broadcast_at);
+        for id in note_ids {
+            if let Err(e) = self.balances_mut().spend_note(*id) {
+                self.journal_finish(journal_id).await;
+                return Err(SendError {
+                    broadcast_tx_id: Some(tx.id.clone()),
+                    ..fail(SendStage::Record, e, true, false)
+                });
+            }
+        }
+        self.balances_mut().record_fee(&tx.id, tx.fee);
+        self.journal_finish(journal_id).await;
+        on_progress(SendProgress::Recorded);
+
+        Ok(tx)
+    }
+
+    fn select_and_lock(&mut self, request: &SendRequest) -> WalletResult<(Vec<Uuid>, u64)> {
+        let addresses = self.spendable_addresses(&request.key_name)?;
+        let required = request
+            .amount
+            .checked_add(request.fee)
+            .ok_or_else(|| WalletError::Transaction("Amount plus fee overflows".to_string()))?;
+
+        let note_ids =
+            self.balances()
+                .select_notes(&addresses, required, request.min_confirmations)?;
+        let total = note_ids
+            .iter()
+            .filter_map(|id| self.balances().get_note(id))
+            .map(|note| note.amount)
+            .sum();
+        self.balances_mut().lock_notes(&note_ids)?;
+        Ok((note_ids, total))
+    }
+
+    /// Builder paying `request` from the selected notes, with change to the
+    /// key's next unused change address
+    fn build_send(
+        &mut self,
+        request: &SendRequest,
+        note_ids: &[Uuid],
+        total: u64,
+    ) -> WalletResult<TransactionBuilder> {
+        let mut builder = TransactionBuilder::new(self.chain_id());
+        for note in note_ids
+            .iter()
+            .filter_map(|id| self.balances().get_note(id))
+        {
+            builder.add_owned_input(
+                TransactionInput {
+                    previous_output: OutPoint::new(note.transaction_id.clone(), note.output_index),
+                    amount: note.amount,
+                },
+                note.address.clone(),
+            );
+        }
+        builder.add_output(TransactionOutput {
+            amount: request.amount,
+            recipient_address: request.to.to_string(),
+        });
+
+        let change = total - request.amount - request.fee;
+        if change > 0 {
+            let change_address = self.keys.change_address(&request.key_name)?;
+            builder.add_output(TransactionOutput {
+                amount: change,
+                recipient_address: change_address.to_string(),
+            });
+        }
+        builder.set_fee(request.fee);
+        if let Some(height) = self.expiry_height(request)? {
+            builder.set_expiry_height(height);
+        }
+
+        Ok(builder)
+    }
+
+    /// Last height `request` may be included at, from its validity and the
+    /// known tip. Checked before any funds are selected, so an unsynced
+    /// wallet is told to sync rather than that it has no spendable funds.
+    fn expiry_height(&self, request: &SendRequest) -> WalletResult<Option<u64>> {
+        let Some(blocks) = request.valid_for_blocks else {
+            return Ok(None);
+        };
+        if blocks == 0 {
+            return Err(WalletError::Transaction(
+                "A transaction must be valid for at least one block".to_string(),
+            ));
+        }
+        let tip = self.balances().tip_height().ok_or_else(|| {
+            WalletError::Transaction(
+                "Chain height unknown; sync before setting an expiry".to_string(),
+            )
+        })?;
+        Ok(Some(tip.saturating_add(blocks)))
+    }
+
+    /// The key's own address plus every receive and change address derived for it
+    fn spendable_addresses(&self, key_name: &str) -> WalletResult<Vec<Address>> {
+        let key = self
+            .keys()
+            .get_key(key_name)
+            .ok_or_else(|| WalletError::KeyNotFound(key_name.to_string()))?;
+
+        let mut addresses = vec![key.address().clone()];
+        let chains = [
+            self.keys().address_chain(key_name),
+            self.keys().change_chain(key_name),
+        ];
+        for chain in chains.into_iter().flatten() {
+            addresses.extend(
+                chain
+                    .addresses()
+                    .iter()
+                    .filter(|address| *address != key.address())
+                    .cloned(),
+            );
+        }
+        Ok(addresses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_address, fake_note, fake_signed_transaction};
+    use crate::wallet::keys::AddressKind;
+    use crate::wallet::spending::SpendingLimits;
+    use std::cell::RefCell;
+
+    /// Broadcaster that records what it was handed, refusing everything if
+    /// `refuse` is set and unforced sends if `short_of_peers` is
+    #[derive(Default)]
+    struct FakeBroadcaster {
+        refuse: bool,
+        short_of_peers: bool,
+        sent: RefCell<Vec<String>>,
+    }
+
+    impl Broadcaster for FakeBroadcaster {
+        async fn broadcast(&self, tx: &SignedTransaction, force: bool) -> WalletResult<()> {
+            if self.refuse {
+                return Err(WalletError::Network("no route to peers".to_string()));
+            }
+            if self.short_of_peers && !force {
+                return Err(WalletError::InsufficientPeers { have: 1, need: 2 });
+            }
+            self.sent.borrow_mut().push(tx.id.clone());
+            Ok(())
+        }
+    }
+
+    /// Wallet with key "main" holding two confirmed notes of 1,000
+    fn funded_wallet() -> Wallet {
+        let mut wallet = Wallet::new();
+        let address = wallet.generate_key("main").unwrap();
+        for txid in ["aa", "bb"] {
+            wallet
+                .balances_mut()
+                .add_note(
+                    fake_note()
+                        .address(address.clone())
+                        .outpoint(txid, 0)
+                        .confirmed_at(1)
+                        .build(),
+                )
+                .unwrap();
+        }
+        wallet.balances_mut().set_tip_height(10);
+        wallet
+    }
+
+    fn request(amount: u64) -> SendRequest {
+        SendRequest {
+            key_name: "main".to_string(),
+            to: fake_address(),
+            amount,
+            fee: 10,
+            min_confirmations: None,
+            valid_for_blocks: None,
+            confirmed_amount: None,
+            force_broadcast: false,
+        }
+    }
+
+    fn locked_notes(wallet: &Wallet) -> usize {
+        wallet
+            .balances()
+            .notes()
+            .iter()
+            .filter(|note| note.locked)
+            .count()
+    }
+
+    #[tokio::test]
+    async fn lowering_the_confirmation_override_lets_a_fresh_note_be_spent() {
+        let mut wallet = Wallet::new();
+        let address = wallet.generate_key("main").unwrap();
+        for txid in ["aa", "bb"] {
+            wallet
+                .balances_mut()
+                .add_note(
+                    fake_note()
+                        .address(address.clone())
+                        .outpoint(txid, 0)
+                        .amount(1_000)
+                        .confirmed_at(9)
+                        .build(),
+                )
+                .unwrap();
+        }
+        // Two confirmations each, one short of the default
+        wallet.balances_mut().set_tip_height(10);
+        assert_eq!(wallet.spendable_summary().awaiting_confirmations, 2_000);
+        let broadcaster = FakeBroadcaster::default();
+
+        let refused = wallet
+            .send(request(500), &broadcaster, |_| {})
+            .await
+            .unwrap_err();
+        assert_eq!(refused.stage, SendStage::SelectFunds);
+        assert!(matches!(
+            refused.source,
+            WalletError::InsufficientFunds { .. }
+        ));
+
+        for override_to in [2, 0] {
+            let tx = wallet
+                .send(
+                    SendRequest {
+                        min_confirmations: Some(override_to),
+                        ..request(500)
+                    },
+                    &broadcaster,
+                    |_| {},
+                )
+                .await
+                .unwrap();
+            assert_eq!(broadcaster.sent.borrow().last(), Some(&tx.id));
+        }
+
+        // The override applied to those sends only
+        assert_eq!(
+            wallet.balances().min_confirmations(),
+            crate::wallet::balance::DEFAULT_MIN_CONFIRMATIONS
+        );
+    }
+
+    #[tokio::test]
+    async fn a_send_short_of_peers_is_retryable_and_can_be_forced() {
+        let mut wallet = funded_wallet();
+        let broadcaster = FakeBroadcaster {
+            short_of_peers: true,
+            ..Default::default()
+        };
+
+        let e = wallet
+            .send(request(500), &broadcaster, |_| {})
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            e.source,
+            WalletError::InsufficientPeers { have: 1, need: 2 }
+        ));
+        assert!(!e.was_broadcast());
+        let report = e.report();
+        assert!(report.retryable);
+        assert!(report.funds_released);
+        assert_eq!(locked_notes(&wallet), 0);
+
+        let tx = wallet
+            .send(
+                SendRequest {
+                    force_broadcast: true,
+                    ..request(500)
+                },
+                &broadcaster,
+                |_| {},
+            )
+            .await
+            .unwrap();
+        assert_eq!(*broadcaster.sent.borrow(), vec![tx.id]);
+    }
+
+    #[tokio::test]
+    async fn change_goes_to_a_new_internal_address_and_repeat_payments_are_counted() {
+        let mut wallet = funded_wallet();
+        let own = wallet.keys().get_key("main").unwrap().address().clone();
+        let to = fake_address();
+        let broadcaster = FakeBroadcaster::default();
+
+        let mut changes = Vec::new();
+        for paid in [1, 2] {
+            let tx = wallet
+                .send(
+                    SendRequest {
+                        to: to.clone(),
+                        ..request(300)
+                    },
+                    &broadcaster,
+                    |_| {},
+                )
+                .await
+                .unwrap();
+            let change = tx
+                .outputs
+                .iter()
+                .find(|output| output.recipient_address != to.to_string())
+                .unwrap();
+            assert_eq!(change.amount, 690);
+            changes.push(Address::from_string(&change.recipient_address).unwrap());
+            assert_eq!(wallet.transactions().payment_counts().get(&to), Some(&paid));
+        }
+
+        let chain = wallet.keys().change_chain("main").unwrap();
+        assert_eq!(chain.index_of(&changes[0]), Some(0));
+        assert_eq!(chain.index_of(&changes[1]), Some(1));
+        for change in &changes {
+            assert_ne!(*change, own);
+            assert_eq!(
+                wallet.keys().address_kind(change),
+                Some(AddressKind::Change)
+            );
+            assert!(wallet.keys().address_kind(change).unwrap().is_internal());
+        }
+        // The change isn't credited before a sync, but the used change
+        // addresses are listed in their own group
+        let breakdown = wallet.address_breakdown();
+        let change_rows: Vec<_> = breakdown
+            .iter()
+            .filter(|row| row.kind == AddressKind::Change)
+            .collect();
+        assert_eq!(change_rows.len(), 2);
+        for row in change_rows {
+            assert!(changes.contains(&row.address));
+            assert_eq!(row.key_name.as_deref(), Some("main"));
+            assert_eq!(row.balance.confirmed + row.balance.unconfirmed, 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn only_broadcast_sends_count_against_the_daily_limit() {
+        let mut wallet = funded_wallet();
+        wallet
+            .set_spending_limits(SpendingLimits {
+                per_transaction: None,
+                daily: Some(1_000),
+            })
+            .unwrap();
+
+        let refusing = FakeBroadcaster {
+            refuse: true,
+            ..Default::default()
+        };
+        assert!(wallet.send(request(500), &refusing, |_| {}).await.is_err());
+        assert_eq!(wallet.daily_allowance().unwrap().remaining, 1_000);
+
+        let broadcaster = FakeBroadcaster::default();
+        let tx = wallet
+            .send(request(500), &broadcaster, |_| {})
+            .await
+            .unwrap();
+        assert!(wallet.spending.has_send(&tx.id));
+        assert_eq!(wallet.daily_allowance().unwrap().remaining, 490);
+
+        let e = wallet
+            .send(request(500), &broadcaster, |_| {})
+            .await
+            .unwrap_err();
+        assert_eq!(e.stage, SendStage::SelectFunds);
+        assert!(matches!(
+            e.source,
+            WalletError::DailyLimitExceeded { remaining: 490, .. }
+        ));
+        assert!(!e.funds_locked);
+    }
+
+    #[tokio::test]
+    async fn a_large_send_goes_through_once_its_amount_is_confirmed() {
+        let mut wallet = funded_wallet();
+        wallet
+            .set_spending_limits(SpendingLimits {
+                per_transaction: Some(100),
+                daily: None,
+            })
+            .unwrap();
+        wallet.unlock(None).unwrap();
+        let broadcaster = FakeBroadcaster::default();
+
+        let e = wallet
+            .send(request(500), &broadcaster, |_| {})
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            e.source,
+            WalletError::ConfirmationRequired { threshold: 100 }
+        ));
+        assert_eq!(locked_notes(&wallet), 0);
+
+        let confirmed = SendRequest {
+            confirmed_amount: Some(500),
+            ..request(500)
+        };
+        assert!(wallet.send(confirmed, &broadcaster, |_| {}).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn a_read_only_handle_refuses_to_send_before_locking_anything() {
+        let dir = crate::testing::TempDir::new("send-read-only");
+        let _writer = Wallet::open(dir.path()).unwrap();
+        let mut reader = Wallet::open(dir.path()).unwrap();
+        let funded = funded_wallet();
+        reader.keys = funded.keys().clone();
+        for note in funded.balances().notes() {
+            reader.balances_mut().add_note(note.clone()).unwrap();
+        }
+        let broadcaster = FakeBroadcaster::default();
+
+        let e = reader
+            .send(request(500), &broadcaster, |_| {})
+            .await
+            .unwrap_err();
+        assert_eq!(e.stage, SendStage::SelectFunds);
+        assert!(matches!(e.source, WalletError::ReadOnly(_)));
+        assert!(!e.funds_locked);
+        assert_eq!(locked_notes(&reader), 0);
+        assert!(broadcaster.sent.borrow().is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_send_reports_every_stage_in_order() {
+        let mut wallet = funded_wallet();
+        let broadcaster = FakeBroadcaster::default();
+        let mut progress = Vec::new();
+
+        let tx = wallet
+            .send(request(1_500), &broadcaster, |event| progress.push(event))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            progress,
+            vec![
+                SendProgress::FundsSelected {
+                    notes: 2,
+                    total: 2_000
+                },
+                SendProgress::Signed,
+                SendProgress::Broadcast {
+                    tx_id: tx.id.clone()
+                },
+                SendProgress::Recorded,
+            ]
+        );
+        let stages: Vec<SendStage> = progress.iter().map(SendProgress::stage).collect();
+        assert_eq!(stages, SendStage::ALL);
+        assert_eq!(*broadcaster.sent.borrow(), vec![tx.id.clone()]);
+        assert_eq!(locked_notes(&wallet), 0);
+        assert!(wallet.balances().notes().iter().all(|note| note.spent));
+    }
+
+    #[tokio::test]
+    async fn failing_to_select_funds_locks_nothing() {
+        let mut wallet = funded_wallet();
+        let mut progress = Vec::new();
+
+        let error = wallet
+            .send(request(5_000), &FakeBroadcaster::default(), |event| {
+                progress.push(event)
+            })
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.stage, SendStage::SelectFunds);
+        assert!(!error.funds_locked && !error.was_broadcast());
+        assert!(progress.is_empty());
+        assert_eq!(locked_notes(&wallet), 0);
+    }
+
+    #[tokio::test]
+    async fn failing_to_sign_releases_the_selected_notes() {
+        let mut wallet = funded_wallet();
+        let mut progress = Vec::new();
+        // An expiry of zero blocks is refused while the transaction is built
+        let send = SendRequest {
+            valid_for_blocks: Some(0),
+            ..request(500)
+        };
+
+        let error = wallet
+            .send(send, &FakeBroadcaster::default(), |event| {
+                progress.push(event)
+            })
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.stage, SendStage::Sign);
+        assert!(error.funds_locked && error.funds_released);
+        assert_eq!(progress.len(), 1);
+        assert_eq!(locked_notes(&wallet), 0);
+        assert_eq!(
+            error.report().outcome(),
+            "Nothing was broadcast. Your funds are available again."
+        );
+    }
+
+    #[tokio::test]
+    async fn a_refused_broadcast_releases_the_notes_and_records_nothing() {
+        let mut wallet = funded_wallet();
+        let broadcaster = FakeBroadcaster {
+            refuse: true,
+            ..FakeBroadcaster::default()
+        };
+        let mut progress = Vec::new();
+
+        let error = wallet
+            .send(request(500), &broadcaster, |event| progress.push(event))
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.stage, SendStage::Broadcast);
+        assert!(error.funds_locked && error.funds_released && !error.was_broadcast());
+        assert_eq!(
+            progress.last().map(SendProgress::stage),
+            Some(SendStage::Sign)
+        );
+        assert_eq!(locked_notes(&wallet), 0);
+        assert!(wallet.transactions().get_pending_transactions().is_empty());
+    }
+
+    #[tokio::test]
+    async fn only_the_wallets_own_dropped_sends_are_rebroadcast() {
+        let mut wallet = funded_wallet();
+        let ours = fake_signed_transaction().build();
+        let received = fake_signed_transaction().build();
+        let strangers = fake_signed_transaction().build();
+        wallet
+            .transactions_mut()
+            .add_pending_transaction(ours.clone(), true);
+        wallet
+            .transactions_mut()
+            .add_pending_transaction(received.clone(), false);
+
+        let broadcaster = FakeBroadcaster::default();
+        let dropped = [ours.clone(), received, strangers];
+        assert_eq!(wallet.rebroadcast_dropped(&dropped, &broadcaster).await, 1);
+        assert_eq!(*broadcaster.sent.borrow(), vec![ours.id.clone()]);
+
+        // Confirmed sends are no longer pending, so aren't sent again
+        wallet
+            .transactions_mut()
+            .confirm_transaction(&ours.id, 5)
+            .unwrap();
+        assert_eq!(wallet.rebroadcast_dropped(&[ours], &broadcaster).await, 0);
+
+        let refusing = FakeBroadcaster {
+            refuse: true,
+            ..FakeBroadcaster::default()
+        };
+        let another = fake_signed_transaction().build();
+        wallet
+            .transactions_mut()
+            .add_pending_transaction(another.clone(), true);
+        assert_eq!(wallet.rebroadcast_dropped(&[another], &refusing).await, 0);
+    }
+
+    #[tokio::test]
+    async fn a_failure_after_broadcast_says_not_to_resend() {
+        let mut wallet = funded_wallet();
+        let broadcaster = FakeBroadcaster::default();
+        let tx = fake_signed_transaction().build();
+        // A note the wallet doesn't hold can't be marked spent
+        let missing = Uuid::new_v4();
+
+        let error = wallet
+            .broadcast_and_record(
+                tx.clone(),
+                1_000,
+                fake_address().to_string(),
+                &[missing],
+                (&broadcaster, false),
+                |_| {},
+            )
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.stage, SendStage::Record);
+        assert_eq!(error.broadcast_tx_id.as_deref(), Some(tx.id.as_str()));
+        assert!(!error.funds_released);
+        assert!(error.report().outcome().contains("do not resend"));
+        assert_eq!(*broadcaster.sent.borrow(), vec![tx.id]);
+    }
+}
@@ -0,0 +1,231 @@
+//! Autosaved Send page form, so a send being composed survives leaving the
+//! page or a crash. There is one slot: each save replaces the last draft.
+//!
+//! A draft holds only what the user typed into the form. Keys, passwords and
+//! signatures never enter it, and neither does the amount retyped to confirm
+//! a large send, which is asked for again.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::wallet::storage::StorageManager;
+use crate::wallet::templates::FeePriority;
+use crate::wallet::{Wallet, WalletResult};
+
+/// Send draft, saved through `StorageManager` in the wallet data dir
+pub const SEND_DRAFT_FILE: &str = "send_draft.json";
+
+/// Send page form as last typed. The default is an empty form.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SendDraft {
+    /// Recipient address in `nock_` form
+    #[serde(default)]
+    pub recipient: Option<String>,
+    /// Amount in base units
+    #[serde(default)]
+    pub amount: Option<u64>,
+    /// Confirmations override from the advanced options, when turned on
+    #[serde(default)]
+    pub min_confirmations: Option<u64>,
+    /// Blocks the transaction stays valid for, from the advanced options
+    #[serde(default)]
+    pub valid_for_blocks: Option<u64>,
+    /// Payment template picked on the page, whose fee priority sets the fee
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Template the send is also being saved as
+    #[serde(default)]
+    pub save_as: Option<TemplateDraft>,
+    pub saved_at: DateTime<Utc>,
+}
+
+/// "Save as template" fields of a draft
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TemplateDraft {
+    pub name: String,
+    pub memo: String,
+    pub fee_priority: FeePriority,
+    /// Reminder interval in days as typed, which may not parse yet
+    pub reminder_days: String,
+}
+
+impl SendDraft {
+    /// Whether nothing was typed, so there is nothing worth restoring
+    pub fn is_empty(&self) -> bool {
+        self.recipient.is_none()
+            && self.amount.is_none()
+            && self.min_confirmations.is_none()
+            && self.valid_for_blocks.is_none()
+            && self.template.is_none()
+            && self.save_as.is_none()
+    }
+}
+
+impl Wallet {
+    /// Draft the Send page left behind, if any
+    pub async fn load_send_draft(&self) -> WalletResult<Option<SendDraft>> {
+        let Some(data_dir) = self.data_dir.clone() else {
+            return Ok(None);
+        };
+        let storage = StorageManager::new(data_dir)?;
+        if !storage.exists(SEND_DRAFT_FILE) {
+            return Ok(None);
+        }
+        storage.load(SEND_DRAFT_FILE).await.map(Some)
+    }
+
+    /// Replace the saved draft; an empty draft clears it. Does nothing in
+    /// read-only mode.
+    pub async fn save_send_draft(&self, draft: &SendDraft) -> WalletResult<()> {
+        if draft.is_empty() {
+            return self.clear_send_draft().await;
+        }
+        let Some(data_dir) = self.data_dir.clone().filter(|_| !self.read_only) else {
+            return Ok(());
+        };
+        let storage = StorageManager::new(data_dir)?;
+        storage.save(SEND_DRAFT_FILE, draft).await
+    }
+
+    /// Delete the saved draft, after a send or when the user discards it.
+    /// Does nothing in read-only mode.
+    pub async fn clear_send_draft(&self) -> WalletResult<()> {
+        let Some(data_dir) = self.data_dir.clone().filter(|_| !self.read_only) else {
+            return Ok(());
+        };
+        let storage = StorageManager::new(data_dir)?;
+        if !storage.exists(SEND_DRAFT_FILE) {
+            return Ok(());
+        }
+        storage.delete(SEND_DRAFT_FILE).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_address, fixture_time, TempDir};
+    use crate::wallet::secret_key::encode_secret_key;
+
+    fn composed() -> SendDraft {
+        SendDraft {
+            recipient: Some(fake_address().to_nockchain_string()),
+            amount: Some(2_500_000),
+            min_confirmations: Some(1),
+            valid_for_blocks: Some(100),
+            template: Some("Rent".to_string()),
+            save_as: Some(TemplateDraft {
+                name: "Landlord".to_string(),
+                memo: "Flat 3".to_string(),
+                fee_priority: FeePriority::default(),
+                reminder_days: "3".to_string(),
+            }),
+            saved_at: fixture_time(300),
+        }
+    }
+
+    fn wallet_in(dir: &TempDir) -> Wallet {
+        let mut wallet = Wallet::new();
+        wallet.data_dir = Some(dir.path().to_path_buf());
+        wallet
+    }
+
+    #[tokio::test]
+    async fn a_draft_survives_reopening_the_wallet() {
+        let dir = TempDir::new("send-draft-reload");
+        let draft = composed();
+        wallet_in(&dir).save_send_draft(&draft).await.unwrap();
+
+        // As after a crash: a fresh wallet over the same data dir
+        let reopened = wallet_in(&dir);
+        assert_eq!(reopened.load_send_draft().await.unwrap(), Some(draft));
+    }
+
+    #[tokio::test]
+    async fn each_save_replaces_the_last() {
+        let dir = TempDir::new("send-draft-replace");
+        let wallet = wallet_in(&dir);
+        wallet.save_send_draft(&composed()).await.unwrap();
+        let later = SendDraft {
+            amount: Some(7),
+            ..composed()
+        };
+        wallet.save_send_draft(&later).await.unwrap();
+        assert_eq!(wallet.load_send_draft().await.unwrap(), Some(later));
+    }
+
+    #[tokio::test]
+    async fn sending_or_discarding_clears_the_draft() {
+        let dir = TempDir::new("send-draft-clear");
+        let wallet = wallet_in(&dir);
+        wallet.save_send_draft(&composed()).await.unwrap();
+        wallet.clear_send_draft().await.unwrap();
+        assert_eq!(wallet.load_send_draft().await.unwrap(), None);
+        assert!(!dir.join(SEND_DRAFT_FILE).exists());
+        // Clearing again is fine
+        wallet.clear_send_draft().await.unwrap();
+
+        // Emptying the form does the same
+        wallet.save_send_draft(&composed()).await.unwrap();
+        let emptied = SendDraft {
+            saved_at: fixture_time(400),
+            ..SendDraft::default()
+        };
+        assert!(emptied.is_empty());
+        wallet.save_send_draft(&emptied).await.unwrap();
+        assert_eq!(wallet.load_send_draft().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn read_only_and_unsaved_wallets_keep_no_draft() {
+        let dir = TempDir::new("send-draft-read-only");
+        let mut wallet = wallet_in(&dir);
+        let draft = composed();
+        wallet.save_send_draft(&draft).await.unwrap();
+        wallet.read_only = true;
+        wallet
+            .save_send_draft(&SendDraft {
+                amount: Some(1),
+                ..composed()
+            })
+            .await
+            .unwrap();
+        wallet.clear_send_draft().await.unwrap();
+        // Neither replaced nor deleted
+        assert_eq!(wallet.load_send_draft().await.unwrap(), Some(draft));
+
+        let in_memory = Wallet::new();
+        in_memory.save_send_draft(&composed()).await.unwrap();
+        assert_eq!(in_memory.load_send_draft().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn no_secret_reaches_the_draft_file() {
+        let dir = TempDir::new("send-draft-secrets");
+        let mut wallet = wallet_in(&dir);
+        let secret = [0x42; 32];
+        wallet
+            .import_key_flexible("Hot", &encode_secret_key(&secret))
+            .unwrap();
+        wallet.save_send_draft(&composed()).await.unwrap();
+
+        let saved = std::fs::read_to_string(dir.join(SEND_DRAFT_FILE)).unwrap();
+        assert!(!saved.contains(&encode_secret_key(&secret)));
+        assert!(!saved.contains(&hex::encode(secret)));
+        let fields: serde_json::Value = serde_json::from_str(&saved).unwrap();
+        let mut names: Vec<_> = fields.as_object().unwrap().keys().cloned().collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "amount",
+                "min_confirmations",
+                "recipient",
+                "save_as",
+                "saved_at",
+                "template",
+                "valid_for_blocks"
+            ]
+        );
+    }
+}
@@ -0,0 +1,520 @@
+//! Unlocked sessions and re-authentication for sensitive operations.
+//!
+//! Unlocking with the wallet password derives a session key with
+//! PBKDF2-HMAC-SHA256 and holds it in memory until the wallet locks, when the
+//! key is overwritten with zeros. The wallet locks itself once it has been
+//! idle for `SecurityConfig::auto_lock_minutes`. Exporting a key and sending
+//! over the per-transaction limit also need the password entered within the
+//! last `SecurityConfig::pin_timeout_minutes`, even while unlocked. A wallet
+//! without a password unlocks without one and never asks again.
+//!
+//! Idle and re-authentication times run on the monotonic clock, so setting
+//! the system clock neither locks the wallet nor keeps it open. That clock
+//! stops while the machine sleeps; a sleep reported through
+//! `Wallet::note_clock_jump` is added on top.
+
+use chrono::TimeDelta;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::wallet::audit::{AuditEvent, AuditOutcome};
+use crate::wallet::clock::ClockJump;
+use crate::wallet::profiles::{pbkdf2_sha256, PASSWORD_ITERATIONS};
+use crate::wallet::send::SendRequest;
+use crate::wallet::{SecurityConfig, Wallet, WalletError, WalletResult};
+
+/// Idle time before the wallet locks itself, unless the config says otherwise
+pub const DEFAULT_AUTO_LOCK_MINUTES: u64 = 15;
+
+/// How recent the last password entry must be for a sensitive operation,
+/// unless the config says otherwise
+pub const DEFAULT_REAUTH_MINUTES: u64 = 5;
+
+/// Key derived from the password at unlock. Zeroed when dropped, so locking
+/// the wallet leaves no copy behind in the session.
+pub struct SessionKey([u8; 32]);
+
+impl SessionKey {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl Drop for SessionKey {
+    fn drop(&mut self) {
+        self.0.fill(0);
+        // Keep the writes from being dropped as dead stores
+        std::hint::black_box(&mut self.0);
+    }
+}
+
+impl fmt::Debug for SessionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SessionKey(..)")
+    }
+}
+
+/// What the password is checked against: the salt the session key is derived
+/// with and a hash of that key. Never the key itself.
+#[derive(Debug, Clone)]
+struct Credential {
+    salt: [u8; 16],
+    iterations: u32,
+    check: [u8; 32],
+}
+
+impl Credential {
+    fn new(password: &str) -> (Self, SessionKey) {
+        let salt: [u8; 16] = rand::random();
+        let key = SessionKey(pbkdf2_sha256(
+            password.as_bytes(),
+            &salt,
+            PASSWORD_ITERATIONS,
+        ));
+        let credential = Self {
+            salt,
+            iterations: PASSWORD_ITERATIONS,
+            check: Sha256::digest(key.as_bytes()).into(),
+        };
+        (credential, key)
+    }
+
+    /// The session key for `password`, if it is the right one
+    fn derive(&self, password: &str) -> Option<SessionKey> {
+        let key = SessionKey(pbkdf2_sha256(
+            password.as_bytes(),
+            &self.salt,
+            self.iterations,
+        ));
+        let check: [u8; 32] = Sha256::digest(key.as_bytes()).into();
+        // Compare every byte so the time taken doesn't depend on where they differ
+        let diff = self
+            .check
+            .iter()
+            .zip(check)
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b));
+        (diff == 0).then_some(key)
+    }
+}
+
+/// Lock state, the held session key and the times the auto-lock and
+/// re-authentication checks go by
+#[derive(Debug)]
+pub struct Session {
+    /// Set by the first unlock with a password
+    credential: Option<Credential>,
+    unlocked: bool,
+    key: Option<SessionKey>,
+    last_auth: Option<Instant>,
+    last_activity: Instant,
+    /// Time asleep since `last_activity` and `last_auth`, which `Instant`
+    /// doesn't count
+    slept_since_activity: Duration,
+    slept_since_auth: Duration,
+    /// `None` never locks on idle
+    auto_lock: Option<TimeDelta>,
+    reauth_window: TimeDelta,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            credential: None,
+            unlocked: false,
+            key: None,
+            last_auth: None,
+            last_activity: Instant::now(),
+            slept_since_activity: Duration::ZERO,
+            slept_since_auth: Duration::ZERO,
+            auto_lock: Some(TimeDelta::minutes(DEFAULT_AUTO_LOCK_MINUTES as i64)),
+            reauth_window: TimeDelta::minutes(DEFAULT_REAUTH_MINUTES as i64),
+        }
+    }
+}
+
+impl Session {
+    pub fn is_unlocked(&self) -> bool {
+        self.unlocked
+    }
+
+    /// Whether unlocking and re-authenticating take a password
+    pub fn has_password(&self) -> bool {
+        self.credential.is_some()
+    }
+
+    pub fn key(&self) -> Option<&SessionKey> {
+        self.key.as_ref()
+    }
+
+    pub fn last_auth(&self) -> Option<Instant> {
+        self.last_auth
+    }
+
+    pub fn reauth_window(&self) -> TimeDelta {
+        self.reauth_window
+    }
+
+    /// Idle time before locking, and how recent a password entry must be for
+    /// sensitive operations. An auto-lock of 0 minutes turns it off.
+    pub fn set_timeouts(&mut self, auto_lock_minutes: u64, reauth_minutes: u64) {
+        self.auto_lock =
+            (auto_lock_minutes > 0).then(|| TimeDelta::minutes(auto_lock_minutes as i64));
+        self.reauth_window = TimeDelta::minutes(reauth_minutes as i64);
+    }
+
+    /// Unlock with `password`, deriving the session key. The first unlock
+    /// with a password sets the one later unlocks are checked against; with
+    /// none set, `None` unlocks without a key.
+    pub fn unlock(&mut self, password: Option<&str>, now: Instant) -> WalletResult<()> {
+        let key = match (&self.credential, password) {
+            (Some(credential), Some(password)) => Some(
+                credential
+                    .derive(password)
+                    .ok_or(WalletError::AuthenticationFailed)?,
+            ),
+            (Some(_), None) => return Err(WalletError::AuthenticationFailed),
+            (None, Some(password)) => {
+                let (credential, key) = Credential::new(password);
+                self.credential = Some(credential);
+                Some(key)
+            }
+            (None, None) => None,
+        };
+        self.key = key;
+        self.unlocked = true;
+        self.authenticated(now);
+        Ok(())
+    }
+
+    /// Lock, dropping the session key
+    pub fn lock(&mut self) {
+        self.unlocked = false;
+        self.key = None;
+        self.last_auth = None;
+    }
+
+    /// Note user activity, putting off the auto-lock
+    pub fn touch(&mut self, now: Instant) {
+        self.last_activity = now;
+        self.slept_since_activity = Duration::ZERO;
+    }
+
+    /// Count `slept` towards both the idle time and the age of the last
+    /// password entry
+    pub fn note_sleep(&mut self, slept: Duration) {
+        self.slept_since_activity += slept;
+        self.slept_since_auth += slept;
+    }
+
+    /// Whether the session has been idle long enough to lock
+    pub fn idle_expired(&self, now: Instant) -> bool {
+        let idle = elapsed(self.last_activity, now, self.slept_since_activity);
+        self.unlocked && self.auto_lock.is_some_and(|limit| idle >= limit)
+    }
+
+    fn authenticated(&mut self, now: Instant) {
+        self.last_auth = Some(now);
+        self.slept_since_auth = Duration::ZERO;
+        self.touch(now);
+    }
+
+    /// Check `password` again while unlocked, restarting the window in which
+    /// sensitive operations go through without asking
+    pub fn reauthenticate(&mut self, password: &str, now: Instant) -> WalletResult<()> {
+        if !self.unlocked {
+            return Err(WalletError::Locked);
+        }
+        if let Some(credential) = &self.credential {
+            let key = credential
+                .derive(password)
+                .ok_or(WalletError::AuthenticationFailed)?;
+            self.key = Some(key);
+        }
+        self.authenticated(now);
+        Ok(())
+    }
+
+    /// Fails unless unlocked with the password entered within `max_age`
+    pub fn check_recent_auth(&self, max_age: TimeDelta, now: Instant) -> WalletResult<()> {
+        if !self.unlocked {
+            return Err(WalletError::Locked);
+        }
+        if self.credential.is_none() {
+            return Ok(());
+        }
+        match self.last_auth {
+            Some(at) if elapsed(at, now, self.slept_since_auth) <= max_age => Ok(()),
+            _ => Err(WalletError::ReauthRequired),
+        }
+    }
+}
+
+impl Wallet {
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// Unlock with the wallet password, or `None` for a wallet without one
+    pub fn unlock(&mut self, password: Option<&str>) -> WalletResult<()> {
+        let result = self.session.unlock(password, Instant::now());
+        let event = match result {
+            Ok(()) => AuditEvent::WalletUnlocked,
+            Err(_) => AuditEvent::PinAttemptFailed,
+        };
+        self.record_audit(event, AuditOutcome::of(&result));
+        result
+    }
+
+    pub fn lock(&mut self) {
+        self.session.lock();
+        self.record_audit(AuditEvent::WalletLocked, AuditOutcome::Success);
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.session.is_unlocked()
+    }
+
+    /// Note user activity, putting off the auto-lock
+    pub fn touch_session(&mut self) {
+        self.session.touch(Instant::now());
+    }
+
+    /// Take a jump of the system clock into account. A forward jump is most
+    /// likely the machine waking from sleep, so it counts as idle time and
+    /// may lock the wallet at the next `check_auto_lock`; a backward jump
+    /// changes nothing.
+    pub fn note_clock_jump(&mut self, jump: &ClockJump) {
+        if jump.is_forward() {
+            self.session.note_sleep(jump.magnitude());
+        }
+    }
+
+    /// Lock the wallet if it has been idle too long. Returns whether it locked.
+    pub fn check_auto_lock(&mut self) -> bool {
+        if !self.session.idle_expired(Instant::now()) {
+            return false;
+        }
+        self.lock();
+        true
+    }
+
+    /// Fails with `WalletError::ReauthRequired` unless the password was
+    /// entered within `max_age`, and with `WalletError::Locked` while locked
+    pub fn require_recent_auth(&self, max_age: TimeDelta) -> WalletResult<()> {
+        self.session.check_recent_auth(max_age, Instant::now())
+    }
+
+    /// `require_recent_auth` with the configured window for sensitive operations
+    pub fn require_sensitive_auth(&self) -> WalletResult<()> {
+        self.require_recent_auth(self.session.reauth_window())
+    }
+
+    /// Enter the password again for a sensitive operation
+    pub fn reauthenticate(&mut self, password: &str) -> WalletResult<()> {
+        let result = self.session.reauthenticate(password, Instant::now());
+        if result.is_err() {
+            self.record_audit(AuditEvent::PinAttemptFailed, AuditOutcome::of(&result));
+        }
+        result
+    }
+
+    /// Sends over the per-transaction limit need a recent password entry, on
+    /// top of the typed confirmation
+    pub fn check_send_auth(&self, request: &SendRequest) -> WalletResult<()> {
+        match self.spending_limits().per_transaction {
+            Some(limit) if request.amount > limit => self.require_sensitive_auth(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Apply the auto-lock and re-authentication timeouts from a wallet config
+    pub fn apply_session_config(&mut self, config: &SecurityConfig) {
+        self.session
+            .set_timeouts(config.auto_lock_minutes, config.pin_timeout_minutes);
+    }
+}
+
+/// Time from `since` to `now` on the monotonic clock, plus `slept`
+fn elapsed(since: Instant, now: Instant, slept: Duration) -> TimeDelta {
+    TimeDelta::from_std(now.saturating_duration_since(since) + slept).unwrap_or(TimeDelta::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fake_address;
+    use crate::wallet::spending::SpendingLimits;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn unlocked(password: Option<&str>, start: Instant) -> Session {
+        let mut session = Session::default();
+        session.set_timeouts(15, 5);
+        session.unlock(password, start).unwrap();
+        session
+    }
+
+    #[test]
+    fn an_idle_session_expires_after_the_auto_lock_time() {
+        let start = Instant::now();
+        let mut session = unlocked(None, start);
+        assert!(!session.idle_expired(start + 15 * MINUTE - Duration::from_secs(1)));
+        assert!(session.idle_expired(start + 15 * MINUTE));
+
+        // Activity puts it off from then on
+        session.touch(start + 10 * MINUTE);
+        assert!(!session.idle_expired(start + 24 * MINUTE));
+        assert!(session.idle_expired(start + 25 * MINUTE));
+
+        // Time asleep counts as idle
+        session.note_sleep(20 * MINUTE);
+        assert!(session.idle_expired(start + 10 * MINUTE));
+
+        session.lock();
+        assert!(!session.idle_expired(start + 60 * MINUTE));
+    }
+
+    #[test]
+    fn an_auto_lock_of_zero_never_expires() {
+        let start = Instant::now();
+        let mut session = unlocked(None, start);
+        session.set_timeouts(0, 5);
+        assert!(!session.idle_expired(start + 24 * 60 * MINUTE));
+    }
+
+    #[test]
+    fn recent_auth_holds_for_the_window_then_needs_the_password() {
+        let start = Instant::now();
+        let mut session = unlocked(Some("hunter2"), start);
+        let window = TimeDelta::minutes(5);
+        assert!(session.check_recent_auth(window, start).is_ok());
+        assert!(session
+            .check_recent_auth(window, start + 5 * MINUTE)
+            .is_ok());
+        assert!(matches!(
+            session.check_recent_auth(window, start + 5 * MINUTE + Duration::from_secs(1)),
+            Err(WalletError::ReauthRequired)
+        ));
+
+        // Activity alone doesn't refresh it
+        session.touch(start + 6 * MINUTE);
+        assert!(session
+            .check_recent_auth(window, start + 6 * MINUTE)
+            .is_err());
+
+        assert!(matches!(
+            session.reauthenticate("wrong", start + 6 * MINUTE),
+            Err(WalletError::AuthenticationFailed)
+        ));
+        session
+            .reauthenticate("hunter2", start + 6 * MINUTE)
+            .unwrap();
+        assert!(session
+            .check_recent_auth(window, start + 10 * MINUTE)
+            .is_ok());
+
+        // A sleep ages the last entry too
+        session.note_sleep(10 * MINUTE);
+        assert!(session
+            .check_recent_auth(window, start + 6 * MINUTE)
+            .is_err());
+    }
+
+    #[test]
+    fn locking_drops_the_key_and_only_the_password_unlocks_again() {
+        let start = Instant::now();
+        let mut session = unlocked(Some("hunter2"), start);
+        assert!(session.has_password());
+        let key = *session.key().unwrap().as_bytes();
+
+        session.lock();
+        assert!(session.key().is_none());
+        assert!(matches!(
+            session.check_recent_auth(TimeDelta::minutes(5), start),
+            Err(WalletError::Locked)
+        ));
+        assert!(matches!(
+            session.reauthenticate("hunter2", start),
+            Err(WalletError::Locked)
+        ));
+        for password in [None, Some("wrong")] {
+            assert!(matches!(
+                session.unlock(password, start),
+                Err(WalletError::AuthenticationFailed)
+            ));
+            assert!(!session.is_unlocked());
+        }
+
+        session.unlock(Some("hunter2"), start).unwrap();
+        assert_eq!(session.key().unwrap().as_bytes(), &key);
+    }
+
+    #[test]
+    fn a_session_without_a_password_never_asks_for_one() {
+        let start = Instant::now();
+        let session = unlocked(None, start);
+        assert!(!session.has_password());
+        assert!(session.key().is_none());
+        assert!(session
+            .check_recent_auth(TimeDelta::zero(), start + 60 * MINUTE)
+            .is_ok());
+    }
+
+    #[test]
+    fn only_sends_over_the_limit_need_a_recent_password() {
+        let mut wallet = Wallet::new();
+        wallet
+            .set_spending_limits(SpendingLimits {
+                per_transaction: Some(500),
+                daily: None,
+            })
+            .unwrap();
+        wallet.unlock(Some("hunter2")).unwrap();
+        let send = |amount| SendRequest {
+            key_name: "main".to_string(),
+            to: fake_address(),
+            amount,
+            fee: 10,
+            min_confirmations: None,
+            valid_for_blocks: None,
+            confirmed_amount: None,
+            force_broadcast: false,
+        };
+        assert!(wallet.check_send_auth(&send(501)).is_ok());
+
+        wallet.session.set_timeouts(15, 0);
+        assert!(wallet.check_send_auth(&send(500)).is_ok());
+        assert!(matches!(
+            wallet.check_send_auth(&send(501)),
+            Err(WalletError::ReauthRequired)
+        ));
+
+        wallet.lock();
+        assert!(!wallet.is_unlocked());
+        assert!(matches!(
+            wallet.require_sensitive_auth(),
+            Err(WalletError::Locked)
+        ));
+    }
+
+    #[test]
+    fn a_sleep_reported_as_a_forward_jump_locks_the_wallet() {
+        let mut wallet = Wallet::new();
+        wallet.unlock(Some("hunter2")).unwrap();
+        wallet.session.set_timeouts(15, 5);
+        let jump = |skew_secs| ClockJump {
+            at: chrono::Utc::now(),
+            skew_secs,
+        };
+
+        // Setting the clock back is not time spent idle
+        wallet.note_clock_jump(&jump(-3600));
+        assert!(!wallet.check_auto_lock());
+        assert!(wallet.is_unlocked());
+
+        wallet.note_clock_jump(&jump(3600));
+        assert!(wallet.check_auto_lock());
+        assert!(!wallet.is_unlocked());
+    }
+}
@@ -0,0 +1,355 @@
+//! Per-transaction and rolling 24-hour spending limits.
+//!
+//! Sends above the per-transaction limit go through only with the amount
+//! typed again as confirmation. Sends that would take the total broadcast in
+//! the last 24 hours over the daily limit are rejected. Only broadcast sends
+//! count; a send that fails before broadcast uses none of the allowance.
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::wallet::audit::{AuditEvent, AuditOutcome};
+use crate::wallet::send::SendRequest;
+use crate::wallet::storage::StorageManager;
+use crate::wallet::{SecurityConfig, Wallet, WalletError, WalletResult};
+
+/// Limits and recent sends, saved through `StorageManager` in the wallet data dir
+pub const SPENDING_FILE: &str = "spending.json";
+
+/// Sends count against the daily limit for this long after their broadcast
+pub fn spending_window() -> TimeDelta {
+    TimeDelta::hours(24)
+}
+
+/// Spending limits in base units; `None` means no limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpendingLimits {
+    /// Sends above this amount need a typed confirmation
+    pub per_transaction: Option<u64>,
+    /// Most that may be sent, fees included, in any 24 hours
+    pub daily: Option<u64>,
+}
+
+impl From<&SecurityConfig> for SpendingLimits {
+    fn from(config: &SecurityConfig) -> Self {
+        Self {
+            per_transaction: config.per_transaction_limit,
+            daily: config.daily_limit,
+        }
+    }
+}
+
+/// A broadcast send, as counted against the daily limit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpendRecord {
+    pub tx_id: String,
+    /// Amount plus fee
+    pub spent: u64,
+    pub broadcast_at: DateTime<Utc>,
+}
+
+/// What is left of the daily limit at a point in time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyAllowance {
+    pub limit: u64,
+    pub spent: u64,
+    pub remaining: u64,
+    /// When the oldest counted send leaves the window and frees up allowance;
+    /// `None` when nothing is counted
+    pub resets_at: Option<DateTime<Utc>>,
+}
+
+/// Spending limits and the sends counted against them
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpendingLedger {
+    #[serde(default)]
+    limits: SpendingLimits,
+    #[serde(default)]
+    sends: Vec<SpendRecord>,
+}
+
+impl SpendingLedger {
+    /// Load the saved ledger, or an empty one if none was saved
+    pub async fn load(storage: &StorageManager) -> WalletResult<Self> {
+        if !storage.exists(SPENDING_FILE) {
+            return Ok(Self::default());
+        }
+        storage.load(SPENDING_FILE).await
+    }
+
+    pub async fn save(&self, storage: &StorageManager) -> WalletResult<()> {
+        storage.save(SPENDING_FILE, self).await
+    }
+
+    pub fn limits(&self) -> SpendingLimits {
+        self.limits
+    }
+
+    pub fn set_limits(&mut self, limits: SpendingLimits) {
+        self.limits = limits;
+    }
+
+    /// Count a broadcast send, forgetting sends that have left the window
+    pub fn record(&mut self, tx_id: &str, spent: u64, broadcast_at: DateTime<Utc>) {
+        self.sends
+            .retain(|send| in_window(send.broadcast_at, broadcast_at));
+        self.sends.push(SpendRecord {
+            tx_id: tx_id.to_string(),
+            spent,
+            broadcast_at,
+        });
+    }
+
+    /// Whether the send `tx_id` is still counted
+    pub fn has_send(&self, tx_id: &str) -> bool {
+        self.sends.iter().any(|send| send.tx_id == tx_id)
+    }
+
+    /// Total counted in the 24 hours up to `now`. A send stops counting exactly
+    /// 24 hours after its broadcast.
+    pub fn spent_in_window(&self, now: DateTime<Utc>) -> u64 {
+        self.counted(now).map(|send| send.spent).sum()
+    }
+
+    /// The daily allowance at `now`, if a daily limit is set
+    pub fn daily_allowance(&self, now: DateTime<Utc>) -> Option<DailyAllowance> {
+        let limit = self.limits.daily?;
+        let spent = self.spent_in_window(now);
+        Some(DailyAllowance {
+            limit,
+            spent,
+            remaining: limit.saturating_sub(spent),
+            resets_at: self
+                .counted(now)
+                .map(|send| send.broadcast_at + spending_window())
+                .min(),
+        })
+    }
+
+    /// Check a send of `amount` plus `fee` at `now`. `confirmed_amount` is the
+    /// amount the user typed again, needed above the per-transaction limit.
+    pub fn check(
+        &self,
+        amount: u64,
+        fee: u64,
+        confirmed_amount: Option<u64>,
+        now: DateTime<Utc>,
+    ) -> WalletResult<()> {
+        if let Some(threshold) = self.limits.per_transaction {
+            if amount > threshold && confirmed_amount != Some(amount) {
+                return Err(WalletError::ConfirmationRequired { threshold });
+            }
+        }
+        if let Some(allowance) = self.daily_allowance(now) {
+            if amount.saturating_add(fee) > allowance.remaining {
+                return Err(WalletError::DailyLimitExceeded {
+                    remaining: allowance.remaining,
+                    resets_at: allowance.resets_at,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn counted(&self, now: DateTime<Utc>) -> impl Iterator<Item = &SpendRecord> {
+        self.sends
+            .iter()
+            .filter(move |send| in_window(send.broadcast_at, now))
+    }
+}
+
+/// Whether a send broadcast at `broadcast_at` still counts at `now`
+fn in_window(broadcast_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    broadcast_at <= now && now < broadcast_at + spending_window()
+}
+
+impl Wallet {
+    pub fn spending_limits(&self) -> SpendingLimits {
+        self.spending.limits()
+    }
+
+    /// Change the limits, recording the change in the audit log
+    pub fn set_spending_limits(&mut self, limits: SpendingLimits) -> WalletResult<()> {
+        let result = self.ensure_writable();
+        if result.is_ok() {
+            self.spending.set_limits(limits);
+        }
+        self.record_audit(
+            AuditEvent::SettingsChanged {
+                setting: "spending_limits".to_string(),
+            },
+            AuditOutcome::of(&result),
+        );
+        result
+    }
+
+    /// Apply the limits and session timeouts from a wallet config
+    pub fn apply_security_config(&mut self, config: &SecurityConfig) -> WalletResult<()> {
+        self.apply_session_config(config);
+        self.set_spending_limits(config.into())
+    }
+
+    /// What is left of the daily limit now, if one is set
+    pub fn daily_allowance(&self) -> Option<DailyAllowance> {
+        self.spending.daily_allowance(Utc::now())
+    }
+
+    /// Check `request` against the spending limits before anything is signed
+    pub fn check_spending_limits(&self, request: &SendRequest) -> WalletResult<()> {
+        self.spending.check(
+            request.amount,
+            request.fee,
+            request.confirmed_amount,
+            Utc::now(),
+        )
+    }
+
+    /// Load the limits and counted sends saved in the wallet data dir
+    pub async fn load_spending_ledger(&mut self) -> WalletResult<()> {
+        let Some(data_dir) = self.data_dir.clone() else {
+            return Ok(());
+        };
+        let storage = StorageManager::new(data_dir)?;
+        self.spending = SpendingLedger::load(&storage).await?;
+        Ok(())
+    }
+
+    /// Save the limits and counted sends; does nothing in read-only mode
+    pub async fn save_spending_ledger(&self) -> WalletResult<()> {
+        let Some(data_dir) = self.data_dir.clone().filter(|_| !self.read_only) else {
+            return Ok(());
+        };
+        let storage = StorageManager::new(data_dir)?;
+        self.spending.save(&storage).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fixture_time, TempDir};
+
+    const HOUR: i64 = 3_600;
+
+    fn ledger(per_transaction: Option<u64>, daily: Option<u64>) -> SpendingLedger {
+        let mut ledger = SpendingLedger::default();
+        ledger.set_limits(SpendingLimits {
+            per_transaction,
+            daily,
+        });
+        ledger
+    }
+
+    #[test]
+    fn a_send_counts_until_exactly_24_hours_after_its_broadcast() {
+        let mut ledger = ledger(None, Some(1_000));
+        let sent_at = fixture_time(0);
+        ledger.record("tx", 400, sent_at);
+
+        assert_eq!(ledger.spent_in_window(sent_at - TimeDelta::seconds(1)), 0);
+        assert_eq!(ledger.spent_in_window(sent_at), 400);
+        assert_eq!(ledger.spent_in_window(fixture_time(24 * HOUR - 1)), 400);
+        assert_eq!(ledger.spent_in_window(fixture_time(24 * HOUR)), 0);
+    }
+
+    #[test]
+    fn the_daily_limit_is_checked_against_the_rolling_window() {
+        let mut ledger = ledger(None, Some(1_000));
+        ledger.record("first", 600, fixture_time(0));
+        ledger.record("second", 300, fixture_time(12 * HOUR));
+
+        let just_before = fixture_time(24 * HOUR - 1);
+        match ledger.check(90, 20, None, just_before) {
+            Err(WalletError::DailyLimitExceeded {
+                remaining,
+                resets_at,
+            }) => {
+                assert_eq!(remaining, 100);
+                assert_eq!(resets_at, Some(fixture_time(24 * HOUR)));
+            }
+            other => panic!("expected the daily limit to be hit, got {:?}", other),
+        }
+        assert!(ledger.check(90, 10, None, just_before).is_ok());
+
+        // The first send has left the window; the second resets 12h later
+        let at_reset = fixture_time(24 * HOUR);
+        assert!(ledger.check(690, 10, None, at_reset).is_ok());
+        assert_eq!(
+            ledger.daily_allowance(at_reset),
+            Some(DailyAllowance {
+                limit: 1_000,
+                spent: 300,
+                remaining: 700,
+                resets_at: Some(fixture_time(36 * HOUR)),
+            })
+        );
+    }
+
+    #[test]
+    fn recording_forgets_sends_that_left_the_window() {
+        let mut ledger = ledger(None, Some(1_000));
+        ledger.record("old", 600, fixture_time(0));
+        ledger.record("new", 100, fixture_time(24 * HOUR));
+        assert!(!ledger.has_send("old"));
+        assert!(ledger.has_send("new"));
+    }
+
+    #[test]
+    fn sends_above_the_per_transaction_limit_need_the_amount_typed_again() {
+        let ledger = ledger(Some(500), None);
+        let now = fixture_time(0);
+        assert!(ledger.check(500, 10, None, now).is_ok());
+        for confirmed in [None, Some(500), Some(5_010)] {
+            assert!(matches!(
+                ledger.check(501, 10, confirmed, now),
+                Err(WalletError::ConfirmationRequired { threshold: 500 })
+            ));
+        }
+        assert!(ledger.check(501, 10, Some(501), now).is_ok());
+    }
+
+    #[test]
+    fn without_limits_nothing_is_refused() {
+        let ledger = SpendingLedger::default();
+        assert_eq!(ledger.daily_allowance(fixture_time(0)), None);
+        assert!(ledger
+            .check(u64::MAX, u64::MAX, None, fixture_time(0))
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn the_ledger_survives_a_restart() {
+        let dir = TempDir::new("spending");
+        let mut wallet = Wallet::new();
+        wallet.data_dir = Some(dir.path().to_path_buf());
+        wallet
+            .set_spending_limits(SpendingLimits {
+                per_transaction: Some(50),
+                daily: Some(1_000),
+            })
+            .unwrap();
+        wallet.spending.record("tx", 400, Utc::now());
+        wallet.save_spending_ledger().await.unwrap();
+
+        let mut restarted = Wallet::new();
+        restarted.data_dir = Some(dir.path().to_path_buf());
+        restarted.load_spending_ledger().await.unwrap();
+        assert_eq!(restarted.spending_limits(), wallet.spending_limits());
+        assert_eq!(restarted.daily_allowance().unwrap().remaining, 600);
+    }
+
+    #[test]
+    fn changing_the_limits_is_audited() {
+        let mut wallet = Wallet::new();
+        wallet
+            .set_spending_limits(SpendingLimits::default())
+            .unwrap();
+        let entries = wallet.audit_log(&Default::default());
+        assert_eq!(
+            entries[0].event,
+            AuditEvent::SettingsChanged {
+                setting: "spending_limits".to_string(),
+            }
+        );
+    }
+}
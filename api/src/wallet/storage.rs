@@ -1,8 +1,258 @@
+use crate::wallet::encoding::{Decode, Encode};
+use crate::wallet::integrity;
 use crate::wallet::{WalletError, WalletResult};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
 use tokio::fs;
 
+/// Default for how long a batched file is held back while saves to it keep
+/// arriving; a crash mid-batch loses at most this much
+pub const MAX_BATCH_DELAY: Duration = Duration::from_secs(2);
+
+/// Records which storage schema last wrote the data dir
+pub const SCHEMA_FILE: &str = "schema.json";
+
+/// Newest layout of the data dir this build reads and writes. Bump it when a
+/// change would have older builds misread or clobber what this one writes.
+pub const STORAGE_SCHEMA_VERSION: u32 = 1;
+
+/// Batches in progress by data dir, shared by every `StorageManager` for it
+static BATCHES: Mutex<BTreeMap<PathBuf, Batch>> = Mutex::new(BTreeMap::new());
+/// Held by whoever is writing files in a data dir, from taking the contents
+/// to write until they are on disk, so older contents never land after newer
+/// ones. Taken before `BATCHES` when both are needed.
+static WRITE_LOCKS: Mutex<BTreeMap<PathBuf, Arc<Mutex<()>>>> = Mutex::new(BTreeMap::new());
+static WRITES_REQUESTED: AtomicU64 = AtomicU64::new(0);
+static WRITES_PERFORMED: AtomicU64 = AtomicU64::new(0);
+/// Current batch delay in milliseconds, `MAX_BATCH_DELAY` unless changed
+static BATCH_DELAY_MS: AtomicU64 = AtomicU64::new(MAX_BATCH_DELAY.as_millis() as u64);
+
+#[derive(Debug, Default)]
+struct Batch {
+    /// Open `begin_batch` calls; nested batches flush when the outermost commits
+    depth: usize,
+    /// Latest contents by file name, not yet written
+    dirty: BTreeMap<String, Vec<u8>>,
+    /// When the oldest unwritten save was made
+    dirty_since: Option<Instant>,
+    /// A deadline task is waiting to flush this batch
+    timer_armed: bool,
+}
+
+/// How long a batched save may wait before it is written
+pub fn batch_delay() -> Duration {
+    Duration::from_millis(BATCH_DELAY_MS.load(Ordering::Relaxed))
+}
+
+/// Change how long batched saves may wait, for every data dir. Shorter
+/// loses less on a crash; longer coalesces more saves into one write.
+pub fn set_batch_delay(delay: Duration) {
+    let millis = u64::try_from(delay.as_millis()).unwrap_or(u64::MAX);
+    BATCH_DELAY_MS.store(millis, Ordering::Relaxed);
+}
+
+/// Saves asked of `StorageManager` against files actually written, since
+/// the process started. Manifest updates count as writes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageWriteStats {
+    pub requested: u64,
+    pub performed: u64,
+}
+
+/// Write counters for the debug bundle
+pub fn write_stats() -> StorageWriteStats {
+    StorageWriteStats {
+        requested: WRITES_REQUESTED.load(Ordering::Relaxed),
+        performed: WRITES_PERFORMED.load(Ordering::Relaxed),
+    }
+}
+
+/// Write every batched file in every data dir, leaving the batches open.
+/// Called on shutdown so a batch left open doesn't lose its saves.
+pub fn flush_all() -> WalletResult<()> {
+    let data_dirs: Vec<PathBuf> = lock_batches().keys().cloned().collect();
+    let mut result = Ok(());
+    for data_dir in data_dirs {
+        let flushed = flush_dir(&data_dir, |batch| Some(batch.take_dirty()));
+        result = result.and(flushed);
+    }
+    result
+}
+
+/// Write every batch whose oldest unwritten save is past the batch delay,
+/// leaving the batches open. Deadline tasks do this on their own inside a
+/// tokio runtime; without one, call it from a periodic tick.
+pub fn flush_overdue() -> WalletResult<()> {
+    let delay = batch_delay();
+    let data_dirs: Vec<PathBuf> = lock_batches().keys().cloned().collect();
+    let mut result = Ok(());
+    for data_dir in data_dirs {
+        let flushed = flush_dir(&data_dir, |batch| {
+            (batch.due_in(delay) == Some(Duration::ZERO)).then(|| batch.take_dirty())
+        });
+        result = result.and(flushed);
+    }
+    result
+}
+
+fn lock_batches() -> MutexGuard<'static, BTreeMap<PathBuf, Batch>> {
+    BATCHES.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The write lock of `data_dir`, created on first use
+fn write_lock(data_dir: &Path) -> Arc<Mutex<()>> {
+    WRITE_LOCKS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .entry(data_dir.to_path_buf())
+        .or_default()
+        .clone()
+}
+
+fn lock_writes(lock: &Mutex<()>) -> MutexGuard<'_, ()> {
+    lock.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Write what `take` takes from the batch of `data_dir`, holding the data
+/// dir's write lock from the take until the files are written
+fn flush_dir(
+    data_dir: &Path,
+    take: impl FnOnce(&mut Batch) -> Option<BTreeMap<String, Vec<u8>>>,
+) -> WalletResult<()> {
+    let lock = write_lock(data_dir);
+    let _writing = lock_writes(&lock);
+    let dirty = lock_batches()
+        .get_mut(data_dir)
+        .and_then(take)
+        .unwrap_or_default();
+    write_dirty(data_dir, dirty)
+}
+
+/// Flush the batch for `data_dir` once its oldest save reaches the batch
+/// delay, even if no further save arrives to notice. Needs a tokio runtime;
+/// outside one the next save or `flush_overdue` catches it instead.
+fn arm_deadline(data_dir: PathBuf) {
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+    runtime.spawn(async move {
+        loop {
+            let wait = {
+                let mut batches = lock_batches();
+                let Some(batch) = batches.get_mut(&data_dir) else {
+                    return;
+                };
+                match batch.due_in(batch_delay()) {
+                    None => {
+                        batch.timer_armed = false;
+                        return;
+                    }
+                    Some(wait) if !wait.is_zero() => wait,
+                    Some(_) => {
+                        batch.timer_armed = false;
+                        drop(batches);
+                        if let Err(e) = flush_dir(&data_dir, |batch| Some(batch.take_dirty())) {
+                            tracing::error!("Failed to flush batched saves: {}", e);
+                        }
+                        return;
+                    }
+                }
+            };
+            tokio::time::sleep(wait).await;
+        }
+    });
+}
+
+impl Batch {
+    /// Time left before the oldest unwritten save is due, zero once it is;
+    /// `None` when there's nothing to write
+    fn due_in(&self, delay: Duration) -> Option<Duration> {
+        let since = self.dirty_since?;
+        Some(delay.saturating_sub(since.elapsed()))
+    }
+
+    fn take_dirty(&mut self) -> BTreeMap<String, Vec<u8>> {
+        self.dirty_since = None;
+        std::mem::take(&mut self.dirty)
+    }
+}
+
+/// Write each file atomically, carrying on past a failure so one bad file
+/// doesn't hold back the rest, then record the written ones in the
+/// integrity manifest with a single update
+fn write_dirty(data_dir: &Path, dirty: BTreeMap<String, Vec<u8>>) -> WalletResult<()> {
+    let mut result = Ok(());
+    let mut written = Vec::new();
+    for (filename, contents) in &dirty {
+        match write_file(data_dir, filename, contents) {
+            Ok(()) => written.push((filename.as_str(), Some(contents.as_slice()))),
+            Err(e) => result = result.and(Err(e)),
+        }
+    }
+    result.and(record_in_manifest(data_dir, written))
+}
+
+/// Update the integrity manifest for files just written or deleted
+fn record_in_manifest(data_dir: &Path, files: Vec<(&str, Option<&[u8]>)>) -> WalletResult<()> {
+    if files.is_empty() {
+        return Ok(());
+    }
+    integrity::record_files(data_dir, files)?;
+    WRITES_PERFORMED.fetch_add(1, Ordering::Relaxed);
+    Ok(())
+}
+
+fn write_file(data_dir: &Path, filename: &str, contents: &[u8]) -> WalletResult<()> {
+    let file_path = data_dir.join(filename);
+    let tmp_path = data_dir.join(format!("{}.tmp", filename));
+    std::fs::write(&tmp_path, contents)
+        .map_err(|e| WalletError::Storage(format!("Failed to write file: {}", e)))?;
+    std::fs::rename(&tmp_path, &file_path)
+        .map_err(|e| WalletError::Storage(format!("Failed to replace file: {}", e)))?;
+    WRITES_PERFORMED.fetch_add(1, Ordering::Relaxed);
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct SchemaMarker {
+    version: u32,
+}
+
+/// Schema the data dir was last written with; `None` when it was never
+/// recorded, as in data dirs from before the marker
+pub fn read_schema_version(data_dir: &Path) -> WalletResult<Option<u32>> {
+    let bytes = match std::fs::read(data_dir.join(SCHEMA_FILE)) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(WalletError::Storage(format!(
+                "Failed to read {}: {}",
+                SCHEMA_FILE, e
+            )))
+        }
+    };
+    serde_json::from_slice::<SchemaMarker>(&bytes)
+        .map(|marker| Some(marker.version))
+        .map_err(|e| WalletError::Storage(format!("Failed to parse {}: {}", SCHEMA_FILE, e)))
+}
+
+/// Record that the data dir is now written with `STORAGE_SCHEMA_VERSION`
+pub(crate) fn write_schema_version(data_dir: &Path) -> WalletResult<()> {
+    let marker = SchemaMarker {
+        version: STORAGE_SCHEMA_VERSION,
+    };
+    let contents = serde_json::to_vec_pretty(&marker)
+        .map_err(|e| WalletError::Storage(format!("Serialization failed: {}", e)))?;
+    let lock = write_lock(data_dir);
+    let _writing = lock_writes(&lock);
+    write_file(data_dir, SCHEMA_FILE, &contents)?;
+    record_in_manifest(data_dir, vec![(SCHEMA_FILE, Some(&contents))])
+}
+
 /// Storage manager for wallet data
 #[derive(Debug)]
 pub struct StorageManager {
@@ -17,21 +267,114 @@ impl StorageManager {
         Ok(Self { data_dir })
     }
 
-    /// Save data to a file
+    /// Save data to a file. The file is replaced atomically, so readers see
+    /// either the old or the new contents, never a partial write, and its
+    /// checksum is recorded in the integrity manifest.
     pub async fn save<T: Serialize>(&self, filename: &str, data: &T) -> WalletResult<()> {
-        let file_path = self.data_dir.join(filename);
         let json_data = serde_json::to_string_pretty(data)
             .map_err(|e| WalletError::Storage(format!("Serialization failed: {}", e)))?;
 
-        fs::write(file_path, json_data)
-            .await
-            .map_err(|e| WalletError::Storage(format!("Failed to write file: {}", e)))?;
+        self.write_atomic(filename, json_data.as_bytes()).await
+    }
+
+    async fn write_atomic(&self, filename: &str, contents: &[u8]) -> WalletResult<()> {
+        WRITES_REQUESTED.fetch_add(1, Ordering::Relaxed);
+        let lock = write_lock(&self.data_dir);
+        let _writing = lock_writes(&lock);
+
+        // Inside a batch only the latest contents are kept, written when the
+        // batch commits or once the oldest unwritten save is `batch_delay` old:
+        // by a deadline task, or by whichever save finds it overdue first
+        let due = {
+            let mut batches = lock_batches();
+            match batches.get_mut(&self.data_dir) {
+                Some(batch) if batch.depth > 0 => {
+                    batch.dirty.insert(filename.to_string(), contents.to_vec());
+                    let since = *batch.dirty_since.get_or_insert_with(Instant::now);
+                    if since.elapsed() < batch_delay() {
+                        if !batch.timer_armed {
+                            batch.timer_armed = true;
+                            arm_deadline(self.data_dir.clone());
+                        }
+                        return Ok(());
+                    }
+                    Some(batch.take_dirty())
+                }
+                _ => None,
+            }
+        };
+        if let Some(dirty) = due {
+            return write_dirty(&self.data_dir, dirty);
+        }
+
+        // Written synchronously: the write lock can't be held across an await
+        write_file(&self.data_dir, filename, contents)?;
+        record_in_manifest(&self.data_dir, vec![(filename, Some(contents))])
+    }
+
+    /// Contents saved in an open batch but not yet written
+    fn batched(&self, filename: &str) -> Option<Vec<u8>> {
+        lock_batches()
+            .get(&self.data_dir)
+            .and_then(|batch| batch.dirty.get(filename).cloned())
+    }
+
+    /// Open a batch that commits when the returned guard is dropped, so early
+    /// returns and `?` can't leave it open
+    pub fn batch(&self) -> BatchGuard {
+        self.begin_batch();
+        BatchGuard {
+            storage: StorageManager {
+                data_dir: self.data_dir.clone(),
+            },
+            open: true,
+        }
+    }
 
-        Ok(())
+    /// Hold saves to this data dir in memory until `commit_batch`, so a file
+    /// saved many times is written once. Batches nest; every `begin_batch`
+    /// needs a matching `commit_batch`, on error paths too. Prefer `batch`.
+    pub fn begin_batch(&self) {
+        lock_batches()
+            .entry(self.data_dir.clone())
+            .or_default()
+            .depth += 1;
+    }
+
+    /// Close a batch, writing the files saved in it once the outermost one closes
+    pub fn commit_batch(&self) -> WalletResult<()> {
+        let lock = write_lock(&self.data_dir);
+        let _writing = lock_writes(&lock);
+        let dirty = {
+            let mut batches = lock_batches();
+            let Some(batch) = batches.get_mut(&self.data_dir) else {
+                return Ok(());
+            };
+            batch.depth = batch.depth.saturating_sub(1);
+            if batch.depth > 0 {
+                return Ok(());
+            }
+            batches
+                .remove(&self.data_dir)
+                .map(|mut batch| batch.take_dirty())
+                .unwrap_or_default()
+        };
+        write_dirty(&self.data_dir, dirty)
+    }
+
+    /// Write the files batched for this data dir without closing the batch,
+    /// e.g. before copying the data dir
+    pub fn flush(&self) -> WalletResult<()> {
+        flush_dir(&self.data_dir, |batch| Some(batch.take_dirty()))
     }
 
     /// Load data from a file
     pub async fn load<T: for<'de> Deserialize<'de>>(&self, filename: &str) -> WalletResult<T> {
+        if let Some(contents) = self.batched(filename) {
+            return serde_json::from_slice(&contents)
+                .map_err(|e| WalletError::Storage(format!("Deserialization failed: {}", e)));
+        }
+
         let file_path = self.data_dir.join(filename);
 
         if !file_path.exists() {
@@ -49,22 +392,54 @@ impl StorageManager {
             .map_err(|e| WalletError::Storage(format!("Deserialization failed: {}", e)))
     }
 
+    /// Save a value in its canonical binary encoding
+    pub async fn save_encoded<T: Encode>(&self, filename: &str, data: &T) -> WalletResult<()> {
+        self.write_atomic(filename, &data.to_bytes()).await
+    }
+
+    /// Load a value stored with `save_encoded`
+    pub async fn load_encoded<T: Decode>(&self, filename: &str) -> WalletResult<T> {
+        if let Some(contents) = self.batched(filename) {
+            return T::from_bytes(&contents);
+        }
+
+        let file_path = self.data_dir.join(filename);
+
+        if !file_path.exists() {
+            return Err(WalletError::Storage(format!(
+                "File {} does not exist",
+                filename
+            )));
+        }
+
+        let bytes = fs::read(file_path)
+            .await
+            .map_err(|e| WalletError::Storage(format!("Failed to read file: {}", e)))?;
+
+        T::from_bytes(&bytes)
+    }
+
     /// Check if a file exists
     pub fn exists(&self, filename: &str) -> bool {
-        self.data_dir.join(filename).exists()
+        self.batched(filename).is_some() || self.data_dir.join(filename).exists()
     }
 
-    /// Delete a file
+    /// Delete a file, along with any batched save of it
     pub async fn delete(&self, filename: &str) -> WalletResult<()> {
+        let lock = write_lock(&self.data_dir);
+        let _writing = lock_writes(&lock);
+        if let Some(batch) = lock_batches().get_mut(&self.data_dir) {
+            batch.dirty.remove(filename);
+        }
+
         let file_path = self.data_dir.join(filename);
 
         if file_path.exists() {
-            fs::remove_file(file_path)
-                .await
+            std::fs::remove_file(file_path)
                 .map_err(|e| WalletError::Storage(format!("Failed to delete file: {}", e)))?;
         }
 
-        Ok(())
+        record_in_manifest(&self.data_dir, vec![(filename, None)])
     }
 
     /// Get the data directory path
@@ -72,3 +447,185 @@ impl StorageManager {
         &self.data_dir
     }
 }
+
+/// An open batch from `StorageManager::batch`. Call `commit` to see write
+/// errors; dropping it commits too, logging any error.
+#[derive(Debug)]
+#[must_use = "the batch commits as soon as the guard is dropped"]
+pub struct BatchGuard {
+    storage: StorageManager,
+    open: bool,
+}
+
+impl BatchGuard {
+    /// Close the batch, writing its files if it is the outermost one
+    pub fn commit(mut self) -> WalletResult<()> {
+        self.open = false;
+        self.storage.commit_batch()
+    }
+}
+
+impl Drop for BatchGuard {
+    fn drop(&mut self) {
+        if self.open {
+            if let Err(e) = self.storage.commit_batch() {
+                tracing::error!("Failed to commit storage batch: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    fn on_disk(dir: &TempDir, filename: &str) -> Option<String> {
+        std::fs::read_to_string(dir.join(filename)).ok()
+    }
+
+    #[tokio::test]
+    async fn a_dropped_guard_commits_its_batch() {
+        let dir = TempDir::new("batch-guard");
+        let storage = StorageManager::new(dir.path().to_path_buf()).unwrap();
+        {
+            let _batch = storage.batch();
+            storage.save("a.json", &1).await.unwrap();
+            storage.save("a.json", &2).await.unwrap();
+            assert_eq!(on_disk(&dir, "a.json"), None);
+            assert_eq!(storage.load::<u32>("a.json").await.unwrap(), 2);
+        }
+        assert_eq!(on_disk(&dir, "a.json").as_deref(), Some("2"));
+        assert!(lock_batches().get(storage.data_dir()).is_none());
+    }
+
+    #[tokio::test]
+    async fn nested_guards_write_when_the_outermost_closes() {
+        let dir = TempDir::new("batch-guard-nested");
+        let storage = StorageManager::new(dir.path().to_path_buf()).unwrap();
+        let outer = storage.batch();
+        let inner = storage.batch();
+        storage.save("a.json", &1).await.unwrap();
+        inner.commit().unwrap();
+        assert_eq!(on_disk(&dir, "a.json"), None);
+        outer.commit().unwrap();
+        assert_eq!(on_disk(&dir, "a.json").as_deref(), Some("1"));
+    }
+
+    #[tokio::test]
+    async fn an_error_path_still_closes_the_batch() {
+        let dir = TempDir::new("batch-guard-error");
+        let storage = StorageManager::new(dir.path().to_path_buf()).unwrap();
+        async fn fails(storage: &StorageManager) -> WalletResult<()> {
+            let _batch = storage.batch();
+            storage.save("a.json", &1).await?;
+            Err(WalletError::Storage("boom".to_string()))
+        }
+        assert!(fails(&storage).await.is_err());
+        assert_eq!(on_disk(&dir, "a.json").as_deref(), Some("1"));
+        storage.save("b.json", &2).await.unwrap();
+        assert_eq!(on_disk(&dir, "b.json").as_deref(), Some("2"));
+    }
+
+    #[tokio::test]
+    async fn a_batch_is_flushed_at_the_deadline_without_another_save() {
+        let dir = TempDir::new("batch-deadline");
+        let storage = StorageManager::new(dir.path().to_path_buf()).unwrap();
+        let batch = storage.batch();
+        storage.save("a.json", &1).await.unwrap();
+        assert_eq!(on_disk(&dir, "a.json"), None);
+
+        tokio::time::sleep(batch_delay() + Duration::from_millis(500)).await;
+        assert_eq!(on_disk(&dir, "a.json").as_deref(), Some("1"));
+
+        // The batch stays open, and the next save arms a fresh deadline
+        storage.save("a.json", &2).await.unwrap();
+        assert_eq!(on_disk(&dir, "a.json").as_deref(), Some("1"));
+        batch.commit().unwrap();
+        assert_eq!(on_disk(&dir, "a.json").as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn flush_overdue_writes_only_batches_past_the_delay() {
+        let dir = TempDir::new("batch-overdue");
+        let data_dir = dir.path().to_path_buf();
+        let storage = StorageManager::new(data_dir.clone()).unwrap();
+        let batch = storage.batch();
+        {
+            let mut batches = lock_batches();
+            let pending = batches.get_mut(&data_dir).unwrap();
+            pending.dirty.insert("a.json".to_string(), b"1".to_vec());
+            pending.dirty_since = Some(Instant::now());
+        }
+        flush_overdue().unwrap();
+        assert_eq!(on_disk(&dir, "a.json"), None);
+
+        lock_batches().get_mut(&data_dir).unwrap().dirty_since =
+            Instant::now().checked_sub(batch_delay());
+        flush_overdue().unwrap();
+        assert_eq!(on_disk(&dir, "a.json").as_deref(), Some("1"));
+        batch.commit().unwrap();
+    }
+
+    #[test]
+    fn a_flush_waits_for_the_write_in_progress_before_taking_its_files() {
+        let dir = TempDir::new("batch-write-lock");
+        let data_dir = dir.path().to_path_buf();
+        let storage = StorageManager::new(data_dir.clone()).unwrap();
+        let batch = storage.batch();
+        lock_batches()
+            .get_mut(&data_dir)
+            .unwrap()
+            .dirty
+            .insert("a.json".to_string(), b"1".to_vec());
+
+        // A writer holds the lock while a newer save lands in the batch; the
+        // flush must write the newer contents, not ones taken before it
+        let lock = write_lock(&data_dir);
+        let writing = lock_writes(&lock);
+        let flusher = StorageManager::new(data_dir.clone()).unwrap();
+        let flush = std::thread::spawn(move || flusher.flush());
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(on_disk(&dir, "a.json"), None);
+        lock_batches()
+            .get_mut(&data_dir)
+            .unwrap()
+            .dirty
+            .insert("a.json".to_string(), b"2".to_vec());
+        drop(writing);
+        flush.join().unwrap().unwrap();
+
+        assert_eq!(on_disk(&dir, "a.json").as_deref(), Some("2"));
+        batch.commit().unwrap();
+        assert_eq!(on_disk(&dir, "a.json").as_deref(), Some("2"));
+    }
+
+    #[tokio::test]
+    async fn a_committed_batch_records_every_file_in_the_manifest() {
+        let dir = TempDir::new("batch-manifest");
+        let storage = StorageManager::new(dir.path().to_path_buf()).unwrap();
+        storage.save("gone.json", &0).await.unwrap();
+        let batch = storage.batch();
+        for (index, filename) in ["a.json", "b.json", "c.json"].into_iter().enumerate() {
+            storage.save(filename, &index).await.unwrap();
+        }
+        storage.delete("gone.json").await.unwrap();
+        batch.commit().unwrap();
+
+        let manifest: serde_json::Value =
+            serde_json::from_str(&on_disk(&dir, integrity::MANIFEST_FILE).unwrap()).unwrap();
+        let files: Vec<&String> = manifest["files"].as_object().unwrap().keys().collect();
+        assert_eq!(files, vec!["a.json", "b.json", "c.json"]);
+    }
+
+    #[test]
+    fn due_in_counts_down_from_the_oldest_save() {
+        let delay = Duration::from_secs(2);
+        let mut batch = Batch::default();
+        assert_eq!(batch.due_in(delay), None);
+        batch.dirty_since = Instant::now().checked_sub(Duration::from_secs(5));
+        assert_eq!(batch.due_in(delay), Some(Duration::ZERO));
+        batch.dirty_since = Some(Instant::now());
+        assert!(batch.due_in(delay).unwrap() > Duration::from_secs(1));
+    }
+}
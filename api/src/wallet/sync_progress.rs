@@ -0,0 +1,289 @@
+//! How far the node is from the network tip, and how long catching up
+//! should take.
+//!
+//! `SyncTracker` is fed the local height, the best height peers advertise
+//! and the bytes received each time node stats are taken. Throughput is
+//! measured over the last `SYNC_WINDOW`, and the rate behind the estimate is
+//! an exponential moving average of it, so a burst of fast blocks or a slow
+//! one moves the estimate gradually. Once no block has arrived for
+//! `SYNC_STALL_AFTER` the estimate is dropped and the sync reported stalled.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Span throughput is measured over
+pub const SYNC_WINDOW: Duration = Duration::from_secs(60);
+
+/// Time without a new block, while behind, after which the sync counts as
+/// stalled
+pub const SYNC_STALL_AFTER: Duration = Duration::from_secs(120);
+
+/// Time constant of the rate average: a change in throughput is about two
+/// thirds reflected in the estimate after this long
+const RATE_SMOOTHING: Duration = Duration::from_secs(30);
+
+/// Span the window must cover before a rate is worked out from it
+const MIN_RATE_SPAN: Duration = Duration::from_secs(5);
+
+/// Where a sync stands
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncProgress {
+    /// Local chain height
+    pub current: u64,
+    /// Best height advertised by peers
+    pub target: u64,
+    /// Smoothed blocks per second
+    pub blocks_per_sec: f64,
+    /// Bytes per second received over the window
+    pub bytes_per_sec: f64,
+    /// Time left at the smoothed rate; `None` while the rate is unknown or
+    /// the sync is stalled
+    pub eta: Option<Duration>,
+    /// Behind, with no new block for `SYNC_STALL_AFTER`
+    pub stalled: bool,
+}
+
+impl SyncProgress {
+    pub fn remaining(&self) -> u64 {
+        self.target.saturating_sub(self.current)
+    }
+
+    pub fn is_synced(&self) -> bool {
+        self.current >= self.target
+    }
+
+    /// Share of the target height reached, from 0.0 to 1.0
+    pub fn fraction(&self) -> f64 {
+        if self.target == 0 {
+            return 1.0;
+        }
+        (self.current as f64 / self.target as f64).min(1.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SyncSample {
+    at: Instant,
+    height: u64,
+    bytes: u64,
+}
+
+/// Sliding window of sync samples and the smoothed rate worked out from them
+#[derive(Debug, Default)]
+pub struct SyncTracker {
+    samples: VecDeque<SyncSample>,
+    /// Blocks per second, averaged over time
+    rate: Option<f64>,
+    /// When the height last went up, or sampling began
+    last_advance: Option<Instant>,
+    last: Option<SyncProgress>,
+}
+
+impl SyncTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take a sample: the local `height`, the `target` peers advertise, if
+    /// any, and the total bytes received so far. Without a target there is
+    /// nothing to sync towards and the window starts over.
+    pub fn record(
+        &mut self,
+        at: Instant,
+        height: u64,
+        target: Option<u64>,
+        bytes: u64,
+    ) -> Option<SyncProgress> {
+        let Some(target) = target else {
+            self.reset();
+            return None;
+        };
+
+        let previous = self.samples.back().copied();
+        if previous.is_none_or(|previous| height > previous.height) {
+            self.last_advance = Some(at);
+        }
+        self.samples.push_back(SyncSample { at, height, bytes });
+        // Keep one sample at or before the window start as its baseline
+        while self.samples.len() > 2 && at.duration_since(self.samples[1].at) >= SYNC_WINDOW {
+            self.samples.pop_front();
+        }
+
+        let first = self.samples[0];
+        let span = at.duration_since(first.at).as_secs_f64();
+        let mut bytes_per_sec = 0.0;
+        if span >= MIN_RATE_SPAN.as_secs_f64() {
+            let window_rate = height.saturating_sub(first.height) as f64 / span;
+            bytes_per_sec = bytes.saturating_sub(first.bytes) as f64 / span;
+            let since_previous = previous
+                .map(|previous| at.duration_since(previous.at).as_secs_f64())
+                .unwrap_or(0.0);
+            let weight = 1.0 - (-since_previous / RATE_SMOOTHING.as_secs_f64()).exp();
+            self.rate = Some(match self.rate {
+                Some(rate) => rate + weight * (window_rate - rate),
+                None => window_rate,
+            });
+        }
+
+        let stalled = height < target
+            && self
+                .last_advance
+                .is_some_and(|advanced| at.duration_since(advanced) >= SYNC_STALL_AFTER);
+        let remaining = target.saturating_sub(height);
+        let eta = if remaining == 0 {
+            Some(Duration::ZERO)
+        } else if stalled {
+            None
+        } else {
+            self.rate
+                .filter(|rate| *rate > 0.0)
+                .map(|rate| Duration::from_secs_f64(remaining as f64 / rate))
+        };
+
+        let progress = SyncProgress {
+            current: height,
+            target,
+            blocks_per_sec: self.rate.unwrap_or(0.0),
+            bytes_per_sec,
+            eta,
+            stalled,
+        };
+        self.last = Some(progress.clone());
+        Some(progress)
+    }
+
+    /// Progress as of the last sample
+    pub fn progress(&self) -> Option<SyncProgress> {
+        self.last.clone()
+    }
+
+    /// Forget every sample, e.g. when the node stops
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    /// Feed `tracker` one sample a second for `seconds`, `blocks_per_sec`
+    /// blocks and 1,000 bytes a block at a time, towards `target`.
+    /// Returns the last progress and the height reached.
+    fn feed(
+        tracker: &mut SyncTracker,
+        start: Instant,
+        from: (u64, u64),
+        seconds: u64,
+        blocks_per_sec: u64,
+        target: u64,
+    ) -> (SyncProgress, u64) {
+        let (offset, mut height) = from;
+        let mut progress = None;
+        for second in 1..=seconds {
+            height += blocks_per_sec;
+            progress = tracker.record(
+                start + SECOND * (offset + second) as u32,
+                height,
+                Some(target),
+                height * 1_000,
+            );
+        }
+        (progress.unwrap(), height)
+    }
+
+    #[test]
+    fn a_steady_rate_gives_a_steady_eta() {
+        let mut tracker = SyncTracker::new();
+        let start = Instant::now();
+        let first = tracker.record(start, 0, Some(10_000), 0).unwrap();
+        // Too early to tell
+        assert_eq!(first.eta, None);
+        assert_eq!(first.blocks_per_sec, 0.0);
+
+        let (progress, height) = feed(&mut tracker, start, (0, 0), 120, 10, 10_000);
+        assert_eq!(height, 1_200);
+        assert!((progress.blocks_per_sec - 10.0).abs() < 1e-9);
+        assert!((progress.bytes_per_sec - 10_000.0).abs() < 1e-6);
+        assert_eq!(progress.eta, Some(Duration::from_secs(880)));
+        assert!(!progress.stalled);
+        assert_eq!(progress.remaining(), 8_800);
+        assert!((progress.fraction() - 0.12).abs() < 1e-9);
+        assert_eq!(tracker.progress(), Some(progress));
+    }
+
+    #[test]
+    fn a_burst_moves_the_rate_gradually() {
+        let mut tracker = SyncTracker::new();
+        let start = Instant::now();
+        tracker.record(start, 0, Some(1_000_000), 0);
+        let (_, height) = feed(&mut tracker, start, (0, 0), 120, 10, 1_000_000);
+
+        let (after_one, height) = feed(&mut tracker, start, (120, height), 1, 100, 1_000_000);
+        assert!(
+            after_one.blocks_per_sec > 10.0 && after_one.blocks_per_sec < 11.0,
+            "{}",
+            after_one.blocks_per_sec
+        );
+        // A minute in, still well short of the new 100 blocks/s
+        let (after_a_minute, _) = feed(&mut tracker, start, (121, height), 59, 100, 1_000_000);
+        assert!(
+            after_a_minute.blocks_per_sec > 50.0 && after_a_minute.blocks_per_sec < 75.0,
+            "{}",
+            after_a_minute.blocks_per_sec
+        );
+    }
+
+    #[test]
+    fn no_new_blocks_for_a_while_stalls_the_sync() {
+        let mut tracker = SyncTracker::new();
+        let start = Instant::now();
+        tracker.record(start, 0, Some(10_000), 0);
+        let (_, height) = feed(&mut tracker, start, (0, 0), 30, 10, 10_000);
+
+        let (still, _) = feed(&mut tracker, start, (30, height), 119, 0, 10_000);
+        assert!(!still.stalled);
+        let (stalled, _) = feed(&mut tracker, start, (149, height), 1, 0, 10_000);
+        assert!(stalled.stalled);
+        assert_eq!(stalled.eta, None);
+
+        // A new block ends the stall
+        let (moving, _) = feed(&mut tracker, start, (150, height), 1, 10, 10_000);
+        assert!(!moving.stalled);
+        assert!(moving.eta.is_some());
+    }
+
+    #[test]
+    fn reaching_the_target_leaves_nothing_remaining() {
+        let mut tracker = SyncTracker::new();
+        let start = Instant::now();
+        let synced = tracker.record(start, 500, Some(400), 0).unwrap();
+        assert!(synced.is_synced());
+        assert_eq!(synced.eta, Some(Duration::ZERO));
+        assert_eq!(synced.fraction(), 1.0);
+
+        // At the tip a lack of new blocks is no stall
+        let (idle, _) = feed(&mut tracker, start, (0, 500), 300, 0, 500);
+        assert!(!idle.stalled);
+        assert_eq!(idle.eta, Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn without_a_target_the_window_starts_over() {
+        let mut tracker = SyncTracker::new();
+        let start = Instant::now();
+        tracker.record(start, 0, Some(10_000), 0);
+        let (_, height) = feed(&mut tracker, start, (0, 0), 30, 10, 10_000);
+
+        assert_eq!(tracker.record(start + SECOND * 31, height, None, 0), None);
+        assert_eq!(tracker.progress(), None);
+        let restarted = tracker
+            .record(start + SECOND * 32, height, Some(10_000), 0)
+            .unwrap();
+        assert_eq!(restarted.blocks_per_sec, 0.0);
+        assert_eq!(restarted.eta, None);
+    }
+}
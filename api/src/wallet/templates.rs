@@ -0,0 +1,449 @@
+//! Saved payments the user makes repeatedly, optionally with a reminder.
+//!
+//! A template keeps the recipient's address itself, so removing the contact
+//! it was made from leaves the template usable; `referencing_contact` finds
+//! the templates to warn about first.
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::wallet::send::SendRequest;
+use crate::wallet::storage::StorageManager;
+use crate::wallet::{Address, Wallet, WalletError, WalletResult};
+
+/// Payment templates, saved through `StorageManager` in the wallet data dir
+pub const TEMPLATES_FILE: &str = "payment_templates.json";
+
+/// How much fee a templated payment offers, relative to the normal fee
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FeePriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl FeePriority {
+    pub const ALL: [FeePriority; 3] = [FeePriority::Low, FeePriority::Normal, FeePriority::High];
+
+    /// Fee for this priority: half, all or twice `normal_fee`, never below 1
+    pub fn fee(&self, normal_fee: u64) -> u64 {
+        let fee = match self {
+            FeePriority::Low => normal_fee / 2,
+            FeePriority::Normal => normal_fee,
+            FeePriority::High => normal_fee.saturating_mul(2),
+        };
+        fee.max(1)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FeePriority::Low => "Low",
+            FeePriority::Normal => "Normal",
+            FeePriority::High => "High",
+        }
+    }
+}
+
+impl fmt::Display for FeePriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaymentTemplate {
+    pub name: String,
+    pub recipient: Address,
+    /// Contact the recipient was picked from, if any
+    #[serde(default)]
+    pub contact: Option<String>,
+    /// Amount in base units
+    pub amount: u64,
+    /// Note for the user, applied as the label of each payment made from it
+    #[serde(default)]
+    pub memo: String,
+    #[serde(default)]
+    pub fee_priority: FeePriority,
+    /// Remind the user this many days after the last payment
+    #[serde(default)]
+    pub reminder_days: Option<u32>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub last_paid: Option<DateTime<Utc>>,
+}
+
+impl PaymentTemplate {
+    pub fn new(name: &str, recipient: Address, amount: u64) -> Self {
+        Self {
+            name: name.trim().to_string(),
+            recipient,
+            contact: None,
+            amount,
+            memo: String::new(),
+            fee_priority: FeePriority::default(),
+            reminder_days: None,
+            created_at: Utc::now(),
+            last_paid: None,
+        }
+    }
+
+    /// When the next payment is due, if a reminder is set. Counts from the
+    /// last payment, or from creation if it was never used.
+    pub fn next_due(&self) -> Option<DateTime<Utc>> {
+        let days = self.reminder_days?;
+        Some(self.last_paid.unwrap_or(self.created_at) + TimeDelta::days(days.into()))
+    }
+
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.next_due().is_some_and(|due| due <= now)
+    }
+
+    /// Send request paying this template from `key_name`, with the fee its
+    /// priority gives relative to `normal_fee`
+    pub fn to_send_request(&self, key_name: &str, normal_fee: u64) -> SendRequest {
+        SendRequest {
+            key_name: key_name.to_string(),
+            to: self.recipient.clone(),
+            amount: self.amount,
+            fee: self.fee_priority.fee(normal_fee),
+            min_confirmations: None,
+            valid_for_blocks: None,
+            confirmed_amount: None,
+            force_broadcast: false,
+        }
+    }
+
+    fn validate(&self) -> WalletResult<()> {
+        if self.name.trim().is_empty() {
+            return Err(WalletError::Template(
+                "Template name must not be empty".to_string(),
+            ));
+        }
+        if self.amount == 0 {
+            return Err(WalletError::Template(
+                "Template amount must be more than zero".to_string(),
+            ));
+        }
+        if self.reminder_days == Some(0) {
+            return Err(WalletError::Template(
+                "Reminder interval must be at least one day".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Payment templates in insertion order; names are unique, ignoring case
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PaymentTemplates {
+    templates: Vec<PaymentTemplate>,
+}
+
+impl PaymentTemplates {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the saved templates, or none if none were saved
+    pub async fn load(storage: &StorageManager) -> WalletResult<Self> {
+        if !storage.exists(TEMPLATES_FILE) {
+            return Ok(Self::new());
+        }
+        storage.load(TEMPLATES_FILE).await
+    }
+
+    pub async fn save(&self, storage: &StorageManager) -> WalletResult<()> {
+        storage.save(TEMPLATES_FILE, self).await
+    }
+
+    pub fn templates(&self) -> &[PaymentTemplate] {
+        &self.templates
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PaymentTemplate> {
+        self.templates
+            .iter()
+            .find(|template| template.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    pub fn add(&mut self, template: PaymentTemplate) -> WalletResult<()> {
+        template.validate()?;
+        if self.get(&template.name).is_some() {
+            return Err(WalletError::Template(format!(
+                "A template named {} already exists",
+                template.name
+            )));
+        }
+        self.templates.push(template);
+        Ok(())
+    }
+
+    /// Replace the template called `name`; the replacement may rename it
+    pub fn update(&mut self, name: &str, template: PaymentTemplate) -> WalletResult<()> {
+        template.validate()?;
+        let index = self.position(name)?;
+        if !template
+            .name
+            .eq_ignore_ascii_case(&self.templates[index].name)
+            && self.get(&template.name).is_some()
+        {
+            return Err(WalletError::Template(format!(
+                "A template named {} already exists",
+                template.name
+            )));
+        }
+        self.templates[index] = template;
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<PaymentTemplate> {
+        let index = self.position(name).ok()?;
+        Some(self.templates.remove(index))
+    }
+
+    /// Record a payment made from the template, restarting its reminder
+    pub fn mark_paid(&mut self, name: &str, at: DateTime<Utc>) -> WalletResult<()> {
+        let index = self.position(name)?;
+        self.templates[index].last_paid = Some(at);
+        Ok(())
+    }
+
+    /// Templates whose reminder has come due at `now`, most overdue first
+    pub fn due(&self, now: DateTime<Utc>) -> Vec<&PaymentTemplate> {
+        let mut due: Vec<&PaymentTemplate> = self
+            .templates
+            .iter()
+            .filter(|template| template.is_due(now))
+            .collect();
+        due.sort_by_key(|template| template.next_due());
+        due
+    }
+
+    /// Templates made from the contact `name`, to warn about before it is removed
+    pub fn referencing_contact(&self, name: &str) -> Vec<&PaymentTemplate> {
+        self.templates
+            .iter()
+            .filter(|template| {
+                template
+                    .contact
+                    .as_deref()
+                    .is_some_and(|contact| contact.eq_ignore_ascii_case(name))
+            })
+            .collect()
+    }
+
+    fn position(&self, name: &str) -> WalletResult<usize> {
+        self.templates
+            .iter()
+            .position(|template| template.name.eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| WalletError::Template(format!("No template named {}", name)))
+    }
+}
+
+impl Wallet {
+    pub fn payment_templates(&self) -> &PaymentTemplates {
+        &self.templates
+    }
+
+    /// Template changes; fails in read-only mode
+    pub fn payment_templates_mut(&mut self) -> WalletResult<&mut PaymentTemplates> {
+        self.ensure_writable()?;
+        Ok(&mut self.templates)
+    }
+
+    /// Record that `tx_id` paid the template called `name`: restart its
+    /// reminder and label the transaction with the template's memo
+    pub fn record_template_payment(
+        &mut self,
+        name: &str,
+        tx_id: &str,
+        at: DateTime<Utc>,
+    ) -> WalletResult<()> {
+        self.ensure_writable()?;
+        self.templates.mark_paid(name, at)?;
+        let memo = self
+            .templates
+            .get(name)
+            .map(|template| template.memo.clone());
+        if let Some(memo) = memo.filter(|memo| !memo.trim().is_empty()) {
+            self.transactions.set_label(tx_id, &memo);
+        }
+        Ok(())
+    }
+
+    /// Load the templates saved in the wallet data dir
+    pub async fn load_payment_templates(&mut self) -> WalletResult<()> {
+        let Some(data_dir) = self.data_dir.clone() else {
+            return Ok(());
+        };
+        let storage = StorageManager::new(data_dir)?;
+        self.templates = PaymentTemplates::load(&storage).await?;
+        Ok(())
+    }
+
+    /// Save the templates; does nothing in read-only mode
+    pub async fn save_payment_templates(&self) -> WalletResult<()> {
+        let Some(data_dir) = self.data_dir.clone().filter(|_| !self.read_only) else {
+            return Ok(());
+        };
+        let storage = StorageManager::new(data_dir)?;
+        self.templates.save(&storage).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_address, fixture_time, TempDir};
+
+    const DAY: i64 = 86_400;
+
+    fn rent() -> PaymentTemplate {
+        PaymentTemplate {
+            memo: "Rent".to_string(),
+            fee_priority: FeePriority::High,
+            reminder_days: Some(30),
+            created_at: fixture_time(0),
+            contact: Some("Landlord".to_string()),
+            ..PaymentTemplate::new(" rent ", fake_address(), 1_200)
+        }
+    }
+
+    #[test]
+    fn a_template_maps_onto_a_send_request() {
+        let template = rent();
+        let request = template.to_send_request("main", 10);
+        assert_eq!(request.key_name, "main");
+        assert_eq!(request.to, template.recipient);
+        assert_eq!(request.amount, 1_200);
+        assert_eq!(request.fee, 20);
+        assert_eq!(request.confirmed_amount, None);
+    }
+
+    #[test]
+    fn fee_priorities_scale_the_normal_fee() {
+        assert_eq!(FeePriority::Low.fee(10), 5);
+        assert_eq!(FeePriority::Normal.fee(10), 10);
+        assert_eq!(FeePriority::High.fee(10), 20);
+        assert_eq!(FeePriority::Low.fee(1), 1);
+        assert_eq!(FeePriority::High.fee(u64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn names_are_trimmed_and_unique_ignoring_case() {
+        let mut templates = PaymentTemplates::new();
+        templates.add(rent()).unwrap();
+        assert_eq!(templates.get("RENT").unwrap().name, "rent");
+        assert!(templates.add(rent()).is_err());
+
+        let mut renamed = rent();
+        renamed.name = "Lease".to_string();
+        templates.add(renamed.clone()).unwrap();
+        assert!(templates.update("lease", rent()).is_err());
+        renamed.amount = 900;
+        templates.update("LEASE", renamed).unwrap();
+        assert_eq!(templates.get("lease").unwrap().amount, 900);
+
+        assert!(templates.remove("Rent").is_some());
+        assert!(templates.remove("Rent").is_none());
+        assert_eq!(templates.templates().len(), 1);
+    }
+
+    #[test]
+    fn invalid_templates_are_refused() {
+        let mut templates = PaymentTemplates::new();
+        for template in [
+            PaymentTemplate::new("  ", fake_address(), 1),
+            PaymentTemplate::new("zero", fake_address(), 0),
+            PaymentTemplate {
+                reminder_days: Some(0),
+                ..PaymentTemplate::new("daily", fake_address(), 1)
+            },
+        ] {
+            assert!(templates.add(template).is_err());
+        }
+        assert!(templates.update("missing", rent()).is_err());
+    }
+
+    fn due_names(templates: &PaymentTemplates, now: DateTime<Utc>) -> Vec<String> {
+        templates
+            .due(now)
+            .iter()
+            .map(|template| template.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn reminders_fall_due_after_the_last_payment() {
+        let mut templates = PaymentTemplates::new();
+        templates.add(rent()).unwrap();
+        templates
+            .add(PaymentTemplate {
+                reminder_days: Some(7),
+                created_at: fixture_time(0),
+                ..PaymentTemplate::new("gym", fake_address(), 50)
+            })
+            .unwrap();
+        templates
+            .add(PaymentTemplate::new("no reminder", fake_address(), 5))
+            .unwrap();
+
+        assert!(due_names(&templates, fixture_time(7 * DAY - 1)).is_empty());
+        assert_eq!(due_names(&templates, fixture_time(7 * DAY)), ["gym"]);
+        assert_eq!(
+            due_names(&templates, fixture_time(30 * DAY)),
+            ["gym", "rent"]
+        );
+
+        templates.mark_paid("gym", fixture_time(29 * DAY)).unwrap();
+        assert_eq!(due_names(&templates, fixture_time(30 * DAY)), ["rent"]);
+        assert_eq!(
+            templates.get("gym").unwrap().next_due(),
+            Some(fixture_time(36 * DAY))
+        );
+        assert!(templates.mark_paid("missing", fixture_time(0)).is_err());
+    }
+
+    #[test]
+    fn templates_made_from_a_contact_are_found_before_it_is_removed() {
+        let mut templates = PaymentTemplates::new();
+        templates.add(rent()).unwrap();
+        templates
+            .add(PaymentTemplate::new("other", fake_address(), 5))
+            .unwrap();
+        let affected = templates.referencing_contact("landlord");
+        assert_eq!(affected.len(), 1);
+        assert_eq!(affected[0].name, "rent");
+        assert!(templates.referencing_contact("Nobody").is_empty());
+    }
+
+    #[tokio::test]
+    async fn templates_survive_a_restart() {
+        let dir = TempDir::new("templates");
+        let mut wallet = Wallet::new();
+        wallet.data_dir = Some(dir.path().to_path_buf());
+        wallet.payment_templates_mut().unwrap().add(rent()).unwrap();
+        wallet.save_payment_templates().await.unwrap();
+
+        let mut restarted = Wallet::new();
+        restarted.data_dir = Some(dir.path().to_path_buf());
+        restarted.load_payment_templates().await.unwrap();
+        assert_eq!(restarted.payment_templates(), wallet.payment_templates());
+    }
+
+    #[test]
+    fn paying_from_a_template_labels_the_transaction_with_its_memo() {
+        let mut wallet = Wallet::new();
+        wallet.payment_templates_mut().unwrap().add(rent()).unwrap();
+        wallet
+            .record_template_payment("rent", "tx1", fixture_time(DAY))
+            .unwrap();
+        assert_eq!(wallet.transactions().label("tx1"), Some("Rent"));
+        assert_eq!(
+            wallet.payment_templates().get("rent").unwrap().last_paid,
+            Some(fixture_time(DAY))
+        );
+    }
+}
@@ -1,22 +1,44 @@
-use crate::wallet::keys::{KeyManager, TransactionInput, TransactionOutput};
-use crate::wallet::{Address, Transaction, TransactionStatus, WalletError, WalletResult};
-use chrono::Utc;
+use crate::wallet::chain::Blockchain;
+use crate::wallet::cold_signing::{PackageInput, UnsignedTransactionPackage};
+use crate::wallet::encoding::{self, Encode};
+use crate::wallet::export::{self, ExportFilter, ExportFormat, ExportSummary, TransactionRow};
+use crate::wallet::keys::{KeyManager, OutPoint, TransactionInput, TransactionOutput};
+use crate::wallet::mempool::Mempool;
+use crate::wallet::reject::Rejection;
+use crate::wallet::{
+    Address, Transaction, TransactionDirection, TransactionOrigin, TransactionStatus, WalletError,
+    WalletResult,
+};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::Write;
 
 /// Transaction builder for creating new transactions
 #[derive(Debug)]
 pub struct TransactionBuilder {
+    /// Chain the signature binds the transaction to
+    chain_id: [u8; 32],
     inputs: Vec<TransactionInput>,
     outputs: Vec<TransactionOutput>,
     fee: u64,
+    /// Last block height the transaction may be mined at
+    expiry_height: Option<u64>,
+    /// Address holding each input, for packages signed elsewhere
+    owners: HashMap<OutPoint, Address>,
 }
 
 impl TransactionBuilder {
-    pub fn new() -> Self {
+    /// Builder for a transaction on the chain with id `chain_id`, as given by
+    /// `BlockchainConfig::chain_id`
+    pub fn new(chain_id: [u8; 32]) -> Self {
         Self {
+            chain_id,
             inputs: Vec::new(),
             outputs: Vec::new(),
             fee: 0,
+            expiry_height: None,
+            owners: HashMap::new(),
         }
     }
 
@@ -25,6 +47,12 @@ impl TransactionBuilder {
         self.inputs.push(input);
     }
 
+    /// Add an input along with the address whose key must sign it
+    pub fn add_owned_input(&mut self, input: TransactionInput, owner: Address) {
+        self.owners.insert(input.previous_output.clone(), owner);
+        self.inputs.push(input);
+    }
+
     /// Add an output to the transaction
     pub fn add_output(&mut self, output: TransactionOutput) {
         self.outputs.push(output);
@@ -35,6 +63,12 @@ impl TransactionBuilder {
         self.fee = fee;
     }
 
+    /// Make the transaction invalid in blocks above `height`. The signature
+    /// covers the expiry, so it can't be extended after signing.
+    pub fn set_expiry_height(&mut self, height: u64) {
+        self.expiry_height = Some(height);
+    }
+
     /// Calculate total input amount
     pub fn total_input(&self) -> u64 {
         self.inputs.iter().map(|input| input.amount).sum()
@@ -78,43 +112,174 @@ impl TransactionBuilder {
         self.validate()?;
 
         // Create transaction hash
-        let tx_hash = key_manager.create_transaction_hash(&self.inputs, &self.outputs, self.fee);
+        let tx_hash = key_manager.create_transaction_hash(
+            &self.chain_id,
+            &self.inputs,
+            &self.outputs,
+            self.fee,
+            self.expiry_height,
+        );
 
         // Sign the transaction
         let signature = key_manager.sign_with_key(key_name, &tx_hash)?;
 
-        // Create transaction ID (in a real implementation, this would be more sophisticated)
-        let tx_id = hex::encode(&tx_hash);
-
-        let signed_tx = SignedTransaction {
-            id: tx_id.clone(),
+        let mut signed_tx = SignedTransaction {
+            id: String::new(),
+            chain_id: self.chain_id,
             inputs: self.inputs.clone(),
             outputs: self.outputs.clone(),
             fee: self.fee,
+            expiry_height: self.expiry_height,
             signature: signature.to_vec(),
             hash: tx_hash,
         };
+        signed_tx.id = encoding::transaction_id(&signed_tx);
 
         Ok(signed_tx)
     }
+
+    /// Package the transaction to be signed elsewhere. Every input must have
+    /// been added with `add_owned_input`.
+    pub fn export_unsigned(&self) -> WalletResult<UnsignedTransactionPackage> {
+        self.validate()?;
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|input| {
+                let owner = self.owners.get(input.outpoint()).ok_or_else(|| {
+                    WalletError::Transaction(format!(
+                        "Owner of input {} is unknown",
+                        input.outpoint()
+                    ))
+                })?;
+                Ok(PackageInput {
+                    input: input.clone(),
+                    owner: owner.clone(),
+                })
+            })
+            .collect::<WalletResult<Vec<_>>>()?;
+        Ok(UnsignedTransactionPackage::new(
+            self.chain_id,
+            inputs,
+            self.outputs.clone(),
+            self.fee,
+            self.expiry_height,
+        ))
+    }
 }
 
 /// A signed transaction ready for broadcast
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SignedTransaction {
     pub id: String,
+    /// Chain the transaction was signed for. Transactions stored before chain
+    /// ids read as all zeroes, which no chain accepts.
+    #[serde(default)]
+    pub chain_id: [u8; 32],
     pub inputs: Vec<TransactionInput>,
     pub outputs: Vec<TransactionOutput>,
     pub fee: u64,
+    /// Last block height the transaction may be mined at; signed along with
+    /// the rest
+    #[serde(default)]
+    pub expiry_height: Option<u64>,
     pub signature: Vec<u8>,
     pub hash: Vec<u8>,
 }
 
+impl SignedTransaction {
+    /// Whether a block at `height` may no longer include it
+    pub fn is_expired_at(&self, height: u64) -> bool {
+        self.expiry_height.is_some_and(|expiry| height > expiry)
+    }
+
+    /// Fail unless the transaction was signed for the chain with id `expected`
+    pub fn check_chain_id(&self, expected: &[u8; 32]) -> WalletResult<()> {
+        if self.chain_id != *expected {
+            return Err(WalletError::ChainIdMismatch {
+                expected: hex::encode(expected),
+                found: hex::encode(self.chain_id),
+            });
+        }
+        Ok(())
+    }
+
+    /// Outputs spent by this transaction, deduplicated and in sorted order
+    pub fn input_outpoints(&self) -> BTreeSet<&OutPoint> {
+        self.inputs.iter().map(TransactionInput::outpoint).collect()
+    }
+
+    /// Whether both transactions spend at least one common output. A transaction
+    /// never conflicts with itself.
+    pub fn conflicts_with(&self, other: &SignedTransaction) -> bool {
+        if self.id == other.id {
+            return false;
+        }
+        let ours = self.input_outpoints();
+        other
+            .inputs
+            .iter()
+            .any(|input| ours.contains(input.outpoint()))
+    }
+}
+
+/// Whether the block a transaction was confirmed in provably contains it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MerkleProofStatus {
+    /// Not in a block yet
+    Unconfirmed,
+    /// The confirming block isn't in the local chain
+    Unavailable,
+    /// The block contains the transaction and its merkle root checks out
+    Verified,
+    /// The block at the confirmed height doesn't commit to the transaction
+    Invalid,
+}
+
+/// One output of a transaction, as shown in its detail view
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputDetail {
+    pub index: u32,
+    pub address: String,
+    pub amount: u64,
+    pub is_mine: bool,
+    /// Label the user gave the address, if it is one of theirs
+    #[serde(default)]
+    pub address_label: Option<String>,
+}
+
+/// Full picture of a transaction for support and inspection
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransactionDetail {
+    pub transaction: Transaction,
+    pub label: Option<String>,
+    pub outputs: Vec<OutputDetail>,
+    /// Fee per byte of the canonical encoding, when the signed form and the
+    /// fee are known
+    pub fee_rate: Option<f64>,
+    /// Blocks on top of and including the confirming one; 0 while pending
+    pub confirmations: u64,
+    pub in_mempool: bool,
+    pub proof: MerkleProofStatus,
+    /// Canonical encoding of the signed transaction, hex-encoded
+    pub raw_hex: Option<String>,
+    /// Child spending this transaction's output to speed it up
+    #[serde(default)]
+    pub accelerated_by: Option<String>,
+}
+
 /// Transaction manager for handling transaction lifecycle
 #[derive(Debug)]
 pub struct TransactionManager {
     pending_transactions: Vec<Transaction>,
     confirmed_transactions: Vec<Transaction>,
+    /// Pending transactions that can no longer be mined, e.g. replaced by a
+    /// double spend
+    failed_transactions: Vec<Transaction>,
+    /// Signed form of every recorded transaction, for rebroadcast and raw export
+    signed: HashMap<String, SignedTransaction>,
+    /// User labels by transaction id
+    labels: HashMap<String, String>,
 }
 
 impl TransactionManager {
@@ -122,28 +287,97 @@ impl TransactionManager {
         Self {
             pending_transactions: Vec::new(),
             confirmed_transactions: Vec::new(),
+            failed_transactions: Vec::new(),
+            signed: HashMap::new(),
+            labels: HashMap::new(),
         }
     }
 
-    /// Add a pending transaction
+    /// Add a pending transaction without knowing which addresses are the wallet's
     pub fn add_pending_transaction(&mut self, signed_tx: SignedTransaction, is_outgoing: bool) {
-        let transaction = Transaction {
+        self.add_classified_transaction(signed_tx, is_outgoing, |_| false);
+    }
+
+    /// Add a pending transaction, classifying it as incoming, outgoing or internal.
+    /// Outputs paying `is_mine` addresses count as change (or receipts), so the
+    /// recorded amount is what actually crosses the wallet boundary.
+    pub fn add_classified_transaction(
+        &mut self,
+        signed_tx: SignedTransaction,
+        funded_by_wallet: bool,
+        is_mine: impl Fn(&Address) -> bool,
+    ) {
+        let transaction = self.classify(signed_tx, funded_by_wallet, is_mine);
+        self.pending_transactions.push(transaction);
+    }
+
+    /// Add a transaction signed outside the wallet that spent its notes and
+    /// was confirmed at `block_height`, classified as one the wallet funded.
+    /// `from_address` is the owner of the first note it spent.
+    pub fn add_external_spend(
+        &mut self,
+        signed_tx: SignedTransaction,
+        fee_known: bool,
+        from_address: Option<Address>,
+        block_height: u64,
+        confirmed_at: DateTime<Utc>,
+        is_mine: impl Fn(&Address) -> bool,
+    ) -> Transaction {
+        let mut transaction = self.classify(signed_tx, true, is_mine);
+        transaction.origin = TransactionOrigin::External { fee_known };
+        transaction.from_address = from_address;
+        transaction.status = TransactionStatus::Confirmed { block_height };
+        transaction.created_at = confirmed_at;
+        transaction.confirmed_at = Some(confirmed_at);
+        self.confirmed_transactions.push(transaction.clone());
+        transaction
+    }
+
+    /// Record of `signed_tx` as a new pending transaction, keeping its signed
+    /// form
+    fn classify(
+        &mut self,
+        signed_tx: SignedTransaction,
+        funded_by_wallet: bool,
+        is_mine: impl Fn(&Address) -> bool,
+    ) -> Transaction {
+        let owned = |output: &TransactionOutput| {
+            Address::from_string(&output.recipient_address)
+                .map(|address| is_mine(&address))
+                .unwrap_or(false)
+        };
+        let (ours, theirs): (Vec<&TransactionOutput>, Vec<&TransactionOutput>) =
+            signed_tx.outputs.iter().partition(|output| owned(output));
+        let sum = |outputs: &[&TransactionOutput]| outputs.iter().map(|o| o.amount).sum::<u64>();
+
+        let (direction, amount, recipient) = if !funded_by_wallet {
+            (TransactionDirection::Incoming, sum(&ours), ours.first())
+        } else if theirs.is_empty() {
+            (TransactionDirection::Internal, sum(&ours), ours.first())
+        } else {
+            (TransactionDirection::Outgoing, sum(&theirs), theirs.first())
+        };
+        let to_address = recipient.and_then(|o| Address::from_string(&o.recipient_address).ok());
+        self.signed.insert(signed_tx.id.clone(), signed_tx.clone());
+
+        Transaction {
             id: signed_tx.id,
             status: TransactionStatus::Pending,
-            amount: signed_tx.outputs.iter().map(|o| o.amount).sum(),
+            amount,
             fee: signed_tx.fee,
             from_address: None, // TODO: Determine from inputs
-            to_address: signed_tx
-                .outputs
-                .first()
-                .map(|o| Address::from_string(&o.recipient_address).ok())
-                .flatten(),
+            to_address,
             created_at: Utc::now(),
+            broadcast_at: None,
             confirmed_at: None,
-            is_outgoing,
-        };
-
-        self.pending_transactions.push(transaction);
+            is_outgoing: funded_by_wallet,
+            direction,
+            inputs: signed_tx.inputs,
+            outputs: signed_tx.outputs,
+            rejections: Vec::new(),
+            origin: TransactionOrigin::Wallet,
+            accelerates: None,
+        }
     }
 
     /// Confirm a transaction
@@ -167,11 +401,101 @@ impl TransactionManager {
         }
     }
 
-    /// Get all transactions (pending + confirmed)
+    /// Mark a pending transaction as never to be mined, keeping it in the
+    /// history with `reason`
+    pub fn fail_transaction(&mut self, tx_id: &str, reason: &str) -> WalletResult<()> {
+        let mut transaction = self
+            .take_pending(tx_id)
+            .ok_or_else(|| WalletError::Transaction(format!("Transaction {} not found", tx_id)))?;
+        transaction.status = TransactionStatus::Failed {
+            reason: reason.to_string(),
+        };
+        self.failed_transactions.push(transaction);
+        Ok(())
+    }
+
+    /// Drop a pending transaction from the history altogether, with its
+    /// signed form
+    pub fn remove_pending(&mut self, tx_id: &str) -> Option<Transaction> {
+        let transaction = self.take_pending(tx_id)?;
+        self.signed.remove(tx_id);
+        Some(transaction)
+    }
+
+    fn take_pending(&mut self, tx_id: &str) -> Option<Transaction> {
+        let pos = self
+            .pending_transactions
+            .iter()
+            .position(|tx| tx.id == tx_id)?;
+        Some(self.pending_transactions.remove(pos))
+    }
+
+    /// Forget the oldest confirmed and failed transactions: those settled
+    /// before `before`, then more until the rest serialize to at most
+    /// `max_bytes`. Pending transactions are always kept. Returns the
+    /// transactions forgotten and their serialized size.
+    pub fn prune_history(
+        &mut self,
+        before: Option<DateTime<Utc>>,
+        max_bytes: Option<u64>,
+    ) -> (usize, u64) {
+        let settled_at = |tx: &Transaction| tx.confirmed_at.unwrap_or(tx.created_at);
+        let size = |tx: &Transaction| serde_json::to_vec(tx).map_or(0, |json| json.len() as u64);
+        let mut settled: Vec<(DateTime<Utc>, String, u64)> = self
+            .confirmed_transactions
+            .iter()
+            .chain(&self.failed_transactions)
+            .map(|tx| (settled_at(tx), tx.id.clone(), size(tx)))
+            .collect();
+        settled.sort();
+
+        let mut total: u64 = settled.iter().map(|(_, _, bytes)| bytes).sum();
+        let mut pruned = HashSet::new();
+        let mut bytes = 0;
+        for (at, id, size) in settled {
+            let old = before.is_some_and(|before| at < before);
+            let over = max_bytes.is_some_and(|max| total > max);
+            if !old && !over {
+                break;
+            }
+            total -= size;
+            bytes += size;
+            pruned.insert(id);
+        }
+
+        self.confirmed_transactions
+            .retain(|tx| !pruned.contains(&tx.id));
+        self.failed_transactions
+            .retain(|tx| !pruned.contains(&tx.id));
+        for id in &pruned {
+            self.signed.remove(id);
+            self.labels.remove(id);
+        }
+        (pruned.len(), bytes)
+    }
+
+    /// Whether any transaction with this id is recorded, whatever its status
+    pub fn contains(&self, tx_id: &str) -> bool {
+        self.pending_transactions
+            .iter()
+            .chain(&self.confirmed_transactions)
+            .chain(&self.failed_transactions)
+            .any(|tx| tx.id == tx_id)
+    }
+
+    /// Transactions recorded, whatever their status
+    pub fn count(&self) -> usize {
+        self.pending_transactions.len()
+            + self.confirmed_transactions.len()
+            + self.failed_transactions.len()
+    }
+
+    /// Get all transactions (pending, confirmed and failed)
     pub fn get_all_transactions(&self) -> Vec<Transaction> {
         let mut all_transactions = Vec::new();
         all_transactions.extend(self.pending_transactions.clone());
         all_transactions.extend(self.confirmed_transactions.clone());
+        all_transactions.extend(self.failed_transactions.clone());
 
         // Sort by creation time (newest first)
         all_transactions.sort_by(|a, b| b.created_at.cmp(&a.created_at));
@@ -179,13 +503,531 @@ impl TransactionManager {
         all_transactions
     }
 
+    /// Write the transactions `filter` selects, newest first, as `format`.
+    /// Rows are streamed; `progress` gets the count written after each and
+    /// returns false to cancel, as described in `export`.
+    pub fn export_to_writer(
+        &self,
+        writer: impl Write,
+        format: ExportFormat,
+        filter: &ExportFilter,
+        progress: impl FnMut(u64) -> bool,
+    ) -> WalletResult<ExportSummary> {
+        let mut transactions: Vec<&Transaction> = self
+            .pending_transactions
+            .iter()
+            .chain(&self.confirmed_transactions)
+            .chain(&self.failed_transactions)
+            .filter(|tx| filter.matches(tx))
+            .collect();
+        transactions.sort_by_key(|tx| std::cmp::Reverse(tx.created_at));
+        let rows = transactions
+            .into_iter()
+            .map(|tx| TransactionRow::new(tx, self.label(&tx.id)));
+        export::write_records(writer, format, rows, progress)
+    }
+
     /// Get pending transactions
     pub fn get_pending_transactions(&self) -> &[Transaction] {
         &self.pending_transactions
     }
 
+    /// Signed form of a pending transaction this wallet sent
+    pub fn pending_signed(&self, tx_id: &str) -> Option<&SignedTransaction> {
+        self.pending_transactions
+            .iter()
+            .any(|tx| tx.id == tx_id && tx.is_outgoing)
+            .then(|| self.signed.get(tx_id))
+            .flatten()
+    }
+
+    /// Signed form of any recorded transaction
+    pub fn signed(&self, tx_id: &str) -> Option<&SignedTransaction> {
+        self.signed.get(tx_id)
+    }
+
+    /// Record when a pending transaction was handed to the node
+    pub fn mark_broadcast(&mut self, tx_id: &str, at: DateTime<Utc>) {
+        if let Some(tx) = self
+            .pending_transactions
+            .iter_mut()
+            .find(|tx| tx.id == tx_id)
+        {
+            tx.broadcast_at = Some(at);
+        }
+    }
+
+    /// Mark pending transaction `child_id` as accelerating `parent_id`
+    pub fn set_accelerates(&mut self, child_id: &str, parent_id: &str) {
+        if let Some(tx) = self
+            .pending_transactions
+            .iter_mut()
+            .find(|tx| tx.id == child_id)
+        {
+            tx.accelerates = Some(parent_id.to_string());
+        }
+    }
+
+    /// The pending or confirmed child accelerating `parent_id`, if any
+    pub fn accelerated_by(&self, parent_id: &str) -> Option<&Transaction> {
+        self.pending_transactions
+            .iter()
+            .chain(&self.confirmed_transactions)
+            .find(|tx| tx.accelerates.as_deref() == Some(parent_id))
+    }
+
+    /// Replace the relay rejections kept for a recorded transaction
+    pub fn set_rejections(&mut self, tx_id: &str, rejections: Vec<Rejection>) {
+        if let Some(tx) = self
+            .pending_transactions
+            .iter_mut()
+            .chain(&mut self.confirmed_transactions)
+            .chain(&mut self.failed_transactions)
+            .find(|tx| tx.id == tx_id)
+        {
+            tx.rejections = rejections;
+        }
+    }
+
+    pub fn label(&self, tx_id: &str) -> Option<&str> {
+        self.labels.get(tx_id).map(String::as_str)
+    }
+
+    /// Set the label shown for a transaction; a blank label removes it
+    pub fn set_label(&mut self, tx_id: &str, label: &str) {
+        let label = label.trim();
+        if label.is_empty() {
+            self.labels.remove(tx_id);
+        } else {
+            self.labels.insert(tx_id.to_string(), label.to_string());
+        }
+    }
+
+    /// Join a recorded transaction with its signed form, label, the mempool and
+    /// the chain. `is_mine` marks outputs paying the wallet; `tip_height` gives
+    /// the confirmation depth.
+    pub fn get_transaction_detail(
+        &self,
+        tx_id: &str,
+        is_mine: impl Fn(&Address) -> bool,
+        tip_height: Option<u64>,
+        mempool: Option<&Mempool>,
+        chain: Option<&Blockchain>,
+    ) -> Option<TransactionDetail> {
+        let transaction = self
+            .pending_transactions
+            .iter()
+            .chain(&self.confirmed_transactions)
+            .chain(&self.failed_transactions)
+            .find(|tx| tx.id == tx_id)?
+            .clone();
+        let signed = self.signed.get(tx_id);
+
+        let outputs = transaction
+            .outputs
+            .iter()
+            .enumerate()
+            .map(|(index, output)| OutputDetail {
+                index: index as u32,
+                address: output.recipient_address.clone(),
+                amount: output.amount,
+                is_mine: Address::from_string(&output.recipient_address)
+                    .is_ok_and(|address| is_mine(&address)),
+                address_label: None,
+            })
+            .collect();
+
+        let (confirmations, proof) = match transaction.status {
+            TransactionStatus::Confirmed { block_height } => {
+                let confirmations = tip_height
+                    .filter(|tip| *tip >= block_height)
+                    .map_or(1, |tip| tip - block_height + 1);
+                let proof = match chain.and_then(|chain| chain.block_at(block_height)) {
+                    Some(block) if block.commits_to(tx_id) => MerkleProofStatus::Verified,
+                    Some(_) => MerkleProofStatus::Invalid,
+                    None => MerkleProofStatus::Unavailable,
+                };
+                (confirmations, proof)
+            }
+            _ => (0, MerkleProofStatus::Unconfirmed),
+        };
+
+        let raw = signed.map(|tx| tx.to_bytes());
+        Some(TransactionDetail {
+            label: self.label(tx_id).map(str::to_string),
+            outputs,
+            fee_rate: raw
+                .as_ref()
+                .filter(|_| transaction.fee_known())
+                .map(|raw| transaction.fee as f64 / raw.len() as f64),
+            confirmations,
+            in_mempool: mempool.is_some_and(|mempool| mempool.contains(tx_id)),
+            proof,
+            raw_hex: raw.map(hex::encode),
+            accelerated_by: self.accelerated_by(tx_id).map(|child| child.id.clone()),
+            transaction,
+        })
+    }
+
     /// Get confirmed transactions
     pub fn get_confirmed_transactions(&self) -> &[Transaction] {
         &self.confirmed_transactions
     }
+
+    /// How many sends, pending or confirmed, paid each outside address. Each
+    /// one after the first links the payments together for anyone watching.
+    pub fn payment_counts(&self) -> HashMap<Address, usize> {
+        let mut counts = HashMap::new();
+        for tx in self
+            .pending_transactions
+            .iter()
+            .chain(&self.confirmed_transactions)
+            .filter(|tx| tx.direction == TransactionDirection::Outgoing)
+        {
+            if let Some(to) = &tx.to_address {
+                *counts.entry(to.clone()).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{
+        fake_address, fake_coinbase, fake_signed_transaction, fakenet_chain_config, fixture_time,
+    };
+    use crate::wallet::keys::NockchainTransaction;
+
+    /// The same payment built and signed for `chain_id`
+    fn signed_for(chain_id: [u8; 32]) -> SignedTransaction {
+        let mut builder = TransactionBuilder::new(chain_id);
+        builder.add_input(TransactionInput {
+            previous_output: OutPoint::new("aa", 0),
+            amount: 1_000,
+        });
+        builder.add_output(TransactionOutput {
+            amount: 990,
+            recipient_address: "nock1recipient".to_string(),
+        });
+        builder.set_fee(10);
+        builder.build_and_sign(&KeyManager::new(), "main").unwrap()
+    }
+
+    #[test]
+    fn a_transaction_signed_for_one_chain_is_rejected_on_another() {
+        let fakenet = crate::wallet::BlockchainConfig::fakenet().chain_id();
+        let mainnet = crate::wallet::BlockchainConfig::default().chain_id();
+        assert_ne!(fakenet, mainnet);
+
+        let on_fakenet = signed_for(fakenet);
+        let on_mainnet = signed_for(mainnet);
+        assert_eq!(on_fakenet.chain_id, fakenet);
+        // The chain id is in what gets signed, so nothing carries across
+        assert_ne!(on_fakenet.hash, on_mainnet.hash);
+        assert_ne!(on_fakenet.id, on_mainnet.id);
+
+        assert!(on_fakenet.check_chain_id(&fakenet).is_ok());
+        assert!(on_mainnet.check_chain_id(&mainnet).is_ok());
+        match on_fakenet.check_chain_id(&mainnet) {
+            Err(WalletError::ChainIdMismatch { expected, found }) => {
+                assert_eq!(expected, hex::encode(mainnet));
+                assert_eq!(found, hex::encode(fakenet));
+            }
+            other => panic!("expected a chain id mismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn transactions_stored_before_chain_ids_are_valid_nowhere() {
+        let mut json = serde_json::to_value(signed_for([1; 32])).unwrap();
+        json.as_object_mut().unwrap().remove("chain_id");
+        let old: SignedTransaction = serde_json::from_value(json).unwrap();
+        assert_eq!(old.chain_id, [0; 32]);
+        for config in [
+            crate::wallet::BlockchainConfig::fakenet(),
+            crate::wallet::BlockchainConfig::default(),
+        ] {
+            assert!(old.check_chain_id(&config.chain_id()).is_err());
+        }
+    }
+
+    #[test]
+    fn classifies_by_funding_and_recipients() {
+        let (mine, theirs) = (fake_address(), fake_address());
+        let is_mine = |address: &Address| *address == mine;
+        let mut manager = TransactionManager::new();
+
+        let incoming = fake_signed_transaction().paying(&mine, 500).build();
+        let outgoing = fake_signed_transaction()
+            .paying(&theirs, 300)
+            .paying(&mine, 90)
+            .build();
+        let internal = fake_signed_transaction().paying(&mine, 70).build();
+        manager.add_classified_transaction(incoming.clone(), false, is_mine);
+        manager.add_classified_transaction(outgoing.clone(), true, is_mine);
+        manager.add_classified_transaction(internal.clone(), true, is_mine);
+
+        let pending = manager.get_pending_transactions();
+        assert_eq!(pending[0].direction, TransactionDirection::Incoming);
+        assert_eq!(pending[0].amount, 500);
+        assert_eq!(pending[1].direction, TransactionDirection::Outgoing);
+        assert_eq!(pending[1].amount, 300);
+        assert_eq!(pending[1].to_address, Some(theirs.clone()));
+        assert_eq!(pending[2].direction, TransactionDirection::Internal);
+        assert!(manager.pending_signed(&outgoing.id).is_some());
+        assert!(manager.pending_signed(&incoming.id).is_none());
+        assert_eq!(manager.payment_counts().get(&theirs), Some(&1));
+    }
+
+    #[test]
+    fn confirming_failing_and_removing_pending_transactions() {
+        let mut manager = TransactionManager::new();
+        let (a, b, c) = (
+            fake_signed_transaction().build(),
+            fake_signed_transaction().build(),
+            fake_signed_transaction().build(),
+        );
+        for tx in [&a, &b, &c] {
+            manager.add_pending_transaction(tx.clone(), true);
+        }
+
+        manager.confirm_transaction(&a.id, 4).unwrap();
+        manager.fail_transaction(&b.id, "double spent").unwrap();
+        assert!(manager.remove_pending(&c.id).is_some());
+        assert!(manager.confirm_transaction(&c.id, 4).is_err());
+
+        assert_eq!(manager.count(), 2);
+        assert!(manager.get_pending_transactions().is_empty());
+        assert_eq!(
+            manager.get_confirmed_transactions()[0].status,
+            TransactionStatus::Confirmed { block_height: 4 }
+        );
+        assert!(manager.signed(&c.id).is_none());
+        assert!(manager.get_all_transactions().iter().any(|tx| tx.id == b.id
+            && tx.status
+                == TransactionStatus::Failed {
+                    reason: "double spent".to_string()
+                }));
+    }
+
+    #[test]
+    fn pruning_keeps_pending_and_forgets_old_settled_transactions() {
+        let mut manager = TransactionManager::new();
+        let (old, pending) = (
+            fake_signed_transaction().build(),
+            fake_signed_transaction().build(),
+        );
+        manager.add_pending_transaction(old.clone(), true);
+        manager.add_pending_transaction(pending.clone(), true);
+        manager.set_label(&old.id, "rent");
+        manager.confirm_transaction(&old.id, 1).unwrap();
+
+        let (pruned, bytes) =
+            manager.prune_history(Some(Utc::now() + chrono::Duration::days(1)), None);
+        assert_eq!(pruned, 1);
+        assert!(bytes > 0);
+        assert!(!manager.contains(&old.id));
+        assert!(manager.label(&old.id).is_none());
+        assert!(manager.contains(&pending.id));
+        assert_eq!(manager.prune_history(Some(fixture_time(0)), None), (0, 0));
+    }
+
+    #[test]
+    fn labels_trim_and_clear() {
+        let mut manager = TransactionManager::new();
+        manager.set_label("tx", "  coffee ");
+        assert_eq!(manager.label("tx"), Some("coffee"));
+        manager.set_label("tx", "   ");
+        assert_eq!(manager.label("tx"), None);
+    }
+
+    #[test]
+    fn detail_counts_confirmations_and_links_accelerations() {
+        let mine = fake_address();
+        let mut manager = TransactionManager::new();
+        let parent = fake_signed_transaction().paying(&mine, 800).build();
+        let child = fake_signed_transaction().paying(&mine, 700).build();
+        manager.add_classified_transaction(parent.clone(), false, |a| *a == mine);
+        manager.add_classified_transaction(child.clone(), true, |a| *a == mine);
+        manager.set_accelerates(&child.id, &parent.id);
+
+        let detail = manager
+            .get_transaction_detail(&parent.id, |a| *a == mine, Some(9), None, None)
+            .unwrap();
+        assert_eq!(detail.accelerated_by, Some(child.id.clone()));
+        assert_eq!(detail.confirmations, 0);
+        assert!(detail.outputs[0].is_mine);
+        assert_eq!(detail.raw_hex, Some(hex::encode(parent.to_bytes())));
+
+        manager.confirm_transaction(&parent.id, 7).unwrap();
+        let detail = manager
+            .get_transaction_detail(&parent.id, |a| *a == mine, Some(9), None, None)
+            .unwrap();
+        assert_eq!(detail.confirmations, 3);
+        assert_eq!(detail.proof, MerkleProofStatus::Unavailable);
+    }
+
+    #[test]
+    fn detail_marks_own_outputs_the_mempool_and_the_label() {
+        let (mine, theirs) = (fake_address(), fake_address());
+        let mut manager = TransactionManager::new();
+        let tx = fake_signed_transaction()
+            .paying(&theirs, 300)
+            .paying(&mine, 90)
+            .build();
+        manager.add_classified_transaction(tx.clone(), true, |a| *a == mine);
+        manager.set_label(&tx.id, "  rent, March ");
+        let mut mempool = Mempool::new();
+
+        let detail = manager
+            .get_transaction_detail(&tx.id, |a| *a == mine, None, Some(&mempool), None)
+            .unwrap();
+        let outputs: Vec<(u32, u64, bool)> = detail
+            .outputs
+            .iter()
+            .map(|output| (output.index, output.amount, output.is_mine))
+            .collect();
+        assert_eq!(outputs, vec![(0, 300, false), (1, 90, true)]);
+        assert_eq!(detail.outputs[1].address, mine.to_string());
+        assert!(!detail.in_mempool);
+        assert_eq!(detail.label.as_deref(), Some("rent, March"));
+        assert_eq!(detail.confirmations, 0);
+        assert_eq!(detail.proof, MerkleProofStatus::Unconfirmed);
+
+        mempool.insert(tx.clone()).unwrap();
+        manager.set_label(&tx.id, " ");
+        let detail = manager
+            .get_transaction_detail(&tx.id, |a| *a == mine, None, Some(&mempool), None)
+            .unwrap();
+        assert!(detail.in_mempool);
+        assert_eq!(detail.label, None);
+        assert!(manager
+            .get_transaction_detail("unknown", |_| false, None, None, None)
+            .is_none());
+    }
+
+    #[test]
+    fn detail_checks_the_confirming_block_commits_to_the_transaction() {
+        const EASY_BITS: u32 = 0x1f7f_ffff;
+        let mut chain = Blockchain::new(crate::wallet::BlockchainConfig {
+            initial_difficulty: EASY_BITS,
+            ..fakenet_chain_config()
+        });
+        let mut genesis = chain.new_block(Vec::new(), EASY_BITS);
+        genesis.mine().unwrap();
+        chain.add_block(genesis).unwrap();
+
+        let included = fake_signed_transaction().build();
+        let elsewhere = fake_signed_transaction().build();
+        let in_block = NockchainTransaction {
+            id: included.id.clone(),
+            inputs: included.inputs.clone(),
+            outputs: included.outputs.clone(),
+            hash: included.hash.clone(),
+            expiry_height: included.expiry_height,
+        };
+        let mut block = chain.new_block(
+            vec![fake_coinbase(&fake_address(), 50), in_block],
+            EASY_BITS,
+        );
+        block.mine().unwrap();
+        chain.add_block(block).unwrap();
+
+        let mut manager = TransactionManager::new();
+        for tx in [&included, &elsewhere] {
+            manager.add_pending_transaction(tx.clone(), false);
+            manager.confirm_transaction(&tx.id, 1).unwrap();
+        }
+        let detail = |tx_id: &str, tip: Option<u64>| {
+            manager
+                .get_transaction_detail(tx_id, |_| false, tip, None, Some(&chain))
+                .unwrap()
+        };
+
+        assert_eq!(
+            detail(&included.id, Some(1)).proof,
+            MerkleProofStatus::Verified
+        );
+        assert_eq!(detail(&included.id, Some(1)).confirmations, 1);
+        assert_eq!(detail(&included.id, Some(4)).confirmations, 4);
+        // Without a tip at or past the block it only counts as confirmed
+        assert_eq!(detail(&included.id, None).confirmations, 1);
+        assert_eq!(detail(&included.id, Some(0)).confirmations, 1);
+        // Block 1 doesn't hold the other transaction
+        assert_eq!(
+            detail(&elsewhere.id, Some(1)).proof,
+            MerkleProofStatus::Invalid
+        );
+
+        // Nor does a block commit to anything once its merkle root is off
+        let mut tampered = chain.block_at(1).unwrap().clone();
+        assert!(tampered.commits_to(&included.id));
+        tampered.header.merkle_root = [0; 32];
+        assert!(!tampered.commits_to(&included.id));
+    }
+
+    #[test]
+    fn history_exports_apply_the_filter_and_carry_labels() {
+        let mine = fake_address();
+        let mut manager = TransactionManager::new();
+        let sent = fake_signed_transaction()
+            .paying(&fake_address(), 300)
+            .build();
+        let received = fake_signed_transaction().paying(&mine, 800).build();
+        manager.add_classified_transaction(sent.clone(), true, |a| *a == mine);
+        manager.add_classified_transaction(received.clone(), false, |a| *a == mine);
+        manager.set_label(&sent.id, "rent, March");
+
+        let outgoing = ExportFilter {
+            direction: Some(TransactionDirection::Outgoing),
+            ..ExportFilter::default()
+        };
+        let mut csv = Vec::new();
+        let summary = manager
+            .export_to_writer(&mut csv, ExportFormat::Csv, &outgoing, |_| true)
+            .unwrap();
+        assert_eq!(summary.rows, 1);
+        let csv = String::from_utf8(csv).unwrap();
+        let row = csv.lines().nth(1).unwrap();
+        assert!(row.starts_with(&format!("{},pending,,outgoing,300,", sent.id)));
+        assert!(row.ends_with(",false,\"rent, March\""));
+
+        let mut json = Vec::new();
+        manager
+            .export_to_writer(
+                &mut json,
+                ExportFormat::Json,
+                &ExportFilter::default(),
+                |_| true,
+            )
+            .unwrap();
+        let rows: Vec<serde_json::Value> = serde_json::from_slice(&json).unwrap();
+        assert_eq!(rows.len(), 2);
+
+        let later = ExportFilter {
+            since: Some(Utc::now() + chrono::Duration::days(1)),
+            ..ExportFilter::default()
+        };
+        let mut csv = Vec::new();
+        let summary = manager
+            .export_to_writer(&mut csv, ExportFormat::Csv, &later, |_| true)
+            .unwrap();
+        assert_eq!(summary.rows, 0);
+        assert_eq!(String::from_utf8(csv).unwrap().lines().count(), 1);
+    }
+
+    #[test]
+    fn conflicts_need_a_shared_input() {
+        let a = fake_signed_transaction().spending("aa", 0, 100).build();
+        let b = fake_signed_transaction()
+            .spending("aa", 0, 100)
+            .paying(&fake_address(), 5)
+            .build();
+        let c = fake_signed_transaction().spending("aa", 1, 100).build();
+        assert!(a.conflicts_with(&b));
+        assert!(!a.conflicts_with(&c));
+        assert!(!a.conflicts_with(&a));
+    }
 }
@@ -0,0 +1,98 @@
+//! Preferences of the app's interface that aren't tied to a wallet, such as
+//! the language it is shown in.
+//!
+//! Saved synchronously next to the profiles file, like the console history,
+//! so they can be read before any wallet is open.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::wallet::{WalletError, WalletResult};
+
+/// UI preferences, saved next to the profiles file
+pub const UI_PREFS_FILE: &str = "ui_prefs.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UiPrefs {
+    /// Language code such as "en"; `None` for the default
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+/// Preferences saved in `dir`; defaults if none were saved
+pub fn load_ui_prefs(dir: &Path) -> WalletResult<UiPrefs> {
+    let path = dir.join(UI_PREFS_FILE);
+    if !path.exists() {
+        return Ok(UiPrefs::default());
+    }
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| WalletError::Storage(format!("Failed to read UI preferences: {}", e)))?;
+    serde_json::from_str(&text)
+        .map_err(|e| WalletError::Serialization(format!("Invalid UI preferences: {}", e)))
+}
+
+pub fn save_ui_prefs(dir: &Path, prefs: &UiPrefs) -> WalletResult<()> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| WalletError::Storage(format!("Failed to create config dir: {}", e)))?;
+    let path = dir.join(UI_PREFS_FILE);
+    let tmp_path = path.with_extension("json.tmp");
+    let contents = serde_json::to_string_pretty(prefs)
+        .map_err(|e| WalletError::Serialization(e.to_string()))?;
+    std::fs::write(&tmp_path, contents)
+        .and_then(|_| std::fs::rename(&tmp_path, &path))
+        .map_err(|e| WalletError::Storage(format!("Failed to save UI preferences: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn missing_prefs_are_the_defaults() {
+        let dir = TempDir::new("ui-prefs-missing");
+        assert_eq!(load_ui_prefs(dir.path()).unwrap(), UiPrefs::default());
+    }
+
+    #[test]
+    fn saved_prefs_load_back() {
+        let dir = TempDir::new("ui-prefs-round-trip");
+        let config = dir.join("config");
+        let prefs = UiPrefs {
+            language: Some("de".to_string()),
+        };
+        save_ui_prefs(&config, &prefs).unwrap();
+        assert_eq!(load_ui_prefs(&config).unwrap(), prefs);
+        assert!(!config.join("ui_prefs.json.tmp").exists());
+
+        save_ui_prefs(&config, &UiPrefs::default()).unwrap();
+        assert_eq!(load_ui_prefs(&config).unwrap().language, None);
+    }
+
+    #[test]
+    fn older_and_newer_files_still_load() {
+        let dir = TempDir::new("ui-prefs-fields");
+        std::fs::write(dir.join(UI_PREFS_FILE), "{}").unwrap();
+        assert_eq!(load_ui_prefs(dir.path()).unwrap(), UiPrefs::default());
+
+        std::fs::write(
+            dir.join(UI_PREFS_FILE),
+            r#"{"language": "en", "theme": "dark"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            load_ui_prefs(dir.path()).unwrap().language.as_deref(),
+            Some("en")
+        );
+    }
+
+    #[test]
+    fn a_corrupt_file_is_an_error() {
+        let dir = TempDir::new("ui-prefs-corrupt");
+        std::fs::write(dir.join(UI_PREFS_FILE), "{\"language\": ").unwrap();
+        assert!(matches!(
+            load_ui_prefs(dir.path()),
+            Err(WalletError::Serialization(_))
+        ));
+    }
+}
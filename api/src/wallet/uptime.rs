@@ -0,0 +1,441 @@
+//! Node status history and the availability report built from it.
+//!
+//! Every status change of the node manager is appended, with its time and
+//! cause, to `uptime.jsonl` in the data dir, one JSON object per line, so the
+//! history survives restarts and an interrupted write loses at most one line.
+//! Stops and cancels the user asked for are kept apart from failures: only
+//! the latter count against the node in the report.
+
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::wallet::network::NodeStatus;
+use crate::wallet::{WalletError, WalletResult};
+
+/// Status history, kept in the data dir and never purged
+pub const UPTIME_FILE: &str = "uptime.jsonl";
+
+/// Why the node changed status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransitionCause {
+    /// A start, stop or cancel the user asked for
+    User,
+    /// The node moved on by itself, e.g. a start completing
+    Node,
+    /// An error, or the app exiting without stopping the node
+    Failure,
+}
+
+impl TransitionCause {
+    /// Cause of a manager transition into `next`: Error is a failure,
+    /// Running is the node finishing a start, everything else was asked for
+    pub fn of(next: &NodeStatus) -> Self {
+        match next {
+            NodeStatus::Error(_) => TransitionCause::Failure,
+            NodeStatus::Running => TransitionCause::Node,
+            NodeStatus::Stopped | NodeStatus::Starting | NodeStatus::Stopping => {
+                TransitionCause::User
+            }
+        }
+    }
+}
+
+/// One recorded status change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusTransition {
+    pub at: DateTime<Utc>,
+    pub from: NodeStatus,
+    pub to: NodeStatus,
+    pub cause: TransitionCause,
+}
+
+/// Share of one UTC day the node was running
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyUptime {
+    pub date: NaiveDate,
+    /// 0–100, of the part of the day inside the report range and not in the future
+    pub uptime_percent: f64,
+}
+
+/// Uptime figures for a time range
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AvailabilityReport {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub uptime_secs: i64,
+    /// Errors while the node was running
+    pub crashes: usize,
+    /// Errors while starting or stopping
+    pub errors: usize,
+    /// Stops and cancelled starts the user asked for
+    pub user_stops: usize,
+    pub longest_run_secs: i64,
+    /// One entry per UTC day touching the range, oldest first
+    pub days: Vec<DailyUptime>,
+}
+
+impl AvailabilityReport {
+    /// Uptime over the whole range, 0–100
+    pub fn uptime_percent(&self) -> f64 {
+        let span = (self.to - self.from).num_seconds();
+        if span <= 0 {
+            return 0.0;
+        }
+        self.uptime_secs as f64 * 100.0 / span as f64
+    }
+}
+
+/// Append-only status history of one data dir
+#[derive(Debug, Clone)]
+pub struct UptimeLog {
+    path: PathBuf,
+}
+
+impl UptimeLog {
+    pub fn new(data_dir: &Path) -> Self {
+        Self {
+            path: data_dir.join(UPTIME_FILE),
+        }
+    }
+
+    /// Append one transition
+    pub fn record(&self, transition: &StatusTransition) -> WalletResult<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| WalletError::Storage(format!("Failed to create data dir: {}", e)))?;
+        }
+        let mut line = serde_json::to_string(transition)
+            .map_err(|e| WalletError::Serialization(e.to_string()))?;
+        line.push('\n');
+        std::fs::OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| {
+                // Start a fresh line after one cut short by a crash, rather
+                // than losing this record to it
+                if ends_mid_line(&mut file)? {
+                    line.insert(0, '\n');
+                }
+                file.write_all(line.as_bytes())
+            })
+            .map_err(|e| WalletError::Storage(format!("Failed to record node status: {}", e)))
+    }
+
+    /// Every recorded transition, oldest first. Unreadable lines, such as one
+    /// cut short by a crash, are skipped.
+    pub fn load(&self) -> WalletResult<Vec<StatusTransition>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let text = std::fs::read_to_string(&self.path).map_err(|e| {
+            WalletError::Storage(format!("Failed to read node status history: {}", e))
+        })?;
+        let mut transitions: Vec<StatusTransition> = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(transition) => Some(transition),
+                Err(e) => {
+                    warn!("Skipping unreadable node status record: {}", e);
+                    None
+                }
+            })
+            .collect();
+        transitions.sort_by_key(|t| t.at);
+        Ok(transitions)
+    }
+
+    /// If the history ends with the node up, the app exited without stopping
+    /// it. Record that as a failure at the last known time, so the run isn't
+    /// taken to have lasted until the next launch.
+    pub fn close_unfinished_run(&self) -> WalletResult<()> {
+        let Some(last) = self.load()?.pop() else {
+            return Ok(());
+        };
+        if matches!(last.to, NodeStatus::Stopped | NodeStatus::Error(_)) {
+            return Ok(());
+        }
+        self.record(&StatusTransition {
+            at: last.at,
+            from: last.to,
+            to: NodeStatus::Error("app exited without stopping the node".to_string()),
+            cause: TransitionCause::Failure,
+        })
+    }
+}
+
+/// Whether `file` is non-empty and doesn't end with a newline
+fn ends_mid_line(file: &mut std::fs::File) -> std::io::Result<bool> {
+    if file.metadata()?.len() == 0 {
+        return Ok(false);
+    }
+    let mut last = [0u8; 1];
+    file.seek(SeekFrom::End(-1))?;
+    file.read_exact(&mut last)?;
+    Ok(last[0] != b'\n')
+}
+
+/// Availability over `range` from `transitions` (oldest first), counting a
+/// run still going at the end of the history as lasting until `now`
+pub fn availability_report(
+    transitions: &[StatusTransition],
+    range: Range<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> AvailabilityReport {
+    let end = range.end.min(now).max(range.start);
+    let start = range.start;
+
+    let mut runs = Vec::new();
+    let mut run_start = None;
+    for transition in transitions {
+        match (&transition.to, run_start) {
+            (NodeStatus::Running, None) => run_start = Some(transition.at),
+            (NodeStatus::Running, Some(_)) => {}
+            (_, Some(began)) => {
+                runs.push((began, transition.at));
+                run_start = None;
+            }
+            (_, None) => {}
+        }
+    }
+    if let Some(began) = run_start {
+        runs.push((began, now));
+    }
+    let runs: Vec<(DateTime<Utc>, DateTime<Utc>)> = runs
+        .into_iter()
+        .filter_map(|(a, b)| clip((a, b), (start, end)))
+        .collect();
+
+    let mut report = AvailabilityReport {
+        from: start,
+        to: end,
+        uptime_secs: runs.iter().map(|(a, b)| (*b - *a).num_seconds()).sum(),
+        crashes: 0,
+        errors: 0,
+        user_stops: 0,
+        longest_run_secs: runs
+            .iter()
+            .map(|(a, b)| (*b - *a).num_seconds())
+            .max()
+            .unwrap_or(0),
+        days: Vec::new(),
+    };
+
+    for transition in transitions.iter().filter(|t| t.at >= start && t.at < end) {
+        match (&transition.from, &transition.to, transition.cause) {
+            (NodeStatus::Running, NodeStatus::Error(_), _) => report.crashes += 1,
+            (_, NodeStatus::Error(_), _) => report.errors += 1,
+            (_, NodeStatus::Stopped, TransitionCause::User) => report.user_stops += 1,
+            _ => {}
+        }
+    }
+
+    let mut date = start.date_naive();
+    while start < end && date <= end.date_naive() {
+        let day_start = Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap_or_default());
+        let day = (
+            day_start.max(start),
+            (day_start + Duration::days(1)).min(end),
+        );
+        let span = (day.1 - day.0).num_seconds();
+        if span > 0 {
+            let up: i64 = runs
+                .iter()
+                .filter_map(|run| clip(*run, day))
+                .map(|(a, b)| (b - a).num_seconds())
+                .sum();
+            report.days.push(DailyUptime {
+                date,
+                uptime_percent: up as f64 * 100.0 / span as f64,
+            });
+        }
+        let Some(next) = date.succ_opt() else {
+            break;
+        };
+        date = next;
+    }
+    report
+}
+
+/// Part of `run` inside `window`, if any
+fn clip(
+    run: (DateTime<Utc>, DateTime<Utc>),
+    window: (DateTime<Utc>, DateTime<Utc>),
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let a = run.0.max(window.0);
+    let b = run.1.min(window.1);
+    (a < b).then_some((a, b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fakenet_config, fixture_time, TempDir};
+    use crate::wallet::network::{NockchainNodeConfig, NockchainNodeManager};
+
+    const HOUR: i64 = 3600;
+
+    fn at(hours: i64) -> DateTime<Utc> {
+        fixture_time(hours * HOUR)
+    }
+
+    fn change(hours: i64, from: NodeStatus, to: NodeStatus) -> StatusTransition {
+        StatusTransition {
+            at: at(hours),
+            cause: TransitionCause::of(&to),
+            from,
+            to,
+        }
+    }
+
+    fn error() -> NodeStatus {
+        NodeStatus::Error("boom".to_string())
+    }
+
+    /// Two days from midnight: a 6h run stopped by the user, a 6h run that
+    /// crashes, a failed start, then a 14h run over midnight stopped by the user
+    fn history() -> Vec<StatusTransition> {
+        use NodeStatus::*;
+        vec![
+            change(0, Stopped, Starting),
+            change(0, Starting, Running),
+            change(6, Running, Stopping),
+            change(6, Stopping, Stopped),
+            change(12, Stopped, Starting),
+            change(12, Starting, Running),
+            change(18, Running, error()),
+            change(20, error(), Starting),
+            change(20, Starting, error()),
+            change(22, error(), Starting),
+            change(22, Starting, Running),
+            change(36, Running, Stopping),
+            change(36, Stopping, Stopped),
+        ]
+    }
+
+    fn percents(report: &AvailabilityReport) -> Vec<f64> {
+        report
+            .days
+            .iter()
+            .map(|day| (day.uptime_percent * 100.0).round() / 100.0)
+            .collect()
+    }
+
+    #[test]
+    fn a_synthetic_history_gives_its_uptime_and_failures() {
+        let report = availability_report(&history(), at(0)..at(48), at(48));
+        assert_eq!(report.uptime_secs, 26 * HOUR);
+        assert_eq!(report.longest_run_secs, 14 * HOUR);
+        assert_eq!(report.crashes, 1);
+        assert_eq!(report.errors, 1);
+        assert_eq!(report.user_stops, 2);
+        assert_eq!(
+            report
+                .days
+                .iter()
+                .map(|day| day.date.to_string())
+                .collect::<Vec<_>>(),
+            ["2024-01-01", "2024-01-02"]
+        );
+        // 14h of the first day, 12h of the second
+        assert_eq!(percents(&report), [58.33, 50.0]);
+        assert!((report.uptime_percent() - 26.0 * 100.0 / 48.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn runs_are_clipped_to_the_range() {
+        let report = availability_report(&history(), at(12)..at(30), at(48));
+        assert_eq!(report.uptime_secs, 14 * HOUR);
+        assert_eq!(report.longest_run_secs, 8 * HOUR);
+        assert_eq!(report.crashes, 1);
+        assert_eq!(report.user_stops, 0);
+        // 8h of the 12h of day one in range, all 6h of day two
+        assert_eq!(percents(&report), [66.67, 100.0]);
+    }
+
+    #[test]
+    fn a_run_still_going_lasts_until_now_and_the_future_is_left_out() {
+        let mut transitions = history();
+        transitions.truncate(11);
+        let report = availability_report(&transitions, at(0)..at(48), at(30));
+        assert_eq!(report.to, at(30));
+        assert_eq!(report.uptime_secs, (6 + 6 + 8) * HOUR);
+        assert_eq!(percents(&report), [58.33, 100.0]);
+
+        let empty = availability_report(&[], at(0)..at(24), at(48));
+        assert_eq!(empty.uptime_secs, 0);
+        assert_eq!(percents(&empty), [0.0]);
+        assert_eq!(
+            availability_report(&[], at(5)..at(1), at(48)).uptime_percent(),
+            0.0
+        );
+    }
+
+    #[test]
+    fn the_log_survives_a_torn_line_and_closes_an_unfinished_run() {
+        let dir = TempDir::new("uptime");
+        let log = UptimeLog::new(dir.path());
+        let mut transitions = history();
+        transitions.truncate(11);
+        for transition in &transitions {
+            log.record(transition).unwrap();
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(dir.join(UPTIME_FILE))
+            .unwrap();
+        file.write_all(b"{\"at\":\"2024-01-0").unwrap();
+        assert_eq!(log.load().unwrap(), transitions);
+
+        log.close_unfinished_run().unwrap();
+        let closed = log.load().unwrap();
+        let last = closed.last().unwrap();
+        assert_eq!((last.at, last.cause), (at(22), TransitionCause::Failure));
+        assert!(matches!(last.to, NodeStatus::Error(_)));
+        let report = availability_report(&closed, at(0)..at(48), at(48));
+        assert_eq!(report.crashes, 2);
+        assert_eq!(report.uptime_secs, 12 * HOUR);
+
+        // Already closed: nothing more is added
+        log.close_unfinished_run().unwrap();
+        assert_eq!(log.load().unwrap().len(), closed.len());
+    }
+
+    #[tokio::test]
+    async fn a_manual_start_and_stop_are_recorded_as_the_users() {
+        let dir = TempDir::new("uptime-node");
+        let mut manager = NockchainNodeManager::new(NockchainNodeConfig {
+            data_dir: dir.path().to_path_buf(),
+            ..fakenet_config()
+        });
+        manager.start_node().await.unwrap();
+        manager.stop_node().await.unwrap();
+
+        let causes: Vec<(NodeStatus, TransitionCause)> = UptimeLog::new(dir.path())
+            .load()
+            .unwrap()
+            .into_iter()
+            .map(|t| (t.to, t.cause))
+            .collect();
+        assert_eq!(
+            causes,
+            [
+                (NodeStatus::Starting, TransitionCause::User),
+                (NodeStatus::Running, TransitionCause::Node),
+                (NodeStatus::Stopping, TransitionCause::User),
+                (NodeStatus::Stopped, TransitionCause::User),
+            ]
+        );
+        let report = manager.availability_report(fixture_time(0)..Utc::now());
+        assert_eq!(
+            (report.crashes, report.errors, report.user_stops),
+            (0, 0, 1)
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Watchdog that fails a node start which stops making progress.
+//!
+//! The start path enters each bootstrap phase and sends heartbeats as it
+//! makes progress; every heartbeat pushes the deadline out again, so a
+//! legitimately slow phase keeps going while a wedged one is cut off.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// How long a start may go without progress before the watchdog fires
+pub const DEFAULT_START_TIMEOUT_SECS: u64 = 60;
+
+/// Bootstrap phases of a node start, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StartPhase {
+    /// Config validation, data dir, lockfile and mempool reload
+    Preparing,
+    ResolvingSeeds,
+    DialingPeers,
+}
+
+impl StartPhase {
+    pub fn label(&self) -> &'static str {
+        match self {
+            StartPhase::Preparing => "preparing",
+            StartPhase::ResolvingSeeds => "resolving seeds",
+            StartPhase::DialingPeers => "dialing peers",
+        }
+    }
+}
+
+impl fmt::Display for StartPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+#[derive(Debug)]
+struct WatchdogState {
+    phase: StartPhase,
+    deadline: Instant,
+}
+
+/// Active start phase and the time by which the start must next show progress.
+/// Clones share state, so the start path and the watcher can each hold one.
+#[derive(Debug, Clone)]
+pub struct StartWatchdog {
+    timeout: Duration,
+    state: Arc<Mutex<WatchdogState>>,
+}
+
+impl StartWatchdog {
+    /// Watchdog in the `Preparing` phase, due `timeout` from now
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            state: Arc::new(Mutex::new(WatchdogState {
+                phase: StartPhase::Preparing,
+                deadline: Instant::now() + timeout,
+            })),
+        }
+    }
+
+    /// Move to `phase`; entering a phase counts as progress
+    pub fn enter(&self, phase: StartPhase) {
+        if let Ok(mut state) = self.state.lock() {
+            state.phase = phase;
+            state.deadline = Instant::now() + self.timeout;
+        }
+    }
+
+    /// Report progress in the current phase, pushing the deadline out
+    pub fn heartbeat(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.deadline = Instant::now() + self.timeout;
+        }
+    }
+
+    pub fn phase(&self) -> StartPhase {
+        self.state
+            .lock()
+            .map_or(StartPhase::Preparing, |state| state.phase)
+    }
+
+    /// Resolves with the active phase once a deadline passes without progress
+    pub async fn expired(&self) -> StartPhase {
+        loop {
+            let Ok((phase, deadline)) =
+                self.state.lock().map(|state| (state.phase, state.deadline))
+            else {
+                // A poisoned watchdog can't tell progress apart; never fire
+                return std::future::pending().await;
+            };
+            if Instant::now() >= deadline {
+                return phase;
+            }
+            tokio::time::sleep_until(deadline).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_millis(100);
+
+    #[tokio::test]
+    async fn fires_with_the_active_phase_once_progress_stops() {
+        let watchdog = StartWatchdog::new(TIMEOUT);
+        watchdog.enter(StartPhase::ResolvingSeeds);
+        watchdog.enter(StartPhase::DialingPeers);
+        let started = Instant::now();
+        assert_eq!(watchdog.expired().await, StartPhase::DialingPeers);
+        assert!(started.elapsed() >= TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn heartbeats_keep_a_slow_phase_alive() {
+        let watchdog = StartWatchdog::new(TIMEOUT);
+        let beating = watchdog.clone();
+        let heartbeats = async move {
+            for _ in 0..8 {
+                tokio::time::sleep(TIMEOUT / 2).await;
+                beating.heartbeat();
+            }
+        };
+        // Four timeouts pass while the heartbeats run, and the watchdog holds off
+        tokio::select! {
+            _ = heartbeats => {}
+            phase = watchdog.expired() => panic!("fired at {} despite heartbeats", phase),
+        }
+        assert_eq!(watchdog.expired().await, StartPhase::Preparing);
+    }
+
+    #[test]
+    fn phases_are_named_for_the_error_message() {
+        let labels = [
+            StartPhase::Preparing,
+            StartPhase::ResolvingSeeds,
+            StartPhase::DialingPeers,
+        ]
+        .map(|phase| phase.to_string());
+        assert_eq!(labels, ["preparing", "resolving seeds", "dialing peers"]);
+    }
+}
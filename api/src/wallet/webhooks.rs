@@ -0,0 +1,1086 @@
+//! Webhooks: wallet events POSTed to a merchant's own server.
+//!
+//! Each configured webhook names a URL, a shared secret and the event types
+//! it wants. A matching event is queued once per webhook under an id derived
+//! from the event, so the same payment never queues twice and a retry carries
+//! the same id; receivers use it to drop duplicates, since delivery is
+//! at-least-once. The body is signed with HMAC-SHA256 over its exact bytes in
+//! `WEBHOOK_SIGNATURE_HEADER`.
+//!
+//! Sending happens outside the wallet lock: `take_due` hands out the requests
+//! whose retry time has come and `record_attempt` files the result. A failed
+//! attempt is retried after `retry_delay`, doubling each time, until
+//! `WEBHOOK_MAX_ATTEMPTS` attempts have failed and the delivery is given up.
+//! Only plain `http://` URLs can be reached; there is no TLS client here.
+
+use chrono::{DateTime, TimeDelta, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::wallet::amount::{format_amount, AmountUnit};
+use crate::wallet::audit::{AuditEvent, AuditOutcome};
+use crate::wallet::encoding;
+use crate::wallet::incoming::IncomingEvent;
+use crate::wallet::payment_request::PaymentMatch;
+use crate::wallet::storage::StorageManager;
+use crate::wallet::{TransactionDirection, TransactionStatus, Wallet, WalletError, WalletResult};
+
+/// Webhooks and their deliveries, saved through `StorageManager` in the
+/// wallet data dir
+pub const WEBHOOKS_FILE: &str = "webhooks.json";
+
+/// Header carrying `sha256=<hex HMAC of the body>`
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-Nock-Signature";
+
+/// Header carrying the event's idempotency id, also in the body as `id`
+pub const WEBHOOK_ID_HEADER: &str = "X-Nock-Webhook-Id";
+
+/// Failed attempts after which a delivery is given up
+pub const WEBHOOK_MAX_ATTEMPTS: u32 = 8;
+
+/// Finished deliveries kept for the Settings page; pending ones are never
+/// dropped
+pub const WEBHOOK_HISTORY: usize = 200;
+
+/// Event ids remembered so an event isn't queued twice, oldest forgotten
+/// first. Well past `WEBHOOK_HISTORY`, so an event outlives its delivery.
+pub const WEBHOOK_SEEN_EVENTS: usize = 5 * WEBHOOK_HISTORY;
+
+/// How long one attempt may take, connecting included
+pub const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest wait between attempts
+const MAX_RETRY_DELAY_SECS: i64 = 3600;
+
+/// Wait before retrying after `failures` failed attempts: 10 seconds, doubled
+/// for each further failure, at most an hour
+pub fn retry_delay(failures: u32) -> TimeDelta {
+    let secs = 10_i64.saturating_mul(1 << failures.saturating_sub(1).min(16));
+    TimeDelta::seconds(secs.min(MAX_RETRY_DELAY_SECS))
+}
+
+/// Kinds of event a webhook can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventType {
+    IncomingTx,
+    Confirmed,
+    NodeError,
+    PaymentReceived,
+    PaymentExpired,
+}
+
+impl WebhookEventType {
+    pub const ALL: [WebhookEventType; 5] = [
+        WebhookEventType::IncomingTx,
+        WebhookEventType::Confirmed,
+        WebhookEventType::NodeError,
+        WebhookEventType::PaymentReceived,
+        WebhookEventType::PaymentExpired,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WebhookEventType::IncomingTx => "Incoming payment",
+            WebhookEventType::Confirmed => "Payment confirmed",
+            WebhookEventType::NodeError => "Node error",
+            WebhookEventType::PaymentReceived => "Payment request paid",
+            WebhookEventType::PaymentExpired => "Payment request expired",
+        }
+    }
+}
+
+/// One webhook endpoint, as set in `WalletConfig::webhooks`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Key the body is signed with
+    pub secret: String,
+    pub event_types: BTreeSet<WebhookEventType>,
+}
+
+impl WebhookConfig {
+    pub fn validate(&self) -> WalletResult<()> {
+        parse_http_url(&self.url)?;
+        if self.secret.is_empty() {
+            return Err(WalletError::Network(format!(
+                "Webhook {} needs a secret to sign with",
+                self.url
+            )));
+        }
+        if self.event_types.is_empty() {
+            return Err(WalletError::Network(format!(
+                "Webhook {} subscribes to no events",
+                self.url
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Something a webhook can be told about
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WalletEvent {
+    /// A payment to the wallet reached the mempool
+    IncomingTx { tx_id: String, amount: u64 },
+    /// A payment to the wallet reached the confirmation threshold
+    Confirmed {
+        tx_id: String,
+        amount: u64,
+        block_height: u64,
+        confirmations: u64,
+    },
+    /// The node stopped with an error
+    NodeError { message: String, at: DateTime<Utc> },
+    /// A payment reached the address of a payment request before it expired
+    PaymentReceived {
+        request_id: String,
+        tx_id: String,
+        matched_amount: u64,
+        outcome: PaymentMatch,
+    },
+    /// A payment request expired with nothing received
+    PaymentExpired { request_id: String },
+}
+
+impl WalletEvent {
+    /// Webhook event for an incoming payment change, if one is sent for it.
+    /// Confirmations are announced at the threshold by
+    /// `Wallet::confirmed_payment_events` instead.
+    pub fn from_incoming(event: &IncomingEvent) -> Option<Self> {
+        match event {
+            IncomingEvent::Arrived { tx_id, amount } => Some(WalletEvent::IncomingTx {
+                tx_id: tx_id.clone(),
+                amount: *amount,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn event_type(&self) -> WebhookEventType {
+        match self {
+            WalletEvent::IncomingTx { .. } => WebhookEventType::IncomingTx,
+            WalletEvent::Confirmed { .. } => WebhookEventType::Confirmed,
+            WalletEvent::NodeError { .. } => WebhookEventType::NodeError,
+            WalletEvent::PaymentReceived { .. } => WebhookEventType::PaymentReceived,
+            WalletEvent::PaymentExpired { .. } => WebhookEventType::PaymentExpired,
+        }
+    }
+
+    /// Idempotency id: the same for every report of the same event
+    pub fn id(&self) -> String {
+        let key = match self {
+            WalletEvent::IncomingTx { tx_id, .. } => format!("incoming_tx:{}", tx_id),
+            WalletEvent::Confirmed { tx_id, .. } => format!("confirmed:{}", tx_id),
+            WalletEvent::NodeError { message, at } => {
+                format!("node_error:{}:{}", at.to_rfc3339(), message)
+            }
+            WalletEvent::PaymentReceived { request_id, .. } => {
+                format!("payment_received:{}", request_id)
+            }
+            WalletEvent::PaymentExpired { request_id } => {
+                format!("payment_expired:{}", request_id)
+            }
+        };
+        hex::encode(&encoding::sha256(key.as_bytes())[..16])
+    }
+}
+
+impl fmt::Display for WalletEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WalletEvent::IncomingTx { tx_id, .. } => write!(f, "Incoming payment {}", tx_id),
+            WalletEvent::Confirmed {
+                tx_id,
+                confirmations,
+                ..
+            } => write!(f, "Payment {} confirmed ({} blocks)", tx_id, confirmations),
+            WalletEvent::NodeError { message, .. } => write!(f, "Node error: {}", message),
+            WalletEvent::PaymentReceived {
+                request_id,
+                matched_amount,
+                outcome,
+                ..
+            } => write!(
+                f,
+                "Payment request {} {}: received {} NOCK",
+                request_id,
+                outcome,
+                format_amount(*matched_amount, AmountUnit::Nock)
+            ),
+            WalletEvent::PaymentExpired { request_id } => {
+                write!(f, "Payment request {} expired unpaid", request_id)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    /// Failed `WEBHOOK_MAX_ATTEMPTS` times, or the webhook was removed
+    GaveUp,
+}
+
+/// An event on its way to one webhook
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub event_id: String,
+    pub url: String,
+    pub event: WalletEvent,
+    pub created_at: DateTime<Utc>,
+    pub status: DeliveryStatus,
+    /// Attempts made so far
+    pub attempts: u32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+impl WebhookDelivery {
+    /// JSON body; the same bytes on every attempt, so the signature is too
+    pub fn body(&self) -> Vec<u8> {
+        serde_json::to_vec(&serde_json::json!({
+            "id": self.event_id,
+            "created_at": self.created_at,
+            "event": self.event,
+        }))
+        .unwrap_or_default()
+    }
+}
+
+/// A POST ready to send, from `WebhookQueue::take_due`
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebhookRequest {
+    pub event_id: String,
+    pub url: String,
+    pub body: Vec<u8>,
+    /// `sha256=` and the hex HMAC of `body`
+    pub signature: String,
+}
+
+/// Hex HMAC-SHA256 of `body` keyed with `secret`
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Configured webhooks and their deliveries
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WebhookQueue {
+    #[serde(default)]
+    hooks: Vec<WebhookConfig>,
+    /// Oldest first
+    #[serde(default)]
+    deliveries: Vec<WebhookDelivery>,
+    /// Ids of the last `WEBHOOK_SEEN_EVENTS` events queued, oldest first, so
+    /// one isn't queued again after its delivery left the history
+    #[serde(default)]
+    seen: VecDeque<String>,
+}
+
+impl WebhookQueue {
+    /// Load the saved queue, or an empty one if none was saved
+    pub async fn load(storage: &StorageManager) -> WalletResult<Self> {
+        if !storage.exists(WEBHOOKS_FILE) {
+            return Ok(Self::default());
+        }
+        storage.load(WEBHOOKS_FILE).await
+    }
+
+    pub async fn save(&self, storage: &StorageManager) -> WalletResult<()> {
+        storage.save(WEBHOOKS_FILE, self).await
+    }
+
+    pub fn hooks(&self) -> &[WebhookConfig] {
+        &self.hooks
+    }
+
+    /// Replace the webhooks. Pending deliveries to a URL no longer
+    /// configured are given up.
+    pub fn set_hooks(&mut self, hooks: Vec<WebhookConfig>, now: DateTime<Utc>) {
+        self.hooks = hooks;
+        for delivery in &mut self.deliveries {
+            if delivery.status == DeliveryStatus::Pending
+                && !self.hooks.iter().any(|hook| hook.url == delivery.url)
+            {
+                delivery.status = DeliveryStatus::GaveUp;
+                delivery.last_attempt_at = Some(now);
+                delivery.last_error = Some("Webhook removed".to_string());
+            }
+        }
+    }
+
+    /// Deliveries, newest first
+    pub fn deliveries(&self) -> impl Iterator<Item = &WebhookDelivery> {
+        self.deliveries.iter().rev()
+    }
+
+    /// Queue `event` for every webhook subscribed to its type. An event seen
+    /// before is ignored. Returns how many deliveries were queued.
+    pub fn enqueue(&mut self, event: WalletEvent, now: DateTime<Utc>) -> usize {
+        let event_id = event.id();
+        if self.seen.contains(&event_id) {
+            return 0;
+        }
+        self.seen.push_back(event_id.clone());
+        while self.seen.len() > WEBHOOK_SEEN_EVENTS {
+            self.seen.pop_front();
+        }
+        let event_type = event.event_type();
+        let urls: Vec<String> = self
+            .hooks
+            .iter()
+            .filter(|hook| hook.event_types.contains(&event_type))
+            .map(|hook| hook.url.clone())
+            .collect();
+        for url in &urls {
+            self.deliveries.push(WebhookDelivery {
+                event_id: event_id.clone(),
+                url: url.clone(),
+                event: event.clone(),
+                created_at: now,
+                status: DeliveryStatus::Pending,
+                attempts: 0,
+                next_attempt_at: now,
+                last_attempt_at: None,
+                last_error: None,
+            });
+        }
+        self.trim();
+        urls.len()
+    }
+
+    /// Signed requests for the pending deliveries due by `now`. A retry
+    /// scheduled further out than the longest delay can only come from the
+    /// clock having been set back, and is due straight away.
+    pub fn take_due(&self, now: DateTime<Utc>) -> Vec<WebhookRequest> {
+        let latest = now + TimeDelta::seconds(MAX_RETRY_DELAY_SECS);
+        self.deliveries
+            .iter()
+            .filter(|delivery| {
+                delivery.status == DeliveryStatus::Pending
+                    && (delivery.next_attempt_at <= now || delivery.next_attempt_at > latest)
+            })
+            .filter_map(|delivery| {
+                let hook = self.hooks.iter().find(|hook| hook.url == delivery.url)?;
+                let body = delivery.body();
+                Some(WebhookRequest {
+                    event_id: delivery.event_id.clone(),
+                    url: delivery.url.clone(),
+                    signature: format!("sha256={}", sign(&hook.secret, &body)),
+                    body,
+                })
+            })
+            .collect()
+    }
+
+    /// File the result of sending `request`: delivered, retried later, or
+    /// given up after `WEBHOOK_MAX_ATTEMPTS` failures
+    pub fn record_attempt(
+        &mut self,
+        request: &WebhookRequest,
+        result: Result<(), String>,
+        now: DateTime<Utc>,
+    ) {
+        let Some(delivery) = self.deliveries.iter_mut().find(|delivery| {
+            delivery.event_id == request.event_id
+                && delivery.url == request.url
+                && delivery.status == DeliveryStatus::Pending
+        }) else {
+            return;
+        };
+        delivery.attempts += 1;
+        delivery.last_attempt_at = Some(now);
+        match result {
+            Ok(()) => {
+                delivery.status = DeliveryStatus::Delivered;
+                delivery.last_error = None;
+            }
+            Err(error) => {
+                if delivery.attempts >= WEBHOOK_MAX_ATTEMPTS {
+                    delivery.status = DeliveryStatus::GaveUp;
+                } else {
+                    delivery.next_attempt_at = now + retry_delay(delivery.attempts);
+                }
+                delivery.last_error = Some(error);
+            }
+        }
+        self.trim();
+    }
+
+    /// Drop the oldest finished deliveries beyond `WEBHOOK_HISTORY`
+    fn trim(&mut self) {
+        let finished = self
+            .deliveries
+            .iter()
+            .filter(|delivery| delivery.status != DeliveryStatus::Pending)
+            .count();
+        let mut excess = finished.saturating_sub(WEBHOOK_HISTORY);
+        self.deliveries.retain(|delivery| {
+            if excess > 0 && delivery.status != DeliveryStatus::Pending {
+                excess -= 1;
+                return false;
+            }
+            true
+        });
+    }
+}
+
+/// Host, port and path of an `http://` URL. An IPv6 host is written in
+/// brackets and returned without them.
+fn parse_http_url(url: &str) -> WalletResult<(String, u16, String)> {
+    let invalid = |reason: &str| WalletError::Network(format!("Webhook URL {}: {}", url, reason));
+    let rest = url.strip_prefix("http://").ok_or_else(|| {
+        invalid("only http:// is supported; put a TLS-terminating proxy in front for https")
+    })?;
+    let (authority, path) = match rest.find('/') {
+        Some(index) => (&rest[..index], &rest[index..]),
+        None => (rest, "/"),
+    };
+    let parse_port = |port: &str| port.parse::<u16>().map_err(|_| invalid("invalid port"));
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(bracketed) => {
+            let (host, rest) = bracketed
+                .split_once(']')
+                .ok_or_else(|| invalid("unclosed [ in host"))?;
+            let port = match rest {
+                "" => 80,
+                _ => parse_port(
+                    rest.strip_prefix(':')
+                        .ok_or_else(|| invalid("invalid port"))?,
+                )?,
+            };
+            (host, port)
+        }
+        None => match authority.rsplit_once(':') {
+            Some((host, port)) => (host, parse_port(port)?),
+            None => (authority, 80),
+        },
+    };
+    if host.is_empty() {
+        return Err(invalid("missing host"));
+    }
+    Ok((host.to_string(), port, path.to_string()))
+}
+
+/// POST `request`, succeeding on any 2xx answer within `WEBHOOK_TIMEOUT`
+pub async fn send_webhook(request: &WebhookRequest) -> Result<(), String> {
+    tokio::time::timeout(WEBHOOK_TIMEOUT, post(request))
+        .await
+        .unwrap_or_else(|_| Err(format!("No answer within {}s", WEBHOOK_TIMEOUT.as_secs())))
+}
+
+async fn post(request: &WebhookRequest) -> Result<(), String> {
+    let (host, port, path) = parse_http_url(&request.url).map_err(|e| e.to_string())?;
+    let mut stream = TcpStream::connect((host.as_str(), port))
+        .await
+        .map_err(|e| format!("Connection failed: {}", e))?;
+    let authority = if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    };
+    let head = format!(
+        "POST {path} HTTP/1.1\r\nHost: {authority}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}: {}\r\n{}: {}\r\nConnection: close\r\n\r\n",
+        request.body.len(),
+        WEBHOOK_ID_HEADER,
+        request.event_id,
+        WEBHOOK_SIGNATURE_HEADER,
+        request.signature,
+    );
+    stream
+        .write_all(head.as_bytes())
+        .await
+        .map_err(|e| format!("Write failed: {}", e))?;
+    stream
+        .write_all(&request.body)
+        .await
+        .map_err(|e| format!("Write failed: {}", e))?;
+
+    // Only the status line matters
+    let mut response = Vec::new();
+    let mut buf = [0u8; 256];
+    while !response.contains(&b'\n') {
+        let read = stream
+            .read(&mut buf)
+            .await
+            .map_err(|e| format!("Read failed: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        response.extend_from_slice(&buf[..read]);
+    }
+    let status_line = String::from_utf8_lossy(&response);
+    let status = status_line
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| "No HTTP status in the answer".to_string())?;
+    if (200..300).contains(&status) {
+        Ok(())
+    } else {
+        Err(format!("HTTP {}", status))
+    }
+}
+
+/// Send every delivery of `wallet` that is due, one attempt each, and file
+/// the results. The wallet lock is only held to pick them up and file them.
+/// Returns how many were attempted.
+pub async fn deliver_due_webhooks(wallet: &tokio::sync::RwLock<Wallet>) -> usize {
+    let requests = wallet.read().await.webhooks.take_due(Utc::now());
+    for request in &requests {
+        let result = send_webhook(request).await;
+        wallet
+            .write()
+            .await
+            .webhooks
+            .record_attempt(request, result, Utc::now());
+    }
+    requests.len()
+}
+
+impl Wallet {
+    pub fn webhooks(&self) -> &WebhookQueue {
+        &self.webhooks
+    }
+
+    /// Replace the webhooks, recording the change in the audit log
+    pub fn set_webhooks(&mut self, hooks: Vec<WebhookConfig>) -> WalletResult<()> {
+        let result = self
+            .ensure_writable()
+            .and_then(|_| hooks.iter().try_for_each(WebhookConfig::validate));
+        if result.is_ok() {
+            self.webhooks.set_hooks(hooks, Utc::now());
+        }
+        self.record_audit(
+            AuditEvent::SettingsChanged {
+                setting: "webhooks".to_string(),
+            },
+            AuditOutcome::of(&result),
+        );
+        result
+    }
+
+    /// Queue `event` for the webhooks subscribed to it. Nothing is queued in
+    /// read-only mode, where the queue can't be saved.
+    pub fn queue_webhook_event(&mut self, event: WalletEvent) -> usize {
+        if self.ensure_writable().is_err() {
+            return 0;
+        }
+        self.webhooks.enqueue(event, Utc::now())
+    }
+
+    /// Incoming payments with at least `threshold` confirmations
+    pub fn confirmed_payment_events(&self, threshold: u64) -> Vec<WalletEvent> {
+        let tip = self.balances.tip_height();
+        self.transactions
+            .get_confirmed_transactions()
+            .iter()
+            .filter(|tx| tx.direction == TransactionDirection::Incoming)
+            .filter_map(|tx| {
+                let TransactionStatus::Confirmed { block_height } = tx.status else {
+                    return None;
+                };
+                let confirmations = tip
+                    .filter(|tip| *tip >= block_height)
+                    .map_or(1, |tip| tip - block_height + 1);
+                (confirmations >= threshold).then(|| WalletEvent::Confirmed {
+                    tx_id: tx.id.clone(),
+                    amount: tx.amount,
+                    block_height,
+                    confirmations,
+                })
+            })
+            .collect()
+    }
+
+    /// Load the webhooks and deliveries saved in the wallet data dir
+    pub async fn load_webhooks(&mut self) -> WalletResult<()> {
+        let Some(data_dir) = self.data_dir.clone() else {
+            return Ok(());
+        };
+        let storage = StorageManager::new(data_dir)?;
+        self.webhooks = WebhookQueue::load(&storage).await?;
+        Ok(())
+    }
+
+    /// Save the webhooks and deliveries. Does nothing in read-only mode.
+    pub async fn save_webhooks(&self) -> WalletResult<()> {
+        let Some(data_dir) = self.data_dir.clone().filter(|_| !self.read_only) else {
+            return Ok(());
+        };
+        let storage = StorageManager::new(data_dir)?;
+        self.webhooks.save(&storage).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{fake_address, fake_signed_transaction, fixture_time, TempDir};
+    use crate::wallet::keys::NockchainKeyManager;
+    use std::sync::{Arc, Mutex};
+    use tokio::net::TcpListener;
+
+    /// A request the mock server received
+    #[derive(Debug, Clone)]
+    struct Received {
+        head: String,
+        body: Vec<u8>,
+    }
+
+    impl Received {
+        fn header(&self, name: &str) -> Option<&str> {
+            self.head.lines().find_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                key.eq_ignore_ascii_case(name).then(|| value.trim())
+            })
+        }
+    }
+
+    /// HTTP server on a local port answering each request with the next of
+    /// `statuses`, the last one repeated. Returns its URL and what it got.
+    async fn mock_server(statuses: Vec<u16>) -> (String, Arc<Mutex<Vec<Received>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hooks/nock", listener.local_addr().unwrap());
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let log = received.clone();
+        tokio::spawn(async move {
+            for index in 0.. {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let mut data = Vec::new();
+                let mut buf = [0u8; 1024];
+                let head_end = loop {
+                    if let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break end;
+                    }
+                    let read = stream.read(&mut buf).await.unwrap();
+                    if read == 0 {
+                        return;
+                    }
+                    data.extend_from_slice(&buf[..read]);
+                };
+                let head = String::from_utf8_lossy(&data[..head_end]).to_string();
+                let length: usize = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("Content-Length: "))
+                    .and_then(|length| length.parse().ok())
+                    .unwrap_or(0);
+                while data.len() < head_end + 4 + length {
+                    let read = stream.read(&mut buf).await.unwrap();
+                    data.extend_from_slice(&buf[..read]);
+                }
+                let body = data[head_end + 4..head_end + 4 + length].to_vec();
+                log.lock().unwrap().push(Received { head, body });
+                let status = statuses[index.min(statuses.len() - 1)];
+                let answer = format!("HTTP/1.1 {} Mock\r\nContent-Length: 0\r\n\r\n", status);
+                let _ = stream.write_all(answer.as_bytes()).await;
+            }
+        });
+        (url, received)
+    }
+
+    fn hook(url: &str, event_types: &[WebhookEventType]) -> WebhookConfig {
+        WebhookConfig {
+            url: url.to_string(),
+            secret: "shared-secret".to_string(),
+            event_types: event_types.iter().copied().collect(),
+        }
+    }
+
+    fn incoming(tx_id: &str) -> WalletEvent {
+        WalletEvent::IncomingTx {
+            tx_id: tx_id.to_string(),
+            amount: 2_500,
+        }
+    }
+
+    fn queue_for(hooks: Vec<WebhookConfig>) -> WebhookQueue {
+        let mut queue = WebhookQueue::default();
+        queue.set_hooks(hooks, fixture_time(0));
+        queue
+    }
+
+    #[test]
+    fn signatures_are_hmac_sha256_of_the_body() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_ne!(sign("other", b"body"), sign("secret", b"body"));
+    }
+
+    #[test]
+    fn retries_back_off_exponentially_up_to_an_hour() {
+        let delays: Vec<i64> = (1..=10)
+            .map(|failures| retry_delay(failures).num_seconds())
+            .collect();
+        assert_eq!(
+            delays,
+            vec![10, 20, 40, 80, 160, 320, 640, 1280, 2560, 3600]
+        );
+        assert_eq!(retry_delay(u32::MAX).num_seconds(), 3600);
+    }
+
+    #[test]
+    fn only_plain_http_urls_with_a_secret_and_events_are_accepted() {
+        assert_eq!(
+            parse_http_url("http://shop.example:8080/hooks?x=1").unwrap(),
+            ("shop.example".to_string(), 8080, "/hooks?x=1".to_string())
+        );
+        assert_eq!(
+            parse_http_url("http://shop.example").unwrap(),
+            ("shop.example".to_string(), 80, "/".to_string())
+        );
+        assert_eq!(
+            parse_http_url("http://[::1]:8080/hooks").unwrap(),
+            ("::1".to_string(), 8080, "/hooks".to_string())
+        );
+        assert_eq!(
+            parse_http_url("http://[fe80::1]").unwrap(),
+            ("fe80::1".to_string(), 80, "/".to_string())
+        );
+        for url in [
+            "https://shop.example/",
+            "http://:80/",
+            "http://shop:port/",
+            "http://[::1/",
+            "http://[::1]8080/",
+            "http://[]:80/",
+        ] {
+            assert!(parse_http_url(url).is_err(), "{}", url);
+        }
+
+        assert!(hook("http://shop/", &[WebhookEventType::IncomingTx])
+            .validate()
+            .is_ok());
+        let mut no_secret = hook("http://shop/", &[WebhookEventType::IncomingTx]);
+        no_secret.secret.clear();
+        assert!(no_secret.validate().is_err());
+        assert!(hook("http://shop/", &[]).validate().is_err());
+    }
+
+    #[test]
+    fn the_idempotency_id_depends_on_the_event_not_the_report() {
+        assert_eq!(incoming("tx1").id(), incoming("tx1").id());
+        assert_ne!(incoming("tx1").id(), incoming("tx2").id());
+        // A later amount or confirmation count is still the same event
+        let confirmed = |confirmations| WalletEvent::Confirmed {
+            tx_id: "tx1".to_string(),
+            amount: 1,
+            block_height: 5,
+            confirmations,
+        };
+        assert_eq!(confirmed(6).id(), confirmed(9).id());
+        assert_ne!(confirmed(6).id(), incoming("tx1").id());
+        assert_eq!(incoming("tx1").id().len(), 32);
+    }
+
+    #[test]
+    fn events_queue_once_for_each_subscribed_webhook() {
+        let mut queue = queue_for(vec![
+            hook("http://a/", &[WebhookEventType::IncomingTx]),
+            hook(
+                "http://b/",
+                &[WebhookEventType::IncomingTx, WebhookEventType::NodeError],
+            ),
+            hook("http://c/", &[WebhookEventType::Confirmed]),
+        ]);
+        assert_eq!(queue.enqueue(incoming("tx1"), fixture_time(0)), 2);
+        assert_eq!(queue.enqueue(incoming("tx1"), fixture_time(5)), 0);
+        let error = WalletEvent::NodeError {
+            message: "crashed".to_string(),
+            at: fixture_time(1),
+        };
+        assert_eq!(queue.enqueue(error, fixture_time(1)), 1);
+
+        let urls: Vec<&str> = queue.deliveries().map(|d| d.url.as_str()).collect();
+        assert_eq!(urls, vec!["http://b/", "http://b/", "http://a/"]);
+        assert!(queue
+            .deliveries()
+            .all(|delivery| delivery.status == DeliveryStatus::Pending));
+    }
+
+    #[test]
+    fn every_attempt_carries_the_same_signed_body() {
+        let mut queue = queue_for(vec![hook("http://a/", &[WebhookEventType::IncomingTx])]);
+        queue.enqueue(incoming("tx1"), fixture_time(0));
+        let first = queue.take_due(fixture_time(0)).remove(0);
+        queue.record_attempt(&first, Err("HTTP 500".to_string()), fixture_time(0));
+        let retry = queue.take_due(fixture_time(10)).remove(0);
+        assert_eq!(retry, first);
+        assert_eq!(
+            first.signature,
+            format!("sha256={}", sign("shared-secret", &first.body))
+        );
+
+        let body: serde_json::Value = serde_json::from_slice(&first.body).unwrap();
+        assert_eq!(body["id"], incoming("tx1").id());
+        assert_eq!(body["event"]["type"], "incoming_tx");
+        assert_eq!(body["event"]["tx_id"], "tx1");
+    }
+
+    #[test]
+    fn failures_retry_on_schedule_then_give_up() {
+        let mut queue = queue_for(vec![hook("http://a/", &[WebhookEventType::IncomingTx])]);
+        queue.enqueue(incoming("tx1"), fixture_time(0));
+        let mut now = fixture_time(0);
+        for attempt in 1..=WEBHOOK_MAX_ATTEMPTS {
+            let due = queue.take_due(now);
+            assert_eq!(due.len(), 1, "attempt {}", attempt);
+            queue.record_attempt(&due[0], Err("HTTP 500".to_string()), now);
+            let delivery = queue.deliveries().next().unwrap();
+            assert_eq!(delivery.attempts, attempt);
+            if attempt < WEBHOOK_MAX_ATTEMPTS {
+                assert_eq!(delivery.next_attempt_at, now + retry_delay(attempt));
+                // Nothing is due before the retry time
+                let early = delivery.next_attempt_at - TimeDelta::seconds(1);
+                assert!(queue.take_due(early).is_empty());
+                now = delivery.next_attempt_at;
+            }
+        }
+        let delivery = queue.deliveries().next().unwrap();
+        assert_eq!(delivery.status, DeliveryStatus::GaveUp);
+        assert_eq!(delivery.last_error.as_deref(), Some("HTTP 500"));
+        assert!(queue.take_due(now + TimeDelta::days(1)).is_empty());
+    }
+
+    #[test]
+    fn a_retry_is_due_at_once_after_the_clock_is_set_back() {
+        let mut queue = queue_for(vec![hook("http://a/", &[WebhookEventType::IncomingTx])]);
+        queue.enqueue(incoming("tx1"), fixture_time(0));
+        let request = queue.take_due(fixture_time(0)).remove(0);
+        queue.record_attempt(&request, Err("down".to_string()), fixture_time(86_400));
+        assert!(queue.take_due(fixture_time(86_401)).is_empty());
+        assert_eq!(queue.take_due(fixture_time(0)).len(), 1);
+    }
+
+    #[test]
+    fn removing_a_webhook_gives_up_its_pending_deliveries() {
+        let mut queue = queue_for(vec![
+            hook("http://a/", &[WebhookEventType::IncomingTx]),
+            hook("http://b/", &[WebhookEventType::IncomingTx]),
+        ]);
+        queue.enqueue(incoming("tx1"), fixture_time(0));
+        queue.set_hooks(
+            vec![hook("http://b/", &[WebhookEventType::IncomingTx])],
+            fixture_time(5),
+        );
+        let removed = queue.deliveries().find(|d| d.url == "http://a/").unwrap();
+        assert_eq!(removed.status, DeliveryStatus::GaveUp);
+        assert_eq!(removed.last_error.as_deref(), Some("Webhook removed"));
+        let due = queue.take_due(fixture_time(5));
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].url, "http://b/");
+    }
+
+    #[test]
+    fn finished_deliveries_are_trimmed_but_pending_ones_kept() {
+        let mut queue = queue_for(vec![hook("http://a/", &[WebhookEventType::IncomingTx])]);
+        queue.enqueue(incoming("pending"), fixture_time(0));
+        for i in 0..WEBHOOK_HISTORY + 20 {
+            queue.enqueue(incoming(&format!("tx{}", i)), fixture_time(0));
+            let request = queue
+                .take_due(fixture_time(0))
+                .into_iter()
+                .find(|request| request.event_id == incoming(&format!("tx{}", i)).id())
+                .unwrap();
+            queue.record_attempt(&request, Ok(()), fixture_time(0));
+        }
+        assert_eq!(queue.deliveries().count(), WEBHOOK_HISTORY + 1);
+        assert!(queue
+            .deliveries()
+            .any(|delivery| delivery.event_id == incoming("pending").id()));
+        // Trimmed events still aren't queued again
+        assert_eq!(queue.enqueue(incoming("tx0"), fixture_time(0)), 0);
+    }
+
+    #[test]
+    fn only_the_latest_event_ids_are_remembered() {
+        let mut queue = queue_for(vec![hook("http://a/", &[WebhookEventType::IncomingTx])]);
+        for i in 0..=WEBHOOK_SEEN_EVENTS {
+            queue.enqueue(incoming(&format!("tx{}", i)), fixture_time(0));
+        }
+        assert_eq!(queue.seen.len(), WEBHOOK_SEEN_EVENTS);
+        let latest = format!("tx{}", WEBHOOK_SEEN_EVENTS);
+        assert_eq!(queue.enqueue(incoming(&latest), fixture_time(0)), 0);
+        // The oldest id was forgotten, so its event queues again
+        assert_eq!(queue.enqueue(incoming("tx0"), fixture_time(0)), 1);
+    }
+
+    #[tokio::test]
+    async fn a_delivery_reaches_the_server_signed() {
+        let (url, received) = mock_server(vec![200]).await;
+        let wallet = tokio::sync::RwLock::new(Wallet::new());
+        wallet
+            .write()
+            .await
+            .set_webhooks(vec![hook(&url, &[WebhookEventType::IncomingTx])])
+            .unwrap();
+        assert_eq!(wallet.write().await.queue_webhook_event(incoming("tx1")), 1);
+
+        assert_eq!(deliver_due_webhooks(&wallet).await, 1);
+        let received = received.lock().unwrap().clone();
+        assert_eq!(received.len(), 1);
+        let request = &received[0];
+        assert!(
+            request.head.starts_with("POST /hooks/nock HTTP/1.1"),
+            "{}",
+            request.head
+        );
+        assert_eq!(
+            request.header(WEBHOOK_SIGNATURE_HEADER),
+            Some(format!("sha256={}", sign("shared-secret", &request.body)).as_str())
+        );
+        assert_eq!(
+            request.header(WEBHOOK_ID_HEADER),
+            Some(incoming("tx1").id().as_str())
+        );
+        assert_eq!(request.header("Content-Type"), Some("application/json"));
+
+        let wallet = wallet.read().await;
+        let delivery = wallet.webhooks().deliveries().next().unwrap();
+        assert_eq!(delivery.status, DeliveryStatus::Delivered);
+        assert_eq!(delivery.attempts, 1);
+        assert_eq!(delivery.last_error, None);
+    }
+
+    #[tokio::test]
+    async fn a_server_error_is_retried_until_it_succeeds() {
+        let (url, received) = mock_server(vec![500, 503, 204]).await;
+        let mut queue = queue_for(vec![hook(&url, &[WebhookEventType::IncomingTx])]);
+        queue.enqueue(incoming("tx1"), fixture_time(0));
+
+        let mut now = fixture_time(0);
+        let mut results = Vec::new();
+        for _ in 0..3 {
+            let request = queue.take_due(now).remove(0);
+            let result = send_webhook(&request).await;
+            results.push(result.clone());
+            queue.record_attempt(&request, result, now);
+            now = queue.deliveries().next().unwrap().next_attempt_at;
+        }
+        assert_eq!(
+            results,
+            vec![
+                Err("HTTP 500".to_string()),
+                Err("HTTP 503".to_string()),
+                Ok(())
+            ]
+        );
+        let delivery = queue.deliveries().next().unwrap();
+        assert_eq!(delivery.status, DeliveryStatus::Delivered);
+        assert_eq!(delivery.attempts, 3);
+
+        // At-least-once: every attempt had the same id and body
+        let received = received.lock().unwrap().clone();
+        assert_eq!(received.len(), 3);
+        assert!(received.windows(2).all(|pair| pair[0].body == pair[1].body
+            && pair[0].header(WEBHOOK_ID_HEADER) == pair[1].header(WEBHOOK_ID_HEADER)));
+    }
+
+    #[tokio::test]
+    async fn a_server_that_keeps_failing_is_given_up_on() {
+        let (url, received) = mock_server(vec![500]).await;
+        let mut queue = queue_for(vec![hook(&url, &[WebhookEventType::IncomingTx])]);
+        queue.enqueue(incoming("tx1"), fixture_time(0));
+        let mut now = fixture_time(0);
+        while let Some(request) = queue.take_due(now).pop() {
+            let result = send_webhook(&request).await;
+            queue.record_attempt(&request, result, now);
+            now = queue.deliveries().next().unwrap().next_attempt_at;
+        }
+        assert_eq!(
+            received.lock().unwrap().len(),
+            WEBHOOK_MAX_ATTEMPTS as usize
+        );
+        let delivery = queue.deliveries().next().unwrap();
+        assert_eq!(delivery.status, DeliveryStatus::GaveUp);
+        assert_eq!(delivery.last_error.as_deref(), Some("HTTP 500"));
+    }
+
+    #[tokio::test]
+    async fn an_unreachable_server_is_a_failed_attempt() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let request = WebhookRequest {
+            event_id: "id".to_string(),
+            url: format!("http://{}/", addr),
+            body: b"{}".to_vec(),
+            signature: sign("s", b"{}"),
+        };
+        let err = send_webhook(&request).await.unwrap_err();
+        assert!(err.starts_with("Connection failed"), "{}", err);
+    }
+
+    #[test]
+    fn payments_are_announced_once_they_reach_the_threshold() {
+        let mut wallet = Wallet::new();
+        wallet.keys = NockchainKeyManager::from_seed([8; 32]);
+        let address = wallet.generate_key("shop").unwrap();
+        let paid = fake_signed_transaction().paying(&address, 700).build();
+        let other = fake_signed_transaction()
+            .paying(&fake_address(), 300)
+            .build();
+        wallet.record_transaction(paid.clone(), false);
+        wallet.record_transaction(other.clone(), true);
+        wallet
+            .transactions_mut()
+            .confirm_transaction(&paid.id, 10)
+            .unwrap();
+        wallet
+            .transactions_mut()
+            .confirm_transaction(&other.id, 10)
+            .unwrap();
+
+        wallet.balances.set_tip_height(11);
+        assert!(wallet.confirmed_payment_events(3).is_empty());
+        wallet.balances.set_tip_height(12);
+        assert_eq!(
+            wallet.confirmed_payment_events(3),
+            vec![WalletEvent::Confirmed {
+                tx_id: paid.id.clone(),
+                amount: 700,
+                block_height: 10,
+                confirmations: 3,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn the_queue_survives_reopening_and_read_only_queues_nothing() {
+        let dir = TempDir::new("webhooks");
+        let mut wallet = Wallet::new();
+        wallet.data_dir = Some(dir.path().to_path_buf());
+        wallet
+            .set_webhooks(vec![hook("http://a/", &[WebhookEventType::IncomingTx])])
+            .unwrap();
+        wallet.queue_webhook_event(incoming("tx1"));
+        wallet.save_webhooks().await.unwrap();
+
+        let mut reopened = Wallet::new();
+        reopened.data_dir = Some(dir.path().to_path_buf());
+        reopened.load_webhooks().await.unwrap();
+        assert_eq!(reopened.webhooks(), wallet.webhooks());
+
+        reopened.read_only = true;
+        assert_eq!(reopened.queue_webhook_event(incoming("tx2")), 0);
+        assert!(reopened.set_webhooks(Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,104 @@
+//! The tracing setup as the desktop app installs it: nothing on stdout, and
+//! our own events bridged into the node log buffers.
+
+use api::wallet::data_dir::DataDirLimits;
+use api::wallet::logging::{init_tracing, set_debug_logging};
+use api::wallet::network::{LogLevel, NockchainNodeConfig, NockchainNodeManager};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Set in the child process that runs the node for the stdout check
+const CHILD_ENV: &str = "NOCKCHAIN_QUIET_STDOUT_CHILD";
+const BEGIN: &str = "<<node start>>";
+const END: &str = "<<node stopped>>";
+
+fn temp_dir(label: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("nockchain-{}-{}", label, uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn fakenet_in(dir: &Path) -> NockchainNodeConfig {
+    NockchainNodeConfig {
+        fakenet: true,
+        peers: Vec::new(),
+        persist_mempool: false,
+        data_dir: dir.to_path_buf(),
+        data_dir_limits: DataDirLimits {
+            min_free_bytes: 0,
+            soft_cap_bytes: None,
+        },
+        ..NockchainNodeConfig::default()
+    }
+}
+
+async fn start_and_stop(dir: &Path, debug_logging: bool) -> NockchainNodeManager {
+    let mut manager = NockchainNodeManager::new(NockchainNodeConfig {
+        debug_logging,
+        ..fakenet_in(dir)
+    });
+    manager.start_node().await.unwrap();
+    manager.get_node_stats().unwrap();
+    manager.stop_node().await.unwrap();
+    manager
+}
+
+/// Runs only inside the child spawned by `start_and_stop_print_nothing_to_stdout`
+#[tokio::test]
+async fn child_starts_and_stops_a_node() {
+    if std::env::var_os(CHILD_ENV).is_none() {
+        return;
+    }
+    init_tracing(false);
+    let dir = temp_dir("quiet-child");
+    use std::io::Write;
+    print!("{}", BEGIN);
+    std::io::stdout().flush().unwrap();
+    start_and_stop(&dir, false).await;
+    print!("{}", END);
+    std::io::stdout().flush().unwrap();
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn start_and_stop_print_nothing_to_stdout() {
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "child_starts_and_stops_a_node", "--nocapture"])
+        .env(CHILD_ENV, "1")
+        .env_remove("NOCKCHAIN_DEBUG_LOGGING")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let begin = stdout.find(BEGIN).expect("child ran") + BEGIN.len();
+    let end = stdout.find(END).expect("child finished");
+    assert_eq!(&stdout[begin..end], "", "start/stop wrote to stdout");
+}
+
+#[tokio::test]
+async fn debug_events_reach_the_node_console_only_when_enabled() {
+    init_tracing(false);
+    let dir = temp_dir("bridge");
+
+    set_debug_logging(true);
+    let manager = start_and_stop(&dir, true).await;
+    let debug_entries = |manager: &NockchainNodeManager| {
+        manager
+            .get_logs(None)
+            .into_iter()
+            .filter(|entry| {
+                entry.level == LogLevel::Debug
+                    && entry
+                        .message
+                        .contains("NockchainNodeManager::start_node() called")
+            })
+            .count()
+    };
+    assert_eq!(debug_entries(&manager), 1);
+
+    set_debug_logging(false);
+    let quiet = start_and_stop(&dir, false).await;
+    assert_eq!(debug_entries(&quiet), 0);
+    let _ = std::fs::remove_dir_all(dir);
+}
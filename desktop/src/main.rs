@@ -1,54 +1,1269 @@
-use api::wallet::network::{LogEntry, LogLevel, LogSource, NockchainNodeManager, NodeStatus};
-use api::wallet::WalletError;
-use api::Balance;
+use api::wallet::network::{
+    LogEntry, LogLevel, LogSource, NockchainNodeConfig, NockchainNodeManager, NodeHandle,
+    NodeStats, NodeStatus,
+};
+use api::wallet::rpc::{RpcContext, RpcServer};
+use api::wallet::selftest;
+use api::wallet::send::SendRequest;
+use api::wallet::{qr, BlockchainConfig, Decode, Encode, WalletError, DEFAULT_MIN_CONFIRMATIONS};
+use api::{
+    deliver_due_webhooks, export_ledger_to_writer, export_logs_to_writer, export_to_file,
+    load_console_history, load_ui_prefs, save_console_history, save_ui_prefs, Address,
+    AddressBalance, AuditCategory, AuditChainBreak, AuditEntry, AuditEvent, AuditFilter,
+    AuditOutcome, AvailabilityReport, Balance, BlockView, Blockchain, BlocksPage,
+    ChainTransactionView, CheckStatus, CompactionReport, ConfigLayers, DailyAllowance,
+    DataDirUsage, DisplaySettings, ExportFilter, ExportFormat, ExportSummary, FeeEstimator,
+    FeePriority, FilterSyncStats, HealthReport, IntegrityReport, KeyImportFormat, KeyImportReport,
+    LaunchOptions, LogBufferConfig, LogLevelCounts, LogLevels, MaintenanceReport, MiningReward,
+    MnemonicLanguage, NetworkConditions, Note, NoteProvenance, PackageSignatures, PaymentRequest,
+    PaymentTemplate, PeerInfo, PurgeScope, ReconciliationReport, RecoveryAction, SearchResult,
+    SearchTarget, SendDraft, SendFailure, SendProgress, SendStage, Socks5Proxy, SpendableSummary,
+    SpendingLimits, StorageBreakdown, Transaction, UnsignedTransactionPackage, ValidationPhase,
+    VersionInfo, Wallet, WalletEvent, WalletProfileManager, WebhookConfig, WebhookDelivery,
+    DEFAULT_KEEP_FULL_BLOCKS, LAUNCH_USAGE,
+};
 use dioxus::prelude::*;
-use std::sync::{Arc, Mutex};
-use ui::{BalanceCard, Hero, Navbar, NodeConsole};
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, error, warn};
+use ui::format::{format_bytes, format_count};
+use ui::{
+    print, t, AboutPanel, AddressBreakdown, AuditLogViewer, BalanceCard, BlockDetail, BlockList,
+    ChainTransactionDetail, ClockEpoch, ColdSigningPanel, ConsoleState, DangerZone,
+    DataDirUsageBar, DisplaySettingsForm, DraftBanner, DuePaymentsCard, ErrorState, ExportPanel,
+    FeeSelector, Hero, HeroAction, IntegrityPanel, KeyImportResults, LanguagePicker, LoadState,
+    Locale, LogLevelGrid, MaintenancePanel, MiningPanel, MnemonicRestoreForm, Navbar, NodeConsole,
+    NodeStatsSkeleton, NodeStatsStrip, NoteList, OverridesChip, PaymentRequestValues, PeerTable,
+    PreflightScreen, ProfilePicker, ReadOnlyBanner, ReauthDialog, ReceiveView, RecoveryDialog,
+    SearchBar, SecretKeyImportForm, SendForm, SendFormValues, SendPrefill, SimulatedNetworkBanner,
+    SpendablePanel, SpendingLimitsForm, StoragePanel, TemplateDetails, TemplatePicker,
+    TransactionDecoder, TransactionDetail, TransactionList, UptimeCalendar, WebhookSettings,
+};
+use uuid::Uuid;
 
 #[derive(Clone, Routable, Debug, PartialEq)]
 enum Route {
     #[layout(Layout)]
     #[route("/")]
     Home {},
+    #[route("/send?:template")]
+    SendPage { template: String },
+    #[route("/keys")]
+    Keys {},
+    #[route("/tx/:id")]
+    TransactionPage { id: String },
+    #[route("/notes?:address")]
+    NotesPage { address: String },
     #[route("/node")]
     Node {},
+    #[route("/settings")]
+    Settings {},
+    #[route("/decode")]
+    DecodeTool {},
+    #[route("/explorer")]
+    Explorer {},
+    #[route("/explorer/block/:id")]
+    ExplorerBlock { id: String },
+    #[route("/explorer/tx/:id")]
+    ExplorerTransaction { id: String },
 }
 
+/// Flat fee attached to sends from the desktop app
+const SEND_FEE: u64 = 10;
+
+/// Width in pixels of saved address QR images
+const QR_IMAGE_SIZE: u32 = 512;
+
+/// Recent blocks charted in the Send page fee selector
+const FEE_CHART_BLOCKS: usize = 50;
+
+/// How long the Send page waits after the last change before saving its draft
+const DRAFT_SAVE_DELAY: Duration = Duration::from_secs(1);
+
+/// How long the Home page waits for the wallet before showing an error
+const WALLET_READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Days of node availability shown on the Node page calendar
+const UPTIME_CALENDAR_DAYS: i64 = 91;
+
+/// Blocks per page of the explorer's latest-blocks list
+const EXPLORER_PAGE_SIZE: usize = 25;
+
+/// Clicks and key presses closer together than this count as one for the
+/// wallet's auto-lock
+const SESSION_TOUCH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Flags the app was started with, set once in `main`
+static LAUNCH_OPTIONS: OnceLock<LaunchOptions> = OnceLock::new();
+
 fn main() {
+    let options = match LaunchOptions::from_env() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, LAUNCH_USAGE);
+            rfd::MessageDialog::new()
+                .set_level(rfd::MessageLevel::Error)
+                .set_title("Can't start with these options")
+                .set_description(format!("{}\n\n{}", e, LAUNCH_USAGE))
+                .set_buttons(rfd::MessageButtons::Ok)
+                .show();
+            std::process::exit(2);
+        }
+    };
+    if options.help {
+        println!("{}", LAUNCH_USAGE);
+        return;
+    }
+
+    let debug_logging = options
+        .log_level
+        .is_some_and(|level| level <= LogLevel::Debug);
+    api::wallet::logging::init_tracing(debug_logging);
+    LAUNCH_OPTIONS.get_or_init(|| options);
     dioxus::launch(App);
 }
 
+fn launch_options() -> &'static LaunchOptions {
+    LAUNCH_OPTIONS.get_or_init(LaunchOptions::default)
+}
+
 #[component]
 fn App() -> Element {
+    // Startup checks run before anything touches the data dir or ports; the
+    // app opens straight away only when every one passes
+    let mut checks = use_signal(selftest::run);
+    let mut continued = use_signal(|| false);
+
+    if continued() || selftest::worst_status(&checks.read()) == CheckStatus::Pass {
+        return rsx! {
+            Router::<Route> {}
+        };
+    }
     rsx! {
-        Router::<Route> {}
+        PreflightScreen {
+            results: checks(),
+            on_continue: move |_| continued.set(true),
+            on_retry: move |_| checks.set(selftest::run()),
+        }
     }
 }
 
 #[component]
 fn Layout() -> Element {
+    let node = use_context_provider(|| {
+        // Defaults, then nockchain.toml, then NOCKCHAIN_* variables, then
+        // saved edits, then the command line for this session only
+        let overrides = launch_options().config_overrides();
+        let manager = match ConfigLayers::load_with_overrides(std::env::vars(), overrides) {
+            Ok((config, layers)) => NockchainNodeManager::new(config).with_config_layers(layers),
+            Err(e) => {
+                error!("Failed to load node config, using defaults: {}", e);
+                NockchainNodeManager::new(NockchainNodeConfig::default())
+            }
+        };
+        NodeHandle::new(manager)
+    });
+    // No wallet is open until a profile is picked
+    let wallet = use_context_provider(|| Arc::new(tokio::sync::RwLock::new(Wallet::new())));
+    // Local chain state, read by search and the explorer pages. Nothing
+    // syncs blocks into it yet.
+    let chain = use_context_provider(|| {
+        Arc::new(tokio::sync::RwLock::new(Blockchain::new(
+            BlockchainConfig::default(),
+        )))
+    });
+    let config_dir = use_hook(|| app_config_dir(&node));
+    // Language from the UI prefs; every `t!` below reads this signal
+    use_context_provider(|| {
+        let prefs = config_dir
+            .as_deref()
+            .map(load_ui_prefs)
+            .transpose()
+            .unwrap_or_else(|e| {
+                error!("Failed to load UI preferences: {}", e);
+                None
+            })
+            .unwrap_or_default();
+        Signal::new(
+            prefs
+                .language
+                .as_deref()
+                .and_then(Locale::from_code)
+                .unwrap_or_default(),
+        )
+    });
+    // Bumped on each system clock jump so relative times are redrawn
+    let mut clock_epoch = use_context_provider(|| Signal::new(ClockEpoch::default()));
+    // Console lines live here so leaving the Node page doesn't clear them
+    let console = use_context_provider(|| new_console_state(&node, config_dir.as_deref()));
+    // On close, keep the end of the console for the next launch and write
+    // out any batched storage saves
+    dioxus::desktop::use_wry_event_handler(move |event, _| {
+        use dioxus::desktop::tao::event::{Event, WindowEvent};
+        if let Event::WindowEvent {
+            event: WindowEvent::CloseRequested,
+            ..
+        } = event
+        {
+            if let Some(dir) = &config_dir {
+                if let Err(e) = save_console_history(dir, &console.logs.peek()) {
+                    error!("Failed to save console history: {}", e);
+                }
+            }
+            // Write anything an unfinished batch is still holding back
+            if let Err(e) = api::wallet::storage::flush_all() {
+                error!("Failed to flush wallet storage: {}", e);
+            }
+        }
+    });
+    // Node data dir from the config, used by profiles that share node data
+    let shared_node_dir = use_hook(|| {
+        node.try_with(|manager| manager.get_config().data_dir.clone())
+            .unwrap_or_else(|| NockchainNodeConfig::default().data_dir)
+    });
+    let mut profiles =
+        use_signal(|| load_profiles(&node, &shared_node_dir).map_err(|e| e.to_string()));
+    let mut active_profile = use_signal(|| None::<String>);
+    let mut profile_error = use_signal(|| None::<String>);
+    let mut profile_busy = use_signal(|| false);
+    let mut health = use_signal(|| None::<HealthReport>);
+    let mut read_only = use_signal(|| false);
+    let mut integrity_failed = use_signal(|| false);
+    // A newer version wrote the wallet's data dir, so it stays read-only
+    let mut newer_schema = use_signal(|| false);
+    // What recovery did with operations the last session left unfinished
+    let mut recovery_actions = use_signal(|| None::<Vec<RecoveryAction>>);
+    let mut recovering = use_signal(|| false);
+    // While the wallet is auto-locked: whether unlocking takes a password
+    let mut session_locked = use_signal(|| None::<bool>);
+    let mut unlock_error = use_signal(|| None::<String>);
+    let mut last_touch = use_signal(Instant::now);
+    // `--locked` and `--auto-start-node` apply to the first profile opened
+    let mut first_open = use_signal(|| true);
+
+    // Serve JSON-RPC on localhost only
+    let rpc_ctx = RpcContext {
+        wallet: wallet.clone(),
+        node: node.clone(),
+    };
+    use_future(move || {
+        let ctx = rpc_ctx.clone();
+        async move {
+            let rpc_port = ctx
+                .node
+                .with_timeout(Duration::from_secs(2), |manager| {
+                    manager.get_config().rpc_port
+                })
+                .await
+                .unwrap_or(NockchainNodeConfig::default().rpc_port);
+            let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, rpc_port));
+            match RpcServer::bind(addr, ctx).await {
+                Ok(server) => {
+                    debug!("RPC server listening on {}", addr);
+                    if let Err(e) = server.serve().await {
+                        error!("RPC server stopped: {}", e);
+                    }
+                }
+                Err(e) => error!("{}", e),
+            }
+        }
+    });
+
+    // Refresh the navbar health indicator, and pick up the wallet lock once
+    // another instance releases it
+    let health_node = node.clone();
+    let health_wallet = wallet.clone();
+    use_future(move || {
+        let node = health_node.clone();
+        let wallet = health_wallet.clone();
+        async move {
+            loop {
+                let report = wallet.read().await.health_check(&node).await;
+                health.set(Some(report));
+                read_only.set(upgrade_wallet(&wallet).await);
+                integrity_failed.set(wallet.read().await.integrity_failed());
+                // A sleep or clock change: the node forgives the peer timeouts it
+                // caused, and time asleep counts as idle for the auto-lock
+                let jump = node.try_with(|manager| manager.check_clock()).flatten();
+                if let Some(jump) = jump {
+                    wallet.write().await.note_clock_jump(&jump);
+                    clock_epoch.with_mut(|epoch| epoch.0 += 1);
+                }
+                // Lock once idle too long; the lock screen asks for the password
+                {
+                    let mut wallet = wallet.write().await;
+                    wallet.check_auto_lock();
+                    let locked = active_profile.peek().is_some() && !wallet.is_unlocked();
+                    session_locked.set(locked.then(|| wallet.session().has_password()));
+                }
+                // Sends left unresolved at open can be settled once the node runs
+                if node.status() == NodeStatus::Running
+                    && !wallet.read().await.pending_operations().is_empty()
+                {
+                    let actions = wallet.write().await.recover_operations(&node).await;
+                    if !actions.is_empty() {
+                        recovery_actions.set(Some(actions));
+                    }
+                }
+                // Catches entries recorded without an explicit save
+                save_wallet_records(&wallet).await;
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+        }
+    });
+
+    // Prune old records per the retention policy, once the wallet is open
+    // and daily after
+    let maintenance_node = node.clone();
+    let maintenance_wallet = wallet.clone();
+    use_future(move || {
+        let node = maintenance_node.clone();
+        let wallet = maintenance_wallet.clone();
+        async move {
+            loop {
+                let due = {
+                    let wallet = wallet.read().await;
+                    wallet.maintenance_due() && !wallet.is_read_only()
+                };
+                let failed = due && run_wallet_maintenance(&wallet, &node).await.is_err();
+                let wait = if failed { 3600 } else { 60 };
+                tokio::time::sleep(Duration::from_secs(wait)).await;
+            }
+        }
+    });
+
+    // Payments to the wallet show as soon as they reach the mempool. There is
+    // no local chain yet, so they stay pending once they leave it unless a
+    // double spend took their place.
+    let mut incoming_toast = use_signal(|| None::<String>);
+    let incoming_node = node.clone();
+    let incoming_wallet = wallet.clone();
+    use_future(move || {
+        let node = incoming_node.clone();
+        let wallet = incoming_wallet.clone();
+        async move {
+            loop {
+                let pending = if node.status() == NodeStatus::Running {
+                    node.with_timeout(Duration::from_secs(1), |manager| {
+                        manager.with_mempool(|mempool| {
+                            mempool.transactions().cloned().collect::<Vec<_>>()
+                        })
+                    })
+                    .await
+                    .flatten()
+                } else {
+                    None
+                };
+                if let Some(pending) = pending {
+                    let mut wallet = wallet.write().await;
+                    let events = wallet.track_incoming(&pending, None);
+                    for event in events.iter().filter_map(WalletEvent::from_incoming) {
+                        wallet.queue_webhook_event(event);
+                    }
+                    drop(wallet);
+                    if !events.is_empty() {
+                        let message = events
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect::<Vec<_>>()
+                            .join("\n");
+                        incoming_toast.set(Some(message));
+                    }
+                }
+
+                // Payment requests are settled by what reached their addresses,
+                // or expire, whether or not the node runs
+                let request_events = {
+                    let mut wallet = wallet.write().await;
+                    let events = wallet.check_payment_requests(chrono::Utc::now());
+                    for event in &events {
+                        wallet.queue_webhook_event(event.clone());
+                    }
+                    events
+                };
+                if !request_events.is_empty() {
+                    let message = request_events
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    incoming_toast.set(Some(message));
+                }
+
+                // Sends every answering peer refused are failed and their notes freed
+                let sent: Vec<String> = {
+                    let wallet = wallet.read().await;
+                    wallet
+                        .transactions()
+                        .get_pending_transactions()
+                        .iter()
+                        .filter(|tx| tx.is_outgoing && !wallet.is_read_only())
+                        .map(|tx| tx.id.clone())
+                        .collect()
+                };
+                let reports = if sent.is_empty() || node.status() != NodeStatus::Running {
+                    None
+                } else {
+                    node.with_timeout(Duration::from_secs(1), move |manager| {
+                        sent.into_iter()
+                            .filter_map(|id| manager.relay_report(&id).map(|report| (id, report)))
+                            .collect::<Vec<_>>()
+                    })
+                    .await
+                };
+                for (id, report) in reports.unwrap_or_default() {
+                    let mut wallet = wallet.write().await;
+                    match wallet.apply_relay_report(&id, &report) {
+                        Ok(true) => {
+                            if let Err(e) = report.outcome() {
+                                incoming_toast.set(Some(e.to_string()));
+                            }
+                        }
+                        Ok(false) => {}
+                        Err(e) => error!("Failed to apply relay report for {}: {}", id, e),
+                    }
+                }
+                tokio::time::sleep(Duration::from_secs(3)).await;
+            }
+        }
+    });
+
+    // Webhooks: payments reaching the confirmation threshold and node errors
+    // are queued here, and due deliveries sent, outside the wallet lock
+    let webhook_wallet = wallet.clone();
+    use_future(move || {
+        let wallet = webhook_wallet.clone();
+        async move {
+            loop {
+                {
+                    let mut wallet = wallet.write().await;
+                    let threshold = wallet.balances().min_confirmations();
+                    for event in wallet.confirmed_payment_events(threshold) {
+                        wallet.queue_webhook_event(event);
+                    }
+                }
+                deliver_due_webhooks(&wallet).await;
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+        }
+    });
+    let error_wallet = wallet.clone();
+    let error_node = node.clone();
+    use_future(move || {
+        let wallet = error_wallet.clone();
+        let mut status_rx = error_node.subscribe_status();
+        async move {
+            while status_rx.changed().await.is_ok() {
+                let status = status_rx.borrow_and_update().clone();
+                if let NodeStatus::Error(message) = status {
+                    wallet
+                        .write()
+                        .await
+                        .queue_webhook_event(WalletEvent::NodeError {
+                            message,
+                            at: chrono::Utc::now(),
+                        });
+                }
+            }
+        }
+    });
+
+    let retry_writable = {
+        let wallet = wallet.clone();
+        move |_| {
+            let wallet = wallet.clone();
+            spawn(async move {
+                {
+                    // Files may have been restored since the failed check
+                    let mut wallet = wallet.write().await;
+                    if wallet.integrity_failed() {
+                        wallet.verify_integrity();
+                    }
+                }
+                read_only.set(upgrade_wallet(&wallet).await);
+                integrity_failed.set(wallet.read().await.integrity_failed());
+            });
+        }
+    };
+
+    let mut search_results = use_signal(Vec::<SearchResult>::new);
+    let search_handler = {
+        let wallet = wallet.clone();
+        let chain = chain.clone();
+        move |query: String| {
+            let wallet = wallet.clone();
+            let chain = chain.clone();
+            spawn(async move {
+                // No contact book in the desktop app yet
+                let chain = chain.read().await;
+                search_results.set(wallet.read().await.search(&query, None, Some(&chain)));
+            });
+        }
+    };
+    let navigator = use_navigator();
+    // Bumped to remount a page that failed to render
+    let mut page_mounts = use_signal(|| 0_u32);
+    let open_search_result = move |target: SearchTarget| {
+        let route = match target {
+            SearchTarget::Transaction { tx_id } => Route::TransactionPage { id: tx_id },
+            SearchTarget::Address { .. } => Route::Keys {},
+            SearchTarget::Contact { .. } => Route::SendPage {
+                template: String::new(),
+            },
+            SearchTarget::Block { height, .. } => Route::ExplorerBlock {
+                id: height.to_string(),
+            },
+        };
+        navigator.push(route);
+    };
+
+    let open_profile = {
+        let wallet = wallet.clone();
+        let node = node.clone();
+        move |(name, password): (String, Option<String>)| {
+            profile_error.set(None);
+            let opened = match profiles.write().as_mut() {
+                Ok(manager) => open_profile_wallet(
+                    manager,
+                    &node,
+                    &shared_node_dir,
+                    &name,
+                    password.as_deref(),
+                ),
+                Err(e) => Err(e.clone()),
+            };
+            let opened = match opened {
+                Ok(opened) => opened,
+                Err(e) => {
+                    profile_error.set(Some(e));
+                    return;
+                }
+            };
+            let wallet = wallet.clone();
+            let node = node.clone();
+            profile_busy.set(true);
+            spawn(async move {
+                // Load the records under the same lock, so the periodic save
+                // can't write the empty ones of the new wallet over them
+                let mut opened_wallet = wallet.write().await;
+                *opened_wallet = opened;
+                load_wallet_records(&mut opened_wallet).await;
+                // Warn when a newer version wrote the data dir: saves are refused
+                newer_schema.set(opened_wallet.schema_too_new());
+                if opened_wallet.schema_too_new() {
+                    if let Err(e) = opened_wallet.ensure_writable() {
+                        warn!("Opened profile {} read-only: {}", name, e);
+                        incoming_toast
+                            .set(Some(format!("⚠️ {}. Update the app to make changes.", e)));
+                    }
+                }
+                let interrupted = !opened_wallet.pending_operations().is_empty();
+                if interrupted {
+                    recovery_actions.set(Some(Vec::new()));
+                    recovering.set(true);
+                    let actions = opened_wallet.recover_operations(&node).await;
+                    recovery_actions.set(Some(actions));
+                    recovering.set(false);
+                }
+                drop(opened_wallet);
+                if interrupted {
+                    save_wallet_records(&wallet).await;
+                }
+                active_profile.set(Some(name));
+                profile_busy.set(false);
+
+                if std::mem::replace(&mut *first_open.write(), false) {
+                    let options = launch_options();
+                    if options.locked {
+                        let mut wallet = wallet.write().await;
+                        wallet.lock();
+                        session_locked.set(Some(wallet.session().has_password()));
+                    }
+                    if options.auto_start_node {
+                        auto_start_node(&node).await;
+                    }
+                }
+            });
+        }
+    };
+    // `--profile` opens its profile straight away, or picks it in the
+    // picker when it takes a password
+    use_hook({
+        let mut open_profile = open_profile.clone();
+        move || {
+            let Some(name) = launch_options().profile.as_deref() else {
+                return;
+            };
+            let found = profiles.peek().as_ref().ok().map(|manager| {
+                manager
+                    .get(name)
+                    .map(|profile| (profile.name.clone(), profile.has_password()))
+            });
+            match found {
+                Some(Some((name, false))) => open_profile((name, None)),
+                Some(None) => {
+                    profile_error.set(Some(format!("No profile named {} (from --profile)", name)))
+                }
+                _ => {}
+            }
+        }
+    });
+    let mut open_profile_create = open_profile.clone();
+    let create_profile = move |(name, password): (String, Option<String>)| {
+        profile_error.set(None);
+        let created = match profiles.write().as_mut() {
+            Ok(manager) => manager
+                .create(&name, password.as_deref())
+                .map(|profile| profile.name.clone())
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.clone()),
+        };
+        match created {
+            Ok(name) => open_profile_create((name, password)),
+            Err(e) => profile_error.set(Some(e)),
+        }
+    };
+
+    // Lock the current wallet, save its records and drop it, releasing its
+    // lockfile, then go back to the profile picker
+    let switch_profile = {
+        let wallet = wallet.clone();
+        move |_| {
+            let wallet = wallet.clone();
+            spawn(async move {
+                wallet.write().await.lock();
+                save_wallet_records(&wallet).await;
+                *wallet.write().await = Wallet::new();
+                search_results.set(Vec::new());
+                active_profile.set(None);
+                navigator.push(Route::Home {});
+            });
+        }
+    };
+
+    let unlock_wallet = {
+        let wallet = wallet.clone();
+        move |password: String| {
+            let wallet = wallet.clone();
+            spawn(async move {
+                let mut wallet = wallet.write().await;
+                let password = wallet.session().has_password().then_some(password);
+                match wallet.unlock(password.as_deref()) {
+                    Ok(()) => {
+                        unlock_error.set(None);
+                        session_locked.set(None);
+                    }
+                    Err(e) => unlock_error.set(Some(e.to_string())),
+                }
+            });
+        }
+    };
+
+    // Any click or key press puts off the auto-lock
+    let touch_session = {
+        let wallet = wallet.clone();
+        move || {
+            if last_touch.peek().elapsed() < SESSION_TOUCH_INTERVAL {
+                return;
+            }
+            last_touch.set(Instant::now());
+            if let Ok(mut wallet) = wallet.try_write() {
+                wallet.touch_session();
+            }
+        }
+    };
+
+    let profile_list = profiles
+        .read()
+        .as_ref()
+        .map(|manager| manager.profiles().into_iter().cloned().collect())
+        .unwrap_or_default();
+    let initial_profile = launch_options().profile.as_deref().and_then(|name| {
+        let profiles = profiles.read();
+        let profile = profiles.as_ref().ok()?.get(name)?;
+        Some(profile.name.clone())
+    });
+    let picker_error = profile_error().or_else(|| profiles.read().as_ref().err().cloned());
+    let read_only_message = if newer_schema() {
+        Some("A newer version of the app saved this wallet. Update the app to make changes; until then it opens read-only.".to_string())
+    } else {
+        integrity_failed().then(|| {
+            "Some wallet files failed the integrity check. Review them under Settings, then restore them from a backup or trust them as they are.".to_string()
+        })
+    };
+
     rsx! {
-        div { style: "min-height: 100vh; display: flex; flex-direction: column;",
-            Navbar {}
+        div {
+            style: "min-height: 100vh; display: flex; flex-direction: column;",
+            onclick: {
+                let mut touch_session = touch_session.clone();
+                move |_| touch_session()
+            },
+            onkeydown: {
+                let mut touch_session = touch_session.clone();
+                move |_| touch_session()
+            },
+            Navbar { health: health(),
+                if let Some(name) = active_profile() {
+                    SearchBar {
+                        results: search_results(),
+                        on_query: search_handler,
+                        on_open: open_search_result,
+                    }
+                    button {
+                        style: "padding: 4px 10px;",
+                        title: "Lock this wallet and choose another profile",
+                        onclick: switch_profile,
+                        "{name} · Switch"
+                    }
+                }
+            }
+            if let Some(password_required) = session_locked() {
+                ReauthDialog {
+                    title: "Wallet locked",
+                    message: "The wallet locked itself after being left idle.".to_string(),
+                    password_required,
+                    error: unlock_error(),
+                    on_submit: unlock_wallet,
+                }
+            }
+            if let Some(actions) = recovery_actions() {
+                RecoveryDialog {
+                    actions,
+                    recovering: recovering(),
+                    on_dismiss: move |_| recovery_actions.set(None),
+                }
+            }
+            if read_only() {
+                ReadOnlyBanner {
+                    on_retry: retry_writable,
+                    message: read_only_message,
+                }
+            }
+            if let Some(message) = incoming_toast() {
+                div {
+                    style: "position: fixed; bottom: 24px; right: 24px; max-width: 420px; background: #1a1a1a; color: white; padding: 12px 16px; border-radius: 8px; box-shadow: 0 4px 20px rgba(0, 0, 0, 0.25); display: flex; gap: 12px; align-items: flex-start; z-index: 1000; white-space: pre-line;",
+                    role: "status",
+                    aria_live: "polite",
+                    span { "{message}" }
+                    button {
+                        style: "background: none; border: none; color: #9ca3af; cursor: pointer;",
+                        aria_label: t!("nav.dismiss"),
+                        onclick: move |_| incoming_toast.set(None),
+                        "✕"
+                    }
+                }
+            }
             main { style: "flex: 1; padding: 20px;",
-                Outlet::<Route> {}
+                if active_profile().is_some() {
+                    // A page that panics or fails to render shows this
+                    // instead of leaving the window blank. A new key mounts
+                    // the page afresh.
+                    ErrorBoundary {
+                        key: "{page_mounts}",
+                        handle_error: move |errors: ErrorContext| {
+                            let message = errors
+                                .errors()
+                                .iter()
+                                .map(ToString::to_string)
+                                .collect::<Vec<_>>()
+                                .join("\n");
+                            rsx! {
+                                ErrorState {
+                                    title: t!("error.page"),
+                                    message,
+                                    on_retry: move |_| page_mounts += 1,
+                                }
+                                button {
+                                    onclick: move |_| {
+                                        navigator.push(Route::Home {});
+                                        page_mounts += 1;
+                                    },
+                                    {t!("error.go_home")}
+                                }
+                            }
+                        },
+                        Outlet::<Route> {}
+                    }
+                } else {
+                    ProfilePicker {
+                        profiles: profile_list,
+                        on_open: open_profile,
+                        on_create: create_profile,
+                        error: picker_error,
+                        busy: profile_busy(),
+                        initial: initial_profile,
+                    }
+                }
             }
         }
     }
 }
 
+/// Console state holding the previous session's saved lines, if any, then a
+/// first line for this one
+fn new_console_state(node: &NodeHandle, history_dir: Option<&Path>) -> ConsoleState {
+    let history = match history_dir.map(load_console_history) {
+        Some(Ok(history)) => history,
+        Some(Err(e)) => {
+            error!("Failed to load console history: {}", e);
+            Vec::new()
+        }
+        None => Vec::new(),
+    };
+    let first = LogEntry {
+        timestamp: chrono::Utc::now(),
+        level: LogLevel::Info,
+        source: LogSource::Node,
+        message: "Nockchain node ready to start. Click Start Node to begin.".to_string(),
+        operation_id: None,
+        repeat: None,
+    };
+    let max_lines = node
+        .try_with(|manager| manager.get_config().log_buffer.max_entries)
+        .unwrap_or_else(|| LogBufferConfig::default().max_entries);
+    ConsoleState::new(history, first, max_lines)
+}
+
+/// Where app files such as the console history and UI prefs are kept: next
+/// to the profiles, or in the node data dir without a config dir
+fn app_config_dir(node: &NodeHandle) -> Option<PathBuf> {
+    node.try_with(|manager| {
+        manager
+            .config_dir()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| manager.get_config().data_dir.clone())
+    })
+}
+
+/// Profiles listed next to the node config. The wallet from before profiles
+/// existed, in the node data dir, is listed as "Default".
+fn load_profiles(node: &NodeHandle, node_dir: &Path) -> Result<WalletProfileManager, WalletError> {
+    let root = node
+        .try_with(|manager| manager.config_dir().map(Path::to_path_buf))
+        .flatten()
+        .unwrap_or_else(|| node_dir.to_path_buf());
+    let mut manager = WalletProfileManager::load(&root)?;
+    if manager.profiles().is_empty() && node_dir.join("wallet").exists() {
+        manager.adopt("Default", node_dir, None)?;
+    }
+    Ok(manager)
+}
+
+/// Open a profile's wallet and point the node at the profile's node data
+/// dir. The node must be stopped to change its data dir.
+fn open_profile_wallet(
+    manager: &mut WalletProfileManager,
+    node: &NodeHandle,
+    shared_node_dir: &Path,
+    name: &str,
+    password: Option<&str>,
+) -> Result<Wallet, String> {
+    let node_dir = manager
+        .get(name)
+        .map(|profile| manager.node_data_dir(profile, shared_node_dir))
+        .ok_or_else(|| format!("No profile named {}", name))?;
+    let (current_dir, chain_id) = node
+        .try_with(|manager| {
+            let config = manager.get_config();
+            (config.data_dir.clone(), config.chain_id())
+        })
+        .ok_or("The node is busy; try again in a moment")?;
+    if current_dir != node_dir && node.status() != NodeStatus::Stopped {
+        return Err("Stop the node before opening a profile with its own node data".to_string());
+    }
+
+    let mut wallet = manager.open(name, password).map_err(|e| e.to_string())?;
+    // Sign for the chain the node is on, so the node accepts what we send
+    wallet.set_chain_id(chain_id);
+    if current_dir != node_dir {
+        node.try_with(|manager| {
+            let mut config = manager.get_config().clone();
+            config.data_dir = node_dir;
+            manager.update_config(config);
+        })
+        .ok_or("The node is busy; try again in a moment")?;
+    }
+    Ok(wallet)
+}
+
+/// Start the node for `--auto-start-node`, once a profile has settled its
+/// data dir. The Node page shows the start as it goes.
+async fn auto_start_node(node: &NodeHandle) {
+    if node.status() != NodeStatus::Stopped {
+        return;
+    }
+    let shared = node.shared();
+    let result = match shared.lock() {
+        Ok(mut manager) => manager.start_node().await,
+        Err(e) => Err(WalletError::Network(format!("Lock error: {}", e))),
+    };
+    if let Err(e) = result {
+        error!("Failed to start the node (--auto-start-node): {}", e);
+    }
+}
+
+/// Load the audit log, spending ledger, payment templates, payment requests,
+/// address labels, display settings, operation journal, fee history, webhooks
+/// and retention policy of a wallet just opened
+async fn load_wallet_records(wallet: &mut Wallet) {
+    if let Err(e) = wallet.load_audit_log().await {
+        error!("Failed to load audit log: {}", e);
+    }
+    if let Err(e) = wallet.load_spending_ledger().await {
+        error!("Failed to load spending ledger: {}", e);
+    }
+    if let Err(e) = wallet.load_payment_templates().await {
+        error!("Failed to load payment templates: {}", e);
+    }
+    if let Err(e) = wallet.load_payment_requests().await {
+        error!("Failed to load payment requests: {}", e);
+    }
+    if let Err(e) = wallet.load_address_labels().await {
+        error!("Failed to load address labels: {}", e);
+    }
+    if let Err(e) = wallet.load_display_settings().await {
+        error!("Failed to load display settings: {}", e);
+    }
+    if let Err(e) = wallet.load_operation_journal().await {
+        error!("Failed to load operation journal: {}", e);
+    }
+    if let Err(e) = wallet.load_fee_history().await {
+        error!("Failed to load fee history: {}", e);
+    }
+    if let Err(e) = wallet.load_webhooks().await {
+        error!("Failed to load webhooks: {}", e);
+    }
+    if let Err(e) = wallet.load_retention_policy().await {
+        error!("Failed to load retention policy: {}", e);
+    }
+}
+
+/// Prune the wallet's records and the node's log files to the retention policy
+async fn run_wallet_maintenance(
+    wallet: &Arc<tokio::sync::RwLock<Wallet>>,
+    node: &NodeHandle,
+) -> Result<MaintenanceReport, WalletError> {
+    let log_dir = node
+        .with_timeout(Duration::from_secs(2), |manager| {
+            manager.get_config().data_dir.join("logs")
+        })
+        .await;
+    let report = wallet
+        .write()
+        .await
+        .run_maintenance(log_dir.as_deref())
+        .await;
+    if let Err(e) = &report {
+        error!("Maintenance failed: {}", e);
+    }
+    report
+}
+
+/// Take the wallet lock if this handle is read-only. Returns whether the
+/// wallet is still read-only.
+async fn upgrade_wallet(wallet: &Arc<tokio::sync::RwLock<Wallet>>) -> bool {
+    if !wallet.read().await.is_read_only() {
+        return false;
+    }
+    let mut wallet = wallet.write().await;
+    if let Err(e) = wallet.try_upgrade_to_writable() {
+        error!("Failed to take the wallet lock: {}", e);
+    }
+    wallet.is_read_only()
+}
+
+/// Write audit entries recorded since the last save, the spending ledger, the
+/// payment templates and requests, the address labels, the display settings,
+/// the fee history, the webhook queue and the retention policy
+async fn save_wallet_records(wallet: &Arc<tokio::sync::RwLock<Wallet>>) {
+    let mut wallet = wallet.write().await;
+    if let Err(e) = wallet.save_audit_log().await {
+        error!("Failed to save audit log: {}", e);
+    }
+    if let Err(e) = wallet.save_spending_ledger().await {
+        error!("Failed to save spending ledger: {}", e);
+    }
+    if let Err(e) = wallet.save_payment_templates().await {
+        error!("Failed to save payment templates: {}", e);
+    }
+    if let Err(e) = wallet.save_payment_requests().await {
+        error!("Failed to save payment requests: {}", e);
+    }
+    if let Err(e) = wallet.save_address_labels().await {
+        error!("Failed to save address labels: {}", e);
+    }
+    if let Err(e) = wallet.save_display_settings().await {
+        error!("Failed to save display settings: {}", e);
+    }
+    if let Err(e) = wallet.save_fee_history().await {
+        error!("Failed to save fee history: {}", e);
+    }
+    if let Err(e) = wallet.save_webhooks().await {
+        error!("Failed to save webhooks: {}", e);
+    }
+    if let Err(e) = wallet.save_retention_policy().await {
+        error!("Failed to save retention policy: {}", e);
+    }
+}
+
+/// Rows written by a running export and its cancel flag, shared with the
+/// thread writing it
+#[derive(Clone, Default)]
+struct ExportRun {
+    rows: Arc<AtomicU64>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl ExportRun {
+    /// Progress callback for the export functions
+    fn report(&self, written: u64) -> bool {
+        self.rows.store(written, Ordering::Relaxed);
+        !self.cancel.load(Ordering::Relaxed)
+    }
+
+    fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Ask where to save a `format` export named `file_name`, then stream it
+/// there with `write` on a blocking thread, showing the rows written in
+/// `rows` until it ends. Returns what to tell the user, or `None` if no file
+/// was chosen.
+async fn save_export(
+    file_name: &str,
+    format: ExportFormat,
+    run: ExportRun,
+    mut rows: Signal<Option<u64>>,
+    write: impl FnOnce(&mut std::fs::File, &ExportRun) -> Result<ExportSummary, WalletError>
+        + Send
+        + 'static,
+) -> Option<String> {
+    let file = rfd::AsyncFileDialog::new()
+        .set_file_name(format!("{}.{}", file_name, format.extension()))
+        .add_filter(format.to_string(), &[format.extension()])
+        .save_file()
+        .await?;
+    let path = file.path().to_path_buf();
+
+    rows.set(Some(0));
+    let task = tokio::task::spawn_blocking({
+        let run = run.clone();
+        move || export_to_file(&path, |file| write(file, &run))
+    });
+    while !task.is_finished() {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        rows.set(Some(run.rows.load(Ordering::Relaxed)));
+    }
+    rows.set(None);
+
+    let message = match task.await {
+        Ok(Ok((path, summary))) if summary.complete => format!(
+            "Exported {} rows to {}",
+            format_count(summary.rows),
+            path.display()
+        ),
+        Ok(Ok((path, summary))) => format!(
+            "Export cancelled after {} rows. {} is marked incomplete.",
+            format_count(summary.rows),
+            path.display()
+        ),
+        Ok(Err(e)) => format!("❌ Export failed: {}", e),
+        Err(e) => format!("❌ Export failed: {}", e),
+    };
+    Some(message)
+}
+
 #[component]
 fn Home() -> Element {
-    let balance = Balance {
-        confirmed: 0,
-        unconfirmed: 0,
-        locked: 0,
+    let node = use_context::<NodeHandle>();
+    let wallet = use_context::<Arc<tokio::sync::RwLock<Wallet>>>();
+    let navigator = use_navigator();
+    let mut node_status = use_signal(|| node.status());
+    let mut node_stats = use_signal(|| None::<NodeStats>);
+    let mut wallet_created = use_signal(|| false);
+    let mut balance = use_signal(LoadState::<Balance>::default);
+    let mut transactions = use_signal(LoadState::<Vec<Transaction>>::default);
+    let mut due_payments = use_signal(Vec::<PaymentTemplate>::new);
+    let mut address_labels = use_signal(HashMap::<Address, String>::new);
+    let mut addresses = use_signal(Vec::<AddressBalance>::new);
+    let mut display = use_signal(DisplaySettings::default);
+    let export_rows = use_signal(|| None::<u64>);
+    let mut export_total = use_signal(|| None::<u64>);
+    let mut export_run = use_signal(|| None::<ExportRun>);
+    let mut export_message = use_signal(|| None::<String>);
+
+    // Wallet data for the page. A long write, such as a sync, can hold the
+    // wallet; past WALLET_READ_TIMEOUT the cards show an error to retry.
+    let load_wallet = {
+        let wallet = wallet.clone();
+        move || {
+            let wallet = wallet.clone();
+            async move {
+                let Ok(wallet) = tokio::time::timeout(WALLET_READ_TIMEOUT, wallet.read()).await
+                else {
+                    let message = "The wallet is busy with another operation".to_string();
+                    balance.set(LoadState::Error(message.clone()));
+                    transactions.set(LoadState::Error(message));
+                    return;
+                };
+                wallet_created.set(!wallet.keys().list_keys().is_empty());
+                balance.set(LoadState::Ready(wallet.balances().get_total_balance()));
+                transactions.set(LoadState::Ready(
+                    wallet.transactions().get_all_transactions(),
+                ));
+                address_labels.set(wallet.keys().address_labels().clone());
+                addresses.set(wallet.address_breakdown());
+                display.set(wallet.display_settings());
+                due_payments.set(
+                    wallet
+                        .payment_templates()
+                        .due(chrono::Utc::now())
+                        .into_iter()
+                        .cloned()
+                        .collect(),
+                );
+            }
+        }
+    };
+
+    // Live chips for the Hero
+    use_future({
+        let node = node.clone();
+        let load_wallet = load_wallet.clone();
+        move || {
+            let node = node.clone();
+            let load_wallet = load_wallet.clone();
+            async move {
+                loop {
+                    node_status.set(node.status());
+                    node_stats.set(
+                        node.with_timeout(Duration::from_secs(1), |manager| {
+                            manager.get_node_stats()
+                        })
+                        .await
+                        .flatten(),
+                    );
+                    load_wallet().await;
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+            }
+        }
+    });
+    let reload_wallet = move |_: ()| {
+        balance.set(LoadState::Loading);
+        transactions.set(LoadState::Loading);
+        spawn(load_wallet());
+    };
+
+    // The history streams to the file under the wallet's read lock, so a
+    // long one doesn't have to fit in memory
+    let export_history = {
+        let wallet = wallet.clone();
+        move |format: ExportFormat| {
+            let wallet = wallet.clone();
+            let run = ExportRun::default();
+            export_run.set(Some(run.clone()));
+            export_message.set(None);
+            spawn(async move {
+                export_total.set(Some(wallet.read().await.transactions().count() as u64));
+                let message = save_export("transactions", format, run, export_rows, {
+                    let wallet = wallet.clone();
+                    move |file, run| {
+                        wallet.blocking_read().transactions().export_to_writer(
+                            file,
+                            format,
+                            &ExportFilter::default(),
+                            |written| run.report(written),
+                        )
+                    }
+                })
+                .await;
+                export_run.set(None);
+                if message.is_some() {
+                    export_message.set(message);
+                }
+            });
+        }
+    };
+
+    let hero_action = move |action: HeroAction| match action {
+        HeroAction::CreateWallet => {
+            let wallet = wallet.clone();
+            spawn(async move {
+                let result = wallet.write().await.generate_key("default");
+                match result {
+                    Ok(_) => wallet_created.set(true),
+                    Err(e) => error!("Failed to create wallet key: {}", e),
+                }
+                save_wallet_records(&wallet).await;
+            });
+        }
+        HeroAction::StartNode => {
+            navigator.push(Route::Node {});
+        }
+        HeroAction::SendFunds => {
+            navigator.push(Route::SendPage {
+                template: String::new(),
+            });
+        }
     };
 
+    let stats = node_stats.read().clone();
+
     rsx! {
         div {
-            Hero {}
-            BalanceCard { balance, is_loading: false }
+            Hero {
+                node_status: Some(node_status.read().clone()),
+                block_height: stats.as_ref().map(|stats| stats.block_height),
+                peer_count: stats.as_ref().map(|stats| stats.connected_peers),
+                wallet_created: wallet_created(),
+                on_action: hero_action,
+            }
+            DuePaymentsCard {
+                due: due_payments(),
+                on_pay: move |template| {
+                    navigator.push(Route::SendPage { template });
+                },
+            }
+            BalanceCard {
+                state: balance(),
+                on_refresh: reload_wallet.clone(),
+                display: display(),
+            }
+            AddressBreakdown {
+                addresses: addresses(),
+                display: display(),
+                on_open_address: move |address: Address| {
+                    navigator.push(Route::NotesPage { address: address.to_string() });
+                },
+            }
+            TransactionList {
+                state: transactions(),
+                on_retry: reload_wallet,
+                address_labels: address_labels(),
+                display: display(),
+                on_open: move |id| {
+                    navigator.push(Route::TransactionPage { id });
+                },
+            }
+            ExportPanel {
+                subject: "transaction history",
+                on_export: export_history,
+                rows: export_rows(),
+                total: export_total(),
+                on_cancel: move |_| {
+                    if let Some(run) = export_run.read().as_ref() {
+                        run.cancel();
+                    }
+                },
+                message: export_message(),
+            }
 
             div { style: "margin-top: 40px;",
                 h2 { style: "color: #333; margin-bottom: 20px;", "Quick Actions" }
@@ -75,252 +1290,1653 @@ fn Home() -> Element {
 }
 
 #[component]
-fn Node() -> Element {
+fn SendPage(template: String) -> Element {
+    let wallet = use_context::<Arc<tokio::sync::RwLock<Wallet>>>();
+    let node = use_context::<NodeHandle>();
+    let mut submitting = use_signal(|| false);
+    let mut progress = use_signal(|| None::<SendProgress>);
+    let mut failure = use_signal(|| None::<SendFailure>);
+    let mut sent_tx_id = use_signal(|| None::<String>);
+    let mut spendable = use_signal(|| None::<SpendableSummary>);
+    let mut limits = use_signal(SpendingLimits::default);
+    let mut templates = use_signal(Vec::<PaymentTemplate>::new);
+    let mut payment_counts = use_signal(HashMap::<Address, usize>::new);
+    let mut display = use_signal(DisplaySettings::default);
+    let mut fee_estimator = use_signal(FeeEstimator::default);
+    let mut fee_priority = use_signal(|| None::<FeePriority>);
+    // A send over the per-transaction limit waits here for the password
+    let mut held_send = use_signal(|| None::<HeldSend>);
+    let mut reauth_error = use_signal(|| None::<String>);
+    // A page opened to pay a template starts a new draft instead of restoring one
+    let restore_draft = template.is_empty();
+    // Template the form was filled from; its fee priority and memo apply to the send
+    let mut active_template = use_signal(|| (!template.is_empty()).then_some(template));
+    let network = node
+        .try_with(|manager| manager.get_config().address_network())
+        .unwrap_or_default();
+
+    // Keep the breakdown current as notes confirm while the page is open
+    use_future({
+        let wallet = wallet.clone();
+        move || {
+            let wallet = wallet.clone();
+            async move {
+                loop {
+                    {
+                        let wallet = wallet.read().await;
+                        spendable.set(Some(wallet.spendable_summary()));
+                        limits.set(wallet.spending_limits());
+                        templates.set(wallet.payment_templates().templates().to_vec());
+                        payment_counts.set(wallet.transactions().payment_counts());
+                        display.set(wallet.display_settings());
+                        fee_estimator.set(wallet.fee_estimator().clone());
+                    }
+                    tokio::time::sleep(Duration::from_secs(2)).await;
+                }
+            }
+        }
+    });
+
+    // Autosaved form, restored when the page opens and written
+    // DRAFT_SAVE_DELAY after the last change
+    let mut form_draft = use_signal(|| None::<SendDraft>);
+    let mut restored_draft = use_signal(|| None::<SendDraft>);
+    let mut draft_banner = use_signal(|| None::<chrono::DateTime<chrono::Utc>>);
+    // Last draft saved or restored, so an unchanged form isn't written again
+    let mut last_draft = use_signal(|| None::<SendDraft>);
+    // Bumped by every change; a pending save only writes if it is still the latest
+    let mut draft_edits = use_signal(|| 0_u64);
+    // Until the saved draft is read, the empty form must not replace it
+    let mut draft_loaded = use_signal(|| false);
+    use_future({
+        let wallet = wallet.clone();
+        move || {
+            let wallet = wallet.clone();
+            async move {
+                let wallet = wallet.read().await;
+                match wallet.load_send_draft().await {
+                    Ok(Some(draft)) if restore_draft && !draft.is_empty() => {
+                        // Templates first, so the picked template's prefill
+                        // lands before the draft and doesn't replace it
+                        templates.set(wallet.payment_templates().templates().to_vec());
+                        active_template.set(draft.template.clone());
+                        draft_banner.set(Some(draft.saved_at));
+                        form_draft.set(Some(draft.clone()));
+                        last_draft.set(Some(draft.clone()));
+                        restored_draft.set(Some(draft));
+                    }
+                    Ok(_) => {}
+                    Err(e) => error!("Failed to load send draft: {}", e),
+                }
+                draft_loaded.set(true);
+            }
+        }
+    });
+    use_effect({
+        let wallet = wallet.clone();
+        move || {
+            let Some(mut draft) = form_draft() else {
+                return;
+            };
+            draft.template = active_template();
+            if !draft_loaded() {
+                return;
+            }
+            let unchanged = last_draft.peek().as_ref().is_some_and(|last| {
+                *last
+                    == SendDraft {
+                        saved_at: last.saved_at,
+                        ..draft.clone()
+                    }
+            });
+            if unchanged {
+                return;
+            }
+            last_draft.set(Some(draft.clone()));
+            let edit = *draft_edits.peek() + 1;
+            draft_edits.set(edit);
+            let wallet = wallet.clone();
+            spawn(async move {
+                tokio::time::sleep(DRAFT_SAVE_DELAY).await;
+                if *draft_edits.peek() != edit {
+                    return;
+                }
+                if let Err(e) = wallet.read().await.save_send_draft(&draft).await {
+                    error!("Failed to save send draft: {}", e);
+                }
+            });
+        }
+    });
+
+    let active = active_template.read().as_ref().and_then(|name| {
+        templates
+            .read()
+            .iter()
+            .find(|template| &template.name == name)
+            .cloned()
+    });
+    // Priority picked on the page, else the template's; the fee comes from
+    // recent blocks once enough are known, SEND_FEE scaled until then
+    let priority = fee_priority()
+        .or(active.as_ref().map(|template| template.fee_priority))
+        .unwrap_or_default();
+    let fee = fee_estimator.read().estimate(priority, SEND_FEE);
+    let fee_options: Vec<(FeePriority, u64)> = FeePriority::ALL
+        .into_iter()
+        .map(|priority| (priority, fee_estimator.read().estimate(priority, SEND_FEE)))
+        .collect();
+
+    // Values of the last send from the form, for a retry after it failed for
+    // want of peers; a send held until they connect waits in `peer_wait`
+    let mut last_send = use_signal(|| None::<SendFormValues>);
+    let mut peer_wait = use_signal(|| None::<Task>);
+
+    // Send the form values; `force` broadcasts even with too few peers
+    let send_handler = {
+        let wallet = wallet.clone();
+        let node = node.clone();
+        let paying_template = active.as_ref().map(|template| template.name.clone());
+        move |(values, force): (SendFormValues, bool)| {
+            let wallet = wallet.clone();
+            let node = node.clone();
+            let paying_template = paying_template.clone();
+            last_send.set(Some(values.clone()));
+            submitting.set(true);
+            progress.set(None);
+            failure.set(None);
+            sent_tx_id.set(None);
+
+            spawn(async move {
+                let mut wallet = wallet.write().await;
+                let result = match send_request(&wallet, values.clone(), fee) {
+                    Ok(request)
+                        if matches!(
+                            wallet.check_send_auth(&request),
+                            Err(WalletError::ReauthRequired)
+                        ) =>
+                    {
+                        held_send.set(Some(HeldSend::Broadcast(values, force)));
+                        submitting.set(false);
+                        return;
+                    }
+                    Ok(mut request) => {
+                        request.force_broadcast = force;
+                        wallet
+                            .send(request, &node, |event| progress.set(Some(event)))
+                            .await
+                            .map_err(|e| e.report())
+                    }
+                    Err(e) => Err(SendFailure {
+                        stage: SendStage::SelectFunds,
+                        message: e.to_string(),
+                        funds_released: false,
+                        broadcast_tx_id: None,
+                        retryable: false,
+                    }),
+                };
+
+                if let Err(e) = wallet.save_audit_log().await {
+                    error!("Failed to save audit log: {}", e);
+                }
+                if let Err(e) = wallet.save_spending_ledger().await {
+                    error!("Failed to save spending ledger: {}", e);
+                }
+
+                match result {
+                    Ok(tx) => {
+                        if let Some(name) = paying_template {
+                            if let Err(e) =
+                                wallet.record_template_payment(&name, &tx.id, chrono::Utc::now())
+                            {
+                                error!("Failed to record template payment: {}", e);
+                            }
+                            if let Err(e) = wallet.save_payment_templates().await {
+                                error!("Failed to save payment templates: {}", e);
+                            }
+                        }
+                        // Drop any save still pending along with the draft
+                        draft_edits += 1;
+                        draft_banner.set(None);
+                        if let Err(e) = wallet.clear_send_draft().await {
+                            error!("Failed to clear send draft: {}", e);
+                        }
+                        sent_tx_id.set(Some(tx.id));
+                    }
+                    Err(report) => {
+                        error!("Send failed: {:?}", report);
+                        failure.set(Some(report));
+                    }
+                }
+                submitting.set(false);
+            });
+        }
+    };
+
+    // Offline signing: the exported package waits here until its signatures
+    // are imported or it is discarded
+    let mut cold_path = use_signal(String::new);
+    let mut pending_package = use_signal(|| None::<UnsignedTransactionPackage>);
+    let mut cold_busy = use_signal(|| false);
+    let mut cold_message = use_signal(|| None::<String>);
+    let mut cold_error = use_signal(|| None::<String>);
+
+    let export_handler = {
+        let wallet = wallet.clone();
+        move |values: SendFormValues| {
+            let wallet = wallet.clone();
+            let path = PathBuf::from(cold_path.read().trim());
+            cold_message.set(None);
+            cold_error.set(None);
+            if path.as_os_str().is_empty() {
+                cold_error.set(Some("Enter the file to export the package to".to_string()));
+                return;
+            }
+            cold_busy.set(true);
+
+            spawn(async move {
+                let mut wallet = wallet.write().await;
+                let request = send_request(&wallet, values.clone(), fee);
+                if request.as_ref().is_ok_and(|request| {
+                    matches!(
+                        wallet.check_send_auth(request),
+                        Err(WalletError::ReauthRequired)
+                    )
+                }) {
+                    held_send.set(Some(HeldSend::Export(values)));
+                    cold_busy.set(false);
+                    return;
+                }
+                let result = request
+                    .and_then(|request| wallet.export_unsigned_send(&request))
+                    .map_err(|e| e.to_string())
+                    .and_then(|package| match std::fs::write(&path, package.to_bytes()) {
+                        Ok(()) => Ok(package),
+                        Err(e) => {
+                            wallet.release_unsigned(&package);
+                            Err(format!("Failed to write {}: {}", path.display(), e))
+                        }
+                    });
+                match result {
+                    Ok(package) => {
+                        pending_package.set(Some(package));
+                        cold_message.set(Some(format!(
+                            "Package written to {}. Its funds are held until it is imported or discarded.",
+                            path.display()
+                        )));
+                    }
+                    Err(e) => cold_error.set(Some(e)),
+                }
+                cold_busy.set(false);
+            });
+        }
+    };
+
+    // Check the password, then carry on with the held send
+    let reauth_handler = {
+        let wallet = wallet.clone();
+        let send_handler = send_handler.clone();
+        let export_handler = export_handler.clone();
+        move |password: String| {
+            let wallet = wallet.clone();
+            let mut send_handler = send_handler.clone();
+            let mut export_handler = export_handler.clone();
+            spawn(async move {
+                let result = wallet.write().await.reauthenticate(&password);
+                match result {
+                    Ok(()) => {
+                        reauth_error.set(None);
+                        match held_send.take() {
+                            Some(HeldSend::Broadcast(values, force)) => {
+                                send_handler((values, force))
+                            }
+                            Some(HeldSend::Export(values)) => export_handler(values),
+                            None => {}
+                        }
+                    }
+                    Err(e) => reauth_error.set(Some(e.to_string())),
+                }
+            });
+        }
+    };
+
+    let sign_handler = {
+        let wallet = wallet.clone();
+        move |_| {
+            let wallet = wallet.clone();
+            let path = PathBuf::from(cold_path.read().trim());
+            let sig_path = PathBuf::from(format!("{}.sig", path.display()));
+            cold_message.set(None);
+            cold_error.set(None);
+            cold_busy.set(true);
+
+            spawn(async move {
+                let wallet = wallet.read().await;
+                let result = std::fs::read(&path)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+                    .and_then(|bytes| {
+                        UnsignedTransactionPackage::from_bytes(&bytes).map_err(|e| e.to_string())
+                    })
+                    .and_then(|package| {
+                        let key = wallet
+                            .keys()
+                            .get_default_key()
+                            .ok_or_else(|| WalletError::NoDefaultKey.to_string())?;
+                        wallet
+                            .keys()
+                            .sign_package(&package, key.name())
+                            .map_err(|e| e.to_string())
+                    })
+                    .and_then(|signatures| {
+                        std::fs::write(&sig_path, signatures.to_bytes())
+                            .map_err(|e| format!("Failed to write {}: {}", sig_path.display(), e))
+                    });
+                match result {
+                    Ok(()) => cold_message.set(Some(format!(
+                        "Signatures written to {}",
+                        sig_path.display()
+                    ))),
+                    Err(e) => cold_error.set(Some(e)),
+                }
+                cold_busy.set(false);
+            });
+        }
+    };
+
+    let import_handler = {
+        let wallet = wallet.clone();
+        let node = node.clone();
+        move |_| {
+            let Some(package) = pending_package() else {
+                return;
+            };
+            let wallet = wallet.clone();
+            let node = node.clone();
+            let path = PathBuf::from(cold_path.read().trim());
+            cold_message.set(None);
+            cold_error.set(None);
+            cold_busy.set(true);
+            progress.set(None);
+            failure.set(None);
+            sent_tx_id.set(None);
+
+            spawn(async move {
+                let signatures = std::fs::read(&path)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+                    .and_then(|bytes| {
+                        PackageSignatures::from_bytes(&bytes).map_err(|e| e.to_string())
+                    });
+                match signatures {
+                    Ok(signatures) => {
+                        let result = wallet
+                            .write()
+                            .await
+                            .broadcast_signed_package(
+                                &package,
+                                &signatures,
+                                &node,
+                                false,
+                                |event| progress.set(Some(event)),
+                            )
+                            .await;
+                        save_wallet_records(&wallet).await;
+                        match result {
+                            Ok(tx) => {
+                                pending_package.set(None);
+                                draft_edits += 1;
+                                draft_banner.set(None);
+                                if let Err(e) = wallet.read().await.clear_send_draft().await {
+                                    error!("Failed to clear send draft: {}", e);
+                                }
+                                sent_tx_id.set(Some(tx.id));
+                            }
+                            Err(e) => {
+                                if e.funds_released {
+                                    pending_package.set(None);
+                                }
+                                // A retry sends the form again, not this package
+                                failure.set(Some(SendFailure {
+                                    retryable: false,
+                                    ..e.report()
+                                }));
+                            }
+                        }
+                    }
+                    Err(e) => cold_error.set(Some(e)),
+                }
+                cold_busy.set(false);
+            });
+        }
+    };
+
+    // Hold the last send until enough peers connect, then send it again
+    let retry_when_peers_handler = {
+        let node = node.clone();
+        let send_handler = send_handler.clone();
+        move |_| {
+            let Some(values) = last_send() else {
+                return;
+            };
+            let node = node.clone();
+            let mut send_handler = send_handler.clone();
+            failure.set(None);
+            let task = spawn(async move {
+                let waited = node.wait_for_broadcast_peers().await;
+                peer_wait.set(None);
+                match waited {
+                    Ok(()) => send_handler((values, false)),
+                    Err(e) => failure.set(Some(SendFailure {
+                        stage: SendStage::Broadcast,
+                        message: e.to_string(),
+                        funds_released: false,
+                        broadcast_tx_id: None,
+                        retryable: false,
+                    })),
+                }
+            });
+            peer_wait.set(Some(task));
+        }
+    };
+
+    let send_anyway_handler = {
+        let send_handler = send_handler.clone();
+        move |_| {
+            if let Some(task) = peer_wait.take() {
+                task.cancel();
+            }
+            if let Some(values) = last_send() {
+                let mut send_handler = send_handler.clone();
+                send_handler((values, true));
+            }
+        }
+    };
+
+    let save_template_handler = {
+        let wallet = wallet.clone();
+        move |(details, (to, amount, ..)): (TemplateDetails, SendFormValues)| {
+            let wallet = wallet.clone();
+            spawn(async move {
+                let result = {
+                    let mut wallet = wallet.write().await;
+                    Address::from_string(&to).and_then(|recipient| {
+                        let mut template = PaymentTemplate::new(&details.name, recipient, amount);
+                        template.memo = details.memo;
+                        template.fee_priority = details.fee_priority;
+                        template.reminder_days = details.reminder_days;
+                        wallet.payment_templates_mut()?.add(template)
+                    })
+                };
+                match result {
+                    Ok(()) => save_wallet_records(&wallet).await,
+                    Err(e) => error!("Failed to save template: {}", e),
+                }
+            });
+        }
+    };
+
+    let delete_template_handler = {
+        let wallet = wallet.clone();
+        move |name: String| {
+            let wallet = wallet.clone();
+            spawn(async move {
+                let result = wallet
+                    .write()
+                    .await
+                    .payment_templates_mut()
+                    .map(|templates| templates.remove(&name));
+                match result {
+                    Ok(_) => {
+                        active_template.set(None);
+                        templates.write().retain(|template| template.name != name);
+                        save_wallet_records(&wallet).await;
+                    }
+                    Err(e) => error!("Failed to delete template: {}", e),
+                }
+            });
+        }
+    };
+
+    let discard_draft = {
+        let wallet = wallet.clone();
+        move |_| {
+            let wallet = wallet.clone();
+            draft_edits += 1;
+            draft_banner.set(None);
+            active_template.set(None);
+            // A fresh timestamp, so discarding twice still clears the form
+            let empty = SendDraft {
+                saved_at: chrono::Utc::now(),
+                ..SendDraft::default()
+            };
+            last_draft.set(Some(empty.clone()));
+            restored_draft.set(Some(empty));
+            spawn(async move {
+                if let Err(e) = wallet.read().await.clear_send_draft().await {
+                    error!("Failed to clear send draft: {}", e);
+                }
+            });
+        }
+    };
+
+    let cancel_handler = move |_| {
+        let wallet = wallet.clone();
+        let Some(package) = pending_package() else {
+            return;
+        };
+        spawn(async move {
+            wallet.write().await.release_unsigned(&package);
+            pending_package.set(None);
+            cold_message.set(Some(
+                "Package discarded; its funds are spendable again".to_string(),
+            ));
+        });
+    };
+
+    let summary = spendable();
+
+    rsx! {
+        if let Some(saved_at) = draft_banner() {
+            DraftBanner {
+                saved_at,
+                on_dismiss: move |_| draft_banner.set(None),
+                on_discard: discard_draft,
+            }
+        }
+        if let Some(summary) = summary.clone() {
+            SpendablePanel { summary }
+        }
+        TemplatePicker {
+            templates: templates(),
+            selected: active_template(),
+            on_select: move |name| {
+                active_template.set(name);
+                fee_priority.set(None);
+            },
+            on_delete: delete_template_handler,
+        }
+        FeeSelector {
+            priority,
+            fees: fee_options,
+            from_history: fee_estimator.read().has_history(),
+            history: fee_estimator
+                .read()
+                .recent(FEE_CHART_BLOCKS)
+                .into_iter()
+                .cloned()
+                .collect::<Vec<_>>(),
+            on_change: move |priority| fee_priority.set(Some(priority)),
+            disabled: submitting(),
+            display: display(),
+        }
+        SendForm {
+            on_send: {
+                let mut send_handler = send_handler.clone();
+                move |values| send_handler((values, false))
+            },
+            network,
+            available: summary.as_ref().map(|summary| summary.spendable),
+            min_confirmations: summary
+                .as_ref()
+                .map_or(DEFAULT_MIN_CONFIRMATIONS, |summary| summary.min_confirmations),
+            submitting: submitting(),
+            progress: progress(),
+            failure: failure(),
+            confirm_above: limits().per_transaction,
+            payment_counts: payment_counts(),
+            on_export_unsigned: export_handler,
+            prefill: active.as_ref().map(SendPrefill::from),
+            draft: restored_draft(),
+            on_draft_change: move |draft| form_draft.set(Some(draft)),
+            on_save_template: save_template_handler,
+            display: display(),
+            on_retry_when_peers: retry_when_peers_handler,
+            on_send_anyway: send_anyway_handler,
+            waiting_for_peers: peer_wait.read().is_some(),
+            on_stop_waiting: move |_| {
+                if let Some(task) = peer_wait.take() {
+                    task.cancel();
+                }
+            },
+        }
+        if held_send.read().is_some() {
+            ReauthDialog {
+                title: "Confirm it's you",
+                message: "Sends over the per-transaction limit need the wallet password.".to_string(),
+                error: reauth_error(),
+                on_submit: reauth_handler,
+                on_cancel: move |_| {
+                    held_send.set(None);
+                    reauth_error.set(None);
+                },
+            }
+        }
+        if let Some(tx_id) = sent_tx_id() {
+            p { style: "color: #28a745;",
+                "Sent transaction "
+                Link { to: Route::TransactionPage { id: tx_id.clone() }, "{tx_id}" }
+            }
+        }
+        ColdSigningPanel {
+            path: cold_path(),
+            on_path_change: move |path| cold_path.set(path),
+            on_sign: sign_handler,
+            pending: pending_package.read().is_some(),
+            on_import: import_handler,
+            on_cancel: cancel_handler,
+            busy: cold_busy(),
+            message: cold_message(),
+            error: cold_error(),
+        }
+    }
+}
+
+/// A send held back until the password is entered again
+#[derive(Clone)]
+enum HeldSend {
+    /// Broadcast, forced past the node's peer minimum if set
+    Broadcast(SendFormValues, bool),
+    /// Exported for offline signing instead
+    Export(SendFormValues),
+}
+
+/// Build a send from the form values, paid from the default key
+fn send_request(
+    wallet: &Wallet,
+    (to, amount, min_confirmations, confirmed_amount, valid_for_blocks): SendFormValues,
+    fee: u64,
+) -> Result<SendRequest, WalletError> {
+    let to = Address::from_string(&to)?;
+    let key_name = wallet
+        .keys()
+        .get_default_key()
+        .ok_or(WalletError::NoDefaultKey)?
+        .name()
+        .to_string();
+    Ok(SendRequest {
+        key_name,
+        to,
+        amount,
+        fee,
+        min_confirmations,
+        valid_for_blocks,
+        confirmed_amount,
+        force_broadcast: false,
+    })
+}
+
+#[component]
+fn TransactionPage(id: String) -> Element {
+    let wallet = use_context::<Arc<tokio::sync::RwLock<Wallet>>>();
+    let node = use_context::<NodeHandle>();
+    let mut detail = use_signal(|| None::<api::TransactionDetail>);
+    let mut loaded = use_signal(|| false);
+    let mut receipt = use_signal(|| None::<api::Receipt>);
+    let mut receipt_error = use_signal(|| None::<String>);
+    let mut rebroadcasting = use_signal(|| false);
+    let mut accelerating = use_signal(|| false);
+    let mut toast_message = use_signal(|| None::<String>);
+    let navigator = use_navigator();
+
+    // Refresh while open so status, depth and mempool membership stay current
+    use_future({
+        let wallet = wallet.clone();
+        let id = id.clone();
+        let node = node.clone();
+        move || {
+            let wallet = wallet.clone();
+            let node = node.clone();
+            let id = id.clone();
+            async move {
+                loop {
+                    let found = {
+                        let wallet = wallet.read().await;
+                        node.with_timeout(Duration::from_secs(1), |manager| {
+                            manager.with_mempool(|mempool| {
+                                wallet.transaction_detail(&id, Some(mempool), None)
+                            })
+                        })
+                        .await
+                        .flatten()
+                        .unwrap_or_else(|| wallet.transaction_detail(&id, None, None))
+                    };
+                    detail.set(found);
+                    loaded.set(true);
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+            }
+        }
+    });
+
+    let label_handler = {
+        let wallet = wallet.clone();
+        let id = id.clone();
+        move |label: String| {
+            let wallet = wallet.clone();
+            let id = id.clone();
+            spawn(async move {
+                let mut wallet = wallet.write().await;
+                wallet.transactions_mut().set_label(&id, &label);
+                detail.set(wallet.transaction_detail(&id, None, None));
+            });
+        }
+    };
+
+    // A child paying the high-priority rate for itself and the stuck payment
+    let accelerate_handler = {
+        let wallet = wallet.clone();
+        let node = node.clone();
+        let id = id.clone();
+        move |_| {
+            let wallet = wallet.clone();
+            let node = node.clone();
+            let id = id.clone();
+            accelerating.set(true);
+            spawn(async move {
+                let result = {
+                    let mut wallet = wallet.write().await;
+                    let result = wallet
+                        .accelerate_incoming(&id, FeePriority::High, SEND_FEE, &node)
+                        .await;
+                    detail.set(wallet.transaction_detail(&id, None, None));
+                    result
+                };
+                save_wallet_records(&wallet).await;
+                let message = match result {
+                    Ok(child) => format!(
+                        "Broadcast {} with a fee of {} nano to speed this payment up",
+                        child.id, child.fee
+                    ),
+                    Err(e) => e.report().message,
+                };
+                toast_message.set(Some(message));
+                accelerating.set(false);
+            });
+        }
+    };
+
+    let rebroadcast_handler = {
+        let wallet = wallet.clone();
+        let id = id.clone();
+        move |_| {
+            let wallet = wallet.clone();
+            let node = node.clone();
+            let id = id.clone();
+            rebroadcasting.set(true);
+            spawn(async move {
+                let result = node.rebroadcast(&id).await;
+                let failed = {
+                    let mut wallet = wallet.write().await;
+                    let failed = wallet.apply_rebroadcast(&id, &result);
+                    detail.set(wallet.transaction_detail(&id, None, None));
+                    failed
+                };
+                if matches!(failed, Ok(true)) {
+                    save_wallet_records(&wallet).await;
+                }
+                let message = match (result, failed) {
+                    (Ok(outcome), _) => outcome.to_string(),
+                    (Err(e), Ok(true)) => format!("{e}. Marked as failed."),
+                    (Err(e), _) => e.to_string(),
+                };
+                toast_message.set(Some(message));
+                rebroadcasting.set(false);
+            });
+        }
+    };
+
+    let receipt_handler = move |_| {
+        let wallet = wallet.clone();
+        let id = id.clone();
+        spawn(async move {
+            match wallet.read().await.generate_receipt(&id, None) {
+                Ok(generated) => {
+                    receipt.set(Some(generated));
+                    receipt_error.set(None);
+                }
+                Err(e) => {
+                    receipt.set(None);
+                    receipt_error.set(Some(e.to_string()));
+                }
+            }
+        });
+    };
+
+    rsx! {
+        div {
+            Link { to: Route::Home {}, "← Back" }
+            match detail() {
+                Some(detail) => rsx! {
+                    TransactionDetail {
+                        detail,
+                        on_label_change: label_handler,
+                        on_export_receipt: receipt_handler,
+                        receipt: receipt(),
+                        receipt_error: receipt_error(),
+                        on_rebroadcast: rebroadcast_handler,
+                        rebroadcasting: rebroadcasting(),
+                        on_accelerate: accelerate_handler,
+                        accelerating: accelerating(),
+                        on_open_transaction: move |id| {
+                            navigator.push(Route::TransactionPage { id });
+                        },
+                    }
+                },
+                None if loaded() => rsx! {
+                    p { style: "color: #666;", "This wallet has no record of that transaction." }
+                },
+                None => rsx! {
+                    p { style: "color: #666;", "Loading transaction..." }
+                },
+            }
+            if let Some(message) = toast_message() {
+                div {
+                    style: "position: fixed; bottom: 24px; right: 24px; max-width: 420px; background: #1a1a1a; color: white; padding: 12px 16px; border-radius: 8px; box-shadow: 0 4px 20px rgba(0, 0, 0, 0.25); display: flex; gap: 12px; align-items: flex-start; z-index: 1000; word-break: break-all;",
+                    role: "status",
+                    aria_live: "polite",
+                    span { "{message}" }
+                    button {
+                        style: "background: none; border: none; color: #9ca3af; cursor: pointer;",
+                        aria_label: t!("nav.dismiss"),
+                        onclick: move |_| toast_message.set(None),
+                        "✕"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The wallet's notes, or those of `address` when given, each expandable to
+/// the transaction and block that created it
+#[component]
+fn NotesPage(address: String) -> Element {
+    let wallet = use_context::<Arc<tokio::sync::RwLock<Wallet>>>();
+    let chain = use_context::<Arc<tokio::sync::RwLock<Blockchain>>>();
+    let node = use_context::<NodeHandle>();
+    let navigator = use_navigator();
+    let mut notes = use_signal(Vec::<Note>::new);
+    let mut display = use_signal(DisplaySettings::default);
+    let mut expanded = use_signal(|| None::<Uuid>);
+    let mut provenance = use_signal(|| None::<NoteProvenance>);
+    let mut provenance_error = use_signal(|| None::<String>);
+
+    // Refresh while open so states and the expanded note's confirmations stay current
+    use_future({
+        let wallet = wallet.clone();
+        let chain = chain.clone();
+        let node = node.clone();
+        let address = address.clone();
+        move || {
+            let wallet = wallet.clone();
+            let chain = chain.clone();
+            let node = node.clone();
+            let filter = Address::from_string(&address).ok();
+            async move {
+                loop {
+                    {
+                        let wallet = wallet.read().await;
+                        notes.set(
+                            wallet
+                                .balances()
+                                .notes()
+                                .into_iter()
+                                .filter(|note| filter.as_ref().is_none_or(|a| &note.address == a))
+                                .cloned()
+                                .collect(),
+                        );
+                        display.set(wallet.display_settings());
+                        if let Some(id) = expanded() {
+                            let chain = chain.read().await;
+                            let found = node
+                                .with_timeout(Duration::from_secs(1), |manager| {
+                                    manager.with_mempool(|mempool| {
+                                        wallet.note_provenance(&id, Some(mempool), Some(&chain))
+                                    })
+                                })
+                                .await
+                                .flatten()
+                                .unwrap_or_else(|| wallet.note_provenance(&id, None, Some(&chain)));
+                            match found {
+                                Ok(found) => {
+                                    provenance.set(Some(found));
+                                    provenance_error.set(None);
+                                }
+                                Err(e) => provenance_error.set(Some(e.to_string())),
+                            }
+                        }
+                    }
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+            }
+        }
+    });
+
+    let toggle_handler = move |id: Uuid| {
+        if expanded() == Some(id) {
+            expanded.set(None);
+            return;
+        }
+        expanded.set(Some(id));
+        provenance_error.set(None);
+        let wallet = wallet.clone();
+        let chain = chain.clone();
+        spawn(async move {
+            let wallet = wallet.read().await;
+            let chain = chain.read().await;
+            match wallet.note_provenance(&id, None, Some(&chain)) {
+                Ok(found) => provenance.set(Some(found)),
+                Err(e) => provenance_error.set(Some(e.to_string())),
+            }
+        });
+    };
+
+    rsx! {
+        div {
+            h2 { style: "color: #333; margin-bottom: 24px;", "Notes" }
+            if !address.is_empty() {
+                div {
+                    style: "margin-bottom: 16px; font-size: 14px; color: #6c757d; word-break: break-all;",
+                    "At {address} · "
+                    Link { to: Route::NotesPage { address: String::new() }, "Show all" }
+                }
+            }
+            NoteList {
+                notes: notes(),
+                expanded: expanded(),
+                provenance: provenance(),
+                provenance_error: provenance_error(),
+                on_toggle: toggle_handler,
+                on_open_transaction: move |id| {
+                    navigator.push(Route::TransactionPage { id });
+                },
+                on_open_chain_transaction: move |id| {
+                    navigator.push(Route::ExplorerTransaction { id });
+                },
+                on_open_block: move |id| {
+                    navigator.push(Route::ExplorerBlock { id });
+                },
+                display: display(),
+            }
+        }
+    }
+}
+
+const KEY_IMPORT_HINT: &str = r#"A JSON array of { "name", "secret_hex" } entries, or a jammed nockchain-wallet key export. Entries that fail or are already in the wallet are reported and skipped."#;
+
+#[component]
+fn Keys() -> Element {
+    let wallet = use_context::<Arc<tokio::sync::RwLock<Wallet>>>();
+    let mut keys = use_signal(Vec::<(String, Address)>::new);
+    let mut import_path = use_signal(String::new);
+    // None picks the format from the file extension
+    let mut import_format = use_signal(|| None::<KeyImportFormat>);
+    let mut importing = use_signal(|| false);
+    let mut report = use_signal(|| None::<KeyImportReport>);
+    let mut import_error = use_signal(|| None::<String>);
+    let mut receive_address = use_signal(|| None::<(Address, Option<String>)>);
+    // Payment request made for the address shown, refreshed to follow its status
+    let mut payment_request = use_signal(|| None::<PaymentRequest>);
+    let mut receive_error = use_signal(|| None::<String>);
+    let mut restore_error = use_signal(|| None::<String>);
+    let mut restoring = use_signal(|| false);
+    let mut secret_import_error = use_signal(|| None::<String>);
+    let mut print_error = use_signal(|| None::<String>);
+
+    let new_address_handler = {
+        let wallet = wallet.clone();
+        move |label: String| {
+            let wallet = wallet.clone();
+            receive_error.set(None);
+            spawn(async move {
+                let result = {
+                    let mut wallet = wallet.write().await;
+                    let key_name = wallet
+                        .keys()
+                        .get_default_key()
+                        .map(|key| key.name().to_string());
+                    match key_name {
+                        Some(key_name) => wallet.new_receive_address(&key_name, &label).await,
+                        None => Err(WalletError::NoDefaultKey),
+                    }
+                };
+                match result {
+                    Ok(address) => {
+                        let label = (!label.is_empty()).then_some(label);
+                        receive_address.set(Some((address, label)));
+                        payment_request.set(None);
+                    }
+                    Err(e) => receive_error.set(Some(e.to_string())),
+                }
+                save_wallet_records(&wallet).await;
+            });
+        }
+    };
+
+    let new_request_handler = {
+        let wallet = wallet.clone();
+        move |values: PaymentRequestValues| {
+            let wallet = wallet.clone();
+            receive_error.set(None);
+            spawn(async move {
+                let expires_in = values
+                    .expires_in_minutes
+                    .map(|minutes| chrono::TimeDelta::minutes(minutes.into()));
+                let result = {
+                    let mut wallet = wallet.write().await;
+                    wallet
+                        .create_payment_request(values.amount, &values.label, expires_in)
+                        .await
+                        .map(|handle| wallet.payment_requests().get(&handle.id).cloned())
+                };
+                match result {
+                    Ok(request) => {
+                        if let Some(request) = request.as_ref() {
+                            let label = (!request.label.is_empty()).then(|| request.label.clone());
+                            receive_address.set(Some((request.address.clone(), label)));
+                        }
+                        payment_request.set(request);
+                    }
+                    Err(e) => receive_error.set(Some(e.to_string())),
+                }
+                save_wallet_records(&wallet).await;
+            });
+        }
+    };
+
+    // The status shown follows the request as payments arrive or it expires
+    use_future({
+        let wallet = wallet.clone();
+        move || {
+            let wallet = wallet.clone();
+            async move {
+                loop {
+                    tokio::time::sleep(Duration::from_secs(2)).await;
+                    let Some(id) = payment_request
+                        .peek()
+                        .as_ref()
+                        .map(|request| request.id.clone())
+                    else {
+                        continue;
+                    };
+                    let latest = wallet.read().await.payment_requests().get(&id).cloned();
+                    if latest != *payment_request.peek() {
+                        payment_request.set(latest);
+                    }
+                }
+            }
+        }
+    });
+
+    let save_image_handler = move |_| {
+        let Some((address, _)) = receive_address() else {
+            return;
+        };
+        receive_error.set(None);
+        spawn(async move {
+            let data = address.to_nockchain_string();
+            let Some(file) = rfd::AsyncFileDialog::new()
+                .set_file_name(format!("{}.png", data))
+                .add_filter("PNG image", &["png"])
+                .save_file()
+                .await
+            else {
+                return;
+            };
+            let result = qr::render_png(&data, QR_IMAGE_SIZE).and_then(|png| {
+                std::fs::write(file.path(), png)
+                    .map_err(|e| WalletError::Storage(format!("Failed to save image: {}", e)))
+            });
+            if let Err(e) = result {
+                receive_error.set(Some(e.to_string()));
+            }
+        });
+    };
+
+    // Only the address, its label and creation date go on the sheet
+    let print_sheet = {
+        let wallet = wallet.clone();
+        move |name: String| {
+            let wallet = wallet.clone();
+            print_error.set(None);
+            spawn(async move {
+                match wallet.read().await.address_backup_sheet(&name) {
+                    Ok(sheet) => print::print_html(&sheet),
+                    Err(e) => print_error.set(Some(e.to_string())),
+                }
+            });
+        }
+    };
+
+    let load_keys = {
+        let wallet = wallet.clone();
+        move || {
+            let wallet = wallet.clone();
+            async move {
+                let mut list: Vec<(String, Address)> = wallet
+                    .read()
+                    .await
+                    .keys()
+                    .get_all_addresses()
+                    .into_iter()
+                    .collect();
+                list.sort_by(|a, b| a.0.cmp(&b.0));
+                keys.set(list);
+            }
+        }
+    };
+    use_future(load_keys.clone());
+
+    // Restore the seed, then derive the first account's key from it as
+    // creating a wallet does
+    let restore_handler = {
+        let wallet = wallet.clone();
+        let load_keys = load_keys.clone();
+        move |(phrase, passphrase, language): (String, String, Option<MnemonicLanguage>)| {
+            let wallet = wallet.clone();
+            let load_keys = load_keys.clone();
+            restore_error.set(None);
+            restoring.set(true);
+            spawn(async move {
+                let result = {
+                    let mut wallet = wallet.write().await;
+                    wallet
+                        .restore_from_mnemonic(&phrase, &passphrase, language)
+                        .and_then(|_| wallet.generate_key("default"))
+                };
+                save_wallet_records(&wallet).await;
+                if let Err(e) = result {
+                    restore_error.set(Some(e.to_string()));
+                }
+                load_keys().await;
+                restoring.set(false);
+            });
+        }
+    };
+
+    let secret_import_handler = {
+        let wallet = wallet.clone();
+        let load_keys = load_keys.clone();
+        move |(name, secret): (String, String)| {
+            let wallet = wallet.clone();
+            let load_keys = load_keys.clone();
+            secret_import_error.set(None);
+            importing.set(true);
+            spawn(async move {
+                let result = wallet.write().await.import_key_flexible(&name, &secret);
+                save_wallet_records(&wallet).await;
+                if let Err(e) = result {
+                    secret_import_error.set(Some(e.to_string()));
+                }
+                load_keys().await;
+                importing.set(false);
+            });
+        }
+    };
+
+    let import_handler = move |_| {
+        let wallet = wallet.clone();
+        let load_keys = load_keys.clone();
+        let path = PathBuf::from(import_path.read().trim());
+        let format = import_format()
+            .or_else(|| KeyImportFormat::from_path(&path))
+            .unwrap_or_default();
+        importing.set(true);
+        import_error.set(None);
+
+        spawn(async move {
+            let result = wallet.write().await.import_keys_from_file(&path, format);
+            save_wallet_records(&wallet).await;
+            match result {
+                Ok(imported) => report.set(Some(imported)),
+                Err(e) => {
+                    report.set(None);
+                    import_error.set(Some(e.to_string()));
+                }
+            }
+            load_keys().await;
+            importing.set(false);
+        });
+    };
+
+    rsx! {
+        div {
+            h2 { style: "color: #333; margin-bottom: 24px;", "🔑 Keys" }
+
+            table {
+                style: "width: 100%; border-collapse: collapse; margin-bottom: 32px;",
+                thead {
+                    tr {
+                        th { style: "text-align: left; padding: 6px 8px; color: #666;", "Name" }
+                        th { style: "text-align: left; padding: 6px 8px; color: #666;", "Address" }
+                        th {}
+                    }
+                }
+                tbody {
+                    for (name, address) in keys() {
+                        tr {
+                            key: "{name}",
+                            td { style: "padding: 6px 8px;", "{name}" }
+                            td { style: "padding: 6px 8px; font-family: monospace;", "{address.to_nockchain_string()}" }
+                            td {
+                                style: "padding: 6px 8px;",
+                                button {
+                                    onclick: {
+                                        let mut print_sheet = print_sheet.clone();
+                                        let name = name.clone();
+                                        move |_| print_sheet(name.clone())
+                                    },
+                                    "Print backup sheet"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            if let Some(message) = print_error() {
+                p { style: "color: #dc3545;", "❌ {message}" }
+            }
+
+            ReceiveView {
+                address: receive_address().map(|(address, _)| address.to_nockchain_string()).unwrap_or_default(),
+                label: receive_address().and_then(|(_, label)| label),
+                on_new_address: new_address_handler,
+                on_save_image: save_image_handler,
+                on_new_request: new_request_handler,
+                request: payment_request(),
+            }
+            if let Some(message) = receive_error() {
+                p { style: "color: #dc3545;", "❌ {message}" }
+            }
+
+            if keys.read().is_empty() {
+                MnemonicRestoreForm {
+                    on_restore: restore_handler,
+                    error: restore_error(),
+                    disabled: restoring(),
+                }
+            }
+
+            SecretKeyImportForm {
+                on_import: secret_import_handler,
+                error: secret_import_error(),
+                disabled: importing(),
+            }
+
+            h3 { style: "color: #333;", "Import from file" }
+            p { style: "color: #666;",
+                "{KEY_IMPORT_HINT}"
+            }
+            div { style: "display: flex; gap: 8px; align-items: center;",
+                input {
+                    style: "flex: 1; padding: 8px; border: 1px solid #ccc; border-radius: 4px;",
+                    placeholder: "/path/to/keys.json",
+                    value: "{import_path}",
+                    disabled: importing(),
+                    oninput: move |evt| import_path.set(evt.value()),
+                }
+                select {
+                    style: "padding: 8px; border: 1px solid #ccc; border-radius: 4px;",
+                    disabled: importing(),
+                    onchange: move |evt| {
+                        let format = KeyImportFormat::ALL
+                            .into_iter()
+                            .find(|format| format.label() == evt.value());
+                        import_format.set(format);
+                    },
+                    option { value: "auto", "Detect from extension" }
+                    for format in KeyImportFormat::ALL {
+                        option { value: "{format.label()}", "{format.label()}" }
+                    }
+                }
+                button {
+                    style: "padding: 8px 16px; background: #007bff; color: white; border: none; border-radius: 4px; cursor: pointer;",
+                    disabled: importing() || import_path.read().trim().is_empty(),
+                    onclick: import_handler,
+                    if importing() { "Importing..." } else { "Import" }
+                }
+            }
+
+            if let Some(message) = import_error() {
+                p { style: "color: #dc3545;", "❌ {message}" }
+            }
+            if let Some(report) = report() {
+                KeyImportResults { report }
+            }
+        }
+    }
+}
+
+#[component]
+fn Node() -> Element {
     // Add initialization guard to prevent infinite re-initialization
     static COMPONENT_INIT_COUNT: std::sync::atomic::AtomicUsize =
         std::sync::atomic::AtomicUsize::new(0);
     let init_count = COMPONENT_INIT_COUNT.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
-    println!(
-        "[UI-DEBUG] Node component initializing... (count: {})",
-        init_count
-    );
+    debug!("Node component initializing... (count: {})", init_count);
+
+    if init_count > 5 {
+        error!("Too many component re-initializations! Preventing infinite loop.");
+        return rsx! {
+            div {
+                style: "padding: 20px; color: red; border: 2px solid red;",
+                h2 { "⚠️ Component Re-initialization Loop Detected" }
+                p { "The Node component has been re-initialized too many times. This indicates a potential infinite loop." }
+                p { "Please refresh the page to reset the component state." }
+            }
+        };
+    }
+
+    // The node manager is owned by the layout so health checks and RPC see the same node
+    let node_handle = use_context::<NodeHandle>();
+    let wallet = use_context::<Arc<tokio::sync::RwLock<Wallet>>>();
+    let node_runner = use_signal({
+        let node_handle = node_handle.clone();
+        move || node_handle.shared()
+    });
+
+    // Status comes only from the manager's state machine
+    let mut node_status = use_signal({
+        let node_handle = node_handle.clone();
+        move || node_handle.status()
+    });
+    use_future({
+        let node_handle = node_handle.clone();
+        move || {
+            let mut status_rx = node_handle.subscribe_status();
+            async move {
+                while status_rx.changed().await.is_ok() {
+                    let status = status_rx.borrow_and_update().clone();
+                    node_status.set(status);
+                }
+            }
+        }
+    });
+    // Kept by the layout, so lines and scroll position survive leaving the page
+    let console = use_context::<ConsoleState>();
+    let logs = console.logs;
+    let mut log_level = use_signal(|| LogLevel::Info);
+    let mut auto_scroll = console.follow_tail;
+    let mut node_stats = use_signal(|| None::<NodeStats>);
+    let mut peers = use_signal(Vec::<PeerInfo>::new);
+    let mut data_dir_usage = use_signal(|| None::<DataDirUsage>);
+    let mut logs_dropped = use_signal(|| 0u64);
+    let mut level_counts = use_signal(LogLevelCounts::default);
+    let mut network_conditions = use_signal(NetworkConditions::default);
+    let mut toast_message = use_signal(|| None::<String>);
+    let mut mining_rewards = use_signal(Vec::<MiningReward>::new);
+    let mut chain_tip = use_signal(|| None::<u64>);
+    let mut availability = use_signal(|| None::<AvailabilityReport>);
+    let chain = use_context::<Arc<tokio::sync::RwLock<Blockchain>>>();
+    let mut validation_phase = use_signal(|| None::<ValidationPhase>);
+    let mut filter_sync = use_signal(|| None::<FilterSyncStats>);
+    let log_export_rows = use_signal(|| None::<u64>);
+    let mut log_export_total = use_signal(|| None::<u64>);
+    let mut log_export_run = use_signal(|| None::<ExportRun>);
+    let mut storage_breakdown = use_signal(|| None::<StorageBreakdown>);
+    let mut compaction_report = use_signal(|| None::<CompactionReport>);
+    let mut compacting = use_signal(|| false);
+
+    // Walking the data dir is slow, so the breakdown refreshes twice a minute
+    use_future({
+        let wallet = wallet.clone();
+        let chain = chain.clone();
+        move || {
+            let wallet = wallet.clone();
+            let chain = chain.clone();
+            async move {
+                loop {
+                    let data_dir = node_runner
+                        .read()
+                        .try_lock()
+                        .ok()
+                        .map(|runner| runner.get_config().data_dir.clone());
+                    if let Some(data_dir) = data_dir {
+                        let wallet_dir = wallet.read().await.data_dir().map(Path::to_path_buf);
+                        let chain = chain.read().await;
+                        storage_breakdown.set(Some(StorageBreakdown::measure(
+                            &data_dir,
+                            wallet_dir.as_deref(),
+                            &chain,
+                        )));
+                    }
+                    tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
+                }
+            }
+        }
+    });
+
+    // Keeps the newest blocks and every block with a wallet transaction whole
+    let compact_wallet = wallet.clone();
+    let compact_chain = chain.clone();
+    let compact_handler = move |_| {
+        let wallet = compact_wallet.clone();
+        let chain = compact_chain.clone();
+        compacting.set(true);
+        spawn(async move {
+            let mut chain = chain.write().await;
+            let keep_after = chain
+                .height()
+                .map_or(0, |tip| (tip + 1).saturating_sub(DEFAULT_KEEP_FULL_BLOCKS));
+            let report = wallet.read().await.compact_chain(&mut chain, keep_after);
+            toast_message.set(Some(format!(
+                "🗜️ Pruned {} block bodies, freed {}",
+                report.pruned_blocks,
+                format_bytes(report.freed_bytes)
+            )));
+            if let Some(breakdown) = storage_breakdown.write().as_mut() {
+                breakdown.blocks = breakdown.blocks.saturating_sub(report.freed_bytes);
+            }
+            compaction_report.set(Some(report));
+            compacting.set(false);
+        });
+    };
+
+    // Mining payouts live in the wallet, not the node manager; the
+    // validation phase comes from the local chain
+    use_future({
+        let wallet = wallet.clone();
+        move || {
+            let wallet = wallet.clone();
+            let chain = chain.clone();
+            async move {
+                loop {
+                    if let Ok(wallet) = wallet.try_read() {
+                        mining_rewards
+                            .set(wallet.mining_rewards(..).into_iter().cloned().collect());
+                        chain_tip.set(wallet.balances().tip_height());
+                        filter_sync.set(wallet.last_filter_sync().cloned());
+                    }
+                    if let Ok(chain) = chain.try_read() {
+                        validation_phase.set(Some(chain.validation_phase()));
+                    }
+                    tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+                }
+            }
+        }
+    });
 
-    if init_count > 5 {
-        println!("[UI-ERROR] Too many component re-initializations! Preventing infinite loop.");
-        return rsx! {
-            div {
-                style: "padding: 20px; color: red; border: 2px solid red;",
-                h2 { "⚠️ Component Re-initialization Loop Detected" }
-                p { "The Node component has been re-initialized too many times. This indicates a potential infinite loop." }
-                p { "Please refresh the page to reset the component state." }
+    // Refresh stats and peer counters once per second; skip ticks while a
+    // start/stop holds the manager lock rather than blocking the UI.
+    use_future(move || async move {
+        loop {
+            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+            if let Ok(runner) = node_runner.read().try_lock() {
+                node_stats.set(runner.get_node_stats());
+                peers.set(runner.list_peers());
+                data_dir_usage.set(runner.data_dir_usage());
+                logs_dropped.set(runner.logs_dropped_count());
+                level_counts.set(runner.log_level_counts());
+                network_conditions.set(runner.network_conditions().clone());
             }
-        };
-    }
+        }
+    });
 
-    // Create a shared node runner instance with proper Arc<Mutex<>> handling - ONLY ONCE
-    let node_runner = use_signal(|| {
-        println!("[UI-DEBUG] Creating NockchainNodeManager... (ONE TIME INITIALIZATION)");
-        let runner = Arc::new(Mutex::new(NockchainNodeManager::new(
-            api::wallet::network::NockchainNodeConfig::default(),
-        )));
-        println!("[UI-DEBUG] NockchainNodeManager created successfully (ONE TIME)");
-        runner
-    });
-
-    let mut node_status = use_signal(|| NodeStatus::Stopped);
-    let mut logs = use_signal(|| {
-        println!("[UI-DEBUG] Initializing logs with default entry");
-        vec![LogEntry {
-            timestamp: chrono::Utc::now(),
-            level: LogLevel::Info,
-            source: LogSource::Node,
-            message: "Nockchain node ready to start. Click Start Node to begin.".to_string(),
-        }]
-    });
-    let mut is_starting = use_signal(|| false);
-    let mut is_stopping = use_signal(|| false);
-    let mut log_level = use_signal(|| LogLevel::Info);
-    let mut auto_scroll = use_signal(|| true);
+    // The status history is a file read, so the calendar refreshes less often
+    use_future(move || async move {
+        loop {
+            if let Ok(runner) = node_runner.read().try_lock() {
+                let now = chrono::Utc::now();
+                availability.set(Some(runner.availability_report(
+                    now - chrono::Duration::days(UPTIME_CALENDAR_DAYS)..now,
+                )));
+            }
+            tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
+        }
+    });
 
+    // Used after a start to rebroadcast our transactions the reloaded mempool dropped
+    let start_node_handle = node_handle.clone();
+    let start_wallet = wallet.clone();
     let start_node_handler = move |_| {
-        println!("[UI-DEBUG] start_node_handler called!");
+        debug!("start_node_handler called!");
 
         let node_runner_clone = node_runner.clone();
-        let mut is_starting_clone = is_starting.clone();
-        let mut node_status_clone = node_status.clone();
-        let mut logs_clone = logs.clone();
-
-        // Prevent multiple start attempts
-        println!(
-            "[UI-DEBUG] Checking current status - is_starting: {}, node_status: {:?}",
-            *is_starting.read(),
-            *node_status.read()
-        );
-
-        if *is_starting.read()
-            || matches!(
-                *node_status.read(),
-                NodeStatus::Running | NodeStatus::Starting
-            )
-        {
-            println!("[UI-DEBUG] Preventing multiple start attempts, returning early");
+        let node_handle = start_node_handle.clone();
+        let wallet = start_wallet.clone();
+
+        // The manager rejects invalid transitions; this only avoids queuing a
+        // start behind a lock that is already busy with one
+        if !matches!(
+            *node_status.read(),
+            NodeStatus::Stopped | NodeStatus::Error(_)
+        ) {
+            debug!("Node is {:?}, ignoring start request", *node_status.read());
             return;
         }
 
-        println!("[UI-DEBUG] Setting is_starting to true and status to Starting");
-        is_starting.set(true);
-        node_status.set(NodeStatus::Starting);
-
         // Add initial log immediately
-        println!("[UI-DEBUG] Adding initial log entry");
+        debug!("Adding initial log entry");
         {
-            let mut current_logs = logs_clone.read().clone();
-            current_logs.push(LogEntry {
+            console.push(LogEntry {
                 timestamp: chrono::Utc::now(),
                 level: LogLevel::Info,
                 source: LogSource::Node,
                 message: "🚀 Starting nockchain node with libraries...".to_string(),
+                operation_id: None,
+                repeat: None,
             });
-            logs_clone.set(current_logs);
-            println!("[UI-DEBUG] Initial log entry added to UI");
+            debug!("Initial log entry added to UI");
         }
 
-        println!("[UI-DEBUG] Spawning async task for node startup");
+        debug!("Spawning async task for node startup");
         spawn(async move {
-            println!("[UI-DEBUG] Inside async spawn task");
-
-            // Add timeout protection
-            println!("[UI-DEBUG] Starting timeout protection (30 seconds)");
-            let start_result = tokio::time::timeout(tokio::time::Duration::from_secs(30), async {
-                println!("[UI-DEBUG] Inside timeout async block");
-
-                // Try to acquire lock with timeout
-                println!("[UI-DEBUG] Attempting to acquire node runner lock (5 second timeout)");
-                let runner_result =
-                    tokio::time::timeout(tokio::time::Duration::from_secs(5), async {
-                        println!("[UI-DEBUG] Inside lock timeout block");
-                        match node_runner_clone.read().lock() {
-                            Ok(mut runner) => {
-                                println!("[UI-DEBUG] Successfully acquired node runner lock");
-
-                                // Add progress log
-                                let mut current_logs = logs_clone.read().clone();
-                                current_logs.push(LogEntry {
-                                    timestamp: chrono::Utc::now(),
-                                    level: LogLevel::Info,
-                                    source: LogSource::Node,
-                                    message: "🔧 Initializing node components...".to_string(),
-                                });
-                                logs_clone.set(current_logs);
-                                println!(
-                                    "[UI-DEBUG] Progress log added, calling runner.start_node()"
-                                );
-
-                                let result = runner.start_node().await;
-                                println!(
-                                    "[UI-DEBUG] runner.start_node() completed with result: {:?}",
-                                    result
-                                );
-                                result
-                            }
-                            Err(e) => {
-                                println!("[UI-DEBUG] Failed to acquire node runner lock: {}", e);
-                                Err(WalletError::Network(format!("Lock error: {}", e)))
-                            }
-                        }
-                    })
-                    .await;
+            debug!("Inside async spawn task");
+
+            // A start that stops making progress is failed by the manager's
+            // watchdog, so the start itself is never cut short here
+            let start_result = match node_runner_clone.read().lock() {
+                Ok(mut runner) => {
+                    debug!("Successfully acquired node runner lock");
 
-                println!("[UI-DEBUG] Lock timeout result: {:?}", runner_result);
+                    // Add progress log
+                    console.push(LogEntry {
+                        timestamp: chrono::Utc::now(),
+                        level: LogLevel::Info,
+                        source: LogSource::Node,
+                        message: "🔧 Initializing node components...".to_string(),
+                        operation_id: None,
+                        repeat: None,
+                    });
+                    debug!("Progress log added, calling runner.start_node()");
 
-                match runner_result {
-                    Ok(result) => {
-                        println!("[UI-DEBUG] Lock acquired successfully, returning result");
-                        result
-                    }
-                    Err(_) => {
-                        println!("[UI-DEBUG] Lock timeout occurred");
-                        Err(WalletError::Network(
-                            "Timeout acquiring node lock".to_string(),
-                        ))
-                    }
+                    runner.start_node().await
                 }
-            })
-            .await;
+                Err(e) => {
+                    debug!("Failed to acquire node runner lock: {}", e);
+                    Err(WalletError::Network(format!("Lock error: {}", e)))
+                }
+            };
 
-            println!(
-                "[UI-DEBUG] Overall timeout completed, handling result: {:?}",
-                start_result
-            );
+            debug!("Node start finished with result: {:?}", start_result);
 
             // Handle the result
             match start_result {
-                Ok(Ok(())) => {
-                    println!("[UI-DEBUG] Node started successfully!");
-                    node_status_clone.set(NodeStatus::Running);
-                    let mut current_logs = logs_clone.read().clone();
-                    current_logs.push(LogEntry {
+                Ok(()) => {
+                    debug!("Node started successfully!");
+                    console.push(LogEntry {
                         timestamp: chrono::Utc::now(),
                         level: LogLevel::Info,
                         source: LogSource::Node,
                         message: "✅ Node started successfully!".to_string(),
+                        operation_id: None,
+                        repeat: None,
                     });
-                    logs_clone.set(current_logs);
 
                     // Get fresh logs from node
-                    println!("[UI-DEBUG] Attempting to get fresh logs from node");
+                    debug!("Attempting to get fresh logs from node");
                     if let Ok(runner) = node_runner_clone.read().lock() {
                         let node_logs = runner.get_logs(Some(50));
                         if !node_logs.is_empty() {
-                            println!("[UI-DEBUG] Got {} fresh logs from node", node_logs.len());
-                            logs_clone.set(node_logs);
+                            debug!("Got {} fresh logs from node", node_logs.len());
+                            console.replace(node_logs);
                         } else {
-                            println!("[UI-DEBUG] No fresh logs available from node");
+                            debug!("No fresh logs available from node");
                         }
                     } else {
-                        println!("[UI-DEBUG] Failed to acquire lock for getting fresh logs");
+                        debug!("Failed to acquire lock for getting fresh logs");
+                    }
+
+                    let dropped = node_handle
+                        .try_with(|node| node.take_dropped_transactions())
+                        .unwrap_or_default();
+                    if !dropped.is_empty() {
+                        let resent = wallet
+                            .read()
+                            .await
+                            .rebroadcast_dropped(&dropped, &node_handle)
+                            .await;
+                        debug!("Rebroadcast {} dropped transactions", resent);
+                    }
+                }
+                Err(WalletError::StartCancelled) => {
+                    debug!("Node start cancelled");
+                    if let Ok(runner) = node_runner_clone.read().lock() {
+                        console.replace(runner.get_logs(Some(50)));
                     }
                 }
-                Ok(Err(e)) => {
+                Err(e) => {
                     let error_msg = format!("❌ Failed to start node: {}", e);
-                    println!("[UI-DEBUG] Node start failed: {}", error_msg);
-                    node_status_clone.set(NodeStatus::Error(error_msg.clone()));
-                    let mut current_logs = logs_clone.read().clone();
-                    current_logs.push(LogEntry {
-                        timestamp: chrono::Utc::now(),
-                        level: LogLevel::Error,
-                        source: LogSource::Node,
-                        message: error_msg,
-                    });
-                    logs_clone.set(current_logs);
-                }
-                Err(_) => {
-                    let error_msg = "⏰ Node start timeout after 30 seconds".to_string();
-                    println!("[UI-DEBUG] Node start timeout: {}", error_msg);
-                    node_status_clone.set(NodeStatus::Error(error_msg.clone()));
-                    let mut current_logs = logs_clone.read().clone();
-                    current_logs.push(LogEntry {
+                    debug!("Node start failed: {}", error_msg);
+                    console.push(LogEntry {
                         timestamp: chrono::Utc::now(),
                         level: LogLevel::Error,
                         source: LogSource::Node,
                         message: error_msg,
+                        operation_id: None,
+                        repeat: None,
                     });
-                    logs_clone.set(current_logs);
                 }
             }
-
-            println!("[UI-DEBUG] Setting is_starting back to false");
-            is_starting_clone.set(false);
         });
     };
 
     let stop_node_handler = move |_| {
-        println!("[UI-DEBUG] stop_node_handler called!");
+        debug!("stop_node_handler called!");
 
         let node_runner_clone = node_runner.clone();
-        let mut is_stopping_clone = is_stopping.clone();
-        let mut node_status_clone = node_status.clone();
-        let mut logs_clone = logs.clone();
 
-        println!("[UI-DEBUG] Setting is_stopping to true and status to Stopping");
-        is_stopping.set(true);
-        node_status.set(NodeStatus::Stopping);
+        if !matches!(
+            *node_status.read(),
+            NodeStatus::Running | NodeStatus::Error(_)
+        ) {
+            debug!("Node is {:?}, ignoring stop request", *node_status.read());
+            return;
+        }
 
-        println!("[UI-DEBUG] Spawning async task for node stop");
+        debug!("Spawning async task for node stop");
         spawn(async move {
-            println!("[UI-DEBUG] Inside stop async spawn task");
+            debug!("Inside stop async spawn task");
 
             // Safely handle the mutex lock
-            println!("[UI-DEBUG] Attempting to acquire node runner lock for stop");
+            debug!("Attempting to acquire node runner lock for stop");
             let stop_result = match node_runner_clone.read().lock() {
                 Ok(mut runner) => {
-                    println!("[UI-DEBUG] Successfully acquired lock for stop, calling stop_node()");
+                    debug!("Successfully acquired lock for stop, calling stop_node()");
                     let result = runner.stop_node().await;
-                    println!("[UI-DEBUG] stop_node() completed with result: {:?}", result);
+                    debug!("stop_node() completed with result: {:?}", result);
                     result
                 }
                 Err(e) => {
-                    println!("[UI-DEBUG] Failed to acquire lock for stop: {}", e);
+                    debug!("Failed to acquire lock for stop: {}", e);
                     Err(WalletError::Network(format!(
                         "Failed to acquire node runner lock: {}",
                         e
@@ -330,39 +2946,129 @@ fn Node() -> Element {
 
             match stop_result {
                 Ok(()) => {
-                    node_status_clone.set(NodeStatus::Stopped);
                     // Get the latest logs from the node runner
                     if let Ok(runner) = node_runner_clone.read().lock() {
                         let node_logs = runner.get_logs(Some(50));
-                        logs_clone.set(node_logs);
+                        console.replace(node_logs);
                     }
                 }
                 Err(e) => {
                     let error_msg = format!("Failed to stop node: {}", e);
-                    node_status_clone.set(NodeStatus::Error(error_msg.clone()));
-                    let mut current_logs = logs_clone.read().clone();
-                    current_logs.push(LogEntry {
+                    console.push(LogEntry {
                         timestamp: chrono::Utc::now(),
                         level: LogLevel::Error,
                         source: LogSource::Node,
                         message: error_msg,
+                        operation_id: None,
+                        repeat: None,
                     });
-                    logs_clone.set(current_logs);
                 }
             }
-            is_stopping_clone.set(false);
         });
     };
 
+    let cancel_start_handler = {
+        let node_handle = node_handle.clone();
+        move |_| {
+            // Losing the race to Running is expected; the status update covers it
+            if let Err(e) = node_handle.cancel_start() {
+                debug!("Cancel start ignored: {}", e);
+            }
+        }
+    };
+
+    let export_bundle_handler = move |_| {
+        let node_runner_clone = node_runner.clone();
+        let mut toast_clone = toast_message.clone();
+
+        spawn(async move {
+            let result = match node_runner_clone.read().lock() {
+                Ok(runner) => {
+                    let path = runner.default_debug_bundle_path();
+                    runner.export_debug_bundle(&path).await
+                }
+                Err(e) => Err(WalletError::Network(format!("Lock error: {}", e))),
+            };
+
+            let message = match result {
+                Ok(path) => format!("🧰 Debug bundle saved to {}", path.display()),
+                Err(e) => format!("❌ Failed to export debug bundle: {}", e),
+            };
+            toast_clone.set(Some(message));
+        });
+    };
+
+    let purge_handler = move |scope: PurgeScope| {
+        let node_runner_clone = node_runner.clone();
+        let mut toast_clone = toast_message.clone();
+        let mut data_dir_usage_clone = data_dir_usage.clone();
+
+        spawn(async move {
+            let result = match node_runner_clone.read().lock() {
+                Ok(mut runner) => {
+                    let result = runner.purge_data(scope);
+                    data_dir_usage_clone.set(runner.data_dir_usage());
+                    result
+                }
+                Err(e) => Err(WalletError::Network(format!("Lock error: {}", e))),
+            };
+
+            let message = match result {
+                Ok(freed) => format!("🗑️ Purged {:?} data, freed {}", scope, format_bytes(freed)),
+                Err(e) => format!("❌ Failed to purge node data: {}", e),
+            };
+            toast_clone.set(Some(message));
+        });
+    };
+
+    // Log levels apply to the running node straight away
+    let levels_wallet = wallet.clone();
+    let log_levels_handler = move |levels: LogLevels| {
+        let node_runner_clone = node_runner.clone();
+
+        if let Ok(mut runner) = node_runner_clone.read().lock() {
+            let outcome = match runner.get_config().to_builder().log_levels(levels).build() {
+                Ok(config) => {
+                    runner.update_config(config);
+                    AuditOutcome::Success
+                }
+                Err(problems) => {
+                    for problem in &problems {
+                        error!("Not applying log levels: {}", problem);
+                    }
+                    AuditOutcome::Failure(
+                        problems
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect::<Vec<_>>()
+                            .join("; "),
+                    )
+                }
+            };
+            console.replace(runner.get_logs(Some(50)));
+
+            let wallet = levels_wallet.clone();
+            spawn(async move {
+                wallet.write().await.record_audit(
+                    AuditEvent::SettingsChanged {
+                        setting: "log_levels".to_string(),
+                    },
+                    outcome,
+                );
+                save_wallet_records(&wallet).await;
+            });
+        }
+    };
+
     // Replace infinite loop with a safer approach - just update logs when needed
     // Commented out to prevent potential infinite loops that cause hanging
     // use_effect(move || {
-    //     println!("[UI-DEBUG] Setting up periodic log updates effect");
+    //     debug!("Setting up periodic log updates effect");
     //     let node_runner_clone = node_runner.clone();
     //     let mut logs_clone = logs.clone();
     //
     //     spawn(async move {
-    //         println!("[UI-DEBUG] Starting limited log update loop");
+    //         debug!("Starting limited log update loop");
     //         for i in 0..10 {
     //             tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
     //             tokio::task::yield_now().await;
@@ -372,13 +3078,13 @@ fn Node() -> Element {
     //                 if runner.is_running() {
     //                     let node_logs = runner.get_logs(50);
     //                     if !node_logs.is_empty() {
-    //                         println!("[UI-DEBUG] Update cycle {}: {} log entries", i, node_logs.len());
+    //                         debug!("Update cycle {}: {} log entries", i, node_logs.len());
     //                         logs_clone.set(node_logs);
     //                     }
     //                 }
     //             }
     //         }
-    //         println!("[UI-DEBUG] Limited log update loop completed");
+    //         debug!("Limited log update loop completed");
     //     });
     // });
 
@@ -400,6 +3106,35 @@ fn Node() -> Element {
         .cloned()
         .collect::<Vec<_>>();
 
+    // The lines shown, at the level picked, saved as a file
+    let export_logs = {
+        let entries = filtered_logs.clone();
+        move |format: ExportFormat| {
+            let entries = entries.clone();
+            let run = ExportRun::default();
+            log_export_run.set(Some(run.clone()));
+            log_export_total.set(Some(entries.len() as u64));
+            spawn(async move {
+                let message = save_export(
+                    "node-log",
+                    format,
+                    run,
+                    log_export_rows,
+                    move |file, run| {
+                        export_logs_to_writer(file, &entries, format, |written| run.report(written))
+                    },
+                )
+                .await;
+                log_export_run.set(None);
+                if message.is_some() {
+                    toast_message.set(message);
+                }
+            });
+        }
+    };
+
+    let clear_conditions_handle = node_handle.clone();
+
     // Get current node configuration for display
     let node_config = {
         if let Ok(runner) = node_runner.read().lock() {
@@ -465,6 +3200,67 @@ fn Node() -> Element {
                     style: "color: #666; font-size: 14px;",
                     "Showing {filtered_logs.len()} / {logs.read().len()} logs"
                 }
+                button {
+                    style: "margin-left: auto; padding: 6px 12px; border: 1px solid #ccc; border-radius: 4px; background: white; cursor: pointer;",
+                    onclick: export_bundle_handler,
+                    "🧰 Export debug bundle"
+                }
+            }
+            ExportPanel {
+                subject: "shown log lines",
+                on_export: export_logs,
+                rows: log_export_rows(),
+                total: log_export_total(),
+                on_cancel: move |_| {
+                    if let Some(run) = log_export_run.read().as_ref() {
+                        run.cancel();
+                    }
+                },
+            }
+
+            LogLevelGrid {
+                levels: node_config.log_levels.clone(),
+                on_change: log_levels_handler,
+            }
+
+            if node_config.fakenet && network_conditions.read().is_active() {
+                SimulatedNetworkBanner {
+                    conditions: network_conditions(),
+                    on_clear: move |_| {
+                        let node_handle = clear_conditions_handle.clone();
+                        spawn(async move {
+                            match node_handle.set_network_conditions(NetworkConditions::default()).await {
+                                Ok(()) => network_conditions.set(NetworkConditions::default()),
+                                Err(e) => toast_message.set(Some(e.to_string())),
+                            }
+                        });
+                    },
+                }
+            }
+
+            if let Some(message) = toast_message.read().clone() {
+                div {
+                    style: "position: fixed; bottom: 24px; right: 24px; max-width: 420px; background: #1a1a1a; color: white; padding: 12px 16px; border-radius: 8px; box-shadow: 0 4px 20px rgba(0, 0, 0, 0.25); display: flex; gap: 12px; align-items: flex-start; z-index: 1000; word-break: break-all;",
+                    role: "status",
+                    aria_live: "polite",
+                    span { "{message}" }
+                    button {
+                        style: "background: none; border: none; color: #9ca3af; cursor: pointer;",
+                        aria_label: t!("nav.dismiss"),
+                        onclick: move |_| toast_message.set(None),
+                        "✕"
+                    }
+                }
+            }
+
+            if *node_status.read() == NodeStatus::Running && node_stats.read().is_none() {
+                NodeStatsSkeleton {}
+            } else {
+                NodeStatsStrip {
+                    stats: node_stats.read().clone(),
+                    validation: validation_phase(),
+                    filter_sync: filter_sync(),
+                }
             }
 
             NodeConsole {
@@ -472,8 +3268,30 @@ fn Node() -> Element {
                 logs: filtered_logs,
                 on_start_node: start_node_handler,
                 on_stop_node: stop_node_handler,
-                is_starting: *is_starting.read(),
-                is_stopping: *is_stopping.read(),
+                on_cancel_start: cancel_start_handler,
+                logs_dropped: *logs_dropped.read(),
+                level_counts: level_counts(),
+                follow_tail: auto_scroll(),
+                scroll_top: *console.scroll_top.peek(),
+                on_scroll: move |top| {
+                    let mut scroll_top = console.scroll_top;
+                    scroll_top.set(top);
+                },
+                session_started: console.session_started,
+            }
+
+            PeerTable {
+                peers: peers.read().clone(),
+                locations: !node_config.geoip_databases.is_empty(),
+            }
+            MiningPanel { rewards: mining_rewards.read().clone(), tip_height: *chain_tip.read() }
+            UptimeCalendar { report: availability.read().clone() }
+
+            StoragePanel {
+                breakdown: storage_breakdown.read().clone(),
+                report: compaction_report.read().clone(),
+                on_compact: compact_handler,
+                busy: *compacting.read(),
             }
 
             // Node configuration info - using real config from node runner
@@ -484,7 +3302,7 @@ fn Node() -> Element {
                     "Node Configuration"
                 }
                 div {
-                    style: "display: grid; grid-template-columns: repeat(auto-fit, minmax(200px, 1fr)); gap: 16px; color: #666;",
+                    style: "display: grid; grid-template-columns: repeat(auto-fit, minmax(min(200px, 100%), 1fr)); gap: 16px; color: #666;",
                     div {
                         strong { "Network: " }
                         if node_config.fakenet {
@@ -525,6 +3343,17 @@ fn Node() -> Element {
                             "Unlimited"
                         }
                     }
+                    div {
+                        strong { "Outbound: " }
+                        if let Some(proxy) = Socks5Proxy::from_config(&node_config) {
+                            span { style: "color: #6f42c1;", "via {proxy}" }
+                            if proxy.remote_dns {
+                                " (DNS through proxy)"
+                            }
+                        } else {
+                            "Direct"
+                        }
+                    }
                 }
 
                 div {
@@ -551,7 +3380,444 @@ fn Node() -> Element {
                         style: "font-family: monospace; font-size: 12px; color: #6c757d; word-break: break-all;",
                         "{node_config.data_dir.display()}"
                     }
+                    div {
+                        style: "margin-top: 8px;",
+                        DataDirUsageBar { usage: data_dir_usage.read().clone() }
+                    }
+                }
+
+                DangerZone {
+                    disabled: !matches!(*node_status.read(), NodeStatus::Stopped | NodeStatus::Error(_)),
+                    on_purge: purge_handler,
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn Settings() -> Element {
+    let wallet = use_context::<Arc<tokio::sync::RwLock<Wallet>>>();
+    let mut category = use_signal(|| None::<AuditCategory>);
+    let mut entries = use_signal(Vec::<AuditEntry>::new);
+    let mut chain_break = use_signal(|| None::<AuditChainBreak>);
+    let mut limits = use_signal(SpendingLimits::default);
+    let mut allowance = use_signal(|| None::<DailyAllowance>);
+    let mut read_only = use_signal(|| false);
+    let mut integrity = use_signal(|| None::<IntegrityReport>);
+    let mut verifying = use_signal(|| false);
+    let mut balance_check = use_signal(|| None::<ReconciliationReport>);
+    let mut display = use_signal(DisplaySettings::default);
+    let mut webhooks = use_signal(Vec::<WebhookConfig>::new);
+    let mut deliveries = use_signal(Vec::<WebhookDelivery>::new);
+    let mut maintenance = use_signal(|| None::<MaintenanceReport>);
+    let mut maintenance_error = use_signal(|| None::<String>);
+    let mut cleaning = use_signal(|| false);
+    let ledger_export_rows = use_signal(|| None::<u64>);
+    let mut ledger_export_total = use_signal(|| None::<u64>);
+    let mut ledger_export_run = use_signal(|| None::<ExportRun>);
+    let mut ledger_export_message = use_signal(|| None::<String>);
+    let mut version_info = use_signal(|| None::<VersionInfo>);
+    let mut version_error = use_signal(|| None::<String>);
+    let mut locale = use_context::<Signal<Locale>>();
+    let node = use_context::<NodeHandle>();
+    let cleanup_node = node.clone();
+
+    use_future({
+        let wallet = wallet.clone();
+        let node = node.clone();
+        move || {
+            let wallet = wallet.clone();
+            let node = node.clone();
+            async move {
+                match node.version_info().await {
+                    Ok(info) => {
+                        let schema = wallet.read().await.storage_schema();
+                        version_info.set(Some(info.with_wallet_schema(schema)));
+                    }
+                    Err(e) => version_error.set(Some(e.to_string())),
+                }
+            }
+        }
+    });
+
+    let load_entries = {
+        let wallet = wallet.clone();
+        move || {
+            let wallet = wallet.clone();
+            async move {
+                let wallet = wallet.read().await;
+                let filter = AuditFilter {
+                    category: category(),
+                    ..AuditFilter::default()
+                };
+                entries.set(wallet.audit_log(&filter));
+                chain_break.set(wallet.audit_chain_break().cloned());
+                limits.set(wallet.spending_limits());
+                allowance.set(wallet.daily_allowance());
+                read_only.set(wallet.is_read_only());
+                integrity.set(wallet.integrity_report().cloned());
+                display.set(wallet.display_settings());
+                webhooks.set(wallet.webhooks().hooks().to_vec());
+                deliveries.set(wallet.webhooks().deliveries().cloned().collect());
+                maintenance.set(wallet.last_maintenance().cloned());
+            }
+        }
+    };
+
+    // Pick up entries recorded elsewhere in the app
+    use_future({
+        let load_entries = load_entries.clone();
+        move || {
+            let load_entries = load_entries.clone();
+            async move {
+                loop {
+                    load_entries().await;
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+            }
+        }
+    });
+
+    let filter_handler = {
+        let load_entries = load_entries.clone();
+        move |selected: Option<AuditCategory>| {
+            category.set(selected);
+            spawn(load_entries());
+        }
+    };
+
+    let verify_handler = {
+        let wallet = wallet.clone();
+        move |_| {
+            let wallet = wallet.clone();
+            verifying.set(true);
+            spawn(async move {
+                let mut wallet = wallet.write().await;
+                integrity.set(Some(wallet.verify_integrity()));
+                balance_check.set(Some(wallet.reconcile_balances()));
+                read_only.set(wallet.is_read_only());
+                verifying.set(false);
+            });
+        }
+    };
+    // Streams under the wallet's read lock, like the transaction history
+    let export_ledger = {
+        let wallet = wallet.clone();
+        move |format: ExportFormat| {
+            let wallet = wallet.clone();
+            let run = ExportRun::default();
+            ledger_export_run.set(Some(run.clone()));
+            ledger_export_message.set(None);
+            spawn(async move {
+                ledger_export_total.set(Some(wallet.read().await.ledger(..).len() as u64));
+                let message = save_export("ledger", format, run, ledger_export_rows, {
+                    let wallet = wallet.clone();
+                    move |file, run| {
+                        let wallet = wallet.blocking_read();
+                        export_ledger_to_writer(file, wallet.ledger(..), format, |written| {
+                            run.report(written)
+                        })
+                    }
+                })
+                .await;
+                ledger_export_run.set(None);
+                if message.is_some() {
+                    ledger_export_message.set(message);
+                }
+            });
+        }
+    };
+    let accept_handler = {
+        let wallet = wallet.clone();
+        move |_| {
+            let wallet = wallet.clone();
+            verifying.set(true);
+            spawn(async move {
+                let mut wallet = wallet.write().await;
+                match wallet.accept_wallet_files() {
+                    Ok(report) => integrity.set(Some(report)),
+                    Err(e) => error!("Failed to accept wallet files: {}", e),
+                }
+                read_only.set(wallet.is_read_only());
+                verifying.set(false);
+            });
+        }
+    };
+
+    let save_display = {
+        let wallet = wallet.clone();
+        move |settings: DisplaySettings| {
+            let wallet = wallet.clone();
+            spawn(async move {
+                if let Err(e) = wallet.write().await.set_display_settings(settings) {
+                    error!("Failed to change display settings: {}", e);
+                }
+                save_wallet_records(&wallet).await;
+                display.set(wallet.read().await.display_settings());
+            });
+        }
+    };
+
+    let change_language = move |selected: Locale| {
+        locale.set(selected);
+        let Some(dir) = app_config_dir(&node) else {
+            return;
+        };
+        let saved = load_ui_prefs(&dir).and_then(|mut prefs| {
+            prefs.language = Some(selected.code().to_string());
+            save_ui_prefs(&dir, &prefs)
+        });
+        if let Err(e) = saved {
+            error!("Failed to save UI preferences: {}", e);
+        }
+    };
+
+    let save_webhooks = {
+        let wallet = wallet.clone();
+        let load_entries = load_entries.clone();
+        move |hooks: Vec<WebhookConfig>| {
+            let wallet = wallet.clone();
+            let load_entries = load_entries.clone();
+            spawn(async move {
+                if let Err(e) = wallet.write().await.set_webhooks(hooks) {
+                    error!("Failed to change webhooks: {}", e);
+                }
+                save_wallet_records(&wallet).await;
+                load_entries().await;
+            });
+        }
+    };
+
+    let cleanup_handler = {
+        let wallet = wallet.clone();
+        let node = cleanup_node;
+        move |_| {
+            let wallet = wallet.clone();
+            let node = node.clone();
+            cleaning.set(true);
+            spawn(async move {
+                match run_wallet_maintenance(&wallet, &node).await {
+                    Ok(report) => {
+                        maintenance.set(Some(report));
+                        maintenance_error.set(None);
+                    }
+                    Err(e) => maintenance_error.set(Some(e.to_string())),
                 }
+                cleaning.set(false);
+            });
+        }
+    };
+
+    let save_limits = move |new_limits: SpendingLimits| {
+        let wallet = wallet.clone();
+        let load_entries = load_entries.clone();
+        spawn(async move {
+            if let Err(e) = wallet.write().await.set_spending_limits(new_limits) {
+                error!("Failed to change spending limits: {}", e);
+            }
+            save_wallet_records(&wallet).await;
+            load_entries().await;
+        });
+    };
+
+    rsx! {
+        div {
+            h2 { style: "color: #333; margin-bottom: 24px;", "⚙️ Settings" }
+
+            OverridesChip { flags: launch_options().active_flags() }
+
+            LanguagePicker {
+                value: locale(),
+                on_change: change_language,
+            }
+
+            SpendingLimitsForm {
+                limits: limits(),
+                allowance: allowance(),
+                on_save: save_limits,
+                disabled: read_only(),
+            }
+
+            DisplaySettingsForm {
+                settings: display(),
+                on_save: save_display,
+                disabled: read_only(),
+            }
+
+            WebhookSettings {
+                hooks: webhooks(),
+                deliveries: deliveries(),
+                on_save: save_webhooks,
+                disabled: read_only(),
+            }
+
+            AuditLogViewer {
+                entries: entries(),
+                chain_break: chain_break(),
+                on_filter: filter_handler,
+            }
+
+            IntegrityPanel {
+                report: integrity(),
+                on_verify: verify_handler,
+                on_accept: accept_handler,
+                busy: verifying(),
+                balances: balance_check(),
+            }
+            ExportPanel {
+                subject: "ledger",
+                on_export: export_ledger,
+                rows: ledger_export_rows(),
+                total: ledger_export_total(),
+                on_cancel: move |_| {
+                    if let Some(run) = ledger_export_run.read().as_ref() {
+                        run.cancel();
+                    }
+                },
+                message: ledger_export_message(),
+            }
+
+            MaintenancePanel {
+                report: maintenance(),
+                on_run: cleanup_handler,
+                error: maintenance_error(),
+                busy: cleaning(),
+                disabled: read_only(),
+            }
+
+            h3 { style: "color: #333; margin-top: 24px;", "Tools" }
+            Link { to: Route::DecodeTool {}, "Decode a raw transaction" }
+
+            div { style: "margin-top: 24px;",
+                AboutPanel { info: version_info(), error: version_error() }
+            }
+        }
+    }
+}
+
+/// Inspect raw transaction hex, e.g. from a failed broadcast
+#[component]
+fn DecodeTool() -> Element {
+    rsx! {
+        div {
+            h2 { style: "color: #333; margin-bottom: 24px;", "Decode transaction" }
+            TransactionDecoder {}
+        }
+    }
+}
+
+/// Latest blocks of the local chain
+#[component]
+fn Explorer() -> Element {
+    let chain = use_context::<Arc<tokio::sync::RwLock<Blockchain>>>();
+    let navigator = use_navigator();
+    let mut offset = use_signal(|| 0_usize);
+    let mut page = use_signal(BlocksPage::default);
+
+    // Follow new blocks while open; only the page shown is built
+    let chain_for_page = chain.clone();
+    use_future(move || {
+        let chain = chain.clone();
+        async move {
+            loop {
+                page.set(BlocksPage::of(
+                    &*chain.read().await,
+                    offset(),
+                    EXPLORER_PAGE_SIZE,
+                ));
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+        }
+    });
+    let show_page = move |new_offset: usize| {
+        let chain = chain_for_page.clone();
+        offset.set(new_offset);
+        spawn(async move {
+            page.set(BlocksPage::of(
+                &*chain.read().await,
+                new_offset,
+                EXPLORER_PAGE_SIZE,
+            ));
+        });
+    };
+
+    rsx! {
+        div {
+            h2 { style: "color: #333; margin-bottom: 24px;", "Explorer" }
+            BlockList {
+                page: page(),
+                page_size: EXPLORER_PAGE_SIZE,
+                on_page: show_page,
+                on_open_block: move |id| {
+                    navigator.push(Route::ExplorerBlock { id });
+                },
+            }
+        }
+    }
+}
+
+/// One block of the local chain, by height or hash
+#[component]
+fn ExplorerBlock(id: String) -> Element {
+    let chain = use_context::<Arc<tokio::sync::RwLock<Blockchain>>>();
+    let navigator = use_navigator();
+    let block = use_resource(use_reactive((&id,), move |(id,)| {
+        let chain = chain.clone();
+        async move { BlockView::find(&*chain.read().await, &id) }
+    }));
+
+    rsx! {
+        div {
+            h2 { style: "color: #333; margin-bottom: 24px;", "Block" }
+            Link { to: Route::Explorer {}, "← Latest blocks" }
+            match block() {
+                None => rsx! { div { class: "empty-state", "Loading…" } },
+                Some(None) => rsx! { div { class: "empty-state", "No block {id} in the local chain." } },
+                Some(Some(block)) => rsx! {
+                    BlockDetail {
+                        block,
+                        on_open_block: move |id| {
+                            navigator.push(Route::ExplorerBlock { id });
+                        },
+                        on_open_transaction: move |id| {
+                            navigator.push(Route::ExplorerTransaction { id });
+                        },
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// A transaction on the local chain
+#[component]
+fn ExplorerTransaction(id: String) -> Element {
+    let chain = use_context::<Arc<tokio::sync::RwLock<Blockchain>>>();
+    let navigator = use_navigator();
+    let transaction = use_resource(use_reactive((&id,), move |(id,)| {
+        let chain = chain.clone();
+        async move { ChainTransactionView::find(&*chain.read().await, &id) }
+    }));
+
+    rsx! {
+        div {
+            h2 { style: "color: #333; margin-bottom: 24px;", "Transaction" }
+            Link { to: Route::Explorer {}, "← Latest blocks" }
+            match transaction() {
+                None => rsx! { div { class: "empty-state", "Loading…" } },
+                Some(None) => rsx! { div { class: "empty-state", "No transaction {id} in the local chain." } },
+                Some(Some(transaction)) => rsx! {
+                    ChainTransactionDetail {
+                        transaction,
+                        on_open_block: move |id| {
+                            navigator.push(Route::ExplorerBlock { id });
+                        },
+                        on_open_transaction: move |id| {
+                            navigator.push(Route::ExplorerTransaction { id });
+                        },
+                    }
+                },
             }
         }
     }
@@ -0,0 +1,90 @@
+//! Fails the build if a `t!` key is missing from a translation bundle, or if
+//! the bundles don't all have the same keys.
+
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+fn main() {
+    println!("cargo:rerun-if-changed=src");
+
+    let mut used = BTreeSet::new();
+    collect_used_keys(Path::new("src"), &mut used);
+
+    let mut bundles = Vec::new();
+    for entry in fs::read_dir("src/i18n").expect("src/i18n is missing") {
+        let path = entry.expect("cannot read src/i18n").path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            let text = fs::read_to_string(&path).expect("cannot read bundle");
+            let root: Value = serde_json::from_str(&text)
+                .unwrap_or_else(|e| panic!("{} is not valid JSON: {}", path.display(), e));
+            let mut keys = BTreeSet::new();
+            collect_bundle_keys("", &root, &mut keys);
+            bundles.push((path, keys));
+        }
+    }
+    bundles.sort();
+
+    let mut problems = Vec::new();
+    for (path, keys) in &bundles {
+        for key in used.difference(keys) {
+            problems.push(format!("{}: missing \"{}\"", path.display(), key));
+        }
+        for (other_path, other) in &bundles {
+            for key in other.difference(keys) {
+                if !used.contains(key) {
+                    problems.push(format!(
+                        "{}: missing \"{}\" (in {})",
+                        path.display(),
+                        key,
+                        other_path.display()
+                    ));
+                }
+            }
+        }
+    }
+    if !problems.is_empty() {
+        panic!("translation bundles are incomplete:\n{}", problems.join("\n"));
+    }
+}
+
+fn collect_used_keys(dir: &Path, used: &mut BTreeSet<String>) {
+    for entry in fs::read_dir(dir).expect("cannot read src") {
+        let path = entry.expect("cannot read src").path();
+        if path.is_dir() {
+            collect_used_keys(&path, used);
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            let text = fs::read_to_string(&path).expect("cannot read source file");
+            for (i, m) in text.match_indices("t!(\"") {
+                // Skip `format!(`, `print!(` and the like
+                let macro_start = text[..i].chars().next_back();
+                if macro_start.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                    continue;
+                }
+                let rest = &text[i + m.len()..];
+                if let Some(end) = rest.find('"') {
+                    used.insert(rest[..end].to_string());
+                }
+            }
+        }
+    }
+}
+
+fn collect_bundle_keys(prefix: &str, value: &Value, keys: &mut BTreeSet<String>) {
+    match value {
+        Value::Object(map) if !map.contains_key("other") => {
+            for (name, child) in map {
+                let key = if prefix.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}.{}", prefix, name)
+                };
+                collect_bundle_keys(&key, child, keys);
+            }
+        }
+        _ => {
+            keys.insert(prefix.to_string());
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Clipboard access shared by every platform. Desktop, web and mobile all
+//! render in a webview, so both directions go through `navigator.clipboard`.
+
+use api::{Address, AddressError, AddressNetwork};
+use dioxus::prelude::*;
+
+/// Scheme of payment URIs, `nock:<address>[?amount=...]`
+pub const NOCK_URI_SCHEME: &str = "nock:";
+
+/// How long copy confirmations and paste errors stay visible
+pub(crate) const CONFIRMATION_MS: u32 = 2000;
+
+/// Put `text` on the clipboard
+pub async fn copy(text: &str) -> Result<(), String> {
+    let eval = document::eval(
+        r#"
+        const text = await dioxus.recv();
+        await navigator.clipboard.writeText(text);
+        return true;
+        "#,
+    );
+    eval.send(text).map_err(|e| format!("Copy failed: {}", e))?;
+    eval.join::<bool>()
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("Copy failed: {}", e))
+}
+
+/// Current clipboard text, or `None` if it is empty or can't be read
+pub async fn read() -> Option<String> {
+    document::eval("return await navigator.clipboard.readText();")
+        .join::<String>()
+        .await
+        .ok()
+        .filter(|text| !text.trim().is_empty())
+}
+
+/// Wait `ms` milliseconds without tying the ui crate to an async runtime
+pub(crate) async fn delay(ms: u32) {
+    let eval = document::eval(&format!(
+        "await new Promise(resolve => setTimeout(resolve, {})); return true;",
+        ms
+    ));
+    let _ = eval.join::<bool>().await;
+}
+
+/// Address in `text`, which may be a bare address or a `nock:` URI.
+/// Surrounding whitespace and URI query parameters are ignored.
+pub fn detect_address(text: &str, network: AddressNetwork) -> Result<Address, AddressError> {
+    Address::from_string_checked(strip_nock_uri(text), network)
+}
+
+/// What pasting `clipboard` into an address field gives: the address, or the
+/// message to show instead
+pub fn paste_address(clipboard: Option<&str>, network: AddressNetwork) -> Result<Address, String> {
+    match clipboard {
+        Some(text) => detect_address(text, network)
+            .map_err(|e| format!("Clipboard doesn't contain a valid address: {}", e)),
+        None => Err("Clipboard is empty or can't be read".to_string()),
+    }
+}
+
+/// The address part of a `nock:` URI, or the trimmed text if it isn't one
+pub fn strip_nock_uri(text: &str) -> &str {
+    let text = text.trim();
+    match text.get(..NOCK_URI_SCHEME.len()) {
+        Some(scheme) if scheme.eq_ignore_ascii_case(NOCK_URI_SCHEME) => {
+            let rest = &text[NOCK_URI_SCHEME.len()..];
+            let rest = rest.strip_prefix("//").unwrap_or(rest);
+            rest.split(['?', '#']).next().unwrap_or(rest)
+        }
+        _ => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use api::testing::fake_address;
+
+    #[test]
+    fn bare_addresses_and_nock_uris_are_detected() {
+        let address = fake_address();
+        let checked = address.to_checked_string(AddressNetwork::Mainnet);
+        for clipboard in [
+            address.to_string(),
+            format!("  {}\n", checked),
+            format!("nock:{}", checked),
+            format!("NOCK://{}?amount=1.5&label=rent", checked),
+            format!("nock:{}#memo", address),
+        ] {
+            assert_eq!(
+                detect_address(&clipboard, AddressNetwork::Mainnet),
+                Ok(address.clone()),
+                "{}",
+                clipboard
+            );
+        }
+    }
+
+    #[test]
+    fn pasting_anything_but_an_address_explains_why() {
+        let checked = fake_address().to_checked_string(AddressNetwork::Fakenet);
+        let cases = [
+            (None, "Clipboard is empty or can't be read".to_string()),
+            (
+                Some("nock:"),
+                "Clipboard doesn't contain a valid address: Address is empty".to_string(),
+            ),
+            (
+                Some("hello world"),
+                format!(
+                    "Clipboard doesn't contain a valid address: {}",
+                    detect_address("hello world", AddressNetwork::Mainnet).unwrap_err()
+                ),
+            ),
+        ];
+        for (clipboard, expected) in cases {
+            assert_eq!(
+                paste_address(clipboard, AddressNetwork::Mainnet),
+                Err(expected)
+            );
+        }
+        // A checked address for another network is refused
+        assert!(matches!(
+            detect_address(&checked, AddressNetwork::Mainnet),
+            Err(AddressError::WrongNetwork { .. })
+        ));
+    }
+
+    #[test]
+    fn only_the_nock_scheme_is_stripped() {
+        assert_eq!(strip_nock_uri(" nock:abc?amount=2 "), "abc");
+        assert_eq!(strip_nock_uri("bitcoin:abc"), "bitcoin:abc");
+        assert_eq!(strip_nock_uri("no"), "no");
+    }
+}
@@ -0,0 +1,222 @@
+use dioxus::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Gives each mounted dialog its own element id
+static NEXT_DIALOG_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Elements Tab moves between inside a dialog
+const FOCUSABLE: &str =
+    "a[href], button, input, select, textarea, summary, [tabindex]:not([tabindex=\"-1\"])";
+
+/// Modal panel over a backdrop. Focus moves into it when it opens, Tab and
+/// Shift+Tab cycle through its controls without leaving it, and focus goes
+/// back where it was when it closes. Every modal builds on this so they all
+/// behave the same for keyboard and screen-reader users.
+#[component]
+pub fn Dialog(
+    /// Heading, also the dialog's accessible name
+    title: String,
+    /// Called on Escape or a click on the backdrop. Without it the dialog
+    /// stays until one of its own buttons closes it.
+    #[props(default)]
+    on_close: Option<EventHandler<()>>,
+    /// Extra class on the panel, for the content's own styles
+    #[props(default)]
+    class: String,
+    children: Element,
+) -> Element {
+    let id = use_hook(|| format!("dialog-{}", NEXT_DIALOG_ID.fetch_add(1, Ordering::Relaxed)));
+    use_drop({
+        let id = id.clone();
+        move || restore_focus(&id)
+    });
+
+    rsx! {
+        div {
+            class: "dialog-backdrop",
+            onclick: move |_| {
+                if let Some(on_close) = on_close {
+                    on_close.call(());
+                }
+            },
+            div {
+                id: "{id}",
+                class: "dialog {class}",
+                role: "dialog",
+                aria_modal: "true",
+                aria_labelledby: "{id}-title",
+                tabindex: "-1",
+                onmounted: {
+                    let id = id.clone();
+                    move |_| focus_first(&id)
+                },
+                // Clicks inside the panel aren't clicks on the backdrop
+                onclick: move |evt| evt.stop_propagation(),
+                onkeydown: {
+                    let id = id.clone();
+                    move |evt: KeyboardEvent| match evt.key() {
+                        Key::Escape => {
+                            if let Some(on_close) = on_close {
+                                on_close.call(());
+                            }
+                        }
+                        Key::Tab => {
+                            evt.prevent_default();
+                            cycle_focus(&id, evt.modifiers().contains(Modifiers::SHIFT));
+                        }
+                        _ => {}
+                    }
+                },
+                h4 { id: "{id}-title", class: "dialog-title", "{title}" }
+                {children}
+            }
+        }
+
+        style { {DIALOG_CSS} }
+    }
+}
+
+/// Index of the control Tab moves to among `count`, from the one at `active`
+/// (`None` when focus is on none of them), wrapping at either end
+fn next_focus_index(active: Option<usize>, count: usize, backwards: bool) -> Option<usize> {
+    if count == 0 {
+        return None;
+    }
+    Some(match (active, backwards) {
+        (None, false) => 0,
+        (None, true) => count - 1,
+        (Some(index), false) => (index + 1) % count,
+        (Some(index), true) => (index + count - 1) % count,
+    })
+}
+
+/// Remember what had focus, then focus the dialog's `autofocus` control, its
+/// first control, or the panel itself
+fn focus_first(id: &str) {
+    let eval = document::eval(&format!(
+        r#"
+        const id = await dioxus.recv();
+        const dialog = document.getElementById(id);
+        if (dialog) {{
+            window.__dialogReturnFocus = window.__dialogReturnFocus || {{}};
+            window.__dialogReturnFocus[id] = document.activeElement;
+            const target = dialog.querySelector("[autofocus]")
+                || dialog.querySelector({FOCUSABLE:?})
+                || dialog;
+            target.focus();
+        }}
+        return true;
+        "#
+    ));
+    let _ = eval.send(id);
+}
+
+/// Move focus one control on inside the dialog, wrapping round
+fn cycle_focus(id: &str, backwards: bool) {
+    let id = id.to_string();
+    spawn(async move {
+        let mut eval = document::eval(&format!(
+            r#"
+            const id = await dioxus.recv();
+            const dialog = document.getElementById(id);
+            const controls = dialog
+                ? Array.from(dialog.querySelectorAll({FOCUSABLE:?}))
+                    .filter((el) => !el.disabled && el.offsetParent !== null)
+                : [];
+            const index = controls.indexOf(document.activeElement);
+            dioxus.send([index < 0 ? null : index, controls.length]);
+            const next = await dioxus.recv();
+            if (next !== null) {{
+                controls[next].focus();
+            }}
+            return true;
+            "#
+        ));
+        if eval.send(&id).is_err() {
+            return;
+        }
+        let Ok((active, count)) = eval.recv::<(Option<usize>, usize)>().await else {
+            return;
+        };
+        let _ = eval.send(next_focus_index(active, count, backwards));
+    });
+}
+
+/// Give focus back to whatever had it before the dialog opened
+fn restore_focus(id: &str) {
+    let eval = document::eval(
+        r#"
+        const id = await dioxus.recv();
+        const returns = window.__dialogReturnFocus || {};
+        const element = returns[id];
+        delete returns[id];
+        if (element && element.isConnected) {
+            element.focus();
+        }
+        return true;
+        "#,
+    );
+    let _ = eval.send(id);
+}
+
+const DIALOG_CSS: &str = r#"
+.dialog-backdrop {
+    position: fixed;
+    inset: 0;
+    background: rgba(0, 0, 0, 0.4);
+    display: flex;
+    align-items: center;
+    justify-content: center;
+    z-index: 1100;
+}
+
+.dialog {
+    background: white;
+    padding: 24px;
+    border-radius: 12px;
+    max-width: 480px;
+    box-shadow: 0 4px 20px rgba(0, 0, 0, 0.25);
+    font-size: 14px;
+    color: #333;
+}
+
+.dialog:focus {
+    outline: none;
+}
+
+.dialog-title {
+    margin: 0 0 12px 0;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tab_cycles_through_the_controls_and_wraps() {
+        let forward: Vec<_> = (0..4)
+            .scan(None, |active, _| {
+                *active = next_focus_index(*active, 3, false);
+                *active
+            })
+            .collect();
+        assert_eq!(forward, vec![0, 1, 2, 0]);
+
+        let backward: Vec<_> = (0..4)
+            .scan(None, |active, _| {
+                *active = next_focus_index(*active, 3, true);
+                *active
+            })
+            .collect();
+        assert_eq!(backward, vec![2, 1, 0, 2]);
+    }
+
+    #[test]
+    fn a_single_control_keeps_focus_and_none_leaves_it_on_the_panel() {
+        assert_eq!(next_focus_index(Some(0), 1, false), Some(0));
+        assert_eq!(next_focus_index(Some(0), 1, true), Some(0));
+        assert_eq!(next_focus_index(None, 0, false), None);
+        assert_eq!(next_focus_index(None, 0, true), None);
+    }
+}
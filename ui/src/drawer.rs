@@ -0,0 +1,242 @@
+use dioxus::prelude::*;
+
+use crate::t;
+
+/// Panel that slides in over the page from the left, with a backdrop that
+/// closes it. It stays mounted while closed so it can slide back out.
+#[component]
+pub fn Drawer(
+    open: bool,
+    /// Called on the backdrop, the close button or Escape
+    on_close: EventHandler<()>,
+    /// Heading next to the close button
+    #[props(default)]
+    title: Option<String>,
+    children: Element,
+) -> Element {
+    let class = if open { "drawer open" } else { "drawer" };
+
+    rsx! {
+        div {
+            class,
+            aria_hidden: if open { "false" } else { "true" },
+            div {
+                class: "drawer-backdrop",
+                onclick: move |_| on_close.call(()),
+            }
+            div {
+                class: "drawer-panel",
+                role: "dialog",
+                tabindex: "-1",
+                onkeydown: move |evt| {
+                    if evt.key() == Key::Escape {
+                        on_close.call(());
+                    }
+                },
+                div {
+                    class: "drawer-header",
+                    if let Some(title) = title {
+                        span { class: "drawer-title", "{title}" }
+                    }
+                    button {
+                        class: "drawer-close",
+                        aria_label: t!("nav.close"),
+                        onclick: move |_| on_close.call(()),
+                        "×"
+                    }
+                }
+                {children}
+            }
+        }
+
+        style { {DRAWER_CSS} }
+    }
+}
+
+const DRAWER_CSS: &str = r#"
+.drawer {
+    position: fixed;
+    inset: 0;
+    z-index: 1000;
+    pointer-events: none;
+}
+
+.drawer.open {
+    pointer-events: auto;
+}
+
+.drawer-backdrop {
+    position: absolute;
+    inset: 0;
+    background: rgba(0, 0, 0, 0.4);
+    opacity: 0;
+    transition: opacity 0.2s ease;
+}
+
+.drawer.open .drawer-backdrop {
+    opacity: 1;
+}
+
+.drawer-panel {
+    position: absolute;
+    top: 0;
+    bottom: 0;
+    left: 0;
+    width: min(280px, 85vw);
+    display: flex;
+    flex-direction: column;
+    background: #1a1a1a;
+    color: white;
+    transform: translateX(-100%);
+    transition: transform 0.2s ease;
+    overflow-y: auto;
+    outline: none;
+}
+
+.drawer.open .drawer-panel {
+    transform: translateX(0);
+}
+
+.drawer-header {
+    display: flex;
+    align-items: center;
+    justify-content: space-between;
+    padding: 12px 16px;
+    border-bottom: 1px solid rgba(255, 255, 255, 0.1);
+}
+
+.drawer-title {
+    font-weight: 700;
+}
+
+.drawer-close {
+    width: 44px;
+    height: 44px;
+    margin-left: auto;
+    background: none;
+    border: none;
+    color: white;
+    font-size: 24px;
+    cursor: pointer;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dioxus::dioxus_core::{AttributeValue, Mutation, Mutations};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Dynamic text attributes and text nodes among `mutations`
+    fn written(mutations: &Mutations) -> (Vec<(String, String)>, Vec<String>) {
+        let mut attributes = Vec::new();
+        let mut texts = Vec::new();
+        for edit in &mutations.edits {
+            match edit {
+                Mutation::SetAttribute {
+                    name,
+                    value: AttributeValue::Text(value),
+                    ..
+                } => attributes.push((name.to_string(), value.clone())),
+                Mutation::CreateTextNode { value, .. } => texts.push(value.clone()),
+                _ => {}
+            }
+        }
+        (attributes, texts)
+    }
+
+    fn attribute(attributes: &[(String, String)], name: &str) -> Option<String> {
+        attributes
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.clone())
+    }
+
+    #[derive(Clone, Default)]
+    struct Handle(Rc<RefCell<Option<Signal<bool>>>>);
+
+    #[component]
+
+    fn App() -> Element {
+        let handle = use_context::<Handle>();
+        let open = use_signal(|| false);
+        use_hook(|| handle.0.replace(Some(open)));
+        let link = "Wallet link";
+        rsx! {
+            Drawer {
+                open: open(),
+                on_close: move |_| {},
+                title: "Menu",
+                "{link}"
+            }
+        }
+    }
+
+    #[test]
+    fn a_closed_drawer_stays_mounted_but_hidden() {
+        let mut dom = VirtualDom::new(App).with_root_context(Handle::default());
+        let (attributes, texts) = written(&dom.rebuild_to_vec());
+        assert_eq!(attribute(&attributes, "class").as_deref(), Some("drawer"));
+        assert_eq!(
+            attribute(&attributes, "aria-hidden").as_deref(),
+            Some("true")
+        );
+        assert!(texts.contains(&"Menu".to_string()));
+        assert!(texts.contains(&"Wallet link".to_string()));
+    }
+
+    #[test]
+    fn opening_slides_it_in() {
+        let handle = Handle::default();
+        let mut dom = VirtualDom::new(App).with_root_context(handle.clone());
+        dom.rebuild_in_place();
+        let mut open = handle.0.borrow().unwrap();
+
+        dom.in_runtime(|| open.set(true));
+        let (attributes, _) = written(&dom.render_immediate_to_vec());
+        assert_eq!(
+            attribute(&attributes, "class").as_deref(),
+            Some("drawer open")
+        );
+        assert_eq!(
+            attribute(&attributes, "aria-hidden").as_deref(),
+            Some("false")
+        );
+
+        dom.in_runtime(|| open.set(false));
+        let (attributes, _) = written(&dom.render_immediate_to_vec());
+        assert_eq!(attribute(&attributes, "class").as_deref(), Some("drawer"));
+    }
+
+    #[test]
+    fn backdrop_close_button_and_escape_listen_for_closing() {
+        let mut dom = VirtualDom::new(App).with_root_context(Handle::default());
+        let mutations = dom.rebuild_to_vec();
+        let mut listeners: Vec<&str> = mutations
+            .edits
+            .iter()
+            .filter_map(|edit| match edit {
+                Mutation::NewEventListener { name, .. } => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        listeners.sort();
+        assert_eq!(listeners, ["click", "click", "keydown"]);
+    }
+
+    #[test]
+    fn the_title_is_optional() {
+        #[component]
+        fn Untitled() -> Element {
+            let body = "Body";
+            rsx! {
+                Drawer { open: true, on_close: move |_| {}, "{body}" }
+            }
+        }
+        let mut dom = VirtualDom::new(Untitled);
+        let (_, texts) = written(&dom.rebuild_to_vec());
+        assert_eq!(texts, ["Body", DRAWER_CSS]);
+    }
+}
@@ -1,26 +1,208 @@
+use chrono::{DateTime, Utc};
 use dioxus::prelude::*;
 
+/// Which echo implementation a submission goes through
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum EchoMode {
+    /// `api::echo_string`, called in-process
+    #[default]
+    Local,
+    /// `api::echo_server`, the async round trip
+    Server,
+}
+
+impl EchoMode {
+    fn label(&self) -> &'static str {
+        match self {
+            EchoMode::Local => "local",
+            EchoMode::Server => "server",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct EchoEntry {
+    input: String,
+    response: Result<String, String>,
+    mode: EchoMode,
+    sent_at: DateTime<Utc>,
+    latency_us: i64,
+}
+
+/// Append `entry`, dropping the oldest entries past `limit`
+fn push_capped(history: &mut Vec<EchoEntry>, entry: EchoEntry, limit: usize) {
+    history.push(entry);
+    let excess = history.len().saturating_sub(limit);
+    history.drain(..excess);
+}
+
+/// The text being typed and whether it changed since it was last submitted,
+/// so Enter followed by the change event only submits it once
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Draft {
+    text: String,
+    dirty: bool,
+}
+
+impl Draft {
+    fn edit(&mut self, text: String) {
+        self.text = text;
+        self.dirty = true;
+    }
+
+    /// The text to submit, if it was edited since the last submit
+    fn take(&mut self) -> Option<String> {
+        if !self.dirty {
+            return None;
+        }
+        self.dirty = false;
+        Some(self.text.clone())
+    }
+}
+
 #[component]
-pub fn Echo() -> Element {
-    let mut response = use_signal(String::new);
+pub fn Echo(
+    /// Heading shown above the input
+    #[props(default = "Echo Test".to_string())]
+    title: String,
+    /// Mode selected when the component first renders
+    #[props(default)]
+    initial_mode: EchoMode,
+    /// Oldest entries are dropped once the history is longer than this
+    #[props(default = 20)]
+    history_limit: usize,
+) -> Element {
+    let mut draft = use_signal(Draft::default);
+    let mut mode = use_signal(|| initial_mode);
+    let mut pending = use_signal(|| false);
+    let mut history = use_signal(Vec::<EchoEntry>::new);
+
+    let mut submit = move || {
+        if *pending.peek() || !draft.peek().dirty {
+            return;
+        }
+        let Some(input) = draft.write().take() else {
+            return;
+        };
+        let request = input.clone();
+        let mode = *mode.peek();
+        let sent_at = Utc::now();
+        let mut record = move |response: Result<String, String>| {
+            let latency_us = (Utc::now() - sent_at)
+                .num_microseconds()
+                .unwrap_or(i64::MAX);
+            push_capped(
+                &mut history.write(),
+                EchoEntry {
+                    input: input.clone(),
+                    response,
+                    mode,
+                    sent_at,
+                    latency_us,
+                },
+                history_limit,
+            );
+        };
+
+        match mode {
+            EchoMode::Local => record(Ok(api::echo_string(request))),
+            EchoMode::Server => {
+                pending.set(true);
+                spawn(async move {
+                    record(api::echo_server(request).await);
+                    pending.set(false);
+                });
+            }
+        }
+    };
+
+    let latest = history.read().last().cloned();
 
     rsx! {
         div {
             class: "echo-container",
-            h2 { "Echo Test" }
+            div {
+                class: "echo-header",
+                h2 { "{title}" }
+                div {
+                    class: "echo-modes",
+                    for option in [EchoMode::Local, EchoMode::Server] {
+                        button {
+                            class: if *mode.read() == option { "echo-mode active" } else { "echo-mode" },
+                            disabled: *pending.read(),
+                            onclick: move |_| mode.set(option),
+                            "{option.label()}"
+                        }
+                    }
+                }
+            }
             input {
-                placeholder: "Type something...",
-                oninput: move |event| {
-                    response.set(api::echo_string(event.value()));
+                placeholder: "Type something and press Enter...",
+                value: "{draft.read().text}",
+                oninput: move |event| draft.write().edit(event.value()),
+                onchange: move |_| submit(),
+                onkeydown: move |event| {
+                    if event.key() == Key::Enter {
+                        submit();
+                    }
+                },
+            }
+
+            if *pending.read() {
+                p { class: "echo-pending", "Waiting for server…" }
+            } else if let Some(entry) = latest {
+                match entry.response {
+                    Ok(response) => rsx! {
+                        p { "Response: {response} ({format_latency(entry.latency_us)})" }
+                    },
+                    Err(error) => rsx! {
+                        p { class: "echo-error", "Echo failed: {error}" }
+                    },
+                }
+            }
+
+            div {
+                class: "echo-history-header",
+                span { "History ({history.read().len()}/{history_limit})" }
+                button {
+                    disabled: history.read().is_empty(),
+                    onclick: move |_| history.write().clear(),
+                    "Clear history"
+                }
+            }
+            div {
+                class: "echo-history",
+                for (index, entry) in history.read().iter().enumerate().rev() {
+                    div {
+                        key: "{index}-{entry.sent_at.timestamp_micros()}",
+                        class: "echo-entry",
+                        span { class: "echo-time", "{entry.sent_at.format(SENT_AT_FORMAT)}" }
+                        span { class: "echo-mode-tag", "{entry.mode.label()}" }
+                        span { class: "echo-input", "{entry.input}" }
+                        match &entry.response {
+                            Ok(response) => rsx! { span { class: "echo-response", "→ {response}" } },
+                            Err(error) => rsx! { span { class: "echo-error", "✗ {error}" } },
+                        }
+                        span { class: "echo-latency", "{format_latency(entry.latency_us)}" }
+                    }
                 }
             }
-            p { "Response: {response}" }
         }
 
         style { {ECHO_CSS} }
     }
 }
 
+const SENT_AT_FORMAT: &str = "%H:%M:%S%.3f";
+
+fn format_latency(latency_us: i64) -> String {
+    if latency_us < 1000 {
+        format!("{} µs", latency_us)
+    } else {
+        format!("{:.1} ms", latency_us as f64 / 1000.0)
+    }
+}
+
 const ECHO_CSS: &str = r#"
 .echo-container {
     padding: 20px;
@@ -33,6 +215,31 @@ const ECHO_CSS: &str = r#"
     margin-top: 0;
 }
 
+.echo-header {
+    display: flex;
+    justify-content: space-between;
+    align-items: center;
+}
+
+.echo-modes {
+    display: flex;
+    gap: 4px;
+}
+
+.echo-mode {
+    padding: 4px 10px;
+    border: 1px solid #ddd;
+    border-radius: 4px;
+    background: white;
+    cursor: pointer;
+}
+
+.echo-mode.active {
+    background: #667eea;
+    border-color: #667eea;
+    color: white;
+}
+
 .echo-container input {
     width: 100%;
     padding: 8px;
@@ -40,4 +247,127 @@ const ECHO_CSS: &str = r#"
     border: 1px solid #ddd;
     border-radius: 4px;
 }
+
+.echo-pending {
+    color: #6c757d;
+    font-style: italic;
+}
+
+.echo-error {
+    color: #dc3545;
+}
+
+.echo-history-header {
+    display: flex;
+    justify-content: space-between;
+    align-items: center;
+    font-size: 13px;
+    color: #666;
+    margin-top: 12px;
+}
+
+.echo-history {
+    max-height: 240px;
+    overflow-y: auto;
+    border-top: 1px solid #eee;
+    margin-top: 6px;
+    font-size: 13px;
+}
+
+.echo-entry {
+    display: flex;
+    gap: 10px;
+    padding: 4px 0;
+    border-bottom: 1px solid #f3f3f3;
+}
+
+.echo-time,
+.echo-latency {
+    font-family: monospace;
+    color: #888;
+}
+
+.echo-mode-tag {
+    font-size: 11px;
+    text-transform: uppercase;
+    color: #667eea;
+}
+
+.echo-input {
+    flex: 1;
+    word-break: break-all;
+}
+
+.echo-latency {
+    margin-left: auto;
+}
 "#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::render;
+
+    fn entry(input: &str) -> EchoEntry {
+        EchoEntry {
+            input: input.to_string(),
+            response: Ok(input.to_string()),
+            mode: EchoMode::Local,
+            sent_at: api::testing::fixture_time(0),
+            latency_us: 5,
+        }
+    }
+
+    #[test]
+    fn the_history_keeps_only_the_newest_entries() {
+        let mut history = Vec::new();
+        for input in ["a", "b", "c", "d"] {
+            push_capped(&mut history, entry(input), 3);
+        }
+        let inputs: Vec<&str> = history.iter().map(|entry| entry.input.as_str()).collect();
+        assert_eq!(inputs, vec!["b", "c", "d"]);
+
+        push_capped(&mut history, entry("e"), 1);
+        assert_eq!(history, vec![entry("e")]);
+    }
+
+    #[test]
+    fn an_edit_is_submitted_once() {
+        let mut draft = Draft::default();
+        assert_eq!(draft.take(), None);
+        draft.edit("hello".to_string());
+        // Enter, then the change event as the input loses focus
+        assert_eq!(draft.take(), Some("hello".to_string()));
+        assert_eq!(draft.take(), None);
+        // Typing the same text again is a new submission
+        draft.edit("hello".to_string());
+        assert_eq!(draft.take(), Some("hello".to_string()));
+    }
+
+    #[test]
+    fn latency_is_shown_in_milliseconds_from_one() {
+        assert_eq!(format_latency(999), "999 µs");
+        assert_eq!(format_latency(1_000), "1.0 ms");
+        assert_eq!(format_latency(1_260), "1.3 ms");
+    }
+
+    #[test]
+    fn props_set_the_title_mode_and_history_limit() {
+        let html = render(
+            Echo,
+            EchoProps {
+                title: "Diagnostics echo".to_string(),
+                initial_mode: EchoMode::Server,
+                history_limit: 5,
+            },
+        );
+        assert!(html.contains("<h2>Diagnostics echo</h2>"), "{}", html);
+        assert!(html.contains("History (0/5)"), "{}", html);
+        let active = html.split(r#"class="echo-mode active""#).nth(1).unwrap();
+        assert!(
+            active[..active.find("</button>").unwrap()].ends_with("server"),
+            "{}",
+            html
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use dioxus::prelude::*;
+
+use crate::t;
+
+/// Stands in for content that failed to load: what went wrong and, when the
+/// failed fetch can be repeated, a button to retry it
+#[component]
+pub fn ErrorState(
+    message: String,
+    /// Heading above the message; a generic one if not given
+    #[props(default)]
+    title: Option<String>,
+    #[props(default)] on_retry: Option<EventHandler<()>>,
+) -> Element {
+    let title = title.unwrap_or_else(|| t!("error.title"));
+
+    rsx! {
+        div {
+            class: "error-state",
+            role: "alert",
+            div { class: "error-state-title", "{title}" }
+            div { class: "error-state-message", "{message}" }
+            if let Some(on_retry) = on_retry {
+                button {
+                    class: "error-state-retry",
+                    onclick: move |_| on_retry.call(()),
+                    {t!("error.retry")}
+                }
+            }
+        }
+
+        style { {ERROR_STATE_CSS} }
+    }
+}
+
+const ERROR_STATE_CSS: &str = r#"
+.error-state {
+    display: flex;
+    flex-direction: column;
+    align-items: flex-start;
+    gap: 8px;
+    padding: 20px;
+    margin-bottom: 24px;
+    background: #fff5f5;
+    border: 1px solid #f5c2c7;
+    border-radius: 8px;
+    color: #842029;
+}
+
+.error-state-title {
+    font-weight: 600;
+}
+
+.error-state-message {
+    font-size: 13px;
+    word-break: break-word;
+}
+
+.error-state-retry {
+    min-height: 36px;
+    padding: 6px 14px;
+    border: 1px solid #842029;
+    border-radius: 6px;
+    background: white;
+    color: #842029;
+    cursor: pointer;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{render, render_root};
+
+    #[test]
+    fn a_generic_title_is_used_when_none_is_given() {
+        let html = render(
+            ErrorState,
+            ErrorStateProps {
+                message: "Connection refused".to_string(),
+                title: None,
+                on_retry: None,
+            },
+        );
+        assert!(html.contains(&format!(
+            r#"<div class="error-state-title">{}</div>"#,
+            t!("error.title")
+        )));
+        assert!(html.contains(r#"<div class="error-state-message">Connection refused</div>"#));
+        assert!(!html.contains("<button"));
+    }
+
+    #[test]
+    fn retry_shows_only_with_a_handler() {
+        #[component]
+        fn Retryable() -> Element {
+            rsx! {
+                ErrorState {
+                    title: "Balance unavailable",
+                    message: "Timed out",
+                    on_retry: move |_| {},
+                }
+            }
+        }
+        let html = render_root(Retryable);
+        assert!(html.contains("Balance unavailable"));
+        assert!(html.contains(&t!("error.retry")));
+    }
+}
@@ -0,0 +1,164 @@
+//! Numbers as the UI shows them.
+//!
+//! Heights and counters get the current language's thousands separator,
+//! byte sizes a binary unit with one decimal, and counts too large for their
+//! space a short form such as "1.2M". Components format numbers through
+//! these rather than on their own, so a value reads the same on every page.
+//!
+//! Rounding never shows a unit's full multiple: 1,048,535 bytes is
+//! "1.0 MiB", not "1024.0 KiB".
+
+use crate::i18n::{current_locale, Locale};
+
+const BYTE_UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+const COMPACT_UNITS: [&str; 5] = ["K", "M", "B", "T", "Q"];
+
+/// `value` with the current language's thousands separator, e.g. "1,234,567"
+pub fn format_count(value: u64) -> String {
+    current_locale().format_number(value)
+}
+
+/// `value` in thousands, millions and up with at most one decimal, e.g.
+/// "1.2M" or "12K"; below 1,000 as is
+pub fn format_compact(value: u64) -> String {
+    compact_in(current_locale(), value)
+}
+
+/// Size in the largest binary unit it reaches with one decimal, e.g.
+/// "70.0 MiB"; below 1 KiB in whole bytes
+pub fn format_bytes(bytes: u64) -> String {
+    bytes_in(current_locale(), bytes)
+}
+
+/// Transfer rate as `format_bytes` per second. Negative and non-finite
+/// rates, which only a clock step can produce, show as 0.
+pub fn format_rate(bytes_per_sec: f64) -> String {
+    // `as` saturates negatives to 0
+    let bytes = if bytes_per_sec.is_finite() {
+        bytes_per_sec.round() as u64
+    } else {
+        0
+    };
+    format!("{}/s", format_bytes(bytes))
+}
+
+fn compact_in(locale: Locale, value: u64) -> String {
+    match scale(value, 1000, &COMPACT_UNITS) {
+        Some((tenths, unit)) if tenths % 10 == 0 => format!("{}{}", tenths / 10, unit),
+        Some((tenths, unit)) => format!("{}{}", decimal(locale, tenths), unit),
+        None => value.to_string(),
+    }
+}
+
+fn bytes_in(locale: Locale, bytes: u64) -> String {
+    match scale(bytes, 1024, &BYTE_UNITS) {
+        Some((tenths, unit)) => format!("{} {}", decimal(locale, tenths), unit),
+        None => format!("{} B", bytes),
+    }
+}
+
+/// `value` in tenths of the largest of `units` it reaches, each `base` times
+/// the one before. A value that rounds up to `base` of one unit moves to the
+/// next. `None` below the first unit.
+fn scale(value: u64, base: u64, units: &[&'static str]) -> Option<(u128, &'static str)> {
+    let value = u128::from(value);
+    let base = u128::from(base);
+    let mut size = 1;
+    let mut scaled: Option<(u128, &'static str)> = None;
+    for unit in units {
+        size *= base;
+        let rounds_up = scaled.is_some_and(|(tenths, _)| tenths >= base * 10);
+        if value < size && !rounds_up {
+            break;
+        }
+        scaled = Some(((value * 10 + size / 2) / size, unit));
+    }
+    scaled
+}
+
+/// A count of tenths as a decimal with `locale`'s separator
+fn decimal(locale: Locale, tenths: u128) -> String {
+    format!(
+        "{}{}{}",
+        tenths / 10,
+        locale.decimal_separator(),
+        tenths % 10
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KIB: u64 = 1024;
+    const MIB: u64 = 1024 * KIB;
+    const GIB: u64 = 1024 * MIB;
+
+    #[test]
+    fn bytes_change_unit_at_each_multiple_of_1024() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(KIB - 1), "1023 B");
+        assert_eq!(format_bytes(KIB), "1.0 KiB");
+        assert_eq!(format_bytes(KIB + KIB / 2), "1.5 KiB");
+        assert_eq!(format_bytes(MIB - 1), "1.0 MiB");
+        assert_eq!(format_bytes(MIB), "1.0 MiB");
+        assert_eq!(format_bytes(73_400_320), "70.0 MiB");
+        assert_eq!(format_bytes(GIB - 1), "1.0 GiB");
+        assert_eq!(format_bytes(GIB), "1.0 GiB");
+        assert_eq!(format_bytes(GIB * 1024), "1.0 TiB");
+        assert_eq!(format_bytes(GIB * 1024 * 1024), "1.0 PiB");
+    }
+
+    #[test]
+    fn rounding_never_shows_a_full_multiple_of_a_unit() {
+        // 1,048,535 is 1023.96 KiB
+        assert_eq!(format_bytes(1_048_535), "1.0 MiB");
+        // 1,048,000 is 1023.44 KiB, well short of rounding up
+        assert_eq!(format_bytes(1_048_000), "1023.4 KiB");
+        assert_eq!(format_compact(999_949), "999.9K");
+        assert_eq!(format_compact(999_950), "1M");
+    }
+
+    #[test]
+    fn the_largest_values_stay_in_the_largest_unit() {
+        assert_eq!(format_bytes(u64::MAX), "16384.0 PiB");
+        assert_eq!(format_compact(u64::MAX), "18446.7Q");
+        assert_eq!(format_count(u64::MAX), "18,446,744,073,709,551,615");
+    }
+
+    #[test]
+    fn compact_counts_drop_a_zero_decimal() {
+        assert_eq!(format_compact(0), "0");
+        assert_eq!(format_compact(999), "999");
+        assert_eq!(format_compact(1_000), "1K");
+        assert_eq!(format_compact(1_049), "1K");
+        assert_eq!(format_compact(1_050), "1.1K");
+        assert_eq!(format_compact(12_000), "12K");
+        assert_eq!(format_compact(1_234_567), "1.2M");
+        assert_eq!(format_compact(2_500_000_000), "2.5B");
+    }
+
+    #[test]
+    fn counts_are_grouped_by_thousands() {
+        assert_eq!(format_count(0), "0");
+        assert_eq!(format_count(999), "999");
+        assert_eq!(format_count(1_234_567), "1,234,567");
+    }
+
+    #[test]
+    fn decimals_use_the_language_separator() {
+        assert_eq!(bytes_in(Locale::German, KIB + KIB / 2), "1,5 KiB");
+        assert_eq!(bytes_in(Locale::German, 1_000), "1000 B");
+        assert_eq!(compact_in(Locale::German, 1_500_000), "1,5M");
+        assert_eq!(compact_in(Locale::German, 2_000_000), "2M");
+    }
+
+    #[test]
+    fn impossible_rates_show_as_zero() {
+        assert_eq!(format_rate(1_536.4), "1.5 KiB/s");
+        assert_eq!(format_rate(0.4), "0 B/s");
+        assert_eq!(format_rate(-5.0), "0 B/s");
+        assert_eq!(format_rate(f64::NAN), "0 B/s");
+        assert_eq!(format_rate(f64::INFINITY), "0 B/s");
+    }
+}
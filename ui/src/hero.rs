@@ -1,19 +1,71 @@
+use api::wallet::network::NodeStatus;
 use dioxus::prelude::*;
 
+use crate::format::format_count;
+use crate::t;
+
+/// What the Hero call-to-action asks the user to do next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeroAction {
+    CreateWallet,
+    StartNode,
+    SendFunds,
+}
+
+impl HeroAction {
+    /// Next step for the given wallet and node state
+    pub fn for_state(wallet_created: bool, node_status: Option<&NodeStatus>) -> Self {
+        if !wallet_created {
+            HeroAction::CreateWallet
+        } else if matches!(node_status, Some(NodeStatus::Running)) {
+            HeroAction::SendFunds
+        } else {
+            HeroAction::StartNode
+        }
+    }
+
+    /// Button text in the current language
+    pub fn label(&self) -> String {
+        match self {
+            HeroAction::CreateWallet => t!("hero.action.create_wallet"),
+            HeroAction::StartNode => t!("hero.action.start_node"),
+            HeroAction::SendFunds => t!("hero.action.send_funds"),
+        }
+    }
+}
+
 #[component]
-pub fn Hero() -> Element {
+pub fn Hero(
+    #[props(default)] node_status: Option<NodeStatus>,
+    #[props(default)] block_height: Option<u64>,
+    #[props(default)] peer_count: Option<u32>,
+    #[props(default)] wallet_created: bool,
+    /// Called with the suggested action; without a handler no button is shown
+    #[props(default)]
+    on_action: Option<EventHandler<HeroAction>>,
+) -> Element {
+    let chips = hero_chips(node_status.as_ref(), block_height, peer_count);
+    let action = HeroAction::for_state(wallet_created, node_status.as_ref());
+
     rsx! {
         div {
             class: "hero",
             div {
                 class: "hero-content",
-                h1 { "🦄 Nockchain Wallet" }
-                p { "A secure, self-sovereign wallet with built-in full node support" }
+                h1 { {t!("hero.title")} }
+                p { {t!("hero.tagline")} }
                 div {
                     class: "hero-features",
-                    div { class: "feature", "🔐 Secure Key Management" }
-                    div { class: "feature", "⚡ Built-in Full Node" }
-                    div { class: "feature", "🌐 Cross-Platform" }
+                    for chip in chips {
+                        div { class: "feature", "{chip}" }
+                    }
+                }
+                if let Some(on_action) = on_action {
+                    button {
+                        class: "hero-cta",
+                        onclick: move |_| on_action.call(action),
+                        "{action.label()}"
+                    }
                 }
             }
         }
@@ -22,6 +74,45 @@ pub fn Hero() -> Element {
     }
 }
 
+/// One chip per known live value, or the static feature chips if none is known
+fn hero_chips(
+    node_status: Option<&NodeStatus>,
+    block_height: Option<u64>,
+    peer_count: Option<u32>,
+) -> Vec<String> {
+    let mut chips = Vec::new();
+    if let Some(status) = node_status {
+        chips.push(t!("hero.chip.node", status = status_label(status)));
+    }
+    if let Some(height) = block_height {
+        chips.push(t!("hero.chip.height", height = format_count(height)));
+    }
+    if let Some(peers) = peer_count {
+        chips.push(t!("hero.chip.peers", count = format_count(peers.into())));
+    }
+
+    if chips.is_empty() {
+        // Shown when no live node data is available
+        vec![
+            t!("hero.feature.keys"),
+            t!("hero.feature.node"),
+            t!("hero.feature.platform"),
+        ]
+    } else {
+        chips
+    }
+}
+
+fn status_label(status: &NodeStatus) -> String {
+    match status {
+        NodeStatus::Stopped => t!("node.status.stopped"),
+        NodeStatus::Starting => t!("node.status.starting"),
+        NodeStatus::Running => t!("node.status.running"),
+        NodeStatus::Stopping => t!("node.status.stopping"),
+        NodeStatus::Error(_) => t!("node.status.error"),
+    }
+}
+
 const HERO_CSS: &str = r#"
 .hero {
     background: linear-gradient(135deg, #667eea 0%, #764ba2 100%);
@@ -59,6 +150,23 @@ const HERO_CSS: &str = r#"
     font-weight: 500;
 }
 
+.hero-cta {
+    margin-top: 30px;
+    padding: 14px 32px;
+    font-size: 1.1rem;
+    font-weight: 600;
+    color: #764ba2;
+    background: white;
+    border: none;
+    border-radius: 8px;
+    cursor: pointer;
+    box-shadow: 0 4px 14px rgba(0, 0, 0, 0.15);
+}
+
+.hero-cta:hover {
+    transform: translateY(-1px);
+}
+
 @media (max-width: 768px) {
     .hero-content h1 {
         font-size: 2rem;
@@ -70,3 +178,52 @@ const HERO_CSS: &str = r#"
     }
 }
 "#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn without_live_data_the_static_features_are_shown() {
+        assert_eq!(
+            hero_chips(None, None, None),
+            vec![
+                "🔐 Secure Key Management",
+                "⚡ Built-in Full Node",
+                "🌐 Cross-Platform"
+            ]
+        );
+    }
+
+    #[test]
+    fn each_known_value_gets_a_chip() {
+        assert_eq!(
+            hero_chips(Some(&NodeStatus::Running), Some(12_345), Some(8)),
+            vec!["Node: Running", "Height: 12,345", "Peers: 8"]
+        );
+        // A partial picture shows only what is known, never the static copy
+        assert_eq!(hero_chips(None, None, Some(0)), vec!["Peers: 0"]);
+        assert_eq!(
+            hero_chips(Some(&NodeStatus::Error("boom".to_string())), None, None),
+            vec!["Node: Error"]
+        );
+    }
+
+    #[test]
+    fn the_action_follows_wallet_then_node_state() {
+        assert_eq!(
+            HeroAction::for_state(false, Some(&NodeStatus::Running)),
+            HeroAction::CreateWallet
+        );
+        assert_eq!(HeroAction::for_state(true, None), HeroAction::StartNode);
+        assert_eq!(
+            HeroAction::for_state(true, Some(&NodeStatus::Starting)),
+            HeroAction::StartNode
+        );
+        assert_eq!(
+            HeroAction::for_state(true, Some(&NodeStatus::Running)),
+            HeroAction::SendFunds
+        );
+        assert_eq!(HeroAction::SendFunds.label(), "Send funds");
+    }
+}
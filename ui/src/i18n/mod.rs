@@ -0,0 +1,404 @@
+//! Translated UI strings.
+//!
+//! Each language is a JSON bundle embedded in the binary. Keys are dotted
+//! paths into the bundle (`"balance.title"`); a value is either a string or,
+//! for messages that depend on a count, an object with `"one"` and `"other"`
+//! forms picked by the `count` argument. `{name}` in a message is replaced by
+//! the argument of that name.
+//!
+//! The app provides the current language as a `Signal<Locale>` context;
+//! `t!` reads it, so a component re-renders when the language changes.
+//! Without the context everything is English. `build.rs` checks that every
+//! key passed to `t!` is in every bundle. Relative times are shown against
+//! `display_now`, which redraws them when the system clock jumps.
+
+use chrono::{DateTime, Local, Utc};
+use dioxus::prelude::*;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Translate a key in the current language, with `name = value` arguments
+///
+/// ```ignore
+/// t!("balance.title")
+/// t!("console.showing", shown = 10, total = 200)
+/// ```
+#[macro_export]
+macro_rules! t {
+    ($key:literal) => {
+        $crate::i18n::current_locale().translate($key, &[])
+    };
+    ($key:literal, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::current_locale()
+            .translate($key, &[$((stringify!($name), $value.to_string())),+])
+    };
+}
+
+/// Language the UI is shown in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Locale {
+    #[default]
+    English,
+    German,
+}
+
+type Bundle = HashMap<String, Message>;
+
+enum Message {
+    Text(String),
+    Plural { one: String, other: String },
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::English, Locale::German];
+
+    /// ISO 639-1 code, as saved in the UI prefs
+    pub fn code(&self) -> &'static str {
+        match self {
+            Locale::English => "en",
+            Locale::German => "de",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|locale| locale.code().eq_ignore_ascii_case(code))
+    }
+
+    /// Name of the language in that language, for the picker
+    pub fn native_name(&self) -> &'static str {
+        match self {
+            Locale::English => "English",
+            Locale::German => "Deutsch",
+        }
+    }
+
+    fn bundle(&self) -> &'static Bundle {
+        static ENGLISH: OnceLock<Bundle> = OnceLock::new();
+        static GERMAN: OnceLock<Bundle> = OnceLock::new();
+        match self {
+            Locale::English => ENGLISH.get_or_init(|| load_bundle(include_str!("en.json"))),
+            Locale::German => GERMAN.get_or_init(|| load_bundle(include_str!("de.json"))),
+        }
+    }
+
+    /// Message `key` with `args` filled in. Falls back to English, then to
+    /// the key itself.
+    pub fn translate(&self, key: &str, args: &[(&str, String)]) -> String {
+        let message = self
+            .bundle()
+            .get(key)
+            .or_else(|| Locale::English.bundle().get(key));
+        let template = match message {
+            Some(Message::Text(text)) => text,
+            Some(Message::Plural { one, other }) => {
+                let count = args
+                    .iter()
+                    .find(|(name, _)| *name == "count")
+                    .and_then(|(_, value)| value.parse::<u64>().ok());
+                if count == Some(1) {
+                    one
+                } else {
+                    other
+                }
+            }
+            None => return key.to_string(),
+        };
+        args.iter().fold(template.clone(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), value)
+        })
+    }
+
+    /// `value` with this language's thousands separator
+    pub fn format_number(&self, value: u64) -> String {
+        let separator = match self {
+            Locale::English => ',',
+            Locale::German => '.',
+        };
+        let digits = value.to_string();
+        let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                grouped.push(separator);
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+
+    /// Separator between the whole and fractional part of a decimal
+    pub fn decimal_separator(&self) -> char {
+        match self {
+            Locale::English => '.',
+            Locale::German => ',',
+        }
+    }
+
+    /// Date and time in local time, in this language's usual order
+    pub fn format_datetime(&self, at: &DateTime<Utc>) -> String {
+        let format = match self {
+            Locale::English => "%Y-%m-%d %H:%M",
+            Locale::German => "%d.%m.%Y %H:%M",
+        };
+        at.with_timezone(&Local).format(format).to_string()
+    }
+
+    /// How long before `now` the moment `at` was, in the largest whole unit,
+    /// e.g. "5 minutes ago"
+    pub fn format_age(&self, at: &DateTime<Utc>, now: &DateTime<Utc>) -> String {
+        let secs = (*now - *at).num_seconds().max(0) as u64;
+        let (key, count) = match secs {
+            0..60 => return self.translate("time.just_now", &[]),
+            60..3600 => ("time.minutes_ago", secs / 60),
+            3600..86400 => ("time.hours_ago", secs / 3600),
+            _ => ("time.days_ago", secs / 86400),
+        };
+        self.translate(key, &[("count", count.to_string())])
+    }
+
+    /// Time of day with seconds; the same in every supported language
+    pub fn format_time(&self, at: &DateTime<Utc>) -> String {
+        at.format("%H:%M:%S").to_string()
+    }
+}
+
+/// Count of system clock jumps, provided by the app as a `Signal<ClockEpoch>`
+/// context and bumped on each one
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClockEpoch(pub u64);
+
+/// The time relative times are shown against. Reading it subscribes the
+/// component to the `Signal<ClockEpoch>` context, so after the clock jumps
+/// "5 minutes ago" is worked out again from the new time.
+pub fn display_now() -> DateTime<Utc> {
+    if let Some(epoch) = try_consume_context::<Signal<ClockEpoch>>() {
+        epoch.read();
+    }
+    Utc::now()
+}
+
+/// Language from the `Signal<Locale>` context, English if there is none
+pub fn current_locale() -> Locale {
+    try_consume_context::<Signal<Locale>>()
+        .map(|locale| locale())
+        .unwrap_or_default()
+}
+
+fn load_bundle(json: &str) -> Bundle {
+    let mut bundle = Bundle::new();
+    // build.rs has already rejected a bundle that doesn't parse
+    if let Ok(root) = serde_json::from_str::<Value>(json) {
+        flatten("", &root, &mut bundle);
+    }
+    bundle
+}
+
+fn flatten(prefix: &str, value: &Value, bundle: &mut Bundle) {
+    match value {
+        Value::String(text) => {
+            bundle.insert(prefix.to_string(), Message::Text(text.clone()));
+        }
+        Value::Object(map) if map.contains_key("other") => {
+            let form = |name: &str| map.get(name).and_then(Value::as_str).unwrap_or_default();
+            bundle.insert(
+                prefix.to_string(),
+                Message::Plural {
+                    one: form("one").to_string(),
+                    other: form("other").to_string(),
+                },
+            );
+        }
+        Value::Object(map) => {
+            for (name, child) in map {
+                let key = if prefix.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}.{}", prefix, name)
+                };
+                flatten(&key, child, bundle);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::cell::RefCell;
+    use std::collections::BTreeSet;
+    use std::rc::Rc;
+
+    fn placeholders(text: &str) -> BTreeSet<&str> {
+        text.split('{')
+            .skip(1)
+            .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+            .collect()
+    }
+
+    fn forms(message: &Message) -> Vec<&str> {
+        match message {
+            Message::Text(text) => vec![text],
+            Message::Plural { one, other } => vec![one, other],
+        }
+    }
+
+    #[test]
+    fn every_bundle_has_the_same_keys_and_parameters() {
+        let english = Locale::English.bundle();
+        assert!(english.len() > 100);
+        for locale in Locale::ALL {
+            let bundle = locale.bundle();
+            let keys: BTreeSet<_> = bundle.keys().collect();
+            assert_eq!(keys, english.keys().collect(), "{:?}", locale);
+            for (key, message) in bundle {
+                let expected = &english[key];
+                assert_eq!(
+                    std::mem::discriminant(message),
+                    std::mem::discriminant(expected),
+                    "{:?} {}",
+                    locale,
+                    key
+                );
+                for (form, english_form) in forms(message).into_iter().zip(forms(expected)) {
+                    assert!(!form.is_empty(), "{:?} {} is empty", locale, key);
+                    assert_eq!(
+                        placeholders(form),
+                        placeholders(english_form),
+                        "{:?} {}",
+                        locale,
+                        key
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parameters_are_filled_in() {
+        let args = [("shown", "10".to_string()), ("total", "200".to_string())];
+        assert_eq!(
+            Locale::English.translate("console.showing", &args),
+            "Showing 10 of 200 lines"
+        );
+        assert_eq!(
+            Locale::German.translate("console.showing", &args),
+            "10 von 200 Zeilen"
+        );
+    }
+
+    #[test]
+    fn plurals_follow_the_count() {
+        let count = |n: &str| [("count", n.to_string())];
+        let age = |locale: Locale, n: &str| locale.translate("time.minutes_ago", &count(n));
+        assert_eq!(age(Locale::German, "1"), "vor 1 Minute");
+        assert_eq!(age(Locale::German, "0"), "vor 0 Minuten");
+        assert_eq!(age(Locale::German, "2"), "vor 2 Minuten");
+        assert_eq!(age(Locale::English, "1"), "1 minute ago");
+        assert_eq!(age(Locale::English, "21"), "21 minutes ago");
+        // Without a count the "other" form is used
+        assert_eq!(
+            Locale::English.translate("time.minutes_ago", &[]),
+            "{count} minutes ago"
+        );
+    }
+
+    #[test]
+    fn unknown_keys_come_back_as_the_key() {
+        assert_eq!(Locale::German.translate("no.such.key", &[]), "no.such.key");
+        assert_eq!(Locale::English.translate("nav", &[]), "nav");
+    }
+
+    #[test]
+    fn codes_round_trip() {
+        for locale in Locale::ALL {
+            assert_eq!(Locale::from_code(locale.code()), Some(locale));
+        }
+        assert_eq!(Locale::from_code("DE"), Some(Locale::German));
+        assert_eq!(Locale::from_code("fr"), None);
+        assert_eq!(Locale::German.native_name(), "Deutsch");
+    }
+
+    #[test]
+    fn numbers_are_grouped_the_way_the_language_does() {
+        assert_eq!(Locale::English.format_number(0), "0");
+        assert_eq!(Locale::English.format_number(999), "999");
+        assert_eq!(Locale::English.format_number(1_000), "1,000");
+        assert_eq!(Locale::English.format_number(12_345_678), "12,345,678");
+        assert_eq!(Locale::German.format_number(12_345_678), "12.345.678");
+        assert_eq!(
+            Locale::German.format_number(u64::MAX),
+            "18.446.744.073.709.551.615"
+        );
+        assert_eq!(Locale::English.decimal_separator(), '.');
+        assert_eq!(Locale::German.decimal_separator(), ',');
+    }
+
+    #[test]
+    fn dates_use_the_language_order() {
+        let at = Utc.with_ymd_and_hms(2024, 3, 9, 12, 0, 0).unwrap();
+        let local = at.with_timezone(&Local);
+        assert_eq!(
+            Locale::English.format_datetime(&at),
+            local.format("%Y-%m-%d %H:%M").to_string()
+        );
+        assert_eq!(
+            Locale::German.format_datetime(&at),
+            local.format("%d.%m.%Y %H:%M").to_string()
+        );
+        assert_eq!(Locale::German.format_time(&at), "12:00:00");
+    }
+
+    #[test]
+    fn ages_use_the_largest_whole_unit() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 9, 12, 0, 0).unwrap();
+        let ago = |secs: i64| now - chrono::Duration::seconds(secs);
+        let english = |secs| Locale::English.format_age(&ago(secs), &now);
+        assert_eq!(english(59), Locale::English.translate("time.just_now", &[]));
+        assert_eq!(english(60), "1 minute ago");
+        assert_eq!(english(3599), "59 minutes ago");
+        assert_eq!(english(7200), "2 hours ago");
+        assert_eq!(english(86400 * 3 + 5), "3 days ago");
+        // A time in the future counts as just now
+        assert_eq!(english(-30), english(0));
+        assert_eq!(Locale::German.format_age(&ago(86400), &now), "vor 1 Tag");
+    }
+
+    /// Where `Title` leaves the text it rendered
+    #[derive(Clone, Default)]
+    struct Rendered(Rc<RefCell<String>>);
+
+    #[component]
+    fn Title() -> Element {
+        let rendered = use_context::<Rendered>();
+        let text = crate::t!("console.showing", shown = 1, total = 2);
+        rendered.0.replace(text.clone());
+        rsx! { "{text}" }
+    }
+
+    fn render(root: fn() -> Element) -> String {
+        let rendered = Rendered::default();
+        let mut dom = VirtualDom::new(root).with_root_context(rendered.clone());
+        dom.rebuild_in_place();
+        let text = rendered.0.borrow().clone();
+        text
+    }
+
+    #[test]
+    fn t_reads_the_locale_context() {
+        #[component]
+        fn English() -> Element {
+            rsx! { Title {} }
+        }
+        #[component]
+        fn German() -> Element {
+            use_context_provider(|| Signal::new(Locale::German));
+            rsx! { Title {} }
+        }
+        assert_eq!(render(English), "Showing 1 of 2 lines");
+        assert_eq!(render(German), "1 von 2 Zeilen");
+    }
+}
@@ -1,14 +1,46 @@
 //! This crate contains all shared UI for the workspace.
 
+pub mod clipboard;
+pub mod dialog;
+pub mod drawer;
 pub mod echo;
+pub mod error_state;
+pub mod format;
 pub mod hero;
+pub mod i18n;
+pub mod load_state;
 pub mod navbar;
+pub mod print;
+pub mod scroll;
+pub mod skeleton;
+pub mod swipe_row;
+#[cfg(test)]
+mod testing;
 pub mod wallet;
 
 // Re-export commonly used components
-pub use echo::Echo;
-pub use hero::Hero;
+pub use dialog::Dialog;
+pub use drawer::Drawer;
+pub use echo::{Echo, EchoMode};
+pub use error_state::ErrorState;
+pub use hero::{Hero, HeroAction};
+pub use i18n::{ClockEpoch, Locale};
+pub use load_state::LoadState;
 pub use navbar::Navbar;
+pub use skeleton::Skeleton;
+pub use swipe_row::SwipeRow;
 
 // Re-export wallet components
-pub use wallet::{BalanceCard, NodeConsole, QuickActions, ReceiveView, SendForm, TransactionList};
+pub use wallet::{
+    AboutPanel, AddressBreakdown, AddressInput, AmountInput, AuditLogViewer, BalanceCard,
+    BalanceCardSkeleton, BlockDetail, BlockList, ChainTransactionDetail, ColdSigningPanel,
+    ConsoleState, CopyButton, DangerZone, DataDirUsageBar, DisplaySettingsForm, DraftBanner,
+    DuePaymentsCard, ExportPanel, FeeSelector, IntegrityPanel, KeyImportResults, LanguagePicker,
+    LogLevelGrid, MaintenancePanel, MiningPanel, MnemonicRestoreForm, NodeConsole,
+    NodeStatsSkeleton, NodeStatsStrip, NoteList, OverridesChip, PasteAddressButton,
+    PaymentRequestValues, PeerTable, PreflightScreen, ProfilePicker, QuickActions, ReadOnlyBanner,
+    ReauthDialog, ReceiptView, ReceiveView, RecoveryDialog, SearchBar, SecretKeyImportForm,
+    SendForm, SendFormValues, SendPrefill, SimulatedNetworkBanner, SpendablePanel,
+    SpendingLimitsForm, StoragePanel, TemplateDetails, TemplatePicker, TransactionDecoder,
+    TransactionDetail, TransactionList, TransactionListSkeleton, UptimeCalendar, WebhookSettings,
+};
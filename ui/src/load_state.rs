@@ -0,0 +1,54 @@
+use std::fmt::Display;
+
+/// Data a component shows once an async fetch completes
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum LoadState<T> {
+    /// No result yet; components show a skeleton
+    #[default]
+    Loading,
+    Ready(T),
+    /// The fetch failed; components show the message with a retry button
+    Error(String),
+}
+
+impl<T> LoadState<T> {
+    pub fn ready(&self) -> Option<&T> {
+        match self {
+            LoadState::Ready(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl<T, E: Display> From<Result<T, E>> for LoadState<T> {
+    fn from(result: Result<T, E>) -> Self {
+        match result {
+            Ok(value) => LoadState::Ready(value),
+            Err(e) => LoadState::Error(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn results_become_ready_or_error() {
+        let ok: Result<u32, String> = Ok(7);
+        assert_eq!(LoadState::from(ok), LoadState::Ready(7));
+        let err: Result<u32, String> = Err("disk full".to_string());
+        assert_eq!(
+            LoadState::from(err),
+            LoadState::Error("disk full".to_string())
+        );
+    }
+
+    #[test]
+    fn only_a_ready_state_has_a_value() {
+        assert_eq!(LoadState::Ready(3).ready(), Some(&3));
+        assert_eq!(LoadState::<u32>::Loading.ready(), None);
+        assert_eq!(LoadState::<u32>::Error("x".to_string()).ready(), None);
+        assert_eq!(LoadState::<u32>::default(), LoadState::Loading);
+    }
+}
@@ -1,19 +1,71 @@
+use api::{HealthReport, HealthStatus};
 use dioxus::prelude::*;
 
+use crate::drawer::Drawer;
+use crate::t;
+
 #[component]
-pub fn Navbar() -> Element {
+pub fn Navbar(
+    /// Latest health check; the indicator is hidden until the first one completes
+    #[props(default)]
+    health: Option<HealthReport>,
+    /// Extra content between the brand and the links, e.g. a search field
+    children: Element,
+) -> Element {
+    let indicator = health.as_ref().map(|report| {
+        let class = match report.overall {
+            HealthStatus::Ok => "health-dot health-ok",
+            HealthStatus::Degraded => "health-dot health-degraded",
+            HealthStatus::Unhealthy => "health-dot health-unhealthy",
+        };
+        let problems = report.problems();
+        let tooltip = if problems.is_empty() {
+            t!("nav.healthy")
+        } else {
+            problems.join("\n")
+        };
+        (class, tooltip)
+    });
+    // Narrow screens show the links in a drawer instead of the bar
+    let mut menu_open = use_signal(|| false);
+
     rsx! {
         nav {
             class: "navbar",
+            button {
+                class: "nav-menu-button",
+                title: t!("nav.menu"),
+                aria_label: t!("nav.menu"),
+                aria_expanded: "{menu_open}",
+                onclick: move |_| menu_open.set(true),
+                "☰"
+            }
             div {
                 class: "nav-brand",
                 Link { to: "/", "🦄 Nockchain" }
             }
+            {children}
             div {
                 class: "nav-links",
-                Link { to: "/", class: "nav-link", "Wallet" }
-                Link { to: "/node", class: "nav-link", "Node" }
-                a { href: "#settings", class: "nav-link", "Settings" }
+                NavLinks {}
+                if let Some((class, tooltip)) = &indicator {
+                    span { class: "{class}", title: "{tooltip}" }
+                }
+            }
+            if let Some((class, tooltip)) = indicator {
+                span { class: "{class} nav-health-compact", title: "{tooltip}" }
+            }
+        }
+
+        Drawer {
+            open: menu_open(),
+            on_close: move |_| menu_open.set(false),
+            title: "🦄 Nockchain".to_string(),
+            div {
+                class: "nav-drawer-links",
+                // Following a link closes the drawer
+                onclick: move |_| menu_open.set(false),
+                NavLinks {}
             }
         }
 
@@ -21,7 +73,26 @@ pub fn Navbar() -> Element {
     }
 }
 
+#[component]
+fn NavLinks() -> Element {
+    rsx! {
+        Link { to: "/", class: "nav-link", {t!("nav.wallet")} }
+        Link { to: "/send", class: "nav-link", {t!("nav.send")} }
+        Link { to: "/keys", class: "nav-link", {t!("nav.keys")} }
+        Link { to: "/notes", class: "nav-link", {t!("nav.notes")} }
+        Link { to: "/node", class: "nav-link", {t!("nav.node")} }
+        Link { to: "/explorer", class: "nav-link", {t!("nav.explorer")} }
+        Link { to: "/settings", class: "nav-link", {t!("nav.settings")} }
+    }
+}
+
 const NAVBAR_CSS: &str = r#"
+/* The navbar is on every page, so the focus ring for keyboard users lives here */
+:focus-visible {
+    outline: 2px solid #667eea;
+    outline-offset: 2px;
+}
+
 .navbar {
     display: flex;
     justify-content: space-between;
@@ -60,6 +131,54 @@ const NAVBAR_CSS: &str = r#"
     background: rgba(255, 255, 255, 0.1);
 }
 
+.nav-links {
+    align-items: center;
+}
+
+.health-dot {
+    width: 10px;
+    height: 10px;
+    border-radius: 50%;
+    cursor: help;
+}
+
+.health-ok {
+    background: #28a745;
+}
+
+.health-degraded {
+    background: #ffc107;
+}
+
+.health-unhealthy {
+    background: #dc3545;
+}
+
+.nav-menu-button,
+.nav-health-compact {
+    display: none;
+}
+
+.nav-menu-button {
+    width: 44px;
+    height: 44px;
+    background: none;
+    border: none;
+    color: white;
+    font-size: 22px;
+    cursor: pointer;
+}
+
+.nav-drawer-links {
+    display: flex;
+    flex-direction: column;
+    padding: 8px;
+}
+
+.nav-drawer-links .nav-link {
+    padding: 14px 16px;
+}
+
 @media (max-width: 768px) {
     .navbar {
         flex-direction: column;
@@ -70,4 +189,26 @@ const NAVBAR_CSS: &str = r#"
         gap: 15px;
     }
 }
+
+@media (max-width: 480px) {
+    .navbar {
+        flex-direction: row;
+        gap: 8px;
+        padding: 8px 12px;
+    }
+
+    .nav-brand {
+        flex: 1;
+        font-size: 1.2rem;
+    }
+
+    .nav-links {
+        display: none;
+    }
+
+    .nav-menu-button,
+    .nav-health-compact {
+        display: block;
+    }
+}
 "#;
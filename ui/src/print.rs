@@ -0,0 +1,28 @@
+//! Printing a standalone HTML page, such as an address backup sheet, from
+//! the webview without leaving the current view.
+
+use dioxus::prelude::*;
+
+/// Open the print dialog for `html`, laid out in a hidden frame so the app's
+/// own page isn't what gets printed
+pub fn print_html(html: &str) {
+    let eval = document::eval(
+        r#"
+        const html = await dioxus.recv();
+        const frame = document.createElement("iframe");
+        frame.style.position = "fixed";
+        frame.style.width = "0";
+        frame.style.height = "0";
+        frame.style.border = "0";
+        frame.onload = () => {
+            frame.contentWindow.focus();
+            frame.contentWindow.print();
+            setTimeout(() => frame.remove(), 1000);
+        };
+        frame.srcdoc = html;
+        document.body.appendChild(frame);
+        return true;
+        "#,
+    );
+    let _ = eval.send(html);
+}
@@ -0,0 +1,52 @@
+//! Scroll position of elements by id, read and set in the webview so a
+//! view can be put back where the user left it.
+
+use dioxus::prelude::*;
+
+/// Vertical scroll offset of the element `id`, 0 if there is no such element
+pub async fn scroll_top(id: &str) -> f64 {
+    let eval = document::eval(
+        r#"
+        const id = await dioxus.recv();
+        const element = document.getElementById(id);
+        return element ? element.scrollTop : 0;
+        "#,
+    );
+    if eval.send(id).is_err() {
+        return 0.0;
+    }
+    eval.join::<f64>().await.unwrap_or(0.0)
+}
+
+/// Scroll the element `id` to `top`, or to its end if `None`
+pub fn scroll_to(id: &str, top: Option<f64>) {
+    let eval = document::eval(
+        r#"
+        const [id, top] = await dioxus.recv();
+        const element = document.getElementById(id);
+        if (element) {
+            element.scrollTop = top === null ? element.scrollHeight : top;
+        }
+        return true;
+        "#,
+    );
+    let _ = eval.send((id, top));
+}
+
+/// Scroll the element `id` so its descendant `target` sits a third of the way
+/// down it, measured from where `target` is laid out right now
+pub fn scroll_into_view(id: &str, target: &str) {
+    let eval = document::eval(
+        r#"
+        const [id, target] = await dioxus.recv();
+        const element = document.getElementById(id);
+        const line = document.getElementById(target);
+        if (element && line) {
+            const offset = line.getBoundingClientRect().top - element.getBoundingClientRect().top;
+            element.scrollTop += offset - element.clientHeight / 3;
+        }
+        return true;
+        "#,
+    );
+    let _ = eval.send((id, target));
+}
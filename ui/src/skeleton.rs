@@ -0,0 +1,46 @@
+use dioxus::prelude::*;
+
+/// Shimmering placeholder bar standing in for content that is still loading
+#[component]
+pub fn Skeleton(
+    /// Any CSS width, e.g. "60%" or "120px"
+    #[props(default = "100%".to_string())]
+    width: String,
+    #[props(default = "14px".to_string())] height: String,
+) -> Element {
+    rsx! {
+        span {
+            class: "skeleton",
+            style: "width: {width}; height: {height};",
+            aria_hidden: "true",
+        }
+
+        style { {SKELETON_CSS} }
+    }
+}
+
+const SKELETON_CSS: &str = r#"
+.skeleton {
+    display: inline-block;
+    border-radius: 4px;
+    background: linear-gradient(
+        90deg,
+        rgba(128, 128, 128, 0.15) 25%,
+        rgba(128, 128, 128, 0.3) 50%,
+        rgba(128, 128, 128, 0.15) 75%
+    );
+    background-size: 200% 100%;
+    animation: skeleton-shimmer 1.4s ease-in-out infinite;
+}
+
+@keyframes skeleton-shimmer {
+    0% { background-position: 200% 0; }
+    100% { background-position: -200% 0; }
+}
+
+@media (prefers-reduced-motion: reduce) {
+    .skeleton {
+        animation: none;
+    }
+}
+"#;
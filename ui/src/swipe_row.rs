@@ -0,0 +1,217 @@
+use dioxus::prelude::*;
+
+/// Movement in pixels before a touch is taken as a swipe or a scroll
+const SWIPE_SLOP: f64 = 10.0;
+
+/// A touch in progress on a row
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Drag {
+    start_x: f64,
+    start_y: f64,
+    /// Offset of the row when the touch began
+    base: f64,
+    /// Decided once the touch has moved past `SWIPE_SLOP`: true for a
+    /// swipe, false for a vertical scroll, which the row leaves alone
+    horizontal: Option<bool>,
+}
+
+impl Drag {
+    fn new(x: f64, y: f64, base: f64) -> Self {
+        Drag {
+            start_x: x,
+            start_y: y,
+            base,
+            horizontal: None,
+        }
+    }
+
+    /// Follows the finger to `(x, y)`: the row's offset, kept between
+    /// `-width` and 0, if this touch is a swipe; `None` while it is still
+    /// undecided or once it turned out to be a scroll
+    fn follow(&mut self, x: f64, y: f64, width: f64) -> Option<f64> {
+        let (dx, dy) = (x - self.start_x, y - self.start_y);
+        if self.horizontal.is_none() && dx.abs().max(dy.abs()) > SWIPE_SLOP {
+            self.horizontal = Some(dx.abs() > dy.abs());
+        }
+        (self.horizontal == Some(true)).then(|| (self.base + dx).clamp(-width, 0.0))
+    }
+}
+
+/// Row that slides left under a finger to uncover `actions` behind it, and
+/// snaps open or shut when let go. Tapping an action shuts it again. Mouse
+/// input is left to the row's content.
+#[component]
+pub fn SwipeRow(
+    /// Buttons uncovered by the swipe, right-aligned behind the row
+    actions: Element,
+    /// How far in pixels the row slides open
+    #[props(default = 128.0)]
+    actions_width: f64,
+    children: Element,
+) -> Element {
+    let mut offset = use_signal(|| 0.0_f64);
+    let mut drag = use_signal(|| None::<Drag>);
+
+    let dragging = drag.read().is_some_and(|d| d.horizontal == Some(true));
+    let class = if dragging {
+        "swipe-row-content dragging"
+    } else {
+        "swipe-row-content"
+    };
+    // Hidden while shut so they don't show through a transparent row
+    let actions_visibility = if dragging || offset() < 0.0 {
+        "visible"
+    } else {
+        "hidden"
+    };
+
+    rsx! {
+        div {
+            class: "swipe-row",
+            div {
+                class: "swipe-row-actions",
+                style: "width: {actions_width}px; visibility: {actions_visibility};",
+                onclick: move |_| offset.set(0.0),
+                {actions}
+            }
+            div {
+                class,
+                style: "transform: translateX({offset}px);",
+                ontouchstart: move |evt| {
+                    if let Some((x, y)) = first_touch(&evt) {
+                        drag.set(Some(Drag::new(x, y, offset())));
+                    }
+                },
+                ontouchmove: move |evt| {
+                    let (Some(before), Some((x, y))) = (drag(), first_touch(&evt)) else {
+                        return;
+                    };
+                    let mut current = before;
+                    let moved_to = current.follow(x, y, actions_width);
+                    if current != before {
+                        drag.set(Some(current));
+                    }
+                    if let Some(moved_to) = moved_to {
+                        offset.set(moved_to);
+                    }
+                },
+                ontouchend: move |_| {
+                    offset.set(settle(offset(), actions_width));
+                    drag.set(None);
+                },
+                ontouchcancel: move |_| {
+                    if let Some(current) = drag() {
+                        offset.set(current.base);
+                    }
+                    drag.set(None);
+                },
+                {children}
+            }
+        }
+
+        style { {SWIPE_ROW_CSS} }
+    }
+}
+
+/// Viewport coordinates of the first finger on the screen
+fn first_touch(evt: &TouchEvent) -> Option<(f64, f64)> {
+    let at = evt.touches().first()?.client_coordinates();
+    Some((at.x, at.y))
+}
+
+/// Where a row let go at `offset` comes to rest: open if dragged past half
+/// of `width`, shut otherwise
+fn settle(offset: f64, width: f64) -> f64 {
+    if offset < -width / 2.0 {
+        -width
+    } else {
+        0.0
+    }
+}
+
+const SWIPE_ROW_CSS: &str = r#"
+.swipe-row {
+    position: relative;
+    overflow: hidden;
+}
+
+.swipe-row-actions {
+    position: absolute;
+    top: 0;
+    right: 0;
+    bottom: 0;
+    display: flex;
+    align-items: stretch;
+    justify-content: flex-end;
+    gap: 4px;
+    padding: 4px;
+    box-sizing: border-box;
+}
+
+.swipe-row-actions button {
+    flex: 1;
+    min-height: 44px;
+}
+
+.swipe-row-content {
+    position: relative;
+    background: white;
+    transition: transform 0.2s ease;
+    touch-action: pan-y;
+}
+
+.swipe-row-content.dragging {
+    transition: none;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WIDTH: f64 = 128.0;
+
+    #[test]
+    fn small_movements_are_undecided() {
+        let mut drag = Drag::new(100.0, 100.0, 0.0);
+        assert_eq!(drag.follow(92.0, 105.0, WIDTH), None);
+        assert_eq!(drag.horizontal, None);
+        // Back under the slop after wandering: still undecided
+        assert_eq!(drag.follow(100.0, 100.0, WIDTH), None);
+    }
+
+    #[test]
+    fn a_sideways_drag_moves_the_row_within_the_actions() {
+        let mut drag = Drag::new(300.0, 100.0, 0.0);
+        assert_eq!(drag.follow(260.0, 104.0, WIDTH), Some(-40.0));
+        assert_eq!(drag.horizontal, Some(true));
+        // Once swiping, the vertical drift no longer matters
+        assert_eq!(drag.follow(250.0, 180.0, WIDTH), Some(-50.0));
+        assert_eq!(drag.follow(0.0, 100.0, WIDTH), Some(-WIDTH));
+        assert_eq!(drag.follow(400.0, 100.0, WIDTH), Some(0.0));
+    }
+
+    #[test]
+    fn a_drag_starts_from_where_the_row_was() {
+        let mut drag = Drag::new(300.0, 100.0, -WIDTH);
+        assert_eq!(drag.follow(340.0, 100.0, WIDTH), Some(-88.0));
+    }
+
+    #[test]
+    fn a_vertical_drag_is_left_to_scroll() {
+        let mut drag = Drag::new(300.0, 100.0, 0.0);
+        assert_eq!(drag.follow(295.0, 140.0, WIDTH), None);
+        assert_eq!(drag.horizontal, Some(false));
+        // Turning sideways later doesn't make it a swipe
+        assert_eq!(drag.follow(100.0, 140.0, WIDTH), None);
+    }
+
+    #[test]
+    fn a_row_let_go_snaps_to_the_nearer_end() {
+        assert_eq!(settle(0.0, WIDTH), 0.0);
+        assert_eq!(settle(-63.0, WIDTH), 0.0);
+        assert_eq!(settle(-64.0, WIDTH), 0.0);
+        assert_eq!(settle(-65.0, WIDTH), -WIDTH);
+        assert_eq!(settle(-WIDTH, WIDTH), -WIDTH);
+    }
+}
@@ -0,0 +1,99 @@
+//! Helpers for component tests: render a `VirtualDom` to HTML-like text so a
+//! test can check what a component shows without a renderer.
+
+use dioxus::dioxus_core::{
+    AttributeValue, DynamicNode, NoOpMutations, TemplateAttribute, TemplateNode, VNode, VirtualDom,
+};
+use dioxus::prelude::*;
+
+/// `component` with `props`, rendered once
+pub fn render<P: Clone + 'static>(component: fn(P) -> Element, props: P) -> String {
+    let mut dom = VirtualDom::new_with_props(component, props);
+    dom.rebuild_in_place();
+    html(&dom)
+}
+
+/// A root component, rendered once
+pub fn render_root(root: fn() -> Element) -> String {
+    let mut dom = VirtualDom::new(root);
+    dom.rebuild_in_place();
+    html(&dom)
+}
+
+/// Run queued effects and tasks and re-render until nothing is left to do,
+/// as the renderer would between frames
+pub fn settle(dom: &mut VirtualDom) {
+    for _ in 0..20 {
+        dom.process_events();
+        dom.render_immediate(&mut NoOpMutations);
+    }
+}
+
+/// The whole tree under the root, as markup. Listeners are left out, and so
+/// is the text of `style` elements so CSS can't match an assertion.
+pub fn html(dom: &VirtualDom) -> String {
+    let mut out = String::new();
+    write_vnode(dom, dom.base_scope().root_node(), &mut out);
+    out
+}
+
+fn write_vnode(dom: &VirtualDom, vnode: &VNode, out: &mut String) {
+    for root in vnode.template.roots {
+        write_template_node(dom, vnode, root, out);
+    }
+}
+
+fn write_template_node(dom: &VirtualDom, vnode: &VNode, node: &TemplateNode, out: &mut String) {
+    match node {
+        TemplateNode::Element {
+            tag,
+            attrs,
+            children,
+            ..
+        } => {
+            out.push('<');
+            out.push_str(tag);
+            for attr in *attrs {
+                match attr {
+                    TemplateAttribute::Static { name, value, .. } => {
+                        out.push_str(&format!(" {}=\"{}\"", name, value));
+                    }
+                    TemplateAttribute::Dynamic { id } => {
+                        for attr in vnode.dynamic_attrs[*id].iter() {
+                            let value = match &attr.value {
+                                AttributeValue::Text(text) => text.clone(),
+                                AttributeValue::Bool(b) => b.to_string(),
+                                AttributeValue::Int(n) => n.to_string(),
+                                AttributeValue::Float(n) => n.to_string(),
+                                _ => continue,
+                            };
+                            out.push_str(&format!(" {}=\"{}\"", attr.name, value));
+                        }
+                    }
+                }
+            }
+            out.push('>');
+            if *tag != "style" {
+                for child in *children {
+                    write_template_node(dom, vnode, child, out);
+                }
+            }
+            out.push_str(&format!("</{}>", tag));
+        }
+        TemplateNode::Text { text } => out.push_str(text),
+        TemplateNode::Dynamic { id } => match &vnode.dynamic_nodes[*id] {
+            DynamicNode::Text(text) => out.push_str(&text.value),
+            DynamicNode::Placeholder(_) => {}
+            DynamicNode::Fragment(nodes) => {
+                for node in nodes {
+                    write_vnode(dom, node, out);
+                }
+            }
+            DynamicNode::Component(component) => {
+                if let Some(scope) = component.mounted_scope(*id, vnode, dom) {
+                    write_vnode(dom, scope.root_node(), out);
+                }
+            }
+        },
+    }
+}
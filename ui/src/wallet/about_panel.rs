@@ -0,0 +1,93 @@
+use api::VersionInfo;
+use dioxus::prelude::*;
+
+use super::CopyButton;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct AboutPanelProps {
+    /// Versions of this installation, once read from the node
+    #[props(default)]
+    pub info: Option<VersionInfo>,
+    /// Why the versions couldn't be read
+    #[props(default)]
+    pub error: Option<String>,
+}
+
+/// Crate, library, schema, chain and build versions, with a button copying
+/// them for a support request
+pub fn AboutPanel(props: AboutPanelProps) -> Element {
+    rsx! {
+        div {
+            class: "about-panel",
+            div {
+                class: "about-header",
+                h3 { "About" }
+                if let Some(info) = props.info.as_ref() {
+                    CopyButton { text: info.to_string(), label: "Copy versions" }
+                }
+            }
+            if let Some(info) = props.info.as_ref() {
+                table {
+                    tbody {
+                        for (label, value) in info.fields() {
+                            tr {
+                                key: "{label}",
+                                td { class: "about-label", "{label}" }
+                                td { class: "about-value", "{value}" }
+                            }
+                        }
+                    }
+                }
+            } else if let Some(error) = props.error.as_ref() {
+                div { class: "about-error", "{error}" }
+            } else {
+                div { class: "empty-state", "Loading..." }
+            }
+        }
+
+        style { {ABOUT_PANEL_CSS} }
+    }
+}
+
+const ABOUT_PANEL_CSS: &str = r#"
+.about-panel {
+    display: flex;
+    flex-direction: column;
+    gap: 12px;
+    background: #f8f9fa;
+    padding: 20px;
+    border-radius: 8px;
+    margin-bottom: 24px;
+    font-size: 14px;
+    color: #333;
+}
+
+.about-header {
+    display: flex;
+    align-items: center;
+    justify-content: space-between;
+}
+
+.about-header h3 {
+    margin: 0;
+}
+
+.about-panel td {
+    padding: 4px 12px 4px 0;
+    vertical-align: top;
+}
+
+.about-label {
+    color: #6c757d;
+    white-space: nowrap;
+}
+
+.about-value {
+    font-family: monospace;
+    word-break: break-all;
+}
+
+.about-error {
+    color: #dc3545;
+}
+"#;
@@ -0,0 +1,131 @@
+use api::{format_display, Address, AddressBalance, AddressKind, DisplaySettings};
+use dioxus::prelude::*;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct AddressBreakdownProps {
+    /// Addresses holding funds or already used, as
+    /// `Wallet::address_breakdown` lists them
+    pub addresses: Vec<AddressBalance>,
+    #[props(default)]
+    pub display: DisplaySettings,
+    /// Called with an address to list its notes; without it addresses are
+    /// shown unlinked
+    #[props(default)]
+    pub on_open_address: Option<EventHandler<Address>>,
+}
+
+/// Funds per address, with change addresses in their own group since they
+/// are never handed out to payers
+pub fn AddressBreakdown(props: AddressBreakdownProps) -> Element {
+    if props.addresses.is_empty() {
+        return rsx! {};
+    }
+    let groups = [
+        (AddressKind::Receive, "Receive addresses"),
+        (AddressKind::Change, "Change"),
+        (AddressKind::WatchOnly, "Watch-only"),
+    ];
+
+    rsx! {
+        div {
+            class: "address-breakdown",
+            h4 { "Funds by address" }
+            for (kind, title) in groups {
+                if props.addresses.iter().any(|row| row.kind == kind) {
+                    div {
+                        key: "{title}",
+                        class: "address-breakdown-group",
+                        div { class: "address-breakdown-title", "{title}" }
+                        for row in props.addresses.iter().filter(|row| row.kind == kind) {
+                            div {
+                                key: "{row.address}",
+                                class: "address-breakdown-row",
+                                if let Some(on_open_address) = props.on_open_address {
+                                    button {
+                                        class: "address-breakdown-address address-breakdown-link",
+                                        title: "Show the notes at this address",
+                                        onclick: {
+                                            let address = row.address.clone();
+                                            move |_| on_open_address.call(address.clone())
+                                        },
+                                        "{row.address.to_nockchain_string()}"
+                                    }
+                                } else {
+                                    span { class: "address-breakdown-address", "{row.address.to_nockchain_string()}" }
+                                }
+                                if let Some(key_name) = row.key_name.as_ref() {
+                                    span { class: "address-breakdown-key", "{key_name}" }
+                                }
+                                span {
+                                    class: "address-breakdown-amount",
+                                    "{format_display(row.balance.confirmed + row.balance.unconfirmed, &props.display)} NOCK"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        style { {ADDRESS_BREAKDOWN_CSS} }
+    }
+}
+
+const ADDRESS_BREAKDOWN_CSS: &str = r#"
+.address-breakdown {
+    background: #f8f9fa;
+    padding: 16px 20px;
+    border-radius: 8px;
+    margin-bottom: 16px;
+}
+
+.address-breakdown h4 {
+    color: #333;
+    margin: 0 0 8px 0;
+}
+
+.address-breakdown-group + .address-breakdown-group {
+    margin-top: 12px;
+}
+
+.address-breakdown-title {
+    font-size: 12px;
+    font-weight: 600;
+    color: #6c757d;
+    text-transform: uppercase;
+}
+
+.address-breakdown-row {
+    display: flex;
+    align-items: baseline;
+    gap: 12px;
+    padding: 4px 0;
+    font-size: 14px;
+}
+
+.address-breakdown-address {
+    flex: 1;
+    font-family: monospace;
+    font-size: 12px;
+    word-break: break-all;
+    color: #333;
+}
+
+.address-breakdown-link {
+    padding: 0;
+    background: none;
+    border: none;
+    cursor: pointer;
+    text-align: left;
+    text-decoration: underline;
+}
+
+.address-breakdown-key {
+    color: #6c757d;
+}
+
+.address-breakdown-amount {
+    font-weight: 600;
+    white-space: nowrap;
+}
+"#;
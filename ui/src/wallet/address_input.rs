@@ -0,0 +1,272 @@
+use api::{Address, AddressError, AddressNetwork, ContactBook};
+use dioxus::prelude::*;
+
+use crate::clipboard::strip_nock_uri;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct AddressInputProps {
+    /// Current recipient; `None` clears the field
+    pub value: Option<Address>,
+    /// Called with the parsed address, or `None` while the text isn't a valid one
+    pub on_change: EventHandler<Option<Address>>,
+    /// Checked addresses for another network are rejected
+    #[props(default)]
+    pub network: AddressNetwork,
+    /// Contacts offered as suggestions while typing a name
+    #[props(default)]
+    pub contacts: ContactBook,
+    #[props(default = "Recipient Address".to_string())]
+    pub placeholder: String,
+    #[props(default)]
+    pub disabled: bool,
+}
+
+/// Recipient field validated on every keystroke. Accepts checked or plain
+/// addresses, with or without the `nock_` prefix, expands `nock:` URIs, and
+/// completes contact names.
+pub fn AddressInput(props: AddressInputProps) -> Element {
+    let network = props.network;
+    let mut text = use_signal(|| {
+        props
+            .value
+            .as_ref()
+            .map(|address| address.to_nockchain_string())
+            .unwrap_or_default()
+    });
+    let mut highlighted = use_signal(|| 0usize);
+    let mut suggestions_open = use_signal(|| true);
+
+    // Follow values set by the parent, such as a pasted address or clearing after a send
+    use_effect(use_reactive((&props.value,), move |(value,)| {
+        if Address::from_string_checked(&text.peek(), network).ok() != value {
+            text.set(
+                value
+                    .map(|address| address.to_nockchain_string())
+                    .unwrap_or_default(),
+            );
+        }
+    }));
+
+    let on_change = props.on_change;
+    let mut apply = move |entered: String| {
+        let entered = strip_nock_uri(&entered).to_string();
+        on_change.call(Address::from_string_checked(&entered, network).ok());
+        text.set(entered);
+        highlighted.set(0);
+        suggestions_open.set(true);
+    };
+
+    let parsed = Address::from_string_checked(&text.read(), network);
+    let suggestions: Vec<(String, Address)> = if parsed.is_ok() || !*suggestions_open.read() {
+        Vec::new()
+    } else {
+        props
+            .contacts
+            .matching(&text.read())
+            .into_iter()
+            .map(|contact| (contact.name.clone(), contact.address.clone()))
+            .collect()
+    };
+    let suggestion_count = suggestions.len();
+    let current = (*highlighted.read()).min(suggestion_count.saturating_sub(1));
+    let highlighted_address = suggestions.get(current).map(|(_, address)| address.clone());
+
+    rsx! {
+        div {
+            class: "address-input",
+            input {
+                placeholder: "{props.placeholder}",
+                value: "{text}",
+                disabled: props.disabled,
+                spellcheck: false,
+                oninput: move |evt| apply(evt.value()),
+                onkeydown: move |evt| {
+                    if suggestion_count == 0 {
+                        return;
+                    }
+                    match evt.key() {
+                        Key::ArrowDown => {
+                            evt.prevent_default();
+                            highlighted.set((current + 1) % suggestion_count);
+                        }
+                        Key::ArrowUp => {
+                            evt.prevent_default();
+                            highlighted.set((current + suggestion_count - 1) % suggestion_count);
+                        }
+                        Key::Enter => {
+                            // Pick the contact instead of submitting the form
+                            evt.prevent_default();
+                            if let Some(address) = highlighted_address.clone() {
+                                apply(address.to_nockchain_string());
+                            }
+                        }
+                        Key::Escape => suggestions_open.set(false),
+                        _ => {}
+                    }
+                },
+            }
+
+            if suggestion_count > 0 {
+                ul {
+                    class: "address-suggestions",
+                    for (index, (name, address)) in suggestions.into_iter().enumerate() {
+                        li {
+                            key: "{name}",
+                            class: if index == current { "address-suggestion active" } else { "address-suggestion" },
+                            onmousedown: move |evt| {
+                                evt.prevent_default();
+                                apply(address.to_nockchain_string());
+                            },
+                            span { class: "address-suggestion-name", "{name}" }
+                            span { class: "address-suggestion-address", "{truncate(&address.to_nockchain_string())}" }
+                        }
+                    }
+                }
+            } else if !text.read().trim().is_empty() {
+                match parsed {
+                    Ok(address) => rsx! {
+                        div {
+                            class: "address-status valid",
+                            title: "{address.to_nockchain_string()}",
+                            "✓ {truncate(&address.to_nockchain_string())}"
+                        }
+                    },
+                    Err(e) => rsx! {
+                        div { class: "address-status invalid", "{address_message(&e)}" }
+                    },
+                }
+            }
+        }
+
+        style { {ADDRESS_INPUT_CSS} }
+    }
+}
+
+fn address_message(error: &AddressError) -> String {
+    match error {
+        AddressError::InvalidCharacter { character, .. } => format!(
+            "'{}' can't appear in an address (base58 excludes 0, O, I and l)",
+            character
+        ),
+        AddressError::WrongLength(_) => {
+            "Address is the wrong length; check it was copied completely".to_string()
+        }
+        other => other.to_string(),
+    }
+}
+
+fn truncate(address: &str) -> String {
+    if address.len() > 20 {
+        format!("{}…{}", &address[..10], &address[address.len() - 8..])
+    } else {
+        address.to_string()
+    }
+}
+
+const ADDRESS_INPUT_CSS: &str = r#"
+.address-input {
+    position: relative;
+    display: flex;
+    flex-direction: column;
+    gap: 4px;
+    flex: 1;
+}
+
+.address-status {
+    font-size: 12px;
+}
+
+.address-status.valid {
+    color: #28a745;
+    font-family: monospace;
+}
+
+.address-status.invalid {
+    color: #dc3545;
+}
+
+.address-suggestions {
+    position: absolute;
+    top: 100%;
+    left: 0;
+    right: 0;
+    margin: 2px 0 0 0;
+    padding: 4px 0;
+    list-style: none;
+    background: white;
+    border: 1px solid #ccc;
+    border-radius: 4px;
+    box-shadow: 0 4px 12px rgba(0, 0, 0, 0.1);
+    z-index: 10;
+}
+
+.address-suggestion {
+    display: flex;
+    justify-content: space-between;
+    gap: 12px;
+    padding: 6px 10px;
+    cursor: pointer;
+}
+
+.address-suggestion.active {
+    background: #eef0ff;
+}
+
+.address-suggestion-name {
+    font-weight: 600;
+}
+
+.address-suggestion-address {
+    font-family: monospace;
+    font-size: 12px;
+    color: #6c757d;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use api::testing::fake_address;
+
+    fn message_for(text: &str) -> String {
+        address_message(&Address::from_string_checked(text, AddressNetwork::Mainnet).unwrap_err())
+    }
+
+    #[test]
+    fn each_validation_failure_reads_differently() {
+        let address = fake_address();
+        let checked = address.to_checked_string(AddressNetwork::Mainnet);
+        let mut typo: Vec<char> = checked.chars().collect();
+        let middle = typo.len() / 2;
+        typo[middle] = if typo[middle] == '2' { '3' } else { '2' };
+        let typo: String = typo.into_iter().collect();
+
+        let messages = [
+            message_for("nock_"),
+            message_for("abcO"),
+            message_for("abc"),
+            message_for(&typo),
+            message_for(&address.to_checked_string(AddressNetwork::Fakenet)),
+        ];
+        assert_eq!(
+            messages,
+            [
+                "Address is empty".to_string(),
+                "'O' can't appear in an address (base58 excludes 0, O, I and l)".to_string(),
+                "Address is the wrong length; check it was copied completely".to_string(),
+                "Checksum does not match; the address may have a typo".to_string(),
+                "Address is for fakenet, not mainnet".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn long_addresses_are_shortened_around_an_ellipsis() {
+        let address = fake_address().to_nockchain_string();
+        let short = truncate(&address);
+        assert_eq!(short.chars().count(), 19);
+        assert!(short.starts_with(&address[..10]));
+        assert!(short.ends_with(&address[address.len() - 8..]));
+        assert_eq!(truncate("nock_abc"), "nock_abc");
+    }
+}
@@ -0,0 +1,301 @@
+use api::{format_amount, parse_amount, AmountUnit, WalletError};
+use dioxus::prelude::*;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct AmountInputProps {
+    /// Current amount in base units; `None` clears the field
+    pub value: Option<u64>,
+    /// Called with the amount in base units, or `None` while the text is empty or invalid
+    pub on_change: EventHandler<Option<u64>>,
+    /// Amounts above this are clamped to it, and a Max button fills it in
+    #[props(default)]
+    pub available: Option<u64>,
+    #[props(default = "Amount".to_string())]
+    pub placeholder: String,
+    #[props(default)]
+    pub disabled: bool,
+}
+
+/// Amount field with a NOCK / nano unit toggle. Text is parsed exactly with the
+/// shared amount rules; switching units rewrites the text without changing the value.
+pub fn AmountInput(props: AmountInputProps) -> Element {
+    let mut unit = use_signal(AmountUnit::default);
+    let mut text = use_signal(|| {
+        props
+            .value
+            .map(|value| format_amount(value, AmountUnit::default()))
+            .unwrap_or_default()
+    });
+    let mut notice = use_signal(|| None::<String>);
+
+    // Follow values set by the parent, such as clearing the field after a send
+    use_effect(use_reactive((&props.value,), move |(value,)| {
+        let unit = *unit.peek();
+        if parse_amount(&text.peek(), unit).ok() != value {
+            text.set(
+                value
+                    .map(|value| format_amount(value, unit))
+                    .unwrap_or_default(),
+            );
+        }
+    }));
+
+    let available = props.available;
+    let on_change = props.on_change;
+    let mut apply = move |entered: String| {
+        let entry = enter_amount(entered, *unit.peek(), available);
+        text.set(entry.text);
+        notice.set(entry.notice);
+        on_change.call(entry.amount);
+    };
+
+    let current_unit = *unit.read();
+    let parsed = parse_amount(&text.read(), current_unit).ok();
+    let other_unit = match current_unit {
+        AmountUnit::Nock => AmountUnit::Nano,
+        AmountUnit::Nano => AmountUnit::Nock,
+    };
+
+    rsx! {
+        div {
+            class: "amount-input",
+            div {
+                class: "amount-input-row",
+                input {
+                    placeholder: "{props.placeholder}",
+                    inputmode: "decimal",
+                    value: "{text}",
+                    disabled: props.disabled,
+                    oninput: move |evt| apply(evt.value()),
+                }
+                div {
+                    class: "amount-units",
+                    for option in AmountUnit::ALL {
+                        button {
+                            r#type: "button",
+                            class: if option == current_unit { "amount-unit active" } else { "amount-unit" },
+                            disabled: props.disabled,
+                            onclick: move |_| {
+                                let from = *unit.peek();
+                                if from == option {
+                                    return;
+                                }
+                                let entered = switch_unit(&text.peek(), from, option);
+                                unit.set(option);
+                                apply(entered);
+                            },
+                            "{option.label()}"
+                        }
+                    }
+                }
+                if let Some(max) = available {
+                    button {
+                        r#type: "button",
+                        class: "amount-max",
+                        disabled: props.disabled,
+                        onclick: move |_| apply(format_amount(max, *unit.peek())),
+                        "Max"
+                    }
+                }
+            }
+
+            if let Some(message) = notice.read().as_ref() {
+                div { class: "amount-input-notice", "{message}" }
+            } else if let Some(amount) = parsed {
+                div {
+                    class: "amount-input-hint",
+                    "= {format_amount(amount, other_unit)} {other_unit.label()}"
+                }
+            }
+        }
+
+        style { {AMOUNT_INPUT_CSS} }
+    }
+}
+
+/// What the field shows and emits once `entered` is typed in `unit`
+#[derive(Debug, PartialEq)]
+struct AmountEntry {
+    text: String,
+    amount: Option<u64>,
+    notice: Option<String>,
+}
+
+fn enter_amount(entered: String, unit: AmountUnit, available: Option<u64>) -> AmountEntry {
+    match parse_amount(&entered, unit) {
+        Ok(amount) => match available.filter(|max| amount > *max) {
+            Some(max) => AmountEntry {
+                text: format_amount(max, unit),
+                amount: Some(max),
+                notice: Some(format!(
+                    "Clamped to the available {} {}",
+                    format_amount(max, unit),
+                    unit.label()
+                )),
+            },
+            None => AmountEntry {
+                text: entered,
+                amount: Some(amount),
+                notice: None,
+            },
+        },
+        Err(e) => {
+            let empty = entered.trim().is_empty();
+            AmountEntry {
+                text: entered,
+                amount: None,
+                notice: (!empty).then(|| amount_error(e)),
+            }
+        }
+    }
+}
+
+/// `text` rewritten from `from` into `to`. Invalid text is kept for the user to fix.
+fn switch_unit(text: &str, from: AmountUnit, to: AmountUnit) -> String {
+    match parse_amount(text, from) {
+        Ok(amount) => format_amount(amount, to),
+        Err(_) => text.to_string(),
+    }
+}
+
+fn amount_error(error: WalletError) -> String {
+    match error {
+        WalletError::InvalidAmount(message) => message,
+        other => other.to_string(),
+    }
+}
+
+const AMOUNT_INPUT_CSS: &str = r#"
+.amount-input {
+    display: flex;
+    flex-direction: column;
+    gap: 4px;
+}
+
+.amount-input-row {
+    display: flex;
+    gap: 8px;
+}
+
+.amount-input-row input {
+    flex: 1;
+}
+
+.amount-units {
+    display: flex;
+}
+
+.amount-unit {
+    padding: 6px 10px;
+    border: 1px solid #ccc;
+    background: white;
+    cursor: pointer;
+}
+
+.amount-unit:first-child {
+    border-radius: 4px 0 0 4px;
+}
+
+.amount-unit:last-child {
+    border-radius: 0 4px 4px 0;
+    border-left: none;
+}
+
+.amount-unit.active {
+    background: #667eea;
+    border-color: #667eea;
+    color: white;
+}
+
+.amount-max {
+    padding: 6px 10px;
+    border: 1px solid #ccc;
+    border-radius: 4px;
+    background: white;
+    cursor: pointer;
+}
+
+.amount-input-hint {
+    font-size: 12px;
+    color: #6c757d;
+}
+
+.amount-input-notice {
+    font-size: 12px;
+    color: #dc3545;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOCK: AmountUnit = AmountUnit::Nock;
+    const NANO: AmountUnit = AmountUnit::Nano;
+
+    #[test]
+    fn switching_units_back_and_forth_keeps_the_value_exactly() {
+        for amount in [
+            0,
+            1,
+            10,
+            999_999,
+            1_000_000,
+            1_000_001,
+            123_456_789,
+            u64::MAX,
+        ] {
+            let nock = format_amount(amount, NOCK);
+            let nano = switch_unit(&nock, NOCK, NANO);
+            assert_eq!(nano, amount.to_string());
+            let back = switch_unit(&nano, NANO, NOCK);
+            assert_eq!(back, nock);
+            assert_eq!(enter_amount(back, NOCK, None).amount, Some(amount));
+        }
+        assert_eq!(switch_unit("1.5", NOCK, NANO), "1500000");
+        assert_eq!(switch_unit("0.000001", NOCK, NANO), "1");
+        assert_eq!(switch_unit("1500001", NANO, NOCK), "1.500001");
+    }
+
+    #[test]
+    fn invalid_text_survives_a_unit_switch() {
+        assert_eq!(switch_unit("1.2.3", NOCK, NANO), "1.2.3");
+        // Valid as NOCK but not as nano: the user sees why after switching
+        assert_eq!(switch_unit("0.5", NANO, NOCK), "0.5");
+        assert_eq!(
+            enter_amount("0.5".to_string(), NANO, None).notice,
+            Some("nano amounts are whole numbers".to_string())
+        );
+    }
+
+    #[test]
+    fn amounts_over_the_available_balance_are_clamped() {
+        let entry = enter_amount("3".to_string(), NOCK, Some(2_500_000));
+        assert_eq!(
+            entry,
+            AmountEntry {
+                text: "2.5".to_string(),
+                amount: Some(2_500_000),
+                notice: Some("Clamped to the available 2.5 NOCK".to_string()),
+            }
+        );
+        let exact = enter_amount("2500000".to_string(), NANO, Some(2_500_000));
+        assert_eq!((exact.amount, exact.notice), (Some(2_500_000), None));
+    }
+
+    #[test]
+    fn an_empty_field_emits_nothing_without_complaining() {
+        assert_eq!(
+            enter_amount("  ".to_string(), NOCK, None),
+            AmountEntry {
+                text: "  ".to_string(),
+                amount: None,
+                notice: None,
+            }
+        );
+        assert_eq!(
+            enter_amount("abc".to_string(), NOCK, None).notice,
+            Some("'abc' is not a number".to_string())
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use api::{AuditCategory, AuditChainBreak, AuditEntry, AuditOutcome};
+use dioxus::prelude::*;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct AuditLogViewerProps {
+    /// Entries to show, newest first
+    pub entries: Vec<AuditEntry>,
+    /// Set when the saved log failed verification
+    #[props(default)]
+    pub chain_break: Option<AuditChainBreak>,
+    /// Called with the chosen category (`None` for all); without it no filter is shown
+    #[props(default)]
+    pub on_filter: Option<EventHandler<Option<AuditCategory>>>,
+}
+
+/// Read-only view of the wallet audit log
+pub fn AuditLogViewer(props: AuditLogViewerProps) -> Element {
+    rsx! {
+        div {
+            class: "audit-log",
+            div {
+                class: "audit-log-header",
+                h3 { "Audit log" }
+                if let Some(on_filter) = props.on_filter {
+                    select {
+                        onchange: move |evt| {
+                            let category = AuditCategory::ALL
+                                .into_iter()
+                                .find(|category| category.label() == evt.value());
+                            on_filter.call(category);
+                        },
+                        option { value: "all", "All events" }
+                        for category in AuditCategory::ALL {
+                            option { value: "{category.label()}", "{category.label()}" }
+                        }
+                    }
+                }
+            }
+
+            if let Some(chain_break) = props.chain_break.as_ref() {
+                div { class: "audit-log-warning", role: "alert", "⚠️ {chain_break}" }
+            }
+
+            if props.entries.is_empty() {
+                div { class: "empty-state", "No recorded activity" }
+            } else {
+                table {
+                    tbody {
+                        for entry in props.entries.iter() {
+                            tr {
+                                key: "{entry.seq}",
+                                td { class: "audit-log-time", "{format_time(entry)}" }
+                                td { "{entry.event}" }
+                                match &entry.outcome {
+                                    AuditOutcome::Success => rsx! {
+                                        td { class: "audit-log-outcome ok", "OK" }
+                                    },
+                                    AuditOutcome::Failure(reason) => rsx! {
+                                        td { class: "audit-log-outcome failed", title: "{reason}", "Failed" }
+                                    },
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        style { {AUDIT_LOG_VIEWER_CSS} }
+    }
+}
+
+fn format_time(entry: &AuditEntry) -> String {
+    entry.timestamp.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+}
+
+const AUDIT_LOG_VIEWER_CSS: &str = r#"
+.audit-log {
+    background: #f8f9fa;
+    padding: 20px;
+    border-radius: 8px;
+    font-size: 14px;
+    color: #333;
+}
+
+.audit-log-header {
+    display: flex;
+    align-items: center;
+    justify-content: space-between;
+    margin-bottom: 12px;
+}
+
+.audit-log-header h3 {
+    margin: 0;
+}
+
+.audit-log-warning {
+    padding: 8px 12px;
+    margin-bottom: 12px;
+    background: #fff3cd;
+    border: 1px solid #ffe69c;
+    border-radius: 4px;
+    color: #664d03;
+}
+
+.audit-log table {
+    width: 100%;
+    border-collapse: collapse;
+}
+
+.audit-log td {
+    padding: 6px 8px 6px 0;
+    border-bottom: 1px solid #eef0f2;
+}
+
+.audit-log-time {
+    color: #6c757d;
+    font-size: 12px;
+    white-space: nowrap;
+}
+
+.audit-log-outcome {
+    text-align: right;
+    font-weight: 600;
+}
+
+.audit-log-outcome.ok {
+    color: #28a745;
+}
+
+.audit-log-outcome.failed {
+    color: #dc3545;
+}
+"#;
@@ -1,59 +1,98 @@
-use api::Balance;
+use api::{format_display, Balance, DisplaySettings};
 use dioxus::prelude::*;
 
+use super::CopyButton;
+use crate::error_state::ErrorState;
+use crate::load_state::LoadState;
+use crate::skeleton::Skeleton;
+use crate::t;
+
 #[derive(Props, Clone, PartialEq)]
 pub struct BalanceCardProps {
-    pub balance: Balance,
-    pub is_loading: bool,
+    pub state: LoadState<Balance>,
+    /// Fetch the balance again, from the refresh button or after an error
+    #[props(default)]
+    pub on_refresh: Option<EventHandler<()>>,
+    /// Receive address shown under the balance with a copy button
+    #[props(default)]
+    pub address: Option<String>,
+    #[props(default)]
+    pub display: DisplaySettings,
 }
 
 pub fn BalanceCard(props: BalanceCardProps) -> Element {
-    let balance = props.balance;
-    let is_loading = props.is_loading;
+    let balance = match props.state {
+        LoadState::Loading => return rsx! { BalanceCardSkeleton {} },
+        LoadState::Error(message) => {
+            return rsx! {
+                ErrorState {
+                    title: t!("balance.error"),
+                    message,
+                    on_retry: props.on_refresh,
+                }
+            };
+        }
+        LoadState::Ready(balance) => balance,
+    };
+    let format = |amount: u64| format_display(amount, &props.display);
 
     rsx! {
         div {
             class: "balance-card",
             div {
                 class: "balance-header",
-                h2 { class: "balance-title", "Total Balance" }
-                if is_loading {
-                    div { class: "loading-spinner" }
-                } else {
+                h2 { class: "balance-title", {t!("balance.title")} }
+                if let Some(on_refresh) = props.on_refresh {
                     button {
                         class: "refresh-button",
-                        onclick: move |_| {
-                            // TODO: Implement balance refresh
-                        },
+                        title: t!("balance.refresh"),
+                        aria_label: t!("balance.refresh"),
+                        onclick: move |_| on_refresh.call(()),
                         "↻"
                     }
                 }
             }
 
-            div { class: "balance-main" }
-            if is_loading {
-                div { class: "balance-loading", "Loading..." }
-            } else {
-                div { class: "balance-amount" }
-                span { class: "balance-value", "{format_balance(balance.total())}" }
-                span { class: "balance-currency", "NOCK" }
+            div {
+                class: "balance-main",
+                div {
+                    class: "balance-amount",
+                    span { class: "balance-value", "{format(balance.total())}" }
+                    span { class: "balance-currency", "NOCK" }
+                }
             }
 
-            div { class: "balance-details" }
-            div { class: "balance-row" }
-            span { class: "balance-label", "Available:" }
-            span { class: "balance-amount-small", "{format_balance(balance.available())}" }
+            div {
+                class: "balance-details",
+                div {
+                    class: "balance-row",
+                    span { class: "balance-label", {t!("balance.available")} }
+                    span { class: "balance-amount-small", "{format(balance.available())}" }
+                }
+
+                if balance.unconfirmed > 0 {
+                    div {
+                        class: "balance-row",
+                        span { class: "balance-label", {t!("balance.pending")} }
+                        span { class: "balance-amount-small pending", "{format(balance.unconfirmed)}" }
+                    }
+                }
 
-            if balance.unconfirmed > 0 {
-                div { class: "balance-row" }
-                span { class: "balance-label", "Pending:" }
-                span { class: "balance-amount-small pending", "{format_balance(balance.unconfirmed)}" }
+                if balance.locked > 0 {
+                    div {
+                        class: "balance-row",
+                        span { class: "balance-label", {t!("balance.locked")} }
+                        span { class: "balance-amount-small locked", "{format(balance.locked)}" }
+                    }
+                }
             }
 
-            if balance.locked > 0 {
-                div { class: "balance-row" }
-                span { class: "balance-label", "Locked:" }
-                span { class: "balance-amount-small locked", "{format_balance(balance.locked)}" }
+            if let Some(address) = props.address {
+                div {
+                    class: "balance-address",
+                    span { class: "balance-address-value", title: "{address}", "{address}" }
+                    CopyButton { text: address.clone(), label: t!("balance.copy") }
+                }
             }
         }
 
@@ -61,9 +100,42 @@ pub fn BalanceCard(props: BalanceCardProps) -> Element {
     }
 }
 
-fn format_balance(amount: u64) -> String {
-    let nock_amount = amount as f64 / 1_000_000.0; // Assuming 6 decimal places
-    format!("{:.6}", nock_amount)
+/// NOCK amount at full precision
+pub(crate) fn format_balance(amount: u64) -> String {
+    format_display(amount, &DisplaySettings::default())
+}
+
+/// Placeholder in the shape of a `BalanceCard` while the balance loads
+pub fn BalanceCardSkeleton() -> Element {
+    rsx! {
+        div {
+            class: "balance-card",
+            aria_busy: "true",
+            div {
+                class: "balance-header",
+                h2 { class: "balance-title", {t!("balance.title")} }
+            }
+            div {
+                class: "balance-main",
+                Skeleton { width: "60%", height: "40px" }
+            }
+            div {
+                class: "balance-details",
+                div {
+                    class: "balance-row",
+                    Skeleton { width: "30%" }
+                    Skeleton { width: "25%" }
+                }
+                div {
+                    class: "balance-row",
+                    Skeleton { width: "25%" }
+                    Skeleton { width: "20%" }
+                }
+            }
+        }
+
+        style { {BALANCE_CARD_CSS} }
+    }
 }
 
 const BALANCE_CARD_CSS: &str = r#"
@@ -123,20 +195,6 @@ const BALANCE_CARD_CSS: &str = r#"
     transform: rotate(180deg);
 }
 
-.loading-spinner {
-    width: 20px;
-    height: 20px;
-    border: 2px solid rgba(255, 255, 255, 0.3);
-    border-top: 2px solid white;
-    border-radius: 50%;
-    animation: spin 1s linear infinite;
-}
-
-@keyframes spin {
-    0% { transform: rotate(0deg); }
-    100% { transform: rotate(360deg); }
-}
-
 .balance-main {
     margin-bottom: 20px;
 }
@@ -159,13 +217,6 @@ const BALANCE_CARD_CSS: &str = r#"
     opacity: 0.8;
 }
 
-.balance-loading {
-    font-size: 24px;
-    opacity: 0.7;
-    text-align: center;
-    padding: 20px 0;
-}
-
 .balance-details {
     display: flex;
     flex-direction: column;
@@ -198,6 +249,22 @@ const BALANCE_CARD_CSS: &str = r#"
     color: #ff6b6b;
 }
 
+.balance-address {
+    display: flex;
+    align-items: center;
+    gap: 8px;
+    margin-top: 16px;
+}
+
+.balance-address-value {
+    font-family: monospace;
+    font-size: 12px;
+    opacity: 0.9;
+    overflow: hidden;
+    text-overflow: ellipsis;
+    white-space: nowrap;
+}
+
 @media (max-width: 768px) {
     .balance-card {
         padding: 20px;
@@ -208,4 +275,101 @@ const BALANCE_CARD_CSS: &str = r#"
         font-size: 28px;
     }
 }
+
+@media (max-width: 480px) {
+    .balance-card {
+        padding: 16px;
+        border-radius: 12px;
+    }
+
+    .balance-value {
+        font-size: 24px;
+    }
+
+    .refresh-button {
+        width: 44px;
+        height: 44px;
+    }
+
+    .balance-row {
+        flex-wrap: wrap;
+        gap: 4px;
+    }
+
+    .balance-address .copy-button {
+        min-height: 44px;
+    }
+}
 "#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{render, render_root};
+
+    fn card(state: LoadState<Balance>) -> String {
+        render(
+            BalanceCard,
+            BalanceCardProps {
+                state,
+                on_refresh: None,
+                address: None,
+                display: DisplaySettings::default(),
+            },
+        )
+    }
+
+    #[test]
+    fn loading_shows_the_skeleton() {
+        let html = card(LoadState::Loading);
+        assert!(html.contains(r#"aria-busy="true""#));
+        assert_eq!(html.matches(r#"class="skeleton""#).count(), 5);
+        assert!(!html.contains("balance-value"));
+    }
+
+    #[test]
+    fn ready_shows_the_amounts() {
+        let balance = Balance {
+            confirmed: 150_000_000,
+            unconfirmed: 25_000_000,
+            locked: 0,
+            immature: 0,
+        };
+        let html = card(LoadState::Ready(balance.clone()));
+        assert!(html.contains(&format!(
+            r#"<span class="balance-value">{}</span>"#,
+            format_balance(balance.total())
+        )));
+        assert!(html.contains(&format_balance(balance.available())));
+        assert!(html.contains(&t!("balance.pending")));
+        assert!(!html.contains(&t!("balance.locked")));
+        assert!(!html.contains("skeleton"));
+        assert!(!html.contains("refresh-button"));
+    }
+
+    #[test]
+    fn an_error_shows_the_message_and_a_retry_button() {
+        let html = card(LoadState::Error("wallet is locked".to_string()));
+        assert!(html.contains(r#"role="alert""#));
+        assert!(html.contains(&t!("balance.error")));
+        assert!(html.contains("wallet is locked"));
+        // Nothing to retry without a refresh handler
+        assert!(!html.contains("error-state-retry"));
+
+        #[component]
+
+        fn Retryable() -> Element {
+            rsx! {
+                BalanceCard {
+                    state: LoadState::Error("timed out".to_string()),
+                    on_refresh: move |_| {},
+                }
+            }
+        }
+        let html = render_root(Retryable);
+        assert!(html.contains(&format!(
+            r#"<button class="error-state-retry">{}</button>"#,
+            t!("error.retry")
+        )));
+    }
+}
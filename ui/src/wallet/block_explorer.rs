@@ -0,0 +1,520 @@
+use api::{BlockView, BlocksPage, ChainTransactionView};
+use chrono::{DateTime, Utc};
+use dioxus::prelude::*;
+
+use super::balance_card::format_balance;
+use super::CopyButton;
+use crate::format::{format_bytes, format_count};
+
+#[derive(Props, Clone, PartialEq)]
+pub struct BlockListProps {
+    pub page: BlocksPage,
+    /// Blocks per page, to step `offset` by
+    pub page_size: usize,
+    /// Called with the offset of the page to show
+    pub on_page: EventHandler<usize>,
+    /// Called with the height of a block to open
+    pub on_open_block: EventHandler<String>,
+}
+
+/// Latest blocks of the local chain, newest first, a page at a time
+pub fn BlockList(props: BlockListProps) -> Element {
+    let page = &props.page;
+    let page_size = props.page_size.max(1);
+    let first = page.offset + 1;
+    let last = page.offset + page.blocks.len();
+    let (newer, older) = pager_offsets(page, page_size);
+
+    rsx! {
+        div {
+            class: "explorer",
+            if page.blocks.is_empty() {
+                div { class: "empty-state", "No blocks in the local chain yet." }
+            } else {
+                table {
+                    class: "explorer-table",
+                    thead {
+                        tr {
+                            th { "Height" }
+                            th { "Hash" }
+                            th { "Time" }
+                            th { "Transactions" }
+                            th { "Size" }
+                        }
+                    }
+                    tbody {
+                        for block in page.blocks.iter() {
+                            tr {
+                                key: "{block.height}",
+                                td {
+                                    button {
+                                        class: "explorer-link",
+                                        onclick: {
+                                            let height = block.height.to_string();
+                                            move |_| props.on_open_block.call(height.clone())
+                                        },
+                                        "{format_count(block.height)}"
+                                    }
+                                }
+                                td { class: "explorer-mono", "{short_hash(&block.hash)}" }
+                                td { {format_time(block.timestamp)} }
+                                if block.body_pruned {
+                                    td { class: "explorer-muted", "pruned" }
+                                } else {
+                                    td { "{format_count(block.tx_count as u64)}" }
+                                }
+                                td { "{format_bytes(block.size as u64)}" }
+                            }
+                        }
+                    }
+                }
+                div {
+                    class: "explorer-pager",
+                    button {
+                        disabled: newer.is_none(),
+                        onclick: move |_| {
+                            if let Some(offset) = newer {
+                                props.on_page.call(offset);
+                            }
+                        },
+                        "‹ Newer"
+                    }
+                    span { "Blocks {format_count(first as u64)}–{format_count(last as u64)} of {format_count(page.total as u64)}" }
+                    button {
+                        disabled: older.is_none(),
+                        onclick: move |_| {
+                            if let Some(offset) = older {
+                                props.on_page.call(offset);
+                            }
+                        },
+                        "Older ›"
+                    }
+                }
+            }
+        }
+
+        style { {EXPLORER_CSS} }
+    }
+}
+
+#[derive(Props, Clone, PartialEq)]
+pub struct BlockDetailProps {
+    pub block: BlockView,
+    /// Called with the height or hash of a linked block
+    pub on_open_block: EventHandler<String>,
+    /// Called with the id of a transaction in the block
+    pub on_open_transaction: EventHandler<String>,
+}
+
+/// Header fields, miner and transactions of one block
+pub fn BlockDetail(props: BlockDetailProps) -> Element {
+    let block = &props.block;
+    let summary = &block.summary;
+    let previous = (summary.height > 0).then(|| block.previous_hash.clone());
+
+    rsx! {
+        div {
+            class: "explorer",
+            h3 { "Block {format_count(summary.height)}" }
+            div {
+                class: "explorer-hash",
+                code { "{summary.hash}" }
+                CopyButton { text: summary.hash.clone(), label: "Copy hash" }
+            }
+            dl {
+                class: "explorer-fields",
+                dt { "Time" }
+                dd { {format_time(summary.timestamp)} }
+                dt { "Previous block" }
+                dd {
+                    if let Some(previous) = previous {
+                        button {
+                            class: "explorer-link explorer-mono",
+                            onclick: {
+                                let previous = previous.clone();
+                                move |_| props.on_open_block.call(previous.clone())
+                            },
+                            "{short_hash(&previous)}"
+                        }
+                        CopyButton { text: previous.clone() }
+                    } else {
+                        "None (genesis)"
+                    }
+                }
+                dt { "Next block" }
+                dd {
+                    if let Some(next) = block.next_height {
+                        button {
+                            class: "explorer-link",
+                            onclick: move |_| props.on_open_block.call(next.to_string()),
+                            "{format_count(next)}"
+                        }
+                    } else {
+                        "None yet"
+                    }
+                }
+                dt { "Merkle root" }
+                dd { class: "explorer-mono", "{block.merkle_root}" }
+                dt { "Difficulty" }
+                dd { "{format_difficulty(block.difficulty)} (bits {block.bits:#010x})" }
+                dt { "Nonce" }
+                dd { "{block.nonce}" }
+                dt { "Version" }
+                dd { "{block.version}" }
+                dt { "Size" }
+                dd { "{format_bytes(summary.size as u64)}" }
+                dt { "Miner" }
+                dd {
+                    if let Some(address) = block.miner_address.clone() {
+                        span { class: "explorer-mono", "{address}" }
+                        CopyButton { text: address }
+                    } else {
+                        "—"
+                    }
+                }
+            }
+
+            if summary.body_pruned {
+                h4 { "Transactions" }
+                div { class: "explorer-muted", "Block body pruned; only the header is kept." }
+            } else {
+                h4 { "Transactions ({format_count(summary.tx_count as u64)})" }
+            }
+            table {
+                class: "explorer-table",
+                tbody {
+                    for tx in block.transactions.iter() {
+                        tr {
+                            key: "{tx.tx_id}",
+                            td {
+                                button {
+                                    class: "explorer-link explorer-mono",
+                                    onclick: {
+                                        let tx_id = tx.tx_id.clone();
+                                        move |_| props.on_open_transaction.call(tx_id.clone())
+                                    },
+                                    "{short_hash(&tx.tx_id)}"
+                                }
+                                if tx.coinbase {
+                                    span { class: "explorer-tag", "coinbase" }
+                                }
+                            }
+                            td { "{tx.input_count} in · {tx.output_count} out" }
+                            td { class: "explorer-amount", "{format_balance(tx.output_total)} NOCK" }
+                        }
+                    }
+                }
+            }
+        }
+
+        style { {EXPLORER_CSS} }
+    }
+}
+
+#[derive(Props, Clone, PartialEq)]
+pub struct ChainTransactionDetailProps {
+    pub transaction: ChainTransactionView,
+    /// Called with the hash of the containing block
+    pub on_open_block: EventHandler<String>,
+    /// Called with the id of the transaction an input spends
+    pub on_open_transaction: EventHandler<String>,
+}
+
+/// A transaction as recorded on the chain, linked to its block and to the
+/// transactions its inputs spend
+pub fn ChainTransactionDetail(props: ChainTransactionDetailProps) -> Element {
+    let tx = &props.transaction;
+
+    rsx! {
+        div {
+            class: "explorer",
+            h3 { "Transaction" }
+            div {
+                class: "explorer-hash",
+                code { "{tx.tx_id}" }
+                CopyButton { text: tx.tx_id.clone(), label: "Copy ID" }
+            }
+            dl {
+                class: "explorer-fields",
+                dt { "Block" }
+                dd {
+                    button {
+                        class: "explorer-link",
+                        onclick: {
+                            let hash = tx.block_hash.clone();
+                            move |_| props.on_open_block.call(hash.clone())
+                        },
+                        "{format_count(tx.block_height)}"
+                    }
+                    span { class: "explorer-mono", " {short_hash(&tx.block_hash)}" }
+                }
+                dt { "Time" }
+                dd { {format_time(tx.block_time)} }
+                dt { "Confirmations" }
+                dd { "{format_count(tx.confirmations)}" }
+                dt { "Fee" }
+                dd { "{format_balance(tx.fee)} NOCK" }
+            }
+
+            h4 { "Inputs" }
+            if tx.coinbase {
+                div { class: "empty-state", "Coinbase: newly mined coins" }
+            }
+            table {
+                class: "explorer-table",
+                tbody {
+                    for (outpoint, amount) in tx.inputs.iter() {
+                        tr {
+                            key: "{outpoint}",
+                            td {
+                                button {
+                                    class: "explorer-link explorer-mono",
+                                    onclick: {
+                                        let txid = outpoint.txid.clone();
+                                        move |_| props.on_open_transaction.call(txid.clone())
+                                    },
+                                    "{outpoint}"
+                                }
+                            }
+                            td { class: "explorer-amount", "{format_balance(*amount)} NOCK" }
+                        }
+                    }
+                }
+            }
+
+            h4 { "Outputs" }
+            table {
+                class: "explorer-table",
+                tbody {
+                    for (index, (address, amount)) in tx.outputs.iter().enumerate() {
+                        tr {
+                            key: "{index}",
+                            td { "#{index}" }
+                            td { class: "explorer-mono", "{address}" }
+                            td { CopyButton { text: address.clone() } }
+                            td { class: "explorer-amount", "{format_balance(*amount)} NOCK" }
+                        }
+                    }
+                }
+            }
+        }
+
+        style { {EXPLORER_CSS} }
+    }
+}
+
+/// Offsets of the newer and older pages next to `page`, if there are any
+fn pager_offsets(page: &BlocksPage, page_size: usize) -> (Option<usize>, Option<usize>) {
+    let newer = page
+        .offset
+        .checked_sub(page_size)
+        .or((page.offset > 0).then_some(0));
+    let last = page.offset + page.blocks.len();
+    let older = (last < page.total).then_some(page.offset + page_size);
+    (newer, older)
+}
+
+/// First and last eight characters of a hex hash or id
+fn short_hash(hash: &str) -> String {
+    if hash.len() <= 20 {
+        return hash.to_string();
+    }
+    format!("{}…{}", &hash[..8], &hash[hash.len() - 8..])
+}
+
+/// Fakenet difficulties are tiny fractions, so those get an exponent
+fn format_difficulty(difficulty: f64) -> String {
+    if difficulty != 0.0 && difficulty < 0.01 {
+        format!("{difficulty:.3e}")
+    } else {
+        format!("{difficulty:.2}")
+    }
+}
+
+fn format_time(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+}
+
+const EXPLORER_CSS: &str = r#"
+.explorer {
+    background: #f8f9fa;
+    padding: 20px;
+    border-radius: 8px;
+    font-size: 14px;
+    color: #333;
+}
+
+.explorer h3 {
+    margin: 0 0 8px;
+}
+
+.explorer-hash {
+    display: flex;
+    align-items: flex-start;
+    gap: 8px;
+    margin-bottom: 12px;
+}
+
+.explorer-hash code {
+    flex: 1;
+    word-break: break-all;
+    font-size: 12px;
+}
+
+.explorer-fields {
+    display: grid;
+    grid-template-columns: max-content 1fr;
+    gap: 6px 16px;
+    margin: 0;
+}
+
+.explorer-fields dt {
+    color: #6c757d;
+}
+
+.explorer-fields dd {
+    margin: 0;
+    display: flex;
+    align-items: center;
+    gap: 8px;
+    word-break: break-all;
+}
+
+.explorer-table {
+    width: 100%;
+    border-collapse: collapse;
+}
+
+.explorer-table th,
+.explorer-table td {
+    padding: 6px 8px;
+    text-align: left;
+    border-bottom: 1px solid #e9ecef;
+}
+
+.explorer-mono {
+    font-family: monospace;
+    font-size: 12px;
+}
+
+.explorer-amount {
+    text-align: right;
+    white-space: nowrap;
+}
+
+.explorer-link {
+    padding: 0;
+    background: none;
+    border: none;
+    color: #667eea;
+    cursor: pointer;
+    text-decoration: underline;
+}
+
+.explorer-tag {
+    margin-left: 6px;
+    padding: 1px 6px;
+    background: #e9ecef;
+    border-radius: 10px;
+    font-size: 11px;
+}
+
+.explorer-muted {
+    color: #6c757d;
+    font-style: italic;
+}
+
+.explorer-pager {
+    display: flex;
+    align-items: center;
+    justify-content: space-between;
+    margin-top: 12px;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::render;
+    use api::testing::fixture_time;
+    use api::BlockSummary;
+
+    /// Page of a 95-block chain starting `offset` blocks below the tip
+    fn page(offset: usize, len: usize) -> BlocksPage {
+        BlocksPage {
+            blocks: (0..len)
+                .map(|i| {
+                    let height = (94 - offset - i) as u64;
+                    BlockSummary {
+                        height,
+                        hash: format!("{:064x}", height),
+                        timestamp: fixture_time(height as i64 * 600),
+                        tx_count: 2,
+                        size: 1_536,
+                        body_pruned: height < 10,
+                    }
+                })
+                .collect(),
+            total: 95,
+            offset,
+        }
+    }
+
+    #[test]
+    fn the_pager_links_to_the_pages_either_side() {
+        assert_eq!(pager_offsets(&page(0, 25), 25), (None, Some(25)));
+        assert_eq!(pager_offsets(&page(25, 25), 25), (Some(0), Some(50)));
+        assert_eq!(pager_offsets(&page(75, 20), 25), (Some(50), None));
+        // An offset off the page grid still gets back to the tip
+        assert_eq!(pager_offsets(&page(10, 25), 25), (Some(0), Some(35)));
+        assert_eq!(pager_offsets(&BlocksPage::default(), 25), (None, None));
+    }
+
+    #[test]
+    fn hashes_are_shortened_and_difficulties_stay_readable() {
+        let hash = "0123456789abcdef".repeat(4);
+        assert_eq!(short_hash(&hash), "01234567…89abcdef");
+        assert_eq!(short_hash("short"), "short");
+        assert_eq!(format_difficulty(0.000_012_34), "1.234e-5");
+        assert_eq!(format_difficulty(0.0), "0.00");
+        assert_eq!(format_difficulty(1_234.5), "1234.50");
+    }
+
+    #[component]
+    fn List(page: BlocksPage) -> Element {
+        rsx! {
+            BlockList {
+                page,
+                page_size: 25,
+                on_page: |_| {},
+                on_open_block: |_| {},
+            }
+        }
+    }
+
+    #[test]
+    fn the_block_list_shows_a_row_per_block_and_the_range() {
+        let html = render(List, ListProps { page: page(75, 20) });
+        assert_eq!(html.matches("<tr").count(), 21, "{}", html);
+        assert!(html.contains("Blocks 76–95 of 95"), "{}", html);
+        assert!(html.contains("00000000…00000013"), "{}", html);
+        assert!(html.contains(">pruned<"), "{}", html);
+        assert!(!html.contains("No blocks"), "{}", html);
+    }
+
+    #[test]
+    fn an_empty_chain_says_so() {
+        let html = render(
+            List,
+            ListProps {
+                page: BlocksPage::default(),
+            },
+        );
+        assert!(
+            html.contains("No blocks in the local chain yet."),
+            "{}",
+            html
+        );
+        assert!(!html.contains("explorer-pager"), "{}", html);
+    }
+}
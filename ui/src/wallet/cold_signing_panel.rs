@@ -0,0 +1,122 @@
+use dioxus::prelude::*;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct ColdSigningPanelProps {
+    /// File the package is exported to, signed from, or signatures are imported from
+    pub path: String,
+    pub on_path_change: EventHandler<String>,
+    /// Sign the package at `path` with the default key, writing the signatures beside it
+    pub on_sign: EventHandler<()>,
+    /// An exported package is waiting for its signatures
+    #[props(default)]
+    pub pending: bool,
+    /// Import signatures from `path` and broadcast the pending package
+    pub on_import: EventHandler<()>,
+    /// Abandon the pending package and release its funds
+    pub on_cancel: EventHandler<()>,
+    #[props(default)]
+    pub busy: bool,
+    /// Outcome of the last action
+    #[props(default)]
+    pub message: Option<String>,
+    #[props(default)]
+    pub error: Option<String>,
+}
+
+/// File-based export, offline signing and import of split-signed transactions
+pub fn ColdSigningPanel(props: ColdSigningPanelProps) -> Element {
+    let no_path = props.path.trim().is_empty();
+
+    rsx! {
+        div {
+            class: "cold-signing",
+            h3 { "Offline signing" }
+            ol {
+                class: "cold-signing-steps",
+                li { "Online: fill in the form above and choose Export unsigned to write the package to the file below." }
+                li { "Offline: enter the package file and choose Sign package. Signatures are written next to it." }
+                li { "Online: enter the signatures file and choose Import and broadcast." }
+            }
+            input {
+                placeholder: "/path/to/transaction.nockpkg",
+                aria_label: "Transaction file path",
+                value: "{props.path}",
+                disabled: props.busy,
+                oninput: move |evt| props.on_path_change.call(evt.value()),
+            }
+            div {
+                class: "cold-signing-actions",
+                button {
+                    disabled: props.busy || no_path,
+                    onclick: move |_| props.on_sign.call(()),
+                    "Sign package"
+                }
+                if props.pending {
+                    button {
+                        disabled: props.busy || no_path,
+                        onclick: move |_| props.on_import.call(()),
+                        "Import and broadcast"
+                    }
+                    button {
+                        disabled: props.busy,
+                        onclick: move |_| props.on_cancel.call(()),
+                        "Discard package"
+                    }
+                }
+            }
+            if let Some(message) = props.message.as_ref() {
+                div { class: "cold-signing-message", "{message}" }
+            }
+            if let Some(error) = props.error.as_ref() {
+                div { class: "cold-signing-error", "{error}" }
+            }
+        }
+
+        style { {COLD_SIGNING_CSS} }
+    }
+}
+
+const COLD_SIGNING_CSS: &str = r#"
+.cold-signing {
+    display: flex;
+    flex-direction: column;
+    gap: 8px;
+    margin-top: 24px;
+    background: #f8f9fa;
+    padding: 20px;
+    border-radius: 8px;
+    font-size: 14px;
+    color: #333;
+}
+
+.cold-signing h3 {
+    margin: 0;
+}
+
+.cold-signing-steps {
+    margin: 0;
+    padding-left: 20px;
+    color: #6c757d;
+    font-size: 13px;
+}
+
+.cold-signing input {
+    padding: 6px 8px;
+    border: 1px solid #ccc;
+    border-radius: 4px;
+}
+
+.cold-signing-actions {
+    display: flex;
+    gap: 8px;
+}
+
+.cold-signing-message {
+    color: #28a745;
+    word-break: break-all;
+}
+
+.cold-signing-error {
+    color: #dc3545;
+}
+"#;
@@ -0,0 +1,183 @@
+use api::wallet::network::LogEntry;
+use chrono::{DateTime, Utc};
+use dioxus::prelude::*;
+
+/// Node console lines and scroll state, provided by the layout so they
+/// outlive the Node page
+#[derive(Clone, Copy)]
+pub struct ConsoleState {
+    pub logs: Signal<Vec<LogEntry>>,
+    /// Lines logged before this were loaded from the previous session
+    pub session_started: DateTime<Utc>,
+    pub follow_tail: Signal<bool>,
+    /// Where the console was scrolled to when the Node page was left
+    pub scroll_top: Signal<f64>,
+    /// Most lines kept, the size of the node manager's log buffer
+    max_lines: usize,
+}
+
+impl ConsoleState {
+    /// The previous session's `history`, then `first` to open this one.
+    /// Must be created inside a component, e.g. with `use_context_provider`.
+    pub fn new(mut history: Vec<LogEntry>, first: LogEntry, max_lines: usize) -> Self {
+        let session_started = first.timestamp;
+        history.push(first);
+        let excess = history.len().saturating_sub(max_lines);
+        history.drain(..excess);
+        Self {
+            logs: Signal::new(history),
+            session_started,
+            follow_tail: Signal::new(true),
+            scroll_top: Signal::new(0.0),
+            max_lines,
+        }
+    }
+
+    pub fn push(self, entry: LogEntry) {
+        let mut logs = self.logs;
+        logs.with_mut(|logs| {
+            logs.push(entry);
+            let excess = logs.len().saturating_sub(self.max_lines);
+            logs.drain(..excess);
+        });
+    }
+
+    /// Replace this session's lines with `entries` from the node, keeping
+    /// the previous session's
+    pub fn replace(self, entries: Vec<LogEntry>) {
+        let mut logs = self.logs;
+        logs.with_mut(|logs| {
+            logs.retain(|log| log.timestamp < self.session_started);
+            logs.extend(entries);
+            let excess = logs.len().saturating_sub(self.max_lines);
+            logs.drain(..excess);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use api::testing::fixture_time;
+    use api::wallet::network::{LogLevel, LogSource};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn line(message: &str, at: i64) -> LogEntry {
+        LogEntry {
+            timestamp: fixture_time(at),
+            level: LogLevel::Info,
+            source: LogSource::Node,
+            message: message.to_string(),
+            operation_id: None,
+            repeat: None,
+        }
+    }
+
+    fn messages(state: &ConsoleState) -> Vec<String> {
+        state
+            .logs
+            .peek()
+            .iter()
+            .map(|log| log.message.clone())
+            .collect()
+    }
+
+    /// The console state and the Node page switch, as the app created them
+    type AppState = (ConsoleState, Signal<bool>);
+
+    /// What the test reaches into the app with
+    #[derive(Clone, Default)]
+    struct Handles(Rc<RefCell<Option<AppState>>>);
+
+    /// Layout-like root owning the console state, with the Node page mounted
+    /// while `on_node_page` is set
+    #[component]
+    fn App() -> Element {
+        let handles = use_context::<Handles>();
+        let console = use_context_provider(|| {
+            ConsoleState::new(vec![line("previous session", 1)], line("ready", 10), 5)
+        });
+        let on_node_page = use_signal(|| true);
+        use_hook(|| handles.0.replace(Some((console, on_node_page))));
+        rsx! {
+            if on_node_page() {
+                NodePage {}
+            }
+        }
+    }
+
+    /// Stand-in for the Node page: logs on each mount and, like a user
+    /// scrolling up, stops following the tail
+    #[component]
+    fn NodePage() -> Element {
+        let console = use_context::<ConsoleState>();
+        use_hook(|| {
+            let mounts = console.logs.peek().len();
+            console.push(line(&format!("mount {}", mounts), 20 + mounts as i64));
+            let mut follow_tail = console.follow_tail;
+            follow_tail.set(false);
+            let mut scroll_top = console.scroll_top;
+            scroll_top.set(480.0);
+        });
+        rsx! { "{console.logs.read().len()} lines" }
+    }
+
+    #[test]
+    fn console_state_survives_the_node_page_remounting() {
+        let handles = Handles::default();
+        let mut dom = VirtualDom::new(App).with_root_context(handles.clone());
+        dom.rebuild_in_place();
+        let (console, mut on_node_page) = handles.0.borrow().unwrap();
+
+        dom.in_runtime(|| on_node_page.set(false));
+        dom.render_immediate_to_vec();
+        dom.in_runtime(|| {
+            assert_eq!(messages(&console), ["previous session", "ready", "mount 2"]);
+            assert!(!*console.follow_tail.peek());
+            assert_eq!(*console.scroll_top.peek(), 480.0);
+            on_node_page.set(true);
+        });
+        dom.render_immediate_to_vec();
+        dom.in_runtime(|| {
+            assert_eq!(
+                messages(&console),
+                ["previous session", "ready", "mount 2", "mount 3"]
+            );
+        });
+    }
+
+    fn mounted_app() -> (VirtualDom, ConsoleState) {
+        let handles = Handles::default();
+        let mut dom = VirtualDom::new(App).with_root_context(handles.clone());
+        dom.rebuild_in_place();
+        let (console, _) = handles.0.borrow().unwrap();
+        (dom, console)
+    }
+
+    #[test]
+    fn a_refresh_from_the_node_keeps_the_previous_session() {
+        let (dom, console) = mounted_app();
+        dom.in_runtime(|| {
+            console.replace(vec![line("started", 30), line("peer", 31)]);
+            assert_eq!(messages(&console), ["previous session", "started", "peer"]);
+        });
+    }
+
+    #[test]
+    fn lines_past_the_log_buffer_size_are_dropped_oldest_first() {
+        let (dom, console) = mounted_app();
+        dom.in_runtime(|| {
+            for at in 30..33 {
+                console.push(line(&format!("line {}", at), at));
+            }
+            assert_eq!(
+                messages(&console),
+                ["ready", "mount 2", "line 30", "line 31", "line 32"]
+            );
+            // Once the previous session's lines are gone, a refresh keeps none
+            console.replace((40..47).map(|at| line("new", at)).collect());
+            assert_eq!(messages(&console).len(), 5);
+        });
+    }
+}
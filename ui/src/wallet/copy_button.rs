@@ -0,0 +1,85 @@
+use dioxus::prelude::*;
+
+use crate::clipboard::{self, CONFIRMATION_MS};
+
+#[derive(Clone, PartialEq)]
+enum CopyState {
+    Idle,
+    Copied,
+    Failed(String),
+}
+
+#[derive(Props, Clone, PartialEq)]
+pub struct CopyButtonProps {
+    /// Text put on the clipboard
+    pub text: String,
+    #[props(default = "Copy".to_string())]
+    pub label: String,
+}
+
+/// Copies `text` and briefly confirms it in place of the label
+pub fn CopyButton(props: CopyButtonProps) -> Element {
+    let mut state = use_signal(|| CopyState::Idle);
+    let text = props.text.clone();
+
+    let (class, label, title) = match &*state.read() {
+        CopyState::Idle => ("copy-button", props.label.clone(), props.text.clone()),
+        CopyState::Copied => (
+            "copy-button copied",
+            "Copied ✓".to_string(),
+            props.text.clone(),
+        ),
+        CopyState::Failed(error) => (
+            "copy-button failed",
+            "Copy failed".to_string(),
+            error.clone(),
+        ),
+    };
+
+    rsx! {
+        button {
+            class,
+            title,
+            // Announces "Copied" or the failure when the label changes
+            aria_live: "polite",
+            onclick: move |evt| {
+                // Copy buttons sit inside clickable rows
+                evt.stop_propagation();
+                let text = text.clone();
+                spawn(async move {
+                    match clipboard::copy(&text).await {
+                        Ok(()) => state.set(CopyState::Copied),
+                        Err(e) => state.set(CopyState::Failed(e)),
+                    }
+                    clipboard::delay(CONFIRMATION_MS).await;
+                    state.set(CopyState::Idle);
+                });
+            },
+            "{label}"
+        }
+
+        style { {COPY_BUTTON_CSS} }
+    }
+}
+
+const COPY_BUTTON_CSS: &str = r#"
+.copy-button {
+    padding: 4px 10px;
+    font-size: 12px;
+    border: 1px solid #ccc;
+    border-radius: 4px;
+    background: white;
+    color: #333;
+    cursor: pointer;
+}
+
+.copy-button.copied {
+    border-color: #28a745;
+    color: #28a745;
+}
+
+.copy-button.failed {
+    border-color: #dc3545;
+    color: #dc3545;
+}
+"#;
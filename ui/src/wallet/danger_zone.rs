@@ -0,0 +1,185 @@
+use api::PurgeScope;
+use dioxus::prelude::*;
+
+use crate::Dialog;
+
+/// Text the user must type before a purge is carried out
+const CONFIRM_PHRASE: &str = "purge";
+
+#[derive(Props, Clone, PartialEq)]
+pub struct DangerZoneProps {
+    /// Purging is only possible while the node is stopped
+    pub disabled: bool,
+    pub on_purge: EventHandler<PurgeScope>,
+}
+
+pub fn DangerZone(props: DangerZoneProps) -> Element {
+    let mut pending = use_signal(|| None::<PurgeScope>);
+    let mut confirm_text = use_signal(String::new);
+
+    let confirmed = confirm_text.read().trim() == CONFIRM_PHRASE;
+
+    rsx! {
+        div {
+            class: "danger-zone",
+            h4 { "Danger Zone" }
+            if props.disabled {
+                p { class: "danger-hint", "Stop the node to purge its data." }
+            }
+            div {
+                class: "danger-actions",
+                for scope in [PurgeScope::ChainOnly, PurgeScope::PeersOnly, PurgeScope::Everything] {
+                    button {
+                        key: "{scope:?}",
+                        class: "danger-button",
+                        disabled: props.disabled,
+                        onclick: move |_| {
+                            confirm_text.set(String::new());
+                            pending.set(Some(scope));
+                        },
+                        "{scope_label(scope)}"
+                    }
+                }
+            }
+
+            if let Some(scope) = *pending.read() {
+                Dialog {
+                    title: "{scope_label(scope)}?",
+                    class: "danger-dialog",
+                    on_close: move |_| pending.set(None),
+                    p { "{scope_description(scope)}" }
+                    p { "Type " strong { "{CONFIRM_PHRASE}" } " to confirm." }
+                    input {
+                        aria_label: "Type {CONFIRM_PHRASE} to confirm",
+                        autofocus: true,
+                        value: "{confirm_text}",
+                        oninput: move |evt| confirm_text.set(evt.value()),
+                    }
+                    div {
+                        class: "danger-dialog-buttons",
+                        button {
+                            class: "cancel-button",
+                            onclick: move |_| pending.set(None),
+                            "Cancel"
+                        }
+                        button {
+                            class: "danger-button",
+                            disabled: !confirmed || props.disabled,
+                            onclick: move |_| {
+                                pending.set(None);
+                                props.on_purge.call(scope);
+                            },
+                            "Purge"
+                        }
+                    }
+                }
+            }
+        }
+
+        style { {DANGER_ZONE_CSS} }
+    }
+}
+
+fn scope_label(scope: PurgeScope) -> &'static str {
+    match scope {
+        PurgeScope::ChainOnly => "Purge chain data",
+        PurgeScope::PeersOnly => "Purge peer data",
+        PurgeScope::Everything => "Purge all node data",
+    }
+}
+
+fn scope_description(scope: PurgeScope) -> &'static str {
+    match scope {
+        PurgeScope::ChainOnly => {
+            "Deletes blocks, headers and the UTXO set. The node resyncs from scratch and the wallet rescans its balances."
+        }
+        PurgeScope::PeersOnly => "Forgets known peers and bans. Bootstrap peers are used on next start.",
+        PurgeScope::Everything => {
+            "Deletes everything in the node data directory. Wallet keys are stored elsewhere and are not affected."
+        }
+    }
+}
+
+const DANGER_ZONE_CSS: &str = r#"
+.danger-zone {
+    margin-top: 16px;
+    padding: 16px;
+    border: 1px solid #f5c2c7;
+    border-radius: 8px;
+    background: #fff5f5;
+}
+
+.danger-zone h4 {
+    color: #b02a37;
+    margin: 0 0 8px 0;
+    font-size: 14px;
+}
+
+.danger-hint {
+    color: #6c757d;
+    font-size: 12px;
+    margin: 0 0 8px 0;
+}
+
+.danger-actions {
+    display: flex;
+    flex-wrap: wrap;
+    gap: 8px;
+}
+
+.danger-button {
+    padding: 6px 12px;
+    border: 1px solid #dc3545;
+    border-radius: 4px;
+    background: white;
+    color: #dc3545;
+    cursor: pointer;
+}
+
+.danger-button:hover:not(:disabled) {
+    background: #dc3545;
+    color: white;
+}
+
+.danger-button:disabled {
+    opacity: 0.5;
+    cursor: not-allowed;
+}
+
+.danger-dialog {
+    max-width: 420px;
+}
+
+.danger-dialog h4 {
+    color: #b02a37;
+    margin: 0 0 12px 0;
+}
+
+.danger-dialog p {
+    color: #333;
+    font-size: 14px;
+}
+
+.danger-dialog input {
+    width: 100%;
+    padding: 8px;
+    border: 1px solid #ccc;
+    border-radius: 4px;
+    box-sizing: border-box;
+}
+
+.danger-dialog-buttons {
+    display: flex;
+    justify-content: flex-end;
+    gap: 8px;
+    margin-top: 16px;
+}
+
+.cancel-button {
+    padding: 6px 12px;
+    border: 1px solid #ccc;
+    border-radius: 4px;
+    background: white;
+    cursor: pointer;
+}
+"#;
@@ -0,0 +1,168 @@
+use api::wallet::{NANO_PER_NOCK, NOCK_DECIMALS};
+use api::{format_display, DigitGrouping, DisplaySettings, RoundingMode};
+use dioxus::prelude::*;
+
+/// Amount the preview line formats: 1,234,567.891500 NOCK
+const PREVIEW_AMOUNT: u64 = 1_234_567 * NANO_PER_NOCK + 891_500;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct DisplaySettingsFormProps {
+    /// Settings currently in force
+    pub settings: DisplaySettings,
+    pub on_save: EventHandler<DisplaySettings>,
+    #[props(default)]
+    pub disabled: bool,
+}
+
+/// How amounts are shown: decimals, rounding, trailing zeros and digit grouping
+pub fn DisplaySettingsForm(props: DisplaySettingsFormProps) -> Element {
+    let mut draft = use_signal(|| props.settings);
+
+    let settings = props.settings;
+    use_effect(use_reactive((&settings,), move |(settings,)| {
+        draft.set(settings);
+    }));
+
+    let current = draft();
+
+    rsx! {
+        div {
+            class: "display-settings",
+            h3 { "Display" }
+            label {
+                "Decimal places"
+                select {
+                    disabled: props.disabled,
+                    onchange: move |evt| {
+                        if let Ok(decimals) = evt.value().parse() {
+                            draft.with_mut(|draft| draft.decimals = decimals);
+                        }
+                    },
+                    for decimals in 0..=NOCK_DECIMALS {
+                        option {
+                            value: "{decimals}",
+                            selected: current.decimals == decimals,
+                            "{decimals}"
+                        }
+                    }
+                }
+            }
+            label {
+                "Rounding"
+                select {
+                    disabled: props.disabled,
+                    onchange: move |evt| {
+                        let rounding = RoundingMode::ALL
+                            .into_iter()
+                            .find(|mode| mode.label() == evt.value());
+                        if let Some(rounding) = rounding {
+                            draft.with_mut(|draft| draft.rounding = rounding);
+                        }
+                    },
+                    for mode in RoundingMode::ALL {
+                        option {
+                            value: "{mode.label()}",
+                            selected: current.rounding == mode,
+                            "{mode.label()}"
+                        }
+                    }
+                }
+            }
+            label {
+                "Digit grouping"
+                select {
+                    disabled: props.disabled,
+                    onchange: move |evt| {
+                        let grouping = DigitGrouping::ALL
+                            .into_iter()
+                            .find(|grouping| grouping.label() == evt.value());
+                        if let Some(grouping) = grouping {
+                            draft.with_mut(|draft| draft.grouping = grouping);
+                        }
+                    },
+                    for grouping in DigitGrouping::ALL {
+                        option {
+                            value: "{grouping.label()}",
+                            selected: current.grouping == grouping,
+                            "{grouping.label()}"
+                        }
+                    }
+                }
+            }
+            label {
+                class: "display-settings-check",
+                input {
+                    r#type: "checkbox",
+                    checked: current.trailing_zeros,
+                    disabled: props.disabled,
+                    onchange: move |evt| draft.with_mut(|draft| draft.trailing_zeros = evt.checked()),
+                }
+                "Show trailing zeros"
+            }
+            div {
+                class: "display-settings-preview",
+                "Preview: {format_display(PREVIEW_AMOUNT, &current)} NOCK"
+            }
+            div {
+                class: "display-settings-note",
+                "Exports and receipts always keep full precision."
+            }
+            button {
+                disabled: props.disabled || current == props.settings,
+                onclick: move |_| props.on_save.call(current),
+                "Save display settings"
+            }
+        }
+
+        style { {DISPLAY_SETTINGS_CSS} }
+    }
+}
+
+const DISPLAY_SETTINGS_CSS: &str = r#"
+.display-settings {
+    display: flex;
+    flex-direction: column;
+    gap: 12px;
+    background: #f8f9fa;
+    padding: 20px;
+    border-radius: 8px;
+    margin-bottom: 24px;
+    font-size: 14px;
+    color: #333;
+}
+
+.display-settings h3 {
+    margin: 0;
+}
+
+.display-settings label {
+    display: flex;
+    flex-direction: column;
+    gap: 4px;
+}
+
+.display-settings label.display-settings-check {
+    flex-direction: row;
+    align-items: center;
+    gap: 8px;
+}
+
+.display-settings select {
+    align-self: flex-start;
+    padding: 4px 8px;
+}
+
+.display-settings-preview {
+    font-family: monospace;
+}
+
+.display-settings-note {
+    color: #6c757d;
+    font-size: 12px;
+}
+
+.display-settings button {
+    align-self: flex-start;
+    padding: 6px 14px;
+}
+"#;
@@ -0,0 +1,98 @@
+use chrono::{DateTime, Utc};
+use dioxus::prelude::*;
+
+use crate::i18n::{current_locale, display_now};
+use crate::t;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct DraftBannerProps {
+    /// When the restored draft was saved
+    pub saved_at: DateTime<Utc>,
+    /// Hide the banner, keeping the restored fields
+    pub on_dismiss: EventHandler<()>,
+    /// Clear the form and delete the draft
+    pub on_discard: EventHandler<()>,
+}
+
+/// Shown above the send form after it was filled from an autosaved draft
+pub fn DraftBanner(props: DraftBannerProps) -> Element {
+    let age = current_locale().format_age(&props.saved_at, &display_now());
+
+    rsx! {
+        div {
+            class: "draft-banner",
+            role: "status",
+            span { class: "draft-banner-text", {t!("send.draft_restored", age = age)} }
+            button {
+                class: "draft-banner-discard",
+                onclick: move |_| props.on_discard.call(()),
+                {t!("send.draft_discard")}
+            }
+            button {
+                class: "draft-banner-dismiss",
+                onclick: move |_| props.on_dismiss.call(()),
+                {t!("send.draft_dismiss")}
+            }
+        }
+
+        style { {DRAFT_BANNER_CSS} }
+    }
+}
+
+const DRAFT_BANNER_CSS: &str = r#"
+.draft-banner {
+    display: flex;
+    flex-wrap: wrap;
+    align-items: center;
+    gap: 12px;
+    padding: 10px 16px;
+    margin-bottom: 16px;
+    background: #e7f1ff;
+    border: 1px solid #b6d4fe;
+    border-radius: 8px;
+    color: #084298;
+    font-size: 14px;
+}
+
+.draft-banner-text {
+    flex: 1;
+}
+
+.draft-banner button {
+    min-height: 36px;
+    padding: 6px 14px;
+    background: white;
+    color: #084298;
+    border: 1px solid #b6d4fe;
+    border-radius: 4px;
+    cursor: pointer;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{html, settle};
+
+    #[component]
+    fn Restored() -> Element {
+        rsx! {
+            DraftBanner {
+                saved_at: Utc::now() - chrono::Duration::minutes(5),
+                on_dismiss: move |_| {},
+                on_discard: move |_| {},
+            }
+        }
+    }
+
+    #[test]
+    fn the_banner_says_how_old_the_draft_is() {
+        let mut dom = VirtualDom::new(Restored);
+        dom.rebuild_in_place();
+        settle(&mut dom);
+        let html = html(&dom);
+        assert!(html.contains("Restored draft from 5 minutes ago"));
+        assert!(html.contains(&t!("send.draft_discard")));
+        assert!(html.contains(&t!("send.draft_dismiss")));
+    }
+}
@@ -0,0 +1,60 @@
+use api::{format_amount, AmountUnit, PaymentTemplate};
+use dioxus::prelude::*;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct DuePaymentsCardProps {
+    /// Templates whose reminder has come due, most overdue first
+    pub due: Vec<PaymentTemplate>,
+    /// Called with the template name to start paying it
+    pub on_pay: EventHandler<String>,
+}
+
+/// Reminders for recurring payments that are due; renders nothing when none are
+pub fn DuePaymentsCard(props: DuePaymentsCardProps) -> Element {
+    if props.due.is_empty() {
+        return rsx! {};
+    }
+
+    rsx! {
+        div {
+            class: "due-payments",
+            for template in props.due.iter().cloned() {
+                div {
+                    key: "{template.name}",
+                    class: "due-payment",
+                    span {
+                        strong { "{template.name} payment due" }
+                        " · {format_amount(template.amount, AmountUnit::Nock)} NOCK"
+                    }
+                    button {
+                        onclick: move |_| props.on_pay.call(template.name.clone()),
+                        "Pay"
+                    }
+                }
+            }
+        }
+
+        style { {DUE_PAYMENTS_CSS} }
+    }
+}
+
+const DUE_PAYMENTS_CSS: &str = r#"
+.due-payments {
+    display: flex;
+    flex-direction: column;
+    gap: 8px;
+    margin-bottom: 24px;
+}
+
+.due-payment {
+    display: flex;
+    align-items: center;
+    justify-content: space-between;
+    padding: 12px 16px;
+    background: #fff3cd;
+    border: 1px solid #ffe69c;
+    border-radius: 8px;
+    color: #664d03;
+    font-size: 14px;
+}
+"#;
@@ -0,0 +1,119 @@
+use api::ExportFormat;
+use dioxus::prelude::*;
+
+use crate::format::format_count;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct ExportPanelProps {
+    /// What is exported, e.g. "transaction history"
+    pub subject: String,
+    /// Start an export; the caller asks where to save it
+    pub on_export: EventHandler<ExportFormat>,
+    /// Rows written so far while an export runs
+    #[props(default)]
+    pub rows: Option<u64>,
+    /// Rows the running export will write, if known
+    #[props(default)]
+    pub total: Option<u64>,
+    /// Stop the running export, leaving a file marked incomplete
+    #[props(default)]
+    pub on_cancel: Option<EventHandler<()>>,
+    /// Outcome of the last export
+    #[props(default)]
+    pub message: Option<String>,
+}
+
+/// Buttons to export as CSV or JSON, and a progress bar with a cancel button
+/// while an export runs
+pub fn ExportPanel(props: ExportPanelProps) -> Element {
+    let subject = props.subject.clone();
+
+    rsx! {
+        div {
+            class: "export-panel",
+            if let Some(rows) = props.rows {
+                div {
+                    class: "export-progress",
+                    match props.total.filter(|total| *total > 0) {
+                        Some(total) => rsx! {
+                            progress {
+                                max: "{total}",
+                                value: "{rows.min(total)}",
+                                aria_label: "Exporting {subject}",
+                            }
+                            span { "{format_count(rows)} of {format_count(total)} rows" }
+                        },
+                        None => rsx! {
+                            progress { aria_label: "Exporting {subject}" }
+                            span { "{format_count(rows)} rows" }
+                        },
+                    }
+                    if let Some(on_cancel) = props.on_cancel {
+                        button {
+                            class: "export-cancel",
+                            onclick: move |_| on_cancel.call(()),
+                            "Cancel"
+                        }
+                    }
+                }
+            } else {
+                span { class: "export-title", "Export {subject}" }
+                for format in [ExportFormat::Csv, ExportFormat::Json] {
+                    button {
+                        key: "{format}",
+                        class: "export-button",
+                        onclick: move |_| props.on_export.call(format),
+                        "{format}"
+                    }
+                }
+            }
+            if let Some(message) = props.message.as_ref() {
+                div { class: "export-message", "{message}" }
+            }
+        }
+
+        style { {EXPORT_PANEL_CSS} }
+    }
+}
+
+const EXPORT_PANEL_CSS: &str = r#"
+.export-panel {
+    display: flex;
+    flex-wrap: wrap;
+    align-items: center;
+    gap: 8px;
+    margin: 12px 0;
+    font-size: 14px;
+}
+
+.export-title {
+    color: #6c757d;
+}
+
+.export-button,
+.export-cancel {
+    padding: 4px 12px;
+    border: 1px solid #ccc;
+    border-radius: 4px;
+    background: white;
+    cursor: pointer;
+}
+
+.export-progress {
+    display: flex;
+    align-items: center;
+    gap: 8px;
+    flex: 1;
+}
+
+.export-progress progress {
+    flex: 1;
+    max-width: 320px;
+}
+
+.export-message {
+    flex-basis: 100%;
+    color: #333;
+    word-break: break-all;
+}
+"#;
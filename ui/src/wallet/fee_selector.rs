@@ -0,0 +1,311 @@
+use api::{format_display, BlockFees, DisplaySettings, FeePriority};
+use dioxus::prelude::*;
+
+use super::send_form::fee_label;
+use crate::t;
+
+/// Bars taller than this many times the typical median are cut off, so one
+/// outlier block doesn't flatten the rest of the chart
+const CHART_CLIP: f64 = 3.0;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct FeeSelectorProps {
+    pub priority: FeePriority,
+    /// Fee each priority would pay, in base units
+    pub fees: Vec<(FeePriority, u64)>,
+    /// Whether the fees were suggested from recent blocks rather than the
+    /// static defaults
+    #[props(default)]
+    pub from_history: bool,
+    /// Recent blocks, oldest first, charted by median fee rate
+    #[props(default)]
+    pub history: Vec<BlockFees>,
+    pub on_change: EventHandler<FeePriority>,
+    #[props(default)]
+    pub disabled: bool,
+    #[props(default)]
+    pub display: DisplaySettings,
+}
+
+/// Fee priority picker for the Send page, with a bar per recent block
+/// showing the median fee rate it confirmed
+pub fn FeeSelector(props: FeeSelectorProps) -> Element {
+    let bars = chart_bars(&props.history);
+    let block_count = props.history.len();
+
+    rsx! {
+        div {
+            class: "fee-selector",
+            div {
+                class: "fee-options",
+                for (priority, fee) in props.fees.iter().copied() {
+                    button {
+                        key: "{priority.label()}",
+                        class: if priority == props.priority { "fee-option active" } else { "fee-option" },
+                        disabled: props.disabled,
+                        onclick: move |_| props.on_change.call(priority),
+                        span { class: "fee-option-label", {fee_label(priority)} }
+                        span { class: "fee-option-amount", "{format_display(fee, &props.display)} NOCK" }
+                    }
+                }
+            }
+            if !bars.is_empty() {
+                div {
+                    class: "fee-chart",
+                    title: t!("send.fee.chart", count = block_count),
+                    for (height, bar) in bars {
+                        match bar {
+                            Some((percent, rate)) => rsx! {
+                                div {
+                                    key: "{height}",
+                                    class: "fee-bar",
+                                    style: "height: {percent:.0}%;",
+                                    title: t!("send.fee.bar", height = height, rate = format!("{rate:.2}")),
+                                }
+                            },
+                            None => rsx! {
+                                div {
+                                    key: "{height}",
+                                    class: "fee-bar empty",
+                                    title: t!("send.fee.bar_empty", height = height),
+                                }
+                            },
+                        }
+                    }
+                }
+            }
+            div {
+                class: "fee-source",
+                if props.from_history {
+                    {t!("send.fee.from_history", count = block_count)}
+                } else {
+                    {t!("send.fee.fallback")}
+                }
+            }
+        }
+
+        style { {FEE_SELECTOR_CSS} }
+    }
+}
+
+/// Height, and for blocks with transactions the bar height in percent and
+/// the median rate, per block
+fn chart_bars(history: &[BlockFees]) -> Vec<(u64, Option<(f64, f64)>)> {
+    let mut medians: Vec<f64> = history
+        .iter()
+        .filter_map(|block| block.rates.map(|rates| rates.median))
+        .collect();
+    medians.sort_by(f64::total_cmp);
+    let typical = medians.get(medians.len() / 2).copied().unwrap_or_default();
+    let top = medians
+        .last()
+        .copied()
+        .unwrap_or_default()
+        .min(typical * CHART_CLIP);
+
+    history
+        .iter()
+        .map(|block| {
+            let bar = block.rates.map(|rates| {
+                let percent = if top > 0.0 {
+                    (rates.median / top).min(1.0) * 100.0
+                } else {
+                    0.0
+                };
+                // Keep a sliver visible for blocks that paid nothing
+                (percent.max(4.0), rates.median)
+            });
+            (block.height, bar)
+        })
+        .collect()
+}
+
+const FEE_SELECTOR_CSS: &str = r#"
+.fee-selector {
+    display: flex;
+    flex-direction: column;
+    gap: 8px;
+    margin-bottom: 16px;
+}
+
+.fee-options {
+    display: flex;
+    flex-wrap: wrap;
+    gap: 8px;
+}
+
+.fee-option {
+    display: flex;
+    flex-direction: column;
+    align-items: flex-start;
+    gap: 2px;
+    min-height: 44px;
+    padding: 6px 12px;
+    background: white;
+    border: 1px solid #ccc;
+    border-radius: 6px;
+    cursor: pointer;
+}
+
+.fee-option.active {
+    border-color: #667eea;
+    box-shadow: 0 0 0 1px #667eea;
+}
+
+.fee-option:disabled {
+    opacity: 0.6;
+    cursor: default;
+}
+
+.fee-option-label {
+    font-size: 13px;
+    font-weight: 600;
+}
+
+.fee-option-amount {
+    font-family: monospace;
+    font-size: 12px;
+    color: #6c757d;
+}
+
+.fee-chart {
+    display: flex;
+    align-items: flex-end;
+    gap: 1px;
+    height: 32px;
+    max-width: 320px;
+}
+
+.fee-bar {
+    flex: 1;
+    min-width: 2px;
+    background: #667eea;
+    border-radius: 1px 1px 0 0;
+}
+
+.fee-bar.empty {
+    height: 2px;
+    background: #d0d5dd;
+}
+
+.fee-source {
+    font-size: 12px;
+    color: #6c757d;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::render;
+    use api::FeeRates;
+
+    fn block(height: u64, median: Option<f64>) -> BlockFees {
+        BlockFees {
+            height,
+            tx_count: usize::from(median.is_some()),
+            rates: median.map(|median| FeeRates {
+                p25: median,
+                median,
+                p90: median,
+                median_size: 100,
+            }),
+        }
+    }
+
+    #[test]
+    fn bars_scale_to_the_highest_median() {
+        let bars = chart_bars(&[
+            block(1, Some(2.0)),
+            block(2, Some(4.0)),
+            block(3, Some(3.0)),
+        ]);
+        assert_eq!(
+            bars,
+            vec![
+                (1, Some((50.0, 2.0))),
+                (2, Some((100.0, 4.0))),
+                (3, Some((75.0, 3.0))),
+            ]
+        );
+    }
+
+    #[test]
+    fn an_outlier_is_clipped_so_the_rest_stay_readable() {
+        let bars = chart_bars(&[
+            block(1, Some(3.0)),
+            block(2, Some(4.0)),
+            block(3, Some(4.0)),
+            block(4, Some(600.0)),
+        ]);
+        // The top is 3x the typical 4.0, not the outlier
+        assert_eq!(bars[0].1, Some((25.0, 3.0)));
+        assert_eq!(bars[3].1, Some((100.0, 600.0)));
+    }
+
+    #[test]
+    fn empty_and_free_blocks_still_get_a_bar() {
+        let bars = chart_bars(&[block(1, None), block(2, Some(0.0)), block(3, Some(5.0))]);
+        assert_eq!(bars[0], (1, None));
+        assert_eq!(bars[1], (2, Some((4.0, 0.0))));
+        assert!(chart_bars(&[]).is_empty());
+        assert_eq!(
+            chart_bars(&[block(1, Some(0.0))]),
+            vec![(1, Some((4.0, 0.0)))]
+        );
+    }
+
+    #[component]
+    fn Selector(from_history: bool, history: Vec<BlockFees>) -> Element {
+        rsx! {
+            FeeSelector {
+                priority: FeePriority::High,
+                fees: vec![
+                    (FeePriority::Low, 250),
+                    (FeePriority::Normal, 500),
+                    (FeePriority::High, 900),
+                ],
+                from_history,
+                history,
+                on_change: |_| {},
+            }
+        }
+    }
+
+    #[test]
+    fn the_selector_charts_the_history_it_was_given() {
+        let html = render(
+            Selector,
+            SelectorProps {
+                from_history: true,
+                history: vec![block(41, Some(2.5)), block(42, None)],
+            },
+        );
+        assert!(html.contains("fee-option active"), "{}", html);
+        assert!(html.contains("High fee"), "{}", html);
+        assert!(html.contains("Block 41: median 2.50 per byte"), "{}", html);
+        assert!(html.contains("Block 42: no transactions"), "{}", html);
+        assert!(
+            html.contains("Suggested from the last 2 blocks"),
+            "{}",
+            html
+        );
+    }
+
+    #[test]
+    fn without_history_there_is_no_chart_and_the_defaults_say_so() {
+        let html = render(
+            Selector,
+            SelectorProps {
+                from_history: false,
+                history: Vec::new(),
+            },
+        );
+        assert!(!html.contains("fee-chart"), "{}", html);
+        assert!(
+            html.contains("Default fees until enough blocks have been seen"),
+            "{}",
+            html
+        );
+    }
+}
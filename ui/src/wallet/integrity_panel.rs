@@ -0,0 +1,180 @@
+use api::{IntegrityReport, ReconciliationReport, Severity};
+use dioxus::prelude::*;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct IntegrityPanelProps {
+    /// Result of the last check, if one has run
+    #[props(default)]
+    pub report: Option<IntegrityReport>,
+    pub on_verify: EventHandler<()>,
+    /// Trust the files as they are now; without it the option isn't offered
+    #[props(default)]
+    pub on_accept: Option<EventHandler<()>>,
+    #[props(default)]
+    pub busy: bool,
+    /// Check of the cached balances against the notes, run with the last
+    /// verify
+    #[props(default)]
+    pub balances: Option<ReconciliationReport>,
+}
+
+/// Checksum check of the wallet's files, with the problems it found
+pub fn IntegrityPanel(props: IntegrityPanelProps) -> Element {
+    let problems: Vec<_> = props
+        .report
+        .iter()
+        .flat_map(|report| report.problems())
+        .cloned()
+        .collect();
+    let summary = props.report.as_ref().map(|report| {
+        let checked = report.checked_at.format("%Y-%m-%d %H:%M UTC");
+        match report.severity() {
+            None => format!(
+                "All {} files match their checksums · checked {}",
+                report.files.len(),
+                checked
+            ),
+            Some(severity) => format!(
+                "{} problem(s), worst: {} · checked {}",
+                problems.len(),
+                severity,
+                checked
+            ),
+        }
+    });
+    let balance_summary = props.balances.as_ref().map(|report| {
+        if report.is_consistent() {
+            format!(
+                "Balances of {} address(es) match their {} notes",
+                report.addresses, report.notes
+            )
+        } else if report.repaired {
+            format!(
+                "Balances of {} address(es) didn't match their notes and were rebuilt from them",
+                report.discrepancies.len()
+            )
+        } else {
+            format!(
+                "Balances of {} address(es) don't match their notes",
+                report.discrepancies.len()
+            )
+        }
+    });
+    let ledger_summary = props
+        .report
+        .as_ref()
+        .and_then(|report| report.ledger.as_ref())
+        .map(|check| check.to_string());
+    let needs_accept = props
+        .report
+        .as_ref()
+        .is_some_and(|report| report.severity() >= Some(Severity::Warning));
+
+    rsx! {
+        div {
+            class: "integrity-panel",
+            h3 { "Wallet integrity" }
+            if let Some(summary) = summary {
+                div { class: "integrity-summary", "{summary}" }
+            }
+            if let Some(summary) = balance_summary {
+                div { class: "integrity-summary", "{summary}" }
+            }
+            if let Some(summary) = ledger_summary {
+                div { class: "integrity-summary", "{summary}" }
+            }
+            if !problems.is_empty() {
+                table {
+                    tbody {
+                        for check in problems {
+                            tr {
+                                key: "{check.file}",
+                                td { class: "integrity-severity {severity_class(check.severity)}", "{check.severity}" }
+                                td { class: "integrity-file", "{check.file}" }
+                                td { "{check.status}" }
+                            }
+                        }
+                    }
+                }
+            }
+            div {
+                class: "integrity-actions",
+                button {
+                    disabled: props.busy,
+                    onclick: move |_| props.on_verify.call(()),
+                    "Verify wallet"
+                }
+                if let (Some(on_accept), true) = (props.on_accept, needs_accept) {
+                    button {
+                        disabled: props.busy,
+                        title: "Record the current files as correct. Files that can't be read stay flagged.",
+                        onclick: move |_| on_accept.call(()),
+                        "Trust current files"
+                    }
+                }
+            }
+        }
+
+        style { {INTEGRITY_PANEL_CSS} }
+    }
+}
+
+fn severity_class(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Info => "info",
+        Severity::Warning => "warning",
+        Severity::Critical => "critical",
+    }
+}
+
+const INTEGRITY_PANEL_CSS: &str = r#"
+.integrity-panel {
+    display: flex;
+    flex-direction: column;
+    gap: 12px;
+    background: #f8f9fa;
+    padding: 20px;
+    border-radius: 8px;
+    margin-bottom: 24px;
+    font-size: 14px;
+    color: #333;
+}
+
+.integrity-panel h3 {
+    margin: 0;
+}
+
+.integrity-summary {
+    color: #6c757d;
+}
+
+.integrity-panel td {
+    padding: 4px 12px 4px 0;
+}
+
+.integrity-file {
+    font-family: monospace;
+}
+
+.integrity-severity.info {
+    color: #6c757d;
+}
+
+.integrity-severity.warning {
+    color: #b58105;
+}
+
+.integrity-severity.critical {
+    color: #dc3545;
+    font-weight: 600;
+}
+
+.integrity-actions {
+    display: flex;
+    gap: 8px;
+}
+
+.integrity-actions button {
+    padding: 6px 14px;
+}
+"#;
@@ -0,0 +1,115 @@
+use api::{KeyImportOutcome, KeyImportReport};
+use dioxus::prelude::*;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct KeyImportResultsProps {
+    pub report: KeyImportReport,
+}
+
+/// Outcome of every entry of a key import file
+pub fn KeyImportResults(props: KeyImportResultsProps) -> Element {
+    let report = props.report;
+
+    rsx! {
+        div {
+            class: "key-import-results",
+            p {
+                class: "key-import-summary",
+                "{report.imported()} imported, {report.skipped()} skipped, {report.failed()} failed"
+            }
+            if !report.entries.is_empty() {
+                table {
+                    thead {
+                        tr {
+                            th { "Name" }
+                            th { "Address" }
+                            th { "Result" }
+                        }
+                    }
+                    tbody {
+                        for (index, entry) in report.entries.iter().enumerate() {
+                            tr {
+                                key: "{index}",
+                                td { "{entry.name}" }
+                                td {
+                                    class: "key-import-address",
+                                    if let Some(address) = entry.address.as_ref() {
+                                        "{address}"
+                                    } else {
+                                        "—"
+                                    }
+                                }
+                                match &entry.outcome {
+                                    KeyImportOutcome::Imported => rsx! {
+                                        td { class: "key-import-outcome imported", "Imported" }
+                                    },
+                                    KeyImportOutcome::SkippedDuplicate(reason) => rsx! {
+                                        td { class: "key-import-outcome skipped", "Skipped: {reason}" }
+                                    },
+                                    KeyImportOutcome::Failed(reason) => rsx! {
+                                        td { class: "key-import-outcome failed", "Failed: {reason}" }
+                                    },
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        style { {KEY_IMPORT_RESULTS_CSS} }
+    }
+}
+
+const KEY_IMPORT_RESULTS_CSS: &str = r#"
+.key-import-results {
+    background: #f8f9fa;
+    padding: 16px 20px;
+    border-radius: 8px;
+    margin-top: 16px;
+    overflow-x: auto;
+}
+
+.key-import-summary {
+    font-weight: 600;
+    color: #333;
+    margin: 0 0 12px 0;
+}
+
+.key-import-results table {
+    width: 100%;
+    border-collapse: collapse;
+    font-size: 13px;
+}
+
+.key-import-results th {
+    text-align: left;
+    color: #666;
+    font-weight: 600;
+    padding: 6px 8px;
+    border-bottom: 1px solid #dee2e6;
+}
+
+.key-import-results td {
+    padding: 6px 8px;
+    border-bottom: 1px solid #eef0f2;
+    color: #333;
+}
+
+.key-import-address {
+    font-family: monospace;
+    word-break: break-all;
+}
+
+.key-import-outcome.imported {
+    color: #28a745;
+}
+
+.key-import-outcome.skipped {
+    color: #b8860b;
+}
+
+.key-import-outcome.failed {
+    color: #dc3545;
+}
+"#;
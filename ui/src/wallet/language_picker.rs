@@ -0,0 +1,55 @@
+use crate::i18n::Locale;
+use crate::t;
+use dioxus::prelude::*;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct LanguagePickerProps {
+    /// Language currently in force
+    pub value: Locale,
+    pub on_change: EventHandler<Locale>,
+}
+
+/// Language the UI is shown in, each listed under its own name
+pub fn LanguagePicker(props: LanguagePickerProps) -> Element {
+    rsx! {
+        label {
+            class: "language-picker",
+            {t!("settings.language")}
+            select {
+                onchange: move |evt| {
+                    if let Some(locale) = Locale::from_code(&evt.value()) {
+                        props.on_change.call(locale);
+                    }
+                },
+                for locale in Locale::ALL {
+                    option {
+                        value: "{locale.code()}",
+                        selected: props.value == locale,
+                        "{locale.native_name()}"
+                    }
+                }
+            }
+        }
+
+        style { {LANGUAGE_PICKER_CSS} }
+    }
+}
+
+const LANGUAGE_PICKER_CSS: &str = r#"
+.language-picker {
+    display: flex;
+    flex-direction: column;
+    gap: 4px;
+    background: #f8f9fa;
+    padding: 20px;
+    border-radius: 8px;
+    margin-bottom: 24px;
+    font-size: 14px;
+    color: #333;
+}
+
+.language-picker select {
+    align-self: flex-start;
+    padding: 4px 8px;
+}
+"#;
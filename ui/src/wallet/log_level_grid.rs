@@ -0,0 +1,141 @@
+use api::wallet::network::{LogLevel, LogSource};
+use api::LogLevels;
+use dioxus::prelude::*;
+
+use super::node_console::{format_log_level, format_log_source, get_log_level_class};
+
+/// Option value meaning a source follows the default level
+const INHERIT: &str = "default";
+
+#[derive(Props, Clone, PartialEq)]
+pub struct LogLevelGridProps {
+    /// Levels currently applied by the node
+    pub levels: LogLevels,
+    /// Called with the full set of levels whenever one of them changes
+    pub on_change: EventHandler<LogLevels>,
+}
+
+pub fn LogLevelGrid(props: LogLevelGridProps) -> Element {
+    let default_levels = props.levels.clone();
+    let on_default_change = props.on_change;
+
+    rsx! {
+        div {
+            class: "log-level-grid",
+            div {
+                class: "log-level-cell default",
+                label { "Default" }
+                select {
+                    onchange: move |evt| {
+                        if let Some(level) = parse_level(&evt.value()) {
+                            let mut levels = default_levels.clone();
+                            levels.default = level;
+                            on_default_change.call(levels);
+                        }
+                    },
+                    for level in LogLevel::ALL {
+                        option {
+                            value: get_log_level_class(&level),
+                            selected: level == props.levels.default,
+                            "{format_log_level(&level)}"
+                        }
+                    }
+                }
+            }
+
+            for source in LogSource::ALL {
+                {
+                    let levels = props.levels.clone();
+                    let on_change = props.on_change;
+                    let current = props.levels.sources.get(&source).copied();
+                    rsx! {
+                        div {
+                            key: "{format_log_source(&source)}",
+                            class: "log-level-cell",
+                            label { "{format_log_source(&source)}" }
+                            select {
+                                onchange: move |evt| {
+                                    let mut levels = levels.clone();
+                                    match parse_level(&evt.value()) {
+                                        Some(level) => levels.sources.insert(source, level),
+                                        None => levels.sources.remove(&source),
+                                    };
+                                    on_change.call(levels);
+                                },
+                                option {
+                                    value: INHERIT,
+                                    selected: current.is_none(),
+                                    "Default ({format_log_level(&props.levels.default)})"
+                                }
+                                for level in LogLevel::ALL {
+                                    option {
+                                        value: get_log_level_class(&level),
+                                        selected: current == Some(level),
+                                        "{format_log_level(&level)}"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        style { {LOG_LEVEL_GRID_CSS} }
+    }
+}
+
+fn parse_level(value: &str) -> Option<LogLevel> {
+    LogLevel::ALL
+        .into_iter()
+        .find(|level| get_log_level_class(level) == value)
+}
+
+const LOG_LEVEL_GRID_CSS: &str = r#"
+.log-level-grid {
+    display: grid;
+    grid-template-columns: repeat(auto-fill, minmax(150px, 1fr));
+    gap: 8px 16px;
+    background: #f8f9fa;
+    padding: 16px;
+    border-radius: 8px;
+    margin-bottom: 16px;
+}
+
+.log-level-cell {
+    display: flex;
+    flex-direction: column;
+    gap: 4px;
+}
+
+.log-level-cell label {
+    font-size: 11px;
+    font-weight: 600;
+    color: #6c757d;
+    text-transform: uppercase;
+}
+
+.log-level-cell.default label {
+    color: #333;
+}
+
+.log-level-cell select {
+    padding: 4px 8px;
+    border: 1px solid #ccc;
+    border-radius: 4px;
+    background: white;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_level_option_parses_back_and_default_inherits() {
+        for level in LogLevel::ALL {
+            assert_eq!(parse_level(get_log_level_class(&level)), Some(level));
+        }
+        assert_eq!(parse_level(INHERIT), None);
+    }
+}
@@ -0,0 +1,117 @@
+use api::MaintenanceReport;
+use dioxus::prelude::*;
+
+use crate::format::format_bytes;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct MaintenancePanelProps {
+    /// Result of the last cleanup, if one has run this session
+    #[props(default)]
+    pub report: Option<MaintenanceReport>,
+    pub on_run: EventHandler<()>,
+    /// Why the last cleanup failed
+    #[props(default)]
+    pub error: Option<String>,
+    #[props(default)]
+    pub busy: bool,
+    #[props(default)]
+    pub disabled: bool,
+}
+
+/// Pruning of old logs, fee history, audit entries and settled transactions
+/// under the retention policy, with what the last run removed
+pub fn MaintenancePanel(props: MaintenancePanelProps) -> Element {
+    let summary = props.report.as_ref().map(|report| {
+        format!(
+            "Reclaimed {} · ran {}",
+            format_bytes(report.bytes_reclaimed()),
+            report.ran_at.format("%Y-%m-%d %H:%M UTC")
+        )
+    });
+    let pruned: Vec<_> = props
+        .report
+        .iter()
+        .flat_map(|report| &report.categories)
+        .filter(|pruned| pruned.removed > 0)
+        .copied()
+        .collect();
+
+    rsx! {
+        div {
+            class: "maintenance-panel",
+            h3 { "Data retention" }
+            div {
+                class: "maintenance-hint",
+                "Keys, notes and pending transactions are never removed."
+            }
+            if let Some(summary) = summary {
+                div { class: "maintenance-summary", "{summary}" }
+            }
+            if !pruned.is_empty() {
+                table {
+                    tbody {
+                        for pruned in pruned {
+                            tr {
+                                key: "{pruned.category}",
+                                td { "{pruned.removed} {pruned.category}" }
+                                td { class: "maintenance-bytes", "{format_bytes(pruned.bytes)}" }
+                            }
+                        }
+                    }
+                }
+            }
+            if let Some(error) = props.error.as_ref() {
+                div { class: "maintenance-error", "{error}" }
+            }
+            div {
+                button {
+                    disabled: props.busy || props.disabled,
+                    onclick: move |_| props.on_run.call(()),
+                    if props.busy { "Cleaning up…" } else { "Run cleanup now" }
+                }
+            }
+        }
+
+        style { {MAINTENANCE_PANEL_CSS} }
+    }
+}
+
+const MAINTENANCE_PANEL_CSS: &str = r#"
+.maintenance-panel {
+    display: flex;
+    flex-direction: column;
+    gap: 12px;
+    background: #f8f9fa;
+    padding: 20px;
+    border-radius: 8px;
+    margin-bottom: 24px;
+    font-size: 14px;
+    color: #333;
+}
+
+.maintenance-panel h3 {
+    margin: 0;
+}
+
+.maintenance-hint,
+.maintenance-summary {
+    color: #6c757d;
+}
+
+.maintenance-panel td {
+    padding: 4px 12px 4px 0;
+}
+
+.maintenance-bytes {
+    text-align: right;
+    font-variant-numeric: tabular-nums;
+}
+
+.maintenance-error {
+    color: #dc3545;
+}
+
+.maintenance-panel button {
+    padding: 6px 14px;
+}
+"#;
@@ -0,0 +1,179 @@
+use api::MiningReward;
+use dioxus::prelude::*;
+
+use super::balance_card::format_balance;
+use crate::format::format_count;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct MiningPanelProps {
+    /// Payouts of blocks this wallet mined, oldest first
+    pub rewards: Vec<MiningReward>,
+    /// Current chain height, used to tell mature payouts from immature ones
+    #[props(default)]
+    pub tip_height: Option<u64>,
+}
+
+pub fn MiningPanel(props: MiningPanelProps) -> Element {
+    let total: u64 = props.rewards.iter().map(|reward| reward.amount).sum();
+    let mature: u64 = props
+        .rewards
+        .iter()
+        .filter(|reward| reward.is_mature(props.tip_height))
+        .map(|reward| reward.amount)
+        .sum();
+    let immature = total - mature;
+
+    rsx! {
+        div {
+            class: "mining-panel",
+            h4 { "Mining rewards" }
+            div {
+                class: "mining-totals",
+                div {
+                    class: "mining-total",
+                    span { class: "mining-total-label", "Blocks" }
+                    span { class: "mining-total-value", "{format_count(props.rewards.len() as u64)}" }
+                }
+                div {
+                    class: "mining-total",
+                    span { class: "mining-total-label", "Total" }
+                    span { class: "mining-total-value", "{format_balance(total)} NOCK" }
+                }
+                div {
+                    class: "mining-total",
+                    span { class: "mining-total-label", "Spendable" }
+                    span { class: "mining-total-value", "{format_balance(mature)} NOCK" }
+                }
+                div {
+                    class: "mining-total",
+                    span { class: "mining-total-label", "Maturing" }
+                    span { class: "mining-total-value", "{format_balance(immature)} NOCK" }
+                }
+            }
+
+            if props.rewards.is_empty() {
+                div { class: "mining-empty", "No blocks mined yet" }
+            } else {
+                table {
+                    thead {
+                        tr {
+                            th { "Height" }
+                            th { "Block" }
+                            th { "Paid to" }
+                            th { "Amount" }
+                            th { "Mined" }
+                            th { "Status" }
+                        }
+                    }
+                    tbody {
+                        for reward in props.rewards.iter().rev() {
+                            tr {
+                                key: "{reward.block_hash}",
+                                td { "{format_count(reward.height)}" }
+                                td { class: "mining-hash", title: "{reward.block_hash}", "{short(&reward.block_hash)}" }
+                                td { class: "mining-hash", title: "{reward.address}", "{short(&reward.address.to_string())}" }
+                                td { "{format_balance(reward.amount)} NOCK" }
+                                td { "{reward.timestamp.format(MINED_AT_FORMAT)}" }
+                                td {
+                                    if reward.is_mature(props.tip_height) {
+                                        span { class: "mining-status mature", "spendable" }
+                                    } else {
+                                        span { class: "mining-status", "maturing" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        style { {MINING_PANEL_CSS} }
+    }
+}
+
+const MINED_AT_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+fn short(value: &str) -> String {
+    if value.len() > 16 {
+        format!("{}…{}", &value[..8], &value[value.len() - 6..])
+    } else {
+        value.to_string()
+    }
+}
+
+const MINING_PANEL_CSS: &str = r#"
+.mining-panel {
+    background: #f8f9fa;
+    padding: 20px;
+    border-radius: 8px;
+    margin-top: 24px;
+    overflow-x: auto;
+}
+
+.mining-panel h4 {
+    color: #333;
+    margin: 0 0 12px 0;
+}
+
+.mining-totals {
+    display: flex;
+    gap: 24px;
+    margin-bottom: 12px;
+}
+
+.mining-total {
+    display: flex;
+    flex-direction: column;
+}
+
+.mining-total-label {
+    font-size: 11px;
+    color: #6c757d;
+    text-transform: uppercase;
+}
+
+.mining-total-value {
+    font-size: 14px;
+    font-weight: 600;
+    color: #333;
+}
+
+.mining-panel table {
+    width: 100%;
+    border-collapse: collapse;
+    font-size: 12px;
+}
+
+.mining-panel th {
+    text-align: left;
+    color: #666;
+    font-weight: 600;
+    padding: 6px 8px;
+    border-bottom: 1px solid #dee2e6;
+}
+
+.mining-panel td {
+    padding: 6px 8px;
+    border-bottom: 1px solid #eef0f2;
+    color: #333;
+}
+
+.mining-hash {
+    font-family: monospace;
+}
+
+.mining-status {
+    color: #6c757d;
+}
+
+.mining-status.mature {
+    color: #28a745;
+    font-weight: 600;
+}
+
+.mining-empty {
+    color: #6c757d;
+    font-style: italic;
+}
+"#;
@@ -0,0 +1,182 @@
+use api::{check_words, detect_language, validate_mnemonic, MnemonicLanguage};
+use dioxus::prelude::*;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct MnemonicRestoreFormProps {
+    /// Called with the phrase, the passphrase (empty if none) and the chosen
+    /// wordlist, `None` to detect it
+    pub on_restore: EventHandler<(String, String, Option<MnemonicLanguage>)>,
+    /// Why the last restore failed
+    #[props(default)]
+    pub error: Option<String>,
+    #[props(default)]
+    pub disabled: bool,
+}
+
+/// Restores a wallet seed from a BIP39 recovery phrase in any wordlist,
+/// checking each word as it is typed
+pub fn MnemonicRestoreForm(props: MnemonicRestoreFormProps) -> Element {
+    let mut phrase = use_signal(String::new);
+    let mut passphrase = use_signal(String::new);
+    // None detects the wordlist from the words typed
+    let mut language = use_signal(|| None::<MnemonicLanguage>);
+
+    let checked_language = language().or_else(|| detect_language(&phrase.read()));
+    let words = checked_language
+        .map(|language| check_words(&phrase.read(), language))
+        .unwrap_or_default();
+    let validation = (!phrase.read().trim().is_empty())
+        .then(|| validate_mnemonic(&phrase.read(), checked_language));
+    let valid = matches!(validation, Some(Ok(_)));
+
+    rsx! {
+        div {
+            class: "mnemonic-restore",
+            h3 { "Restore from recovery phrase" }
+            label {
+                "Wordlist"
+                select {
+                    disabled: props.disabled,
+                    onchange: move |evt| {
+                        let chosen = MnemonicLanguage::ALL
+                            .into_iter()
+                            .find(|language| language.label() == evt.value());
+                        language.set(chosen);
+                    },
+                    option { value: "detect", "Detect" }
+                    for option_language in MnemonicLanguage::ALL {
+                        option {
+                            value: "{option_language.label()}",
+                            selected: language() == Some(option_language),
+                            "{option_language.label()}"
+                        }
+                    }
+                }
+            }
+            textarea {
+                rows: 3,
+                placeholder: "Recovery phrase",
+                aria_label: "Recovery phrase",
+                disabled: props.disabled,
+                value: "{phrase}",
+                oninput: move |evt| phrase.set(evt.value()),
+            }
+            if !words.is_empty() {
+                div {
+                    class: "mnemonic-words",
+                    for (index, word) in words.iter().enumerate() {
+                        span {
+                            key: "{index}",
+                            class: if word.known { "mnemonic-word" } else { "mnemonic-word unknown" },
+                            title: if !word.known { "Not in the wordlist" },
+                            "{index + 1}. {word.word}"
+                        }
+                    }
+                }
+            }
+            match validation {
+                Some(Ok(language)) => rsx! {
+                    div { class: "mnemonic-status valid", "Valid {language} recovery phrase" }
+                },
+                Some(Err(e)) => rsx! {
+                    div { class: "mnemonic-status", "{e}" }
+                },
+                None => rsx! {},
+            }
+            input {
+                r#type: "password",
+                placeholder: "Passphrase (optional)",
+                aria_label: "Passphrase (optional)",
+                disabled: props.disabled,
+                value: "{passphrase}",
+                oninput: move |evt| passphrase.set(evt.value()),
+            }
+            button {
+                disabled: props.disabled || !valid,
+                onclick: move |_| {
+                    props.on_restore.call((phrase(), passphrase(), language()));
+                    passphrase.set(String::new());
+                },
+                "Restore"
+            }
+            if let Some(error) = props.error {
+                div { class: "mnemonic-error", "{error}" }
+            }
+        }
+
+        style { {MNEMONIC_RESTORE_CSS} }
+    }
+}
+
+const MNEMONIC_RESTORE_CSS: &str = r#"
+.mnemonic-restore {
+    display: flex;
+    flex-direction: column;
+    gap: 12px;
+    background: #f8f9fa;
+    padding: 20px;
+    border-radius: 8px;
+    margin: 24px 0;
+    font-size: 14px;
+    color: #333;
+}
+
+.mnemonic-restore h3 {
+    margin: 0;
+}
+
+.mnemonic-restore label {
+    display: flex;
+    flex-direction: column;
+    gap: 4px;
+}
+
+.mnemonic-restore select {
+    align-self: flex-start;
+    padding: 4px 8px;
+}
+
+.mnemonic-restore textarea,
+.mnemonic-restore input {
+    padding: 8px;
+    border: 1px solid #ccc;
+    border-radius: 4px;
+    font-family: inherit;
+}
+
+.mnemonic-words {
+    display: flex;
+    flex-wrap: wrap;
+    gap: 6px;
+}
+
+.mnemonic-word {
+    padding: 2px 8px;
+    border: 1px solid #ccc;
+    border-radius: 4px;
+    background: white;
+    font-size: 13px;
+}
+
+.mnemonic-word.unknown {
+    border-color: #dc3545;
+    color: #dc3545;
+}
+
+.mnemonic-status {
+    color: #6c757d;
+}
+
+.mnemonic-status.valid {
+    color: #198754;
+}
+
+.mnemonic-restore button {
+    align-self: flex-start;
+    padding: 6px 14px;
+}
+
+.mnemonic-error {
+    color: #dc3545;
+}
+"#;
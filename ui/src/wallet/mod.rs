@@ -1,13 +1,101 @@
+pub mod about_panel;
+pub mod address_breakdown;
+pub mod address_input;
+pub mod amount_input;
+pub mod audit_log_viewer;
 pub mod balance_card;
+pub mod block_explorer;
+pub mod cold_signing_panel;
+pub mod console_state;
+pub mod copy_button;
+pub mod danger_zone;
+pub mod display_settings_form;
+pub mod draft_banner;
+pub mod due_payments_card;
+pub mod export_panel;
+pub mod fee_selector;
+pub mod integrity_panel;
+pub mod key_import_results;
+pub mod language_picker;
+pub mod log_level_grid;
+pub mod maintenance_panel;
+pub mod mining_panel;
+pub mod mnemonic_restore_form;
 pub mod node_console;
+pub mod node_stats;
+pub mod note_list;
+pub mod overrides_chip;
+pub mod paste_address_button;
+pub mod peer_table;
+pub mod preflight_screen;
+pub mod profile_picker;
 pub mod quick_actions;
+pub mod read_only_banner;
+pub mod reauth_dialog;
+pub mod receipt_view;
 pub mod receive_view;
+pub mod recovery_dialog;
+pub mod search_bar;
+pub mod secret_key_import_form;
 pub mod send_form;
+pub mod simulated_network_banner;
+pub mod spendable_panel;
+pub mod spending_limits_form;
+pub mod storage_panel;
+pub mod template_picker;
+pub mod transaction_decoder;
+pub mod transaction_detail;
 pub mod transaction_list;
+pub mod uptime_calendar;
+pub mod webhook_settings;
 
-pub use balance_card::BalanceCard;
+pub use about_panel::AboutPanel;
+pub use address_breakdown::AddressBreakdown;
+pub use address_input::AddressInput;
+pub use amount_input::AmountInput;
+pub use audit_log_viewer::AuditLogViewer;
+pub use balance_card::{BalanceCard, BalanceCardSkeleton};
+pub use block_explorer::{BlockDetail, BlockList, ChainTransactionDetail};
+pub use cold_signing_panel::ColdSigningPanel;
+pub use console_state::ConsoleState;
+pub use copy_button::CopyButton;
+pub use danger_zone::DangerZone;
+pub use display_settings_form::DisplaySettingsForm;
+pub use draft_banner::DraftBanner;
+pub use due_payments_card::DuePaymentsCard;
+pub use export_panel::ExportPanel;
+pub use fee_selector::FeeSelector;
+pub use integrity_panel::IntegrityPanel;
+pub use key_import_results::KeyImportResults;
+pub use language_picker::LanguagePicker;
+pub use log_level_grid::LogLevelGrid;
+pub use maintenance_panel::MaintenancePanel;
+pub use mining_panel::MiningPanel;
+pub use mnemonic_restore_form::MnemonicRestoreForm;
 pub use node_console::NodeConsole;
+pub use node_stats::{DataDirUsageBar, NodeStatsSkeleton, NodeStatsStrip};
+pub use note_list::NoteList;
+pub use overrides_chip::OverridesChip;
+pub use paste_address_button::PasteAddressButton;
+pub use peer_table::PeerTable;
+pub use preflight_screen::PreflightScreen;
+pub use profile_picker::ProfilePicker;
 pub use quick_actions::QuickActions;
-pub use receive_view::ReceiveView;
-pub use send_form::SendForm;
-pub use transaction_list::TransactionList;
+pub use read_only_banner::ReadOnlyBanner;
+pub use reauth_dialog::ReauthDialog;
+pub use receipt_view::ReceiptView;
+pub use receive_view::{PaymentRequestValues, ReceiveView};
+pub use recovery_dialog::RecoveryDialog;
+pub use search_bar::SearchBar;
+pub use secret_key_import_form::SecretKeyImportForm;
+pub use send_form::{SendForm, SendFormValues, SendPrefill, TemplateDetails};
+pub use simulated_network_banner::SimulatedNetworkBanner;
+pub use spendable_panel::SpendablePanel;
+pub use spending_limits_form::SpendingLimitsForm;
+pub use storage_panel::StoragePanel;
+pub use template_picker::TemplatePicker;
+pub use transaction_decoder::TransactionDecoder;
+pub use transaction_detail::TransactionDetail;
+pub use transaction_list::{TransactionList, TransactionListSkeleton};
+pub use uptime_calendar::UptimeCalendar;
+pub use webhook_settings::WebhookSettings;
@@ -1,5 +1,21 @@
-use api::wallet::network::{LogEntry, LogLevel, NodeStatus};
+use api::wallet::network::{LogEntry, LogLevel, LogSource, NodeStatus};
+use api::LogLevelCounts;
+use chrono::{DateTime, TimeDelta, Utc};
 use dioxus::prelude::*;
+use std::collections::HashSet;
+
+use crate::i18n::current_locale;
+use crate::scroll::{scroll_into_view, scroll_to, scroll_top};
+use crate::t;
+
+/// Id of the scrolling log list
+const CONSOLE_LOGS_ID: &str = "console-logs";
+
+/// Silence between two shown lines after which a divider marks the gap
+const QUIET_GAP: TimeDelta = TimeDelta::minutes(5);
+
+/// Characters of a message shown before it is cut short with an ellipsis
+pub const DEFAULT_MAX_MESSAGE_CHARS: usize = 2000;
 
 #[derive(Props, Clone, PartialEq)]
 pub struct NodeConsoleProps {
@@ -7,13 +23,87 @@ pub struct NodeConsoleProps {
     pub logs: Vec<LogEntry>,
     pub on_start_node: EventHandler<()>,
     pub on_stop_node: EventHandler<()>,
-    pub is_starting: bool,
-    pub is_stopping: bool,
+    /// Abort a start in progress
+    pub on_cancel_start: EventHandler<()>,
+    /// Entries evicted from the in-memory log buffer
+    #[props(default)]
+    pub logs_dropped: u64,
+    /// Keep the newest line in view as lines arrive
+    #[props(default)]
+    pub follow_tail: bool,
+    /// Scroll offset to put the console back to when it mounts without
+    /// following the tail
+    #[props(default)]
+    pub scroll_top: f64,
+    /// Called with the scroll offset as the console is scrolled
+    #[props(default)]
+    pub on_scroll: Option<EventHandler<f64>>,
+    /// Lines logged before this were loaded from the previous session
+    #[props(default)]
+    pub session_started: Option<DateTime<Utc>>,
+    /// Warn and Error entries since the node started, shown above the lines
+    #[props(default)]
+    pub level_counts: LogLevelCounts,
+    /// Longer messages are cut short, with a button that shows the rest
+    #[props(default = DEFAULT_MAX_MESSAGE_CHARS)]
+    pub max_message_chars: usize,
 }
 
 pub fn NodeConsole(props: NodeConsoleProps) -> Element {
     let status = props.status;
-    let logs = props.logs;
+    let locale = current_locale();
+    let total_lines = props.logs.len();
+    // Operation the console is narrowed to, picked by clicking one of its lines
+    let mut operation_filter = use_signal(|| None);
+    // Source the console is narrowed to, toggled from a source badge
+    let mut source_filter = use_signal(|| None::<LogSource>);
+    // Long messages shown in full, by when they were logged and their length
+    let mut expanded = use_signal(HashSet::<(DateTime<Utc>, usize)>::new);
+    let max_message_chars = props.max_message_chars;
+    let logs: Vec<LogEntry> = props
+        .logs
+        .into_iter()
+        .filter(|log| operation_filter().is_none() || log.operation_id == operation_filter())
+        .filter(|log| source_filter().is_none_or(|source| log.source == source))
+        .collect();
+    let mut toggle_source = move |source: LogSource| {
+        source_filter.set(toggled_source(source_filter(), source));
+    };
+
+    // Level being stepped through and the position among its shown lines;
+    // following the tail is paused until the user goes back to live output
+    let mut jump = use_signal(|| None::<(LogLevel, usize)>);
+    let warn_lines = lines_at_level(&logs, LogLevel::Warn);
+    let error_lines = lines_at_level(&logs, LogLevel::Error);
+    let jump_lines = match jump() {
+        Some((LogLevel::Warn, _)) => warn_lines.clone(),
+        Some((LogLevel::Error, _)) => error_lines.clone(),
+        _ => Vec::new(),
+    };
+    let jump_position = jump().map(|(_, position)| clamp_jump(position, &jump_lines));
+    let jump_target = jump_position.and_then(|position| jump_lines.get(position).copied());
+    let jump_total = jump_lines.len();
+    let mut jump_to = move |level: LogLevel| jump.set(jump_clicked(jump(), level));
+    use_effect(use_reactive((&jump_target,), move |(jump_target,)| {
+        if let Some(index) = jump_target {
+            scroll_into_view(CONSOLE_LOGS_ID, &line_id(index));
+        }
+    }));
+
+    let follow_tail = props.follow_tail && jump().is_none();
+    let line_count = logs.len();
+    use_effect(use_reactive(
+        (&line_count, &follow_tail),
+        move |(_, follow_tail)| {
+            if follow_tail {
+                scroll_to(CONSOLE_LOGS_ID, None);
+            }
+        },
+    ));
+    let restore_top = props.scroll_top;
+    let on_scroll = props.on_scroll;
+    let session_started = props.session_started;
+    let dividers = dividers(&logs, session_started);
 
     rsx! {
         div {
@@ -29,7 +119,7 @@ pub fn NodeConsole(props: NodeConsoleProps) -> Element {
                     }
                     div {
                         class: "status-info",
-                        h3 { class: "status-title", "Nockchain Node" }
+                        h3 { class: "status-title", {t!("console.title")} }
                         span { class: "status-text", "{get_status_text(&status)}" }
                     }
                 }
@@ -41,34 +131,23 @@ pub fn NodeConsole(props: NodeConsoleProps) -> Element {
                             button {
                                 class: "control-button start",
                                 onclick: move |_| props.on_start_node.call(()),
-                                disabled: props.is_starting,
-                                if props.is_starting {
-                                    span { class: "spinner" }
-                                    "Starting..."
-                                } else {
-                                    "▶ Start Node"
-                                }
+                                {t!("console.start")}
                             }
                         },
                         NodeStatus::Running => rsx! {
                             button {
                                 class: "control-button stop",
                                 onclick: move |_| props.on_stop_node.call(()),
-                                disabled: props.is_stopping,
-                                if props.is_stopping {
-                                    span { class: "spinner" }
-                                    "Stopping..."
-                                } else {
-                                    "⏹ Stop Node"
-                                }
+                                {t!("console.stop")}
                             }
                         },
                         NodeStatus::Starting => rsx! {
                             button {
                                 class: "control-button starting",
-                                disabled: true,
+                                title: t!("console.cancel_startup"),
+                                onclick: move |_| props.on_cancel_start.call(()),
                                 span { class: "spinner" }
-                                "Starting..."
+                                {t!("console.cancel")}
                             }
                         },
                         NodeStatus::Stopping => rsx! {
@@ -76,14 +155,14 @@ pub fn NodeConsole(props: NodeConsoleProps) -> Element {
                                 class: "control-button stopping",
                                 disabled: true,
                                 span { class: "spinner" }
-                                "Stopping..."
+                                {t!("console.stopping")}
                             }
                         },
                         NodeStatus::Error(_) => rsx! {
                             button {
                                 class: "control-button start",
                                 onclick: move |_| props.on_start_node.call(()),
-                                "🔄 Restart"
+                                {t!("console.restart")}
                             }
                         },
                     }
@@ -95,30 +174,194 @@ pub fn NodeConsole(props: NodeConsoleProps) -> Element {
                 class: "console-container",
                 div {
                     class: "console-header",
-                    h4 { "Console Output" }
+                    h4 { {t!("console.output")} }
                     div {
                         class: "log-count",
-                        "{logs.len()} lines"
+                        if operation_filter().is_some() {
+                            button {
+                                class: "operation-filter-clear",
+                                onclick: move |_| operation_filter.set(None),
+                                {t!("console.one_operation")}
+                            }
+                        }
+                        if logs.len() == total_lines {
+                            {t!("console.lines", count = locale.format_number(total_lines as u64))}
+                        } else {
+                            {t!("console.showing", shown = locale.format_number(logs.len() as u64), total = locale.format_number(total_lines as u64))}
+                        }
+                    }
+                }
+
+                div {
+                    class: "source-legend",
+                    for source in LogSource::ALL {
+                        button {
+                            key: "{format_log_source(&source)}",
+                            class: if source_filter() == Some(source) { "source-badge active" } else { "source-badge" },
+                            style: "--source-color: {source_badge(&source).1};",
+                            title: source_filter_title(source_filter() == Some(source)),
+                            onclick: move |_| toggle_source(source),
+                            "{source_badge(&source).0} {format_log_source(&source)}"
+                        }
+                    }
+                }
+
+                if props.logs_dropped > 0 {
+                    div {
+                        class: "logs-dropped",
+                        {t!("console.evicted", count = props.logs_dropped)}
+                    }
+                }
+
+                if props.level_counts.warn > 0 || props.level_counts.error > 0 {
+                    div {
+                        class: "level-summary",
+                        title: t!("console.since_start"),
+                        if props.level_counts.error > 0 {
+                            button {
+                                class: if matches!(jump(), Some((LogLevel::Error, _))) { "level-count error active" } else { "level-count error" },
+                                disabled: error_lines.is_empty(),
+                                title: if error_lines.is_empty() { t!("console.jump_none") } else { t!("console.jump_first") },
+                                onclick: move |_| jump_to(LogLevel::Error),
+                                {t!("console.errors", count = props.level_counts.error)}
+                            }
+                        }
+                        if props.level_counts.warn > 0 {
+                            button {
+                                class: if matches!(jump(), Some((LogLevel::Warn, _))) { "level-count warn active" } else { "level-count warn" },
+                                disabled: warn_lines.is_empty(),
+                                title: if warn_lines.is_empty() { t!("console.jump_none") } else { t!("console.jump_first") },
+                                onclick: move |_| jump_to(LogLevel::Warn),
+                                {t!("console.warnings", count = props.level_counts.warn)}
+                            }
+                        }
+                        if let Some(position) = jump_position.filter(|_| jump_total > 0) {
+                            div {
+                                class: "jump-controls",
+                                button {
+                                    class: "jump-button",
+                                    disabled: position == 0,
+                                    onclick: move |_| jump.set(jump().map(|(level, _)| (level, position - 1))),
+                                    {t!("console.jump_previous")}
+                                }
+                                span {
+                                    class: "jump-position",
+                                    {t!("console.jump_position", current = position + 1, total = jump_total)}
+                                }
+                                button {
+                                    class: "jump-button",
+                                    disabled: position + 1 >= jump_total,
+                                    onclick: move |_| jump.set(jump().map(|(level, _)| (level, position + 1))),
+                                    {t!("console.jump_next")}
+                                }
+                                button {
+                                    class: "jump-button",
+                                    onclick: move |_| {
+                                        jump.set(None);
+                                        scroll_to(CONSOLE_LOGS_ID, None);
+                                    },
+                                    {t!("console.jump_exit")}
+                                }
+                            }
+                        }
                     }
                 }
 
                 div {
                     class: "console-logs",
-                    id: "console-logs",
+                    id: CONSOLE_LOGS_ID,
+                    role: "log",
+                    aria_live: "polite",
+                    aria_label: t!("console.output"),
+                    // Focusable so the log scrolls from the keyboard
+                    tabindex: "0",
+                    onmounted: move |_| scroll_to(CONSOLE_LOGS_ID, (!follow_tail).then_some(restore_top)),
+                    onscroll: move |_| {
+                        if let Some(on_scroll) = on_scroll {
+                            spawn(async move { on_scroll.call(scroll_top(CONSOLE_LOGS_ID).await) });
+                        }
+                    },
                     if logs.is_empty() {
                         div {
                             class: "console-empty",
-                            "No logs yet. Start the node to see output."
+                            {t!("console.empty")}
                         }
                     } else {
                         for (index, log) in logs.iter().enumerate() {
                             div {
                                 key: "{index}",
-                                class: "log-line {get_log_level_class(&log.level)}",
-                                span { class: "log-time", "{format_timestamp(&log.timestamp)}" }
-                                span { class: "log-level", "{format_log_level(&log.level)}" }
-                                span { class: "log-source", "[{format_log_source(&log.source)}]" }
-                                span { class: "log-message", "{log.message}" }
+                                if let Some(divider) = dividers[index] {
+                                    div { class: "session-divider", {divider_text(divider)} }
+                                }
+                                div {
+                                    id: line_id(index),
+                                    class: "log-line {get_log_level_class(&log.level)} {operation_class(&logs, index)} {session_class(log, session_started)} {jump_class(jump_target == Some(index))}",
+                                    title: if log.operation_id.is_some() && operation_filter().is_none() { t!("console.filter_operation") },
+                                    onclick: {
+                                        let operation_id = log.operation_id;
+                                        move |_| {
+                                            if operation_id.is_some() {
+                                                operation_filter.set(operation_id);
+                                            }
+                                        }
+                                    },
+                                    span { class: "log-time", "{locale.format_time(&log.timestamp)}" }
+                                    span { class: "log-level", "{format_log_level(&log.level)}" }
+                                    span {
+                                        class: "log-source source-badge",
+                                        style: "--source-color: {source_badge(&log.source).1};",
+                                        title: source_filter_title(source_filter() == Some(log.source)),
+                                        onclick: {
+                                            let source = log.source;
+                                            move |evt: MouseEvent| {
+                                                evt.stop_propagation();
+                                                toggle_source(source);
+                                            }
+                                        },
+                                        "{source_badge(&log.source).0} {format_log_source(&log.source)}"
+                                    }
+                                    span {
+                                        class: "log-message",
+                                        match message_cut(&log.message, max_message_chars) {
+                                            Some((shown, hidden)) if !expanded.read().contains(&message_key(log)) => rsx! {
+                                                "{shown}…"
+                                                button {
+                                                    class: "log-expand",
+                                                    onclick: {
+                                                        let key = message_key(log);
+                                                        move |evt: MouseEvent| {
+                                                            evt.stop_propagation();
+                                                            expanded.write().insert(key);
+                                                        }
+                                                    },
+                                                    {t!("console.expand", count = locale.format_number(hidden as u64))}
+                                                }
+                                            },
+                                            Some(_) => rsx! {
+                                                "{log.message}"
+                                                button {
+                                                    class: "log-expand",
+                                                    onclick: {
+                                                        let key = message_key(log);
+                                                        move |evt: MouseEvent| {
+                                                            evt.stop_propagation();
+                                                            expanded.write().remove(&key);
+                                                        }
+                                                    },
+                                                    {t!("console.collapse")}
+                                                }
+                                            },
+                                            None => rsx! { "{log.message}" },
+                                        }
+                                    }
+                                    if let Some(repeat) = &log.repeat {
+                                        span {
+                                            class: "log-repeat",
+                                            title: t!("console.repeated", count = repeat.count, until = locale.format_time(&repeat.last_at)),
+                                            "×{repeat.count}"
+                                        }
+                                    }
+                                }
                             }
                         }
                     }
@@ -130,6 +373,152 @@ pub fn NodeConsole(props: NodeConsoleProps) -> Element {
     }
 }
 
+/// The start of `message` to show and the count of characters left out,
+/// if it is longer than `max_chars`
+fn message_cut(message: &str, max_chars: usize) -> Option<(&str, usize)> {
+    let (end, _) = message.char_indices().nth(max_chars)?;
+    Some((&message[..end], message[end..].chars().count()))
+}
+
+/// Identifies a line's message across renders, as lines arrive and filters
+/// change the positions
+fn message_key(log: &LogEntry) -> (DateTime<Utc>, usize) {
+    (log.timestamp, log.message.len())
+}
+
+/// Element id of the shown line at `index`, for jumping to it
+fn line_id(index: usize) -> String {
+    format!("console-line-{}", index)
+}
+
+/// Indices of the shown lines logged at `level`
+fn lines_at_level(logs: &[LogEntry], level: LogLevel) -> Vec<usize> {
+    logs.iter()
+        .enumerate()
+        .filter(|(_, log)| log.level == level)
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Jump after clicking the `level` count: the first line at a new level, or
+/// the next line at the level being stepped through
+fn jump_clicked(jump: Option<(LogLevel, usize)>, level: LogLevel) -> Option<(LogLevel, usize)> {
+    match jump {
+        Some((current, position)) if current == level => Some((level, position + 1)),
+        _ => Some((level, 0)),
+    }
+}
+
+/// Position among the jump's `lines`, held at the last one once past it
+fn clamp_jump(position: usize, lines: &[usize]) -> usize {
+    position.min(lines.len().saturating_sub(1))
+}
+
+/// Marks lines logged during a start or stop; the first of a run of
+/// consecutive lines from the same operation also starts a new group
+fn operation_class(logs: &[LogEntry], index: usize) -> &'static str {
+    let operation_id = logs[index].operation_id;
+    if operation_id.is_none() {
+        return "";
+    }
+    if index > 0 && logs[index - 1].operation_id == operation_id {
+        "in-operation"
+    } else {
+        "in-operation operation-start"
+    }
+}
+
+/// Icon and color of a source's badge. Matches every source so a new one
+/// has to be given a badge here before the console builds.
+fn source_badge(source: &LogSource) -> (&'static str, &'static str) {
+    match source {
+        LogSource::Node => ("◆", "#60a5fa"),
+        LogSource::Wallet => ("◈", "#34d399"),
+        LogSource::P2P => ("⇄", "#22d3ee"),
+        LogSource::Mining => ("⛏", "#fbbf24"),
+        LogSource::Consensus => ("⚖", "#a78bfa"),
+        LogSource::Network => ("◎", "#2dd4bf"),
+        LogSource::VM => ("λ", "#f472b6"),
+        LogSource::Debug => ("·", "#9ca3af"),
+    }
+}
+
+/// Break in the output marked above a shown line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Divider {
+    /// First line of this session after lines loaded from the previous one
+    PreviousSession,
+    /// First line of a start or stop after lines of an earlier one
+    NewOperation,
+    /// Nothing was shown for this long before the line
+    Quiet(TimeDelta),
+}
+
+/// Divider above each of `logs`, if any. Worked out over the lines as
+/// shown, so a filter that hides the lines in between can open up a gap.
+/// Where more than one applies, the session boundary wins, then the new
+/// operation.
+fn dividers(logs: &[LogEntry], started: Option<DateTime<Utc>>) -> Vec<Option<Divider>> {
+    let mut last_operation = None;
+    let mut previous: Option<&LogEntry> = None;
+    logs.iter()
+        .map(|log| {
+            let divider = previous.and_then(|previous| {
+                // A collapsed burst ended at its last repeat
+                let ended = previous
+                    .repeat
+                    .as_ref()
+                    .map_or(previous.timestamp, |repeat| repeat.last_at);
+                let quiet = log.timestamp - ended;
+                if started.is_some_and(|started| ended < started && log.timestamp >= started) {
+                    Some(Divider::PreviousSession)
+                } else if log.operation_id.is_some()
+                    && last_operation.is_some()
+                    && log.operation_id != last_operation
+                {
+                    Some(Divider::NewOperation)
+                } else {
+                    (quiet >= QUIET_GAP).then_some(Divider::Quiet(quiet))
+                }
+            });
+            if log.operation_id.is_some() {
+                last_operation = log.operation_id;
+            }
+            previous = Some(log);
+            divider
+        })
+        .collect()
+}
+
+fn divider_text(divider: Divider) -> String {
+    match divider {
+        Divider::PreviousSession => t!("console.previous_session"),
+        Divider::NewOperation => t!("console.new_operation"),
+        Divider::Quiet(quiet) if quiet < TimeDelta::hours(1) => {
+            t!("console.quiet_minutes", count = quiet.num_minutes())
+        }
+        Divider::Quiet(quiet) if quiet < TimeDelta::days(1) => {
+            t!("console.quiet_hours", count = quiet.num_hours())
+        }
+        Divider::Quiet(quiet) => t!("console.quiet_days", count = quiet.num_days()),
+    }
+}
+
+fn jump_class(is_target: bool) -> &'static str {
+    if is_target {
+        "jump-target"
+    } else {
+        ""
+    }
+}
+
+fn session_class(log: &LogEntry, started: Option<DateTime<Utc>>) -> &'static str {
+    match started {
+        Some(started) if log.timestamp < started => "previous-session",
+        _ => "",
+    }
+}
+
 fn get_status_class(status: &NodeStatus) -> &'static str {
     match status {
         NodeStatus::Stopped => "stopped",
@@ -142,15 +531,30 @@ fn get_status_class(status: &NodeStatus) -> &'static str {
 
 fn get_status_text(status: &NodeStatus) -> String {
     match status {
-        NodeStatus::Stopped => "Stopped".to_string(),
-        NodeStatus::Starting => "Starting...".to_string(),
-        NodeStatus::Running => "Running".to_string(),
-        NodeStatus::Stopping => "Stopping...".to_string(),
-        NodeStatus::Error(msg) => format!("Error: {}", msg),
+        NodeStatus::Stopped => t!("console.status.stopped"),
+        NodeStatus::Starting => t!("console.status.starting"),
+        NodeStatus::Running => t!("console.status.running"),
+        NodeStatus::Stopping => t!("console.status.stopping"),
+        NodeStatus::Error(msg) => t!("console.status.error", message = msg),
     }
 }
 
-fn get_log_level_class(level: &LogLevel) -> &'static str {
+/// Source filter after clicking the badge of `source`: narrows to it, or
+/// clears the filter if it was already narrowed to it
+fn toggled_source(current: Option<LogSource>, source: LogSource) -> Option<LogSource> {
+    (current != Some(source)).then_some(source)
+}
+
+/// Tooltip of a source badge, whose click toggles filtering to its source
+fn source_filter_title(active: bool) -> String {
+    if active {
+        t!("console.show_all_sources")
+    } else {
+        t!("console.show_only_source")
+    }
+}
+
+pub(crate) fn get_log_level_class(level: &LogLevel) -> &'static str {
     match level {
         LogLevel::Trace => "trace",
         LogLevel::Debug => "debug",
@@ -160,11 +564,7 @@ fn get_log_level_class(level: &LogLevel) -> &'static str {
     }
 }
 
-fn format_timestamp(timestamp: &chrono::DateTime<chrono::Utc>) -> String {
-    timestamp.format("%H:%M:%S").to_string()
-}
-
-fn format_log_level(level: &LogLevel) -> String {
+pub(crate) fn format_log_level(level: &LogLevel) -> String {
     match level {
         LogLevel::Trace => "TRACE".to_string(),
         LogLevel::Debug => "DEBUG".to_string(),
@@ -174,7 +574,7 @@ fn format_log_level(level: &LogLevel) -> String {
     }
 }
 
-fn format_log_source(source: &api::wallet::network::LogSource) -> String {
+pub(crate) fn format_log_source(source: &api::wallet::network::LogSource) -> String {
     match source {
         api::wallet::network::LogSource::Node => "NODE".to_string(),
         api::wallet::network::LogSource::Wallet => "WALLET".to_string(),
@@ -310,6 +710,10 @@ const NODE_CONSOLE_CSS: &str = r#"
     color: white;
 }
 
+.control-button.starting:hover {
+    background: #4b5563;
+}
+
 .spinner {
     width: 12px;
     height: 12px;
@@ -350,6 +754,74 @@ const NODE_CONSOLE_CSS: &str = r#"
     color: #6b7280;
 }
 
+.logs-dropped {
+    padding: 6px 20px;
+    font-size: 11px;
+    color: #f59e0b;
+    background: #111827;
+    border-bottom: 1px solid #374151;
+}
+
+.level-summary {
+    display: flex;
+    flex-wrap: wrap;
+    align-items: center;
+    gap: 8px;
+    padding: 6px 20px;
+    background: #111827;
+    border-bottom: 1px solid #374151;
+    font-size: 12px;
+}
+
+.level-count {
+    padding: 2px 10px;
+    background: transparent;
+    border: 1px solid currentColor;
+    border-radius: 999px;
+    cursor: pointer;
+    font-size: 12px;
+}
+
+.level-count.warn {
+    color: #f59e0b;
+}
+
+.level-count.error {
+    color: #ef4444;
+}
+
+.level-count.active {
+    background: #1f2937;
+}
+
+.level-count:disabled {
+    opacity: 0.5;
+    cursor: default;
+}
+
+.jump-controls {
+    display: flex;
+    align-items: center;
+    gap: 6px;
+    margin-left: auto;
+    color: #9ca3af;
+}
+
+.jump-button {
+    padding: 2px 8px;
+    background: #1f2937;
+    color: #e5e7eb;
+    border: 1px solid #374151;
+    border-radius: 4px;
+    cursor: pointer;
+    font-size: 12px;
+}
+
+.jump-button:disabled {
+    opacity: 0.5;
+    cursor: default;
+}
+
 .console-logs {
     max-height: 400px;
     overflow-y: auto;
@@ -377,6 +849,44 @@ const NODE_CONSOLE_CSS: &str = r#"
     background: #1f2937;
 }
 
+.log-line.in-operation {
+    border-left: 2px solid #374151;
+    padding-left: 18px;
+    cursor: pointer;
+}
+
+.log-line.operation-start {
+    margin-top: 4px;
+}
+
+.log-line.previous-session {
+    opacity: 0.6;
+}
+
+.log-line.jump-target {
+    background: #374151;
+    outline: 1px solid #6b7280;
+}
+
+.session-divider {
+    padding: 4px 20px;
+    text-align: center;
+    color: #6b7280;
+    font-size: 11px;
+    border-bottom: 1px solid #374151;
+}
+
+.operation-filter-clear {
+    margin-right: 8px;
+    padding: 2px 8px;
+    border: 1px solid #374151;
+    border-radius: 4px;
+    background: transparent;
+    color: #9ca3af;
+    font-size: 11px;
+    cursor: pointer;
+}
+
 .log-time {
     color: #6b7280;
     min-width: 60px;
@@ -390,14 +900,64 @@ const NODE_CONSOLE_CSS: &str = r#"
 }
 
 .log-source {
-    color: #9ca3af;
     min-width: 60px;
     font-size: 11px;
 }
 
+.source-legend {
+    display: flex;
+    flex-wrap: wrap;
+    gap: 6px;
+    padding: 8px 20px;
+    background: #111827;
+    border-bottom: 1px solid #374151;
+}
+
+.source-badge {
+    --source-color: #9ca3af;
+    padding: 0 6px;
+    border: 1px solid var(--source-color);
+    border-radius: 4px;
+    background: transparent;
+    color: var(--source-color);
+    font-family: inherit;
+    font-size: 11px;
+    white-space: nowrap;
+    cursor: pointer;
+}
+
+.source-badge.active,
+.source-badge:hover {
+    background: var(--source-color);
+    color: #111827;
+}
+
 .log-message {
     flex: 1;
-    word-break: break-word;
+    min-width: 0;
+    white-space: pre-wrap;
+    overflow-wrap: anywhere;
+}
+
+.log-expand {
+    margin-left: 6px;
+    padding: 0 6px;
+    border: 1px solid #4b5563;
+    border-radius: 4px;
+    background: none;
+    color: #93c5fd;
+    font-size: 11px;
+    cursor: pointer;
+}
+
+.log-repeat {
+    align-self: flex-start;
+    padding: 0 6px;
+    border-radius: 8px;
+    background: #374151;
+    color: #fbbf24;
+    font-weight: 600;
+    white-space: nowrap;
 }
 
 .log-line.trace .log-level {
@@ -475,4 +1035,309 @@ const NODE_CONSOLE_CSS: &str = r#"
         min-width: auto;
     }
 }
+
+@media (max-width: 480px) {
+    .node-controls {
+        flex-direction: column;
+    }
+
+    .control-button {
+        min-height: 44px;
+        justify-content: center;
+    }
+
+    .source-badge {
+        padding: 6px 10px;
+    }
+
+    .console-logs {
+        max-height: 60vh;
+    }
+}
 "#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::render;
+    use std::collections::HashSet;
+
+    #[test]
+    fn every_source_has_its_own_icon_and_color() {
+        let badges: Vec<(&str, &str)> = LogSource::ALL.iter().map(source_badge).collect();
+        let icons: HashSet<&str> = badges.iter().map(|badge| badge.0).collect();
+        let colors: HashSet<&str> = badges.iter().map(|badge| badge.1).collect();
+        assert_eq!(icons.len(), LogSource::ALL.len());
+        assert_eq!(colors.len(), LogSource::ALL.len());
+        for (icon, color) in badges {
+            assert_eq!(icon.chars().count(), 1, "{}", icon);
+            assert!(
+                color.len() == 7
+                    && color.starts_with('#')
+                    && color[1..].chars().all(|c| c.is_ascii_hexdigit()),
+                "{} is not a #rrggbb color",
+                color
+            );
+        }
+    }
+
+    #[test]
+    fn badges_keep_their_mapping() {
+        assert_eq!(source_badge(&LogSource::P2P), ("⇄", "#22d3ee"));
+        assert_eq!(source_badge(&LogSource::Mining), ("⛏", "#fbbf24"));
+        assert_eq!(source_badge(&LogSource::Consensus), ("⚖", "#a78bfa"));
+        assert_eq!(source_badge(&LogSource::Debug), ("·", "#9ca3af"));
+    }
+
+    #[test]
+    fn clicking_a_badge_toggles_its_source_filter() {
+        let narrowed = toggled_source(None, LogSource::P2P);
+        assert_eq!(narrowed, Some(LogSource::P2P));
+        assert_eq!(
+            toggled_source(narrowed, LogSource::Mining),
+            Some(LogSource::Mining)
+        );
+        assert_eq!(toggled_source(narrowed, LogSource::P2P), None);
+    }
+
+    fn log(level: LogLevel, message: &str) -> LogEntry {
+        LogEntry {
+            timestamp: api::testing::fixture_time(0),
+            level,
+            source: LogSource::Node,
+            message: message.to_string(),
+            operation_id: None,
+            repeat: None,
+        }
+    }
+
+    #[test]
+    fn lines_at_level_picks_out_the_shown_lines_of_that_level() {
+        let logs = vec![
+            log(LogLevel::Info, "starting"),
+            log(LogLevel::Warn, "slow peer"),
+            log(LogLevel::Error, "bad block"),
+            log(LogLevel::Warn, "slow peer again"),
+        ];
+        assert_eq!(lines_at_level(&logs, LogLevel::Warn), vec![1, 3]);
+        assert_eq!(lines_at_level(&logs, LogLevel::Error), vec![2]);
+        assert_eq!(lines_at_level(&logs, LogLevel::Debug), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn clicking_a_count_starts_at_its_first_line_then_steps_on() {
+        let first = jump_clicked(None, LogLevel::Warn);
+        assert_eq!(first, Some((LogLevel::Warn, 0)));
+        let second = jump_clicked(first, LogLevel::Warn);
+        assert_eq!(second, Some((LogLevel::Warn, 1)));
+        // Switching level starts again from its first line
+        assert_eq!(
+            jump_clicked(second, LogLevel::Error),
+            Some((LogLevel::Error, 0))
+        );
+    }
+
+    #[test]
+    fn stepping_past_the_last_line_stays_on_it() {
+        let lines = [4, 9, 12];
+        assert_eq!(clamp_jump(0, &lines), 0);
+        assert_eq!(clamp_jump(2, &lines), 2);
+        assert_eq!(clamp_jump(7, &lines), 2);
+        assert_eq!(clamp_jump(3, &[]), 0);
+    }
+
+    /// Info line `seconds` after the fixture epoch, logged during `operation`
+    fn line(seconds: i64, operation: Option<u128>) -> LogEntry {
+        LogEntry {
+            timestamp: api::testing::fixture_time(seconds),
+            operation_id: operation.map(uuid::Uuid::from_u128),
+            ..log(LogLevel::Info, "tick")
+        }
+    }
+
+    const MINUTE: i64 = 60;
+
+    #[test]
+    fn a_quiet_gap_of_the_threshold_or_more_gets_a_divider() {
+        assert!(dividers(&[], None).is_empty());
+        let logs = [
+            line(0, None),
+            line(5 * MINUTE - 1, None),
+            line(10 * MINUTE - 1, None),
+            line(24 * MINUTE - 1, None),
+        ];
+        assert_eq!(
+            dividers(&logs, None),
+            vec![
+                None,
+                None,
+                Some(Divider::Quiet(TimeDelta::minutes(5))),
+                Some(Divider::Quiet(TimeDelta::minutes(14))),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_collapsed_burst_ends_at_its_last_repeat() {
+        let mut burst = line(0, None);
+        burst.repeat = Some(api::wallet::network::LogRepeat {
+            count: 40,
+            last_at: api::testing::fixture_time(20 * MINUTE),
+        });
+        let logs = [burst, line(21 * MINUTE, None)];
+        assert_eq!(dividers(&logs, None), vec![None, None]);
+    }
+
+    #[test]
+    fn a_new_operation_is_marked_but_lines_without_one_are_not() {
+        let logs = [
+            line(0, Some(1)),
+            line(1, None),
+            line(2, Some(1)),
+            line(3, None),
+            line(4, Some(2)),
+        ];
+        assert_eq!(
+            dividers(&logs, None),
+            vec![None, None, None, None, Some(Divider::NewOperation)]
+        );
+    }
+
+    #[test]
+    fn the_session_boundary_wins_over_the_other_dividers() {
+        let started = api::testing::fixture_time(30 * MINUTE);
+        let logs = [
+            line(0, Some(1)),
+            line(31 * MINUTE, Some(2)),
+            line(32 * MINUTE, Some(3)),
+        ];
+        assert_eq!(
+            dividers(&logs, Some(started)),
+            vec![
+                None,
+                Some(Divider::PreviousSession),
+                Some(Divider::NewOperation)
+            ]
+        );
+    }
+
+    #[test]
+    fn dividers_follow_the_filtered_lines() {
+        let mut logs = vec![
+            line(0, None),
+            line(4 * MINUTE, None),
+            line(8 * MINUTE, None),
+        ];
+        logs[1].level = LogLevel::Debug;
+        assert_eq!(dividers(&logs, None), vec![None, None, None]);
+
+        let shown: Vec<LogEntry> = logs
+            .into_iter()
+            .filter(|log| log.level != LogLevel::Debug)
+            .collect();
+        assert_eq!(
+            dividers(&shown, None),
+            vec![None, Some(Divider::Quiet(TimeDelta::minutes(8)))]
+        );
+    }
+
+    #[test]
+    fn quiet_gaps_read_in_the_largest_whole_unit() {
+        let quiet = |minutes| divider_text(Divider::Quiet(TimeDelta::minutes(minutes)));
+        assert_eq!(quiet(14), "— 14 minutes with no output —");
+        assert_eq!(quiet(59), "— 59 minutes with no output —");
+        assert_eq!(quiet(60), "— 1 hour with no output —");
+        assert_eq!(quiet(25 * 60), "— 1 day with no output —");
+        assert_eq!(quiet(3 * 24 * 60), "— 3 days with no output —");
+        assert_eq!(
+            divider_text(Divider::NewOperation),
+            "— node stopped or started —"
+        );
+    }
+
+    #[component]
+    fn Console(warn: u64, error: u64) -> Element {
+        rsx! {
+            NodeConsole {
+                status: NodeStatus::Running,
+                logs: vec![
+                    log(LogLevel::Warn, "slow peer"),
+                    log(LogLevel::Info, "synced"),
+                ],
+                on_start_node: |_| {},
+                on_stop_node: |_| {},
+                on_cancel_start: |_| {},
+                level_counts: LogLevelCounts { warn, error },
+            }
+        }
+    }
+
+    #[test]
+    fn the_strip_shows_the_counts_since_the_node_started() {
+        let html = render(Console, ConsoleProps { warn: 3, error: 1 });
+        assert!(html.contains("level-summary"), "{}", html);
+        assert!(html.contains("⚠ 3 warnings"), "{}", html);
+        assert!(html.contains("✖ 1 error"), "{}", html);
+        // Only the warning is among the shown lines, so only it can be jumped to
+        assert!(html.contains("None in the lines shown"), "{}", html);
+        assert!(html.contains("Jump to the first one"), "{}", html);
+        assert!(!html.contains("jump-controls"), "{}", html);
+    }
+
+    #[test]
+    fn the_log_is_a_polite_live_region_reachable_from_the_keyboard() {
+        let html = render(Console, ConsoleProps { warn: 0, error: 0 });
+        let start = html.find(r#"class="console-logs""#).expect("console log");
+        let tag = &html[start..start + html[start..].find('>').unwrap()];
+        assert!(tag.contains(r#"role="log""#), "{}", tag);
+        assert!(tag.contains(r#"aria-live="polite""#), "{}", tag);
+        assert!(tag.contains(r#"tabindex="0""#), "{}", tag);
+        assert!(
+            tag.contains(&format!(r#"aria-label="{}""#, t!("console.output"))),
+            "{}",
+            tag
+        );
+    }
+
+    #[test]
+    fn the_strip_is_hidden_until_something_is_counted() {
+        let html = render(Console, ConsoleProps { warn: 0, error: 0 });
+        assert!(!html.contains("level-summary"), "{}", html);
+        let html = render(Console, ConsoleProps { warn: 0, error: 2 });
+        assert!(html.contains("✖ 2 errors"), "{}", html);
+        assert!(!html.contains("warnings"), "{}", html);
+    }
+
+    #[test]
+    fn long_messages_are_cut_on_a_character_boundary() {
+        assert_eq!(message_cut("short", 5), None);
+        assert_eq!(message_cut("longer", 4), Some(("long", 2)));
+        assert_eq!(message_cut("ééééé", 2), Some(("éé", 3)));
+        assert_eq!(message_cut("", 0), None);
+    }
+
+    #[component]
+    fn LongLine(max: usize) -> Element {
+        rsx! {
+            NodeConsole {
+                status: NodeStatus::Running,
+                logs: vec![log(LogLevel::Info, &"ab".repeat(20))],
+                on_start_node: |_| {},
+                on_stop_node: |_| {},
+                on_cancel_start: |_| {},
+                max_message_chars: max,
+            }
+        }
+    }
+
+    #[test]
+    fn a_cut_message_offers_the_rest() {
+        let html = render(LongLine, LongLineProps { max: 25 });
+        assert!(html.contains(&format!("{}a…", "ab".repeat(12))), "{}", html);
+        assert!(html.contains("Show all (15 more characters)"), "{}", html);
+
+        let html = render(LongLine, LongLineProps { max: 40 });
+        assert!(html.contains(&"ab".repeat(20)), "{}", html);
+        assert!(!html.contains("log-expand"), "{}", html);
+    }
+}
@@ -0,0 +1,345 @@
+use api::{DataDirUsage, FilterSyncStats, NodeStats, SyncProgress, ValidationPhase};
+use dioxus::prelude::*;
+use std::time::Duration;
+
+use crate::format::{format_bytes, format_compact, format_count, format_rate};
+use crate::skeleton::Skeleton;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct NodeStatsStripProps {
+    pub stats: Option<NodeStats>,
+    /// How the local chain checks new blocks
+    #[props(default)]
+    pub validation: Option<ValidationPhase>,
+    /// Downloads of the wallet's last filtered sync
+    #[props(default)]
+    pub filter_sync: Option<FilterSyncStats>,
+}
+
+pub fn NodeStatsStrip(props: NodeStatsStripProps) -> Element {
+    let Some(stats) = props.stats else {
+        return rsx! {
+            div {
+                class: "node-stats-strip idle",
+                span { class: "stat-idle", "Node statistics are available while the node is running" }
+            }
+            style { {NODE_STATS_CSS} }
+        };
+    };
+
+    let syncing = stats.sync.clone().filter(|progress| !progress.is_synced());
+    let stalled = syncing.as_ref().is_some_and(|progress| progress.stalled);
+
+    rsx! {
+        div {
+            class: "node-stats-strip",
+            if let Some(progress) = syncing {
+                div {
+                    class: "stat",
+                    title: "{format_count(progress.current)} of {format_count(progress.target)} blocks, {format_compact(progress.remaining())} to go",
+                    span { class: "stat-label", "Sync" }
+                    span { class: "stat-value", "{(progress.fraction() * 100.0).floor()}%" }
+                    span {
+                        class: if progress.stalled { "stat-total warn" } else { "stat-total" },
+                        "{sync_estimate(&progress)}"
+                    }
+                }
+            }
+            div {
+                class: "stat",
+                span { class: "stat-label", "Peers" }
+                span { class: "stat-value", "{format_count(stats.connected_peers.into())}" }
+                if stats.min_broadcast_peers > 0 {
+                    span {
+                        class: if stats.connected_peers < stats.min_broadcast_peers { "stat-total warn" } else { "stat-total" },
+                        title: "Sends are held until this many peers are connected",
+                        "of {format_count(stats.min_broadcast_peers.into())} needed to send"
+                    }
+                }
+            }
+            div {
+                class: "stat",
+                span { class: "stat-label", "⬇ In" }
+                span { class: "stat-value", "{format_rate(stats.network_in_rate)}" }
+                span { class: "stat-total", "{format_bytes(stats.network_in_bytes)} total" }
+            }
+            div {
+                class: "stat",
+                span { class: "stat-label", "⬆ Out" }
+                span { class: "stat-value", "{format_rate(stats.network_out_rate)}" }
+                span { class: "stat-total", "{format_bytes(stats.network_out_bytes)} total" }
+            }
+            div {
+                class: "stat",
+                span { class: "stat-label", "Uptime" }
+                span { class: "stat-value", "{format_count(stats.uptime_seconds)}s" }
+            }
+            if let Some(validation) = props.validation {
+                div {
+                    class: "stat",
+                    title: "{validation}",
+                    span { class: "stat-label", "Validation" }
+                    span {
+                        class: "stat-value",
+                        match validation {
+                            ValidationPhase::Fast { .. } => "Fast",
+                            ValidationPhase::Full => "Full",
+                        }
+                    }
+                    if let ValidationPhase::Fast { checkpoint } = validation {
+                        span { class: "stat-total", "below checkpoint {format_count(checkpoint)}" }
+                    }
+                }
+            }
+            if let Some(sync) = props.filter_sync {
+                div {
+                    class: "stat",
+                    title: "{format_count(sync.false_positives)} fetched blocks held nothing for the wallet",
+                    span { class: "stat-label", "Wallet sync" }
+                    span { class: "stat-value", "{format_bytes(sync.bytes_saved())} saved" }
+                    span {
+                        class: "stat-total",
+                        "{format_compact(sync.blocks_fetched)} of {format_compact(sync.blocks_scanned)} blocks fetched"
+                    }
+                }
+            }
+        }
+        if stalled {
+            div {
+                class: "sync-stalled",
+                "Sync appears stalled: no new blocks for a while. Check that peers are connected, or restart the node."
+            }
+        }
+
+        style { {NODE_STATS_CSS} }
+    }
+}
+
+/// Time left of a sync, e.g. "~12 minutes remaining"
+fn sync_estimate(progress: &SyncProgress) -> String {
+    if progress.stalled {
+        return "stalled".to_string();
+    }
+    let Some(eta) = progress.eta else {
+        return "estimating…".to_string();
+    };
+    let plural = |count: u64, unit: &str| {
+        let s = if count == 1 { "" } else { "s" };
+        format!("~{} {}{} remaining", count, unit, s)
+    };
+    match eta.as_secs() {
+        0..60 => "less than a minute remaining".to_string(),
+        secs if eta < Duration::from_secs(2 * 3600) => plural((secs + 30) / 60, "minute"),
+        secs if eta < Duration::from_secs(2 * 86400) => plural((secs + 1800) / 3600, "hour"),
+        secs => plural((secs + 43_200) / 86400, "day"),
+    }
+}
+
+/// Placeholder in the shape of a `NodeStatsStrip` while the first stats of a
+/// running node are fetched
+pub fn NodeStatsSkeleton() -> Element {
+    rsx! {
+        div {
+            class: "node-stats-strip",
+            aria_busy: "true",
+            for stat in 0..4 {
+                div {
+                    key: "{stat}",
+                    class: "stat",
+                    Skeleton { width: "48px", height: "11px" }
+                    Skeleton { width: "72px", height: "18px" }
+                }
+            }
+        }
+
+        style { {NODE_STATS_CSS} }
+    }
+}
+
+#[derive(Props, Clone, PartialEq)]
+pub struct DataDirUsageBarProps {
+    pub usage: Option<DataDirUsage>,
+}
+
+pub fn DataDirUsageBar(props: DataDirUsageBarProps) -> Element {
+    let Some(usage) = props.usage else {
+        return rsx! {
+            div { class: "data-dir-usage idle", "Disk usage not measured yet" }
+            style { {NODE_STATS_CSS} }
+        };
+    };
+
+    let near_cap = usage.is_near_soft_cap();
+    let percent = usage
+        .soft_cap_fraction()
+        .map(|fraction| (fraction * 100.0).min(100.0));
+
+    rsx! {
+        div {
+            class: "data-dir-usage",
+            div {
+                class: "usage-summary",
+                span { "Used: {format_bytes(usage.used_bytes)}" }
+                span { "Free on disk: {format_bytes(usage.available_bytes)} of {format_bytes(usage.total_bytes)}" }
+            }
+            if let (Some(percent), Some(cap)) = (percent, usage.soft_cap_bytes) {
+                div {
+                    class: "usage-track",
+                    div {
+                        class: if near_cap { "usage-fill warn" } else { "usage-fill" },
+                        style: "width: {percent:.0}%;",
+                    }
+                }
+                div {
+                    class: if near_cap { "usage-cap warn" } else { "usage-cap" },
+                    "{percent:.0}% of {format_bytes(cap)} soft cap"
+                }
+            }
+        }
+
+        style { {NODE_STATS_CSS} }
+    }
+}
+
+const NODE_STATS_CSS: &str = r#"
+.node-stats-strip {
+    display: flex;
+    flex-wrap: wrap;
+    gap: 24px;
+    padding: 12px 20px;
+    background: #111827;
+    color: #e5e7eb;
+    border-radius: 8px;
+    margin-bottom: 16px;
+}
+
+.node-stats-strip.idle {
+    color: #6b7280;
+    font-style: italic;
+}
+
+.node-stats-strip .stat {
+    display: flex;
+    align-items: baseline;
+    gap: 6px;
+}
+
+.node-stats-strip .stat-label {
+    font-size: 12px;
+    color: #9ca3af;
+}
+
+.node-stats-strip .stat-value {
+    font-family: 'SF Mono', 'Monaco', 'Cascadia Code', 'Roboto Mono', monospace;
+    font-size: 14px;
+    font-weight: 600;
+}
+
+.node-stats-strip .stat-total {
+    font-size: 11px;
+    color: #6b7280;
+}
+
+.sync-stalled {
+    margin: -8px 0 16px 0;
+    padding: 8px 12px;
+    background: #fff3cd;
+    color: #856404;
+    border-radius: 4px;
+    font-size: 13px;
+}
+
+.node-stats-strip .stat-total.warn {
+    color: #fbbf24;
+}
+
+.data-dir-usage {
+    font-size: 12px;
+    color: #6c757d;
+}
+
+.data-dir-usage.idle {
+    font-style: italic;
+}
+
+.data-dir-usage .usage-summary {
+    display: flex;
+    flex-wrap: wrap;
+    gap: 16px;
+    margin-bottom: 6px;
+}
+
+.data-dir-usage .usage-track {
+    height: 6px;
+    background: #e9ecef;
+    border-radius: 3px;
+    overflow: hidden;
+}
+
+.data-dir-usage .usage-fill {
+    height: 100%;
+    background: #28a745;
+}
+
+.data-dir-usage .usage-fill.warn {
+    background: #dc3545;
+}
+
+.data-dir-usage .usage-cap {
+    margin-top: 4px;
+}
+
+.data-dir-usage .usage-cap.warn {
+    color: #dc3545;
+    font-weight: 600;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::render_root;
+
+    #[test]
+    fn the_skeleton_has_a_label_and_value_bar_per_stat() {
+        let html = render_root(NodeStatsSkeleton);
+        assert!(html.starts_with(r#"<div class="node-stats-strip" aria-busy="true">"#));
+        assert_eq!(html.matches(r#"<div class="stat">"#).count(), 4);
+        assert_eq!(html.matches(r#"class="skeleton""#).count(), 8);
+    }
+
+    fn eta(secs: u64) -> SyncProgress {
+        SyncProgress {
+            current: 100,
+            target: 1_000,
+            blocks_per_sec: 1.0,
+            bytes_per_sec: 0.0,
+            eta: Some(Duration::from_secs(secs)),
+            stalled: false,
+        }
+    }
+
+    #[test]
+    fn the_estimate_rounds_to_a_readable_unit() {
+        assert_eq!(sync_estimate(&eta(59)), "less than a minute remaining");
+        assert_eq!(sync_estimate(&eta(60)), "~1 minute remaining");
+        assert_eq!(sync_estimate(&eta(12 * 60 + 20)), "~12 minutes remaining");
+        assert_eq!(sync_estimate(&eta(2 * 3600 - 1)), "~120 minutes remaining");
+        assert_eq!(sync_estimate(&eta(2 * 3600)), "~2 hours remaining");
+        assert_eq!(sync_estimate(&eta(2 * 86_400)), "~2 days remaining");
+    }
+
+    #[test]
+    fn an_unknown_or_stalled_sync_says_so() {
+        let unknown = SyncProgress {
+            eta: None,
+            ..eta(0)
+        };
+        assert_eq!(sync_estimate(&unknown), "estimating…");
+        let stalled = SyncProgress {
+            stalled: true,
+            ..unknown
+        };
+        assert_eq!(sync_estimate(&stalled), "stalled");
+    }
+}
@@ -0,0 +1,260 @@
+use api::{format_display, DisplaySettings, Note, NoteProvenance};
+use dioxus::prelude::*;
+use uuid::Uuid;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct NoteListProps {
+    /// Notes to list, as `BalanceManager::notes` gives them
+    pub notes: Vec<Note>,
+    /// Note whose provenance is expanded
+    #[props(default)]
+    pub expanded: Option<Uuid>,
+    /// Provenance of the expanded note, once loaded
+    #[props(default)]
+    pub provenance: Option<NoteProvenance>,
+    /// Why the expanded note's provenance couldn't be loaded
+    #[props(default)]
+    pub provenance_error: Option<String>,
+    /// Called with a note's id when its provenance is expanded or collapsed
+    pub on_toggle: EventHandler<Uuid>,
+    /// Called with the id of a transaction the wallet recorded
+    pub on_open_transaction: EventHandler<String>,
+    /// Called with the id of a transaction only the chain has
+    pub on_open_chain_transaction: EventHandler<String>,
+    /// Called with the hash of a block
+    pub on_open_block: EventHandler<String>,
+    #[props(default)]
+    pub display: DisplaySettings,
+}
+
+/// Every note with its amount, address and state, each with a Provenance
+/// expander showing the transaction and block that created it
+pub fn NoteList(props: NoteListProps) -> Element {
+    if props.notes.is_empty() {
+        return rsx! {
+            div { class: "empty-state", "No notes yet" }
+        };
+    }
+
+    rsx! {
+        div {
+            class: "note-list",
+            for note in props.notes.iter() {
+                div {
+                    key: "{note.id}",
+                    class: "note-row",
+                    div {
+                        class: "note-summary",
+                        span {
+                            class: "note-amount",
+                            "{format_display(note.amount, &props.display)} NOCK"
+                        }
+                        span { class: "note-address", "{note.address.to_nockchain_string()}" }
+                        span { class: "note-state", "{note_state(note)}" }
+                        if let Some(origin) = note.origin {
+                            span { class: "note-tag", "{origin}" }
+                        }
+                        button {
+                            class: "note-toggle",
+                            aria_expanded: props.expanded == Some(note.id),
+                            onclick: {
+                                let id = note.id;
+                                move |_| props.on_toggle.call(id)
+                            },
+                            if props.expanded == Some(note.id) { "▾ Provenance" } else { "▸ Provenance" }
+                        }
+                    }
+                    if props.expanded == Some(note.id) {
+                        if let Some(provenance) = props.provenance.as_ref().filter(|p| p.note.id == note.id) {
+                            ProvenanceView {
+                                provenance: provenance.clone(),
+                                on_open_transaction: props.on_open_transaction,
+                                on_open_chain_transaction: props.on_open_chain_transaction,
+                                on_open_block: props.on_open_block,
+                            }
+                        } else if let Some(error) = props.provenance_error.as_ref() {
+                            div { class: "note-provenance note-error", "{error}" }
+                        } else {
+                            div { class: "note-provenance empty-state", "Loading..." }
+                        }
+                    }
+                }
+            }
+        }
+
+        style { {NOTE_LIST_CSS} }
+    }
+}
+
+#[derive(Props, Clone, PartialEq)]
+struct ProvenanceViewProps {
+    provenance: NoteProvenance,
+    on_open_transaction: EventHandler<String>,
+    on_open_chain_transaction: EventHandler<String>,
+    on_open_block: EventHandler<String>,
+}
+
+fn ProvenanceView(props: ProvenanceViewProps) -> Element {
+    let provenance = &props.provenance;
+    let note = &provenance.note;
+    let tx_id = note.transaction_id.clone();
+    let recorded = provenance.transaction.is_some();
+    let fee = provenance
+        .transaction
+        .as_ref()
+        .filter(|detail| detail.transaction.fee_known())
+        .map(|detail| detail.transaction.fee)
+        .or(provenance.chain_transaction.as_ref().map(|tx| tx.fee));
+    let block_time = provenance
+        .block
+        .as_ref()
+        .map(|block| block.timestamp.format("%Y-%m-%d %H:%M UTC").to_string());
+
+    rsx! {
+        table {
+            class: "note-provenance",
+            tbody {
+                tr {
+                    td { class: "note-label", "Origin" }
+                    td { "{provenance.origin}" }
+                }
+                tr {
+                    td { class: "note-label", "Created by" }
+                    td {
+                        button {
+                            class: "note-link",
+                            onclick: move |_| {
+                                if recorded {
+                                    props.on_open_transaction.call(tx_id.clone());
+                                } else {
+                                    props.on_open_chain_transaction.call(tx_id.clone());
+                                }
+                            },
+                            "{note.transaction_id}"
+                        }
+                        " output #{note.output_index}"
+                    }
+                }
+                if let Some(fee) = fee {
+                    tr {
+                        td { class: "note-label", "Fee paid" }
+                        td { "{fee} nano" }
+                    }
+                }
+                tr {
+                    td { class: "note-label", "Block" }
+                    td {
+                        if let Some(block) = provenance.block.as_ref() {
+                            button {
+                                class: "note-link",
+                                onclick: {
+                                    let hash = block.hash.clone();
+                                    move |_| props.on_open_block.call(hash.clone())
+                                },
+                                "#{block.height}"
+                            }
+                            " · {block_time.clone().unwrap_or_default()}"
+                        } else if let Some(height) = note.block_height {
+                            "#{height} (not in the local chain)"
+                        } else {
+                            "Not in a block yet"
+                        }
+                    }
+                }
+                tr {
+                    td { class: "note-label", "Confirmations" }
+                    td { "{provenance.confirmations}" }
+                }
+            }
+        }
+    }
+}
+
+fn note_state(note: &Note) -> &'static str {
+    if note.spent {
+        "spent"
+    } else if note.locked {
+        "locked"
+    } else if note.block_height.is_none() {
+        "pending"
+    } else {
+        "unspent"
+    }
+}
+
+const NOTE_LIST_CSS: &str = r#"
+.note-list {
+    display: flex;
+    flex-direction: column;
+    background: #f8f9fa;
+    padding: 12px 20px;
+    border-radius: 8px;
+}
+
+.note-row + .note-row {
+    border-top: 1px solid #e9ecef;
+}
+
+.note-summary {
+    display: flex;
+    align-items: baseline;
+    gap: 12px;
+    padding: 8px 0;
+    font-size: 14px;
+}
+
+.note-amount {
+    font-weight: 600;
+    white-space: nowrap;
+}
+
+.note-address {
+    flex: 1;
+    font-family: monospace;
+    font-size: 12px;
+    word-break: break-all;
+    color: #333;
+}
+
+.note-state,
+.note-label {
+    color: #6c757d;
+}
+
+.note-tag {
+    padding: 1px 6px;
+    border-radius: 4px;
+    background: #e9ecef;
+    font-size: 12px;
+}
+
+.note-toggle,
+.note-link {
+    padding: 0;
+    background: none;
+    border: none;
+    color: #667eea;
+    cursor: pointer;
+}
+
+.note-link {
+    font-family: monospace;
+    text-decoration: underline;
+    word-break: break-all;
+    text-align: left;
+}
+
+.note-provenance {
+    margin: 0 0 12px 0;
+    font-size: 13px;
+}
+
+.note-provenance td {
+    padding: 2px 12px 2px 0;
+    vertical-align: top;
+}
+
+.note-error {
+    color: #dc3545;
+}
+"#;
@@ -0,0 +1,71 @@
+use dioxus::prelude::*;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct OverridesChipProps {
+    /// Command-line flags in effect, as `LaunchOptions::active_flags` lists them
+    pub flags: Vec<String>,
+}
+
+/// Chip on the Settings page saying the app was started with flags that
+/// override saved settings for this session; hover or expand it for the list
+pub fn OverridesChip(props: OverridesChipProps) -> Element {
+    if props.flags.is_empty() {
+        return rsx! {};
+    }
+    let flags = props.flags.join(" ");
+
+    rsx! {
+        details {
+            class: "overrides-chip",
+            title: "{flags}",
+            summary { "Overrides active" }
+            div {
+                class: "overrides-chip-body",
+                p { "Started with these command-line options. They apply to this session only and aren't saved." }
+                ul {
+                    for flag in props.flags.iter() {
+                        li { key: "{flag}", code { "{flag}" } }
+                    }
+                }
+            }
+        }
+
+        style { {OVERRIDES_CHIP_CSS} }
+    }
+}
+
+const OVERRIDES_CHIP_CSS: &str = r#"
+.overrides-chip {
+    display: inline-block;
+    margin-bottom: 16px;
+    font-size: 13px;
+}
+
+.overrides-chip summary {
+    display: inline-block;
+    padding: 2px 10px;
+    border-radius: 12px;
+    background: #fff3cd;
+    color: #856404;
+    border: 1px solid #ffe69c;
+    cursor: pointer;
+    list-style: none;
+}
+
+.overrides-chip-body {
+    margin-top: 8px;
+    padding: 8px 12px;
+    background: #f8f9fa;
+    border-radius: 6px;
+    color: #333;
+}
+
+.overrides-chip-body p {
+    margin: 0 0 4px 0;
+}
+
+.overrides-chip-body ul {
+    margin: 0;
+    padding-left: 20px;
+}
+"#;
@@ -0,0 +1,76 @@
+use api::{Address, AddressNetwork};
+use dioxus::prelude::*;
+
+use crate::clipboard::{self, CONFIRMATION_MS};
+
+#[derive(Props, Clone, PartialEq)]
+pub struct PasteAddressButtonProps {
+    /// Called with the address found on the clipboard
+    pub on_paste: EventHandler<Address>,
+    /// Checked addresses for another network are rejected
+    #[props(default)]
+    pub network: AddressNetwork,
+    #[props(default)]
+    pub disabled: bool,
+}
+
+/// Fills a recipient field from the clipboard, accepting an address or a
+/// `nock:` URI, and shows a toast if the clipboard holds neither
+pub fn PasteAddressButton(props: PasteAddressButtonProps) -> Element {
+    let mut error = use_signal(|| None::<String>);
+
+    rsx! {
+        button {
+            r#type: "button",
+            class: "paste-address-button",
+            disabled: props.disabled,
+            onclick: move |_| {
+                let network = props.network;
+                spawn(async move {
+                    let text = clipboard::read().await;
+                    let message = match clipboard::paste_address(text.as_deref(), network) {
+                        Ok(address) => {
+                            props.on_paste.call(address);
+                            return;
+                        }
+                        Err(message) => message,
+                    };
+                    error.set(Some(message));
+                    clipboard::delay(CONFIRMATION_MS).await;
+                    error.set(None);
+                });
+            },
+            "📋 Paste"
+        }
+
+        if let Some(message) = error.read().as_ref() {
+            div { class: "paste-address-toast", "❌ {message}" }
+        }
+
+        style { {PASTE_ADDRESS_BUTTON_CSS} }
+    }
+}
+
+const PASTE_ADDRESS_BUTTON_CSS: &str = r#"
+.paste-address-button {
+    padding: 8px 12px;
+    border: 1px solid #ccc;
+    border-radius: 4px;
+    background: white;
+    cursor: pointer;
+    white-space: nowrap;
+}
+
+.paste-address-toast {
+    position: fixed;
+    bottom: 24px;
+    right: 24px;
+    max-width: 420px;
+    background: #1a1a1a;
+    color: white;
+    padding: 12px 16px;
+    border-radius: 8px;
+    box-shadow: 0 4px 20px rgba(0, 0, 0, 0.25);
+    z-index: 1000;
+}
+"#;
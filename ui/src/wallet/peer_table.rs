@@ -0,0 +1,295 @@
+use api::{country_flag, service_labels, PeerInfo};
+use dioxus::prelude::*;
+
+use crate::format::{format_bytes, format_count, format_rate};
+
+#[derive(Props, Clone, PartialEq)]
+pub struct PeerTableProps {
+    pub peers: Vec<PeerInfo>,
+    /// Location databases are configured; adds the country and ASN columns
+    #[props(default)]
+    pub locations: bool,
+}
+
+pub fn PeerTable(props: PeerTableProps) -> Element {
+    let locations = props.locations;
+
+    rsx! {
+        div {
+            class: "peer-table",
+            h4 { "Peers ({format_count(props.peers.len() as u64)})" }
+            if props.peers.is_empty() {
+                div { class: "peer-empty", "No peers yet" }
+            } else {
+                div {
+                    class: "peer-table-scroll",
+                    table {
+                        thead {
+                            tr {
+                                th { "Peer" }
+                                th { "Source" }
+                                if locations {
+                                    th { "Country" }
+                                    th { "ASN" }
+                                }
+                                th { "State" }
+                                th { "Height" }
+                                th { "Agent" }
+                                th { "Services" }
+                                th { "Score" }
+                                th { "In" }
+                                th { "Out" }
+                                th { "Total in" }
+                                th { "Total out" }
+                            }
+                        }
+                        tbody {
+                            for peer in props.peers {
+                                tr {
+                                    key: "{peer.peer_id}",
+                                    td { class: "peer-id", title: "{peer.address}", "{short_peer_id(&peer.peer_id)}" }
+                                    td { class: "peer-source", "{peer.source.label()}" }
+                                    if locations {
+                                        td {
+                                            class: "peer-country",
+                                            match peer.country_code.as_deref() {
+                                                Some(code) => match country_flag(code) {
+                                                    Some(flag) => format!("{} {}", flag, code),
+                                                    None => code.to_string(),
+                                                },
+                                                None => "—".to_string(),
+                                            }
+                                        }
+                                        td {
+                                            class: "peer-asn",
+                                            title: peer.as_org.clone().unwrap_or_default(),
+                                            match peer.asn {
+                                                Some(asn) => format!("AS{}", asn),
+                                                None => "—".to_string(),
+                                            }
+                                        }
+                                    }
+                                    td {
+                                        if peer.banned {
+                                            span { class: "peer-state banned", "banned" }
+                                        } else if let Some(reason) = peer.rejection.clone() {
+                                            span { class: "peer-state rejected", title: "{reason}", "rejected" }
+                                        } else if peer.connected {
+                                            span { class: "peer-state connected", "connected" }
+                                        } else {
+                                            span { class: "peer-state", "disconnected" }
+                                        }
+                                    }
+                                    td {
+                                        if let Some(height) = peer.best_height {
+                                            "{format_count(height)}"
+                                        } else {
+                                            "—"
+                                        }
+                                    }
+                                    td { class: "peer-agent", {peer.user_agent.clone().unwrap_or_else(|| "—".to_string())} }
+                                    td {
+                                        class: "peer-services",
+                                        match peer.services {
+                                            Some(services) => {
+                                                let labels = service_labels(services);
+                                                if labels.is_empty() { "none".to_string() } else { labels.join(", ") }
+                                            }
+                                            None => "—".to_string(),
+                                        }
+                                    }
+                                    td { "{peer.score}" }
+                                    td { "{format_rate(peer.rate_in)}" }
+                                    td { "{format_rate(peer.rate_out)}" }
+                                    td { "{format_bytes(peer.bytes_in)}" }
+                                    td { "{format_bytes(peer.bytes_out)}" }
+                                }
+                            }
+                        }
+                    }
+                }
+                if locations {
+                    div {
+                        class: "peer-privacy",
+                        "Countries and networks come from the location databases on this computer; peer addresses are never sent anywhere to look them up."
+                    }
+                }
+            }
+        }
+
+        style { {PEER_TABLE_CSS} }
+    }
+}
+
+fn short_peer_id(peer_id: &str) -> String {
+    if peer_id.len() > 16 {
+        format!("{}…{}", &peer_id[..8], &peer_id[peer_id.len() - 6..])
+    } else {
+        peer_id.to_string()
+    }
+}
+
+const PEER_TABLE_CSS: &str = r#"
+.peer-table {
+    background: #f8f9fa;
+    padding: 20px;
+    border-radius: 8px;
+    margin-top: 24px;
+}
+
+.peer-table-scroll {
+    overflow-x: auto;
+    -webkit-overflow-scrolling: touch;
+}
+
+.peer-table h4 {
+    color: #333;
+    margin: 0 0 12px 0;
+}
+
+.peer-table table {
+    width: 100%;
+    border-collapse: collapse;
+    font-size: 12px;
+}
+
+.peer-table th {
+    text-align: left;
+    color: #666;
+    font-weight: 600;
+    padding: 6px 8px;
+    border-bottom: 1px solid #dee2e6;
+}
+
+.peer-table td {
+    padding: 6px 8px;
+    border-bottom: 1px solid #eef0f2;
+    color: #333;
+    white-space: nowrap;
+}
+
+/* The peer id stays in view while the other columns scroll */
+.peer-table th:first-child,
+.peer-table td:first-child {
+    position: sticky;
+    left: 0;
+    background: #f8f9fa;
+    z-index: 1;
+}
+
+.peer-table .peer-id {
+    font-family: monospace;
+}
+
+.peer-table .peer-source,
+.peer-table .peer-asn,
+.peer-table .peer-agent,
+.peer-table .peer-services {
+    color: #6c757d;
+    white-space: nowrap;
+}
+
+.peer-state {
+    color: #6c757d;
+}
+
+.peer-state.connected {
+    color: #28a745;
+    font-weight: 600;
+}
+
+.peer-state.rejected {
+    color: #b45309;
+    cursor: help;
+}
+
+.peer-state.banned {
+    color: #dc3545;
+    font-weight: 600;
+}
+
+.peer-table .peer-asn[title]:not([title=""]) {
+    cursor: help;
+}
+
+.peer-privacy {
+    margin-top: 8px;
+    color: #6c757d;
+    font-size: 12px;
+}
+
+.peer-empty {
+    color: #6c757d;
+    font-style: italic;
+}
+
+@media (max-width: 480px) {
+    .peer-table {
+        padding: 12px;
+    }
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::render;
+    use api::wallet::handshake::{SERVICE_FULL_NODE, SERVICE_MINING};
+    use api::PeerSource;
+
+    fn peer(peer_id: &str) -> PeerInfo {
+        PeerInfo {
+            peer_id: peer_id.to_string(),
+            address: format!("/ip4/10.0.0.1/tcp/4001/p2p/{}", peer_id),
+            source: PeerSource::Static,
+            connected: true,
+            bytes_in: 0,
+            bytes_out: 0,
+            rate_in: 0.0,
+            rate_out: 0.0,
+            score: 100,
+            banned: false,
+            best_height: None,
+            user_agent: None,
+            services: None,
+            rejection: None,
+            country_code: None,
+            asn: None,
+            as_org: None,
+        }
+    }
+
+    #[test]
+    fn peers_show_their_agent_services_and_rejection() {
+        let peers = vec![
+            PeerInfo {
+                user_agent: Some("nockchain/1.2.3".to_string()),
+                services: Some(SERVICE_FULL_NODE | SERVICE_MINING),
+                ..peer("current")
+            },
+            PeerInfo {
+                connected: false,
+                user_agent: Some("nockchain/0.1.0".to_string()),
+                services: Some(0),
+                rejection: Some("version 0.1.0 does not satisfy >=0.2.0".to_string()),
+                ..peer("old")
+            },
+        ];
+        let html = render(
+            PeerTable,
+            PeerTableProps {
+                peers,
+                locations: false,
+            },
+        );
+        assert!(html.contains("nockchain/1.2.3"), "{}", html);
+        assert!(html.contains(">full node, mining<"), "{}", html);
+        assert!(html.contains(">none<"), "{}", html);
+        assert!(
+            html.contains(r#"title="version 0.1.0 does not satisfy >=0.2.0">rejected<"#),
+            "{}",
+            html
+        );
+        assert_eq!(html.matches(">connected<").count(), 1, "{}", html);
+    }
+}
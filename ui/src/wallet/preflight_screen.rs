@@ -0,0 +1,146 @@
+use api::{CheckResult, CheckStatus};
+use dioxus::prelude::*;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct PreflightScreenProps {
+    /// Results of the startup self-test, in the order run
+    pub results: Vec<CheckResult>,
+    /// Open the app despite warnings; not offered while a check fails
+    pub on_continue: EventHandler<()>,
+    pub on_retry: EventHandler<()>,
+    #[props(default)]
+    pub busy: bool,
+}
+
+/// Startup self-test results shown before the main UI, each warning or
+/// failure with what to do about it
+pub fn PreflightScreen(props: PreflightScreenProps) -> Element {
+    let worst = props
+        .results
+        .iter()
+        .map(|result| result.status)
+        .max()
+        .unwrap_or(CheckStatus::Pass);
+    let summary = match worst {
+        CheckStatus::Pass => "Everything checks out.",
+        CheckStatus::Warn => "Some checks found problems. The app can still open.",
+        CheckStatus::Fail => "Fix the failed checks below, then check again.",
+    };
+
+    rsx! {
+        div {
+            class: "preflight",
+            h2 { "Startup checks" }
+            div { class: "preflight-summary", "{summary}" }
+            ul {
+                class: "preflight-list",
+                for result in props.results.iter() {
+                    li {
+                        key: "{result.name}",
+                        class: match result.status {
+                            CheckStatus::Pass => "preflight-check preflight-pass",
+                            CheckStatus::Warn => "preflight-check preflight-warn",
+                            CheckStatus::Fail => "preflight-check preflight-fail",
+                        },
+                        div {
+                            class: "preflight-check-head",
+                            span { class: "preflight-name", "{result.name}" }
+                            span { class: "preflight-status", "{result.status}" }
+                        }
+                        div { class: "preflight-detail", "{result.detail}" }
+                        if let Some(hint) = result.hint.as_ref() {
+                            div { class: "preflight-hint", "{hint}" }
+                        }
+                    }
+                }
+            }
+            div {
+                class: "preflight-actions",
+                button {
+                    disabled: props.busy,
+                    onclick: move |_| props.on_retry.call(()),
+                    if props.busy { "Checking…" } else { "Check again" }
+                }
+                if worst < CheckStatus::Fail {
+                    button {
+                        disabled: props.busy,
+                        onclick: move |_| props.on_continue.call(()),
+                        "Continue"
+                    }
+                }
+            }
+        }
+
+        style { {PREFLIGHT_SCREEN_CSS} }
+    }
+}
+
+const PREFLIGHT_SCREEN_CSS: &str = r#"
+.preflight {
+    display: flex;
+    flex-direction: column;
+    gap: 12px;
+    max-width: 560px;
+    margin: 40px auto;
+    background: #f8f9fa;
+    padding: 20px;
+    border-radius: 8px;
+    font-size: 14px;
+    color: #333;
+}
+
+.preflight h2 {
+    margin: 0;
+}
+
+.preflight-summary,
+.preflight-detail {
+    color: #6c757d;
+}
+
+.preflight-list {
+    display: flex;
+    flex-direction: column;
+    gap: 8px;
+    list-style: none;
+    margin: 0;
+    padding: 0;
+}
+
+.preflight-check {
+    border-left: 4px solid #28a745;
+    padding: 4px 0 4px 12px;
+}
+
+.preflight-warn {
+    border-left-color: #ffc107;
+}
+
+.preflight-fail {
+    border-left-color: #dc3545;
+}
+
+.preflight-check-head {
+    display: flex;
+    justify-content: space-between;
+    font-weight: 600;
+}
+
+.preflight-fail .preflight-status {
+    color: #dc3545;
+}
+
+.preflight-hint {
+    margin-top: 4px;
+}
+
+.preflight-actions {
+    display: flex;
+    justify-content: flex-end;
+    gap: 8px;
+}
+
+.preflight-actions button {
+    padding: 6px 14px;
+}
+"#;
@@ -0,0 +1,181 @@
+use api::WalletProfile;
+use dioxus::prelude::*;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct ProfilePickerProps {
+    /// Profiles to choose from, most recently opened first
+    pub profiles: Vec<WalletProfile>,
+    /// Called with the profile name and the password entered, if any
+    pub on_open: EventHandler<(String, Option<String>)>,
+    /// Called with the new profile's name and optional password
+    pub on_create: EventHandler<(String, Option<String>)>,
+    /// Why the last open or create failed
+    #[props(default)]
+    pub error: Option<String>,
+    #[props(default)]
+    pub busy: bool,
+    /// Profile selected at first instead of the most recent one
+    #[props(default)]
+    pub initial: Option<String>,
+}
+
+/// Chooses the wallet profile to open, or creates a new one
+pub fn ProfilePicker(props: ProfilePickerProps) -> Element {
+    let mut selected = use_signal(|| {
+        props
+            .initial
+            .clone()
+            .or_else(|| props.profiles.first().map(|profile| profile.name.clone()))
+    });
+    let mut password = use_signal(String::new);
+    let mut new_name = use_signal(String::new);
+    let mut new_password = use_signal(String::new);
+
+    let needs_password = props.profiles.iter().any(|profile| {
+        selected.read().as_deref() == Some(profile.name.as_str()) && profile.has_password()
+    });
+    let optional = |value: String| (!value.is_empty()).then_some(value);
+
+    rsx! {
+        div {
+            class: "profile-picker",
+            h2 { "Choose a wallet" }
+            if !props.profiles.is_empty() {
+                div {
+                    class: "profile-picker-list",
+                    for profile in props.profiles.iter() {
+                        label {
+                            key: "{profile.name}",
+                            class: "profile-picker-item",
+                            input {
+                                r#type: "radio",
+                                name: "profile",
+                                checked: selected.read().as_deref() == Some(profile.name.as_str()),
+                                onchange: {
+                                    let name = profile.name.clone();
+                                    move |_| {
+                                        selected.set(Some(name.clone()));
+                                        password.set(String::new());
+                                    }
+                                },
+                            }
+                            span { class: "profile-picker-name", "{profile.name}" }
+                            span {
+                                class: "profile-picker-opened",
+                                match profile.last_opened {
+                                    Some(at) => format!("Last opened {}", at.format("%Y-%m-%d %H:%M UTC")),
+                                    None => "Never opened".to_string(),
+                                }
+                            }
+                        }
+                    }
+                }
+                if needs_password {
+                    input {
+                        r#type: "password",
+                        placeholder: "Password",
+                        aria_label: "Password",
+                        value: "{password}",
+                        oninput: move |evt| password.set(evt.value()),
+                    }
+                }
+                button {
+                    disabled: props.busy || selected.read().is_none(),
+                    onclick: move |_| {
+                        if let Some(name) = selected() {
+                            props.on_open.call((name, optional(password())));
+                        }
+                    },
+                    "Open"
+                }
+            }
+            div {
+                class: "profile-picker-create",
+                h3 { "New profile" }
+                input {
+                    placeholder: "Name",
+                    aria_label: "Profile name",
+                    value: "{new_name}",
+                    oninput: move |evt| new_name.set(evt.value()),
+                }
+                input {
+                    r#type: "password",
+                    placeholder: "Password (optional)",
+                    aria_label: "Password (optional)",
+                    value: "{new_password}",
+                    oninput: move |evt| new_password.set(evt.value()),
+                }
+                button {
+                    disabled: props.busy || new_name.read().trim().is_empty(),
+                    onclick: move |_| {
+                        props.on_create.call((new_name(), optional(new_password())));
+                        new_password.set(String::new());
+                    },
+                    "Create"
+                }
+            }
+            if let Some(error) = props.error {
+                div { class: "profile-picker-error", "{error}" }
+            }
+        }
+
+        style { {PROFILE_PICKER_CSS} }
+    }
+}
+
+const PROFILE_PICKER_CSS: &str = r#"
+.profile-picker {
+    display: flex;
+    flex-direction: column;
+    gap: 12px;
+    max-width: 420px;
+    margin: 40px auto;
+    background: #f8f9fa;
+    padding: 20px;
+    border-radius: 8px;
+    font-size: 14px;
+    color: #333;
+}
+
+.profile-picker h2,
+.profile-picker h3 {
+    margin: 0;
+}
+
+.profile-picker-list,
+.profile-picker-create {
+    display: flex;
+    flex-direction: column;
+    gap: 8px;
+}
+
+.profile-picker-item {
+    display: flex;
+    align-items: center;
+    gap: 8px;
+}
+
+.profile-picker-name {
+    font-weight: 600;
+}
+
+.profile-picker-opened {
+    margin-left: auto;
+    color: #6c757d;
+    font-size: 12px;
+}
+
+.profile-picker input[type="password"],
+.profile-picker-create input {
+    padding: 6px 8px;
+}
+
+.profile-picker button {
+    align-self: flex-start;
+    padding: 6px 14px;
+}
+
+.profile-picker-error {
+    color: #dc3545;
+}
+"#;
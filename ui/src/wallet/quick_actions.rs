@@ -176,6 +176,12 @@ const QUICK_ACTIONS_CSS: &str = r#"
         padding: 12px 8px;
         min-height: 70px;
         border-radius: 12px;
+        touch-action: manipulation;
+    }
+
+    /* No hover on touch screens; show the press instead */
+    .action-button:active {
+        transform: scale(0.96);
     }
 }
 "#;
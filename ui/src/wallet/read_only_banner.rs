@@ -0,0 +1,75 @@
+use dioxus::prelude::*;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct ReadOnlyBannerProps {
+    /// Try to take the wallet lock again
+    #[props(default)]
+    pub on_retry: Option<EventHandler<()>>,
+    /// Why the wallet is read-only, if not because another instance holds the lock
+    #[props(default)]
+    pub message: Option<String>,
+}
+
+/// Shown while the wallet is read-only, normally because another instance
+/// holds the wallet lock
+pub fn ReadOnlyBanner(props: ReadOnlyBannerProps) -> Element {
+    let message = props.message.unwrap_or_else(|| {
+        "Another instance of the app has this wallet open. Balances, history and receive addresses still work; sending, key changes and settings are disabled until it exits.".to_string()
+    });
+
+    rsx! {
+        div {
+            class: "read-only-banner",
+            role: "status",
+            span { class: "read-only-icon", "🔒" }
+            div {
+                class: "read-only-text",
+                strong { "Read-only" }
+                span { " {message}" }
+            }
+            if let Some(on_retry) = props.on_retry {
+                button {
+                    class: "read-only-retry",
+                    onclick: move |_| on_retry.call(()),
+                    "Retry"
+                }
+            }
+        }
+
+        style { {READ_ONLY_BANNER_CSS} }
+    }
+}
+
+const READ_ONLY_BANNER_CSS: &str = r#"
+.read-only-banner {
+    display: flex;
+    align-items: center;
+    gap: 12px;
+    padding: 10px 20px;
+    background: #fff3cd;
+    border-bottom: 1px solid #ffe69c;
+    color: #664d03;
+    font-size: 14px;
+}
+
+.read-only-icon {
+    font-size: 18px;
+}
+
+.read-only-text {
+    flex: 1;
+}
+
+.read-only-retry {
+    padding: 6px 14px;
+    background: white;
+    color: #664d03;
+    border: 1px solid #ffda6a;
+    border-radius: 4px;
+    cursor: pointer;
+}
+
+.read-only-retry:hover {
+    background: #fff8e1;
+}
+"#;
@@ -0,0 +1,130 @@
+use dioxus::prelude::*;
+
+use crate::Dialog;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct ReauthDialogProps {
+    /// What the password is being asked for
+    pub title: String,
+    #[props(default)]
+    pub message: Option<String>,
+    /// Without a password set, confirming is enough
+    #[props(default = true)]
+    pub password_required: bool,
+    /// Why the last attempt failed
+    #[props(default)]
+    pub error: Option<String>,
+    #[props(default)]
+    pub busy: bool,
+    /// Called with the password entered
+    pub on_submit: EventHandler<String>,
+    /// Omitted where the dialog can't be dismissed, as on the lock screen
+    #[props(default)]
+    pub on_cancel: Option<EventHandler<()>>,
+}
+
+/// Asks for the wallet password, to unlock after an auto-lock or before a
+/// sensitive operation when the last entry is too old
+pub fn ReauthDialog(props: ReauthDialogProps) -> Element {
+    let mut password = use_signal(String::new);
+    let can_submit = !props.busy && (!props.password_required || !password.read().is_empty());
+    let mut submit = move || {
+        props.on_submit.call(password());
+        password.set(String::new());
+    };
+
+    rsx! {
+        Dialog {
+            title: props.title.clone(),
+            class: "reauth-dialog",
+            on_close: props.on_cancel.filter(|_| !props.busy),
+            if let Some(message) = props.message.clone() {
+                p { "{message}" }
+            }
+            if props.password_required {
+                input {
+                    r#type: "password",
+                    placeholder: "Password",
+                    aria_label: "Password",
+                    autofocus: true,
+                    value: "{password}",
+                    disabled: props.busy,
+                    oninput: move |evt| password.set(evt.value()),
+                    onkeydown: move |evt| {
+                        if evt.key() == Key::Enter && can_submit {
+                            submit();
+                        }
+                    },
+                }
+            }
+            if let Some(error) = props.error.clone() {
+                div { class: "reauth-error", role: "alert", "{error}" }
+            }
+            div {
+                class: "reauth-actions",
+                if let Some(on_cancel) = props.on_cancel {
+                    button {
+                        disabled: props.busy,
+                        onclick: move |_| on_cancel.call(()),
+                        "Cancel"
+                    }
+                }
+                button {
+                    class: "reauth-confirm",
+                    disabled: !can_submit,
+                    onclick: move |_| submit(),
+                    if props.password_required { "Confirm" } else { "Unlock" }
+                }
+            }
+        }
+
+        style { {REAUTH_DIALOG_CSS} }
+    }
+}
+
+const REAUTH_DIALOG_CSS: &str = r#"
+.reauth-dialog {
+    display: flex;
+    flex-direction: column;
+    gap: 12px;
+    width: 360px;
+}
+
+.reauth-dialog h4,
+.reauth-dialog p {
+    margin: 0;
+}
+
+.reauth-dialog input {
+    padding: 8px;
+    border: 1px solid #ced4da;
+    border-radius: 4px;
+}
+
+.reauth-error {
+    color: #dc3545;
+}
+
+.reauth-actions {
+    display: flex;
+    justify-content: flex-end;
+    gap: 8px;
+}
+
+.reauth-actions button {
+    padding: 6px 14px;
+}
+
+.reauth-confirm {
+    background: #667eea;
+    color: white;
+    border: none;
+    border-radius: 4px;
+    cursor: pointer;
+}
+
+.reauth-confirm:disabled {
+    opacity: 0.6;
+    cursor: default;
+}
+"#;
@@ -0,0 +1,73 @@
+use api::wallet::qr::render_svg;
+use api::Receipt;
+use dioxus::prelude::*;
+
+use super::balance_card::format_balance;
+use super::CopyButton;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct ReceiptViewProps {
+    pub receipt: Receipt,
+}
+
+/// A signed payment receipt as a QR code, with its JSON and QR text to copy
+pub fn ReceiptView(props: ReceiptViewProps) -> Element {
+    let receipt = props.receipt;
+    let json = receipt.to_json();
+    let qr_text = receipt.to_qr_string();
+    let qr_svg = render_svg(&qr_text).ok();
+    let status = if receipt.proof.is_some() {
+        "Includes a merkle proof of confirmation"
+    } else {
+        "Unconfirmed; export again after confirmation to include a proof"
+    };
+
+    rsx! {
+        div {
+            class: "receipt-view",
+            div {
+                class: "receipt-view-summary",
+                "{format_balance(receipt.amount)} NOCK to {receipt.recipient}"
+            }
+            div { class: "receipt-view-status", "{status}" }
+            if let Some(qr_svg) = qr_svg {
+                div { class: "receipt-view-qr", dangerous_inner_html: qr_svg }
+            }
+            div {
+                class: "receipt-view-actions",
+                CopyButton { text: json, label: "Copy JSON" }
+                CopyButton { text: qr_text, label: "Copy QR text" }
+            }
+        }
+
+        style { {RECEIPT_VIEW_CSS} }
+    }
+}
+
+const RECEIPT_VIEW_CSS: &str = r#"
+.receipt-view {
+    display: flex;
+    flex-direction: column;
+    gap: 8px;
+    margin-top: 8px;
+}
+
+.receipt-view-summary {
+    word-break: break-all;
+}
+
+.receipt-view-status {
+    color: #6c757d;
+    font-size: 12px;
+}
+
+.receipt-view-qr svg {
+    width: 200px;
+    height: 200px;
+}
+
+.receipt-view-actions {
+    display: flex;
+    gap: 8px;
+}
+"#;
@@ -1,17 +1,243 @@
+use api::wallet::qr::render_svg;
+use api::{format_amount, AmountUnit, PaymentMatch, PaymentRequest, PaymentRequestStatus};
 use dioxus::prelude::*;
 
+use super::{AmountInput, CopyButton};
+use crate::t;
+
+/// Expiry choices offered for a payment request, in minutes; `None` never expires
+const REQUEST_EXPIRIES: [Option<u32>; 4] = [None, Some(15), Some(60), Some(24 * 60)];
+
+/// A payment request as entered
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaymentRequestValues {
+    pub label: String,
+    /// Amount asked for, in base units
+    pub amount: u64,
+    pub expires_in_minutes: Option<u32>,
+}
+
 #[derive(Props, Clone, PartialEq)]
 pub struct ReceiveViewProps {
+    /// Address to show; empty until one has been generated
     pub address: String,
+    /// Label the user gave the address
+    #[props(default)]
+    pub label: Option<String>,
+    /// Generate a new address, called with the label to give it (possibly
+    /// blank); without it no new address is offered
+    #[props(default)]
+    pub on_new_address: Option<EventHandler<String>>,
+    /// Save the address QR code as an image; without it no button is shown
+    #[props(default)]
+    pub on_save_image: Option<EventHandler<()>>,
+    /// Ask for a payment at a fresh address; without it no amount can be
+    /// entered
+    #[props(default)]
+    pub on_new_request: Option<EventHandler<PaymentRequestValues>>,
+    /// Payment request made for the address shown, to show its status
+    #[props(default)]
+    pub request: Option<PaymentRequest>,
 }
 
 pub fn ReceiveView(props: ReceiveViewProps) -> Element {
+    let mut new_label = use_signal(String::new);
+    let mut request_amount = use_signal(|| None::<u64>);
+    let mut request_expiry = use_signal(|| None::<u32>);
+    let status = props.request.as_ref().map(request_status);
+    let qr_svg = (!props.address.is_empty())
+        .then(|| render_svg(&props.address).ok())
+        .flatten();
+
     rsx! {
         div {
             class: "receive-view",
-            h3 { "Receive Nockchain" }
-            div { class: "qr-code-placeholder", "QR Code Here" }
-            div { class: "address", "{props.address}" }
+            h3 { {t!("receive.title")} }
+            if props.address.is_empty() {
+                div { class: "empty-state", {t!("receive.empty")} }
+            } else {
+                if let Some(qr_svg) = qr_svg {
+                    div { class: "receive-qr", dangerous_inner_html: qr_svg }
+                }
+                if let Some(label) = props.label.as_ref() {
+                    div { class: "receive-label", "{label}" }
+                }
+                div { class: "address", "{props.address}" }
+                if let Some((class, status)) = status {
+                    div { class: "receive-request {class}", role: "status", "{status}" }
+                }
+                div {
+                    class: "receive-actions",
+                    CopyButton { text: props.address.clone(), label: t!("receive.copy_address") }
+                    if let Some(on_save_image) = props.on_save_image {
+                        button { onclick: move |_| on_save_image.call(()), {t!("receive.save_image")} }
+                    }
+                }
+            }
+            if let Some(on_new_address) = props.on_new_address {
+                div {
+                    class: "receive-new",
+                    input {
+                        placeholder: t!("receive.label_placeholder"),
+                        value: "{new_label}",
+                        oninput: move |evt| new_label.set(evt.value()),
+                    }
+                    button {
+                        onclick: move |_| {
+                            on_new_address.call(new_label.read().trim().to_string());
+                            new_label.set(String::new());
+                        },
+                        {t!("receive.new_address")}
+                    }
+                }
+            }
+            if let Some(on_new_request) = props.on_new_request {
+                div {
+                    class: "receive-new",
+                    AmountInput {
+                        value: request_amount(),
+                        on_change: move |amount| request_amount.set(amount),
+                        placeholder: t!("receive.request_amount"),
+                    }
+                    select {
+                        aria_label: t!("receive.expiry.label"),
+                        onchange: move |evt| request_expiry.set(evt.value().parse().ok()),
+                        for minutes in REQUEST_EXPIRIES {
+                            option {
+                                value: minutes.map(|minutes| minutes.to_string()).unwrap_or_default(),
+                                selected: request_expiry() == minutes,
+                                {expiry_label(minutes)}
+                            }
+                        }
+                    }
+                    button {
+                        disabled: request_amount().is_none(),
+                        onclick: move |_| {
+                            let Some(amount) = request_amount() else {
+                                return;
+                            };
+                            on_new_request.call(PaymentRequestValues {
+                                label: new_label.read().trim().to_string(),
+                                amount,
+                                expires_in_minutes: request_expiry(),
+                            });
+                            new_label.set(String::new());
+                            request_amount.set(None);
+                        },
+                        {t!("receive.request_payment")}
+                    }
+                }
+            }
+        }
+
+        style { {RECEIVE_VIEW_CSS} }
+    }
+}
+
+fn expiry_label(minutes: Option<u32>) -> String {
+    match minutes {
+        None => t!("receive.expiry.never"),
+        Some(minutes) if minutes % 60 == 0 => t!("receive.expiry.hours", count = minutes / 60),
+        Some(minutes) => t!("receive.expiry.minutes", count = minutes),
+    }
+}
+
+/// CSS class and text for the status of `request`
+fn request_status(request: &PaymentRequest) -> (&'static str, String) {
+    let expected = format_amount(request.amount, AmountUnit::Nock);
+    match &request.status {
+        PaymentRequestStatus::Waiting => {
+            let text = match request.expires_at {
+                Some(at) => t!(
+                    "receive.status.waiting_until",
+                    amount = expected,
+                    time = at.format("%H:%M UTC")
+                ),
+                None => t!("receive.status.waiting", amount = expected),
+            };
+            ("waiting", text)
+        }
+        PaymentRequestStatus::Paid {
+            received, matched, ..
+        } => {
+            let received = format_amount(*received, AmountUnit::Nock);
+            match matched {
+                PaymentMatch::Exact => ("paid", t!("receive.status.paid", amount = received)),
+                PaymentMatch::Over => (
+                    "paid",
+                    t!(
+                        "receive.status.overpaid",
+                        amount = received,
+                        expected = expected
+                    ),
+                ),
+                PaymentMatch::Under => (
+                    "under",
+                    t!(
+                        "receive.status.underpaid",
+                        amount = received,
+                        expected = expected
+                    ),
+                ),
+            }
         }
+        PaymentRequestStatus::Expired { .. } => ("expired", t!("receive.status.expired")),
     }
 }
+
+const RECEIVE_VIEW_CSS: &str = r#"
+.receive-qr svg {
+    width: 200px;
+    height: 200px;
+}
+
+.receive-actions {
+    display: flex;
+    gap: 8px;
+}
+
+.receive-view .address {
+    font-family: monospace;
+    word-break: break-all;
+    margin: 8px 0;
+}
+
+.receive-label {
+    font-weight: 600;
+    color: #333;
+}
+
+.receive-new {
+    display: flex;
+    gap: 8px;
+    margin-top: 12px;
+}
+
+.receive-new input {
+    flex: 1;
+    padding: 6px 8px;
+    border: 1px solid #ccc;
+    border-radius: 4px;
+}
+
+.receive-request {
+    margin-bottom: 8px;
+    font-weight: 600;
+}
+
+.receive-request.waiting {
+    color: #6c757d;
+}
+
+.receive-request.paid {
+    color: #28a745;
+}
+
+.receive-request.under {
+    color: #b58105;
+}
+
+.receive-request.expired {
+    color: #dc3545;
+}
+"#;
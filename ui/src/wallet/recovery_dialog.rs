@@ -0,0 +1,67 @@
+use api::RecoveryAction;
+use dioxus::prelude::*;
+
+use crate::Dialog;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct RecoveryDialogProps {
+    /// What recovery did with each interrupted operation
+    pub actions: Vec<RecoveryAction>,
+    /// Recovery is still running
+    #[props(default)]
+    pub recovering: bool,
+    pub on_dismiss: EventHandler<()>,
+}
+
+/// Shown after opening a wallet whose last session was interrupted part-way
+/// through an operation
+pub fn RecoveryDialog(props: RecoveryDialogProps) -> Element {
+    let title = if props.recovering {
+        "Recovering previous session…"
+    } else {
+        "Previous session recovered"
+    };
+
+    rsx! {
+        Dialog {
+            title,
+            class: "recovery-dialog",
+            on_close: (!props.recovering).then_some(props.on_dismiss),
+            p { "The app closed before these operations finished." }
+            ul {
+                aria_live: "polite",
+                for action in props.actions.iter() {
+                    li {
+                        class: if matches!(action, RecoveryAction::Failed { .. }) { "recovery-failed" },
+                        "{action}"
+                    }
+                }
+            }
+            button {
+                disabled: props.recovering,
+                onclick: move |_| props.on_dismiss.call(()),
+                "OK"
+            }
+        }
+
+        style { {RECOVERY_DIALOG_CSS} }
+    }
+}
+
+const RECOVERY_DIALOG_CSS: &str = r#"
+.recovery-dialog ul {
+    padding-left: 20px;
+}
+
+.recovery-dialog li {
+    margin-bottom: 6px;
+}
+
+.recovery-dialog li.recovery-failed {
+    color: #dc3545;
+}
+
+.recovery-dialog button {
+    padding: 6px 14px;
+}
+"#;
@@ -0,0 +1,226 @@
+use api::{MatchRank, SearchResult, SearchTarget};
+use dioxus::prelude::*;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct SearchBarProps {
+    /// Results for the current query, best first
+    pub results: Vec<SearchResult>,
+    /// Called with the query as it is typed
+    pub on_query: EventHandler<String>,
+    /// Called with the chosen result's target
+    pub on_open: EventHandler<SearchTarget>,
+    /// Results shown before "Show more"
+    #[props(default = 8)]
+    pub limit: usize,
+}
+
+/// Search field for transaction ids, addresses, contacts, labels and blocks
+pub fn SearchBar(props: SearchBarProps) -> Element {
+    let mut query = use_signal(String::new);
+    let mut show_all = use_signal(|| false);
+
+    let (shown, hidden) = visible_results(props.results.len(), props.limit, show_all());
+    let open = !query.read().trim().is_empty();
+
+    rsx! {
+        div {
+            class: "search-bar",
+            input {
+                r#type: "search",
+                placeholder: "Search transactions, addresses, contacts…",
+                aria_label: "Search transactions, addresses, contacts…",
+                value: "{query}",
+                oninput: move |evt| {
+                    show_all.set(false);
+                    query.set(evt.value());
+                    props.on_query.call(evt.value());
+                },
+                onkeydown: move |evt| {
+                    if evt.key() == Key::Escape {
+                        query.set(String::new());
+                        props.on_query.call(String::new());
+                    }
+                },
+            }
+            if open {
+                div {
+                    class: "search-results",
+                    if props.results.is_empty() {
+                        div { class: "search-empty", "No matches" }
+                    }
+                    for result in props.results.iter().take(shown).cloned() {
+                        button {
+                            key: "{kind_label(&result.target)}-{result.title}",
+                            class: "search-result",
+                            onclick: move |_| {
+                                query.set(String::new());
+                                props.on_query.call(String::new());
+                                props.on_open.call(result.target.clone());
+                            },
+                            span { class: "search-kind", "{kind_label(&result.target)}" }
+                            span {
+                                class: "search-title",
+                                "{result.title}"
+                                if result.rank == MatchRank::Exact {
+                                    span { class: "search-exact", "exact" }
+                                }
+                            }
+                            span { class: "search-detail", "{result.detail}" }
+                        }
+                    }
+                    if hidden > 0 {
+                        button {
+                            class: "search-more",
+                            onclick: move |_| show_all.set(true),
+                            "Show {hidden} more"
+                        }
+                    }
+                }
+            }
+        }
+
+        style { {SEARCH_BAR_CSS} }
+    }
+}
+
+/// Results to show and to hide behind "Show more", out of `total`
+fn visible_results(total: usize, limit: usize, show_all: bool) -> (usize, usize) {
+    let shown = if show_all { total } else { total.min(limit) };
+    (shown, total - shown)
+}
+
+fn kind_label(target: &SearchTarget) -> &'static str {
+    match target {
+        SearchTarget::Transaction { .. } => "Transaction",
+        SearchTarget::Address { .. } => "Address",
+        SearchTarget::Contact { .. } => "Contact",
+        SearchTarget::Block { .. } => "Block",
+    }
+}
+
+const SEARCH_BAR_CSS: &str = r#"
+.search-bar {
+    position: relative;
+    flex: 1;
+    max-width: 420px;
+    margin: 0 20px;
+}
+
+.search-bar input {
+    width: 100%;
+    padding: 8px 12px;
+    border: none;
+    border-radius: 6px;
+    font-size: 14px;
+}
+
+.search-results {
+    position: absolute;
+    top: calc(100% + 4px);
+    left: 0;
+    right: 0;
+    z-index: 10;
+    display: flex;
+    flex-direction: column;
+    background: white;
+    border-radius: 6px;
+    box-shadow: 0 4px 12px rgba(0, 0, 0, 0.2);
+    max-height: 400px;
+    overflow-y: auto;
+}
+
+.search-result,
+.search-more {
+    display: flex;
+    flex-direction: column;
+    align-items: flex-start;
+    gap: 2px;
+    padding: 8px 12px;
+    border: none;
+    border-bottom: 1px solid #eee;
+    background: none;
+    color: #333;
+    text-align: left;
+    cursor: pointer;
+}
+
+.search-result:hover,
+.search-more:hover {
+    background: #f1f3f5;
+}
+
+.search-kind {
+    font-size: 11px;
+    color: #6c757d;
+    text-transform: uppercase;
+}
+
+.search-title {
+    font-family: monospace;
+    font-size: 13px;
+    word-break: break-all;
+}
+
+.search-exact {
+    margin-left: 6px;
+    padding: 0 6px;
+    background: #d1e7dd;
+    color: #0f5132;
+    border-radius: 8px;
+    font-family: sans-serif;
+    font-size: 11px;
+}
+
+.search-detail {
+    font-size: 12px;
+    color: #6c757d;
+    word-break: break-all;
+}
+
+.search-more {
+    align-items: center;
+    color: #007bff;
+}
+
+.search-empty {
+    padding: 8px 12px;
+    color: #6c757d;
+    font-size: 13px;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn results_past_the_limit_wait_behind_show_more() {
+        assert_eq!(visible_results(3, 8, false), (3, 0));
+        assert_eq!(visible_results(20, 8, false), (8, 12));
+        assert_eq!(visible_results(20, 8, true), (20, 0));
+        assert_eq!(visible_results(0, 8, false), (0, 0));
+    }
+
+    #[test]
+    fn every_target_kind_has_a_label() {
+        let targets = [
+            SearchTarget::Transaction {
+                tx_id: "aa".to_string(),
+            },
+            SearchTarget::Address {
+                address: "nock1".to_string(),
+            },
+            SearchTarget::Contact {
+                name: "Bob".to_string(),
+            },
+            SearchTarget::Block {
+                height: 1,
+                hash: "bb".to_string(),
+            },
+        ];
+        assert_eq!(
+            targets.each_ref().map(kind_label),
+            ["Transaction", "Address", "Contact", "Block"]
+        );
+    }
+}
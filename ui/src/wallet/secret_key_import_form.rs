@@ -0,0 +1,124 @@
+use api::preview_secret_key;
+use dioxus::prelude::*;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct SecretKeyImportFormProps {
+    /// Called with the key name and the secret as pasted
+    pub on_import: EventHandler<(String, String)>,
+    /// Why the last import failed
+    #[props(default)]
+    pub error: Option<String>,
+    #[props(default)]
+    pub disabled: bool,
+}
+
+/// Imports one key from a secret in hex, base58 or the checked form, showing
+/// the address it derives before anything is added to the wallet
+pub fn SecretKeyImportForm(props: SecretKeyImportFormProps) -> Element {
+    let mut name = use_signal(String::new);
+    let mut secret = use_signal(String::new);
+
+    let preview = (!secret.read().trim().is_empty()).then(|| preview_secret_key(&secret.read()));
+    let valid = matches!(preview, Some(Ok(_)));
+
+    rsx! {
+        div {
+            class: "secret-key-import",
+            h3 { "Import a secret key" }
+            input {
+                placeholder: "Key name",
+                aria_label: "Key name",
+                disabled: props.disabled,
+                value: "{name}",
+                oninput: move |evt| name.set(evt.value()),
+            }
+            input {
+                r#type: "password",
+                placeholder: "Secret key: hex, base58 or checked base58",
+                aria_label: "Secret key",
+                disabled: props.disabled,
+                value: "{secret}",
+                oninput: move |evt| secret.set(evt.value()),
+            }
+            match preview {
+                Some(Ok(preview)) => rsx! {
+                    div {
+                        class: "secret-key-preview",
+                        div { class: "secret-key-format", "Read as {preview.format.label()}" }
+                        div { class: "secret-key-address", "{preview.address}" }
+                    }
+                },
+                Some(Err(e)) => rsx! {
+                    div { class: "secret-key-invalid", "{e}" }
+                },
+                None => rsx! {},
+            }
+            button {
+                disabled: props.disabled || !valid || name.read().trim().is_empty(),
+                onclick: move |_| {
+                    props.on_import.call((name(), secret()));
+                    secret.set(String::new());
+                },
+                "Import key"
+            }
+            if let Some(error) = props.error {
+                div { class: "secret-key-error", "{error}" }
+            }
+        }
+
+        style { {SECRET_KEY_IMPORT_CSS} }
+    }
+}
+
+const SECRET_KEY_IMPORT_CSS: &str = r#"
+.secret-key-import {
+    display: flex;
+    flex-direction: column;
+    gap: 12px;
+    background: #f8f9fa;
+    padding: 20px;
+    border-radius: 8px;
+    margin: 24px 0;
+    font-size: 14px;
+    color: #333;
+}
+
+.secret-key-import h3 {
+    margin: 0;
+}
+
+.secret-key-import input {
+    padding: 8px;
+    border: 1px solid #ccc;
+    border-radius: 4px;
+    font-family: inherit;
+}
+
+.secret-key-preview {
+    display: flex;
+    flex-direction: column;
+    gap: 4px;
+}
+
+.secret-key-format {
+    color: #198754;
+}
+
+.secret-key-address {
+    font-family: monospace;
+    word-break: break-all;
+}
+
+.secret-key-invalid {
+    color: #6c757d;
+}
+
+.secret-key-import button {
+    align-self: flex-start;
+    padding: 6px 14px;
+}
+
+.secret-key-error {
+    color: #dc3545;
+}
+"#;
@@ -1,20 +1,839 @@
+use api::wallet::DEFAULT_MIN_CONFIRMATIONS;
+use api::{
+    format_display, parse_amount, Address, AddressNetwork, AmountUnit, ContactBook,
+    DisplaySettings, FeePriority, PaymentTemplate, SendDraft, SendFailure, SendProgress, SendStage,
+    TemplateDraft,
+};
 use dioxus::prelude::*;
+use std::collections::HashMap;
+
+use super::{AddressInput, AmountInput, PasteAddressButton};
+use crate::t;
+
+/// (address, amount, min confirmations override for this send, amount typed
+/// again to confirm a large send, blocks the transaction stays valid for)
+pub type SendFormValues = (String, u64, Option<u64>, Option<u64>, Option<u64>);
+
+/// Recipient and amount to fill the form with
+#[derive(Debug, Clone, PartialEq)]
+pub struct SendPrefill {
+    pub recipient: Address,
+    pub amount: u64,
+}
+
+impl From<&PaymentTemplate> for SendPrefill {
+    fn from(template: &PaymentTemplate) -> Self {
+        Self {
+            recipient: template.recipient.clone(),
+            amount: template.amount,
+        }
+    }
+}
+
+/// What the user entered for a send saved as a template
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateDetails {
+    pub name: String,
+    pub memo: String,
+    pub fee_priority: FeePriority,
+    /// Remind this many days after each payment
+    pub reminder_days: Option<u32>,
+}
+
+/// Blocks offered when limiting how long a send stays valid
+const DEFAULT_VALID_FOR_BLOCKS: u64 = 100;
 
 #[derive(Props, Clone, PartialEq)]
 pub struct SendFormProps {
-    pub on_send: EventHandler<(String, u64)>, // (address, amount)
+    pub on_send: EventHandler<SendFormValues>,
+    /// A send is in flight
+    #[props(default)]
+    pub submitting: bool,
+    /// Last completed stage of the send in flight
+    #[props(default)]
+    pub progress: Option<SendProgress>,
+    /// Why the last send failed, if it did
+    #[props(default)]
+    pub failure: Option<SendFailure>,
+    /// Spendable balance in base units; larger amounts are clamped to it
+    #[props(default)]
+    pub available: Option<u64>,
+    /// Network recipient addresses must belong to
+    #[props(default)]
+    pub network: AddressNetwork,
+    /// Contacts suggested in the recipient field
+    #[props(default)]
+    pub contacts: ContactBook,
+    /// Wallet confirmation threshold, the starting point for the override
+    #[props(default = DEFAULT_MIN_CONFIRMATIONS)]
+    pub min_confirmations: u64,
+    /// Per-transaction limit; larger sends need the amount typed again
+    #[props(default)]
+    pub confirm_above: Option<u64>,
+    /// Times the wallet has paid each address before; paying one again
+    /// shows a warning but doesn't block the send
+    #[props(default)]
+    pub payment_counts: HashMap<Address, usize>,
+    /// Called with the same values as `on_send` to export the transaction for
+    /// offline signing instead; without it only Send is offered
+    #[props(default)]
+    pub on_export_unsigned: Option<EventHandler<SendFormValues>>,
+    /// Replaces the recipient and amount whenever it changes, e.g. when a
+    /// template is picked
+    #[props(default)]
+    pub prefill: Option<SendPrefill>,
+    /// Replaces every field whenever it changes, e.g. with a restored draft;
+    /// an empty draft clears the form. Applied after `prefill`.
+    #[props(default)]
+    pub draft: Option<SendDraft>,
+    /// Called with the form as a draft whenever a field changes. The
+    /// template is left for the parent to fill in.
+    #[props(default)]
+    pub on_draft_change: Option<EventHandler<SendDraft>>,
+    /// Called with the template details and the form values before `on_send`
+    /// when the user asks to save the send as a template; without it the
+    /// option is hidden
+    #[props(default)]
+    pub on_save_template: Option<EventHandler<(TemplateDetails, SendFormValues)>>,
+    /// How the send preview shows amounts
+    #[props(default)]
+    pub display: DisplaySettings,
+    /// Called to send again by itself once enough peers connect; offered
+    /// when the last send failed for want of peers
+    #[props(default)]
+    pub on_retry_when_peers: Option<EventHandler<()>>,
+    /// Called to send again without waiting for peers
+    #[props(default)]
+    pub on_send_anyway: Option<EventHandler<()>>,
+    /// A send is held until enough peers connect
+    #[props(default)]
+    pub waiting_for_peers: bool,
+    /// Called to give up on the held send
+    #[props(default)]
+    pub on_stop_waiting: Option<EventHandler<()>>,
 }
 
 pub fn SendForm(props: SendFormProps) -> Element {
+    let mut address = use_signal(|| None::<Address>);
+    let mut amount = use_signal(|| None::<u64>);
+
+    let parsed_amount = *amount.read();
+    let mut allow_low_confirmations = use_signal(|| false);
+    let mut confirmations_override =
+        use_signal(|| props.min_confirmations.saturating_sub(1).max(1));
+    let min_confirmations = allow_low_confirmations().then_some(confirmations_override());
+    let mut limit_validity = use_signal(|| false);
+    let mut validity_blocks = use_signal(|| DEFAULT_VALID_FOR_BLOCKS);
+    let valid_for_blocks = limit_validity().then_some(validity_blocks());
+    let mut confirmation = use_signal(String::new);
+    let mut save_template = use_signal(|| false);
+    let mut template_name = use_signal(String::new);
+    let mut template_memo = use_signal(String::new);
+    let mut template_priority = use_signal(FeePriority::default);
+    let mut template_reminder = use_signal(String::new);
+    let reminder_days = template_reminder.read().trim().parse::<u32>().ok();
+    let reminder_valid =
+        template_reminder.read().trim().is_empty() || reminder_days.is_some_and(|days| days > 0);
+
+    let prefill = props.prefill.clone();
+    use_effect(use_reactive((&prefill,), move |(prefill,)| {
+        if let Some(prefill) = prefill {
+            address.set(Some(prefill.recipient));
+            amount.set(Some(prefill.amount));
+        }
+    }));
+    let draft = props.draft.clone();
+    use_effect(use_reactive((&draft,), move |(draft,)| {
+        let Some(draft) = draft else {
+            return;
+        };
+        address.set(
+            draft
+                .recipient
+                .as_deref()
+                .and_then(|recipient| Address::from_string(recipient).ok()),
+        );
+        amount.set(draft.amount);
+        allow_low_confirmations.set(draft.min_confirmations.is_some());
+        if let Some(confirmations) = draft.min_confirmations {
+            confirmations_override.set(confirmations);
+        }
+        limit_validity.set(draft.valid_for_blocks.is_some());
+        if let Some(blocks) = draft.valid_for_blocks {
+            validity_blocks.set(blocks);
+        }
+        save_template.set(draft.save_as.is_some());
+        let save_as = draft.save_as.unwrap_or_default();
+        template_name.set(save_as.name);
+        template_memo.set(save_as.memo);
+        template_priority.set(save_as.fee_priority);
+        template_reminder.set(save_as.reminder_days);
+    }));
+    let on_draft_change = props.on_draft_change;
+    use_effect(move || {
+        let draft = SendDraft {
+            recipient: address
+                .read()
+                .as_ref()
+                .map(|address| address.to_nockchain_string()),
+            amount: amount(),
+            min_confirmations: allow_low_confirmations().then_some(confirmations_override()),
+            valid_for_blocks: limit_validity().then_some(validity_blocks()),
+            template: None,
+            save_as: save_template().then(|| TemplateDraft {
+                name: template_name(),
+                memo: template_memo(),
+                fee_priority: template_priority(),
+                reminder_days: template_reminder(),
+            }),
+            saved_at: chrono::Utc::now(),
+        };
+        if let Some(on_draft_change) = on_draft_change {
+            on_draft_change.call(draft);
+        }
+    });
+    let needs_confirmation = parsed_amount
+        .zip(props.confirm_above)
+        .is_some_and(|(amount, threshold)| amount > threshold);
+    let confirmed_amount = parsed_amount.filter(|amount| {
+        parse_amount(&confirmation.read(), AmountUnit::Nock).ok() == Some(*amount)
+    });
+    let recipient = address.read().clone();
+    let preview = recipient.as_ref().zip(parsed_amount).map(|(to, amount)| {
+        t!(
+            "send.preview",
+            amount = format_display(amount, &props.display),
+            recipient = to
+        )
+    });
+    let times_paid = recipient
+        .as_ref()
+        .and_then(|to| props.payment_counts.get(to))
+        .copied()
+        .filter(|count| *count > 0);
+    let can_send = !props.submitting
+        && recipient.is_some()
+        && parsed_amount.is_some()
+        && (!needs_confirmation || confirmed_amount.is_some())
+        && (!save_template() || (!template_name.read().trim().is_empty() && reminder_valid));
+    let show_steps = props.submitting || props.progress.is_some() || props.failure.is_some();
+
     rsx! {
         div {
             class: "send-form",
-            h3 { "Send Nockchain" }
+            h3 { {t!("send.title")} }
             form {
-                input { placeholder: "Recipient Address" }
-                input { placeholder: "Amount" }
-                button { "Send" }
+                onsubmit: move |evt| {
+                    evt.prevent_default();
+                    if let (Some(recipient), Some(amount)) = (address.read().as_ref(), parsed_amount) {
+                        let values = (
+                            recipient.to_string(),
+                            amount,
+                            min_confirmations,
+                            confirmed_amount,
+                            valid_for_blocks,
+                        );
+                        if let (Some(on_save_template), true) = (props.on_save_template, save_template()) {
+                            let details = TemplateDetails {
+                                name: template_name.read().trim().to_string(),
+                                memo: template_memo.read().trim().to_string(),
+                                fee_priority: template_priority(),
+                                reminder_days,
+                            };
+                            on_save_template.call((details, values.clone()));
+                            save_template.set(false);
+                            template_name.set(String::new());
+                            template_memo.set(String::new());
+                            template_reminder.set(String::new());
+                        }
+                        props.on_send.call(values);
+                    }
+                },
+                div {
+                    class: "send-recipient",
+                    AddressInput {
+                        value: recipient,
+                        network: props.network,
+                        contacts: props.contacts.clone(),
+                        disabled: props.submitting,
+                        on_change: move |value| address.set(value),
+                    }
+                    PasteAddressButton {
+                        network: props.network,
+                        disabled: props.submitting,
+                        on_paste: move |pasted| address.set(Some(pasted)),
+                    }
+                }
+                AmountInput {
+                    value: parsed_amount,
+                    available: props.available,
+                    disabled: props.submitting,
+                    on_change: move |value| amount.set(value),
+                }
+                if let Some(preview) = preview {
+                    div { class: "send-preview", "{preview}" }
+                }
+                if let Some(count) = times_paid {
+                    div {
+                        class: "send-reuse-warning",
+                        {t!("send.address_reused", count = count)}
+                    }
+                }
+                if let (true, Some(threshold)) = (needs_confirmation, props.confirm_above) {
+                    label {
+                        class: "send-confirm",
+                        {t!("send.confirm_over_limit", limit = format_display(threshold, &props.display))}
+                        input {
+                            value: "{confirmation}",
+                            disabled: props.submitting,
+                            oninput: move |evt| confirmation.set(evt.value()),
+                        }
+                    }
+                }
+                details {
+                    class: "send-advanced",
+                    summary { {t!("send.advanced")} }
+                    label {
+                        input {
+                            r#type: "checkbox",
+                            checked: allow_low_confirmations(),
+                            disabled: props.submitting,
+                            onchange: move |evt| allow_low_confirmations.set(evt.checked()),
+                        }
+                        " "
+                        {t!("send.low_confirmations", count = props.min_confirmations)}
+                    }
+                    if allow_low_confirmations() {
+                        label {
+                            {t!("send.min_confirmations")}
+                            " "
+                            input {
+                                r#type: "number",
+                                min: "1",
+                                value: "{confirmations_override}",
+                                disabled: props.submitting,
+                                oninput: move |evt| {
+                                    if let Ok(value) = evt.value().trim().parse::<u64>() {
+                                        confirmations_override.set(value.max(1));
+                                    }
+                                },
+                            }
+                        }
+                    }
+                    label {
+                        input {
+                            r#type: "checkbox",
+                            checked: limit_validity(),
+                            disabled: props.submitting,
+                            onchange: move |evt| limit_validity.set(evt.checked()),
+                        }
+                        " "
+                        {t!("send.limit_validity")}
+                    }
+                    if limit_validity() {
+                        label {
+                            {t!("send.valid_for")}
+                            " "
+                            input {
+                                r#type: "number",
+                                min: "1",
+                                value: "{validity_blocks}",
+                                disabled: props.submitting,
+                                oninput: move |evt| {
+                                    if let Ok(value) = evt.value().trim().parse::<u64>() {
+                                        validity_blocks.set(value.max(1));
+                                    }
+                                },
+                            }
+                            " "
+                            {t!("send.blocks", count = validity_blocks())}
+                        }
+                    }
+                }
+                if props.on_save_template.is_some() {
+                    div {
+                        class: "send-save-template",
+                        label {
+                            input {
+                                r#type: "checkbox",
+                                checked: save_template(),
+                                disabled: props.submitting,
+                                onchange: move |evt| save_template.set(evt.checked()),
+                            }
+                            " "
+                            {t!("send.save_template")}
+                        }
+                        if save_template() {
+                            input {
+                                placeholder: t!("send.template_name"),
+                                aria_label: t!("send.template_name"),
+                                value: "{template_name}",
+                                disabled: props.submitting,
+                                oninput: move |evt| template_name.set(evt.value()),
+                            }
+                            input {
+                                placeholder: t!("send.memo"),
+                                aria_label: t!("send.memo"),
+                                value: "{template_memo}",
+                                disabled: props.submitting,
+                                oninput: move |evt| template_memo.set(evt.value()),
+                            }
+                            select {
+                                aria_label: t!("send.fee_priority"),
+                                disabled: props.submitting,
+                                onchange: move |evt| {
+                                    if let Some(priority) = FeePriority::ALL
+                                        .into_iter()
+                                        .find(|priority| priority.label() == evt.value())
+                                    {
+                                        template_priority.set(priority);
+                                    }
+                                },
+                                for priority in FeePriority::ALL {
+                                    option {
+                                        value: priority.label(),
+                                        selected: priority == template_priority(),
+                                        {fee_label(priority)}
+                                    }
+                                }
+                            }
+                            input {
+                                r#type: "number",
+                                min: "1",
+                                placeholder: t!("send.remind_every"),
+                                aria_label: t!("send.remind_every"),
+                                value: "{template_reminder}",
+                                disabled: props.submitting,
+                                oninput: move |evt| template_reminder.set(evt.value()),
+                            }
+                        }
+                    }
+                }
+                div {
+                    class: "send-buttons",
+                    button {
+                        r#type: "submit",
+                        disabled: !can_send,
+                        if props.submitting { {t!("send.sending")} } else { {t!("send.send")} }
+                    }
+                    if let Some(on_export_unsigned) = props.on_export_unsigned {
+                        button {
+                            r#type: "button",
+                            disabled: !can_send,
+                            onclick: move |_| {
+                                if let (Some(recipient), Some(amount)) = (address.read().as_ref(), parsed_amount) {
+                                    on_export_unsigned.call((
+                                        recipient.to_string(),
+                                        amount,
+                                        min_confirmations,
+                                        confirmed_amount,
+                                        valid_for_blocks,
+                                    ));
+                                }
+                            },
+                            {t!("send.export_unsigned")}
+                        }
+                    }
+                }
+            }
+
+            if show_steps {
+                SendSteps {
+                    submitting: props.submitting,
+                    progress: props.progress.clone(),
+                    failure: props.failure.clone(),
+                }
+            }
+
+            if let Some(failure) = props.failure.as_ref() {
+                div {
+                    class: "send-failure",
+                    role: "alert",
+                    p { strong { {t!("send.failed_while", stage = failed_stage_label(failure.stage))} " " } "{failure.message}" }
+                    p { "{failure.outcome()}" }
+                    if failure.retryable {
+                        div {
+                            class: "send-buttons",
+                            if let Some(on_retry_when_peers) = props.on_retry_when_peers {
+                                button {
+                                    r#type: "button",
+                                    onclick: move |_| on_retry_when_peers.call(()),
+                                    {t!("send.retry_when_peers")}
+                                }
+                            }
+                            if let Some(on_send_anyway) = props.on_send_anyway {
+                                button {
+                                    r#type: "button",
+                                    onclick: move |_| on_send_anyway.call(()),
+                                    {t!("send.send_anyway")}
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            if props.waiting_for_peers {
+                div {
+                    class: "send-waiting",
+                    role: "status",
+                    p { {t!("send.waiting_for_peers")} }
+                    div {
+                        class: "send-buttons",
+                        if let Some(on_stop_waiting) = props.on_stop_waiting {
+                            button {
+                                r#type: "button",
+                                onclick: move |_| on_stop_waiting.call(()),
+                                {t!("send.stop_waiting")}
+                            }
+                        }
+                        if let Some(on_send_anyway) = props.on_send_anyway {
+                            button {
+                                r#type: "button",
+                                onclick: move |_| on_send_anyway.call(()),
+                                {t!("send.send_anyway")}
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        style { {SEND_FORM_CSS} }
+    }
+}
+
+#[derive(Props, Clone, PartialEq)]
+struct SendStepsProps {
+    submitting: bool,
+    progress: Option<SendProgress>,
+    failure: Option<SendFailure>,
+}
+
+/// Select → Sign → Broadcast → Record indicator
+fn SendSteps(props: SendStepsProps) -> Element {
+    let completed = props.progress.as_ref().map(SendProgress::stage);
+    let failed = props.failure.as_ref().map(|failure| failure.stage);
+
+    rsx! {
+        ol {
+            class: "send-steps",
+            aria_live: "polite",
+            for stage in SendStage::ALL {
+                li {
+                    key: "{stage:?}",
+                    class: "send-step {step_class(stage, completed, failed, props.submitting)}",
+                    aria_current: if step_class(stage, completed, failed, props.submitting) == "active" { "step" },
+                    "{step_label(stage)}"
+                }
+            }
+        }
+    }
+}
+
+fn step_class(
+    stage: SendStage,
+    completed: Option<SendStage>,
+    failed: Option<SendStage>,
+    submitting: bool,
+) -> &'static str {
+    // The stage running now is the first one after the last completed stage
+    let running = SendStage::ALL
+        .into_iter()
+        .find(|s| completed.is_none_or(|done| *s > done));
+
+    if failed == Some(stage) {
+        "failed"
+    } else if completed.is_some_and(|done| stage <= done) {
+        "done"
+    } else if submitting && failed.is_none() && running == Some(stage) {
+        "active"
+    } else {
+        ""
+    }
+}
+
+fn step_label(stage: SendStage) -> String {
+    match stage {
+        SendStage::SelectFunds => t!("send.step.select"),
+        SendStage::Sign => t!("send.step.sign"),
+        SendStage::Broadcast => t!("send.step.broadcast"),
+        SendStage::Record => t!("send.step.record"),
+    }
+}
+
+/// What the send was doing at `stage`, e.g. "signing"
+fn failed_stage_label(stage: SendStage) -> String {
+    match stage {
+        SendStage::SelectFunds => t!("send.stage.select"),
+        SendStage::Sign => t!("send.stage.sign"),
+        SendStage::Broadcast => t!("send.stage.broadcast"),
+        SendStage::Record => t!("send.stage.record"),
+    }
+}
+
+pub(crate) fn fee_label(priority: FeePriority) -> String {
+    match priority {
+        FeePriority::Low => t!("send.fee.low"),
+        FeePriority::Normal => t!("send.fee.normal"),
+        FeePriority::High => t!("send.fee.high"),
+    }
+}
+
+const SEND_FORM_CSS: &str = r#"
+.send-form form {
+    display: flex;
+    flex-direction: column;
+    gap: 8px;
+}
+
+.send-preview {
+    font-size: 13px;
+    color: #6c757d;
+    word-break: break-all;
+}
+
+.send-reuse-warning {
+    font-size: 13px;
+    color: #856404;
+    background: #fff3cd;
+    padding: 6px 10px;
+    border-radius: 4px;
+}
+
+.send-confirm {
+    display: flex;
+    flex-direction: column;
+    gap: 4px;
+    padding: 8px;
+    background: #fff3cd;
+    border-radius: 4px;
+    font-size: 13px;
+    color: #664d03;
+}
+
+.send-buttons {
+    display: flex;
+    gap: 8px;
+}
+
+.send-save-template {
+    display: flex;
+    align-items: center;
+    flex-wrap: wrap;
+    gap: 8px;
+    font-size: 13px;
+    color: #555;
+}
+
+.send-advanced {
+    font-size: 13px;
+    color: #555;
+}
+
+.send-advanced label {
+    display: block;
+    margin-top: 6px;
+}
+
+.send-advanced input[type="number"] {
+    width: 64px;
+}
+
+.send-recipient {
+    display: flex;
+    align-items: flex-start;
+    gap: 8px;
+}
+
+.send-steps {
+    display: flex;
+    gap: 8px;
+    list-style: none;
+    padding: 0;
+    margin: 12px 0;
+    font-size: 12px;
+}
+
+.send-step {
+    padding: 4px 10px;
+    border-radius: 12px;
+    background: #e9ecef;
+    color: #6c757d;
+}
+
+.send-step.active {
+    background: #cfe2ff;
+    color: #084298;
+}
+
+.send-step.done {
+    background: #d1e7dd;
+    color: #0f5132;
+}
+
+.send-step.failed {
+    background: #f8d7da;
+    color: #842029;
+}
+
+.send-failure {
+    padding: 8px 12px;
+    border: 1px solid #f5c2c7;
+    border-radius: 6px;
+    background: #fff5f5;
+    font-size: 13px;
+}
+
+.send-failure p {
+    margin: 4px 0;
+}
+
+.send-waiting {
+    padding: 8px 12px;
+    border: 1px solid #ffe69c;
+    border-radius: 6px;
+    background: #fffbeb;
+    font-size: 13px;
+}
+
+.send-waiting p {
+    margin: 4px 0;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{html, settle};
+    use api::testing::fake_address;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn classes(
+        completed: Option<SendStage>,
+        failed: Option<SendStage>,
+        submitting: bool,
+    ) -> Vec<&'static str> {
+        SendStage::ALL
+            .iter()
+            .map(|stage| step_class(*stage, completed, failed, submitting))
+            .collect()
+    }
+
+    #[test]
+    fn the_step_after_the_last_completed_one_is_active() {
+        assert_eq!(classes(None, None, true), ["active", "", "", ""]);
+        assert_eq!(
+            classes(Some(SendStage::Sign), None, true),
+            ["done", "done", "active", ""]
+        );
+        assert_eq!(
+            classes(Some(SendStage::Record), None, false),
+            ["done", "done", "done", "done"]
+        );
+        assert_eq!(classes(None, None, false), ["", "", "", ""]);
+    }
+
+    #[test]
+    fn a_failed_step_is_marked_and_nothing_stays_active() {
+        assert_eq!(
+            classes(Some(SendStage::SelectFunds), Some(SendStage::Sign), true),
+            ["done", "failed", "", ""]
+        );
+        assert_eq!(
+            classes(None, Some(SendStage::SelectFunds), false),
+            ["failed", "", "", ""]
+        );
+    }
+
+    #[test]
+    fn a_template_prefills_its_recipient_and_amount() {
+        let template = PaymentTemplate {
+            memo: "Rent".to_string(),
+            fee_priority: FeePriority::High,
+            ..PaymentTemplate::new("rent", fake_address(), 1_200)
+        };
+        assert_eq!(
+            SendPrefill::from(&template),
+            SendPrefill {
+                recipient: template.recipient.clone(),
+                amount: 1_200,
+            }
+        );
+    }
+
+    /// Drafts the form reported, oldest first
+    #[derive(Clone, Default)]
+    struct Reported(Rc<RefCell<Vec<SendDraft>>>);
+
+    /// Send page as reopened after a reload, filled from the saved draft
+    #[component]
+    fn Reopened() -> Element {
+        let reported = use_context::<Reported>();
+        let saved = use_context::<SendDraft>();
+        rsx! {
+            SendForm {
+                on_send: move |_| {},
+                on_save_template: move |_| {},
+                draft: Some(saved),
+                on_draft_change: move |draft| reported.0.borrow_mut().push(draft),
+            }
+        }
+    }
+
+    #[test]
+    fn a_restored_draft_fills_every_field() {
+        let saved = SendDraft {
+            recipient: Some(fake_address().to_nockchain_string()),
+            amount: Some(2_500_000),
+            min_confirmations: Some(1),
+            valid_for_blocks: Some(50),
+            template: None,
+            save_as: Some(TemplateDraft {
+                name: "Landlord".to_string(),
+                memo: "Flat 3".to_string(),
+                fee_priority: FeePriority::High,
+                reminder_days: "30".to_string(),
+            }),
+            saved_at: api::testing::fixture_time(0),
+        };
+        let reported = Reported::default();
+        let mut dom = VirtualDom::new(Reopened)
+            .with_root_context(reported.clone())
+            .with_root_context(saved.clone());
+        dom.rebuild_in_place();
+        settle(&mut dom);
+
+        // The form reads back exactly what was saved
+        let last = reported.0.borrow().last().cloned().unwrap();
+        assert_eq!(
+            SendDraft {
+                saved_at: saved.saved_at,
+                ..last
+            },
+            saved
+        );
+        assert!(html(&dom).contains("Flat 3"));
+    }
+
+    #[test]
+    fn an_untouched_form_reports_an_empty_draft() {
+        #[component]
+        fn Fresh() -> Element {
+            let reported = use_context::<Reported>();
+            rsx! {
+                SendForm {
+                    on_send: move |_| {},
+                    on_draft_change: move |draft| reported.0.borrow_mut().push(draft),
+                }
             }
         }
+        let reported = Reported::default();
+        let mut dom = VirtualDom::new(Fresh).with_root_context(reported.clone());
+        dom.rebuild_in_place();
+        settle(&mut dom);
+        assert!(reported.0.borrow().iter().all(SendDraft::is_empty));
+        assert!(!reported.0.borrow().is_empty());
     }
 }
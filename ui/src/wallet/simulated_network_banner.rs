@@ -0,0 +1,160 @@
+use api::NetworkConditions;
+use dioxus::prelude::*;
+
+use crate::t;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct SimulatedNetworkBannerProps {
+    pub conditions: NetworkConditions,
+    /// Restore a clean network
+    #[props(default)]
+    pub on_clear: Option<EventHandler<()>>,
+}
+
+/// Shown on the Node page while fakenet runs with simulated latency, message
+/// loss or partitions, so odd behaviour isn't mistaken for a real fault
+pub fn SimulatedNetworkBanner(props: SimulatedNetworkBannerProps) -> Element {
+    let conditions = &props.conditions;
+    let mut faults = Vec::new();
+    if conditions.latency_ms > 0 {
+        faults.push(t!("node.netsim.latency", ms = conditions.latency_ms));
+    }
+    if !conditions.link_latency_ms.is_empty() {
+        faults.push(t!(
+            "node.netsim.link_latency",
+            count = conditions.link_latency_ms.len()
+        ));
+    }
+    if conditions.drop_rate > 0.0 {
+        faults.push(t!(
+            "node.netsim.drop",
+            percent = format!("{:.0}", conditions.drop_rate * 100.0)
+        ));
+    }
+    if !conditions.partitioned.is_empty() {
+        faults.push(t!(
+            "node.netsim.partitioned",
+            count = conditions.partitioned.len()
+        ));
+    }
+    let faults = faults.join(" · ");
+
+    rsx! {
+        div {
+            class: "simulated-network-banner",
+            role: "status",
+            span { class: "simulated-network-icon", "🧪" }
+            div {
+                class: "simulated-network-text",
+                strong { {t!("node.netsim.title")} }
+                span { " {faults}" }
+            }
+            if let Some(on_clear) = props.on_clear {
+                button {
+                    class: "simulated-network-clear",
+                    onclick: move |_| on_clear.call(()),
+                    {t!("node.netsim.clear")}
+                }
+            }
+        }
+
+        style { {SIMULATED_NETWORK_BANNER_CSS} }
+    }
+}
+
+const SIMULATED_NETWORK_BANNER_CSS: &str = r#"
+.simulated-network-banner {
+    display: flex;
+    align-items: center;
+    gap: 12px;
+    padding: 10px 16px;
+    margin-bottom: 16px;
+    background: #f3e8ff;
+    border: 1px solid #d8b4fe;
+    border-radius: 6px;
+    color: #581c87;
+    font-size: 14px;
+}
+
+.simulated-network-icon {
+    font-size: 18px;
+}
+
+.simulated-network-text {
+    flex: 1;
+}
+
+.simulated-network-clear {
+    padding: 6px 14px;
+    background: white;
+    color: #581c87;
+    border: 1px solid #d8b4fe;
+    border-radius: 4px;
+    cursor: pointer;
+}
+
+.simulated-network-clear:hover {
+    background: #faf5ff;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::render;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    #[component]
+    fn Banner(conditions: NetworkConditions, clearable: bool) -> Element {
+        rsx! {
+            SimulatedNetworkBanner {
+                conditions,
+                on_clear: clearable.then(|| EventHandler::new(|_| {})),
+            }
+        }
+    }
+
+    #[test]
+    fn the_banner_lists_every_active_fault() {
+        let conditions = NetworkConditions {
+            latency_ms: 250,
+            link_latency_ms: BTreeMap::from([("a".to_string(), 900)]),
+            drop_rate: 0.2,
+            partitioned: BTreeSet::from(["b".to_string(), "c".to_string()]),
+        };
+        let html = render(
+            Banner,
+            BannerProps {
+                conditions,
+                clearable: true,
+            },
+        );
+        assert!(html.contains("Simulated network:"), "{}", html);
+        assert!(
+            html.contains(
+                "250ms latency · custom latency on 1 link · 20% of messages dropped · 2 peers partitioned"
+            ),
+            "{}",
+            html
+        );
+        assert!(html.contains("Clear"), "{}", html);
+    }
+
+    #[test]
+    fn only_set_faults_are_listed_and_clear_is_optional() {
+        let conditions = NetworkConditions {
+            drop_rate: 0.5,
+            ..NetworkConditions::default()
+        };
+        let html = render(
+            Banner,
+            BannerProps {
+                conditions,
+                clearable: false,
+            },
+        );
+        assert!(html.contains(" 50% of messages dropped"), "{}", html);
+        assert!(!html.contains("latency"), "{}", html);
+        assert!(!html.contains("simulated-network-clear"), "{}", html);
+    }
+}
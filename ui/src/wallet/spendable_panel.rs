@@ -0,0 +1,100 @@
+use api::SpendableSummary;
+use dioxus::prelude::*;
+
+use super::balance_card::format_balance;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct SpendablePanelProps {
+    pub summary: SpendableSummary,
+}
+
+/// Breakdown of why part of the balance can't be sent yet
+pub fn SpendablePanel(props: SpendablePanelProps) -> Element {
+    let summary = props.summary;
+    let rows = [
+        (
+            "Spendable now",
+            summary.spendable,
+            format!("{}+ confirmations", summary.min_confirmations),
+        ),
+        (
+            "Awaiting confirmations",
+            summary.awaiting_confirmations,
+            format!("fewer than {} confirmations", summary.min_confirmations),
+        ),
+        (
+            "Maturing rewards",
+            summary.immature,
+            "mined coins not yet mature".to_string(),
+        ),
+        (
+            "Locked",
+            summary.locked,
+            "reserved by a send in progress".to_string(),
+        ),
+        (
+            "Watch-only",
+            summary.watch_only,
+            "no key to spend with".to_string(),
+        ),
+    ];
+
+    rsx! {
+        div {
+            class: "spendable-panel",
+            h4 { "What you can send" }
+            for (label, amount, reason) in rows {
+                if amount > 0 || label == "Spendable now" {
+                    div {
+                        key: "{label}",
+                        class: "spendable-row",
+                        span { class: "spendable-label", "{label}" }
+                        span { class: "spendable-reason", "{reason}" }
+                        span { class: "spendable-amount", "{format_balance(amount)} NOCK" }
+                    }
+                }
+            }
+        }
+
+        style { {SPENDABLE_PANEL_CSS} }
+    }
+}
+
+const SPENDABLE_PANEL_CSS: &str = r#"
+.spendable-panel {
+    background: #f8f9fa;
+    padding: 16px 20px;
+    border-radius: 8px;
+    margin-bottom: 16px;
+}
+
+.spendable-panel h4 {
+    color: #333;
+    margin: 0 0 8px 0;
+}
+
+.spendable-row {
+    display: flex;
+    align-items: baseline;
+    gap: 12px;
+    padding: 4px 0;
+    font-size: 14px;
+}
+
+.spendable-label {
+    font-weight: 600;
+    color: #333;
+    min-width: 180px;
+}
+
+.spendable-reason {
+    flex: 1;
+    font-size: 12px;
+    color: #6c757d;
+}
+
+.spendable-amount {
+    font-family: monospace;
+    color: #333;
+}
+"#;
@@ -0,0 +1,112 @@
+use api::{format_amount, AmountUnit, DailyAllowance, SpendingLimits};
+use chrono::{DateTime, Utc};
+use dioxus::prelude::*;
+
+use super::AmountInput;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct SpendingLimitsFormProps {
+    /// Limits currently in force
+    pub limits: SpendingLimits,
+    /// What is left of the daily limit, if one is set
+    #[props(default)]
+    pub allowance: Option<DailyAllowance>,
+    pub on_save: EventHandler<SpendingLimits>,
+    #[props(default)]
+    pub disabled: bool,
+}
+
+/// Per-transaction and daily spending limits; an empty field means no limit
+pub fn SpendingLimitsForm(props: SpendingLimitsFormProps) -> Element {
+    let mut per_transaction = use_signal(|| props.limits.per_transaction);
+    let mut daily = use_signal(|| props.limits.daily);
+
+    let limits = props.limits;
+    use_effect(use_reactive((&limits,), move |(limits,)| {
+        per_transaction.set(limits.per_transaction);
+        daily.set(limits.daily);
+    }));
+
+    let draft = SpendingLimits {
+        per_transaction: per_transaction(),
+        daily: daily(),
+    };
+
+    rsx! {
+        div {
+            class: "spending-limits",
+            h3 { "Spending limits" }
+            label {
+                "Confirm sends above"
+                AmountInput {
+                    value: per_transaction(),
+                    placeholder: "No limit",
+                    disabled: props.disabled,
+                    on_change: move |value| per_transaction.set(value),
+                }
+            }
+            label {
+                "Daily limit (any 24 hours, fees included)"
+                AmountInput {
+                    value: daily(),
+                    placeholder: "No limit",
+                    disabled: props.disabled,
+                    on_change: move |value| daily.set(value),
+                }
+            }
+            if let Some(allowance) = props.allowance {
+                div {
+                    class: "spending-allowance",
+                    "{format_amount(allowance.remaining, AmountUnit::Nock)} of {format_amount(allowance.limit, AmountUnit::Nock)} NOCK left"
+                    if let Some(resets_at) = allowance.resets_at {
+                        " · more from {format_time(resets_at)}"
+                    }
+                }
+            }
+            button {
+                disabled: props.disabled || draft == props.limits,
+                onclick: move |_| props.on_save.call(draft),
+                "Save limits"
+            }
+        }
+
+        style { {SPENDING_LIMITS_CSS} }
+    }
+}
+
+fn format_time(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M UTC").to_string()
+}
+
+const SPENDING_LIMITS_CSS: &str = r#"
+.spending-limits {
+    display: flex;
+    flex-direction: column;
+    gap: 12px;
+    background: #f8f9fa;
+    padding: 20px;
+    border-radius: 8px;
+    margin-bottom: 24px;
+    font-size: 14px;
+    color: #333;
+}
+
+.spending-limits h3 {
+    margin: 0;
+}
+
+.spending-limits label {
+    display: flex;
+    flex-direction: column;
+    gap: 4px;
+}
+
+.spending-allowance {
+    color: #6c757d;
+}
+
+.spending-limits button {
+    align-self: flex-start;
+    padding: 6px 14px;
+}
+"#;
@@ -0,0 +1,147 @@
+use api::{CompactionReport, StorageBreakdown};
+use dioxus::prelude::*;
+
+use crate::format::{format_bytes, format_count};
+
+/// Bar colour of each part of `StorageBreakdown::parts`, in order
+const PART_COLORS: [&str; 5] = ["#007bff", "#6f42c1", "#20c997", "#fd7e14", "#adb5bd"];
+
+#[derive(Props, Clone, PartialEq)]
+pub struct StoragePanelProps {
+    /// What the data takes, once measured
+    #[props(default)]
+    pub breakdown: Option<StorageBreakdown>,
+    /// Result of the last compaction, if one has run this session
+    #[props(default)]
+    pub report: Option<CompactionReport>,
+    pub on_compact: EventHandler<()>,
+    #[props(default)]
+    pub busy: bool,
+}
+
+/// Disk used by blocks, headers, UTXO set, wallet and logs as one stacked
+/// bar, with a button pruning old block bodies
+pub fn StoragePanel(props: StoragePanelProps) -> Element {
+    let summary = props.report.as_ref().map(|report| {
+        let mut summary = format!(
+            "Pruned {} block bodies, freeing {}",
+            format_count(report.pruned_blocks),
+            format_bytes(report.freed_bytes)
+        );
+        if report.kept_blocks > 0 {
+            summary.push_str(&format!(
+                " · kept {} with wallet transactions",
+                format_count(report.kept_blocks)
+            ));
+        }
+        summary
+    });
+
+    rsx! {
+        div {
+            class: "storage-panel",
+            h3 { "Storage" }
+            if let Some(breakdown) = props.breakdown.as_ref() {
+                div {
+                    class: "storage-total",
+                    "{format_bytes(breakdown.total())} in use"
+                }
+                div {
+                    class: "storage-bar",
+                    for ((label, bytes), color) in breakdown.parts().into_iter().zip(PART_COLORS) {
+                        if bytes > 0 {
+                            div {
+                                key: "{label}",
+                                class: "storage-segment",
+                                title: "{label}: {format_bytes(bytes)}",
+                                style: "flex-grow: {bytes}; background: {color};",
+                            }
+                        }
+                    }
+                }
+                div {
+                    class: "storage-legend",
+                    for ((label, bytes), color) in breakdown.parts().into_iter().zip(PART_COLORS) {
+                        span {
+                            key: "{label}",
+                            span { class: "storage-swatch", style: "background: {color};" }
+                            "{label} {format_bytes(bytes)}"
+                        }
+                    }
+                }
+            } else {
+                div { class: "storage-hint", "Not measured yet" }
+            }
+            div {
+                class: "storage-hint",
+                "Compacting drops the transactions of old blocks, keeping their headers and every block with a wallet transaction."
+            }
+            if let Some(summary) = summary {
+                div { class: "storage-hint", "{summary}" }
+            }
+            div {
+                button {
+                    disabled: props.busy,
+                    onclick: move |_| props.on_compact.call(()),
+                    if props.busy { "Compacting…" } else { "Compact now" }
+                }
+            }
+        }
+
+        style { {STORAGE_PANEL_CSS} }
+    }
+}
+
+const STORAGE_PANEL_CSS: &str = r#"
+.storage-panel {
+    display: flex;
+    flex-direction: column;
+    gap: 12px;
+    background: #f8f9fa;
+    padding: 20px;
+    border-radius: 8px;
+    margin-bottom: 24px;
+    font-size: 14px;
+    color: #333;
+}
+
+.storage-panel h3 {
+    margin: 0;
+}
+
+.storage-bar {
+    display: flex;
+    height: 12px;
+    border-radius: 6px;
+    overflow: hidden;
+    background: #e9ecef;
+}
+
+.storage-segment {
+    min-width: 2px;
+}
+
+.storage-legend {
+    display: flex;
+    flex-wrap: wrap;
+    gap: 16px;
+    font-size: 12px;
+    color: #6c757d;
+}
+
+.storage-swatch {
+    display: inline-block;
+    width: 10px;
+    height: 10px;
+    border-radius: 2px;
+    margin-right: 6px;
+}
+
+.storage-hint {
+    color: #6c757d;
+}
+
+.storage-panel button {
+    padding: 6px 14px;
+}
+"#;
@@ -0,0 +1,92 @@
+use api::{format_amount, AmountUnit, PaymentTemplate};
+use dioxus::prelude::*;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct TemplatePickerProps {
+    pub templates: Vec<PaymentTemplate>,
+    /// Name of the template in use, if any
+    #[props(default)]
+    pub selected: Option<String>,
+    /// Called with the chosen template name, or `None` to stop using one
+    pub on_select: EventHandler<Option<String>>,
+    /// Delete a template by name; without it templates can't be deleted here
+    #[props(default)]
+    pub on_delete: Option<EventHandler<String>>,
+}
+
+/// Picks a saved payment to fill the send form with
+pub fn TemplatePicker(props: TemplatePickerProps) -> Element {
+    if props.templates.is_empty() {
+        return rsx! {};
+    }
+    let selected = props.selected.as_deref().and_then(|name| {
+        props
+            .templates
+            .iter()
+            .find(|template| template.name == name)
+            .cloned()
+    });
+
+    rsx! {
+        div {
+            class: "template-picker",
+            label {
+                "Template "
+                select {
+                    onchange: move |evt| {
+                        let name = evt.value();
+                        props.on_select.call((!name.is_empty()).then_some(name));
+                    },
+                    option { value: "", selected: props.selected.is_none(), "None" }
+                    for template in props.templates.iter() {
+                        option {
+                            key: "{template.name}",
+                            value: "{template.name}",
+                            selected: props.selected.as_deref() == Some(template.name.as_str()),
+                            "{template.name}"
+                        }
+                    }
+                }
+            }
+            if let Some(template) = selected {
+                div {
+                    class: "template-picker-summary",
+                    "{format_amount(template.amount, AmountUnit::Nock)} NOCK · {template.fee_priority} fee"
+                    if !template.memo.is_empty() {
+                        " · {template.memo}"
+                    }
+                }
+                if let Some(on_delete) = props.on_delete {
+                    button {
+                        r#type: "button",
+                        onclick: move |_| on_delete.call(template.name.clone()),
+                        "Delete template"
+                    }
+                }
+            }
+        }
+
+        style { {TEMPLATE_PICKER_CSS} }
+    }
+}
+
+const TEMPLATE_PICKER_CSS: &str = r#"
+.template-picker {
+    display: flex;
+    align-items: center;
+    flex-wrap: wrap;
+    gap: 12px;
+    margin-bottom: 16px;
+    font-size: 14px;
+    color: #333;
+}
+
+.template-picker select {
+    padding: 4px 8px;
+}
+
+.template-picker-summary {
+    color: #6c757d;
+    font-size: 13px;
+}
+"#;
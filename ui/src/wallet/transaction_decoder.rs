@@ -0,0 +1,189 @@
+use api::{decode_transaction, DecodedTransaction};
+use dioxus::prelude::*;
+
+use super::balance_card::format_balance;
+
+/// Paste a raw transaction and see what it contains, as far as it decodes
+pub fn TransactionDecoder() -> Element {
+    let mut raw = use_signal(String::new);
+    let mut decoded = use_signal(|| None::<DecodedTransaction>);
+
+    rsx! {
+        div {
+            class: "tx-decoder",
+            textarea {
+                placeholder: "Transaction hex",
+                aria_label: "Transaction hex",
+                rows: "6",
+                value: "{raw}",
+                oninput: move |evt| raw.set(evt.value()),
+            }
+            button {
+                disabled: raw.read().trim().is_empty(),
+                onclick: move |_| decoded.set(Some(decode_transaction(raw.read().as_str()))),
+                "Decode"
+            }
+            if let Some(decoded) = decoded() {
+                DecodedBreakdown { decoded }
+            }
+        }
+
+        style { {TX_DECODER_CSS} }
+    }
+}
+
+#[derive(Props, Clone, PartialEq)]
+struct DecodedBreakdownProps {
+    decoded: DecodedTransaction,
+}
+
+fn DecodedBreakdown(props: DecodedBreakdownProps) -> Element {
+    let decoded = props.decoded;
+    let status = if decoded.is_complete() {
+        "Decoded".to_string()
+    } else {
+        format!("Partially decoded ({} bytes)", decoded.size)
+    };
+
+    rsx! {
+        div {
+            class: "tx-decoder-result",
+            div { class: "tx-decoder-status", "{status}" }
+            if let Some(error) = decoded.error.as_ref() {
+                div { class: "tx-decoder-error", "{error}" }
+            }
+            for warning in decoded.warnings.iter() {
+                div { class: "tx-decoder-warning", "{warning}" }
+            }
+
+            table {
+                tbody {
+                    tr { td { "Transaction ID" } td { class: "tx-decoder-mono", {optional(decoded.txid.as_ref())} } }
+                    tr { td { "Size" } td { "{decoded.size} bytes" } }
+                    tr { td { "Version" } td { {optional(decoded.version.as_ref())} } }
+                    tr { td { "Chain ID" } td { class: "tx-decoder-mono", {optional(decoded.chain_id.as_ref())} } }
+                    tr { td { "Fee" } td { {optional(decoded.fee.map(format_balance).as_ref())} " NOCK" } }
+                    if let Some(expiry) = decoded.expiry_height {
+                        tr { td { "Expires after block" } td { "{expiry}" } }
+                    }
+                }
+            }
+
+            h4 { "Inputs ({count(decoded.inputs.len(), decoded.input_count)})" }
+            table {
+                tbody {
+                    for input in decoded.inputs.iter() {
+                        tr {
+                            key: "{input.offset}",
+                            td { class: "tx-decoder-offset", "@{input.offset}" }
+                            td { class: "tx-decoder-mono", "{input.txid}:{input.index}" }
+                            td { class: "tx-decoder-amount", "{format_balance(input.amount)} NOCK" }
+                        }
+                    }
+                }
+            }
+
+            h4 { "Outputs ({count(decoded.outputs.len(), decoded.output_count)})" }
+            table {
+                tbody {
+                    for output in decoded.outputs.iter() {
+                        tr {
+                            key: "{output.offset}",
+                            td { class: "tx-decoder-offset", "@{output.offset}" }
+                            td {
+                                class: if output.address_valid { "tx-decoder-mono" } else { "tx-decoder-mono tx-decoder-invalid" },
+                                "{output.address}"
+                            }
+                            td { class: "tx-decoder-amount", "{format_balance(output.amount)} NOCK" }
+                        }
+                    }
+                }
+            }
+
+            if let Some(signature) = decoded.signature.as_ref() {
+                h4 { "Signature ({signature.len() / 2} bytes)" }
+                code { class: "tx-decoder-mono", "{signature}" }
+            }
+        }
+    }
+}
+
+fn optional<T: ToString>(value: Option<&T>) -> String {
+    value.map_or_else(|| "—".to_string(), ToString::to_string)
+}
+
+/// "decoded of declared" once they differ, e.g. after truncation
+fn count(decoded: usize, declared: Option<u32>) -> String {
+    match declared {
+        Some(declared) if declared as usize != decoded => format!("{} of {}", decoded, declared),
+        _ => decoded.to_string(),
+    }
+}
+
+const TX_DECODER_CSS: &str = r#"
+.tx-decoder {
+    display: flex;
+    flex-direction: column;
+    gap: 12px;
+    font-size: 14px;
+    color: #333;
+}
+
+.tx-decoder textarea {
+    font-family: monospace;
+    font-size: 12px;
+    padding: 8px;
+    border: 1px solid #ccc;
+    border-radius: 4px;
+}
+
+.tx-decoder button {
+    align-self: flex-start;
+    padding: 6px 14px;
+}
+
+.tx-decoder-result {
+    background: #f8f9fa;
+    padding: 20px;
+    border-radius: 8px;
+}
+
+.tx-decoder-result td {
+    padding: 4px 8px 4px 0;
+    vertical-align: top;
+}
+
+.tx-decoder-status {
+    font-weight: 600;
+}
+
+.tx-decoder-error {
+    color: #dc3545;
+    margin-top: 8px;
+}
+
+.tx-decoder-warning {
+    color: #856404;
+    margin-top: 4px;
+}
+
+.tx-decoder-mono {
+    font-family: monospace;
+    font-size: 12px;
+    word-break: break-all;
+}
+
+.tx-decoder-invalid {
+    color: #dc3545;
+}
+
+.tx-decoder-offset {
+    color: #6c757d;
+    font-size: 12px;
+}
+
+.tx-decoder-amount {
+    text-align: right;
+    white-space: nowrap;
+}
+"#;
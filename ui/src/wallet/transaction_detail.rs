@@ -0,0 +1,476 @@
+use api::{MerkleProofStatus, TransactionDirection, TransactionStatus};
+use chrono::{DateTime, Utc};
+use dioxus::prelude::*;
+
+use super::balance_card::format_balance;
+use super::{CopyButton, ReceiptView};
+
+#[derive(Props, Clone, PartialEq)]
+pub struct TransactionDetailProps {
+    pub detail: api::TransactionDetail,
+    /// Called with the new label when it is saved; without it the label is read-only
+    #[props(default)]
+    pub on_label_change: Option<EventHandler<String>>,
+    /// Called to create a signed receipt; without it no export is offered
+    #[props(default)]
+    pub on_export_receipt: Option<EventHandler<()>>,
+    /// Receipt created by the last export
+    #[props(default)]
+    pub receipt: Option<api::Receipt>,
+    /// Why the last export failed
+    #[props(default)]
+    pub receipt_error: Option<String>,
+    /// Called to relay a pending transaction to peers again; without it no
+    /// rebroadcast is offered
+    #[props(default)]
+    pub on_rebroadcast: Option<EventHandler<()>>,
+    /// A rebroadcast is in flight
+    #[props(default)]
+    pub rebroadcasting: bool,
+    /// Called to speed up a pending incoming payment by spending its output
+    /// with a high-fee child; without it no acceleration is offered
+    #[props(default)]
+    pub on_accelerate: Option<EventHandler<()>>,
+    /// An acceleration is in flight
+    #[props(default)]
+    pub accelerating: bool,
+    /// Called with the id of a linked parent or child transaction; without it
+    /// the id is shown unlinked
+    #[props(default)]
+    pub on_open_transaction: Option<EventHandler<String>>,
+}
+
+/// Everything known about one transaction: timeline, inputs, outputs, fee,
+/// confirmation depth, inclusion proof, the raw encoding and, for sends, a
+/// signed receipt
+pub fn TransactionDetail(props: TransactionDetailProps) -> Element {
+    let detail = props.detail;
+    let tx = &detail.transaction;
+    let mut label_draft = use_signal(|| detail.label.clone().unwrap_or_default());
+
+    let label = detail.label.clone();
+    use_effect(use_reactive((&label,), move |(label,)| {
+        label_draft.set(label.unwrap_or_default());
+    }));
+
+    let failed = match &tx.status {
+        TransactionStatus::Failed { reason } => Some(reason.clone()),
+        _ => None,
+    };
+    let timeline = [
+        ("Created", Some(tx.created_at)),
+        ("Broadcast", tx.broadcast_at),
+        ("Confirmed", tx.confirmed_at),
+    ];
+    let pending = matches!(tx.status, TransactionStatus::Pending);
+    let incoming = tx.direction == TransactionDirection::Incoming;
+    let links = [
+        ("Accelerating parent", tx.accelerates.clone()),
+        ("Accelerated by", detail.accelerated_by.clone()),
+    ];
+    let status = if detail.confirmations > 0 {
+        format!("{} confirmations", detail.confirmations)
+    } else if detail.in_mempool {
+        "In mempool".to_string()
+    } else {
+        "Pending".to_string()
+    };
+
+    rsx! {
+        div {
+            class: "tx-detail",
+            div {
+                class: "tx-detail-header",
+                h3 { "Transaction" }
+                span { class: "tx-detail-status", "{status}" }
+            }
+            if pending && detail.confirmations == 0 {
+                if let Some(on_rebroadcast) = props.on_rebroadcast {
+                    div {
+                        class: "tx-detail-rebroadcast",
+                        button {
+                            disabled: props.rebroadcasting,
+                            onclick: move |_| on_rebroadcast.call(()),
+                            if props.rebroadcasting { "Rebroadcasting..." } else { "Rebroadcast now" }
+                        }
+                        span {
+                            class: "tx-detail-muted",
+                            "Announce it to connected peers again instead of waiting"
+                        }
+                    }
+                }
+                if incoming && detail.accelerated_by.is_none() {
+                    if let Some(on_accelerate) = props.on_accelerate {
+                        div {
+                            class: "tx-detail-rebroadcast",
+                            button {
+                                disabled: props.accelerating,
+                                onclick: move |_| on_accelerate.call(()),
+                                if props.accelerating { "Accelerating..." } else { "Accelerate" }
+                            }
+                            span {
+                                class: "tx-detail-muted",
+                                "Spend the payment back to yourself with a fee that pays for both"
+                            }
+                        }
+                    }
+                }
+            }
+            for (note, linked) in links {
+                if let Some(linked) = linked {
+                    div {
+                        key: "{note}",
+                        class: "tx-detail-link",
+                        span { class: "tx-detail-muted", "{note} " }
+                        if let Some(on_open) = props.on_open_transaction {
+                            button {
+                                class: "tx-detail-open tx-detail-mono",
+                                onclick: {
+                                    let linked = linked.clone();
+                                    move |_| on_open.call(linked.clone())
+                                },
+                                "{linked}"
+                            }
+                        } else {
+                            code { "{linked}" }
+                        }
+                    }
+                }
+            }
+            div {
+                class: "tx-detail-id",
+                code { "{tx.id}" }
+                CopyButton { text: tx.id.clone(), label: "Copy ID" }
+            }
+
+            if let Some(on_label_change) = props.on_label_change {
+                div {
+                    class: "tx-detail-label",
+                    input {
+                        placeholder: "Add a label",
+                        aria_label: "Label",
+                        value: "{label_draft}",
+                        oninput: move |evt| label_draft.set(evt.value()),
+                    }
+                    button {
+                        disabled: label_draft.read().trim() == detail.label.as_deref().unwrap_or(""),
+                        onclick: move |_| on_label_change.call(label_draft.read().clone()),
+                        "Save label"
+                    }
+                }
+            } else if let Some(label) = detail.label.as_ref() {
+                div { class: "tx-detail-label", "{label}" }
+            }
+
+            h4 { "Timeline" }
+            ol {
+                class: "tx-detail-timeline",
+                for (stage, at) in timeline {
+                    li {
+                        class: if at.is_some() { "done" } else { "" },
+                        span { class: "tx-detail-stage", "{stage}" }
+                        span { class: "tx-detail-time", "{format_time(at)}" }
+                    }
+                }
+            }
+            if let Some(reason) = failed {
+                div { class: "tx-detail-failed", "Failed: {reason}" }
+            }
+            if !tx.rejections.is_empty() {
+                h4 { "Rejected by peers" }
+                table {
+                    class: "tx-detail-rejections",
+                    tbody {
+                        for rejection in tx.rejections.iter() {
+                            tr {
+                                key: "{rejection.peer}",
+                                td { class: "tx-detail-mono", "{rejection.peer}" }
+                                td { class: "tx-detail-reject-code", "{rejection.code}" }
+                                td { "{rejection.detail}" }
+                                td { class: "tx-detail-time", "{format_time(Some(rejection.at))}" }
+                            }
+                        }
+                    }
+                }
+            }
+
+            h4 { "Inputs" }
+            if tx.inputs.is_empty() {
+                div { class: "empty-state", "None" }
+            }
+            table {
+                tbody {
+                    for input in tx.inputs.iter() {
+                        tr {
+                            td { class: "tx-detail-mono", "{input.previous_output}" }
+                            td { class: "tx-detail-amount", "{format_balance(input.amount)} NOCK" }
+                        }
+                    }
+                }
+            }
+
+            h4 { "Outputs" }
+            if detail.outputs.is_empty() {
+                div { class: "empty-state", "None" }
+            }
+            table {
+                tbody {
+                    for output in detail.outputs.iter() {
+                        tr {
+                            key: "{output.index}",
+                            td { "#{output.index}" }
+                            td {
+                                class: "tx-detail-mono",
+                                "{output.address}"
+                                if let Some(label) = output.address_label.as_ref() {
+                                    span { class: "tx-detail-mine", "{label}" }
+                                } else if output.is_mine {
+                                    span { class: "tx-detail-mine", "mine" }
+                                }
+                            }
+                            td { CopyButton { text: output.address.clone() } }
+                            td { class: "tx-detail-amount", "{format_balance(output.amount)} NOCK" }
+                        }
+                    }
+                }
+            }
+
+            h4 { "Fee" }
+            if tx.fee_known() {
+                div {
+                    "{format_balance(tx.fee)} NOCK"
+                    if let Some(rate) = detail.fee_rate {
+                        span { class: "tx-detail-muted", " ({rate:.2} nano/byte)" }
+                    }
+                }
+            } else {
+                div {
+                    class: "tx-detail-muted",
+                    "Unknown: signed outside this wallet, with inputs it doesn't hold"
+                }
+            }
+
+            h4 { "Inclusion proof" }
+            div { class: "tx-detail-proof {proof_class(detail.proof)}", "{proof_label(detail.proof)}" }
+
+            h4 { "Raw transaction" }
+            if let Some(raw) = detail.raw_hex.clone() {
+                div {
+                    class: "tx-detail-raw",
+                    code { "{raw}" }
+                    CopyButton { text: raw, label: "Copy hex" }
+                }
+            } else {
+                div { class: "empty-state", "Signed form not available" }
+            }
+
+            if tx.is_outgoing {
+                if let Some(on_export_receipt) = props.on_export_receipt {
+                    h4 { "Receipt" }
+                    button {
+                        onclick: move |_| on_export_receipt.call(()),
+                        "Export receipt"
+                    }
+                    if let Some(error) = props.receipt_error.as_ref() {
+                        div { class: "tx-detail-failed", "{error}" }
+                    }
+                    if let Some(receipt) = props.receipt.clone() {
+                        ReceiptView { receipt }
+                    }
+                }
+            }
+        }
+
+        style { {TRANSACTION_DETAIL_CSS} }
+    }
+}
+
+fn format_time(at: Option<DateTime<Utc>>) -> String {
+    at.map_or_else(
+        || "—".to_string(),
+        |at| at.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+    )
+}
+
+fn proof_label(proof: MerkleProofStatus) -> &'static str {
+    match proof {
+        MerkleProofStatus::Unconfirmed => "Not in a block yet",
+        MerkleProofStatus::Unavailable => "Confirming block isn't in the local chain",
+        MerkleProofStatus::Verified => "Verified against the block's merkle root",
+        MerkleProofStatus::Invalid => "Block does not commit to this transaction",
+    }
+}
+
+fn proof_class(proof: MerkleProofStatus) -> &'static str {
+    match proof {
+        MerkleProofStatus::Verified => "verified",
+        MerkleProofStatus::Invalid => "invalid",
+        MerkleProofStatus::Unconfirmed | MerkleProofStatus::Unavailable => "",
+    }
+}
+
+const TRANSACTION_DETAIL_CSS: &str = r#"
+.tx-detail {
+    background: #f8f9fa;
+    padding: 20px;
+    border-radius: 8px;
+    font-size: 14px;
+    color: #333;
+}
+
+.tx-detail-header {
+    display: flex;
+    align-items: baseline;
+    justify-content: space-between;
+}
+
+.tx-detail-header h3 {
+    margin: 0;
+}
+
+.tx-detail-status {
+    color: #6c757d;
+}
+
+.tx-detail-id,
+.tx-detail-raw {
+    display: flex;
+    align-items: flex-start;
+    gap: 8px;
+    margin-top: 8px;
+}
+
+.tx-detail-id code,
+.tx-detail-raw code {
+    flex: 1;
+    word-break: break-all;
+    font-size: 12px;
+}
+
+.tx-detail-raw code {
+    max-height: 160px;
+    overflow-y: auto;
+}
+
+.tx-detail-rebroadcast {
+    display: flex;
+    align-items: center;
+    gap: 8px;
+    margin-top: 8px;
+}
+
+.tx-detail-link {
+    margin-top: 8px;
+    word-break: break-all;
+}
+
+.tx-detail-open {
+    padding: 0;
+    background: none;
+    border: none;
+    color: #667eea;
+    cursor: pointer;
+    text-decoration: underline;
+    text-align: left;
+}
+
+.tx-detail-label {
+    display: flex;
+    gap: 8px;
+    margin-top: 12px;
+}
+
+.tx-detail-label input {
+    flex: 1;
+    padding: 6px 8px;
+    border: 1px solid #ccc;
+    border-radius: 4px;
+}
+
+.tx-detail h4 {
+    margin: 16px 0 6px 0;
+    font-size: 12px;
+    color: #6c757d;
+    text-transform: uppercase;
+}
+
+.tx-detail-timeline {
+    list-style: none;
+    padding: 0;
+    margin: 0;
+    display: flex;
+    gap: 24px;
+}
+
+.tx-detail-timeline li {
+    display: flex;
+    flex-direction: column;
+    color: #adb5bd;
+}
+
+.tx-detail-timeline li.done {
+    color: #333;
+}
+
+.tx-detail-stage {
+    font-weight: 600;
+}
+
+.tx-detail-time {
+    font-size: 12px;
+}
+
+.tx-detail-failed {
+    margin-top: 8px;
+    color: #dc3545;
+}
+
+.tx-detail-reject-code {
+    color: #dc3545;
+    font-weight: 600;
+    white-space: nowrap;
+}
+
+.tx-detail table {
+    width: 100%;
+    border-collapse: collapse;
+}
+
+.tx-detail td {
+    padding: 4px 8px 4px 0;
+    border-bottom: 1px solid #eef0f2;
+}
+
+.tx-detail-mono {
+    font-family: monospace;
+    font-size: 12px;
+    word-break: break-all;
+}
+
+.tx-detail-amount {
+    text-align: right;
+    white-space: nowrap;
+}
+
+.tx-detail-mine {
+    margin-left: 6px;
+    padding: 1px 6px;
+    background: #d1e7dd;
+    color: #0f5132;
+    border-radius: 8px;
+    font-family: sans-serif;
+    font-size: 11px;
+}
+
+.tx-detail-muted {
+    color: #6c757d;
+}
+
+.tx-detail-proof.verified {
+    color: #28a745;
+}
+
+.tx-detail-proof.invalid {
+    color: #dc3545;
+}
+"#;
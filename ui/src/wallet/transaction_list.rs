@@ -1,31 +1,569 @@
-use api::Transaction;
+use api::{
+    format_display, Address, DisplaySettings, Transaction, TransactionDirection, TransactionOutput,
+    TransactionStatus,
+};
 use dioxus::prelude::*;
+use std::collections::HashMap;
+
+use super::CopyButton;
+use crate::error_state::ErrorState;
+use crate::i18n::current_locale;
+use crate::load_state::LoadState;
+use crate::skeleton::Skeleton;
+use crate::swipe_row::SwipeRow;
+use crate::t;
 
 #[derive(Props, Clone, PartialEq)]
 pub struct TransactionListProps {
-    pub transactions: Vec<Transaction>,
-    pub is_loading: bool,
+    pub state: LoadState<Vec<Transaction>>,
+    /// Fetch the transactions again after an error
+    #[props(default)]
+    pub on_retry: Option<EventHandler<()>>,
+    /// Open the full detail view for a transaction id
+    #[props(default)]
+    pub on_open: Option<EventHandler<String>>,
+    /// Labels of the wallet's own addresses, shown for the address a
+    /// transaction paid or was paid from
+    #[props(default)]
+    pub address_labels: HashMap<Address, String>,
+    #[props(default)]
+    pub display: DisplaySettings,
 }
 
 pub fn TransactionList(props: TransactionListProps) -> Element {
+    let mut expanded = use_signal(|| None::<String>);
+    let locale = current_locale();
+
+    let transactions = match props.state {
+        LoadState::Loading => return rsx! { TransactionListSkeleton {} },
+        LoadState::Error(message) => {
+            return rsx! {
+                ErrorState {
+                    title: t!("transactions.error"),
+                    message,
+                    on_retry: props.on_retry,
+                }
+            };
+        }
+        LoadState::Ready(transactions) => transactions,
+    };
+
     rsx! {
         div {
             class: "transaction-list",
-            h3 { "Recent Transactions" }
-            if props.is_loading {
-                div { "Loading transactions..." }
-            } else if props.transactions.is_empty() {
-                div { class: "empty-state", "No transactions yet" }
+            h3 { {t!("transactions.title")} }
+            if transactions.is_empty() {
+                div { class: "empty-state", {t!("transactions.empty")} }
             } else {
-                for transaction in props.transactions {
-                    div {
+                for transaction in transactions {
+                    SwipeRow {
                         key: "{transaction.id}",
-                        class: "transaction-item",
-                        div { "{transaction.id}" }
-                        div { "{transaction.amount}" }
+                        actions: rsx! {
+                            CopyButton { text: transaction.id.clone(), label: t!("transactions.copy_id") }
+                            if let Some(on_open) = props.on_open {
+                                button {
+                                    class: "transaction-swipe-open",
+                                    onclick: {
+                                        let id = transaction.id.clone();
+                                        move |_| on_open.call(id.clone())
+                                    },
+                                    {t!("transactions.open")}
+                                }
+                            }
+                        },
+                        div {
+                            class: "transaction-item",
+                            title: "{locale.format_datetime(&transaction.created_at)}",
+                            onclick: {
+                                let id = transaction.id.clone();
+                                move |_| {
+                                    let next = if expanded.read().as_ref() == Some(&id) {
+                                        None
+                                    } else {
+                                        Some(id.clone())
+                                    };
+                                    expanded.set(next);
+                                }
+                            },
+                            div {
+                                class: "transaction-summary",
+                                span {
+                                    class: "transaction-icon {direction_class(transaction.direction)}",
+                                    title: "{direction_label(transaction.direction)}",
+                                    "{direction_icon(transaction.direction)}"
+                                }
+                                span { class: "transaction-id", "{transaction.id}" }
+                                if let Some(label) = own_label(&props.address_labels, &transaction) {
+                                    span { class: "transaction-label", "{label}" }
+                                }
+                                match &transaction.status {
+                                    TransactionStatus::Pending => rsx! {
+                                        span {
+                                            class: "transaction-badge zero-conf",
+                                            title: t!("transactions.zero_conf_hint"),
+                                            {t!("transactions.zero_conf")}
+                                        }
+                                    },
+                                    TransactionStatus::Failed { reason } => rsx! {
+                                        span {
+                                            class: "transaction-badge failed",
+                                            title: "{reason}",
+                                            {t!("transactions.failed")}
+                                        }
+                                    },
+                                    TransactionStatus::Confirmed { .. } => rsx! {},
+                                }
+                                if transaction.is_external() {
+                                    span {
+                                        class: "transaction-badge external",
+                                        title: t!("transactions.external_hint"),
+                                        {t!("transactions.external")}
+                                    }
+                                }
+                                CopyButton { text: transaction.id.clone(), label: t!("transactions.copy_id") }
+                                span {
+                                    class: "transaction-net {direction_class(transaction.direction)}",
+                                    "{format_net(transaction.net_amount(), &props.display)} NOCK"
+                                }
+                            }
+                            if let TransactionStatus::Failed { reason } = &transaction.status {
+                                div { class: "transaction-note failed", "{reason}" }
+                            }
+                            if transaction.direction == TransactionDirection::Internal {
+                                div {
+                                    class: "transaction-note",
+                                    {t!("transactions.internal_note", amount = format_display(transaction.amount, &props.display))}
+                                }
+                            }
+                            if expanded.read().as_ref() == Some(&transaction.id) {
+                                div {
+                                    class: "transaction-detail",
+                                    h4 { {t!("transactions.inputs")} }
+                                    if transaction.inputs.is_empty() {
+                                        div { class: "empty-state", {t!("transactions.none")} }
+                                    }
+                                    for input in transaction.inputs.iter() {
+                                        div { class: "transaction-io", "{input}" }
+                                    }
+                                    h4 { {t!("transactions.outputs")} }
+                                    if transaction.outputs.is_empty() {
+                                        div { class: "empty-state", {t!("transactions.none")} }
+                                    }
+                                    for output in transaction.outputs.iter() {
+                                        div { class: "transaction-io", "{output_text(output)}" }
+                                    }
+                                    if let Some(on_open) = props.on_open {
+                                        button {
+                                            class: "transaction-open",
+                                            onclick: {
+                                                let id = transaction.id.clone();
+                                                move |evt: MouseEvent| {
+                                                    evt.stop_propagation();
+                                                    on_open.call(id.clone());
+                                                }
+                                            },
+                                            {t!("transactions.view_details")}
+                                        }
+                                    }
+                                }
+                            }
+                        }
                     }
                 }
             }
         }
+
+        style { {TRANSACTION_LIST_CSS} }
+    }
+}
+
+#[derive(Props, Clone, PartialEq)]
+pub struct TransactionListSkeletonProps {
+    #[props(default = 3)]
+    pub rows: usize,
+}
+
+/// Placeholder rows in the shape of a `TransactionList` while it loads
+pub fn TransactionListSkeleton(props: TransactionListSkeletonProps) -> Element {
+    rsx! {
+        div {
+            class: "transaction-list",
+            aria_busy: "true",
+            h3 { {t!("transactions.title")} }
+            for row in 0..props.rows {
+                div {
+                    key: "{row}",
+                    class: "transaction-item",
+                    div {
+                        class: "transaction-summary",
+                        Skeleton { width: "20px", height: "20px" }
+                        span {
+                            class: "transaction-id",
+                            Skeleton { width: "70%" }
+                        }
+                        Skeleton { width: "80px" }
+                    }
+                }
+            }
+        }
+
+        style { {TRANSACTION_LIST_CSS} }
+    }
+}
+
+/// Label of the owned address on the wallet's side of a transaction: the
+/// recipient for incoming funds, the sender for outgoing ones
+fn own_label<'a>(
+    labels: &'a HashMap<Address, String>,
+    transaction: &Transaction,
+) -> Option<&'a str> {
+    let own = match transaction.direction {
+        TransactionDirection::Outgoing => [&transaction.from_address, &transaction.to_address],
+        TransactionDirection::Incoming | TransactionDirection::Internal => {
+            [&transaction.to_address, &transaction.from_address]
+        }
+    };
+    own.into_iter()
+        .flatten()
+        .find_map(|address| labels.get(address))
+        .map(String::as_str)
+}
+
+/// An output with its recipient in the nockchain form, or as stored if that
+/// isn't an address
+fn output_text(output: &TransactionOutput) -> String {
+    let recipient = Address::from_string(&output.recipient_address)
+        .map(|address| address.to_nockchain_string())
+        .unwrap_or_else(|_| output.recipient_address.clone());
+    format!("{} ({})", recipient, output.amount)
+}
+
+fn direction_icon(direction: TransactionDirection) -> &'static str {
+    match direction {
+        TransactionDirection::Incoming => "↓",
+        TransactionDirection::Outgoing => "↑",
+        TransactionDirection::Internal => "⇄",
+    }
+}
+
+fn direction_label(direction: TransactionDirection) -> String {
+    match direction {
+        TransactionDirection::Incoming => t!("transactions.direction.incoming"),
+        TransactionDirection::Outgoing => t!("transactions.direction.outgoing"),
+        TransactionDirection::Internal => t!("transactions.direction.internal"),
+    }
+}
+
+fn direction_class(direction: TransactionDirection) -> &'static str {
+    match direction {
+        TransactionDirection::Incoming => "incoming",
+        TransactionDirection::Outgoing => "outgoing",
+        TransactionDirection::Internal => "internal",
+    }
+}
+
+fn format_net(net: i128, display: &DisplaySettings) -> String {
+    let amount = format_display(
+        u64::try_from(net.unsigned_abs()).unwrap_or(u64::MAX),
+        display,
+    );
+    match net.signum() {
+        1 => format!("+{}", amount),
+        -1 => format!("-{}", amount),
+        _ => amount,
+    }
+}
+
+const TRANSACTION_LIST_CSS: &str = r#"
+.transaction-item {
+    cursor: pointer;
+}
+
+.transaction-summary {
+    display: flex;
+    align-items: center;
+    gap: 8px;
+}
+
+.transaction-id {
+    flex: 1;
+    overflow: hidden;
+    text-overflow: ellipsis;
+    font-family: monospace;
+}
+
+.transaction-icon {
+    width: 20px;
+    text-align: center;
+    font-weight: 700;
+}
+
+.incoming {
+    color: #28a745;
+}
+
+.outgoing {
+    color: #dc3545;
+}
+
+.internal {
+    color: #6c757d;
+}
+
+.transaction-label {
+    padding: 1px 6px;
+    background: #d1e7dd;
+    color: #0f5132;
+    border-radius: 8px;
+    font-size: 11px;
+}
+
+.transaction-badge {
+    padding: 1px 6px;
+    border-radius: 8px;
+    font-size: 11px;
+    white-space: nowrap;
+}
+
+.transaction-badge.zero-conf {
+    background: #fff3cd;
+    color: #664d03;
+}
+
+.transaction-badge.external {
+    background: #e2e3e5;
+    color: #41464b;
+}
+
+.transaction-badge.failed {
+    background: #f8d7da;
+    color: #842029;
+}
+
+.transaction-note {
+    font-size: 12px;
+    color: #6c757d;
+}
+
+.transaction-note.failed {
+    color: #842029;
+}
+
+.transaction-detail {
+    margin-top: 8px;
+    padding: 8px 12px;
+    background: #f8f9fa;
+    border-radius: 6px;
+}
+
+.transaction-detail h4 {
+    margin: 8px 0 4px 0;
+    font-size: 12px;
+    color: #6c757d;
+    text-transform: uppercase;
+}
+
+.transaction-io {
+    font-family: monospace;
+    font-size: 12px;
+    word-break: break-all;
+}
+
+.transaction-swipe-open {
+    border: none;
+    border-radius: 4px;
+    background: #007bff;
+    color: white;
+    font-size: 12px;
+    cursor: pointer;
+}
+
+.transaction-open {
+    margin-top: 8px;
+    padding: 4px 10px;
+    background: none;
+    border: 1px solid #007bff;
+    border-radius: 4px;
+    color: #007bff;
+    cursor: pointer;
+}
+
+@media (max-width: 480px) {
+    .transaction-item {
+        padding: 12px 0;
+    }
+
+    /* Swiping the row uncovers these */
+    .transaction-summary .copy-button {
+        display: none;
+    }
+
+    .transaction-net {
+        white-space: nowrap;
+    }
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{render, render_root};
+    use api::testing::{fake_address, fake_transaction};
+
+    #[test]
+    fn internal_transfers_get_their_own_icon_and_class() {
+        let icons = [
+            TransactionDirection::Incoming,
+            TransactionDirection::Outgoing,
+            TransactionDirection::Internal,
+        ]
+        .map(direction_icon);
+        assert_eq!(icons, ["↓", "↑", "⇄"]);
+        assert_eq!(direction_class(TransactionDirection::Internal), "internal");
+    }
+
+    #[test]
+    fn the_net_effect_is_signed() {
+        let display = DisplaySettings::default();
+        let self_send = fake_transaction().internal().amount(800).fee(15).build();
+        let send = fake_transaction().outgoing().amount(300).fee(10).build();
+        let receipt = fake_transaction().amount(250).build();
+
+        assert_eq!(
+            format_net(self_send.net_amount(), &display),
+            format!("-{}", format_display(15, &display))
+        );
+        assert_eq!(
+            format_net(send.net_amount(), &display),
+            format!("-{}", format_display(310, &display))
+        );
+        assert_eq!(
+            format_net(receipt.net_amount(), &display),
+            format!("+{}", format_display(250, &display))
+        );
+        assert_eq!(format_net(0, &display), format_display(0, &display));
+    }
+
+    #[test]
+    fn internal_transfers_are_labelled_by_the_receiving_address() {
+        let (from, to) = (fake_address(), fake_address());
+        let labels = HashMap::from([
+            (from.clone(), "Spending".to_string()),
+            (to.clone(), "Savings".to_string()),
+        ]);
+        let internal = fake_transaction().internal().from(from).to(to).build();
+        assert_eq!(own_label(&labels, &internal), Some("Savings"));
+
+        let outgoing = fake_transaction().outgoing().build();
+        assert_eq!(own_label(&labels, &outgoing), None);
+    }
+
+    #[test]
+    fn an_incoming_payment_shows_the_label_of_the_address_it_paid() {
+        let donations = fake_address();
+        let labels = HashMap::from([(donations.clone(), "Donations".to_string())]);
+        let incoming = fake_transaction()
+            .from(fake_address())
+            .to(donations)
+            .build();
+        assert_eq!(own_label(&labels, &incoming), Some("Donations"));
+    }
+
+    #[test]
+    fn recipients_show_in_the_nockchain_form_whatever_was_stored() {
+        let address = fake_address();
+        for stored in [address.to_string(), address.to_nockchain_string()] {
+            let output = TransactionOutput {
+                amount: 40,
+                recipient_address: stored,
+            };
+            assert_eq!(
+                output_text(&output),
+                format!("{} (40)", address.to_nockchain_string())
+            );
+        }
+        let legacy = TransactionOutput {
+            amount: 5,
+            recipient_address: "coinbase".to_string(),
+        };
+        assert_eq!(output_text(&legacy), "coinbase (5)");
+    }
+
+    fn list(state: LoadState<Vec<Transaction>>) -> String {
+        render(
+            TransactionList,
+            TransactionListProps {
+                state,
+                on_retry: None,
+                on_open: None,
+                address_labels: HashMap::new(),
+                display: DisplaySettings::default(),
+            },
+        )
+    }
+
+    #[test]
+    fn loading_shows_skeleton_rows() {
+        let html = list(LoadState::Loading);
+        assert!(html.contains(r#"aria-busy="true""#));
+        assert_eq!(html.matches(r#"class="transaction-item""#).count(), 3);
+        assert!(html.contains("skeleton"));
+    }
+
+    #[test]
+    fn ready_shows_a_row_per_transaction() {
+        let transactions = vec![
+            fake_transaction().id("tx-one").amount(250).build(),
+            fake_transaction()
+                .id("tx-two")
+                .outgoing()
+                .amount(300)
+                .build(),
+        ];
+        let html = list(LoadState::Ready(transactions));
+        assert!(html.contains("tx-one"));
+        assert!(html.contains("tx-two"));
+        assert!(html.contains(&format_net(250, &DisplaySettings::default())));
+        assert!(!html.contains("skeleton"));
+        assert!(!html.contains(&t!("transactions.empty")));
+
+        let empty = list(LoadState::Ready(Vec::new()));
+        assert!(empty.contains(&t!("transactions.empty")));
+    }
+
+    #[test]
+    fn an_error_shows_the_message_and_a_retry_button() {
+        let html = list(LoadState::Error("node unreachable".to_string()));
+        assert!(html.contains(&t!("transactions.error")));
+        assert!(html.contains("node unreachable"));
+        assert!(!html.contains("error-state-retry"));
+
+        #[component]
+
+        fn Retryable() -> Element {
+            rsx! {
+                TransactionList {
+                    state: LoadState::Error("node unreachable".to_string()),
+                    on_retry: move |_| {},
+                }
+            }
+        }
+        assert!(render_root(Retryable).contains("error-state-retry"));
+    }
+
+    #[test]
+    fn unconfirmed_and_failed_transactions_are_badged() {
+        let pending = fake_transaction().id("zero").build();
+        let failed = fake_transaction()
+            .id("replaced")
+            .failed("Replaced by transaction other (double spend)")
+            .build();
+        let html = list(LoadState::Ready(vec![pending, failed]));
+        assert_eq!(html.matches("transaction-badge zero-conf").count(), 1);
+        assert!(html.contains(&t!("transactions.zero_conf")));
+        assert!(html.contains(
+            r#"<div class="transaction-note failed">Replaced by transaction other (double spend)</div>"#
+        ));
+        assert_eq!(html.matches("transaction-badge failed").count(), 1);
     }
 }
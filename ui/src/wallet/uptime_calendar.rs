@@ -0,0 +1,165 @@
+use api::AvailabilityReport;
+use chrono::Datelike;
+use dioxus::prelude::*;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct UptimeCalendarProps {
+    pub report: Option<AvailabilityReport>,
+}
+
+/// Node availability over the report range: a summary line and one cell per
+/// day, in week columns, shaded by that day's uptime
+pub fn UptimeCalendar(props: UptimeCalendarProps) -> Element {
+    let Some(report) = props.report else {
+        return rsx! {
+            div { class: "uptime-calendar idle", "No node status history yet" }
+            style { {UPTIME_CALENDAR_CSS} }
+        };
+    };
+
+    // Blank cells so the first day lands on its weekday row, Monday first
+    let offset = report
+        .days
+        .first()
+        .map(|day| day.date.weekday().num_days_from_monday())
+        .unwrap_or(0);
+
+    rsx! {
+        div {
+            class: "uptime-calendar",
+            div {
+                class: "uptime-summary",
+                span { "Uptime {report.uptime_percent():.1}%" }
+                span { "Longest run {format_span(report.longest_run_secs)}" }
+                span { class: if report.crashes > 0 { "uptime-failures" } else { "" }, "{report.crashes} crashes" }
+                span { class: if report.errors > 0 { "uptime-failures" } else { "" }, "{report.errors} failed starts" }
+                span { class: "uptime-user", "{report.user_stops} manual stops" }
+            }
+            div {
+                class: "uptime-grid",
+                for _ in 0..offset {
+                    div { class: "uptime-day blank" }
+                }
+                for day in report.days.iter() {
+                    div {
+                        key: "{day.date}",
+                        class: "uptime-day {uptime_shade(day.uptime_percent)}",
+                        title: "{day.date}: {day.uptime_percent:.1}% up",
+                    }
+                }
+            }
+        }
+
+        style { {UPTIME_CALENDAR_CSS} }
+    }
+}
+
+fn uptime_shade(percent: f64) -> &'static str {
+    match percent {
+        p if p <= 0.0 => "down",
+        p if p < 50.0 => "low",
+        p if p < 90.0 => "mid",
+        p if p < 99.0 => "high",
+        _ => "full",
+    }
+}
+
+fn format_span(secs: i64) -> String {
+    let hours = secs / 3600;
+    let minutes = (secs % 3600) / 60;
+    if hours >= 24 {
+        format!("{}d {}h", hours / 24, hours % 24)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+const UPTIME_CALENDAR_CSS: &str = r#"
+.uptime-calendar {
+    display: flex;
+    flex-direction: column;
+    gap: 10px;
+    background: #f8f9fa;
+    padding: 16px 20px;
+    border-radius: 8px;
+    margin-top: 24px;
+    font-size: 13px;
+    color: #333;
+}
+
+.uptime-calendar.idle {
+    color: #6c757d;
+}
+
+.uptime-summary {
+    display: flex;
+    flex-wrap: wrap;
+    gap: 16px;
+}
+
+.uptime-failures {
+    color: #dc3545;
+    font-weight: 600;
+}
+
+.uptime-user {
+    color: #6c757d;
+}
+
+.uptime-grid {
+    display: grid;
+    grid-template-rows: repeat(7, 12px);
+    grid-auto-flow: column;
+    grid-auto-columns: 12px;
+    gap: 3px;
+}
+
+.uptime-day {
+    border-radius: 2px;
+    background: #ebedf0;
+}
+
+.uptime-day.blank {
+    background: transparent;
+}
+
+.uptime-day.low {
+    background: #f8d7da;
+}
+
+.uptime-day.mid {
+    background: #ffe69c;
+}
+
+.uptime-day.high {
+    background: #a3cfbb;
+}
+
+.uptime-day.full {
+    background: #198754;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days_are_shaded_by_their_uptime() {
+        let shades = [0.0, 0.1, 49.9, 50.0, 89.9, 90.0, 98.9, 99.0, 100.0].map(uptime_shade);
+        assert_eq!(
+            shades,
+            ["down", "low", "low", "mid", "mid", "high", "high", "full", "full"]
+        );
+    }
+
+    #[test]
+    fn spans_read_in_the_largest_fitting_units() {
+        assert_eq!(format_span(59), "0m");
+        assert_eq!(format_span(45 * 60), "45m");
+        assert_eq!(format_span(14 * 3600 + 5 * 60), "14h 5m");
+        assert_eq!(format_span(50 * 3600), "2d 2h");
+    }
+}
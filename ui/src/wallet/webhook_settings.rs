@@ -0,0 +1,368 @@
+use api::{DeliveryStatus, WebhookConfig, WebhookDelivery, WebhookEventType};
+use chrono::{DateTime, Utc};
+use dioxus::prelude::*;
+use std::collections::BTreeSet;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct WebhookSettingsProps {
+    pub hooks: Vec<WebhookConfig>,
+    /// Recent deliveries, newest first
+    #[props(default)]
+    pub deliveries: Vec<WebhookDelivery>,
+    /// Save the whole list after an add or remove
+    pub on_save: EventHandler<Vec<WebhookConfig>>,
+    #[props(default)]
+    pub disabled: bool,
+}
+
+/// Webhook endpoints and the delivery queue
+pub fn WebhookSettings(props: WebhookSettingsProps) -> Element {
+    let mut url = use_signal(String::new);
+    let mut secret = use_signal(String::new);
+    let mut event_types = use_signal(|| BTreeSet::from(WebhookEventType::ALL));
+    let mut error = use_signal(|| None::<String>);
+
+    let hooks = props.hooks.clone();
+    let add = move |_| {
+        let hook = WebhookConfig {
+            url: url().trim().to_string(),
+            secret: secret(),
+            event_types: event_types(),
+        };
+        let updated = match with_hook(&hooks, hook) {
+            Ok(updated) => updated,
+            Err(e) => {
+                error.set(Some(e));
+                return;
+            }
+        };
+        props.on_save.call(updated);
+        url.set(String::new());
+        secret.set(String::new());
+        error.set(None);
+    };
+
+    rsx! {
+        div {
+            class: "webhook-settings",
+            h3 { "Webhooks" }
+            p {
+                class: "webhook-hint",
+                "Events are POSTed as JSON, signed with HMAC-SHA256 of the body in the X-Nock-Signature header. Each carries an id to drop duplicates by; failed deliveries are retried with backoff."
+            }
+            for (index, hook) in props.hooks.iter().enumerate() {
+                div {
+                    key: "{hook.url}",
+                    class: "webhook-row",
+                    span { class: "webhook-url", "{hook.url}" }
+                    span {
+                        class: "webhook-events",
+                        {hook.event_types.iter().map(|event_type| event_type.label()).collect::<Vec<_>>().join(", ")}
+                    }
+                    button {
+                        disabled: props.disabled,
+                        onclick: {
+                            let hooks = props.hooks.clone();
+                            move |_| {
+                                let mut updated = hooks.clone();
+                                updated.remove(index);
+                                props.on_save.call(updated);
+                            }
+                        },
+                        "Remove"
+                    }
+                }
+            }
+            div {
+                class: "webhook-add",
+                input {
+                    r#type: "url",
+                    placeholder: "http://example.com/hooks/nock",
+                    aria_label: "Webhook URL",
+                    value: "{url}",
+                    disabled: props.disabled,
+                    oninput: move |evt| url.set(evt.value()),
+                }
+                input {
+                    r#type: "password",
+                    placeholder: "Shared secret",
+                    aria_label: "Shared secret",
+                    value: "{secret}",
+                    disabled: props.disabled,
+                    oninput: move |evt| secret.set(evt.value()),
+                }
+                for event_type in WebhookEventType::ALL {
+                    label {
+                        key: "{event_type.label()}",
+                        input {
+                            r#type: "checkbox",
+                            checked: event_types.read().contains(&event_type),
+                            disabled: props.disabled,
+                            onchange: move |evt| {
+                                if evt.checked() {
+                                    event_types.write().insert(event_type);
+                                } else {
+                                    event_types.write().remove(&event_type);
+                                }
+                            },
+                        }
+                        " {event_type.label()}"
+                    }
+                }
+                button {
+                    disabled: props.disabled || url().trim().is_empty(),
+                    onclick: add,
+                    "Add webhook"
+                }
+            }
+            if let Some(message) = error() {
+                div { class: "webhook-error", "{message}" }
+            }
+            if !props.deliveries.is_empty() {
+                table {
+                    class: "webhook-deliveries",
+                    thead {
+                        tr {
+                            th { "Queued" }
+                            th { "Event" }
+                            th { "Webhook" }
+                            th { "Status" }
+                        }
+                    }
+                    tbody {
+                        for delivery in props.deliveries.iter() {
+                            tr {
+                                key: "{delivery.event_id}-{delivery.url}",
+                                td { {format_time(delivery.created_at)} }
+                                td { "{delivery.event}" }
+                                td { class: "webhook-url", "{delivery.url}" }
+                                td {
+                                    class: status_class(delivery.status),
+                                    title: delivery.last_error.clone().unwrap_or_default(),
+                                    {status_label(delivery)}
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        style { {WEBHOOK_SETTINGS_CSS} }
+    }
+}
+
+/// `hooks` with `hook` added, if it is valid and its URL isn't taken
+fn with_hook(hooks: &[WebhookConfig], hook: WebhookConfig) -> Result<Vec<WebhookConfig>, String> {
+    hook.validate().map_err(|e| e.to_string())?;
+    if hooks.iter().any(|existing| existing.url == hook.url) {
+        return Err(format!("{} is already a webhook", hook.url));
+    }
+    let mut updated = hooks.to_vec();
+    updated.push(hook);
+    Ok(updated)
+}
+
+fn status_label(delivery: &WebhookDelivery) -> String {
+    match delivery.status {
+        DeliveryStatus::Delivered => "Delivered".to_string(),
+        DeliveryStatus::GaveUp => format!("Gave up after {} attempts", delivery.attempts),
+        DeliveryStatus::Pending if delivery.attempts == 0 => "Sending".to_string(),
+        DeliveryStatus::Pending => format!(
+            "Retrying at {} ({} failed)",
+            delivery.next_attempt_at.format("%H:%M:%S"),
+            delivery.attempts
+        ),
+    }
+}
+
+fn status_class(status: DeliveryStatus) -> &'static str {
+    match status {
+        DeliveryStatus::Pending => "webhook-status pending",
+        DeliveryStatus::Delivered => "webhook-status delivered",
+        DeliveryStatus::GaveUp => "webhook-status gave-up",
+    }
+}
+
+fn format_time(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M UTC").to_string()
+}
+
+const WEBHOOK_SETTINGS_CSS: &str = r#"
+.webhook-settings {
+    display: flex;
+    flex-direction: column;
+    gap: 12px;
+    background: #f8f9fa;
+    padding: 20px;
+    border-radius: 8px;
+    margin-bottom: 24px;
+    font-size: 14px;
+    color: #333;
+}
+
+.webhook-settings h3 {
+    margin: 0;
+}
+
+.webhook-hint {
+    margin: 0;
+    color: #6c757d;
+}
+
+.webhook-row,
+.webhook-add {
+    display: flex;
+    flex-wrap: wrap;
+    align-items: center;
+    gap: 12px;
+}
+
+.webhook-url {
+    font-family: monospace;
+    word-break: break-all;
+}
+
+.webhook-events {
+    flex: 1;
+    color: #6c757d;
+}
+
+.webhook-add input[type="url"] {
+    flex: 1;
+    min-width: 240px;
+    padding: 6px 8px;
+}
+
+.webhook-error {
+    color: #dc3545;
+}
+
+.webhook-deliveries {
+    width: 100%;
+    border-collapse: collapse;
+    font-size: 13px;
+}
+
+.webhook-deliveries th,
+.webhook-deliveries td {
+    padding: 6px 8px;
+    text-align: left;
+    border-bottom: 1px solid #e9ecef;
+}
+
+.webhook-status.delivered {
+    color: #198754;
+}
+
+.webhook-status.gave-up {
+    color: #dc3545;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::render;
+    use api::testing::fixture_time;
+    use api::WalletEvent;
+
+    fn hook(url: &str) -> WebhookConfig {
+        WebhookConfig {
+            url: url.to_string(),
+            secret: "secret".to_string(),
+            event_types: BTreeSet::from([WebhookEventType::IncomingTx]),
+        }
+    }
+
+    fn delivery(status: DeliveryStatus, attempts: u32) -> WebhookDelivery {
+        WebhookDelivery {
+            event_id: format!("event-{}", attempts),
+            url: "http://shop/hooks".to_string(),
+            event: WalletEvent::IncomingTx {
+                tx_id: "abc123".to_string(),
+                amount: 100,
+            },
+            created_at: fixture_time(0),
+            status,
+            attempts,
+            next_attempt_at: fixture_time(3_725),
+            last_attempt_at: None,
+            last_error: (attempts > 0).then(|| "HTTP 500".to_string()),
+        }
+    }
+
+    #[test]
+    fn a_webhook_is_added_only_if_valid_and_new() {
+        let hooks = vec![hook("http://a/")];
+        let added = with_hook(&hooks, hook("http://b/")).unwrap();
+        assert_eq!(added, vec![hook("http://a/"), hook("http://b/")]);
+
+        let err = with_hook(&hooks, hook("http://a/")).unwrap_err();
+        assert_eq!(err, "http://a/ is already a webhook");
+        let err = with_hook(&hooks, hook("https://c/")).unwrap_err();
+        assert!(err.contains("only http://"), "{}", err);
+    }
+
+    #[test]
+    fn each_status_reads_as_what_happens_next() {
+        assert_eq!(
+            status_label(&delivery(DeliveryStatus::Pending, 0)),
+            "Sending"
+        );
+        assert_eq!(
+            status_label(&delivery(DeliveryStatus::Pending, 2)),
+            "Retrying at 01:02:05 (2 failed)"
+        );
+        assert_eq!(
+            status_label(&delivery(DeliveryStatus::Delivered, 1)),
+            "Delivered"
+        );
+        assert_eq!(
+            status_label(&delivery(DeliveryStatus::GaveUp, 8)),
+            "Gave up after 8 attempts"
+        );
+    }
+
+    #[component]
+    fn Settings(deliveries: Vec<WebhookDelivery>) -> Element {
+        rsx! {
+            WebhookSettings {
+                hooks: vec![hook("http://shop/hooks")],
+                deliveries,
+                on_save: |_| {},
+            }
+        }
+    }
+
+    #[test]
+    fn the_delivery_queue_is_listed_with_its_status() {
+        let html = render(
+            Settings,
+            SettingsProps {
+                deliveries: vec![
+                    delivery(DeliveryStatus::GaveUp, 8),
+                    delivery(DeliveryStatus::Delivered, 1),
+                ],
+            },
+        );
+        assert!(html.contains("http://shop/hooks"), "{}", html);
+        assert!(html.contains("Incoming payment"), "{}", html);
+        assert!(html.contains("Incoming payment abc123"), "{}", html);
+        assert!(html.contains("2024-01-01 00:00 UTC"), "{}", html);
+        assert!(html.contains("webhook-status gave-up"), "{}", html);
+        assert!(html.contains("title=\"HTTP 500\""), "{}", html);
+        assert!(html.contains("webhook-status delivered"), "{}", html);
+    }
+
+    #[test]
+    fn without_deliveries_there_is_no_table() {
+        let html = render(
+            Settings,
+            SettingsProps {
+                deliveries: Vec::new(),
+            },
+        );
+        assert!(!html.contains("webhook-deliveries"), "{}", html);
+    }
+}